@@ -0,0 +1,10 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // use the vendored protobuf compiler, so that none has to be installed to build the node
+    std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+    tonic_build::configure()
+        .build_client(false)
+        .compile(&["proto/massa/api/v1/api.proto"], &["proto"])?;
+    Ok(())
+}
@@ -7,12 +7,15 @@ use crate::{APIConfig, ApiServer, ApiV2, StopHandle, API};
 use async_trait::async_trait;
 use jsonrpsee::core::error::SubscriptionClosed;
 use jsonrpsee::core::{Error as JsonRpseeError, RpcResult};
-use jsonrpsee::types::SubscriptionResult;
+use jsonrpsee::types::error::ErrorCode;
+use jsonrpsee::types::{ErrorObject, SubscriptionResult};
 use jsonrpsee::SubscriptionSink;
 use massa_consensus_exports::ConsensusChannels;
-use massa_models::version::Version;
+use massa_models::{address::Address, version::Version};
+use massa_pool_exports::PoolChannels;
 use massa_protocol_exports::ProtocolSenders;
 use serde::Serialize;
+use tokio::sync::broadcast::error::RecvError;
 use tokio_stream::wrappers::BroadcastStream;
 
 impl API<ApiV2> {
@@ -20,12 +23,14 @@ impl API<ApiV2> {
     pub fn new(
         consensus_channels: ConsensusChannels,
         protocol_senders: ProtocolSenders,
+        pool_channels: PoolChannels,
         api_settings: APIConfig,
         version: Version,
     ) -> Self {
         API(ApiV2 {
             consensus_channels,
             protocol_senders,
+            pool_channels,
             api_settings,
             version,
         })
@@ -39,7 +44,7 @@ impl ApiServer for API<ApiV2> {
         url: &SocketAddr,
         api_config: &APIConfig,
     ) -> Result<StopHandle, JsonRpseeError> {
-        crate::serve(self.into_rpc(), url, api_config).await
+        crate::serve(self.into_rpc(), url, api_config, None, None).await
     }
 }
 
@@ -65,10 +70,65 @@ impl MassaApiServer for API<ApiV2> {
         Ok(())
     }
 
+    fn subscribe_new_final_blocks(&self, sink: SubscriptionSink) -> SubscriptionResult {
+        broadcast_via_ws(self.0.consensus_channels.final_block_sender.clone(), sink);
+        Ok(())
+    }
+
     fn subscribe_new_operations(&self, sink: SubscriptionSink) -> SubscriptionResult {
         broadcast_via_ws(self.0.protocol_senders.operation_sender.clone(), sink);
         Ok(())
     }
+
+    fn subscribe_new_operations_by_address(
+        &self,
+        mut sink: SubscriptionSink,
+        address: Address,
+    ) -> SubscriptionResult {
+        sink.accept()?;
+        let mut rx = self.0.consensus_channels.filled_block_sender.subscribe();
+        tokio::spawn(async move {
+            loop {
+                let filled_block = match rx.recv().await {
+                    Ok(filled_block) => filled_block,
+                    // some blocks were missed by this subscription, keep going with the next ones
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => {
+                        sink.close(SubscriptionClosed::Success);
+                        return;
+                    }
+                };
+                for operation in filled_block
+                    .operations
+                    .into_iter()
+                    .filter_map(|(_id, operation)| operation)
+                    .filter(|operation| {
+                        operation.get_ledger_involved_addresses().contains(&address)
+                    })
+                {
+                    match sink.send(&operation) {
+                        Ok(true) => {}
+                        // the subscription was closed by the remote peer
+                        Ok(false) => return,
+                        Err(err) => {
+                            sink.close(ErrorObject::owned(
+                                ErrorCode::InternalError.code(),
+                                err.to_string(),
+                                None::<()>,
+                            ));
+                            return;
+                        }
+                    }
+                }
+            }
+        });
+        Ok(())
+    }
+
+    fn subscribe_new_pool_events(&self, sink: SubscriptionSink) -> SubscriptionResult {
+        broadcast_via_ws(self.0.pool_channels.pool_event_sender.clone(), sink);
+        Ok(())
+    }
 }
 
 /// Brodcast the stream(sender) content via a WebSocket
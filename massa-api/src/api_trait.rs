@@ -2,7 +2,7 @@
 //! Json RPC API for a massa-node
 use jsonrpsee::core::RpcResult;
 use jsonrpsee::proc_macros::rpc;
-use massa_models::version::Version;
+use massa_models::{address::Address, version::Version};
 
 /// Exposed API methods
 #[rpc(server)]
@@ -35,6 +35,14 @@ pub trait MassaApi {
 	)]
     fn subscribe_new_filled_blocks(&self);
 
+    /// New final blocks.
+    #[subscription(
+		name = "subscribe_new_final_blocks" => "new_final_blocks",
+		unsubscribe = "unsubscribe_new_final_blocks",
+		item = Block
+	)]
+    fn subscribe_new_final_blocks(&self);
+
     /// New produced operations.
     #[subscription(
 		name = "subscribe_new_operations" => "new_operations",
@@ -42,4 +50,20 @@ pub trait MassaApi {
 		item = Operation
 	)]
     fn subscribe_new_operations(&self);
+
+    /// Operations included in new blocks that involve a given address (as creator, recipient or called smart contract).
+    #[subscription(
+		name = "subscribe_new_operations_by_address" => "new_operations_by_address",
+		unsubscribe = "unsubscribe_new_operations_by_address",
+		item = WrappedOperation
+	)]
+    fn subscribe_new_operations_by_address(&self, address: Address);
+
+    /// New pool events (operations added, dropped or included in a block).
+    #[subscription(
+		name = "subscribe_new_pool_events" => "new_pool_events",
+		unsubscribe = "unsubscribe_new_pool_events",
+		item = PoolEvent
+	)]
+    fn subscribe_new_pool_events(&self);
 }
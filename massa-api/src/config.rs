@@ -1,5 +1,7 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
+use massa_models::amount::Amount;
+use massa_models::slot_scheduler::SlotTiming;
 use massa_time::MassaTime;
 use std::net::SocketAddr;
 use std::path::PathBuf;
@@ -18,6 +20,14 @@ pub struct APIConfig {
     pub bind_public: SocketAddr,
     /// bind for the Massa API
     pub bind_api: SocketAddr,
+    /// bind for the gRPC API, not served if `None`
+    pub bind_grpc: Option<SocketAddr>,
+    /// observer mode: the staking keys are not loaded and cannot be changed
+    pub observer: bool,
+    /// bearer token required by the private API, no authentication if `None`
+    pub private_api_token: Option<String>,
+    /// max number of periods the blockclique can lag behind the current slot for the node to be considered synced
+    pub max_sync_lag_periods: u64,
     /// max argument count
     pub max_arguments: u64,
     /// openrpc specification path
@@ -38,6 +48,10 @@ pub struct APIConfig {
     pub max_log_length: u32,
     /// host filtering.
     pub allow_hosts: Vec<String>,
+    /// origins allowed to make cross-origin requests, any origin if empty.
+    pub cors_allowed_origins: Vec<String>,
+    /// how long browsers can cache the responses to CORS preflight requests.
+    pub cors_max_age: MassaTime,
     /// whether batch requests are supported by this server or not.
     pub batch_requests_supported: bool,
     /// the interval at which `Ping` frames are submitted.
@@ -66,4 +80,23 @@ pub struct APIConfig {
     pub t0: MassaTime,
     /// periods per cycle
     pub periods_per_cycle: u64,
+    /// endorsement count
+    pub endorsement_count: u32,
+    /// operation validity periods
+    pub operation_validity_periods: u64,
+    /// max number of periods between now and the start of the validity of a submitted operation
+    pub max_operation_future_validity_start_periods: u64,
+    /// cost (in coins) of a single roll
+    pub roll_price: Amount,
+}
+
+impl APIConfig {
+    /// Get the timing parameters of the slots
+    pub fn get_slot_timing(&self) -> SlotTiming {
+        SlotTiming {
+            thread_count: self.thread_count,
+            t0: self.t0,
+            genesis_timestamp: self.genesis_timestamp,
+        }
+    }
 }
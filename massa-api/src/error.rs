@@ -52,6 +52,8 @@ pub enum ApiError {
     BadRequest(String),
     /// Internal server error: {0}
     InternalServerError(String),
+    /// Node is unhealthy: {0}
+    Unhealthy(String),
 }
 
 impl From<ApiError> for JsonRpseeError {
@@ -75,8 +77,22 @@ impl From<ApiError> for JsonRpseeError {
             ApiError::MissingCommandSender(_) => -32017,
             ApiError::MissingConfig(_) => -32018,
             ApiError::WrongAPI => -32019,
+            ApiError::Unhealthy(_) => -32020,
         };
 
         CallError::Custom(ErrorObject::owned(code, err.to_string(), None::<()>)).into()
     }
 }
+
+impl From<ApiError> for tonic::Status {
+    fn from(err: ApiError) -> Self {
+        match err {
+            ApiError::BadRequest(_) | ApiError::ModelsError(_) => {
+                tonic::Status::invalid_argument(err.to_string())
+            }
+            ApiError::NotFound => tonic::Status::not_found(err.to_string()),
+            ApiError::Unhealthy(_) => tonic::Status::unavailable(err.to_string()),
+            _ => tonic::Status::internal(err.to_string()),
+        }
+    }
+}
@@ -0,0 +1,334 @@
+//! Copyright (c) 2022 MASSA LABS <info@massa.net>
+//! gRPC API for a massa-node, generated from `proto/massa/api/v1/api.proto`.
+//! Blocks and operations are streamed to the subscribers as they are received by the node,
+//! a subscriber too slow to follow misses the oldest ones, like the WebSocket subscriptions.
+
+use crate::error::ApiError;
+use crate::public::deserialize_wrapped_operation;
+use crate::{Grpc, API};
+use massa_models::{
+    address::Address,
+    block::{Block, BlockId, FilledBlock},
+    operation::{OperationId, WrappedOperation},
+    prehash::PreHashSet,
+    slot::Slot,
+    wrapped::WrappedSerializer,
+};
+use massa_serialization::Serializer;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use tokio::sync::{broadcast, broadcast::error::RecvError, mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use tonic::{Request, Response, Status};
+use tracing::{info, warn};
+
+/// Types and service generated from the protobuf definitions
+#[allow(missing_docs, clippy::derive_partial_eq_without_eq)]
+pub mod proto {
+    tonic::include_proto!("massa.api.v1");
+}
+
+use proto::massa_service_server::{MassaService, MassaServiceServer};
+
+/// Number of messages buffered for each subscriber before the stream waits for it
+const SUBSCRIPTION_BUFFER_SIZE: usize = 64;
+
+impl API<Grpc> {
+    /// generate a new gRPC API
+    pub fn new(grpc: Grpc) -> Self {
+        API(grpc)
+    }
+
+    /// Start serving the gRPC API on `url`
+    pub async fn serve(self, url: &SocketAddr) -> Result<GrpcStopHandle, std::io::Error> {
+        let listener = tokio::net::TcpListener::bind(url).await?;
+        let (stop_tx, stop_rx) = oneshot::channel::<()>();
+        let join_handle = tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(MassaServiceServer::new(self))
+                .serve_with_incoming_shutdown(TcpListenerStream::new(listener), async {
+                    let _ = stop_rx.await;
+                }),
+        );
+        Ok(GrpcStopHandle {
+            stop_tx,
+            join_handle,
+        })
+    }
+
+    fn check_arguments_count(&self, count: usize) -> Result<(), Status> {
+        if count as u64 > self.0.api_settings.max_arguments {
+            return Err(Status::invalid_argument("too many arguments"));
+        }
+        Ok(())
+    }
+}
+
+/// Used to be able to stop the gRPC API
+pub struct GrpcStopHandle {
+    stop_tx: oneshot::Sender<()>,
+    join_handle: JoinHandle<Result<(), tonic::transport::Error>>,
+}
+
+impl GrpcStopHandle {
+    /// stop the gRPC API gracefully
+    pub async fn stop(self) {
+        let _ = self.stop_tx.send(());
+        match self.join_handle.await {
+            Ok(Ok(())) => info!("gRPC API finished cleanly"),
+            Ok(Err(err)) => warn!("gRPC API stopped with an error: {}", err),
+            Err(err) => warn!("gRPC API thread panicked: {:?}", err),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl MassaService for API<Grpc> {
+    async fn get_version(
+        &self,
+        _request: Request<proto::GetVersionRequest>,
+    ) -> Result<Response<proto::GetVersionResponse>, Status> {
+        Ok(Response::new(proto::GetVersionResponse {
+            version: self.0.version.to_string(),
+        }))
+    }
+
+    async fn get_blocks(
+        &self,
+        request: Request<proto::GetBlocksRequest>,
+    ) -> Result<Response<proto::GetBlocksResponse>, Status> {
+        let ids = request.into_inner().ids;
+        self.check_arguments_count(ids.len())?;
+        let ids = parse_all::<BlockId>(&ids)?;
+        let read_blocks = self.0.storage.read_blocks();
+        let blocks = ids
+            .iter()
+            .filter_map(|id| read_blocks.get(id))
+            .map(|block| block_to_proto(&block.content))
+            .collect();
+        Ok(Response::new(proto::GetBlocksResponse { blocks }))
+    }
+
+    async fn get_operations(
+        &self,
+        request: Request<proto::GetOperationsRequest>,
+    ) -> Result<Response<proto::GetOperationsResponse>, Status> {
+        let ids = request.into_inner().ids;
+        self.check_arguments_count(ids.len())?;
+        let ids = parse_all::<OperationId>(&ids)?;
+        let read_operations = self.0.storage.read_operations();
+        let operations = ids
+            .iter()
+            .filter_map(|id| read_operations.get(id))
+            .map(operation_to_proto)
+            .collect();
+        Ok(Response::new(proto::GetOperationsResponse { operations }))
+    }
+
+    async fn get_balances(
+        &self,
+        request: Request<proto::GetBalancesRequest>,
+    ) -> Result<Response<proto::GetBalancesResponse>, Status> {
+        let addresses = request.into_inner().addresses;
+        self.check_arguments_count(addresses.len())?;
+        let addresses = parse_all::<Address>(&addresses)?;
+        let balances = self
+            .0
+            .execution_controller
+            .get_final_and_candidate_balance(&addresses)
+            .into_iter()
+            .zip(addresses.iter())
+            .map(
+                |((final_balance, candidate_balance), address)| proto::AddressBalance {
+                    address: address.to_string(),
+                    final_balance: final_balance.map(|amount| amount.to_string()),
+                    candidate_balance: candidate_balance.map(|amount| amount.to_string()),
+                },
+            )
+            .collect();
+        Ok(Response::new(proto::GetBalancesResponse { balances }))
+    }
+
+    async fn send_operations(
+        &self,
+        request: Request<proto::SendOperationsRequest>,
+    ) -> Result<Response<proto::SendOperationsResponse>, Status> {
+        let operations = request.into_inner().operations;
+        self.check_arguments_count(operations.len())?;
+        if self.0.resource_status.is_degraded() {
+            return Err(Status::unavailable(
+                "degraded read-only mode, new operations are not accepted",
+            ));
+        }
+        let operations = operations
+            .iter()
+            .map(|serialized| deserialize_wrapped_operation(serialized, &self.0.api_settings))
+            .collect::<Result<Vec<WrappedOperation>, ApiError>>()?;
+        WrappedOperation::verify_signatures_batch(&operations).map_err(ApiError::ModelsError)?;
+        let ids = operations
+            .iter()
+            .map(|operation| operation.id.to_string())
+            .collect();
+
+        let mut to_send = self.0.storage.clone_without_refs();
+        to_send.store_operations(operations);
+        self.0
+            .pool_command_sender
+            .clone()
+            .add_operations(to_send.clone());
+        let mut protocol_sender = self.0.protocol_command_sender.clone();
+        tokio::task::spawn_blocking(move || protocol_sender.propagate_operations(to_send))
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?
+            .map_err(|err| Status::internal(format!("Failed to propagate operations: {}", err)))?;
+        Ok(Response::new(proto::SendOperationsResponse { ids }))
+    }
+
+    type SubscribeNewBlocksStream = ReceiverStream<Result<proto::Block, Status>>;
+
+    async fn subscribe_new_blocks(
+        &self,
+        _request: Request<proto::SubscribeNewBlocksRequest>,
+    ) -> Result<Response<Self::SubscribeNewBlocksStream>, Status> {
+        Ok(Response::new(stream_broadcast(
+            &self.0.consensus_channels.block_sender,
+            |block: Block| vec![block_to_proto(&block)],
+        )))
+    }
+
+    type SubscribeNewFinalBlocksStream = ReceiverStream<Result<proto::Block, Status>>;
+
+    async fn subscribe_new_final_blocks(
+        &self,
+        _request: Request<proto::SubscribeNewFinalBlocksRequest>,
+    ) -> Result<Response<Self::SubscribeNewFinalBlocksStream>, Status> {
+        Ok(Response::new(stream_broadcast(
+            &self.0.consensus_channels.final_block_sender,
+            |block: Block| vec![block_to_proto(&block)],
+        )))
+    }
+
+    type SubscribeNewOperationsStream = ReceiverStream<Result<proto::IncludedOperation, Status>>;
+
+    async fn subscribe_new_operations(
+        &self,
+        request: Request<proto::SubscribeNewOperationsRequest>,
+    ) -> Result<Response<Self::SubscribeNewOperationsStream>, Status> {
+        let addresses = request.into_inner().addresses;
+        self.check_arguments_count(addresses.len())?;
+        let addresses: PreHashSet<Address> =
+            parse_all::<Address>(&addresses)?.into_iter().collect();
+        Ok(Response::new(stream_broadcast(
+            &self.0.consensus_channels.filled_block_sender,
+            move |filled_block: FilledBlock| {
+                let block_id = filled_block.header.id.to_string();
+                let slot = slot_to_proto(filled_block.header.content.slot);
+                filled_block
+                    .operations
+                    .iter()
+                    .filter_map(|(_id, operation)| operation.as_ref())
+                    .filter(|operation| {
+                        addresses.is_empty()
+                            || !operation
+                                .get_ledger_involved_addresses()
+                                .is_disjoint(&addresses)
+                    })
+                    .map(|operation| proto::IncludedOperation {
+                        operation: Some(operation_to_proto(operation)),
+                        block_id: block_id.clone(),
+                        slot: Some(slot.clone()),
+                    })
+                    .collect()
+            },
+        )))
+    }
+}
+
+/// Forward the items broadcast by `sender` to a new subscriber, each item becoming zero or more messages.
+/// The forwarding stops when the subscriber drops the stream or the sender is dropped.
+fn stream_broadcast<T, M, F>(
+    sender: &broadcast::Sender<T>,
+    to_messages: F,
+) -> ReceiverStream<Result<M, Status>>
+where
+    T: Clone + Send + 'static,
+    M: Send + 'static,
+    F: Fn(T) -> Vec<M> + Send + 'static,
+{
+    let mut rx = sender.subscribe();
+    let (tx, stream_rx) = mpsc::channel(SUBSCRIPTION_BUFFER_SIZE);
+    tokio::spawn(async move {
+        loop {
+            let item = match rx.recv().await {
+                Ok(item) => item,
+                // some items were missed by this subscriber, keep going with the next ones
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return,
+            };
+            for message in to_messages(item) {
+                if tx.send(Ok(message)).await.is_err() {
+                    // the subscriber dropped the stream
+                    return;
+                }
+            }
+        }
+    });
+    ReceiverStream::new(stream_rx)
+}
+
+/// Parse ids or addresses given in their text form
+fn parse_all<T: FromStr>(values: &[String]) -> Result<Vec<T>, Status> {
+    values
+        .iter()
+        .map(|value| {
+            T::from_str(value).map_err(|_| Status::invalid_argument(format!("invalid {}", value)))
+        })
+        .collect()
+}
+
+fn slot_to_proto(slot: Slot) -> proto::Slot {
+    proto::Slot {
+        period: slot.period,
+        thread: slot.thread as u32,
+    }
+}
+
+fn block_to_proto(block: &Block) -> proto::Block {
+    let header = &block.header;
+    let mut serialized_header = Vec::new();
+    if let Err(err) = WrappedSerializer::new().serialize(header, &mut serialized_header) {
+        warn!(
+            "could not serialize the header of block {}: {}",
+            header.id, err
+        );
+    }
+    proto::Block {
+        id: header.id.to_string(),
+        slot: Some(slot_to_proto(header.content.slot)),
+        creator_address: header.creator_address.to_string(),
+        parents: header
+            .content
+            .parents
+            .iter()
+            .map(|parent| parent.to_string())
+            .collect(),
+        operation_ids: block.operations.iter().map(|id| id.to_string()).collect(),
+        endorsement_count: header.content.endorsements.len() as u32,
+        serialized_header,
+    }
+}
+
+fn operation_to_proto(operation: &WrappedOperation) -> proto::Operation {
+    let mut serialized = Vec::new();
+    if let Err(err) = WrappedSerializer::new().serialize(operation, &mut serialized) {
+        warn!("could not serialize operation {}: {}", operation.id, err);
+    }
+    proto::Operation {
+        id: operation.id.to_string(),
+        creator_address: operation.creator_address.to_string(),
+        fee: operation.content.fee.to_string(),
+        expire_period: operation.content.expire_period,
+        serialized,
+    }
+}
@@ -5,25 +5,38 @@
 #![warn(unused_crate_dependencies)]
 use crate::api_trait::MassaApiServer;
 use crate::error::ApiError::WrongAPI;
-use hyper::Method;
+use hyper::{header::HeaderValue, Method};
 use jsonrpsee::core::{Error as JsonRpseeError, RpcResult};
 use jsonrpsee::proc_macros::rpc;
+use jsonrpsee::server::middleware::proxy_get_request::ProxyGetRequestLayer;
 use jsonrpsee::server::{AllowHosts, ServerBuilder, ServerHandle};
 use jsonrpsee::RpcModule;
 use massa_consensus_exports::{ConsensusChannels, ConsensusController};
 use massa_execution_exports::ExecutionController;
+use massa_final_state::FinalState;
+use massa_logging::{LogFilterHandle, RecentLogs};
 use massa_models::api::{
-    AddressInfo, BlockInfo, BlockSummary, DatastoreEntryInput, DatastoreEntryOutput,
-    EndorsementInfo, EventFilter, NodeStatus, OperationInfo, OperationInput,
-    ReadOnlyBytecodeExecution, ReadOnlyCall, TimeInterval,
+    AddressInfo, BlockCandidateInput, BlockInfo, BlockSummary, BlockValidityReport,
+    ConnectedNodeInfo, DatastoreEntryInput, DatastoreEntryOutput, EndorsementInfo, EventFilter,
+    ExportContent, ExportFormat, ExportStats, FinalOperationEntry, ForkChoiceExplanation,
+    NetworkVersions, NodeHealth, NodeStatus, OperationInfo, OperationInput, OperationStatusInfo,
+    OperationSubmissionResult, PagedResult, Pagination, PeerConnectionEvent, ProductionOverride,
+    ReadOnlyBytecodeExecution, ReadOnlyCall, ReorgAlert, StorageCheckReport, TimeInterval,
 };
+use massa_models::balance_proof::FinalBalanceProof;
+use massa_models::ban_list::{BanList, IpSubnet};
 use massa_models::clique::Clique;
 use massa_models::composite::PubkeySig;
 use massa_models::execution::ExecuteReadOnlyResponse;
+use massa_models::network_faults::{MessageFaults, NetworkFaults};
 use massa_models::node::NodeId;
 use massa_models::operation::OperationId;
 use massa_models::output_event::SCOutputEvent;
 use massa_models::prehash::PreHashSet;
+use massa_models::production_gate::ProductionGate;
+use massa_models::resource_status::ResourceStatus;
+use massa_models::stats::{CycleProductionStats, OperationPoolStats};
+use massa_models::sync_progress::SyncProgressStatus;
 use massa_models::{
     address::Address,
     block::{Block, BlockId},
@@ -32,16 +45,21 @@ use massa_models::{
     version::Version,
 };
 use massa_network_exports::{NetworkCommandSender, NetworkConfig};
-use massa_pool_exports::PoolController;
+use massa_pool_exports::{PoolChannels, PoolController};
 use massa_pos_exports::SelectorController;
-use massa_protocol_exports::{ProtocolCommandSender, ProtocolSenders};
-use massa_storage::Storage;
+use massa_protocol_exports::{NetworkPartitionStatus, ProtocolCommandSender, ProtocolSenders};
+use massa_storage::{address_index::AddressIndex, archive::Archive, Storage};
 use massa_wallet::Wallet;
 use parking_lot::RwLock;
 use serde_json::Value;
 use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
 use std::sync::Arc;
-use tower_http::cors::{Any, CorsLayer};
+use std::time::Instant;
+use tower_http::{
+    cors::{AllowOrigin, CorsLayer},
+    validate_request::ValidateRequestHeaderLayer,
+};
 
 use tokio::sync::mpsc;
 use tracing::{info, warn};
@@ -50,9 +68,11 @@ mod api;
 mod api_trait;
 mod config;
 mod error;
+mod grpc;
 mod private;
 mod public;
 pub use config::APIConfig;
+pub use grpc::{proto as grpc_proto, GrpcStopHandle};
 
 /// Public API component
 pub struct Public {
@@ -68,6 +88,8 @@ pub struct Public {
     pub protocol_command_sender: ProtocolCommandSender,
     /// Massa storage
     pub storage: Storage,
+    /// index of the final operations by address, if enabled
+    pub address_index: Option<AddressIndex>,
     /// API settings
     pub api_settings: APIConfig,
     /// network setting
@@ -78,6 +100,14 @@ pub struct Public {
     pub network_command_sender: NetworkCommandSender,
     /// our node id
     pub node_id: NodeId,
+    /// alerts of the network partition watchdog
+    pub partition_status: NetworkPartitionStatus,
+    /// progress of the synchronization, updated by the bootstrap and the protocol
+    pub sync_progress: SyncProgressStatus,
+    /// alerts of the resource monitor, new operations are refused while the node is degraded
+    pub resource_status: ResourceStatus,
+    /// instant at which the API was created, used to compute the node uptime
+    pub start_instant: Instant,
 }
 
 /// Private API content
@@ -86,12 +116,28 @@ pub struct Private {
     pub network_command_sender: NetworkCommandSender,
     /// link to the execution component
     pub execution_controller: Box<dyn ExecutionController>,
+    /// link to the consensus component
+    pub consensus_controller: Box<dyn ConsensusController>,
+    /// final state
+    pub final_state: Arc<RwLock<FinalState>>,
     /// API settings
     pub api_settings: APIConfig,
     /// stop channel
     pub stop_node_channel: mpsc::Sender<()>,
     /// User wallet
     pub node_wallet: Arc<RwLock<Wallet>>,
+    /// handle to change the log filter at runtime
+    pub log_filter_handle: LogFilterHandle,
+    /// latest log lines of the node
+    pub recent_logs: RecentLogs,
+    /// configuration reload request channel
+    pub reload_config_channel: mpsc::Sender<()>,
+    /// archive of the final blocks, on archival nodes
+    pub archive: Option<Archive>,
+    /// index of the final operations by address, if enabled
+    pub address_index: Option<AddressIndex>,
+    /// override of the block production interlock
+    pub production_gate: ProductionGate,
 }
 
 /// API v2 content
@@ -100,12 +146,34 @@ pub struct ApiV2 {
     pub consensus_channels: ConsensusChannels,
     /// link(channels) to the protocol component
     pub protocol_senders: ProtocolSenders,
+    /// link(channels) to the pool component
+    pub pool_channels: PoolChannels,
     /// API settings
     pub api_settings: APIConfig,
     /// node version
     pub version: Version,
 }
 
+/// gRPC API content
+pub struct Grpc {
+    /// link(channels) to the consensus component
+    pub consensus_channels: ConsensusChannels,
+    /// link to the execution component
+    pub execution_controller: Box<dyn ExecutionController>,
+    /// link to the pool component
+    pub pool_command_sender: Box<dyn PoolController>,
+    /// link to the protocol component
+    pub protocol_command_sender: ProtocolCommandSender,
+    /// Massa storage
+    pub storage: Storage,
+    /// API settings
+    pub api_settings: APIConfig,
+    /// node version
+    pub version: Version,
+    /// alerts of the resource monitor, new operations are refused while the node is degraded
+    pub resource_status: ResourceStatus,
+}
+
 /// The API wrapper
 pub struct API<T>(T);
 
@@ -131,10 +199,14 @@ pub trait ApiServer: MassaApiServer {
     ) -> Result<StopHandle, JsonRpseeError>;
 }
 
+/// Start a server, requiring `bearer_token` in the `Authorization` header of every request if set,
+/// and answering HTTP GET requests on `/health` with the result of `health_method` if set
 async fn serve<T>(
     api: RpcModule<T>,
     url: &SocketAddr,
     api_config: &APIConfig,
+    bearer_token: Option<&str>,
+    health_method: Option<&str>,
 ) -> Result<StopHandle, JsonRpseeError> {
     let allowed_hosts = if api_config.allow_hosts.is_empty() {
         AllowHosts::Any
@@ -151,6 +223,7 @@ async fn serve<T>(
         .max_request_body_size(api_config.max_request_body_size)
         .max_response_body_size(api_config.max_response_body_size)
         .max_connections(api_config.max_connections)
+        .max_subscriptions_per_connection(api_config.max_subscriptions_per_connection)
         .set_host_filtering(allowed_hosts)
         .batch_requests_supported(api_config.batch_requests_supported)
         .ping_interval(api_config.ping_interval.to_duration());
@@ -163,14 +236,32 @@ async fn serve<T>(
         panic!("wrong server configuration, you can't disable both http and ws");
     }
 
+    // Allow requests from any origin unless restricted by the configuration
+    let allowed_origins = if api_config.cors_allowed_origins.is_empty() {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(api_config.cors_allowed_origins.iter().map(|origin| {
+            origin.parse::<HeaderValue>().unwrap_or_else(|_| {
+                panic!("wrong server configuration, invalid CORS origin {}", origin)
+            })
+        }))
+    };
+
     let cors = CorsLayer::new()
         // Allow `POST` and `OPTIONS` when accessing the resource
         .allow_methods([Method::POST, Method::OPTIONS])
-        // Allow requests from any origin
-        .allow_origin(Any)
-        .allow_headers([hyper::header::CONTENT_TYPE]);
-
-    let middleware = tower::ServiceBuilder::new().layer(cors);
+        .allow_origin(allowed_origins)
+        .allow_headers([hyper::header::CONTENT_TYPE, hyper::header::AUTHORIZATION])
+        // Let browsers cache the preflight responses
+        .max_age(api_config.cors_max_age.to_duration());
+
+    // CORS preflight requests are answered before the token is checked
+    let middleware = tower::ServiceBuilder::new()
+        .layer(cors)
+        .option_layer(health_method.map(|method| {
+            ProxyGetRequestLayer::new("/health", method).expect("invalid health endpoint path")
+        }))
+        .option_layer(bearer_token.map(ValidateRequestHeaderLayer::bearer));
 
     let server = server_builder
         .set_middleware(middleware)
@@ -241,6 +332,11 @@ pub trait MassaRpc {
     #[method(name = "get_staking_addresses")]
     async fn get_staking_addresses(&self) -> RpcResult<PreHashSet<Address>>;
 
+    /// Returns the block production stats of the staking addresses for the last cycles:
+    /// produced vs expected blocks, stale blocks, observed stale rate, average block fill and latency.
+    #[method(name = "get_stats")]
+    async fn get_stats(&self) -> RpcResult<Vec<CycleProductionStats>>;
+
     /// Bans given IP address(es).
     /// No confirmation to expect.
     #[method(name = "node_ban_by_ip")]
@@ -296,6 +392,64 @@ pub trait MassaRpc {
     #[method(name = "node_remove_from_bootstrap_blacklist")]
     async fn node_remove_from_bootstrap_blacklist(&self, arg: Vec<IpAddr>) -> RpcResult<()>;
 
+    /// Export the final state and the final blocks to a snapshot file on the node machine.
+    /// The file can be imported at startup through the `snapshot_import_path` bootstrap setting.
+    /// Returns the final slot of the snapshot.
+    #[method(name = "node_export_snapshot")]
+    async fn node_export_snapshot(&self, arg: PathBuf) -> RpcResult<Slot>;
+
+    /// Export the archived final blocks of the slot range from `start` (included) to `end` (excluded)
+    /// to a file on the node machine, one record per block or per operation, in JSON lines or CSV.
+    #[method(name = "node_export_chain_data")]
+    async fn node_export_chain_data(
+        &self,
+        start: Slot,
+        end: Slot,
+        content: ExportContent,
+        format: ExportFormat,
+        path: PathBuf,
+    ) -> RpcResult<ExportStats>;
+
+    /// Replace the log filter of the node by comma-separated directives,
+    /// a default level optionally followed by per-module levels (ex: `info,massa_protocol_worker=debug`).
+    #[method(name = "node_set_log_filter")]
+    async fn node_set_log_filter(&self, arg: String) -> RpcResult<()>;
+
+    /// Get the current log filter directives of the node.
+    #[method(name = "node_get_log_filter")]
+    async fn node_get_log_filter(&self) -> RpcResult<String>;
+
+    /// Get the latest `arg` lines logged by the node, oldest first.
+    /// The node keeps the number of lines set by `logging.recent_lines` in its configuration.
+    #[method(name = "node_get_recent_logs")]
+    async fn node_get_recent_logs(&self, arg: usize) -> RpcResult<Vec<String>>;
+
+    /// Read the configuration files again and apply the settings that can be changed without restarting the node:
+    /// connection limits, peers dump interval, pool minimal fee and log levels.
+    /// The outcome of the reload is reported in the node logs.
+    #[method(name = "node_reload_config")]
+    async fn node_reload_config(&self) -> RpcResult<()>;
+
+    /// Override the safety interlock of the block production: `automatic` only produces while the node is connected
+    /// to enough nodes and synced, `always_produce` ignores these conditions and `never_produce` pauses the production.
+    #[method(name = "node_set_production_override")]
+    async fn node_set_production_override(&self, arg: ProductionOverride) -> RpcResult<()>;
+
+    /// Get the override of the safety interlock of the block production.
+    #[method(name = "node_get_production_override")]
+    async fn node_get_production_override(&self) -> RpcResult<ProductionOverride>;
+
+    /// Accept the blockclique switch rejected for rolling back more than `max_reorg_depth_periods` periods,
+    /// once the next block is added to the graph. Returns the alert raised for it, fails if no switch is rejected.
+    #[method(name = "node_accept_reorg")]
+    async fn node_accept_reorg(&self) -> RpcResult<ReorgAlert>;
+
+    /// Check the integrity of the archive and of the address index: every archived block decodes, matches its slot
+    /// and is validly signed, and every index entry points to the block archived at its slot.
+    /// If `repair` is true, the corrupted entries are removed and the archived blocks are indexed again.
+    #[method(name = "node_check_storage")]
+    async fn node_check_storage(&self, repair: bool) -> RpcResult<StorageCheckReport>;
+
     /// Unban given IP address(es).
     /// No confirmation to expect.
     #[method(name = "node_unban_by_ip")]
@@ -306,10 +460,77 @@ pub trait MassaRpc {
     #[method(name = "node_unban_by_id")]
     async fn node_unban_by_id(&self, arg: Vec<NodeId>) -> RpcResult<()>;
 
+    /// Bans given subnet(s), in CIDR notation.
+    /// No confirmation to expect.
+    #[method(name = "node_ban_by_subnet")]
+    async fn node_ban_by_subnet(&self, arg: Vec<IpSubnet>) -> RpcResult<()>;
+
+    /// Unban given subnet(s), with the IP addresses and smaller subnets they contain.
+    /// No confirmation to expect.
+    #[method(name = "node_unban_by_subnet")]
+    async fn node_unban_by_subnet(&self, arg: Vec<IpSubnet>) -> RpcResult<()>;
+
+    /// Get the banned IP addresses and subnets, with the reason and expiry of their ban,
+    /// in a format that other nodes can import.
+    #[method(name = "node_export_ban_list")]
+    async fn node_export_ban_list(&self) -> RpcResult<BanList>;
+
+    /// Merge the given bans with the bans of the node, the longest ban of a target winning.
+    /// The connections of the newly banned IP addresses are closed.
+    /// Imported bans are kept in the peers file until they expire.
+    #[method(name = "node_import_ban_list")]
+    async fn node_import_ban_list(&self, arg: BanList) -> RpcResult<()>;
+
+    /// Write the known peers and bans to the peers file right away, whatever the dump interval.
+    /// Returns once the file is written.
+    #[method(name = "node_flush_peers")]
+    async fn node_flush_peers(&self) -> RpcResult<()>;
+
+    /// Read the peers file again and merge it with the peers and bans in memory:
+    /// the unknown peers are added, the known peers take the peer type of the file and the bans are merged.
+    /// The connections of the newly banned IP addresses are closed.
+    #[method(name = "node_reload_peers")]
+    async fn node_reload_peers(&self) -> RpcResult<()>;
+
+    /// Get the last ended connections of the peer at the given IP address, oldest first,
+    /// with their direction, duration and close reason.
+    #[method(name = "get_peer_history")]
+    async fn get_peer_history(&self, arg: IpAddr) -> RpcResult<Vec<PeerConnectionEvent>>;
+
+    /// Set the faults injected in the messages sent to the given peer, or to all the peers without faults of their own if no node ID is given.
+    /// The faults are removed if none are given, all of them if no node ID is given either.
+    /// Returns all the faults then injected.
+    /// Refused unless the node is built with the `fault_injection` feature, never enable it on a production node.
+    #[method(name = "node_set_network_faults")]
+    async fn node_set_network_faults(
+        &self,
+        arg1: Option<NodeId>,
+        arg2: Option<MessageFaults>,
+    ) -> RpcResult<NetworkFaults>;
+
     /// Summary of the current state: time, last final blocks (hash, thread, slot, timestamp), clique count, connected nodes count.
     #[method(name = "get_status")]
     async fn get_status(&self) -> RpcResult<NodeStatus>;
 
+    /// Get the nodes connected to our node: id, ip address, direction of the connection,
+    /// last time the peer was alive and bytes exchanged since the connection was established.
+    #[method(name = "get_connected_nodes")]
+    async fn get_connected_nodes(&self) -> RpcResult<Vec<ConnectedNodeInfo>>;
+
+    /// Get the software versions announced by the connected nodes in their handshake,
+    /// with the number of nodes running each of them, to follow the adoption of an upgrade.
+    #[method(name = "get_network_versions")]
+    async fn get_network_versions(&self) -> RpcResult<NetworkVersions>;
+
+    /// Check that the node is synced and connected to other nodes.
+    /// Also served as a plain HTTP GET on `/health` by the public API, for load balancers and monitoring probes.
+    #[method(name = "get_health")]
+    async fn get_health(&self) -> RpcResult<NodeHealth>;
+
+    /// Get the size of the operation pool and the histogram of the fee per byte of its operations.
+    #[method(name = "get_operation_pool_stats")]
+    async fn get_operation_pool_stats(&self) -> RpcResult<OperationPoolStats>;
+
     /// Get cliques.
     #[method(name = "get_cliques")]
     async fn get_cliques(&self) -> RpcResult<Vec<Clique>>;
@@ -322,6 +543,14 @@ pub trait MassaRpc {
     #[method(name = "get_operations")]
     async fn get_operations(&self, arg: Vec<OperationId>) -> RpcResult<Vec<OperationInfo>>;
 
+    /// Returns the lifecycle status (pending, included, final, dropped) of a given list of operation(s) ID(s).
+    /// Statuses are only kept for a bounded number of operations: forgotten operations are reported as unknown.
+    #[method(name = "get_operation_statuses")]
+    async fn get_operation_statuses(
+        &self,
+        arg: Vec<OperationId>,
+    ) -> RpcResult<Vec<OperationStatusInfo>>;
+
     /// Returns endorsement(s) information associated to a given list of endorsement(s) ID(s)
     #[method(name = "get_endorsements")]
     async fn get_endorsements(&self, arg: Vec<EndorsementId>) -> RpcResult<Vec<EndorsementInfo>>;
@@ -340,6 +569,40 @@ pub trait MassaRpc {
     #[method(name = "get_graph_interval")]
     async fn get_graph_interval(&self, arg: TimeInterval) -> RpcResult<Vec<BlockSummary>>;
 
+    /// Get the stored blocks from `<start>` (included) to `<end>` (excluded) slot, sorted by slot.
+    #[method(name = "get_blocks_by_slot_range")]
+    async fn get_blocks_by_slot_range(
+        &self,
+        start: Slot,
+        end: Slot,
+        pagination: Pagination,
+    ) -> RpcResult<PagedResult<BlockSummary>>;
+
+    /// Get the stored blocks created by an address, sorted by slot.
+    #[method(name = "get_blocks_by_creator")]
+    async fn get_blocks_by_creator(
+        &self,
+        address: Address,
+        pagination: Pagination,
+    ) -> RpcResult<PagedResult<BlockSummary>>;
+
+    /// Get the stored operations involving an address as creator, recipient or call target, sorted by expiration period.
+    #[method(name = "get_operations_by_address")]
+    async fn get_operations_by_address(
+        &self,
+        address: Address,
+        pagination: Pagination,
+    ) -> RpcResult<PagedResult<OperationInfo>>;
+
+    /// Get the final operations involving an address as creator, recipient or call target, sorted by slot.
+    /// Only available if the address index is enabled.
+    #[method(name = "get_final_operations_by_address")]
+    async fn get_final_operations_by_address(
+        &self,
+        address: Address,
+        pagination: Pagination,
+    ) -> RpcResult<PagedResult<FinalOperationEntry>>;
+
     /// Get multiple datastore entries.
     #[method(name = "get_datastore_entries")]
     async fn get_datastore_entries(
@@ -351,10 +614,31 @@ pub trait MassaRpc {
     #[method(name = "get_addresses")]
     async fn get_addresses(&self, arg: Vec<Address>) -> RpcResult<Vec<AddressInfo>>;
 
+    /// Get the final balance of an address with a Merkle proof against the latest final balance state root.
+    #[method(name = "get_balance_proof")]
+    async fn get_balance_proof(&self, arg: Address) -> RpcResult<FinalBalanceProof>;
+
     /// Adds operations to pool. Returns operations that were ok and sent to pool.
     #[method(name = "send_operations")]
     async fn send_operations(&self, arg: Vec<OperationInput>) -> RpcResult<Vec<OperationId>>;
 
+    /// Validate an operation (signature, expiry and balance), add it to pool (fee, conflicts and capacity) and propagate it if it was added.
+    /// Returns whether the operation was accepted, with a reason code if it was rejected.
+    #[method(name = "submit_operation")]
+    async fn submit_operation(&self, arg: OperationInput) -> RpcResult<OperationSubmissionResult>;
+
+    /// Check a candidate block against the current graph state without inserting it.
+    /// Returns the reasons why the block would be rejected, if any.
+    #[method(name = "check_block_validity")]
+    async fn check_block_validity(
+        &self,
+        arg: BlockCandidateInput,
+    ) -> RpcResult<BlockValidityReport>;
+
+    /// Explain the fork choice status of a block: its cliques, why it is or is not in the blockclique and its distance from finality.
+    #[method(name = "explain_fork_choice")]
+    async fn explain_fork_choice(&self, arg: BlockId) -> RpcResult<ForkChoiceExplanation>;
+
     /// Get events optionally filtered by:
     /// * start slot
     /// * end slot
@@ -7,18 +7,32 @@ use crate::{MassaRpcServer, Private, RpcServer, StopHandle, Value, API};
 use async_trait::async_trait;
 use itertools::Itertools;
 use jsonrpsee::core::{Error as JsonRpseeError, RpcResult};
+use massa_bootstrap::export_snapshot;
+use massa_consensus_exports::ConsensusController;
 use massa_execution_exports::ExecutionController;
+use massa_final_state::FinalState;
+use massa_logging::{LogFilterHandle, RecentLogs};
 use massa_models::api::{
-    AddressInfo, BlockInfo, BlockSummary, DatastoreEntryInput, DatastoreEntryOutput,
-    EndorsementInfo, EventFilter, ListType, NodeStatus, OperationInfo, OperationInput,
-    ReadOnlyBytecodeExecution, ReadOnlyCall, ScrudOperation, TimeInterval,
+    AddressInfo, BlockCandidateInput, BlockInfo, BlockSummary, BlockValidityReport,
+    ConnectedNodeInfo, DatastoreEntryInput, DatastoreEntryOutput, EndorsementInfo, EventFilter,
+    ExportContent, ExportFormat, ExportStats, FinalOperationEntry, ForkChoiceExplanation, ListType,
+    NetworkVersions, NodeHealth, NodeStatus, OperationInfo, OperationInput, OperationStatusInfo,
+    OperationSubmissionResult, PagedResult, Pagination, PeerConnectionEvent, ProductionOverride,
+    ReadOnlyBytecodeExecution, ReadOnlyCall, ReorgAlert, ScrudOperation, StorageCheckReport,
+    TimeInterval,
 };
+use massa_models::balance_proof::FinalBalanceProof;
+use massa_models::ban_list::{BanList, IpSubnet};
 use massa_models::clique::Clique;
 use massa_models::composite::PubkeySig;
+use massa_models::config::VERSION;
 use massa_models::execution::ExecuteReadOnlyResponse;
+use massa_models::network_faults::{MessageFaults, NetworkFaults};
 use massa_models::node::NodeId;
 use massa_models::output_event::SCOutputEvent;
 use massa_models::prehash::PreHashSet;
+use massa_models::production_gate::ProductionGate;
+use massa_models::stats::{CycleProductionStats, OperationPoolStats};
 use massa_models::{
     address::Address,
     block::{Block, BlockId},
@@ -26,8 +40,14 @@ use massa_models::{
     operation::OperationId,
     slot::Slot,
 };
-use massa_network_exports::NetworkCommandSender;
+use massa_network_exports::{BanReason, NetworkCommandSender};
 use massa_signature::KeyPair;
+use massa_storage::{
+    address_index::AddressIndex,
+    archive::Archive,
+    check::check_storage,
+    export::{export_blocks, ExportError},
+};
 use massa_wallet::Wallet;
 
 use parking_lot::RwLock;
@@ -37,24 +57,41 @@ use std::net::{IpAddr, SocketAddr};
 use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
-use tokio::sync::mpsc;
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tracing::info;
 
 impl API<Private> {
     /// generate a new private API
     pub fn new(
         network_command_sender: NetworkCommandSender,
         execution_controller: Box<dyn ExecutionController>,
+        consensus_controller: Box<dyn ConsensusController>,
+        final_state: Arc<RwLock<FinalState>>,
         api_settings: APIConfig,
         node_wallet: Arc<RwLock<Wallet>>,
+        log_filter_handle: LogFilterHandle,
+        recent_logs: RecentLogs,
+        reload_config_channel: mpsc::Sender<()>,
+        archive: Option<Archive>,
+        address_index: Option<AddressIndex>,
+        production_gate: ProductionGate,
     ) -> (Self, mpsc::Receiver<()>) {
         let (stop_node_channel, rx) = mpsc::channel(1);
         (
             API(Private {
                 network_command_sender,
                 execution_controller,
+                consensus_controller,
+                final_state,
                 api_settings,
                 stop_node_channel,
                 node_wallet,
+                log_filter_handle,
+                recent_logs,
+                reload_config_channel,
+                archive,
+                address_index,
+                production_gate,
             }),
             rx,
         )
@@ -68,7 +105,14 @@ impl RpcServer for API<Private> {
         url: &SocketAddr,
         settings: &APIConfig,
     ) -> Result<StopHandle, JsonRpseeError> {
-        crate::serve(self.into_rpc(), url, settings).await
+        crate::serve(
+            self.into_rpc(),
+            url,
+            settings,
+            settings.private_api_token.as_deref(),
+            None,
+        )
+        .await
     }
 }
 
@@ -92,6 +136,12 @@ impl MassaRpcServer for API<Private> {
     }
 
     async fn add_staking_secret_keys(&self, secret_keys: Vec<String>) -> RpcResult<()> {
+        if self.0.api_settings.observer {
+            return Err(ApiError::BadRequest(
+                "observer node: the staking keys are not loaded".to_string(),
+            )
+            .into());
+        }
         let keypairs = match secret_keys.iter().map(|x| KeyPair::from_str(x)).collect() {
             Ok(keypairs) => keypairs,
             Err(e) => return Err(ApiError::BadRequest(e.to_string()).into()),
@@ -120,6 +170,12 @@ impl MassaRpcServer for API<Private> {
     }
 
     async fn remove_staking_addresses(&self, addresses: Vec<Address>) -> RpcResult<()> {
+        if self.0.api_settings.observer {
+            return Err(ApiError::BadRequest(
+                "observer node: the staking keys are not loaded".to_string(),
+            )
+            .into());
+        }
         let node_wallet = self.0.node_wallet.clone();
         let mut w_wallet = node_wallet.write();
         w_wallet
@@ -133,10 +189,18 @@ impl MassaRpcServer for API<Private> {
         Ok(w_wallet.get_wallet_address_list())
     }
 
+    async fn get_stats(&self) -> RpcResult<Vec<CycleProductionStats>> {
+        let staking_addresses = self.0.node_wallet.read().get_wallet_address_list();
+        self.0
+            .consensus_controller
+            .get_production_stats(&staking_addresses)
+            .map_err(|e| ApiError::ConsensusError(e).into())
+    }
+
     async fn node_ban_by_ip(&self, ips: Vec<IpAddr>) -> RpcResult<()> {
         let network_command_sender = self.0.network_command_sender.clone();
         network_command_sender
-            .node_ban_by_ips(ips)
+            .node_ban_by_ips(ips, BanReason::Manual)
             .await
             .map_err(|e| ApiError::NetworkError(e).into())
     }
@@ -144,7 +208,7 @@ impl MassaRpcServer for API<Private> {
     async fn node_ban_by_id(&self, ids: Vec<NodeId>) -> RpcResult<()> {
         let network_command_sender = self.0.network_command_sender.clone();
         network_command_sender
-            .node_ban_by_ids(ids)
+            .node_ban_by_ids(ids, BanReason::Manual)
             .await
             .map_err(|e| ApiError::NetworkError(e).into())
     }
@@ -165,10 +229,94 @@ impl MassaRpcServer for API<Private> {
             .map_err(|e| ApiError::NetworkError(e).into())
     }
 
+    async fn node_ban_by_subnet(&self, subnets: Vec<IpSubnet>) -> RpcResult<()> {
+        let network_command_sender = self.0.network_command_sender.clone();
+        network_command_sender
+            .node_ban_by_subnets(subnets)
+            .await
+            .map_err(|e| ApiError::NetworkError(e).into())
+    }
+
+    async fn node_unban_by_subnet(&self, subnets: Vec<IpSubnet>) -> RpcResult<()> {
+        let network_command_sender = self.0.network_command_sender.clone();
+        network_command_sender
+            .node_unban_subnets(subnets)
+            .await
+            .map_err(|e| ApiError::NetworkError(e).into())
+    }
+
+    async fn node_export_ban_list(&self) -> RpcResult<BanList> {
+        let network_command_sender = self.0.network_command_sender.clone();
+        network_command_sender
+            .get_ban_list()
+            .await
+            .map_err(|e| ApiError::NetworkError(e).into())
+    }
+
+    async fn node_import_ban_list(&self, ban_list: BanList) -> RpcResult<()> {
+        let network_command_sender = self.0.network_command_sender.clone();
+        network_command_sender
+            .import_ban_list(ban_list)
+            .await
+            .map_err(|e| ApiError::NetworkError(e).into())
+    }
+
+    async fn node_flush_peers(&self) -> RpcResult<()> {
+        let network_command_sender = self.0.network_command_sender.clone();
+        network_command_sender
+            .flush_peers()
+            .await
+            .map_err(|e| ApiError::NetworkError(e).into())
+    }
+
+    async fn node_reload_peers(&self) -> RpcResult<()> {
+        let network_command_sender = self.0.network_command_sender.clone();
+        network_command_sender
+            .reload_peers()
+            .await
+            .map_err(|e| ApiError::NetworkError(e).into())
+    }
+
+    async fn get_peer_history(&self, ip: IpAddr) -> RpcResult<Vec<PeerConnectionEvent>> {
+        let network_command_sender = self.0.network_command_sender.clone();
+        network_command_sender
+            .get_peer_history(ip)
+            .await
+            .map_err(|e| ApiError::NetworkError(e).into())
+    }
+
+    async fn node_set_network_faults(
+        &self,
+        node_id: Option<NodeId>,
+        faults: Option<MessageFaults>,
+    ) -> RpcResult<NetworkFaults> {
+        let network_command_sender = self.0.network_command_sender.clone();
+        network_command_sender
+            .set_faults(node_id, faults)
+            .await
+            .map_err(|e| ApiError::NetworkError(e).into())
+    }
+
     async fn get_status(&self) -> RpcResult<NodeStatus> {
         crate::wrong_api::<NodeStatus>()
     }
 
+    async fn get_connected_nodes(&self) -> RpcResult<Vec<ConnectedNodeInfo>> {
+        crate::wrong_api::<Vec<ConnectedNodeInfo>>()
+    }
+
+    async fn get_network_versions(&self) -> RpcResult<NetworkVersions> {
+        crate::wrong_api::<NetworkVersions>()
+    }
+
+    async fn get_health(&self) -> RpcResult<NodeHealth> {
+        crate::wrong_api::<NodeHealth>()
+    }
+
+    async fn get_operation_pool_stats(&self) -> RpcResult<OperationPoolStats> {
+        crate::wrong_api::<OperationPoolStats>()
+    }
+
     async fn get_cliques(&self) -> RpcResult<Vec<Clique>> {
         crate::wrong_api::<Vec<Clique>>()
     }
@@ -181,6 +329,13 @@ impl MassaRpcServer for API<Private> {
         crate::wrong_api::<Vec<OperationInfo>>()
     }
 
+    async fn get_operation_statuses(
+        &self,
+        _: Vec<OperationId>,
+    ) -> RpcResult<Vec<OperationStatusInfo>> {
+        crate::wrong_api::<Vec<OperationStatusInfo>>()
+    }
+
     async fn get_endorsements(&self, _: Vec<EndorsementId>) -> RpcResult<Vec<EndorsementInfo>> {
         crate::wrong_api::<Vec<EndorsementInfo>>()
     }
@@ -197,6 +352,39 @@ impl MassaRpcServer for API<Private> {
         crate::wrong_api::<Vec<BlockSummary>>()
     }
 
+    async fn get_blocks_by_slot_range(
+        &self,
+        _: Slot,
+        _: Slot,
+        _: Pagination,
+    ) -> RpcResult<PagedResult<BlockSummary>> {
+        crate::wrong_api::<PagedResult<BlockSummary>>()
+    }
+
+    async fn get_blocks_by_creator(
+        &self,
+        _: Address,
+        _: Pagination,
+    ) -> RpcResult<PagedResult<BlockSummary>> {
+        crate::wrong_api::<PagedResult<BlockSummary>>()
+    }
+
+    async fn get_operations_by_address(
+        &self,
+        _: Address,
+        _: Pagination,
+    ) -> RpcResult<PagedResult<OperationInfo>> {
+        crate::wrong_api::<PagedResult<OperationInfo>>()
+    }
+
+    async fn get_final_operations_by_address(
+        &self,
+        _: Address,
+        _: Pagination,
+    ) -> RpcResult<PagedResult<FinalOperationEntry>> {
+        crate::wrong_api::<PagedResult<FinalOperationEntry>>()
+    }
+
     async fn get_datastore_entries(
         &self,
         _: Vec<DatastoreEntryInput>,
@@ -212,6 +400,22 @@ impl MassaRpcServer for API<Private> {
         crate::wrong_api::<Vec<OperationId>>()
     }
 
+    async fn submit_operation(&self, _: OperationInput) -> RpcResult<OperationSubmissionResult> {
+        crate::wrong_api::<OperationSubmissionResult>()
+    }
+
+    async fn get_balance_proof(&self, _: Address) -> RpcResult<FinalBalanceProof> {
+        crate::wrong_api::<FinalBalanceProof>()
+    }
+
+    async fn check_block_validity(&self, _: BlockCandidateInput) -> RpcResult<BlockValidityReport> {
+        crate::wrong_api::<BlockValidityReport>()
+    }
+
+    async fn explain_fork_choice(&self, _: BlockId) -> RpcResult<ForkChoiceExplanation> {
+        crate::wrong_api::<ForkChoiceExplanation>()
+    }
+
     async fn get_filtered_sc_output_event(&self, _: EventFilter) -> RpcResult<Vec<SCOutputEvent>> {
         crate::wrong_api::<Vec<SCOutputEvent>>()
     }
@@ -300,6 +504,124 @@ impl MassaRpcServer for API<Private> {
         )
     }
 
+    async fn node_export_snapshot(&self, path: PathBuf) -> RpcResult<Slot> {
+        let consensus_controller = self.0.consensus_controller.clone();
+        let final_state = self.0.final_state.clone();
+        tokio::task::spawn_blocking(move || {
+            export_snapshot(&path, &final_state, consensus_controller.as_ref(), *VERSION)
+        })
+        .await
+        .map_err(|e| ApiError::InternalServerError(format!("snapshot export panicked: {}", e)))?
+        .map_err(|e| {
+            ApiError::InternalServerError(format!("could not export snapshot: {}", e)).into()
+        })
+    }
+
+    async fn node_export_chain_data(
+        &self,
+        start: Slot,
+        end: Slot,
+        content: ExportContent,
+        format: ExportFormat,
+        path: PathBuf,
+    ) -> RpcResult<ExportStats> {
+        let archive = self.0.archive.clone().ok_or_else(|| {
+            ApiError::MissingConfig("the archive is not enabled on this node".into())
+        })?;
+        tokio::task::spawn_blocking(move || -> Result<ExportStats, ExportError> {
+            let file = std::fs::File::create(&path)?;
+            export_blocks(
+                &archive,
+                start,
+                end,
+                content,
+                format,
+                std::io::BufWriter::new(file),
+            )
+        })
+        .await
+        .map_err(|e| ApiError::InternalServerError(format!("export panicked: {}", e)))?
+        .map_err(|e| ApiError::InternalServerError(format!("could not export: {}", e)).into())
+    }
+
+    async fn node_set_log_filter(&self, directives: String) -> RpcResult<()> {
+        self.0
+            .log_filter_handle
+            .set_filter(&directives)
+            .map_err(|e| ApiError::BadRequest(format!("invalid log filter: {}", e)).into())
+    }
+
+    async fn node_get_log_filter(&self) -> RpcResult<String> {
+        self.0
+            .log_filter_handle
+            .get_filter()
+            .map_err(|e| ApiError::InternalServerError(e).into())
+    }
+
+    async fn node_get_recent_logs(&self, count: usize) -> RpcResult<Vec<String>> {
+        Ok(self.0.recent_logs.get(count))
+    }
+
+    async fn node_reload_config(&self) -> RpcResult<()> {
+        match self.0.reload_config_channel.try_send(()) {
+            // a pending reload will read the latest configuration files anyway
+            Ok(()) | Err(TrySendError::Full(_)) => Ok(()),
+            Err(TrySendError::Closed(_)) => Err(ApiError::InternalServerError(
+                "the configuration reloader is not running".to_string(),
+            )
+            .into()),
+        }
+    }
+
+    async fn node_set_production_override(
+        &self,
+        production_override: ProductionOverride,
+    ) -> RpcResult<()> {
+        self.0.production_gate.set_override(production_override);
+        info!("block production override set to {}", production_override);
+        Ok(())
+    }
+
+    async fn node_get_production_override(&self) -> RpcResult<ProductionOverride> {
+        Ok(self.0.production_gate.get_override())
+    }
+
+    async fn node_accept_reorg(&self) -> RpcResult<ReorgAlert> {
+        let alert = match self.0.consensus_controller.get_reorg_alert() {
+            Some(alert) => alert,
+            None => {
+                return Err(ApiError::BadRequest(
+                    "no blockclique switch is rejected for its depth".to_string(),
+                )
+                .into())
+            }
+        };
+        self.0.consensus_controller.accept_reorg();
+        info!(
+            "reorganization accepted by the node administrator: {}",
+            alert
+        );
+        Ok(alert)
+    }
+
+    async fn node_check_storage(&self, repair: bool) -> RpcResult<StorageCheckReport> {
+        if self.0.archive.is_none() && self.0.address_index.is_none() {
+            return Err(ApiError::MissingConfig(
+                "neither the archive nor the address index is enabled on this node".into(),
+            )
+            .into());
+        }
+        let archive = self.0.archive.clone();
+        let address_index = self.0.address_index.clone();
+        let report = tokio::task::spawn_blocking(move || {
+            check_storage(archive.as_ref(), address_index.as_ref(), repair)
+        })
+        .await
+        .map_err(|err| ApiError::InternalServerError(err.to_string()))?
+        .map_err(|err| ApiError::InternalServerError(err.to_string()))?;
+        Ok(report)
+    }
+
     async fn get_openrpc_spec(&self) -> RpcResult<Value> {
         crate::wrong_api::<Value>()
     }
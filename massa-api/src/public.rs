@@ -12,18 +12,26 @@ use massa_execution_exports::{
     ExecutionController, ExecutionStackElement, ReadOnlyExecutionRequest, ReadOnlyExecutionTarget,
 };
 use massa_models::api::{
-    BlockGraphStatus, DatastoreEntryInput, DatastoreEntryOutput, OperationInput,
-    ReadOnlyBytecodeExecution, ReadOnlyCall, SlotAmount,
+    BlockCandidateInput, BlockGraphStatus, BlockValidityReport, DatastoreEntryInput,
+    DatastoreEntryOutput, ExportContent, ExportFormat, ExportStats, FinalOperationEntry,
+    ForkChoiceExplanation, OperationInput, OperationRejectionCode, OperationStatusInfo,
+    OperationSubmissionResult, PeerConnectionEvent, ReadOnlyBytecodeExecution, ReadOnlyCall,
+    SlotAmount, StorageCheckReport,
 };
+use massa_models::balance_proof::FinalBalanceProof;
+use massa_models::ban_list::{BanList, IpSubnet};
+use massa_models::block::{BlockHeaderDeserializer, WrappedHeader};
 use massa_models::execution::ReadOnlyResult;
+use massa_models::network_faults::{MessageFaults, NetworkFaults};
 use massa_models::operation::OperationDeserializer;
+use massa_models::resource_status::ResourceStatus;
+use massa_models::sync_progress::SyncProgressStatus;
 use massa_models::wrapped::WrappedDeserializer;
 use massa_models::{
     block::Block, endorsement::WrappedEndorsement, error::ModelsError, operation::WrappedOperation,
-    timeslots,
 };
 use massa_pos_exports::SelectorController;
-use massa_protocol_exports::ProtocolCommandSender;
+use massa_protocol_exports::{NetworkPartitionStatus, ProtocolCommandSender};
 use massa_serialization::{DeserializeError, Deserializer};
 
 use itertools::{izip, Itertools};
@@ -31,8 +39,9 @@ use massa_models::datastore::DatastoreDeserializer;
 use massa_models::{
     address::Address,
     api::{
-        AddressInfo, BlockInfo, BlockInfoContent, BlockSummary, EndorsementInfo, EventFilter,
-        NodeStatus, OperationInfo, TimeInterval,
+        AddressInfo, BlockInfo, BlockInfoContent, BlockSummary, ConnectedNodeInfo, EndorsementInfo,
+        EventFilter, NetworkVersionCount, NetworkVersions, NodeHealth, NodeStatus, NodeSyncState,
+        OperationInfo, PagedResult, Pagination, ProductionOverride, ReorgAlert, TimeInterval,
     },
     block::BlockId,
     clique::Clique,
@@ -45,16 +54,19 @@ use massa_models::{
     output_event::SCOutputEvent,
     prehash::{PreHashMap, PreHashSet},
     slot::Slot,
-    timeslots::{get_latest_block_slot_at_timestamp, time_range_to_slot_range},
+    stats::{CycleProductionStats, OperationPoolStats},
+    timeslots::time_range_to_slot_range,
     version::Version,
 };
 use massa_network_exports::{NetworkCommandSender, NetworkConfig};
-use massa_pool_exports::PoolController;
+use massa_pool_exports::{OperationInsertionOutcome, PoolController};
 use massa_signature::KeyPair;
-use massa_storage::Storage;
+use massa_storage::{address_index::AddressIndex, Storage};
 use massa_time::MassaTime;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::time::Instant;
 
 impl API<Public> {
     /// generate a new public API
@@ -70,6 +82,10 @@ impl API<Public> {
         network_command_sender: NetworkCommandSender,
         node_id: NodeId,
         storage: Storage,
+        address_index: Option<AddressIndex>,
+        partition_status: NetworkPartitionStatus,
+        sync_progress: SyncProgressStatus,
+        resource_status: ResourceStatus,
     ) -> Self {
         API(Public {
             consensus_controller,
@@ -83,8 +99,48 @@ impl API<Public> {
             execution_controller,
             selector_controller,
             storage,
+            address_index,
+            partition_status,
+            sync_progress,
+            resource_status,
+            start_instant: Instant::now(),
         })
     }
+
+    /// Get the summaries of the stored blocks among `ids`, in the same order
+    fn get_block_summaries(&self, ids: impl Iterator<Item = BlockId>) -> Vec<BlockSummary> {
+        let blocks: Vec<(BlockId, Slot, Address, Vec<BlockId>)> = {
+            let read_blocks = self.0.storage.read_blocks();
+            ids.filter_map(|id| {
+                read_blocks.get(&id).map(|block| {
+                    (
+                        id,
+                        block.content.header.content.slot,
+                        block.creator_address,
+                        block.content.header.content.parents.clone(),
+                    )
+                })
+            })
+            .collect()
+        };
+        let statuses = self
+            .0
+            .consensus_controller
+            .get_block_statuses(&blocks.iter().map(|(id, ..)| *id).collect::<Vec<_>>());
+        blocks
+            .into_iter()
+            .zip(statuses)
+            .map(|((id, slot, creator, parents), status)| BlockSummary {
+                id,
+                is_final: status == BlockGraphStatus::Final,
+                is_stale: status == BlockGraphStatus::Discarded,
+                is_in_blockclique: status == BlockGraphStatus::ActiveInBlockclique,
+                slot,
+                creator,
+                parents,
+            })
+            .collect()
+    }
 }
 
 #[async_trait]
@@ -94,7 +150,7 @@ impl RpcServer for API<Public> {
         url: &SocketAddr,
         api_config: &APIConfig,
     ) -> Result<StopHandle, JsonRpseeError> {
-        crate::serve(self.into_rpc(), url, api_config).await
+        crate::serve(self.into_rpc(), url, api_config, None, Some("get_health")).await
     }
 }
 
@@ -279,6 +335,10 @@ impl MassaRpcServer for API<Public> {
         crate::wrong_api::<PreHashSet<Address>>()
     }
 
+    async fn get_stats(&self) -> RpcResult<Vec<CycleProductionStats>> {
+        crate::wrong_api::<Vec<CycleProductionStats>>()
+    }
+
     async fn node_ban_by_ip(&self, _: Vec<IpAddr>) -> RpcResult<()> {
         crate::wrong_api::<()>()
     }
@@ -295,6 +355,42 @@ impl MassaRpcServer for API<Public> {
         crate::wrong_api::<()>()
     }
 
+    async fn node_ban_by_subnet(&self, _: Vec<IpSubnet>) -> RpcResult<()> {
+        crate::wrong_api::<()>()
+    }
+
+    async fn node_unban_by_subnet(&self, _: Vec<IpSubnet>) -> RpcResult<()> {
+        crate::wrong_api::<()>()
+    }
+
+    async fn node_export_ban_list(&self) -> RpcResult<BanList> {
+        crate::wrong_api::<BanList>()
+    }
+
+    async fn node_import_ban_list(&self, _: BanList) -> RpcResult<()> {
+        crate::wrong_api::<()>()
+    }
+
+    async fn node_flush_peers(&self) -> RpcResult<()> {
+        crate::wrong_api::<()>()
+    }
+
+    async fn node_reload_peers(&self) -> RpcResult<()> {
+        crate::wrong_api::<()>()
+    }
+
+    async fn get_peer_history(&self, _: IpAddr) -> RpcResult<Vec<PeerConnectionEvent>> {
+        crate::wrong_api::<Vec<PeerConnectionEvent>>()
+    }
+
+    async fn node_set_network_faults(
+        &self,
+        _: Option<NodeId>,
+        _: Option<MessageFaults>,
+    ) -> RpcResult<NetworkFaults> {
+        crate::wrong_api::<NetworkFaults>()
+    }
+
     async fn get_status(&self) -> RpcResult<NodeStatus> {
         let execution_controller = self.0.execution_controller.clone();
         let consensus_controller = self.0.consensus_controller.clone();
@@ -304,18 +400,16 @@ impl MassaRpcServer for API<Public> {
         let api_settings = self.0.api_settings.clone();
         let pool_command_sender = self.0.pool_command_sender.clone();
         let node_id = self.0.node_id;
-        let config = CompactConfig::default();
+        let config = CompactConfig {
+            genesis_timestamp: api_settings.genesis_timestamp,
+            ..Default::default()
+        };
         let now = match MassaTime::now() {
             Ok(now) => now,
             Err(e) => return Err(ApiError::TimeError(e).into()),
         };
 
-        let last_slot_result = get_latest_block_slot_at_timestamp(
-            api_settings.thread_count,
-            api_settings.t0,
-            api_settings.genesis_timestamp,
-            now,
-        );
+        let last_slot_result = api_settings.get_slot_timing().get_latest_slot_at(now);
         let last_slot = match last_slot_result {
             Ok(last_slot) => last_slot,
             Err(e) => return Err(ApiError::ModelsError(e).into()),
@@ -367,14 +461,49 @@ impl MassaRpcServer for API<Public> {
             })
             .collect::<BTreeMap<_, _>>();
 
+        let best_parents = consensus_controller.get_best_parents();
+        let network_partition_alerts = self.0.partition_status.get_alerts();
+        let sync_state = get_sync_state(
+            last_slot,
+            &best_parents,
+            connected_nodes.len(),
+            !network_partition_alerts.is_empty(),
+            api_settings.max_sync_lag_periods,
+        );
+        let blockclique_lag_periods = last_slot.map_or(0, |last_slot| {
+            last_slot.period.saturating_sub(
+                best_parents
+                    .iter()
+                    .map(|(_, period)| *period)
+                    .max()
+                    .unwrap_or(0),
+            )
+        });
+
+        let last_final_slots = consensus_controller
+            .get_latest_final_blocks_periods()
+            .into_iter()
+            .enumerate()
+            .map(|(thread, (_, period))| Slot::new(period, thread as u8))
+            .collect();
+
         Ok(NodeStatus {
             node_id,
             node_ip: network_config.routable_ip,
             version,
             current_time: now,
+            uptime: MassaTime::from_millis(self.0.start_instant.elapsed().as_millis() as u64),
+            sync_state,
+            sync_progress: self.0.sync_progress.get(),
+            network_partition_alerts,
+            resource_alerts: self.0.resource_status.get_alerts(),
+            reorg_alert: consensus_controller.get_reorg_alert(),
+            state_divergence: consensus_controller.get_state_divergence(),
+            blockclique_lag_periods,
             connected_nodes,
             last_slot,
             next_slot,
+            last_final_slots,
             execution_stats,
             consensus_stats,
             network_stats,
@@ -386,6 +515,114 @@ impl MassaRpcServer for API<Public> {
         })
     }
 
+    async fn get_connected_nodes(&self) -> RpcResult<Vec<ConnectedNodeInfo>> {
+        let peers = match self.0.network_command_sender.get_peers().await {
+            Ok(peers) => peers,
+            Err(e) => return Err(ApiError::NetworkError(e).into()),
+        };
+        let mut connected_nodes: Vec<ConnectedNodeInfo> = peers
+            .peers
+            .iter()
+            .flat_map(|(ip, peer)| {
+                peer.active_nodes.iter().map(move |(node_id, is_outgoing)| {
+                    let traffic = peer.traffic.get(node_id).copied().unwrap_or_default();
+                    ConnectedNodeInfo {
+                        node_id: *node_id,
+                        ip: *ip,
+                        is_outgoing: *is_outgoing,
+                        last_alive: peer.peer_info.last_alive,
+                        bytes_sent: traffic.bytes_sent,
+                        bytes_received: traffic.bytes_received,
+                        version: peer.peer_info.version,
+                    }
+                })
+            })
+            .collect();
+        connected_nodes.sort_unstable_by_key(|node| node.node_id);
+        Ok(connected_nodes)
+    }
+
+    async fn get_network_versions(&self) -> RpcResult<NetworkVersions> {
+        let our_version = self.0.version;
+        let peers = match self.0.network_command_sender.get_peers().await {
+            Ok(peers) => peers,
+            Err(e) => return Err(ApiError::NetworkError(e).into()),
+        };
+        let mut node_count = 0;
+        let mut node_counts: HashMap<Version, usize> = HashMap::new();
+        for peer in peers.peers.values() {
+            if peer.active_nodes.is_empty() {
+                continue;
+            }
+            node_count += peer.active_nodes.len();
+            if let Some(version) = peer.peer_info.version {
+                *node_counts.entry(version).or_default() += peer.active_nodes.len();
+            }
+        }
+        let mut versions: Vec<NetworkVersionCount> = node_counts
+            .into_iter()
+            .map(|(version, node_count)| NetworkVersionCount {
+                version,
+                node_count,
+                compatible: our_version.is_compatible(&version),
+            })
+            .collect();
+        versions.sort_unstable_by(|a, b| {
+            b.node_count
+                .cmp(&a.node_count)
+                .then_with(|| a.version.to_string().cmp(&b.version.to_string()))
+        });
+        Ok(NetworkVersions {
+            our_version,
+            node_count,
+            versions,
+        })
+    }
+
+    async fn get_health(&self) -> RpcResult<NodeHealth> {
+        let api_settings = &self.0.api_settings;
+        let now = match MassaTime::now() {
+            Ok(now) => now,
+            Err(e) => return Err(ApiError::TimeError(e).into()),
+        };
+        let last_slot = match api_settings.get_slot_timing().get_latest_slot_at(now) {
+            Ok(last_slot) => last_slot,
+            Err(e) => return Err(ApiError::ModelsError(e).into()),
+        };
+        let connected_node_count = match self.0.network_command_sender.get_network_stats().await {
+            Ok(network_stats) => network_stats.active_node_count as usize,
+            Err(e) => return Err(ApiError::NetworkError(e).into()),
+        };
+        let sync_state = get_sync_state(
+            last_slot,
+            &self.0.consensus_controller.get_best_parents(),
+            connected_node_count,
+            !self.0.partition_status.get_alerts().is_empty(),
+            api_settings.max_sync_lag_periods,
+        );
+        if sync_state != NodeSyncState::Synced {
+            return Err(ApiError::Unhealthy(sync_state.to_string()).into());
+        }
+        if self.0.resource_status.is_degraded() {
+            return Err(ApiError::Unhealthy("degraded read-only mode".to_string()).into());
+        }
+        if self.0.consensus_controller.get_reorg_alert().is_some() {
+            return Err(ApiError::Unhealthy("deep reorganization rejected".to_string()).into());
+        }
+        if self.0.consensus_controller.get_state_divergence().is_some() {
+            return Err(ApiError::Unhealthy("final state divergence detected".to_string()).into());
+        }
+        Ok(NodeHealth {
+            sync_state,
+            last_slot,
+            connected_node_count,
+        })
+    }
+
+    async fn get_operation_pool_stats(&self) -> RpcResult<OperationPoolStats> {
+        Ok(self.0.pool_command_sender.get_operation_pool_stats())
+    }
+
     async fn get_cliques(&self) -> RpcResult<Vec<Clique>> {
         let consensus_controller = self.0.consensus_controller.clone();
         Ok(consensus_controller.get_cliques())
@@ -400,12 +637,7 @@ impl MassaRpcServer for API<Public> {
             Err(e) => return Err(ApiError::TimeError(e).into()),
         };
 
-        let latest_block_slot_at_timestamp_result = get_latest_block_slot_at_timestamp(
-            cfg.thread_count,
-            cfg.t0,
-            cfg.genesis_timestamp,
-            now,
-        );
+        let latest_block_slot_at_timestamp_result = cfg.get_slot_timing().get_latest_slot_at(now);
 
         let curr_cycle = match latest_block_slot_at_timestamp_result {
             Ok(curr_cycle) => curr_cycle
@@ -501,6 +733,21 @@ impl MassaRpcServer for API<Public> {
         Ok(res)
     }
 
+    async fn get_operation_statuses(
+        &self,
+        ops: Vec<OperationId>,
+    ) -> RpcResult<Vec<OperationStatusInfo>> {
+        if ops.len() as u64 > self.0.api_settings.max_arguments {
+            return Err(ApiError::BadRequest("too many arguments".into()).into());
+        }
+        let statuses = self.0.pool_command_sender.get_operation_statuses(&ops);
+        Ok(ops
+            .into_iter()
+            .zip(statuses)
+            .map(|(id, status)| OperationStatusInfo { id, status })
+            .collect())
+    }
+
     async fn get_endorsements(&self, eds: Vec<EndorsementId>) -> RpcResult<Vec<EndorsementInfo>> {
         // get the endorsements and the list of blocks that contain them from storage
         let storage_info: Vec<(WrappedEndorsement, PreHashSet<BlockId>)> = {
@@ -700,6 +947,102 @@ impl MassaRpcServer for API<Public> {
         Ok(res)
     }
 
+    async fn get_blocks_by_slot_range(
+        &self,
+        start: Slot,
+        end: Slot,
+        pagination: Pagination,
+    ) -> RpcResult<PagedResult<BlockSummary>> {
+        if pagination.limit as u64 > self.0.api_settings.max_arguments {
+            return Err(ApiError::BadRequest("too many arguments".into()).into());
+        }
+        let mut block_ids = self
+            .0
+            .storage
+            .read_blocks()
+            .get_blocks_by_slot_range(&start, &end);
+        block_ids.sort_unstable();
+        let page = PagedResult::from_results(block_ids, pagination);
+        Ok(PagedResult {
+            items: self.get_block_summaries(page.items.into_iter().map(|(_, id)| id)),
+            total_count: page.total_count,
+        })
+    }
+
+    async fn get_blocks_by_creator(
+        &self,
+        address: Address,
+        pagination: Pagination,
+    ) -> RpcResult<PagedResult<BlockSummary>> {
+        if pagination.limit as u64 > self.0.api_settings.max_arguments {
+            return Err(ApiError::BadRequest("too many arguments".into()).into());
+        }
+        let mut block_ids: Vec<(Slot, BlockId)> = {
+            let read_blocks = self.0.storage.read_blocks();
+            read_blocks
+                .get_blocks_created_by(&address)
+                .into_iter()
+                .flatten()
+                .filter_map(|id| {
+                    read_blocks
+                        .get(id)
+                        .map(|block| (block.content.header.content.slot, *id))
+                })
+                .collect()
+        };
+        block_ids.sort_unstable();
+        let page = PagedResult::from_results(block_ids, pagination);
+        Ok(PagedResult {
+            items: self.get_block_summaries(page.items.into_iter().map(|(_, id)| id)),
+            total_count: page.total_count,
+        })
+    }
+
+    async fn get_operations_by_address(
+        &self,
+        address: Address,
+        pagination: Pagination,
+    ) -> RpcResult<PagedResult<OperationInfo>> {
+        if pagination.limit as u64 > self.0.api_settings.max_arguments {
+            return Err(ApiError::BadRequest("too many arguments".into()).into());
+        }
+        let mut op_ids: Vec<(u64, OperationId)> = {
+            let read_ops = self.0.storage.read_operations();
+            read_ops
+                .get_operations_involving(&address)
+                .into_iter()
+                .flatten()
+                .filter_map(|id| read_ops.get(id).map(|op| (op.content.expire_period, *id)))
+                .collect()
+        };
+        op_ids.sort_unstable();
+        let page = PagedResult::from_results(op_ids, pagination);
+        Ok(PagedResult {
+            items: self
+                .get_operations(page.items.into_iter().map(|(_, id)| id).collect())
+                .await?,
+            total_count: page.total_count,
+        })
+    }
+
+    async fn get_final_operations_by_address(
+        &self,
+        address: Address,
+        pagination: Pagination,
+    ) -> RpcResult<PagedResult<FinalOperationEntry>> {
+        if pagination.limit as u64 > self.0.api_settings.max_arguments {
+            return Err(ApiError::BadRequest("too many arguments".into()).into());
+        }
+        let address_index = self.0.address_index.clone().ok_or_else(|| {
+            ApiError::MissingConfig("the address index is not enabled on this node".into())
+        })?;
+        let entries = tokio::task::spawn_blocking(move || address_index.get_operations(&address))
+            .await
+            .map_err(|err| ApiError::InternalServerError(err.to_string()))?
+            .map_err(|err| ApiError::InternalServerError(err.to_string()))?;
+        Ok(PagedResult::from_results(entries, pagination))
+    }
+
     async fn get_datastore_entries(
         &self,
         entries: Vec<DatastoreEntryInput>,
@@ -765,13 +1108,12 @@ impl MassaRpcServer for API<Public> {
 
         // get future draws from selector
         let selection_draws = {
-            let cur_slot = timeslots::get_current_latest_block_slot(
-                self.0.api_settings.thread_count,
-                self.0.api_settings.t0,
-                self.0.api_settings.genesis_timestamp,
-            )
-            .expect("could not get latest current slot")
-            .unwrap_or_else(|| Slot::new(0, 0));
+            let cur_slot = self
+                .0
+                .api_settings
+                .get_slot_timing()
+                .get_current_slot_or_first()
+                .expect("could not get latest current slot");
             let slot_end = Slot::new(
                 cur_slot
                     .period
@@ -862,6 +1204,235 @@ impl MassaRpcServer for API<Public> {
         if ops.len() as u64 > api_cfg.max_arguments {
             return Err(ApiError::BadRequest("too many arguments".into()).into());
         }
+        if self.0.resource_status.is_degraded() {
+            return Err(ApiError::Unhealthy(
+                "degraded read-only mode, new operations are not accepted".to_string(),
+            )
+            .into());
+        }
+        let verified_ops = ops
+            .into_iter()
+            .map(|op_input| deserialize_operation(op_input, &api_cfg).map_err(JsonRpseeError::from))
+            .collect::<RpcResult<Vec<WrappedOperation>>>()?;
+        WrappedOperation::verify_signatures_batch(&verified_ops).map_err(ApiError::ModelsError)?;
+        to_send.store_operations(verified_ops.clone());
+        let ids: Vec<OperationId> = verified_ops.iter().map(|op| op.id).collect();
+        cmd_sender.add_operations(to_send.clone());
+
+        tokio::task::spawn_blocking(move || protocol_sender.propagate_operations(to_send))
+            .await
+            .map_err(|err| ApiError::InternalServerError(err.to_string()))?
+            .map_err(|err| {
+                ApiError::InternalServerError(format!("Failed to propagate operations: {}", err))
+            })?;
+        Ok(ids)
+    }
+
+    async fn submit_operation(
+        &self,
+        op_input: OperationInput,
+    ) -> RpcResult<OperationSubmissionResult> {
+        let api_cfg = self.0.api_settings.clone();
+        let operation = match deserialize_operation(op_input, &api_cfg) {
+            Ok(operation) => operation,
+            Err(err) => {
+                return Ok(OperationSubmissionResult::rejected(
+                    None,
+                    OperationRejectionCode::InvalidFormat,
+                    err.to_string(),
+                ))
+            }
+        };
+        if let Err(err) = operation.verify_signature() {
+            return Ok(OperationSubmissionResult::rejected(
+                Some(operation.id),
+                OperationRejectionCode::InvalidSignature,
+                err.to_string(),
+            ));
+        }
+
+        // the operation must still be includable in a future slot of its thread,
+        // and its validity must not start too far in the future
+        let now = MassaTime::now().map_err(ApiError::TimeError)?;
+        let latest_slot = api_cfg
+            .get_slot_timing()
+            .get_latest_slot_at(now)
+            .map_err(ApiError::ModelsError)?;
+        let op_thread = operation.creator_address.get_thread(api_cfg.thread_count);
+        let next_period = latest_slot.map_or(0, |slot| slot.get_next_period_in_thread(op_thread));
+        let validity_range = operation.get_validity_range(api_cfg.operation_validity_periods);
+        if *validity_range.end() < next_period {
+            return Ok(OperationSubmissionResult::rejected(
+                Some(operation.id),
+                OperationRejectionCode::Expired,
+                format!(
+                    "expire period {} is before the next period {} of thread {}",
+                    validity_range.end(),
+                    next_period,
+                    op_thread
+                ),
+            ));
+        }
+        if *validity_range.start()
+            > next_period.saturating_add(api_cfg.max_operation_future_validity_start_periods)
+        {
+            return Ok(OperationSubmissionResult::rejected(
+                Some(operation.id),
+                OperationRejectionCode::ValidityTooFar,
+                format!(
+                    "validity starts at period {}, more than {} periods after the next period {}",
+                    validity_range.start(),
+                    api_cfg.max_operation_future_validity_start_periods,
+                    next_period
+                ),
+            ));
+        }
+
+        // the candidate balance of the creator must cover everything the operation might spend
+        let max_spending = operation.get_max_spending(api_cfg.roll_price);
+        let balance = self
+            .0
+            .execution_controller
+            .get_final_and_candidate_balance(&[operation.creator_address])
+            .get(0)
+            .and_then(|(final_balance, candidate_balance)| candidate_balance.or(*final_balance))
+            .unwrap_or_default();
+        if balance < max_spending {
+            return Ok(OperationSubmissionResult::rejected(
+                Some(operation.id),
+                OperationRejectionCode::InsufficientBalance,
+                format!(
+                    "balance {} of {} does not cover the {} the operation might spend",
+                    balance, operation.creator_address, max_spending
+                ),
+            ));
+        }
+
+        if self.0.resource_status.is_degraded() {
+            return Ok(OperationSubmissionResult::rejected(
+                Some(operation.id),
+                OperationRejectionCode::NodeDegraded,
+                "the node is in degraded read-only mode, new operations are not accepted"
+                    .to_string(),
+            ));
+        }
+
+        let operation_id = operation.id;
+        let operation_fee = operation.content.fee;
+        let mut to_send = self.0.storage.clone_without_refs();
+        to_send.store_operations(vec![operation]);
+        let outcome = self
+            .0
+            .pool_command_sender
+            .clone()
+            .add_operations_with_outcome(to_send.clone())
+            .remove(&operation_id)
+            .ok_or_else(|| {
+                ApiError::InternalServerError(format!(
+                    "no insertion outcome returned by the pool for operation {}",
+                    operation_id
+                ))
+            })?;
+        let rejection = match outcome {
+            OperationInsertionOutcome::Added => None,
+            // already known operations are not propagated again
+            OperationInsertionOutcome::AlreadyPooled => {
+                return Ok(OperationSubmissionResult::accepted(operation_id))
+            }
+            OperationInsertionOutcome::Expired => Some((
+                OperationRejectionCode::Expired,
+                "the operation can not be included in a future block anymore".to_string(),
+            )),
+            OperationInsertionOutcome::FeeTooLow { minimal_fee } => Some((
+                OperationRejectionCode::FeeTooLow,
+                format!(
+                    "fee {} is below the minimal fee {}",
+                    operation_fee, minimal_fee
+                ),
+            )),
+            OperationInsertionOutcome::ReplacementFeeTooLow {
+                conflicting,
+                minimal_fee,
+            } => Some((
+                OperationRejectionCode::ReplacementFeeTooLow,
+                format!(
+                    "fee {} is below the fee {} required to replace the conflicting operation {}",
+                    operation_fee, minimal_fee, conflicting
+                ),
+            )),
+            OperationInsertionOutcome::SenderLimit {
+                max_operations_per_sender,
+            } => Some((
+                OperationRejectionCode::SenderLimitReached,
+                format!(
+                    "{} operations of the sender paying a better fee are already pooled",
+                    max_operations_per_sender
+                ),
+            )),
+            OperationInsertionOutcome::PoolFull => Some((
+                OperationRejectionCode::PoolFull,
+                "the pool is full of operations paying a better fee".to_string(),
+            )),
+            OperationInsertionOutcome::NodeDegraded => Some((
+                OperationRejectionCode::NodeDegraded,
+                "the node is in degraded read-only mode, new operations are not accepted"
+                    .to_string(),
+            )),
+        };
+        if let Some((code, message)) = rejection {
+            return Ok(OperationSubmissionResult::rejected(
+                Some(operation_id),
+                code,
+                message,
+            ));
+        }
+
+        let mut protocol_sender = self.0.protocol_command_sender.clone();
+        tokio::task::spawn_blocking(move || protocol_sender.propagate_operations(to_send))
+            .await
+            .map_err(|err| ApiError::InternalServerError(err.to_string()))?
+            .map_err(|err| {
+                ApiError::InternalServerError(format!("Failed to propagate operations: {}", err))
+            })?;
+        Ok(OperationSubmissionResult::accepted(operation_id))
+    }
+
+    async fn get_balance_proof(&self, address: Address) -> RpcResult<FinalBalanceProof> {
+        Ok(self
+            .0
+            .execution_controller
+            .get_final_balance_proof(&address))
+    }
+
+    async fn check_block_validity(
+        &self,
+        candidate: BlockCandidateInput,
+    ) -> RpcResult<BlockValidityReport> {
+        let consensus_controller = self.0.consensus_controller.clone();
+        let api_cfg = self.0.api_settings.clone();
+
+        if candidate.operations.len() as u64 > api_cfg.max_arguments {
+            return Err(ApiError::BadRequest("too many arguments".into()).into());
+        }
+        let header_deserializer = WrappedDeserializer::new(BlockHeaderDeserializer::new(
+            api_cfg.thread_count,
+            api_cfg.endorsement_count,
+        ));
+        let mut header_serialized = Vec::new();
+        header_serialized.extend(candidate.signature.to_bytes());
+        header_serialized.extend(candidate.creator_public_key.to_bytes());
+        header_serialized.extend(candidate.serialized_header_content);
+        let (rest, header): (&[u8], WrappedHeader) = header_deserializer
+            .deserialize::<DeserializeError>(&header_serialized)
+            .map_err(|err| ApiError::ModelsError(ModelsError::DeserializeError(err.to_string())))?;
+        if !rest.is_empty() {
+            return Err(ApiError::ModelsError(ModelsError::DeserializeError(
+                "There is data left after header deserialization".to_owned(),
+            ))
+            .into());
+        }
+
+        // signatures are part of the validity report, they are not checked here
         let operation_deserializer = WrappedDeserializer::new(OperationDeserializer::new(
             api_cfg.max_datastore_value_length,
             api_cfg.max_function_name_length,
@@ -870,7 +1441,8 @@ impl MassaRpcServer for API<Public> {
             api_cfg.max_op_datastore_key_length,
             api_cfg.max_op_datastore_value_length,
         ));
-        let verified_ops = ops
+        let operations = candidate
+            .operations
             .into_iter()
             .map(|op_input| {
                 let mut op_serialized = Vec::new();
@@ -891,28 +1463,18 @@ impl MassaRpcServer for API<Public> {
                     .into())
                 }
             })
-            .map(|op| match op {
-                Ok(operation) => {
-                    let _verify_signature = match operation.verify_signature() {
-                        Ok(()) => (),
-                        Err(e) => return Err(ApiError::ModelsError(e).into()),
-                    };
-                    Ok(operation)
-                }
-                Err(e) => Err(e),
-            })
             .collect::<RpcResult<Vec<WrappedOperation>>>()?;
-        to_send.store_operations(verified_ops.clone());
-        let ids: Vec<OperationId> = verified_ops.iter().map(|op| op.id).collect();
-        cmd_sender.add_operations(to_send.clone());
 
-        tokio::task::spawn_blocking(move || protocol_sender.propagate_operations(to_send))
-            .await
-            .map_err(|err| ApiError::InternalServerError(err.to_string()))?
-            .map_err(|err| {
-                ApiError::InternalServerError(format!("Failed to propagate operations: {}", err))
-            })?;
-        Ok(ids)
+        consensus_controller
+            .check_block_validity(header, operations)
+            .map_err(|e| ApiError::ConsensusError(e).into())
+    }
+
+    async fn explain_fork_choice(&self, block_id: BlockId) -> RpcResult<ForkChoiceExplanation> {
+        self.0
+            .consensus_controller
+            .explain_fork_choice(block_id)
+            .map_err(|e| ApiError::ConsensusError(e).into())
     }
 
     /// Get events optionally filtered by:
@@ -974,6 +1536,53 @@ impl MassaRpcServer for API<Public> {
         crate::wrong_api::<()>()
     }
 
+    async fn node_export_snapshot(&self, _: PathBuf) -> RpcResult<Slot> {
+        crate::wrong_api::<Slot>()
+    }
+
+    async fn node_export_chain_data(
+        &self,
+        _: Slot,
+        _: Slot,
+        _: ExportContent,
+        _: ExportFormat,
+        _: PathBuf,
+    ) -> RpcResult<ExportStats> {
+        crate::wrong_api::<ExportStats>()
+    }
+
+    async fn node_set_log_filter(&self, _: String) -> RpcResult<()> {
+        crate::wrong_api::<()>()
+    }
+
+    async fn node_get_log_filter(&self) -> RpcResult<String> {
+        crate::wrong_api::<String>()
+    }
+
+    async fn node_get_recent_logs(&self, _: usize) -> RpcResult<Vec<String>> {
+        crate::wrong_api::<Vec<String>>()
+    }
+
+    async fn node_reload_config(&self) -> RpcResult<()> {
+        crate::wrong_api::<()>()
+    }
+
+    async fn node_set_production_override(&self, _: ProductionOverride) -> RpcResult<()> {
+        crate::wrong_api::<()>()
+    }
+
+    async fn node_get_production_override(&self) -> RpcResult<ProductionOverride> {
+        crate::wrong_api::<ProductionOverride>()
+    }
+
+    async fn node_accept_reorg(&self) -> RpcResult<ReorgAlert> {
+        crate::wrong_api::<ReorgAlert>()
+    }
+
+    async fn node_check_storage(&self, _: bool) -> RpcResult<StorageCheckReport> {
+        crate::wrong_api::<StorageCheckReport>()
+    }
+
     async fn get_openrpc_spec(&self) -> RpcResult<Value> {
         let openrpc_spec_path = self.0.api_settings.openrpc_spec_path.clone();
         let openrpc: RpcResult<Value> = std::fs::read_to_string(openrpc_spec_path)
@@ -997,3 +1606,72 @@ impl MassaRpcServer for API<Public> {
         openrpc
     }
 }
+
+/// Deserialize an operation submitted through the API
+fn deserialize_operation(
+    op_input: OperationInput,
+    api_cfg: &APIConfig,
+) -> Result<WrappedOperation, ApiError> {
+    let mut op_serialized = Vec::new();
+    op_serialized.extend(op_input.signature.to_bytes());
+    op_serialized.extend(op_input.creator_public_key.to_bytes());
+    op_serialized.extend(op_input.serialized_content);
+    deserialize_wrapped_operation(&op_serialized, api_cfg)
+}
+
+/// Deserialize a signed operation: signature, creator public key and content
+pub(crate) fn deserialize_wrapped_operation(
+    op_serialized: &[u8],
+    api_cfg: &APIConfig,
+) -> Result<WrappedOperation, ApiError> {
+    let operation_deserializer = WrappedDeserializer::new(OperationDeserializer::new(
+        api_cfg.max_datastore_value_length,
+        api_cfg.max_function_name_length,
+        api_cfg.max_parameter_size,
+        api_cfg.max_op_datastore_entry_count,
+        api_cfg.max_op_datastore_key_length,
+        api_cfg.max_op_datastore_value_length,
+    ));
+    let (rest, op): (&[u8], WrappedOperation) = operation_deserializer
+        .deserialize::<DeserializeError>(op_serialized)
+        .map_err(|err| ApiError::ModelsError(ModelsError::DeserializeError(err.to_string())))?;
+    if rest.is_empty() {
+        Ok(op)
+    } else {
+        Err(ApiError::ModelsError(ModelsError::DeserializeError(
+            "There is data left after operation deserialization".to_owned(),
+        )))
+    }
+}
+
+/// Get the synchronization state of the node from the current slot,
+/// the best parents of the blockclique, the number of connected nodes
+/// and whether the network partition watchdog raised alerts
+fn get_sync_state(
+    last_slot: Option<Slot>,
+    best_parents: &[(BlockId, u64)],
+    connected_node_count: usize,
+    partitioned: bool,
+    max_sync_lag_periods: u64,
+) -> NodeSyncState {
+    let last_slot = match last_slot {
+        Some(last_slot) => last_slot,
+        None => return NodeSyncState::WaitingForGenesis,
+    };
+    if connected_node_count == 0 {
+        return NodeSyncState::Isolated;
+    }
+    if partitioned {
+        return NodeSyncState::Partitioned;
+    }
+    let latest_period = best_parents
+        .iter()
+        .map(|(_, period)| *period)
+        .max()
+        .unwrap_or(0);
+    if last_slot.period.saturating_sub(latest_period) > max_sync_lag_periods {
+        NodeSyncState::Syncing
+    } else {
+        NodeSyncState::Synced
+    }
+}
@@ -1,16 +1,14 @@
 use humantime::format_duration;
-use std::{net::SocketAddr, sync::Arc, time::Duration};
+use std::{net::SocketAddr, path::Path, sync::Arc, time::Duration};
 
 use massa_final_state::FinalState;
 use massa_logging::massa_trace;
-use massa_models::{streaming_step::StreamingStep, version::Version};
+use massa_models::{
+    streaming_step::StreamingStep, sync_progress::SyncProgressStatus, version::Version,
+};
 use massa_signature::PublicKey;
 use massa_time::MassaTime;
 use parking_lot::RwLock;
-use rand::{
-    prelude::{SliceRandom, StdRng},
-    SeedableRng,
-};
 use tokio::time::sleep;
 use tracing::{debug, info, warn};
 
@@ -18,17 +16,115 @@ use crate::{
     client_binder::BootstrapClientBinder,
     error::BootstrapError,
     messages::{BootstrapClientMessage, BootstrapServerMessage},
+    server_stats::BootstrapServerStatsDb,
+    snapshot::import_snapshot,
     BootstrapConfig, Establisher, GlobalBootstrapState,
 };
 
+/// Applies a `BootstrapServerMessage::BootstrapPart` to the final state and consensus graph being bootstrapped.
+/// Returns the `BootstrapClientMessage::AskBootstrapPart` to send to resume the stream after this part.
+pub(crate) fn apply_bootstrap_part(
+    global_bootstrap_state: &mut GlobalBootstrapState,
+    part: BootstrapServerMessage,
+) -> Result<BootstrapClientMessage, BootstrapError> {
+    let BootstrapServerMessage::BootstrapPart {
+        slot,
+        ledger_part,
+        async_pool_part,
+        pos_cycle_part,
+        pos_credits_part,
+        exec_ops_part,
+        final_state_changes,
+        consensus_part,
+        consensus_outdated_ids,
+    } = part else {
+        return Err(BootstrapError::UnexpectedServerMessage(part));
+    };
+
+    // Set final state
+    let mut write_final_state = global_bootstrap_state.final_state.write();
+    let last_ledger_step = write_final_state.ledger.set_ledger_part(ledger_part)?;
+    let last_pool_step = write_final_state.async_pool.set_pool_part(async_pool_part);
+    let last_cycle_step = write_final_state
+        .pos_state
+        .set_cycle_history_part(pos_cycle_part);
+    let last_credits_step = write_final_state
+        .pos_state
+        .set_deferred_credits_part(pos_credits_part);
+    let last_ops_step = write_final_state
+        .executed_ops
+        .set_executed_ops_part(exec_ops_part);
+    for (changes_slot, changes) in final_state_changes.iter() {
+        write_final_state
+            .ledger
+            .apply_changes(changes.ledger_changes.clone(), *changes_slot);
+        write_final_state
+            .async_pool
+            .apply_changes_unchecked(&changes.async_pool_changes);
+        if !changes.pos_changes.is_empty() {
+            write_final_state.pos_state.apply_changes(
+                changes.pos_changes.clone(),
+                *changes_slot,
+                false,
+            )?;
+        }
+        if !changes.executed_ops_changes.is_empty() {
+            write_final_state
+                .executed_ops
+                .apply_changes(changes.executed_ops_changes.clone(), *changes_slot);
+        }
+    }
+    write_final_state.slot = slot;
+    drop(write_final_state);
+
+    // Set consensus blocks
+    if let Some(graph) = global_bootstrap_state.graph.as_mut() {
+        // Extend the final blocks with the received part
+        graph.final_blocks.extend(consensus_part.final_blocks);
+        // Remove every outdated block
+        graph
+            .final_blocks
+            .retain(|block_export| !consensus_outdated_ids.contains(&block_export.block.id));
+    } else {
+        global_bootstrap_state.graph = Some(consensus_part);
+    }
+    let last_consensus_step = StreamingStep::Ongoing(
+        // Note that this unwrap call is safe because of the above conditional statement
+        global_bootstrap_state
+            .graph
+            .as_ref()
+            .unwrap()
+            .final_blocks
+            .iter()
+            .map(|b_export| b_export.block.id)
+            .collect(),
+    );
+
+    debug!(
+        "client final state slot changes length: {}",
+        final_state_changes.len()
+    );
+
+    Ok(BootstrapClientMessage::AskBootstrapPart {
+        last_slot: Some(slot),
+        last_ledger_step,
+        last_pool_step,
+        last_cycle_step,
+        last_credits_step,
+        last_ops_step,
+        last_consensus_step,
+    })
+}
+
 /// This function will send the starting point to receive a stream of the ledger and will receive and process each part until receive a `BootstrapServerMessage::FinalStateFinished` message from the server.
 /// `next_bootstrap_message` passed as parameter must be `BootstrapClientMessage::AskFinalStatePart` enum variant.
 /// `next_bootstrap_message` will be updated after receiving each part so that in case of connection lost we can restart from the last message we processed.
-async fn stream_final_state_and_consensus(
+pub(crate) async fn stream_final_state_and_consensus(
     cfg: &BootstrapConfig,
     client: &mut BootstrapClientBinder,
     next_bootstrap_message: &mut BootstrapClientMessage,
     global_bootstrap_state: &mut GlobalBootstrapState,
+    sync_progress: &SyncProgressStatus,
 ) -> Result<(), BootstrapError> {
     if let BootstrapClientMessage::AskBootstrapPart { .. } = &next_bootstrap_message {
         match tokio::time::timeout(
@@ -58,96 +154,22 @@ async fn stream_final_state_and_consensus(
                 Ok(Ok(msg)) => msg,
             };
             match msg {
-                BootstrapServerMessage::BootstrapPart {
-                    slot,
-                    ledger_part,
-                    async_pool_part,
-                    pos_cycle_part,
-                    pos_credits_part,
-                    exec_ops_part,
-                    final_state_changes,
-                    consensus_part,
-                    consensus_outdated_ids,
-                } => {
-                    // Set final state
-                    let mut write_final_state = global_bootstrap_state.final_state.write();
-                    let last_ledger_step = write_final_state.ledger.set_ledger_part(ledger_part)?;
-                    let last_pool_step =
-                        write_final_state.async_pool.set_pool_part(async_pool_part);
-                    let last_cycle_step = write_final_state
-                        .pos_state
-                        .set_cycle_history_part(pos_cycle_part);
-                    let last_credits_step = write_final_state
-                        .pos_state
-                        .set_deferred_credits_part(pos_credits_part);
-                    let last_ops_step = write_final_state
-                        .executed_ops
-                        .set_executed_ops_part(exec_ops_part);
-                    for (changes_slot, changes) in final_state_changes.iter() {
-                        write_final_state
-                            .ledger
-                            .apply_changes(changes.ledger_changes.clone(), *changes_slot);
-                        write_final_state
-                            .async_pool
-                            .apply_changes_unchecked(&changes.async_pool_changes);
-                        if !changes.pos_changes.is_empty() {
-                            write_final_state.pos_state.apply_changes(
-                                changes.pos_changes.clone(),
-                                *changes_slot,
-                                false,
-                            )?;
-                        }
-                        if !changes.executed_ops_changes.is_empty() {
-                            write_final_state
-                                .executed_ops
-                                .apply_changes(changes.executed_ops_changes.clone(), *changes_slot);
-                        }
-                    }
-                    write_final_state.slot = slot;
-
-                    // Set consensus blocks
-                    if let Some(graph) = global_bootstrap_state.graph.as_mut() {
-                        // Extend the final blocks with the received part
-                        graph.final_blocks.extend(consensus_part.final_blocks);
-                        // Remove every outdated block
-                        graph.final_blocks.retain(|block_export| {
-                            !consensus_outdated_ids.contains(&block_export.block.id)
-                        });
-                    } else {
-                        global_bootstrap_state.graph = Some(consensus_part);
-                    }
-                    let last_consensus_step = StreamingStep::Ongoing(
-                        // Note that this unwrap call is safe because of the above conditional statement
-                        global_bootstrap_state
-                            .graph
-                            .as_ref()
-                            .unwrap()
-                            .final_blocks
-                            .iter()
-                            .map(|b_export| b_export.block.id)
-                            .collect(),
-                    );
-
+                msg @ BootstrapServerMessage::BootstrapPart { .. } => {
                     // Set new message in case of disconnection
-                    *next_bootstrap_message = BootstrapClientMessage::AskBootstrapPart {
-                        last_slot: Some(slot),
-                        last_ledger_step,
-                        last_pool_step,
-                        last_cycle_step,
-                        last_credits_step,
-                        last_ops_step,
-                        last_consensus_step,
-                    };
+                    *next_bootstrap_message = apply_bootstrap_part(global_bootstrap_state, msg)?;
+                    if let BootstrapClientMessage::AskBootstrapPart {
+                        last_slot: Some(last_slot),
+                        ..
+                    } = next_bootstrap_message
+                    {
+                        sync_progress.note_bootstrap_slot(*last_slot);
+                    }
 
                     // Logs for an easier diagnostic if needed
                     debug!(
                         "client final state bootstrap cursors: {:?}",
                         next_bootstrap_message
                     );
-                    debug!(
-                        "client final state slot changes length: {}",
-                        final_state_changes.len()
-                    );
                 }
                 BootstrapServerMessage::BootstrapFinished => {
                     info!("State bootstrap complete");
@@ -157,6 +179,9 @@ async fn stream_final_state_and_consensus(
                 }
                 BootstrapServerMessage::SlotTooOld => {
                     info!("Slot is too old retry bootstrap from scratch");
+                    // the final state and the final blocks are streamed again from the start
+                    global_bootstrap_state.final_state.write().reset();
+                    global_bootstrap_state.graph = None;
                     *next_bootstrap_message = BootstrapClientMessage::AskBootstrapPart {
                         last_slot: None,
                         last_ledger_step: StreamingStep::Started,
@@ -196,6 +221,7 @@ async fn bootstrap_from_server(
     next_bootstrap_message: &mut BootstrapClientMessage,
     global_bootstrap_state: &mut GlobalBootstrapState,
     our_version: Version,
+    sync_progress: &SyncProgressStatus,
 ) -> Result<(), BootstrapError> {
     massa_trace!("bootstrap.lib.bootstrap_from_server", {});
 
@@ -231,13 +257,33 @@ async fn bootstrap_from_server(
     }
 
     // compute ping
-    let ping = MassaTime::now()?.saturating_sub(send_time_uncompensated);
+    let ping = send_time_uncompensated.elapsed()?;
     if ping > cfg.max_ping {
         return Err(BootstrapError::GeneralError(
             "bootstrap ping too high".into(),
         ));
     }
 
+    // present the token of restricted servers, which check it right after the handshake
+    if let Some(token) = &cfg.bootstrap_token {
+        match tokio::time::timeout(
+            cfg.write_timeout.into(),
+            client.send(&BootstrapClientMessage::Authenticate {
+                token: token.clone(),
+            }),
+        )
+        .await
+        {
+            Err(_) => Err(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                "bootstrap token send timed out",
+            )
+            .into()),
+            Ok(Err(e)) => Err(e),
+            Ok(Ok(_)) => Ok(()),
+        }?;
+    }
+
     // First, clock and version.
     // client.next() is not cancel-safe but we drop the whole client object if cancelled => it's OK
     let server_time = match tokio::time::timeout(cfg.read_timeout.into(), client.next()).await {
@@ -283,6 +329,16 @@ async fn bootstrap_from_server(
     // the complete ping value being client -> server -> client
     let adjusted_server_time = server_time.checked_add(ping.checked_div_u64(2)?)?;
     let clock_delta = adjusted_server_time.abs_diff(recv_time);
+    info!(
+        "clock of the bootstrap server is {} ms {} ours (ping: {} ms)",
+        clock_delta.to_millis(),
+        if adjusted_server_time > recv_time {
+            "ahead of"
+        } else {
+            "behind"
+        },
+        ping.to_millis()
+    );
 
     // if clock delta is too high warn the user and restart bootstrap
     if clock_delta > cfg.max_clock_delta {
@@ -294,7 +350,19 @@ async fn bootstrap_from_server(
         return Err(BootstrapError::ClockError(message));
     }
 
+    if let BootstrapClientMessage::AskBootstrapPart {
+        last_slot: Some(last_slot),
+        ..
+    } = next_bootstrap_message
+    {
+        info!(
+            "Resuming the state bootstrap from the parts received up to slot {}",
+            last_slot
+        );
+    }
+
     let write_timeout: std::time::Duration = cfg.write_timeout.into();
+
     // Loop to ask data to the server depending on the last message we sent
     loop {
         match next_bootstrap_message {
@@ -304,6 +372,7 @@ async fn bootstrap_from_server(
                     client,
                     next_bootstrap_message,
                     global_bootstrap_state,
+                    sync_progress,
                 )
                 .await?;
             }
@@ -407,8 +476,59 @@ async fn connect_to_server(
     ))
 }
 
+/// Import the snapshot saved by the last clean shutdown if it is younger than `shutdown_snapshot_max_age`.
+/// The snapshot is removed in any case: it is only meant for the restart that follows the shutdown.
+///
+/// Returns `None` if the snapshot was not imported, in which case the final state was left untouched.
+pub(crate) fn import_shutdown_snapshot(
+    bootstrap_config: &BootstrapConfig,
+    snapshot_path: &Path,
+    final_state: Arc<RwLock<FinalState>>,
+    version: Version,
+) -> Result<Option<GlobalBootstrapState>, BootstrapError> {
+    let age = std::fs::metadata(snapshot_path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok());
+    let result = match age {
+        Some(age) if age <= bootstrap_config.shutdown_snapshot_max_age.to_duration() => {
+            match import_snapshot(bootstrap_config, snapshot_path, final_state, version) {
+                Ok(global_bootstrap_state) => {
+                    info!(
+                        "State imported from the snapshot saved at the last shutdown, {} ago",
+                        format_duration(Duration::from_secs(age.as_secs()))
+                    );
+                    Ok(Some(global_bootstrap_state))
+                }
+                Err(BootstrapError::SnapshotError(err)) => {
+                    warn!(
+                        "Could not import the snapshot saved at the last shutdown: {}. Bootstrapping instead.",
+                        err
+                    );
+                    Ok(None)
+                }
+                Err(err) => Err(err),
+            }
+        }
+        _ => {
+            info!("The snapshot saved at the last shutdown is too old to be imported, bootstrapping instead");
+            Ok(None)
+        }
+    };
+    if let Err(err) = std::fs::remove_file(snapshot_path) {
+        warn!(
+            "could not remove the shutdown snapshot {}: {}",
+            snapshot_path.display(),
+            err
+        );
+    }
+    result
+}
+
 /// Gets the state from a bootstrap server
 /// needs to be CANCELLABLE
+///
+/// The slot of the final state being bootstrapped is reported to `sync_progress` as the parts are received.
 pub async fn get_state(
     bootstrap_config: &BootstrapConfig,
     final_state: Arc<RwLock<FinalState>>,
@@ -416,6 +536,7 @@ pub async fn get_state(
     version: Version,
     genesis_timestamp: MassaTime,
     end_timestamp: Option<MassaTime>,
+    sync_progress: SyncProgressStatus,
 ) -> Result<GlobalBootstrapState, BootstrapError> {
     massa_trace!("bootstrap.lib.get_state", {});
     let now = MassaTime::now()?;
@@ -437,15 +558,45 @@ pub async fn get_state(
         }
         return Ok(GlobalBootstrapState::new(final_state));
     }
-    // we are after genesis => bootstrap
+    // we are after genesis => import the local snapshot if there is one
+    if let Some(snapshot_path) = &bootstrap_config.snapshot_import_path {
+        massa_trace!("bootstrap.lib.get_state.init_from_snapshot", {});
+        match import_snapshot(
+            bootstrap_config,
+            snapshot_path,
+            final_state.clone(),
+            version,
+        ) {
+            Ok(global_bootstrap_state) => {
+                info!("State imported from snapshot {}", snapshot_path.display());
+                return Ok(global_bootstrap_state);
+            }
+            // the final state was not modified, we can safely bootstrap from others
+            Err(BootstrapError::SnapshotError(err)) => warn!(
+                "Could not import snapshot {}: {}. Bootstrapping from the network instead.",
+                snapshot_path.display(),
+                err
+            ),
+            Err(err) => return Err(err),
+        }
+    }
+    // import the snapshot saved at the last clean shutdown if it is recent enough
+    if let Some(snapshot_path) = &bootstrap_config.shutdown_snapshot_path && snapshot_path.is_file() {
+        massa_trace!("bootstrap.lib.get_state.init_from_shutdown_snapshot", {});
+        if let Some(global_bootstrap_state) =
+            import_shutdown_snapshot(bootstrap_config, snapshot_path, final_state.clone(), version)?
+        {
+            return Ok(global_bootstrap_state);
+        }
+    }
+    // otherwise => bootstrap
     massa_trace!("bootstrap.lib.get_state.init_from_others", {});
     if bootstrap_config.bootstrap_list.is_empty() {
         return Err(BootstrapError::GeneralError(
             "no bootstrap nodes found in list".into(),
         ));
     }
-    let mut shuffled_list = bootstrap_config.bootstrap_list.clone();
-    shuffled_list.shuffle(&mut StdRng::from_entropy());
+    let mut server_stats = BootstrapServerStatsDb::load(bootstrap_config.server_stats_path.clone());
     let mut next_bootstrap_message: BootstrapClientMessage =
         BootstrapClientMessage::AskBootstrapPart {
             last_slot: None,
@@ -458,7 +609,11 @@ pub async fn get_state(
         };
     let mut global_bootstrap_state = GlobalBootstrapState::new(final_state.clone());
     loop {
-        for (addr, pub_key) in shuffled_list.iter() {
+        // the servers are ordered again at each round to take the failures of the previous rounds into account
+        for (addr, pub_key) in server_stats
+            .order_servers(&bootstrap_config.bootstrap_list)
+            .iter()
+        {
             if let Some(end) = end_timestamp {
                 if MassaTime::now().expect("could not get now time") > end {
                     panic!("This episode has come to an end, please get the latest testnet node version to continue");
@@ -467,7 +622,7 @@ pub async fn get_state(
             info!("Start bootstrapping from {}", addr);
             match connect_to_server(&mut establisher, bootstrap_config, addr, pub_key).await {
                 Ok(mut client) => {
-                    match bootstrap_from_server(bootstrap_config, &mut client, &mut next_bootstrap_message, &mut global_bootstrap_state,version)
+                    match bootstrap_from_server(bootstrap_config, &mut client, &mut next_bootstrap_message, &mut global_bootstrap_state, version, &sync_progress)
                     .await  // cancellable
                     {
                         Err(BootstrapError::ReceivedError(error)) => warn!("Error received from bootstrap server: {}", error),
@@ -477,6 +632,7 @@ pub async fn get_state(
                             let _ = tokio::time::timeout(bootstrap_config.write_error_timeout.into(), client.send(&BootstrapClientMessage::BootstrapError { error: e.to_string() })).await;
                         }
                         Ok(()) => {
+                            server_stats.note_success(*addr);
                             return Ok(global_bootstrap_state)
                         }
                     }
@@ -486,6 +642,7 @@ pub async fn get_state(
                 }
             };
 
+            server_stats.note_failure(*addr);
             info!("Bootstrap from server {} failed. Your node will try to bootstrap from another server in {}.", addr, format_duration(bootstrap_config.retry_delay.to_duration()).to_string());
             sleep(bootstrap_config.retry_delay.into()).await;
         }
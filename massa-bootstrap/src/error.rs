@@ -52,4 +52,6 @@ pub enum BootstrapError {
     ReceivedError(String),
     /// clock error: {0}
     ClockError(String),
+    /// snapshot error: {0}
+    SnapshotError(String),
 }
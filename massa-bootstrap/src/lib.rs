@@ -7,6 +7,21 @@
 //! On server side, the server will query consensus for the graph and the ledger,
 //! execution for execution related data and network for the peer list.
 //!
+//! Every message of the server (clock, final state and graph parts, peers) is signed with the key
+//! of the server listed in the `bootstrap_list` of the client, along with the hash of the previous message
+//! of the exchange. The first one is chained to random bytes sent by the client during the handshake,
+//! so that the messages of a bootstrap session can not be replayed in another one.
+//! The client checks each signature before applying the message, and the signed server time against its own clock.
+//!
+//! The final state and the final blocks are streamed in parts of bounded size. After each part, the client
+//! keeps the cursors of the last part it applied: if the connection is lost, the stream is resumed from
+//! those cursors with the next server instead of starting over. The server answers `SlotTooOld` if the
+//! slot of the last part is too old for it to send the changes that followed, in which case the client starts over.
+//! The final state parts read by the server are cached until the final slot changes and shared by its sessions.
+//!
+//! With `shutdown_snapshot_path`, the node saves a snapshot of its final state and final blocks when it stops cleanly,
+//! and imports it at the next startup instead of bootstrapping if it is recent enough.
+//!
 #![feature(async_closure)]
 #![warn(missing_docs)]
 #![warn(unused_crate_dependencies)]
@@ -27,7 +42,10 @@ mod establisher;
 mod messages;
 mod server;
 mod server_binder;
+mod server_cache;
+mod server_stats;
 mod settings;
+mod snapshot;
 mod tools;
 pub use client::get_state;
 pub use establisher::types;
@@ -37,6 +55,7 @@ pub use messages::{
 };
 pub use server::{start_bootstrap_server, BootstrapManager};
 pub use settings::BootstrapConfig;
+pub use snapshot::{export_snapshot, import_snapshot};
 
 #[cfg(test)]
 pub mod tests;
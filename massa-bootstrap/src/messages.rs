@@ -551,6 +551,11 @@ pub enum BootstrapClientMessage {
     },
     /// Bootstrap succeed
     BootstrapSuccess,
+    /// Token allowing the client to bootstrap from a server restricted to known clients
+    Authenticate {
+        /// Token given by the operator of the server
+        token: String,
+    },
 }
 
 #[derive(IntoPrimitive, Debug, Eq, PartialEq, TryFromPrimitive)]
@@ -560,8 +565,12 @@ enum MessageClientTypeId {
     AskFinalStatePart = 1u32,
     BootstrapError = 2u32,
     BootstrapSuccess = 3u32,
+    Authenticate = 4u32,
 }
 
+/// Maximum length in bytes of a bootstrap authentication token
+const MAX_BOOTSTRAP_TOKEN_LENGTH: u32 = 1024;
+
 /// Serializer for `BootstrapClientMessage`
 pub struct BootstrapClientMessageSerializer {
     u32_serializer: U32VarIntSerializer,
@@ -664,6 +673,17 @@ impl Serializer<BootstrapClientMessage> for BootstrapClientMessageSerializer {
                 self.u32_serializer
                     .serialize(&u32::from(MessageClientTypeId::BootstrapSuccess), buffer)?;
             }
+            BootstrapClientMessage::Authenticate { token } => {
+                self.u32_serializer
+                    .serialize(&u32::from(MessageClientTypeId::Authenticate), buffer)?;
+                self.u32_serializer.serialize(
+                    &token.len().try_into().map_err(|_| {
+                        SerializeError::GeneralError("Fail to convert usize to u32".to_string())
+                    })?,
+                    buffer,
+                )?;
+                buffer.extend(token.as_bytes())
+            }
         }
         Ok(())
     }
@@ -673,6 +693,7 @@ impl Serializer<BootstrapClientMessage> for BootstrapClientMessageSerializer {
 pub struct BootstrapClientMessageDeserializer {
     id_deserializer: U32VarIntDeserializer,
     length_error_deserializer: U32VarIntDeserializer,
+    length_token_deserializer: U32VarIntDeserializer,
     slot_deserializer: SlotDeserializer,
     ledger_step_deserializer: StreamingStepDeserializer<Vec<u8>, KeyDeserializer>,
     pool_step_deserializer: StreamingStepDeserializer<AsyncMessageId, AsyncMessageIdDeserializer>,
@@ -694,6 +715,10 @@ impl BootstrapClientMessageDeserializer {
         Self {
             id_deserializer: U32VarIntDeserializer::new(Included(0), Included(u32::MAX)),
             length_error_deserializer: U32VarIntDeserializer::new(Included(0), Included(100000)),
+            length_token_deserializer: U32VarIntDeserializer::new(
+                Included(0),
+                Included(MAX_BOOTSTRAP_TOKEN_LENGTH),
+            ),
             slot_deserializer: SlotDeserializer::new(
                 (Included(0), Included(u64::MAX)),
                 (Included(0), Excluded(thread_count)),
@@ -840,6 +865,16 @@ impl Deserializer<BootstrapClientMessage> for BootstrapClientMessageDeserializer
                 MessageClientTypeId::BootstrapSuccess => {
                     Ok((input, BootstrapClientMessage::BootstrapSuccess))
                 }
+                MessageClientTypeId::Authenticate => context(
+                    "Failed Authenticate deserialization",
+                    length_data(context("Failed length deserialization", |input| {
+                        self.length_token_deserializer.deserialize(input)
+                    })),
+                )
+                .map(|token| BootstrapClientMessage::Authenticate {
+                    token: String::from_utf8_lossy(token).into_owned(),
+                })
+                .parse(input),
             }
         })
         .parse(buffer)
@@ -1,3 +1,4 @@
+use async_speed_limit::Limiter;
 use futures::stream::FuturesUnordered;
 use futures::StreamExt;
 use humantime::format_duration;
@@ -6,13 +7,13 @@ use massa_consensus_exports::{bootstrapable_graph::BootstrapableGraph, Consensus
 use massa_final_state::{FinalState, FinalStateError};
 use massa_logging::massa_trace;
 use massa_models::{
-    block::BlockId, prehash::PreHashSet, slot::Slot, streaming_step::StreamingStep,
-    version::Version,
+    block::BlockId, prehash::PreHashSet, resource_status::ResourceStatus, slot::Slot,
+    streaming_step::StreamingStep, version::Version,
 };
 use massa_network_exports::NetworkCommandSender;
 use massa_signature::KeyPair;
 use massa_time::MassaTime;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use std::{
     collections::{hash_map, HashMap, HashSet},
     net::{IpAddr, SocketAddr},
@@ -20,6 +21,7 @@ use std::{
     sync::Arc,
     time::{Duration, Instant},
 };
+use subtle::{Choice, ConstantTimeEq};
 use tokio::{sync::mpsc, task::JoinHandle};
 use tracing::{debug, info, warn};
 
@@ -27,6 +29,7 @@ use crate::{
     error::BootstrapError,
     messages::{BootstrapClientMessage, BootstrapServerMessage},
     server_binder::BootstrapServerBinder,
+    server_cache::{BootstrapPartCache, FinalStateCursors, FinalStatePart},
     tools::normalize_ip,
     BootstrapConfig, Establisher,
 };
@@ -54,6 +57,7 @@ impl BootstrapManager {
 ///
 /// start a bootstrap server.
 /// Once your node will be ready, you may want other to bootstrap from you.
+/// No session is served while `resource_status` reports the node as degraded.
 pub async fn start_bootstrap_server(
     consensus_controller: Box<dyn ConsensusController>,
     network_command_sender: NetworkCommandSender,
@@ -62,6 +66,7 @@ pub async fn start_bootstrap_server(
     establisher: Establisher,
     keypair: KeyPair,
     version: Version,
+    resource_status: ResourceStatus,
 ) -> Result<Option<BootstrapManager>, BootstrapError> {
     massa_trace!("bootstrap.lib.start_bootstrap_server", {});
     if let Some(bind) = bootstrap_config.bind {
@@ -77,7 +82,13 @@ pub async fn start_bootstrap_server(
                 bind,
                 keypair,
                 version,
+                resource_status,
                 ip_hist_map: HashMap::with_capacity(bootstrap_config.ip_list_max_size),
+                total_limiter: Limiter::new(bootstrap_config.max_bytes_read_write_total),
+                part_cache: Arc::new(Mutex::new(BootstrapPartCache::new(
+                    bootstrap_config.part_cache_max_age.to_duration(),
+                    bootstrap_config.part_cache_max_entries,
+                ))),
                 bootstrap_config,
             }
             .run()
@@ -102,7 +113,13 @@ struct BootstrapServer {
     keypair: KeyPair,
     bootstrap_config: BootstrapConfig,
     version: Version,
+    /// bootstrap sessions are refused while the node is degraded
+    resource_status: ResourceStatus,
     ip_hist_map: HashMap<IpAddr, Instant>,
+    /// bandwidth limiter shared by all the bootstrap sessions
+    total_limiter: Limiter,
+    /// final state parts shared by all the bootstrap sessions
+    part_cache: Arc<Mutex<BootstrapPartCache>>,
 }
 
 #[allow(clippy::result_large_err)]
@@ -151,6 +168,15 @@ impl BootstrapServer {
         massa_trace!("bootstrap.lib.run", {});
         let mut listener = self.establisher.get_listener(self.bind).await?;
         let mut bootstrap_sessions = FuturesUnordered::new();
+        // clients being handshaked and authenticated, before they take a session slot
+        let mut pending_clients = FuturesUnordered::new();
+        let max_simultaneous_bootstraps: usize = self
+            .bootstrap_config
+            .max_simultaneous_bootstraps
+            .try_into()
+            .map_err(|_| {
+                BootstrapError::GeneralError("Fail to convert u32 to usize".to_string())
+            })?;
         let cache_timeout = self.bootstrap_config.cache_duration.to_duration();
         let (mut whitelist, mut blacklist) = reload_whitelist_blacklist(
             &self.bootstrap_config.bootstrap_whitelist_path,
@@ -165,7 +191,8 @@ impl BootstrapServer {
                 * manager commands to avoid waiting too long to stop in case of contention
                 * cache timeout to avoid skipping timeouts cleanup tasks (they are relatively rare)
                 * bootstrap sessions (rare)
-                * listener: most frequent => last
+                * listener: most frequent
+                * authenticated clients, as frequent as the listener
         */
         loop {
             massa_trace!("bootstrap.lib.run.select", {});
@@ -193,7 +220,43 @@ impl BootstrapServer {
                     } else {
                         continue;
                     };
-                    if bootstrap_sessions.len() < self.bootstrap_config.max_simultaneous_bootstraps.try_into().map_err(|_| BootstrapError::GeneralError("Fail to convert u32 to usize".to_string()))? {
+                    if self.resource_status.is_degraded() {
+                        let config = self.bootstrap_config.clone();
+                        let mut server = BootstrapServerBinder::new(dplx, self.keypair.clone(), config.max_bytes_read_write, self.total_limiter.clone(), config.max_bootstrap_message_size, config.thread_count, config.max_datastore_key_length, config.randomness_size_bytes, config.consensus_bootstrap_part_size);
+                        let _ = tokio::time::timeout(config.write_error_timeout.into(), server.send(BootstrapServerMessage::BootstrapError {
+                            error: "Bootstrap failed because the bootstrap server is in degraded read-only mode.".to_string()
+                        })).await;
+                        debug!("did not bootstrap {}: degraded read-only mode", remote_addr);
+                        continue;
+                    }
+                    // the handshake and the token check of a client do not take a session slot yet,
+                    // but their number is bounded all the same
+                    if pending_clients.len() >= max_simultaneous_bootstraps {
+                        let config = self.bootstrap_config.clone();
+                        let mut server = BootstrapServerBinder::new(dplx, self.keypair.clone(), config.max_bytes_read_write, self.total_limiter.clone(), config.max_bootstrap_message_size, config.thread_count, config.max_datastore_key_length, config.randomness_size_bytes, config.consensus_bootstrap_part_size);
+                        let _ = tokio::time::timeout(config.write_error_timeout.into(), server.send(BootstrapServerMessage::BootstrapError {
+                            error: "Bootstrap failed because the bootstrap server currently has no slots available.".to_string()
+                        })).await;
+                        debug!("did not bootstrap {}: no available slots", remote_addr);
+                        continue;
+                    }
+                    let version = self.version;
+                    let config = self.bootstrap_config.clone();
+                    let mut server = BootstrapServerBinder::new(dplx, self.keypair.clone(), config.max_bytes_read_write, self.total_limiter.clone(), config.max_bootstrap_message_size, config.thread_count, config.max_datastore_key_length, config.randomness_size_bytes, config.consensus_bootstrap_part_size);
+                    pending_clients.push(async move {
+                        let authentication = authenticate_client(&config, &mut server, version).await;
+                        (server, remote_addr, authentication)
+                    });
+                }
+
+                // client handshaked and, for a restricted server, authenticated
+                Some((mut server, remote_addr, authentication)) = pending_clients.next() => {
+                    if let Err(err) = authentication {
+                        debug!("did not bootstrap {}: {}", remote_addr, err);
+                        let _ = tokio::time::timeout(self.bootstrap_config.write_error_timeout.into(), server.send(BootstrapServerMessage::BootstrapError { error: err.to_string() })).await;
+                        continue;
+                    }
+                    if bootstrap_sessions.len() < max_simultaneous_bootstraps {
 
                         massa_trace!("bootstrap.lib.run.select.accept", {"remote_addr": remote_addr});
                         let now = Instant::now();
@@ -212,7 +275,6 @@ impl BootstrapServer {
                         match self.ip_hist_map.entry(remote_addr.ip()) {
                             hash_map::Entry::Occupied(mut occ) => {
                                 if now.duration_since(*occ.get()) <= per_ip_min_interval {
-                                    let mut server = BootstrapServerBinder::new(dplx, self.keypair.clone(), self.bootstrap_config.max_bytes_read_write, self.bootstrap_config.max_bootstrap_message_size, self.bootstrap_config.thread_count, self.bootstrap_config.max_datastore_key_length, self.bootstrap_config.randomness_size_bytes, self.bootstrap_config.consensus_bootstrap_part_size);
                                     let _ = match tokio::time::timeout(self.bootstrap_config.write_error_timeout.into(), server.send(BootstrapServerMessage::BootstrapError {
                                         error:
                                         format!("Your last bootstrap on this server was {} ago and you have to wait {} before retrying.", format_duration(occ.get().elapsed()), format_duration(per_ip_min_interval.saturating_sub(occ.get().elapsed())))
@@ -251,14 +313,13 @@ impl BootstrapServer {
 
                         let version = self.version;
                         let data_execution = self.final_state.clone();
+                        let part_cache = self.part_cache.clone();
                         let consensus_command_sender = self.consensus_controller.clone();
                         let network_command_sender = self.network_command_sender.clone();
-                        let keypair = self.keypair.clone();
                         let config = self.bootstrap_config.clone();
 
                         bootstrap_sessions.push(async move {
-                            let mut server = BootstrapServerBinder::new(dplx, keypair, config.max_bytes_read_write, config.max_bootstrap_message_size, config.thread_count, config.max_datastore_key_length, config.randomness_size_bytes, config.consensus_bootstrap_part_size);
-                            match manage_bootstrap(&config, &mut server, data_execution, version, consensus_command_sender, network_command_sender).await {
+                            match manage_bootstrap(&config, &mut server, data_execution, part_cache, version, consensus_command_sender, network_command_sender).await {
                                 Ok(_) => {
                                     info!("bootstrapped peer {}", remote_addr)
                                 },
@@ -273,9 +334,7 @@ impl BootstrapServer {
                         });
                         massa_trace!("bootstrap.session.started", {"active_count": bootstrap_sessions.len()});
                     } else {
-                        let config = self.bootstrap_config.clone();
-                        let mut server = BootstrapServerBinder::new(dplx, self.keypair.clone(), config.max_bytes_read_write, config.max_bootstrap_message_size, config.thread_count, config.max_datastore_key_length, config.randomness_size_bytes, config.consensus_bootstrap_part_size);
-                        let _ = match tokio::time::timeout(config.clone().write_error_timeout.into(), server.send(BootstrapServerMessage::BootstrapError {
+                        let _ = match tokio::time::timeout(self.bootstrap_config.write_error_timeout.into(), server.send(BootstrapServerMessage::BootstrapError {
                             error: "Bootstrap failed because the bootstrap server currently has no slots available.".to_string()
                         })).await {
                             Err(_) => Err(std::io::Error::new(std::io::ErrorKind::TimedOut, "bootstrap error no available slots send timed out").into()),
@@ -294,10 +353,41 @@ impl BootstrapServer {
     }
 }
 
+/// Read the final state parts following `cursors`
+pub(crate) fn read_final_state_part(
+    final_state: &FinalState,
+    cursors: &FinalStateCursors,
+) -> Result<FinalStatePart, BootstrapError> {
+    let (ledger_part, ledger) = final_state.ledger.get_ledger_part(cursors.ledger.clone())?;
+    let (async_pool_part, pool) = final_state.async_pool.get_pool_part(cursors.pool);
+    let (pos_cycle_part, cycle) = final_state
+        .pos_state
+        .get_cycle_history_part(cursors.cycle)?;
+    let (pos_credits_part, credits) = final_state
+        .pos_state
+        .get_deferred_credits_part(cursors.credits);
+    let (exec_ops_part, ops) = final_state.executed_ops.get_executed_ops_part(cursors.ops);
+    Ok(FinalStatePart {
+        ledger_part,
+        async_pool_part,
+        pos_cycle_part,
+        pos_credits_part,
+        exec_ops_part,
+        next_cursors: FinalStateCursors {
+            ledger,
+            pool,
+            cycle,
+            credits,
+            ops,
+        },
+    })
+}
+
 #[allow(clippy::too_many_arguments)]
 pub async fn stream_bootstrap_information(
     server: &mut BootstrapServerBinder,
     final_state: Arc<RwLock<FinalState>>,
+    part_cache: Arc<Mutex<BootstrapPartCache>>,
     consensus_controller: Box<dyn ConsensusController>,
     mut last_slot: Option<Slot>,
     mut last_ledger_step: StreamingStep<Vec<u8>>,
@@ -328,29 +418,36 @@ pub async fn stream_bootstrap_information(
         // Scope of the final state read
         {
             let final_state_read = final_state.read();
-            let (data, new_ledger_step) = final_state_read
-                .ledger
-                .get_ledger_part(last_ledger_step.clone())?;
-            ledger_part = data;
-
-            let (pool_data, new_pool_step) =
-                final_state_read.async_pool.get_pool_part(last_pool_step);
-            async_pool_part = pool_data;
-
-            let (cycle_data, new_cycle_step) = final_state_read
-                .pos_state
-                .get_cycle_history_part(last_cycle_step)?;
-            pos_cycle_part = cycle_data;
-
-            let (credits_data, new_credits_step) = final_state_read
-                .pos_state
-                .get_deferred_credits_part(last_credits_step);
-            pos_credits_part = credits_data;
-
-            let (ops_data, new_ops_step) = final_state_read
-                .executed_ops
-                .get_executed_ops_part(last_ops_step);
-            exec_ops_part = ops_data;
+            let cursors = FinalStateCursors {
+                ledger: last_ledger_step.clone(),
+                pool: last_pool_step,
+                cycle: last_cycle_step,
+                credits: last_credits_step,
+                ops: last_ops_step,
+            };
+            let cached_part = part_cache.lock().get(final_state_read.slot, &cursors);
+            let part = match cached_part {
+                Some(part) => part,
+                None => {
+                    let part = read_final_state_part(&final_state_read, &cursors)?;
+                    part_cache
+                        .lock()
+                        .insert(final_state_read.slot, cursors, part.clone());
+                    part
+                }
+            };
+            ledger_part = part.ledger_part;
+            async_pool_part = part.async_pool_part;
+            pos_cycle_part = part.pos_cycle_part;
+            pos_credits_part = part.pos_credits_part;
+            exec_ops_part = part.exec_ops_part;
+            let FinalStateCursors {
+                ledger: new_ledger_step,
+                pool: new_pool_step,
+                cycle: new_cycle_step,
+                credits: new_credits_step,
+                ops: new_ops_step,
+            } = part.next_cursors;
 
             if let Some(slot) = last_slot && slot != final_state_read.slot {
                 if slot > final_state_read.slot {
@@ -501,19 +598,16 @@ pub async fn stream_bootstrap_information(
     Ok(())
 }
 
-#[allow(clippy::manual_async_fn)]
-#[allow(clippy::too_many_arguments)]
-async fn manage_bootstrap(
+/// Handshake with a newly connected client and, if this server is restricted to known clients,
+/// check that the client presents one of the accepted tokens.
+///
+/// This is done before the client takes a session slot or a place in the per-IP history,
+/// so that unknown clients can neither use up the slots nor delay the retries of the known ones.
+pub(crate) async fn authenticate_client(
     bootstrap_config: &BootstrapConfig,
     server: &mut BootstrapServerBinder,
-    final_state: Arc<RwLock<FinalState>>,
     version: Version,
-    consensus_controller: Box<dyn ConsensusController>,
-    network_command_sender: NetworkCommandSender,
 ) -> Result<(), BootstrapError> {
-    massa_trace!("bootstrap.lib.manage_bootstrap", {});
-    let read_error_timeout: std::time::Duration = bootstrap_config.read_error_timeout.into();
-
     match tokio::time::timeout(
         bootstrap_config.read_timeout.into(),
         server.handshake(version),
@@ -531,12 +625,66 @@ async fn manage_bootstrap(
         Ok(Ok(_)) => (),
     };
 
+    if bootstrap_config.bootstrap_tokens.is_empty() {
+        return Ok(());
+    }
+    let authenticated =
+        match tokio::time::timeout(bootstrap_config.read_timeout.into(), server.next()).await {
+            Err(_) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    "bootstrap token read timed out",
+                )
+                .into())
+            }
+            Ok(Err(e)) => return Err(e),
+            Ok(Ok(BootstrapClientMessage::Authenticate { token })) => {
+                is_accepted_token(&bootstrap_config.bootstrap_tokens, &token)
+            }
+            Ok(Ok(_)) => false,
+        };
+    if !authenticated {
+        return Err(BootstrapError::GeneralError(
+            "This bootstrap server is restricted: set a valid bootstrap_token in your bootstrap settings.".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Whether `token` is one of `accepted_tokens`.
+/// The comparisons take the same time whatever the bytes of `token`, so that they do not tell how close it is to a valid token.
+fn is_accepted_token(accepted_tokens: &[String], token: &str) -> bool {
+    accepted_tokens
+        .iter()
+        .fold(Choice::from(0), |accepted, accepted_token| {
+            accepted | accepted_token.as_bytes().ct_eq(token.as_bytes())
+        })
+        .into()
+}
+
+#[allow(clippy::manual_async_fn)]
+#[allow(clippy::too_many_arguments)]
+async fn manage_bootstrap(
+    bootstrap_config: &BootstrapConfig,
+    server: &mut BootstrapServerBinder,
+    final_state: Arc<RwLock<FinalState>>,
+    part_cache: Arc<Mutex<BootstrapPartCache>>,
+    version: Version,
+    consensus_controller: Box<dyn ConsensusController>,
+    network_command_sender: NetworkCommandSender,
+) -> Result<(), BootstrapError> {
+    massa_trace!("bootstrap.lib.manage_bootstrap", {});
+    let read_error_timeout: std::time::Duration = bootstrap_config.read_error_timeout.into();
+
     match tokio::time::timeout(read_error_timeout, server.next()).await {
         Err(_) => (),
         Ok(Err(e)) => return Err(e),
         Ok(Ok(BootstrapClientMessage::BootstrapError { error })) => {
             return Err(BootstrapError::GeneralError(error))
         }
+        // a token sent to a server that is not restricted is ignored
+        Ok(Ok(BootstrapClientMessage::Authenticate { .. }))
+            if bootstrap_config.bootstrap_tokens.is_empty() => {}
         Ok(Ok(msg)) => return Err(BootstrapError::UnexpectedClientMessage(msg)),
     };
 
@@ -598,6 +746,7 @@ async fn manage_bootstrap(
                     stream_bootstrap_information(
                         server,
                         final_state.clone(),
+                        part_cache.clone(),
                         consensus_controller.clone(),
                         last_slot,
                         last_ledger_step,
@@ -614,6 +763,8 @@ async fn manage_bootstrap(
                 BootstrapClientMessage::BootstrapError { error } => {
                     break Err(BootstrapError::ReceivedError(error));
                 }
+                // the token is ignored when this server is not restricted
+                BootstrapClientMessage::Authenticate { .. } => {}
             },
         };
     }
@@ -26,7 +26,7 @@ pub struct BootstrapServerBinder {
     randomness_size_bytes: usize,
     size_field_len: usize,
     local_keypair: KeyPair,
-    duplex: Resource<Duplex, StandardClock>,
+    duplex: Resource<Resource<Duplex, StandardClock>, StandardClock>,
     prev_message: Option<Hash>,
     version_serializer: VersionSerializer,
     version_deserializer: VersionDeserializer,
@@ -39,11 +39,13 @@ impl BootstrapServerBinder {
     /// * `duplex`: duplex stream.
     /// * `local_keypair`: local node user keypair
     /// * `limit`: limit max bytes per second (up and down)
+    /// * `total_limiter`: limiter shared by all the bootstrap sessions of the server
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         duplex: Duplex,
         local_keypair: KeyPair,
         limit: f64,
+        total_limiter: Limiter,
         max_bootstrap_message_size: u32,
         thread_count: u8,
         max_datastore_key_length: u8,
@@ -56,7 +58,7 @@ impl BootstrapServerBinder {
             consensus_bootstrap_part_size,
             size_field_len,
             local_keypair,
-            duplex: <Limiter>::new(limit).limit(duplex),
+            duplex: total_limiter.limit(<Limiter>::new(limit).limit(duplex)),
             prev_message: None,
             thread_count,
             max_datastore_key_length,
@@ -0,0 +1,107 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Cache of the final state parts streamed by the bootstrap server.
+//!
+//! The parts read for a cursor are kept until the final state slot changes, so that the sessions
+//! asking for the same cursor at the same slot (ex: the first part, when several clients bootstrap at once)
+//! do not read and copy them from the final state again.
+
+use massa_async_pool::{AsyncMessage, AsyncMessageId};
+use massa_metrics::BOOTSTRAP_METRICS;
+use massa_models::{
+    operation::OperationId, prehash::PreHashSet, slot::Slot, streaming_step::StreamingStep,
+};
+use massa_pos_exports::{CycleInfo, DeferredCredits};
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+/// Cursors of the final state streaming
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct FinalStateCursors {
+    pub ledger: StreamingStep<Vec<u8>>,
+    pub pool: StreamingStep<AsyncMessageId>,
+    pub cycle: StreamingStep<u64>,
+    pub credits: StreamingStep<Slot>,
+    pub ops: StreamingStep<Slot>,
+}
+
+/// Final state parts read from given cursors, along with the cursors of the next parts
+#[derive(Debug, Clone)]
+pub(crate) struct FinalStatePart {
+    pub ledger_part: Vec<u8>,
+    pub async_pool_part: BTreeMap<AsyncMessageId, AsyncMessage>,
+    pub pos_cycle_part: Option<CycleInfo>,
+    pub pos_credits_part: DeferredCredits,
+    pub exec_ops_part: BTreeMap<Slot, PreHashSet<OperationId>>,
+    pub next_cursors: FinalStateCursors,
+}
+
+/// Final state parts of the current final slot, by cursor
+pub(crate) struct BootstrapPartCache {
+    /// maximal duration an entry is served
+    max_age: Duration,
+    /// maximal number of entries, 0 disables the cache
+    max_entries: usize,
+    /// final slot of the cached parts
+    slot: Option<Slot>,
+    /// parts by cursor, with the instant they were read
+    parts: HashMap<FinalStateCursors, (Instant, FinalStatePart)>,
+}
+
+impl BootstrapPartCache {
+    /// Create an empty cache
+    pub fn new(max_age: Duration, max_entries: usize) -> Self {
+        BootstrapPartCache {
+            max_age,
+            max_entries,
+            slot: None,
+            parts: HashMap::new(),
+        }
+    }
+
+    /// Get the part read from `cursors` at the final slot `slot`, if it is cached and not too old
+    pub fn get(&mut self, slot: Slot, cursors: &FinalStateCursors) -> Option<FinalStatePart> {
+        self.refresh(slot);
+        let part = match self.parts.get(cursors) {
+            Some((read_at, part)) if read_at.elapsed() <= self.max_age => Some(part.clone()),
+            _ => None,
+        };
+        if part.is_some() {
+            BOOTSTRAP_METRICS.part_cache_hits.inc();
+        } else {
+            BOOTSTRAP_METRICS.part_cache_misses.inc();
+        }
+        part
+    }
+
+    /// Cache the part read from `cursors` at the final slot `slot`, replacing the oldest one if the cache is full
+    pub fn insert(&mut self, slot: Slot, cursors: FinalStateCursors, part: FinalStatePart) {
+        self.refresh(slot);
+        if self.max_entries == 0 {
+            return;
+        }
+        if self.parts.len() >= self.max_entries && !self.parts.contains_key(&cursors) {
+            let oldest = self
+                .parts
+                .iter()
+                .min_by_key(|(_, (read_at, _))| *read_at)
+                .map(|(cursors, _)| cursors.clone());
+            if let Some(oldest) = oldest {
+                self.parts.remove(&oldest);
+            }
+        }
+        self.parts.insert(cursors, (Instant::now(), part));
+        BOOTSTRAP_METRICS
+            .part_cache_entries
+            .set(self.parts.len() as i64);
+    }
+
+    /// Drop the parts read at another final slot: the final state changed since
+    fn refresh(&mut self, slot: Slot) {
+        if self.slot != Some(slot) {
+            self.parts.clear();
+            self.slot = Some(slot);
+            BOOTSTRAP_METRICS.part_cache_entries.set(0);
+        }
+    }
+}
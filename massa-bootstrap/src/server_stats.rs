@@ -0,0 +1,108 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Statistics of the bootstrap attempts of the client, per server.
+//!
+//! They are saved to a file after each attempt so that the servers that kept failing
+//! during the previous runs are tried after the others.
+
+use massa_signature::PublicKey;
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use tracing::warn;
+
+/// Bootstrap attempts made to a server
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BootstrapServerStats {
+    /// number of successful bootstraps
+    pub successes: u64,
+    /// number of failed attempts (connection, error or timeout)
+    pub failures: u64,
+    /// number of failed attempts since the last success
+    pub consecutive_failures: u64,
+}
+
+/// Bootstrap attempt statistics of all the servers, persisted to a file
+pub(crate) struct BootstrapServerStatsDb {
+    /// file the statistics are saved to
+    path: PathBuf,
+    /// statistics per server address
+    stats: HashMap<SocketAddr, BootstrapServerStats>,
+}
+
+impl BootstrapServerStatsDb {
+    /// Load the statistics saved in `path`, starting from empty ones if there are none
+    pub fn load(path: PathBuf) -> Self {
+        let stats = if path.is_file() {
+            std::fs::read_to_string(&path)
+                .map_err(|err| err.to_string())
+                .and_then(|json| serde_json::from_str(&json).map_err(|err| err.to_string()))
+                .unwrap_or_else(|err| {
+                    warn!(
+                        "could not load the bootstrap server stats from {}, starting from empty ones: {}",
+                        path.display(),
+                        err
+                    );
+                    HashMap::new()
+                })
+        } else {
+            HashMap::new()
+        };
+        BootstrapServerStatsDb { path, stats }
+    }
+
+    /// Get the order in which the servers should be tried:
+    /// random, except that the servers with the fewest consecutive failures come first
+    pub fn order_servers(
+        &self,
+        servers: &[(SocketAddr, PublicKey)],
+    ) -> Vec<(SocketAddr, PublicKey)> {
+        let mut ordered = servers.to_vec();
+        ordered.shuffle(&mut StdRng::from_entropy());
+        // the sort is stable: the servers with as many consecutive failures stay shuffled
+        ordered.sort_by_key(|(addr, _)| {
+            self.stats
+                .get(addr)
+                .map_or(0, |stats| stats.consecutive_failures)
+        });
+        ordered
+    }
+
+    /// Account a successful bootstrap from `addr`
+    pub fn note_success(&mut self, addr: SocketAddr) {
+        let stats = self.stats.entry(addr).or_default();
+        stats.successes = stats.successes.saturating_add(1);
+        stats.consecutive_failures = 0;
+        self.save();
+    }
+
+    /// Account a failed bootstrap attempt from `addr`
+    pub fn note_failure(&mut self, addr: SocketAddr) {
+        let stats = self.stats.entry(addr).or_default();
+        stats.failures = stats.failures.saturating_add(1);
+        stats.consecutive_failures = stats.consecutive_failures.saturating_add(1);
+        self.save();
+    }
+
+    /// Get the statistics of a server
+    #[cfg(test)]
+    pub fn get(&self, addr: &SocketAddr) -> Option<&BootstrapServerStats> {
+        self.stats.get(addr)
+    }
+
+    /// Save the statistics, failing to do so only loses them for the next runs
+    fn save(&self) {
+        let result = serde_json::to_string_pretty(&self.stats)
+            .map_err(|err| err.to_string())
+            .and_then(|json| std::fs::write(&self.path, json).map_err(|err| err.to_string()));
+        if let Err(err) = result {
+            warn!(
+                "could not save the bootstrap server stats to {}: {}",
+                self.path.display(),
+                err
+            );
+        }
+    }
+}
@@ -14,6 +14,20 @@ pub struct BootstrapConfig {
     pub bootstrap_whitelist_path: PathBuf,
     /// Path to the bootstrap blacklist file. This whitelist define IPs that will not be able to bootstrap on your node. This list is optional.
     pub bootstrap_blacklist_path: PathBuf,
+    /// Token sent to the bootstrap servers, required by the servers restricted to known clients.
+    /// It is sent to all the servers of `bootstrap_list`.
+    pub bootstrap_token: Option<String>,
+    /// Path to a local snapshot to import at startup instead of bootstrapping from the network.
+    pub snapshot_import_path: Option<PathBuf>,
+    /// Path where a snapshot is saved at clean shutdown, to be imported at the next startup.
+    pub shutdown_snapshot_path: Option<PathBuf>,
+    /// Maximal age of the shutdown snapshot for it to be imported instead of bootstrapping.
+    pub shutdown_snapshot_max_age: MassaTime,
+    /// Path to the file where the client saves its bootstrap attempt statistics per server.
+    /// The servers that failed the most recent attempts are tried last.
+    pub server_stats_path: PathBuf,
+    /// Tokens accepted from the clients. If not empty, only the clients presenting one of them can bootstrap from this server.
+    pub bootstrap_tokens: Vec<String>,
     /// Port to listen if we choose to allow other nodes to use us as bootstrap node.
     pub bind: Option<SocketAddr>,
     /// connection timeout
@@ -34,6 +48,10 @@ pub struct BootstrapConfig {
     pub max_clock_delta: MassaTime,
     /// Cache duration
     pub cache_duration: MassaTime,
+    /// Maximal duration a final state part is served from the cache of the server
+    pub part_cache_max_age: MassaTime,
+    /// Maximal number of final state parts in the cache of the server, 0 disables it
+    pub part_cache_max_entries: usize,
     /// Max simultaneous bootstraps
     pub max_simultaneous_bootstraps: u32,
     /// Minimum interval between two bootstrap attempts from a given IP
@@ -42,6 +60,8 @@ pub struct BootstrapConfig {
     pub ip_list_max_size: usize,
     /// Read-Write limitation for a connection in bytes per seconds
     pub max_bytes_read_write: f64,
+    /// Read-Write limitation in bytes per seconds shared by all the bootstrap sessions of the server
+    pub max_bytes_read_write_total: f64,
     /// max bootstrap message size in bytes
     pub max_bootstrap_message_size: u32,
     /// thread count
@@ -0,0 +1,357 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Local snapshots of the final state and the consensus graph.
+//!
+//! A snapshot file contains the hash of its content followed by the node version
+//! and the sequence of `BootstrapServerMessage` a bootstrap server would stream at the snapshot final slot.
+//! Importing it at startup fills the final state and the consensus graph exactly like a network bootstrap would.
+
+use std::{
+    convert::TryInto,
+    fs::File,
+    io::{BufWriter, Seek, SeekFrom, Write},
+    path::Path,
+    sync::Arc,
+};
+
+use massa_consensus_exports::{bootstrapable_graph::BootstrapableGraph, ConsensusController};
+use massa_final_state::{FinalState, FinalStateError};
+use massa_hash::{Hash, Hasher, HASH_SIZE_BYTES};
+use massa_models::{
+    block::BlockId,
+    prehash::PreHashSet,
+    slot::Slot,
+    streaming_step::StreamingStep,
+    version::{Version, VersionDeserializer, VersionSerializer},
+};
+use massa_serialization::{DeserializeError, Deserializer, Serializer};
+use parking_lot::RwLock;
+use tracing::debug;
+
+use crate::{
+    client::apply_bootstrap_part,
+    error::BootstrapError,
+    messages::{
+        BootstrapServerMessage, BootstrapServerMessageDeserializer,
+        BootstrapServerMessageSerializer,
+    },
+    BootstrapConfig, GlobalBootstrapState,
+};
+
+/// Size of the length field preceding each message of a snapshot
+const MESSAGE_LENGTH_SIZE_BYTES: usize = 4;
+
+/// Export the final state and the final blocks of the consensus graph to a snapshot file.
+///
+/// The parts are read like a bootstrap server streams them: the final state is only read-locked
+/// while reading each part, and the final state changes that happened since the previous part are exported along.
+/// Each part is written as soon as it is read, to a temporary file atomically moved to `path` at the end.
+///
+/// Returns the final slot of the exported snapshot.
+pub fn export_snapshot(
+    path: &Path,
+    final_state: &Arc<RwLock<FinalState>>,
+    consensus_controller: &dyn ConsensusController,
+    version: Version,
+) -> Result<Slot, BootstrapError> {
+    let message_serializer = BootstrapServerMessageSerializer::new();
+    let tmp_path = path.with_extension("tmp");
+    let mut writer = SnapshotWriter::create(&tmp_path)?;
+    let mut version_bytes = Vec::new();
+    VersionSerializer::new().serialize(&version, &mut version_bytes)?;
+    writer.write(&version_bytes)?;
+
+    let mut last_slot: Option<Slot> = None;
+    let mut last_ledger_step = StreamingStep::Started;
+    let mut last_pool_step = StreamingStep::Started;
+    let mut last_cycle_step = StreamingStep::Started;
+    let mut last_credits_step = StreamingStep::Started;
+    let mut last_ops_step = StreamingStep::Started;
+    let mut last_consensus_step: StreamingStep<PreHashSet<BlockId>> = StreamingStep::Started;
+    let slot = loop {
+        // Scope of the final state read
+        let (
+            slot,
+            ledger_part,
+            async_pool_part,
+            pos_cycle_part,
+            pos_credits_part,
+            exec_ops_part,
+            final_state_changes,
+        ) = {
+            let final_state_read = final_state.read();
+            let (ledger_part, new_ledger_step) =
+                final_state_read.ledger.get_ledger_part(last_ledger_step)?;
+            let (async_pool_part, new_pool_step) =
+                final_state_read.async_pool.get_pool_part(last_pool_step);
+            let (pos_cycle_part, new_cycle_step) = final_state_read
+                .pos_state
+                .get_cycle_history_part(last_cycle_step)?;
+            let (pos_credits_part, new_credits_step) = final_state_read
+                .pos_state
+                .get_deferred_credits_part(last_credits_step);
+            let (exec_ops_part, new_ops_step) = final_state_read
+                .executed_ops
+                .get_executed_ops_part(last_ops_step);
+            let final_state_changes = match last_slot {
+                Some(slot) if slot != final_state_read.slot => final_state_read
+                    .get_state_changes_part(
+                        slot,
+                        new_ledger_step.clone(),
+                        new_pool_step,
+                        new_cycle_step,
+                        new_credits_step,
+                        new_ops_step,
+                    )
+                    .map_err(|err| match err {
+                        FinalStateError::InvalidSlot(_) => BootstrapError::SnapshotError(
+                            "the final state moved past its changes history during the export"
+                                .to_string(),
+                        ),
+                        err => BootstrapError::FinalStateError(err),
+                    })?,
+                _ => Vec::new(),
+            };
+            last_ledger_step = new_ledger_step;
+            last_pool_step = new_pool_step;
+            last_cycle_step = new_cycle_step;
+            last_credits_step = new_credits_step;
+            last_ops_step = new_ops_step;
+            last_slot = Some(final_state_read.slot);
+            (
+                final_state_read.slot,
+                ledger_part,
+                async_pool_part,
+                pos_cycle_part,
+                pos_credits_part,
+                exec_ops_part,
+                final_state_changes,
+            )
+        };
+
+        let final_state_finished = last_ledger_step.finished()
+            && last_pool_step.finished()
+            && last_cycle_step.finished()
+            && last_credits_step.finished()
+            && last_ops_step.finished();
+        let final_state_changes_step = if final_state_changes.is_empty() {
+            StreamingStep::Finished(Some(slot))
+        } else {
+            StreamingStep::Ongoing(slot)
+        };
+
+        // Export consensus blocks once the final state is complete
+        let mut consensus_part = BootstrapableGraph {
+            final_blocks: Default::default(),
+        };
+        let mut consensus_outdated_ids: PreHashSet<BlockId> = PreHashSet::default();
+        if final_state_finished {
+            let (part, outdated_ids, new_consensus_step) = consensus_controller
+                .get_bootstrap_part(last_consensus_step, final_state_changes_step)?;
+            consensus_part = part;
+            consensus_outdated_ids = outdated_ids;
+            last_consensus_step = new_consensus_step;
+        }
+
+        writer.write_message(
+            &message_serializer,
+            &BootstrapServerMessage::BootstrapPart {
+                slot,
+                ledger_part,
+                async_pool_part,
+                pos_cycle_part,
+                pos_credits_part,
+                exec_ops_part,
+                final_state_changes,
+                consensus_part,
+                consensus_outdated_ids,
+            },
+        )?;
+
+        if final_state_finished
+            && final_state_changes_step.finished()
+            && last_consensus_step.finished()
+        {
+            break slot;
+        }
+    };
+    writer.write_message(
+        &message_serializer,
+        &BootstrapServerMessage::BootstrapFinished,
+    )?;
+
+    let size = writer.finish()?;
+    std::fs::rename(&tmp_path, path)?;
+    debug!(
+        "snapshot of slot {} exported to {} ({} bytes)",
+        slot,
+        path.display(),
+        size
+    );
+    Ok(slot)
+}
+
+/// Import a snapshot file previously created by `export_snapshot` into the given final state.
+///
+/// The whole file is read and its hash verified before anything is written to the final state:
+/// a `BootstrapError::SnapshotError` means that the final state was left untouched.
+pub fn import_snapshot(
+    cfg: &BootstrapConfig,
+    path: &Path,
+    final_state: Arc<RwLock<FinalState>>,
+    version: Version,
+) -> Result<GlobalBootstrapState, BootstrapError> {
+    let parts = read_snapshot(cfg, path, version)?;
+    let mut global_bootstrap_state = GlobalBootstrapState::new(final_state);
+    for part in parts {
+        apply_bootstrap_part(&mut global_bootstrap_state, part)?;
+    }
+    Ok(global_bootstrap_state)
+}
+
+/// Writer of a snapshot file: the hash of the content is written in front of it once the content is complete
+struct SnapshotWriter {
+    file: BufWriter<File>,
+    hasher: Hasher,
+    size: usize,
+}
+
+impl SnapshotWriter {
+    /// Create the file at `path`, leaving room for the hash of the content
+    fn create(path: &Path) -> Result<Self, BootstrapError> {
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(&[0; HASH_SIZE_BYTES])?;
+        Ok(SnapshotWriter {
+            file,
+            hasher: Hasher::new(),
+            size: HASH_SIZE_BYTES,
+        })
+    }
+
+    /// Append `bytes` to the content
+    fn write(&mut self, bytes: &[u8]) -> Result<(), BootstrapError> {
+        self.file.write_all(bytes)?;
+        self.hasher.update(bytes);
+        self.size = self.size.saturating_add(bytes.len());
+        Ok(())
+    }
+
+    /// Append `message` to the content, preceded by its length
+    fn write_message(
+        &mut self,
+        message_serializer: &BootstrapServerMessageSerializer,
+        message: &BootstrapServerMessage,
+    ) -> Result<(), BootstrapError> {
+        let mut msg_bytes = Vec::new();
+        message_serializer.serialize(message, &mut msg_bytes)?;
+        let msg_len: u32 = msg_bytes.len().try_into().map_err(|e| {
+            BootstrapError::GeneralError(format!("snapshot message too large to encode: {}", e))
+        })?;
+        self.write(&msg_len.to_be_bytes())?;
+        self.write(&msg_bytes)
+    }
+
+    /// Write the hash of the content in front of it and sync the file to the disk.
+    /// Returns the size of the file.
+    fn finish(self) -> Result<usize, BootstrapError> {
+        let mut file = self.file.into_inner().map_err(|err| err.into_error())?;
+        file.seek(SeekFrom::Start(0))?;
+        file.write_all(&self.hasher.finalize().to_bytes())?;
+        file.sync_all()?;
+        Ok(self.size)
+    }
+}
+
+/// Read a snapshot file, verify its hash and version, and return the final state and consensus parts it contains
+fn read_snapshot(
+    cfg: &BootstrapConfig,
+    path: &Path,
+    version: Version,
+) -> Result<Vec<BootstrapServerMessage>, BootstrapError> {
+    let file_bytes = std::fs::read(path).map_err(|err| {
+        BootstrapError::SnapshotError(format!("could not read {}: {}", path.display(), err))
+    })?;
+    if file_bytes.len() < HASH_SIZE_BYTES {
+        return Err(BootstrapError::SnapshotError(
+            "snapshot file is truncated".to_string(),
+        ));
+    }
+    let (hash_bytes, content) = file_bytes.split_at(HASH_SIZE_BYTES);
+    let expected_hash = Hash::from_bytes(
+        hash_bytes
+            .try_into()
+            .expect("hash slice has HASH_SIZE_BYTES length"),
+    );
+    if Hash::compute_from(content) != expected_hash {
+        return Err(BootstrapError::SnapshotError(
+            "snapshot hash verification failed".to_string(),
+        ));
+    }
+
+    let (mut rest, snapshot_version) = VersionDeserializer::new()
+        .deserialize::<DeserializeError>(content)
+        .map_err(|err| BootstrapError::SnapshotError(format!("invalid version: {}", err)))?;
+    if !version.is_compatible(&snapshot_version) {
+        return Err(BootstrapError::SnapshotError(format!(
+            "snapshot was created by an incompatible version: {} (local node version: {})",
+            snapshot_version, version
+        )));
+    }
+
+    let message_deserializer = BootstrapServerMessageDeserializer::new(
+        cfg.thread_count,
+        cfg.endorsement_count,
+        cfg.max_advertise_length,
+        cfg.max_bootstrap_blocks_length,
+        cfg.max_operations_per_block,
+        cfg.max_bootstrap_final_state_parts_size,
+        cfg.max_async_pool_changes,
+        cfg.max_async_pool_length,
+        cfg.max_async_message_data,
+        cfg.max_ledger_changes_count,
+        cfg.max_datastore_key_length,
+        cfg.max_datastore_value_length,
+        cfg.max_datastore_entry_count,
+        cfg.max_bootstrap_error_length,
+        cfg.max_changes_slot_count,
+        cfg.max_rolls_length,
+        cfg.max_production_stats_length,
+        cfg.max_credits_length,
+        cfg.max_executed_ops_length,
+        cfg.max_ops_changes_length,
+    );
+    let mut parts = Vec::new();
+    loop {
+        if rest.len() < MESSAGE_LENGTH_SIZE_BYTES {
+            return Err(BootstrapError::SnapshotError(
+                "snapshot file is truncated".to_string(),
+            ));
+        }
+        let (len_bytes, next) = rest.split_at(MESSAGE_LENGTH_SIZE_BYTES);
+        let msg_len = u32::from_be_bytes(
+            len_bytes
+                .try_into()
+                .expect("length slice has MESSAGE_LENGTH_SIZE_BYTES length"),
+        ) as usize;
+        if next.len() < msg_len {
+            return Err(BootstrapError::SnapshotError(
+                "snapshot file is truncated".to_string(),
+            ));
+        }
+        let (msg_bytes, next) = next.split_at(msg_len);
+        rest = next;
+        let (_, msg) = message_deserializer
+            .deserialize::<DeserializeError>(msg_bytes)
+            .map_err(|err| BootstrapError::SnapshotError(format!("invalid message: {}", err)))?;
+        match msg {
+            BootstrapServerMessage::BootstrapPart { .. } => parts.push(msg),
+            BootstrapServerMessage::BootstrapFinished => return Ok(parts),
+            other => {
+                return Err(BootstrapError::SnapshotError(format!(
+                    "unexpected message in snapshot: {:?}",
+                    other
+                )))
+            }
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use std::str::FromStr;
 
+use crate::error::BootstrapError;
 use crate::messages::{BootstrapClientMessage, BootstrapServerMessage};
 use crate::types::Duplex;
 use crate::BootstrapConfig;
@@ -7,6 +8,8 @@ use crate::{
     client_binder::BootstrapClientBinder, server_binder::BootstrapServerBinder,
     tests::tools::get_bootstrap_config, BootstrapPeers,
 };
+use async_speed_limit::Limiter;
+use massa_consensus_exports::bootstrapable_graph::BootstrapableGraph;
 use massa_models::config::{
     BOOTSTRAP_RANDOMNESS_SIZE_BYTES, CONSENSUS_BOOTSTRAP_PART_SIZE, ENDORSEMENT_COUNT,
     MAX_ADVERTISE_LENGTH, MAX_ASYNC_MESSAGE_DATA, MAX_ASYNC_POOL_LENGTH,
@@ -16,9 +19,13 @@ use massa_models::config::{
     MAX_EXECUTED_OPS_CHANGES_LENGTH, MAX_EXECUTED_OPS_LENGTH, MAX_LEDGER_CHANGES_COUNT,
     MAX_OPERATIONS_PER_BLOCK, MAX_PRODUCTION_STATS_LENGTH, MAX_ROLLS_COUNT_LENGTH, THREAD_COUNT,
 };
-use massa_models::version::Version;
+use massa_models::{prehash::PreHashSet, slot::Slot, version::Version};
+use massa_network_exports::AdvertisedPeer;
+use massa_pos_exports::DeferredCredits;
 use massa_signature::{KeyPair, PublicKey};
 use serial_test::serial;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
 use tokio::io::duplex;
 
 lazy_static::lazy_static! {
@@ -70,6 +77,7 @@ async fn test_binders() {
         server,
         server_keypair.clone(),
         f64::INFINITY,
+        Limiter::new(f64::INFINITY),
         MAX_BOOTSTRAP_MESSAGE_SIZE,
         THREAD_COUNT,
         MAX_DATASTORE_KEY_LENGTH,
@@ -81,7 +89,7 @@ async fn test_binders() {
 
     let server_thread = tokio::spawn(async move {
         // Test message 1
-        let vector_peers = vec![bootstrap_config.bootstrap_list[0].0.ip()];
+        let vector_peers = vec![AdvertisedPeer::from(bootstrap_config.bootstrap_list[0].0)];
         let test_peers_message = BootstrapServerMessage::BootstrapPeers {
             peers: BootstrapPeers(vector_peers.clone()),
         };
@@ -101,9 +109,9 @@ async fn test_binders() {
 
         // Test message 3
         let vector_peers = vec![
-            bootstrap_config.bootstrap_list[0].0.ip(),
-            bootstrap_config.bootstrap_list[0].0.ip(),
-            bootstrap_config.bootstrap_list[0].0.ip(),
+            AdvertisedPeer::from(bootstrap_config.bootstrap_list[0].0),
+            AdvertisedPeer::from(bootstrap_config.bootstrap_list[0].0),
+            AdvertisedPeer::from(bootstrap_config.bootstrap_list[0].0),
         ];
         let test_peers_message = BootstrapServerMessage::BootstrapPeers {
             peers: BootstrapPeers(vector_peers.clone()),
@@ -114,7 +122,7 @@ async fn test_binders() {
 
     let client_thread = tokio::spawn(async move {
         // Test message 1
-        let vector_peers = vec![bootstrap_config.bootstrap_list[0].0.ip()];
+        let vector_peers = vec![AdvertisedPeer::from(bootstrap_config.bootstrap_list[0].0)];
 
         let version: Version = Version::from_str("TEST.1.10").unwrap();
 
@@ -136,9 +144,9 @@ async fn test_binders() {
 
         // Test message 3
         let vector_peers = vec![
-            bootstrap_config.bootstrap_list[0].0.ip(),
-            bootstrap_config.bootstrap_list[0].0.ip(),
-            bootstrap_config.bootstrap_list[0].0.ip(),
+            AdvertisedPeer::from(bootstrap_config.bootstrap_list[0].0),
+            AdvertisedPeer::from(bootstrap_config.bootstrap_list[0].0),
+            AdvertisedPeer::from(bootstrap_config.bootstrap_list[0].0),
         ];
         let message = client.next().await.unwrap();
         match message {
@@ -164,6 +172,7 @@ async fn test_binders_double_send_server_works() {
         server,
         server_keypair.clone(),
         f64::INFINITY,
+        Limiter::new(f64::INFINITY),
         MAX_BOOTSTRAP_MESSAGE_SIZE,
         THREAD_COUNT,
         MAX_DATASTORE_KEY_LENGTH,
@@ -175,7 +184,7 @@ async fn test_binders_double_send_server_works() {
 
     let server_thread = tokio::spawn(async move {
         // Test message 1
-        let vector_peers = vec![bootstrap_config.bootstrap_list[0].0.ip()];
+        let vector_peers = vec![AdvertisedPeer::from(bootstrap_config.bootstrap_list[0].0)];
         let test_peers_message = BootstrapServerMessage::BootstrapPeers {
             peers: BootstrapPeers(vector_peers.clone()),
         };
@@ -187,9 +196,9 @@ async fn test_binders_double_send_server_works() {
 
         // Test message 2
         let vector_peers = vec![
-            bootstrap_config.bootstrap_list[0].0.ip(),
-            bootstrap_config.bootstrap_list[0].0.ip(),
-            bootstrap_config.bootstrap_list[0].0.ip(),
+            AdvertisedPeer::from(bootstrap_config.bootstrap_list[0].0),
+            AdvertisedPeer::from(bootstrap_config.bootstrap_list[0].0),
+            AdvertisedPeer::from(bootstrap_config.bootstrap_list[0].0),
         ];
         let test_peers_message = BootstrapServerMessage::BootstrapPeers {
             peers: BootstrapPeers(vector_peers.clone()),
@@ -200,7 +209,7 @@ async fn test_binders_double_send_server_works() {
 
     let client_thread = tokio::spawn(async move {
         // Test message 1
-        let vector_peers = vec![bootstrap_config.bootstrap_list[0].0.ip()];
+        let vector_peers = vec![AdvertisedPeer::from(bootstrap_config.bootstrap_list[0].0)];
 
         let version: Version = Version::from_str("TEST.1.10").unwrap();
 
@@ -215,9 +224,9 @@ async fn test_binders_double_send_server_works() {
 
         // Test message 2
         let vector_peers = vec![
-            bootstrap_config.bootstrap_list[0].0.ip(),
-            bootstrap_config.bootstrap_list[0].0.ip(),
-            bootstrap_config.bootstrap_list[0].0.ip(),
+            AdvertisedPeer::from(bootstrap_config.bootstrap_list[0].0),
+            AdvertisedPeer::from(bootstrap_config.bootstrap_list[0].0),
+            AdvertisedPeer::from(bootstrap_config.bootstrap_list[0].0),
         ];
         let message = client.next().await.unwrap();
         match message {
@@ -243,6 +252,7 @@ async fn test_binders_try_double_send_client_works() {
         server,
         server_keypair.clone(),
         f64::INFINITY,
+        Limiter::new(f64::INFINITY),
         MAX_BOOTSTRAP_MESSAGE_SIZE,
         THREAD_COUNT,
         MAX_DATASTORE_KEY_LENGTH,
@@ -254,7 +264,7 @@ async fn test_binders_try_double_send_client_works() {
 
     let server_thread = tokio::spawn(async move {
         // Test message 1
-        let vector_peers = vec![bootstrap_config.bootstrap_list[0].0.ip()];
+        let vector_peers = vec![AdvertisedPeer::from(bootstrap_config.bootstrap_list[0].0)];
         let test_peers_message = BootstrapServerMessage::BootstrapPeers {
             peers: BootstrapPeers(vector_peers.clone()),
         };
@@ -284,7 +294,7 @@ async fn test_binders_try_double_send_client_works() {
 
     let client_thread = tokio::spawn(async move {
         // Test message 1
-        let vector_peers = vec![bootstrap_config.bootstrap_list[0].0.ip()];
+        let vector_peers = vec![AdvertisedPeer::from(bootstrap_config.bootstrap_list[0].0)];
         let version: Version = Version::from_str("TEST.1.10").unwrap();
 
         client.handshake(version).await.unwrap();
@@ -311,7 +321,7 @@ async fn test_binders_try_double_send_client_works() {
             .await
             .unwrap();
 
-        let vector_peers = vec![bootstrap_config.bootstrap_list[0].0.ip()];
+        let vector_peers = vec![AdvertisedPeer::from(bootstrap_config.bootstrap_list[0].0)];
         let message = client.next().await.unwrap();
         match message {
             BootstrapServerMessage::BootstrapPeers { peers } => {
@@ -324,3 +334,124 @@ async fn test_binders_try_double_send_client_works() {
     server_thread.await.unwrap();
     client_thread.await.unwrap();
 }
+
+/// The client will refuse the messages that are not signed by the expected server
+#[tokio::test]
+#[serial]
+async fn test_binders_reject_unknown_server_key() {
+    let (bootstrap_config, _): &(BootstrapConfig, KeyPair) = &BOOTSTRAP_CONFIG_KEYPAIR;
+    let (client, server) = duplex(1000000);
+    let mut server = BootstrapServerBinder::new(
+        server,
+        KeyPair::generate(),
+        f64::INFINITY,
+        Limiter::new(f64::INFINITY),
+        MAX_BOOTSTRAP_MESSAGE_SIZE,
+        THREAD_COUNT,
+        MAX_DATASTORE_KEY_LENGTH,
+        BOOTSTRAP_RANDOMNESS_SIZE_BYTES,
+        CONSENSUS_BOOTSTRAP_PART_SIZE,
+    );
+    let mut client =
+        BootstrapClientBinder::test_default(client, bootstrap_config.bootstrap_list[0].1);
+
+    let server_thread = tokio::spawn(async move {
+        let version: Version = Version::from_str("TEST.1.10").unwrap();
+        server.handshake(version).await.unwrap();
+        server
+            .send(BootstrapServerMessage::BootstrapPeers {
+                peers: BootstrapPeers(vec![AdvertisedPeer::from(
+                    bootstrap_config.bootstrap_list[0].0,
+                )]),
+            })
+            .await
+            .unwrap();
+    });
+
+    let client_thread = tokio::spawn(async move {
+        let version: Version = Version::from_str("TEST.1.10").unwrap();
+        client.handshake(version).await.unwrap();
+        match client.next().await {
+            Err(BootstrapError::MassaSignatureError(_)) => {}
+            other => panic!("Expected a signature error, got {:?}", other),
+        }
+    });
+
+    server_thread.await.unwrap();
+    client_thread.await.unwrap();
+}
+
+/// Two sessions sharing a total limit of `TOTAL_LIMIT` bytes per second, each one also limited to `TOTAL_LIMIT`,
+/// send `PART_SIZE` bytes each: the transfer takes about twice the time of a single session.
+#[tokio::test]
+#[serial]
+async fn test_total_limiter_shared_by_sessions() {
+    const TOTAL_LIMIT: f64 = 100_000.0;
+    const PART_SIZE: usize = 100_000;
+    let (bootstrap_config, server_keypair): &(BootstrapConfig, KeyPair) = &BOOTSTRAP_CONFIG_KEYPAIR;
+    let total_limiter = Limiter::new(TOTAL_LIMIT);
+
+    let start = Instant::now();
+    let mut sessions = Vec::new();
+    for _ in 0..2 {
+        let (client, server) = duplex(1000000);
+        let mut server = BootstrapServerBinder::new(
+            server,
+            server_keypair.clone(),
+            TOTAL_LIMIT,
+            total_limiter.clone(),
+            MAX_BOOTSTRAP_MESSAGE_SIZE,
+            THREAD_COUNT,
+            MAX_DATASTORE_KEY_LENGTH,
+            BOOTSTRAP_RANDOMNESS_SIZE_BYTES,
+            CONSENSUS_BOOTSTRAP_PART_SIZE,
+        );
+        let mut client =
+            BootstrapClientBinder::test_default(client, bootstrap_config.bootstrap_list[0].1);
+
+        sessions.push(tokio::spawn(async move {
+            let version: Version = Version::from_str("TEST.1.10").unwrap();
+            server.handshake(version).await.unwrap();
+            server
+                .send(BootstrapServerMessage::BootstrapPart {
+                    slot: Slot::new(0, 0),
+                    ledger_part: vec![0; PART_SIZE],
+                    async_pool_part: BTreeMap::new(),
+                    pos_cycle_part: None,
+                    pos_credits_part: DeferredCredits::default(),
+                    exec_ops_part: BTreeMap::new(),
+                    final_state_changes: Vec::new(),
+                    consensus_part: BootstrapableGraph {
+                        final_blocks: Vec::new(),
+                    },
+                    consensus_outdated_ids: PreHashSet::default(),
+                })
+                .await
+                .unwrap();
+        }));
+        sessions.push(tokio::spawn(async move {
+            let version: Version = Version::from_str("TEST.1.10").unwrap();
+            client.handshake(version).await.unwrap();
+            match client.next().await.unwrap() {
+                BootstrapServerMessage::BootstrapPart { ledger_part, .. } => {
+                    assert_eq!(ledger_part.len(), PART_SIZE);
+                }
+                other => panic!(
+                    "Bad message receive: Expected a bootstrap part, got {:?}",
+                    other
+                ),
+            }
+        }));
+    }
+    for session in sessions {
+        session.await.unwrap();
+    }
+
+    // a limit applied per session only would let both parts through in about one second
+    let elapsed = start.elapsed();
+    assert!(
+        elapsed >= Duration::from_millis(1500),
+        "the sessions were not limited together: {:?}",
+        elapsed
+    );
+}
@@ -3,4 +3,6 @@
 mod binders;
 pub mod mock_establisher;
 mod scenarios;
+mod server_cache;
+mod server_stats;
 pub mod tools;
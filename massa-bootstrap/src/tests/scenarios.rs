@@ -7,15 +7,22 @@ use super::{
         get_random_ledger_changes, wait_network_command,
     },
 };
+use crate::client::{import_shutdown_snapshot, stream_final_state_and_consensus};
+use crate::error::BootstrapError;
+use crate::messages::{BootstrapClientMessage, BootstrapServerMessage};
+use crate::server::{authenticate_client, read_final_state_part, stream_bootstrap_information};
+use crate::server_cache::{BootstrapPartCache, FinalStateCursors};
 use crate::tests::tools::{
     get_random_async_pool_changes, get_random_executed_ops_changes, get_random_pos_changes,
 };
-use crate::BootstrapConfig;
+use crate::{client_binder::BootstrapClientBinder, server_binder::BootstrapServerBinder};
 use crate::{
-    get_state, start_bootstrap_server,
+    export_snapshot, get_state, import_snapshot, start_bootstrap_server,
     tests::tools::{assert_eq_bootstrap_graph, get_bootstrap_config},
 };
-use massa_async_pool::AsyncPoolConfig;
+use crate::{BootstrapConfig, GlobalBootstrapState};
+use async_speed_limit::Limiter;
+use massa_async_pool::{AsyncPool, AsyncPoolConfig};
 use massa_consensus_exports::{
     bootstrapable_graph::BootstrapableGraph,
     test_exports::{MockConsensusController, MockConsensusControllerMessage},
@@ -27,23 +34,33 @@ use massa_final_state::{
 };
 use massa_hash::{Hash, HASH_SIZE_BYTES};
 use massa_ledger_exports::LedgerConfig;
-use massa_models::{address::Address, slot::Slot, streaming_step::StreamingStep, version::Version};
+use massa_models::{
+    address::Address, api::SyncPhase, slot::Slot, streaming_step::StreamingStep, version::Version,
+};
 use massa_models::{
     config::{
         MAX_ASYNC_MESSAGE_DATA, MAX_ASYNC_POOL_LENGTH, MAX_DATASTORE_KEY_LENGTH, POS_SAVED_CYCLES,
     },
     prehash::PreHashSet,
+    resource_status::ResourceStatus,
+    sync_progress::SyncProgressStatus,
 };
 use massa_network_exports::{NetworkCommand, NetworkCommandSender};
 use massa_pos_exports::{
     test_exports::assert_eq_pos_selection, PoSConfig, PoSFinalState, SelectorConfig,
+    SelectorManager,
 };
 use massa_pos_worker::start_selector_worker;
 use massa_signature::KeyPair;
 use massa_time::MassaTime;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use serial_test::serial;
-use std::{path::PathBuf, str::FromStr, sync::Arc, time::Duration};
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
 use tempfile::TempDir;
 use tokio::sync::mpsc;
 
@@ -152,6 +169,7 @@ async fn test_bootstrap_server() {
         bootstrap_establisher,
         keypair.clone(),
         Version::from_str("TEST.1.10").unwrap(),
+        ResourceStatus::default(),
     )
     .await
     .unwrap()
@@ -159,6 +177,8 @@ async fn test_bootstrap_server() {
 
     // launch the get_state process
     let (remote_establisher, mut remote_interface) = mock_establisher::new();
+    let sync_progress = SyncProgressStatus::default();
+    let sync_progress_clone = sync_progress.clone();
     let get_state_h = tokio::spawn(async move {
         get_state(
             bootstrap_config,
@@ -167,6 +187,7 @@ async fn test_bootstrap_server() {
             Version::from_str("TEST.1.10").unwrap(),
             MassaTime::now().unwrap().saturating_sub(1000.into()),
             None,
+            sync_progress_clone,
         )
         .await
         .unwrap()
@@ -323,6 +344,14 @@ async fn test_bootstrap_server() {
     assert_eq_final_state(&final_state_server.read(), &final_state_client.read());
     assert_eq_final_state_hash(&final_state_server.read(), &final_state_client.read());
 
+    // check the reported bootstrap progress
+    let progress = sync_progress.get();
+    assert_eq!(progress.phase, SyncPhase::Bootstrapping);
+    assert_eq!(
+        progress.local_final_slot,
+        Some(final_state_client.read().slot)
+    );
+
     // compute initial draws
     final_state_server.write().compute_initial_draws().unwrap();
     final_state_client.write().compute_initial_draws().unwrap();
@@ -352,3 +381,782 @@ async fn test_bootstrap_server() {
     server_selector_manager.stop();
     client_selector_manager.stop();
 }
+
+#[test]
+#[serial]
+fn test_snapshot_export_import() {
+    let thread_count = 2;
+    let periods_per_cycle = 2;
+    let (bootstrap_config, _): &(BootstrapConfig, KeyPair) = &BOOTSTRAP_CONFIG_KEYPAIR;
+    let rolls_path = PathBuf::from_str("../massa-node/base_config/initial_rolls.json").unwrap();
+    let genesis_address = Address::from_public_key(&KeyPair::generate().get_public_key());
+    let version = Version::from_str("TEST.1.10").unwrap();
+
+    let (consensus_controller, mut consensus_event_receiver) =
+        MockConsensusController::new_with_receiver();
+
+    // setup final state local config
+    let temp_dir = TempDir::new().unwrap();
+    let final_state_local_config = FinalStateConfig {
+        ledger_config: LedgerConfig {
+            thread_count,
+            initial_ledger_path: "".into(),
+            disk_ledger_path: temp_dir.path().to_path_buf(),
+            max_key_length: MAX_DATASTORE_KEY_LENGTH,
+            max_ledger_part_size: 100_000,
+        },
+        async_pool_config: AsyncPoolConfig {
+            thread_count,
+            max_length: MAX_ASYNC_POOL_LENGTH,
+            max_async_message_data: MAX_ASYNC_MESSAGE_DATA,
+            bootstrap_part_size: 100,
+        },
+        pos_config: PoSConfig {
+            periods_per_cycle,
+            thread_count,
+            cycle_history_length: POS_SAVED_CYCLES,
+            credits_bootstrap_part_size: 100,
+        },
+        executed_ops_config: ExecutedOpsConfig {
+            thread_count,
+            bootstrap_part_size: 10,
+        },
+        final_history_length: 100,
+        initial_seed_string: "".into(),
+        initial_rolls_path: "".into(),
+        thread_count,
+        periods_per_cycle,
+    };
+
+    // start proof-of-stake selectors
+    let selector_local_config = SelectorConfig {
+        thread_count,
+        periods_per_cycle,
+        genesis_address,
+        ..Default::default()
+    };
+    let (mut exporter_selector_manager, exporter_selector_controller) =
+        start_selector_worker(selector_local_config.clone())
+            .expect("could not start exporter selector controller");
+    let (mut importer_selector_manager, importer_selector_controller) =
+        start_selector_worker(selector_local_config)
+            .expect("could not start importer selector controller");
+
+    // setup final states
+    let final_state_exporter = Arc::new(RwLock::new(get_random_final_state_bootstrap(
+        PoSFinalState::new(
+            final_state_local_config.pos_config.clone(),
+            "",
+            &rolls_path,
+            exporter_selector_controller,
+            Hash::from_bytes(&[0; HASH_SIZE_BYTES]),
+        )
+        .unwrap(),
+        final_state_local_config.clone(),
+    )));
+    let final_state_importer = Arc::new(RwLock::new(FinalState::create_final_state(
+        PoSFinalState::new(
+            final_state_local_config.pos_config.clone(),
+            "",
+            &rolls_path,
+            importer_selector_controller,
+            Hash::from_bytes(&[0; HASH_SIZE_BYTES]),
+        )
+        .unwrap(),
+        final_state_local_config,
+    )));
+
+    // answer the consensus part request with the whole graph
+    let sent_graph = get_boot_state();
+    let sent_graph_clone = sent_graph.clone();
+    std::thread::spawn(move || {
+        consensus_event_receiver.wait_command(MassaTime::from_millis(10_000), |cmd| match &cmd {
+            MockConsensusControllerMessage::GetBootstrapableGraph { response_tx, .. } => {
+                response_tx
+                    .send(Ok((
+                        sent_graph_clone.clone(),
+                        PreHashSet::default(),
+                        StreamingStep::Finished(None),
+                    )))
+                    .unwrap();
+                Some(())
+            }
+            _ => None,
+        });
+    });
+
+    // export then import the snapshot
+    let snapshot_dir = TempDir::new().unwrap();
+    let snapshot_path = snapshot_dir.path().join("snapshot.dat");
+    let slot = export_snapshot(
+        &snapshot_path,
+        &final_state_exporter,
+        consensus_controller.as_ref(),
+        version,
+    )
+    .expect("could not export snapshot");
+    assert_eq!(slot, final_state_exporter.read().slot);
+    let import_res = import_snapshot(
+        bootstrap_config,
+        &snapshot_path,
+        final_state_importer.clone(),
+        version,
+    )
+    .expect("could not import snapshot");
+
+    // check final states and graphs
+    assert_eq_final_state(&final_state_exporter.read(), &final_state_importer.read());
+    assert_eq_final_state_hash(&final_state_exporter.read(), &final_state_importer.read());
+    assert_eq_bootstrap_graph(&sent_graph, &import_res.graph.unwrap());
+
+    // check that a corrupted snapshot is rejected
+    let mut snapshot_bytes = std::fs::read(&snapshot_path).unwrap();
+    let last = snapshot_bytes.len() - 1;
+    snapshot_bytes[last] ^= 1;
+    std::fs::write(&snapshot_path, snapshot_bytes).unwrap();
+    assert!(matches!(
+        import_snapshot(
+            bootstrap_config,
+            &snapshot_path,
+            final_state_importer,
+            version
+        ),
+        Err(BootstrapError::SnapshotError(_))
+    ));
+
+    // stop selector controllers
+    exporter_selector_manager.stop();
+    importer_selector_manager.stop();
+}
+
+/// Handshake a server accepting the `accepted_tokens` with a client presenting `token`,
+/// and return the result of the authentication on the server side
+async fn authenticate_with_token(
+    accepted_tokens: &[&str],
+    token: &str,
+) -> Result<(), BootstrapError> {
+    let (bootstrap_config, server_keypair): &(BootstrapConfig, KeyPair) = &BOOTSTRAP_CONFIG_KEYPAIR;
+    let bootstrap_config = BootstrapConfig {
+        bootstrap_tokens: accepted_tokens
+            .iter()
+            .map(|token| token.to_string())
+            .collect(),
+        ..bootstrap_config.clone()
+    };
+    let version = Version::from_str("TEST.1.10").unwrap();
+    let (client_duplex, server_duplex) = tokio::io::duplex(1000000);
+    let mut server = BootstrapServerBinder::new(
+        server_duplex,
+        server_keypair.clone(),
+        f64::INFINITY,
+        Limiter::new(f64::INFINITY),
+        bootstrap_config.max_bootstrap_message_size,
+        bootstrap_config.thread_count,
+        bootstrap_config.max_datastore_key_length,
+        bootstrap_config.randomness_size_bytes,
+        bootstrap_config.consensus_bootstrap_part_size,
+    );
+    let mut client =
+        BootstrapClientBinder::test_default(client_duplex, bootstrap_config.bootstrap_list[0].1);
+    let token = token.to_string();
+    let client_thread = tokio::spawn(async move {
+        client.handshake(version).await.unwrap();
+        client
+            .send(&BootstrapClientMessage::Authenticate { token })
+            .await
+            .unwrap();
+        client
+    });
+    let authentication = authenticate_client(&bootstrap_config, &mut server, version).await;
+    client_thread.await.unwrap();
+    authentication
+}
+
+/// A restricted bootstrap server accepts a client presenting one of its tokens
+#[tokio::test]
+#[serial]
+async fn test_bootstrap_token_accepted() {
+    authenticate_with_token(&["first token", "second token"], "second token")
+        .await
+        .expect("a valid token was rejected");
+}
+
+/// A restricted bootstrap server rejects a client presenting an unknown token, even a prefix of a valid one
+#[tokio::test]
+#[serial]
+async fn test_bootstrap_token_rejected() {
+    for token in ["other token", "first", ""] {
+        assert!(
+            matches!(
+                authenticate_with_token(&["first token"], token).await,
+                Err(BootstrapError::GeneralError(_))
+            ),
+            "the token {:?} was accepted",
+            token
+        );
+    }
+}
+
+/// Final state config of the bootstrap tests below, with a ledger on disk at `ledger_path`
+fn get_test_final_state_config(ledger_path: PathBuf) -> FinalStateConfig {
+    let thread_count = 2;
+    let periods_per_cycle = 2;
+    FinalStateConfig {
+        ledger_config: LedgerConfig {
+            thread_count,
+            initial_ledger_path: "".into(),
+            disk_ledger_path: ledger_path,
+            max_key_length: MAX_DATASTORE_KEY_LENGTH,
+            max_ledger_part_size: 100_000,
+        },
+        async_pool_config: AsyncPoolConfig {
+            thread_count,
+            max_length: MAX_ASYNC_POOL_LENGTH,
+            max_async_message_data: MAX_ASYNC_MESSAGE_DATA,
+            bootstrap_part_size: 100,
+        },
+        pos_config: PoSConfig {
+            periods_per_cycle,
+            thread_count,
+            cycle_history_length: POS_SAVED_CYCLES,
+            credits_bootstrap_part_size: 100,
+        },
+        executed_ops_config: ExecutedOpsConfig {
+            thread_count,
+            bootstrap_part_size: 10,
+        },
+        final_history_length: 100,
+        initial_seed_string: "".into(),
+        initial_rolls_path: "".into(),
+        thread_count,
+        periods_per_cycle,
+    }
+}
+
+/// Open a bootstrap session between a client and a server, both handshaken
+async fn open_bootstrap_session() -> (BootstrapClientBinder, BootstrapServerBinder) {
+    let (bootstrap_config, server_keypair): &(BootstrapConfig, KeyPair) = &BOOTSTRAP_CONFIG_KEYPAIR;
+    let version = Version::from_str("TEST.1.10").unwrap();
+    let (client_duplex, server_duplex) = tokio::io::duplex(1000000);
+    let mut server = BootstrapServerBinder::new(
+        server_duplex,
+        server_keypair.clone(),
+        f64::INFINITY,
+        Limiter::new(f64::INFINITY),
+        bootstrap_config.max_bootstrap_message_size,
+        bootstrap_config.thread_count,
+        bootstrap_config.max_datastore_key_length,
+        bootstrap_config.randomness_size_bytes,
+        bootstrap_config.consensus_bootstrap_part_size,
+    );
+    let mut client =
+        BootstrapClientBinder::test_default(client_duplex, bootstrap_config.bootstrap_list[0].1);
+    let (client_handshake, server_handshake) =
+        tokio::join!(client.handshake(version), server.handshake(version));
+    client_handshake.unwrap();
+    server_handshake.unwrap();
+    (client, server)
+}
+
+/// Serve the first part asked by the client from `final_state`, then drop the connection
+async fn serve_one_part_and_disconnect(
+    mut server: BootstrapServerBinder,
+    final_state: Arc<RwLock<FinalState>>,
+) {
+    let BootstrapClientMessage::AskBootstrapPart {
+        last_ledger_step,
+        last_pool_step,
+        last_cycle_step,
+        last_credits_step,
+        last_ops_step,
+        ..
+    } = server.next().await.unwrap() else {
+        panic!("expected the client to ask for a bootstrap part");
+    };
+    let cursors = FinalStateCursors {
+        ledger: last_ledger_step,
+        pool: last_pool_step,
+        cycle: last_cycle_step,
+        credits: last_credits_step,
+        ops: last_ops_step,
+    };
+    let (slot, part) = {
+        let final_state_read = final_state.read();
+        (
+            final_state_read.slot,
+            read_final_state_part(&final_state_read, &cursors).unwrap(),
+        )
+    };
+    server
+        .send(BootstrapServerMessage::BootstrapPart {
+            slot,
+            ledger_part: part.ledger_part,
+            async_pool_part: part.async_pool_part,
+            pos_cycle_part: part.pos_cycle_part,
+            pos_credits_part: part.pos_credits_part,
+            exec_ops_part: part.exec_ops_part,
+            final_state_changes: Vec::new(),
+            consensus_part: BootstrapableGraph {
+                final_blocks: Vec::new(),
+            },
+            consensus_outdated_ids: PreHashSet::default(),
+        })
+        .await
+        .unwrap();
+}
+
+/// Serve the parts asked by the client from `final_state` as the bootstrap server does,
+/// with a consensus having no final block to stream
+async fn serve_parts(mut server: BootstrapServerBinder, final_state: Arc<RwLock<FinalState>>) {
+    let (consensus_controller, mut consensus_event_receiver) =
+        MockConsensusController::new_with_receiver();
+    std::thread::spawn(move || {
+        while let Some(()) =
+            consensus_event_receiver.wait_command(MassaTime::from_millis(20_000), |cmd| match cmd {
+                MockConsensusControllerMessage::GetBootstrapableGraph { response_tx, .. } => {
+                    response_tx
+                        .send(Ok((
+                            BootstrapableGraph {
+                                final_blocks: Vec::new(),
+                            },
+                            PreHashSet::default(),
+                            StreamingStep::Finished(None),
+                        )))
+                        .unwrap();
+                    Some(())
+                }
+                _ => None,
+            })
+        {}
+    });
+    let BootstrapClientMessage::AskBootstrapPart {
+        last_slot,
+        last_ledger_step,
+        last_pool_step,
+        last_cycle_step,
+        last_credits_step,
+        last_ops_step,
+        last_consensus_step,
+    } = server.next().await.unwrap() else {
+        panic!("expected the client to ask for a bootstrap part");
+    };
+    stream_bootstrap_information(
+        &mut server,
+        final_state,
+        Arc::new(Mutex::new(BootstrapPartCache::new(Duration::ZERO, 0))),
+        consensus_controller,
+        last_slot,
+        last_ledger_step,
+        last_pool_step,
+        last_cycle_step,
+        last_credits_step,
+        last_ops_step,
+        last_consensus_step,
+        Duration::from_secs(10),
+    )
+    .await
+    .unwrap();
+}
+
+/// First message of a bootstrap stream starting from scratch
+fn ask_first_bootstrap_part() -> BootstrapClientMessage {
+    BootstrapClientMessage::AskBootstrapPart {
+        last_slot: None,
+        last_ledger_step: StreamingStep::Started,
+        last_pool_step: StreamingStep::Started,
+        last_cycle_step: StreamingStep::Started,
+        last_credits_step: StreamingStep::Started,
+        last_ops_step: StreamingStep::Started,
+        last_consensus_step: StreamingStep::Started,
+    }
+}
+
+/// After a disconnection, the client resumes the stream from the cursors of the last part it applied
+#[tokio::test]
+#[serial]
+async fn test_bootstrap_resume_after_disconnect() {
+    let (bootstrap_config, _): &(BootstrapConfig, KeyPair) = &BOOTSTRAP_CONFIG_KEYPAIR;
+    let rolls_path = PathBuf::from_str("../massa-node/base_config/initial_rolls.json").unwrap();
+    let temp_dir = TempDir::new().unwrap();
+    let final_state_config = get_test_final_state_config(temp_dir.path().to_path_buf());
+    let (mut selector_manager, selector_controller) = start_selector_worker(SelectorConfig {
+        thread_count: final_state_config.thread_count,
+        periods_per_cycle: final_state_config.periods_per_cycle,
+        genesis_address: Address::from_public_key(&KeyPair::generate().get_public_key()),
+        ..Default::default()
+    })
+    .expect("could not start selector controller");
+    let new_pos_state = || {
+        PoSFinalState::new(
+            final_state_config.pos_config.clone(),
+            "",
+            &rolls_path,
+            selector_controller.clone(),
+            Hash::from_bytes(&[0; HASH_SIZE_BYTES]),
+        )
+        .unwrap()
+    };
+    let final_state_server = Arc::new(RwLock::new(get_random_final_state_bootstrap(
+        new_pos_state(),
+        final_state_config.clone(),
+    )));
+    let final_state_client = Arc::new(RwLock::new(FinalState::create_final_state(
+        new_pos_state(),
+        final_state_config.clone(),
+    )));
+    let mut global_bootstrap_state = GlobalBootstrapState::new(final_state_client.clone());
+    let mut next_bootstrap_message = ask_first_bootstrap_part();
+    let sync_progress = SyncProgressStatus::default();
+
+    // the connection is lost after the first part
+    let (mut client, server) = open_bootstrap_session().await;
+    let (stream_result, ()) = tokio::join!(
+        stream_final_state_and_consensus(
+            bootstrap_config,
+            &mut client,
+            &mut next_bootstrap_message,
+            &mut global_bootstrap_state,
+            &sync_progress,
+        ),
+        serve_one_part_and_disconnect(server, final_state_server.clone())
+    );
+    assert!(stream_result.is_err());
+    match &next_bootstrap_message {
+        BootstrapClientMessage::AskBootstrapPart {
+            last_slot,
+            last_ledger_step,
+            last_ops_step,
+            ..
+        } => {
+            assert_eq!(*last_slot, Some(final_state_server.read().slot));
+            assert!(matches!(last_ledger_step, StreamingStep::Ongoing(_)));
+            assert!(matches!(last_ops_step, StreamingStep::Ongoing(_)));
+        }
+        msg => panic!("unexpected next bootstrap message {:?}", msg),
+    }
+
+    // the stream is resumed from these cursors with another session
+    let (mut client, server) = open_bootstrap_session().await;
+    let (stream_result, ()) = tokio::join!(
+        stream_final_state_and_consensus(
+            bootstrap_config,
+            &mut client,
+            &mut next_bootstrap_message,
+            &mut global_bootstrap_state,
+            &sync_progress,
+        ),
+        serve_parts(server, final_state_server.clone())
+    );
+    stream_result.unwrap();
+    assert!(matches!(
+        next_bootstrap_message,
+        BootstrapClientMessage::AskBootstrapPeers
+    ));
+    assert_eq_final_state(&final_state_server.read(), &final_state_client.read());
+    assert_eq_final_state_hash(&final_state_server.read(), &final_state_client.read());
+
+    selector_manager.stop();
+}
+
+/// When the slot of its cursors is too old for the server, the client drops what it bootstrapped
+/// and starts over
+#[tokio::test]
+#[serial]
+async fn test_bootstrap_restart_after_slot_too_old() {
+    let (bootstrap_config, _): &(BootstrapConfig, KeyPair) = &BOOTSTRAP_CONFIG_KEYPAIR;
+    let rolls_path = PathBuf::from_str("../massa-node/base_config/initial_rolls.json").unwrap();
+    let temp_dir = TempDir::new().unwrap();
+    let final_state_config = get_test_final_state_config(temp_dir.path().to_path_buf());
+    let (mut selector_manager, selector_controller) = start_selector_worker(SelectorConfig {
+        thread_count: final_state_config.thread_count,
+        periods_per_cycle: final_state_config.periods_per_cycle,
+        genesis_address: Address::from_public_key(&KeyPair::generate().get_public_key()),
+        ..Default::default()
+    })
+    .expect("could not start selector controller");
+    let new_pos_state = || {
+        PoSFinalState::new(
+            final_state_config.pos_config.clone(),
+            "",
+            &rolls_path,
+            selector_controller.clone(),
+            Hash::from_bytes(&[0; HASH_SIZE_BYTES]),
+        )
+        .unwrap()
+    };
+    // a first server whose final slot the second one no longer has changes for
+    let final_state_old_server = Arc::new(RwLock::new(get_random_final_state_bootstrap(
+        new_pos_state(),
+        final_state_config.clone(),
+    )));
+    let final_state_server = Arc::new(RwLock::new(get_random_final_state_bootstrap(
+        new_pos_state(),
+        final_state_config.clone(),
+    )));
+    {
+        let mut final_state_server_write = final_state_server.write();
+        final_state_server_write.slot = Slot::new(3, 0);
+        final_state_server_write
+            .changes_history
+            .push_back((Slot::new(3, 0), StateChanges::default()));
+    }
+    let final_state_client = Arc::new(RwLock::new(FinalState::create_final_state(
+        new_pos_state(),
+        final_state_config.clone(),
+    )));
+    let mut global_bootstrap_state = GlobalBootstrapState::new(final_state_client.clone());
+    let mut next_bootstrap_message = ask_first_bootstrap_part();
+    let sync_progress = SyncProgressStatus::default();
+
+    // a first part is applied from the first server
+    let (mut client, server) = open_bootstrap_session().await;
+    let (stream_result, ()) = tokio::join!(
+        stream_final_state_and_consensus(
+            bootstrap_config,
+            &mut client,
+            &mut next_bootstrap_message,
+            &mut global_bootstrap_state,
+            &sync_progress,
+        ),
+        serve_one_part_and_disconnect(server, final_state_old_server.clone())
+    );
+    assert!(stream_result.is_err());
+    assert!(!final_state_client
+        .read()
+        .ledger
+        .get_every_address()
+        .is_empty());
+
+    // the second server answers that the slot of the cursors is too old
+    let (mut client, server) = open_bootstrap_session().await;
+    let (stream_result, ()) = tokio::join!(
+        stream_final_state_and_consensus(
+            bootstrap_config,
+            &mut client,
+            &mut next_bootstrap_message,
+            &mut global_bootstrap_state,
+            &sync_progress,
+        ),
+        serve_parts(server, final_state_server.clone())
+    );
+    assert!(stream_result.is_err());
+    assert!(matches!(
+        next_bootstrap_message,
+        BootstrapClientMessage::AskBootstrapPart {
+            last_slot: None,
+            last_ledger_step: StreamingStep::Started,
+            last_pool_step: StreamingStep::Started,
+            last_cycle_step: StreamingStep::Started,
+            last_credits_step: StreamingStep::Started,
+            last_ops_step: StreamingStep::Started,
+            last_consensus_step: StreamingStep::Started,
+        }
+    ));
+    assert!(global_bootstrap_state.graph.is_none());
+    {
+        let final_state_client_read = final_state_client.read();
+        assert!(final_state_client_read
+            .ledger
+            .get_every_address()
+            .is_empty());
+        assert_eq!(
+            final_state_client_read.async_pool.hash,
+            AsyncPool::new(final_state_config.async_pool_config.clone()).hash
+        );
+        assert!(final_state_client_read.pos_state.cycle_history.is_empty());
+        assert!(final_state_client_read.executed_ops.ops.is_empty());
+    }
+
+    // the final state is bootstrapped again from scratch
+    let (mut client, server) = open_bootstrap_session().await;
+    let (stream_result, ()) = tokio::join!(
+        stream_final_state_and_consensus(
+            bootstrap_config,
+            &mut client,
+            &mut next_bootstrap_message,
+            &mut global_bootstrap_state,
+            &sync_progress,
+        ),
+        serve_parts(server, final_state_server.clone())
+    );
+    stream_result.unwrap();
+    assert_eq_final_state(&final_state_server.read(), &final_state_client.read());
+    assert_eq_final_state_hash(&final_state_server.read(), &final_state_client.read());
+
+    selector_manager.stop();
+}
+
+/// Final states of the shutdown snapshot tests: the exported one is random, the imported one is empty
+struct ShutdownSnapshotTest {
+    final_state_config: FinalStateConfig,
+    exported: Arc<RwLock<FinalState>>,
+    imported: Arc<RwLock<FinalState>>,
+    selector_manager: Box<dyn SelectorManager>,
+    _ledger_dir: TempDir,
+}
+
+impl ShutdownSnapshotTest {
+    fn new() -> Self {
+        let rolls_path = PathBuf::from_str("../massa-node/base_config/initial_rolls.json").unwrap();
+        let ledger_dir = TempDir::new().unwrap();
+        let final_state_config = get_test_final_state_config(ledger_dir.path().to_path_buf());
+        let (selector_manager, selector_controller) = start_selector_worker(SelectorConfig {
+            thread_count: final_state_config.thread_count,
+            periods_per_cycle: final_state_config.periods_per_cycle,
+            genesis_address: Address::from_public_key(&KeyPair::generate().get_public_key()),
+            ..Default::default()
+        })
+        .expect("could not start selector controller");
+        let new_pos_state = || {
+            PoSFinalState::new(
+                final_state_config.pos_config.clone(),
+                "",
+                &rolls_path,
+                selector_controller.clone(),
+                Hash::from_bytes(&[0; HASH_SIZE_BYTES]),
+            )
+            .unwrap()
+        };
+        ShutdownSnapshotTest {
+            exported: Arc::new(RwLock::new(get_random_final_state_bootstrap(
+                new_pos_state(),
+                final_state_config.clone(),
+            ))),
+            imported: Arc::new(RwLock::new(FinalState::create_final_state(
+                new_pos_state(),
+                final_state_config.clone(),
+            ))),
+            final_state_config,
+            selector_manager,
+            _ledger_dir: ledger_dir,
+        }
+    }
+
+    /// Save the exported final state and `graph` at `snapshot_path`, as the node does at shutdown
+    fn save(&self, snapshot_path: &Path, graph: BootstrapableGraph) {
+        let (consensus_controller, mut consensus_event_receiver) =
+            MockConsensusController::new_with_receiver();
+        std::thread::spawn(move || {
+            consensus_event_receiver.wait_command(
+                MassaTime::from_millis(10_000),
+                |cmd| match cmd {
+                    MockConsensusControllerMessage::GetBootstrapableGraph {
+                        response_tx, ..
+                    } => {
+                        response_tx
+                            .send(Ok((
+                                graph.clone(),
+                                PreHashSet::default(),
+                                StreamingStep::Finished(None),
+                            )))
+                            .unwrap();
+                        Some(())
+                    }
+                    _ => None,
+                },
+            );
+        });
+        export_snapshot(
+            snapshot_path,
+            &self.exported,
+            consensus_controller.as_ref(),
+            Version::from_str("TEST.1.10").unwrap(),
+        )
+        .expect("could not export snapshot");
+    }
+
+    /// Import the snapshot at `snapshot_path`, as the node does at startup
+    fn import(
+        &self,
+        bootstrap_config: &BootstrapConfig,
+        snapshot_path: &Path,
+    ) -> Option<GlobalBootstrapState> {
+        import_shutdown_snapshot(
+            bootstrap_config,
+            snapshot_path,
+            self.imported.clone(),
+            Version::from_str("TEST.1.10").unwrap(),
+        )
+        .expect("could not import the shutdown snapshot")
+    }
+
+    /// Check that nothing was imported
+    fn assert_nothing_imported(&self) {
+        let imported = self.imported.read();
+        assert!(imported.ledger.get_every_address().is_empty());
+        assert_eq!(
+            imported.async_pool.hash,
+            AsyncPool::new(self.final_state_config.async_pool_config.clone()).hash
+        );
+        assert!(imported.pos_state.cycle_history.is_empty());
+        assert!(imported.executed_ops.ops.is_empty());
+    }
+}
+
+/// The snapshot saved at shutdown is imported at the next startup, then removed
+#[test]
+#[serial]
+fn test_shutdown_snapshot_round_trip() {
+    let (bootstrap_config, _): &(BootstrapConfig, KeyPair) = &BOOTSTRAP_CONFIG_KEYPAIR;
+    let mut test = ShutdownSnapshotTest::new();
+    let snapshot_dir = TempDir::new().unwrap();
+    let snapshot_path = snapshot_dir.path().join("shutdown_snapshot.dat");
+    let graph = get_boot_state();
+
+    test.save(&snapshot_path, graph.clone());
+    let global_bootstrap_state = test
+        .import(bootstrap_config, &snapshot_path)
+        .expect("the shutdown snapshot was not imported");
+
+    assert_eq_final_state(&test.exported.read(), &test.imported.read());
+    assert_eq_final_state_hash(&test.exported.read(), &test.imported.read());
+    assert_eq_bootstrap_graph(&graph, &global_bootstrap_state.graph.unwrap());
+    assert!(!snapshot_path.exists());
+
+    test.selector_manager.stop();
+}
+
+/// A snapshot older than `shutdown_snapshot_max_age` is not imported, and removed
+#[test]
+#[serial]
+fn test_shutdown_snapshot_too_old() {
+    let (bootstrap_config, _): &(BootstrapConfig, KeyPair) = &BOOTSTRAP_CONFIG_KEYPAIR;
+    let bootstrap_config = BootstrapConfig {
+        shutdown_snapshot_max_age: MassaTime::from_millis(1),
+        ..bootstrap_config.clone()
+    };
+    let mut test = ShutdownSnapshotTest::new();
+    let snapshot_dir = TempDir::new().unwrap();
+    let snapshot_path = snapshot_dir.path().join("shutdown_snapshot.dat");
+
+    test.save(&snapshot_path, get_boot_state());
+    std::thread::sleep(Duration::from_millis(50));
+
+    assert!(test.import(&bootstrap_config, &snapshot_path).is_none());
+    test.assert_nothing_imported();
+    assert!(!snapshot_path.exists());
+
+    test.selector_manager.stop();
+}
+
+/// A corrupted snapshot is not imported, and removed
+#[test]
+#[serial]
+fn test_shutdown_snapshot_corrupted() {
+    let (bootstrap_config, _): &(BootstrapConfig, KeyPair) = &BOOTSTRAP_CONFIG_KEYPAIR;
+    let mut test = ShutdownSnapshotTest::new();
+    let snapshot_dir = TempDir::new().unwrap();
+    let snapshot_path = snapshot_dir.path().join("shutdown_snapshot.dat");
+
+    test.save(&snapshot_path, get_boot_state());
+    let mut snapshot_bytes = std::fs::read(&snapshot_path).unwrap();
+    let middle = snapshot_bytes.len() / 2;
+    snapshot_bytes[middle] ^= 0xff;
+    std::fs::write(&snapshot_path, snapshot_bytes).unwrap();
+
+    assert!(test.import(bootstrap_config, &snapshot_path).is_none());
+    test.assert_nothing_imported();
+    assert!(!snapshot_path.exists());
+
+    test.selector_manager.stop();
+}
@@ -0,0 +1,57 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use crate::server_cache::{BootstrapPartCache, FinalStateCursors, FinalStatePart};
+use massa_models::{slot::Slot, streaming_step::StreamingStep};
+use std::time::Duration;
+
+fn get_part(ledger_part: Vec<u8>) -> FinalStatePart {
+    FinalStatePart {
+        ledger_part: ledger_part.clone(),
+        async_pool_part: Default::default(),
+        pos_cycle_part: None,
+        pos_credits_part: Default::default(),
+        exec_ops_part: Default::default(),
+        next_cursors: FinalStateCursors {
+            ledger: StreamingStep::Ongoing(ledger_part),
+            pool: StreamingStep::Started,
+            cycle: StreamingStep::Started,
+            credits: StreamingStep::Started,
+            ops: StreamingStep::Started,
+        },
+    }
+}
+
+fn get_cursors(ledger_key: Vec<u8>) -> FinalStateCursors {
+    get_part(ledger_key).next_cursors
+}
+
+#[test]
+fn test_bootstrap_part_cache() {
+    let slot = Slot::new(10, 0);
+    let mut cache = BootstrapPartCache::new(Duration::from_secs(60), 2);
+    assert!(cache.get(slot, &get_cursors(vec![1])).is_none());
+
+    cache.insert(slot, get_cursors(vec![1]), get_part(vec![2]));
+    let part = cache.get(slot, &get_cursors(vec![1])).unwrap();
+    assert_eq!(part.ledger_part, vec![2]);
+    assert!(cache.get(slot, &get_cursors(vec![2])).is_none());
+
+    // the oldest part is replaced once the cache is full
+    cache.insert(slot, get_cursors(vec![2]), get_part(vec![3]));
+    cache.insert(slot, get_cursors(vec![3]), get_part(vec![4]));
+    assert!(cache.get(slot, &get_cursors(vec![1])).is_none());
+    assert!(cache.get(slot, &get_cursors(vec![2])).is_some());
+    assert!(cache.get(slot, &get_cursors(vec![3])).is_some());
+
+    // the parts are dropped once the final slot changes
+    let next_slot = Slot::new(10, 1);
+    assert!(cache.get(next_slot, &get_cursors(vec![3])).is_none());
+    cache.insert(slot, get_cursors(vec![3]), get_part(vec![4]));
+    assert!(cache.get(next_slot, &get_cursors(vec![3])).is_none());
+
+    // the parts are not served past their maximal age
+    let mut cache = BootstrapPartCache::new(Duration::ZERO, 2);
+    cache.insert(slot, get_cursors(vec![1]), get_part(vec![2]));
+    std::thread::sleep(Duration::from_millis(1));
+    assert!(cache.get(slot, &get_cursors(vec![1])).is_none());
+}
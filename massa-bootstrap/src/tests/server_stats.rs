@@ -0,0 +1,42 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use crate::server_stats::{BootstrapServerStats, BootstrapServerStatsDb};
+use massa_signature::KeyPair;
+use std::net::SocketAddr;
+
+#[test]
+fn test_bootstrap_server_stats() {
+    let stats_dir = tempfile::tempdir().unwrap();
+    let stats_path = stats_dir.path().join("bootstrap_server_stats.json");
+    let failing: SocketAddr = "127.0.0.1:31245".parse().unwrap();
+    let servers: Vec<_> = (0..5)
+        .map(|i| {
+            (
+                SocketAddr::new(failing.ip(), 31245 + i),
+                KeyPair::generate().get_public_key(),
+            )
+        })
+        .collect();
+
+    let mut stats = BootstrapServerStatsDb::load(stats_path.clone());
+    stats.note_failure(failing);
+    stats.note_failure(failing);
+    stats.note_success(servers[1].0);
+    for _ in 0..10 {
+        assert_eq!(stats.order_servers(&servers).last().unwrap().0, failing);
+    }
+
+    // the stats are kept between runs
+    let mut stats = BootstrapServerStatsDb::load(stats_path);
+    assert_eq!(
+        stats.get(&failing),
+        Some(&BootstrapServerStats {
+            successes: 0,
+            failures: 2,
+            consecutive_failures: 2,
+        })
+    );
+    stats.note_success(failing);
+    assert_eq!(stats.get(&failing).unwrap().consecutive_failures, 0);
+    assert_eq!(stats.order_servers(&servers).len(), servers.len());
+}
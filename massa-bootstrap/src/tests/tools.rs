@@ -41,7 +41,7 @@ use massa_models::{
     wrapped::Id,
     wrapped::WrappedContent,
 };
-use massa_network_exports::{BootstrapPeers, NetworkCommand};
+use massa_network_exports::{AdvertisedPeer, BootstrapPeers, NetworkCommand};
 use massa_pos_exports::{CycleInfo, DeferredCredits, PoSChanges, PoSFinalState, ProductionStats};
 use massa_serialization::{DeserializeError, Deserializer, Serializer};
 use massa_signature::{KeyPair, PublicKey, Signature};
@@ -289,12 +289,21 @@ pub fn get_bootstrap_config(bootstrap_public_key: PublicKey) -> BootstrapConfig
         bootstrap_blacklist_path: PathBuf::from(
             "../massa-node/base_config/bootstrap_blacklist.json",
         ),
+        bootstrap_token: None,
+        snapshot_import_path: None,
+        shutdown_snapshot_path: None,
+        shutdown_snapshot_max_age: MassaTime::from_millis(300000),
+        bootstrap_tokens: Vec::new(),
+        server_stats_path: std::env::temp_dir().join("massa_bootstrap_server_stats.json"),
         max_clock_delta: MassaTime::from_millis(1000),
         cache_duration: 10000.into(),
+        part_cache_max_age: 10000.into(),
+        part_cache_max_entries: 16,
         max_simultaneous_bootstraps: 2,
         ip_list_max_size: 10,
         per_ip_min_interval: 10000.into(),
         max_bytes_read_write: std::f64::INFINITY,
+        max_bytes_read_write_total: std::f64::INFINITY,
         max_bootstrap_message_size: MAX_BOOTSTRAP_MESSAGE_SIZE,
         max_datastore_key_length: MAX_DATASTORE_KEY_LENGTH,
         randomness_size_bytes: BOOTSTRAP_RANDOMNESS_SIZE_BYTES,
@@ -397,6 +406,7 @@ pub fn get_boot_state() -> BootstrapableGraph {
                         )
                         .unwrap(),
                     ],
+                    state_commitment: None,
                 },
                 BlockHeaderSerializer::new(),
                 &keypair,
@@ -443,8 +453,11 @@ pub fn get_boot_state() -> BootstrapableGraph {
 
 pub fn get_peers() -> BootstrapPeers {
     BootstrapPeers(vec![
-        "82.245.123.77".parse().unwrap(),
-        "82.220.123.78".parse().unwrap(),
+        AdvertisedPeer {
+            address: "82.245.123.77:31244".parse().unwrap(),
+            other_addresses: vec!["[2a01:db8::77]:31244".parse().unwrap()],
+        },
+        AdvertisedPeer::from("82.220.123.78:31244".parse::<SocketAddr>().unwrap()),
     ])
 }
 
@@ -6,8 +6,14 @@
 
 use pbkdf2::Params;
 
-/// Cipher version
-pub const VERSION: u32 = 0;
+/// Cipher version, written at the start of the encrypted data.
+///
+/// * 0: the key is derived from the password with `PBKDF2`, only supported for decryption
+/// * 1: the key is derived from the password with `Argon2id`
+pub const VERSION: u32 = 1;
+
+/// Cipher version of the data encrypted with a `PBKDF2` derived key
+pub const PBKDF2_VERSION: u32 = 0;
 
 /// AES-GCM-SIV nonce size.
 ///
@@ -15,7 +21,7 @@ pub const VERSION: u32 = 0;
 /// Nonces need not be random: a counter can be used so long as the values are never repeated under the same key.
 pub const NONCE_SIZE: usize = 12;
 
-/// `PBKDF2` salt size.
+/// Salt size, for both `PBKDF2` and `Argon2id`.
 pub const SALT_SIZE: usize = 12;
 
 /// `PBKDF2` hash parameters.
@@ -23,3 +29,15 @@ pub const HASH_PARAMS: Params = Params {
     rounds: 10_000,
     output_length: 32,
 };
+
+/// `Argon2id` memory cost, in KiB.
+pub const ARGON2_MEMORY_COST: u32 = 19_456;
+
+/// `Argon2id` number of iterations.
+pub const ARGON2_TIME_COST: u32 = 2;
+
+/// `Argon2id` degree of parallelism.
+pub const ARGON2_PARALLELISM: u32 = 1;
+
+/// Size of the AES-256 key derived from the password.
+pub const KEY_SIZE: usize = 32;
@@ -6,19 +6,19 @@
 
 use aes_gcm::aead::Aead;
 use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
-use pbkdf2::{
-    password_hash::{PasswordHasher, SaltString},
-    Pbkdf2,
-};
 
-use crate::constants::{HASH_PARAMS, NONCE_SIZE, SALT_SIZE};
+use crate::constants::{NONCE_SIZE, PBKDF2_VERSION, SALT_SIZE, VERSION};
 use crate::error::CipherError;
+use crate::kdf::{derive_key, derive_legacy_key};
 use massa_serialization::{DeserializeError, Deserializer, U32VarIntDeserializer};
 
 use std::ops::Bound::Included;
 
 /// Decryption function using AES-GCM cipher.
 ///
+/// The data encrypted by all the versions of the cipher can be decrypted,
+/// the returned version tells whether it should be encrypted again with the current one.
+///
 /// Read `lib.rs` module documentation for more information.
 pub fn decrypt(password: &str, data: &[u8]) -> Result<(u32, Vec<u8>), CipherError> {
     // parse cipher version
@@ -30,21 +30,24 @@ pub fn decrypt(password: &str, data: &[u8]) -> Result<(u32, Vec<u8>), CipherErro
             )
         })?;
 
-    // parse PBKDF2 salt
-    let salt_data = rest.get(..SALT_SIZE).ok_or_else(|| {
+    // parse salt
+    let salt = rest.get(..SALT_SIZE).ok_or_else(|| {
         CipherError::DecryptionError(
             "wallet file truncated: salt missing or incomplete".to_string(),
         )
     })?;
-    let salt = SaltString::new(std::str::from_utf8(salt_data)?)
-        .map_err(|e| CipherError::DecryptionError(e.to_string()))?;
 
-    // compute PBKDF2 password hash
-    let password_hash = Pbkdf2
-        .hash_password_customized(password.as_bytes(), None, None, HASH_PARAMS, &salt)
-        .map_err(|e| CipherError::DecryptionError(e.to_string()))?
-        .hash
-        .expect("content is missing after a successful hash");
+    // derive the key from the password, as done by the version that encrypted the data
+    let key = match version {
+        VERSION => derive_key(password, salt)?,
+        PBKDF2_VERSION => derive_legacy_key(password, salt)?,
+        _ => {
+            return Err(CipherError::DecryptionError(format!(
+            "unsupported cipher version {}, the data was encrypted by a more recent node or client",
+            version
+        )))
+        }
+    };
 
     // parse AES-GCM nonce
     let nonce_end_index = SALT_SIZE + NONCE_SIZE;
@@ -55,7 +58,7 @@ pub fn decrypt(password: &str, data: &[u8]) -> Result<(u32, Vec<u8>), CipherErro
     })?);
 
     // decrypt the data
-    let cipher = Aes256Gcm::new_from_slice(password_hash.as_bytes()).expect("invalid size key");
+    let cipher = Aes256Gcm::new_from_slice(&key).expect("invalid size key");
     let decrypted_bytes = cipher
         .decrypt(
             nonce,
@@ -0,0 +1,89 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! massa-cipher key derivation module.
+//!
+//! Read `lib.rs` module documentation for more information.
+
+use argon2::{Algorithm, Argon2, Version};
+use pbkdf2::{
+    password_hash::{PasswordHasher, SaltString},
+    Pbkdf2,
+};
+
+use crate::constants::{
+    ARGON2_MEMORY_COST, ARGON2_PARALLELISM, ARGON2_TIME_COST, HASH_PARAMS, KEY_SIZE,
+};
+use crate::error::CipherError;
+
+/// Derive the cipher key from the password with `Argon2id`
+pub(crate) fn derive_key(password: &str, salt: &[u8]) -> Result<[u8; KEY_SIZE], CipherError> {
+    let params = argon2::Params::new(
+        ARGON2_MEMORY_COST,
+        ARGON2_TIME_COST,
+        ARGON2_PARALLELISM,
+        Some(KEY_SIZE),
+    )
+    .map_err(|e| CipherError::KeyDerivationError(e.to_string()))?;
+    let mut key = [0u8; KEY_SIZE];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(password.as_bytes(), salt, &mut key)
+        .map_err(|e| CipherError::KeyDerivationError(e.to_string()))?;
+    Ok(key)
+}
+
+/// Derive the cipher key from the password with `PBKDF2`, as done by the version 0 of the cipher
+pub(crate) fn derive_legacy_key(
+    password: &str,
+    salt: &[u8],
+) -> Result<[u8; KEY_SIZE], CipherError> {
+    let salt = SaltString::new(std::str::from_utf8(salt)?)
+        .map_err(|e| CipherError::KeyDerivationError(e.to_string()))?;
+    let password_hash = Pbkdf2
+        .hash_password_customized(password.as_bytes(), None, None, HASH_PARAMS, &salt)
+        .map_err(|e| CipherError::KeyDerivationError(e.to_string()))?
+        .hash
+        .expect("content is missing after a successful hash");
+    let mut key = [0u8; KEY_SIZE];
+    key.copy_from_slice(password_hash.as_bytes());
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::{PBKDF2_VERSION, VERSION};
+    use crate::test_exports::encrypt_legacy;
+    use crate::{decrypt, encrypt, CipherError};
+
+    const PASSWORD: &str = "password";
+    const DATA: &[u8] = b"some secret keys";
+
+    #[test]
+    fn test_decrypt_legacy_data() {
+        let encrypted = encrypt_legacy(PASSWORD, DATA).unwrap();
+        let (version, decrypted) = decrypt(PASSWORD, &encrypted).unwrap();
+        assert_eq!(version, PBKDF2_VERSION);
+        assert_eq!(decrypted, DATA);
+    }
+
+    #[test]
+    fn test_migrate_legacy_data() {
+        let (_, decrypted) = decrypt(PASSWORD, &encrypt_legacy(PASSWORD, DATA).unwrap()).unwrap();
+        let encrypted = encrypt(PASSWORD, &decrypted).unwrap();
+        let (version, decrypted) = decrypt(PASSWORD, &encrypted).unwrap();
+        assert_eq!(version, VERSION);
+        assert_eq!(decrypted, DATA);
+    }
+
+    #[test]
+    fn test_wrong_password() {
+        for encrypted in [
+            encrypt(PASSWORD, DATA).unwrap(),
+            encrypt_legacy(PASSWORD, DATA).unwrap(),
+        ] {
+            assert!(matches!(
+                decrypt("wrong password", &encrypted),
+                Err(CipherError::DecryptionError(_))
+            ));
+        }
+    }
+}
@@ -7,8 +7,11 @@
 //! AES-GCM is a state-of-the-art high-performance Authenticated Encryption with Associated Data (AEAD)
 //! that provides confidentiality and authenticity.
 //!
-//! To hash the password before using it as a cipher key, we use the `PBKDF2` key derivation function
-//! as specified in [RFC 2898](https://datatracker.ietf.org/doc/html/rfc2898).
+//! To derive the cipher key from the password, we use the `Argon2id` key derivation function
+//! as specified in [RFC 9106](https://datatracker.ietf.org/doc/html/rfc9106).
+//! Its memory cost makes the brute-forcing of the password on dedicated hardware expensive.
+//! The data encrypted by the version 0 of the cipher, whose key was derived with `PBKDF2`
+//! ([RFC 2898](https://datatracker.ietf.org/doc/html/rfc2898)), can still be decrypted.
 //!
 //! The AES-GCM crate we use has received one security audit by NCC Group, with no significant findings.
 
@@ -16,7 +19,12 @@ mod constants;
 mod decrypt;
 mod encrypt;
 mod error;
+mod kdf;
 
+pub use constants::VERSION;
 pub use decrypt::decrypt;
 pub use encrypt::encrypt;
 pub use error::CipherError;
+
+#[cfg(any(test, feature = "testing"))]
+pub mod test_exports;
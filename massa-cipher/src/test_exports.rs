@@ -0,0 +1,15 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Tools to test the decryption of the data written by the previous versions of the cipher
+
+use crate::constants::PBKDF2_VERSION;
+use crate::encrypt::{encrypt_with_key, generate_salt};
+use crate::error::CipherError;
+use crate::kdf::derive_legacy_key;
+
+/// Encrypt `data` as done by the version 0 of the cipher, with a key derived by `PBKDF2`
+pub fn encrypt_legacy(password: &str, data: &[u8]) -> Result<Vec<u8>, CipherError> {
+    let salt = generate_salt();
+    let key = derive_legacy_key(password, salt.as_bytes())?;
+    encrypt_with_key(PBKDF2_VERSION, &salt, &key, data)
+}
@@ -4,23 +4,25 @@ use crate::repl::Output;
 use anyhow::{anyhow, bail, Error, Result};
 use console::style;
 use massa_models::api::{
-    AddressInfo, CompactAddressInfo, DatastoreEntryInput, EventFilter, OperationInput,
+    AddressInfo, BlockCandidateInput, CompactAddressInfo, DatastoreEntryInput, EventFilter,
+    ExportContent, ExportFormat, OperationInput, Pagination, ProductionOverride,
 };
 use massa_models::api::{ReadOnlyBytecodeExecution, ReadOnlyCall};
+use massa_models::ban_list::{BanList, IpSubnet};
+use massa_models::network_faults::MessageFaults;
 use massa_models::node::NodeId;
 use massa_models::prehash::PreHashMap;
-use massa_models::timeslots::get_current_latest_block_slot;
 use massa_models::{
     address::Address,
     amount::Amount,
     block::BlockId,
+    builder::OperationBuilder,
     endorsement::EndorsementId,
-    operation::{Operation, OperationId, OperationType},
+    operation::{OperationId, OperationType},
     slot::Slot,
 };
 use massa_sdk::Client;
 use massa_signature::KeyPair;
-use massa_time::MassaTime;
 use massa_wallet::Wallet;
 use serde::Serialize;
 use std::collections::{BTreeMap, HashMap};
@@ -71,12 +73,72 @@ pub enum Command {
     )]
     node_ban_by_id,
 
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "Subnet1 Subnet2 ..."),
+        message = "ban given subnet(s), in CIDR notation (ex: 1.2.3.0/24)"
+    )]
+    node_ban_by_subnet,
+
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "Subnet1 Subnet2 ..."),
+        message = "unban given subnet(s), with the IP addresses and smaller subnets they contain"
+    )]
+    node_unban_by_subnet,
+
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "[FilePath]"),
+        message = "show the banned IP addresses and subnets with the reason and expiry of their ban, or save them to a JSON file that other nodes can import"
+    )]
+    node_export_ban_list,
+
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "FilePath"),
+        message = "merge the bans of a JSON file exported by node_export_ban_list with the bans of the node"
+    )]
+    node_import_ban_list,
+
+    #[strum(
+        ascii_case_insensitive,
+        message = "write the known peers and bans of the node to its peers file right away"
+    )]
+    node_flush_peers,
+
+    #[strum(
+        ascii_case_insensitive,
+        message = "read the peers file of the node again and merge it with the known peers and bans, e.g. after editing it"
+    )]
+    node_reload_peers,
+
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "IpAddr"),
+        message = "show the last ended connections of the peer at the given IP address, with their direction, duration and close reason"
+    )]
+    get_peer_history,
+
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "FilePath|none [NodeId]"),
+        message = "inject the faults of a JSON file in the messages sent to the given peer, or to all the peers (none to remove them), only on a node built with the fault_injection feature"
+    )]
+    node_set_network_faults,
+
     #[strum(ascii_case_insensitive, message = "stops the node")]
     node_stop,
 
     #[strum(ascii_case_insensitive, message = "show staking addresses")]
     node_get_staking_addresses,
 
+    #[strum(
+        ascii_case_insensitive,
+        message = "show block production stats of the staking addresses for the last cycles"
+    )]
+    node_get_stats,
+
     #[strum(
         ascii_case_insensitive,
         props(args = "Address1 Address2 ..."),
@@ -119,12 +181,89 @@ pub enum Command {
     )]
     node_peers_whitelist,
 
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "Path"),
+        message = "export the node final state and final blocks to a snapshot file on the node machine"
+    )]
+    node_export_snapshot,
+
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "StartSlot EndSlot (blocks or operations) (jsonl or csv) Path"),
+        message = "export the archived final blocks or operations of a slot range to a file on the node machine"
+    )]
+    node_export_chain_data,
+
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "Directives"),
+        message = "replace the log filter of the node by comma-separated directives, ex: info,massa_protocol_worker=debug"
+    )]
+    node_set_log_filter,
+
+    #[strum(
+        ascii_case_insensitive,
+        message = "show the current log filter of the node"
+    )]
+    node_get_log_filter,
+
+    #[strum(
+        ascii_case_insensitive,
+        message = "reload the connection limits, peers dump interval, pool minimal fee and log levels from the node configuration files"
+    )]
+    node_reload_config,
+
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "automatic|always_produce|never_produce"),
+        message = "override the safety interlock that stops the block production while the node has too few peers or is not synced"
+    )]
+    node_set_production_override,
+
+    #[strum(
+        ascii_case_insensitive,
+        message = "show the override of the block production safety interlock"
+    )]
+    node_get_production_override,
+
+    #[strum(
+        ascii_case_insensitive,
+        message = "accept the blockclique switch rejected for being deeper than the max reorg depth, only if it is legitimate"
+    )]
+    node_accept_reorg,
+
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "[repair]"),
+        message = "check the integrity of the archive and of the address index of the node, removing the corrupted entries with repair"
+    )]
+    node_check_storage,
+
     #[strum(
         ascii_case_insensitive,
         message = "show the status of the node (reachable? number of peers connected, consensus, version, config parameter summary...)"
     )]
     get_status,
 
+    #[strum(
+        ascii_case_insensitive,
+        message = "check that the node is synced and connected to other nodes"
+    )]
+    get_health,
+
+    #[strum(
+        ascii_case_insensitive,
+        message = "show the software versions of the connected nodes, with the number of nodes running each of them"
+    )]
+    get_network_versions,
+
+    #[strum(
+        ascii_case_insensitive,
+        message = "show the size of the operation pool and the histogram of the fee per byte of its operations"
+    )]
+    get_operation_pool_stats,
+
     #[strum(
         ascii_case_insensitive,
         props(args = "Address1 Address2 ..."),
@@ -132,6 +271,13 @@ pub enum Command {
     )]
     get_addresses,
 
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "Address"),
+        message = "get the final balance of an address with a Merkle proof against the final balance state root"
+    )]
+    get_balance_proof,
+
     #[strum(
         ascii_case_insensitive,
         props(args = "Address Key"),
@@ -146,6 +292,34 @@ pub enum Command {
     )]
     get_blocks,
 
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "StartSlot EndSlot Offset Limit"),
+        message = "list the blocks from a slot (included) to another (excluded), slots are given as period.thread"
+    )]
+    get_blocks_by_slot_range,
+
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "Address Offset Limit"),
+        message = "list the blocks created by an address"
+    )]
+    get_blocks_by_creator,
+
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "PathToCandidateJson"),
+        message = "check whether a candidate block (header and operations) would be accepted, without submitting it"
+    )]
+    check_block_validity,
+
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "BlockId"),
+        message = "explain the fork choice status of a block (cliques, fitness, distance from finality)"
+    )]
+    explain_fork_choice,
+
     #[strum(
         ascii_case_insensitive,
         props(args = "EndorsementId1 EndorsementId2 ..."),
@@ -160,6 +334,27 @@ pub enum Command {
     )]
     get_operations,
 
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "OperationId1 OperationId2 ..."),
+        message = "show the lifecycle status of a list of operations (pending, included, final or dropped)"
+    )]
+    get_operation_statuses,
+
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "Address Offset Limit"),
+        message = "list the operations involving an address as creator, recipient or call target"
+    )]
+    get_operations_by_address,
+
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "Address Offset Limit"),
+        message = "list the final operations involving an address, with their block and slot (needs the address index on the node)"
+    )]
+    get_final_operations_by_address,
+
     #[strum(
         ascii_case_insensitive,
         props(
@@ -474,6 +669,133 @@ impl Command {
                 Ok(Box::new(()))
             }
 
+            Command::node_ban_by_subnet => {
+                let subnets = parse_vec::<IpSubnet>(parameters)?;
+                match client.private.node_ban_by_subnet(subnets).await {
+                    Ok(()) => {
+                        if !json {
+                            println!("Request of banning successfully sent!")
+                        }
+                    }
+                    Err(e) => rpc_error!(e),
+                }
+                Ok(Box::new(()))
+            }
+
+            Command::node_unban_by_subnet => {
+                let subnets = parse_vec::<IpSubnet>(parameters)?;
+                match client.private.node_unban_by_subnet(subnets).await {
+                    Ok(()) => {
+                        if !json {
+                            println!("Request of unbanning successfully sent!")
+                        }
+                    }
+                    Err(e) => rpc_error!(e),
+                }
+                Ok(Box::new(()))
+            }
+
+            Command::node_export_ban_list => {
+                if parameters.len() > 1 {
+                    bail!("wrong param numbers, expecting at most the path of the file to write")
+                }
+                let ban_list = match client.private.node_export_ban_list().await {
+                    Ok(ban_list) => ban_list,
+                    Err(e) => rpc_error!(e),
+                };
+                match parameters.first() {
+                    Some(path) => {
+                        tokio::fs::write(path, serde_json::to_string_pretty(&ban_list)?).await?;
+                        if !json {
+                            println!("{} ban(s) exported to {}", ban_list.bans.len(), path);
+                        }
+                        Ok(Box::new(()))
+                    }
+                    None => Ok(Box::new(ban_list)),
+                }
+            }
+
+            Command::node_import_ban_list => {
+                if parameters.len() != 1 {
+                    bail!("wrong param numbers, expecting the path to a ban list JSON file")
+                }
+                let path = parameters[0].parse::<PathBuf>()?;
+                let ban_list: BanList =
+                    serde_json::from_slice(&get_file_as_byte_vec(&path).await?)?;
+                let ban_count = ban_list.bans.len();
+                match client.private.node_import_ban_list(ban_list).await {
+                    Ok(()) => {
+                        if !json {
+                            println!(
+                                "Request of importing {} ban(s) successfully sent!",
+                                ban_count
+                            )
+                        }
+                    }
+                    Err(e) => rpc_error!(e),
+                }
+                Ok(Box::new(()))
+            }
+
+            Command::node_flush_peers => {
+                match client.private.node_flush_peers().await {
+                    Ok(()) => {
+                        if !json {
+                            println!("Peers file written");
+                        }
+                    }
+                    Err(e) => rpc_error!(e),
+                };
+                Ok(Box::new(()))
+            }
+
+            Command::node_reload_peers => {
+                match client.private.node_reload_peers().await {
+                    Ok(()) => {
+                        if !json {
+                            println!("Peers file reloaded");
+                        }
+                    }
+                    Err(e) => rpc_error!(e),
+                };
+                Ok(Box::new(()))
+            }
+
+            Command::get_peer_history => {
+                if parameters.len() != 1 {
+                    bail!("wrong param numbers, expecting an IP address")
+                }
+                let ip = parameters[0].parse::<IpAddr>()?;
+                match client.private.get_peer_history(ip).await {
+                    Ok(history) => Ok(Box::new(history)),
+                    Err(e) => rpc_error!(e),
+                }
+            }
+
+            Command::node_set_network_faults => {
+                if parameters.is_empty() || parameters.len() > 2 {
+                    bail!("wrong param numbers, expecting the path to a faults JSON file or none, and an optional node ID")
+                }
+                let faults: Option<MessageFaults> = if parameters[0] == "none" {
+                    None
+                } else {
+                    let path = parameters[0].parse::<PathBuf>()?;
+                    Some(serde_json::from_slice(&get_file_as_byte_vec(&path).await?)?)
+                };
+                let node_id = match parameters.get(1) {
+                    Some(node_id) => Some(node_id.parse::<NodeId>()?),
+                    None => None,
+                };
+                match client
+                    .private
+                    .node_set_network_faults(node_id, faults)
+                    .await
+                {
+                    Ok(network_faults) => Ok(Box::new(network_faults)),
+                    Err(e) => rpc_error!(e),
+                }
+            }
+
             Command::node_stop => {
                 match client.private.stop_node().await {
                     Ok(()) => {
@@ -486,6 +808,127 @@ impl Command {
                 Ok(Box::new(()))
             }
 
+            Command::node_export_snapshot => {
+                if parameters.len() != 1 {
+                    bail!("wrong number of parameters");
+                }
+                let path = PathBuf::from(&parameters[0]);
+                match client.private.node_export_snapshot(path).await {
+                    Ok(slot) => {
+                        if !json {
+                            println!("Snapshot successfully exported at slot:");
+                        }
+                        Ok(Box::new(slot))
+                    }
+                    Err(e) => rpc_error!(e),
+                }
+            }
+
+            Command::node_export_chain_data => {
+                if parameters.len() != 5 {
+                    bail!("wrong number of parameters");
+                }
+                let start = parameters[0].parse::<Slot>()?;
+                let end = parameters[1].parse::<Slot>()?;
+                let content = parameters[2]
+                    .parse::<ExportContent>()
+                    .map_err(|_| anyhow!("expected blocks or operations"))?;
+                let format = parameters[3]
+                    .parse::<ExportFormat>()
+                    .map_err(|_| anyhow!("expected jsonl or csv"))?;
+                let path = PathBuf::from(&parameters[4]);
+                match client
+                    .private
+                    .node_export_chain_data(start, end, content, format, path)
+                    .await
+                {
+                    Ok(stats) => Ok(Box::new(stats)),
+                    Err(e) => rpc_error!(e),
+                }
+            }
+
+            Command::node_set_log_filter => {
+                if parameters.len() != 1 {
+                    bail!("wrong number of parameters");
+                }
+                match client
+                    .private
+                    .node_set_log_filter(parameters[0].clone())
+                    .await
+                {
+                    Ok(()) => {
+                        if !json {
+                            println!("Log filter successfully updated");
+                        }
+                    }
+                    Err(e) => rpc_error!(e),
+                };
+                Ok(Box::new(()))
+            }
+
+            Command::node_get_log_filter => match client.private.node_get_log_filter().await {
+                Ok(filter) => Ok(Box::new(filter)),
+                Err(e) => rpc_error!(e),
+            },
+
+            Command::node_reload_config => {
+                match client.private.node_reload_config().await {
+                    Ok(()) => {
+                        if !json {
+                            println!("Configuration reload requested, check the node logs for the outcome");
+                        }
+                    }
+                    Err(e) => rpc_error!(e),
+                };
+                Ok(Box::new(()))
+            }
+
+            Command::node_set_production_override => {
+                if parameters.len() != 1 {
+                    bail!("wrong number of parameters");
+                }
+                let production_override = parameters[0]
+                    .parse::<ProductionOverride>()
+                    .map_err(|_| anyhow!("expected automatic, always_produce or never_produce"))?;
+                match client
+                    .private
+                    .node_set_production_override(production_override)
+                    .await
+                {
+                    Ok(()) => {
+                        if !json {
+                            println!("Block production override set to {}", production_override);
+                        }
+                    }
+                    Err(e) => rpc_error!(e),
+                };
+                Ok(Box::new(()))
+            }
+
+            Command::node_get_production_override => {
+                match client.private.node_get_production_override().await {
+                    Ok(production_override) => Ok(Box::new(production_override)),
+                    Err(e) => rpc_error!(e),
+                }
+            }
+
+            Command::node_accept_reorg => match client.private.node_accept_reorg().await {
+                Ok(alert) => Ok(Box::new(alert)),
+                Err(e) => rpc_error!(e),
+            },
+
+            Command::node_check_storage => {
+                let repair = match parameters.as_slice() {
+                    [] => false,
+                    [repair] if repair.eq_ignore_ascii_case("repair") => true,
+                    _ => bail!("wrong parameters, expected nothing or repair"),
+                };
+                match client.private.node_check_storage(repair).await {
+                    Ok(report) => Ok(Box::new(report)),
+                    Err(e) => rpc_error!(e),
+                }
+            }
+
             Command::node_get_staking_addresses => {
                 match client.private.get_staking_addresses().await {
                     Ok(staking_addresses) => Ok(Box::new(staking_addresses)),
@@ -493,6 +936,11 @@ impl Command {
                 }
             }
 
+            Command::node_get_stats => match client.private.get_stats().await {
+                Ok(stats) => Ok(Box::new(stats)),
+                Err(e) => rpc_error!(e),
+            },
+
             Command::node_remove_staking_addresses => {
                 let addresses = parse_vec::<Address>(parameters)?;
                 match client.private.remove_staking_addresses(addresses).await {
@@ -558,6 +1006,23 @@ impl Command {
                 Err(e) => rpc_error!(e),
             },
 
+            Command::get_health => match client.public.get_health().await {
+                Ok(node_health) => Ok(Box::new(node_health)),
+                Err(e) => rpc_error!(e),
+            },
+
+            Command::get_network_versions => match client.public.get_network_versions().await {
+                Ok(versions) => Ok(Box::new(versions)),
+                Err(e) => rpc_error!(e),
+            },
+
+            Command::get_operation_pool_stats => {
+                match client.public.get_operation_pool_stats().await {
+                    Ok(stats) => Ok(Box::new(stats)),
+                    Err(e) => rpc_error!(e),
+                }
+            }
+
             Command::get_addresses => {
                 let addresses = parse_vec::<Address>(parameters)?;
                 match client.public.get_addresses(addresses).await {
@@ -566,6 +1031,17 @@ impl Command {
                 }
             }
 
+            Command::get_balance_proof => {
+                if parameters.len() != 1 {
+                    bail!("wrong param numbers, expecting an address")
+                }
+                let address = parameters[0].parse::<Address>()?;
+                match client.public.get_balance_proof(address).await {
+                    Ok(proof) => Ok(Box::new(proof)),
+                    Err(e) => rpc_error!(e),
+                }
+            }
+
             Command::get_datastore_entry => {
                 if parameters.len() != 2 {
                     bail!("invalid number of parameters");
@@ -593,6 +1069,63 @@ impl Command {
                 }
             }
 
+            Command::get_blocks_by_slot_range => {
+                if parameters.len() != 4 {
+                    bail!("invalid number of parameters");
+                }
+                let start = parameters[0].parse::<Slot>()?;
+                let end = parameters[1].parse::<Slot>()?;
+                let pagination = parse_pagination(&parameters[2], &parameters[3])?;
+                match client
+                    .public
+                    .get_blocks_by_slot_range(start, end, pagination)
+                    .await
+                {
+                    Ok(blocks) => Ok(Box::new(blocks)),
+                    Err(e) => rpc_error!(e),
+                }
+            }
+
+            Command::get_blocks_by_creator => {
+                if parameters.len() != 3 {
+                    bail!("invalid number of parameters");
+                }
+                let address = parameters[0].parse::<Address>()?;
+                let pagination = parse_pagination(&parameters[1], &parameters[2])?;
+                match client
+                    .public
+                    .get_blocks_by_creator(address, pagination)
+                    .await
+                {
+                    Ok(blocks) => Ok(Box::new(blocks)),
+                    Err(e) => rpc_error!(e),
+                }
+            }
+
+            Command::check_block_validity => {
+                if parameters.len() != 1 {
+                    bail!("wrong param numbers, expecting the path to a candidate block JSON file")
+                }
+                let path = parameters[0].parse::<PathBuf>()?;
+                let candidate: BlockCandidateInput =
+                    serde_json::from_slice(&get_file_as_byte_vec(&path).await?)?;
+                match client.public.check_block_validity(candidate).await {
+                    Ok(report) => Ok(Box::new(report)),
+                    Err(e) => rpc_error!(e),
+                }
+            }
+
+            Command::explain_fork_choice => {
+                if parameters.len() != 1 {
+                    bail!("wrong param numbers, expecting a block id")
+                }
+                let block_id = parameters[0].parse::<BlockId>()?;
+                match client.public.explain_fork_choice(block_id).await {
+                    Ok(explanation) => Ok(Box::new(explanation)),
+                    Err(e) => rpc_error!(e),
+                }
+            }
+
             Command::get_endorsements => {
                 let endorsements = parse_vec::<EndorsementId>(parameters)?;
                 match client.public.get_endorsements(endorsements).await {
@@ -609,6 +1142,46 @@ impl Command {
                 }
             }
 
+            Command::get_operations_by_address => {
+                if parameters.len() != 3 {
+                    bail!("invalid number of parameters");
+                }
+                let address = parameters[0].parse::<Address>()?;
+                let pagination = parse_pagination(&parameters[1], &parameters[2])?;
+                match client
+                    .public
+                    .get_operations_by_address(address, pagination)
+                    .await
+                {
+                    Ok(operations) => Ok(Box::new(operations)),
+                    Err(e) => rpc_error!(e),
+                }
+            }
+
+            Command::get_final_operations_by_address => {
+                if parameters.len() != 3 {
+                    bail!("invalid number of parameters");
+                }
+                let address = parameters[0].parse::<Address>()?;
+                let pagination = parse_pagination(&parameters[1], &parameters[2])?;
+                match client
+                    .public
+                    .get_final_operations_by_address(address, pagination)
+                    .await
+                {
+                    Ok(operations) => Ok(Box::new(operations)),
+                    Err(e) => rpc_error!(e),
+                }
+            }
+
+            Command::get_operation_statuses => {
+                let operations = parse_vec::<OperationId>(parameters)?;
+                match client.public.get_operation_statuses(operations).await {
+                    Ok(statuses) => Ok(Box::new(statuses)),
+                    Err(e) => rpc_error!(e),
+                }
+            }
+
             Command::get_filtered_sc_output_event => {
                 let p_list: [&str; 7] = [
                     "start",
@@ -660,8 +1233,7 @@ impl Command {
             }
 
             Command::wallet_generate_secret_key => {
-                let key = KeyPair::generate();
-                let ad = wallet.add_keypairs(vec![key])?[0];
+                let ad = wallet.generate_keypair()?;
                 if json {
                     Ok(Box::new(ad.to_string()))
                 } else {
@@ -834,8 +1406,7 @@ impl Command {
                 };
                 let mut res = "".to_string();
                 if let Some(e) = end {
-                    let (days, hours, mins, secs) =
-                        e.saturating_sub(MassaTime::now()?).days_hours_mins_secs()?; // compensation milliseconds is zero
+                    let (days, hours, mins, secs) = e.time_until()?.days_hours_mins_secs()?; // compensation milliseconds is zero
 
                     let _ = write!(res, "{} days, {} hours, {} minutes, {} seconds remaining until the end of the current episode", days, hours, mins, secs);
                 } else {
@@ -1219,19 +1790,14 @@ async fn send_operation(
     }
     .config;
 
-    let slot = get_current_latest_block_slot(cfg.thread_count, cfg.t0, cfg.genesis_timestamp)?
-        .unwrap_or_else(|| Slot::new(0, 0));
-    let mut expire_period = slot.period + cfg.operation_validity_periods;
-    if slot.thread >= addr.get_thread(cfg.thread_count) {
-        expire_period += 1;
-    };
+    let slot = cfg.get_slot_timing().get_current_slot_or_first()?;
+    let expire_period = slot.get_next_period_in_thread(addr.get_thread(cfg.thread_count))
+        + cfg.operation_validity_periods;
 
     let op = wallet.create_operation(
-        Operation {
-            fee,
-            expire_period,
-            op,
-        },
+        OperationBuilder::new(op)
+            .fee(fee)
+            .expire_period(expire_period),
         addr,
     )?;
 
@@ -1239,7 +1805,7 @@ async fn send_operation(
         .public
         .send_operations(vec![OperationInput {
             creator_public_key: op.creator_public_key,
-            serialized_content: op.serialized_data,
+            serialized_content: op.serialized_data.to_vec(),
             signature: op.signature,
         }])
         .await
@@ -1268,6 +1834,18 @@ where
         .collect()
 }
 
+/// parse the offset and limit of a paginated query
+fn parse_pagination(offset: &str, limit: &str) -> anyhow::Result<Pagination> {
+    Ok(Pagination {
+        offset: offset
+            .parse()
+            .map_err(|e| anyhow!("failed to parse offset \"{}\" due to: {}", offset, e))?,
+        limit: limit
+            .parse()
+            .map_err(|e| anyhow!("failed to parse limit \"{}\" due to: {}", limit, e))?,
+    })
+}
+
 /// reads a file
 async fn get_file_as_byte_vec(filename: &std::path::Path) -> Result<Vec<u8>> {
     Ok(tokio::fs::read(filename).await?)
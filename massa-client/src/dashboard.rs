@@ -0,0 +1,426 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Terminal dashboard of a node: status, connected peers and recent logs.
+//!
+//! The dashboard is refreshed each time the node announces a new block header through the
+//! WebSocket subscription API, or every second if that API cannot be reached.
+
+use anyhow::Result;
+use crossterm::event::{Event, EventStream, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::execute;
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use futures::StreamExt;
+use massa_models::api::{ConnectedNodeInfo, NodeStatus};
+use massa_models::block::BlockHeader;
+use massa_models::node::NodeId;
+use massa_sdk::{Client, HttpConfig, RpcWsClient, Subscription};
+use massa_time::MassaTime;
+use std::collections::HashMap;
+use std::io::{self, Stdout};
+use std::time::{Duration, Instant};
+use tui::backend::{Backend, CrosstermBackend};
+use tui::layout::{Constraint, Direction, Layout, Rect};
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, List, ListItem, Paragraph, Row, Table};
+use tui::{Frame, Terminal};
+
+/// Interval between two redraws, and between two refreshes when the subscription API is not available
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Minimal interval between two refreshes triggered by new block headers
+const MIN_REFRESH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Number of log lines asked to the node
+const LOG_LINE_COUNT: usize = 200;
+
+/// Bytes exchanged with a node, sampled at some instant
+struct TrafficSample {
+    instant: Instant,
+    bytes: HashMap<NodeId, (u64, u64)>,
+}
+
+/// What the dashboard displays
+#[derive(Default)]
+struct Dashboard {
+    status: Option<NodeStatus>,
+    nodes: Vec<ConnectedNodeInfo>,
+    /// bytes per second received from and sent to each node since the previous refresh
+    rates: HashMap<NodeId, (f64, f64)>,
+    previous_sample: Option<TrafficSample>,
+    logs: Vec<String>,
+    /// last error returned by the node, if any
+    error: Option<String>,
+    /// error of the subscription API, none while it notifies the new block headers
+    subscription_error: Option<String>,
+}
+
+impl Dashboard {
+    /// Ask the node for its status, its connected nodes and its recent logs
+    async fn refresh(&mut self, client: &Client) {
+        let (status, nodes, logs) = tokio::join!(
+            client.public.get_status(),
+            client.public.get_connected_nodes(),
+            client.private.node_get_recent_logs(LOG_LINE_COUNT)
+        );
+        self.error = None;
+        match status {
+            Ok(status) => self.status = Some(status),
+            Err(e) => self.error = Some(format!("could not get the node status: {}", e)),
+        }
+        match nodes {
+            Ok(nodes) => self.update_nodes(nodes),
+            Err(e) => self.error = Some(format!("could not get the connected nodes: {}", e)),
+        }
+        match logs {
+            Ok(logs) => self.logs = logs,
+            Err(e) => {
+                self.logs = vec![format!(
+                    "could not get the node logs from the private API: {}",
+                    e
+                )]
+            }
+        }
+    }
+
+    /// Replace the connected nodes, computing their rates from the previous sample
+    fn update_nodes(&mut self, nodes: Vec<ConnectedNodeInfo>) {
+        let sample = TrafficSample {
+            instant: Instant::now(),
+            bytes: nodes
+                .iter()
+                .map(|node| (node.node_id, (node.bytes_received, node.bytes_sent)))
+                .collect(),
+        };
+        self.rates.clear();
+        if let Some(previous) = &self.previous_sample {
+            let elapsed = sample
+                .instant
+                .duration_since(previous.instant)
+                .as_secs_f64();
+            if elapsed > 0.0 {
+                for (node_id, (received, sent)) in sample.bytes.iter() {
+                    // a node that reconnected has its counters reset, it has no rate until the next sample
+                    if let Some((previous_received, previous_sent)) = previous.bytes.get(node_id) {
+                        if received >= previous_received && sent >= previous_sent {
+                            self.rates.insert(
+                                *node_id,
+                                (
+                                    (received - previous_received) as f64 / elapsed,
+                                    (sent - previous_sent) as f64 / elapsed,
+                                ),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        self.previous_sample = Some(sample);
+        self.nodes = nodes;
+    }
+}
+
+/// Restores the terminal when dropped, including when the dashboard fails
+struct TerminalGuard {
+    terminal: Terminal<CrosstermBackend<Stdout>>,
+}
+
+impl TerminalGuard {
+    fn new() -> Result<Self> {
+        enable_raw_mode()?;
+        let mut stdout = io::stdout();
+        if let Err(e) = execute!(stdout, EnterAlternateScreen) {
+            let _ = disable_raw_mode();
+            return Err(e.into());
+        }
+        let terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+        Ok(TerminalGuard { terminal })
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = disable_raw_mode();
+        let _ = execute!(self.terminal.backend_mut(), LeaveAlternateScreen);
+        let _ = self.terminal.show_cursor();
+    }
+}
+
+/// Run the dashboard until the user quits with `q`, `Esc` or `Ctrl+C`.
+/// `ws_url` is the url of the subscription API of the node.
+pub(crate) async fn run(client: &Client, ws_url: &str, http_config: &HttpConfig) -> Result<()> {
+    let mut dashboard = Dashboard::default();
+    let mut headers = match subscribe(ws_url, http_config).await {
+        Ok(headers) => Some(headers),
+        Err(e) => {
+            dashboard.subscription_error = Some(e);
+            None
+        }
+    };
+    dashboard.refresh(client).await;
+    let mut last_refresh = Instant::now();
+
+    let mut guard = TerminalGuard::new()?;
+    let mut events = EventStream::new();
+    let mut tick = tokio::time::interval(TICK_INTERVAL);
+    loop {
+        guard.terminal.draw(|f| draw(f, &dashboard))?;
+        tokio::select! {
+            event = events.next() => match event {
+                Some(Ok(Event::Key(key))) if is_quit_key(&key) => break,
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(e.into()),
+                None => break,
+            },
+            header = next_header(&mut headers) => match header {
+                Some(Ok(_)) => {
+                    if last_refresh.elapsed() >= MIN_REFRESH_INTERVAL {
+                        dashboard.refresh(client).await;
+                        last_refresh = Instant::now();
+                    }
+                }
+                Some(Err(e)) => {
+                    dashboard.subscription_error = Some(format!("invalid block header notification: {}", e));
+                    headers = None;
+                }
+                None => {
+                    dashboard.subscription_error = Some("subscription closed by the node".into());
+                    headers = None;
+                }
+            },
+            _ = tick.tick() => {
+                if headers.is_none() {
+                    dashboard.refresh(client).await;
+                    last_refresh = Instant::now();
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Subscribe to the new block headers, the client being kept alive along with the subscription
+async fn subscribe(
+    ws_url: &str,
+    http_config: &HttpConfig,
+) -> Result<(RpcWsClient, Subscription<BlockHeader>), String> {
+    let ws_client = RpcWsClient::from_url(ws_url, http_config)
+        .await
+        .map_err(|e| format!("could not connect to {}: {}", ws_url, e))?;
+    let subscription = ws_client
+        .subscribe_new_blocks_headers()
+        .await
+        .map_err(|e| format!("could not subscribe to the new block headers: {}", e))?;
+    Ok((ws_client, subscription))
+}
+
+/// Wait for the next block header, forever if there is no subscription
+async fn next_header(
+    headers: &mut Option<(RpcWsClient, Subscription<BlockHeader>)>,
+) -> Option<Result<BlockHeader, String>> {
+    match headers {
+        Some((_, subscription)) => subscription
+            .next()
+            .await
+            .map(|header| header.map_err(|e| e.to_string())),
+        None => futures::future::pending().await,
+    }
+}
+
+fn is_quit_key(key: &KeyEvent) -> bool {
+    match key.code {
+        KeyCode::Char('q') | KeyCode::Esc => true,
+        KeyCode::Char('c') => key.modifiers.contains(KeyModifiers::CONTROL),
+        _ => false,
+    }
+}
+
+fn draw<B: Backend>(f: &mut Frame<B>, dashboard: &Dashboard) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(7),
+            Constraint::Percentage(50),
+            Constraint::Min(5),
+        ])
+        .split(f.size());
+    draw_status(f, chunks[0], dashboard);
+    draw_peers(f, chunks[1], dashboard);
+    draw_logs(f, chunks[2], dashboard);
+}
+
+fn draw_status<B: Backend>(f: &mut Frame<B>, area: Rect, dashboard: &Dashboard) {
+    let mut lines = Vec::new();
+    if let Some(status) = &dashboard.status {
+        lines.push(Spans::from(format!(
+            "Node {} (version {}), up for {}",
+            status.node_id,
+            status.version,
+            format_duration(status.uptime)
+        )));
+        lines.push(Spans::from(format!(
+            "Slot {}, cycle {}, {} ({} periods behind)",
+            status
+                .last_slot
+                .map_or_else(|| "-".to_string(), |slot| slot.to_string()),
+            status.current_cycle,
+            status.sync_state,
+            status.blockclique_lag_periods
+        )));
+        let progress = &status.sync_progress;
+        lines.push(Spans::from(format!(
+            "Sync: {}, final slot {} of {}{}",
+            progress.phase,
+            progress
+                .local_final_slot
+                .map_or_else(|| "-".to_string(), |slot| slot.to_string()),
+            progress
+                .highest_remote_slot
+                .map_or_else(|| "-".to_string(), |slot| slot.to_string()),
+            progress
+                .estimated_time_remaining
+                .map_or_else(String::new, |remaining| format!(
+                    ", about {} remaining",
+                    format_duration(remaining)
+                ))
+        )));
+        let final_periods = status.last_final_slots.iter().map(|slot| slot.period);
+        match (final_periods.clone().min(), final_periods.max()) {
+            (Some(min), Some(max)) => lines.push(Spans::from(format!(
+                "Latest final periods: {} to {} over {} threads",
+                min,
+                max,
+                status.last_final_slots.len()
+            ))),
+            _ => lines.push(Spans::from("Latest final periods: -")),
+        }
+        lines.push(Spans::from(format!(
+            "Pool: {} operations, {} endorsements",
+            status.pool_stats.0, status.pool_stats.1
+        )));
+        for alert in &status.network_partition_alerts {
+            lines.push(Spans::from(Span::styled(
+                format!("Network partition alert: {}", alert),
+                Style::default().fg(Color::Red),
+            )));
+        }
+        for alert in &status.resource_alerts {
+            lines.push(Spans::from(Span::styled(
+                format!("Degraded read-only mode: {}", alert),
+                Style::default().fg(Color::Red),
+            )));
+        }
+        if let Some(alert) = &status.reorg_alert {
+            lines.push(Spans::from(Span::styled(
+                format!("REORG ALERT: {}", alert),
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            )));
+        }
+        if let Some(divergence) = &status.state_divergence {
+            lines.push(Spans::from(Span::styled(
+                format!(
+                    "STATE DIVERGENCE at slot {} with block {}: {}",
+                    divergence.local.slot,
+                    divergence.block_id,
+                    divergence.diverging_parts.join(", ")
+                ),
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            )));
+        }
+    }
+    lines.push(Spans::from(match &dashboard.subscription_error {
+        None => Span::styled("Refreshed on new blocks", Style::default().fg(Color::Green)),
+        Some(e) => Span::styled(
+            format!("Refreshed every second ({})", e),
+            Style::default().fg(Color::Yellow),
+        ),
+    }));
+    if let Some(e) = &dashboard.error {
+        lines.push(Spans::from(Span::styled(
+            e.clone(),
+            Style::default().fg(Color::Red),
+        )));
+    }
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Status (q to quit)"),
+    );
+    f.render_widget(paragraph, area);
+}
+
+fn draw_peers<B: Backend>(f: &mut Frame<B>, area: Rect, dashboard: &Dashboard) {
+    let now = MassaTime::now().ok();
+    let rows = dashboard.nodes.iter().map(|node| {
+        let last_alive = match (node.last_alive, now) {
+            (Some(last_alive), Some(now)) => {
+                format!("{} ago", format_duration(now.saturating_sub(last_alive)))
+            }
+            _ => "-".to_string(),
+        };
+        let (download, upload) = match dashboard.rates.get(&node.node_id) {
+            Some((received, sent)) => (format_rate(*received), format_rate(*sent)),
+            None => ("-".to_string(), "-".to_string()),
+        };
+        Row::new(vec![
+            node.ip.to_string(),
+            if node.is_outgoing { "out" } else { "in" }.to_string(),
+            last_alive,
+            download,
+            upload,
+            node.node_id.to_string(),
+        ])
+    });
+    let table = Table::new(rows)
+        .header(
+            Row::new(vec!["IP", "Direction", "Last alive", "↓", "↑", "Node id"])
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("Connected nodes ({})", dashboard.nodes.len())),
+        )
+        .widths(&[
+            Constraint::Length(40),
+            Constraint::Length(10),
+            Constraint::Length(12),
+            Constraint::Length(12),
+            Constraint::Length(12),
+            Constraint::Min(20),
+        ]);
+    f.render_widget(table, area);
+}
+
+fn draw_logs<B: Backend>(f: &mut Frame<B>, area: Rect, dashboard: &Dashboard) {
+    // only the latest lines that fit in the pane, borders excluded
+    let visible = area.height.saturating_sub(2) as usize;
+    let skipped = dashboard.logs.len().saturating_sub(visible);
+    let items: Vec<ListItem> = dashboard.logs[skipped..]
+        .iter()
+        .map(|line| ListItem::new(line.trim_end().to_string()))
+        .collect();
+    let list = List::new(items).block(Block::default().borders(Borders::ALL).title("Recent logs"));
+    f.render_widget(list, area);
+}
+
+fn format_duration(time: MassaTime) -> String {
+    let secs = time.to_duration().as_secs();
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m{}s", secs / 60, secs % 60),
+        _ => format!("{}h{}m", secs / 3600, (secs % 3600) / 60),
+    }
+}
+
+fn format_rate(bytes_per_sec: f64) -> String {
+    if bytes_per_sec >= 1_000_000.0 {
+        format!("{:.1} MB/s", bytes_per_sec / 1_000_000.0)
+    } else if bytes_per_sec >= 1_000.0 {
+        format!("{:.1} kB/s", bytes_per_sec / 1_000.0)
+    } else {
+        format!("{:.0} B/s", bytes_per_sec)
+    }
+}
@@ -12,12 +12,13 @@ use dialoguer::Password;
 use massa_sdk::{Client, HttpConfig};
 use massa_wallet::Wallet;
 use serde::Serialize;
-use std::net::IpAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicUsize, Ordering};
 use structopt::StructOpt;
 
 mod cmds;
+mod dashboard;
 mod repl;
 mod settings;
 
@@ -32,6 +33,12 @@ struct Args {
     /// Port to listen on (Massa private API).
     #[structopt(long)]
     private_port: Option<u16>,
+    /// Port of the Massa WebSocket subscription API, used by the dashboard
+    #[structopt(long)]
+    api_port: Option<u16>,
+    /// Bearer token of the Massa private API
+    #[structopt(long)]
+    private_api_token: Option<String>,
     /// Address to listen on
     #[structopt(long)]
     ip: Option<IpAddr>,
@@ -55,6 +62,9 @@ struct Args {
     #[structopt(short = "p", long = "pwd")]
     /// Wallet password
     password: Option<String>,
+    /// Show a dashboard of the node (status, connected nodes, recent logs) instead of running a command
+    #[structopt(long)]
+    tui: bool,
 }
 
 #[derive(Serialize)]
@@ -120,6 +130,13 @@ async fn run(args: Args) -> Result<()> {
         Some(private_port) => private_port,
         None => settings.default_node.private_port,
     };
+    let api_port = match args.api_port {
+        Some(api_port) => api_port,
+        None => settings.default_node.api_port,
+    };
+    let private_api_token = args
+        .private_api_token
+        .or_else(|| settings.default_node.private_api_token.clone());
 
     // Setup panic handlers,
     // and when a panic occurs,
@@ -131,10 +148,30 @@ async fn run(args: Args) -> Result<()> {
         std::process::exit(1);
     }));
 
+    if args.tui {
+        let client = Client::new(
+            address,
+            public_port,
+            private_port,
+            private_api_token.as_deref(),
+            &http_config,
+        )
+        .await;
+        let ws_url = format!("ws://{}", SocketAddr::new(address, api_port));
+        return dashboard::run(&client, &ws_url, &http_config).await;
+    }
+
     // ...
     let password = args.password.unwrap_or_else(|| ask_password(&args.wallet));
     let mut wallet = Wallet::new(args.wallet, password)?;
-    let client = Client::new(address, public_port, private_port, &http_config).await;
+    let client = Client::new(
+        address,
+        public_port,
+        private_port,
+        private_api_token.as_deref(),
+        &http_config,
+    )
+    .await;
     if atty::is(Stream::Stdout) && args.command == Command::help && !args.json {
         // Interactive mode
         repl::run(&client, &mut wallet).await?;
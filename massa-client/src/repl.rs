@@ -6,13 +6,20 @@ use anyhow::Result;
 use console::style;
 use erased_serde::{Serialize, Serializer};
 use massa_models::api::{
-    AddressInfo, BlockInfo, DatastoreEntryOutput, EndorsementInfo, NodeStatus, OperationInfo,
+    AddressInfo, BlockInfo, BlockValidityReport, DatastoreEntryOutput, EndorsementInfo,
+    ExportStats, ForkChoiceExplanation, NetworkVersions, NodeHealth, NodeStatus, OperationInfo,
+    OperationStatusInfo, PagedResult, PeerConnectionEvent, ProductionOverride, ReorgAlert,
+    StorageCheckReport,
 };
+use massa_models::balance_proof::FinalBalanceProof;
+use massa_models::ban_list::BanList;
 use massa_models::composite::PubkeySig;
 use massa_models::execution::ExecuteReadOnlyResponse;
+use massa_models::network_faults::{MessageFaults, NetworkFaults};
 use massa_models::output_event::SCOutputEvent;
 use massa_models::prehash::PreHashSet;
-use massa_models::{address::Address, operation::OperationId};
+use massa_models::stats::{CycleProductionStats, OperationPoolStats};
+use massa_models::{address::Address, operation::OperationId, slot::Slot};
 use massa_sdk::Client;
 use massa_wallet::Wallet;
 use rustyline::completion::{Completer, FilenameCompleter, Pair};
@@ -247,6 +254,78 @@ impl Output for BlockInfo {
     }
 }
 
+impl Output for BlockValidityReport {
+    fn pretty_print(&self) {
+        println!("{}", self);
+    }
+}
+
+impl Output for ForkChoiceExplanation {
+    fn pretty_print(&self) {
+        println!("{}", self);
+    }
+}
+
+impl Output for ProductionOverride {
+    fn pretty_print(&self) {
+        println!("{}", self);
+    }
+}
+
+impl Output for ReorgAlert {
+    fn pretty_print(&self) {
+        println!("Accepted: {}", self);
+    }
+}
+
+impl Output for ExportStats {
+    fn pretty_print(&self) {
+        print!("{}", self);
+    }
+}
+
+impl Output for StorageCheckReport {
+    fn pretty_print(&self) {
+        print!("{}", self);
+    }
+}
+
+impl Output for BanList {
+    fn pretty_print(&self) {
+        print!("{}", self);
+    }
+}
+
+impl Output for NodeHealth {
+    fn pretty_print(&self) {
+        println!("{}", self);
+    }
+}
+
+impl Output for NetworkVersions {
+    fn pretty_print(&self) {
+        println!("{}", self);
+    }
+}
+
+impl<T: std::fmt::Display + serde::Serialize> Output for PagedResult<T> {
+    fn pretty_print(&self) {
+        println!("{}", self);
+    }
+}
+
+impl Output for OperationPoolStats {
+    fn pretty_print(&self) {
+        println!("{}", self);
+    }
+}
+
+impl Output for FinalBalanceProof {
+    fn pretty_print(&self) {
+        println!("{}", self);
+    }
+}
+
 impl Output for PreHashSet<Address> {
     fn pretty_print(&self) {
         println!(
@@ -281,6 +360,14 @@ impl Output for Vec<EndorsementInfo> {
     }
 }
 
+impl Output for Vec<CycleProductionStats> {
+    fn pretty_print(&self) {
+        for cycle_stats in self {
+            println!("{}", cycle_stats);
+        }
+    }
+}
+
 impl Output for Vec<IpAddr> {
     fn pretty_print(&self) {
         for ips in self {
@@ -289,6 +376,42 @@ impl Output for Vec<IpAddr> {
     }
 }
 
+impl Output for Vec<PeerConnectionEvent> {
+    fn pretty_print(&self) {
+        if self.is_empty() {
+            println!("No ended connection with this peer");
+        }
+        for event in self {
+            println!("{}", event);
+        }
+    }
+}
+
+impl Output for NetworkFaults {
+    fn pretty_print(&self) {
+        let print_faults = |target: &str, faults: &MessageFaults| {
+            println!(
+                "{}: drop {}, duplicate {}, reorder {}, delay {} to {} ms",
+                target,
+                faults.drop_ratio,
+                faults.duplicate_ratio,
+                faults.reorder_ratio,
+                faults.min_delay.to_millis(),
+                faults.max_delay.to_millis()
+            );
+        };
+        if self.all_peers.is_none() && self.per_peer.is_empty() {
+            println!("No fault injected");
+        }
+        if let Some(faults) = &self.all_peers {
+            print_faults("All peers", faults);
+        }
+        for (node_id, faults) in &self.per_peer {
+            print_faults(&format!("Node {}", node_id), faults);
+        }
+    }
+}
+
 impl Output for Vec<OperationInfo> {
     fn pretty_print(&self) {
         for operation_info in self {
@@ -297,6 +420,14 @@ impl Output for Vec<OperationInfo> {
     }
 }
 
+impl Output for Vec<OperationStatusInfo> {
+    fn pretty_print(&self) {
+        for status_info in self {
+            println!("{}", status_info);
+        }
+    }
+}
+
 impl Output for Vec<BlockInfo> {
     fn pretty_print(&self) {
         for block_info in self {
@@ -335,6 +466,12 @@ impl Output for PubkeySig {
     }
 }
 
+impl Output for Slot {
+    fn pretty_print(&self) {
+        println!("{}", self);
+    }
+}
+
 impl Output for ExecuteReadOnlyResponse {
     fn pretty_print(&self) {
         println!("{}", self);
@@ -24,6 +24,8 @@ pub struct DefaultNode {
     pub ip: IpAddr,
     pub private_port: u16,
     pub public_port: u16,
+    pub api_port: u16,
+    pub private_api_token: Option<String>,
 }
 
 /// Http Client settings.
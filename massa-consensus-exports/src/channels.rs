@@ -3,12 +3,15 @@ use massa_models::block::{Block, BlockHeader, FilledBlock};
 use massa_pool_exports::PoolController;
 use massa_pos_exports::SelectorController;
 use massa_protocol_exports::ProtocolCommandSender;
+use massa_storage::{address_index::AddressIndex, archive::Archive};
 
 use crate::events::ConsensusEvent;
 
 /// Contains a reference to the pool, selector and execution controller
 /// Contains a channel to send info to protocol
 /// Contains channels to send info to api
+/// Contains the archive the final blocks are stored in, on archival nodes
+/// Contains the index of the final operations by address, if enabled
 #[derive(Clone)]
 pub struct ConsensusChannels {
     pub execution_controller: Box<dyn ExecutionController>,
@@ -19,4 +22,7 @@ pub struct ConsensusChannels {
     pub block_sender: tokio::sync::broadcast::Sender<Block>,
     pub block_header_sender: tokio::sync::broadcast::Sender<BlockHeader>,
     pub filled_block_sender: tokio::sync::broadcast::Sender<FilledBlock>,
+    pub final_block_sender: tokio::sync::broadcast::Sender<Block>,
+    pub archive: Option<Archive>,
+    pub address_index: Option<AddressIndex>,
 }
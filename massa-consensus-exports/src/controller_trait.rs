@@ -3,11 +3,14 @@ use crate::{bootstrapable_graph::BootstrapableGraph, error::ConsensusError};
 use massa_models::prehash::PreHashSet;
 use massa_models::streaming_step::StreamingStep;
 use massa_models::{
-    api::BlockGraphStatus,
+    address::Address,
+    api::{BlockGraphStatus, BlockValidityReport, ForkChoiceExplanation, ReorgAlert},
     block::{BlockHeader, BlockId},
     clique::Clique,
+    operation::WrappedOperation,
     slot::Slot,
-    stats::ConsensusStats,
+    state_commitment::StateDivergence,
+    stats::{ConsensusStats, CycleProductionStats},
     wrapped::Wrapped,
 };
 use massa_storage::Storage;
@@ -69,12 +72,56 @@ pub trait ConsensusController: Send + Sync {
     /// The stats of the consensus
     fn get_stats(&self) -> Result<ConsensusStats, ConsensusError>;
 
+    /// Get the block production stats of the last cycles
+    ///
+    /// # Arguments
+    /// * `addresses`: the addresses for which produced, expected and stale blocks are counted
+    ///
+    /// # Returns
+    /// The production stats of each kept cycle, from the oldest to the current one
+    fn get_production_stats(
+        &self,
+        addresses: &PreHashSet<Address>,
+    ) -> Result<Vec<CycleProductionStats>, ConsensusError>;
+
+    /// Check whether a candidate block would be accepted by the graph, without inserting it
+    ///
+    /// # Arguments
+    /// * `header`: the header of the candidate block
+    /// * `operations`: the operations of the candidate block, in block order
+    ///
+    /// # Returns
+    /// A report listing every reason why the block would be rejected or put on hold
+    fn check_block_validity(
+        &self,
+        header: Wrapped<BlockHeader, BlockId>,
+        operations: Vec<WrappedOperation>,
+    ) -> Result<BlockValidityReport, ConsensusError>;
+
+    /// Explain the fork choice status of a block
+    ///
+    /// # Arguments
+    /// * `block_id`: the block to explain
+    ///
+    /// # Returns
+    /// The cliques the block belongs to, why it is or is not in the blockclique and its distance from finality
+    fn explain_fork_choice(
+        &self,
+        block_id: BlockId,
+    ) -> Result<ForkChoiceExplanation, ConsensusError>;
+
     /// Get the best parents for the next block to be produced
     ///
     /// # Returns
     /// The id of best parents for the next block to be produced along with their period
     fn get_best_parents(&self) -> Vec<(BlockId, u64)>;
 
+    /// Get the latest final blocks
+    ///
+    /// # Returns
+    /// The id of the latest final block of each thread along with its period
+    fn get_latest_final_blocks_periods(&self) -> Vec<(BlockId, u64)>;
+
     /// Get the block id of the block at a specific slot in the blockclique
     ///
     /// # Arguments
@@ -116,6 +163,28 @@ pub trait ConsensusController: Send + Sync {
     /// * `header`: the header of the block to mark as invalid
     fn mark_invalid_block(&self, block_id: BlockId, header: Wrapped<BlockHeader, BlockId>);
 
+    /// Forget the blocks that are still waiting for their slot or for their dependencies,
+    /// so that they can be received again later. The other blocks are left untouched.
+    ///
+    /// # Arguments
+    /// * `block_ids`: the ids of the blocks to forget
+    fn drop_pending_blocks(&self, block_ids: Vec<BlockId>);
+
+    /// Get the blockclique switch currently rejected for being deeper than `max_reorg_depth_periods`
+    ///
+    /// # Returns
+    /// The alert raised for the rejected switch, if any
+    fn get_reorg_alert(&self) -> Option<ReorgAlert>;
+
+    /// Get the first divergence found between our final state and the one committed in a block header
+    ///
+    /// # Returns
+    /// The report of the divergence, if any
+    fn get_state_divergence(&self) -> Option<StateDivergence>;
+
+    /// Accept the currently rejected blockclique switch whatever its depth, once the next block is added to the graph
+    fn accept_reorg(&self);
+
     /// Returns a boxed clone of self.
     /// Useful to allow cloning `Box<dyn ConsensusController>`.
     fn clone_box(&self) -> Box<dyn ConsensusController>;
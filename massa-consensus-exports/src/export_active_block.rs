@@ -195,6 +195,7 @@ impl Deserializer<ExportActiveBlock> for ExportActiveBlockDeserializer {
     ///             )
     ///             .unwrap(),
     ///         ],
+    ///         state_commitment: None,
     ///     },
     ///     BlockHeaderSerializer::new(),
     ///     &keypair,
@@ -1,3 +1,6 @@
+use std::collections::BTreeMap;
+
+use massa_models::{block::BlockId, slot::Slot, upgrade_schedule::UpgradeSchedule};
 use massa_signature::KeyPair;
 use massa_time::MassaTime;
 use serde::{Deserialize, Serialize};
@@ -20,7 +23,7 @@ pub struct ConsensusConfig {
     pub max_future_processing_blocks: usize,
     /// Maximum number of blocks allowed in `DependencyWaitingBlocks`.
     pub max_dependency_blocks: usize,
-    /// max event send wait
+    /// max event send wait, also the max wait for room in the full command channel when sending a block command
     pub max_send_wait: MassaTime,
     /// old blocks are pruned every `block_db_prune_interval`
     pub block_db_prune_interval: MassaTime,
@@ -42,6 +45,17 @@ pub struct ConsensusConfig {
     pub end_timestamp: Option<MassaTime>,
     /// stats time span
     pub stats_timespan: MassaTime,
+    /// number of cycles for which block production stats are kept
+    pub stats_cycle_count: usize,
+    /// max number of operations per block
+    pub max_operations_per_block: u32,
+    /// operator-specified checkpoints: blocks conflicting with them are rejected
+    pub checkpoints: BTreeMap<Slot, BlockId>,
+    /// slots from which the protocol features are active: blocks using them before are rejected
+    pub upgrade_schedule: UpgradeSchedule,
+    /// max number of periods of the blockclique a switch to another clique can roll back,
+    /// deeper switches are rejected until the node administrator accepts them (0 to disable)
+    pub max_reorg_depth_periods: u64,
     /// channel size
     pub channel_size: usize,
     /// size of a consensus bootstrap streaming part
@@ -54,4 +68,6 @@ pub struct ConsensusConfig {
     pub broadcast_blocks_capacity: usize,
     /// filled blocks sender(channel) capacity
     pub broadcast_filled_blocks_capacity: usize,
+    /// final blocks sender(channel) capacity
+    pub broadcast_final_blocks_capacity: usize,
 }
@@ -1,10 +1,12 @@
 use massa_models::config::{
     constants::{
         CHANNEL_SIZE, DELTA_F0, ENDORSEMENT_COUNT, GENESIS_KEY, GENESIS_TIMESTAMP,
-        MAX_GAS_PER_BLOCK, OPERATION_VALIDITY_PERIODS, PERIODS_PER_CYCLE, T0, THREAD_COUNT,
+        MAX_GAS_PER_BLOCK, MAX_OPERATIONS_PER_BLOCK, OPERATION_VALIDITY_PERIODS, PERIODS_PER_CYCLE,
+        T0, THREAD_COUNT,
     },
     CONSENSUS_BOOTSTRAP_PART_SIZE,
 };
+use massa_models::upgrade_schedule::UpgradeSchedule;
 use massa_time::MassaTime;
 
 use crate::ConsensusConfig;
@@ -31,12 +33,18 @@ impl Default for ConsensusConfig {
             endorsement_count: ENDORSEMENT_COUNT,
             end_timestamp: None,
             stats_timespan: MassaTime::from_millis(60000),
+            stats_cycle_count: 5,
+            max_operations_per_block: MAX_OPERATIONS_PER_BLOCK,
+            checkpoints: Default::default(),
+            upgrade_schedule: UpgradeSchedule::all_active(),
+            max_reorg_depth_periods: 0,
             channel_size: CHANNEL_SIZE,
             bootstrap_part_size: CONSENSUS_BOOTSTRAP_PART_SIZE,
             broadcast_enabled: true,
             broadcast_blocks_headers_capacity: 128,
             broadcast_blocks_capacity: 128,
             broadcast_filled_blocks_capacity: 128,
+            broadcast_final_blocks_capacity: 128,
         }
     }
 }
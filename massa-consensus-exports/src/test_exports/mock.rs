@@ -6,12 +6,15 @@ use std::sync::{
 };
 
 use massa_models::{
-    api::BlockGraphStatus,
+    address::Address,
+    api::{BlockGraphStatus, BlockValidityReport, ForkChoiceExplanation, ReorgAlert},
     block::{BlockHeader, BlockId},
     clique::Clique,
+    operation::WrappedOperation,
     prehash::PreHashSet,
     slot::Slot,
-    stats::ConsensusStats,
+    state_commitment::StateDivergence,
+    stats::{ConsensusStats, CycleProductionStats},
     streaming_step::StreamingStep,
     wrapped::Wrapped,
 };
@@ -61,9 +64,25 @@ pub enum MockConsensusControllerMessage {
     GetStats {
         response_tx: mpsc::Sender<Result<ConsensusStats, ConsensusError>>,
     },
+    GetProductionStats {
+        addresses: PreHashSet<Address>,
+        response_tx: mpsc::Sender<Result<Vec<CycleProductionStats>, ConsensusError>>,
+    },
+    CheckBlockValidity {
+        header: Wrapped<BlockHeader, BlockId>,
+        operations: Vec<WrappedOperation>,
+        response_tx: mpsc::Sender<Result<BlockValidityReport, ConsensusError>>,
+    },
+    ExplainForkChoice {
+        block_id: BlockId,
+        response_tx: mpsc::Sender<Result<ForkChoiceExplanation, ConsensusError>>,
+    },
     GetBestParents {
         response_tx: mpsc::Sender<Vec<(BlockId, u64)>>,
     },
+    GetLatestFinalBlocksPeriods {
+        response_tx: mpsc::Sender<Vec<(BlockId, u64)>>,
+    },
     GetBlockcliqueBlockAtSlot {
         slot: Slot,
         response_tx: mpsc::Sender<Option<BlockId>>,
@@ -76,6 +95,9 @@ pub enum MockConsensusControllerMessage {
         block_id: BlockId,
         header: Wrapped<BlockHeader, BlockId>,
     },
+    DropPendingBlocks {
+        block_ids: Vec<BlockId>,
+    },
     RegisterBlock {
         block_id: BlockId,
         slot: Slot,
@@ -86,6 +108,13 @@ pub enum MockConsensusControllerMessage {
         block_id: BlockId,
         header: Wrapped<BlockHeader, BlockId>,
     },
+    GetReorgAlert {
+        response_tx: mpsc::Sender<Option<ReorgAlert>>,
+    },
+    AcceptReorg,
+    GetStateDivergence {
+        response_tx: mpsc::Sender<Option<StateDivergence>>,
+    },
 }
 
 /// A mocked graph controller that will intercept calls on its methods
@@ -202,6 +231,56 @@ impl ConsensusController for MockConsensusController {
         response_rx.recv().unwrap()
     }
 
+    fn get_production_stats(
+        &self,
+        addresses: &PreHashSet<Address>,
+    ) -> Result<Vec<CycleProductionStats>, ConsensusError> {
+        let (response_tx, response_rx) = mpsc::channel();
+        self.0
+            .lock()
+            .unwrap()
+            .send(MockConsensusControllerMessage::GetProductionStats {
+                addresses: addresses.clone(),
+                response_tx,
+            })
+            .unwrap();
+        response_rx.recv().unwrap()
+    }
+
+    fn check_block_validity(
+        &self,
+        header: Wrapped<BlockHeader, BlockId>,
+        operations: Vec<WrappedOperation>,
+    ) -> Result<BlockValidityReport, ConsensusError> {
+        let (response_tx, response_rx) = mpsc::channel();
+        self.0
+            .lock()
+            .unwrap()
+            .send(MockConsensusControllerMessage::CheckBlockValidity {
+                header,
+                operations,
+                response_tx,
+            })
+            .unwrap();
+        response_rx.recv().unwrap()
+    }
+
+    fn explain_fork_choice(
+        &self,
+        block_id: BlockId,
+    ) -> Result<ForkChoiceExplanation, ConsensusError> {
+        let (response_tx, response_rx) = mpsc::channel();
+        self.0
+            .lock()
+            .unwrap()
+            .send(MockConsensusControllerMessage::ExplainForkChoice {
+                block_id,
+                response_tx,
+            })
+            .unwrap();
+        response_rx.recv().unwrap()
+    }
+
     fn get_best_parents(&self) -> Vec<(BlockId, u64)> {
         let (response_tx, response_rx) = mpsc::channel();
         self.0
@@ -212,6 +291,16 @@ impl ConsensusController for MockConsensusController {
         response_rx.recv().unwrap()
     }
 
+    fn get_latest_final_blocks_periods(&self) -> Vec<(BlockId, u64)> {
+        let (response_tx, response_rx) = mpsc::channel();
+        self.0
+            .lock()
+            .unwrap()
+            .send(MockConsensusControllerMessage::GetLatestFinalBlocksPeriods { response_tx })
+            .unwrap();
+        response_rx.recv().unwrap()
+    }
+
     fn get_blockclique_block_at_slot(&self, slot: Slot) -> Option<BlockId> {
         let (response_tx, response_rx) = mpsc::channel();
         self.0
@@ -245,6 +334,14 @@ impl ConsensusController for MockConsensusController {
             .unwrap();
     }
 
+    fn drop_pending_blocks(&self, block_ids: Vec<BlockId>) {
+        self.0
+            .lock()
+            .unwrap()
+            .send(MockConsensusControllerMessage::DropPendingBlocks { block_ids })
+            .unwrap();
+    }
+
     fn register_block(&self, block_id: BlockId, slot: Slot, block_storage: Storage, created: bool) {
         self.0
             .lock()
@@ -266,6 +363,34 @@ impl ConsensusController for MockConsensusController {
             .unwrap();
     }
 
+    fn get_reorg_alert(&self) -> Option<ReorgAlert> {
+        let (response_tx, response_rx) = mpsc::channel();
+        self.0
+            .lock()
+            .unwrap()
+            .send(MockConsensusControllerMessage::GetReorgAlert { response_tx })
+            .unwrap();
+        response_rx.recv().unwrap()
+    }
+
+    fn get_state_divergence(&self) -> Option<StateDivergence> {
+        let (response_tx, response_rx) = mpsc::channel();
+        self.0
+            .lock()
+            .unwrap()
+            .send(MockConsensusControllerMessage::GetStateDivergence { response_tx })
+            .unwrap();
+        response_rx.recv().unwrap()
+    }
+
+    fn accept_reorg(&self) {
+        self.0
+            .lock()
+            .unwrap()
+            .send(MockConsensusControllerMessage::AcceptReorg)
+            .unwrap();
+    }
+
     fn clone_box(&self) -> Box<dyn ConsensusController> {
         Box::new(self.clone())
     }
@@ -5,9 +5,13 @@ use massa_models::{
 };
 use massa_storage::Storage;
 
+/// The blocks and headers are registered in the span they were received in,
+/// so that their processing by the consensus is traced as part of their path through the node
 #[allow(clippy::large_enum_variant)]
 pub enum ConsensusCommand {
-    RegisterBlock(BlockId, Slot, Storage, bool),
-    RegisterBlockHeader(BlockId, Wrapped<BlockHeader, BlockId>),
+    RegisterBlock(BlockId, Slot, Storage, bool, tracing::Span),
+    RegisterBlockHeader(BlockId, Wrapped<BlockHeader, BlockId>, tracing::Span),
     MarkInvalidBlock(BlockId, Wrapped<BlockHeader, BlockId>),
+    DropPendingBlocks(Vec<BlockId>),
+    AcceptReorg,
 }
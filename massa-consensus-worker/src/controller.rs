@@ -1,25 +1,32 @@
+use crossbeam_channel::{Sender, TrySendError};
 use massa_consensus_exports::{
     block_graph_export::BlockGraphExport, block_status::BlockStatus,
     bootstrapable_graph::BootstrapableGraph, error::ConsensusError,
     export_active_block::ExportActiveBlock, ConsensusChannels, ConsensusController,
 };
+use massa_metrics::CONSENSUS_METRICS;
 use massa_models::{
-    api::BlockGraphStatus,
+    address::Address,
+    api::{BlockGraphStatus, BlockValidityReport, ForkChoiceExplanation, ReorgAlert},
     block::{BlockHeader, BlockId, FilledBlock},
     clique::Clique,
-    operation::{Operation, OperationId},
+    operation::{Operation, OperationId, WrappedOperation},
     prehash::PreHashSet,
     slot::Slot,
-    stats::ConsensusStats,
+    state_commitment::StateDivergence,
+    stats::{ConsensusStats, CycleProductionStats},
     streaming_step::StreamingStep,
     wrapped::Wrapped,
 };
 use massa_storage::Storage;
 use parking_lot::RwLock;
-use std::sync::{mpsc::SyncSender, Arc};
+use std::{sync::Arc, time::Duration};
 use tracing::log::warn;
 
-use crate::{commands::ConsensusCommand, state::ConsensusState};
+use crate::{
+    commands::ConsensusCommand,
+    state::{check_block_candidate_content, ConsensusState},
+};
 
 /// The retrieval of data is made using a shared state and modifications are asked by sending message to a channel.
 /// This is done mostly to be able to:
@@ -28,22 +35,28 @@ use crate::{commands::ConsensusCommand, state::ConsensusState};
 /// - still be able to read the current state of the graph as processed so far (for this we need a shared state)
 ///
 /// Note that sending commands and reading the state is done from different, mutually-asynchronous tasks and they can have data that are not sync yet.
+///
+/// Block commands are consensus-critical: when the command channel is full, the sender waits for room
+/// (at most `max_send_wait`, as the consensus worker may itself be waiting for protocol to take its commands),
+/// slowing protocol down instead of dropping them.
 #[derive(Clone)]
 pub struct ConsensusControllerImpl {
-    command_sender: SyncSender<ConsensusCommand>,
+    command_sender: Sender<ConsensusCommand>,
     channels: ConsensusChannels,
     shared_state: Arc<RwLock<ConsensusState>>,
     bootstrap_part_size: u64,
     broadcast_enabled: bool,
+    max_send_wait: Duration,
 }
 
 impl ConsensusControllerImpl {
     pub fn new(
-        command_sender: SyncSender<ConsensusCommand>,
+        command_sender: Sender<ConsensusCommand>,
         channels: ConsensusChannels,
         shared_state: Arc<RwLock<ConsensusState>>,
         bootstrap_part_size: u64,
         broadcast_enabled: bool,
+        max_send_wait: Duration,
     ) -> Self {
         Self {
             command_sender,
@@ -51,8 +64,27 @@ impl ConsensusControllerImpl {
             shared_state,
             bootstrap_part_size,
             broadcast_enabled,
+            max_send_wait,
         }
     }
+
+    /// Send a block command, waiting for room at most `max_send_wait` if the channel is full
+    fn send_block_command(&self, command: ConsensusCommand) -> Result<(), String> {
+        let command = match self.command_sender.try_send(command) {
+            Ok(()) => return Ok(()),
+            Err(TrySendError::Full(command)) => command,
+            Err(TrySendError::Disconnected(_)) => return Err("channel disconnected".into()),
+        };
+        CONSENSUS_METRICS.saturated_commands.inc();
+        self.command_sender
+            .send_timeout(command, self.max_send_wait)
+            .map_err(|err| {
+                if err.is_timeout() {
+                    CONSENSUS_METRICS.dropped_commands.inc();
+                }
+                err.to_string()
+            })
+    }
 }
 
 impl ConsensusController for ConsensusControllerImpl {
@@ -191,6 +223,67 @@ impl ConsensusController for ConsensusControllerImpl {
         self.shared_state.read().get_stats()
     }
 
+    /// Get the block production stats of the last cycles
+    fn get_production_stats(
+        &self,
+        addresses: &PreHashSet<Address>,
+    ) -> Result<Vec<CycleProductionStats>, ConsensusError> {
+        Ok(self.shared_state.read().get_production_stats(addresses))
+    }
+
+    /// Check a candidate block against the current state of the graph without inserting it
+    ///
+    /// # Arguments:
+    /// * `header`: the header of the candidate block
+    /// * `operations`: the operations of the candidate block, in block order
+    ///
+    /// # Returns:
+    /// A report with all the reasons why the block would be rejected, empty if it is valid
+    fn check_block_validity(
+        &self,
+        header: Wrapped<BlockHeader, BlockId>,
+        operations: Vec<WrappedOperation>,
+    ) -> Result<BlockValidityReport, ConsensusError> {
+        // only the checks of the graph hold the lock: the signatures are verified once it is released
+        let (config, mut rejection_reasons) = {
+            let read_shared_state = self.shared_state.read();
+            (
+                read_shared_state.config.clone(),
+                read_shared_state.check_block_candidate_in_graph(&header)?,
+            )
+        };
+        rejection_reasons.extend(check_block_candidate_content(&config, &header, &operations));
+
+        let op_ids: PreHashSet<OperationId> = operations.iter().map(|op| op.id).collect();
+        let unexecuted_ops = self
+            .channels
+            .execution_controller
+            .unexecuted_ops_among(&op_ids, header.content.slot.thread);
+        for op_id in op_ids.difference(&unexecuted_ops) {
+            rejection_reasons.push(format!("operation {} was already executed", op_id));
+        }
+
+        Ok(BlockValidityReport {
+            block_id: header.id,
+            is_valid: rejection_reasons.is_empty(),
+            rejection_reasons,
+        })
+    }
+
+    /// Explain the fork choice status of a block
+    ///
+    /// # Arguments:
+    /// * `block_id`: the block to explain
+    ///
+    /// # Returns:
+    /// The cliques the block belongs to, why it is or is not in the blockclique and its distance from finality
+    fn explain_fork_choice(
+        &self,
+        block_id: BlockId,
+    ) -> Result<ForkChoiceExplanation, ConsensusError> {
+        self.shared_state.read().explain_fork_choice(&block_id)
+    }
+
     /// Get the current best parents for a block creation
     ///
     /// # Returns:
@@ -199,6 +292,14 @@ impl ConsensusController for ConsensusControllerImpl {
         self.shared_state.read().best_parents.clone()
     }
 
+    /// Get the latest final blocks
+    ///
+    /// # Returns:
+    /// The id of the latest final block of each thread along with its period
+    fn get_latest_final_blocks_periods(&self) -> Vec<(BlockId, u64)> {
+        self.shared_state.read().latest_final_blocks_periods.clone()
+    }
+
     /// Get the block, that is in the blockclique, at a given slot.
     ///
     /// # Arguments:
@@ -258,15 +359,13 @@ impl ConsensusController for ConsensusControllerImpl {
             };
         }
 
-        if let Err(err) = self
-            .command_sender
-            .try_send(ConsensusCommand::RegisterBlock(
-                block_id,
-                slot,
-                block_storage,
-                created,
-            ))
-        {
+        if let Err(err) = self.send_block_command(ConsensusCommand::RegisterBlock(
+            block_id,
+            slot,
+            block_storage,
+            created,
+            tracing::Span::current(),
+        )) {
             warn!("error trying to register a block: {}", err);
         }
     }
@@ -278,23 +377,43 @@ impl ConsensusController for ConsensusControllerImpl {
                 .block_header_sender
                 .send(header.clone().content);
         }
-        if let Err(err) = self
-            .command_sender
-            .try_send(ConsensusCommand::RegisterBlockHeader(block_id, header))
-        {
+        if let Err(err) = self.send_block_command(ConsensusCommand::RegisterBlockHeader(
+            block_id,
+            header,
+            tracing::Span::current(),
+        )) {
             warn!("error trying to register a block header: {}", err);
         }
     }
 
     fn mark_invalid_block(&self, block_id: BlockId, header: Wrapped<BlockHeader, BlockId>) {
-        if let Err(err) = self
-            .command_sender
-            .try_send(ConsensusCommand::MarkInvalidBlock(block_id, header))
+        if let Err(err) =
+            self.send_block_command(ConsensusCommand::MarkInvalidBlock(block_id, header))
         {
             warn!("error trying to mark block as invalid: {}", err);
         }
     }
 
+    fn drop_pending_blocks(&self, block_ids: Vec<BlockId>) {
+        if let Err(err) = self.send_block_command(ConsensusCommand::DropPendingBlocks(block_ids)) {
+            warn!("error trying to drop pending blocks: {}", err);
+        }
+    }
+
+    fn get_reorg_alert(&self) -> Option<ReorgAlert> {
+        self.shared_state.read().reorg_alert
+    }
+
+    fn get_state_divergence(&self) -> Option<StateDivergence> {
+        self.shared_state.read().state_divergence.clone()
+    }
+
+    fn accept_reorg(&self) {
+        if let Err(err) = self.send_block_command(ConsensusCommand::AcceptReorg) {
+            warn!("error trying to accept a reorganization: {}", err);
+        }
+    }
+
     fn clone_box(&self) -> Box<dyn ConsensusController> {
         Box::new(self.clone())
     }
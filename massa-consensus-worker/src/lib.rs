@@ -12,7 +12,6 @@
 //!
 //! This module doesn't use asynchronous code.
 
-#![feature(deadline_api)]
 #![feature(let_chains)]
 
 mod commands;
@@ -21,4 +20,7 @@ mod manager;
 mod state;
 mod worker;
 
-pub use worker::start_consensus_worker;
+#[cfg(test)]
+mod tests;
+
+pub use worker::{create_genesis_block, start_consensus_worker};
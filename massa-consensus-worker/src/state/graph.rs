@@ -5,7 +5,14 @@ use massa_consensus_exports::{
     error::ConsensusError,
 };
 use massa_logging::massa_trace;
-use massa_models::{block::BlockId, clique::Clique, prehash::PreHashSet, slot::Slot};
+use massa_models::{
+    api::{CliqueFitness, ForkChoiceExplanation, ReorgAlert},
+    block::BlockId,
+    clique::Clique,
+    prehash::PreHashSet,
+    slot::Slot,
+};
+use tracing::{error, info, warn};
 
 use super::ConsensusState;
 
@@ -51,6 +58,7 @@ impl ConsensusState {
     ) -> Result<usize, ConsensusError> {
         let mut blockclique_i = 0usize;
         let mut max_clique_fitness = (0u64, num::BigInt::default());
+        let mut clique_fitnesses = Vec::with_capacity(self.max_cliques.len());
         for (clique_i, clique) in self.max_cliques.iter_mut().enumerate() {
             clique.fitness = 0;
             clique.is_blockclique = false;
@@ -69,13 +77,118 @@ impl ConsensusState {
             let cur_fit = (clique.fitness, sum_hash);
             if cur_fit > max_clique_fitness {
                 blockclique_i = clique_i;
-                max_clique_fitness = cur_fit;
+                max_clique_fitness = cur_fit.clone();
             }
+            clique_fitnesses.push(cur_fit);
+        }
+        if let Some(kept_i) = self.check_reorg_depth(blockclique_i, &clique_fitnesses)? {
+            blockclique_i = kept_i;
         }
         self.max_cliques[blockclique_i].is_blockclique = true;
         Ok(blockclique_i)
     }
 
+    /// Get the number of periods of the blockclique notified to execution that a switch to `clique` rolls back,
+    /// along with the slot of the earliest block it drops. None if it drops no block.
+    fn get_reorg_depth(&self, clique: &PreHashSet<BlockId>) -> Option<(u64, Slot)> {
+        let head_period = self
+            .prev_blockclique
+            .values()
+            .map(|slot| slot.period)
+            .max()?;
+        let fork_slot = self
+            .prev_blockclique
+            .iter()
+            .filter(|(block_id, _)| {
+                !clique.contains(block_id)
+                    && matches!(
+                        self.block_statuses.get(block_id),
+                        Some(BlockStatus::Active { a_block, .. }) if !a_block.is_final
+                    )
+            })
+            .map(|(_, slot)| *slot)
+            .min()?;
+        Some((head_period.saturating_sub(fork_slot.period) + 1, fork_slot))
+    }
+
+    /// Check that the switch to the heaviest clique, at `blockclique_i`, does not roll back more than
+    /// `max_reorg_depth_periods` periods of the blockclique notified to execution.
+    ///
+    /// A deeper switch is rejected unless the node administrator accepted it: the heaviest clique within the limit
+    /// is kept as the blockclique instead, and a reorg alert is raised until no switch is rejected anymore.
+    ///
+    /// # Arguments
+    /// * `blockclique_i`: index of the heaviest clique
+    /// * `clique_fitnesses`: fitness of each clique, compared as when looking for the heaviest one
+    ///
+    /// # Returns
+    /// The index of the clique to keep as blockclique, if the switch is rejected
+    fn check_reorg_depth(
+        &mut self,
+        blockclique_i: usize,
+        clique_fitnesses: &[(u64, num::BigInt)],
+    ) -> Result<Option<usize>, ConsensusError> {
+        let max_depth = self.config.max_reorg_depth_periods;
+        if max_depth == 0 {
+            return Ok(None);
+        }
+        let (depth_periods, fork_slot) = match self
+            .get_reorg_depth(&self.max_cliques[blockclique_i].block_ids)
+        {
+            Some((depth_periods, fork_slot)) if depth_periods > max_depth => {
+                (depth_periods, fork_slot)
+            }
+            _ => {
+                self.reorg_accepted = false;
+                if self.reorg_alert.take().is_some() {
+                    info!("no blockclique switch is rejected for its depth anymore, reorg alert cleared");
+                }
+                return Ok(None);
+            }
+        };
+        if self.reorg_accepted {
+            warn!(
+                "switching the blockclique despite a reorganization of {} periods from slot {}, as accepted by the node administrator",
+                depth_periods, fork_slot
+            );
+            self.reorg_accepted = false;
+            self.reorg_alert = None;
+            return Ok(None);
+        }
+        let kept_i = match self
+            .max_cliques
+            .iter()
+            .enumerate()
+            .filter(|(_, clique)| {
+                self.get_reorg_depth(&clique.block_ids)
+                    .map_or(true, |(depth, _)| depth <= max_depth)
+            })
+            .max_by(|(a, _), (b, _)| clique_fitnesses[*a].cmp(&clique_fitnesses[*b]))
+        {
+            Some((kept_i, _)) => kept_i,
+            None => return Ok(None),
+        };
+        let since = match &self.reorg_alert {
+            Some(alert) => alert.since,
+            None => self.slot_scheduler.now()?,
+        };
+        let alert = ReorgAlert {
+            depth_periods,
+            fork_slot,
+            rejected_fitness: self.max_cliques[blockclique_i].fitness,
+            kept_fitness: self.max_cliques[kept_i].fitness,
+            since,
+        };
+        if self.reorg_alert.map(|prev| prev.depth_periods) != Some(depth_periods) {
+            error!(
+                "REORG ALERT: {}: possible long-range attack or local corruption, keeping the current blockclique until the node administrator accepts the switch",
+                alert
+            );
+        }
+        self.reorg_alert = Some(alert);
+        Ok(Some(kept_i))
+    }
+
     pub fn list_stale_blocks(&self, fitness_threshold: u64) -> PreHashSet<BlockId> {
         // iterate from largest to smallest to minimize reallocations
         let mut indices: Vec<usize> = (0..self.max_cliques.len()).collect();
@@ -303,6 +416,152 @@ impl ConsensusState {
             .clone()
     }
 
+    /// Total fitness of the active blocks of `block_ids` within `clique`
+    fn get_fitness_within_clique(&self, block_ids: &PreHashSet<BlockId>, clique: &Clique) -> u64 {
+        block_ids
+            .intersection(&clique.block_ids)
+            .map(|h| match self.block_statuses.get(h) {
+                Some(BlockStatus::Active { a_block, .. }) => a_block.fitness,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    /// Explain the fork choice status of a block: the cliques it belongs to,
+    /// why it is or is not in the blockclique and how far it is from finality
+    pub fn explain_fork_choice(
+        &self,
+        block_id: &BlockId,
+    ) -> Result<ForkChoiceExplanation, ConsensusError> {
+        let clique_fitness = |(index, clique): (usize, &Clique)| CliqueFitness {
+            index,
+            fitness: clique.fitness,
+            block_count: clique.block_ids.len(),
+            is_blockclique: clique.is_blockclique,
+        };
+        let blockclique = self
+            .max_cliques
+            .iter()
+            .enumerate()
+            .find(|(_, clique)| clique.is_blockclique)
+            .map(clique_fitness)
+            .ok_or_else(|| {
+                ConsensusError::ContainerInconsistency("blockclique missing".to_string())
+            })?;
+        let cliques: Vec<CliqueFitness> = self
+            .max_cliques
+            .iter()
+            .enumerate()
+            .filter(|(_, clique)| clique.block_ids.contains(block_id))
+            .map(clique_fitness)
+            .collect();
+        let mut incompatible_blockclique_blocks: Vec<BlockId> = self
+            .gi_head
+            .get(block_id)
+            .map(|incompatibles| {
+                incompatibles
+                    .intersection(&self.max_cliques[blockclique.index].block_ids)
+                    .copied()
+                    .collect()
+            })
+            .unwrap_or_default();
+        incompatible_blockclique_blocks.sort_unstable();
+        let status = self.get_block_status(block_id);
+
+        let mut fitness = None;
+        let mut descendants_fitness = None;
+        let mut explanation = Vec::new();
+        match self.block_statuses.get(block_id) {
+            None => explanation.push("the block is unknown to this node".to_string()),
+            Some(BlockStatus::Incoming(_))
+            | Some(BlockStatus::WaitingForSlot(_))
+            | Some(BlockStatus::WaitingForDependencies { .. }) => explanation.push(format!(
+                "the block was not added to the graph yet ({:?}), it does not take part in the fork choice",
+                status
+            )),
+            Some(BlockStatus::Discarded { reason, .. }) => explanation.push(match reason {
+                DiscardReason::Invalid(reason) => format!("the block was discarded as invalid: {}", reason),
+                DiscardReason::Stale => "the block was discarded as stale: it was incompatible with a final block or its cliques fell too far behind the blockclique".to_string(),
+                DiscardReason::Final => "the block is final and was pruned from the active graph".to_string(),
+            }),
+            Some(BlockStatus::Active { a_block, .. }) => {
+                fitness = Some(a_block.fitness);
+                if a_block.is_final {
+                    explanation.push("the block is final".to_string());
+                } else {
+                    // why the block is or is not in the blockclique
+                    let best_clique = cliques
+                        .iter()
+                        .find(|clique| clique.is_blockclique)
+                        .or_else(|| cliques.iter().max_by_key(|clique| clique.fitness))
+                        .ok_or_else(|| {
+                            ConsensusError::ContainerInconsistency(format!(
+                                "active block {} without clique",
+                                block_id
+                            ))
+                        })?;
+                    if best_clique.is_blockclique {
+                        explanation.push(format!(
+                            "the block belongs to the blockclique, the clique of highest fitness ({})",
+                            blockclique.fitness
+                        ));
+                    } else {
+                        explanation.push(format!(
+                            "the best clique of the block has fitness {} but the blockclique has fitness {}",
+                            best_clique.fitness, blockclique.fitness
+                        ));
+                        if best_clique.fitness == blockclique.fitness {
+                            explanation.push("fitness ties are broken in favor of the clique with the lowest sum of block hashes".to_string());
+                        }
+                        if !incompatible_blockclique_blocks.is_empty() {
+                            explanation.push(format!(
+                                "the block is incompatible with {} blocks of the blockclique",
+                                incompatible_blockclique_blocks.len()
+                            ));
+                        }
+                        explanation.push(format!(
+                            "the block becomes stale if the fitness of its best clique falls more than {} behind the blockclique",
+                            self.config.delta_f0
+                        ));
+                    }
+
+                    // distance from finality
+                    if cliques.len() < self.max_cliques.len() {
+                        explanation.push(format!(
+                            "the block is only in {} of {} cliques, it cannot become final before the other cliques are discarded",
+                            cliques.len(),
+                            self.max_cliques.len()
+                        ));
+                    }
+                    let desc_fit = self.get_fitness_within_clique(
+                        &a_block.descendants,
+                        &self.max_cliques[best_clique.index],
+                    );
+                    descendants_fitness = Some(desc_fit);
+                    explanation.push(format!(
+                        "its descendants have a fitness of {} in its best clique, finality requires more than {} ({} missing)",
+                        desc_fit,
+                        self.config.delta_f0,
+                        (self.config.delta_f0 + 1).saturating_sub(desc_fit)
+                    ));
+                }
+            }
+        }
+
+        Ok(ForkChoiceExplanation {
+            block_id: *block_id,
+            status,
+            fitness,
+            cliques,
+            blockclique,
+            clique_count: self.max_cliques.len(),
+            incompatible_blockclique_blocks,
+            descendants_fitness,
+            finality_threshold: self.config.delta_f0,
+            explanation,
+        })
+    }
+
     pub fn mark_final_blocks(
         &mut self,
         add_block_id: &BlockId,
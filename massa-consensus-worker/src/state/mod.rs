@@ -12,11 +12,13 @@ use massa_consensus_exports::{
 use massa_models::{
     active_block::ActiveBlock,
     address::Address,
-    api::BlockGraphStatus,
+    api::{BlockGraphStatus, ReorgAlert},
     block::{BlockId, WrappedHeader},
     clique::Clique,
     prehash::{CapacityAllocator, PreHashMap, PreHashSet},
     slot::Slot,
+    slot_scheduler::SlotScheduler,
+    state_commitment::StateDivergence,
 };
 use massa_storage::Storage;
 use massa_time::MassaTime;
@@ -30,6 +32,9 @@ mod stats;
 mod tick;
 mod verifications;
 
+use stats::CycleStats;
+pub use verifications::check_block_candidate_content;
+
 #[derive(Clone)]
 pub struct ConsensusState {
     /// Configuration
@@ -85,10 +90,22 @@ pub struct ConsensusState {
     pub stats_history_timespan: MassaTime,
     /// the time span considered for desynchronization detection
     pub stats_desync_detection_timespan: MassaTime,
+    /// block production stats of the last `stats_cycle_count` cycles
+    pub cycle_stats: VecDeque<CycleStats>,
+    /// last slot accounted in `cycle_stats`
+    pub cycle_stats_cursor: Option<Slot>,
     /// blocks we want
     pub wishlist: PreHashMap<BlockId, Option<WrappedHeader>>,
     /// previous blockclique notified to Execution
     pub prev_blockclique: PreHashMap<BlockId, Slot>,
+    /// blockclique switch currently rejected for being deeper than `max_reorg_depth_periods`
+    pub reorg_alert: Option<ReorgAlert>,
+    /// the node administrator accepted the next blockclique switch whatever its depth
+    pub reorg_accepted: bool,
+    /// first divergence found between our final state and the one committed in a block header
+    pub state_divergence: Option<StateDivergence>,
+    /// source of time of the slots
+    pub slot_scheduler: Box<dyn SlotScheduler>,
 }
 
 impl ConsensusState {
@@ -8,18 +8,19 @@ use massa_consensus_exports::{
     error::ConsensusError,
 };
 use massa_logging::massa_trace;
+use massa_metrics::CONSENSUS_METRICS;
 use massa_models::{
     active_block::ActiveBlock,
     address::Address,
-    block::{BlockId, WrappedHeader},
+    block::{ArchivedBlock, BlockId, WrappedHeader},
     clique::Clique,
+    operation::WrappedOperation,
     prehash::{PreHashMap, PreHashSet},
     slot::Slot,
 };
 use massa_signature::PublicKey;
 use massa_storage::Storage;
-use massa_time::MassaTime;
-use tracing::log::{debug, info};
+use tracing::log::{debug, info, warn};
 
 use crate::state::verifications::HeaderCheckOutcome;
 
@@ -54,6 +55,7 @@ impl ConsensusState {
     ///
     /// # Returns:
     /// A list of items to re-ack and process or an error if the process of an item failed
+    #[tracing::instrument(level = "debug", skip_all, fields(block_id = %block_id))]
     pub fn process(
         &mut self,
         block_id: BlockId,
@@ -238,6 +240,7 @@ impl ConsensusState {
                         massa_trace!("consensus.block_graph.process.incoming_block.valid", {
                             "block_id": block_id
                         });
+                        self.check_state_commitment(&block_id, &stored_block.content.header)?;
                         (
                             stored_block.content.header.creator_public_key,
                             slot,
@@ -477,6 +480,7 @@ impl ConsensusState {
     /// # Returns:
     /// Success or error if any steps failed
     #[allow(clippy::too_many_arguments)]
+    #[tracing::instrument(level = "debug", skip_all, fields(block_id = %add_block_id, slot = %add_block_slot))]
     fn add_block_to_graph(
         &mut self,
         add_block_id: BlockId,
@@ -647,6 +651,7 @@ impl ConsensusState {
         );
         self.mark_final_blocks(&add_block_id, final_blocks)?;
 
+        CONSENSUS_METRICS.processed_blocks.inc();
         massa_trace!("consensus.block_graph.add_block_to_graph.end", {});
         Ok(())
     }
@@ -756,19 +761,33 @@ impl ConsensusState {
     /// 9. notify protocol of block wish list
     /// 10. note new latest final periods (prune graph if changed)
     /// 11. add stale blocks to stats
+    #[tracing::instrument(level = "debug", skip_all)]
     pub fn block_db_changed(&mut self) -> Result<(), ConsensusError> {
         let final_block_slots = {
             massa_trace!("consensus.consensus_worker.block_db_changed", {});
 
-            // Propagate new blocks
+            // Propagate new blocks, and notify pool of the operations they include
+            let mut included_operations = Vec::with_capacity(self.to_propagate.len());
             for (block_id, storage) in mem::take(&mut self.to_propagate).into_iter() {
                 massa_trace!("consensus.consensus_worker.block_db_changed.integrated", {
                     "block_id": block_id
                 });
+                if let Some(block) = storage.read_blocks().get(&block_id) {
+                    included_operations.push((
+                        block_id,
+                        block.content.header.content.slot,
+                        block.content.operations.clone(),
+                    ));
+                }
                 self.channels
                     .protocol_command_sender
                     .integrated_block(block_id, storage)?;
             }
+            if !included_operations.is_empty() {
+                self.channels
+                    .pool_command_sender
+                    .notify_included_operations(included_operations);
+            }
 
             // Notify protocol of attack attempts.
             for hash in mem::take(&mut self.attack_attempts).into_iter() {
@@ -781,19 +800,83 @@ impl ConsensusState {
             }
 
             // manage finalized blocks
-            let timestamp = MassaTime::now()?;
+            let timestamp = self.slot_scheduler.now()?;
             let finalized_blocks = mem::take(&mut self.new_final_blocks);
             let mut final_block_slots = HashMap::with_capacity(finalized_blocks.len());
             let mut final_block_stats = VecDeque::with_capacity(finalized_blocks.len());
+            let mut final_block_production = Vec::with_capacity(finalized_blocks.len());
+            let mut final_operations = Vec::with_capacity(finalized_blocks.len());
             for b_id in finalized_blocks {
-                if let Some(BlockStatus::Active {
-                    a_block,
-                    storage: _,
-                }) = self.block_statuses.get(&b_id)
+                if let Some(BlockStatus::Active { a_block, storage }) =
+                    self.block_statuses.get(&b_id)
                 {
+                    tracing::debug!(block_id = %b_id, slot = %a_block.slot, "block finalized");
+
                     // add to final blocks to notify execution
                     final_block_slots.insert(a_block.slot, b_id);
 
+                    // add to production stats and to the final operations to notify pool,
+                    // and broadcast the final block
+                    let operation_ids = match storage.read_blocks().get(&b_id) {
+                        Some(block) => {
+                            if self.config.broadcast_enabled {
+                                let _ =
+                                    self.channels.final_block_sender.send(block.content.clone());
+                            }
+                            if self.channels.archive.is_some()
+                                || self.channels.address_index.is_some()
+                            {
+                                let read_operations = storage.read_operations();
+                                let operations: Option<Vec<WrappedOperation>> = block
+                                    .content
+                                    .operations
+                                    .iter()
+                                    .map(|op_id| read_operations.get(op_id).cloned())
+                                    .collect();
+                                match operations {
+                                    Some(operations) => {
+                                        if let Some(address_index) = &self.channels.address_index {
+                                            if let Err(err) = address_index.index_block(
+                                                &b_id,
+                                                &a_block.slot,
+                                                &operations,
+                                            ) {
+                                                warn!(
+                                                    "could not index the operations of final block {}: {}",
+                                                    b_id, err
+                                                );
+                                            }
+                                        }
+                                        if let Some(archive) = &self.channels.archive {
+                                            if let Err(err) = archive.store_block(&ArchivedBlock {
+                                                header: block.content.header.clone(),
+                                                operations,
+                                            }) {
+                                                warn!(
+                                                    "could not archive final block {}: {}",
+                                                    b_id, err
+                                                );
+                                            }
+                                        }
+                                    }
+                                    None => warn!(
+                                        "could not archive or index final block {}: some of its operations are missing",
+                                        b_id
+                                    ),
+                                }
+                            }
+                            block.content.operations.clone()
+                        }
+                        None => Vec::new(),
+                    };
+                    let operation_count = operation_ids.len();
+                    final_operations.push((b_id, a_block.slot, operation_ids));
+                    final_block_production.push((
+                        a_block.slot,
+                        a_block.creator_address,
+                        operation_count,
+                    ));
+
                     // add to stats
                     let block_is_from_protocol = self
                         .protocol_blocks
@@ -807,12 +890,22 @@ impl ConsensusState {
                 }
             }
             self.final_block_stats.extend(final_block_stats);
+            for (slot, creator, operation_count) in final_block_production {
+                self.note_final_block(slot, creator, operation_count);
+            }
+            // sent before the new final periods so that the pool does not consider final operations as expired
+            if !final_operations.is_empty() {
+                self.channels
+                    .pool_command_sender
+                    .notify_final_operations(final_operations);
+            }
 
             // add stale blocks to stats
             let new_stale_block_ids_creators_slots = mem::take(&mut self.new_stale_blocks);
-            let timestamp = MassaTime::now()?;
-            for (_b_id, (_b_creator, _b_slot)) in new_stale_block_ids_creators_slots.into_iter() {
+            let timestamp = self.slot_scheduler.now()?;
+            for (_b_id, (b_creator, b_slot)) in new_stale_block_ids_creators_slots.into_iter() {
                 self.stale_block_stats.push_back(timestamp);
+                self.note_stale_block(b_slot, b_creator);
             }
             final_block_slots
         };
@@ -10,7 +10,6 @@ use massa_models::{
     slot::Slot,
 };
 use massa_storage::Storage;
-use massa_time::MassaTime;
 use tracing::debug;
 
 use super::ConsensusState;
@@ -41,6 +40,9 @@ impl ConsensusState {
             block_id, header.content.slot
         );
         massa_trace!("consensus.block_graph.incoming_header", {"block_id": block_id, "header": header});
+        if !self.block_statuses.contains_key(&block_id) {
+            self.note_block_latency(header.content.slot)?;
+        }
         let mut to_ack: BTreeSet<(Slot, BlockId)> = BTreeSet::new();
         match self.block_statuses.entry(block_id) {
             // if absent => add as Incoming, call rec_ack on it
@@ -97,11 +99,14 @@ impl ConsensusState {
 
         // Block is coming from protocol mark it for desync calculation
         if !created {
-            let now = MassaTime::now()?;
+            let now = self.slot_scheduler.now()?;
             self.protocol_blocks.push_back((now, block_id));
         }
 
         debug!("received block {} for slot {}", block_id, slot);
+        if !self.block_statuses.contains_key(&block_id) {
+            self.note_block_latency(slot)?;
+        }
 
         let mut to_ack: BTreeSet<(Slot, BlockId)> = BTreeSet::new();
         match self.block_statuses.entry(block_id) {
@@ -185,4 +190,27 @@ impl ConsensusState {
         );
         self.discarded_index.insert(*block_id);
     }
+
+    /// Forget the blocks that are still waiting for their slot or for their dependencies.
+    /// They are not marked as discarded, so they are processed again if they are received again.
+    ///
+    /// # Arguments:
+    /// * `block_ids`: Block ids of the blocks to forget
+    pub fn drop_pending_blocks(&mut self, block_ids: &[BlockId]) {
+        for block_id in block_ids {
+            match self.block_statuses.get(block_id) {
+                Some(BlockStatus::WaitingForSlot(_)) => {
+                    self.waiting_for_slot_index.remove(block_id);
+                }
+                Some(BlockStatus::WaitingForDependencies { .. }) => {
+                    self.waiting_for_dependencies_index.remove(block_id);
+                }
+                _ => continue,
+            }
+            massa_trace!("consensus.block_graph.process.drop_pending_block", {
+                "block_id": block_id
+            });
+            self.block_statuses.remove(block_id);
+        }
+    }
 }
@@ -1,8 +1,16 @@
 use super::ConsensusState;
 use massa_consensus_exports::error::ConsensusError;
-use massa_models::stats::ConsensusStats;
+use massa_metrics::CONSENSUS_METRICS;
+use massa_models::{
+    address::Address,
+    prehash::{PreHashMap, PreHashSet},
+    slot::Slot,
+    stats::{ConsensusStats, CycleProductionStats},
+};
 use massa_time::MassaTime;
 use std::cmp::max;
+use std::ops::Bound;
+use tracing::debug;
 
 #[cfg(not(feature = "sandbox"))]
 use tracing::log::warn;
@@ -10,10 +18,104 @@ use tracing::log::warn;
 #[cfg(not(feature = "sandbox"))]
 use massa_consensus_exports::events::ConsensusEvent;
 
+/// Block production data gathered during a cycle
+#[derive(Debug, Clone)]
+pub struct CycleStats {
+    /// cycle
+    pub cycle: u64,
+    /// number of slots for which each address was selected as block producer
+    pub selected_producers: PreHashMap<Address, u64>,
+    /// number of final blocks per creator
+    pub final_blocks: PreHashMap<Address, u64>,
+    /// number of stale blocks per creator
+    pub stale_blocks: PreHashMap<Address, u64>,
+    /// sum of the operation counts of the final blocks
+    pub final_operation_count: u64,
+    /// sum of the delays between the slot timestamps and the first reception of the blocks
+    pub latency_sum: MassaTime,
+    /// number of blocks accounted in `latency_sum`
+    pub latency_count: u64,
+}
+
+impl CycleStats {
+    fn new(cycle: u64) -> Self {
+        CycleStats {
+            cycle,
+            selected_producers: Default::default(),
+            final_blocks: Default::default(),
+            stale_blocks: Default::default(),
+            final_operation_count: 0,
+            latency_sum: MassaTime::from_millis(0),
+            latency_count: 0,
+        }
+    }
+
+    /// Account a slot for which `producer` was selected
+    fn note_selected_producer(&mut self, producer: Address) {
+        *self.selected_producers.entry(producer).or_default() += 1;
+    }
+
+    /// Account a block received `latency` after its slot timestamp
+    fn note_latency(&mut self, latency: MassaTime) {
+        self.latency_sum = self.latency_sum.saturating_add(latency);
+        self.latency_count += 1;
+    }
+
+    /// Account a final block of `creator` holding `operation_count` operations
+    fn note_final_block(&mut self, creator: Address, operation_count: usize) {
+        *self.final_blocks.entry(creator).or_default() += 1;
+        self.final_operation_count = self
+            .final_operation_count
+            .saturating_add(operation_count as u64);
+    }
+
+    /// Account a stale block of `creator`
+    fn note_stale_block(&mut self, creator: Address) {
+        *self.stale_blocks.entry(creator).or_default() += 1;
+    }
+
+    /// Production stats of the cycle.
+    /// Expected, produced and stale block counts only account for `addresses`,
+    /// the stale rate and the block fill account for all the blocks.
+    fn get_production_stats(
+        &self,
+        addresses: &PreHashSet<Address>,
+        max_operations_per_block: u32,
+    ) -> CycleProductionStats {
+        let count_for_addresses = |counts: &PreHashMap<Address, u64>| -> u64 {
+            addresses.iter().filter_map(|addr| counts.get(addr)).sum()
+        };
+        let final_block_count: u64 = self.final_blocks.values().sum();
+        let stale_block_count: u64 = self.stale_blocks.values().sum();
+        let observed_block_count = final_block_count.saturating_add(stale_block_count);
+        let max_operation_count = final_block_count.saturating_mul(max_operations_per_block as u64);
+        CycleProductionStats {
+            cycle: self.cycle,
+            expected_block_count: count_for_addresses(&self.selected_producers),
+            produced_block_count: count_for_addresses(&self.final_blocks),
+            stale_block_count: count_for_addresses(&self.stale_blocks),
+            stale_rate: if observed_block_count > 0 {
+                stale_block_count as f64 / observed_block_count as f64
+            } else {
+                0.0
+            },
+            average_block_fill: if max_operation_count > 0 {
+                self.final_operation_count as f64 / max_operation_count as f64
+            } else {
+                0.0
+            },
+            average_production_latency: self
+                .latency_sum
+                .checked_div_u64(self.latency_count)
+                .unwrap_or_else(|_| MassaTime::from_millis(0)),
+        }
+    }
+}
+
 impl ConsensusState {
     /// Calculate and return stats about consensus
     pub fn get_stats(&self) -> Result<ConsensusStats, ConsensusError> {
-        let timespan_end = max(self.launch_time, MassaTime::now()?);
+        let timespan_end = max(self.launch_time, self.slot_scheduler.now()?);
         let timespan_start = max(
             timespan_end.saturating_sub(self.config.stats_timespan),
             self.launch_time,
@@ -38,13 +140,111 @@ impl ConsensusState {
         })
     }
 
+    /// Calculate and return the block production stats of the kept cycles.
+    /// Expected, produced and stale block counts only account for the given addresses.
+    pub fn get_production_stats(
+        &self,
+        addresses: &PreHashSet<Address>,
+    ) -> Vec<CycleProductionStats> {
+        self.cycle_stats
+            .iter()
+            .map(|stats| {
+                stats.get_production_stats(addresses, self.config.max_operations_per_block)
+            })
+            .collect()
+    }
+
+    /// Get the mutable production stats of the cycle of a slot, if this cycle is kept
+    fn get_cycle_stats_mut(&mut self, slot: Slot) -> Option<&mut CycleStats> {
+        let cycle = slot.get_cycle(self.config.periods_per_cycle);
+        self.cycle_stats
+            .iter_mut()
+            .rev()
+            .find(|stats| stats.cycle == cycle)
+    }
+
+    /// Account the selected producers of the slots elapsed since the previous call in the production stats.
+    /// A new cycle entry is created when a new cycle starts and the oldest ones are dropped.
+    fn cycle_stats_tick(&mut self, current_slot: Slot) -> Result<(), ConsensusError> {
+        let start = match self.cycle_stats_cursor {
+            Some(cursor) if cursor >= current_slot => return Ok(()),
+            Some(cursor) => Bound::Excluded(cursor),
+            None => Bound::Included(current_slot),
+        };
+        for slot in Slot::iter_range(
+            (start, Bound::Included(current_slot)),
+            self.config.thread_count,
+        ) {
+            let cycle = slot.get_cycle(self.config.periods_per_cycle);
+            if self.cycle_stats.back().map(|stats| stats.cycle) != Some(cycle) {
+                self.cycle_stats.push_back(CycleStats::new(cycle));
+                while self.cycle_stats.len() > self.config.stats_cycle_count {
+                    self.cycle_stats.pop_front();
+                }
+            }
+            match self.channels.selector_controller.get_producer(slot) {
+                Ok(producer) => {
+                    if let Some(stats) = self.get_cycle_stats_mut(slot) {
+                        stats.note_selected_producer(producer);
+                    }
+                }
+                Err(err) => debug!("could not get the producer of slot {}: {}", slot, err),
+            }
+        }
+        self.cycle_stats_cursor = Some(current_slot);
+        Ok(())
+    }
+
+    /// Account the delay between the slot timestamp and the first reception of a block (or its header)
+    pub fn note_block_latency(&mut self, slot: Slot) -> Result<(), ConsensusError> {
+        let slot_timestamp = self.slot_scheduler.get_slot_timestamp(slot)?;
+        let latency = self.slot_scheduler.now()?.saturating_sub(slot_timestamp);
+        if let Some(stats) = self.get_cycle_stats_mut(slot) {
+            stats.note_latency(latency);
+        }
+        Ok(())
+    }
+
+    /// Account a newly final block in the production stats of its cycle
+    pub fn note_final_block(&mut self, slot: Slot, creator: Address, operation_count: usize) {
+        CONSENSUS_METRICS.final_blocks.inc();
+        if let Some(stats) = self.get_cycle_stats_mut(slot) {
+            stats.note_final_block(creator, operation_count);
+        }
+    }
+
+    /// Account a newly stale block in the production stats of its cycle
+    pub fn note_stale_block(&mut self, slot: Slot, creator: Address) {
+        CONSENSUS_METRICS.stale_blocks.inc();
+        if let Some(stats) = self.get_cycle_stats_mut(slot) {
+            stats.note_stale_block(creator);
+        }
+    }
+
     /// Must be called each tick to update stats. Will detect if a desynchronization happened
-    pub fn stats_tick(&mut self) -> Result<(), ConsensusError> {
+    pub fn stats_tick(&mut self, current_slot: Slot) -> Result<(), ConsensusError> {
+        // account the block producers of the elapsed slots
+        self.cycle_stats_tick(current_slot)?;
+
+        // graph metrics
+        CONSENSUS_METRICS
+            .clique_count
+            .set(self.max_cliques.len() as i64);
+        let finality_lag = self
+            .latest_final_blocks_periods
+            .iter()
+            .map(|(_, period)| current_slot.period.saturating_sub(*period))
+            .max()
+            .unwrap_or_default();
+        CONSENSUS_METRICS
+            .finality_lag_periods
+            .set(finality_lag.try_into().unwrap_or(i64::MAX));
+
         // check if there are any final blocks is coming from protocol
         // if none => we are probably desync
         #[cfg(not(feature = "sandbox"))]
         {
-            let now = MassaTime::now()?;
+            let now = self.slot_scheduler.now()?;
             if now
                 > max(self.config.genesis_timestamp, self.launch_time)
                     .saturating_add(self.stats_desync_detection_timespan)
@@ -70,7 +270,10 @@ impl ConsensusState {
 
     /// Remove old stats from consensus storage
     pub fn prune_stats(&mut self) -> Result<(), ConsensusError> {
-        let start_time = MassaTime::now()?.saturating_sub(self.stats_history_timespan);
+        let start_time = self
+            .slot_scheduler
+            .now()?
+            .saturating_sub(self.stats_history_timespan);
         while let Some((t, _, _)) = self.final_block_stats.front() {
             if t < &start_time {
                 self.final_block_stats.pop_front();
@@ -95,3 +298,84 @@ impl ConsensusState {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_signature::KeyPair;
+
+    fn address() -> Address {
+        Address::from_public_key(&KeyPair::generate().get_public_key())
+    }
+
+    #[test]
+    fn test_empty_cycle_stats() {
+        let stats = CycleStats::new(3).get_production_stats(&PreHashSet::default(), 100);
+        assert_eq!(stats.cycle, 3);
+        assert_eq!(stats.expected_block_count, 0);
+        assert_eq!(stats.produced_block_count, 0);
+        assert_eq!(stats.stale_block_count, 0);
+        assert_eq!(stats.stale_rate, 0.0);
+        assert_eq!(stats.average_block_fill, 0.0);
+        assert_eq!(stats.average_production_latency, MassaTime::from_millis(0));
+    }
+
+    #[test]
+    fn test_cycle_production_stats() {
+        let (ours, other) = (address(), address());
+        let mut stats = CycleStats::new(1);
+        for _ in 0..3 {
+            stats.note_selected_producer(ours);
+        }
+        stats.note_selected_producer(other);
+        stats.note_final_block(ours, 10);
+        stats.note_final_block(other, 30);
+        stats.note_stale_block(ours);
+        stats.note_stale_block(other);
+        stats.note_latency(MassaTime::from_millis(100));
+        stats.note_latency(MassaTime::from_millis(300));
+
+        // the counts only account for the given addresses
+        let production = stats.get_production_stats(&PreHashSet::from_iter([ours]), 100);
+        assert_eq!(production.cycle, 1);
+        assert_eq!(production.expected_block_count, 3);
+        assert_eq!(production.produced_block_count, 1);
+        assert_eq!(production.stale_block_count, 1);
+        let production = stats.get_production_stats(&PreHashSet::from_iter([ours, other]), 100);
+        assert_eq!(production.expected_block_count, 4);
+        assert_eq!(production.produced_block_count, 2);
+        assert_eq!(production.stale_block_count, 2);
+
+        // the rates account for all the blocks of the cycle
+        let production = stats.get_production_stats(&PreHashSet::default(), 100);
+        assert_eq!(production.expected_block_count, 0);
+        assert_eq!(production.stale_rate, 0.5);
+        assert_eq!(production.average_block_fill, 0.2);
+        assert_eq!(
+            production.average_production_latency,
+            MassaTime::from_millis(200)
+        );
+    }
+
+    #[test]
+    fn test_cycle_stats_saturation() {
+        let creator = address();
+        let mut stats = CycleStats::new(0);
+        stats.note_final_block(creator, usize::MAX);
+        stats.note_final_block(creator, usize::MAX);
+        assert_eq!(stats.final_operation_count, u64::MAX);
+        stats.note_latency(MassaTime::from_millis(u64::MAX));
+        stats.note_latency(MassaTime::from_millis(1));
+        assert_eq!(stats.latency_sum, MassaTime::from_millis(u64::MAX));
+        assert_eq!(stats.latency_count, 2);
+
+        // a cycle without final blocks has no fill, whatever the max operation count
+        let stats = CycleStats::new(0);
+        assert_eq!(
+            stats
+                .get_production_stats(&PreHashSet::from_iter([creator]), 0)
+                .average_block_fill,
+            0.0
+        );
+    }
+}
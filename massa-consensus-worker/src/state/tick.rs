@@ -40,7 +40,7 @@ impl ConsensusState {
         // process those elements
         self.rec_process(to_process, Some(current_slot))?;
 
-        self.stats_tick()?;
+        self.stats_tick(current_slot)?;
         // take care of block db changes
         self.block_db_changed()?;
 
@@ -3,13 +3,17 @@ use super::ConsensusState;
 use massa_consensus_exports::{
     block_status::{BlockStatus, DiscardReason},
     error::ConsensusError,
+    ConsensusConfig,
 };
 use massa_logging::massa_trace;
 use massa_models::{
     block::{BlockId, WrappedHeader},
-    prehash::PreHashSet,
+    operation::{compute_operations_hash, WrappedOperation},
+    prehash::{CapacityAllocator, PreHashSet},
     slot::Slot,
+    state_commitment::{StateCommitment, StateDivergence},
 };
+use tracing::error;
 
 /// Possible output of a header check
 #[derive(Debug)]
@@ -52,11 +56,13 @@ impl ConsensusState {
     /// - Slot above 0.
     /// - Valid thread.
     /// - Check that the block is older than the latest final one in thread.
+    /// - Check that the block matches the checkpoint of its slot, if any.
     /// - Check that the block slot is not too much into the future,
     ///   as determined by the configuration `future_block_processing_max_periods`.
     /// - Check if it was the creator's turn to create this block.
     /// - TODO: check for double staking.
     /// - Check parents are present.
+    /// - Check that the block does not skip a checkpoint of its thread.
     /// - Check the topological consistency of the parents.
     /// - Check endorsements.
     /// - Check thread incompatibility test.
@@ -87,6 +93,25 @@ impl ConsensusState {
             return Ok(HeaderCheckOutcome::Discard(DiscardReason::Stale));
         }
 
+        // check that the block does not conflict with a checkpoint
+        if let Some(checkpoint_id) = self.config.checkpoints.get(&header.content.slot) {
+            if checkpoint_id != block_id {
+                return Ok(HeaderCheckOutcome::Discard(DiscardReason::Invalid(
+                    format!(
+                        "Block conflicts with checkpoint {} at slot {}",
+                        checkpoint_id, header.content.slot
+                    ),
+                )));
+            }
+        }
+
+        // check that the block only uses the protocol features active at its slot
+        if let Err(err) = self.config.upgrade_schedule.check_header(&header.content) {
+            return Ok(HeaderCheckOutcome::Discard(DiscardReason::Invalid(
+                err.to_string(),
+            )));
+        }
+
         // check if block slot is too much in the future
         if let Some(cur_slot) = current_slot {
             if header.content.slot.period
@@ -181,6 +206,24 @@ impl ConsensusState {
         if !missing_deps.is_empty() {
             return Ok(HeaderCheckOutcome::WaitForDependencies(missing_deps));
         }
+
+        // check that the block does not skip a checkpoint of its thread:
+        // its parent in thread must not be older than a checkpoint older than the block
+        let thread = header.content.slot.thread;
+        let thread_parent_period = parents[thread as usize].1;
+        if let Some((checkpoint_slot, checkpoint_id)) = self
+            .config
+            .checkpoints
+            .range(Slot::new(thread_parent_period.saturating_add(1), thread)..header.content.slot)
+            .find(|(slot, _)| slot.thread == thread)
+        {
+            return Ok(HeaderCheckOutcome::Discard(DiscardReason::Invalid(
+                format!(
+                    "Block does not descend from checkpoint {} at slot {}",
+                    checkpoint_id, checkpoint_slot
+                ),
+            )));
+        }
         let inherited_incomp_count = incomp.len();
 
         // check the topological consistency of the parents
@@ -408,4 +451,177 @@ impl ConsensusState {
 
         Ok(EndorsementsCheckOutcome::Proceed)
     }
+
+    /// Checks of a candidate block that depend on the current graph state.
+    /// Nothing is inserted in the graph.
+    /// The checks that do not need the graph are in `check_block_candidate_content`.
+    ///
+    /// Checks performed:
+    /// - The block is not already known.
+    /// - Every header check of `check_header`.
+    ///
+    /// # Returns
+    /// The reasons why the block would be rejected or put on hold, empty if there are none
+    pub fn check_block_candidate_in_graph(
+        &self,
+        header: &WrappedHeader,
+    ) -> Result<Vec<String>, ConsensusError> {
+        let block_id = header.id;
+        let mut rejection_reasons = Vec::new();
+
+        if self.block_statuses.contains_key(&block_id) {
+            rejection_reasons.push(format!("block {} is already known", block_id));
+        }
+
+        let current_slot = self.slot_scheduler.get_current_slot()?;
+        match self.check_header(&block_id, header, current_slot, self)? {
+            HeaderCheckOutcome::Proceed { .. } => {}
+            HeaderCheckOutcome::Discard(DiscardReason::Invalid(reason)) => {
+                rejection_reasons.push(format!("invalid header: {}", reason))
+            }
+            HeaderCheckOutcome::Discard(DiscardReason::Stale) => {
+                rejection_reasons.push("block is stale".to_string())
+            }
+            HeaderCheckOutcome::Discard(DiscardReason::Final) => {
+                rejection_reasons.push("block is already final".to_string())
+            }
+            HeaderCheckOutcome::WaitForSlot => rejection_reasons.push(format!(
+                "block slot {} is in the future or its draw is not available yet",
+                header.content.slot
+            )),
+            HeaderCheckOutcome::WaitForDependencies(missing) => {
+                let missing: Vec<String> = missing.iter().map(|id| id.to_string()).collect();
+                rejection_reasons.push(format!("missing parents: {}", missing.join(", ")))
+            }
+        }
+
+        Ok(rejection_reasons)
+    }
+
+    /// Compare the state commitment of a header to our final state at the same slot.
+    ///
+    /// # Returns
+    /// Our commitment at that slot if it differs from the one of the header,
+    /// none if the header has no commitment or if our state at its slot is not final yet or too old
+    pub fn get_diverging_state_commitment(
+        &self,
+        header: &WrappedHeader,
+    ) -> Option<StateCommitment> {
+        let announced = header.content.state_commitment?;
+        let local = self
+            .channels
+            .execution_controller
+            .get_final_state_commitment_at(announced.slot)?;
+        (local != announced).then_some(local)
+    }
+
+    /// Flag a divergence between our final state and the one committed in the header of a valid block.
+    /// The block is not discarded: either node may be the one that computed a wrong state.
+    /// Only the first divergence is kept for the status of the node, the next ones are only logged.
+    pub fn check_state_commitment(
+        &mut self,
+        block_id: &BlockId,
+        header: &WrappedHeader,
+    ) -> Result<(), ConsensusError> {
+        let (announced, local) = match (
+            header.content.state_commitment,
+            self.get_diverging_state_commitment(header),
+        ) {
+            (Some(announced), Some(local)) => (announced, local),
+            _ => return Ok(()),
+        };
+        let divergence = StateDivergence::new(
+            *block_id,
+            header.creator_address,
+            announced,
+            local,
+            self.slot_scheduler.now()?,
+        );
+        error!("STATE DIVERGENCE: {}", divergence);
+        if self.state_divergence.is_none() {
+            self.state_divergence = Some(divergence);
+        }
+        Ok(())
+    }
+}
+
+/// Checks of a candidate block that do not depend on the graph state,
+/// so that the signatures are verified without holding the lock of the consensus state.
+///
+/// Checks performed:
+/// - Header signature.
+/// - Number of operations and operation merkle root.
+/// - Operation signatures, threads, validity ranges and uniqueness.
+/// - Total gas of the operations.
+///
+/// # Returns
+/// The reasons why the block would be rejected, empty if there are none
+pub fn check_block_candidate_content(
+    config: &ConsensusConfig,
+    header: &WrappedHeader,
+    operations: &[WrappedOperation],
+) -> Vec<String> {
+    let block_slot = header.content.slot;
+    let mut rejection_reasons = Vec::new();
+
+    if let Err(err) = header.verify_signature() {
+        rejection_reasons.push(format!("invalid header signature: {}", err));
+    }
+
+    if operations.len() > config.max_operations_per_block as usize {
+        rejection_reasons.push(format!(
+            "too many operations: {} (max {})",
+            operations.len(),
+            config.max_operations_per_block
+        ));
+    }
+
+    if header.content.operation_merkle_root
+        != compute_operations_hash(operations.iter().map(|op| &op.id))
+    {
+        rejection_reasons.push("operation merkle root mismatch".to_string());
+    }
+
+    // the signatures are checked one by one only to find the invalid ones
+    let invalid_signatures = WrappedOperation::verify_signatures_batch(operations).is_err();
+    let mut op_ids = PreHashSet::with_capacity(operations.len());
+    let mut total_gas: u64 = 0;
+    for op in operations {
+        if !op_ids.insert(op.id) {
+            rejection_reasons.push(format!("operation {} is included twice", op.id));
+        }
+        if invalid_signatures {
+            if let Err(err) = op.verify_signature() {
+                rejection_reasons.push(format!(
+                    "invalid signature for operation {}: {}",
+                    op.id, err
+                ));
+            }
+        }
+        let op_thread = op.creator_address.get_thread(config.thread_count);
+        if op_thread != block_slot.thread {
+            rejection_reasons.push(format!(
+                "operation {} belongs to thread {} instead of {}",
+                op.id, op_thread, block_slot.thread
+            ));
+        }
+        if !op
+            .get_validity_range(config.operation_validity_periods)
+            .contains(&block_slot.period)
+        {
+            rejection_reasons.push(format!(
+                "operation {} is not valid at period {}",
+                op.id, block_slot.period
+            ));
+        }
+        total_gas = total_gas.saturating_add(op.get_gas_usage());
+    }
+    if total_gas > config.max_gas_per_block {
+        rejection_reasons.push(format!(
+            "operations use {} gas (max {})",
+            total_gas, config.max_gas_per_block
+        ));
+    }
+
+    rejection_reasons
 }
@@ -0,0 +1,4 @@
+mod scenarios;
+mod tools;
+
+pub use tools::*;
@@ -0,0 +1,398 @@
+use super::{create_block_with_header, get_genesis_block_ids, test_config, TestConsensus};
+use massa_consensus_exports::ConsensusConfig;
+use massa_hash::Hash;
+use massa_models::{
+    address::Address,
+    api::BlockGraphStatus,
+    block::{BlockHeader, BlockId, WrappedBlock},
+    slot::Slot,
+    state_commitment::StateCommitment,
+};
+use massa_signature::KeyPair;
+
+/// A candidate block produced by the drawn address on top of the genesis blocks is valid,
+/// and is not inserted in the graph by the check.
+#[test]
+fn test_check_valid_block_candidate() {
+    let test_consensus = TestConsensus::new(test_config());
+    let genesis = test_consensus.get_genesis_blocks();
+    test_consensus.advance_to_slot(Slot::new(1, 0));
+
+    let block =
+        test_consensus.create_block(&test_consensus.staking_keypair, Slot::new(1, 0), genesis);
+    let report = test_consensus
+        .controller
+        .check_block_validity(block.content.header.clone(), Vec::new())
+        .unwrap();
+    assert_eq!(report.block_id, block.id);
+    assert!(report.is_valid, "{}", report);
+    assert!(report.rejection_reasons.is_empty());
+    assert_eq!(
+        test_consensus.controller.get_block_statuses(&[block.id]),
+        vec![BlockGraphStatus::NotFound]
+    );
+}
+
+/// Every reason why a candidate block would be rejected is reported.
+#[test]
+fn test_check_invalid_block_candidate() {
+    let test_consensus = TestConsensus::new(test_config());
+    let genesis = test_consensus.get_genesis_blocks();
+    test_consensus.advance_to_slot(Slot::new(1, 0));
+
+    // not the drawn producer, and an operation merkle root that does not match the empty operation list
+    let block = test_consensus.create_block_with_header(
+        &KeyPair::generate(),
+        BlockHeader {
+            slot: Slot::new(1, 0),
+            parents: genesis,
+            operation_merkle_root: Hash::compute_from(b"not the operations"),
+            endorsements: Vec::new(),
+            state_commitment: None,
+        },
+    );
+    let report = test_consensus
+        .controller
+        .check_block_validity(block.content.header.clone(), Vec::new())
+        .unwrap();
+    assert!(!report.is_valid);
+    assert_eq!(report.rejection_reasons.len(), 2, "{}", report);
+    assert!(report.rejection_reasons[0].contains("Bad creator turn"));
+    assert_eq!(
+        report.rejection_reasons[1],
+        "operation merkle root mismatch"
+    );
+}
+
+/// Configuration in which a blockclique switch may roll back at most one period,
+/// and the cliques lighter than the blockclique by more than 2 blocks are stale
+fn reorg_config() -> ConsensusConfig {
+    ConsensusConfig {
+        max_reorg_depth_periods: 1,
+        delta_f0: 2,
+        ..test_config()
+    }
+}
+
+/// Register a chain of thread 0 from `parent`, with one block per period of `periods`,
+/// whose parent in thread 1 is the genesis block `genesis_1`
+fn register_thread_0_chain(
+    test_consensus: &TestConsensus,
+    mut parent: BlockId,
+    genesis_1: BlockId,
+    periods: std::ops::Range<u64>,
+) -> Vec<WrappedBlock> {
+    let mut chain = Vec::new();
+    for period in periods {
+        let block = test_consensus.create_block(
+            &test_consensus.staking_keypair,
+            Slot::new(period, 0),
+            vec![parent, genesis_1],
+        );
+        test_consensus.register_block_and_wait_active(&block);
+        parent = block.id;
+        chain.push(block);
+    }
+    chain
+}
+
+/// Register the blockclique A1 A2 in thread 0, then a heavier fork B1 B2 B3 dropping both A blocks:
+/// the switch would roll back 2 periods of the blockclique, more than allowed, so it is rejected.
+/// Returns the chains A and B.
+fn register_too_deep_fork(
+    test_consensus: &TestConsensus,
+) -> (Vec<WrappedBlock>, Vec<WrappedBlock>) {
+    let genesis = test_consensus.get_genesis_blocks();
+    test_consensus.advance_to_slot(Slot::new(20, 1));
+
+    let chain_a = register_thread_0_chain(test_consensus, genesis[0], genesis[1], 1..3);
+    let chain_b = register_thread_0_chain(test_consensus, genesis[0], genesis[1], 2..5);
+
+    let alert = test_consensus
+        .controller
+        .get_reorg_alert()
+        .expect("the too deep switch was not rejected");
+    assert_eq!(alert.depth_periods, 2);
+    assert_eq!(alert.fork_slot, Slot::new(1, 0));
+    assert_eq!(alert.rejected_fitness, 3);
+    assert_eq!(alert.kept_fitness, 2);
+    (chain_a, chain_b)
+}
+
+/// A heavier fork rolling back more periods than `max_reorg_depth_periods` does not become the blockclique.
+#[test]
+fn test_too_deep_reorg_rejected() {
+    let test_consensus = TestConsensus::new(reorg_config());
+    let (chain_a, chain_b) = register_too_deep_fork(&test_consensus);
+
+    for block in chain_a.iter() {
+        assert_eq!(
+            test_consensus.get_block_status(block.id),
+            BlockGraphStatus::ActiveInBlockclique
+        );
+    }
+    for block in chain_b.iter() {
+        assert_eq!(
+            test_consensus.get_block_status(block.id),
+            BlockGraphStatus::ActiveInAlternativeCliques
+        );
+    }
+}
+
+/// Once accepted by the node administrator, the rejected switch happens at the next block, whatever its depth.
+#[test]
+fn test_too_deep_reorg_accepted() {
+    let test_consensus = TestConsensus::new(reorg_config());
+    let (chain_a, chain_b) = register_too_deep_fork(&test_consensus);
+
+    test_consensus.controller.accept_reorg();
+    let chain_b = [
+        chain_b.as_slice(),
+        &register_thread_0_chain(
+            &test_consensus,
+            chain_b.last().unwrap().id,
+            test_consensus.get_genesis_blocks()[1],
+            5..6,
+        ),
+    ]
+    .concat();
+
+    assert!(test_consensus.controller.get_reorg_alert().is_none());
+    for block in chain_a.iter() {
+        assert_eq!(
+            test_consensus.get_block_status(block.id),
+            BlockGraphStatus::ActiveInAlternativeCliques
+        );
+    }
+    for block in chain_b.iter() {
+        assert_eq!(
+            test_consensus.get_block_status(block.id),
+            BlockGraphStatus::ActiveInBlockclique
+        );
+    }
+}
+
+/// Once the kept blockclique outgrows the rejected fork, the fork becomes stale, the kept blocks final,
+/// and the reorg alert is cleared.
+#[test]
+fn test_reorg_alert_cleared_once_finalized() {
+    let test_consensus = TestConsensus::new(reorg_config());
+    let (chain_a, chain_b) = register_too_deep_fork(&test_consensus);
+
+    let chain_a = [
+        chain_a.as_slice(),
+        &register_thread_0_chain(
+            &test_consensus,
+            chain_a.last().unwrap().id,
+            test_consensus.get_genesis_blocks()[1],
+            3..7,
+        ),
+    ]
+    .concat();
+
+    assert!(test_consensus.controller.get_reorg_alert().is_none());
+    for block in chain_b.iter() {
+        assert_eq!(
+            test_consensus.get_block_status(block.id),
+            BlockGraphStatus::Discarded
+        );
+    }
+    for block in chain_a[..3].iter() {
+        assert_eq!(
+            test_consensus.get_block_status(block.id),
+            BlockGraphStatus::Final
+        );
+    }
+}
+
+/// Commitment to the final state at `slot`, the hashes of its parts being derived from `ledger`
+fn state_commitment(slot: Slot, ledger: &str) -> StateCommitment {
+    StateCommitment {
+        slot,
+        final_state_hash: Hash::compute_from(format!("final state with {}", ledger).as_bytes()),
+        ledger_hash: Hash::compute_from(ledger.as_bytes()),
+        async_pool_hash: Hash::compute_from(b"async pool"),
+        deferred_credits_hash: Hash::compute_from(b"deferred credits"),
+        executed_ops_hash: Hash::compute_from(b"executed ops"),
+    }
+}
+
+/// Register a block of the slot 1 of thread 0 on top of the genesis blocks, committing to `announced`,
+/// while our final state at the same slot is `local`
+fn register_committing_block(
+    test_consensus: &TestConsensus,
+    announced: StateCommitment,
+    local: StateCommitment,
+) -> WrappedBlock {
+    test_consensus.set_final_state_commitment(local);
+    test_consensus.advance_to_slot(Slot::new(1, 0));
+    let block = test_consensus.create_block_with_header(
+        &test_consensus.staking_keypair,
+        BlockHeader {
+            slot: Slot::new(1, 0),
+            parents: test_consensus.get_genesis_blocks(),
+            operation_merkle_root: Hash::compute_from(&Vec::new()),
+            endorsements: Vec::new(),
+            state_commitment: Some(announced),
+        },
+    );
+    test_consensus.register_block_and_wait_active(&block);
+    block
+}
+
+/// A block committing to the same final state as ours is not reported.
+#[test]
+fn test_matching_state_commitment() {
+    let test_consensus = TestConsensus::new(test_config());
+    let commitment = state_commitment(Slot::new(0, 1), "ledger");
+    register_committing_block(&test_consensus, commitment, commitment);
+    assert!(test_consensus.controller.get_state_divergence().is_none());
+}
+
+/// A block committing to another final state than ours is accepted, and the divergence is reported.
+#[test]
+fn test_mismatched_state_commitment() {
+    let test_consensus = TestConsensus::new(test_config());
+    let announced = state_commitment(Slot::new(0, 1), "other ledger");
+    let local = state_commitment(Slot::new(0, 1), "ledger");
+    let block = register_committing_block(&test_consensus, announced, local);
+
+    let divergence = test_consensus
+        .controller
+        .get_state_divergence()
+        .expect("the divergence was not reported");
+    assert_eq!(divergence.block_id, block.id);
+    assert_eq!(
+        divergence.creator,
+        Address::from_public_key(&test_consensus.staking_keypair.get_public_key())
+    );
+    assert_eq!(divergence.announced, announced);
+    assert_eq!(divergence.local, local);
+    assert_eq!(divergence.diverging_parts, vec!["ledger".to_string()]);
+}
+
+/// The fork choice of a block of the blockclique and of an unknown block are explained.
+#[test]
+fn test_explain_fork_choice() {
+    let test_consensus = TestConsensus::new(test_config());
+    let genesis = test_consensus.get_genesis_blocks();
+    test_consensus.advance_to_slot(Slot::new(1, 0));
+    let block =
+        test_consensus.create_block(&test_consensus.staking_keypair, Slot::new(1, 0), genesis);
+    test_consensus.register_block_and_wait_active(&block);
+
+    let explanation = test_consensus
+        .controller
+        .explain_fork_choice(block.id)
+        .unwrap();
+    assert_eq!(explanation.block_id, block.id);
+    assert_eq!(explanation.status, BlockGraphStatus::ActiveInBlockclique);
+    assert_eq!(explanation.clique_count, 1);
+    assert_eq!(explanation.cliques.len(), 1);
+    assert!(explanation.cliques[0].is_blockclique);
+    assert_eq!(explanation.cliques[0].index, explanation.blockclique.index);
+    assert!(explanation.incompatible_blockclique_blocks.is_empty());
+    assert_eq!(explanation.descendants_fitness, Some(0));
+
+    let unknown = BlockId(Hash::compute_from(b"unknown block"));
+    let explanation = test_consensus
+        .controller
+        .explain_fork_choice(unknown)
+        .unwrap();
+    assert_eq!(explanation.status, BlockGraphStatus::NotFound);
+    assert_eq!(explanation.fitness, None);
+    assert!(explanation.cliques.is_empty());
+    assert_eq!(
+        explanation.explanation,
+        vec!["the block is unknown to this node".to_string()]
+    );
+}
+
+/// Block of `slot` produced by `keypair` on top of `parents`, with no commitment unless `state_commitment` is set
+fn create_checkpoint_test_block(
+    keypair: &KeyPair,
+    slot: Slot,
+    parents: Vec<BlockId>,
+    state_commitment: Option<StateCommitment>,
+) -> WrappedBlock {
+    create_block_with_header(
+        keypair,
+        BlockHeader {
+            slot,
+            parents,
+            operation_merkle_root: Hash::compute_from(&Vec::new()),
+            endorsements: Vec::new(),
+            state_commitment,
+        },
+    )
+}
+
+/// Start a consensus whose only checkpoint is the block of the slot 1 of thread 0 on top of the genesis blocks,
+/// every slot being drawn for `staking_keypair`. Returns the consensus and the checkpoint block.
+fn start_consensus_with_checkpoint(staking_keypair: &KeyPair) -> (TestConsensus, WrappedBlock) {
+    let mut config = test_config();
+    let genesis = get_genesis_block_ids(&config);
+    let checkpoint =
+        create_checkpoint_test_block(staking_keypair, Slot::new(1, 0), genesis.clone(), None);
+    config.checkpoints.insert(Slot::new(1, 0), checkpoint.id);
+    let test_consensus = TestConsensus::new_with_staking_keypair(config, staking_keypair.clone());
+    assert_eq!(test_consensus.get_genesis_blocks(), genesis);
+    (test_consensus, checkpoint)
+}
+
+/// Another block than the checkpoint at the slot of the checkpoint is discarded, the checkpoint is not.
+#[test]
+fn test_block_conflicting_with_checkpoint() {
+    let staking_keypair = KeyPair::generate();
+    let (test_consensus, checkpoint) = start_consensus_with_checkpoint(&staking_keypair);
+    let genesis = test_consensus.get_genesis_blocks();
+    test_consensus.advance_to_slot(Slot::new(1, 0));
+
+    // same slot, producer and parents as the checkpoint, only the state commitment differs
+    let conflicting = create_checkpoint_test_block(
+        &staking_keypair,
+        Slot::new(1, 0),
+        genesis,
+        Some(state_commitment(Slot::new(0, 1), "ledger")),
+    );
+    assert_ne!(conflicting.id, checkpoint.id);
+    test_consensus.register_block(&conflicting);
+    test_consensus.wait_until(|| {
+        test_consensus.get_block_status(conflicting.id) == BlockGraphStatus::Discarded
+    });
+
+    test_consensus.register_block_and_wait_active(&checkpoint);
+    assert_eq!(
+        test_consensus.get_block_status(checkpoint.id),
+        BlockGraphStatus::ActiveInBlockclique
+    );
+}
+
+/// A block whose parent in its thread is older than a checkpoint of that thread is discarded,
+/// a block descending from the checkpoint is not.
+#[test]
+fn test_block_skipping_checkpoint() {
+    let staking_keypair = KeyPair::generate();
+    let (test_consensus, checkpoint) = start_consensus_with_checkpoint(&staking_keypair);
+    let genesis = test_consensus.get_genesis_blocks();
+    test_consensus.advance_to_slot(Slot::new(2, 0));
+
+    let skipping =
+        create_checkpoint_test_block(&staking_keypair, Slot::new(2, 0), genesis.clone(), None);
+    test_consensus.register_block(&skipping);
+    test_consensus
+        .wait_until(|| test_consensus.get_block_status(skipping.id) == BlockGraphStatus::Discarded);
+
+    test_consensus.register_block_and_wait_active(&checkpoint);
+    let descendant = create_checkpoint_test_block(
+        &staking_keypair,
+        Slot::new(2, 0),
+        vec![checkpoint.id, genesis[1]],
+        None,
+    );
+    test_consensus.register_block_and_wait_active(&descendant);
+    assert_eq!(
+        test_consensus.get_block_status(descendant.id),
+        BlockGraphStatus::ActiveInBlockclique
+    );
+}
@@ -0,0 +1,287 @@
+use massa_consensus_exports::{
+    events::ConsensusEvent, ConsensusChannels, ConsensusConfig, ConsensusController,
+    ConsensusManager,
+};
+use massa_execution_exports::test_exports::{
+    MockExecutionController, MockExecutionControllerMessage,
+};
+use massa_hash::Hash;
+use massa_models::{
+    address::Address,
+    api::BlockGraphStatus,
+    block::{Block, BlockHeader, BlockHeaderSerializer, BlockId, BlockSerializer, WrappedBlock},
+    slot::Slot,
+    slot_scheduler::{SlotScheduler, SlotTiming},
+    state_commitment::StateCommitment,
+    test_exports::MockSlotScheduler,
+    wrapped::WrappedContent,
+};
+use massa_pool_exports::test_exports::MockPoolController;
+use massa_pos_exports::{
+    test_exports::{MockSelectorController, MockSelectorControllerMessage},
+    Selection,
+};
+use massa_protocol_exports::ProtocolCommandSender;
+use massa_signature::KeyPair;
+use massa_storage::Storage;
+use massa_time::MassaTime;
+use parking_lot::RwLock;
+use std::{
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+use tokio::sync::{broadcast, mpsc};
+
+use crate::{start_consensus_worker, worker::init::create_genesis_block};
+
+/// Time given to the consensus worker to process the commands sent by a test
+const PROCESSING_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Configuration of the consensus for the tests: 2 threads, periods of one second and genesis at the time 0 of the mock clock
+pub fn test_config() -> ConsensusConfig {
+    ConsensusConfig {
+        thread_count: 2,
+        t0: MassaTime::from_millis(1000),
+        genesis_timestamp: MassaTime::from_millis(0),
+        periods_per_cycle: 8,
+        ..Default::default()
+    }
+}
+
+/// This structure store all information and links to create tests for the consensus.
+/// The consensus worker runs on a `MockSlotScheduler`, so its time only moves when the test says so.
+/// The mocks of the other modules are answered in the background:
+/// every slot is drawn for `staking_keypair`, no operation is executed yet
+/// and the only final state commitment is the one set with `set_final_state_commitment`.
+pub struct TestConsensus {
+    /// controller of the consensus worker
+    pub controller: Box<dyn ConsensusController>,
+    /// time of the slots
+    pub slot_scheduler: MockSlotScheduler,
+    /// key drawn to produce the blocks and the endorsements of every slot
+    pub staking_keypair: KeyPair,
+    storage: Storage,
+    final_state_commitment: Arc<RwLock<Option<StateCommitment>>>,
+    _manager: Box<dyn ConsensusManager>,
+    _event_receiver: crossbeam_channel::Receiver<ConsensusEvent>,
+}
+
+impl TestConsensus {
+    /// Start a consensus worker and all mocks from `config`, at the genesis timestamp
+    pub fn new(config: ConsensusConfig) -> TestConsensus {
+        TestConsensus::new_with_staking_keypair(config, KeyPair::generate())
+    }
+
+    /// Same as `new`, with every slot drawn for `staking_keypair`
+    pub fn new_with_staking_keypair(
+        config: ConsensusConfig,
+        staking_keypair: KeyPair,
+    ) -> TestConsensus {
+        let staking_address = Address::from_public_key(&staking_keypair.get_public_key());
+        let endorsement_count = config.endorsement_count as usize;
+
+        let (selector_controller, selector_receiver) = MockSelectorController::new_with_receiver();
+        thread::spawn(move || {
+            while let Ok(message) = selector_receiver.recv() {
+                match message {
+                    MockSelectorControllerMessage::GetProducer { response_tx, .. } => {
+                        let _ = response_tx.send(Ok(staking_address));
+                    }
+                    MockSelectorControllerMessage::GetSelection { response_tx, .. } => {
+                        let _ = response_tx.send(Ok(Selection {
+                            producer: staking_address,
+                            endorsements: vec![staking_address; endorsement_count],
+                        }));
+                    }
+                    _ => {}
+                }
+            }
+        });
+
+        let final_state_commitment: Arc<RwLock<Option<StateCommitment>>> = Default::default();
+        let (execution_controller, execution_receiver) =
+            MockExecutionController::new_with_receiver();
+        let commitment = final_state_commitment.clone();
+        thread::spawn(move || {
+            while let Ok(message) = execution_receiver.recv() {
+                match message {
+                    MockExecutionControllerMessage::UnexecutedOpsAmong {
+                        ops, response_tx, ..
+                    } => {
+                        let _ = response_tx.send(ops);
+                    }
+                    MockExecutionControllerMessage::GetFinalStateCommitmentAt {
+                        slot,
+                        response_tx,
+                    } => {
+                        let _ = response_tx.send(
+                            commitment
+                                .read()
+                                .filter(|commitment| commitment.slot == slot),
+                        );
+                    }
+                    _ => {}
+                }
+            }
+        });
+
+        let (pool_controller, pool_receiver) = MockPoolController::new_with_receiver();
+        thread::spawn(move || while pool_receiver.0.recv().is_ok() {});
+        let (protocol_command_tx, mut protocol_command_rx) = mpsc::channel(config.channel_size);
+        thread::spawn(move || while protocol_command_rx.blocking_recv().is_some() {});
+        let (controller_event_tx, event_receiver) = crossbeam_channel::unbounded();
+
+        let channels = ConsensusChannels {
+            execution_controller,
+            selector_controller,
+            pool_command_sender: pool_controller,
+            controller_event_tx,
+            protocol_command_sender: ProtocolCommandSender(protocol_command_tx),
+            block_header_sender: broadcast::channel(config.broadcast_blocks_headers_capacity).0,
+            block_sender: broadcast::channel(config.broadcast_blocks_capacity).0,
+            filled_block_sender: broadcast::channel(config.broadcast_filled_blocks_capacity).0,
+            final_block_sender: broadcast::channel(config.broadcast_final_blocks_capacity).0,
+            archive: None,
+            address_index: None,
+        };
+        let slot_scheduler = MockSlotScheduler::new(
+            SlotTiming {
+                thread_count: config.thread_count,
+                t0: config.t0,
+                genesis_timestamp: config.genesis_timestamp,
+            },
+            config.genesis_timestamp,
+        );
+        let storage = Storage::create_root();
+        let (controller, manager) = start_consensus_worker(
+            config,
+            channels,
+            None,
+            storage.clone(),
+            slot_scheduler.clone_box(),
+        );
+
+        TestConsensus {
+            controller,
+            slot_scheduler,
+            staking_keypair,
+            storage,
+            final_state_commitment,
+            _manager: manager,
+            _event_receiver: event_receiver,
+        }
+    }
+
+    /// Get the ids of the genesis blocks, one per thread
+    pub fn get_genesis_blocks(&self) -> Vec<BlockId> {
+        self.controller
+            .get_latest_final_blocks_periods()
+            .into_iter()
+            .map(|(block_id, _)| block_id)
+            .collect()
+    }
+
+    /// Set the commitment to our final state at the slot of `commitment`, given by the execution
+    pub fn set_final_state_commitment(&self, commitment: StateCommitment) {
+        *self.final_state_commitment.write() = Some(commitment);
+    }
+
+    /// Move the time of the consensus to the timestamp of `slot`
+    pub fn advance_to_slot(&self, slot: Slot) {
+        self.slot_scheduler.advance_to_slot(slot).unwrap();
+    }
+
+    /// Create a block without operations nor endorsements
+    /// Arguments:
+    /// - `keypair`: producer of the block
+    /// - `slot`: slot of the block
+    /// - `parents`: one parent per thread
+    pub fn create_block(
+        &self,
+        keypair: &KeyPair,
+        slot: Slot,
+        parents: Vec<BlockId>,
+    ) -> WrappedBlock {
+        self.create_block_with_header(
+            keypair,
+            BlockHeader {
+                slot,
+                parents,
+                operation_merkle_root: Hash::compute_from(&Vec::new()),
+                endorsements: Vec::new(),
+                state_commitment: None,
+            },
+        )
+    }
+
+    /// Create a block without operations from its header
+    pub fn create_block_with_header(&self, keypair: &KeyPair, header: BlockHeader) -> WrappedBlock {
+        create_block_with_header(keypair, header)
+    }
+
+    /// Send a block to the consensus, as if it was received from the network
+    pub fn register_block(&self, block: &WrappedBlock) {
+        let mut storage = self.storage.clone_without_refs();
+        storage.store_block(block.clone());
+        self.controller
+            .register_block(block.id, block.content.header.content.slot, storage, false);
+    }
+
+    /// Get the status of a block in the graph
+    pub fn get_block_status(&self, block_id: BlockId) -> BlockGraphStatus {
+        self.controller
+            .get_block_statuses(&[block_id])
+            .pop()
+            .expect("no status returned for the block")
+    }
+
+    /// Send a block to the consensus and wait until it is active, in the blockclique or not
+    pub fn register_block_and_wait_active(&self, block: &WrappedBlock) {
+        self.register_block(block);
+        self.wait_until(|| {
+            matches!(
+                self.get_block_status(block.id),
+                BlockGraphStatus::ActiveInBlockclique
+                    | BlockGraphStatus::ActiveInAlternativeCliques
+            )
+        });
+    }
+
+    /// Wait until `condition` holds, panics on timeout
+    pub fn wait_until<F: Fn() -> bool>(&self, condition: F) {
+        let deadline = Instant::now() + PROCESSING_TIMEOUT;
+        while !condition() {
+            assert!(
+                Instant::now() < deadline,
+                "timeout while waiting for the consensus"
+            );
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
+
+/// Get the ids of the genesis blocks created by a consensus started from `config`, one per thread
+pub fn get_genesis_block_ids(config: &ConsensusConfig) -> Vec<BlockId> {
+    (0..config.thread_count)
+        .map(|thread| {
+            create_genesis_block(&config.genesis_key, thread)
+                .unwrap()
+                .id
+        })
+        .collect()
+}
+
+/// Create a block without operations from its header, before any consensus is started
+pub fn create_block_with_header(keypair: &KeyPair, header: BlockHeader) -> WrappedBlock {
+    let header = BlockHeader::new_wrapped(header, BlockHeaderSerializer::new(), keypair).unwrap();
+    Block::new_wrapped(
+        Block {
+            header,
+            operations: Default::default(),
+        },
+        BlockSerializer::new(),
+        keypair,
+    )
+    .unwrap()
+}
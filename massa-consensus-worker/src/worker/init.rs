@@ -1,3 +1,4 @@
+use crossbeam_channel::Receiver;
 use massa_consensus_exports::{
     block_status::BlockStatus, bootstrapable_graph::BootstrapableGraph, error::ConsensusError,
     ConsensusConfig,
@@ -9,15 +10,16 @@ use massa_models::{
     block::{Block, BlockHeader, BlockHeaderSerializer, BlockId, BlockSerializer, WrappedBlock},
     prehash::PreHashMap,
     slot::Slot,
-    timeslots::{get_block_slot_timestamp, get_latest_block_slot_at_timestamp},
+    slot_scheduler::SlotScheduler,
+    timeslots::get_block_slot_timestamp,
     wrapped::WrappedContent,
 };
+use massa_signature::KeyPair;
 use massa_storage::Storage;
-use massa_time::MassaTime;
 use parking_lot::RwLock;
 use std::{
     collections::{HashMap, VecDeque},
-    sync::{mpsc, Arc},
+    sync::Arc,
 };
 use tracing::log::info;
 
@@ -28,22 +30,22 @@ use super::ConsensusWorker;
 /// Creates genesis block in given thread.
 ///
 /// # Arguments
-/// * `cfg`: consensus configuration
+/// * `keypair`: genesis key
 /// * `thread_number`: thread in which we want a genesis block
 ///
 /// # Returns
 /// A genesis block
 pub fn create_genesis_block(
-    cfg: &ConsensusConfig,
+    keypair: &KeyPair,
     thread_number: u8,
 ) -> Result<WrappedBlock, ConsensusError> {
-    let keypair = &cfg.genesis_key;
     let header = BlockHeader::new_wrapped(
         BlockHeader {
             slot: Slot::new(0, thread_number),
             parents: Vec::new(),
             operation_merkle_root: Hash::compute_from(&Vec::new()),
             endorsements: Vec::new(),
+            state_commitment: None,
         },
         BlockHeaderSerializer::new(),
         keypair,
@@ -69,30 +71,28 @@ impl ConsensusWorker {
     /// * `shared_state`: shared state with the controller
     /// * `init_graph`: Optional graph of blocks to initiate the worker
     /// * `storage`: shared storage
+    /// * `slot_scheduler`: source of time of the slots
     ///
     /// # Returns:
     /// A `ConsensusWorker`, to interact with it use the `ConsensusController`
     pub fn new(
         config: ConsensusConfig,
-        command_receiver: mpsc::Receiver<ConsensusCommand>,
+        command_receiver: Receiver<ConsensusCommand>,
         shared_state: Arc<RwLock<ConsensusState>>,
         init_graph: Option<BootstrapableGraph>,
         storage: Storage,
+        slot_scheduler: Box<dyn SlotScheduler>,
     ) -> Result<Self, ConsensusError> {
-        let now = MassaTime::now().expect("Couldn't init timer consensus");
-        let previous_slot = get_latest_block_slot_at_timestamp(
-            config.thread_count,
-            config.t0,
-            config.genesis_timestamp,
-            now,
-        )
-        .expect("Couldn't get the init slot consensus.");
+        let now = slot_scheduler.now().expect("Couldn't init timer consensus");
+        let previous_slot = slot_scheduler
+            .get_current_slot()
+            .expect("Couldn't get the init slot consensus.");
 
         // load genesis blocks
         let mut block_statuses = PreHashMap::default();
         let mut genesis_block_ids = Vec::with_capacity(config.thread_count as usize);
         for thread in 0u8..config.thread_count {
-            let block = create_genesis_block(&config, thread).map_err(|err| {
+            let block = create_genesis_block(&config.genesis_key, thread).map_err(|err| {
                 ConsensusError::GenesisCreationError(format!("genesis error {}", err))
             })?;
             let mut storage = storage.clone_without_refs();
@@ -119,13 +119,8 @@ impl ConsensusWorker {
         let next_slot = previous_slot.map_or(Ok(Slot::new(0u64, 0u8)), |s| {
             s.get_next_slot(config.thread_count)
         })?;
-        let next_instant = get_block_slot_timestamp(
-            config.thread_count,
-            config.t0,
-            config.genesis_timestamp,
-            next_slot,
-        )?
-        .estimate_instant()?;
+        let next_instant =
+            slot_scheduler.get_wakeup_instant(slot_scheduler.get_slot_timestamp(next_slot)?);
 
         info!(
             "Started node at time {}, cycle {}, period {}, thread {}",
@@ -169,6 +164,7 @@ impl ConsensusWorker {
             previous_slot,
             next_slot,
             next_instant,
+            slot_scheduler,
         };
 
         if let Some(BootstrapableGraph { final_blocks }) = init_graph {
@@ -1,11 +1,8 @@
-use std::{sync::mpsc, time::Instant};
+use crossbeam_channel::RecvTimeoutError;
+use std::time::Instant;
 
 use massa_consensus_exports::{error::ConsensusError, events::ConsensusEvent};
-use massa_models::{
-    slot::Slot,
-    timeslots::{get_block_slot_timestamp, get_closest_slot_to_timestamp},
-};
-use massa_time::MassaTime;
+use massa_models::slot::Slot;
 use tracing::log::{info, warn};
 
 use crate::commands::ConsensusCommand;
@@ -29,11 +26,13 @@ impl ConsensusWorker {
     fn manage_command(&mut self, command: ConsensusCommand) -> Result<(), ConsensusError> {
         let mut write_shared_state = self.shared_state.write();
         match command {
-            ConsensusCommand::RegisterBlockHeader(block_id, header) => {
+            ConsensusCommand::RegisterBlockHeader(block_id, header, span) => {
+                let _enter = span.enter();
                 write_shared_state.register_block_header(block_id, header, self.previous_slot)?;
                 write_shared_state.block_db_changed()
             }
-            ConsensusCommand::RegisterBlock(block_id, slot, block_storage, created) => {
+            ConsensusCommand::RegisterBlock(block_id, slot, block_storage, created, span) => {
+                let _enter = span.enter();
                 write_shared_state.register_block(
                     block_id,
                     slot,
@@ -47,6 +46,19 @@ impl ConsensusWorker {
                 write_shared_state.mark_invalid_block(&block_id, header);
                 Ok(())
             }
+            ConsensusCommand::DropPendingBlocks(block_ids) => {
+                write_shared_state.drop_pending_blocks(&block_ids);
+                write_shared_state.block_db_changed()
+            }
+            ConsensusCommand::AcceptReorg => {
+                if write_shared_state.reorg_alert.is_some() {
+                    info!("the rejected blockclique switch will be accepted whatever its depth");
+                    write_shared_state.reorg_accepted = true;
+                } else {
+                    info!("no blockclique switch is rejected, nothing to accept");
+                }
+                Ok(())
+            }
         }
     }
 
@@ -66,48 +78,30 @@ impl ConsensusWorker {
                 WaitingStatus::Interrupted
             }
             // timeout => continue main loop
-            Err(mpsc::RecvTimeoutError::Timeout) => WaitingStatus::Ended,
+            Err(RecvTimeoutError::Timeout) => WaitingStatus::Ended,
             // channel disconnected (sender dropped) => quit main loop
-            Err(mpsc::RecvTimeoutError::Disconnected) => WaitingStatus::Disconnected,
+            Err(RecvTimeoutError::Disconnected) => WaitingStatus::Disconnected,
         }
     }
 
-    /// Gets the next slot and the instant when it will happen.
+    /// Gets the next slot and the instant when it will happen, according to the slot scheduler.
     /// Slots can be skipped if we waited too much in-between.
     /// Extra safety against double-production caused by clock adjustments (this is the role of the `previous_slot` parameter).
     fn get_next_slot(&self, previous_slot: Option<Slot>) -> (Slot, Instant) {
-        // get current absolute time
-        let now = MassaTime::now().expect("could not get current time");
-
-        // get closest slot according to the current absolute time
-        let mut next_slot = get_closest_slot_to_timestamp(
-            self.config.thread_count,
-            self.config.t0,
-            self.config.genesis_timestamp,
-            now,
-        );
-
-        // protection against double-production on unexpected system clock adjustment
-        if let Some(prev_slot) = previous_slot {
-            if next_slot <= prev_slot {
-                next_slot = prev_slot
-                    .get_next_slot(self.config.thread_count)
-                    .expect("could not compute next slot");
-            }
-        }
-
-        // get the timestamp of the target slot
-        let next_instant = get_block_slot_timestamp(
-            self.config.thread_count,
-            self.config.t0,
-            self.config.genesis_timestamp,
-            next_slot,
-        )
-        .expect("could not get block slot timestamp")
-        .estimate_instant()
-        .expect("could not estimate block slot instant");
+        let next_slot = self
+            .slot_scheduler
+            .get_next_slot(previous_slot)
+            .expect("could not compute next slot");
+        (next_slot, self.get_slot_wakeup_instant(next_slot))
+    }
 
-        (next_slot, next_instant)
+    /// Gets the instant at which the worker must wake up to process a slot
+    fn get_slot_wakeup_instant(&self, slot: Slot) -> Instant {
+        let slot_timestamp = self
+            .slot_scheduler
+            .get_slot_timestamp(slot)
+            .expect("could not get block slot timestamp");
+        self.slot_scheduler.get_wakeup_instant(slot_timestamp)
     }
 
     /// Runs in loop forever. This loop must stop every slot to perform operations on stats and graph
@@ -117,8 +111,22 @@ impl ConsensusWorker {
         loop {
             match self.wait_slot_or_command(self.next_instant) {
                 WaitingStatus::Ended => {
+                    // the scheduler may wake us up before the slot is reached
+                    if !self
+                        .slot_scheduler
+                        .is_slot_reached(self.next_slot)
+                        .expect("could not get block slot timestamp")
+                    {
+                        self.next_instant = self.get_slot_wakeup_instant(self.next_slot);
+                        continue;
+                    }
                     if let Some(end) = self.config.end_timestamp {
-                        if self.next_instant > end.estimate_instant().unwrap() {
+                        if self
+                            .slot_scheduler
+                            .get_slot_timestamp(self.next_slot)
+                            .expect("could not get block slot timestamp")
+                            > end
+                        {
                             info!("This episode has come to an end, please get the latest testnet node version to continue");
                             let _ = self
                                 .shared_state
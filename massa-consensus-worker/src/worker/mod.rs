@@ -1,3 +1,4 @@
+use crossbeam_channel::{bounded, Receiver};
 use massa_consensus_exports::{
     bootstrapable_graph::BootstrapableGraph, ConsensusChannels, ConsensusConfig,
     ConsensusController, ConsensusManager,
@@ -7,10 +8,10 @@ use massa_models::clique::Clique;
 use massa_models::config::CHANNEL_SIZE;
 use massa_models::prehash::PreHashSet;
 use massa_models::slot::Slot;
+use massa_models::slot_scheduler::SlotScheduler;
 use massa_storage::Storage;
-use massa_time::MassaTime;
 use parking_lot::RwLock;
-use std::sync::{mpsc, Arc};
+use std::sync::Arc;
 use std::thread;
 use std::time::Instant;
 
@@ -22,7 +23,7 @@ use crate::state::ConsensusState;
 /// The consensus worker structure that contains all information and tools for the consensus worker thread.
 pub struct ConsensusWorker {
     /// Channel to receive command from the controller
-    command_receiver: mpsc::Receiver<ConsensusCommand>,
+    command_receiver: Receiver<ConsensusCommand>,
     /// Configuration of the consensus
     config: ConsensusConfig,
     /// State shared with the controller
@@ -33,11 +34,15 @@ pub struct ConsensusWorker {
     next_slot: Slot,
     /// Next slot instant
     next_instant: Instant,
+    /// Source of time of the slots
+    slot_scheduler: Box<dyn SlotScheduler>,
 }
 
-mod init;
+pub(crate) mod init;
 mod main_loop;
 
+pub use init::create_genesis_block;
+
 /// Create a new consensus worker thread.
 ///
 /// # Arguments:
@@ -45,6 +50,7 @@ mod main_loop;
 /// * `channels`: Channels to communicate with others modules
 /// * `init_graph`: Optional initial graph to bootstrap the graph. if None, the graph will have only genesis blocks.
 /// * `storage`: Storage to use for the consensus
+/// * `slot_scheduler`: Source of time of the slots
 ///
 /// # Returns:
 /// * The consensus controller to communicate with the consensus worker thread
@@ -54,12 +60,12 @@ pub fn start_consensus_worker(
     channels: ConsensusChannels,
     init_graph: Option<BootstrapableGraph>,
     storage: Storage,
+    slot_scheduler: Box<dyn SlotScheduler>,
 ) -> (Box<dyn ConsensusController>, Box<dyn ConsensusManager>) {
-    let (tx, rx) = mpsc::sync_channel(CHANNEL_SIZE);
+    let (tx, rx) = bounded(CHANNEL_SIZE);
     // desync detection timespan
     let bootstrap_part_size = config.bootstrap_part_size;
-    let stats_desync_detection_timespan =
-        config.t0.checked_mul(config.periods_per_cycle * 2).unwrap();
+    let stats_desync_detection_timespan = config.t0.saturating_mul(config.periods_per_cycle * 2);
     let shared_state = Arc::new(RwLock::new(ConsensusState {
         storage: storage.clone(),
         config: config.clone(),
@@ -89,18 +95,31 @@ pub fn start_consensus_worker(
         stale_block_stats: Default::default(),
         protocol_blocks: Default::default(),
         wishlist: Default::default(),
-        launch_time: MassaTime::now().unwrap(),
+        launch_time: slot_scheduler.now().unwrap(),
         stats_desync_detection_timespan,
         stats_history_timespan: std::cmp::max(
             stats_desync_detection_timespan,
             config.stats_timespan,
         ),
+        cycle_stats: Default::default(),
+        cycle_stats_cursor: None,
         prev_blockclique: Default::default(),
+        reorg_alert: None,
+        reorg_accepted: false,
+        state_divergence: None,
+        slot_scheduler: slot_scheduler.clone(),
     }));
 
     let shared_state_cloned = shared_state.clone();
-    let mut consensus_worker =
-        ConsensusWorker::new(config.clone(), rx, shared_state_cloned, init_graph, storage).unwrap();
+    let mut consensus_worker = ConsensusWorker::new(
+        config.clone(),
+        rx,
+        shared_state_cloned,
+        init_graph,
+        storage,
+        slot_scheduler,
+    )
+    .unwrap();
 
     let consensus_thread = thread::Builder::new()
         .name("consensus worker".into())
@@ -117,6 +136,7 @@ pub fn start_consensus_worker(
         shared_state,
         bootstrap_part_size,
         config.broadcast_enabled,
+        config.max_send_wait.to_duration(),
     );
 
     (Box::new(controller), Box::new(manager))
@@ -8,12 +8,14 @@ use crate::{ExecutionAddressInfo, ReadOnlyExecutionOutput};
 use massa_models::address::Address;
 use massa_models::amount::Amount;
 use massa_models::api::EventFilter;
+use massa_models::balance_proof::FinalBalanceProof;
 use massa_models::block::BlockId;
 use massa_models::operation::OperationId;
 use massa_models::output_event::SCOutputEvent;
 use massa_models::prehash::PreHashMap;
 use massa_models::prehash::PreHashSet;
 use massa_models::slot::Slot;
+use massa_models::state_commitment::StateCommitment;
 use massa_models::stats::ExecutionStats;
 use massa_storage::Storage;
 use std::collections::BTreeMap;
@@ -51,6 +53,17 @@ pub trait ExecutionController: Send + Sync {
         addresses: &[Address],
     ) -> Vec<(Option<Amount>, Option<Amount>)>;
 
+    /// Get the final balance of an address along with a Merkle proof
+    /// against the balance state root of the latest final slot
+    fn get_final_balance_proof(&self, address: &Address) -> FinalBalanceProof;
+
+    /// Get the commitment to the final state at the output of the latest final slot
+    fn get_final_state_commitment(&self) -> StateCommitment;
+
+    /// Get the commitment to the final state at the output of `slot`,
+    /// if it is among the recently finalized slots
+    fn get_final_state_commitment_at(&self, slot: Slot) -> Option<StateCommitment>;
+
     /// Get a copy of a single datastore entry with its final and active values
     ///
     /// # Return value
@@ -11,11 +11,13 @@ use massa_models::{
     address::Address,
     amount::Amount,
     api::EventFilter,
+    balance_proof::FinalBalanceProof,
     block::BlockId,
     operation::OperationId,
     output_event::SCOutputEvent,
     prehash::{PreHashMap, PreHashSet},
     slot::Slot,
+    state_commitment::StateCommitment,
     stats::ExecutionStats,
 };
 use massa_storage::Storage;
@@ -83,6 +85,25 @@ pub enum MockExecutionControllerMessage {
         /// response channel
         response_tx: mpsc::Sender<Vec<(Option<Amount>, Option<Amount>)>>,
     },
+    /// Get the final balance proof of an address
+    GetFinalBalanceProof {
+        /// address to prove
+        address: Address,
+        /// response channel
+        response_tx: mpsc::Sender<FinalBalanceProof>,
+    },
+    /// get the commitment to the latest final state
+    GetFinalStateCommitment {
+        /// response channel
+        response_tx: mpsc::Sender<StateCommitment>,
+    },
+    /// get the commitment to the final state of a slot
+    GetFinalStateCommitmentAt {
+        /// slot of the final state
+        slot: Slot,
+        /// response channel
+        response_tx: mpsc::Sender<Option<StateCommitment>>,
+    },
 }
 
 /// A mocked execution controller that will intercept calls on its methods
@@ -170,6 +191,51 @@ impl ExecutionController for MockExecutionController {
             .unwrap()
     }
 
+    fn get_final_balance_proof(&self, address: &Address) -> FinalBalanceProof {
+        let (response_tx, response_rx) = mpsc::channel();
+        if let Err(err) = self
+            .0
+            .lock()
+            .send(MockExecutionControllerMessage::GetFinalBalanceProof {
+                address: *address,
+                response_tx,
+            })
+        {
+            println!("mock error {err}");
+        }
+        response_rx
+            .recv_timeout(Duration::from_millis(100))
+            .unwrap()
+    }
+
+    fn get_final_state_commitment(&self) -> StateCommitment {
+        let (response_tx, response_rx) = mpsc::channel();
+        if let Err(err) = self
+            .0
+            .lock()
+            .send(MockExecutionControllerMessage::GetFinalStateCommitment { response_tx })
+        {
+            println!("mock error {err}");
+        }
+        response_rx
+            .recv_timeout(Duration::from_millis(100))
+            .unwrap()
+    }
+
+    fn get_final_state_commitment_at(&self, slot: Slot) -> Option<StateCommitment> {
+        let (response_tx, response_rx) = mpsc::channel();
+        if let Err(err) = self
+            .0
+            .lock()
+            .send(MockExecutionControllerMessage::GetFinalStateCommitmentAt { slot, response_tx })
+        {
+            println!("mock error {err}");
+        }
+        response_rx
+            .recv_timeout(Duration::from_millis(100))
+            .unwrap()
+    }
+
     fn get_final_and_active_data_entry(
         &self,
         _: Vec<(Address, Vec<u8>)>,
@@ -10,8 +10,10 @@ use massa_execution_exports::{
     ReadOnlyExecutionOutput, ReadOnlyExecutionRequest,
 };
 use massa_models::api::EventFilter;
+use massa_models::balance_proof::FinalBalanceProof;
 use massa_models::output_event::SCOutputEvent;
 use massa_models::prehash::{PreHashMap, PreHashSet};
+use massa_models::state_commitment::StateCommitment;
 use massa_models::stats::ExecutionStats;
 use massa_models::{address::Address, amount::Amount, operation::OperationId};
 use massa_models::{block::BlockId, slot::Slot};
@@ -169,6 +171,23 @@ impl ExecutionController for ExecutionControllerImpl {
         result
     }
 
+    /// Get the final balance of an address with a Merkle proof against the latest final balance state root
+    fn get_final_balance_proof(&self, address: &Address) -> FinalBalanceProof {
+        self.execution_state.read().get_final_balance_proof(address)
+    }
+
+    /// Get the commitment to the final state at the output of the latest final slot
+    fn get_final_state_commitment(&self) -> StateCommitment {
+        self.execution_state.read().get_final_state_commitment()
+    }
+
+    /// Get the commitment to the final state at the output of a recently finalized slot
+    fn get_final_state_commitment_at(&self, slot: Slot) -> Option<StateCommitment> {
+        self.execution_state
+            .read()
+            .get_final_state_commitment_at(slot)
+    }
+
     /// Return the active rolls distribution for the given `cycle`
     fn get_cycle_active_rolls(&self, cycle: u64) -> BTreeMap<Address, u64> {
         self.execution_state.read().get_cycle_active_rolls(cycle)
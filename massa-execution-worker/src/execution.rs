@@ -21,8 +21,10 @@ use massa_final_state::FinalState;
 use massa_ledger_exports::{SetOrDelete, SetUpdateOrDelete};
 use massa_models::address::ExecutionAddressCycleInfo;
 use massa_models::api::EventFilter;
+use massa_models::balance_proof::FinalBalanceProof;
 use massa_models::output_event::SCOutputEvent;
 use massa_models::prehash::PreHashSet;
+use massa_models::state_commitment::StateCommitment;
 use massa_models::stats::ExecutionStats;
 use massa_models::{
     address::Address,
@@ -1106,6 +1108,25 @@ impl ExecutionState {
         )
     }
 
+    /// Gets the final balance of an address with a Merkle proof against the latest final balance state root
+    pub fn get_final_balance_proof(&self, address: &Address) -> FinalBalanceProof {
+        let final_state = self.final_state.read();
+        FinalBalanceProof {
+            slot: final_state.slot,
+            proof: final_state.ledger.get_balance_proof(address),
+        }
+    }
+
+    /// Gets the commitment to the final state at the output of the latest final slot
+    pub fn get_final_state_commitment(&self) -> StateCommitment {
+        self.final_state.read().get_state_commitment()
+    }
+
+    /// Gets the commitment to the final state at the output of a recently finalized slot
+    pub fn get_final_state_commitment_at(&self, slot: Slot) -> Option<StateCommitment> {
+        self.final_state.read().get_state_commitment_at(slot)
+    }
+
     /// Gets roll counts both at the latest final and active executed slots
     pub fn get_final_and_candidate_rolls(&self, address: &Address) -> (u64, u64) {
         let final_rolls = self.final_state.read().pos_state.get_rolls_for(address);
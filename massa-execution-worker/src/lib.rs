@@ -75,6 +75,10 @@
 //!
 //! ## `stats.rs`
 //! Defines a structure that gathers execution statistics.
+//!
+//! ## `replay.rs`
+//! Implements `FinalSlotReplayer`
+//! that executes final slots without the worker thread, to replay stored blocks.
 
 #![warn(missing_docs)]
 #![warn(unused_crate_dependencies)]
@@ -88,6 +92,7 @@ mod context;
 mod controller;
 mod execution;
 mod interface_impl;
+mod replay;
 mod request_queue;
 mod slot_sequencer;
 mod speculative_async_pool;
@@ -97,6 +102,7 @@ mod speculative_roll_state;
 mod stats;
 mod worker;
 
+pub use replay::FinalSlotReplayer;
 pub use worker::start_execution_worker;
 
 #[cfg(any(feature = "gas_calibration", feature = "benchmarking"))]
@@ -0,0 +1,50 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Execution of final slots outside of the worker thread, used to replay stored blocks without networking.
+
+use crate::execution::ExecutionState;
+use massa_execution_exports::ExecutionConfig;
+use massa_final_state::FinalState;
+use massa_hash::Hash;
+use massa_models::block::BlockId;
+use massa_models::slot::Slot;
+use massa_pos_exports::SelectorController;
+use massa_storage::Storage;
+use parking_lot::RwLock;
+use std::sync::Arc;
+
+/// Executes final slots one after the other directly on a final state
+pub struct FinalSlotReplayer {
+    /// execution state writing to the final state
+    execution_state: ExecutionState,
+    /// final state the slots are applied to
+    final_state: Arc<RwLock<FinalState>>,
+    /// selector fed with the cycles of the final state
+    selector: Box<dyn SelectorController>,
+}
+
+impl FinalSlotReplayer {
+    /// Create a replayer executing the slots following the one of `final_state`
+    pub fn new(
+        config: ExecutionConfig,
+        final_state: Arc<RwLock<FinalState>>,
+        selector: Box<dyn SelectorController>,
+    ) -> Self {
+        FinalSlotReplayer {
+            execution_state: ExecutionState::new(config, final_state.clone()),
+            final_state,
+            selector,
+        }
+    }
+
+    /// Execute `slot` as final, with its block if it is not a miss.
+    /// The storage of the block must also hold the block it endorses, its parent in the same thread.
+    ///
+    /// # Returns
+    /// The hash of the final state at the output of `slot`
+    pub fn execute_final_slot(&mut self, slot: Slot, block: Option<&(BlockId, Storage)>) -> Hash {
+        self.execution_state
+            .execute_final_slot(&slot, block, self.selector.clone());
+        self.final_state.read().final_state_hash
+    }
+}
@@ -9,6 +9,7 @@ use massa_models::{
     block::BlockId,
     prehash::PreHashMap,
     slot::Slot,
+    slot_scheduler::SlotScheduler,
     timeslots::{get_block_slot_timestamp, get_latest_block_slot_at_timestamp},
 };
 use massa_storage::Storage;
@@ -46,6 +47,9 @@ pub struct SlotSequencer {
     /// Config
     config: ExecutionConfig,
 
+    /// source of the current time
+    slot_scheduler: Box<dyn SlotScheduler>,
+
     /// Continuous sequence of slots containing all the slots relevant for Execution and their current states (see `SlotInfo`). Oldest slot is at front.
     sequence: VecDeque<SlotInfo>,
 
@@ -69,7 +73,12 @@ impl SlotSequencer {
     ///
     /// # Arguments
     /// * `final_cursor`: latest executed SCE-final slot. This is useful on bootstrap in particular in order to avoid re-executing previously executed slots.
-    pub fn new(config: ExecutionConfig, final_cursor: Slot) -> Self {
+    /// * `slot_scheduler`: source of the current time
+    pub fn new(
+        config: ExecutionConfig,
+        final_cursor: Slot,
+        slot_scheduler: Box<dyn SlotScheduler>,
+    ) -> Self {
         SlotSequencer {
             sequence: Default::default(),
             latest_css_final_slots: (0..config.thread_count).map(|t| Slot::new(0, t)).collect(),
@@ -77,6 +86,7 @@ impl SlotSequencer {
             latest_executed_final_slot: final_cursor,
             latest_executed_candidate_slot: final_cursor,
             config,
+            slot_scheduler,
         }
     }
 
@@ -187,7 +197,9 @@ impl SlotSequencer {
     /// Note that this time cursor is shifted by `self.config.cursor_delay`
     /// to avoid computing speculative slots that are too recent, and therefore subject to frequent re-writes.
     fn get_time_cursor(&self) -> Slot {
-        let shifted_now = MassaTime::now()
+        let shifted_now = self
+            .slot_scheduler
+            .now()
             .expect("could not get current time")
             .saturating_sub(self.config.cursor_delay);
         get_latest_block_slot_at_timestamp(
@@ -726,7 +738,9 @@ impl SlotSequencer {
         // This means that we are still waiting for `Self::update` to be called for the first time.
         // To avoid CPU-intensive loops upstream, just register a wake-up after a single slot delay (t0/T).
         if self.sequence.is_empty() {
-            return MassaTime::now()
+            return self
+                .slot_scheduler
+                .now()
                 .expect("could not get current time")
                 .saturating_add(
                     self.config
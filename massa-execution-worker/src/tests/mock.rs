@@ -26,7 +26,10 @@ use massa_models::{
 };
 
 #[cfg(feature = "testing")]
-use massa_execution_exports::ExecutionError;
+use massa_execution_exports::{ExecutionConfig, ExecutionError};
+
+#[cfg(feature = "testing")]
+use massa_models::slot_scheduler::{RealTimeSlotScheduler, SlotScheduler, SlotTiming};
 
 #[cfg(feature = "testing")]
 use massa_hash::Hash;
@@ -144,7 +147,7 @@ pub fn create_block(
 ) -> Result<WrappedBlock, ExecutionError> {
     let operation_merkle_root = Hash::compute_from(
         &operations.iter().fold(Vec::new(), |acc, v| {
-            [acc, v.serialized_data.clone()].concat()
+            [acc, v.serialized_data.to_vec()].concat()
         })[..],
     );
 
@@ -154,6 +157,7 @@ pub fn create_block(
             parents: vec![],
             operation_merkle_root,
             endorsements: vec![],
+            state_commitment: None,
         },
         BlockHeaderSerializer::new(),
         &creator_keypair,
@@ -168,3 +172,13 @@ pub fn create_block(
         &creator_keypair,
     )?)
 }
+
+/// Slot scheduler following the system clock with the timing of `config`
+#[cfg(feature = "testing")]
+pub fn slot_scheduler(config: &ExecutionConfig) -> Box<dyn SlotScheduler> {
+    Box::new(RealTimeSlotScheduler::new(SlotTiming {
+        thread_count: config.thread_count,
+        t0: config.t0,
+        genesis_timestamp: config.genesis_timestamp,
+    }))
+}
@@ -1,7 +1,7 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
 use crate::start_execution_worker;
-use crate::tests::mock::{create_block, get_random_address_full, get_sample_state};
+use crate::tests::mock::{create_block, get_random_address_full, get_sample_state, slot_scheduler};
 use massa_execution_exports::{
     ExecutionConfig, ExecutionController, ExecutionError, ReadOnlyExecutionRequest,
     ReadOnlyExecutionTarget,
@@ -33,6 +33,7 @@ fn test_execution_shutdown() {
         ExecutionConfig::default(),
         sample_state.clone(),
         sample_state.read().pos_state.selector.clone(),
+        slot_scheduler(&ExecutionConfig::default()),
     );
     manager.stop();
 }
@@ -45,6 +46,7 @@ fn test_sending_command() {
         ExecutionConfig::default(),
         sample_state.clone(),
         sample_state.read().pos_state.selector.clone(),
+        slot_scheduler(&ExecutionConfig::default()),
     );
     controller.update_blockclique_status(
         Default::default(),
@@ -62,6 +64,7 @@ fn test_readonly_execution() {
         ExecutionConfig::default(),
         sample_state.clone(),
         sample_state.read().pos_state.selector.clone(),
+        slot_scheduler(&ExecutionConfig::default()),
     );
     let mut res = controller
         .execute_readonly_request(ReadOnlyExecutionRequest {
@@ -130,6 +133,7 @@ fn test_nested_call_gas_usage() {
         exec_cfg.clone(),
         sample_state.clone(),
         sample_state.read().pos_state.selector.clone(),
+        slot_scheduler(&exec_cfg),
     );
     // initialize the execution system with genesis blocks
     init_execution_worker(&exec_cfg, &storage, controller.clone());
@@ -274,6 +278,7 @@ fn send_and_receive_async_message() {
         exec_cfg.clone(),
         sample_state.clone(),
         sample_state.read().pos_state.selector.clone(),
+        slot_scheduler(&exec_cfg),
     );
     // initialize the execution system with genesis blocks
     init_execution_worker(&exec_cfg, &storage, controller.clone());
@@ -352,6 +357,7 @@ fn local_execution() {
         exec_cfg.clone(),
         sample_state.clone(),
         sample_state.read().pos_state.selector.clone(),
+        slot_scheduler(&exec_cfg),
     );
     // initialize the execution system with genesis blocks
     init_execution_worker(&exec_cfg, &storage, controller.clone());
@@ -453,6 +459,7 @@ fn sc_deployment() {
         exec_cfg.clone(),
         sample_state.clone(),
         sample_state.read().pos_state.selector.clone(),
+        slot_scheduler(&exec_cfg),
     );
     // initialize the execution system with genesis blocks
     init_execution_worker(&exec_cfg, &storage, controller.clone());
@@ -537,6 +544,7 @@ fn send_and_receive_async_message_with_trigger() {
         exec_cfg.clone(),
         sample_state.clone(),
         sample_state.read().pos_state.selector.clone(),
+        slot_scheduler(&exec_cfg),
     );
     // initialize the execution system with genesis blocks
     init_execution_worker(&exec_cfg, &storage, controller.clone());
@@ -676,6 +684,7 @@ pub fn send_and_receive_transaction() {
         exec_cfg.clone(),
         sample_state.clone(),
         sample_state.read().pos_state.selector.clone(),
+        slot_scheduler(&exec_cfg),
     );
     // initialize the execution system with genesis blocks
     init_execution_worker(&exec_cfg, &storage, controller.clone());
@@ -754,6 +763,7 @@ pub fn roll_buy() {
         exec_cfg.clone(),
         sample_state.clone(),
         sample_state.read().pos_state.selector.clone(),
+        slot_scheduler(&exec_cfg),
     );
     // initialize the execution system with genesis blocks
     init_execution_worker(&exec_cfg, &storage, controller.clone());
@@ -827,6 +837,7 @@ pub fn roll_sell() {
         exec_cfg.clone(),
         sample_state.clone(),
         sample_state.read().pos_state.selector.clone(),
+        slot_scheduler(&exec_cfg),
     );
     // initialize the execution system with genesis blocks
     init_execution_worker(&exec_cfg, &storage, controller.clone());
@@ -939,6 +950,7 @@ fn sc_execution_error() {
         exec_cfg.clone(),
         sample_state.clone(),
         sample_state.read().pos_state.selector.clone(),
+        slot_scheduler(&exec_cfg),
     );
     // initialize the execution system with genesis blocks
     init_execution_worker(&exec_cfg, &storage, controller.clone());
@@ -1002,6 +1014,7 @@ fn sc_datastore() {
         exec_cfg.clone(),
         sample_state.clone(),
         sample_state.read().pos_state.selector.clone(),
+        slot_scheduler(&exec_cfg),
     );
     // initialize the execution system with genesis blocks
     init_execution_worker(&exec_cfg, &storage, controller.clone());
@@ -1059,6 +1072,7 @@ fn set_bytecode_error() {
         exec_cfg.clone(),
         sample_state.clone(),
         sample_state.read().pos_state.selector.clone(),
+        slot_scheduler(&exec_cfg),
     );
     // initialize the execution system with genesis blocks
     init_execution_worker(&exec_cfg, &storage, controller.clone());
@@ -1120,6 +1134,7 @@ fn datastore_manipulations() {
         exec_cfg.clone(),
         sample_state.clone(),
         sample_state.read().pos_state.selector.clone(),
+        slot_scheduler(&exec_cfg),
     );
     // initialize the execution system with genesis blocks
     init_execution_worker(&exec_cfg, &storage, controller.clone());
@@ -1212,6 +1227,7 @@ fn events_from_switching_blockclique() {
         exec_cfg.clone(),
         sample_state.clone(),
         sample_state.read().pos_state.selector.clone(),
+        slot_scheduler(&exec_cfg),
     );
     // initialize the execution system with genesis blocks
     init_execution_worker(&exec_cfg, &storage, controller.clone());
@@ -1350,6 +1366,7 @@ fn sc_builtins() {
         exec_cfg.clone(),
         sample_state.clone(),
         sample_state.read().pos_state.selector.clone(),
+        slot_scheduler(&exec_cfg),
     );
     // initialize the execution system with genesis blocks
     init_execution_worker(&exec_cfg, &storage, controller.clone());
@@ -16,9 +16,9 @@ use massa_execution_exports::{
 use massa_final_state::FinalState;
 use massa_models::block::BlockId;
 use massa_models::slot::Slot;
+use massa_models::slot_scheduler::SlotScheduler;
 use massa_pos_exports::SelectorController;
 use massa_storage::Storage;
-use massa_time::MassaTime;
 use parking_lot::{Condvar, Mutex, RwLock};
 use std::sync::Arc;
 use std::thread;
@@ -36,6 +36,8 @@ pub(crate) struct ExecutionThread {
     readonly_requests: RequestQueue<ReadOnlyExecutionRequest, ReadOnlyExecutionOutput>,
     /// Selector controller
     selector: Box<dyn SelectorController>,
+    /// source of the current time
+    slot_scheduler: Box<dyn SlotScheduler>,
 }
 
 impl ExecutionThread {
@@ -46,11 +48,13 @@ impl ExecutionThread {
     /// * `config`: execution configuration
     /// * `input_data`: a copy of the input data interface to get incoming requests from
     /// * `execution_state`: an thread-safe shared access to the execution state, which can be bootstrapped or newly created
+    /// * `slot_scheduler`: source of the current time
     pub fn new(
         config: ExecutionConfig,
         input_data: Arc<(Condvar, Mutex<ExecutionInputData>)>,
         execution_state: Arc<RwLock<ExecutionState>>,
         selector: Box<dyn SelectorController>,
+        slot_scheduler: Box<dyn SlotScheduler>,
     ) -> Self {
         // get the latest executed final slot, at the output of which the final ledger is attached
         let final_cursor = execution_state.read().final_cursor;
@@ -60,8 +64,9 @@ impl ExecutionThread {
             input_data,
             readonly_requests: RequestQueue::new(config.readonly_queue_length),
             execution_state,
-            slot_sequencer: SlotSequencer::new(config, final_cursor),
+            slot_sequencer: SlotSequencer::new(config, final_cursor, slot_scheduler.clone()),
             selector,
+            slot_scheduler,
         }
     }
 
@@ -138,7 +143,10 @@ impl ExecutionThread {
             // Compute when the next slot will be
             // This is useful to wait for the next speculative miss to append to active slots.
             let wakeup_deadline = self.slot_sequencer.get_next_slot_deadline();
-            let now = MassaTime::now().expect("could not get current time");
+            let now = self
+                .slot_scheduler
+                .now()
+                .expect("could not get current time");
             if wakeup_deadline <= now {
                 // next slot is right now: the loop needs to iterate
                 return (input_data, false);
@@ -148,9 +156,7 @@ impl ExecutionThread {
             // The return value is ignored because we don't care what woke up the condition variable.
             let _ = self.input_data.0.wait_until(
                 &mut input_data_lock,
-                wakeup_deadline
-                    .estimate_instant()
-                    .expect("could not estimate instant"),
+                self.slot_scheduler.get_wakeup_instant(wakeup_deadline),
             );
         }
     }
@@ -228,6 +234,8 @@ impl ExecutionThread {
 /// # parameters
 /// * `config`: execution configuration
 /// * `final_state`: a thread-safe shared access to the final state for reading and writing
+/// * `selector`: selector controller, to get the block producers of the missed slots
+/// * `slot_scheduler`: source of the current time, the slots are executed as they happen
 ///
 /// # Returns
 /// A pair `(execution_manager, execution_controller)` where:
@@ -237,6 +245,7 @@ pub fn start_execution_worker(
     config: ExecutionConfig,
     final_state: Arc<RwLock<FinalState>>,
     selector: Box<dyn SelectorController>,
+    slot_scheduler: Box<dyn SlotScheduler>,
 ) -> (Box<dyn ExecutionManager>, Box<dyn ExecutionController>) {
     // create an execution state
     let execution_state = Arc::new(RwLock::new(ExecutionState::new(
@@ -261,7 +270,14 @@ pub fn start_execution_worker(
     let thread_builder = thread::Builder::new().name("execution".into());
     let thread_handle = thread_builder
         .spawn(move || {
-            ExecutionThread::new(config, input_data_clone, execution_state, selector).main_loop();
+            ExecutionThread::new(
+                config,
+                input_data_clone,
+                execution_state,
+                selector,
+                slot_scheduler,
+            )
+            .main_loop();
         })
         .expect("failed to spawn thread : execution");
     // create a manager
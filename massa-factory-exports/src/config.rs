@@ -2,6 +2,7 @@
 
 //! This file defines the factory settings
 
+use massa_models::upgrade_schedule::UpgradeSchedule;
 use massa_time::MassaTime;
 
 /// Structure defining the settings of the factory
@@ -24,4 +25,19 @@ pub struct FactoryConfig {
 
     /// maximal block gas
     pub max_block_gas: u64,
+
+    /// blocks are produced once the latest blockclique block is at most this number of periods behind the current slot
+    pub catch_up_max_lag_periods: u64,
+
+    /// maximal duration of the catch-up with the network after startup, blocks are produced once it is elapsed
+    pub catch_up_timeout: MassaTime,
+
+    /// blocks are not produced while the node is connected to fewer nodes, 0 to disable
+    pub min_production_peers: usize,
+
+    /// blocks are not produced while the node is not synced with the network
+    pub produce_only_when_synced: bool,
+
+    /// slots from which the protocol features are active: the blocks only use them from there
+    pub upgrade_schedule: UpgradeSchedule,
 }
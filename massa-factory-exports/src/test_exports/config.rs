@@ -1,6 +1,7 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
 use crate::FactoryConfig;
+use massa_models::upgrade_schedule::UpgradeSchedule;
 use massa_time::MassaTime;
 
 impl Default for FactoryConfig {
@@ -13,6 +14,11 @@ impl Default for FactoryConfig {
             initial_delay: MassaTime::from(0),
             max_block_size: MAX_BLOCK_SIZE as u64,
             max_block_gas: MAX_GAS_PER_BLOCK,
+            catch_up_max_lag_periods: 2,
+            catch_up_timeout: MassaTime::from(0),
+            min_production_peers: 0,
+            produce_only_when_synced: false,
+            upgrade_schedule: UpgradeSchedule::all_active(),
         }
     }
 }
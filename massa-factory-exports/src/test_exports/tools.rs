@@ -14,6 +14,7 @@ pub fn create_empty_block(keypair: &KeyPair, slot: &Slot) -> WrappedBlock {
             parents: Vec::new(),
             operation_merkle_root: Hash::compute_from(&Vec::new()),
             endorsements: Vec::new(),
+            state_commitment: None,
         },
         BlockHeaderSerializer::new(),
         keypair,
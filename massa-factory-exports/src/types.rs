@@ -1,5 +1,9 @@
 use massa_consensus_exports::ConsensusController;
-use massa_models::block::Block;
+use massa_execution_exports::ExecutionController;
+use massa_models::{
+    block::Block, production_gate::ProductionGate, slot_scheduler::SlotScheduler,
+    sync_progress::SyncProgressStatus,
+};
 use massa_pool_exports::PoolController;
 use massa_pos_exports::SelectorController;
 use massa_protocol_exports::ProtocolCommandSender;
@@ -16,10 +20,18 @@ pub struct FactoryChannels {
     pub selector: Box<dyn SelectorController>,
     /// consensus controller
     pub consensus: Box<dyn ConsensusController>,
+    /// execution controller, to commit to the final state in the produced blocks
+    pub execution: Box<dyn ExecutionController>,
     /// pool controller
     pub pool: Box<dyn PoolController>,
     /// protocol controller
     pub protocol: ProtocolCommandSender,
     /// storage instance
     pub storage: Storage,
+    /// connected nodes and administrator override, checked before producing blocks
+    pub production_gate: ProductionGate,
+    /// progress of the synchronization, checked before producing blocks
+    pub sync_progress: SyncProgressStatus,
+    /// source of the current time, the blocks and endorsements are produced as their slots happen
+    pub slot_scheduler: Box<dyn SlotScheduler>,
 }
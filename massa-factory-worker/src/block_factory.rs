@@ -1,14 +1,15 @@
 //! Copyright (c) 2022 MASSA LABS <info@massa.net>
 
 use massa_factory_exports::{FactoryChannels, FactoryConfig};
-use massa_hash::Hash;
 use massa_models::{
-    block::{Block, BlockHeader, BlockHeaderSerializer, BlockId, BlockSerializer, WrappedHeader},
+    api::{ProductionOverride, SyncPhase},
+    block::BlockId,
+    builder::{BlockBuilder, BlockLimits},
     endorsement::WrappedEndorsement,
     prehash::PreHashSet,
     slot::Slot,
     timeslots::{get_block_slot_timestamp, get_closest_slot_to_timestamp},
-    wrapped::WrappedContent,
+    upgrade_schedule::ProtocolFeature,
 };
 use massa_time::MassaTime;
 use massa_wallet::Wallet;
@@ -26,6 +27,8 @@ pub(crate) struct BlockFactoryWorker {
     wallet: Arc<RwLock<Wallet>>,
     channels: FactoryChannels,
     factory_receiver: mpsc::Receiver<()>,
+    /// end of the catch-up with the network after startup, `None` once it is over
+    catch_up_deadline: Option<Instant>,
 }
 
 impl BlockFactoryWorker {
@@ -40,23 +43,29 @@ impl BlockFactoryWorker {
         thread::Builder::new()
             .name("block-factory".into())
             .spawn(|| {
+                let catch_up_deadline = Some(Instant::now() + cfg.catch_up_timeout.to_duration());
                 let mut this = Self {
                     cfg,
                     wallet,
                     channels,
                     factory_receiver,
+                    catch_up_deadline,
                 };
                 this.run();
             })
             .expect("failed to spawn thread : block-factory")
     }
 
-    /// Gets the next slot and the instant when it will happen.
+    /// Gets the next slot and the timestamp when it will happen.
     /// Slots can be skipped if we waited too much in-between.
     /// Extra safety against double-production caused by clock adjustments (this is the role of the `previous_slot` parameter).
-    fn get_next_slot(&self, previous_slot: Option<Slot>) -> (Slot, Instant) {
+    fn get_next_slot(&self, previous_slot: Option<Slot>) -> (Slot, MassaTime) {
         // get current absolute time
-        let now = MassaTime::now().expect("could not get current time");
+        let now = self
+            .channels
+            .slot_scheduler
+            .now()
+            .expect("could not get current time");
 
         // if it's the first computed slot, add a time shift to prevent double-production on node restart with clock skew
         let base_time = if previous_slot.is_none() {
@@ -88,32 +97,96 @@ impl BlockFactoryWorker {
         }
 
         // get the timestamp of the target slot
-        let next_instant = get_block_slot_timestamp(
+        let next_timestamp = get_block_slot_timestamp(
             self.cfg.thread_count,
             self.cfg.t0,
             self.cfg.genesis_timestamp,
             next_slot,
         )
-        .expect("could not get block slot timestamp")
-        .estimate_instant()
-        .expect("could not estimate block slot instant");
+        .expect("could not get block slot timestamp");
 
-        (next_slot, next_instant)
+        (next_slot, next_timestamp)
     }
 
-    /// Wait and interrupt or wait until an instant or a stop signal
+    /// Wait and interrupt or wait until a timestamp or a stop signal
     ///
     /// # Return value
-    /// Returns `true` if the instant was reached, otherwise `false` if there was an interruption.
-    fn interruptible_wait_until(&self, deadline: Instant) -> bool {
-        match self.factory_receiver.recv_deadline(deadline) {
-            // message received => quit main loop
-            Ok(()) => false,
-            // timeout => continue main loop
-            Err(mpsc::RecvTimeoutError::Timeout) => true,
-            // channel disconnected (sender dropped) => quit main loop
-            Err(mpsc::RecvTimeoutError::Disconnected) => false,
+    /// Returns `true` if the timestamp was reached, otherwise `false` if there was an interruption.
+    fn interruptible_wait_until(&self, timestamp: MassaTime) -> bool {
+        let slot_scheduler = &self.channels.slot_scheduler;
+        // the wakeup instant is only an estimation: wait again until the timestamp is actually reached
+        while slot_scheduler.now().expect("could not get current time") < timestamp {
+            match self
+                .factory_receiver
+                .recv_deadline(slot_scheduler.get_wakeup_instant(timestamp))
+            {
+                // message received => quit main loop
+                Ok(()) => return false,
+                // timeout => check the time again
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                // channel disconnected (sender dropped) => quit main loop
+                Err(mpsc::RecvTimeoutError::Disconnected) => return false,
+            }
+        }
+        true
+    }
+
+    /// Check whether the node is still catching up with the network after startup.
+    ///
+    /// The catch-up ends once the latest best parent is at most `catch_up_max_lag_periods` behind `slot`,
+    /// or once `catch_up_timeout` is elapsed. Blocks are not produced before, as they would be built
+    /// on top of outdated parents and rejected as stale by the network.
+    fn is_catching_up(&mut self, slot: Slot, parents: &[(BlockId, u64)]) -> bool {
+        let deadline = match self.catch_up_deadline {
+            Some(deadline) => deadline,
+            None => return false,
+        };
+        let latest_period = parents.iter().map(|(_, period)| *period).max().unwrap_or(0);
+        let lag = slot.period.saturating_sub(latest_period);
+        if lag <= self.cfg.catch_up_max_lag_periods {
+            info!(
+                "block factory caught up with the network at slot {}, starting block production",
+                slot
+            );
+        } else if Instant::now() >= deadline {
+            warn!(
+                "block factory still {} periods behind the network at slot {} after the catch-up timeout, starting block production anyway",
+                lag, slot
+            );
+        } else {
+            return true;
+        }
+        self.catch_up_deadline = None;
+        false
+    }
+
+    /// Check the safety interlock of the production, returning why blocks must not be produced if so.
+    ///
+    /// Unless overridden by the node administrator, blocks are not produced while fewer than `min_production_peers`
+    /// nodes are connected or, if `produce_only_when_synced` is set, while the node is not synced:
+    /// the blocks of a node cut from the network would fork it off once reconnected.
+    fn get_interlock_reason(&self) -> Option<String> {
+        match self.channels.production_gate.get_override() {
+            ProductionOverride::AlwaysProduce => return None,
+            ProductionOverride::NeverProduce => {
+                return Some("production paused by the node administrator".into())
+            }
+            ProductionOverride::Automatic => {}
+        }
+        let connected_node_count = self.channels.production_gate.get_connected_node_count();
+        if connected_node_count < self.cfg.min_production_peers {
+            return Some(format!(
+                "connected to {} nodes, at least {} required",
+                connected_node_count, self.cfg.min_production_peers
+            ));
+        }
+        if self.cfg.produce_only_when_synced {
+            let phase = self.channels.sync_progress.get().phase;
+            if phase != SyncPhase::Synced {
+                return Some(format!("the node is {}", phase));
+            }
         }
+        None
     }
 
     /// Process a slot: produce a block at that slot if one of the managed keys is drawn.
@@ -131,19 +204,37 @@ impl BlockFactoryWorker {
         };
 
         // check if the block producer address is handled by the wallet
-        let block_producer_keypair_ref = self.wallet.read();
-        let block_producer_keypair = if let Some(kp) =
-            block_producer_keypair_ref.find_associated_keypair(&block_producer_addr)
-        {
-            // the selected block producer is managed locally => continue to attempt block production
-            kp
-        } else {
-            // the selected block producer is not managed locally => quit
+        let wallet = self.wallet.read();
+        let block_producer_signer =
+            if let Some(signer) = wallet.find_associated_signer(&block_producer_addr) {
+                // the selected block producer is managed locally => continue to attempt block production
+                signer
+            } else {
+                // the selected block producer is not managed locally => quit
+                return;
+            };
+        // do not produce while the safety interlock is engaged
+        if let Some(reason) = self.get_interlock_reason() {
+            warn!(
+                "block factory skipped the production of slot {}: {}",
+                slot, reason
+            );
             return;
-        };
+        }
+
         // get best parents and their periods
         let parents: Vec<(BlockId, u64)> = self.channels.consensus.get_best_parents(); // Vec<(parent_id, parent_period)>
-                                                                                       // generate the local storage object
+
+        // do not produce while catching up with the network
+        if self.is_catching_up(slot, &parents) {
+            info!(
+                "block factory skipped the production of slot {}: still catching up with the network",
+                slot
+            );
+            return;
+        }
+
+        // generate the local storage object
         let mut block_storage = self.channels.storage.clone_without_refs();
 
         // claim block parents in local storage
@@ -186,39 +277,56 @@ impl BlockFactoryWorker {
         };
         block_storage.extend(endo_storage);
 
-        // gather operations and compute global operations hash
-        let (op_ids, op_storage) = self.channels.pool.get_block_operations(&slot);
-        block_storage.extend(op_storage);
-        let global_operations_hash = Hash::compute_from(
-            &op_ids
-                .iter()
-                .flat_map(|op_id| *op_id.to_bytes())
-                .collect::<Vec<u8>>(),
-        );
+        // commit to our latest final state, once the network activated the commitments
+        let state_commitment = self
+            .cfg
+            .upgrade_schedule
+            .is_active(ProtocolFeature::StateCommitment, slot)
+            .then(|| self.channels.execution.get_final_state_commitment())
+            .filter(|state_commitment| state_commitment.slot < slot);
 
-        // create header
-        let header: WrappedHeader = BlockHeader::new_wrapped::<BlockHeaderSerializer, BlockId>(
-            BlockHeader {
-                slot,
-                parents: parents.into_iter().map(|(id, _period)| id).collect(),
-                operation_merkle_root: global_operations_hash,
-                endorsements,
+        let mut block_builder = BlockBuilder::new(
+            slot,
+            BlockLimits {
+                thread_count: self.cfg.thread_count,
+                max_block_size: self.cfg.max_block_size,
+                ..Default::default()
             },
-            BlockHeaderSerializer::new(), // TODO reuse self.block_header_serializer
-            block_producer_keypair,
         )
-        .expect("error while producing block header");
+        .parents(parents.into_iter().map(|(id, _period)| id).collect())
+        .endorsements(endorsements)
+        .state_commitment(state_commitment);
 
-        // create block
-        let block = Block::new_wrapped(
-            Block {
-                header,
-                operations: op_ids.into_iter().collect(),
-            },
-            BlockSerializer::new(), // TODO reuse self.block_serializer
-            block_producer_keypair,
-        )
-        .expect("error while producing block");
+        // gather operations
+        let (op_ids, op_storage) = self.channels.pool.get_block_operations(&slot);
+        {
+            let stored_operations = op_storage.read_operations();
+            for op_id in &op_ids {
+                let operation = stored_operations
+                    .get(op_id)
+                    .expect("could not retrieve operation");
+                if let Err(err) = block_builder.add_operation(operation) {
+                    warn!(
+                        "block factory could not add operation {} to the block of slot {}: {}",
+                        op_id, slot, err
+                    );
+                    return;
+                }
+            }
+        }
+        block_storage.extend(op_storage);
+
+        // create the signed header and block
+        let block = match block_builder.build(block_producer_signer) {
+            Ok(block) => block,
+            Err(err) => {
+                warn!(
+                    "block factory could not produce the block of slot {}: {}",
+                    slot, err
+                );
+                return;
+            }
+        };
         let block_id = block.id;
         // store block in storage
         block_storage.store_block(block);
@@ -240,10 +348,10 @@ impl BlockFactoryWorker {
         let mut prev_slot = None;
         loop {
             // get next slot
-            let (slot, block_instant) = self.get_next_slot(prev_slot);
+            let (slot, block_timestamp) = self.get_next_slot(prev_slot);
 
             // wait until slot
-            if !self.interruptible_wait_until(block_instant) {
+            if !self.interruptible_wait_until(block_timestamp) {
                 break;
             }
 
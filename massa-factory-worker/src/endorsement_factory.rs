@@ -2,20 +2,19 @@
 
 use massa_factory_exports::{FactoryChannels, FactoryConfig};
 use massa_models::{
+    address::Address,
     block::BlockId,
     endorsement::{Endorsement, EndorsementSerializer, WrappedEndorsement},
     slot::Slot,
     timeslots::{get_block_slot_timestamp, get_closest_slot_to_timestamp},
     wrapped::WrappedContent,
 };
-use massa_signature::KeyPair;
 use massa_time::MassaTime;
 use massa_wallet::Wallet;
 use parking_lot::RwLock;
 use std::{
     sync::{mpsc, Arc},
     thread,
-    time::Instant,
 };
 use tracing::{debug, warn};
 
@@ -57,12 +56,16 @@ impl EndorsementFactoryWorker {
             .expect("failed to spawn thread : endorsement-factory")
     }
 
-    /// Gets the next slot and the instant when the corresponding endorsements should be made.
+    /// Gets the next slot and the timestamp when the corresponding endorsements should be made.
     /// Slots can be skipped if we waited too much in-between.
     /// Extra safety against double-production caused by clock adjustments (this is the role of the `previous_slot` parameter).
-    fn get_next_slot(&self, previous_slot: Option<Slot>) -> (Slot, Instant) {
+    fn get_next_slot(&self, previous_slot: Option<Slot>) -> (Slot, MassaTime) {
         // get delayed time
-        let now = MassaTime::now().expect("could not get current time");
+        let now = self
+            .channels
+            .slot_scheduler
+            .now()
+            .expect("could not get current time");
 
         // if it's the first computed slot, add a time shift to prevent double-production on node restart with clock skew
         let base_time = if previous_slot.is_none() {
@@ -94,33 +97,39 @@ impl EndorsementFactoryWorker {
         }
 
         // get the timestamp of the target slot
-        let next_instant = get_block_slot_timestamp(
+        let next_timestamp = get_block_slot_timestamp(
             self.cfg.thread_count,
             self.cfg.t0,
             self.cfg.genesis_timestamp,
             next_slot,
         )
         .expect("could not get block slot timestamp")
-        .saturating_sub(self.half_t0)
-        .estimate_instant()
-        .expect("could not estimate block slot instant");
+        .saturating_sub(self.half_t0);
 
-        (next_slot, next_instant)
+        (next_slot, next_timestamp)
     }
 
-    /// Wait and interrupt or wait until an instant or a stop signal
+    /// Wait and interrupt or wait until a timestamp or a stop signal
     ///
     /// # Return value
-    /// Returns `true` if the instant was reached, otherwise `false` if there was an interruption.
-    fn interruptible_wait_until(&self, deadline: Instant) -> bool {
-        match self.factory_receiver.recv_deadline(deadline) {
-            // message received => quit main loop
-            Ok(()) => false,
-            // timeout => continue main loop
-            Err(mpsc::RecvTimeoutError::Timeout) => true,
-            // channel disconnected (sender dropped) => quit main loop
-            Err(mpsc::RecvTimeoutError::Disconnected) => false,
+    /// Returns `true` if the timestamp was reached, otherwise `false` if there was an interruption.
+    fn interruptible_wait_until(&self, timestamp: MassaTime) -> bool {
+        let slot_scheduler = &self.channels.slot_scheduler;
+        // the wakeup instant is only an estimation: wait again until the timestamp is actually reached
+        while slot_scheduler.now().expect("could not get current time") < timestamp {
+            match self
+                .factory_receiver
+                .recv_deadline(slot_scheduler.get_wakeup_instant(timestamp))
+            {
+                // message received => quit main loop
+                Ok(()) => return false,
+                // timeout => check the time again
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                // channel disconnected (sender dropped) => quit main loop
+                Err(mpsc::RecvTimeoutError::Disconnected) => return false,
+            }
         }
+        true
     }
 
     /// Process a slot: produce an endorsement at that slot if one of the managed keys is drawn.
@@ -138,20 +147,16 @@ impl EndorsementFactoryWorker {
         };
 
         // get creators if they are managed by our wallet
-        let mut producers_indices: Vec<(KeyPair, usize)> = Vec::new();
+        // (only their addresses are kept: the keys stay in the wallet until signing)
+        let mut producers_indices: Vec<(Address, usize)> = Vec::new();
         {
             let wallet = self.wallet.read();
             for (index, producer_addr) in producer_addrs.into_iter().enumerate() {
                 // check if the block producer address is handled by the wallet
-                let producer_keypair =
-                    if let Some(kp) = wallet.find_associated_keypair(&producer_addr) {
-                        // the selected block producer is managed locally => continue to attempt endorsement production
-                        kp.clone()
-                    } else {
-                        // the selected block producer is not managed locally => continue
-                        continue;
-                    };
-                producers_indices.push((producer_keypair, index));
+                if wallet.find_associated_signer(&producer_addr).is_some() {
+                    // the selected block producer is managed locally => continue to attempt endorsement production
+                    producers_indices.push((producer_addr, index));
+                }
             }
         }
 
@@ -168,7 +173,14 @@ impl EndorsementFactoryWorker {
 
         // produce endorsements
         let mut endorsements: Vec<WrappedEndorsement> = Vec::with_capacity(producers_indices.len());
-        for (keypair, index) in producers_indices {
+        let wallet = self.wallet.read();
+        for (producer_addr, index) in producers_indices {
+            let signer = if let Some(signer) = wallet.find_associated_signer(&producer_addr) {
+                signer
+            } else {
+                // the key was removed from the wallet in the meantime
+                continue;
+            };
             let endorsement = Endorsement::new_wrapped(
                 Endorsement {
                     slot,
@@ -176,7 +188,7 @@ impl EndorsementFactoryWorker {
                     endorsed_block,
                 },
                 self.endorsement_serializer.clone(),
-                &keypair,
+                signer,
             )
             .expect("could not create endorsement");
 
@@ -188,6 +200,7 @@ impl EndorsementFactoryWorker {
 
             endorsements.push(endorsement);
         }
+        drop(wallet);
 
         // store endorsements
         let mut endo_storage = self.channels.storage.clone_without_refs();
@@ -206,10 +219,10 @@ impl EndorsementFactoryWorker {
         let mut prev_slot = None;
         loop {
             // get next slot
-            let (slot, endorsement_instant) = self.get_next_slot(prev_slot);
+            let (slot, endorsement_timestamp) = self.get_next_slot(prev_slot);
 
             // wait until slot
-            if !self.interruptible_wait_until(endorsement_instant) {
+            if !self.interruptible_wait_until(endorsement_timestamp) {
                 break;
             }
 
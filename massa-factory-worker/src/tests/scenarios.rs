@@ -1,6 +1,8 @@
 use super::TestFactory;
+use massa_factory_exports::FactoryConfig;
 use massa_models::{
     amount::Amount,
+    api::{ProductionOverride, SyncPhase, SyncProgress},
     operation::{Operation, OperationSerializer, OperationType},
     wrapped::WrappedContent,
 };
@@ -61,3 +63,62 @@ fn basic_creation_with_multiple_operations() {
     }
     assert_eq!(block.content.operations.len(), 2);
 }
+
+/// Blocks are not produced while too few nodes are connected, unless overridden.
+#[test]
+#[ignore]
+fn no_creation_with_too_few_connected_nodes() {
+    let keypair = KeyPair::generate();
+    let mut test_factory = TestFactory::new_with_config(
+        &keypair,
+        FactoryConfig {
+            min_production_peers: 2,
+            ..Default::default()
+        },
+    );
+    test_factory.production_gate.set_connected_node_count(1);
+    test_factory.assert_no_block_created();
+
+    test_factory.production_gate.set_connected_node_count(2);
+    let (block_id, storage) = test_factory.get_next_created_block(None, None);
+    assert_eq!(block_id, storage.read_blocks().get(&block_id).unwrap().id);
+
+    test_factory
+        .production_gate
+        .set_override(ProductionOverride::NeverProduce);
+    test_factory.assert_no_block_created();
+}
+
+/// Blocks are not produced while the node is not synced, unless overridden.
+#[test]
+#[ignore]
+fn no_creation_when_not_synced() {
+    let keypair = KeyPair::generate();
+    let mut test_factory = TestFactory::new_with_config(
+        &keypair,
+        FactoryConfig {
+            produce_only_when_synced: true,
+            ..Default::default()
+        },
+    );
+    test_factory.sync_progress.set(SyncProgress {
+        phase: SyncPhase::CatchingUp,
+        ..Default::default()
+    });
+    test_factory.assert_no_block_created();
+
+    test_factory
+        .production_gate
+        .set_override(ProductionOverride::AlwaysProduce);
+    let (block_id, _) = test_factory.get_next_created_block(None, None);
+
+    test_factory
+        .production_gate
+        .set_override(ProductionOverride::Automatic);
+    test_factory.sync_progress.set(SyncProgress {
+        phase: SyncPhase::Synced,
+        ..Default::default()
+    });
+    let (next_block_id, _) = test_factory.get_next_created_block(None, None);
+    assert_ne!(block_id, next_block_id);
+}
@@ -4,17 +4,29 @@ use massa_consensus_exports::test_exports::{
 use parking_lot::RwLock;
 use std::{
     sync::{mpsc::Receiver, Arc},
-    thread::sleep,
     time::Duration,
 };
 
+use massa_execution_exports::test_exports::{
+    MockExecutionController, MockExecutionControllerMessage,
+};
 use massa_factory_exports::{
     test_exports::create_empty_block, FactoryChannels, FactoryConfig, FactoryManager,
 };
+use massa_hash::Hash;
 use massa_models::{
-    address::Address, block::BlockId, config::ENDORSEMENT_COUNT, endorsement::WrappedEndorsement,
-    operation::WrappedOperation, prehash::PreHashMap, slot::Slot,
-    test_exports::get_next_slot_instant,
+    address::Address,
+    block::BlockId,
+    config::ENDORSEMENT_COUNT,
+    endorsement::WrappedEndorsement,
+    operation::WrappedOperation,
+    prehash::PreHashMap,
+    production_gate::ProductionGate,
+    slot::Slot,
+    slot_scheduler::{SlotScheduler, SlotTiming},
+    state_commitment::StateCommitment,
+    sync_progress::SyncProgressStatus,
+    test_exports::MockSlotScheduler,
 };
 use massa_pool_exports::test_exports::{
     MockPoolController, MockPoolControllerMessage, PoolEventReceiver,
@@ -35,15 +47,23 @@ use massa_wallet::test_exports::create_test_wallet;
 /// The factory will ask that to the the pool, consensus and factory and then will send the block to the consensus.
 /// You can use the method `new` to build all the mocks and make the connections
 /// Then you can use the method `get_next_created_block` that will manage the answers from the mock to the factory depending on the parameters you gave.
+/// The factory runs on a `MockSlotScheduler`: its time only moves from one slot to the next when the test asks for a block.
 pub struct TestFactory {
     consensus_event_receiver: ConsensusEventReceiver,
     pool_receiver: PoolEventReceiver,
     selector_receiver: Receiver<MockSelectorControllerMessage>,
+    execution_receiver: Receiver<MockExecutionControllerMessage>,
     factory_config: FactoryConfig,
     factory_manager: Box<dyn FactoryManager>,
     genesis_blocks: Vec<(BlockId, u64)>,
     storage: Storage,
     keypair: KeyPair,
+    slot_scheduler: MockSlotScheduler,
+    next_slot: Slot,
+    /// connected nodes and production override read by the factory
+    pub production_gate: ProductionGate,
+    /// sync progress read by the factory
+    pub sync_progress: SyncProgressStatus,
 }
 
 impl TestFactory {
@@ -54,12 +74,29 @@ impl TestFactory {
     /// Returns
     /// - `TestFactory`: the structure that will be used to manage the tests
     pub fn new(default_keypair: &KeyPair) -> TestFactory {
+        TestFactory::new_with_config(default_keypair, FactoryConfig::default())
+    }
+
+    /// Initialize a new factory and all mocks from `factory_config`, at its genesis timestamp
+    /// Arguments:
+    /// - `keypair`: this keypair will be the one added to the wallet that will be used to produce all blocks
+    /// - `factory_config`: configuration of the factory, ex: its safety interlock
+    ///
+    /// Returns
+    /// - `TestFactory`: the structure that will be used to manage the tests
+    pub fn new_with_config(
+        default_keypair: &KeyPair,
+        factory_config: FactoryConfig,
+    ) -> TestFactory {
         let (selector_controller, selector_receiver) = MockSelectorController::new_with_receiver();
         let (consensus_controller, consensus_event_receiver) =
             MockConsensusController::new_with_receiver();
         let (pool_controller, pool_receiver) = MockPoolController::new_with_receiver();
+        let (execution_controller, execution_receiver) =
+            MockExecutionController::new_with_receiver();
         let mut storage = Storage::create_root();
-        let mut factory_config = FactoryConfig::default();
+        let production_gate = ProductionGate::default();
+        let sync_progress = SyncProgressStatus::default();
         let (_protocol_controller, protocol_command_sender) = MockProtocolController::new();
         let producer_keypair = default_keypair;
         let producer_address = Address::from_public_key(&producer_keypair.get_public_key());
@@ -73,20 +110,27 @@ impl TestFactory {
         }
 
         accounts.insert(producer_address, producer_keypair.clone());
-        factory_config.t0 = MassaTime::from_millis(400);
-        factory_config.genesis_timestamp = factory_config
-            .genesis_timestamp
-            .checked_sub(factory_config.t0)
-            .unwrap();
+        let slot_scheduler = MockSlotScheduler::new(
+            SlotTiming {
+                thread_count: factory_config.thread_count,
+                t0: factory_config.t0,
+                genesis_timestamp: factory_config.genesis_timestamp,
+            },
+            factory_config.genesis_timestamp,
+        );
         let factory_manager = start_factory(
             factory_config.clone(),
             Arc::new(RwLock::new(create_test_wallet(Some(accounts)))),
             FactoryChannels {
                 selector: selector_controller.clone(),
                 consensus: consensus_controller,
+                execution: execution_controller,
                 pool: pool_controller.clone(),
                 protocol: protocol_command_sender,
                 storage: storage.clone_without_refs(),
+                production_gate: production_gate.clone(),
+                sync_progress: sync_progress.clone(),
+                slot_scheduler: slot_scheduler.clone_box(),
             },
         );
 
@@ -94,15 +138,82 @@ impl TestFactory {
             consensus_event_receiver,
             pool_receiver,
             selector_receiver,
+            execution_receiver,
             factory_config,
             factory_manager,
             genesis_blocks,
             storage,
             keypair: default_keypair.clone(),
+            slot_scheduler,
+            // the genesis slots are never produced
+            next_slot: Slot::new(1, 0),
+            production_gate,
+            sync_progress,
         }
     }
 
-    /// This functions wait until it's time to create the next block to be sync with the factory.
+    /// Move the time of the factory to the timestamp of the next slot to produce
+    fn advance_to_next_slot(&mut self) {
+        self.slot_scheduler
+            .advance_to_slot(self.next_slot)
+            .expect("could not advance to the next slot");
+        self.next_slot = self
+            .next_slot
+            .get_next_slot(self.factory_config.thread_count)
+            .expect("could not compute the next slot");
+    }
+
+    /// This functions moves the time to the next slot, drawing our keypair as its block producer,
+    /// and checks that the factory does not start the production of a block.
+    pub fn assert_no_block_created(&mut self) {
+        self.advance_to_next_slot();
+        let producer_address = Address::from_public_key(&self.keypair.get_public_key());
+        loop {
+            match self
+                .selector_receiver
+                .recv_timeout(Duration::from_millis(100))
+            {
+                Ok(MockSelectorControllerMessage::GetProducer {
+                    slot: _,
+                    response_tx,
+                }) => {
+                    response_tx.send(Ok(producer_address)).unwrap();
+                }
+                Ok(MockSelectorControllerMessage::GetSelection {
+                    slot: _,
+                    response_tx,
+                }) => {
+                    response_tx
+                        .send(Ok(Selection {
+                            producer: producer_address,
+                            endorsements: vec![producer_address; ENDORSEMENT_COUNT as usize],
+                        }))
+                        .unwrap();
+                }
+                Err(_) => {
+                    break;
+                }
+                _ => panic!("unexpected message"),
+            }
+        }
+        // the production starts by asking the consensus for the best parents
+        while let Some(asked_parents) =
+            self.consensus_event_receiver
+                .wait_command(MassaTime::from_millis(100), |command| {
+                    Some(matches!(
+                        command,
+                        MockConsensusControllerMessage::GetBestParents { .. }
+                    ))
+                })
+        {
+            assert!(
+                !asked_parents,
+                "a block was produced while the interlock was engaged"
+            );
+        }
+    }
+
+    /// This functions moves the time to the next slot to make the factory create its block.
     /// It will answers to all the asks of the factory with mocks and data you provide as parameters.
     ///
     /// Arguments:
@@ -113,13 +224,7 @@ impl TestFactory {
         operations: Option<Vec<WrappedOperation>>,
         endorsements: Option<Vec<WrappedEndorsement>>,
     ) -> (BlockId, Storage) {
-        let now = MassaTime::now().expect("could not get current time");
-        let next_slot_instant = get_next_slot_instant(
-            self.factory_config.genesis_timestamp,
-            self.factory_config.thread_count,
-            self.factory_config.t0,
-        );
-        sleep(next_slot_instant.checked_sub(now).unwrap().to_duration());
+        self.advance_to_next_slot();
         let producer_address = Address::from_public_key(&self.keypair.get_public_key());
         loop {
             match self
@@ -203,6 +308,25 @@ impl TestFactory {
                 _ => panic!("unexpected message"),
             })
             .unwrap();
+
+        match self
+            .execution_receiver
+            .recv_timeout(Duration::from_millis(100))
+        {
+            Ok(MockExecutionControllerMessage::GetFinalStateCommitment { response_tx }) => {
+                response_tx
+                    .send(StateCommitment {
+                        slot: Slot::new(0, 0),
+                        final_state_hash: Hash::compute_from(b"final state"),
+                        ledger_hash: Hash::compute_from(b"ledger"),
+                        async_pool_hash: Hash::compute_from(b"async pool"),
+                        deferred_credits_hash: Hash::compute_from(b"deferred credits"),
+                        executed_ops_hash: Hash::compute_from(b"executed ops"),
+                    })
+                    .unwrap();
+            }
+            _ => panic!("the final state commitment was not requested"),
+        }
         self.consensus_event_receiver
             .wait_command(MassaTime::from_millis(100), |command| {
                 if let MockConsensusControllerMessage::RegisterBlock {
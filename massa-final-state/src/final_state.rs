@@ -10,7 +10,7 @@ use massa_async_pool::{AsyncMessageId, AsyncPool, AsyncPoolChanges, Change};
 use massa_executed_ops::ExecutedOps;
 use massa_hash::{Hash, HASH_SIZE_BYTES};
 use massa_ledger_exports::{get_address_from_key, LedgerChanges, LedgerController};
-use massa_models::{slot::Slot, streaming_step::StreamingStep};
+use massa_models::{slot::Slot, state_commitment::StateCommitment, streaming_step::StreamingStep};
 use massa_pos_exports::{DeferredCredits, PoSFinalState, SelectorController};
 use std::collections::VecDeque;
 use tracing::{debug, info};
@@ -34,6 +34,9 @@ pub struct FinalState {
     pub changes_history: VecDeque<(Slot, StateChanges)>,
     /// hash of the final state, it is computed on finality
     pub final_state_hash: Hash,
+    /// commitments to the recently finalized states, checked against the ones of the block producers
+    /// `front = oldest`, `back = newest`
+    pub state_commitment_history: VecDeque<StateCommitment>,
 }
 
 const FINAL_STATE_HASH_INITIAL_BYTES: &[u8; 32] = &[0; HASH_SIZE_BYTES];
@@ -77,9 +80,23 @@ impl FinalState {
             executed_ops,
             changes_history: Default::default(), // no changes in history
             final_state_hash: Hash::from_bytes(FINAL_STATE_HASH_INITIAL_BYTES),
+            state_commitment_history: Default::default(),
         })
     }
 
+    /// Reset the final state to an empty one attached at the output of the last genesis slot,
+    /// for example to bootstrap it again from scratch
+    pub fn reset(&mut self) {
+        self.slot = Slot::new(0, self.config.thread_count.saturating_sub(1));
+        self.ledger.reset();
+        self.async_pool = AsyncPool::new(self.config.async_pool_config.clone());
+        self.pos_state.reset();
+        self.executed_ops = ExecutedOps::new(self.config.executed_ops_config.clone());
+        self.changes_history.clear();
+        self.final_state_hash = Hash::from_bytes(FINAL_STATE_HASH_INITIAL_BYTES);
+        self.state_commitment_history.clear();
+    }
+
     /// Compute the current state hash.
     ///
     /// Used when finalizing a slot.
@@ -176,6 +193,36 @@ impl FinalState {
         let cycle = slot.get_cycle(self.config.periods_per_cycle);
         self.pos_state
             .feed_cycle_state_hash(cycle, self.final_state_hash);
+
+        // keep the commitment to the state, for as long as its changes
+        if self.config.final_history_length > 0 {
+            while self.state_commitment_history.len() >= self.config.final_history_length {
+                self.state_commitment_history.pop_front();
+            }
+            let state_commitment = self.get_state_commitment();
+            self.state_commitment_history.push_back(state_commitment);
+        }
+    }
+
+    /// Commitment to the state at the output of its current slot
+    pub fn get_state_commitment(&self) -> StateCommitment {
+        StateCommitment {
+            slot: self.slot,
+            final_state_hash: self.final_state_hash,
+            ledger_hash: self.ledger.get_ledger_hash(),
+            async_pool_hash: self.async_pool.hash,
+            deferred_credits_hash: self.pos_state.deferred_credits.hash,
+            executed_ops_hash: self.executed_ops.hash,
+        }
+    }
+
+    /// Commitment to the state at the output of `slot`, if it is among the recently finalized ones
+    pub fn get_state_commitment_at(&self, slot: Slot) -> Option<StateCommitment> {
+        self.state_commitment_history
+            .iter()
+            .rev()
+            .find(|state_commitment| state_commitment.slot == slot)
+            .copied()
     }
 
     /// Used for bootstrap.
@@ -32,6 +32,7 @@ pub fn create_final_state(
         pos_state,
         executed_ops,
         final_state_hash: Hash::from_bytes(&[0; HASH_SIZE_BYTES]),
+        state_commitment_history: Default::default(),
     }
 }
 
@@ -31,6 +31,7 @@ impl FinalState {
             changes_history: Default::default(),
             config,
             final_state_hash: Hash::from_bytes(&[0; HASH_SIZE_BYTES]),
+            state_commitment_history: Default::default(),
         }
     }
 }
@@ -12,4 +12,7 @@ pub enum MassaHashError {
 
     /// Wrong prefix for hash: expected {0}, got {1}
     WrongPrefix(String, String),
+
+    /// worker pool error: {0}
+    WorkerPoolError(String),
 }
@@ -64,6 +64,36 @@ impl BitXor for Hash {
     }
 }
 
+/// Incremental computation of a `Hash`, from data given in several parts
+///
+/// # Example
+///  ```
+/// # use massa_hash::{Hash, Hasher};
+/// let mut hasher = Hasher::new();
+/// hasher.update("hello ".as_bytes());
+/// hasher.update("world".as_bytes());
+/// assert_eq!(hasher.finalize(), Hash::compute_from("hello world".as_bytes()));
+/// ```
+#[derive(Default, Clone)]
+pub struct Hasher(blake3::Hasher);
+
+impl Hasher {
+    /// Start the computation of a hash
+    pub fn new() -> Self {
+        Hasher(blake3::Hasher::new())
+    }
+
+    /// Add `data` to the hashed data
+    pub fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    /// Hash of all the data given so far
+    pub fn finalize(&self) -> Hash {
+        Hash(self.0.finalize())
+    }
+}
+
 impl Hash {
     /// Compute a hash from data.
     ///
@@ -76,6 +106,22 @@ impl Hash {
         Hash(blake3::hash(data))
     }
 
+    /// Compute a hash from the concatenation of `parts`, without concatenating them.
+    ///
+    /// # Example
+    ///  ```
+    /// # use massa_hash::Hash;
+    /// let hash = Hash::compute_from_tuple(&["hello ".as_bytes(), "world".as_bytes()]);
+    /// assert_eq!(hash, Hash::compute_from("hello world".as_bytes()));
+    /// ```
+    pub fn compute_from_tuple(parts: &[&[u8]]) -> Self {
+        let mut hasher = blake3::Hasher::new();
+        for part in parts {
+            hasher.update(part);
+        }
+        Hash(hasher.finalize())
+    }
+
     /// Serialize a Hash using `bs58` encoding with checksum.
     ///
     /// # Example
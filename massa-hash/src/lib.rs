@@ -9,4 +9,6 @@ pub use settings::HASH_SIZE_BYTES;
 mod error;
 mod hash;
 pub use hash::*;
+mod parallel;
+pub use parallel::init_worker_pool;
 mod settings;
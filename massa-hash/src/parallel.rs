@@ -0,0 +1,68 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Parallel hashing of large inputs.
+//!
+//! The work is spread over the rayon global thread pool, which the node shares for its CPU-bound batch
+//! work such as the signature verification of operation batches. Its size is set once at startup by
+//! `init_worker_pool`. Small inputs are hashed on the calling thread, spreading them over the pool
+//! would cost more than it saves.
+
+use crate::{Hash, MassaHashError};
+
+/// Inputs smaller than this are hashed on the calling thread, as advised for `blake3::Hasher::update_rayon`.
+/// Larger inputs are split in chunks hashed by the threads of the pool.
+const PARALLEL_MIN_INPUT_SIZE: usize = 128 * 1024;
+
+/// Set the number of threads of the worker pool shared across the node, 0 for one thread per CPU core.
+/// Fails if the pool was already started.
+pub fn init_worker_pool(thread_count: usize) -> Result<(), MassaHashError> {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(thread_count)
+        .thread_name(|index| format!("worker-pool-{}", index))
+        .build_global()
+        .map_err(|err| MassaHashError::WorkerPoolError(err.to_string()))
+}
+
+impl Hash {
+    /// Compute a hash from data, large data being hashed by the worker pool.
+    /// The hash is the one computed by `Hash::compute_from`.
+    ///
+    /// # Example
+    ///  ```
+    /// # use massa_hash::Hash;
+    /// let data = vec![7u8; 1_000_000];
+    /// assert_eq!(Hash::compute_from_parallel(&data), Hash::compute_from(&data));
+    /// ```
+    pub fn compute_from_parallel(data: &[u8]) -> Self {
+        if data.len() < PARALLEL_MIN_INPUT_SIZE {
+            return Hash::compute_from(data);
+        }
+        let mut hasher = blake3::Hasher::new();
+        hasher.update_rayon(data);
+        Hash(hasher.finalize())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parallel_hash_matches_sequential_hash() {
+        for size in [
+            0,
+            PARALLEL_MIN_INPUT_SIZE - 1,
+            PARALLEL_MIN_INPUT_SIZE,
+            PARALLEL_MIN_INPUT_SIZE + 1,
+            3 * PARALLEL_MIN_INPUT_SIZE + 7,
+        ] {
+            let data: Vec<u8> = (0..size).map(|index| (index % 251) as u8).collect();
+            assert_eq!(
+                Hash::compute_from_parallel(&data),
+                Hash::compute_from(&data),
+                "hashes differ for an input of {} bytes",
+                size
+            );
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use massa_hash::Hash;
 use massa_models::{
-    address::Address, amount::Amount, error::ModelsError, slot::Slot, streaming_step::StreamingStep,
+    address::Address, amount::Amount, balance_proof::BalanceProof, error::ModelsError, slot::Slot,
+    streaming_step::StreamingStep,
 };
 use std::collections::BTreeSet;
 use std::fmt::Debug;
@@ -14,6 +15,9 @@ pub trait LedgerController: Send + Sync + Debug {
     /// Loads ledger from file
     fn load_initial_ledger(&mut self) -> Result<(), LedgerError>;
 
+    /// Removes every entry of the ledger, for example to bootstrap it again from scratch
+    fn reset(&mut self);
+
     /// Gets the balance of a ledger entry
     ///
     /// # Returns
@@ -61,6 +65,12 @@ pub trait LedgerController: Send + Sync + Debug {
     /// Get the current disk ledger hash
     fn get_ledger_hash(&self) -> Hash;
 
+    /// Get the root of the Merkle trees over the balances
+    fn get_balance_state_root(&self) -> Hash;
+
+    /// Get a Merkle proof of the balance (or of the absence) of an address against the balance state root
+    fn get_balance_proof(&self, addr: &Address) -> BalanceProof;
+
     /// Get a part of the ledger
     /// Used for bootstrap
     /// Return: Tuple with data and last key
@@ -72,7 +82,7 @@ pub trait LedgerController: Send + Sync + Debug {
     /// Set a part of the ledger
     /// Used for bootstrap
     /// Return: Last key inserted
-    fn set_ledger_part(&self, data: Vec<u8>) -> Result<StreamingStep<Vec<u8>>, ModelsError>;
+    fn set_ledger_part(&mut self, data: Vec<u8>) -> Result<StreamingStep<Vec<u8>>, ModelsError>;
 
     /// Get every address and their corresponding balance.
     ///
@@ -0,0 +1,156 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! In-memory Merkle trees over the final balances, one per thread.
+//! See `massa_models::balance_proof` for the structure of the trees and of the proofs.
+
+use massa_hash::Hash;
+use massa_models::{
+    address::Address,
+    amount::Amount,
+    balance_proof::{
+        balance_bucket_hash, balance_bucket_index, balance_node_hash, balance_state_root,
+        BalanceProof, BALANCE_TREE_BUCKET_COUNT, BALANCE_TREE_DEPTH,
+    },
+};
+use std::collections::BTreeMap;
+
+/// Merkle trees over the final balances, maintained incrementally
+#[derive(Debug)]
+pub(crate) struct BalanceTree {
+    /// number of threads
+    thread_count: u8,
+    /// balances of each bucket, indexed by `thread * BALANCE_TREE_BUCKET_COUNT + bucket`
+    buckets: Vec<BTreeMap<Address, Amount>>,
+    /// nodes of the tree of each thread, heap-ordered:
+    /// node 1 is the root, node `i` has children `2i` and `2i + 1`,
+    /// and bucket `b` is node `BALANCE_TREE_BUCKET_COUNT + b`
+    nodes: Vec<Vec<Hash>>,
+}
+
+impl BalanceTree {
+    /// Create the trees of an empty ledger
+    pub fn new(thread_count: u8) -> Self {
+        let empty_bucket_hash = balance_bucket_hash(std::iter::empty());
+        let mut thread_nodes = vec![empty_bucket_hash; 2 * BALANCE_TREE_BUCKET_COUNT];
+        for index in (1..BALANCE_TREE_BUCKET_COUNT).rev() {
+            thread_nodes[index] =
+                balance_node_hash(&thread_nodes[2 * index], &thread_nodes[2 * index + 1]);
+        }
+        BalanceTree {
+            thread_count,
+            buckets: vec![BTreeMap::new(); thread_count as usize * BALANCE_TREE_BUCKET_COUNT],
+            nodes: vec![thread_nodes; thread_count as usize],
+        }
+    }
+
+    /// Set the balance of an address, `None` meaning that the address has no ledger entry
+    pub fn set_balance(&mut self, address: &Address, balance: Option<Amount>) {
+        let thread = address.get_thread(self.thread_count) as usize;
+        let bucket_index = balance_bucket_index(address);
+        let bucket = &mut self.buckets[thread * BALANCE_TREE_BUCKET_COUNT + bucket_index];
+        let changed = match balance {
+            Some(amount) => bucket.insert(*address, amount) != Some(amount),
+            None => bucket.remove(address).is_some(),
+        };
+        if !changed {
+            return;
+        }
+
+        // update the path from the bucket to the root
+        let nodes = &mut self.nodes[thread];
+        let mut index = BALANCE_TREE_BUCKET_COUNT + bucket_index;
+        nodes[index] = balance_bucket_hash(bucket.iter());
+        while index > 1 {
+            index /= 2;
+            nodes[index] = balance_node_hash(&nodes[2 * index], &nodes[2 * index + 1]);
+        }
+    }
+
+    /// Get the roots of the trees of all threads
+    pub fn get_thread_roots(&self) -> Vec<Hash> {
+        self.nodes.iter().map(|nodes| nodes[1]).collect()
+    }
+
+    /// Get the balance state root
+    pub fn get_state_root(&self) -> Hash {
+        balance_state_root(&self.get_thread_roots())
+    }
+
+    /// Build the proof of the balance of an address
+    pub fn get_proof(&self, address: &Address) -> BalanceProof {
+        let thread = address.get_thread(self.thread_count) as usize;
+        let bucket_index = balance_bucket_index(address);
+        let bucket = &self.buckets[thread * BALANCE_TREE_BUCKET_COUNT + bucket_index];
+        let nodes = &self.nodes[thread];
+        let mut siblings = Vec::with_capacity(BALANCE_TREE_DEPTH);
+        let mut index = BALANCE_TREE_BUCKET_COUNT + bucket_index;
+        while index > 1 {
+            siblings.push(nodes[index ^ 1]);
+            index /= 2;
+        }
+        let thread_roots = self.get_thread_roots();
+        BalanceProof {
+            address: *address,
+            balance: bucket.get(address).copied(),
+            bucket: bucket
+                .iter()
+                .map(|(addr, amount)| (*addr, *amount))
+                .collect(),
+            siblings,
+            state_root: balance_state_root(&thread_roots),
+            thread_roots,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BalanceTree;
+    use massa_models::{address::Address, amount::Amount};
+    use massa_signature::KeyPair;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_balance_proof() {
+        let thread_count = 2;
+        let addresses: Vec<Address> = (0..10)
+            .map(|_| Address::from_public_key(&KeyPair::generate().get_public_key()))
+            .collect();
+        let mut tree = BalanceTree::new(thread_count);
+        for (i, address) in addresses.iter().enumerate() {
+            tree.set_balance(address, Some(Amount::from_str(&i.to_string()).unwrap()));
+        }
+        let root = tree.get_state_root();
+
+        // proofs of present addresses
+        for (i, address) in addresses.iter().enumerate() {
+            let proof = tree.get_proof(address);
+            assert_eq!(
+                proof.balance,
+                Some(Amount::from_str(&i.to_string()).unwrap())
+            );
+            assert!(proof.verify(&root, thread_count));
+        }
+
+        // proof of absence
+        let absent = Address::from_public_key(&KeyPair::generate().get_public_key());
+        let proof = tree.get_proof(&absent);
+        assert_eq!(proof.balance, None);
+        assert!(proof.verify(&root, thread_count));
+
+        // tampered proof
+        let mut tampered = tree.get_proof(&addresses[0]);
+        tampered.balance = Some(Amount::from_str("1000").unwrap());
+        assert!(!tampered.verify(&root, thread_count));
+
+        // removing a balance changes the root and invalidates old proofs
+        let old_proof = tree.get_proof(&addresses[1]);
+        tree.set_balance(&addresses[1], None);
+        let new_root = tree.get_state_root();
+        assert_ne!(root, new_root);
+        assert!(!old_proof.verify(&new_root, thread_count));
+        assert!(tree
+            .get_proof(&addresses[1])
+            .verify(&new_root, thread_count));
+    }
+}
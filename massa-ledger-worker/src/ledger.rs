@@ -2,6 +2,7 @@
 
 //! This file defines the final ledger associating addresses to their balances, bytecode and data.
 
+use crate::balance_tree::BalanceTree;
 use crate::ledger_db::{LedgerDB, LedgerSubEntry};
 use massa_hash::Hash;
 use massa_ledger_exports::{
@@ -10,6 +11,7 @@ use massa_ledger_exports::{
 use massa_models::{
     address::Address,
     amount::{Amount, AmountDeserializer},
+    balance_proof::BalanceProof,
     error::ModelsError,
     slot::Slot,
     streaming_step::StreamingStep,
@@ -29,6 +31,8 @@ pub struct FinalLedger {
     pub(crate) config: LedgerConfig,
     /// ledger tree, sorted by address
     pub(crate) sorted_ledger: LedgerDB,
+    /// Merkle trees over the balances of the ledger
+    pub(crate) balance_tree: BalanceTree,
 }
 
 impl FinalLedger {
@@ -43,9 +47,20 @@ impl FinalLedger {
         );
 
         // generate the final ledger
-        FinalLedger {
+        let mut final_ledger = FinalLedger {
             sorted_ledger,
+            balance_tree: BalanceTree::new(config.thread_count),
             config,
+        };
+        final_ledger.rebuild_balance_tree();
+        final_ledger
+    }
+
+    /// Rebuild the balance trees from the whole disk ledger
+    pub(crate) fn rebuild_balance_tree(&mut self) {
+        self.balance_tree = BalanceTree::new(self.config.thread_count);
+        for (address, balance) in self.sorted_ledger.get_every_address() {
+            self.balance_tree.set_balance(&address, Some(balance));
         }
     }
 }
@@ -53,7 +68,12 @@ impl FinalLedger {
 impl LedgerController for FinalLedger {
     /// Allows applying `LedgerChanges` to the final ledger
     fn apply_changes(&mut self, changes: LedgerChanges, slot: Slot) {
+        let changed_addresses: Vec<Address> = changes.0.keys().copied().collect();
         self.sorted_ledger.apply_changes(changes, slot);
+        for address in changed_addresses {
+            let balance = self.get_balance(&address);
+            self.balance_tree.set_balance(&address, balance);
+        }
     }
 
     /// Loads ledger from file
@@ -82,9 +102,16 @@ impl LedgerController for FinalLedger {
             ))
         })?;
         self.sorted_ledger.load_initial_ledger(initial_ledger);
+        self.rebuild_balance_tree();
         Ok(())
     }
 
+    /// Removes every entry of the ledger
+    fn reset(&mut self) {
+        self.sorted_ledger.reset();
+        self.balance_tree = BalanceTree::new(self.config.thread_count);
+    }
+
     /// Gets the balance of a ledger entry
     ///
     /// # Returns
@@ -164,6 +191,16 @@ impl LedgerController for FinalLedger {
         self.sorted_ledger.get_ledger_hash()
     }
 
+    /// Get the root of the Merkle trees over the balances
+    fn get_balance_state_root(&self) -> Hash {
+        self.balance_tree.get_state_root()
+    }
+
+    /// Get a Merkle proof of the balance of an address against the balance state root
+    fn get_balance_proof(&self, addr: &Address) -> BalanceProof {
+        self.balance_tree.get_proof(addr)
+    }
+
     /// Get a part of the disk ledger.
     ///
     /// Solely used by the bootstrap.
@@ -178,13 +215,18 @@ impl LedgerController for FinalLedger {
     }
 
     /// Set a part of the disk ledger.
+    /// The balance trees are rebuilt once the last part has been received.
     ///
     /// Solely used by the bootstrap.
     ///
     /// # Returns
     /// The last key inserted
-    fn set_ledger_part(&self, data: Vec<u8>) -> Result<StreamingStep<Vec<u8>>, ModelsError> {
-        self.sorted_ledger.set_ledger_part(data.as_bytes())
+    fn set_ledger_part(&mut self, data: Vec<u8>) -> Result<StreamingStep<Vec<u8>>, ModelsError> {
+        let step = self.sorted_ledger.set_ledger_part(data.as_bytes())?;
+        if step.finished() {
+            self.rebuild_balance_tree();
+        }
+        Ok(step)
     }
 
     /// Get every address and their corresponding balance.
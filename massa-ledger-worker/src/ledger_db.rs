@@ -6,7 +6,7 @@ use massa_hash::{Hash, HASH_SIZE_BYTES};
 use massa_ledger_exports::*;
 use massa_models::{
     address::{Address, ADDRESS_SIZE_BYTES},
-    amount::AmountSerializer,
+    amount::{Amount, AmountDeserializer, AmountSerializer},
     error::ModelsError,
     serialization::{VecU8Deserializer, VecU8Serializer},
     slot::{Slot, SlotSerializer},
@@ -28,9 +28,6 @@ use std::{
     convert::TryInto,
 };
 
-#[cfg(feature = "testing")]
-use massa_models::amount::{Amount, AmountDeserializer};
-
 const LEDGER_CF: &str = "ledger";
 const METADATA_CF: &str = "metadata";
 const OPEN_ERROR: &str = "critical: rocksdb open operation failed";
@@ -63,7 +60,6 @@ pub(crate) struct LedgerDB {
     len_serializer: U64VarIntSerializer,
     max_datastore_key_length: u8,
     ledger_part_size_message_bytes: u64,
-    #[cfg(feature = "testing")]
     amount_deserializer: AmountDeserializer,
 }
 
@@ -149,7 +145,6 @@ impl LedgerDB {
             len_serializer: U64VarIntSerializer::new(),
             max_datastore_key_length,
             ledger_part_size_message_bytes,
-            #[cfg(feature = "testing")]
             amount_deserializer: AmountDeserializer::new(
                 Bound::Included(Amount::MIN),
                 Bound::Included(Amount::MAX),
@@ -175,6 +170,14 @@ impl LedgerDB {
         self.write_batch(batch);
     }
 
+    /// Remove every entry and the slot of the disk ledger, leaving it as a newly created one
+    pub fn reset(&mut self) {
+        for cf in [LEDGER_CF, METADATA_CF] {
+            self.db.drop_cf(cf).expect(CF_ERROR);
+            self.db.create_cf(cf, &Options::default()).expect(CF_ERROR);
+        }
+    }
+
     /// Allows applying `LedgerChanges` to the disk ledger
     ///
     /// # Arguments
@@ -552,14 +555,12 @@ impl LedgerDB {
 
     /// Get every address and their corresponding balance.
     ///
-    /// IMPORTANT: This should only be used for debug purposes.
+    /// IMPORTANT: This iterates over the whole ledger, it should only be used
+    /// for debug purposes and to rebuild the balance trees.
     ///
     /// # Returns
     /// A `BTreeMap` with the address as key and the balance as value
-    #[cfg(feature = "testing")]
-    pub fn get_every_address(
-        &self,
-    ) -> std::collections::BTreeMap<Address, massa_models::amount::Amount> {
+    pub fn get_every_address(&self) -> BTreeMap<Address, Amount> {
         use massa_models::address::AddressDeserializer;
         use massa_serialization::DeserializeError;
 
@@ -708,6 +709,24 @@ mod tests {
         assert!(db.get_entire_datastore(&addr).is_empty());
     }
 
+    #[test]
+    fn test_ledger_reset() {
+        let addr = Address::from_public_key(&KeyPair::generate().get_public_key());
+        let (mut db, _) = init_test_ledger(addr);
+        db.reset();
+        assert!(db.get_every_address().is_empty());
+        assert_eq!(
+            Hash::from_bytes(LEDGER_HASH_INITIAL_BYTES),
+            db.get_ledger_hash()
+        );
+
+        // the ledger is still usable
+        let (other_db, _) = init_test_ledger(addr);
+        let part = other_db.get_ledger_part(StreamingStep::Started).unwrap();
+        db.set_ledger_part(&part.0[..]).unwrap();
+        assert_eq!(db.get_ledger_hash(), other_db.get_ledger_hash());
+    }
+
     #[test]
     fn test_ledger_parts() {
         let pub_a = KeyPair::generate().get_public_key();
@@ -19,6 +19,9 @@
 //! and can be manipulated using `LedgerChanges` (see `ledger_changes.rs`).
 //! The `FinalLedger` is bootstrapped using tooling available in bootstrap.rs
 //!
+//! ## `balance_tree.rs`
+//! Maintains Merkle trees over the final balances, used to prove balances against the balance state root.
+//!
 //! ## `ledger_entry.rs`
 //! Represents an entry in the ledger for a given address.
 //! It contains balances, executable bytecode and an arbitrary datastore.
@@ -38,6 +41,7 @@
 #![warn(missing_docs)]
 #![warn(unused_crate_dependencies)]
 
+mod balance_tree;
 mod ledger;
 mod ledger_db;
 
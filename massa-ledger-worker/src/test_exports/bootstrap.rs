@@ -5,7 +5,7 @@ use massa_models::address::Address;
 use std::collections::HashMap;
 use tempfile::TempDir;
 
-use crate::{ledger_db::LedgerDB, FinalLedger};
+use crate::{balance_tree::BalanceTree, ledger_db::LedgerDB, FinalLedger};
 
 /// This file defines tools to test the ledger bootstrap
 
@@ -21,10 +21,13 @@ pub fn create_final_ledger(
         config.max_ledger_part_size,
     );
     db.load_initial_ledger(initial_ledger);
-    FinalLedger {
+    let mut final_ledger = FinalLedger {
+        balance_tree: BalanceTree::new(config.thread_count),
         config,
         sorted_ledger: db,
-    }
+    };
+    final_ledger.rebuild_balance_tree();
+    final_ledger
 }
 
 /// asserts that two ledger entries are the same
@@ -3,7 +3,7 @@
 /// This file defines testing tools related to the configuration
 use tempfile::TempDir;
 
-use crate::{ledger_db::LedgerDB, FinalLedger};
+use crate::{balance_tree::BalanceTree, ledger_db::LedgerDB, FinalLedger};
 use massa_models::config::{
     LEDGER_PART_SIZE_MESSAGE_BYTES, MAX_DATASTORE_KEY_LENGTH, THREAD_COUNT,
 };
@@ -21,6 +21,7 @@ impl Default for FinalLedger {
         FinalLedger {
             config: Default::default(),
             sorted_ledger: db,
+            balance_tree: BalanceTree::new(THREAD_COUNT),
         }
     }
 }
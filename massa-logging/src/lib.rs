@@ -2,6 +2,14 @@
 //! Log utilities
 
 #![warn(missing_docs)]
+
+mod log_layer;
+
+pub use log_layer::{
+    build_log_filter, build_log_layer, level_filter_from_config, LogFilterHandle, LogFormat,
+    RecentLogs,
+};
+
 #[macro_export]
 /// tracing with some context
 macro_rules! massa_trace {
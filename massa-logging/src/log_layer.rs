@@ -0,0 +1,146 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Log output of the node: text or JSON format, per-module level filters
+//! that can be replaced at runtime through a `LogFilterHandle`,
+//! and the latest lines kept in memory in `RecentLogs`.
+
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::io;
+use std::sync::{Arc, Mutex, PoisonError};
+use tracing_subscriber::{
+    filter::{filter_fn, EnvFilter, LevelFilter},
+    fmt::{self, MakeWriter},
+    reload, Layer, Registry,
+};
+
+/// Output format of the logs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    /// human-readable lines
+    #[default]
+    Text,
+    /// one JSON object per line
+    Json,
+}
+
+/// Get the level filter matching a configured log level.
+/// 0: ERROR, 1: WARN, 2: INFO, 3: DEBUG, 4: TRACE
+pub fn level_filter_from_config(level: usize) -> LevelFilter {
+    match level {
+        4 => LevelFilter::TRACE,
+        3 => LevelFilter::DEBUG,
+        2 => LevelFilter::INFO,
+        1 => LevelFilter::WARN,
+        _ => LevelFilter::ERROR,
+    }
+}
+
+/// Build a log filter from a default level and per-module directives (ex: `massa_protocol_worker=debug`)
+pub fn build_log_filter(level: LevelFilter, directives: &[String]) -> Result<EnvFilter, String> {
+    let mut all_directives = vec![level.to_string()];
+    all_directives.extend(directives.iter().cloned());
+    EnvFilter::try_new(all_directives.join(",")).map_err(|err| err.to_string())
+}
+
+/// Handle to replace the log filter while the node is running
+#[derive(Clone)]
+pub struct LogFilterHandle(reload::Handle<EnvFilter, Registry>);
+
+impl LogFilterHandle {
+    /// Replace the log filter by the comma-separated `directives` (ex: `info,massa_protocol_worker=debug`)
+    pub fn set_filter(&self, directives: &str) -> Result<(), String> {
+        self.replace(EnvFilter::try_new(directives).map_err(|err| err.to_string())?)
+    }
+
+    /// Replace the log filter, ex: by one built with `build_log_filter`
+    pub fn replace(&self, filter: EnvFilter) -> Result<(), String> {
+        self.0.reload(filter).map_err(|err| err.to_string())
+    }
+
+    /// Get the directives of the current log filter
+    pub fn get_filter(&self) -> Result<String, String> {
+        self.0
+            .with_current(|filter| filter.to_string())
+            .map_err(|err| err.to_string())
+    }
+}
+
+/// Latest log lines of the node, in the text format without colors
+#[derive(Clone)]
+pub struct RecentLogs {
+    lines: Arc<Mutex<VecDeque<String>>>,
+    capacity: usize,
+}
+
+impl RecentLogs {
+    /// Keep the latest `capacity` log lines
+    pub fn new(capacity: usize) -> Self {
+        RecentLogs {
+            lines: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+        }
+    }
+
+    /// Get the latest `count` log lines, oldest first
+    pub fn get(&self, count: usize) -> Vec<String> {
+        let lines = self.lines.lock().unwrap_or_else(PoisonError::into_inner);
+        lines
+            .iter()
+            .skip(lines.len().saturating_sub(count))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Each event is formatted and written at once
+impl io::Write for &RecentLogs {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.capacity == 0 {
+            return Ok(buf.len());
+        }
+        let mut lines = self.lines.lock().unwrap_or_else(PoisonError::into_inner);
+        for line in String::from_utf8_lossy(buf).lines() {
+            if lines.len() == self.capacity {
+                lines.pop_front();
+            }
+            lines.push_back(line.to_string());
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for RecentLogs {
+    type Writer = &'a RecentLogs;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self
+    }
+}
+
+/// Build the layer writing the logs of the massa crates to the standard output and to `recent_logs`,
+/// along with the handle to replace its filter at runtime
+pub fn build_log_layer(
+    format: LogFormat,
+    filter: EnvFilter,
+    recent_logs: RecentLogs,
+) -> (impl Layer<Registry> + Send + Sync, LogFilterHandle) {
+    let fmt_layer: Box<dyn Layer<Registry> + Send + Sync> = match format {
+        LogFormat::Text => Box::new(fmt::layer()),
+        LogFormat::Json => Box::new(fmt::layer().json()),
+    };
+    let recent_logs_layer = fmt::layer().with_ansi(false).with_writer(recent_logs);
+    let (filter, handle) = reload::Layer::new(filter);
+    let layer = fmt_layer
+        .and_then(recent_logs_layer)
+        .with_filter(filter)
+        .with_filter(filter_fn(|metadata| {
+            metadata.target().starts_with("massa") // ignore non-massa logs
+        }));
+    (layer, LogFilterHandle(handle))
+}
@@ -0,0 +1,51 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Metrics of the bootstrap server
+
+use crate::register;
+use lazy_static::lazy_static;
+use prometheus::{IntCounter, IntGauge};
+
+lazy_static! {
+    /// Metrics updated by the bootstrap server
+    pub static ref BOOTSTRAP_METRICS: BootstrapMetrics = BootstrapMetrics::new();
+}
+
+/// Metrics of the cache of the final state parts streamed by the bootstrap server.
+/// The hit rate is computed from the counters by the Prometheus queries.
+pub struct BootstrapMetrics {
+    /// number of final state parts served from the cache
+    pub part_cache_hits: IntCounter,
+    /// number of final state parts read from the final state
+    pub part_cache_misses: IntCounter,
+    /// number of final state parts in the cache
+    pub part_cache_entries: IntGauge,
+}
+
+impl BootstrapMetrics {
+    fn new() -> Self {
+        BootstrapMetrics {
+            part_cache_hits: register(
+                IntCounter::new(
+                    "bootstrap_part_cache_hits_total",
+                    "number of final state parts served from the cache",
+                )
+                .expect("invalid metric"),
+            ),
+            part_cache_misses: register(
+                IntCounter::new(
+                    "bootstrap_part_cache_misses_total",
+                    "number of final state parts read from the final state",
+                )
+                .expect("invalid metric"),
+            ),
+            part_cache_entries: register(
+                IntGauge::new(
+                    "bootstrap_part_cache_entries",
+                    "number of final state parts in the cache",
+                )
+                .expect("invalid metric"),
+            ),
+        }
+    }
+}
@@ -0,0 +1,84 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Metrics of the consensus worker
+
+use crate::register;
+use lazy_static::lazy_static;
+use prometheus::{IntCounter, IntGauge};
+
+lazy_static! {
+    /// Metrics updated by the consensus worker
+    pub static ref CONSENSUS_METRICS: ConsensusMetrics = ConsensusMetrics::new();
+}
+
+/// Metrics of the block graph.
+/// Rates (ex: blocks processed per second, stale rate) are computed from the counters by the Prometheus queries.
+pub struct ConsensusMetrics {
+    /// number of blocks added to the graph
+    pub processed_blocks: IntCounter,
+    /// number of blocks that became final
+    pub final_blocks: IntCounter,
+    /// number of blocks that became stale
+    pub stale_blocks: IntCounter,
+    /// number of max cliques in the graph
+    pub clique_count: IntGauge,
+    /// greatest difference, among the threads, between the current period and the last final period
+    pub finality_lag_periods: IntGauge,
+    /// number of block commands sent while the command channel of the consensus worker was full
+    pub saturated_commands: IntCounter,
+    /// number of block commands dropped after waiting too long for room in the command channel
+    pub dropped_commands: IntCounter,
+}
+
+impl ConsensusMetrics {
+    fn new() -> Self {
+        ConsensusMetrics {
+            processed_blocks: register(
+                IntCounter::new(
+                    "consensus_processed_blocks_total",
+                    "number of blocks added to the graph",
+                )
+                .expect("invalid metric"),
+            ),
+            final_blocks: register(
+                IntCounter::new(
+                    "consensus_final_blocks_total",
+                    "number of blocks that became final",
+                )
+                .expect("invalid metric"),
+            ),
+            stale_blocks: register(
+                IntCounter::new(
+                    "consensus_stale_blocks_total",
+                    "number of blocks that became stale",
+                )
+                .expect("invalid metric"),
+            ),
+            clique_count: register(
+                IntGauge::new("consensus_clique_count", "number of max cliques in the graph")
+                    .expect("invalid metric"),
+            ),
+            finality_lag_periods: register(
+                IntGauge::new(
+                    "consensus_finality_lag_periods",
+                    "greatest difference, among the threads, between the current period and the last final period",
+                )
+                .expect("invalid metric"),
+            ),
+            saturated_commands: register(
+                IntCounter::new(
+                    "consensus_saturated_commands_total",
+                    "number of block commands sent while the command channel of the consensus worker was full",
+                )
+                .expect("invalid metric"),
+            ),
+            dropped_commands: register(
+                IntCounter::new(
+                    "consensus_dropped_commands_total",
+                    "number of block commands dropped after waiting too long for room in the command channel",
+                )
+                .expect("invalid metric"),
+            ),
+        }
+    }
+}
@@ -0,0 +1,42 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+//! Prometheus metrics of the node.
+//!
+//! The workers update the metrics of their component (ex: `CONSENSUS_METRICS`, `POOL_METRICS`, `BOOTSTRAP_METRICS`, `NETWORK_METRICS`, `PROTOCOL_METRICS`, `STORAGE_METRICS`).
+//! All the metrics are registered in `REGISTRY` and served in the Prometheus text format
+//! by the HTTP endpoint started with `start_metrics_server`.
+
+#![warn(missing_docs)]
+#![warn(unused_crate_dependencies)]
+
+mod bootstrap;
+mod consensus;
+mod network;
+mod pool;
+mod protocol;
+mod server;
+mod storage;
+
+pub use bootstrap::{BootstrapMetrics, BOOTSTRAP_METRICS};
+pub use consensus::{ConsensusMetrics, CONSENSUS_METRICS};
+pub use network::{ChannelMetrics, NetworkMetrics, NETWORK_METRICS};
+pub use pool::{PoolMetrics, POOL_METRICS};
+pub use protocol::{ProtocolMetrics, PROTOCOL_METRICS};
+pub use server::{start_metrics_server, MetricsServerHandle};
+pub use storage::{StorageMetrics, STORAGE_METRICS};
+
+use lazy_static::lazy_static;
+use prometheus::{core::Collector, Registry};
+
+lazy_static! {
+    /// Registry of all the metrics of the node, their names are prefixed by `massa_`
+    pub static ref REGISTRY: Registry =
+        Registry::new_custom(Some("massa".into()), None).expect("invalid metrics registry prefix");
+}
+
+/// Register a metric in `REGISTRY`, returning it
+fn register<T: Collector + Clone + 'static>(metric: T) -> T {
+    REGISTRY
+        .register(Box::new(metric.clone()))
+        .expect("metric registered twice");
+    metric
+}
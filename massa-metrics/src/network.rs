@@ -0,0 +1,168 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Metrics of the channels carrying the messages of the network layer, of the writes to the sockets,
+//! and of the time it takes to establish the connections
+
+use crate::register;
+use lazy_static::lazy_static;
+use prometheus::{
+    exponential_buckets, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec,
+    IntGauge, IntGaugeVec, Opts,
+};
+
+lazy_static! {
+    /// Metrics updated by the channels of the network workers
+    pub static ref NETWORK_METRICS: NetworkMetrics = NetworkMetrics::new();
+}
+
+/// Metrics of the bounded channels between the node workers, the network worker and the protocol worker,
+/// by channel name (`node_events`, `network_events`, or `node_commands` for the send queues of all the connections).
+pub struct NetworkMetrics {
+    /// number of messages queued in the channel
+    pub queued_messages: IntGaugeVec,
+    /// approximate size in bytes of the messages queued in the channel
+    pub queued_bytes: IntGaugeVec,
+    /// number of messages sent while the channel was full
+    pub saturated_sends: IntCounterVec,
+    /// number of messages dropped because the channel was full, by reason
+    /// (`evicted`: queued gossip replaced by a newer message, `rejected`: gossip sent
+    /// while only consensus-critical messages were queued, `timeout`: consensus-critical
+    /// message that waited too long for room, `overflow`: consensus-critical message
+    /// sent to a full send queue, its connection being closed)
+    pub dropped_messages: IntCounterVec,
+    /// number of messages written to the sockets of the connections
+    pub written_messages: IntCounter,
+    /// number of flushes of the sockets, each written messages group being sent in as few packets as possible
+    pub socket_flushes: IntCounter,
+    /// TCP connect latency of the established outgoing connections, in seconds
+    pub dial_latency_seconds: Histogram,
+    /// duration of the successful handshakes, in seconds, by direction of the connection (`in` or `out`)
+    pub handshake_duration_seconds: HistogramVec,
+}
+
+/// Metrics of one channel, resolved once so that sending a message does not look up the labels
+#[derive(Clone)]
+pub struct ChannelMetrics {
+    /// number of messages queued in the channel
+    pub queued_messages: IntGauge,
+    /// approximate size in bytes of the messages queued in the channel
+    pub queued_bytes: IntGauge,
+    /// number of messages sent while the channel was full
+    pub saturated_sends: IntCounter,
+    /// number of queued gossip messages replaced by a newer message
+    pub evicted_messages: IntCounter,
+    /// number of gossip messages dropped because only consensus-critical messages were queued
+    pub rejected_messages: IntCounter,
+    /// number of consensus-critical messages dropped after waiting too long for room
+    pub timed_out_messages: IntCounter,
+    /// number of consensus-critical messages that did not fit in a full send queue
+    pub overflowed_messages: IntCounter,
+}
+
+/// Buckets of the connection time histograms: from 5 ms to about 20 s
+fn connection_time_buckets() -> Vec<f64> {
+    exponential_buckets(0.005, 2.0, 13).expect("invalid histogram buckets")
+}
+
+impl NetworkMetrics {
+    fn new() -> Self {
+        NetworkMetrics {
+            queued_messages: register(
+                IntGaugeVec::new(
+                    Opts::new(
+                        "network_channel_queued_messages",
+                        "number of messages queued in the channel",
+                    ),
+                    &["channel"],
+                )
+                .expect("invalid metric"),
+            ),
+            queued_bytes: register(
+                IntGaugeVec::new(
+                    Opts::new(
+                        "network_channel_queued_bytes",
+                        "approximate size in bytes of the messages queued in the channel",
+                    ),
+                    &["channel"],
+                )
+                .expect("invalid metric"),
+            ),
+            saturated_sends: register(
+                IntCounterVec::new(
+                    Opts::new(
+                        "network_channel_saturated_sends_total",
+                        "number of messages sent while the channel was full",
+                    ),
+                    &["channel"],
+                )
+                .expect("invalid metric"),
+            ),
+            dropped_messages: register(
+                IntCounterVec::new(
+                    Opts::new(
+                        "network_channel_dropped_messages_total",
+                        "number of messages dropped because the channel was full",
+                    ),
+                    &["channel", "reason"],
+                )
+                .expect("invalid metric"),
+            ),
+            written_messages: register(
+                IntCounter::new(
+                    "network_written_messages_total",
+                    "number of messages written to the sockets of the connections",
+                )
+                .expect("invalid metric"),
+            ),
+            socket_flushes: register(
+                IntCounter::new(
+                    "network_socket_flushes_total",
+                    "number of flushes of the sockets of the connections",
+                )
+                .expect("invalid metric"),
+            ),
+            dial_latency_seconds: register(
+                Histogram::with_opts(
+                    HistogramOpts::new(
+                        "network_dial_latency_seconds",
+                        "TCP connect latency of the established outgoing connections",
+                    )
+                    .buckets(connection_time_buckets()),
+                )
+                .expect("invalid metric"),
+            ),
+            handshake_duration_seconds: register(
+                HistogramVec::new(
+                    HistogramOpts::new(
+                        "network_handshake_duration_seconds",
+                        "duration of the successful handshakes",
+                    )
+                    .buckets(connection_time_buckets()),
+                    &["direction"],
+                )
+                .expect("invalid metric"),
+            ),
+        }
+    }
+
+    /// Get the metrics of the channel `channel`
+    pub fn channel(&self, channel: &str) -> ChannelMetrics {
+        ChannelMetrics {
+            queued_messages: self.queued_messages.with_label_values(&[channel]),
+            queued_bytes: self.queued_bytes.with_label_values(&[channel]),
+            saturated_sends: self.saturated_sends.with_label_values(&[channel]),
+            evicted_messages: self
+                .dropped_messages
+                .with_label_values(&[channel, "evicted"]),
+            rejected_messages: self
+                .dropped_messages
+                .with_label_values(&[channel, "rejected"]),
+            timed_out_messages: self
+                .dropped_messages
+                .with_label_values(&[channel, "timeout"]),
+            overflowed_messages: self
+                .dropped_messages
+                .with_label_values(&[channel, "overflow"]),
+        }
+    }
+}
@@ -0,0 +1,69 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Metrics of the operation and endorsement pools
+
+use crate::register;
+use lazy_static::lazy_static;
+use prometheus::{exponential_buckets, Histogram, HistogramOpts, IntCounterVec, IntGauge, Opts};
+
+lazy_static! {
+    /// Metrics updated by the pool workers
+    pub static ref POOL_METRICS: PoolMetrics = PoolMetrics::new();
+}
+
+/// Metrics of the pools.
+/// Eviction rates and fee percentiles are computed from the counters and the histogram by the Prometheus queries.
+pub struct PoolMetrics {
+    /// number of operations in the pool
+    pub operation_count: IntGauge,
+    /// number of endorsements in the pool
+    pub endorsement_count: IntGauge,
+    /// number of operations removed from the pool before their inclusion, by reason
+    /// (`expired`, `evicted`, `sender_limit` or `replaced`)
+    pub dropped_operations: IntCounterVec,
+    /// fee per byte of the operations added to the pool, in raw units
+    pub operation_fee_per_byte: Histogram,
+}
+
+impl PoolMetrics {
+    fn new() -> Self {
+        PoolMetrics {
+            operation_count: register(
+                IntGauge::new("pool_operation_count", "number of operations in the pool")
+                    .expect("invalid metric"),
+            ),
+            endorsement_count: register(
+                IntGauge::new(
+                    "pool_endorsement_count",
+                    "number of endorsements in the pool",
+                )
+                .expect("invalid metric"),
+            ),
+            dropped_operations: register(
+                IntCounterVec::new(
+                    Opts::new(
+                        "pool_dropped_operations_total",
+                        "number of operations removed from the pool before their inclusion",
+                    ),
+                    &["reason"],
+                )
+                .expect("invalid metric"),
+            ),
+            operation_fee_per_byte: register(
+                Histogram::with_opts(
+                    HistogramOpts::new(
+                        "pool_operation_fee_per_byte",
+                        "fee per byte of the operations added to the pool, in raw units",
+                    )
+                    .buckets(exponential_buckets(1.0, 4.0, 16).expect("invalid histogram buckets")),
+                )
+                .expect("invalid metric"),
+            ),
+        }
+    }
+
+    /// Account an operation removed from the pool for the given reason
+    pub fn note_dropped_operation(&self, reason: &str) {
+        self.dropped_operations.with_label_values(&[reason]).inc();
+    }
+}
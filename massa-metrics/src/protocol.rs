@@ -0,0 +1,51 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Metrics of the protocol worker
+
+use crate::register;
+use lazy_static::lazy_static;
+use prometheus::{IntCounter, IntGauge};
+
+lazy_static! {
+    /// Metrics updated by the protocol worker
+    pub static ref PROTOCOL_METRICS: ProtocolMetrics = ProtocolMetrics::new();
+}
+
+/// Metrics of the cache of the verified headers.
+/// The hit rate is computed from the counters by the Prometheus queries.
+pub struct ProtocolMetrics {
+    /// number of received headers found in the cache, whose verification was skipped
+    pub header_cache_hits: IntCounter,
+    /// number of received headers not found in the cache, that were verified
+    pub header_cache_misses: IntCounter,
+    /// number of headers in the cache
+    pub header_cache_size: IntGauge,
+}
+
+impl ProtocolMetrics {
+    fn new() -> Self {
+        ProtocolMetrics {
+            header_cache_hits: register(
+                IntCounter::new(
+                    "protocol_header_cache_hits_total",
+                    "number of received headers found in the cache of the verified headers",
+                )
+                .expect("invalid metric"),
+            ),
+            header_cache_misses: register(
+                IntCounter::new(
+                    "protocol_header_cache_misses_total",
+                    "number of received headers not found in the cache of the verified headers",
+                )
+                .expect("invalid metric"),
+            ),
+            header_cache_size: register(
+                IntGauge::new(
+                    "protocol_header_cache_size",
+                    "number of headers in the cache of the verified headers",
+                )
+                .expect("invalid metric"),
+            ),
+        }
+    }
+}
@@ -0,0 +1,101 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! HTTP endpoint serving the metrics to Prometheus
+
+use crate::{BOOTSTRAP_METRICS, CONSENSUS_METRICS, NETWORK_METRICS, POOL_METRICS, REGISTRY};
+use hyper::header::CONTENT_TYPE;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use prometheus::{Encoder, TextEncoder};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+/// Handle to stop the metrics server
+pub struct MetricsServerHandle {
+    /// signals the server to stop
+    stop_tx: oneshot::Sender<()>,
+    /// task running the server
+    join_handle: JoinHandle<()>,
+}
+
+impl MetricsServerHandle {
+    /// Stop the server, once the requests in progress are answered
+    pub async fn stop(self) {
+        let _ = self.stop_tx.send(());
+        let _ = self.join_handle.await;
+    }
+}
+
+/// Start serving the metrics on `GET http://<bind>/metrics`.
+/// Must be called from within a tokio runtime.
+pub fn start_metrics_server(bind: SocketAddr) -> Result<MetricsServerHandle, hyper::Error> {
+    // register the metrics of all the components, even if they were not updated yet
+    lazy_static::initialize(&BOOTSTRAP_METRICS);
+    lazy_static::initialize(&CONSENSUS_METRICS);
+    lazy_static::initialize(&NETWORK_METRICS);
+    lazy_static::initialize(&POOL_METRICS);
+
+    let server = Server::try_bind(&bind)?.serve(make_service_fn(|_conn| async {
+        Ok::<_, Infallible>(service_fn(serve_request))
+    }));
+    let (stop_tx, stop_rx) = oneshot::channel();
+    let join_handle = tokio::spawn(async move {
+        if let Err(err) = server
+            .with_graceful_shutdown(async {
+                let _ = stop_rx.await;
+            })
+            .await
+        {
+            warn!("metrics server stopped with an error: {}", err);
+        }
+    });
+    info!("metrics served on http://{}/metrics", bind);
+    Ok(MetricsServerHandle {
+        stop_tx,
+        join_handle,
+    })
+}
+
+/// Answer a request to the metrics server
+async fn serve_request(request: Request<Body>) -> Result<Response<Body>, Infallible> {
+    if request.method() != Method::GET || request.uri().path() != "/metrics" {
+        return Ok(empty_response(StatusCode::NOT_FOUND));
+    }
+    match encode_metrics() {
+        Ok(metrics) => Ok(Response::builder()
+            .header(CONTENT_TYPE, TextEncoder::new().format_type())
+            .body(Body::from(metrics))
+            .expect("invalid metrics response")),
+        Err(err) => {
+            warn!("could not encode the metrics: {}", err);
+            Ok(empty_response(StatusCode::INTERNAL_SERVER_ERROR))
+        }
+    }
+}
+
+/// Build a response without body
+fn empty_response(status: StatusCode) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .body(Body::empty())
+        .expect("invalid metrics response")
+}
+
+/// Encode all the registered metrics in the Prometheus text format
+fn encode_metrics() -> prometheus::Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    TextEncoder::new().encode(&REGISTRY.gather(), &mut buffer)?;
+    Ok(buffer)
+}
+
+#[test]
+fn test_encode_metrics() {
+    CONSENSUS_METRICS.processed_blocks.inc();
+    POOL_METRICS.note_dropped_operation("expired");
+    let metrics = String::from_utf8(encode_metrics().unwrap()).unwrap();
+    assert!(metrics.contains("massa_consensus_processed_blocks_total 1"));
+    assert!(metrics.contains("massa_pool_dropped_operations_total{reason=\"expired\"} 1"));
+}
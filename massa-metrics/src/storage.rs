@@ -0,0 +1,59 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Metrics of the archive maintenance
+
+use crate::register;
+use lazy_static::lazy_static;
+use prometheus::{IntCounter, IntGauge};
+
+lazy_static! {
+    /// Metrics updated by the archive maintenance
+    pub static ref STORAGE_METRICS: StorageMetrics = StorageMetrics::new();
+}
+
+/// Metrics of the pruning and compaction of the archive
+pub struct StorageMetrics {
+    /// number of archived blocks removed
+    pub pruned_blocks: IntCounter,
+    /// number of archived blocks whose operations were trimmed
+    pub trimmed_blocks: IntCounter,
+    /// disk space reclaimed by the compactions, in bytes
+    pub reclaimed_bytes: IntCounter,
+    /// disk space used by the archive after the last maintenance, in bytes
+    pub archive_size_bytes: IntGauge,
+}
+
+impl StorageMetrics {
+    fn new() -> Self {
+        StorageMetrics {
+            pruned_blocks: register(
+                IntCounter::new(
+                    "storage_pruned_blocks_total",
+                    "number of archived blocks removed",
+                )
+                .expect("invalid metric"),
+            ),
+            trimmed_blocks: register(
+                IntCounter::new(
+                    "storage_trimmed_blocks_total",
+                    "number of archived blocks whose operations were trimmed",
+                )
+                .expect("invalid metric"),
+            ),
+            reclaimed_bytes: register(
+                IntCounter::new(
+                    "storage_reclaimed_bytes_total",
+                    "disk space reclaimed by the archive compactions, in bytes",
+                )
+                .expect("invalid metric"),
+            ),
+            archive_size_bytes: register(
+                IntGauge::new(
+                    "storage_archive_size_bytes",
+                    "disk space used by the archive after the last maintenance, in bytes",
+                )
+                .expect("invalid metric"),
+            ),
+        }
+    }
+}
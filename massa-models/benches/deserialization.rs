@@ -0,0 +1,127 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Deserialization of the operations of a block, copying the serialized operations
+//! or sharing the receive buffer, at growing operation counts.
+//! The number of allocations per deserialized block is printed before the timings.
+//! Only a lone operation makes up enough of the buffer to share it: in the larger batches, the operations are copied.
+
+use bytes::Bytes;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use massa_models::{
+    address::Address,
+    amount::Amount,
+    config::{
+        MAX_DATASTORE_VALUE_LENGTH, MAX_FUNCTION_NAME_LENGTH, MAX_OPERATIONS_PER_BLOCK,
+        MAX_OPERATION_DATASTORE_ENTRY_COUNT, MAX_OPERATION_DATASTORE_KEY_LENGTH,
+        MAX_OPERATION_DATASTORE_VALUE_LENGTH, MAX_PARAMETERS_SIZE,
+    },
+    operation::{
+        Operation, OperationSerializer, OperationType, OperationsDeserializer,
+        OperationsSerializer, WrappedOperation,
+    },
+    wrapped::WrappedContent,
+};
+use massa_serialization::{with_shared_buffer, DeserializeError, Deserializer, Serializer};
+use massa_signature::KeyPair;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// System allocator counting the allocations
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Number of allocations made by `f`
+fn count_allocations<R>(f: impl FnOnce() -> R) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    black_box(f());
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+/// Serialize `count` transactions, as the operations of a block
+fn prepare_operations(count: usize) -> Bytes {
+    let keypair = KeyPair::generate();
+    let recipient_address = Address::from_public_key(&KeyPair::generate().get_public_key());
+    let operations: Vec<WrappedOperation> = (0..count)
+        .map(|index| {
+            let content = Operation {
+                fee: Amount::from_raw(index as u64),
+                expire_period: 10,
+                op: OperationType::Transaction {
+                    recipient_address,
+                    amount: Amount::from_raw(1),
+                },
+            };
+            Operation::new_wrapped(content, OperationSerializer::new(), &keypair).unwrap()
+        })
+        .collect();
+    let mut buffer = Vec::new();
+    OperationsSerializer::new()
+        .serialize(&operations, &mut buffer)
+        .unwrap();
+    buffer.into()
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let deserializer = OperationsDeserializer::new(
+        MAX_OPERATIONS_PER_BLOCK,
+        MAX_DATASTORE_VALUE_LENGTH,
+        MAX_FUNCTION_NAME_LENGTH,
+        MAX_PARAMETERS_SIZE,
+        MAX_OPERATION_DATASTORE_ENTRY_COUNT,
+        MAX_OPERATION_DATASTORE_KEY_LENGTH,
+        MAX_OPERATION_DATASTORE_VALUE_LENGTH,
+    );
+    let copy = |buffer: &Bytes| {
+        deserializer
+            .deserialize::<DeserializeError>(buffer)
+            .unwrap()
+            .1
+    };
+    let share = |buffer: &Bytes| {
+        with_shared_buffer(buffer, |input| {
+            deserializer
+                .deserialize::<DeserializeError>(input)
+                .unwrap()
+                .1
+        })
+    };
+
+    let mut group = c.benchmark_group("operations deserialization");
+    for count in [1, 100, MAX_OPERATIONS_PER_BLOCK as usize] {
+        let buffer = prepare_operations(count);
+        println!(
+            "{} operations: {} allocations when copying, {} when sharing the buffer",
+            count,
+            count_allocations(|| copy(&buffer)),
+            count_allocations(|| share(&buffer)),
+        );
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(BenchmarkId::new("copy", count), &buffer, |b, buffer| {
+            b.iter(|| copy(black_box(buffer)))
+        });
+        group.bench_with_input(
+            BenchmarkId::new("shared buffer", count),
+            &buffer,
+            |b, buffer| b.iter(|| share(black_box(buffer))),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,53 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Computation of the operation merkle root of a block, hashing the concatenated operation ids
+//! on the calling thread or with the worker pool, at growing operation counts.
+//! Below 4096 operations the ids are hashed on the calling thread in both cases.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use massa_hash::Hash;
+use massa_models::{
+    config::MAX_OPERATIONS_PER_BLOCK,
+    operation::{compute_operations_hash, OperationId},
+    wrapped::Id,
+};
+
+/// Generate `count` distinct operation ids
+fn prepare_operation_ids(count: usize) -> Vec<OperationId> {
+    (0..count)
+        .map(|index| OperationId::new(Hash::compute_from(&index.to_be_bytes())))
+        .collect()
+}
+
+/// Hash the concatenated ids on the calling thread
+fn compute_operations_hash_sequential(operation_ids: &[OperationId]) -> Hash {
+    let mut total_hash: Vec<u8> = Vec::new();
+    for operation_id in operation_ids {
+        total_hash.extend(operation_id.to_bytes());
+    }
+    Hash::compute_from(&total_hash)
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("operation merkle root");
+    for count in [100, 4096, MAX_OPERATIONS_PER_BLOCK as usize, 100_000] {
+        let operation_ids = prepare_operation_ids(count);
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(
+            BenchmarkId::new("sequential", count),
+            &operation_ids,
+            |b, operation_ids| {
+                b.iter(|| compute_operations_hash_sequential(black_box(operation_ids)))
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("worker pool", count),
+            &operation_ids,
+            |b, operation_ids| b.iter(|| compute_operations_hash(black_box(operation_ids))),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
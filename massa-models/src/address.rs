@@ -2,15 +2,13 @@
 
 use crate::error::ModelsError;
 use crate::prehash::PreHashed;
+use crate::serialization::{from_prefixed_bs58_check, to_prefixed_bs58_check};
 use massa_hash::{Hash, HashDeserializer};
-use massa_serialization::{
-    DeserializeError, Deserializer, Serializer, U64VarIntDeserializer, U64VarIntSerializer,
-};
+use massa_serialization::{Deserializer, Serializer};
 use massa_signature::PublicKey;
 use nom::error::{context, ContextError, ParseError};
 use nom::{IResult, Parser};
 use serde::{Deserialize, Serialize};
-use std::ops::Bound::Included;
 use std::str::FromStr;
 
 /// Size of a serialized address, in bytes
@@ -25,18 +23,10 @@ const ADDRESS_VERSION: u64 = 0;
 
 impl std::fmt::Display for Address {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let u64_serializer = U64VarIntSerializer::new();
-        // might want to allocate the vector with capacity in order to avoid re-allocation
-        let mut bytes: Vec<u8> = Vec::new();
-        u64_serializer
-            .serialize(&ADDRESS_VERSION, &mut bytes)
-            .map_err(|_| std::fmt::Error)?;
-        bytes.extend(self.0.to_bytes());
         write!(
             f,
-            "{}{}",
-            ADDRESS_PREFIX,
-            bs58::encode(bytes).with_check().into_string()
+            "{}",
+            to_prefixed_bs58_check(ADDRESS_PREFIX, ADDRESS_VERSION, self.0.to_bytes())
         )
     }
 }
@@ -127,25 +117,9 @@ impl FromStr for Address {
     /// assert_eq!(address, res_addr);
     /// ```
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut chars = s.chars();
-        match chars.next() {
-            Some(prefix) if prefix == ADDRESS_PREFIX => {
-                let data = chars.collect::<String>();
-                let decoded_bs58_check = bs58::decode(data)
-                    .with_check(None)
-                    .into_vec()
-                    .map_err(|_| ModelsError::AddressParseError)?;
-                let u64_deserializer = U64VarIntDeserializer::new(Included(0), Included(u64::MAX));
-                let (rest, _version) = u64_deserializer
-                    .deserialize::<DeserializeError>(&decoded_bs58_check[..])
-                    .map_err(|_| ModelsError::AddressParseError)?;
-                Ok(Address(Hash::from_bytes(
-                    rest.try_into()
-                        .map_err(|_| ModelsError::AddressParseError)?,
-                )))
-            }
-            _ => Err(ModelsError::AddressParseError),
-        }
+        let bytes = from_prefixed_bs58_check(s, ADDRESS_PREFIX, ADDRESS_VERSION)
+            .map_err(ModelsError::AddressParseError)?;
+        Ok(Address(Hash::from_bytes(&bytes)))
     }
 }
 
@@ -158,6 +132,16 @@ fn test_address_str_format() {
     let a = address.to_string();
     let b = Address::from_str(&a).unwrap();
     assert!(address == b);
+
+    // a mistyped character breaks the checksum
+    let mut typo: Vec<char> = a.chars().collect();
+    let last = typo.len() - 1;
+    typo[last] = if typo[last] == '1' { '2' } else { '1' };
+    let typo: String = typo.into_iter().collect();
+    assert!(Address::from_str(&typo).is_err());
+    // an address is not a block id, even with the same hash
+    assert!(crate::block::BlockId::from_str(&a).is_err());
+    assert!(Address::from_str(&a[1..]).is_err());
 }
 
 impl PreHashed for Address {}
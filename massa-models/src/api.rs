@@ -5,6 +5,7 @@ use crate::endorsement::{EndorsementId, WrappedEndorsement};
 use crate::ledger_models::LedgerData;
 use crate::node::NodeId;
 use crate::operation::{OperationId, WrappedOperation};
+use crate::state_commitment::StateDivergence;
 use crate::stats::{ConsensusStats, ExecutionStats, NetworkStats};
 use crate::{
     address::Address, amount::Amount, block::Block, block::BlockId, config::CompactConfig,
@@ -15,7 +16,7 @@ use massa_time::MassaTime;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::net::IpAddr;
-use strum::Display;
+use strum::{Display, EnumString};
 
 /// operation input
 #[derive(Serialize, Deserialize, Debug)]
@@ -28,6 +29,592 @@ pub struct OperationInput {
     pub serialized_content: Vec<u8>,
 }
 
+/// candidate block input, checked against the graph without being inserted
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BlockCandidateInput {
+    /// The public key of the creator of the block
+    pub creator_public_key: PublicKey,
+    /// The signature of the block header
+    pub signature: Signature,
+    /// The serialized version of the header content
+    pub serialized_header_content: Vec<u8>,
+    /// The operations of the block, in block order
+    pub operations: Vec<OperationInput>,
+}
+
+/// result of the dry-run validity check of a candidate block
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct BlockValidityReport {
+    /// id of the candidate block
+    pub block_id: BlockId,
+    /// true if the block would be accepted by the graph right now
+    pub is_valid: bool,
+    /// reasons why the block would be rejected or put on hold, empty if the block is valid
+    pub rejection_reasons: Vec<String>,
+}
+
+impl std::fmt::Display for BlockValidityReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Block {}", self.block_id)?;
+        if self.is_valid {
+            writeln!(f, "\tValid")?;
+        } else {
+            writeln!(f, "\tInvalid:")?;
+            for reason in &self.rejection_reasons {
+                writeln!(f, "\t\t- {}", reason)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// reason code of the rejection of a submitted operation
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Display)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum OperationRejectionCode {
+    /// the operation could not be deserialized
+    InvalidFormat,
+    /// the signature does not match the content and the creator public key
+    InvalidSignature,
+    /// the expire period of the operation is already reached in its thread
+    Expired,
+    /// the validity of the operation starts too far in the future
+    ValidityTooFar,
+    /// the fee is below the minimal fee accepted by the pool
+    FeeTooLow,
+    /// the operation conflicts with a pooled operation of the same sender without paying the fee increment to replace it
+    ReplacementFeeTooLow,
+    /// the sender already has the maximal number of operations in the pool, all paying a better fee
+    SenderLimitReached,
+    /// the pool is full of operations paying a better fee
+    PoolFull,
+    /// the balance of the creator does not cover the fee and the coins spent by the operation
+    InsufficientBalance,
+    /// the node is in degraded read-only mode and does not accept new operations
+    NodeDegraded,
+}
+
+/// result of the submission of a single operation
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct OperationSubmissionResult {
+    /// id of the operation, if it could be deserialized
+    pub operation_id: Option<OperationId>,
+    /// true if the operation was added to the pool and propagated
+    pub accepted: bool,
+    /// reason code of the rejection, if rejected
+    pub rejection_code: Option<OperationRejectionCode>,
+    /// human readable details about the rejection, if rejected
+    pub rejection_message: Option<String>,
+}
+
+impl OperationSubmissionResult {
+    /// result of an accepted operation
+    pub fn accepted(operation_id: OperationId) -> Self {
+        OperationSubmissionResult {
+            operation_id: Some(operation_id),
+            accepted: true,
+            rejection_code: None,
+            rejection_message: None,
+        }
+    }
+
+    /// result of a rejected operation
+    pub fn rejected(
+        operation_id: Option<OperationId>,
+        code: OperationRejectionCode,
+        message: String,
+    ) -> Self {
+        OperationSubmissionResult {
+            operation_id,
+            accepted: false,
+            rejection_code: Some(code),
+            rejection_message: Some(message),
+        }
+    }
+}
+
+impl std::fmt::Display for OperationSubmissionResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.operation_id {
+            Some(id) => writeln!(f, "Operation {}", id)?,
+            None => writeln!(f, "Operation")?,
+        }
+        if self.accepted {
+            writeln!(f, "\tAccepted")?;
+        } else {
+            match self.rejection_code {
+                Some(code) => writeln!(f, "\tRejected ({})", code)?,
+                None => writeln!(f, "\tRejected")?,
+            }
+            if let Some(message) = &self.rejection_message {
+                writeln!(f, "\t\t{}", message)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// fitness of a max clique, as seen by the fork choice rule
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct CliqueFitness {
+    /// index of the clique among the current max cliques
+    pub index: usize,
+    /// sum of the fitness of the blocks of the clique
+    pub fitness: u64,
+    /// number of blocks in the clique
+    pub block_count: usize,
+    /// true if the clique is the blockclique
+    pub is_blockclique: bool,
+}
+
+impl std::fmt::Display for CliqueFitness {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "clique {}: fitness {}, {} blocks{}",
+            self.index,
+            self.fitness,
+            self.block_count,
+            if self.is_blockclique {
+                " (blockclique)"
+            } else {
+                ""
+            }
+        )
+    }
+}
+
+/// explanation of the fork choice status of a block
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ForkChoiceExplanation {
+    /// explained block
+    pub block_id: BlockId,
+    /// status of the block in the graph
+    pub status: BlockGraphStatus,
+    /// fitness contribution of the block, none if the block is not active
+    pub fitness: Option<u64>,
+    /// max cliques containing the block
+    pub cliques: Vec<CliqueFitness>,
+    /// the clique of highest fitness
+    pub blockclique: CliqueFitness,
+    /// number of max cliques
+    pub clique_count: usize,
+    /// blocks of the blockclique that are incompatible with the block
+    pub incompatible_blockclique_blocks: Vec<BlockId>,
+    /// fitness of the descendants of the block within its best clique, none if the block is not active
+    pub descendants_fitness: Option<u64>,
+    /// descendants fitness that must be exceeded for the block to become final
+    pub finality_threshold: u64,
+    /// human readable explanation of the status
+    pub explanation: Vec<String>,
+}
+
+impl std::fmt::Display for ForkChoiceExplanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Block {}: {:?}", self.block_id, self.status)?;
+        if let Some(fitness) = self.fitness {
+            writeln!(f, "	Fitness: {}", fitness)?;
+        }
+        if let Some(descendants_fitness) = self.descendants_fitness {
+            writeln!(
+                f,
+                "	Descendants fitness: {} (finality threshold: {})",
+                descendants_fitness, self.finality_threshold
+            )?;
+        }
+        writeln!(
+            f,
+            "	In {} of {} cliques:",
+            self.cliques.len(),
+            self.clique_count
+        )?;
+        for clique in &self.cliques {
+            writeln!(f, "		{}", clique)?;
+        }
+        writeln!(f, "	Blockclique: {}", self.blockclique)?;
+        if !self.incompatible_blockclique_blocks.is_empty() {
+            writeln!(f, "	Incompatible blockclique blocks:")?;
+            for block_id in &self.incompatible_blockclique_blocks {
+                writeln!(f, "		{}", block_id)?;
+            }
+        }
+        writeln!(f, "	Explanation:")?;
+        for line in &self.explanation {
+            writeln!(f, "		- {}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// synchronization state of the node
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeSyncState {
+    /// the genesis timestamp is not reached yet
+    WaitingForGenesis,
+    /// the node is not connected to any other node
+    Isolated,
+    /// the blockclique lags behind the current slot
+    Syncing,
+    /// the blockclique follows the current slot
+    Synced,
+    /// the node is connected but seems cut from the rest of the network,
+    /// see the network partition alerts of the node status
+    Partitioned,
+}
+
+impl std::fmt::Display for NodeSyncState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NodeSyncState::WaitingForGenesis => write!(f, "waiting for genesis"),
+            NodeSyncState::Isolated => write!(f, "isolated"),
+            NodeSyncState::Syncing => write!(f, "syncing"),
+            NodeSyncState::Synced => write!(f, "synced"),
+            NodeSyncState::Partitioned => write!(f, "partitioned"),
+        }
+    }
+}
+
+/// phase of the synchronization of the node with the rest of the network
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncPhase {
+    /// the final state is being bootstrapped
+    #[default]
+    Bootstrapping,
+    /// the final blocks lag behind the slots announced by the other nodes
+    CatchingUp,
+    /// the final blocks follow the slots announced by the other nodes
+    Synced,
+}
+
+impl std::fmt::Display for SyncPhase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SyncPhase::Bootstrapping => write!(f, "bootstrapping"),
+            SyncPhase::CatchingUp => write!(f, "catching up"),
+            SyncPhase::Synced => write!(f, "synced"),
+        }
+    }
+}
+
+/// progress of the synchronization of the node, updated by the bootstrap and the protocol
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+pub struct SyncProgress {
+    /// current phase
+    pub phase: SyncPhase,
+    /// highest slot of the valid headers received from the other nodes, none if no header was received yet
+    pub highest_remote_slot: Option<Slot>,
+    /// highest slot of the latest final blocks of the threads,
+    /// or slot of the final state being bootstrapped
+    pub local_final_slot: Option<Slot>,
+    /// estimated time until the node is synced, none if synced or if the node does not catch up
+    pub estimated_time_remaining: Option<MassaTime>,
+}
+
+impl std::fmt::Display for SyncProgress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.phase)?;
+        if let Some(local_final_slot) = self.local_final_slot {
+            write!(f, ", final slot {}", local_final_slot)?;
+        }
+        if let Some(highest_remote_slot) = self.highest_remote_slot {
+            write!(f, ", highest remote slot {}", highest_remote_slot)?;
+        }
+        if let Some(remaining) = self.estimated_time_remaining {
+            write!(
+                f,
+                ", about {} seconds remaining",
+                remaining.to_duration().as_secs()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// override of the safety interlock of the block production, set by the node administrator
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize, Display, EnumString,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case", ascii_case_insensitive)]
+pub enum ProductionOverride {
+    /// blocks are only produced while the node is connected to enough nodes and synced, if configured so
+    #[default]
+    Automatic,
+    /// blocks are produced whatever the connected nodes and the synchronization
+    AlwaysProduce,
+    /// no block is produced
+    NeverProduce,
+}
+
+/// sign, raised by the network partition watchdog, that the node may be cut from the rest of the network
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NetworkPartitionAlert {
+    /// no block became final for `slots` slots while the node was connected to `connected_nodes` nodes
+    StalledFinality {
+        /// number of slots since a block last became final
+        slots: u64,
+        /// number of connected nodes
+        connected_nodes: usize,
+    },
+    /// the outgoing connections are below the configured threshold since `since`
+    LowOutConnections {
+        /// number of outgoing connections
+        out_connections: usize,
+        /// time at which the outgoing connections fell below the threshold
+        since: MassaTime,
+    },
+}
+
+impl std::fmt::Display for NetworkPartitionAlert {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NetworkPartitionAlert::StalledFinality {
+                slots,
+                connected_nodes,
+            } => write!(
+                f,
+                "no block became final for {} slots while connected to {} nodes",
+                slots, connected_nodes
+            ),
+            NetworkPartitionAlert::LowOutConnections {
+                out_connections,
+                since,
+            } => write!(
+                f,
+                "only {} outgoing connections since {}",
+                out_connections,
+                since.to_utc_string()
+            ),
+        }
+    }
+}
+
+/// blockclique switch rolling back more periods than `max_reorg_depth_periods`,
+/// held back by consensus until the node administrator accepts it:
+/// a sign of a long-range attack or of a local corruption
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ReorgAlert {
+    /// number of periods of the current blockclique the switch would roll back
+    pub depth_periods: u64,
+    /// slot of the earliest block of the current blockclique the switch would drop
+    pub fork_slot: Slot,
+    /// fitness of the rejected clique
+    pub rejected_fitness: u64,
+    /// fitness of the clique kept as blockclique instead
+    pub kept_fitness: u64,
+    /// time at which the switch was first rejected
+    pub since: MassaTime,
+}
+
+impl std::fmt::Display for ReorgAlert {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "rejected a reorganization of {} periods from slot {} since {} (rejected clique fitness {}, kept clique fitness {})",
+            self.depth_periods,
+            self.fork_slot,
+            self.since.to_utc_string(),
+            self.rejected_fitness,
+            self.kept_fitness
+        )
+    }
+}
+
+/// resource threshold crossed, putting the node in degraded read-only mode
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResourceAlert {
+    /// fewer than `min_available` bytes are available on the disk of `path`
+    LowDiskSpace {
+        /// watched path
+        path: String,
+        /// available bytes on its disk
+        available: u64,
+        /// configured minimum
+        min_available: u64,
+    },
+    /// the node uses more than `max_used` bytes of memory
+    HighMemoryUsage {
+        /// resident memory of the node in bytes
+        used: u64,
+        /// configured maximum
+        max_used: u64,
+    },
+}
+
+impl std::fmt::Display for ResourceAlert {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResourceAlert::LowDiskSpace {
+                path,
+                available,
+                min_available,
+            } => write!(
+                f,
+                "only {} MB available on the disk of {} (minimum {} MB)",
+                available / 1_000_000,
+                path,
+                min_available / 1_000_000
+            ),
+            ResourceAlert::HighMemoryUsage { used, max_used } => write!(
+                f,
+                "{} MB of memory used (maximum {} MB)",
+                used / 1_000_000,
+                max_used / 1_000_000
+            ),
+        }
+    }
+}
+
+/// node health, a light version of the node status meant for monitoring probes
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct NodeHealth {
+    /// synchronization state
+    pub sync_state: NodeSyncState,
+    /// latest slot, none if now is before genesis timestamp
+    pub last_slot: Option<Slot>,
+    /// number of connected nodes
+    pub connected_node_count: usize,
+}
+
+impl std::fmt::Display for NodeHealth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Sync state: {}", self.sync_state)?;
+        if let Some(last_slot) = self.last_slot {
+            writeln!(f, "Last slot: {}", last_slot)?;
+        }
+        writeln!(f, "Connected nodes: {}", self.connected_node_count)
+    }
+}
+
+/// node connected to our node
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ConnectedNodeInfo {
+    /// node id
+    pub node_id: NodeId,
+    /// ip address of the node
+    pub ip: IpAddr,
+    /// true if the connection is outgoing, false if incoming
+    pub is_outgoing: bool,
+    /// last time the peer at that ip was alive
+    pub last_alive: Option<MassaTime>,
+    /// bytes sent to the node since the connection was established
+    pub bytes_sent: u64,
+    /// bytes received from the node since the connection was established
+    pub bytes_received: u64,
+    /// software version announced by the node in its handshake
+    pub version: Option<Version>,
+}
+
+/// connected nodes running a software version
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct NetworkVersionCount {
+    /// software version announced by the nodes in their handshake
+    pub version: Version,
+    /// number of connected nodes running it
+    pub node_count: usize,
+    /// true if the version is compatible with ours
+    pub compatible: bool,
+}
+
+/// software versions of the connected nodes, to follow the adoption of an upgrade
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct NetworkVersions {
+    /// our software version
+    pub our_version: Version,
+    /// number of connected nodes
+    pub node_count: usize,
+    /// connected nodes by software version, the most common first
+    pub versions: Vec<NetworkVersionCount>,
+}
+
+impl std::fmt::Display for NetworkVersions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Our version: {}", self.our_version)?;
+        writeln!(f, "Connected nodes: {}", self.node_count)?;
+        for count in &self.versions {
+            writeln!(
+                f,
+                "\t{}: {} node(s) ({:.1}%){}",
+                count.version,
+                count.node_count,
+                100.0 * count.node_count as f64 / self.node_count.max(1) as f64,
+                if count.compatible {
+                    ""
+                } else {
+                    ", incompatible"
+                }
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// how a connection with a peer ended
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PeerConnectionCloseReason {
+    /// the outgoing connection could not be established
+    DialFailed,
+    /// our node refused the connection (no slot left, banned peer...)
+    Refused,
+    /// the connection was closed properly
+    Normal,
+    /// the connection failed (handshake, timeout, invalid message...)
+    Failed,
+    /// the connection was closed because the peer was banned
+    Banned,
+    /// the connection was closed because the peer could not keep up with the messages sent to it
+    SendQueueOverflow,
+}
+
+impl std::fmt::Display for PeerConnectionCloseReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PeerConnectionCloseReason::DialFailed => write!(f, "could not connect"),
+            PeerConnectionCloseReason::Refused => write!(f, "refused"),
+            PeerConnectionCloseReason::Normal => write!(f, "closed"),
+            PeerConnectionCloseReason::Failed => write!(f, "failed"),
+            PeerConnectionCloseReason::Banned => write!(f, "banned"),
+            PeerConnectionCloseReason::SendQueueOverflow => write!(f, "send queue overflow"),
+        }
+    }
+}
+
+/// ended connection with a peer, as kept in the connection history of the peer
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub struct PeerConnectionEvent {
+    /// when the connection started to be dialed (outgoing) or was accepted (incoming)
+    pub start: MassaTime,
+    /// true if the connection is outgoing, false if incoming
+    pub is_outgoing: bool,
+    /// time elapsed between the start and the end of the connection
+    pub duration: MassaTime,
+    /// how the connection ended
+    pub close_reason: PeerConnectionCloseReason,
+}
+
+impl std::fmt::Display for PeerConnectionEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} connection, {} after {} ms",
+            self.start.to_utc_string(),
+            if self.is_outgoing {
+                "outgoing"
+            } else {
+                "incoming"
+            },
+            self.close_reason,
+            self.duration.to_millis()
+        )
+    }
+}
+
 /// node status
 #[derive(Debug, Deserialize, Serialize)]
 pub struct NodeStatus {
@@ -39,6 +626,22 @@ pub struct NodeStatus {
     pub version: Version,
     /// now
     pub current_time: MassaTime,
+    /// time elapsed since the node started
+    pub uptime: MassaTime,
+    /// synchronization state
+    pub sync_state: NodeSyncState,
+    /// progress of the synchronization
+    pub sync_progress: SyncProgress,
+    /// alerts currently raised by the network partition watchdog
+    pub network_partition_alerts: Vec<NetworkPartitionAlert>,
+    /// alerts currently raised by the resource monitor, the node is in degraded read-only mode if any
+    pub resource_alerts: Vec<ResourceAlert>,
+    /// blockclique switch deeper than the configured limit, rejected until the node administrator accepts it
+    pub reorg_alert: Option<ReorgAlert>,
+    /// first divergence found between our final state and the one committed in a block header
+    pub state_divergence: Option<StateDivergence>,
+    /// number of periods between the latest slot and the latest block of the blockclique
+    pub blockclique_lag_periods: u64,
     /// current cycle
     pub current_cycle: u64,
     /// connected nodes (node id, ip address, true if the connection is outgoing, false if incoming)
@@ -47,6 +650,8 @@ pub struct NodeStatus {
     pub last_slot: Option<Slot>,
     /// next slot
     pub next_slot: Slot,
+    /// slot of the latest final block of each thread
+    pub last_final_slots: Vec<Slot>,
     /// consensus stats
     pub consensus_stats: ConsensusStats,
     /// pool stats (operation count and endorsement count)
@@ -74,11 +679,50 @@ impl std::fmt::Display for NodeStatus {
         writeln!(f)?;
 
         writeln!(f, "Current time: {}", self.current_time.to_utc_string())?;
+        writeln!(f, "Uptime: {} seconds", self.uptime.to_duration().as_secs())?;
+        writeln!(f, "Sync state: {}", self.sync_state)?;
+        writeln!(f, "Sync progress: {}", self.sync_progress)?;
+        for alert in &self.network_partition_alerts {
+            writeln!(f, "Network partition alert: {}", alert)?;
+        }
+        if !self.resource_alerts.is_empty() {
+            writeln!(
+                f,
+                "Degraded read-only mode: not serving bootstraps nor accepting new operations and endorsements"
+            )?;
+        }
+        for alert in &self.resource_alerts {
+            writeln!(f, "Resource alert: {}", alert)?;
+        }
+        if let Some(alert) = &self.reorg_alert {
+            writeln!(
+                f,
+                "REORG ALERT: {}: possible long-range attack or local corruption, check the node and accept it with node_accept_reorg only if legitimate",
+                alert
+            )?;
+        }
+        if let Some(divergence) = &self.state_divergence {
+            write!(f, "STATE DIVERGENCE: {}", divergence)?;
+        }
+        writeln!(
+            f,
+            "Blockclique lag: {} periods",
+            self.blockclique_lag_periods
+        )?;
         writeln!(f, "Current cycle: {}", self.current_cycle)?;
         if self.last_slot.is_some() {
             writeln!(f, "Last slot: {}", self.last_slot.unwrap())?;
         }
         writeln!(f, "Next slot: {}", self.next_slot)?;
+        writeln!(
+            f,
+            "Last final slots: {}",
+            self.last_final_slots
+                .iter()
+                .map(|slot| slot.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )?;
         writeln!(f)?;
 
         writeln!(f, "{}", self.consensus_stats)?;
@@ -140,6 +784,95 @@ impl std::fmt::Display for OperationInfo {
     }
 }
 
+/// reason why an operation was removed from the pool before being included in a final block
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationDropReason {
+    /// the expire period of the operation became final
+    Expired,
+    /// the pool was full of operations with a better fee density
+    Evicted,
+    /// the creator had too many operations in the pool
+    SenderLimit,
+    /// a conflicting operation of the same creator paying a higher fee replaced it
+    Replaced {
+        /// id of the operation that replaced it
+        replacement: OperationId,
+    },
+}
+
+impl std::fmt::Display for OperationDropReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OperationDropReason::Expired => write!(f, "expired"),
+            OperationDropReason::Evicted => write!(f, "evicted"),
+            OperationDropReason::SenderLimit => write!(f, "sender limit reached"),
+            OperationDropReason::Replaced { replacement } => {
+                write!(f, "replaced by {}", replacement)
+            }
+        }
+    }
+}
+
+/// lifecycle status of an operation, as tracked by the node
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationStatus {
+    /// the operation is not tracked, or not anymore
+    Unknown,
+    /// the operation is waiting in the pool
+    Pending,
+    /// the operation was included in a block that is not final yet (it may still be reverted)
+    Included {
+        /// id of the block
+        block_id: BlockId,
+        /// slot of the block
+        slot: Slot,
+    },
+    /// the operation was included in a final block
+    Final {
+        /// id of the block
+        block_id: BlockId,
+        /// slot of the block
+        slot: Slot,
+    },
+    /// the operation was removed from the pool before being included in a final block
+    Dropped {
+        /// reason of the removal
+        reason: OperationDropReason,
+    },
+}
+
+/// lifecycle status of an operation, along with its id
+#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
+pub struct OperationStatusInfo {
+    /// id of the operation
+    pub id: OperationId,
+    /// status of the operation
+    pub status: OperationStatus,
+}
+
+impl std::fmt::Display for OperationStatusInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Operation {}: ", self.id)?;
+        match self.status {
+            OperationStatus::Unknown => write!(f, "unknown"),
+            OperationStatus::Pending => write!(f, "pending in pool"),
+            OperationStatus::Included { block_id, slot } => {
+                write!(
+                    f,
+                    "included in block {} at slot {} (not final)",
+                    block_id, slot
+                )
+            }
+            OperationStatus::Final { block_id, slot } => {
+                write!(f, "final in block {} at slot {}", block_id, slot)
+            }
+            OperationStatus::Dropped { reason } => write!(f, "dropped ({})", reason),
+        }
+    }
+}
+
 /// Block status within the graph
 #[derive(Eq, PartialEq, Debug, Deserialize, Serialize)]
 pub enum BlockGraphStatus {
@@ -493,6 +1226,117 @@ impl std::fmt::Display for BlockSummary {
     }
 }
 
+/// Final operation involving an address, as found in the address index
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct FinalOperationEntry {
+    /// operation id
+    pub operation_id: OperationId,
+    /// id of the final block including the operation
+    pub block_id: BlockId,
+    /// slot of the block
+    pub slot: Slot,
+}
+
+impl std::fmt::Display for FinalOperationEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Operation {} in block {} at slot {}",
+            self.operation_id, self.block_id, self.slot
+        )
+    }
+}
+
+/// Outcome of an integrity check of the node storage
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct StorageCheckReport {
+    /// number of checked archived blocks and trimmed headers
+    pub checked_archived_blocks: usize,
+    /// number of corrupted archived blocks and trimmed headers
+    pub corrupted_archived_blocks: usize,
+    /// number of checked address index entries
+    pub checked_index_entries: usize,
+    /// number of address index entries that can not be decoded or do not match the archived blocks
+    pub corrupted_index_entries: usize,
+    /// number of archived blocks indexed again
+    pub reindexed_blocks: usize,
+    /// true if the corrupted entries were removed
+    pub repaired: bool,
+}
+
+impl StorageCheckReport {
+    /// true if no corrupted entry was found
+    pub fn is_healthy(&self) -> bool {
+        self.corrupted_archived_blocks == 0 && self.corrupted_index_entries == 0
+    }
+}
+
+impl std::fmt::Display for StorageCheckReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Archived blocks: {} checked, {} corrupted",
+            self.checked_archived_blocks, self.corrupted_archived_blocks
+        )?;
+        writeln!(
+            f,
+            "Address index entries: {} checked, {} corrupted",
+            self.checked_index_entries, self.corrupted_index_entries
+        )?;
+        if self.repaired {
+            writeln!(
+                f,
+                "Repaired: corrupted entries removed, {} archived blocks indexed again",
+                self.reindexed_blocks
+            )?;
+        } else if !self.is_healthy() {
+            writeln!(f, "Not repaired")?;
+        }
+        Ok(())
+    }
+}
+
+/// Records of an export of the archived final blocks
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case", ascii_case_insensitive)]
+pub enum ExportContent {
+    /// one record per block
+    Blocks,
+    /// one record per operation
+    Operations,
+}
+
+/// File format of an export of the archived final blocks
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case", ascii_case_insensitive)]
+pub enum ExportFormat {
+    /// one JSON object per line
+    Jsonl,
+    /// comma separated values with a header line
+    Csv,
+}
+
+/// Outcome of an export of the archived final blocks
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct ExportStats {
+    /// number of exported blocks
+    pub blocks: usize,
+    /// number of written records
+    pub records: usize,
+}
+
+impl std::fmt::Display for ExportStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} records written from {} archived blocks",
+            self.records, self.blocks
+        )
+    }
+}
+
 /// Dumb utils function to display nicely boolean value
 fn display_if_true(value: bool, text: &str) -> String {
     if value {
@@ -511,6 +1355,53 @@ pub struct TimeInterval {
     pub end: Option<MassaTime>,
 }
 
+/// Page requested by a paginated query
+#[derive(Debug, Deserialize, Clone, Copy, Serialize)]
+pub struct Pagination {
+    /// number of results to skip
+    pub offset: usize,
+    /// max number of results to return
+    pub limit: usize,
+}
+
+/// Page of the results of a paginated query
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct PagedResult<T> {
+    /// results in the requested page
+    pub items: Vec<T>,
+    /// total number of results of the query
+    pub total_count: usize,
+}
+
+impl<T> PagedResult<T> {
+    /// Keep the `pagination` page of all the results of a query
+    pub fn from_results(results: Vec<T>, pagination: Pagination) -> Self {
+        let total_count = results.len();
+        PagedResult {
+            items: results
+                .into_iter()
+                .skip(pagination.offset)
+                .take(pagination.limit)
+                .collect(),
+            total_count,
+        }
+    }
+}
+
+impl<T: std::fmt::Display> std::fmt::Display for PagedResult<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for item in &self.items {
+            writeln!(f, "{}", item)?;
+        }
+        writeln!(
+            f,
+            "{} out of {} results",
+            self.items.len(),
+            self.total_count
+        )
+    }
+}
+
 /// Datastore entry query input structure
 #[derive(Debug, Deserialize, Clone, Serialize)]
 pub struct DatastoreEntryInput {
@@ -0,0 +1,144 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Merkle proofs of final balances.
+//!
+//! The final balances of each thread are spread over `BALANCE_TREE_BUCKET_COUNT` buckets
+//! according to the hash of their address, and a binary Merkle tree is built over the buckets of each thread.
+//! The balance state root is the hash of the concatenation of the roots of all thread trees.
+
+use crate::{address::Address, amount::Amount, slot::Slot};
+use massa_hash::{Hash, HASH_SIZE_BYTES};
+use serde::{Deserialize, Serialize};
+
+/// Depth of the balance tree of a thread
+pub const BALANCE_TREE_DEPTH: usize = 12;
+
+/// Number of buckets (leaves) of the balance tree of a thread
+pub const BALANCE_TREE_BUCKET_COUNT: usize = 1 << BALANCE_TREE_DEPTH;
+
+/// Get the index of the bucket of an address in the balance tree of its thread
+pub fn balance_bucket_index(address: &Address) -> usize {
+    let hash_bytes = Hash::compute_from(address.to_bytes()).into_bytes();
+    (u16::from_be_bytes([hash_bytes[0], hash_bytes[1]]) >> (16 - BALANCE_TREE_DEPTH)) as usize
+}
+
+/// Compute the hash of a bucket from its entries, which must be sorted by address
+pub fn balance_bucket_hash<'a, I>(entries: I) -> Hash
+where
+    I: IntoIterator<Item = (&'a Address, &'a Amount)>,
+{
+    let mut bytes = Vec::new();
+    for (address, amount) in entries {
+        bytes.extend(address.to_bytes());
+        bytes.extend(amount.to_raw().to_be_bytes());
+    }
+    Hash::compute_from(&bytes)
+}
+
+/// Compute the hash of an inner node of a balance tree from the hashes of its children
+pub fn balance_node_hash(left: &Hash, right: &Hash) -> Hash {
+    let mut bytes = [0u8; 2 * HASH_SIZE_BYTES];
+    bytes[..HASH_SIZE_BYTES].copy_from_slice(left.to_bytes());
+    bytes[HASH_SIZE_BYTES..].copy_from_slice(right.to_bytes());
+    Hash::compute_from(&bytes)
+}
+
+/// Compute the balance state root from the roots of the balance trees of all threads
+pub fn balance_state_root(thread_roots: &[Hash]) -> Hash {
+    let mut bytes = Vec::with_capacity(thread_roots.len() * HASH_SIZE_BYTES);
+    for root in thread_roots {
+        bytes.extend(root.to_bytes());
+    }
+    Hash::compute_from(&bytes)
+}
+
+/// Proof of the final balance of an address (or of its absence) against a balance state root
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BalanceProof {
+    /// proven address
+    pub address: Address,
+    /// final balance of the address, `None` if the address has no ledger entry
+    pub balance: Option<Amount>,
+    /// every entry of the bucket of the address, sorted by address
+    pub bucket: Vec<(Address, Amount)>,
+    /// hashes of the siblings of the nodes on the path from the bucket to the thread root
+    pub siblings: Vec<Hash>,
+    /// roots of the balance trees of all threads
+    pub thread_roots: Vec<Hash>,
+    /// balance state root
+    pub state_root: Hash,
+}
+
+impl BalanceProof {
+    /// Check the proof against a trusted balance state root
+    pub fn verify(&self, state_root: &Hash, thread_count: u8) -> bool {
+        if self.state_root != *state_root
+            || self.thread_roots.len() != thread_count as usize
+            || balance_state_root(&self.thread_roots) != *state_root
+            || self.siblings.len() != BALANCE_TREE_DEPTH
+        {
+            return false;
+        }
+
+        // the bucket must be sorted and only contain addresses of the same thread and bucket
+        let thread = self.address.get_thread(thread_count);
+        let bucket_index = balance_bucket_index(&self.address);
+        if self.bucket.windows(2).any(|w| w[0].0 >= w[1].0)
+            || self.bucket.iter().any(|(addr, _)| {
+                addr.get_thread(thread_count) != thread
+                    || balance_bucket_index(addr) != bucket_index
+            })
+        {
+            return false;
+        }
+        let bucket_balance = self
+            .bucket
+            .iter()
+            .find(|(addr, _)| *addr == self.address)
+            .map(|(_, amount)| *amount);
+        if bucket_balance != self.balance {
+            return false;
+        }
+
+        // walk up to the thread root
+        let mut hash = balance_bucket_hash(self.bucket.iter().map(|(addr, amount)| (addr, amount)));
+        let mut node_index = bucket_index;
+        for sibling in &self.siblings {
+            hash = if node_index % 2 == 0 {
+                balance_node_hash(&hash, sibling)
+            } else {
+                balance_node_hash(sibling, &hash)
+            };
+            node_index /= 2;
+        }
+        hash == self.thread_roots[thread as usize]
+    }
+}
+
+/// Balance proof against the balance state root of a final slot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FinalBalanceProof {
+    /// final slot of the ledger the proof was generated from
+    pub slot: Slot,
+    /// balance proof
+    pub proof: BalanceProof,
+}
+
+impl std::fmt::Display for FinalBalanceProof {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Address: {}", self.proof.address)?;
+        match self.proof.balance {
+            Some(balance) => writeln!(f, "Final balance: {}", balance)?,
+            None => writeln!(f, "Final balance: no ledger entry")?,
+        }
+        writeln!(f, "Final slot: {}", self.slot)?;
+        writeln!(f, "Balance state root: {}", self.proof.state_root)?;
+        writeln!(
+            f,
+            "Proof: {} bucket entries, {} siblings, {} thread roots",
+            self.proof.bucket.len(),
+            self.proof.siblings.len(),
+            self.proof.thread_roots.len()
+        )
+    }
+}
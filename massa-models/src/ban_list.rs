@@ -0,0 +1,199 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use crate::error::ModelsError;
+use massa_time::MassaTime;
+use serde::{Deserialize, Serialize};
+use serde_with::{DeserializeFromStr, SerializeDisplay};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+
+/// Ip address or subnet, written `ip/prefix_length` (ex: `192.168.0.0/16`), or `ip` for a single address
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, SerializeDisplay, DeserializeFromStr,
+)]
+pub struct IpSubnet {
+    /// first address of the subnet
+    ip: IpAddr,
+    /// number of leading bits shared by the addresses of the subnet
+    prefix_length: u8,
+}
+
+impl IpSubnet {
+    /// Subnet of the addresses sharing the `prefix_length` leading bits of `ip`.
+    /// The other bits of `ip` are ignored.
+    pub fn new(ip: IpAddr, prefix_length: u8) -> Result<Self, ModelsError> {
+        let ip = match ip {
+            IpAddr::V4(ip) if prefix_length <= 32 => {
+                let mask = u32::MAX.checked_shl(32 - prefix_length as u32).unwrap_or(0);
+                IpAddr::V4(Ipv4Addr::from(u32::from(ip) & mask))
+            }
+            IpAddr::V6(ip) if prefix_length <= 128 => {
+                let mask = u128::MAX
+                    .checked_shl(128 - prefix_length as u32)
+                    .unwrap_or(0);
+                IpAddr::V6(Ipv6Addr::from(u128::from(ip) & mask))
+            }
+            _ => {
+                return Err(ModelsError::IpSubnetParseError(format!(
+                    "prefix length {} too long for {}",
+                    prefix_length, ip
+                )))
+            }
+        };
+        Ok(IpSubnet { ip, prefix_length })
+    }
+
+    /// First address of the subnet
+    pub fn ip(&self) -> IpAddr {
+        self.ip
+    }
+
+    /// Number of leading bits shared by the addresses of the subnet
+    pub fn prefix_length(&self) -> u8 {
+        self.prefix_length
+    }
+
+    /// True if the subnet is a single address
+    pub fn is_single_ip(&self) -> bool {
+        match self.ip {
+            IpAddr::V4(_) => self.prefix_length == 32,
+            IpAddr::V6(_) => self.prefix_length == 128,
+        }
+    }
+
+    /// True if `ip` belongs to the subnet.
+    /// An IPv4 address never belongs to an IPv6 subnet, and conversely.
+    /// ```
+    /// # use massa_models::ban_list::IpSubnet;
+    /// # use std::net::IpAddr;
+    /// # use std::str::FromStr;
+    /// let subnet = IpSubnet::from_str("192.168.0.0/16").unwrap();
+    /// assert!(subnet.contains(&IpAddr::from_str("192.168.12.1").unwrap()));
+    /// assert!(!subnet.contains(&IpAddr::from_str("192.169.0.1").unwrap()));
+    /// assert!(!subnet.contains(&IpAddr::from_str("::ffff:192.168.12.1").unwrap()));
+    /// ```
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        match (self.ip, ip) {
+            (IpAddr::V4(_), IpAddr::V4(_)) | (IpAddr::V6(_), IpAddr::V6(_)) => {
+                IpSubnet::new(*ip, self.prefix_length).map_or(false, |subnet| subnet.ip == self.ip)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl From<IpAddr> for IpSubnet {
+    fn from(ip: IpAddr) -> Self {
+        IpSubnet {
+            ip,
+            prefix_length: match ip {
+                IpAddr::V4(_) => 32,
+                IpAddr::V6(_) => 128,
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for IpSubnet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_single_ip() {
+            write!(f, "{}", self.ip)
+        } else {
+            write!(f, "{}/{}", self.ip, self.prefix_length)
+        }
+    }
+}
+
+impl FromStr for IpSubnet {
+    type Err = ModelsError;
+
+    /// ```
+    /// # use massa_models::ban_list::IpSubnet;
+    /// # use std::str::FromStr;
+    /// assert_eq!(IpSubnet::from_str("10.1.2.3/8").unwrap().to_string(), "10.0.0.0/8");
+    /// assert_eq!(IpSubnet::from_str("2001:db8::1").unwrap().to_string(), "2001:db8::1");
+    /// assert!(IpSubnet::from_str("10.1.2.3/33").is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_ip = |ip: &str| {
+            IpAddr::from_str(ip)
+                .map_err(|e| ModelsError::IpSubnetParseError(format!("{}: {}", s, e)))
+        };
+        match s.split_once('/') {
+            Some((ip, prefix_length)) => {
+                let prefix_length = prefix_length
+                    .parse::<u8>()
+                    .map_err(|e| ModelsError::IpSubnetParseError(format!("{}: {}", s, e)))?;
+                IpSubnet::new(parse_ip(ip)?, prefix_length)
+            }
+            None => Ok(IpSubnet::from(parse_ip(s)?)),
+        }
+    }
+}
+
+/// Banned ip address or subnet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BanListEntry {
+    /// banned ip address or subnet
+    pub target: IpSubnet,
+    /// why the peers were banned, for the operators
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// time after which the ban is lifted, none if it lasts until it is lifted by hand
+    #[serde(default)]
+    pub expiry: Option<MassaTime>,
+    /// time of the ban, missing from the bans of previous versions
+    #[serde(default)]
+    pub banned_at: Option<MassaTime>,
+}
+
+impl BanListEntry {
+    /// True if the ban is lifted at `now`
+    pub fn is_expired(&self, now: MassaTime) -> bool {
+        self.expiry.map_or(false, |expiry| expiry <= now)
+    }
+
+    /// Merge `other`, a ban of the same target: the longest ban wins,
+    /// the latest time of ban is kept and the reason of `other` replaces ours if it has one.
+    pub fn merge(&mut self, other: BanListEntry) {
+        self.expiry = match (self.expiry, other.expiry) {
+            (Some(ours), Some(theirs)) => Some(std::cmp::max(ours, theirs)),
+            _ => None,
+        };
+        self.banned_at = std::cmp::max(self.banned_at, other.banned_at);
+        if other.reason.is_some() {
+            self.reason = other.reason;
+        }
+    }
+}
+
+/// Bans exported by a node and imported by others, so that operators can share them across their nodes.
+/// Serialized as JSON: `{"bans": [{"target": "192.168.0.0/16", "reason": "spam", "expiry": 1672531200000, "banned_at": 1671926400000}]}`,
+/// the expiry and the time of ban being in milliseconds since the Unix epoch.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BanList {
+    /// banned ip addresses and subnets
+    pub bans: Vec<BanListEntry>,
+}
+
+impl std::fmt::Display for BanList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.bans.is_empty() {
+            return writeln!(f, "No bans");
+        }
+        for ban in self.bans.iter() {
+            write!(f, "{}", ban.target)?;
+            if let Some(reason) = &ban.reason {
+                write!(f, " ({})", reason)?;
+            }
+            if let Some(banned_at) = ban.banned_at {
+                write!(f, ", banned on {}", banned_at.to_utc_string())?;
+            }
+            match ban.expiry {
+                Some(expiry) => writeln!(f, ", until {}", expiry.to_utc_string())?,
+                None => writeln!(f, ", until unbanned")?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,20 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Regenerate the golden file of the serialization test vectors.
+//!
+//! Only run it when the format of the network objects is changed on purpose:
+//! the nodes running the previous format will not accept the new objects.
+
+use massa_models::test_vectors::{generate, to_golden_file, GOLDEN_FILE};
+use std::path::Path;
+
+fn main() {
+    let vectors = generate().expect("could not generate the test vectors");
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(GOLDEN_FILE);
+    std::fs::write(&path, to_golden_file(&vectors)).expect("could not write the golden file");
+    println!(
+        "{} test vectors written to {}",
+        vectors.len(),
+        path.display()
+    );
+}
@@ -2,19 +2,26 @@
 
 use crate::endorsement::{EndorsementId, EndorsementSerializer, EndorsementSerializerLW};
 use crate::prehash::PreHashed;
-use crate::wrapped::{Id, Wrapped, WrappedContent, WrappedDeserializer, WrappedSerializer};
+use crate::serialization::{from_prefixed_bs58_check, to_prefixed_bs58_check};
+use crate::wrapped::{
+    skip_legacy_format_version, Id, Wrapped, WrappedContent, WrappedDeserializer, WrappedSerializer,
+};
 use crate::{
     endorsement::{Endorsement, EndorsementDeserializerLW, WrappedEndorsement},
     error::ModelsError,
-    operation::{OperationId, OperationIdsDeserializer, OperationIdsSerializer, WrappedOperation},
+    operation::{
+        compute_operations_hash, OperationId, OperationIdsDeserializer, OperationIdsSerializer,
+        OperationsDeserializer, OperationsSerializer, WrappedOperation,
+    },
     slot::{Slot, SlotDeserializer, SlotSerializer},
+    state_commitment::{StateCommitment, StateCommitmentDeserializer, StateCommitmentSerializer},
 };
 use massa_hash::{Hash, HashDeserializer};
 use massa_serialization::{
-    DeserializeError, Deserializer, SerializeError, Serializer, U32VarIntDeserializer,
-    U32VarIntSerializer, U64VarIntDeserializer, U64VarIntSerializer,
+    share_bytes, Deserializer, SerializeError, Serializer, U32VarIntDeserializer,
+    U32VarIntSerializer,
 };
-use massa_signature::{KeyPair, PublicKey, Signature};
+use massa_signature::{PublicKey, Signature, Signer};
 use nom::branch::alt;
 use nom::bytes::complete::tag;
 use nom::error::context;
@@ -58,18 +65,10 @@ const BLOCKID_VERSION: u64 = 0;
 
 impl std::fmt::Display for BlockId {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let u64_serializer = U64VarIntSerializer::new();
-        // might want to allocate the vector with capacity in order to avoid re-allocation
-        let mut bytes: Vec<u8> = Vec::new();
-        u64_serializer
-            .serialize(&BLOCKID_VERSION, &mut bytes)
-            .map_err(|_| std::fmt::Error)?;
-        bytes.extend(self.0.to_bytes());
         write!(
             f,
-            "{}{}",
-            BLOCKID_PREFIX,
-            bs58::encode(bytes).with_check().into_string()
+            "{}",
+            to_prefixed_bs58_check(BLOCKID_PREFIX, BLOCKID_VERSION, self.0.to_bytes())
         )
     }
 }
@@ -94,25 +93,9 @@ impl FromStr for BlockId {
     /// assert_eq!(block_id, res_block_id);
     /// ```
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut chars = s.chars();
-        match chars.next() {
-            Some(prefix) if prefix == BLOCKID_PREFIX => {
-                let data = chars.collect::<String>();
-                let decoded_bs58_check = bs58::decode(data)
-                    .with_check(None)
-                    .into_vec()
-                    .map_err(|_| ModelsError::BlockIdParseError)?;
-                let u64_deserializer = U64VarIntDeserializer::new(Included(0), Included(u64::MAX));
-                let (rest, _version) = u64_deserializer
-                    .deserialize::<DeserializeError>(&decoded_bs58_check[..])
-                    .map_err(|_| ModelsError::BlockIdParseError)?;
-                Ok(BlockId(Hash::from_bytes(
-                    rest.try_into()
-                        .map_err(|_| ModelsError::BlockIdParseError)?,
-                )))
-            }
-            _ => Err(ModelsError::BlockIdParseError),
-        }
+        let bytes = from_prefixed_bs58_check(s, BLOCKID_PREFIX, BLOCKID_VERSION)
+            .map_err(ModelsError::BlockIdParseError)?;
+        Ok(BlockId(Hash::from_bytes(&bytes)))
     }
 }
 
@@ -208,7 +191,7 @@ impl WrappedContent for Block {
     fn new_wrapped<SC: Serializer<Self>, U: Id>(
         content: Self,
         content_serializer: SC,
-        _keypair: &KeyPair,
+        _signer: &dyn Signer,
     ) -> Result<Wrapped<Self, U>, ModelsError> {
         let mut content_serialized = Vec::new();
         content_serializer.serialize(&content, &mut content_serialized)?;
@@ -218,7 +201,7 @@ impl WrappedContent for Block {
             creator_address: content.header.creator_address,
             id: U::new(*content.header.id.get_hash()),
             content,
-            serialized_data: content_serialized,
+            serialized_data: content_serialized.into(),
         })
     }
 
@@ -253,13 +236,30 @@ impl WrappedContent for Block {
                 creator_address: content.header.creator_address,
                 id: U::new(*content.header.id.get_hash()),
                 content,
-                serialized_data: buffer[..buffer.len() - rest.len()].to_vec(),
+                serialized_data: share_bytes(&buffer[..buffer.len() - rest.len()]),
             },
         ))
     }
 }
+/// Version of the binary format of `Block`, serialized first.
+///
+/// The id and signature of a block are those of its header, its own bytes are not hashed:
+/// any change to its format must come with a new version, so that the blocks serialized with the previous formats can still be deserialized.
+pub const BLOCK_FORMAT_VERSION: u32 = 0;
+
+/// Version of the binary format of `BlockHeader`, serialized first.
+///
+/// The block id is the hash of the serialized header: any change to its format must come with a new version,
+/// which is hashed into the ids of the headers serialized with it.
+/// The version 0 is left out of the hash, so that the ids of the headers serialized before the version prefix do not change.
+///
+/// Version 1 appends the state commitment of the producer after the endorsements.
+/// The headers without a state commitment are serialized with the version 0.
+pub const BLOCK_HEADER_FORMAT_VERSION: u32 = 1;
+
 /// Serializer for `Block`
 pub struct BlockSerializer {
+    version_serializer: U32VarIntSerializer,
     header_serializer: WrappedSerializer,
     op_ids_serializer: OperationIdsSerializer,
 }
@@ -268,6 +268,7 @@ impl BlockSerializer {
     /// Creates a new `BlockSerializer`
     pub fn new() -> Self {
         BlockSerializer {
+            version_serializer: U32VarIntSerializer::new(),
             header_serializer: WrappedSerializer::new(),
             op_ids_serializer: OperationIdsSerializer::new(),
         }
@@ -320,6 +321,7 @@ impl Serializer<Block> for BlockSerializer {
     ///             )
     ///             .unwrap(),
     ///         ],
+    ///         state_commitment: None,
     ///     },
     ///     BlockHeaderSerializer::new(),
     ///     &keypair,
@@ -336,6 +338,8 @@ impl Serializer<Block> for BlockSerializer {
     /// BlockSerializer::new().serialize(&orig_block, &mut buffer).unwrap();
     /// ```
     fn serialize(&self, value: &Block, buffer: &mut Vec<u8>) -> Result<(), SerializeError> {
+        self.version_serializer
+            .serialize(&BLOCK_FORMAT_VERSION, buffer)?;
         self.header_serializer.serialize(&value.header, buffer)?;
         self.op_ids_serializer
             .serialize(&value.operations, buffer)?;
@@ -345,6 +349,7 @@ impl Serializer<Block> for BlockSerializer {
 
 /// Deserializer for `Block`
 pub struct BlockDeserializer {
+    version_deserializer: U32VarIntDeserializer,
     header_deserializer: WrappedDeserializer<BlockHeader, BlockHeaderDeserializer>,
     op_ids_deserializer: OperationIdsDeserializer,
 }
@@ -353,6 +358,10 @@ impl BlockDeserializer {
     /// Creates a new `BlockDeserializer`
     pub fn new(thread_count: u8, max_operations_per_block: u32, endorsement_count: u32) -> Self {
         BlockDeserializer {
+            version_deserializer: U32VarIntDeserializer::new(
+                Included(0),
+                Included(BLOCK_FORMAT_VERSION),
+            ),
             header_deserializer: WrappedDeserializer::new(BlockHeaderDeserializer::new(
                 thread_count,
                 endorsement_count,
@@ -402,6 +411,7 @@ impl Deserializer<Block> for BlockDeserializer {
     ///             )
     ///             .unwrap(),
     ///         ],
+    ///         state_commitment: None,
     ///     },
     ///     BlockHeaderSerializer::new(),
     ///     &keypair,
@@ -440,14 +450,19 @@ impl Deserializer<Block> for BlockDeserializer {
     ) -> IResult<&'a [u8], Block, E> {
         context(
             "Failed Block deserialization",
-            tuple((
-                context("Failed header deserialization", |input| {
-                    self.header_deserializer.deserialize(input)
-                }),
-                context("Failed operations deserialization", |input| {
-                    self.op_ids_deserializer.deserialize(input)
+            preceded(
+                context("Failed version deserialization", |input| {
+                    self.version_deserializer.deserialize(input)
                 }),
-            )),
+                tuple((
+                    context("Failed header deserialization", |input| {
+                        self.header_deserializer.deserialize(input)
+                    }),
+                    context("Failed operations deserialization", |input| {
+                        self.op_ids_deserializer.deserialize(input)
+                    }),
+                )),
+            ),
         )
         .map(|(header, operations)| Block { header, operations })
         .parse(buffer)
@@ -498,6 +513,9 @@ pub struct BlockHeader {
     pub operation_merkle_root: Hash,
     /// endorsements
     pub endorsements: Vec<WrappedEndorsement>,
+    /// final state of the producer at one of its latest final slots, before the slot of the block
+    #[serde(default)]
+    pub state_commitment: Option<StateCommitment>,
 }
 
 // NOTE: TODO
@@ -522,24 +540,32 @@ impl WrappedHeader {
     }
 }
 
-impl WrappedContent for BlockHeader {}
+impl WrappedContent for BlockHeader {
+    fn get_hashed_content(serialized_content: &[u8]) -> &[u8] {
+        skip_legacy_format_version(serialized_content)
+    }
+}
 
 /// Serializer for `BlockHeader`
 pub struct BlockHeaderSerializer {
+    version_serializer: U32VarIntSerializer,
     slot_serializer: SlotSerializer,
     endorsement_serializer: WrappedSerializer,
     endorsement_content_serializer: EndorsementSerializerLW,
     u32_serializer: U32VarIntSerializer,
+    state_commitment_serializer: StateCommitmentSerializer,
 }
 
 impl BlockHeaderSerializer {
     /// Creates a new `BlockHeaderSerializer`
     pub fn new() -> Self {
         Self {
+            version_serializer: U32VarIntSerializer::new(),
             slot_serializer: SlotSerializer::new(),
             endorsement_serializer: WrappedSerializer::new(),
             u32_serializer: U32VarIntSerializer::new(),
             endorsement_content_serializer: EndorsementSerializerLW::new(),
+            state_commitment_serializer: StateCommitmentSerializer::new(),
         }
     }
 }
@@ -591,11 +617,18 @@ impl Serializer<BlockHeader> for BlockHeaderSerializer {
     ///     )
     ///     .unwrap(),
     ///    ],
+    ///   state_commitment: None,
     /// };
     /// let mut buffer = vec![];
     /// BlockHeaderSerializer::new().serialize(&header, &mut buffer).unwrap();
     /// ```
     fn serialize(&self, value: &BlockHeader, buffer: &mut Vec<u8>) -> Result<(), SerializeError> {
+        // the headers without a state commitment keep the format of the version 0, and their ids
+        let version = match value.state_commitment {
+            Some(_) => BLOCK_HEADER_FORMAT_VERSION,
+            None => 0,
+        };
+        self.version_serializer.serialize(&version, buffer)?;
         self.slot_serializer.serialize(&value.slot, buffer)?;
         // parents (note: there should be none if slot period=0)
         if value.parents.is_empty() {
@@ -623,16 +656,23 @@ impl Serializer<BlockHeader> for BlockHeaderSerializer {
                 buffer,
             )?;
         }
+
+        if let Some(state_commitment) = &value.state_commitment {
+            self.state_commitment_serializer
+                .serialize(state_commitment, buffer)?;
+        }
         Ok(())
     }
 }
 
 /// Deserializer for `BlockHeader`
 pub struct BlockHeaderDeserializer {
+    version_deserializer: U32VarIntDeserializer,
     slot_deserializer: SlotDeserializer,
     endorsement_serializer: EndorsementSerializer,
     length_endorsements_deserializer: U32VarIntDeserializer,
     hash_deserializer: HashDeserializer,
+    state_commitment_deserializer: StateCommitmentDeserializer,
     thread_count: u8,
     endorsement_count: u32,
 }
@@ -641,6 +681,10 @@ impl BlockHeaderDeserializer {
     /// Creates a new `BlockHeaderDeserializerLW`
     pub const fn new(thread_count: u8, endorsement_count: u32) -> Self {
         Self {
+            version_deserializer: U32VarIntDeserializer::new(
+                Included(0),
+                Included(BLOCK_HEADER_FORMAT_VERSION),
+            ),
             slot_deserializer: SlotDeserializer::new(
                 (Included(0), Included(u64::MAX)),
                 (Included(0), Excluded(thread_count)),
@@ -651,6 +695,7 @@ impl BlockHeaderDeserializer {
                 Included(endorsement_count),
             ),
             hash_deserializer: HashDeserializer::new(),
+            state_commitment_deserializer: StateCommitmentDeserializer::new(thread_count),
             thread_count,
             endorsement_count,
         }
@@ -697,6 +742,7 @@ impl Deserializer<BlockHeader> for BlockHeaderDeserializer {
     ///     )
     ///     .unwrap(),
     ///    ],
+    ///   state_commitment: None,
     /// };
     /// let mut buffer = vec![];
     /// BlockHeaderSerializer::new().serialize(&header, &mut buffer).unwrap();
@@ -710,67 +756,89 @@ impl Deserializer<BlockHeader> for BlockHeaderDeserializer {
         &self,
         buffer: &'a [u8],
     ) -> IResult<&'a [u8], BlockHeader, E> {
-        let (rest, (slot, parents, operation_merkle_root)): (&[u8], (Slot, Vec<BlockId>, Hash)) =
+        let (rest, (version, slot, parents, operation_merkle_root)): (
+            &[u8],
+            (u32, Slot, Vec<BlockId>, Hash),
+        ) = context(
+            "Failed BlockHeader deserialization",
+            tuple((
+                context("Failed version deserialization", |input| {
+                    self.version_deserializer.deserialize(input)
+                }),
+                context("Failed slot deserialization", |input| {
+                    self.slot_deserializer.deserialize(input)
+                }),
+                context(
+                    "Failed parents deserialization",
+                    alt((
+                        preceded(tag(&[0]), |input| Ok((input, Vec::new()))),
+                        preceded(
+                            tag(&[1]),
+                            count(
+                                context("Failed block_id deserialization", |input| {
+                                    self.hash_deserializer
+                                        .deserialize(input)
+                                        .map(|(rest, hash)| (rest, BlockId(hash)))
+                                }),
+                                self.thread_count as usize,
+                            ),
+                        ),
+                    )),
+                ),
+                context("Failed operation_merkle_root", |input| {
+                    self.hash_deserializer.deserialize(input)
+                }),
+            )),
+        )
+        .parse(buffer)?;
+
+        let (rest, endorsements) = if parents.is_empty() {
+            // genesis blocks have no parents to endorse: only an empty endorsement list is canonical
+            let (rest, _) =
+                context("Failed endorsements deserialization", tag(&[0])).parse(rest)?;
+            (rest, Vec::new())
+        } else {
+            // Now deser the endorsements (which were: lw serialized)
+            let endorsement_deserializer =
+                WrappedDeserializer::new(EndorsementDeserializerLW::new(
+                    self.endorsement_count,
+                    slot,
+                    parents[slot.thread as usize],
+                ));
+
             context(
-                "Failed BlockHeader deserialization",
-                tuple((
-                    context("Failed slot deserialization", |input| {
-                        self.slot_deserializer.deserialize(input)
+                "Failed endorsements deserialization",
+                length_count::<&[u8], Wrapped<Endorsement, EndorsementId>, u32, E, _, _>(
+                    context("Failed length deserialization", |input| {
+                        self.length_endorsements_deserializer.deserialize(input)
                     }),
-                    context(
-                        "Failed parents deserialization",
-                        alt((
-                            preceded(tag(&[0]), |input| Ok((input, Vec::new()))),
-                            preceded(
-                                tag(&[1]),
-                                count(
-                                    context("Failed block_id deserialization", |input| {
-                                        self.hash_deserializer
-                                            .deserialize(input)
-                                            .map(|(rest, hash)| (rest, BlockId(hash)))
-                                    }),
-                                    self.thread_count as usize,
-                                ),
-                            ),
-                        )),
-                    ),
-                    context("Failed operation_merkle_root", |input| {
-                        self.hash_deserializer.deserialize(input)
+                    context("Failed endorsement deserialization", |input| {
+                        endorsement_deserializer
+                            .deserialize_with(&self.endorsement_serializer, input)
                     }),
-                )),
+                ),
             )
-            .parse(buffer)?;
+            .parse(rest)?
+        };
 
-        if parents.is_empty() {
-            return Ok((
-                &rest[1..], // Because there is 0 endorsements, we have a remaining 0 in rest and we don't need it
-                BlockHeader {
-                    slot,
-                    parents,
-                    operation_merkle_root,
-                    endorsements: Vec::new(),
-                },
-            ));
-        }
-        // Now deser the endorsements (which were: lw serialized)
-        let endorsement_deserializer = WrappedDeserializer::new(EndorsementDeserializerLW::new(
-            self.endorsement_count,
-            slot,
-            parents[slot.thread as usize],
-        ));
-
-        let (rest, endorsements) = context(
-            "Failed endorsements deserialization",
-            length_count::<&[u8], Wrapped<Endorsement, EndorsementId>, u32, E, _, _>(
-                context("Failed length deserialization", |input| {
-                    self.length_endorsements_deserializer.deserialize(input)
-                }),
-                context("Failed endorsement deserialization", |input| {
-                    endorsement_deserializer.deserialize_with(&self.endorsement_serializer, input)
-                }),
-            ),
-        )
-        .parse(rest)?;
+        // the version 0 has no state commitment, it is always present from the version 1
+        let (rest, state_commitment) = if version == 0 {
+            (rest, None)
+        } else {
+            let (rest_after, state_commitment) =
+                context("Failed state_commitment deserialization", |input| {
+                    self.state_commitment_deserializer.deserialize(input)
+                })
+                .parse(rest)?;
+            // a producer can only commit to a state before the slot of its block
+            if state_commitment.slot >= slot {
+                return Err(nom::Err::Error(ParseError::from_error_kind(
+                    rest,
+                    nom::error::ErrorKind::Verify,
+                )));
+            }
+            (rest_after, Some(state_commitment))
+        };
 
         Ok((
             rest,
@@ -779,11 +847,142 @@ impl Deserializer<BlockHeader> for BlockHeaderDeserializer {
                 parents,
                 operation_merkle_root,
                 endorsements,
+                state_commitment,
             },
         ))
     }
 }
 
+/// Final block kept by an archival node: its header and its operations, in the order of the block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedBlock {
+    /// signed header
+    pub header: WrappedHeader,
+    /// operations
+    pub operations: Vec<WrappedOperation>,
+}
+
+impl ArchivedBlock {
+    /// Returns true if the operations are the ones committed to by the operation merkle root of the header
+    pub fn check_operations(&self) -> bool {
+        self.header.content.operation_merkle_root
+            == compute_operations_hash(self.operations.iter().map(|operation| &operation.id))
+    }
+
+    /// Re-constitute the wrapped block and return it with its operations
+    pub fn into_block(self) -> Result<(WrappedBlock, Vec<WrappedOperation>), ModelsError> {
+        let block = Block {
+            header: self.header,
+            operations: self
+                .operations
+                .iter()
+                .map(|operation| operation.id)
+                .collect(),
+        };
+        let mut serialized_data = Vec::new();
+        BlockSerializer::new().serialize(&block, &mut serialized_data)?;
+        let wrapped_block = Wrapped {
+            signature: block.header.signature,
+            creator_public_key: block.header.creator_public_key,
+            creator_address: block.header.creator_address,
+            id: block.header.id,
+            content: block,
+            serialized_data: serialized_data.into(),
+        };
+        Ok((wrapped_block, self.operations))
+    }
+}
+
+/// Serializer for `ArchivedBlock`
+pub struct ArchivedBlockSerializer {
+    header_serializer: WrappedSerializer,
+    operations_serializer: OperationsSerializer,
+}
+
+impl ArchivedBlockSerializer {
+    /// Creates a new `ArchivedBlockSerializer`
+    pub fn new() -> Self {
+        ArchivedBlockSerializer {
+            header_serializer: WrappedSerializer::new(),
+            operations_serializer: OperationsSerializer::new(),
+        }
+    }
+}
+
+impl Default for ArchivedBlockSerializer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Serializer<ArchivedBlock> for ArchivedBlockSerializer {
+    fn serialize(&self, value: &ArchivedBlock, buffer: &mut Vec<u8>) -> Result<(), SerializeError> {
+        self.header_serializer.serialize(&value.header, buffer)?;
+        self.operations_serializer
+            .serialize(&value.operations, buffer)?;
+        Ok(())
+    }
+}
+
+/// Deserializer for `ArchivedBlock`
+pub struct ArchivedBlockDeserializer {
+    header_deserializer: WrappedDeserializer<BlockHeader, BlockHeaderDeserializer>,
+    operations_deserializer: OperationsDeserializer,
+}
+
+impl ArchivedBlockDeserializer {
+    /// Creates a new `ArchivedBlockDeserializer`
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        thread_count: u8,
+        endorsement_count: u32,
+        max_operations_per_block: u32,
+        max_datastore_value_length: u64,
+        max_function_name_length: u16,
+        max_parameters_size: u32,
+        max_op_datastore_entry_count: u64,
+        max_op_datastore_key_length: u8,
+        max_op_datastore_value_length: u64,
+    ) -> Self {
+        ArchivedBlockDeserializer {
+            header_deserializer: WrappedDeserializer::new(BlockHeaderDeserializer::new(
+                thread_count,
+                endorsement_count,
+            )),
+            operations_deserializer: OperationsDeserializer::new(
+                max_operations_per_block,
+                max_datastore_value_length,
+                max_function_name_length,
+                max_parameters_size,
+                max_op_datastore_entry_count,
+                max_op_datastore_key_length,
+                max_op_datastore_value_length,
+            ),
+        }
+    }
+}
+
+impl Deserializer<ArchivedBlock> for ArchivedBlockDeserializer {
+    fn deserialize<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+        &self,
+        buffer: &'a [u8],
+    ) -> IResult<&'a [u8], ArchivedBlock, E> {
+        context(
+            "Failed ArchivedBlock deserialization",
+            tuple((
+                context("Failed header deserialization", |input| {
+                    self.header_deserializer.deserialize(input)
+                }),
+                context("Failed operations deserialization", |input| {
+                    self.operations_deserializer.deserialize(input)
+                }),
+            )),
+        )
+        .map(|(header, operations)| ArchivedBlock { header, operations })
+        .parse(buffer)
+    }
+}
+
 impl std::fmt::Display for BlockHeader {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         writeln!(
@@ -813,6 +1012,13 @@ impl std::fmt::Display for BlockHeader {
         if self.endorsements.is_empty() {
             writeln!(f, "\tNo endorsements found")?;
         }
+        if let Some(state_commitment) = &self.state_commitment {
+            writeln!(
+                f,
+                "\tState commitment: final state hash {} at slot {}",
+                state_commitment.final_state_hash, state_commitment.slot
+            )?;
+        }
         Ok(())
     }
 }
@@ -864,6 +1070,7 @@ mod test {
                 parents,
                 operation_merkle_root: Hash::compute_from("mno".as_bytes()),
                 endorsements: vec![endo],
+                state_commitment: None,
             },
             BlockHeaderSerializer::new(),
             &keypair,
@@ -937,6 +1144,7 @@ mod test {
                 parents,
                 operation_merkle_root: Hash::compute_from("mno".as_bytes()),
                 endorsements: vec![],
+                state_commitment: None,
             },
             BlockHeaderSerializer::new(),
             &keypair,
@@ -1015,6 +1223,7 @@ mod test {
                     &keypair,
                 )
                 .unwrap()],
+                state_commitment: None,
             },
             BlockHeaderSerializer::new(),
             &keypair,
@@ -1043,4 +1252,204 @@ mod test {
 
         assert!(res.is_err());
     }
+
+    #[test]
+    #[serial]
+    fn test_block_header_format_versioning() {
+        let keypair = KeyPair::generate();
+        let header = BlockHeader {
+            slot: Slot::new(1, 0),
+            parents: (0..THREAD_COUNT)
+                .map(|i| BlockId(Hash::compute_from(&[i])))
+                .collect(),
+            operation_merkle_root: Hash::compute_from("mno".as_bytes()),
+            endorsements: vec![Endorsement::new_wrapped(
+                Endorsement {
+                    slot: Slot::new(1, 0),
+                    index: 1,
+                    endorsed_block: BlockId(Hash::compute_from(&[0])),
+                },
+                EndorsementSerializer::new(),
+                &keypair,
+            )
+            .unwrap()],
+            state_commitment: Some(StateCommitment {
+                slot: Slot::new(0, 1),
+                final_state_hash: Hash::compute_from("final state".as_bytes()),
+                ledger_hash: Hash::compute_from("ledger".as_bytes()),
+                async_pool_hash: Hash::compute_from("async pool".as_bytes()),
+                deferred_credits_hash: Hash::compute_from("deferred credits".as_bytes()),
+                executed_ops_hash: Hash::compute_from("executed ops".as_bytes()),
+            }),
+        };
+        let deserializer = BlockHeaderDeserializer::new(THREAD_COUNT, ENDORSEMENT_COUNT);
+        let mut ser_header = Vec::new();
+        BlockHeaderSerializer::new()
+            .serialize(&header, &mut ser_header)
+            .unwrap();
+        assert_eq!(ser_header[0], BLOCK_HEADER_FORMAT_VERSION as u8);
+
+        // the serialization is canonical: serializing again what was deserialized gives the same bytes
+        let (rest, res_header) = deserializer
+            .deserialize::<DeserializeError>(&ser_header)
+            .unwrap();
+        assert!(rest.is_empty());
+        let mut ser_again = Vec::new();
+        BlockHeaderSerializer::new()
+            .serialize(&res_header, &mut ser_again)
+            .unwrap();
+        assert_eq!(ser_again, ser_header);
+
+        // unknown format versions are refused
+        let mut ser_future = ser_header.clone();
+        ser_future[0] = BLOCK_HEADER_FORMAT_VERSION as u8 + 1;
+        assert!(deserializer
+            .deserialize::<DeserializeError>(&ser_future)
+            .is_err());
+
+        // the headers without a state commitment keep the version 0 format
+        let mut legacy_header = header.clone();
+        legacy_header.state_commitment = None;
+        let mut ser_legacy = Vec::new();
+        BlockHeaderSerializer::new()
+            .serialize(&legacy_header, &mut ser_legacy)
+            .unwrap();
+        assert_eq!(ser_legacy[0], 0);
+        let (rest, res_legacy) = deserializer
+            .deserialize::<DeserializeError>(&ser_legacy)
+            .unwrap();
+        assert!(rest.is_empty());
+        assert!(res_legacy.state_commitment.is_none());
+
+        // the version 0 is not hashed, unlike the later versions
+        let public_key = keypair.get_public_key().to_bytes();
+        let wrapped_legacy =
+            BlockHeader::new_wrapped(legacy_header, BlockHeaderSerializer::new(), &keypair)
+                .unwrap();
+        assert_eq!(
+            *wrapped_legacy.id.get_hash(),
+            Hash::compute_from_tuple(&[&public_key[..], &ser_legacy[1..]])
+        );
+        let wrapped_header =
+            BlockHeader::new_wrapped(header.clone(), BlockHeaderSerializer::new(), &keypair)
+                .unwrap();
+        assert_eq!(
+            *wrapped_header.id.get_hash(),
+            Hash::compute_from_tuple(&[&public_key[..], &ser_header[..]])
+        );
+
+        // a commitment must be to a state before the slot of the block
+        let mut ser_late = Vec::new();
+        let mut late_header = header.clone();
+        late_header.state_commitment.as_mut().unwrap().slot = header.slot;
+        BlockHeaderSerializer::new()
+            .serialize(&late_header, &mut ser_late)
+            .unwrap();
+        assert!(deserializer
+            .deserialize::<DeserializeError>(&ser_late)
+            .is_err());
+
+        // same for the blocks
+        let block = Block {
+            header: BlockHeader::new_wrapped(header, BlockHeaderSerializer::new(), &keypair)
+                .unwrap(),
+            operations: Vec::new(),
+        };
+        let mut ser_block = Vec::new();
+        BlockSerializer::new()
+            .serialize(&block, &mut ser_block)
+            .unwrap();
+        assert_eq!(ser_block[0], BLOCK_FORMAT_VERSION as u8);
+        let block_deserializer =
+            BlockDeserializer::new(THREAD_COUNT, MAX_OPERATIONS_PER_BLOCK, ENDORSEMENT_COUNT);
+        let (rest, res_block) = block_deserializer
+            .deserialize::<DeserializeError>(&ser_block)
+            .unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(res_block.header.id, block.header.id);
+        ser_block[0] = BLOCK_FORMAT_VERSION as u8 + 1;
+        assert!(block_deserializer
+            .deserialize::<DeserializeError>(&ser_block)
+            .is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn test_archived_block_serialization() {
+        use crate::{
+            address::Address,
+            amount::Amount,
+            config::{
+                MAX_DATASTORE_VALUE_LENGTH, MAX_FUNCTION_NAME_LENGTH,
+                MAX_OPERATION_DATASTORE_ENTRY_COUNT, MAX_OPERATION_DATASTORE_KEY_LENGTH,
+                MAX_OPERATION_DATASTORE_VALUE_LENGTH, MAX_PARAMETERS_SIZE,
+            },
+            operation::{Operation, OperationSerializer, OperationType},
+        };
+
+        let keypair = KeyPair::generate();
+        let operation = Operation::new_wrapped(
+            Operation {
+                fee: Amount::from_str("1").unwrap(),
+                expire_period: 10,
+                op: OperationType::Transaction {
+                    recipient_address: Address::from_public_key(&keypair.get_public_key()),
+                    amount: Amount::from_str("300").unwrap(),
+                },
+            },
+            OperationSerializer::new(),
+            &keypair,
+        )
+        .unwrap();
+        let header = BlockHeader::new_wrapped(
+            BlockHeader {
+                slot: Slot::new(1, 0),
+                parents: (0..THREAD_COUNT)
+                    .map(|i| BlockId(Hash::compute_from(&[i])))
+                    .collect(),
+                operation_merkle_root: Hash::compute_from(operation.id.get_hash().to_bytes()),
+                endorsements: Vec::new(),
+                state_commitment: None,
+            },
+            BlockHeaderSerializer::new(),
+            &keypair,
+        )
+        .unwrap();
+        let archived_block = ArchivedBlock {
+            header,
+            operations: vec![operation.clone()],
+        };
+        assert!(archived_block.check_operations());
+
+        let mut buffer = Vec::new();
+        ArchivedBlockSerializer::new()
+            .serialize(&archived_block, &mut buffer)
+            .unwrap();
+        let (rest, res_block) = ArchivedBlockDeserializer::new(
+            THREAD_COUNT,
+            ENDORSEMENT_COUNT,
+            MAX_OPERATIONS_PER_BLOCK,
+            MAX_DATASTORE_VALUE_LENGTH,
+            MAX_FUNCTION_NAME_LENGTH,
+            MAX_PARAMETERS_SIZE,
+            MAX_OPERATION_DATASTORE_ENTRY_COUNT,
+            MAX_OPERATION_DATASTORE_KEY_LENGTH,
+            MAX_OPERATION_DATASTORE_VALUE_LENGTH,
+        )
+        .deserialize::<DeserializeError>(&buffer)
+        .unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(res_block.header.id, archived_block.header.id);
+        assert_eq!(res_block.operations[0].id, operation.id);
+
+        let (wrapped_block, operations) = res_block.into_block().unwrap();
+        assert_eq!(wrapped_block.id, archived_block.header.id);
+        assert_eq!(wrapped_block.content.operations, vec![operation.id]);
+        assert_eq!(operations.len(), 1);
+
+        // operations that do not match the merkle root of the header
+        let mut tampered = archived_block;
+        tampered.operations.push(operation);
+        assert!(!tampered.check_operations());
+    }
 }
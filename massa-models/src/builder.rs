@@ -0,0 +1,476 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Builders of signed blocks and operations.
+//!
+//! They check the fields against the limits the other nodes enforce before hashing and signing,
+//! so that a block or an operation they build is not rejected for its format.
+
+use crate::{
+    amount::Amount,
+    block::{
+        Block, BlockHeader, BlockHeaderSerializer, BlockId, BlockSerializer, WrappedBlock,
+        WrappedHeader,
+    },
+    config::{
+        ENDORSEMENT_COUNT, MAX_BLOCK_SIZE, MAX_DATASTORE_VALUE_LENGTH, MAX_FUNCTION_NAME_LENGTH,
+        MAX_OPERATIONS_PER_BLOCK, MAX_OPERATION_DATASTORE_ENTRY_COUNT,
+        MAX_OPERATION_DATASTORE_KEY_LENGTH, MAX_OPERATION_DATASTORE_VALUE_LENGTH,
+        MAX_PARAMETERS_SIZE, THREAD_COUNT,
+    },
+    endorsement::WrappedEndorsement,
+    error::ModelsError,
+    operation::{
+        compute_operations_hash, Operation, OperationDeserializer, OperationId,
+        OperationSerializer, OperationType, WrappedOperation,
+    },
+    prehash::PreHashSet,
+    slot::Slot,
+    state_commitment::StateCommitment,
+    wrapped::WrappedContent,
+};
+use massa_serialization::{DeserializeError, Deserializer, Serializer};
+use massa_signature::Signer;
+use std::collections::HashSet;
+
+/// Limits checked by the `BlockBuilder`, the ones of the network by default
+#[derive(Debug, Clone, Copy)]
+pub struct BlockLimits {
+    /// number of threads
+    pub thread_count: u8,
+    /// max number of endorsements in a block
+    pub endorsement_count: u32,
+    /// max number of operations in a block
+    pub max_operations_per_block: u32,
+    /// max total serialized size of the operations of a block, in bytes
+    pub max_block_size: u64,
+}
+
+impl Default for BlockLimits {
+    fn default() -> Self {
+        BlockLimits {
+            thread_count: THREAD_COUNT,
+            endorsement_count: ENDORSEMENT_COUNT,
+            max_operations_per_block: MAX_OPERATIONS_PER_BLOCK,
+            max_block_size: MAX_BLOCK_SIZE as u64,
+        }
+    }
+}
+
+/// Builder of a signed block.
+///
+/// ## Example
+/// ```rust
+/// # use massa_hash::Hash;
+/// # use massa_models::{block::BlockId, builder::{BlockBuilder, BlockLimits}, slot::Slot};
+/// # use massa_signature::KeyPair;
+/// let keypair = KeyPair::generate();
+/// let parents: Vec<BlockId> = (0..2).map(|i| BlockId(Hash::compute_from(&[i]))).collect();
+/// let limits = BlockLimits {
+///     thread_count: 2,
+///     ..Default::default()
+/// };
+/// let block = BlockBuilder::new(Slot::new(1, 0), limits)
+///     .parents(parents.clone())
+///     .build(&keypair)
+///     .unwrap();
+/// assert_eq!(block.content.header.content.parents, parents);
+/// assert_eq!(block.id, block.content.header.id);
+/// ```
+pub struct BlockBuilder {
+    limits: BlockLimits,
+    slot: Slot,
+    parents: Vec<BlockId>,
+    endorsements: Vec<WrappedEndorsement>,
+    state_commitment: Option<StateCommitment>,
+    operations: Vec<OperationId>,
+    /// ids of `operations`, to refuse duplicates
+    operation_set: PreHashSet<OperationId>,
+    /// total serialized size of `operations`
+    operations_size: u64,
+}
+
+impl BlockBuilder {
+    /// Builder of a block at `slot`, without parents, endorsements nor operations
+    pub fn new(slot: Slot, limits: BlockLimits) -> Self {
+        BlockBuilder {
+            limits,
+            slot,
+            parents: Vec::new(),
+            endorsements: Vec::new(),
+            state_commitment: None,
+            operations: Vec::new(),
+            operation_set: PreHashSet::default(),
+            operations_size: 0,
+        }
+    }
+
+    /// Set the parents, one per thread
+    pub fn parents(mut self, parents: Vec<BlockId>) -> Self {
+        self.parents = parents;
+        self
+    }
+
+    /// Set the endorsements of the parent in the thread of the block
+    pub fn endorsements(mut self, endorsements: Vec<WrappedEndorsement>) -> Self {
+        self.endorsements = endorsements;
+        self
+    }
+
+    /// Set the final state committed by the producer, at a slot before the one of the block
+    pub fn state_commitment(mut self, state_commitment: Option<StateCommitment>) -> Self {
+        self.state_commitment = state_commitment;
+        self
+    }
+
+    /// Add an operation after the ones already added.
+    ///
+    /// The operation is refused, leaving the block unchanged, if it is already in the block
+    /// or if the block would then exceed the max number of operations or the max block size.
+    pub fn add_operation(&mut self, operation: &WrappedOperation) -> Result<(), ModelsError> {
+        if self.operation_set.contains(&operation.id) {
+            return Err(ModelsError::InvalidBlock(format!(
+                "operation {} is already in the block",
+                operation.id
+            )));
+        }
+        if self.operations.len() >= self.limits.max_operations_per_block as usize {
+            return Err(ModelsError::InvalidBlock(format!(
+                "the block already has {} operations",
+                self.operations.len()
+            )));
+        }
+        let size = operation.serialized_size() as u64;
+        if size > self.remaining_size() {
+            return Err(ModelsError::InvalidBlock(format!(
+                "operation {} of {} bytes does not fit in the {} remaining bytes",
+                operation.id,
+                size,
+                self.remaining_size()
+            )));
+        }
+        self.operations.push(operation.id);
+        self.operation_set.insert(operation.id);
+        self.operations_size += size;
+        Ok(())
+    }
+
+    /// Number of bytes left for the operations in the block
+    pub fn remaining_size(&self) -> u64 {
+        self.limits
+            .max_block_size
+            .saturating_sub(self.operations_size)
+    }
+
+    /// Check the fields, then hash and sign the header and the block with `signer`
+    pub fn build(self, signer: &dyn Signer) -> Result<WrappedBlock, ModelsError> {
+        self.check()?;
+        let header: WrappedHeader = BlockHeader::new_wrapped(
+            BlockHeader {
+                slot: self.slot,
+                parents: self.parents,
+                operation_merkle_root: compute_operations_hash(&self.operations),
+                endorsements: self.endorsements,
+                state_commitment: self.state_commitment,
+            },
+            BlockHeaderSerializer::new(),
+            signer,
+        )?;
+        Block::new_wrapped(
+            Block {
+                header,
+                operations: self.operations,
+            },
+            BlockSerializer::new(),
+            signer,
+        )
+    }
+
+    /// Check the slot, the parents, the endorsements and the state commitment
+    fn check(&self) -> Result<(), ModelsError> {
+        let thread_count = self.limits.thread_count;
+        if self.slot.thread >= thread_count {
+            return Err(ModelsError::InvalidBlock(format!(
+                "thread {} out of the {} threads",
+                self.slot.thread, thread_count
+            )));
+        }
+        // only the genesis blocks have no parents
+        if !(self.slot.period == 0 && self.parents.is_empty())
+            && self.parents.len() != thread_count as usize
+        {
+            return Err(ModelsError::InvalidBlock(format!(
+                "{} parents instead of one per thread",
+                self.parents.len()
+            )));
+        }
+        if self.endorsements.len() > self.limits.endorsement_count as usize {
+            return Err(ModelsError::InvalidBlock(format!(
+                "{} endorsements while at most {} are allowed",
+                self.endorsements.len(),
+                self.limits.endorsement_count
+            )));
+        }
+        let mut endorsement_indices = HashSet::new();
+        for endorsement in &self.endorsements {
+            if endorsement.content.slot != self.slot {
+                return Err(ModelsError::InvalidBlock(format!(
+                    "endorsement {} is for slot {} instead of {}",
+                    endorsement.id, endorsement.content.slot, self.slot
+                )));
+            }
+            if self.parents.get(self.slot.thread as usize)
+                != Some(&endorsement.content.endorsed_block)
+            {
+                return Err(ModelsError::InvalidBlock(format!(
+                    "endorsement {} does not endorse the parent in the thread of the block",
+                    endorsement.id
+                )));
+            }
+            if endorsement.content.index >= self.limits.endorsement_count
+                || !endorsement_indices.insert(endorsement.content.index)
+            {
+                return Err(ModelsError::InvalidBlock(format!(
+                    "endorsement {} has an invalid or duplicate index {}",
+                    endorsement.id, endorsement.content.index
+                )));
+            }
+        }
+        if let Some(state_commitment) = &self.state_commitment {
+            if state_commitment.slot >= self.slot {
+                return Err(ModelsError::InvalidBlock(format!(
+                    "the state committed at slot {} is not before the slot of the block",
+                    state_commitment.slot
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Builder of a signed operation.
+///
+/// ## Example
+/// ```rust
+/// # use massa_models::{amount::Amount, builder::OperationBuilder, operation::OperationType};
+/// # use massa_signature::KeyPair;
+/// # use std::str::FromStr;
+/// let keypair = KeyPair::generate();
+/// let operation = OperationBuilder::new(OperationType::RollBuy { roll_count: 1 })
+///     .fee(Amount::from_str("0.01").unwrap())
+///     .expire_period(10)
+///     .build(&keypair)
+///     .unwrap();
+/// assert_eq!(operation.content.expire_period, 10);
+/// ```
+pub struct OperationBuilder {
+    op: OperationType,
+    fee: Amount,
+    expire_period: Option<u64>,
+}
+
+impl OperationBuilder {
+    /// Builder of an operation of type `op`, without fee
+    pub fn new(op: OperationType) -> Self {
+        OperationBuilder {
+            op,
+            fee: Amount::default(),
+            expire_period: None,
+        }
+    }
+
+    /// Set the fee paid to the block producer
+    pub fn fee(mut self, fee: Amount) -> Self {
+        self.fee = fee;
+        self
+    }
+
+    /// Set the last period at which the operation can be included in a block, required
+    pub fn expire_period(mut self, expire_period: u64) -> Self {
+        self.expire_period = Some(expire_period);
+        self
+    }
+
+    /// Check the fields against the limits of the network, then hash and sign the operation with `signer`
+    pub fn build(self, signer: &dyn Signer) -> Result<WrappedOperation, ModelsError> {
+        let expire_period = self
+            .expire_period
+            .ok_or_else(|| ModelsError::InvalidOperation("no expire period".to_string()))?;
+        if let OperationType::RollBuy { roll_count: 0 }
+        | OperationType::RollSell { roll_count: 0 } = self.op
+        {
+            return Err(ModelsError::InvalidOperation(
+                "no roll bought or sold".to_string(),
+            ));
+        }
+        let operation = Operation {
+            fee: self.fee,
+            expire_period,
+            op: self.op,
+        };
+
+        // the other nodes only accept the operations that they can deserialize with their limits
+        let mut serialized = Vec::new();
+        OperationSerializer::new().serialize(&operation, &mut serialized)?;
+        let deserializer = OperationDeserializer::new(
+            MAX_DATASTORE_VALUE_LENGTH,
+            MAX_FUNCTION_NAME_LENGTH,
+            MAX_PARAMETERS_SIZE,
+            MAX_OPERATION_DATASTORE_ENTRY_COUNT,
+            MAX_OPERATION_DATASTORE_KEY_LENGTH,
+            MAX_OPERATION_DATASTORE_VALUE_LENGTH,
+        );
+        match deserializer.deserialize::<DeserializeError>(&serialized) {
+            Ok((rest, _)) if rest.is_empty() => {}
+            Ok(_) => {
+                return Err(ModelsError::InvalidOperation(
+                    "trailing bytes after the serialized operation".to_string(),
+                ))
+            }
+            Err(err) => {
+                return Err(ModelsError::InvalidOperation(format!(
+                    "a field exceeds the limits of the network: {}",
+                    err
+                )))
+            }
+        }
+
+        let wrapped = Operation::new_wrapped(operation, OperationSerializer::new(), signer)?;
+        if wrapped.serialized_size() as u64 > MAX_BLOCK_SIZE as u64 {
+            return Err(ModelsError::InvalidOperation(format!(
+                "{} bytes do not fit in a block",
+                wrapped.serialized_size()
+            )));
+        }
+        Ok(wrapped)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        address::Address,
+        endorsement::{Endorsement, EndorsementSerializer},
+    };
+    use massa_hash::Hash;
+    use massa_signature::KeyPair;
+
+    fn limits() -> BlockLimits {
+        BlockLimits {
+            thread_count: 2,
+            ..Default::default()
+        }
+    }
+
+    fn parents() -> Vec<BlockId> {
+        (0..2u8)
+            .map(|i| BlockId(Hash::compute_from(&[i])))
+            .collect()
+    }
+
+    fn transaction(keypair: &KeyPair) -> WrappedOperation {
+        OperationBuilder::new(OperationType::Transaction {
+            recipient_address: Address::from_public_key(&KeyPair::generate().get_public_key()),
+            amount: Amount::default(),
+        })
+        .expire_period(10)
+        .build(keypair)
+        .unwrap()
+    }
+
+    #[test]
+    fn test_block_builder_operations() {
+        let keypair = KeyPair::generate();
+        let operation = transaction(&keypair);
+        let mut builder = BlockBuilder::new(
+            Slot::new(1, 0),
+            BlockLimits {
+                max_block_size: 2 * operation.serialized_size() as u64 - 1,
+                ..limits()
+            },
+        )
+        .parents(parents());
+        builder.add_operation(&operation).unwrap();
+        assert!(builder.add_operation(&operation).is_err());
+        // a second operation does not fit in the block
+        assert!(builder.add_operation(&transaction(&keypair)).is_err());
+        let block = builder.build(&keypair).unwrap();
+        assert_eq!(block.content.operations, vec![operation.id]);
+        assert_eq!(
+            block.content.header.content.operation_merkle_root,
+            compute_operations_hash(&[operation.id])
+        );
+    }
+
+    #[test]
+    fn test_block_builder_refusals() {
+        let keypair = KeyPair::generate();
+        assert!(BlockBuilder::new(Slot::new(1, 0), limits())
+            .parents(parents()[..1].to_vec())
+            .build(&keypair)
+            .is_err());
+        assert!(BlockBuilder::new(Slot::new(1, 2), limits())
+            .parents(parents())
+            .build(&keypair)
+            .is_err());
+        // genesis blocks have no parents
+        assert!(BlockBuilder::new(Slot::new(0, 1), limits())
+            .build(&keypair)
+            .is_ok());
+
+        let endorsement = |endorsed_block, index| {
+            Endorsement::new_wrapped(
+                Endorsement {
+                    slot: Slot::new(1, 0),
+                    index,
+                    endorsed_block,
+                },
+                EndorsementSerializer::new(),
+                &keypair,
+            )
+            .unwrap()
+        };
+        assert!(BlockBuilder::new(Slot::new(1, 0), limits())
+            .parents(parents())
+            .endorsements(vec![endorsement(parents()[0], 0)])
+            .build(&keypair)
+            .is_ok());
+        assert!(BlockBuilder::new(Slot::new(1, 0), limits())
+            .parents(parents())
+            .endorsements(vec![endorsement(parents()[1], 0)])
+            .build(&keypair)
+            .is_err());
+        assert!(BlockBuilder::new(Slot::new(1, 0), limits())
+            .parents(parents())
+            .endorsements(vec![
+                endorsement(parents()[0], 0),
+                endorsement(parents()[0], 0)
+            ])
+            .build(&keypair)
+            .is_err());
+    }
+
+    #[test]
+    fn test_operation_builder_refusals() {
+        let keypair = KeyPair::generate();
+        assert!(
+            OperationBuilder::new(OperationType::RollBuy { roll_count: 1 })
+                .build(&keypair)
+                .is_err()
+        );
+        assert!(
+            OperationBuilder::new(OperationType::RollSell { roll_count: 0 })
+                .expire_period(10)
+                .build(&keypair)
+                .is_err()
+        );
+        assert!(OperationBuilder::new(OperationType::ExecuteSC {
+            data: vec![0; MAX_BLOCK_SIZE as usize],
+            max_gas: 0,
+            datastore: Default::default(),
+        })
+        .expire_period(10)
+        .build(&keypair)
+        .is_err());
+    }
+}
@@ -1,5 +1,6 @@
 use super::*;
 use crate::amount::Amount;
+use crate::slot_scheduler::SlotTiming;
 use massa_time::MassaTime;
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
@@ -46,6 +47,17 @@ impl Default for CompactConfig {
     }
 }
 
+impl CompactConfig {
+    /// Get the timing parameters of the slots
+    pub fn get_slot_timing(&self) -> SlotTiming {
+        SlotTiming {
+            thread_count: self.thread_count,
+            t0: self.t0,
+            genesis_timestamp: self.genesis_timestamp,
+        }
+    }
+}
+
 impl Display for CompactConfig {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(
@@ -22,12 +22,18 @@ use num::rational::Ratio;
 
 /// Limit on the number of peers we advertise to others.
 pub const MAX_ADVERTISE_LENGTH: u32 = 10000;
+/// Limit on the number of addresses advertised for a peer, its main address included.
+pub const MAX_ADVERTISED_PEER_ADDRESSES: u32 = 8;
+/// Bound of the reputation of a peer, in absolute value
+pub const MAX_PEER_REPUTATION: i32 = 100;
 /// Maximum message length in bytes
 pub const MAX_MESSAGE_SIZE: u32 = 1048576000;
 /// Max number of hash in the message `AskForBlocks`
 pub const MAX_ASK_BLOCKS_PER_MESSAGE: u32 = 128;
 /// Max number of operations per message
 pub const MAX_OPERATIONS_PER_MESSAGE: u32 = 1024;
+/// Max number of blocks in the messages `AskForBlockRange` and `BlockRange`
+pub const MAX_BLOCK_RANGE_LENGTH: u32 = 128;
 /// Length of the handshake random signature
 pub const HANDSHAKE_RANDOMNESS_SIZE_BYTES: usize = 32;
 
@@ -39,7 +45,7 @@ pub const CHANNEL_SIZE: usize = 1024;
 lazy_static::lazy_static! {
     /// Time in milliseconds when the blockclique started.
     pub static ref GENESIS_TIMESTAMP: MassaTime = if cfg!(feature = "sandbox") {
-        std::env::var("GENESIS_TIMESTAMP").map(|timestamp| timestamp.parse::<u64>().unwrap().into()).unwrap_or_else(|_|
+        std::env::var("GENESIS_TIMESTAMP").map(|timestamp| MassaTime::from_str(&timestamp).expect("GENESIS_TIMESTAMP must be milliseconds since 01/01/1970 or an RFC 3339 date")).unwrap_or_else(|_|
             MassaTime::now()
                 .unwrap()
                 .saturating_add(MassaTime::from_millis(1000 * 10))
@@ -59,12 +65,13 @@ lazy_static::lazy_static! {
         .unwrap();
     /// number of cycle misses (strictly) above which stakers are deactivated
     pub static ref POS_MISS_RATE_DEACTIVATION_THRESHOLD: Ratio<u64> = Ratio::new(7, 10);
-    /// node version
+    /// node version, whose major number changes with the format of the network messages:
+    /// the nodes of another major version are rejected at handshake
     pub static ref VERSION: Version = {
         if cfg!(feature = "sandbox") {
-            "SAND.0.0"
+            "SAND.0.1"
         } else {
-            "TEST.18.0"
+            "TEST.19.0"
         }
         .parse()
         .unwrap()
@@ -215,6 +222,8 @@ pub const NETWORK_CONTROLLER_CHANNEL_SIZE: usize = 10_000;
 pub const NETWORK_EVENT_CHANNEL_SIZE: usize = 10_000;
 /// network node command channel size
 pub const NETWORK_NODE_COMMAND_CHANNEL_SIZE: usize = 10_000;
+/// max size in bytes of the messages queued in a network node command channel
+pub const NETWORK_NODE_COMMAND_CHANNEL_BYTES: usize = 64 * 1024 * 1024;
 /// network node event channel size
 pub const NETWORK_NODE_EVENT_CHANNEL_SIZE: usize = 10_000;
 
@@ -1,13 +1,13 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
 use crate::prehash::PreHashed;
+use crate::serialization::{from_prefixed_bs58_check, to_prefixed_bs58_check};
 use crate::slot::{Slot, SlotDeserializer, SlotSerializer};
 use crate::wrapped::{Id, Wrapped, WrappedContent};
 use crate::{block::BlockId, error::ModelsError};
 use massa_hash::{Hash, HashDeserializer};
 use massa_serialization::{
-    DeserializeError, Deserializer, SerializeError, Serializer, U32VarIntDeserializer,
-    U32VarIntSerializer, U64VarIntDeserializer, U64VarIntSerializer,
+    Deserializer, SerializeError, Serializer, U32VarIntDeserializer, U32VarIntSerializer,
 };
 use nom::error::context;
 use nom::sequence::tuple;
@@ -47,18 +47,14 @@ impl Id for EndorsementId {
 
 impl std::fmt::Display for EndorsementId {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let u64_serializer = U64VarIntSerializer::new();
-        // might want to allocate the vector with capacity in order to avoid re-allocation
-        let mut bytes: Vec<u8> = Vec::new();
-        u64_serializer
-            .serialize(&ENDORSEMENTID_VERSION, &mut bytes)
-            .map_err(|_| std::fmt::Error)?;
-        bytes.extend(self.0.to_bytes());
         write!(
             f,
-            "{}{}",
-            ENDORSEMENTID_PREFIX,
-            bs58::encode(bytes).with_check().into_string()
+            "{}",
+            to_prefixed_bs58_check(
+                ENDORSEMENTID_PREFIX,
+                ENDORSEMENTID_VERSION,
+                self.0.to_bytes()
+            )
         )
     }
 }
@@ -82,25 +78,9 @@ impl FromStr for EndorsementId {
     /// assert_eq!(endo_id, res_endo_id);
     /// ```
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut chars = s.chars();
-        match chars.next() {
-            Some(prefix) if prefix == ENDORSEMENTID_PREFIX => {
-                let data = chars.collect::<String>();
-                let decoded_bs58_check = bs58::decode(data)
-                    .with_check(None)
-                    .into_vec()
-                    .map_err(|_| ModelsError::EndorsementIdParseError)?;
-                let u64_deserializer = U64VarIntDeserializer::new(Included(0), Included(u64::MAX));
-                let (rest, _version) = u64_deserializer
-                    .deserialize::<DeserializeError>(&decoded_bs58_check[..])
-                    .map_err(|_| ModelsError::EndorsementIdParseError)?;
-                Ok(EndorsementId(Hash::from_bytes(
-                    rest.try_into()
-                        .map_err(|_| ModelsError::EndorsementIdParseError)?,
-                )))
-            }
-            _ => Err(ModelsError::EndorsementIdParseError),
-        }
+        let bytes = from_prefixed_bs58_check(s, ENDORSEMENTID_PREFIX, ENDORSEMENTID_VERSION)
+            .map_err(ModelsError::EndorsementIdParseError)?;
+        Ok(EndorsementId(Hash::from_bytes(&bytes)))
     }
 }
 
@@ -1,5 +1,7 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
+use crate::slot::Slot;
+use crate::upgrade_schedule::ProtocolFeature;
 use displaydoc::Display;
 use massa_serialization::SerializeError;
 use thiserror::Error;
@@ -30,16 +32,26 @@ pub enum ModelsError {
     PeriodOverflowError,
     /// amount parse error
     AmountParseError(String),
-    /// address parsing error
-    AddressParseError,
-    /// node id parsing error
-    NodeIdParseError,
-    /// block id parsing error
-    BlockIdParseError,
-    /// operation id parsing error
-    OperationIdParseError,
-    /// endorsement id parsing error
-    EndorsementIdParseError,
+    /// address parsing error: {0}
+    AddressParseError(String),
+    /// node id parsing error: {0}
+    NodeIdParseError(String),
+    /// block id parsing error: {0}
+    BlockIdParseError(String),
+    /// operation id parsing error: {0}
+    OperationIdParseError(String),
+    /// endorsement id parsing error: {0}
+    EndorsementIdParseError(String),
+    /// ip subnet parsing error: {0}
+    IpSubnetParseError(String),
+    /// invalid network faults: {0}
+    InvalidNetworkFaults(String),
+    /// invalid block: {0}
+    InvalidBlock(String),
+    /// invalid operation: {0}
+    InvalidOperation(String),
+    /// protocol feature {0} is not active at slot {1}
+    InactiveProtocolFeature(ProtocolFeature, Slot),
     /// checked operation error
     CheckedOperationError(String),
     /// invalid version identifier: {0}
@@ -17,8 +17,14 @@ pub mod address;
 pub mod amount;
 /// structure use by the API
 pub mod api;
+/// Merkle proofs of final balances
+pub mod balance_proof;
+/// portable list of banned ip addresses and subnets
+pub mod ban_list;
 /// block-related structures
 pub mod block;
+/// builders of signed blocks and operations
+pub mod builder;
 /// clique
 pub mod clique;
 /// various structures
@@ -35,6 +41,8 @@ pub mod error;
 pub mod execution;
 /// ledger related structures
 pub mod ledger_models;
+/// faults injected in the messages sent to the peers
+pub mod network_faults;
 /// node related structure
 pub mod node;
 /// operations
@@ -43,18 +51,32 @@ pub mod operation;
 pub mod output_event;
 /// pre-hashed trait, for hash less hashmap/set
 pub mod prehash;
+/// inputs of the block production interlock shared by the components
+pub mod production_gate;
+/// resource alerts shared by the components
+pub mod resource_status;
 /// rolls
 pub mod rolls;
 /// serialization
 pub mod serialization;
 /// slots
 pub mod slot;
+/// slot timing abstraction
+pub mod slot_scheduler;
+/// commitments of the block producers to their final state
+pub mod state_commitment;
 /// various statistics
 pub mod stats;
 /// bootstrap streaming cursor
 pub mod streaming_step;
+/// synchronization progress shared by the components
+pub mod sync_progress;
+/// canonical serializations checked against a golden file
+pub mod test_vectors;
 /// management of the relation between time and slots
 pub mod timeslots;
+/// scheduled upgrades of the protocol
+pub mod upgrade_schedule;
 /// versions
 pub mod version;
 /// trait for signed structure
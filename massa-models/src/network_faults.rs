@@ -0,0 +1,85 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Faults injected in the messages sent to the peers, to check the robustness of the protocol
+//! under adverse network conditions.
+//!
+//! They are only applied by the nodes built with the `fault_injection` feature of the network worker,
+//! the other nodes refuse to set them.
+
+use crate::error::ModelsError;
+use crate::node::NodeId;
+use massa_time::MassaTime;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Faults injected in the messages sent to a peer, the missing fields meaning no fault
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MessageFaults {
+    /// ratio of the messages that are not sent, between 0 and 1
+    pub drop_ratio: f64,
+    /// ratio of the messages that are sent twice, between 0 and 1
+    pub duplicate_ratio: f64,
+    /// ratio of the messages that are held back and sent after the next message, between 0 and 1
+    pub reorder_ratio: f64,
+    /// minimal delay before sending the messages
+    pub min_delay: MassaTime,
+    /// maximal delay before sending the messages, each delay is drawn uniformly between the minimal and the maximal one
+    pub max_delay: MassaTime,
+}
+
+impl MessageFaults {
+    /// Check that the ratios are between 0 and 1 and that the delays are in order
+    pub fn check(&self) -> Result<(), ModelsError> {
+        for (name, ratio) in [
+            ("drop_ratio", self.drop_ratio),
+            ("duplicate_ratio", self.duplicate_ratio),
+            ("reorder_ratio", self.reorder_ratio),
+        ] {
+            if !(0.0..=1.0).contains(&ratio) {
+                return Err(ModelsError::InvalidNetworkFaults(format!(
+                    "{} must be between 0 and 1, got {}",
+                    name, ratio
+                )));
+            }
+        }
+        if self.min_delay > self.max_delay {
+            return Err(ModelsError::InvalidNetworkFaults(format!(
+                "min_delay {} is greater than max_delay {}",
+                self.min_delay, self.max_delay
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Faults currently injected by the node
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NetworkFaults {
+    /// faults of the messages sent to the peers without faults of their own
+    pub all_peers: Option<MessageFaults>,
+    /// faults of the messages sent to given peers
+    pub per_peer: BTreeMap<NodeId, MessageFaults>,
+}
+
+impl NetworkFaults {
+    /// Faults of the messages sent to `node_id`, if any
+    pub fn get(&self, node_id: &NodeId) -> Option<&MessageFaults> {
+        self.per_peer.get(node_id).or(self.all_peers.as_ref())
+    }
+
+    /// Set the faults of the messages sent to `node_id`, or to all the peers if `node_id` is `None`.
+    /// The faults are removed if `faults` is `None`, all of them if `node_id` is `None` too.
+    pub fn set(&mut self, node_id: Option<NodeId>, faults: Option<MessageFaults>) {
+        match (node_id, faults) {
+            (Some(node_id), Some(faults)) => {
+                self.per_peer.insert(node_id, faults);
+            }
+            (Some(node_id), None) => {
+                self.per_peer.remove(&node_id);
+            }
+            (None, Some(faults)) => self.all_peers = Some(faults),
+            (None, None) => *self = Default::default(),
+        }
+    }
+}
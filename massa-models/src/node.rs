@@ -1,12 +1,9 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
 use crate::error::ModelsError;
-use massa_serialization::{
-    DeserializeError, Deserializer, Serializer, U64VarIntDeserializer, U64VarIntSerializer,
-};
+use crate::serialization::{from_prefixed_bs58_check, to_prefixed_bs58_check};
 use massa_signature::PublicKey;
 use serde_with::{DeserializeFromStr, SerializeDisplay};
-use std::ops::Bound::Included;
 
 /// `NodeId` wraps a public key to uniquely identify a node.
 #[derive(
@@ -31,18 +28,10 @@ impl NodeId {
 
 impl std::fmt::Display for NodeId {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let u64_serializer = U64VarIntSerializer::new();
-        // might want to allocate the vector with capacity in order to avoid re-allocation
-        let mut bytes: Vec<u8> = Vec::new();
-        u64_serializer
-            .serialize(&NODEID_VERSION, &mut bytes)
-            .map_err(|_| std::fmt::Error)?;
-        bytes.extend(self.0.to_bytes());
         write!(
             f,
-            "{}{}",
-            NODEID_PREFIX,
-            bs58::encode(bytes).with_check().into_string()
+            "{}",
+            to_prefixed_bs58_check(NODEID_PREFIX, NODEID_VERSION, self.0.to_bytes())
         )
     }
 }
@@ -69,23 +58,8 @@ impl std::str::FromStr for NodeId {
     /// assert_eq!(node_id, res_node_id);
     /// ```
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut chars = s.chars();
-        match chars.next() {
-            Some(prefix) if prefix == NODEID_PREFIX => {
-                let data = chars.collect::<String>();
-                let decoded_bs58_check = bs58::decode(data)
-                    .with_check(None)
-                    .into_vec()
-                    .map_err(|_| ModelsError::NodeIdParseError)?;
-                let u64_deserializer = U64VarIntDeserializer::new(Included(0), Included(u64::MAX));
-                let (rest, _version) = u64_deserializer
-                    .deserialize::<DeserializeError>(&decoded_bs58_check[..])
-                    .map_err(|_| ModelsError::NodeIdParseError)?;
-                Ok(NodeId(PublicKey::from_bytes(
-                    rest.try_into().map_err(|_| ModelsError::NodeIdParseError)?,
-                )?))
-            }
-            _ => Err(ModelsError::NodeIdParseError),
-        }
+        let bytes = from_prefixed_bs58_check(s, NODEID_PREFIX, NODEID_VERSION)
+            .map_err(ModelsError::NodeIdParseError)?;
+        Ok(NodeId(PublicKey::from_bytes(&bytes)?))
     }
 }
@@ -2,18 +2,22 @@
 
 use crate::datastore::{Datastore, DatastoreDeserializer, DatastoreSerializer};
 use crate::prehash::{PreHashSet, PreHashed};
-use crate::wrapped::{Id, Wrapped, WrappedContent, WrappedDeserializer, WrappedSerializer};
+use crate::wrapped::{
+    skip_legacy_format_version, Id, Wrapped, WrappedContent, WrappedDeserializer, WrappedSerializer,
+};
 use crate::{
     address::{Address, AddressDeserializer},
     amount::{Amount, AmountDeserializer, AmountSerializer},
     error::ModelsError,
-    serialization::{StringDeserializer, StringSerializer, VecU8Deserializer, VecU8Serializer},
+    serialization::{
+        from_prefixed_bs58_check, to_prefixed_bs58_check, StringDeserializer, StringSerializer,
+        VecU8Deserializer, VecU8Serializer,
+    },
 };
 use massa_hash::{Hash, HashDeserializer};
 use massa_serialization::{
-    DeserializeError, Deserializer, SerializeError, Serializer, U16VarIntDeserializer,
-    U16VarIntSerializer, U32VarIntDeserializer, U32VarIntSerializer, U64VarIntDeserializer,
-    U64VarIntSerializer,
+    Deserializer, SerializeError, Serializer, U16VarIntDeserializer, U16VarIntSerializer,
+    U32VarIntDeserializer, U32VarIntSerializer, U64VarIntDeserializer, U64VarIntSerializer,
 };
 use nom::error::context;
 use nom::multi::length_count;
@@ -52,18 +56,10 @@ pub struct OperationPrefixId([u8; OPERATION_ID_PREFIX_SIZE_BYTES]);
 
 impl std::fmt::Display for OperationId {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let u64_serializer = U64VarIntSerializer::new();
-        // might want to allocate the vector with capacity in order to avoid re-allocation
-        let mut bytes: Vec<u8> = Vec::new();
-        u64_serializer
-            .serialize(&OPERATIONID_VERSION, &mut bytes)
-            .map_err(|_| std::fmt::Error)?;
-        bytes.extend(self.0.to_bytes());
         write!(
             f,
-            "{}{}",
-            OPERATIONID_PREFIX,
-            bs58::encode(bytes).with_check().into_string()
+            "{}",
+            to_prefixed_bs58_check(OPERATIONID_PREFIX, OPERATIONID_VERSION, self.0.to_bytes())
         )
     }
 }
@@ -99,25 +95,9 @@ impl FromStr for OperationId {
     /// assert_eq!(op_id, res_op_id);
     /// ```
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut chars = s.chars();
-        match chars.next() {
-            Some(prefix) if prefix == OPERATIONID_PREFIX => {
-                let data = chars.collect::<String>();
-                let decoded_bs58_check = bs58::decode(data)
-                    .with_check(None)
-                    .into_vec()
-                    .map_err(|_| ModelsError::OperationIdParseError)?;
-                let u64_deserializer = U64VarIntDeserializer::new(Included(0), Included(u64::MAX));
-                let (rest, _version) = u64_deserializer
-                    .deserialize::<DeserializeError>(&decoded_bs58_check[..])
-                    .map_err(|_| ModelsError::OperationIdParseError)?;
-                Ok(OperationId(Hash::from_bytes(
-                    rest.try_into()
-                        .map_err(|_| ModelsError::OperationIdParseError)?,
-                )))
-            }
-            _ => Err(ModelsError::OperationIdParseError),
-        }
+        let bytes = from_prefixed_bs58_check(s, OPERATIONID_PREFIX, OPERATIONID_VERSION)
+            .map_err(ModelsError::OperationIdParseError)?;
+        Ok(OperationId(Hash::from_bytes(&bytes)))
     }
 }
 
@@ -184,6 +164,20 @@ impl OperationId {
     }
 }
 
+/// Compute the operation merkle root of a block: the hash of the concatenated ids of its operations.
+/// The ids of a full block are hashed by the worker pool.
+pub fn compute_operations_hash<'a>(
+    operation_ids: impl IntoIterator<Item = &'a OperationId>,
+) -> Hash {
+    let operation_ids = operation_ids.into_iter();
+    let mut total_hash: Vec<u8> =
+        Vec::with_capacity(operation_ids.size_hint().0 * OPERATION_ID_SIZE_BYTES);
+    for operation_id in operation_ids {
+        total_hash.extend(operation_id.to_bytes());
+    }
+    Hash::compute_from_parallel(&total_hash)
+}
+
 /// Serializer for `OperationId`
 #[derive(Default, Clone)]
 pub struct OperationIdSerializer;
@@ -264,10 +258,22 @@ impl std::fmt::Display for Operation {
 /// signed operation
 pub type WrappedOperation = Wrapped<Operation, OperationId>;
 
-impl WrappedContent for Operation {}
+impl WrappedContent for Operation {
+    fn get_hashed_content(serialized_content: &[u8]) -> &[u8] {
+        skip_legacy_format_version(serialized_content)
+    }
+}
+
+/// Version of the binary format of `Operation`, serialized first.
+///
+/// The operation id is the hash of the serialized operation: any change to its format must come with a new version,
+/// which is hashed into the ids of the operations serialized with it.
+/// The version 0 is left out of the hash, so that the ids of the operations serialized before the version prefix do not change.
+pub const OPERATION_FORMAT_VERSION: u32 = 0;
 
 /// Serializer for `Operation`
 pub struct OperationSerializer {
+    version_serializer: U32VarIntSerializer,
     u64_serializer: U64VarIntSerializer,
     amount_serializer: AmountSerializer,
     op_type_serializer: OperationTypeSerializer,
@@ -277,6 +283,7 @@ impl OperationSerializer {
     /// Creates a new `OperationSerializer`
     pub fn new() -> Self {
         Self {
+            version_serializer: U32VarIntSerializer::new(),
             u64_serializer: U64VarIntSerializer::new(),
             amount_serializer: AmountSerializer::new(),
             op_type_serializer: OperationTypeSerializer::new(),
@@ -312,6 +319,8 @@ impl Serializer<Operation> for OperationSerializer {
     /// OperationSerializer::new().serialize(&operation, &mut buffer).unwrap();
     /// ```
     fn serialize(&self, value: &Operation, buffer: &mut Vec<u8>) -> Result<(), SerializeError> {
+        self.version_serializer
+            .serialize(&OPERATION_FORMAT_VERSION, buffer)?;
         self.amount_serializer.serialize(&value.fee, buffer)?;
         self.u64_serializer
             .serialize(&value.expire_period, buffer)?;
@@ -322,6 +331,7 @@ impl Serializer<Operation> for OperationSerializer {
 
 /// Serializer for `Operation`
 pub struct OperationDeserializer {
+    version_deserializer: U32VarIntDeserializer,
     expire_period_deserializer: U64VarIntDeserializer,
     amount_deserializer: AmountDeserializer,
     op_type_deserializer: OperationTypeDeserializer,
@@ -338,6 +348,10 @@ impl OperationDeserializer {
         max_op_datastore_value_length: u64,
     ) -> Self {
         Self {
+            version_deserializer: U32VarIntDeserializer::new(
+                Included(0),
+                Included(OPERATION_FORMAT_VERSION),
+            ),
             expire_period_deserializer: U64VarIntDeserializer::new(Included(0), Included(u64::MAX)),
             amount_deserializer: AmountDeserializer::new(
                 Included(Amount::MIN),
@@ -397,6 +411,9 @@ impl Deserializer<Operation> for OperationDeserializer {
         context(
             "Failed Operation deserialization",
             tuple((
+                context("Failed version deserialization", |input| {
+                    self.version_deserializer.deserialize(input)
+                }),
                 context("Failed fee deserialization", |input| {
                     self.amount_deserializer.deserialize(input)
                 }),
@@ -409,7 +426,7 @@ impl Deserializer<Operation> for OperationDeserializer {
                 }),
             )),
         )
-        .map(|(fee, expire_period, op)| Operation {
+        .map(|(_version, fee, expire_period, op)| Operation {
             fee,
             expire_period,
             op,
@@ -1502,4 +1519,88 @@ mod tests {
 
         assert_eq!(op.get_validity_range(10), 40..=50);
     }
+
+    #[test]
+    #[serial]
+    fn test_operation_format_versioning() {
+        let keypair = KeyPair::generate();
+        let deserializer = OperationDeserializer::new(
+            MAX_DATASTORE_VALUE_LENGTH,
+            MAX_FUNCTION_NAME_LENGTH,
+            MAX_PARAMETERS_SIZE,
+            MAX_OPERATION_DATASTORE_ENTRY_COUNT,
+            MAX_OPERATION_DATASTORE_KEY_LENGTH,
+            MAX_OPERATION_DATASTORE_VALUE_LENGTH,
+        );
+        let op_types = vec![
+            OperationType::Transaction {
+                recipient_address: Address::from_public_key(&keypair.get_public_key()),
+                amount: Amount::from_str("300").unwrap(),
+            },
+            OperationType::RollBuy { roll_count: 10 },
+            OperationType::RollSell { roll_count: 1 },
+            OperationType::ExecuteSC {
+                max_gas: 123,
+                data: vec![23u8, 123u8, 44u8],
+                datastore: BTreeMap::from([(vec![1, 2], vec![254, 255])]),
+            },
+            OperationType::CallSC {
+                max_gas: 123,
+                target_addr: Address::from_public_key(&keypair.get_public_key()),
+                coins: Amount::from_str("456.789").unwrap(),
+                target_func: "target function".to_string(),
+                param: b"parameter".to_vec(),
+            },
+        ];
+        for op in op_types {
+            let content = Operation {
+                fee: Amount::from_str("20").unwrap(),
+                op,
+                expire_period: u64::MAX,
+            };
+            let mut ser_content = Vec::new();
+            OperationSerializer::new()
+                .serialize(&content, &mut ser_content)
+                .unwrap();
+
+            // the format version comes first
+            let (_, version) = U32VarIntDeserializer::new(Included(0), Included(u32::MAX))
+                .deserialize::<DeserializeError>(&ser_content)
+                .unwrap();
+            assert_eq!(version, OPERATION_FORMAT_VERSION);
+
+            // the serialization is canonical: serializing again what was deserialized gives the same bytes
+            let (rest, res_content) = deserializer
+                .deserialize::<DeserializeError>(&ser_content)
+                .unwrap();
+            assert!(rest.is_empty());
+            assert_eq!(res_content, content);
+            let mut ser_again = Vec::new();
+            OperationSerializer::new()
+                .serialize(&res_content, &mut ser_again)
+                .unwrap();
+            assert_eq!(ser_again, ser_content);
+
+            // unknown format versions are refused
+            let mut ser_future = Vec::new();
+            U32VarIntSerializer::new()
+                .serialize(&(OPERATION_FORMAT_VERSION + 1), &mut ser_future)
+                .unwrap();
+            ser_future.extend(&ser_content[1..]);
+            assert!(deserializer
+                .deserialize::<DeserializeError>(&ser_future)
+                .is_err());
+
+            // the version 0 is not hashed: the id is the one of the operation serialized without it
+            let wrapped: WrappedOperation =
+                Operation::new_wrapped(content, OperationSerializer::new(), &keypair).unwrap();
+            assert_eq!(
+                *wrapped.id.get_hash(),
+                Hash::compute_from_tuple(&[
+                    &keypair.get_public_key().to_bytes()[..],
+                    &ser_content[1..]
+                ])
+            );
+        }
+    }
 }
@@ -0,0 +1,52 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use crate::api::ProductionOverride;
+use std::sync::{Arc, PoisonError, RwLock};
+
+#[derive(Debug, Clone, Copy, Default)]
+struct ProductionGateState {
+    /// number of nodes the protocol is connected to
+    connected_node_count: usize,
+    /// override set by the node administrator
+    production_override: ProductionOverride,
+}
+
+/// Inputs of the safety interlock of the block production: the number of connected nodes, updated by the
+/// protocol worker, and the override of the node administrator, set through the private API.
+/// Read by the block factory before producing.
+#[derive(Debug, Clone, Default)]
+pub struct ProductionGate(Arc<RwLock<ProductionGateState>>);
+
+impl ProductionGate {
+    /// Get the number of nodes the protocol is connected to
+    pub fn get_connected_node_count(&self) -> usize {
+        self.0
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .connected_node_count
+    }
+
+    /// Set the number of nodes the protocol is connected to
+    pub fn set_connected_node_count(&self, count: usize) {
+        self.0
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .connected_node_count = count;
+    }
+
+    /// Get the override set by the node administrator
+    pub fn get_override(&self) -> ProductionOverride {
+        self.0
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .production_override
+    }
+
+    /// Replace the override of the node administrator
+    pub fn set_override(&self, production_override: ProductionOverride) {
+        self.0
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .production_override = production_override;
+    }
+}
@@ -0,0 +1,34 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use crate::api::ResourceAlert;
+use std::sync::{Arc, PoisonError, RwLock};
+
+/// Alerts currently raised by the resource monitor of the node.
+/// While any is raised the node is in degraded read-only mode: the bootstrap server refuses sessions
+/// and the pool does not accept new items, so that the storage stops growing.
+#[derive(Debug, Clone, Default)]
+pub struct ResourceStatus(Arc<RwLock<Vec<ResourceAlert>>>);
+
+impl ResourceStatus {
+    /// Get the alerts currently raised, empty if the resources are fine
+    pub fn get_alerts(&self) -> Vec<ResourceAlert> {
+        self.0
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Replace the alerts currently raised
+    pub fn set_alerts(&self, alerts: Vec<ResourceAlert>) {
+        *self.0.write().unwrap_or_else(PoisonError::into_inner) = alerts;
+    }
+
+    /// Returns true if the node is in degraded read-only mode
+    pub fn is_degraded(&self) -> bool {
+        !self
+            .0
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .is_empty()
+    }
+}
@@ -4,8 +4,8 @@ use crate::error::ModelsError;
 use crate::prehash::{PreHashSet, PreHashed};
 use bitvec::prelude::BitVec;
 use massa_serialization::{
-    Deserializer, SerializeError, Serializer, U32VarIntDeserializer, U32VarIntSerializer,
-    U64VarIntDeserializer, U64VarIntSerializer,
+    DeserializeError, Deserializer, SerializeError, Serializer, U32VarIntDeserializer,
+    U32VarIntSerializer, U64VarIntDeserializer, U64VarIntSerializer,
 };
 use nom::bytes::complete::take;
 use nom::multi::{length_count, length_data};
@@ -17,10 +17,70 @@ use nom::{
 };
 use std::convert::TryInto;
 use std::marker::PhantomData;
-use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::ops::Bound;
 use Bound::Included;
 
+/// Encode `bytes` as a user-facing string: `prefix` followed by the base58check encoding of `version` and `bytes`.
+///
+/// The prefix tells the kind of the encoded value (ex: 'A' for addresses) and the checksum
+/// makes the decoding reject the strings with a typo.
+pub fn to_prefixed_bs58_check(prefix: char, version: u64, bytes: &[u8]) -> String {
+    let mut data: Vec<u8> = Vec::with_capacity(bytes.len().saturating_add(1));
+    U64VarIntSerializer::new()
+        .serialize(&version, &mut data)
+        .expect("varint serialization cannot fail");
+    data.extend(bytes);
+    format!(
+        "{}{}",
+        prefix,
+        bs58::encode(data).with_check().into_string()
+    )
+}
+
+/// Decode a string encoded by `to_prefixed_bs58_check` with the same `prefix` and `version`.
+/// On failure, returns a description of the problem meant for the user.
+///
+/// ## Example
+/// ```rust
+/// # use massa_models::serialization::{from_prefixed_bs58_check, to_prefixed_bs58_check};
+/// let encoded = to_prefixed_bs58_check('X', 0, &[1, 2, 3]);
+/// assert_eq!(from_prefixed_bs58_check::<3>(&encoded, 'X', 0).unwrap(), [1, 2, 3]);
+/// assert!(from_prefixed_bs58_check::<3>(&encoded, 'Y', 0).is_err());
+/// assert!(from_prefixed_bs58_check::<3>(&encoded, 'X', 1).is_err());
+/// let typo = format!("{}{}", &encoded[..encoded.len() - 1], if encoded.ends_with('2') { '3' } else { '2' });
+/// assert!(from_prefixed_bs58_check::<3>(&typo, 'X', 0).is_err());
+/// ```
+pub fn from_prefixed_bs58_check<const N: usize>(
+    s: &str,
+    prefix: char,
+    version: u64,
+) -> Result<[u8; N], String> {
+    let data = s
+        .strip_prefix(prefix)
+        .ok_or_else(|| format!("{} does not start with '{}'", s, prefix))?;
+    let decoded = bs58::decode(data)
+        .with_check(None)
+        .into_vec()
+        .map_err(|err| match err {
+            bs58::decode::Error::InvalidChecksum { .. } => {
+                format!("{} has an invalid checksum, check it for typos", s)
+            }
+            err => format!("{} is not valid base58: {}", s, err),
+        })?;
+    let (rest, decoded_version) = U64VarIntDeserializer::new(Included(0), Included(u64::MAX))
+        .deserialize::<DeserializeError>(&decoded)
+        .map_err(|_| format!("{} has no version", s))?;
+    if decoded_version != version {
+        return Err(format!(
+            "{} has the unsupported version {} (expected {})",
+            s, decoded_version, version
+        ));
+    }
+    rest.try_into()
+        .map_err(|_| format!("{} encodes {} bytes instead of {}", s, rest.len(), N))
+}
+
 /// Serialize min big endian integer
 pub trait SerializeMinBEInt {
     /// serializes with the minimal amount of big endian bytes
@@ -228,6 +288,81 @@ impl Deserializer<IpAddr> for IpAddrDeserializer {
     }
 }
 
+/// Serializer for `SocketAddr`: the ip address followed by the big endian port
+#[derive(Default)]
+pub struct SocketAddrSerializer {
+    ip_addr_serializer: IpAddrSerializer,
+}
+
+impl SocketAddrSerializer {
+    /// Creates a `SocketAddrSerializer`
+    pub const fn new() -> Self {
+        Self {
+            ip_addr_serializer: IpAddrSerializer::new(),
+        }
+    }
+}
+
+impl Serializer<SocketAddr> for SocketAddrSerializer {
+    /// ```
+    /// use massa_models::serialization::SocketAddrSerializer;
+    /// use massa_serialization::Serializer;
+    /// use std::net::SocketAddr;
+    ///
+    /// let addr: SocketAddr = "127.0.0.1:31244".parse().unwrap();
+    /// let mut buffer = Vec::new();
+    /// SocketAddrSerializer::new().serialize(&addr, &mut buffer).unwrap();
+    /// assert_eq!(buffer, vec![4, 127, 0, 0, 1, 0x7a, 0x0c]);
+    /// ```
+    fn serialize(&self, value: &SocketAddr, buffer: &mut Vec<u8>) -> Result<(), SerializeError> {
+        self.ip_addr_serializer.serialize(&value.ip(), buffer)?;
+        buffer.extend(value.port().to_be_bytes());
+        Ok(())
+    }
+}
+
+/// Deserializer for `SocketAddr`
+#[derive(Default)]
+pub struct SocketAddrDeserializer {
+    ip_addr_deserializer: IpAddrDeserializer,
+}
+
+impl SocketAddrDeserializer {
+    /// Creates a `SocketAddrDeserializer`
+    pub const fn new() -> Self {
+        Self {
+            ip_addr_deserializer: IpAddrDeserializer::new(),
+        }
+    }
+}
+
+impl Deserializer<SocketAddr> for SocketAddrDeserializer {
+    /// ```
+    /// use massa_models::serialization::{SocketAddrSerializer, SocketAddrDeserializer};
+    /// use massa_serialization::{Serializer, Deserializer, DeserializeError};
+    /// use std::net::SocketAddr;
+    ///
+    /// let addr: SocketAddr = "[::1]:31244".parse().unwrap();
+    /// let mut serialized = Vec::new();
+    /// SocketAddrSerializer::new().serialize(&addr, &mut serialized).unwrap();
+    /// let (rest, addr_deser) = SocketAddrDeserializer::new().deserialize::<DeserializeError>(&serialized).unwrap();
+    /// assert!(rest.is_empty());
+    /// assert_eq!(addr, addr_deser);
+    /// ```
+    fn deserialize<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+        &self,
+        buffer: &'a [u8],
+    ) -> IResult<&'a [u8], SocketAddr, E> {
+        context("Failed SocketAddr deserialization", |input| {
+            let (rest, ip) = self.ip_addr_deserializer.deserialize(input)?;
+            let (rest, port) = take(2usize)(rest)?;
+            // Safe because take would fail just above if less then 2
+            let port = u16::from_be_bytes(port.try_into().unwrap());
+            Ok((rest, SocketAddr::new(ip, port)))
+        })(buffer)
+    }
+}
+
 /// Basic `Vec<u8>` serializer
 #[derive(Clone)]
 pub struct VecU8Serializer {
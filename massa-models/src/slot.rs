@@ -142,20 +142,60 @@ impl std::fmt::Display for Slot {
 
 impl FromStr for Slot {
     type Err = ModelsError;
+
+    /// Parse a slot written as `period.thread` or `period,thread`
+    ///
+    /// ## Example
+    /// ```rust
+    /// # use massa_models::slot::Slot;
+    /// # use std::str::FromStr;
+    /// assert_eq!(Slot::from_str("10.3").unwrap(), Slot::new(10, 3));
+    /// assert_eq!(Slot::from_str("10,3").unwrap(), Slot::new(10, 3));
+    /// assert!(Slot::from_str("10").is_err());
+    /// assert!(Slot::from_str("10.256").is_err());
+    /// ```
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let v: Vec<_> = s.split(',').collect();
-        if v.len() != 2 {
-            Err(ModelsError::DeserializeError(
-                "invalid slot format".to_string(),
-            ))
-        } else {
-            Ok(Slot::new(
-                v[0].parse::<u64>()
-                    .map_err(|_| ModelsError::DeserializeError("invalid period".to_string()))?,
-                v[1].parse::<u8>()
-                    .map_err(|_| ModelsError::DeserializeError("invalid thread".to_string()))?,
-            ))
+        let (period, thread) = s.trim().split_once(['.', ',']).ok_or_else(|| {
+            ModelsError::DeserializeError(format!("invalid slot {}: expected period.thread", s))
+        })?;
+        Ok(Slot::new(
+            period.trim().parse::<u64>().map_err(|_| {
+                ModelsError::DeserializeError(format!("invalid period in slot {}", s))
+            })?,
+            thread.trim().parse::<u8>().map_err(|_| {
+                ModelsError::DeserializeError(format!("invalid thread in slot {}", s))
+            })?,
+        ))
+    }
+}
+
+/// Iterator over the consecutive slots of a range, across threads (see `Slot::iter_range`)
+#[derive(Debug, Clone)]
+pub struct SlotIterator {
+    /// next slot to return, `None` once the range or the slots are exhausted
+    next: Option<Slot>,
+    /// end of the range
+    end: Bound<Slot>,
+    /// number of threads
+    thread_count: u8,
+}
+
+impl Iterator for SlotIterator {
+    type Item = Slot;
+
+    fn next(&mut self) -> Option<Slot> {
+        let slot = self.next?;
+        let in_range = match self.end {
+            Bound::Included(end) => slot <= end,
+            Bound::Excluded(end) => slot < end,
+            Bound::Unbounded => true,
+        };
+        if !in_range {
+            self.next = None;
+            return None;
         }
+        self.next = slot.get_next_slot(self.thread_count).ok();
+        Some(slot)
     }
 }
 
@@ -289,6 +329,48 @@ impl Slot {
         }
     }
 
+    /// Iterate over the slots of a range, in order, across threads
+    ///
+    /// ## Example
+    /// ```rust
+    /// # use massa_models::slot::Slot;
+    /// let slots: Vec<Slot> = Slot::iter_range(Slot::new(1, 1)..=Slot::new(2, 1), 3).collect();
+    /// assert_eq!(
+    ///     slots,
+    ///     vec![Slot::new(1, 1), Slot::new(1, 2), Slot::new(2, 0), Slot::new(2, 1)]
+    /// );
+    /// assert_eq!(Slot::iter_range(Slot::new(1, 1)..Slot::new(1, 1), 3).count(), 0);
+    /// ```
+    pub fn iter_range<R: RangeBounds<Slot>>(range: R, thread_count: u8) -> SlotIterator {
+        let next = match range.start_bound() {
+            Bound::Included(start) => Some(*start),
+            Bound::Excluded(start) => start.get_next_slot(thread_count).ok(),
+            Bound::Unbounded => Some(Slot::min()),
+        };
+        SlotIterator {
+            next,
+            end: range.end_bound().cloned(),
+            thread_count,
+        }
+    }
+
+    /// Returns the period of the first slot of `thread` that comes after self
+    ///
+    /// ## Example
+    /// ```rust
+    /// # use massa_models::slot::Slot;
+    /// let slot = Slot::new(10, 3);
+    /// assert_eq!(slot.get_next_period_in_thread(4), 10);
+    /// assert_eq!(slot.get_next_period_in_thread(3), 11);
+    /// ```
+    pub fn get_next_period_in_thread(&self, thread: u8) -> u64 {
+        if self.thread >= thread {
+            self.period.saturating_add(1)
+        } else {
+            self.period
+        }
+    }
+
     /// Returns the previous Slot
     ///
     /// ## Example
@@ -0,0 +1,154 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Slot timing abstraction.
+//!
+//! Workers ask a `SlotScheduler` for the current time, the next slot to process
+//! and the instant at which they must wake up, instead of reading the system clock directly.
+//! The time is read from the `Clock` of the scheduler: the node uses `RealTimeSlotScheduler`,
+//! tests can use `MockSlotScheduler` (see `test_exports`) to drive time by hand.
+
+use crate::{
+    error::ModelsError,
+    slot::Slot,
+    timeslots::{
+        get_block_slot_timestamp, get_closest_slot_to_timestamp, get_latest_block_slot_at_timestamp,
+    },
+};
+use massa_time::{Clock, MassaTime, SystemClock, TimeError};
+use std::time::Instant;
+
+/// Timing parameters of the slots
+#[derive(Debug, Clone, Copy)]
+pub struct SlotTiming {
+    /// number of threads
+    pub thread_count: u8,
+    /// time between two periods in the same thread
+    pub t0: MassaTime,
+    /// time when the blockclique started
+    pub genesis_timestamp: MassaTime,
+}
+
+impl SlotTiming {
+    /// Get the timestamp of a slot
+    pub fn get_slot_timestamp(&self, slot: Slot) -> Result<MassaTime, ModelsError> {
+        get_block_slot_timestamp(self.thread_count, self.t0, self.genesis_timestamp, slot)
+    }
+
+    /// Get the latest slot at `timestamp` (inclusive), if any happened
+    pub fn get_latest_slot_at(&self, timestamp: MassaTime) -> Result<Option<Slot>, ModelsError> {
+        get_latest_block_slot_at_timestamp(
+            self.thread_count,
+            self.t0,
+            self.genesis_timestamp,
+            timestamp,
+        )
+    }
+
+    /// Get the latest slot at the current system time, or the first slot before genesis
+    pub fn get_current_slot_or_first(&self) -> Result<Slot, ModelsError> {
+        Ok(self
+            .get_latest_slot_at(MassaTime::now()?)?
+            .unwrap_or_else(Slot::min))
+    }
+
+    /// Get the slot whose timestamp is the closest to `timestamp`
+    pub fn get_closest_slot_to(&self, timestamp: MassaTime) -> Slot {
+        get_closest_slot_to_timestamp(
+            self.thread_count,
+            self.t0,
+            self.genesis_timestamp,
+            timestamp,
+        )
+    }
+}
+
+/// Source of time for the slot-based workers
+pub trait SlotScheduler: Send + Sync {
+    /// Get the timing parameters of the slots
+    fn get_timing(&self) -> SlotTiming;
+
+    /// Get the clock the time is read from
+    fn get_clock(&self) -> &dyn Clock;
+
+    /// Returns a boxed clone of self.
+    /// Useful to allow cloning `Box<dyn SlotScheduler>`.
+    fn clone_box(&self) -> Box<dyn SlotScheduler>;
+
+    /// Get the current time
+    fn now(&self) -> Result<MassaTime, TimeError> {
+        self.get_clock().now()
+    }
+
+    /// Get the instant at which a worker waiting for `timestamp` must wake up.
+    /// The worker must check with `now` that the timestamp was actually reached once woken up.
+    fn get_wakeup_instant(&self, timestamp: MassaTime) -> Instant {
+        self.get_clock().get_wakeup_instant(timestamp)
+    }
+
+    /// Get the timestamp of a slot
+    fn get_slot_timestamp(&self, slot: Slot) -> Result<MassaTime, ModelsError> {
+        self.get_timing().get_slot_timestamp(slot)
+    }
+
+    /// Get the latest slot at the current time, if any happened
+    fn get_current_slot(&self) -> Result<Option<Slot>, ModelsError> {
+        self.get_timing().get_latest_slot_at(self.now()?)
+    }
+
+    /// Returns true if the timestamp of `slot` was reached
+    fn is_slot_reached(&self, slot: Slot) -> Result<bool, ModelsError> {
+        Ok(self.now()? >= self.get_slot_timestamp(slot)?)
+    }
+
+    /// Gets the next slot to process.
+    /// Slots can be skipped if we waited too much in-between.
+    /// Extra safety against double-production caused by clock adjustments (this is the role of the `previous_slot` parameter).
+    fn get_next_slot(&self, previous_slot: Option<Slot>) -> Result<Slot, ModelsError> {
+        let timing = self.get_timing();
+
+        // get closest slot according to the current time
+        let next_slot = timing.get_closest_slot_to(self.now()?);
+
+        // protection against double-production on unexpected system clock adjustment
+        match previous_slot {
+            Some(prev_slot) if next_slot <= prev_slot => {
+                prev_slot.get_next_slot(timing.thread_count)
+            }
+            _ => Ok(next_slot),
+        }
+    }
+}
+
+impl Clone for Box<dyn SlotScheduler> {
+    fn clone(&self) -> Box<dyn SlotScheduler> {
+        self.clone_box()
+    }
+}
+
+/// Slot scheduler following the system clock
+#[derive(Debug, Clone)]
+pub struct RealTimeSlotScheduler {
+    /// timing parameters of the slots
+    timing: SlotTiming,
+}
+
+impl RealTimeSlotScheduler {
+    /// Create a slot scheduler following the system clock
+    pub fn new(timing: SlotTiming) -> Self {
+        RealTimeSlotScheduler { timing }
+    }
+}
+
+impl SlotScheduler for RealTimeSlotScheduler {
+    fn get_timing(&self) -> SlotTiming {
+        self.timing
+    }
+
+    fn get_clock(&self) -> &dyn Clock {
+        &SystemClock
+    }
+
+    fn clone_box(&self) -> Box<dyn SlotScheduler> {
+        Box::new(self.clone())
+    }
+}
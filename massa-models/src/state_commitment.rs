@@ -0,0 +1,258 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use crate::address::Address;
+use crate::block::BlockId;
+use crate::slot::{Slot, SlotDeserializer, SlotSerializer};
+use massa_hash::{Hash, HashDeserializer};
+use massa_serialization::{Deserializer, SerializeError, Serializer};
+use massa_time::MassaTime;
+use nom::error::{context, ContextError, ParseError};
+use nom::sequence::tuple;
+use nom::{IResult, Parser};
+use serde::{Deserialize, Serialize};
+use std::ops::Bound::{Excluded, Included};
+
+/// Commitment of a block producer to its final state: the hashes of the final state at the output of
+/// a final slot, and of its parts, so that a divergence can be narrowed down to the diverging part
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateCommitment {
+    /// final slot at the output of which the state is committed
+    pub slot: Slot,
+    /// hash of the whole final state
+    pub final_state_hash: Hash,
+    /// hash of the final ledger
+    pub ledger_hash: Hash,
+    /// hash of the asynchronous pool
+    pub async_pool_hash: Hash,
+    /// hash of the deferred credits
+    pub deferred_credits_hash: Hash,
+    /// hash of the executed operations
+    pub executed_ops_hash: Hash,
+}
+
+impl StateCommitment {
+    /// Names of the parts of the state whose hashes differ between `self` and `other`.
+    /// The PoS cycle history is only covered by the final state hash: it is the part that differs
+    /// when the final state hashes differ but not the ones of the other parts.
+    pub fn diff(&self, other: &StateCommitment) -> Vec<&'static str> {
+        let mut parts = Vec::new();
+        if self.ledger_hash != other.ledger_hash {
+            parts.push("ledger");
+        }
+        if self.async_pool_hash != other.async_pool_hash {
+            parts.push("async pool");
+        }
+        if self.deferred_credits_hash != other.deferred_credits_hash {
+            parts.push("deferred credits");
+        }
+        if self.executed_ops_hash != other.executed_ops_hash {
+            parts.push("executed operations");
+        }
+        if parts.is_empty() && self.final_state_hash != other.final_state_hash {
+            parts.push("cycle history");
+        }
+        parts
+    }
+}
+
+impl std::fmt::Display for StateCommitment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Slot: {}", self.slot)?;
+        writeln!(f, "Final state hash: {}", self.final_state_hash)?;
+        writeln!(f, "Ledger hash: {}", self.ledger_hash)?;
+        writeln!(f, "Async pool hash: {}", self.async_pool_hash)?;
+        writeln!(f, "Deferred credits hash: {}", self.deferred_credits_hash)?;
+        writeln!(f, "Executed operations hash: {}", self.executed_ops_hash)
+    }
+}
+
+/// Serializer for `StateCommitment`
+pub struct StateCommitmentSerializer {
+    slot_serializer: SlotSerializer,
+}
+
+impl StateCommitmentSerializer {
+    /// Creates a new `StateCommitmentSerializer`
+    pub const fn new() -> Self {
+        Self {
+            slot_serializer: SlotSerializer::new(),
+        }
+    }
+}
+
+impl Default for StateCommitmentSerializer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Serializer<StateCommitment> for StateCommitmentSerializer {
+    fn serialize(
+        &self,
+        value: &StateCommitment,
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), SerializeError> {
+        self.slot_serializer.serialize(&value.slot, buffer)?;
+        for hash in [
+            &value.final_state_hash,
+            &value.ledger_hash,
+            &value.async_pool_hash,
+            &value.deferred_credits_hash,
+            &value.executed_ops_hash,
+        ] {
+            buffer.extend(hash.to_bytes());
+        }
+        Ok(())
+    }
+}
+
+/// Deserializer for `StateCommitment`
+pub struct StateCommitmentDeserializer {
+    slot_deserializer: SlotDeserializer,
+    hash_deserializer: HashDeserializer,
+}
+
+impl StateCommitmentDeserializer {
+    /// Creates a new `StateCommitmentDeserializer`
+    pub const fn new(thread_count: u8) -> Self {
+        Self {
+            slot_deserializer: SlotDeserializer::new(
+                (Included(0), Included(u64::MAX)),
+                (Included(0), Excluded(thread_count)),
+            ),
+            hash_deserializer: HashDeserializer::new(),
+        }
+    }
+}
+
+impl Deserializer<StateCommitment> for StateCommitmentDeserializer {
+    /// ## Example:
+    /// ```rust
+    /// use massa_hash::Hash;
+    /// use massa_models::slot::Slot;
+    /// use massa_models::state_commitment::{StateCommitment, StateCommitmentDeserializer, StateCommitmentSerializer};
+    /// use massa_serialization::{Deserializer, DeserializeError, Serializer};
+    ///
+    /// let commitment = StateCommitment {
+    ///     slot: Slot::new(10, 3),
+    ///     final_state_hash: Hash::compute_from(b"final state"),
+    ///     ledger_hash: Hash::compute_from(b"ledger"),
+    ///     async_pool_hash: Hash::compute_from(b"async pool"),
+    ///     deferred_credits_hash: Hash::compute_from(b"deferred credits"),
+    ///     executed_ops_hash: Hash::compute_from(b"executed ops"),
+    /// };
+    /// let mut buffer = Vec::new();
+    /// StateCommitmentSerializer::new().serialize(&commitment, &mut buffer).unwrap();
+    /// let (rest, deserialized) = StateCommitmentDeserializer::new(32)
+    ///     .deserialize::<DeserializeError>(&buffer)
+    ///     .unwrap();
+    /// assert!(rest.is_empty());
+    /// assert_eq!(deserialized, commitment);
+    /// ```
+    fn deserialize<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+        &self,
+        buffer: &'a [u8],
+    ) -> IResult<&'a [u8], StateCommitment, E> {
+        context(
+            "Failed StateCommitment deserialization",
+            tuple((
+                context("Failed slot deserialization", |input| {
+                    self.slot_deserializer.deserialize(input)
+                }),
+                context("Failed final_state_hash deserialization", |input| {
+                    self.hash_deserializer.deserialize(input)
+                }),
+                context("Failed ledger_hash deserialization", |input| {
+                    self.hash_deserializer.deserialize(input)
+                }),
+                context("Failed async_pool_hash deserialization", |input| {
+                    self.hash_deserializer.deserialize(input)
+                }),
+                context("Failed deferred_credits_hash deserialization", |input| {
+                    self.hash_deserializer.deserialize(input)
+                }),
+                context("Failed executed_ops_hash deserialization", |input| {
+                    self.hash_deserializer.deserialize(input)
+                }),
+            )),
+        )
+        .map(
+            |(
+                slot,
+                final_state_hash,
+                ledger_hash,
+                async_pool_hash,
+                deferred_credits_hash,
+                executed_ops_hash,
+            )| StateCommitment {
+                slot,
+                final_state_hash,
+                ledger_hash,
+                async_pool_hash,
+                deferred_credits_hash,
+                executed_ops_hash,
+            },
+        )
+        .parse(buffer)
+    }
+}
+
+/// Divergence between the final state committed in the header of a block and ours at the same slot:
+/// one of the two nodes computed a wrong state at or before that slot
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateDivergence {
+    /// block carrying the diverging commitment
+    pub block_id: BlockId,
+    /// creator of the block
+    pub creator: Address,
+    /// state committed in the block header
+    pub announced: StateCommitment,
+    /// our state at the same slot
+    pub local: StateCommitment,
+    /// parts of the state whose hashes differ
+    pub diverging_parts: Vec<String>,
+    /// time at which the divergence was detected
+    pub detected_at: MassaTime,
+}
+
+impl StateDivergence {
+    /// Report the divergence between `announced` in `block_id` and `local`
+    pub fn new(
+        block_id: BlockId,
+        creator: Address,
+        announced: StateCommitment,
+        local: StateCommitment,
+        detected_at: MassaTime,
+    ) -> Self {
+        StateDivergence {
+            block_id,
+            creator,
+            announced,
+            local,
+            diverging_parts: announced
+                .diff(&local)
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            detected_at,
+        }
+    }
+}
+
+impl std::fmt::Display for StateDivergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "final state at slot {} diverges from the one committed by block {} of {}, detected at {}",
+            self.local.slot,
+            self.block_id,
+            self.creator,
+            self.detected_at.to_utc_string()
+        )?;
+        writeln!(f, "Diverging parts: {}", self.diverging_parts.join(", "))?;
+        writeln!(f, "Committed state:")?;
+        write!(f, "{}", self.announced)?;
+        writeln!(f, "Local state:")?;
+        write!(f, "{}", self.local)
+    }
+}
@@ -1,6 +1,6 @@
 //! Copyright (c) 2022 MASSA LABS <info@massa.net>
 
-use crate::slot::Slot;
+use crate::{amount::Amount, slot::Slot};
 use massa_time::MassaTime;
 use serde::{Deserialize, Serialize};
 use std::fmt::Formatter;
@@ -61,6 +61,8 @@ pub struct NetworkStats {
     pub banned_peer_count: u64,
     /// active node count
     pub active_node_count: u64,
+    /// median clock offset of the active nodes relative to ours, in milliseconds (positive if they are ahead)
+    pub median_clock_offset: Option<i64>,
 }
 
 impl std::fmt::Display for NetworkStats {
@@ -71,6 +73,9 @@ impl std::fmt::Display for NetworkStats {
         writeln!(f, "\tKnown peers: {}", self.known_peer_count)?;
         writeln!(f, "\tBanned peers: {}", self.banned_peer_count)?;
         writeln!(f, "\tActive nodes: {}", self.active_node_count)?;
+        if let Some(offset) = self.median_clock_offset {
+            writeln!(f, "\tMedian clock offset of the peers: {} ms", offset)?;
+        }
         Ok(())
     }
 }
@@ -110,6 +115,49 @@ impl std::fmt::Display for ConsensusStats {
     }
 }
 
+/// block production stats of a cycle, produced by consensus module
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CycleProductionStats {
+    /// cycle
+    pub cycle: u64,
+    /// number of slots for which the considered addresses were selected as block producers
+    pub expected_block_count: u64,
+    /// number of final blocks produced by the considered addresses
+    pub produced_block_count: u64,
+    /// number of stale blocks produced by the considered addresses
+    pub stale_block_count: u64,
+    /// ratio of stale blocks among all the final and stale blocks observed during the cycle
+    pub stale_rate: f64,
+    /// average ratio between the operation count and the max operation count of the final blocks of the cycle
+    pub average_block_fill: f64,
+    /// average delay between the slot timestamp and the reception of the blocks of the cycle
+    pub average_production_latency: MassaTime,
+}
+
+impl std::fmt::Display for CycleProductionStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Cycle {} production stats:", self.cycle)?;
+        writeln!(
+            f,
+            "\tProduced/expected block count: {}/{}",
+            self.produced_block_count, self.expected_block_count
+        )?;
+        writeln!(f, "\tStale block count: {}", self.stale_block_count)?;
+        writeln!(f, "\tObserved stale rate: {:.2}%", self.stale_rate * 100.0)?;
+        writeln!(
+            f,
+            "\tAverage block fill: {:.2}%",
+            self.average_block_fill * 100.0
+        )?;
+        writeln!(
+            f,
+            "\tAverage production latency: {} ms",
+            self.average_production_latency.to_millis()
+        )?;
+        Ok(())
+    }
+}
+
 /// stats produced by pool module
 #[derive(Serialize, Deserialize, Debug)]
 pub struct PoolStats {
@@ -127,3 +175,64 @@ impl std::fmt::Display for PoolStats {
         Ok(())
     }
 }
+
+/// Operations of the operation pool whose fee density lies in a given range
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FeeHistogramBucket {
+    /// minimal fee per byte of the bucket (included)
+    pub min_fee_per_byte: Amount,
+    /// maximal fee per byte of the bucket (excluded), `None` for the last bucket
+    pub max_fee_per_byte: Option<Amount>,
+    /// number of operations of the bucket
+    pub operation_count: u64,
+}
+
+/// Operation pool statistics
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OperationPoolStats {
+    /// number of operations in the pool
+    pub operation_count: u64,
+    /// maximal number of operations in the pool
+    pub max_operation_count: u64,
+    /// minimal fee for an operation to be accepted in the pool
+    pub minimal_fee: Amount,
+    /// lowest fee per byte among the operations of the pool
+    pub min_fee_per_byte: Option<Amount>,
+    /// highest fee per byte among the operations of the pool
+    pub max_fee_per_byte: Option<Amount>,
+    /// non-empty buckets of the fee density histogram, by increasing fee per byte
+    pub fee_histogram: Vec<FeeHistogramBucket>,
+}
+
+impl std::fmt::Display for OperationPoolStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Operation pool stats:")?;
+        writeln!(
+            f,
+            "\tOperations: {}/{}",
+            self.operation_count, self.max_operation_count
+        )?;
+        writeln!(f, "\tMinimal fee: {}", self.minimal_fee)?;
+        if let (Some(min), Some(max)) = (self.min_fee_per_byte, self.max_fee_per_byte) {
+            writeln!(f, "\tFee per byte: from {} to {}", min, max)?;
+        }
+        if !self.fee_histogram.is_empty() {
+            writeln!(f, "\tFee per byte histogram:")?;
+        }
+        for bucket in &self.fee_histogram {
+            match bucket.max_fee_per_byte {
+                Some(max) => writeln!(
+                    f,
+                    "\t\t[{}, {}): {}",
+                    bucket.min_fee_per_byte, max, bucket.operation_count
+                )?,
+                None => writeln!(
+                    f,
+                    "\t\t[{}, ...): {}",
+                    bucket.min_fee_per_byte, bucket.operation_count
+                )?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -9,7 +9,7 @@ use nom::{
 use std::{marker::PhantomData, ops::Bound::Included};
 
 /// Streaming step cursor
-#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
 pub enum StreamingStep<T> {
     /// Started step, only when launching the streaming
     Started,
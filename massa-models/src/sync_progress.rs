@@ -0,0 +1,31 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use crate::api::{SyncPhase, SyncProgress};
+use crate::slot::Slot;
+use std::sync::{Arc, PoisonError, RwLock};
+
+/// Progress of the synchronization of the node, updated by the bootstrap client while the final state
+/// is bootstrapped, then by the protocol worker, and read by the API
+#[derive(Debug, Clone, Default)]
+pub struct SyncProgressStatus(Arc<RwLock<SyncProgress>>);
+
+impl SyncProgressStatus {
+    /// Get the current progress
+    pub fn get(&self) -> SyncProgress {
+        *self.0.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Replace the current progress
+    pub fn set(&self, progress: SyncProgress) {
+        *self.0.write().unwrap_or_else(PoisonError::into_inner) = progress;
+    }
+
+    /// Note that the final state being bootstrapped reached `slot`
+    pub fn note_bootstrap_slot(&self, slot: Slot) {
+        self.set(SyncProgress {
+            phase: SyncPhase::Bootstrapping,
+            local_final_slot: Some(slot),
+            ..Default::default()
+        });
+    }
+}
@@ -1,3 +1,5 @@
+mod slot_scheduler;
 mod tools;
 
+pub use slot_scheduler::*;
 pub use tools::*;
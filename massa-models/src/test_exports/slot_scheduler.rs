@@ -0,0 +1,110 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use crate::{
+    error::ModelsError,
+    slot::Slot,
+    slot_scheduler::{SlotScheduler, SlotTiming},
+};
+use massa_time::{Clock, MassaTime, MockClock};
+
+/// Slot scheduler whose time only moves when the test says so.
+/// Clones share the same time.
+#[derive(Debug, Clone)]
+pub struct MockSlotScheduler {
+    /// timing parameters of the slots
+    timing: SlotTiming,
+    /// simulated time
+    clock: MockClock,
+}
+
+impl MockSlotScheduler {
+    /// Create a mock slot scheduler starting at `now`
+    pub fn new(timing: SlotTiming, now: MassaTime) -> Self {
+        MockSlotScheduler::with_clock(timing, MockClock::new(now))
+    }
+
+    /// Create a mock slot scheduler following `clock`, which can be shared with other workers
+    pub fn with_clock(timing: SlotTiming, clock: MockClock) -> Self {
+        MockSlotScheduler { timing, clock }
+    }
+
+    /// Get the simulated clock of the scheduler
+    pub fn get_mock_clock(&self) -> &MockClock {
+        &self.clock
+    }
+
+    /// Set the simulated time
+    pub fn set_now(&self, now: MassaTime) {
+        self.clock.set_now(now);
+    }
+
+    /// Move the simulated time forward
+    pub fn advance(&self, duration: MassaTime) {
+        self.clock.advance(duration);
+    }
+
+    /// Move the simulated time to the timestamp of `slot`
+    pub fn advance_to_slot(&self, slot: Slot) -> Result<(), ModelsError> {
+        self.set_now(self.get_slot_timestamp(slot)?);
+        Ok(())
+    }
+}
+
+impl SlotScheduler for MockSlotScheduler {
+    fn get_timing(&self) -> SlotTiming {
+        self.timing
+    }
+
+    fn get_clock(&self) -> &dyn Clock {
+        &self.clock
+    }
+
+    fn clone_box(&self) -> Box<dyn SlotScheduler> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MockSlotScheduler;
+    use crate::{
+        slot::Slot,
+        slot_scheduler::{SlotScheduler, SlotTiming},
+    };
+    use massa_time::MassaTime;
+
+    #[test]
+    fn test_mock_slot_scheduler() {
+        let timing = SlotTiming {
+            thread_count: 2,
+            t0: MassaTime::from_millis(1000),
+            genesis_timestamp: MassaTime::from_millis(10_000),
+        };
+        let scheduler = MockSlotScheduler::new(timing, MassaTime::from_millis(0));
+        assert_eq!(scheduler.get_current_slot().unwrap(), None);
+        assert_eq!(scheduler.get_next_slot(None).unwrap(), Slot::new(0, 0));
+
+        // clones share the same time
+        let clone = scheduler.clone_box();
+        scheduler.advance_to_slot(Slot::new(3, 1)).unwrap();
+        assert_eq!(clone.get_current_slot().unwrap(), Some(Slot::new(3, 1)));
+        assert!(clone.is_slot_reached(Slot::new(3, 1)).unwrap());
+        assert!(!clone.is_slot_reached(Slot::new(4, 0)).unwrap());
+
+        // never go back to an already processed slot
+        assert_eq!(
+            clone.get_next_slot(Some(Slot::new(3, 1))).unwrap(),
+            Slot::new(4, 0)
+        );
+        scheduler.advance(MassaTime::from_millis(2000));
+        assert_eq!(
+            clone.get_next_slot(Some(Slot::new(3, 1))).unwrap(),
+            Slot::new(5, 1)
+        );
+
+        // schedulers built on the same clock share the same time
+        let other = MockSlotScheduler::with_clock(timing, scheduler.get_mock_clock().clone());
+        scheduler.advance_to_slot(Slot::new(6, 0)).unwrap();
+        assert_eq!(other.get_current_slot().unwrap(), Some(Slot::new(6, 0)));
+    }
+}
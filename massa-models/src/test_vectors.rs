@@ -0,0 +1,218 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Canonical serializations of representative operations, endorsements, block headers and blocks.
+//!
+//! The nodes hash and sign the serialized objects, so a change of their serialization makes a node
+//! refuse the objects of the other nodes. The test vectors are checked against a golden file,
+//! which is only regenerated on purpose with `cargo run -p massa_models --bin generate_test_vectors`
+//! when the format of the network changes.
+
+use crate::{
+    address::Address,
+    amount::Amount,
+    block::{BlockHeader, BlockHeaderSerializer, BlockId, WrappedHeader},
+    builder::{BlockBuilder, BlockLimits, OperationBuilder},
+    endorsement::{Endorsement, EndorsementSerializer, WrappedEndorsement},
+    error::ModelsError,
+    operation::{compute_operations_hash, OperationId, OperationType, WrappedOperation},
+    slot::Slot,
+    state_commitment::StateCommitment,
+    wrapped::{Id, Wrapped, WrappedContent, WrappedSerializer},
+};
+use massa_hash::Hash;
+use massa_serialization::Serializer;
+use massa_signature::KeyPair;
+use std::{collections::BTreeMap, fmt::Display};
+
+/// Path of the golden file, relative to the root of the `massa_models` crate
+pub const GOLDEN_FILE: &str = "test_vectors/serialization.txt";
+
+/// Canonical serialization of an object
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestVector {
+    /// name of the object
+    pub name: &'static str,
+    /// id of the object, the hash that is signed
+    pub id: Hash,
+    /// object serialized as sent in the network
+    pub bytes: Vec<u8>,
+}
+
+impl TestVector {
+    fn new<T: Display + WrappedContent, U: Id>(
+        name: &'static str,
+        wrapped: &Wrapped<T, U>,
+    ) -> Result<Self, ModelsError> {
+        let mut bytes = Vec::new();
+        WrappedSerializer::new().serialize(wrapped, &mut bytes)?;
+        Ok(TestVector {
+            name,
+            id: *wrapped.id.get_hash(),
+            bytes,
+        })
+    }
+}
+
+impl Display for TestVector {
+    /// One line of the golden file: the name, the id and the bytes in hexadecimal
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            self.name,
+            to_hex(self.id.to_bytes()),
+            to_hex(&self.bytes)
+        )
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Build the test vectors. They only depend on fixed keys and fields,
+/// and ed25519 signatures are deterministic, so the vectors are the same at each call.
+pub fn generate() -> Result<Vec<TestVector>, ModelsError> {
+    let keypair = KeyPair::from_bytes(&[1; 32])?;
+    let recipient = Address::from_public_key(&KeyPair::from_bytes(&[2; 32])?.get_public_key());
+
+    let transaction: WrappedOperation = OperationBuilder::new(OperationType::Transaction {
+        recipient_address: recipient,
+        amount: Amount::from_raw(300_000_000_000),
+    })
+    .fee(Amount::from_raw(10_000_000))
+    .expire_period(10)
+    .build(&keypair)?;
+    let roll_buy = OperationBuilder::new(OperationType::RollBuy { roll_count: 3 })
+        .expire_period(10)
+        .build(&keypair)?;
+    let roll_sell = OperationBuilder::new(OperationType::RollSell { roll_count: 1 })
+        .fee(Amount::from_raw(1))
+        .expire_period(u64::MAX)
+        .build(&keypair)?;
+    let execute_sc = OperationBuilder::new(OperationType::ExecuteSC {
+        data: vec![1, 2, 3],
+        max_gas: 100_000,
+        datastore: BTreeMap::from([(vec![1], vec![2, 3])]),
+    })
+    .expire_period(200)
+    .build(&keypair)?;
+    let call_sc = OperationBuilder::new(OperationType::CallSC {
+        target_addr: recipient,
+        target_func: "transfer".to_string(),
+        param: vec![4, 5],
+        max_gas: 10_000,
+        coins: Amount::from_raw(1_000_000_000),
+    })
+    .fee(Amount::from_raw(1_000))
+    .expire_period(300)
+    .build(&keypair)?;
+
+    let parents: Vec<BlockId> = ["parent 0", "parent 1"]
+        .iter()
+        .map(|parent| BlockId(Hash::compute_from(parent.as_bytes())))
+        .collect();
+    let slot = Slot::new(4, 1);
+    let endorsement: WrappedEndorsement = Endorsement::new_wrapped(
+        Endorsement {
+            slot,
+            index: 7,
+            endorsed_block: parents[1],
+        },
+        EndorsementSerializer::new(),
+        &keypair,
+    )?;
+
+    let genesis_header: WrappedHeader = BlockHeader::new_wrapped(
+        BlockHeader {
+            slot: Slot::new(0, 0),
+            parents: Vec::new(),
+            operation_merkle_root: compute_operations_hash(&[] as &[OperationId]),
+            endorsements: Vec::new(),
+            state_commitment: None,
+        },
+        BlockHeaderSerializer::new(),
+        &keypair,
+    )?;
+
+    let limits = BlockLimits {
+        thread_count: 2,
+        ..Default::default()
+    };
+    let mut builder = BlockBuilder::new(slot, limits)
+        .parents(parents)
+        .endorsements(vec![endorsement.clone()]);
+    builder.add_operation(&transaction)?;
+    builder.add_operation(&call_sc)?;
+    let block = builder.build(&keypair)?;
+    let committed_block = BlockBuilder::new(slot, limits)
+        .parents(block.content.header.content.parents.clone())
+        .state_commitment(Some(StateCommitment {
+            slot: Slot::new(3, 1),
+            final_state_hash: Hash::compute_from(b"final state"),
+            ledger_hash: Hash::compute_from(b"ledger"),
+            async_pool_hash: Hash::compute_from(b"async pool"),
+            deferred_credits_hash: Hash::compute_from(b"deferred credits"),
+            executed_ops_hash: Hash::compute_from(b"executed operations"),
+        }))
+        .build(&keypair)?;
+
+    Ok(vec![
+        TestVector::new("operation_transaction", &transaction)?,
+        TestVector::new("operation_roll_buy", &roll_buy)?,
+        TestVector::new("operation_roll_sell", &roll_sell)?,
+        TestVector::new("operation_execute_sc", &execute_sc)?,
+        TestVector::new("operation_call_sc", &call_sc)?,
+        TestVector::new("endorsement", &endorsement)?,
+        TestVector::new("block_header_genesis", &genesis_header)?,
+        TestVector::new("block_header", &block.content.header)?,
+        TestVector::new("block", &block)?,
+        TestVector::new("block_with_state_commitment", &committed_block)?,
+    ])
+}
+
+/// Content of the golden file for `vectors`, one vector per line after the comments
+pub fn to_golden_file(vectors: &[TestVector]) -> String {
+    let mut content = String::from(
+        "# Canonical serializations of the network objects: name, id and bytes in hexadecimal.\n\
+         # Generated by `cargo run -p massa_models --bin generate_test_vectors`, do not edit.\n",
+    );
+    for vector in vectors {
+        content.push_str(&vector.to_string());
+        content.push('\n');
+    }
+    content
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_serialization_golden_file() {
+        let golden: Vec<&str> = include_str!("../test_vectors/serialization.txt")
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .collect();
+        let vectors = generate().unwrap();
+        assert_eq!(
+            golden.len(),
+            vectors.len(),
+            "the golden file does not have one line per test vector"
+        );
+        for (line, vector) in golden.iter().zip(vectors.iter()) {
+            assert_eq!(
+                *line,
+                vector.to_string(),
+                "the serialization of {} changed: if the change of the network format is intended, \
+                 regenerate the golden file with `cargo run -p massa_models --bin generate_test_vectors`",
+                vector.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_vectors_are_deterministic() {
+        assert_eq!(generate().unwrap(), generate().unwrap());
+    }
+}
@@ -0,0 +1,144 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Scheduled upgrades of the protocol.
+//!
+//! A change of the protocol is shipped as a `ProtocolFeature` that is only active from the slot
+//! scheduled in the genesis of the network, so that all its nodes share the same schedule. Before that slot, the blocks using it are invalid
+//! and the node does not produce them, so the nodes can upgrade their software ahead of the activation.
+
+use crate::{block::BlockHeader, error::ModelsError, slot::Slot};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use strum::{Display, EnumIter, EnumString, IntoEnumIterator};
+
+/// Change of the protocol activated by a scheduled upgrade
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Deserialize,
+    Serialize,
+    Display,
+    EnumString,
+    EnumIter,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum ProtocolFeature {
+    /// the block headers commit to the final state of their producer
+    StateCommitment,
+}
+
+impl ProtocolFeature {
+    /// True if `header` uses the feature
+    pub fn is_used_by(&self, header: &BlockHeader) -> bool {
+        match self {
+            ProtocolFeature::StateCommitment => header.state_commitment.is_some(),
+        }
+    }
+}
+
+/// Slot from which each protocol feature is active, the features not scheduled are not active
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UpgradeSchedule(BTreeMap<ProtocolFeature, Slot>);
+
+impl UpgradeSchedule {
+    /// Schedule activating each feature at its slot
+    pub fn new(activation_slots: BTreeMap<ProtocolFeature, Slot>) -> Self {
+        UpgradeSchedule(activation_slots)
+    }
+
+    /// Schedule activating every feature at genesis, for the new networks and the tests
+    pub fn all_active() -> Self {
+        UpgradeSchedule(
+            ProtocolFeature::iter()
+                .map(|feature| (feature, Slot::new(0, 0)))
+                .collect(),
+        )
+    }
+
+    /// Iterate over the scheduled features and their activation slot, in feature order
+    pub fn iter(&self) -> impl Iterator<Item = (ProtocolFeature, Slot)> + '_ {
+        self.0.iter().map(|(feature, slot)| (*feature, *slot))
+    }
+
+    /// Slot from which `feature` is active, if it is scheduled
+    pub fn activation_slot(&self, feature: ProtocolFeature) -> Option<Slot> {
+        self.0.get(&feature).copied()
+    }
+
+    /// True if `feature` is active at `slot`
+    pub fn is_active(&self, feature: ProtocolFeature, slot: Slot) -> bool {
+        self.activation_slot(feature)
+            .map_or(false, |activation_slot| slot >= activation_slot)
+    }
+
+    /// Check that `header` only uses the features active at its slot
+    pub fn check_header(&self, header: &BlockHeader) -> Result<(), ModelsError> {
+        match ProtocolFeature::iter()
+            .find(|feature| feature.is_used_by(header) && !self.is_active(*feature, header.slot))
+        {
+            Some(feature) => Err(ModelsError::InactiveProtocolFeature(feature, header.slot)),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{block::BlockId, state_commitment::StateCommitment};
+    use massa_hash::Hash;
+
+    fn header(slot: Slot, state_commitment: bool) -> BlockHeader {
+        let hash = Hash::compute_from(b"hash");
+        BlockHeader {
+            slot,
+            parents: vec![BlockId(hash), BlockId(hash)],
+            operation_merkle_root: hash,
+            endorsements: Vec::new(),
+            state_commitment: state_commitment.then_some(StateCommitment {
+                slot: Slot::new(0, 0),
+                final_state_hash: hash,
+                ledger_hash: hash,
+                async_pool_hash: hash,
+                deferred_credits_hash: hash,
+                executed_ops_hash: hash,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_upgrade_schedule() {
+        let schedule = UpgradeSchedule::new(BTreeMap::from([(
+            ProtocolFeature::StateCommitment,
+            Slot::new(10, 1),
+        )]));
+        assert!(!schedule.is_active(ProtocolFeature::StateCommitment, Slot::new(10, 0)));
+        assert!(schedule.is_active(ProtocolFeature::StateCommitment, Slot::new(10, 1)));
+
+        // the headers not using the feature are valid before its activation
+        assert!(schedule
+            .check_header(&header(Slot::new(5, 0), false))
+            .is_ok());
+        assert!(schedule
+            .check_header(&header(Slot::new(5, 0), true))
+            .is_err());
+        assert!(schedule
+            .check_header(&header(Slot::new(11, 0), true))
+            .is_ok());
+
+        // a feature that is not scheduled is never active
+        assert!(UpgradeSchedule::default()
+            .check_header(&header(Slot::new(1000, 0), true))
+            .is_err());
+        assert!(UpgradeSchedule::all_active()
+            .check_header(&header(Slot::new(1, 0), true))
+            .is_ok());
+    }
+}
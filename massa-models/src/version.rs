@@ -17,7 +17,7 @@ use std::{convert::TryInto, fmt, str::FromStr};
 const INSTANCE_LEN: usize = 4;
 
 /// Application version, checked during handshakes
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Version {
     /// ASCII uppercase alpha
     instance: [char; INSTANCE_LEN],
@@ -191,6 +191,20 @@ impl Version {
     pub fn is_compatible(&self, other: &Version) -> bool {
         self.instance == other.instance && self.major == other.major
     }
+
+    /// true if instance is the same and self is not older than other
+    /// ```rust
+    /// # use massa_models::*;
+    /// # use std::str::FromStr;
+    /// let v: version::Version = version::Version::from_str("TEST.1.10").unwrap();
+    /// assert!(v.is_at_least(&version::Version::from_str("TEST.1.9").unwrap()));
+    /// assert!(v.is_at_least(&version::Version::from_str("TEST.0.12").unwrap()));
+    /// assert!(!v.is_at_least(&version::Version::from_str("TEST.2.0").unwrap()));
+    /// assert!(!v.is_at_least(&version::Version::from_str("SAND.1.0").unwrap()));
+    /// ```
+    pub fn is_at_least(&self, other: &Version) -> bool {
+        self.instance == other.instance && (self.major, self.minor) >= (other.major, other.minor)
+    }
 }
 
 impl fmt::Display for Version {
@@ -1,11 +1,12 @@
 use std::fmt::Display;
 
 use crate::{address::Address, error::ModelsError};
+use bytes::Bytes;
 use massa_hash::Hash;
-use massa_serialization::{Deserializer, SerializeError, Serializer};
+use massa_serialization::{share_bytes, Deserializer, SerializeError, Serializer};
 use massa_signature::{
-    KeyPair, PublicKey, PublicKeyDeserializer, Signature, SignatureDeserializer,
-    PUBLIC_KEY_SIZE_BYTES, SIGNATURE_SIZE_BYTES,
+    verify_signature_batch, PublicKey, PublicKeyDeserializer, Signature, SignatureDeserializer,
+    Signer, PUBLIC_KEY_SIZE_BYTES, SIGNATURE_SIZE_BYTES,
 };
 use nom::{
     error::{context, ContextError, ParseError},
@@ -32,8 +33,9 @@ where
     /// Id
     pub id: U,
     #[serde(skip)]
-    /// Content serialized
-    pub serialized_data: Vec<u8>,
+    /// Content serialized, a view of the receive buffer when deserialized with `massa_serialization::with_shared_buffer`
+    /// and making up most of that buffer
+    pub serialized_data: Bytes,
 }
 
 /// Used by signed structure
@@ -49,26 +51,31 @@ pub trait WrappedContent
 where
     Self: Sized + Display,
 {
-    /// Creates a wrapped version of the object
+    /// Get the part of `serialized_content` hashed into the id and signed, all of it by default
+    fn get_hashed_content(serialized_content: &[u8]) -> &[u8] {
+        serialized_content
+    }
+
+    /// Creates a wrapped version of the object, signed by `signer`
     fn new_wrapped<SC: Serializer<Self>, U: Id>(
         content: Self,
         content_serializer: SC,
-        keypair: &KeyPair,
+        signer: &dyn Signer,
     ) -> Result<Wrapped<Self, U>, ModelsError> {
         let mut content_serialized = Vec::new();
         content_serializer.serialize(&content, &mut content_serialized)?;
-        let mut hash_data = Vec::new();
-        let public_key = keypair.get_public_key();
-        hash_data.extend(public_key.to_bytes());
-        hash_data.extend(content_serialized.clone());
-        let hash = Hash::compute_from(&hash_data);
+        let public_key = signer.get_public_key();
+        let hash = Hash::compute_from_tuple(&[
+            &public_key.to_bytes()[..],
+            Self::get_hashed_content(&content_serialized),
+        ]);
         let creator_address = Address::from_public_key(&public_key);
         Ok(Wrapped {
-            signature: keypair.sign(&hash)?,
+            signature: signer.sign(&hash)?,
             creator_public_key: public_key,
             creator_address,
             content,
-            serialized_data: content_serialized,
+            serialized_data: content_serialized.into(),
             id: U::new(hash),
         })
     }
@@ -121,14 +128,16 @@ where
                         nom::error::ErrorKind::Fail,
                     ))
                 })?;
-            content_buffer
+            Bytes::from(content_buffer)
         } else {
             // Avoid getting the rest of the data in the serialized data
-            serialized_data[..serialized_data.len() - rest.len()].to_vec()
+            share_bytes(&serialized_data[..serialized_data.len() - rest.len()])
         };
         let creator_address = Address::from_public_key(&creator_public_key);
-        let mut serialized_full_data = creator_public_key.to_bytes().to_vec();
-        serialized_full_data.extend(&content_serialized);
+        let id = U::new(Hash::compute_from_tuple(&[
+            &creator_public_key.to_bytes()[..],
+            Self::get_hashed_content(&content_serialized),
+        ]));
         Ok((
             rest,
             Wrapped {
@@ -136,23 +145,34 @@ where
                 signature,
                 creator_public_key,
                 creator_address,
-                serialized_data: content_serialized.to_vec(),
-                id: U::new(Hash::compute_from(&serialized_full_data)),
+                serialized_data: content_serialized,
+                id,
             },
         ))
     }
 }
 
+/// Leave out of the hash the format version 0 prefixing `serialized_content`:
+/// it is the format of the contents serialized before the version prefix was added,
+/// whose ids must stay the same. The later versions are hashed with the rest of the content.
+pub(crate) fn skip_legacy_format_version(serialized_content: &[u8]) -> &[u8] {
+    // the varint of 0 is a single zero byte, no other varint starts with it
+    match serialized_content.split_first() {
+        Some((0, rest)) => rest,
+        _ => serialized_content,
+    }
+}
+
 impl<T, U> Display for Wrapped<T, U>
 where
     T: Display + WrappedContent,
-    U: Id,
+    U: Id + Display,
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "Signature: {}", self.signature)?;
         writeln!(f, "Creator pubkey: {}", self.creator_public_key)?;
         writeln!(f, "Creator address: {}", self.creator_address)?;
-        writeln!(f, "Id: {}", self.id.get_hash())?;
+        writeln!(f, "Id: {}", self.id)?;
         writeln!(f, "{}", self.content)?;
         Ok(())
     }
@@ -170,6 +190,22 @@ where
             .verify_signature(self.id.get_hash(), &self.signature)?)
     }
 
+    /// check the signatures of all the `wrapped` at once, which is faster than checking them one by one.
+    /// Fails if at least one of them has not been signed by its creator public key.
+    pub fn verify_signatures_batch<'a>(
+        wrapped: impl IntoIterator<Item = &'a Self>,
+    ) -> Result<(), ModelsError>
+    where
+        T: 'a,
+        U: 'a,
+    {
+        let batch: Vec<(Hash, Signature, PublicKey)> = wrapped
+            .into_iter()
+            .map(|item| (*item.id.get_hash(), item.signature, item.creator_public_key))
+            .collect();
+        Ok(verify_signature_batch(&batch)?)
+    }
+
     /// get full serialized size
     pub fn serialized_size(&self) -> usize {
         self.serialized_data
@@ -0,0 +1,508 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Bounded channels applying a backpressure policy to each message.
+//!
+//! When the receiver is slower than the senders, the channel fills up and each message sent
+//! is handled according to its policy:
+//! * gossip (`BackpressurePolicy::DropOldest`) is only useful while fresh: sending it never waits,
+//!   it takes the place of the oldest queued gossip, or is dropped if only consensus-critical
+//!   messages are queued.
+//! * consensus-critical messages (`BackpressurePolicy::Block`) are never dropped to make room:
+//!   they take the place of the oldest queued gossip, or their sender waits for room,
+//!   which slows down the producer instead of growing the queue.
+//!
+//! A channel is bounded by a number of messages, and optionally by the total size of the queued messages
+//! as estimated by `Backpressure::queued_size`. A sender that must not wait uses `try_send`, which hands
+//! the consensus-critical messages that do not fit back to the caller instead.
+//!
+//! Messages are received in the order they were sent. The queue length, the saturated sends
+//! and the dropped messages of each channel are exported in `massa_metrics::NETWORK_METRICS`.
+//! The channels created with the same name share their metrics.
+
+use massa_metrics::{ChannelMetrics, NETWORK_METRICS};
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::error::{SendError, SendTimeoutError, TrySendError};
+use tokio::sync::{Notify, Semaphore};
+
+/// What happens to a message sent to a full channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackpressurePolicy {
+    /// take the place of the oldest queued `DropOldest` message, or be dropped if there is none
+    DropOldest,
+    /// take the place of the oldest queued `DropOldest` message, or wait for room
+    Block,
+}
+
+/// Message that can be sent through a backpressure channel
+pub trait Backpressure {
+    /// Policy applied to the message when the channel is full
+    fn backpressure_policy(&self) -> BackpressurePolicy;
+
+    /// Approximate size in bytes of the message, counted in the byte limit of the channel
+    fn queued_size(&self) -> usize {
+        0
+    }
+}
+
+/// Message waiting in the queue
+struct Queued<T> {
+    policy: BackpressurePolicy,
+    size: usize,
+    message: T,
+}
+
+/// Queued messages and ends of the channel that are still alive
+struct Queue<T> {
+    /// queued messages, oldest first
+    messages: VecDeque<Queued<T>>,
+    /// total size of the queued messages
+    bytes: usize,
+    /// number of senders alive
+    sender_count: usize,
+    /// false once the receiver is dropped
+    receiver_alive: bool,
+}
+
+/// State shared by the ends of the channel
+struct Shared<T> {
+    queue: Mutex<Queue<T>>,
+    /// one permit per free slot of the queue, closed once the receiver is dropped
+    room: Semaphore,
+    /// max total size of the queued messages
+    max_bytes: usize,
+    /// notified when a message is queued or the last sender is dropped
+    message_notify: Notify,
+    metrics: ChannelMetrics,
+}
+
+impl<T> Shared<T> {
+    /// Queue a message that has a slot, and wake the receiver up
+    fn push(&self, queue: &mut Queue<T>, policy: BackpressurePolicy, size: usize, message: T) {
+        queue.messages.push_back(Queued {
+            policy,
+            size,
+            message,
+        });
+        queue.bytes += size;
+        self.metrics.queued_messages.inc();
+        self.metrics.queued_bytes.add(size as i64);
+        self.message_notify.notify_one();
+    }
+
+    /// Remove the queued message at `index`, its slot is not released
+    fn remove(&self, queue: &mut Queue<T>, index: usize) -> Option<T> {
+        let queued = queue.messages.remove(index)?;
+        queue.bytes -= queued.size;
+        self.metrics.queued_messages.dec();
+        self.metrics.queued_bytes.sub(queued.size as i64);
+        Some(queued.message)
+    }
+
+    /// Remove all the queued messages and release their slots
+    fn clear(&self, queue: &mut Queue<T>) {
+        let count = queue.messages.len();
+        queue.messages.clear();
+        self.metrics.queued_messages.sub(count as i64);
+        self.metrics.queued_bytes.sub(queue.bytes as i64);
+        queue.bytes = 0;
+        self.room.add_permits(count);
+    }
+
+    /// Whether `size` more bytes fit in the byte limit. A message alone in the queue always fits.
+    fn fits(&self, queue: &Queue<T>, size: usize) -> bool {
+        queue.messages.is_empty() || queue.bytes.saturating_add(size) <= self.max_bytes
+    }
+
+    /// Queue a message if there is room for it, dropping the oldest queued gossip to make room if needed.
+    /// Returns the message if there is not enough room even without the queued gossip, no gossip being dropped then.
+    fn try_push(
+        &self,
+        queue: &mut Queue<T>,
+        policy: BackpressurePolicy,
+        size: usize,
+        message: T,
+    ) -> Result<(), T> {
+        let mut has_slot = match self.room.try_acquire() {
+            Ok(permit) => {
+                permit.forget();
+                true
+            }
+            Err(_) => false,
+        };
+        if has_slot && self.fits(queue, size) {
+            self.push(queue, policy, size, message);
+            return Ok(());
+        }
+        self.metrics.saturated_sends.inc();
+
+        let (gossip_count, gossip_bytes) = queue
+            .messages
+            .iter()
+            .filter(|queued| queued.policy == BackpressurePolicy::DropOldest)
+            .fold((0, 0), |(count, bytes), queued| {
+                (count + 1, bytes + queued.size)
+            });
+        let fits_without_gossip = gossip_count == queue.messages.len()
+            || (queue.bytes - gossip_bytes).saturating_add(size) <= self.max_bytes;
+        if (!has_slot && gossip_count == 0) || !fits_without_gossip {
+            if has_slot {
+                self.room.add_permits(1);
+            }
+            return Err(message);
+        }
+        // make room by dropping the oldest queued gossip, the message takes the slot of the first one
+        while !(has_slot && self.fits(queue, size)) {
+            let index = queue
+                .messages
+                .iter()
+                .position(|queued| queued.policy == BackpressurePolicy::DropOldest)
+                .expect("critical: enough gossip to make room checked above");
+            self.remove(queue, index);
+            self.metrics.evicted_messages.inc();
+            if has_slot {
+                self.room.add_permits(1);
+            } else {
+                has_slot = true;
+            }
+        }
+        self.push(queue, policy, size, message);
+        Ok(())
+    }
+}
+
+/// Create a backpressure channel holding at most `capacity` messages.
+/// `name` labels the metrics of the channel.
+pub fn backpressure_channel<T: Backpressure>(
+    name: &str,
+    capacity: usize,
+) -> (BackpressureSender<T>, BackpressureReceiver<T>) {
+    bounded_backpressure_channel(name, capacity, usize::MAX)
+}
+
+/// Create a backpressure channel holding at most `capacity` messages of at most `max_bytes` bytes in total.
+/// `name` labels the metrics of the channel.
+pub fn bounded_backpressure_channel<T: Backpressure>(
+    name: &str,
+    capacity: usize,
+    max_bytes: usize,
+) -> (BackpressureSender<T>, BackpressureReceiver<T>) {
+    let shared = Arc::new(Shared {
+        queue: Mutex::new(Queue {
+            messages: VecDeque::with_capacity(capacity),
+            bytes: 0,
+            sender_count: 1,
+            receiver_alive: true,
+        }),
+        room: Semaphore::new(capacity),
+        max_bytes,
+        message_notify: Notify::new(),
+        metrics: NETWORK_METRICS.channel(name),
+    });
+    (
+        BackpressureSender {
+            shared: shared.clone(),
+        },
+        BackpressureReceiver { shared },
+    )
+}
+
+/// Sending end of a backpressure channel
+pub struct BackpressureSender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T: Backpressure> BackpressureSender<T> {
+    /// Send a message, a `Block` one waiting for room as long as needed.
+    /// A gossip message dropped because of its policy is not an error, it is accounted in the metrics.
+    pub async fn send(&self, message: T) -> Result<(), SendError<T>> {
+        self.send_with_timeout(message, None)
+            .await
+            .map_err(|err| match err {
+                SendTimeoutError::Closed(message) | SendTimeoutError::Timeout(message) => {
+                    SendError(message)
+                }
+            })
+    }
+
+    /// Send a message, a `Block` one waiting for room at most `timeout`.
+    /// A gossip message dropped because of its policy is not an error, it is accounted in the metrics.
+    pub async fn send_timeout(
+        &self,
+        message: T,
+        timeout: Duration,
+    ) -> Result<(), SendTimeoutError<T>> {
+        self.send_with_timeout(message, Some(timeout)).await
+    }
+
+    /// Send a message without waiting: a `Block` message that does not fit is returned as `TrySendError::Full`.
+    /// A gossip message dropped because of its policy is not an error, it is accounted in the metrics.
+    pub fn try_send(&self, message: T) -> Result<(), TrySendError<T>> {
+        let shared = &self.shared;
+        let policy = message.backpressure_policy();
+        let size = message.queued_size();
+        let mut queue = shared.queue.lock();
+        if !queue.receiver_alive {
+            return Err(TrySendError::Closed(message));
+        }
+        match shared.try_push(&mut queue, policy, size, message) {
+            Ok(()) => Ok(()),
+            Err(_) if policy == BackpressurePolicy::DropOldest => {
+                shared.metrics.rejected_messages.inc();
+                Ok(())
+            }
+            Err(message) => {
+                shared.metrics.overflowed_messages.inc();
+                Err(TrySendError::Full(message))
+            }
+        }
+    }
+
+    /// Drop all the queued messages and queue `message` instead, whatever its size.
+    /// Used to get a message through without waiting for the receiver to go through the queue.
+    pub fn clear_and_send(&self, message: T) -> Result<(), SendError<T>> {
+        let shared = &self.shared;
+        let policy = message.backpressure_policy();
+        let size = message.queued_size();
+        let mut queue = shared.queue.lock();
+        if !queue.receiver_alive {
+            return Err(SendError(message));
+        }
+        shared
+            .metrics
+            .evicted_messages
+            .inc_by(queue.messages.len() as u64);
+        shared.clear(&mut queue);
+        shared
+            .try_push(&mut queue, policy, size, message)
+            .map_err(SendError)
+    }
+
+    async fn send_with_timeout(
+        &self,
+        message: T,
+        timeout: Option<Duration>,
+    ) -> Result<(), SendTimeoutError<T>> {
+        let shared = &self.shared;
+        let policy = message.backpressure_policy();
+        let size = message.queued_size();
+        let message = {
+            let mut queue = shared.queue.lock();
+            if !queue.receiver_alive {
+                return Err(SendTimeoutError::Closed(message));
+            }
+            match shared.try_push(&mut queue, policy, size, message) {
+                Ok(()) => return Ok(()),
+                Err(_) if policy == BackpressurePolicy::DropOldest => {
+                    shared.metrics.rejected_messages.inc();
+                    return Ok(());
+                }
+                Err(message) => message,
+            }
+        };
+
+        // only consensus-critical messages are queued: wait for the receiver to take one.
+        // The message is then queued even if the byte limit is still exceeded.
+        let acquired = match timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, shared.room.acquire()).await {
+                Ok(acquired) => acquired,
+                Err(_) => {
+                    shared.metrics.timed_out_messages.inc();
+                    return Err(SendTimeoutError::Timeout(message));
+                }
+            },
+            None => shared.room.acquire().await,
+        };
+        match acquired {
+            Ok(permit) => permit.forget(),
+            Err(_) => return Err(SendTimeoutError::Closed(message)),
+        }
+        let mut queue = shared.queue.lock();
+        if !queue.receiver_alive {
+            return Err(SendTimeoutError::Closed(message));
+        }
+        shared.push(&mut queue, policy, size, message);
+        Ok(())
+    }
+}
+
+impl<T> Clone for BackpressureSender<T> {
+    fn clone(&self) -> Self {
+        self.shared.queue.lock().sender_count += 1;
+        BackpressureSender {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Drop for BackpressureSender<T> {
+    fn drop(&mut self) {
+        let mut queue = self.shared.queue.lock();
+        queue.sender_count -= 1;
+        if queue.sender_count == 0 {
+            self.shared.message_notify.notify_one();
+        }
+    }
+}
+
+/// Receiving end of a backpressure channel
+pub struct BackpressureReceiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> BackpressureReceiver<T> {
+    /// Receive the oldest queued message, waiting for one if needed.
+    /// Returns `None` once all the senders are dropped and the queue is empty.
+    pub async fn recv(&mut self) -> Option<T> {
+        loop {
+            {
+                let mut queue = self.shared.queue.lock();
+                if let Some(message) = self.shared.remove(&mut queue, 0) {
+                    self.shared.room.add_permits(1);
+                    return Some(message);
+                }
+                if queue.sender_count == 0 {
+                    return None;
+                }
+            }
+            // a notification sent since the queue was unlocked is kept for this call
+            self.shared.message_notify.notified().await;
+        }
+    }
+}
+
+impl<T> Drop for BackpressureReceiver<T> {
+    fn drop(&mut self) {
+        let mut queue = self.shared.queue.lock();
+        queue.receiver_alive = false;
+        self.shared.clear(&mut queue);
+        // wake the waiting senders up, they fail
+        self.shared.room.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Eq)]
+    enum Message {
+        Gossip(u32),
+        Critical(u32),
+    }
+
+    impl Backpressure for Message {
+        fn backpressure_policy(&self) -> BackpressurePolicy {
+            match self {
+                Message::Gossip(_) => BackpressurePolicy::DropOldest,
+                Message::Critical(_) => BackpressurePolicy::Block,
+            }
+        }
+
+        fn queued_size(&self) -> usize {
+            match self {
+                Message::Gossip(size) | Message::Critical(size) => *size as usize,
+            }
+        }
+    }
+
+    async fn receive_all(rx: &mut BackpressureReceiver<Message>) -> Vec<Message> {
+        let mut received = Vec::new();
+        while let Ok(Some(message)) =
+            tokio::time::timeout(Duration::from_millis(10), rx.recv()).await
+        {
+            received.push(message);
+        }
+        received
+    }
+
+    #[tokio::test]
+    async fn test_full_channel_drops_oldest_gossip() {
+        let (tx, mut rx) = backpressure_channel("test_drops_oldest_gossip", 3);
+        for message in [
+            Message::Gossip(0),
+            Message::Critical(1),
+            Message::Gossip(2),
+            Message::Gossip(3),
+            Message::Critical(4),
+        ] {
+            tx.send(message).await.unwrap();
+        }
+        assert_eq!(
+            receive_all(&mut rx).await,
+            vec![
+                Message::Critical(1),
+                Message::Gossip(3),
+                Message::Critical(4)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_full_channel_of_critical_messages() {
+        let (tx, mut rx) = backpressure_channel("test_critical_messages", 2);
+        tx.send(Message::Critical(0)).await.unwrap();
+        tx.send(Message::Critical(1)).await.unwrap();
+        // gossip is dropped without waiting
+        tx.send(Message::Gossip(2)).await.unwrap();
+        // a critical message waits for room
+        assert!(matches!(
+            tx.send_timeout(Message::Critical(3), Duration::from_millis(10))
+                .await,
+            Err(SendTimeoutError::Timeout(Message::Critical(3)))
+        ));
+        let sender = tokio::spawn(async move {
+            tx.send(Message::Critical(4)).await.unwrap();
+        });
+        assert_eq!(rx.recv().await, Some(Message::Critical(0)));
+        sender.await.unwrap();
+        assert_eq!(
+            receive_all(&mut rx).await,
+            vec![Message::Critical(1), Message::Critical(4)]
+        );
+        // all the senders are dropped
+        assert_eq!(rx.recv().await, None);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_receiver_fails_waiting_senders() {
+        let (tx, rx) = backpressure_channel("test_dropped_receiver", 1);
+        tx.send(Message::Critical(0)).await.unwrap();
+        let sender = tokio::spawn(async move { tx.send(Message::Critical(1)).await });
+        tokio::task::yield_now().await;
+        drop(rx);
+        assert_eq!(
+            sender.await.unwrap().map_err(|SendError(message)| message),
+            Err(Message::Critical(1))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_byte_limit() {
+        let (tx, mut rx) = bounded_backpressure_channel("test_byte_limit", 10, 100);
+        tx.try_send(Message::Gossip(40)).unwrap();
+        tx.try_send(Message::Critical(50)).unwrap();
+        // the oldest gossip makes room
+        tx.try_send(Message::Gossip(30)).unwrap();
+        // gossip that does not fit even without the queued gossip is dropped, the queued gossip is kept
+        tx.try_send(Message::Gossip(60)).unwrap();
+        // a consensus-critical message that does not fit is handed back
+        assert!(matches!(
+            tx.try_send(Message::Critical(60)),
+            Err(TrySendError::Full(Message::Critical(60)))
+        ));
+        assert_eq!(
+            receive_all(&mut rx).await,
+            vec![Message::Critical(50), Message::Gossip(30)]
+        );
+
+        // a message larger than the limit goes through an empty queue
+        tx.try_send(Message::Critical(150)).unwrap();
+        tx.try_send(Message::Gossip(10)).unwrap();
+        // the queued messages are replaced
+        tx.clear_and_send(Message::Critical(1)).unwrap();
+        assert_eq!(receive_all(&mut rx).await, vec![Message::Critical(1)]);
+    }
+}
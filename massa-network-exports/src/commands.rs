@@ -69,25 +69,36 @@
 //! Look at `massa-protocol-worker/src/node-info.rs` to look further how we
 //! remember which node know what.
 
-use crate::{BootstrapPeers, ConnectionClosureReason, Peers};
+use crate::{
+    AdvertisedPeer, Backpressure, BackpressurePolicy, BanReason, BootstrapPeers,
+    ConnectionClosureReason, NetworkError, NetworkReloadableConfig, PeerFeedback, Peers,
+};
 use massa_models::{
-    block::{BlockId, WrappedHeader},
+    api::PeerConnectionEvent,
+    ban_list::{BanList, IpSubnet},
+    block::{ArchivedBlock, BlockId, WrappedHeader},
     composite::PubkeySig,
     endorsement::WrappedEndorsement,
+    network_faults::{MessageFaults, NetworkFaults},
     node::NodeId,
-    operation::{OperationId, OperationPrefixIds, WrappedOperation},
+    operation::{OperationId, OperationPrefixId, OperationPrefixIds, WrappedOperation},
+    slot::Slot,
     stats::NetworkStats,
 };
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, net::IpAddr};
+use std::{
+    collections::HashMap,
+    mem::size_of,
+    net::{IpAddr, SocketAddr},
+};
 use tokio::sync::oneshot;
 
 /// network command
 #[derive(Clone, Debug)]
 #[allow(clippy::large_enum_variant)]
 pub enum NodeCommand {
-    /// Send given peer list to node.
-    SendPeerList(Vec<IpAddr>),
+    /// Send given peer list to node, with the addresses of each peer.
+    SendPeerList(Vec<AdvertisedPeer>),
     /// Send the header of a block to a node.
     SendBlockHeader(WrappedHeader),
     /// Ask for info on a list of blocks.
@@ -106,6 +117,112 @@ pub enum NodeCommand {
     SendEndorsements(Vec<WrappedEndorsement>),
     /// Ask peer list
     AskPeerList,
+    /// Ask an archival node for the final blocks of a slot range
+    AskForBlockRange {
+        /// first slot of the range
+        start: Slot,
+        /// end of the range, excluded
+        end: Slot,
+    },
+    /// Send final blocks to a node that asked for a slot range
+    SendBlockRange(Vec<ArchivedBlock>),
+    /// Ask an archival node for the headers of the final blocks of a slot range
+    AskForHeaderRange {
+        /// first slot of the range
+        start: Slot,
+        /// end of the range, excluded
+        end: Slot,
+    },
+    /// Send headers of final blocks to a node that asked for a slot range
+    SendHeaderRange(Vec<WrappedHeader>),
+}
+
+impl Backpressure for NodeCommand {
+    /// Blocks, their headers and the closing orders are consensus-critical,
+    /// operations, endorsements and peer lists are gossip
+    fn backpressure_policy(&self) -> BackpressurePolicy {
+        match self {
+            NodeCommand::SendBlockHeader(_)
+            | NodeCommand::AskForBlocks(_)
+            | NodeCommand::ReplyForBlocks(_)
+            | NodeCommand::Close(_)
+            | NodeCommand::AskForBlockRange { .. }
+            | NodeCommand::SendBlockRange(_)
+            | NodeCommand::AskForHeaderRange { .. }
+            | NodeCommand::SendHeaderRange(_) => BackpressurePolicy::Block,
+            NodeCommand::SendPeerList(_)
+            | NodeCommand::SendOperations(_)
+            | NodeCommand::SendOperationAnnouncements(_)
+            | NodeCommand::AskForOperations(_)
+            | NodeCommand::SendEndorsements(_)
+            | NodeCommand::AskPeerList => BackpressurePolicy::DropOldest,
+        }
+    }
+
+    /// Size of the serialized objects and of the ids carried by the command
+    fn queued_size(&self) -> usize {
+        match self {
+            NodeCommand::SendPeerList(peers) => peers
+                .iter()
+                .map(|peer| (1 + peer.other_addresses.len()) * size_of::<SocketAddr>())
+                .sum(),
+            NodeCommand::SendBlockHeader(header) => header.serialized_data.len(),
+            NodeCommand::AskForBlocks(list) => list
+                .iter()
+                .map(|(_, info)| match info {
+                    AskForBlocksInfo::Operations(ids) => {
+                        size_of::<BlockId>() + ids.len() * size_of::<OperationId>()
+                    }
+                    AskForBlocksInfo::Header | AskForBlocksInfo::Info => size_of::<BlockId>(),
+                })
+                .sum(),
+            NodeCommand::ReplyForBlocks(list) => list
+                .iter()
+                .map(|(_, reply)| {
+                    size_of::<BlockId>()
+                        + match reply {
+                            BlockInfoReply::Header(header) => header.serialized_data.len(),
+                            BlockInfoReply::Info(ids) => ids.len() * size_of::<OperationId>(),
+                            BlockInfoReply::Operations(operations) => operations
+                                .iter()
+                                .map(|operation| operation.serialized_data.len())
+                                .sum(),
+                            BlockInfoReply::NotFound => 0,
+                        }
+                })
+                .sum(),
+            NodeCommand::SendOperations(operations) => operations
+                .iter()
+                .map(|operation| operation.serialized_data.len())
+                .sum(),
+            NodeCommand::SendOperationAnnouncements(ids) | NodeCommand::AskForOperations(ids) => {
+                ids.len() * size_of::<OperationPrefixId>()
+            }
+            NodeCommand::SendEndorsements(endorsements) => endorsements
+                .iter()
+                .map(|endorsement| endorsement.serialized_data.len())
+                .sum(),
+            NodeCommand::SendBlockRange(blocks) => blocks
+                .iter()
+                .map(|block| {
+                    block.header.serialized_data.len()
+                        + block
+                            .operations
+                            .iter()
+                            .map(|operation| operation.serialized_data.len())
+                            .sum::<usize>()
+                })
+                .sum(),
+            NodeCommand::SendHeaderRange(headers) => headers
+                .iter()
+                .map(|header| header.serialized_data.len())
+                .sum(),
+            NodeCommand::Close(_)
+            | NodeCommand::AskPeerList
+            | NodeCommand::AskForBlockRange { .. }
+            | NodeCommand::AskForHeaderRange { .. } => 0,
+        }
+    }
 }
 
 /// Event types that node worker can emit
@@ -116,8 +233,8 @@ pub enum NodeCommand {
 pub enum NodeEventType {
     /// Node we are connected to asked for advertised peers
     AskedPeerList,
-    /// Node we are connected to sent peer list
-    ReceivedPeerList(Vec<IpAddr>),
+    /// Node we are connected to sent peer list, with the addresses of each peer
+    ReceivedPeerList(Vec<AdvertisedPeer>),
     /// Node we are connected to sent block header
     ReceivedBlockHeader(WrappedHeader),
     /// Node we are connected asked for info on a list of blocks.
@@ -132,6 +249,24 @@ pub enum NodeEventType {
     ReceivedAskForOperations(OperationPrefixIds),
     /// Receive a set of endorsement
     ReceivedEndorsements(Vec<WrappedEndorsement>),
+    /// Node we are connected to asked for the final blocks of a slot range
+    ReceivedAskForBlockRange {
+        /// first slot of the range
+        start: Slot,
+        /// end of the range, excluded
+        end: Slot,
+    },
+    /// Node we are connected to sent final blocks of a slot range
+    ReceivedBlockRange(Vec<ArchivedBlock>),
+    /// Node we are connected to asked for the headers of the final blocks of a slot range
+    ReceivedAskForHeaderRange {
+        /// first slot of the range
+        start: Slot,
+        /// end of the range, excluded
+        end: Slot,
+    },
+    /// Node we are connected to sent headers of final blocks of a slot range
+    ReceivedHeaderRange(Vec<WrappedHeader>),
 }
 
 /// Events node worker can emit.
@@ -139,6 +274,27 @@ pub enum NodeEventType {
 #[derive(Clone, Debug)]
 pub struct NodeEvent(pub NodeId, pub NodeEventType);
 
+impl Backpressure for NodeEvent {
+    /// Blocks and their headers are consensus-critical, operations, endorsements, peer lists and block or header ranges are gossip
+    fn backpressure_policy(&self) -> BackpressurePolicy {
+        match self.1 {
+            NodeEventType::ReceivedBlockHeader(_)
+            | NodeEventType::ReceivedAskForBlocks(_)
+            | NodeEventType::ReceivedReplyForBlocks(_) => BackpressurePolicy::Block,
+            NodeEventType::AskedPeerList
+            | NodeEventType::ReceivedPeerList(_)
+            | NodeEventType::ReceivedOperations(_)
+            | NodeEventType::ReceivedOperationAnnouncements(_)
+            | NodeEventType::ReceivedAskForOperations(_)
+            | NodeEventType::ReceivedEndorsements(_)
+            | NodeEventType::ReceivedAskForBlockRange { .. }
+            | NodeEventType::ReceivedBlockRange(_)
+            | NodeEventType::ReceivedAskForHeaderRange { .. }
+            | NodeEventType::ReceivedHeaderRange(_) => BackpressurePolicy::DropOldest,
+        }
+    }
+}
+
 /// Ask for the info about a block.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub enum AskForBlocksInfo {
@@ -178,14 +334,45 @@ pub enum NetworkCommand {
     GetPeers(oneshot::Sender<Peers>),
     /// get peers for bootstrap server
     GetBootstrapPeers(oneshot::Sender<BootstrapPeers>),
-    /// Ban a list of peer by their node id
-    NodeBanByIds(Vec<NodeId>),
-    /// Ban a list of peer by their ip address
-    NodeBanByIps(Vec<IpAddr>),
+    /// Ban a list of peer by their node id, for a reason
+    NodeBanByIds(Vec<NodeId>, BanReason),
+    /// Ban a list of peer by their ip address, for a reason
+    NodeBanByIps(Vec<IpAddr>, BanReason),
+    /// Feed observations of the protocol on the blocks of nodes back into the reputation of their peers
+    PeerFeedback(Vec<(NodeId, PeerFeedback)>),
     /// Unban a list of peer by their node id
     NodeUnbanByIds(Vec<NodeId>),
     /// Unban a list of peer by their ip address
     NodeUnbanByIps(Vec<IpAddr>),
+    /// Unban a list of subnets, with the ip addresses and smaller subnets they contain
+    NodeUnbanBySubnets(Vec<IpSubnet>),
+    /// Get the banned ip addresses and subnets
+    GetBanList(oneshot::Sender<BanList>),
+    /// Merge bans exported by another node, closing the connections of the newly banned ips
+    ImportBanList(BanList),
+    /// Get the last ended connections of a peer, oldest first
+    GetPeerHistory {
+        /// ip of the peer
+        ip: IpAddr,
+        /// response channel
+        response_tx: oneshot::Sender<Vec<PeerConnectionEvent>>,
+    },
+    /// Dump the peers file right away, whatever the dump interval
+    FlushPeers(oneshot::Sender<Result<(), NetworkError>>),
+    /// Read the peers file again and merge it with the known peers and bans,
+    /// closing the connections of the newly banned ips
+    ReloadPeers(oneshot::Sender<Result<(), NetworkError>>),
+    /// Set the faults injected in the messages sent to a peer, or to all the peers if `node_id` is `None`,
+    /// returning all the faults then injected.
+    /// Refused by the nodes not built with the `fault_injection` feature.
+    SetFaults {
+        /// peer whose messages get the faults
+        node_id: Option<NodeId>,
+        /// faults to inject, `None` to remove them
+        faults: Option<MessageFaults>,
+        /// response channel
+        response_tx: oneshot::Sender<Result<NetworkFaults, NetworkError>>,
+    },
     /// Send endorsements to a node
     SendEndorsements {
         /// to node id
@@ -227,10 +414,45 @@ pub enum NetworkCommand {
         /// operation ids in the wish list
         wishlist: OperationPrefixIds,
     },
+    /// Ask an archival node for the final blocks of a slot range
+    AskForBlockRange {
+        /// to node id
+        node: NodeId,
+        /// first slot of the range
+        start: Slot,
+        /// end of the range, excluded
+        end: Slot,
+    },
+    /// Send final blocks to a node that asked for a slot range
+    SendBlockRange {
+        /// to node id
+        node: NodeId,
+        /// blocks, by ascending slot
+        blocks: Vec<ArchivedBlock>,
+    },
+    /// Ask an archival node for the headers of the final blocks of a slot range
+    AskForHeaderRange {
+        /// to node id
+        node: NodeId,
+        /// first slot of the range
+        start: Slot,
+        /// end of the range, excluded
+        end: Slot,
+    },
+    /// Send headers of final blocks to a node that asked for a slot range
+    SendHeaderRange {
+        /// to node id
+        node: NodeId,
+        /// headers, by ascending slot
+        headers: Vec<WrappedHeader>,
+    },
     /// Whitelist a list of `IpAddr`
     Whitelist(Vec<IpAddr>),
     /// Remove from whitelist a list of `IpAddr`
     RemoveFromWhitelist(Vec<IpAddr>),
+    /// Apply new connection limits and peers dump interval.
+    /// Connections above the new limits are not closed, they are just not replaced.
+    UpdateConfig(NetworkReloadableConfig),
 }
 
 /// A node replied with info about a block.
@@ -252,7 +474,14 @@ pub enum BlockInfoReply {
 #[derive(Debug)]
 pub enum NetworkEvent {
     /// new connection from node
-    NewConnection(NodeId),
+    NewConnection {
+        /// node id
+        node: NodeId,
+        /// true if the node announced itself as an observer during handshake
+        observer: bool,
+        /// true if we dialed the node
+        outgoing: bool,
+    },
     /// connection to node was closed
     ConnectionClosed(NodeId),
     /// Info about a block was received
@@ -268,6 +497,8 @@ pub enum NetworkEvent {
         source_node_id: NodeId,
         /// header
         header: WrappedHeader,
+        /// span of the reception of the block, continued by the protocol and the consensus while they process it
+        span: tracing::Span,
     },
     /// Someone ask for block with given header hash.
     AskedForBlocks {
@@ -304,8 +535,65 @@ pub enum NetworkEvent {
         /// Endorsements
         endorsements: Vec<WrappedEndorsement>,
     },
+    /// Someone asked for the final blocks of a slot range
+    AskedForBlockRange {
+        /// node id
+        node: NodeId,
+        /// first slot of the range
+        start: Slot,
+        /// end of the range, excluded
+        end: Slot,
+    },
+    /// Received final blocks of a slot range
+    ReceivedBlockRange {
+        /// node id
+        node: NodeId,
+        /// blocks, by ascending slot
+        blocks: Vec<ArchivedBlock>,
+    },
+    /// Someone asked for the headers of the final blocks of a slot range
+    AskedForHeaderRange {
+        /// node id
+        node: NodeId,
+        /// first slot of the range
+        start: Slot,
+        /// end of the range, excluded
+        end: Slot,
+    },
+    /// Received headers of final blocks of a slot range
+    ReceivedHeaderRange {
+        /// node id
+        node: NodeId,
+        /// headers, by ascending slot
+        headers: Vec<WrappedHeader>,
+    },
+}
+
+impl Backpressure for NetworkEvent {
+    /// Connections, blocks and their headers are consensus-critical, operations, endorsements and block or header ranges are gossip
+    fn backpressure_policy(&self) -> BackpressurePolicy {
+        match self {
+            NetworkEvent::NewConnection { .. }
+            | NetworkEvent::ConnectionClosed(_)
+            | NetworkEvent::ReceivedBlockInfo { .. }
+            | NetworkEvent::ReceivedBlockHeader { .. }
+            | NetworkEvent::AskedForBlocks { .. } => BackpressurePolicy::Block,
+            NetworkEvent::ReceivedOperations { .. }
+            | NetworkEvent::ReceivedOperationAnnouncements { .. }
+            | NetworkEvent::ReceiveAskForOperations { .. }
+            | NetworkEvent::ReceivedEndorsements { .. }
+            | NetworkEvent::AskedForBlockRange { .. }
+            | NetworkEvent::ReceivedBlockRange { .. }
+            | NetworkEvent::AskedForHeaderRange { .. }
+            | NetworkEvent::ReceivedHeaderRange { .. } => BackpressurePolicy::DropOldest,
+        }
+    }
 }
 
 /// Network management command
 #[derive(Debug)]
-pub enum NetworkManagementCommand {}
+pub enum NetworkManagementCommand {
+    /// Stop accepting incoming connections and stop trying new outgoing ones,
+    /// the established connections are kept until the network is stopped
+    StopListening,
+}
@@ -23,4 +23,6 @@ pub enum ConnectionClosureReason {
     Failed,
     /// Connection closed after node ban
     Banned,
+    /// Connection closed because the node could not keep up with the messages sent to it
+    SendQueueOverflow,
 }
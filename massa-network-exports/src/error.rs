@@ -1,6 +1,9 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
-use crate::{peers::PeerType, ConnectionId};
+use crate::{
+    peers::{AdvertisedPeer, PeerType},
+    ConnectionId,
+};
 use displaydoc::Display;
 use massa_models::error::ModelsError;
 use massa_serialization::SerializeError;
@@ -27,10 +30,16 @@ pub enum NetworkError {
     InvalidIpError(IpAddr),
     /// Active connection missing:`{0}`
     ActiveConnectionMissing(ConnectionId),
+    /// Connection `{0}` cannot make this transition from state {1}
+    InvalidConnectionTransition(ConnectionId, String),
     /// IO error : {0}
     IOError(#[from] std::io::Error),
     /// Serde error : {0}
     SerdeError(#[from] serde_json::Error),
+    /// invalid peers file: {0}
+    PeersFileError(String),
+    /// invalid peer filter expression: {0}
+    PeerFilterParseError(String),
     /// `MassaHash` error {0}
     MassaHashError(#[from] massa_hash::MassaHashError),
     /// `massa_signature` error {0}
@@ -55,6 +64,8 @@ pub enum NetworkError {
     SerializeError(#[from] SerializeError),
     /// container inconsistency error: {0}
     ContainerInconsistencyError(String),
+    /// fault injection is not available: the node was not built with the `fault_injection` feature
+    FaultInjectionDisabled,
 }
 
 /// Handshake error type
@@ -74,8 +85,10 @@ pub enum HandshakeErrorType {
     HandshakeInvalidSignature,
     /// Incompatible version
     IncompatibleVersion,
+    /// Peer belongs to a different network
+    IncompatibleNetwork,
     /// Outgoing connection returned a bootstrapable peer list: {0:?}
-    PeerListReceived(Vec<IpAddr>),
+    PeerListReceived(Vec<AdvertisedPeer>),
 }
 
 /// return handshake error
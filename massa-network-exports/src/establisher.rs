@@ -39,6 +39,8 @@ mod types {
         pub async fn accept(&mut self) -> io::Result<(ReadHalf, WriteHalf, SocketAddr)> {
             // accept
             let (sock, mut remote_addr) = self.0.accept().await?;
+            // the messages are batched by the node workers, Nagle's algorithm would only delay them
+            sock.set_nodelay(true)?;
             // normalize IP
             remote_addr.set_ip(remote_addr.ip().to_canonical());
             // split into read half and write half
@@ -59,6 +61,7 @@ mod types {
         pub async fn connect(&mut self, addr: SocketAddr) -> io::Result<(ReadHalf, WriteHalf)> {
             match timeout(self.0.to_duration(), TcpStream::connect(addr)).await {
                 Ok(Ok(sock)) => {
+                    sock.set_nodelay(true)?;
                     let (reader, writer) = sock.into_split();
                     Ok((reader, writer))
                 }
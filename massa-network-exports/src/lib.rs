@@ -4,6 +4,10 @@
 #![warn(unused_crate_dependencies)]
 #![feature(ip)]
 
+pub use backpressure::{
+    backpressure_channel, bounded_backpressure_channel, Backpressure, BackpressurePolicy,
+    BackpressureReceiver, BackpressureSender,
+};
 pub use commands::{
     AskForBlocksInfo, BlockInfoReply, NetworkCommand, NetworkEvent, NetworkManagementCommand,
     NodeCommand, NodeEvent, NodeEventType,
@@ -13,17 +17,21 @@ pub use common::{ConnectionClosureReason, ConnectionId};
 pub use error::{HandshakeErrorType, NetworkConnectionErrorType, NetworkError};
 pub use establisher::{Establisher, Listener, ReadHalf, WriteHalf};
 pub use network_controller::{NetworkCommandSender, NetworkEventReceiver, NetworkManager};
+pub use peer_filter::{PeerExpression, PeerFilter};
 pub use peers::{
-    BootstrapPeers, BootstrapPeersDeserializer, BootstrapPeersSerializer, ConnectionCount, Peer,
-    PeerInfo, PeerType, Peers,
+    AdvertisedPeer, AdvertisedPeerDeserializer, AdvertisedPeerSerializer, BanReason,
+    BootstrapPeers, BootstrapPeersDeserializer, BootstrapPeersSerializer, ConnectionCount,
+    NodeTraffic, Peer, PeerFeedback, PeerInfo, PeerType, Peers,
 };
-pub use settings::NetworkConfig;
+pub use settings::{NetworkConfig, NetworkReloadableConfig};
 
+mod backpressure;
 mod commands;
 mod common;
 mod error;
 mod establisher;
 mod network_controller;
+mod peer_filter;
 mod peers;
 
 /// network settings
@@ -3,14 +3,19 @@
 use crate::{
     commands::{AskForBlocksInfo, NetworkManagementCommand},
     error::NetworkError,
-    BlockInfoReply, BootstrapPeers, NetworkCommand, NetworkEvent, Peers,
+    BackpressureReceiver, BanReason, BlockInfoReply, BootstrapPeers, NetworkCommand, NetworkEvent,
+    NetworkReloadableConfig, PeerFeedback, Peers,
 };
 use massa_models::{
-    block::{BlockId, WrappedHeader},
+    api::PeerConnectionEvent,
+    ban_list::{BanList, BanListEntry, IpSubnet},
+    block::{ArchivedBlock, BlockId, WrappedHeader},
     composite::PubkeySig,
     endorsement::WrappedEndorsement,
+    network_faults::{MessageFaults, NetworkFaults},
     node::NodeId,
     operation::{OperationPrefixIds, WrappedOperation},
+    slot::Slot,
     stats::NetworkStats,
 };
 use std::{
@@ -31,19 +36,27 @@ use tracing::{info, warn};
 pub struct NetworkCommandSender(pub mpsc::Sender<NetworkCommand>);
 
 impl NetworkCommandSender {
-    /// ban node(s) by id(s)
-    pub async fn node_ban_by_ids(&self, ids: Vec<NodeId>) -> Result<(), NetworkError> {
+    /// ban node(s) by id(s) for `reason`
+    pub async fn node_ban_by_ids(
+        &self,
+        ids: Vec<NodeId>,
+        reason: BanReason,
+    ) -> Result<(), NetworkError> {
         self.0
-            .send(NetworkCommand::NodeBanByIds(ids))
+            .send(NetworkCommand::NodeBanByIds(ids, reason))
             .await
             .map_err(|_| NetworkError::ChannelError("could not send BanId command".into()))?;
         Ok(())
     }
 
-    /// ban node(s) by ip(s)
-    pub async fn node_ban_by_ips(&self, ips: Vec<IpAddr>) -> Result<(), NetworkError> {
+    /// ban node(s) by ip(s) for `reason`
+    pub async fn node_ban_by_ips(
+        &self,
+        ips: Vec<IpAddr>,
+        reason: BanReason,
+    ) -> Result<(), NetworkError> {
         self.0
-            .send(NetworkCommand::NodeBanByIps(ips))
+            .send(NetworkCommand::NodeBanByIps(ips, reason))
             .await
             .map_err(|_| NetworkError::ChannelError("could not send BanIp command".into()))?;
         Ok(())
@@ -69,6 +82,17 @@ impl NetworkCommandSender {
         Ok(())
     }
 
+    /// apply the part of the network configuration that can be changed at runtime
+    pub async fn update_config(&self, config: NetworkReloadableConfig) -> Result<(), NetworkError> {
+        self.0
+            .send(NetworkCommand::UpdateConfig(config))
+            .await
+            .map_err(|_| {
+                NetworkError::ChannelError("could not send UpdateConfig command".into())
+            })?;
+        Ok(())
+    }
+
     /// remove from banned node(s) by id(s)
     pub async fn node_unban_by_ids(&self, ids: Vec<NodeId>) -> Result<(), NetworkError> {
         self.0
@@ -87,6 +111,114 @@ impl NetworkCommandSender {
         Ok(())
     }
 
+    /// ban subnet(s) by hand, without expiry
+    pub async fn node_ban_by_subnets(&self, subnets: Vec<IpSubnet>) -> Result<(), NetworkError> {
+        let bans = subnets
+            .into_iter()
+            .map(|target| BanListEntry {
+                target,
+                reason: Some(BanReason::Manual.to_string()),
+                expiry: None,
+                banned_at: None,
+            })
+            .collect();
+        self.import_ban_list(BanList { bans }).await
+    }
+
+    /// remove from banned subnet(s)
+    pub async fn node_unban_subnets(&self, subnets: Vec<IpSubnet>) -> Result<(), NetworkError> {
+        self.0
+            .send(NetworkCommand::NodeUnbanBySubnets(subnets))
+            .await
+            .map_err(|_| NetworkError::ChannelError("could not send Unban command".into()))?;
+        Ok(())
+    }
+
+    /// get the banned ip addresses and subnets
+    pub async fn get_ban_list(&self) -> Result<BanList, NetworkError> {
+        let (response_tx, response_rx) = oneshot::channel();
+        self.0
+            .send(NetworkCommand::GetBanList(response_tx))
+            .await
+            .map_err(|_| NetworkError::ChannelError("could not send GetBanList command".into()))?;
+        response_rx.await.map_err(|_| {
+            NetworkError::ChannelError("could not receive the ban list upstream".into())
+        })
+    }
+
+    /// merge bans exported by another node
+    pub async fn import_ban_list(&self, ban_list: BanList) -> Result<(), NetworkError> {
+        self.0
+            .send(NetworkCommand::ImportBanList(ban_list))
+            .await
+            .map_err(|_| {
+                NetworkError::ChannelError("could not send ImportBanList command".into())
+            })?;
+        Ok(())
+    }
+
+    /// get the last ended connections of the peer at `ip`, oldest first
+    pub async fn get_peer_history(
+        &self,
+        ip: IpAddr,
+    ) -> Result<Vec<PeerConnectionEvent>, NetworkError> {
+        let (response_tx, response_rx) = oneshot::channel();
+        self.0
+            .send(NetworkCommand::GetPeerHistory { ip, response_tx })
+            .await
+            .map_err(|_| {
+                NetworkError::ChannelError("could not send GetPeerHistory command".into())
+            })?;
+        response_rx.await.map_err(|_| {
+            NetworkError::ChannelError("could not receive the peer history upstream".into())
+        })
+    }
+
+    /// dump the peers file right away, whatever the dump interval, returning once it is written
+    pub async fn flush_peers(&self) -> Result<(), NetworkError> {
+        let (response_tx, response_rx) = oneshot::channel();
+        self.0
+            .send(NetworkCommand::FlushPeers(response_tx))
+            .await
+            .map_err(|_| NetworkError::ChannelError("could not send FlushPeers command".into()))?;
+        response_rx.await.map_err(|_| {
+            NetworkError::ChannelError("could not receive the peers flush result upstream".into())
+        })?
+    }
+
+    /// set the faults injected in the messages sent to `node_id`, or to all the peers if `node_id` is `None`,
+    /// returning all the faults then injected
+    pub async fn set_faults(
+        &self,
+        node_id: Option<NodeId>,
+        faults: Option<MessageFaults>,
+    ) -> Result<NetworkFaults, NetworkError> {
+        let (response_tx, response_rx) = oneshot::channel();
+        self.0
+            .send(NetworkCommand::SetFaults {
+                node_id,
+                faults,
+                response_tx,
+            })
+            .await
+            .map_err(|_| NetworkError::ChannelError("could not send SetFaults command".into()))?;
+        response_rx.await.map_err(|_| {
+            NetworkError::ChannelError("could not receive the injected faults upstream".into())
+        })?
+    }
+
+    /// read the peers file again and merge it with the known peers and bans
+    pub async fn reload_peers(&self) -> Result<(), NetworkError> {
+        let (response_tx, response_rx) = oneshot::channel();
+        self.0
+            .send(NetworkCommand::ReloadPeers(response_tx))
+            .await
+            .map_err(|_| NetworkError::ChannelError("could not send ReloadPeers command".into()))?;
+        response_rx.await.map_err(|_| {
+            NetworkError::ChannelError("could not receive the peers reload result upstream".into())
+        })?
+    }
+
     /// Send info about the contents of a block.
     pub async fn send_block_info(
         &self,
@@ -217,6 +349,26 @@ impl NetworkCommandSender {
         Ok(())
     }
 
+    /// Feed observations on the blocks of nodes back into the reputation of their peers.
+    /// The feedback is dropped if the command channel is full.
+    pub async fn send_peer_feedback(
+        &self,
+        feedback: Vec<(NodeId, PeerFeedback)>,
+    ) -> Result<(), NetworkError> {
+        match self.0.try_send(NetworkCommand::PeerFeedback(feedback)) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                warn!("Failed to send NetworkCommand PeerFeedback channel full");
+            }
+            Err(TrySendError::Closed(_)) => {
+                return Err(NetworkError::ChannelError(
+                    "could not send PeerFeedback command".into(),
+                ));
+            }
+        };
+        Ok(())
+    }
+
     /// Create a new call to the network, sending a `wishlist` of `operationIds` to a
     /// target node (`to_node`) in order to receive the full operations in the future.
     ///
@@ -252,6 +404,68 @@ impl NetworkCommandSender {
         Ok(())
     }
 
+    /// ask an archival node for the final blocks of the slots from `start` (included) to `end` (excluded)
+    pub async fn ask_for_block_range(
+        &self,
+        node: NodeId,
+        start: Slot,
+        end: Slot,
+    ) -> Result<(), NetworkError> {
+        self.0
+            .send(NetworkCommand::AskForBlockRange { node, start, end })
+            .await
+            .map_err(|_| {
+                NetworkError::ChannelError("could not send AskForBlockRange command".into())
+            })?;
+        Ok(())
+    }
+
+    /// send final blocks to a node that asked for a slot range
+    pub async fn send_block_range(
+        &self,
+        node: NodeId,
+        blocks: Vec<ArchivedBlock>,
+    ) -> Result<(), NetworkError> {
+        self.0
+            .send(NetworkCommand::SendBlockRange { node, blocks })
+            .await
+            .map_err(|_| {
+                NetworkError::ChannelError("could not send SendBlockRange command".into())
+            })?;
+        Ok(())
+    }
+
+    /// ask an archival node for the headers of the final blocks of the slots from `start` (included) to `end` (excluded)
+    pub async fn ask_for_header_range(
+        &self,
+        node: NodeId,
+        start: Slot,
+        end: Slot,
+    ) -> Result<(), NetworkError> {
+        self.0
+            .send(NetworkCommand::AskForHeaderRange { node, start, end })
+            .await
+            .map_err(|_| {
+                NetworkError::ChannelError("could not send AskForHeaderRange command".into())
+            })?;
+        Ok(())
+    }
+
+    /// send headers of final blocks to a node that asked for a slot range
+    pub async fn send_header_range(
+        &self,
+        node: NodeId,
+        headers: Vec<WrappedHeader>,
+    ) -> Result<(), NetworkError> {
+        self.0
+            .send(NetworkCommand::SendHeaderRange { node, headers })
+            .await
+            .map_err(|_| {
+                NetworkError::ChannelError("could not send SendHeaderRange command".into())
+            })?;
+        Ok(())
+    }
+
     /// Sign a message using the node's keypair
     pub async fn node_sign_message(&self, msg: Vec<u8>) -> Result<PubkeySig, NetworkError> {
         let (response_tx, response_rx) = oneshot::channel();
@@ -268,7 +482,7 @@ impl NetworkCommandSender {
 }
 
 /// network event receiver
-pub struct NetworkEventReceiver(pub mpsc::Receiver<NetworkEvent>);
+pub struct NetworkEventReceiver(pub BackpressureReceiver<NetworkEvent>);
 
 impl NetworkEventReceiver {
     /// wait network event
@@ -299,6 +513,14 @@ pub struct NetworkManager {
 }
 
 impl NetworkManager {
+    /// stop accepting new connections, first step of the node shutdown
+    pub async fn stop_listening(&self) -> Result<(), NetworkError> {
+        self.manager_tx
+            .send(NetworkManagementCommand::StopListening)
+            .await
+            .map_err(|_| NetworkError::ChannelError("could not send StopListening command".into()))
+    }
+
     /// stop network
     pub async fn stop(
         self,
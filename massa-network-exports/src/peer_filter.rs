@@ -0,0 +1,121 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Filters of the peer lists exchanged with the other nodes.
+//!
+//! The peers advertised to us go through `candidate_peer_filter` before being merged with the known peers,
+//! and the peers we advertise go through `advertised_peer_filter`. Unlike a ban, a filter neither closes
+//! the connections of a peer nor refuses them: a filtered peer is only left out of the lists.
+
+use crate::NetworkError;
+use massa_models::ban_list::IpSubnet;
+use serde::Deserialize;
+use serde_with::DeserializeFromStr;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+/// Filter of the ips of a peer list: an ip is dropped if it belongs to one of `deny_subnets`
+/// or matches one of `deny_expressions`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PeerFilter {
+    /// the ips of these subnets are dropped
+    pub deny_subnets: Vec<IpSubnet>,
+    /// the ips matching one of these expressions are dropped
+    pub deny_expressions: Vec<PeerExpression>,
+}
+
+impl PeerFilter {
+    /// True if the filter drops nothing
+    pub fn is_empty(&self) -> bool {
+        self.deny_subnets.is_empty() && self.deny_expressions.is_empty()
+    }
+
+    /// True if `ip` is dropped by the filter
+    pub fn denies(&self, ip: &IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        self.deny_subnets.iter().any(|subnet| subnet.contains(&ip))
+            || self.deny_expressions.iter().any(|expr| expr.matches(&ip))
+    }
+}
+
+/// Condition on the ip of a peer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PeerCondition {
+    /// IPv4 address
+    Ipv4,
+    /// IPv6 address
+    Ipv6,
+    /// address of the subnet
+    InSubnet(IpSubnet),
+}
+
+impl PeerCondition {
+    fn matches(&self, ip: &IpAddr) -> bool {
+        match self {
+            PeerCondition::Ipv4 => ip.is_ipv4(),
+            PeerCondition::Ipv6 => ip.is_ipv6(),
+            PeerCondition::InSubnet(subnet) => subnet.contains(ip),
+        }
+    }
+}
+
+/// Expression on the ip of a peer: conditions joined by `&&`, each being `ipv4`, `ipv6` or `in <subnet>`,
+/// and negated if prefixed by `!`. Ex: `ipv6 && !in 2001:db8::/32` matches the IPv6 addresses
+/// outside of `2001:db8::/32`.
+#[derive(Debug, Clone, PartialEq, Eq, DeserializeFromStr)]
+pub struct PeerExpression {
+    /// conditions that must all hold, with whether they are negated
+    conditions: Vec<(bool, PeerCondition)>,
+}
+
+impl PeerExpression {
+    /// True if `ip` matches the expression
+    pub fn matches(&self, ip: &IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        self.conditions
+            .iter()
+            .all(|(negated, condition)| condition.matches(&ip) != *negated)
+    }
+}
+
+impl FromStr for PeerExpression {
+    type Err = NetworkError;
+
+    /// ```
+    /// # use massa_network_exports::PeerExpression;
+    /// # use std::net::IpAddr;
+    /// # use std::str::FromStr;
+    /// let expr = PeerExpression::from_str("ipv6 && !in 2001:db8::/32").unwrap();
+    /// assert!(expr.matches(&IpAddr::from_str("2001:db9::1").unwrap()));
+    /// assert!(!expr.matches(&IpAddr::from_str("2001:db8::1").unwrap()));
+    /// assert!(!expr.matches(&IpAddr::from_str("169.202.0.1").unwrap()));
+    /// assert!(PeerExpression::from_str("ipv6 &&").is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let conditions = s
+            .split("&&")
+            .map(|term| {
+                let term = term.trim();
+                let (negated, term) = match term.strip_prefix('!') {
+                    Some(term) => (true, term.trim_start()),
+                    None => (false, term),
+                };
+                let condition = match term {
+                    "ipv4" => PeerCondition::Ipv4,
+                    "ipv6" => PeerCondition::Ipv6,
+                    _ => match term.strip_prefix("in ") {
+                        Some(subnet) => PeerCondition::InSubnet(IpSubnet::from_str(subnet.trim())?),
+                        None => {
+                            return Err(NetworkError::PeerFilterParseError(format!(
+                                "{}: unknown condition `{}`",
+                                s, term
+                            )))
+                        }
+                    },
+                };
+                Ok((negated, condition))
+            })
+            .collect::<Result<Vec<_>, NetworkError>>()?;
+        Ok(PeerExpression { conditions })
+    }
+}
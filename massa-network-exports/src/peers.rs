@@ -1,18 +1,25 @@
 use crate::settings::PeerTypeConnectionConfig;
 use displaydoc::Display;
 use enum_map::Enum;
+use massa_models::config::MAX_ADVERTISED_PEER_ADDRESSES;
 use massa_models::node::NodeId;
-use massa_models::serialization::{IpAddrDeserializer, IpAddrSerializer};
+use massa_models::serialization::{SocketAddrDeserializer, SocketAddrSerializer};
+use massa_models::version::Version;
 use massa_serialization::{
     Deserializer, SerializeError, Serializer, U32VarIntDeserializer, U32VarIntSerializer,
 };
 use massa_time::MassaTime;
+use nom::error::context;
 use nom::error::{ContextError, ParseError};
 use nom::multi::length_count;
+use nom::sequence::tuple;
 use nom::{IResult, Parser};
 use serde::{Deserialize, Serialize};
 use std::ops::Bound::Included;
-use std::{collections::HashMap, net::IpAddr};
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+};
 /// Associate a peer info with nodes
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Peer {
@@ -20,6 +27,17 @@ pub struct Peer {
     pub peer_info: PeerInfo,
     /// corresponding nodes (true if the connection is outgoing, false if incoming)
     pub active_nodes: Vec<(NodeId, bool)>,
+    /// bytes exchanged with each of the corresponding nodes
+    pub traffic: HashMap<NodeId, NodeTraffic>,
+}
+
+/// Bytes exchanged with a node since its connection was established, handshake included
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeTraffic {
+    /// bytes sent to the node
+    pub bytes_sent: u64,
+    /// bytes received from the node
+    pub bytes_received: u64,
 }
 
 /// peers
@@ -31,14 +49,146 @@ pub struct Peers {
     pub peers: HashMap<IpAddr, Peer>,
 }
 
-/// Peers that are transmitted during bootstrap
+/// Entry of a peer list: the addresses under which a peer can be reached, by order of preference
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub struct BootstrapPeers(pub Vec<IpAddr>);
+pub struct AdvertisedPeer {
+    /// main address of the peer, under which it is known
+    pub address: SocketAddr,
+    /// other addresses of the peer (IPv4 or IPv6, other ports), tried after the main one
+    #[serde(default)]
+    pub other_addresses: Vec<SocketAddr>,
+}
+
+impl From<SocketAddr> for AdvertisedPeer {
+    fn from(address: SocketAddr) -> Self {
+        AdvertisedPeer {
+            address,
+            other_addresses: Vec::new(),
+        }
+    }
+}
+
+impl AdvertisedPeer {
+    /// All the addresses of the peer, by order of preference
+    pub fn addresses(&self) -> impl Iterator<Item = &SocketAddr> {
+        std::iter::once(&self.address).chain(self.other_addresses.iter())
+    }
+}
+
+/// Serializer for `AdvertisedPeer`
+pub struct AdvertisedPeerSerializer {
+    u32_serializer: U32VarIntSerializer,
+    socket_addr_serializer: SocketAddrSerializer,
+}
+
+impl AdvertisedPeerSerializer {
+    /// Creates a new `AdvertisedPeerSerializer`
+    pub fn new() -> Self {
+        Self {
+            u32_serializer: U32VarIntSerializer::new(),
+            socket_addr_serializer: SocketAddrSerializer::new(),
+        }
+    }
+}
+
+impl Default for AdvertisedPeerSerializer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Serializer<AdvertisedPeer> for AdvertisedPeerSerializer {
+    fn serialize(
+        &self,
+        value: &AdvertisedPeer,
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), SerializeError> {
+        self.socket_addr_serializer
+            .serialize(&value.address, buffer)?;
+        let addresses_count: u32 = value.other_addresses.len().try_into().map_err(|err| {
+            SerializeError::NumberTooBig(format!("too many addresses in AdvertisedPeer: {}", err))
+        })?;
+        self.u32_serializer.serialize(&addresses_count, buffer)?;
+        for address in value.other_addresses.iter() {
+            self.socket_addr_serializer.serialize(address, buffer)?;
+        }
+        Ok(())
+    }
+}
+
+/// Deserializer for `AdvertisedPeer`
+pub struct AdvertisedPeerDeserializer {
+    length_deserializer: U32VarIntDeserializer,
+    socket_addr_deserializer: SocketAddrDeserializer,
+}
+
+impl AdvertisedPeerDeserializer {
+    /// Creates a new `AdvertisedPeerDeserializer`,
+    /// accepting up to `MAX_ADVERTISED_PEER_ADDRESSES` addresses per peer
+    pub fn new() -> Self {
+        Self {
+            length_deserializer: U32VarIntDeserializer::new(
+                Included(0),
+                Included(MAX_ADVERTISED_PEER_ADDRESSES.saturating_sub(1)),
+            ),
+            socket_addr_deserializer: SocketAddrDeserializer::new(),
+        }
+    }
+}
+
+impl Default for AdvertisedPeerDeserializer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deserializer<AdvertisedPeer> for AdvertisedPeerDeserializer {
+    /// ```
+    /// use massa_network_exports::{AdvertisedPeer, AdvertisedPeerSerializer, AdvertisedPeerDeserializer};
+    /// use massa_serialization::{Serializer, Deserializer, DeserializeError};
+    /// use std::str::FromStr;
+    /// use std::net::SocketAddr;
+    ///
+    /// let peer = AdvertisedPeer {
+    ///     address: SocketAddr::from_str("169.202.0.1:31244").unwrap(),
+    ///     other_addresses: vec![SocketAddr::from_str("[2001:db8::1]:31245").unwrap()],
+    /// };
+    /// let mut serialized = Vec::new();
+    /// AdvertisedPeerSerializer::new().serialize(&peer, &mut serialized).unwrap();
+    /// let (rest, peer_deser) = AdvertisedPeerDeserializer::new().deserialize::<DeserializeError>(&serialized).unwrap();
+    /// assert!(rest.is_empty());
+    /// assert_eq!(peer, peer_deser);
+    /// ```
+    fn deserialize<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+        &self,
+        buffer: &'a [u8],
+    ) -> IResult<&'a [u8], AdvertisedPeer, E> {
+        context(
+            "Failed AdvertisedPeer deserialization",
+            tuple((
+                |input| self.socket_addr_deserializer.deserialize(input),
+                length_count(
+                    |input| self.length_deserializer.deserialize(input),
+                    |input| self.socket_addr_deserializer.deserialize(input),
+                ),
+            )),
+        )
+        .map(|(address, other_addresses)| AdvertisedPeer {
+            address,
+            other_addresses,
+        })
+        .parse(buffer)
+    }
+}
+
+/// Peers that are transmitted during bootstrap, with their addresses
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BootstrapPeers(pub Vec<AdvertisedPeer>);
 
 /// Serializer for `BootstrapPeers`
 pub struct BootstrapPeersSerializer {
     u32_serializer: U32VarIntSerializer,
-    ip_addr_serializer: IpAddrSerializer,
+    advertised_peer_serializer: AdvertisedPeerSerializer,
 }
 
 impl BootstrapPeersSerializer {
@@ -46,7 +196,7 @@ impl BootstrapPeersSerializer {
     pub fn new() -> Self {
         Self {
             u32_serializer: U32VarIntSerializer::new(),
-            ip_addr_serializer: IpAddrSerializer::new(),
+            advertised_peer_serializer: AdvertisedPeerSerializer::new(),
         }
     }
 }
@@ -62,12 +212,12 @@ impl Serializer<BootstrapPeers> for BootstrapPeersSerializer {
     /// use massa_network_exports::{BootstrapPeers, BootstrapPeersSerializer};
     /// use massa_serialization::Serializer;
     /// use std::str::FromStr;
-    /// use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+    /// use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
     ///
-    /// let localhost_v4 = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
-    /// let localhost_v6 = IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1));
+    /// let localhost_v4 = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 31244);
+    /// let localhost_v6 = SocketAddr::new(IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1)), 31245);
     /// let mut serialized = Vec::new();
-    /// let peers = BootstrapPeers(vec![localhost_v4, localhost_v6]);
+    /// let peers = BootstrapPeers(vec![localhost_v4.into(), localhost_v6.into()]);
     /// let peers_serializer = BootstrapPeersSerializer::new();
     /// peers_serializer.serialize(&peers, &mut serialized).unwrap();
     /// ```
@@ -84,7 +234,7 @@ impl Serializer<BootstrapPeers> for BootstrapPeersSerializer {
         })?;
         self.u32_serializer.serialize(&peers_count, buffer)?;
         for peer in value.0.iter() {
-            self.ip_addr_serializer.serialize(peer, buffer)?;
+            self.advertised_peer_serializer.serialize(peer, buffer)?;
         }
         Ok(())
     }
@@ -93,7 +243,7 @@ impl Serializer<BootstrapPeers> for BootstrapPeersSerializer {
 /// Deserializer for `BootstrapPeers`
 pub struct BootstrapPeersDeserializer {
     length_deserializer: U32VarIntDeserializer,
-    ip_addr_deserializer: IpAddrDeserializer,
+    advertised_peer_deserializer: AdvertisedPeerDeserializer,
 }
 
 impl BootstrapPeersDeserializer {
@@ -105,7 +255,7 @@ impl BootstrapPeersDeserializer {
     pub fn new(max_peers: u32) -> Self {
         Self {
             length_deserializer: U32VarIntDeserializer::new(Included(0), Included(max_peers)),
-            ip_addr_deserializer: IpAddrDeserializer::new(),
+            advertised_peer_deserializer: AdvertisedPeerDeserializer::new(),
         }
     }
 }
@@ -115,12 +265,12 @@ impl Deserializer<BootstrapPeers> for BootstrapPeersDeserializer {
     /// use massa_network_exports::{BootstrapPeers, BootstrapPeersSerializer, BootstrapPeersDeserializer};
     /// use massa_serialization::{Serializer, Deserializer, DeserializeError};
     /// use std::str::FromStr;
-    /// use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+    /// use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
     ///
-    /// let localhost_v4 = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
-    /// let localhost_v6 = IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1));
+    /// let localhost_v4 = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 31244);
+    /// let localhost_v6 = SocketAddr::new(IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1)), 31245);
     /// let mut serialized = Vec::new();
-    /// let peers = BootstrapPeers(vec![localhost_v4, localhost_v6]);
+    /// let peers = BootstrapPeers(vec![localhost_v4.into(), localhost_v6.into()]);
     /// let peers_serializer = BootstrapPeersSerializer::new();
     /// let peers_deserializer = BootstrapPeersDeserializer::new(1000);
     /// peers_serializer.serialize(&peers, &mut serialized).unwrap();
@@ -134,13 +284,52 @@ impl Deserializer<BootstrapPeers> for BootstrapPeersDeserializer {
     ) -> IResult<&'a [u8], BootstrapPeers, E> {
         length_count(
             |input| self.length_deserializer.deserialize(input),
-            |input| self.ip_addr_deserializer.deserialize(input),
+            |input| self.advertised_peer_deserializer.deserialize(input),
         )
         .map(BootstrapPeers)
         .parse(buffer)
     }
 }
 
+/// Why a peer is banned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum BanReason {
+    /// banned by the operator
+    Manual,
+    /// banned for misbehaving
+    Misbehavior,
+    /// on another network
+    OtherNetwork,
+}
+
+/// Observation of the protocol on the blocks of a peer, fed back into its reputation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum PeerFeedback {
+    /// sent an invalid block
+    InvalidBlock,
+    /// first announced a block that became final
+    FinalBlock,
+    /// announced a block long after the other peers
+    LateAnnouncement,
+    /// repeatedly sent items far outside of the validity window
+    IrrelevantItems,
+    /// made us buffer more blocks that could not be processed yet than its quota
+    ExcessPendingBlocks,
+}
+
+impl PeerFeedback {
+    /// Change of the reputation of the peer caused by the feedback
+    pub fn reputation_delta(&self) -> i32 {
+        match self {
+            PeerFeedback::InvalidBlock => -20,
+            PeerFeedback::FinalBlock => 1,
+            PeerFeedback::LateAnnouncement => -2,
+            PeerFeedback::IrrelevantItems => -5,
+            PeerFeedback::ExcessPendingBlocks => -5,
+        }
+    }
+}
+
 /// Peer categories.
 /// There is a defined number of slots for each category.
 /// Order matters: less prioritized peer type first
@@ -176,10 +365,17 @@ impl Default for PeerType {
 }
 
 /// All information concerning a peer is here
-#[derive(Clone, Copy, Serialize, Deserialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct PeerInfo {
     /// Peer ip address.
     pub ip: IpAddr,
+    /// Protocol port of the peer, as advertised.
+    /// None if unknown, the `protocol_port` of the configuration being assumed.
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// Other addresses advertised for the peer, tried in this order after its main address when dialing
+    #[serde(default)]
+    pub other_addresses: Vec<SocketAddr>,
     /// The category the peer is in affects how it's treated.
     pub peer_type: PeerType,
     /// Time in milliseconds when peer was last alive
@@ -190,6 +386,24 @@ pub struct PeerInfo {
     pub advertised: bool,
     /// peer was banned
     pub banned: bool,
+    /// Why the peer was banned, if it is
+    #[serde(default)]
+    pub ban_reason: Option<String>,
+    /// Time in milliseconds of the last ban of the peer, if it is banned
+    #[serde(default)]
+    pub banned_at: Option<MassaTime>,
+    /// Node id proven by the peer in its last successful handshake.
+    /// Isn't dump into peer file.
+    #[serde(default)]
+    pub node_id: Option<NodeId>,
+    /// Software version announced by the peer in its last successful handshake.
+    /// Isn't dump into peer file.
+    #[serde(default)]
+    pub version: Option<Version>,
+    /// Sum of the feedback of the protocol on the blocks of the peer, bounded by `MAX_PEER_REPUTATION`.
+    /// The peers with the highest reputation are tried first. Isn't dump into peer file.
+    #[serde(default)]
+    pub reputation: i32,
     /// Current number of active out connection attempts with that peer.
     /// Isn't dump into peer file.
     #[serde(default = "usize::default")]
@@ -211,6 +425,8 @@ impl PeerInfo {
         // canonicalize IP
         self.ip = self.ip.to_canonical();
         self.banned = false;
+        self.ban_reason = None;
+        self.banned_at = None;
         // ensure that connections are set to zero
         self.active_out_connection_attempts = 0;
         self.active_out_connections = 0;
@@ -236,6 +452,8 @@ impl PeerInfo {
     pub fn new(ip: IpAddr, advertised: bool) -> PeerInfo {
         PeerInfo {
             ip,
+            port: None,
+            other_addresses: Vec::new(),
             last_alive: None,
             last_failure: None,
             advertised,
@@ -244,6 +462,24 @@ impl PeerInfo {
             active_in_connections: 0,
             peer_type: Default::default(),
             banned: false,
+            ban_reason: None,
+            banned_at: None,
+            node_id: None,
+            version: None,
+            reputation: 0,
+        }
+    }
+
+    /// Address to connect to the peer, on `default_port` if its port is unknown
+    pub fn get_address(&self, default_port: u16) -> SocketAddr {
+        SocketAddr::new(self.ip, self.port.unwrap_or(default_port))
+    }
+
+    /// Addresses of the peer to advertise, its main one being on `default_port` if its port is unknown
+    pub fn get_advertised_peer(&self, default_port: u16) -> AdvertisedPeer {
+        AdvertisedPeer {
+            address: self.get_address(default_port),
+            other_addresses: self.other_addresses.clone(),
         }
     }
 
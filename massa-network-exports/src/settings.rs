@@ -1,10 +1,12 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
 use enum_map::EnumMap;
+use massa_hash::Hash;
 use massa_time::MassaTime;
 use serde::Deserialize;
 use std::net::{IpAddr, SocketAddr};
 
+use crate::peer_filter::PeerFilter;
 use crate::peers::PeerType;
 
 /// Network configuration
@@ -14,37 +16,67 @@ pub struct NetworkConfig {
     pub bind: SocketAddr,
     /// Our own IP if it is routable, else None.
     pub routable_ip: Option<IpAddr>,
+    /// Observer mode: announced to the peers during handshake so that they prefer other nodes when asking for blocks
+    pub observer: bool,
     /// Protocol port
     pub protocol_port: u16,
+    /// Other addresses at which we can be reached (IPv4 or IPv6, other ports),
+    /// advertised after `routable_ip` and `protocol_port` by order of preference.
+    /// Ignored if `routable_ip` is None.
+    pub other_routable_addresses: Vec<SocketAddr>,
     /// Time interval spent waiting for a response from a peer.
     /// In milliseconds
     pub connect_timeout: MassaTime,
+    /// Shortest timeout of the connection attempts, whatever the dial latencies observed in the network group of the peer.
+    /// In milliseconds
+    pub min_connect_timeout: MassaTime,
+    /// The connection attempts towards a network group time out after the 99th percentile of its observed dial latencies
+    /// times this factor, clamped between `min_connect_timeout` and `connect_timeout`. 0 to always use `connect_timeout`.
+    pub connect_timeout_latency_factor: f64,
     /// `Network_worker` will try to connect to available peers every `wakeup_interval`.
     /// In milliseconds
     pub wakeup_interval: MassaTime,
     /// Path to the file containing initial peers.
     pub initial_peers_file: std::path::PathBuf,
-    /// Path to the file containing known peers.
+    /// Path to the file containing known peers and the bans of ip addresses and subnets.
     pub peers_file: std::path::PathBuf,
-    /// Path to the file containing our keypair
-    pub keypair_file: std::path::PathBuf,
+    /// Format in which the peers file is written. Peers files are read whatever their format.
+    pub peers_file_format: PeersFileFormat,
+    /// Password encrypting the peers file, None to write it in clear
+    pub peers_file_password: Option<String>,
+    /// Path to the append-only log of the bans and unbans, one JSON object per line. None to not log them.
+    pub ban_audit_file: Option<std::path::PathBuf>,
     /// Configuration for `PeerType` connections
     pub peer_types_config: EnumMap<PeerType, PeerTypeConnectionConfig>,
     /// Limit on the number of in connections per ip.
     pub max_in_connections_per_ip: usize,
+    /// Budget of new out connection attempts per minute towards all the peers, on top of the `max_out_attempts`
+    /// of each peer type, to smooth the reconnections after a network outage. 0 for no budget.
+    pub max_out_attempts_per_minute: u32,
+    /// Max number of out connection attempts started at once when the budget of `max_out_attempts_per_minute` is full
+    pub max_out_attempts_burst: u32,
     /// Limit on the number of idle peers we remember.
     pub max_idle_peers: usize,
     /// Limit on the number of banned peers we remember.
     pub max_banned_peers: usize,
+    /// Limit on the number of idle peers, and on the number of banned peers, we remember in the same network group
+    /// (/16 subnet for IPv4, /32 subnet for IPv6).
+    pub max_peers_per_network_group: usize,
+    /// Number of ended connections kept in the connection history of each peer.
+    pub max_peer_history_length: usize,
+    /// Filter of the peers advertised to us, applied before merging them with the known peers.
+    pub candidate_peer_filter: PeerFilter,
+    /// Filter of the peers we advertise. Our own routable ip is advertised whatever the filter.
+    pub advertised_peer_filter: PeerFilter,
     /// Peer database is dumped every `peers_file_dump_interval` in milliseconds
     pub peers_file_dump_interval: MassaTime,
     /// After `message_timeout` milliseconds we are no longer waiting on handshake message
     pub message_timeout: MassaTime,
     /// Every `ask_peer_list_interval` in milliseconds we ask every one for its advertisable peers list.
     pub ask_peer_list_interval: MassaTime,
-    /// Max wait time for sending a Node event.
+    /// Max wait time for sending a consensus-critical Node event, gossip never waits.
     pub max_send_wait_node_event: MassaTime,
-    /// Max wait time for sending a Network event.
+    /// Max wait time for sending a consensus-critical Network event, gossip never waits.
     pub max_send_wait_network_event: MassaTime,
     /// Time after which we forget a node
     pub ban_timeout: MassaTime,
@@ -58,10 +90,16 @@ pub struct NetworkConfig {
     pub max_bytes_read: f64,
     /// Write limitation for a connection in bytes per seconds
     pub max_bytes_write: f64,
+    /// A warning is logged when the median clock offset of the peers exceeds `max_clock_offset` milliseconds
+    pub max_clock_offset: MassaTime,
     /// Max number ids in ask blocks message
     pub max_ask_blocks: u32,
+    /// Max number of blocks in block range messages
+    pub max_block_range_length: u32,
     /// Max operations per block
     pub max_operations_per_block: u32,
+    /// Network ID derived from the genesis parameters, peers with a different one are refused during handshake
+    pub network_id: Hash,
     /// Thread count
     pub thread_count: u8,
     /// Endorsement count
@@ -72,6 +110,11 @@ pub struct NetworkConfig {
     pub max_endorsements_per_message: u32,
     /// Max message size
     pub max_message_size: u32,
+    /// Whether the messages sent after the handshake to the nodes supporting it carry a CRC32 checksum, checked by the receiving nodes
+    pub message_checksum: bool,
+    /// Max delay before sending the buffered small messages (operations, endorsements, peer lists) of a connection,
+    /// the blocks and headers being sent immediately along with them. 0 to send each message at once
+    pub write_coalescing_delay: MassaTime,
     /// Maximum length of a datastore value
     pub max_datastore_value_length: u64,
     /// Maximum entry in an operation datastore
@@ -90,13 +133,40 @@ pub struct NetworkConfig {
     pub event_channel_size: usize,
     /// Node command channel size
     pub node_command_channel_size: usize,
+    /// Max size in bytes of the messages queued in a node command channel.
+    /// A node that lets its channel overflow with consensus-critical messages is disconnected
+    pub node_command_channel_bytes: usize,
     /// Node event channel size
     pub node_event_channel_size: usize,
 }
 
+/// Part of the network configuration that can be changed while the node is running
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetworkReloadableConfig {
+    /// Configuration for `PeerType` connections
+    pub peer_types_config: EnumMap<PeerType, PeerTypeConnectionConfig>,
+    /// Limit on the number of in connections per ip.
+    pub max_in_connections_per_ip: usize,
+    /// Peer database is dumped every `peers_file_dump_interval` in milliseconds
+    pub peers_file_dump_interval: MassaTime,
+}
+
+/// Format of the peers file
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PeersFileFormat {
+    /// pretty JSON, for humans
+    #[default]
+    Json,
+    /// one line per peer or ban, for spreadsheets and scripts
+    Csv,
+    /// compact binary encoding, for large peer databases
+    Binary,
+}
+
 /// Connection configuration for a peer type
 /// Limit the current connections for a given peer type as a whole
-#[derive(Debug, Deserialize, Clone, Default)]
+#[derive(Debug, Deserialize, Clone, Default, PartialEq, Eq)]
 pub struct PeerTypeConnectionConfig {
     /// max number of incoming connection
     pub max_in_connections: usize,
@@ -110,14 +180,15 @@ pub struct PeerTypeConnectionConfig {
 #[cfg(feature = "testing")]
 pub mod tests {
     use crate::NetworkConfig;
-    use crate::{test_exports::tools::get_temp_keypair_file, PeerType};
+    use crate::{test_exports::tools::get_test_network_id, PeerType};
     use enum_map::enum_map;
     use massa_models::config::{
         ENDORSEMENT_COUNT, MAX_ADVERTISE_LENGTH, MAX_ASK_BLOCKS_PER_MESSAGE,
-        MAX_DATASTORE_VALUE_LENGTH, MAX_ENDORSEMENTS_PER_MESSAGE, MAX_FUNCTION_NAME_LENGTH,
-        MAX_MESSAGE_SIZE, MAX_OPERATIONS_PER_MESSAGE, MAX_OPERATION_DATASTORE_ENTRY_COUNT,
-        MAX_OPERATION_DATASTORE_KEY_LENGTH, MAX_OPERATION_DATASTORE_VALUE_LENGTH,
-        MAX_PARAMETERS_SIZE, NETWORK_CONTROLLER_CHANNEL_SIZE, NETWORK_EVENT_CHANNEL_SIZE,
+        MAX_BLOCK_RANGE_LENGTH, MAX_DATASTORE_VALUE_LENGTH, MAX_ENDORSEMENTS_PER_MESSAGE,
+        MAX_FUNCTION_NAME_LENGTH, MAX_MESSAGE_SIZE, MAX_OPERATIONS_PER_MESSAGE,
+        MAX_OPERATION_DATASTORE_ENTRY_COUNT, MAX_OPERATION_DATASTORE_KEY_LENGTH,
+        MAX_OPERATION_DATASTORE_VALUE_LENGTH, MAX_PARAMETERS_SIZE, NETWORK_CONTROLLER_CHANNEL_SIZE,
+        NETWORK_EVENT_CHANNEL_SIZE, NETWORK_NODE_COMMAND_CHANNEL_BYTES,
         NETWORK_NODE_COMMAND_CHANNEL_SIZE, NETWORK_NODE_EVENT_CHANNEL_SIZE, THREAD_COUNT,
     };
     use massa_time::MassaTime;
@@ -147,17 +218,29 @@ pub mod tests {
             NetworkConfig {
                 bind: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8080),
                 routable_ip: Some(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1))),
+                observer: false,
                 protocol_port: 0,
+                other_routable_addresses: Vec::new(),
                 connect_timeout: MassaTime::from_millis(180_000),
+                min_connect_timeout: MassaTime::from_millis(1000),
+                connect_timeout_latency_factor: 4.0,
                 wakeup_interval: MassaTime::from_millis(10_000),
                 peers_file: std::path::PathBuf::new(),
+                peers_file_format: Default::default(),
+                peers_file_password: None,
+                ban_audit_file: None,
                 max_in_connections_per_ip: 2,
+                max_out_attempts_per_minute: 0,
+                max_out_attempts_burst: 0,
                 max_idle_peers: 3,
                 max_banned_peers: 3,
+                max_peers_per_network_group: 100,
+                max_peer_history_length: 10,
+                candidate_peer_filter: Default::default(),
+                advertised_peer_filter: Default::default(),
                 peers_file_dump_interval: MassaTime::from_millis(10_000),
                 message_timeout: MassaTime::from_millis(5000u64),
                 ask_peer_list_interval: MassaTime::from_millis(50000u64),
-                keypair_file: std::path::PathBuf::new(),
                 max_send_wait_node_event: MassaTime::from_millis(100),
                 max_send_wait_network_event: MassaTime::from_millis(100),
                 ban_timeout: MassaTime::from_millis(100_000_000),
@@ -168,13 +251,18 @@ pub mod tests {
                 max_operations_per_message: MAX_OPERATIONS_PER_MESSAGE,
                 max_bytes_read: std::f64::INFINITY,
                 max_bytes_write: std::f64::INFINITY,
+                max_clock_offset: MassaTime::from_millis(1000),
                 max_ask_blocks: MAX_ASK_BLOCKS_PER_MESSAGE,
+                max_block_range_length: MAX_BLOCK_RANGE_LENGTH,
                 endorsement_count: ENDORSEMENT_COUNT,
                 max_endorsements_per_message: MAX_ENDORSEMENTS_PER_MESSAGE,
                 max_operations_per_block: MAX_OPERATIONS_PER_MESSAGE,
                 max_peer_advertise_length: MAX_ADVERTISE_LENGTH,
+                network_id: get_test_network_id(),
                 thread_count: THREAD_COUNT,
                 max_message_size: MAX_MESSAGE_SIZE,
+                message_checksum: false,
+                write_coalescing_delay: MassaTime::from_millis(0),
                 max_datastore_value_length: MAX_DATASTORE_VALUE_LENGTH,
                 max_op_datastore_entry_count: MAX_OPERATION_DATASTORE_ENTRY_COUNT,
                 max_op_datastore_key_length: MAX_OPERATION_DATASTORE_KEY_LENGTH,
@@ -184,6 +272,7 @@ pub mod tests {
                 controller_channel_size: NETWORK_CONTROLLER_CHANNEL_SIZE,
                 event_channel_size: NETWORK_EVENT_CHANNEL_SIZE,
                 node_command_channel_size: NETWORK_NODE_COMMAND_CHANNEL_SIZE,
+                node_command_channel_bytes: NETWORK_NODE_COMMAND_CHANNEL_BYTES,
                 node_event_channel_size: NETWORK_NODE_EVENT_CHANNEL_SIZE,
             }
         }
@@ -214,17 +303,29 @@ pub mod tests {
             Self {
                 bind,
                 routable_ip,
+                observer: false,
                 protocol_port: port,
+                other_routable_addresses: Vec::new(),
                 connect_timeout: MassaTime::from_millis(3000),
+                min_connect_timeout: MassaTime::from_millis(1000),
+                connect_timeout_latency_factor: 0.0,
                 peers_file: peers_file.to_path_buf(),
+                peers_file_format: Default::default(),
+                peers_file_password: None,
+                ban_audit_file: None,
                 wakeup_interval: MassaTime::from_millis(3000),
                 max_in_connections_per_ip: 100,
+                max_out_attempts_per_minute: 0,
+                max_out_attempts_burst: 0,
                 max_idle_peers: 100,
                 max_banned_peers: 100,
+                max_peers_per_network_group: 100,
+                max_peer_history_length: 10,
+                candidate_peer_filter: Default::default(),
+                advertised_peer_filter: Default::default(),
                 peers_file_dump_interval: MassaTime::from_millis(30000),
                 message_timeout: MassaTime::from_millis(5000u64),
                 ask_peer_list_interval: MassaTime::from_millis(50000u64),
-                keypair_file: get_temp_keypair_file().path().to_path_buf(),
                 max_send_wait_node_event: MassaTime::from_millis(100),
                 max_send_wait_network_event: MassaTime::from_millis(100),
                 ban_timeout: MassaTime::from_millis(100_000_000),
@@ -235,13 +336,18 @@ pub mod tests {
                 max_operations_per_message: MAX_OPERATIONS_PER_MESSAGE,
                 max_bytes_read: std::f64::INFINITY,
                 max_bytes_write: std::f64::INFINITY,
+                max_clock_offset: MassaTime::from_millis(1000),
                 max_ask_blocks: 10,
+                max_block_range_length: 10,
                 endorsement_count: 8,
                 max_endorsements_per_message: MAX_ENDORSEMENTS_PER_MESSAGE,
                 max_operations_per_block: MAX_OPERATIONS_PER_MESSAGE,
                 max_peer_advertise_length: 128,
+                network_id: get_test_network_id(),
                 thread_count: THREAD_COUNT,
                 max_message_size: MAX_MESSAGE_SIZE,
+                message_checksum: false,
+                write_coalescing_delay: MassaTime::from_millis(0),
                 max_datastore_value_length: MAX_DATASTORE_VALUE_LENGTH,
                 max_op_datastore_entry_count: MAX_OPERATION_DATASTORE_ENTRY_COUNT,
                 max_op_datastore_key_length: MAX_OPERATION_DATASTORE_KEY_LENGTH,
@@ -251,6 +357,7 @@ pub mod tests {
                 controller_channel_size: NETWORK_CONTROLLER_CHANNEL_SIZE,
                 event_channel_size: NETWORK_EVENT_CHANNEL_SIZE,
                 node_command_channel_size: NETWORK_NODE_COMMAND_CHANNEL_SIZE,
+                node_command_channel_bytes: NETWORK_NODE_COMMAND_CHANNEL_BYTES,
                 node_event_channel_size: NETWORK_NODE_EVENT_CHANNEL_SIZE,
             }
         }
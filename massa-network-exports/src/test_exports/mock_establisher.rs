@@ -171,21 +171,17 @@ impl MockEstablisherInterface {
                 "mock connect_to_controller_listener channel not initialized".to_string(),
             )
         })?;
-        let (response_tx, response_rx) = oneshot::channel::<(ReadHalf, WriteHalf)>();
-        sender.send((*addr, response_tx)).await.map_err(|_err| {
-            io::Error::new(
-                io::ErrorKind::Other,
-                "mock connect_to_controller_listener channel to listener closed".to_string(),
-            )
-        })?;
-        let (duplex_mock_read, duplex_mock_write) = response_rx.await.map_err(|_| {
-            io::Error::new(
-                io::ErrorKind::Other,
-                "MockListener connect_to_controller_listener channel from listener closed"
-                    .to_string(),
-            )
-        })?;
-        Ok((duplex_mock_read, duplex_mock_write))
+        connect_to_listener(sender, addr).await
+    }
+
+    /// get a handle connecting addresses to the controller,
+    /// usable while the interface waits for the connection attempts of the controller
+    pub fn get_listener_handle(&self) -> Option<MockListenerHandle> {
+        self.connection_listener_tx
+            .clone()
+            .map(|connection_listener_tx| MockListenerHandle {
+                connection_listener_tx,
+            })
     }
 
     /// wait connection attempt from controller
@@ -200,3 +196,39 @@ impl MockEstablisherInterface {
         })
     }
 }
+
+/// handle connecting addresses to the listener of a controller
+#[derive(Debug, Clone)]
+pub struct MockListenerHandle {
+    connection_listener_tx: mpsc::Sender<AddrSender>,
+}
+
+impl MockListenerHandle {
+    /// connect address to controller
+    pub async fn connect_to_controller(
+        &self,
+        addr: &SocketAddr,
+    ) -> io::Result<(ReadHalf, WriteHalf)> {
+        connect_to_listener(&self.connection_listener_tx, addr).await
+    }
+}
+
+async fn connect_to_listener(
+    sender: &mpsc::Sender<AddrSender>,
+    addr: &SocketAddr,
+) -> io::Result<(ReadHalf, WriteHalf)> {
+    let (response_tx, response_rx) = oneshot::channel::<(ReadHalf, WriteHalf)>();
+    sender.send((*addr, response_tx)).await.map_err(|_err| {
+        io::Error::new(
+            io::ErrorKind::Other,
+            "mock connect_to_controller_listener channel to listener closed".to_string(),
+        )
+    })?;
+    let (duplex_mock_read, duplex_mock_write) = response_rx.await.map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::Other,
+            "MockListener connect_to_controller_listener channel from listener closed".to_string(),
+        )
+    })?;
+    Ok((duplex_mock_read, duplex_mock_write))
+}
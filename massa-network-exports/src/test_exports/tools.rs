@@ -1,6 +1,12 @@
+use massa_hash::Hash;
 use tempfile::NamedTempFile;
 
 /// named temp file for keypairs
 pub fn get_temp_keypair_file() -> NamedTempFile {
     NamedTempFile::new().expect("cannot create temp file")
 }
+
+/// network ID shared by the nodes of the tests
+pub fn get_test_network_id() -> Hash {
+    Hash::compute_from(b"massa-test-network")
+}
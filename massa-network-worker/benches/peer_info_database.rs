@@ -0,0 +1,109 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Peer database operations run on every peer, at growing peer counts.
+
+#[cfg(feature = "benchmarking")]
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+
+#[cfg(feature = "benchmarking")]
+fn criterion_benchmark(c: &mut Criterion) {
+    use im::HashMap;
+    use massa_network_exports::{AdvertisedPeer, NetworkConfig, PeerInfo, PeerType};
+    use massa_network_worker::{cleanup_peers, PeerInfoDatabase};
+    use massa_time::{MassaTime, SystemClock};
+    use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+    /// Global ip of the peer of index `index`
+    fn peer_ip(index: u32) -> IpAddr {
+        IpAddr::V4(Ipv4Addr::from(0x0B00_0000 + index))
+    }
+
+    /// Build `count` peers: mostly idle advertised ones, some banned, a few connected and bootstrap ones
+    fn prepare_peers(count: u32) -> HashMap<IpAddr, PeerInfo> {
+        let now = MassaTime::now().unwrap();
+        (0..count)
+            .map(|index| {
+                let mut peer = PeerInfo::new(peer_ip(index), true);
+                // spread the times over the last hours, the failures being older than the wakeup interval
+                peer.last_alive = Some(
+                    now.saturating_sub(MassaTime::from_millis((index as u64 * 7_919) % 10_000_000)),
+                );
+                peer.last_failure = match index % 3 {
+                    0 => None,
+                    _ => Some(now.saturating_sub(MassaTime::from_millis(
+                        60_000 + (index as u64 * 104_729) % 10_000_000,
+                    ))),
+                };
+                match index % 100 {
+                    0 => peer.active_out_connections = 1,
+                    1 => peer.active_in_connections = 1,
+                    2 => peer.peer_type = PeerType::Bootstrap,
+                    3..=12 => peer.banned = true,
+                    _ => {}
+                }
+                (peer.ip, peer)
+            })
+            .collect()
+    }
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let _guard = runtime.enter();
+    let mut group = c.benchmark_group("peer info database");
+    for count in [1_000, 10_000, 100_000] {
+        let peers = prepare_peers(count);
+        let now = MassaTime::now().unwrap();
+        let cfg = NetworkConfig {
+            routable_ip: None,
+            observer: false,
+            max_idle_peers: count as usize,
+            max_banned_peers: count as usize / 20,
+            max_peers_per_network_group: count as usize,
+            ..Default::default()
+        };
+        group.bench_with_input(
+            BenchmarkId::new("cleanup_peers", count),
+            &peers,
+            |b, peers| {
+                b.iter_batched(
+                    || peers.clone(),
+                    |mut peers| {
+                        cleanup_peers(&cfg, black_box(&mut peers), None, cfg.ban_timeout, now)
+                            .unwrap()
+                    },
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+        let db = PeerInfoDatabase::from_peers(&cfg, peers.clone(), Box::new(SystemClock));
+        group.bench_function(
+            BenchmarkId::new("get_out_connection_candidate_ips", count),
+            |b| b.iter(|| black_box(&db).get_out_connection_candidate_ips().unwrap()),
+        );
+        let new_peers: Vec<AdvertisedPeer> = (count..count + 100)
+            .map(|index| AdvertisedPeer::from(SocketAddr::new(peer_ip(index), 31244)))
+            .collect();
+        group.bench_with_input(
+            BenchmarkId::new("merge_candidate_peers", count),
+            &peers,
+            |b, peers| {
+                b.iter_batched(
+                    || PeerInfoDatabase::from_peers(&cfg, peers.clone(), Box::new(SystemClock)),
+                    |mut db| db.merge_candidate_peers(black_box(&new_peers)).unwrap(),
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+    }
+    group.finish();
+}
+
+#[cfg(feature = "benchmarking")]
+criterion_group!(benches, criterion_benchmark);
+
+#[cfg(feature = "benchmarking")]
+criterion_main!(benches);
+
+#[cfg(not(feature = "benchmarking"))]
+fn main() {
+    println!("Please use the `--features benchmarking` flag to run this benchmark.");
+}
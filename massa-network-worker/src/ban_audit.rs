@@ -0,0 +1,106 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Append-only log of the bans and unbans, so that operators can reconstruct why an ip was banned.
+//!
+//! Each ban or unban of an ip address, a subnet or a node id is appended to the `ban_audit_file`
+//! as a JSON object on its own line, ex:
+//! `{"time":1671926400000,"action":"ban","target":"169.202.0.1","reason":"banned by the operator","expiry":null}`.
+//! The lines are written by a spawned task, so that the peer database never waits for the disk.
+
+use massa_models::ban_list::IpSubnet;
+use massa_models::node::NodeId;
+use massa_time::MassaTime;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+/// Whether a target was banned or unbanned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum BanAuditAction {
+    Ban,
+    Unban,
+}
+
+/// Banned or unbanned ip address, subnet or node id
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum BanAuditTarget {
+    Subnet(IpSubnet),
+    Node(NodeId),
+}
+
+/// Line of the ban audit log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct BanAuditRecord {
+    /// time of the ban or unban
+    pub time: MassaTime,
+    pub action: BanAuditAction,
+    pub target: BanAuditTarget,
+    /// why the target was banned, none for the unbans
+    pub reason: Option<String>,
+    /// time after which the ban is lifted, none if it lasts until it is lifted by hand or for the unbans
+    pub expiry: Option<MassaTime>,
+}
+
+/// Appender of the records to the ban audit log
+pub(crate) struct BanAuditLog {
+    record_tx: mpsc::UnboundedSender<BanAuditRecord>,
+    join_handle: JoinHandle<()>,
+}
+
+impl BanAuditLog {
+    /// Spawn the task appending the records to the file at `path`, created if missing.
+    /// A warning is raised for each record that could not be written.
+    pub fn start(path: PathBuf) -> Self {
+        let (record_tx, mut record_rx) = mpsc::unbounded_channel::<BanAuditRecord>();
+        let join_handle = tokio::spawn(async move {
+            while let Some(record) = record_rx.recv().await {
+                let mut line = match serde_json::to_vec(&record) {
+                    Ok(line) => line,
+                    Err(e) => {
+                        warn!("could not serialize ban audit record: {}", e);
+                        continue;
+                    }
+                };
+                line.push(b'\n');
+                // reopened for each record so that the log can be rotated while the node runs
+                let res = async {
+                    tokio::fs::OpenOptions::new()
+                        .create(true)
+                        .append(true)
+                        .open(&path)
+                        .await?
+                        .write_all(&line)
+                        .await
+                }
+                .await;
+                if let Err(e) = res {
+                    warn!("could not append to the ban audit file: {}", e);
+                }
+            }
+        });
+        BanAuditLog {
+            record_tx,
+            join_handle,
+        }
+    }
+
+    /// Queue a record to be appended to the log
+    pub fn append(&self, record: BanAuditRecord) {
+        if self.record_tx.send(record).is_err() {
+            warn!("could not send the record to the ban audit log task");
+        }
+    }
+
+    /// Wait until the queued records are written
+    pub async fn stop(self) {
+        drop(self.record_tx);
+        if let Err(e) = self.join_handle.await {
+            warn!("ban audit log task failed: {}", e);
+        }
+    }
+}
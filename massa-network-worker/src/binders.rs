@@ -1,25 +1,136 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
-//! `Flexbuffer` layer between raw data and our objects.
+//! Framing layer between raw data and our objects.
+//!
+//! Each message is sent as a frame: a 4-byte big endian length prefix, the serialized message, and the CRC32
+//! checksum of the serialized message if the highest bit of the prefix is set. The checksum is optional for
+//! the sender, the receiver checks it whenever it is present. The frames larger than the max message size
+//! are rejected as soon as their prefix is read.
 use crate::messages::{MessageDeserializer, MessageSerializer};
 
 use super::messages::Message;
-use massa_models::{
-    error::ModelsError,
-    serialization::{DeserializeMinBEInt, SerializeMinBEInt},
-};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use futures::{SinkExt, StreamExt};
+use massa_metrics::NETWORK_METRICS;
+use massa_models::error::ModelsError;
 use massa_network_exports::{NetworkError, ReadHalf, WriteHalf};
 use massa_serialization::Serializer;
-use massa_serialization::{DeserializeError, Deserializer};
+use massa_serialization::{with_shared_buffer, DeserializeError, Deserializer};
 use std::convert::TryInto;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+use tokio_util::codec::{Decoder, Encoder, FramedRead, FramedWrite};
 use tracing::warn;
 
+/// Size of the length prefix of a frame
+const LENGTH_PREFIX_SIZE: usize = 4;
+/// Size of the checksum following the payload of a checksummed frame
+const CHECKSUM_SIZE: usize = 4;
+/// Bit of the length prefix telling that the payload is followed by its CRC32 checksum
+const CHECKSUM_FLAG: u32 = 1 << 31;
+
+/// Codec of the frames carrying the messages
+#[derive(Debug, Clone)]
+pub struct FrameCodec {
+    max_frame_size: u32,
+    checksum: bool,
+    /// number of bytes of the frames encoded or decoded so far
+    frame_bytes: Arc<AtomicU64>,
+}
+
+impl FrameCodec {
+    /// Creates a codec of frames of at most `max_frame_size` bytes, without their prefix and checksum.
+    /// If `checksum` is true, the encoded frames carry the checksum of their payload.
+    pub fn new(max_frame_size: u32, checksum: bool) -> Self {
+        FrameCodec {
+            max_frame_size: max_frame_size.min(!CHECKSUM_FLAG),
+            checksum,
+            frame_bytes: Default::default(),
+        }
+    }
+
+    /// Get the counter of the bytes of the frames encoded or decoded so far
+    pub fn frame_bytes(&self) -> Arc<AtomicU64> {
+        self.frame_bytes.clone()
+    }
+}
+
+impl Decoder for FrameCodec {
+    type Item = BytesMut;
+    type Error = NetworkError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<BytesMut>, NetworkError> {
+        if src.len() < LENGTH_PREFIX_SIZE {
+            return Ok(None);
+        }
+        let prefix = u32::from_be_bytes(
+            src[..LENGTH_PREFIX_SIZE]
+                .try_into()
+                .expect("the length prefix has 4 bytes"),
+        );
+        let (has_checksum, size) = (prefix & CHECKSUM_FLAG != 0, prefix & !CHECKSUM_FLAG);
+        if size > self.max_frame_size {
+            return Err(NetworkError::GeneralProtocolError(format!(
+                "frame of {} bytes exceeds the max message size of {} bytes",
+                size, self.max_frame_size
+            )));
+        }
+        let frame_len =
+            LENGTH_PREFIX_SIZE + size as usize + if has_checksum { CHECKSUM_SIZE } else { 0 };
+        if src.len() < frame_len {
+            return Ok(None);
+        }
+        src.advance(LENGTH_PREFIX_SIZE);
+        let payload = src.split_to(size as usize);
+        if has_checksum && src.get_u32() != crc32fast::hash(&payload) {
+            return Err(NetworkError::GeneralProtocolError(
+                "frame checksum mismatch".into(),
+            ));
+        }
+        self.frame_bytes
+            .fetch_add(frame_len as u64, Ordering::Relaxed);
+        Ok(Some(payload))
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<BytesMut>, NetworkError> {
+        // a frame interrupted by the end of the stream is dropped with the connection
+        self.decode(src)
+    }
+}
+
+impl Encoder<Bytes> for FrameCodec {
+    type Error = NetworkError;
+
+    fn encode(&mut self, payload: Bytes, dst: &mut BytesMut) -> Result<(), NetworkError> {
+        let size: u32 = payload
+            .len()
+            .try_into()
+            .ok()
+            .filter(|size| *size <= self.max_frame_size)
+            .ok_or_else(|| NetworkError::GeneralProtocolError("message too long".into()))?;
+        let frame_len =
+            LENGTH_PREFIX_SIZE + payload.len() + if self.checksum { CHECKSUM_SIZE } else { 0 };
+        dst.reserve(frame_len);
+        if self.checksum {
+            dst.put_u32(size | CHECKSUM_FLAG);
+            dst.put_slice(&payload);
+            dst.put_u32(crc32fast::hash(&payload));
+        } else {
+            dst.put_u32(size);
+            dst.put_slice(&payload);
+        }
+        self.frame_bytes
+            .fetch_add(frame_len as u64, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
 /// Used to serialize and send data.
 pub struct WriteBinder {
-    pub(crate) write_half: WriteHalf,
+    framed: FramedWrite<WriteHalf, FrameCodec>,
     message_index: u64,
-    max_message_size: u32,
 }
 
 impl WriteBinder {
@@ -28,48 +139,59 @@ impl WriteBinder {
     /// # Argument
     /// * `write_half`: writer half.
     /// * `limit`: limit max bytes per second write
-    pub fn new(write_half: WriteHalf, _limit: f64, max_message_size: u32) -> Self {
+    /// * `max_message_size`: max size of a sent message
+    /// * `checksum`: whether the sent frames carry a checksum
+    pub fn new(write_half: WriteHalf, _limit: f64, max_message_size: u32, checksum: bool) -> Self {
         WriteBinder {
-            write_half,
+            framed: FramedWrite::new(write_half, FrameCodec::new(max_message_size, checksum)),
             message_index: 0,
-            max_message_size,
         }
     }
 
-    /// Sends a serialized message.
+    /// Get the counter of the bytes written so far
+    pub fn bytes_written(&self) -> Arc<AtomicU64> {
+        self.framed.encoder().frame_bytes()
+    }
+
+    /// Sends a serialized message, along with the buffered ones.
     ///
     /// # Argument
     /// * `buf`: data to transmit.
     pub async fn send(&mut self, msg: &Message) -> Result<u64, NetworkError> {
+        let res_index = self.feed(msg).await?;
+        self.flush().await?;
+        Ok(res_index)
+    }
+
+    /// Buffers a serialized message, to be sent at the next flush.
+    /// Only waits for the socket if the buffer is full.
+    pub async fn feed(&mut self, msg: &Message) -> Result<u64, NetworkError> {
         //        massa_trace!("binder.send", { "msg": msg });
         let mut buf = Vec::new();
         MessageSerializer::new().serialize(msg, &mut buf)?;
-        let msg_size: u32 = buf
-            .len()
-            .try_into()
-            .map_err(|_| NetworkError::GeneralProtocolError("message too long".into()))?;
-        self.write_half
-            .write_all(&msg_size.to_be_bytes_min(self.max_message_size)?[..])
-            .await?;
-
-        // send message
-        self.write_half.write_all(&buf).await?;
+        self.framed.feed(Bytes::from(buf)).await?;
+        NETWORK_METRICS.written_messages.inc();
 
         let res_index = self.message_index;
         self.message_index += 1;
         //        massa_trace!("binder.send END", { "index": res_index });
         Ok(res_index)
     }
+
+    /// Sends the buffered messages
+    pub async fn flush(&mut self) -> Result<(), NetworkError> {
+        self.framed.flush().await?;
+        NETWORK_METRICS.socket_flushes.inc();
+        Ok(())
+    }
 }
 
 /// Used to receive and deserialize data.
+/// The deserialized operations, endorsements and headers making up most of the frame they were received in
+/// share its memory, the others are copied.
 pub struct ReadBinder {
-    read_half: ReadHalf,
+    framed: FramedRead<ReadHalf, FrameCodec>,
     message_index: u64,
-    buf: Vec<u8>,
-    cursor: usize,
-    msg_size: Option<u32>,
-    max_message_size: u32,
     message_deserializer: MessageDeserializer,
 }
 
@@ -86,103 +208,46 @@ impl ReadBinder {
         message_deserializer: MessageDeserializer,
     ) -> Self {
         ReadBinder {
-            read_half,
+            framed: FramedRead::new(read_half, FrameCodec::new(max_message_size, false)),
             message_index: 0,
-            buf: Vec::new(),
-            cursor: 0,
-            msg_size: None,
-            max_message_size,
             message_deserializer,
         }
     }
 
+    /// Get the counter of the bytes read so far
+    pub fn bytes_read(&self) -> Arc<AtomicU64> {
+        self.framed.decoder().frame_bytes()
+    }
+
     /// Awaits the next incoming message and deserializes it. Asynchronous cancel-safe.
     /// Returns the message, as well as the serialized object in the case of a block.
     ///
     /// This function must be asynchronous cancel-safe.
-    /// This means that the function can restart from the beginning at any "await" point and we need to avoid losing any data,
-    /// and we can only use the cancel-safe read() function
-    /// `https://doc.rust-lang.org/std/io/trait.Read.html#tymethod.read`
-    /// that returns an arbitrary number of bytes that were received that is `> 0 and <= buffer.len()`,
-    /// or = 0 if there is no more data.
-    /// We can't use `read_exact` and similar because they are not cancel-safe:
-    /// `https://docs.rs/tokio/latest/tokio/io/trait.AsyncReadExt.html#cancel-safety-2`
+    /// This means that the function can restart from the beginning at any "await" point and we need to avoid losing any data.
+    /// The received bytes are kept in the buffer of the framed reader until a whole frame is available,
+    /// so that the readout resumes smoothly when the function is called again.
     pub async fn next(&mut self) -> Result<Option<(u64, Message)>, NetworkError> {
-        // check if we are in the process of reading the message length
-        if self.msg_size.is_none() {
-            // pre-allocate the buffer to fit the encoded message size if the buffer is not already allocated
-            let size_field_len = u32::be_bytes_min_length(self.max_message_size);
-            if self.buf.len() != size_field_len {
-                self.buf = vec![0u8; size_field_len];
-            }
-
-            // Try to read the full message size field
-            // The self.cursor variable indicates how many bytes of the "message size" field we have received so far.
-            // We need to keep all states (buffer and cursor) to ensure that if the function restarts at the read's await,
-            // the state will remain consistent and resume the readout smoothly.
-            while self.cursor < size_field_len {
-                match self.read_half.read(&mut self.buf[self.cursor..]).await {
-                    Ok(nr) => {
-                        if nr == 0 {
-                            return Ok(None);
-                        }
-                        self.cursor += nr;
-                    }
-                    Err(err) => {
-                        if err.kind() == std::io::ErrorKind::UnexpectedEof {
-                            return Ok(None);
-                        } else {
-                            return Err(err.into());
-                        }
-                    }
-                }
+        let frame = match self.framed.next().await {
+            Some(Ok(frame)) => frame,
+            Some(Err(NetworkError::IOError(err)))
+                if err.kind() == std::io::ErrorKind::UnexpectedEof =>
+            {
+                return Ok(None)
             }
-
-            // once we have all the message size bytes, deserialize it
-            let res_size = u32::from_be_bytes_min(&self.buf, self.max_message_size)?.0;
-            // set self.msg_size to indicate that we are now in the process of reading the message contents (and not the size anymore).
-            self.msg_size = Some(res_size);
-            // allocate the buffer to match the message length
-            if self.buf.len() != (res_size as usize) {
-                self.buf = vec![0u8; res_size as usize];
-            }
-            // reset the cursor so that it now represents how many content bytes have been read so far
-            self.cursor = 0;
-        }
-
-        // read message in the same cancel-safe way as msg_size above
-        while self.cursor < self.msg_size.unwrap() as usize {
-            // does not panic
-            match self.read_half.read(&mut self.buf[self.cursor..]).await {
-                Ok(nr) => {
-                    if nr == 0 {
-                        return Ok(None);
-                    }
-                    self.cursor += nr;
-                }
-                Err(err) => {
-                    if err.kind() == std::io::ErrorKind::UnexpectedEof {
-                        return Ok(None);
-                    } else {
-                        return Err(err.into());
-                    }
-                }
-            }
-        }
-        let (_, res_msg) = self
-            .message_deserializer
-            .deserialize::<DeserializeError>(&self.buf)
-            .map_err(|err| {
-                warn!("error deserializing message: {:?}", err);
-                NetworkError::ModelsError(ModelsError::DeserializeError(err.to_string()))
-            })?;
-
-        // now the message readout is over, we reset the state to start reading the next message's size field again at the next run
-        self.cursor = 0;
-        self.msg_size = None;
-
-        // clear the buffer to not leave dangling data around (note that clear() doesn't deallocate)
-        self.buf.clear();
+            Some(Err(err)) => return Err(err),
+            None => return Ok(None),
+        };
+        // the serialized objects of the message are views of the frame instead of copies
+        let frame = frame.freeze();
+        let res_msg = with_shared_buffer(&frame, |buffer| {
+            self.message_deserializer
+                .deserialize::<DeserializeError>(buffer)
+                .map(|(_, message)| message)
+                .map_err(|err| {
+                    warn!("error deserializing message: {:?}", err);
+                    NetworkError::ModelsError(ModelsError::DeserializeError(err.to_string()))
+                })
+        })?;
 
         // update sequence numbers and return the deserialized message
         let res_index = self.message_index;
@@ -0,0 +1,275 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! State machine of the connections of the network worker.
+//!
+//! A connection goes through `Dialing` (outgoing connections only), `Handshaking`, `Connected`
+//! and `Closing`, and is forgotten once closed. Each transition notifies the `PeerInfoDatabase`,
+//! so that its connection counts always match the connections held by the worker.
+//! Ended connections, refused ones included, are added to the connection history of their peer.
+//! The TCP connect latencies and the durations of the successful handshakes are accounted
+//! in the connection timings of the network group of the peer.
+
+use crate::peer_info_database::PeerInfoDatabase;
+use massa_models::api::{PeerConnectionCloseReason, PeerConnectionEvent};
+use massa_models::node::NodeId;
+use massa_models::version::Version;
+use massa_network_exports::{ConnectionClosureReason, ConnectionId, NetworkError};
+use massa_time::MassaTime;
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+/// State of a connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ConnectionState {
+    /// Outgoing connection being established
+    Dialing,
+    /// Connection established, handshake running
+    Handshaking,
+    /// Handshake succeeded, a node worker runs for the node
+    Connected(NodeId),
+    /// Connection asked to close (ex: peer banned), waiting for its handshake or node worker to end
+    Closing,
+}
+
+/// Connection held by the network worker
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Connection {
+    /// ip of the peer
+    pub ip: IpAddr,
+    /// true if we initiated the connection
+    pub is_outgoing: bool,
+    /// current state
+    pub state: ConnectionState,
+    /// when the connection started to be dialed or was accepted
+    pub start: MassaTime,
+    /// when the handshake started, once the connection was established or accepted
+    pub handshake_start: MassaTime,
+}
+
+/// Connections of the network worker, by id
+#[derive(Default)]
+pub(crate) struct Connections {
+    /// connections that are not closed yet
+    connections: HashMap<ConnectionId, Connection>,
+    /// id of the next connection
+    next_id: ConnectionId,
+}
+
+impl Connections {
+    /// Get a connection
+    pub fn get(&self, id: &ConnectionId) -> Option<&Connection> {
+        self.connections.get(id)
+    }
+
+    /// Iterate over the connections
+    pub fn iter(&self) -> impl Iterator<Item = (&ConnectionId, &Connection)> {
+        self.connections.iter()
+    }
+
+    /// Start an outgoing connection attempt towards `ip`, a new connection in `Dialing` state
+    pub fn dial(
+        &mut self,
+        peer_info_db: &mut PeerInfoDatabase,
+        ip: IpAddr,
+    ) -> Result<ConnectionId, NetworkError> {
+        let start = peer_info_db.now()?;
+        peer_info_db.new_out_connection_attempt(&ip)?;
+        Ok(self.insert(ip, true, ConnectionState::Dialing, start))
+    }
+
+    /// `Dialing` connection is established: it goes `Handshaking`.
+    /// Returns false if the peer cannot take it anymore (no slot left or banned meanwhile):
+    /// the connection is forgotten and should be dropped.
+    pub fn dial_succeeded(
+        &mut self,
+        peer_info_db: &mut PeerInfoDatabase,
+        id: ConnectionId,
+    ) -> Result<bool, NetworkError> {
+        let connection = self.expect_state(id, |state| state == ConnectionState::Dialing)?;
+        let now = peer_info_db.now()?;
+        peer_info_db.record_dial_latency(&connection.ip, now.saturating_sub(connection.start));
+        if peer_info_db.try_out_connection_attempt_success(&connection.ip)? {
+            if let Some(connection) = self.connections.get_mut(&id) {
+                connection.state = ConnectionState::Handshaking;
+                connection.handshake_start = now;
+            }
+            Ok(true)
+        } else {
+            self.connections.remove(&id);
+            record_end(
+                peer_info_db,
+                &connection,
+                PeerConnectionCloseReason::Refused,
+            )?;
+            Ok(false)
+        }
+    }
+
+    /// `Dialing` connection could not be established: it is forgotten
+    pub fn dial_failed(
+        &mut self,
+        peer_info_db: &mut PeerInfoDatabase,
+        id: ConnectionId,
+    ) -> Result<(), NetworkError> {
+        let connection = self.expect_state(id, |state| state == ConnectionState::Dialing)?;
+        peer_info_db.out_connection_attempt_failed(&connection.ip)?;
+        self.connections.remove(&id);
+        record_end(
+            peer_info_db,
+            &connection,
+            PeerConnectionCloseReason::DialFailed,
+        )?;
+        Ok(())
+    }
+
+    /// Accept an incoming connection from `ip`, a new connection in `Handshaking` state
+    pub fn accept(
+        &mut self,
+        peer_info_db: &mut PeerInfoDatabase,
+        ip: IpAddr,
+    ) -> Result<ConnectionId, NetworkError> {
+        let start = peer_info_db.now()?;
+        if let Err(err) = peer_info_db.try_new_in_connection(&ip) {
+            // refused because of the state of the peer, not because of an internal error
+            if matches!(err, NetworkError::PeerConnectionError(_)) {
+                peer_info_db.record_connection(
+                    &ip,
+                    PeerConnectionEvent {
+                        start,
+                        is_outgoing: false,
+                        duration: MassaTime::from_millis(0),
+                        close_reason: PeerConnectionCloseReason::Refused,
+                    },
+                );
+            }
+            return Err(err);
+        }
+        Ok(self.insert(ip, false, ConnectionState::Handshaking, start))
+    }
+
+    /// `Handshaking` connection identified `node_id` running `version`: it goes `Connected` and the peer is alive
+    pub fn handshake_succeeded(
+        &mut self,
+        peer_info_db: &mut PeerInfoDatabase,
+        id: ConnectionId,
+        node_id: NodeId,
+        version: Version,
+    ) -> Result<(), NetworkError> {
+        let connection = self.expect_state(id, |state| state == ConnectionState::Handshaking)?;
+        let duration = peer_info_db
+            .now()?
+            .saturating_sub(connection.handshake_start);
+        peer_info_db.record_handshake_duration(&connection.ip, connection.is_outgoing, duration);
+        peer_info_db.peer_alive(&connection.ip, node_id, version)?;
+        self.set_state(id, ConnectionState::Connected(node_id));
+        Ok(())
+    }
+
+    /// Ask a `Handshaking` or `Connected` connection to close: it goes `Closing`.
+    /// `Dialing` connections are left as they are, the peer database refuses them once established.
+    pub fn start_closing(&mut self, id: &ConnectionId) {
+        if let Some(connection) = self.connections.get_mut(id) {
+            if connection.state != ConnectionState::Dialing {
+                connection.state = ConnectionState::Closing;
+            }
+        }
+    }
+
+    /// Established connection closed for `reason`: it is forgotten and returned
+    pub fn closed(
+        &mut self,
+        peer_info_db: &mut PeerInfoDatabase,
+        id: ConnectionId,
+        reason: ConnectionClosureReason,
+    ) -> Result<Connection, NetworkError> {
+        let connection = self.expect_state(id, |state| state != ConnectionState::Dialing)?;
+        let close_reason = match reason {
+            ConnectionClosureReason::Normal => PeerConnectionCloseReason::Normal,
+            ConnectionClosureReason::Failed => {
+                peer_info_db.peer_failed(&connection.ip)?;
+                PeerConnectionCloseReason::Failed
+            }
+            ConnectionClosureReason::Banned => {
+                // nothing here, because peer_info_db.peer_banned called in NetworkCommand::Ban
+                PeerConnectionCloseReason::Banned
+            }
+            ConnectionClosureReason::SendQueueOverflow => {
+                peer_info_db.peer_failed(&connection.ip)?;
+                PeerConnectionCloseReason::SendQueueOverflow
+            }
+        };
+        if connection.is_outgoing {
+            peer_info_db.out_connection_closed(&connection.ip)?;
+        } else {
+            peer_info_db.in_connection_closed(&connection.ip)?;
+        }
+        self.connections.remove(&id);
+        record_end(peer_info_db, &connection, close_reason)?;
+        Ok(connection)
+    }
+
+    fn insert(
+        &mut self,
+        ip: IpAddr,
+        is_outgoing: bool,
+        state: ConnectionState,
+        start: MassaTime,
+    ) -> ConnectionId {
+        let id = self.next_id;
+        self.next_id.0 += 1;
+        self.connections.insert(
+            id,
+            Connection {
+                ip,
+                is_outgoing,
+                state,
+                start,
+                handshake_start: start,
+            },
+        );
+        id
+    }
+
+    fn set_state(&mut self, id: ConnectionId, state: ConnectionState) {
+        if let Some(connection) = self.connections.get_mut(&id) {
+            connection.state = state;
+        }
+    }
+
+    /// Get the connection `id`, failing if its state is not one the transition starts from
+    fn expect_state(
+        &self,
+        id: ConnectionId,
+        allowed: impl Fn(ConnectionState) -> bool,
+    ) -> Result<Connection, NetworkError> {
+        let connection = *self
+            .connections
+            .get(&id)
+            .ok_or(NetworkError::ActiveConnectionMissing(id))?;
+        if !allowed(connection.state) {
+            return Err(NetworkError::InvalidConnectionTransition(
+                id,
+                format!("{:?}", connection.state),
+            ));
+        }
+        Ok(connection)
+    }
+}
+
+/// Add the ended `connection` to the history of its peer
+fn record_end(
+    peer_info_db: &mut PeerInfoDatabase,
+    connection: &Connection,
+    close_reason: PeerConnectionCloseReason,
+) -> Result<(), NetworkError> {
+    peer_info_db.record_connection(
+        &connection.ip,
+        PeerConnectionEvent {
+            start: connection.start,
+            is_outgoing: connection.is_outgoing,
+            duration: peer_info_db.now()?.saturating_sub(connection.start),
+            close_reason,
+        },
+    );
+    Ok(())
+}
@@ -0,0 +1,111 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Faults injected in the messages sent by the node workers.
+//!
+//! The faults are set by the network worker and read by the node workers through a shared `FaultInjector`.
+//! They are only applied with the `fault_injection` feature: otherwise setting them is refused,
+//! and the node workers send their messages untouched.
+
+use massa_models::network_faults::{MessageFaults, NetworkFaults};
+use massa_models::node::NodeId;
+use massa_network_exports::NetworkError;
+use std::sync::{Arc, RwLock};
+
+#[cfg(feature = "fault_injection")]
+use crate::messages::Message;
+#[cfg(feature = "fault_injection")]
+use rand::{rngs::StdRng, Rng, SeedableRng};
+#[cfg(feature = "fault_injection")]
+use std::time::Duration;
+
+/// Faults currently injected, shared by the network worker and the node workers
+#[derive(Clone, Default)]
+pub(crate) struct FaultInjector(Arc<RwLock<NetworkFaults>>);
+
+impl FaultInjector {
+    /// Faults of the messages sent to `node_id`, if any
+    pub fn get(&self, node_id: &NodeId) -> Option<MessageFaults> {
+        self.0
+            .read()
+            .expect("fault injector lock poisoned")
+            .get(node_id)
+            .cloned()
+    }
+
+    /// Set the faults of the messages sent to `node_id`, or to all the peers if `node_id` is `None`,
+    /// returning all the faults then injected
+    #[cfg(feature = "fault_injection")]
+    pub fn set(
+        &self,
+        node_id: Option<NodeId>,
+        faults: Option<MessageFaults>,
+    ) -> Result<NetworkFaults, NetworkError> {
+        if let Some(faults) = &faults {
+            faults.check()?;
+        }
+        let mut network_faults = self.0.write().expect("fault injector lock poisoned");
+        network_faults.set(node_id, faults);
+        Ok(network_faults.clone())
+    }
+
+    /// Refuse to set faults, the node is not built to inject them
+    #[cfg(not(feature = "fault_injection"))]
+    pub fn set(
+        &self,
+        _node_id: Option<NodeId>,
+        _faults: Option<MessageFaults>,
+    ) -> Result<NetworkFaults, NetworkError> {
+        Err(NetworkError::FaultInjectionDisabled)
+    }
+}
+
+/// Applies the faults to the messages sent to one node
+#[cfg(feature = "fault_injection")]
+pub(crate) struct FaultyWriter {
+    /// message held back by a reordering, sent after the next message
+    held_back: Option<Message>,
+    rng: StdRng,
+}
+
+#[cfg(feature = "fault_injection")]
+impl FaultyWriter {
+    pub fn new() -> Self {
+        FaultyWriter {
+            held_back: None,
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    /// Returns the messages to send instead of `messages`, in order,
+    /// and the delay to wait before sending them
+    pub fn apply(
+        &mut self,
+        faults: &MessageFaults,
+        messages: Vec<Message>,
+    ) -> (Vec<Message>, Duration) {
+        let mut to_send = Vec::with_capacity(messages.len());
+        for message in messages {
+            if self.rng.gen_bool(faults.drop_ratio) {
+                continue;
+            }
+            if self.held_back.is_none() && self.rng.gen_bool(faults.reorder_ratio) {
+                self.held_back = Some(message);
+                continue;
+            }
+            if self.rng.gen_bool(faults.duplicate_ratio) {
+                to_send.push(message.clone());
+            }
+            to_send.push(message);
+            if let Some(held_back) = self.held_back.take() {
+                to_send.push(held_back);
+            }
+        }
+        let delay = if faults.min_delay < faults.max_delay {
+            self.rng
+                .gen_range(faults.min_delay.to_millis()..=faults.max_delay.to_millis())
+        } else {
+            faults.min_delay.to_millis()
+        };
+        (to_send, Duration::from_millis(delay))
+    }
+}
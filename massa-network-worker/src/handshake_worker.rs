@@ -1,6 +1,11 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
 //! Here are happening handshakes.
+//!
+//! Each side sends a random nonce, and proves that it owns the key of the node id it announces by signing
+//! the nonce of the other side together with its own nonce and node id. The signature is thus bound to
+//! this connection and to the announced node id: it can not be replayed on another connection, and a peer
+//! announcing a node id it does not own is rejected.
 
 use crate::messages::MessageDeserializer;
 
@@ -21,7 +26,7 @@ use massa_models::{
     version::Version,
 };
 use massa_models::{
-    config::{MAX_ASK_BLOCKS_PER_MESSAGE, MAX_OPERATIONS_PER_MESSAGE},
+    config::{MAX_ASK_BLOCKS_PER_MESSAGE, MAX_BLOCK_RANGE_LENGTH, MAX_OPERATIONS_PER_MESSAGE},
     node::NodeId,
 };
 use massa_network_exports::{
@@ -34,8 +39,29 @@ use rand::{rngs::StdRng, RngCore, SeedableRng};
 use tokio::{task::JoinHandle, time::timeout};
 use tracing::debug;
 
-/// Type alias for more readability
-pub type HandshakeReturnType = Result<(NodeId, ReadBinder, WriteBinder), NetworkError>;
+/// Type alias for more readability.
+/// On success: the peer node id, the binders to communicate with it,
+/// the estimated offset of its clock relative to ours, in milliseconds,
+/// whether the peer is an observer node, and the software version it announced.
+pub type HandshakeReturnType =
+    Result<(NodeId, ReadBinder, WriteBinder, i64, bool, Version), NetworkError>;
+
+/// Domain of the handshake signatures, so that they can not be mistaken for signatures of other objects
+const HANDSHAKE_CHALLENGE_DOMAIN: &[u8] = b"massa-network-handshake";
+
+/// Hash signed by `signer` to answer the `challenge` nonce of its peer, `signer_random_bytes` being its own nonce
+fn handshake_challenge(
+    challenge: &[u8; 32],
+    signer_random_bytes: &[u8; 32],
+    signer: &NodeId,
+) -> Hash {
+    Hash::compute_from_tuple(&[
+        HANDSHAKE_CHALLENGE_DOMAIN,
+        challenge,
+        signer_random_bytes,
+        signer.get_public_key().to_bytes(),
+    ])
+}
 
 /// Manages handshakes.
 pub struct HandshakeWorker {
@@ -50,6 +76,10 @@ pub struct HandshakeWorker {
     /// After `timeout_duration` milliseconds, the handshake attempt is dropped.
     timeout_duration: MassaTime,
     version: Version,
+    /// Our network ID, peers with a different one are refused.
+    network_id: Hash,
+    /// True if we are an observer node, announced to the peer.
+    observer: bool,
 }
 
 impl HandshakeWorker {
@@ -69,6 +99,8 @@ impl HandshakeWorker {
     /// * `timeout_duration`: after `timeout_duration` milliseconds, the handshake attempt is dropped.
     /// * `connection_id`: Node we are trying to connect for debugging
     /// * `version`: Node version used in handshake initialization (check peers compatibility)
+    /// * `network_id`: ID of our network, derived from the genesis parameters (check peers compatibility)
+    /// * `observer`: true if we are an observer node, announced to the peer
     #[allow(clippy::too_many_arguments)]
     pub fn spawn(
         socket_reader: ReadHalf,
@@ -77,9 +109,12 @@ impl HandshakeWorker {
         keypair: KeyPair,
         timeout_duration: MassaTime,
         version: Version,
+        network_id: Hash,
+        observer: bool,
         connection_id: ConnectionId,
         max_bytes_read: f64,
         max_bytes_write: f64,
+        message_checksum: bool,
     ) -> JoinHandle<(ConnectionId, HandshakeReturnType)> {
         debug!("starting handshake with connection_id={}", connection_id);
         massa_trace!("network_worker.new_connection", {
@@ -100,6 +135,7 @@ impl HandshakeWorker {
                             ENDORSEMENT_COUNT,
                             MAX_ADVERTISE_LENGTH,
                             MAX_ASK_BLOCKS_PER_MESSAGE,
+                            MAX_BLOCK_RANGE_LENGTH,
                             MAX_OPERATIONS_PER_BLOCK,
                             MAX_OPERATIONS_PER_MESSAGE,
                             MAX_ENDORSEMENTS_PER_MESSAGE,
@@ -111,11 +147,18 @@ impl HandshakeWorker {
                             MAX_OPERATION_DATASTORE_VALUE_LENGTH,
                         ),
                     ),
-                    writer: WriteBinder::new(socket_writer, max_bytes_write, MAX_MESSAGE_SIZE),
+                    writer: WriteBinder::new(
+                        socket_writer,
+                        max_bytes_write,
+                        MAX_MESSAGE_SIZE,
+                        message_checksum,
+                    ),
                     self_node_id,
                     keypair,
                     timeout_duration,
                     version,
+                    network_id,
+                    observer,
                 }
                 .run()
                 .await,
@@ -131,12 +174,13 @@ impl HandshakeWorker {
         // generate random bytes
         let mut self_random_bytes = [0u8; 32];
         StdRng::from_entropy().fill_bytes(&mut self_random_bytes);
-        let self_random_hash = Hash::compute_from(&self_random_bytes);
         // send handshake init future
         let msg = Message::HandshakeInitiation {
             public_key: self.self_node_id.get_public_key(),
             random_bytes: self_random_bytes,
             version: self.version,
+            network_id: self.network_id,
+            observer: self.observer,
         };
         let send_init_fut = self.writer.send(&msg);
 
@@ -144,25 +188,28 @@ impl HandshakeWorker {
         let recv_init_fut = self.reader.next();
 
         // join send_init_fut and recv_init_fut with a timeout, and match result
-        let (other_node_id, other_random_bytes, other_version) = match timeout(
-            self.timeout_duration.to_duration(),
-            try_join(send_init_fut, recv_init_fut),
-        )
-        .await
-        {
-            Err(_) => throw!(HandshakeTimeout),
-            Ok(Err(e)) => return Err(e),
-            Ok(Ok((_, None))) => throw!(HandshakeInterruption, "init".into()),
-            Ok(Ok((_, Some((_, msg))))) => match msg {
-                Message::HandshakeInitiation {
-                    public_key: pk,
-                    random_bytes: rb,
-                    version,
-                } => (NodeId::new(pk), rb, version),
-                Message::PeerList(list) => throw!(PeerListReceived, list),
-                _ => throw!(HandshakeWrongMessage),
-            },
-        };
+        let (other_node_id, other_random_bytes, other_version, other_network_id, other_observer) =
+            match timeout(
+                self.timeout_duration.to_duration(),
+                try_join(send_init_fut, recv_init_fut),
+            )
+            .await
+            {
+                Err(_) => throw!(HandshakeTimeout),
+                Ok(Err(e)) => return Err(e),
+                Ok(Ok((_, None))) => throw!(HandshakeInterruption, "init".into()),
+                Ok(Ok((_, Some((_, msg))))) => match msg {
+                    Message::HandshakeInitiation {
+                        public_key: pk,
+                        random_bytes: rb,
+                        version,
+                        network_id,
+                        observer,
+                    } => (NodeId::new(pk), rb, version, network_id, observer),
+                    Message::PeerList(list) => throw!(PeerListReceived, list),
+                    _ => throw!(HandshakeWrongMessage),
+                },
+            };
 
         // check if remote node ID is the same as ours
         if other_node_id == self.self_node_id {
@@ -174,13 +221,23 @@ impl HandshakeWorker {
             throw!(IncompatibleVersion)
         }
 
-        // sign their random bytes
-        let other_random_hash = Hash::compute_from(&other_random_bytes);
-        let self_signature = self.keypair.sign(&other_random_hash)?;
+        // check if the peer belongs to our network
+        if other_network_id != self.network_id {
+            throw!(IncompatibleNetwork)
+        }
+
+        // sign their random bytes, bound to ours and to our node id
+        let self_signature = self.keypair.sign(&handshake_challenge(
+            &other_random_bytes,
+            &self_random_bytes,
+            &self.self_node_id,
+        ))?;
 
         // send handshake reply future
+        let reply_sent = MassaTime::now()?;
         let msg = Message::HandshakeReply {
             signature: self_signature,
+            timestamp: reply_sent,
         };
         let send_reply_fut = self.writer.send(&msg);
 
@@ -188,7 +245,7 @@ impl HandshakeWorker {
         let recv_reply_fut = self.reader.next();
 
         // join send_reply_fut and recv_reply_fut with a timeout, and match result
-        let other_signature = match timeout(
+        let (other_signature, other_timestamp) = match timeout(
             self.timeout_duration.to_duration(),
             try_join(send_reply_fut, recv_reply_fut),
         )
@@ -198,19 +255,37 @@ impl HandshakeWorker {
             Ok(Err(e)) => return Err(e),
             Ok(Ok((_, None))) => throw!(HandshakeInterruption, "repl".into()),
             Ok(Ok((_, Some((_, msg))))) => match msg {
-                Message::HandshakeReply { signature: sig } => sig,
+                Message::HandshakeReply {
+                    signature: sig,
+                    timestamp,
+                } => (sig, timestamp),
                 _ => throw!(HandshakeWrongMessage),
             },
         };
 
-        // check their signature
+        // check that they own the key of their node id
         other_node_id
             .get_public_key()
-            .verify_signature(&self_random_hash, &other_signature)
+            .verify_signature(
+                &handshake_challenge(&self_random_bytes, &other_random_bytes, &other_node_id),
+                &other_signature,
+            )
             .map_err(|_err| {
                 NetworkError::HandshakeError(HandshakeErrorType::HandshakeInvalidSignature)
             })?;
 
-        Ok((other_node_id, self.reader, self.writer))
+        // both replies are sent at about the same time: their timestamp is compared
+        // to the middle of the interval between sending ours and receiving theirs
+        let reply_received = MassaTime::now()?;
+        let clock_offset = other_timestamp.signed_diff(reply_sent.midpoint(reply_received));
+
+        Ok((
+            other_node_id,
+            self.reader,
+            self.writer,
+            clock_offset,
+            other_observer,
+            other_version,
+        ))
     }
 }
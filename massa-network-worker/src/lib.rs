@@ -14,15 +14,20 @@ use crate::{
 use massa_logging::massa_trace;
 use massa_models::{node::NodeId, version::Version};
 use massa_network_exports::{
-    BootstrapPeers, Establisher, NetworkCommand, NetworkCommandSender, NetworkConfig, NetworkError,
-    NetworkEvent, NetworkEventReceiver, NetworkManagementCommand, NetworkManager,
+    backpressure_channel, BootstrapPeers, Establisher, NetworkCommand, NetworkCommandSender,
+    NetworkConfig, NetworkError, NetworkEvent, NetworkEventReceiver, NetworkManagementCommand,
+    NetworkManager,
 };
 use massa_signature::KeyPair;
+use massa_time::Clock;
 use tokio::sync::mpsc;
-use tracing::{debug, error, info, warn};
+use tracing::{debug, error, info};
 
 //pub use establisher::Establisher;
+mod ban_audit;
 mod binders;
+mod connections;
+mod fault_injection;
 mod handshake_worker;
 mod messages;
 mod network_cmd_impl;
@@ -30,19 +35,27 @@ mod network_event;
 mod network_worker;
 mod node_worker;
 mod peer_info_database;
+mod peers_file;
 
 #[cfg(test)]
 pub mod tests;
 
+#[cfg(feature = "testing")]
+pub use peer_info_database::{cleanup_peers, PeerInfoDatabase};
+
 /// Starts a new `NetworkWorker` in a spawned task
 ///
 /// # Arguments
 /// * `cfg`: network configuration
+/// * `keypair`: keypair identifying the node on the network
+/// * `clock`: source of the current time of the peer database and of the connection retries
 pub async fn start_network_controller(
     network_settings: &NetworkConfig,
+    keypair: KeyPair,
     mut establisher: Establisher,
     initial_peers: Option<BootstrapPeers>,
     version: Version,
+    clock: Box<dyn Clock>,
 ) -> Result<
     (
         NetworkCommandSender,
@@ -62,28 +75,6 @@ pub async fn start_network_controller(
         }
     }
 
-    // try to read node keypair from file, otherwise generate it & write to file. Then derive nodeId
-    let keypair = if std::path::Path::is_file(&network_settings.keypair_file) {
-        // file exists: try to load it
-        let keypair_bs58_check_encoded = tokio::fs::read_to_string(&network_settings.keypair_file)
-            .await
-            .map_err(|err| {
-                std::io::Error::new(err.kind(), format!("could not load node key file: {}", err))
-            })?;
-        serde_json::from_slice::<KeyPair>(keypair_bs58_check_encoded.as_bytes())?
-    } else {
-        // node file does not exist: generate the key and save it
-        let keypair = KeyPair::generate();
-        if let Err(e) = tokio::fs::write(
-            &network_settings.keypair_file,
-            serde_json::to_string(&keypair)?,
-        )
-        .await
-        {
-            warn!("could not generate node key file: {}", e);
-        }
-        keypair
-    };
     let self_node_id = NodeId::new(keypair.get_public_key());
 
     info!("The node_id of this node is: {}", self_node_id);
@@ -94,7 +85,7 @@ pub async fn start_network_controller(
 
     debug!("Loading peer database");
     // load peer info database
-    let mut peer_info_db = PeerInfoDatabase::new(network_settings).await?;
+    let mut peer_info_db = PeerInfoDatabase::new(network_settings, clock).await?;
 
     // add bootstrap peers
     if let Some(peers) = initial_peers {
@@ -105,7 +96,7 @@ pub async fn start_network_controller(
     let (command_tx, controller_command_rx) =
         mpsc::channel::<NetworkCommand>(network_settings.controller_channel_size);
     let (controller_event_tx, event_rx) =
-        mpsc::channel::<NetworkEvent>(network_settings.event_channel_size);
+        backpressure_channel::<NetworkEvent>("network_events", network_settings.event_channel_size);
     let (manager_tx, controller_manager_rx) = mpsc::channel::<NetworkManagementCommand>(1);
     let cfg_copy = network_settings.clone();
     let keypair_cloned = keypair.clone();
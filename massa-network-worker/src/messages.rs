@@ -1,8 +1,11 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
-use massa_hash::HashDeserializer;
+use massa_hash::{Hash, HashDeserializer};
 use massa_models::{
-    block::{BlockHeader, BlockHeaderDeserializer, BlockId, WrappedHeader},
+    block::{
+        ArchivedBlock, ArchivedBlockDeserializer, ArchivedBlockSerializer, BlockHeader,
+        BlockHeaderDeserializer, BlockId, WrappedHeader,
+    },
     config::HANDSHAKE_RANDOMNESS_SIZE_BYTES,
     endorsement::{Endorsement, EndorsementDeserializer, WrappedEndorsement},
     operation::{
@@ -11,17 +14,23 @@ use massa_models::{
         OperationsSerializer, WrappedOperation,
     },
     serialization::array_from_slice,
-    serialization::{IpAddrDeserializer, IpAddrSerializer},
+    slot::{Slot, SlotDeserializer, SlotSerializer},
     version::{Version, VersionDeserializer, VersionSerializer},
     wrapped::{WrappedDeserializer, WrappedSerializer},
 };
-use massa_network_exports::{AskForBlocksInfo, BlockInfoReply};
+use massa_network_exports::{
+    AdvertisedPeer, AdvertisedPeerDeserializer, AdvertisedPeerSerializer, AskForBlocksInfo,
+    BlockInfoReply,
+};
 use massa_serialization::{
     Deserializer, SerializeError, Serializer, U32VarIntDeserializer, U32VarIntSerializer,
 };
 use massa_signature::{PublicKey, PublicKeyDeserializer, Signature, SignatureDeserializer};
+use massa_time::{MassaTime, MassaTimeDeserializer, MassaTimeSerializer};
 use nom::{
-    bytes::complete::take,
+    branch::alt,
+    bytes::complete::{tag, take},
+    combinator::value,
     error::{context, ContextError, ParseError},
     multi::length_count,
     sequence::tuple,
@@ -29,12 +38,11 @@ use nom::{
 };
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 use serde::{Deserialize, Serialize};
-use std::net::IpAddr;
 use std::ops::Bound::{Excluded, Included};
 
 /// All messages that can be sent or received.
 #[allow(clippy::large_enum_variant)]
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Message {
     /// Initiates handshake.
     HandshakeInitiation {
@@ -45,11 +53,18 @@ pub enum Message {
         /// let us know their public key.
         random_bytes: [u8; HANDSHAKE_RANDOMNESS_SIZE_BYTES],
         version: Version,
+        /// ID of the network we belong to, derived from the genesis parameters.
+        network_id: Hash,
+        /// True if we are an observer node: we do not produce blocks,
+        /// so peers should prefer other nodes when asking for blocks.
+        observer: bool,
     },
     /// Reply to a handshake initiation message.
     HandshakeReply {
         /// Signature of the received random bytes with our `keypair`.
         signature: Signature,
+        /// Our local time when sending the reply, used by the peer to measure its clock offset.
+        timestamp: MassaTime,
     },
     /// Block header
     BlockHeader(WrappedHeader),
@@ -63,7 +78,8 @@ pub enum Message {
     /// Peers are ordered from most to less reliable.
     /// If the ip of the node that sent that message is routable,
     /// it is the first ip of the list.
-    PeerList(Vec<IpAddr>),
+    /// Each peer comes with its addresses by order of preference, the first one being on its ip.
+    PeerList(Vec<AdvertisedPeer>),
     /// Batch of operation ids
     OperationsAnnouncement(OperationPrefixIds),
     /// Someone ask for operations.
@@ -72,6 +88,26 @@ pub enum Message {
     Operations(Vec<WrappedOperation>),
     /// Endorsements
     Endorsements(Vec<WrappedEndorsement>),
+    /// The sender is closing the connection normally, ex: because it is shutting down
+    Disconnect,
+    /// Message asking an archival peer for the final blocks of the slots from `start` (included) to `end` (excluded)
+    AskForBlockRange {
+        /// first slot of the range
+        start: Slot,
+        /// end of the range, excluded
+        end: Slot,
+    },
+    /// Final blocks with their operations, by ascending slot
+    BlockRange(Vec<ArchivedBlock>),
+    /// Message asking an archival peer for the headers of the final blocks of the slots from `start` (included) to `end` (excluded)
+    AskForHeaderRange {
+        /// first slot of the range
+        start: Slot,
+        /// end of the range, excluded
+        end: Slot,
+    },
+    /// Headers of final blocks, by ascending slot
+    HeaderRange(Vec<WrappedHeader>),
 }
 
 #[derive(IntoPrimitive, Debug, Eq, PartialEq, TryFromPrimitive)]
@@ -88,6 +124,11 @@ pub(crate) enum MessageTypeId {
     AskForOperations,
     OperationsAnnouncement,
     ReplyForBlocks,
+    Disconnect,
+    AskForBlockRange,
+    BlockRange,
+    AskForHeaderRange,
+    HeaderRange,
 }
 
 #[derive(IntoPrimitive, Debug, Eq, PartialEq, TryFromPrimitive)]
@@ -107,7 +148,10 @@ pub struct MessageSerializer {
     operation_prefix_ids_serializer: OperationPrefixIdsSerializer,
     operations_ids_serializer: OperationIdsSerializer,
     operations_serializer: OperationsSerializer,
-    ip_addr_serializer: IpAddrSerializer,
+    advertised_peer_serializer: AdvertisedPeerSerializer,
+    time_serializer: MassaTimeSerializer,
+    slot_serializer: SlotSerializer,
+    archived_block_serializer: ArchivedBlockSerializer,
 }
 
 impl MessageSerializer {
@@ -120,7 +164,10 @@ impl MessageSerializer {
             operation_prefix_ids_serializer: OperationPrefixIdsSerializer::new(),
             operations_ids_serializer: OperationIdsSerializer::new(),
             operations_serializer: OperationsSerializer::new(),
-            ip_addr_serializer: IpAddrSerializer::new(),
+            advertised_peer_serializer: AdvertisedPeerSerializer::new(),
+            time_serializer: MassaTimeSerializer::new(),
+            slot_serializer: SlotSerializer::new(),
+            archived_block_serializer: ArchivedBlockSerializer::new(),
         }
     }
 }
@@ -140,17 +187,25 @@ impl Serializer<Message> for MessageSerializer {
                 public_key,
                 random_bytes,
                 version,
+                network_id,
+                observer,
             } => {
                 self.u32_serializer
                     .serialize(&(MessageTypeId::HandshakeInitiation as u32), buffer)?;
                 buffer.extend(public_key.to_bytes());
                 buffer.extend(random_bytes);
                 self.version_serializer.serialize(version, buffer)?;
+                buffer.extend(network_id.to_bytes());
+                buffer.push(u8::from(*observer));
             }
-            Message::HandshakeReply { signature } => {
+            Message::HandshakeReply {
+                signature,
+                timestamp,
+            } => {
                 self.u32_serializer
                     .serialize(&(MessageTypeId::HandshakeReply as u32), buffer)?;
                 buffer.extend(signature.to_bytes());
+                self.time_serializer.serialize(timestamp, buffer)?;
             }
             Message::BlockHeader(header) => {
                 self.u32_serializer
@@ -212,7 +267,7 @@ impl Serializer<Message> for MessageSerializer {
                 self.u32_serializer
                     .serialize(&(peers.len() as u32), buffer)?;
                 for peer in peers {
-                    self.ip_addr_serializer.serialize(peer, buffer)?;
+                    self.advertised_peer_serializer.serialize(peer, buffer)?;
                 }
             }
             Message::OperationsAnnouncement(operation_prefix_ids) => {
@@ -241,6 +296,40 @@ impl Serializer<Message> for MessageSerializer {
                     self.wrapped_serializer.serialize(endorsement, buffer)?;
                 }
             }
+            Message::Disconnect => {
+                self.u32_serializer
+                    .serialize(&(MessageTypeId::Disconnect as u32), buffer)?;
+            }
+            Message::AskForBlockRange { start, end } => {
+                self.u32_serializer
+                    .serialize(&(MessageTypeId::AskForBlockRange as u32), buffer)?;
+                self.slot_serializer.serialize(start, buffer)?;
+                self.slot_serializer.serialize(end, buffer)?;
+            }
+            Message::BlockRange(blocks) => {
+                self.u32_serializer
+                    .serialize(&(MessageTypeId::BlockRange as u32), buffer)?;
+                self.u32_serializer
+                    .serialize(&(blocks.len() as u32), buffer)?;
+                for block in blocks {
+                    self.archived_block_serializer.serialize(block, buffer)?;
+                }
+            }
+            Message::AskForHeaderRange { start, end } => {
+                self.u32_serializer
+                    .serialize(&(MessageTypeId::AskForHeaderRange as u32), buffer)?;
+                self.slot_serializer.serialize(start, buffer)?;
+                self.slot_serializer.serialize(end, buffer)?;
+            }
+            Message::HeaderRange(headers) => {
+                self.u32_serializer
+                    .serialize(&(MessageTypeId::HeaderRange as u32), buffer)?;
+                self.u32_serializer
+                    .serialize(&(headers.len() as u32), buffer)?;
+                for header in headers {
+                    self.wrapped_serializer.serialize(header, buffer)?;
+                }
+            }
         }
         Ok(())
     }
@@ -261,7 +350,11 @@ pub struct MessageDeserializer {
     endorsement_deserializer: WrappedDeserializer<Endorsement, EndorsementDeserializer>,
     operation_prefix_ids_deserializer: OperationPrefixIdsDeserializer,
     infos_deserializer: OperationIdsDeserializer,
-    ip_addr_deserializer: IpAddrDeserializer,
+    advertised_peer_deserializer: AdvertisedPeerDeserializer,
+    time_deserializer: MassaTimeDeserializer,
+    slot_deserializer: SlotDeserializer,
+    block_range_length_deserializer: U32VarIntDeserializer,
+    archived_block_deserializer: ArchivedBlockDeserializer,
 }
 
 impl MessageDeserializer {
@@ -272,6 +365,7 @@ impl MessageDeserializer {
         endorsement_count: u32,
         max_advertise_length: u32,
         max_ask_block: u32,
+        max_block_range_length: u32,
         max_operations_per_block: u32,
         max_operations_per_message: u32,
         max_endorsements_per_message: u32,
@@ -321,7 +415,30 @@ impl MessageDeserializer {
                 max_operations_per_message,
             ),
             infos_deserializer: OperationIdsDeserializer::new(max_operations_per_block),
-            ip_addr_deserializer: IpAddrDeserializer::new(),
+            advertised_peer_deserializer: AdvertisedPeerDeserializer::new(),
+            time_deserializer: MassaTimeDeserializer::new((
+                Included(MassaTime::from_millis(0)),
+                Included(MassaTime::from_millis(u64::MAX)),
+            )),
+            slot_deserializer: SlotDeserializer::new(
+                (Included(0), Included(u64::MAX)),
+                (Included(0), Excluded(thread_count)),
+            ),
+            block_range_length_deserializer: U32VarIntDeserializer::new(
+                Included(0),
+                Included(max_block_range_length),
+            ),
+            archived_block_deserializer: ArchivedBlockDeserializer::new(
+                thread_count,
+                endorsement_count,
+                max_operations_per_block,
+                max_datastore_value_length,
+                max_function_name_length,
+                max_parameters_size,
+                max_op_datastore_entry_count,
+                max_op_datastore_key_length,
+                max_op_datastore_value_length,
+            ),
         }
     }
 }
@@ -353,24 +470,44 @@ impl Deserializer<Message> for MessageDeserializer {
                         context("Failed version deserialization", |input| {
                             self.version_deserializer.deserialize(input)
                         }),
+                        context("Failed network_id deserialization", |input| {
+                            self.hash_deserializer.deserialize(input)
+                        }),
+                        context(
+                            "Failed observer deserialization",
+                            alt((value(true, tag(&[1u8])), value(false, tag(&[0u8])))),
+                        ),
                     ))
-                    .map(|(public_key, random_bytes, version)| {
-                        // Unwrap safety: we checked above that we took enough bytes
-                        Message::HandshakeInitiation {
-                            public_key,
-                            random_bytes: array_from_slice(random_bytes).unwrap(),
-                            version,
-                        }
-                    }),
+                    .map(
+                        |(public_key, random_bytes, version, network_id, observer)| {
+                            // Unwrap safety: we checked above that we took enough bytes
+                            Message::HandshakeInitiation {
+                                public_key,
+                                random_bytes: array_from_slice(random_bytes).unwrap(),
+                                version,
+                                network_id,
+                                observer,
+                            }
+                        },
+                    ),
                 )
                 .parse(input),
-                MessageTypeId::HandshakeReply => {
-                    context("Failed HandshakeReply deserialization", |input| {
-                        self.signature_deserializer.deserialize(input)
-                    })
-                    .map(|signature| Message::HandshakeReply { signature })
-                    .parse(input)
-                }
+                MessageTypeId::HandshakeReply => context(
+                    "Failed HandshakeReply deserialization",
+                    tuple((
+                        context("Failed signature deserialization", |input| {
+                            self.signature_deserializer.deserialize(input)
+                        }),
+                        context("Failed timestamp deserialization", |input| {
+                            self.time_deserializer.deserialize(input)
+                        }),
+                    )),
+                )
+                .map(|(signature, timestamp)| Message::HandshakeReply {
+                    signature,
+                    timestamp,
+                })
+                .parse(input),
                 MessageTypeId::BlockHeader => {
                     context("Failed BlockHeader deserialization", |input| {
                         self.block_header_deserializer.deserialize(input)
@@ -487,7 +624,7 @@ impl Deserializer<Message> for MessageDeserializer {
                             self.peer_list_length_deserializer.deserialize(input)
                         }),
                         context("Failed peer deserialization", |input| {
-                            self.ip_addr_deserializer.deserialize(input)
+                            self.advertised_peer_deserializer.deserialize(input)
                         }),
                     ),
                 )
@@ -527,6 +664,59 @@ impl Deserializer<Message> for MessageDeserializer {
                 )
                 .map(Message::Endorsements)
                 .parse(input),
+                MessageTypeId::Disconnect => Ok((input, Message::Disconnect)),
+                MessageTypeId::AskForBlockRange => context(
+                    "Failed AskForBlockRange deserialization",
+                    tuple((
+                        context("Failed start deserialization", |input| {
+                            self.slot_deserializer.deserialize(input)
+                        }),
+                        context("Failed end deserialization", |input| {
+                            self.slot_deserializer.deserialize(input)
+                        }),
+                    )),
+                )
+                .map(|(start, end)| Message::AskForBlockRange { start, end })
+                .parse(input),
+                MessageTypeId::BlockRange => context(
+                    "Failed BlockRange deserialization",
+                    length_count(
+                        context("Failed length deserialization", |input| {
+                            self.block_range_length_deserializer.deserialize(input)
+                        }),
+                        context("Failed block deserialization", |input| {
+                            self.archived_block_deserializer.deserialize(input)
+                        }),
+                    ),
+                )
+                .map(Message::BlockRange)
+                .parse(input),
+                MessageTypeId::AskForHeaderRange => context(
+                    "Failed AskForHeaderRange deserialization",
+                    tuple((
+                        context("Failed start deserialization", |input| {
+                            self.slot_deserializer.deserialize(input)
+                        }),
+                        context("Failed end deserialization", |input| {
+                            self.slot_deserializer.deserialize(input)
+                        }),
+                    )),
+                )
+                .map(|(start, end)| Message::AskForHeaderRange { start, end })
+                .parse(input),
+                MessageTypeId::HeaderRange => context(
+                    "Failed HeaderRange deserialization",
+                    length_count(
+                        context("Failed length deserialization", |input| {
+                            self.block_range_length_deserializer.deserialize(input)
+                        }),
+                        context("Failed header deserialization", |input| {
+                            self.block_header_deserializer.deserialize(input)
+                        }),
+                    ),
+                )
+                .map(Message::HeaderRange)
+                .parse(input),
             }
         })
         .parse(buffer)
@@ -536,13 +726,15 @@ impl Deserializer<Message> for MessageDeserializer {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use massa_models::block::BlockHeaderSerializer;
     use massa_models::config::{
         ENDORSEMENT_COUNT, MAX_ADVERTISE_LENGTH, MAX_ASK_BLOCKS_PER_MESSAGE,
-        MAX_DATASTORE_VALUE_LENGTH, MAX_ENDORSEMENTS_PER_MESSAGE, MAX_FUNCTION_NAME_LENGTH,
-        MAX_OPERATIONS_PER_BLOCK, MAX_OPERATIONS_PER_MESSAGE, MAX_OPERATION_DATASTORE_ENTRY_COUNT,
-        MAX_OPERATION_DATASTORE_KEY_LENGTH, MAX_OPERATION_DATASTORE_VALUE_LENGTH,
-        MAX_PARAMETERS_SIZE, THREAD_COUNT,
+        MAX_BLOCK_RANGE_LENGTH, MAX_DATASTORE_VALUE_LENGTH, MAX_ENDORSEMENTS_PER_MESSAGE,
+        MAX_FUNCTION_NAME_LENGTH, MAX_OPERATIONS_PER_BLOCK, MAX_OPERATIONS_PER_MESSAGE,
+        MAX_OPERATION_DATASTORE_ENTRY_COUNT, MAX_OPERATION_DATASTORE_KEY_LENGTH,
+        MAX_OPERATION_DATASTORE_VALUE_LENGTH, MAX_PARAMETERS_SIZE, THREAD_COUNT,
     };
+    use massa_models::wrapped::WrappedContent;
     use massa_serialization::DeserializeError;
     use massa_signature::KeyPair;
     use rand::{prelude::StdRng, RngCore, SeedableRng};
@@ -558,6 +750,7 @@ mod tests {
             ENDORSEMENT_COUNT,
             MAX_ADVERTISE_LENGTH,
             MAX_ASK_BLOCKS_PER_MESSAGE,
+            MAX_BLOCK_RANGE_LENGTH,
             MAX_OPERATIONS_PER_BLOCK,
             MAX_OPERATIONS_PER_MESSAGE,
             MAX_ENDORSEMENTS_PER_MESSAGE,
@@ -575,6 +768,8 @@ mod tests {
             public_key: keypair.get_public_key(),
             random_bytes,
             version: Version::from_str("TEST.1.10").unwrap(),
+            network_id: Hash::compute_from(b"network"),
+            observer: true,
         };
         let mut ser = Vec::new();
         message_serializer.serialize(&msg, &mut ser).unwrap();
@@ -587,16 +782,84 @@ mod tests {
                     public_key: pk1,
                     random_bytes: rb1,
                     version: v1,
+                    network_id: n1,
+                    observer: o1,
                 },
                 Message::HandshakeInitiation {
                     public_key,
                     random_bytes,
                     version,
+                    network_id,
+                    observer,
                 },
             ) => {
                 assert_eq!(pk1, public_key);
                 assert_eq!(rb1, random_bytes);
                 assert_eq!(v1, version);
+                assert_eq!(n1, network_id);
+                assert_eq!(o1, observer);
+            }
+            _ => panic!("unexpected message"),
+        }
+
+        let mut ser = Vec::new();
+        message_serializer
+            .serialize(&Message::Disconnect, &mut ser)
+            .unwrap();
+        let (rest, deser) = message_deserializer
+            .deserialize::<DeserializeError>(&ser)
+            .unwrap();
+        assert!(rest.is_empty());
+        assert!(matches!(deser, Message::Disconnect));
+
+        let mut ser = Vec::new();
+        message_serializer
+            .serialize(
+                &Message::AskForBlockRange {
+                    start: Slot::new(3, 1),
+                    end: Slot::new(10, 0),
+                },
+                &mut ser,
+            )
+            .unwrap();
+        let (rest, deser) = message_deserializer
+            .deserialize::<DeserializeError>(&ser)
+            .unwrap();
+        assert!(rest.is_empty());
+        match deser {
+            Message::AskForBlockRange { start, end } => {
+                assert_eq!(start, Slot::new(3, 1));
+                assert_eq!(end, Slot::new(10, 0));
+            }
+            _ => panic!("unexpected message"),
+        }
+
+        let header = BlockHeader::new_wrapped(
+            BlockHeader {
+                slot: Slot::new(3, 1),
+                parents: (0..THREAD_COUNT)
+                    .map(|thread| BlockId(Hash::compute_from(&[thread])))
+                    .collect(),
+                operation_merkle_root: Hash::compute_from(&Vec::new()),
+                endorsements: Vec::new(),
+                state_commitment: None,
+            },
+            BlockHeaderSerializer::new(),
+            &keypair,
+        )
+        .unwrap();
+        let mut ser = Vec::new();
+        message_serializer
+            .serialize(&Message::HeaderRange(vec![header.clone()]), &mut ser)
+            .unwrap();
+        let (rest, deser) = message_deserializer
+            .deserialize::<DeserializeError>(&ser)
+            .unwrap();
+        assert!(rest.is_empty());
+        match deser {
+            Message::HeaderRange(headers) => {
+                assert_eq!(headers.len(), 1);
+                assert_eq!(headers[0].id, header.id);
             }
             _ => panic!("unexpected message"),
         }
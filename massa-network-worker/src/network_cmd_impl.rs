@@ -10,8 +10,8 @@
 //! async fn manage_network_command(&mut self, cmd: NetworkCommand) -> Result<(), NetworkError> {
 //!     use crate::network_cmd_impl::*;
 //!     match cmd {
-//!         NetworkCommand::NodeBanByIps(ips) => on_node_ban_by_ips_cmd(self, ips).await?,
-//!         NetworkCommand::NodeBanByIds(ids) => on_node_ban_by_ids_cmd(self, ids).await?,
+//!         NetworkCommand::NodeBanByIps(ips, reason) => on_node_ban_by_ips_cmd(self, ips, reason).await?,
+//!         NetworkCommand::NodeBanByIds(ids, reason) => on_node_ban_by_ids_cmd(self, ids, reason).await?,
 //!         NetworkCommand::SendBlockHeader { node, header } => on_send_block_header_cmd(self, node, header).await?,
 //!         NetworkCommand::AskForBlocks { list } => on_ask_for_block_cmd(self, list).await,
 //!         NetworkCommand::SendBlock { node, block } => on_send_block_cmd(self, node, block).await?,
@@ -24,16 +24,20 @@ use futures::{stream::FuturesUnordered, StreamExt};
 use massa_hash::Hash;
 use massa_logging::massa_trace;
 use massa_models::{
-    block::{BlockId, WrappedHeader},
+    api::PeerConnectionEvent,
+    ban_list::{BanList, IpSubnet},
+    block::{ArchivedBlock, BlockId, WrappedHeader},
     composite::PubkeySig,
     endorsement::WrappedEndorsement,
+    network_faults::{MessageFaults, NetworkFaults},
     node::NodeId,
     operation::{OperationPrefixIds, WrappedOperation},
+    slot::Slot,
     stats::NetworkStats,
 };
 use massa_network_exports::{
-    AskForBlocksInfo, BlockInfoReply, BootstrapPeers, ConnectionClosureReason, ConnectionId,
-    NetworkError, NodeCommand, Peer, Peers,
+    AskForBlocksInfo, BanReason, BlockInfoReply, BootstrapPeers, ConnectionClosureReason,
+    ConnectionId, NetworkError, NetworkReloadableConfig, NodeCommand, Peer, PeerFeedback, Peers,
 };
 use std::{
     collections::{HashMap, HashSet},
@@ -43,12 +47,12 @@ use tokio::sync::oneshot;
 use tracing::warn;
 
 /// Remove the `ids` from the `worker`
-/// - clean `worker.running_handshakes`
+/// - set the connections `Closing` in `worker.connections`
 /// - send `NodeCommand::Close` to the active nodes
 async fn ban_connection_ids(worker: &mut NetworkWorker, ids: HashSet<ConnectionId>) {
     for ban_conn_id in ids.iter() {
-        // remove the connectionId entry in running_handshakes
-        worker.running_handshakes.remove(ban_conn_id);
+        // running handshakes see it when they end
+        worker.connections.start_closing(ban_conn_id);
     }
     for (conn_id, node_command_tx) in worker.active_nodes.values() {
         if ids.contains(conn_id) {
@@ -66,17 +70,21 @@ async fn ban_connection_ids(worker: &mut NetworkWorker, ids: HashSet<ConnectionI
     }
 }
 
-/// Ban the connections corresponding to `ips` from the `worker`
+/// Ban the connections corresponding to `ips` from the `worker`, for `reason`
 /// See also `ban_connection_ids`
-async fn node_ban_by_ips(worker: &mut NetworkWorker, ips: Vec<IpAddr>) -> Result<(), NetworkError> {
+async fn node_ban_by_ips(
+    worker: &mut NetworkWorker,
+    ips: Vec<IpAddr>,
+    reason: BanReason,
+) -> Result<(), NetworkError> {
     for ip in ips.iter() {
-        worker.peer_info_db.peer_banned(ip)?;
+        worker.peer_info_db.peer_banned(ip, reason)?;
     }
     let connexion_ids = worker
-        .active_connections
+        .connections
         .iter()
-        .filter_map(|(conn_id, (ip, _))| {
-            if ips.contains(ip) {
+        .filter_map(|(conn_id, connection)| {
+            if ips.contains(&connection.ip) {
                 Some(conn_id)
             } else {
                 None
@@ -88,13 +96,19 @@ async fn node_ban_by_ips(worker: &mut NetworkWorker, ips: Vec<IpAddr>) -> Result
     Ok(())
 }
 
-/// Ban the connections corresponding to node `ids` from the `worker`
+/// Ban the node `ids`, and the connections corresponding to them from the `worker`, for `reason`.
+/// The banned nodes are refused at the end of their handshake, even under another ip.
 /// See also `ban_connection_ids`
-async fn node_ban_by_ids(worker: &mut NetworkWorker, ids: Vec<NodeId>) -> Result<(), NetworkError> {
+async fn node_ban_by_ids(
+    worker: &mut NetworkWorker,
+    ids: Vec<NodeId>,
+    reason: BanReason,
+) -> Result<(), NetworkError> {
+    worker.peer_info_db.ban_node_ids(&ids, reason)?;
     // get all connection IDs to ban
     let connection_ids_to_ban = ids
         .iter()
-        .map(|id| get_connection_ids(worker, id))
+        .map(|id| get_connection_ids(worker, id, reason))
         .filter(|res| res.is_ok())
         .flat_map(|res| res.unwrap())
         .collect::<HashSet<_>>();
@@ -113,12 +127,28 @@ async fn get_peers(worker: &mut NetworkWorker, response_tx: oneshot::Sender<Peer
             (
                 *peer_ip_addr,
                 Peer {
-                    peer_info: *peer,
+                    peer_info: peer.clone(),
+                    traffic: worker
+                        .active_nodes
+                        .iter()
+                        .filter(|(_, (conn_id, _))| {
+                            worker
+                                .connections
+                                .get(conn_id)
+                                .map_or(false, |connection| connection.ip == peer.ip)
+                        })
+                        .filter_map(|(node_id, _)| {
+                            worker
+                                .traffic
+                                .get(node_id)
+                                .map(|traffic| (*node_id, traffic.get()))
+                        })
+                        .collect(),
                     active_nodes: worker
-                        .active_connections
+                        .connections
                         .iter()
-                        .filter(|(_, (ip_addr, _))| &peer.ip == ip_addr)
-                        .filter_map(|(out_conn_id, (_, out_going))| {
+                        .filter(|(_, connection)| peer.ip == connection.ip)
+                        .filter_map(|(out_conn_id, connection)| {
                             worker
                                 .active_nodes
                                 .iter()
@@ -130,7 +160,7 @@ async fn get_peers(worker: &mut NetworkWorker, response_tx: oneshot::Sender<Peer
                                     }
                                 })
                                 .next()
-                                .map(|node_id| (*node_id, *out_going))
+                                .map(|node_id| (*node_id, connection.is_outgoing))
                         })
                         .collect(),
                 },
@@ -138,7 +168,7 @@ async fn get_peers(worker: &mut NetworkWorker, response_tx: oneshot::Sender<Peer
         })
         .collect();
 
-    // HashMap<NodeId, (ConnectionId, mpsc::Sender<NodeCommand>)
+    // HashMap<NodeId, (ConnectionId, BackpressureSender<NodeCommand>)
     if response_tx
         .send(Peers {
             peers,
@@ -153,23 +183,47 @@ async fn get_peers(worker: &mut NetworkWorker, response_tx: oneshot::Sender<Peer
 pub async fn on_node_ban_by_ips_cmd(
     worker: &mut NetworkWorker,
     ips: Vec<IpAddr>,
+    reason: BanReason,
 ) -> Result<(), NetworkError> {
     massa_trace!(
         "network_worker.manage_network_command receive NetworkCommand::NodeBanByIps",
-        { "ips": ips }
+        { "ips": ips, "reason": reason.to_string() }
     );
-    node_ban_by_ips(worker, ips).await
+    node_ban_by_ips(worker, ips, reason).await
 }
 
 pub async fn on_node_ban_by_ids_cmd(
     worker: &mut NetworkWorker,
     ids: Vec<NodeId>,
+    reason: BanReason,
 ) -> Result<(), NetworkError> {
     massa_trace!(
         "network_worker.manage_network_command receive NetworkCommand::NodeBanByIds",
-        { "ids": ids }
+        { "ids": ids, "reason": reason.to_string() }
+    );
+    node_ban_by_ids(worker, ids, reason).await
+}
+
+/// Apply the feedback on each node to the peers at its ips:
+/// the ip of its active connection, or the ips of the known peers that proved it in their last handshake.
+pub fn on_peer_feedback_cmd(worker: &mut NetworkWorker, feedback: Vec<(NodeId, PeerFeedback)>) {
+    massa_trace!(
+        "network_worker.manage_network_command receive NetworkCommand::PeerFeedback",
+        { "count": feedback.len() }
     );
-    node_ban_by_ids(worker, ids).await
+    for (node_id, node_feedback) in feedback {
+        let ips = match worker
+            .active_nodes
+            .get(&node_id)
+            .and_then(|(conn_id, _)| worker.connections.get(conn_id))
+        {
+            Some(connection) => vec![connection.ip],
+            None => worker.peer_info_db.get_node_ips(&node_id),
+        };
+        for ip in ips {
+            worker.peer_info_db.peer_feedback(&ip, node_feedback);
+        }
+    }
 }
 
 pub async fn on_send_block_header_cmd(
@@ -245,7 +299,7 @@ pub async fn on_get_bootstrap_peers_cmd(
         "network_worker.manage_network_command receive NetworkCommand::GetBootstrapPeers",
         {}
     );
-    let peer_list = worker.peer_info_db.get_advertisable_peer_ips();
+    let peer_list = worker.peer_info_db.get_advertisable_peers();
     if response_tx.send(BootstrapPeers(peer_list)).is_err() {
         warn!("network: could not send GetBootstrapPeers response upstream");
     }
@@ -296,9 +350,14 @@ pub async fn on_node_unban_by_ids_cmd(
     worker: &mut NetworkWorker,
     ids: Vec<NodeId>,
 ) -> Result<(), NetworkError> {
+    worker.peer_info_db.unban_node_ids(&ids)?;
     let ips_to_unban = ids
         .iter()
-        .flat_map(|id| get_ip(worker, id))
+        .flat_map(|id| {
+            let mut ips = worker.peer_info_db.get_node_ips(id);
+            ips.extend(get_ip(worker, id));
+            ips
+        })
         .collect::<Vec<_>>();
     worker.peer_info_db.unban(ips_to_unban)
 }
@@ -310,6 +369,129 @@ pub async fn on_node_unban_by_ips_cmd(
     worker.peer_info_db.unban(ips)
 }
 
+pub async fn on_node_unban_by_subnets_cmd(
+    worker: &mut NetworkWorker,
+    subnets: Vec<IpSubnet>,
+) -> Result<(), NetworkError> {
+    worker.peer_info_db.unban_subnets(subnets)
+}
+
+pub fn on_get_ban_list_cmd(worker: &mut NetworkWorker, response_tx: oneshot::Sender<BanList>) {
+    if response_tx
+        .send(worker.peer_info_db.get_ban_list())
+        .is_err()
+    {
+        warn!("network: could not send GetBanList response upstream");
+    }
+}
+
+/// Merge the imported bans into the peer database and close the connections of the ips they ban
+pub async fn on_import_ban_list_cmd(
+    worker: &mut NetworkWorker,
+    ban_list: BanList,
+) -> Result<(), NetworkError> {
+    massa_trace!(
+        "network_worker.manage_network_command receive NetworkCommand::ImportBanList",
+        { "ban_count": ban_list.bans.len() }
+    );
+    worker.peer_info_db.import_ban_list(ban_list)?;
+    let connection_ids = worker
+        .connections
+        .iter()
+        .filter(|(_, connection)| worker.peer_info_db.is_banned(&connection.ip))
+        .map(|(conn_id, _)| *conn_id)
+        .collect::<HashSet<_>>();
+    ban_connection_ids(worker, connection_ids).await;
+    Ok(())
+}
+
+pub fn on_get_peer_history_cmd(
+    worker: &mut NetworkWorker,
+    ip: IpAddr,
+    response_tx: oneshot::Sender<Vec<PeerConnectionEvent>>,
+) {
+    if response_tx
+        .send(worker.peer_info_db.get_peer_history(&ip))
+        .is_err()
+    {
+        warn!("network: could not send GetPeerHistory response upstream");
+    }
+}
+
+pub fn on_flush_peers_cmd(
+    worker: &mut NetworkWorker,
+    response_tx: oneshot::Sender<Result<(), NetworkError>>,
+) {
+    massa_trace!(
+        "network_worker.manage_network_command receive NetworkCommand::FlushPeers",
+        {}
+    );
+    // the saver answers once the file is written, without blocking the worker meanwhile
+    let (saver_response_tx, saver_response_rx) = oneshot::channel();
+    if let Err(err) = worker.peer_info_db.flush(saver_response_tx) {
+        if response_tx.send(Err(err)).is_err() {
+            warn!("network: could not send FlushPeers response upstream");
+        }
+        return;
+    }
+    tokio::spawn(async move {
+        let res = saver_response_rx.await.unwrap_or_else(|_| {
+            Err(NetworkError::ChannelError(
+                "peers saver stopped before dumping".into(),
+            ))
+        });
+        if response_tx.send(res).is_err() {
+            warn!("network: could not send FlushPeers response upstream");
+        }
+    });
+}
+
+pub fn on_set_faults_cmd(
+    worker: &mut NetworkWorker,
+    node_id: Option<NodeId>,
+    faults: Option<MessageFaults>,
+    response_tx: oneshot::Sender<Result<NetworkFaults, NetworkError>>,
+) {
+    massa_trace!(
+        "network_worker.manage_network_command receive NetworkCommand::SetFaults",
+        { "node_id": node_id }
+    );
+    // the node workers read the faults before sending each batch of messages
+    let res = worker.faults.set(node_id, faults);
+    if let Ok(network_faults) = &res {
+        warn!(
+            "network: injecting faults in the sent messages: {:?}",
+            network_faults
+        );
+    }
+    if response_tx.send(res).is_err() {
+        warn!("network: could not send SetFaults response upstream");
+    }
+}
+
+pub async fn on_reload_peers_cmd(
+    worker: &mut NetworkWorker,
+    response_tx: oneshot::Sender<Result<(), NetworkError>>,
+) {
+    massa_trace!(
+        "network_worker.manage_network_command receive NetworkCommand::ReloadPeers",
+        {}
+    );
+    let res = worker.peer_info_db.reload_peers_file().await;
+    if res.is_ok() {
+        let connection_ids = worker
+            .connections
+            .iter()
+            .filter(|(_, connection)| worker.peer_info_db.is_banned(&connection.ip))
+            .map(|(conn_id, _)| *conn_id)
+            .collect::<HashSet<_>>();
+        ban_connection_ids(worker, connection_ids).await;
+    }
+    if response_tx.send(res).is_err() {
+        warn!("network: could not send ReloadPeers response upstream");
+    }
+}
+
 pub async fn on_whitelist_cmd(
     worker: &mut NetworkWorker,
     ips: Vec<IpAddr>,
@@ -324,6 +506,10 @@ pub async fn on_remove_from_whitelist_cmd(
     worker.peer_info_db.remove_from_whitelist(ips).await
 }
 
+pub fn on_update_config_cmd(worker: &mut NetworkWorker, config: NetworkReloadableConfig) {
+    worker.peer_info_db.update_config(config);
+}
+
 pub async fn on_get_stats_cmd(
     worker: &mut NetworkWorker,
     response_tx: oneshot::Sender<NetworkStats>,
@@ -339,6 +525,7 @@ pub async fn on_get_stats_cmd(
             .filter(|(_, p)| p.banned)
             .fold(0, |acc, _| acc + 1),
         active_node_count: worker.active_nodes.len() as u64,
+        median_clock_offset: worker.median_clock_offset(),
     };
     if response_tx.send(res).is_err() {
         warn!("network: could not send NodeSignMessage response upstream");
@@ -423,16 +610,103 @@ pub async fn on_ask_for_operations_cmd(
         .await;
 }
 
+/// Network worker received the command `NetworkCommand::AskForBlockRange` from the controller,
+/// forward it to the `NodeWorker` of the archival node
+pub async fn on_ask_for_block_range_cmd(
+    worker: &mut NetworkWorker,
+    to_node: NodeId,
+    start: Slot,
+    end: Slot,
+) {
+    massa_trace!(
+        "network_worker.manage_network_command receive NetworkCommand::AskForBlockRange",
+        { "node": to_node, "start": start, "end": end }
+    );
+    worker
+        .event
+        .forward(
+            to_node,
+            worker.active_nodes.get(&to_node),
+            NodeCommand::AskForBlockRange { start, end },
+        )
+        .await;
+}
+
+/// Network worker received the command `NetworkCommand::SendBlockRange` from the controller,
+/// forward the blocks to the `NodeWorker` of the node that asked for them
+pub async fn on_send_block_range_cmd(
+    worker: &mut NetworkWorker,
+    to_node: NodeId,
+    blocks: Vec<ArchivedBlock>,
+) {
+    massa_trace!(
+        "network_worker.manage_network_command receive NetworkCommand::SendBlockRange",
+        { "node": to_node, "block_count": blocks.len() }
+    );
+    worker
+        .event
+        .forward(
+            to_node,
+            worker.active_nodes.get(&to_node),
+            NodeCommand::SendBlockRange(blocks),
+        )
+        .await;
+}
+
+/// Network worker received the command `NetworkCommand::AskForHeaderRange` from the controller,
+/// forward it to the `NodeWorker` of the archival node
+pub async fn on_ask_for_header_range_cmd(
+    worker: &mut NetworkWorker,
+    to_node: NodeId,
+    start: Slot,
+    end: Slot,
+) {
+    massa_trace!(
+        "network_worker.manage_network_command receive NetworkCommand::AskForHeaderRange",
+        { "node": to_node, "start": start, "end": end }
+    );
+    worker
+        .event
+        .forward(
+            to_node,
+            worker.active_nodes.get(&to_node),
+            NodeCommand::AskForHeaderRange { start, end },
+        )
+        .await;
+}
+
+/// Network worker received the command `NetworkCommand::SendHeaderRange` from the controller,
+/// forward the headers to the `NodeWorker` of the node that asked for them
+pub async fn on_send_header_range_cmd(
+    worker: &mut NetworkWorker,
+    to_node: NodeId,
+    headers: Vec<WrappedHeader>,
+) {
+    massa_trace!(
+        "network_worker.manage_network_command receive NetworkCommand::SendHeaderRange",
+        { "node": to_node, "header_count": headers.len() }
+    );
+    worker
+        .event
+        .forward(
+            to_node,
+            worker.active_nodes.get(&to_node),
+            NodeCommand::SendHeaderRange(headers),
+        )
+        .await;
+}
+
 fn get_connection_ids(
     worker: &mut NetworkWorker,
     node: &NodeId,
+    reason: BanReason,
 ) -> Result<HashSet<ConnectionId>, NetworkError> {
     let mut ids: HashSet<ConnectionId> = HashSet::new();
     if let Some((orig_conn_id, _)) = worker.active_nodes.get(node) {
-        if let Some((orig_ip, _)) = worker.active_connections.get(orig_conn_id) {
-            worker.peer_info_db.peer_banned(orig_ip)?;
-            for (target_conn_id, (target_ip, _)) in worker.active_connections.iter() {
-                if target_ip == orig_ip {
+        if let Some(orig_ip) = worker.connections.get(orig_conn_id).map(|c| c.ip) {
+            worker.peer_info_db.peer_banned(&orig_ip, reason)?;
+            for (target_conn_id, target) in worker.connections.iter() {
+                if target.ip == orig_ip {
                     ids.insert(*target_conn_id);
                 }
             }
@@ -444,10 +718,10 @@ fn get_connection_ids(
 
 fn get_ip(worker: &mut NetworkWorker, node: &NodeId) -> Option<IpAddr> {
     if let Some((orig_conn_id, _)) = worker.active_nodes.get(node) {
-        if let Some((orig_ip, _)) = worker.active_connections.get(orig_conn_id) {
-            for (_, (target_ip, _)) in worker.active_connections.iter() {
-                if target_ip == orig_ip {
-                    return Some(*target_ip);
+        if let Some(orig) = worker.connections.get(orig_conn_id) {
+            for (_, target) in worker.connections.iter() {
+                if target.ip == orig.ip {
+                    return Some(target.ip);
                 }
             }
         }
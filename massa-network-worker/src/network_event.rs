@@ -1,22 +1,25 @@
 use massa_models::node::NodeId;
-use massa_network_exports::{ConnectionId, NetworkError, NetworkEvent, NodeCommand, NodeEvent};
+use massa_network_exports::{
+    BackpressureSender, ConnectionClosureReason, ConnectionId, NetworkError, NetworkEvent,
+    NodeCommand, NodeEvent,
+};
 use std::time::Duration;
-use tokio::sync::mpsc::{self, error::SendTimeoutError};
+use tokio::sync::mpsc::error::{SendTimeoutError, TrySendError};
 use tracing::debug;
 
 pub struct EventSender {
     /// Sender for network events
-    controller_event_tx: mpsc::Sender<NetworkEvent>,
+    controller_event_tx: BackpressureSender<NetworkEvent>,
     /// Channel for sending node events.
-    node_event_tx: mpsc::Sender<NodeEvent>,
-    /// Max time spend to wait
+    node_event_tx: BackpressureSender<NodeEvent>,
+    /// Max time spent waiting for room to send a consensus-critical event
     max_send_wait: Duration,
 }
 
 impl EventSender {
     pub fn new(
-        controller_event_tx: mpsc::Sender<NetworkEvent>,
-        node_event_tx: mpsc::Sender<NodeEvent>,
+        controller_event_tx: BackpressureSender<NetworkEvent>,
+        node_event_tx: BackpressureSender<NodeEvent>,
         max_send_wait: Duration,
     ) -> Self {
         Self {
@@ -26,6 +29,9 @@ impl EventSender {
         }
     }
 
+    /// Send an event to the protocol worker: gossip never waits,
+    /// consensus-critical events wait for room at most `max_send_wait`.
+    /// The wait is bounded because the protocol worker may itself be waiting for us to take its commands.
     pub async fn send(&self, event: NetworkEvent) -> Result<(), NetworkError> {
         let result = self
             .controller_event_tx
@@ -46,20 +52,35 @@ impl EventSender {
         Err(NetworkError::ChannelError("Failed to send event.".into()))
     }
 
-    /// Forward a message to a node worker. If it fails, notify upstream about connection closure.
+    /// Forward a message to a node worker without waiting. If it fails, notify upstream about connection closure.
+    ///
+    /// When the send queue of the node is full, gossip is dropped. If a consensus-critical message does not fit,
+    /// the node can not keep up: the connection is closed without going through the queued messages.
     pub async fn forward(
         &self,
         node_id: NodeId,
-        node: Option<&(ConnectionId, mpsc::Sender<NodeCommand>)>,
+        node: Option<&(ConnectionId, BackpressureSender<NodeCommand>)>,
         message: NodeCommand,
     ) {
         if let Some((_, node_command_tx)) = node {
-            if node_command_tx.send(message).await.is_err() {
-                debug!(
-                    "{}",
-                    NetworkError::ChannelError("contact with node worker lost while trying to send it a message. Probably a peer disconnect.".into())
-                );
-            };
+            match node_command_tx.try_send(message) {
+                Ok(()) => {}
+                Err(TrySendError::Full(_)) => {
+                    debug!(
+                        "send queue of node_id={} overflowed, closing the connection",
+                        node_id
+                    );
+                    let _ = node_command_tx.clear_and_send(NodeCommand::Close(
+                        ConnectionClosureReason::SendQueueOverflow,
+                    ));
+                }
+                Err(TrySendError::Closed(_)) => {
+                    debug!(
+                        "{}",
+                        NetworkError::ChannelError("contact with node worker lost while trying to send it a message. Probably a peer disconnect.".into())
+                    );
+                }
+            }
         } else {
             // We probably weren't able to send this event previously,
             // retry it now.
@@ -67,7 +88,7 @@ impl EventSender {
         }
     }
 
-    pub fn clone_node_sender(&self) -> mpsc::Sender<NodeEvent> {
+    pub fn clone_node_sender(&self) -> BackpressureSender<NodeEvent> {
         self.node_event_tx.clone()
     }
 
@@ -80,15 +101,15 @@ pub mod event_impl {
     use crate::network_worker::NetworkWorker;
     use massa_logging::massa_trace;
     use massa_models::{
-        block::{BlockId, WrappedHeader},
+        block::{ArchivedBlock, BlockId, WrappedHeader},
         endorsement::WrappedEndorsement,
         node::NodeId,
         operation::{OperationPrefixIds, WrappedOperation},
+        slot::Slot,
         wrapped::Id,
     };
-    use massa_network_exports::{AskForBlocksInfo, BlockInfoReply, NodeCommand};
+    use massa_network_exports::{AdvertisedPeer, AskForBlocksInfo, BlockInfoReply, NodeCommand};
     use massa_network_exports::{NetworkError, NetworkEvent};
-    use std::net::IpAddr;
     use tracing::{debug, info};
     macro_rules! evt_failed {
         ($err: ident) => {
@@ -100,7 +121,7 @@ pub mod event_impl {
     pub fn on_received_peer_list(
         worker: &mut NetworkWorker,
         from: NodeId,
-        list: &[IpAddr],
+        list: &[AdvertisedPeer],
     ) -> Result<(), NetworkError> {
         debug!("node_id={} sent us a peer list ({} ips)", from, list.len());
         massa_trace!("peer_list_received", {
@@ -134,11 +155,13 @@ pub mod event_impl {
             "network_worker.on_node_event receive NetworkEvent::ReceivedBlockHeader",
             {"hash": header.id.get_hash(), "header": header, "node": from}
         );
+        let span = tracing::debug_span!("block", block_id = %header.id, node_id = %from);
         if let Err(err) = worker
             .event
             .send(NetworkEvent::ReceivedBlockHeader {
                 source_node_id: from,
                 header,
+                span,
             })
             .await
         {
@@ -168,7 +191,7 @@ pub mod event_impl {
     ) -> Result<(), NetworkError> {
         debug!("node_id={} asked us for peer list", from);
         massa_trace!("node_asked_peer_list", { "node_id": from });
-        let peer_list = worker.peer_info_db.get_advertisable_peer_ips();
+        let peer_list = worker.peer_info_db.get_advertisable_peers();
         if let Some((_, node_command_tx)) = worker.active_nodes.get(&from) {
             let res = node_command_tx
                 .send(NodeCommand::SendPeerList(peer_list))
@@ -279,4 +302,91 @@ pub mod event_impl {
             evt_failed!(err)
         }
     }
+
+    /// The node worker signal that a node asked for the final blocks of a slot range
+    pub async fn on_received_ask_for_block_range(
+        worker: &mut NetworkWorker,
+        from: NodeId,
+        start: Slot,
+        end: Slot,
+    ) {
+        massa_trace!(
+            "network_worker.on_node_event receive NetworkEvent::AskedForBlockRange",
+            { "node": from, "start": start, "end": end }
+        );
+        if let Err(err) = worker
+            .event
+            .send(NetworkEvent::AskedForBlockRange {
+                node: from,
+                start,
+                end,
+            })
+            .await
+        {
+            evt_failed!(err)
+        }
+    }
+
+    pub async fn on_received_block_range(
+        worker: &mut NetworkWorker,
+        from: NodeId,
+        blocks: Vec<ArchivedBlock>,
+    ) {
+        massa_trace!(
+            "network_worker.on_node_event receive NetworkEvent::ReceivedBlockRange",
+            { "node": from, "block_count": blocks.len() }
+        );
+        if let Err(err) = worker
+            .event
+            .send(NetworkEvent::ReceivedBlockRange { node: from, blocks })
+            .await
+        {
+            evt_failed!(err)
+        }
+    }
+
+    /// The node worker signal that a node asked for the headers of the final blocks of a slot range
+    pub async fn on_received_ask_for_header_range(
+        worker: &mut NetworkWorker,
+        from: NodeId,
+        start: Slot,
+        end: Slot,
+    ) {
+        massa_trace!(
+            "network_worker.on_node_event receive NetworkEvent::AskedForHeaderRange",
+            { "node": from, "start": start, "end": end }
+        );
+        if let Err(err) = worker
+            .event
+            .send(NetworkEvent::AskedForHeaderRange {
+                node: from,
+                start,
+                end,
+            })
+            .await
+        {
+            evt_failed!(err)
+        }
+    }
+
+    pub async fn on_received_header_range(
+        worker: &mut NetworkWorker,
+        from: NodeId,
+        headers: Vec<WrappedHeader>,
+    ) {
+        massa_trace!(
+            "network_worker.on_node_event receive NetworkEvent::ReceivedHeaderRange",
+            { "node": from, "header_count": headers.len() }
+        );
+        if let Err(err) = worker
+            .event
+            .send(NetworkEvent::ReceivedHeaderRange {
+                node: from,
+                headers,
+            })
+            .await
+        {
+            evt_failed!(err)
+        }
+    }
 }
@@ -6,6 +6,8 @@ use super::{
 };
 use crate::{
     binders::{ReadBinder, WriteBinder},
+    connections::{ConnectionState, Connections},
+    fault_injection::FaultInjector,
     handshake_worker::HandshakeWorker,
     messages::{Message, MessageDeserializer},
     network_event::EventSender,
@@ -14,18 +16,24 @@ use futures::{stream::FuturesUnordered, StreamExt};
 use massa_logging::massa_trace;
 use massa_models::{node::NodeId, version::Version};
 use massa_network_exports::{
-    ConnectionClosureReason, ConnectionId, Establisher, HandshakeErrorType, Listener,
+    backpressure_channel, bounded_backpressure_channel, BackpressureReceiver, BackpressureSender,
+    BanReason, ConnectionClosureReason, ConnectionId, Establisher, HandshakeErrorType, Listener,
     NetworkCommand, NetworkConfig, NetworkConnectionErrorType, NetworkError, NetworkEvent,
-    NetworkManagementCommand, NodeCommand, NodeEvent, NodeEventType, ReadHalf, WriteHalf,
+    NetworkManagementCommand, NodeCommand, NodeEvent, NodeEventType, NodeTraffic, ReadHalf,
+    WriteHalf,
 };
 use massa_signature::KeyPair;
 use std::{
-    collections::{hash_map, HashMap, HashSet},
+    collections::{hash_map, HashMap},
     net::{IpAddr, SocketAddr},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
 };
 use tokio::sync::mpsc;
 use tokio::task::JoinHandle;
-use tracing::{debug, trace, warn};
+use tracing::{debug, info, trace, warn};
 
 /// Real job is done by network worker
 pub struct NetworkWorker {
@@ -45,30 +53,52 @@ pub struct NetworkWorker {
     controller_command_rx: mpsc::Receiver<NetworkCommand>,
     /// Receiver for network management commands
     controller_manager_rx: mpsc::Receiver<NetworkManagementCommand>,
-    /// Set of connection id of node with running handshake.
-    pub(crate) running_handshakes: HashSet<ConnectionId>,
     /// Running handshakes futures.
     handshake_futures: FuturesUnordered<JoinHandle<(ConnectionId, HandshakeReturnType)>>,
     /// Running handshakes that send a list of peers.
     handshake_peer_list_futures: FuturesUnordered<JoinHandle<()>>,
     /// Receiving channel for node events.
-    node_event_rx: mpsc::Receiver<NodeEvent>,
+    node_event_rx: BackpressureReceiver<NodeEvent>,
     /// Ids of active nodes mapped to Connection id, node command sender and handle on the associated node worker.
-    pub(crate) active_nodes: HashMap<NodeId, (ConnectionId, mpsc::Sender<NodeCommand>)>,
+    pub(crate) active_nodes: HashMap<NodeId, (ConnectionId, BackpressureSender<NodeCommand>)>,
     /// Node worker handles
     node_worker_handles:
         FuturesUnordered<JoinHandle<(NodeId, Result<ConnectionClosureReason, NetworkError>)>>,
-    /// Map of connection to ip, `is_outgoing`.
-    pub(crate) active_connections: HashMap<ConnectionId, (IpAddr, bool)>,
+    /// Connections, from the outgoing attempts to the closure, keeping `peer_info_db` in sync.
+    pub(crate) connections: Connections,
+    /// Clock offset of the active nodes relative to ours, measured during the handshake, in milliseconds.
+    pub(crate) clock_offsets: HashMap<NodeId, i64>,
+    /// Bytes exchanged with the active nodes.
+    pub(crate) traffic: HashMap<NodeId, TrafficCounters>,
+    /// Whether we already warned that our clock deviates from the network one.
+    clock_offset_warned: bool,
     /// Node version
     version: Version,
     /// Event sender
     pub(crate) event: EventSender,
+    /// Faults injected in the messages sent by the node workers
+    pub(crate) faults: FaultInjector,
+}
+
+/// Counters of the bytes exchanged with a node, updated by the binders of its node worker
+pub(crate) struct TrafficCounters {
+    pub bytes_sent: Arc<AtomicU64>,
+    pub bytes_received: Arc<AtomicU64>,
+}
+
+impl TrafficCounters {
+    /// Read the counters
+    pub fn get(&self) -> NodeTraffic {
+        NodeTraffic {
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+        }
+    }
 }
 
 pub struct NetworkWorkerChannels {
     pub controller_command_rx: mpsc::Receiver<NetworkCommand>,
-    pub controller_event_tx: mpsc::Sender<NetworkEvent>,
+    pub controller_event_tx: BackpressureSender<NetworkEvent>,
     pub controller_manager_rx: mpsc::Receiver<NetworkManagementCommand>,
 }
 
@@ -100,7 +130,7 @@ impl NetworkWorker {
         let self_node_id = NodeId::new(keypair.get_public_key());
 
         let (node_event_tx, node_event_rx) =
-            mpsc::channel::<NodeEvent>(cfg.node_event_channel_size);
+            backpressure_channel::<NodeEvent>("node_events", cfg.node_event_channel_size);
         let max_wait_event = cfg.max_send_wait_network_event.to_duration();
         NetworkWorker {
             cfg,
@@ -112,14 +142,17 @@ impl NetworkWorker {
             controller_command_rx,
             event: EventSender::new(controller_event_tx, node_event_tx, max_wait_event),
             controller_manager_rx,
-            running_handshakes: HashSet::new(),
             handshake_futures: FuturesUnordered::new(),
             handshake_peer_list_futures: FuturesUnordered::new(),
             node_event_rx,
             active_nodes: HashMap::new(),
             node_worker_handles: FuturesUnordered::new(),
-            active_connections: HashMap::new(),
+            connections: Connections::default(),
+            clock_offsets: HashMap::new(),
+            traffic: HashMap::new(),
+            clock_offset_warned: false,
             version,
+            faults: FaultInjector::default(),
         }
     }
 
@@ -127,30 +160,44 @@ impl NetworkWorker {
     /// There is a `tokio::select!` inside the loop
     pub async fn run_loop(mut self) -> Result<(), NetworkError> {
         let mut out_connecting_futures = FuturesUnordered::new();
-        let mut cur_connection_id = ConnectionId::default();
 
-        // wake up the controller at a regular interval to retry connections
-        let mut wakeup_interval = tokio::time::interval(self.cfg.wakeup_interval.to_duration());
+        // wake up the controller at a regular interval to retry connections,
+        // following the clock of the peer database
+        let mut next_wakeup = self
+            .peer_info_db
+            .now()?
+            .saturating_add(self.cfg.wakeup_interval);
         let mut need_connect_retry = true;
+        // false once the node started shutting down
+        let mut listening = true;
 
         loop {
-            if need_connect_retry {
+            if need_connect_retry && listening {
                 // try to connect to candidate IPs
                 let candidate_ips = self.peer_info_db.get_out_connection_candidate_ips()?;
                 for ip in candidate_ips {
                     debug!("starting outgoing connection attempt towards ip={}", ip);
                     massa_trace!("out_connection_attempt_start", { "ip": ip });
-                    self.peer_info_db.new_out_connection_attempt(&ip)?;
+                    let connection_id = self.connections.dial(&mut self.peer_info_db, ip)?;
                     let mut connector = self
                         .establisher
-                        .get_connector(self.cfg.connect_timeout)
+                        .get_connector(self.peer_info_db.get_connect_timeout(&ip))
                         .await?;
-                    let addr = SocketAddr::new(ip, self.cfg.protocol_port);
+                    let addrs = self.peer_info_db.get_dial_addresses(&ip);
                     out_connecting_futures.push(async move {
-                        match connector.connect(addr).await {
-                            Ok((reader, writer)) => (addr.ip(), Ok((reader, writer))),
-                            Err(e) => (addr.ip(), Err(e)),
+                        // try the addresses of the peer by order of preference
+                        let mut res = Err(std::io::Error::new(
+                            std::io::ErrorKind::AddrNotAvailable,
+                            "no address to dial",
+                        ));
+                        for addr in addrs {
+                            res = connector.connect(addr).await;
+                            if res.is_ok() {
+                                break;
+                            }
+                            debug!("could not reach ip={} at {}", ip, addr);
                         }
+                        (connection_id, ip, res)
                     });
                 }
                 need_connect_retry = false;
@@ -173,7 +220,10 @@ impl NetworkWorker {
                 cmd = self.controller_manager_rx.recv() => {
                     match cmd {
                         None => break,
-                        Some(_) => {}
+                        Some(NetworkManagementCommand::StopListening) => {
+                            info!("network stopped accepting new connections");
+                            listening = false;
+                        }
                     }
                 },
 
@@ -190,10 +240,15 @@ impl NetworkWorker {
                 },
 
                 // wake up interval
-                _ = wakeup_interval.tick() => {
-                    self.peer_info_db.update()?; // notify tick to peer db
+                _ = tokio::time::sleep_until(self.peer_info_db.clock.get_wakeup_instant(next_wakeup).into()) => {
+                    // the clock may wake us up before the wakeup time is reached
+                    let now = self.peer_info_db.now()?;
+                    if now >= next_wakeup {
+                        self.peer_info_db.update()?; // notify tick to peer db
 
-                    need_connect_retry = true; // retry out connections
+                        need_connect_retry = true; // retry out connections
+                        next_wakeup = now.saturating_add(self.cfg.wakeup_interval);
+                    }
                 }
 
                 // wait for a handshake future to complete
@@ -232,9 +287,7 @@ impl NetworkWorker {
                     let _ = self
                         .event.send(NetworkEvent::ConnectionClosed(node_id))
                         .await;
-                    if let Some((connection_id, _)) = self
-                        .active_nodes
-                        .remove(&node_id) {
+                    if let Some(connection_id) = self.remove_active_node(&node_id) {
                         massa_trace!("protocol channel closed", {"node_id": node_id});
                         self.connection_closed(connection_id, reason).await?;
                     }
@@ -243,21 +296,18 @@ impl NetworkWorker {
                 },
 
                 // out-connector event
-                Some((ip_addr, res)) = out_connecting_futures.next() => {
+                Some((connection_id, ip_addr, res)) = out_connecting_futures.next() => {
                     need_connect_retry = true; // retry out connections
                     self.manage_out_connections(
                         res,
                         ip_addr,
-                        &mut cur_connection_id,
+                        connection_id,
                     ).await?
                 },
 
                 // listener socket received
-                res = self.listener.accept() => {
-                    self.manage_in_connections(
-                        res,
-                        &mut cur_connection_id,
-                    ).await?
+                res = self.listener.accept(), if listening => {
+                    self.manage_in_connections(res).await?
                 }
             }
         }
@@ -305,12 +355,59 @@ impl NetworkWorker {
         }
 
         // wait for all running handshakes
-        self.running_handshakes.clear();
         while self.handshake_futures.next().await.is_some() {}
         while self.handshake_peer_list_futures.next().await.is_some() {}
         Ok(())
     }
 
+    /// Forget a node whose worker stopped, returns its connection id if it was active.
+    /// Its clock offset no longer counts in the median.
+    fn remove_active_node(&mut self, node_id: &NodeId) -> Option<ConnectionId> {
+        let (connection_id, _) = self.active_nodes.remove(node_id)?;
+        self.clock_offsets.remove(node_id);
+        self.traffic.remove(node_id);
+        self.check_clock_offset();
+        Some(connection_id)
+    }
+
+    /// Median of the clock offsets of the active nodes relative to ours, in milliseconds.
+    /// None if no node is connected.
+    pub(crate) fn median_clock_offset(&self) -> Option<i64> {
+        let mut offsets: Vec<i64> = self.clock_offsets.values().copied().collect();
+        if offsets.is_empty() {
+            return None;
+        }
+        offsets.sort_unstable();
+        let middle = offsets.len() / 2;
+        if offsets.len() % 2 == 0 {
+            Some((offsets[middle - 1] + offsets[middle]) / 2)
+        } else {
+            Some(offsets[middle])
+        }
+    }
+
+    /// Warn once when our clock deviates from the median clock of our peers by more than `max_clock_offset`.
+    /// A wrong clock makes the node produce and judge blocks at the wrong slots.
+    fn check_clock_offset(&mut self) {
+        let median = match self.median_clock_offset() {
+            Some(median) => median,
+            None => return,
+        };
+        let max_offset = self.cfg.max_clock_offset.to_millis();
+        if median.unsigned_abs() <= max_offset {
+            self.clock_offset_warned = false;
+        } else if !self.clock_offset_warned {
+            self.clock_offset_warned = true;
+            warn!(
+                "YOUR CLOCK IS OFF: the clocks of your {} peers are {} ms {} yours (tolerated: {} ms). Synchronize your system time (ex: with NTP) or your node will miss its blocks and endorsements",
+                self.clock_offsets.len(),
+                median.unsigned_abs(),
+                if median > 0 { "ahead of" } else { "behind" },
+                max_offset
+            );
+        }
+    }
+
     /// Manages finished handshakes.
     /// Only used by the worker.
     ///
@@ -327,7 +424,7 @@ impl NetworkWorker {
         });
         match outcome {
             // a handshake finished, and succeeded
-            Ok((new_node_id, socket_reader, socket_writer)) => {
+            Ok((new_node_id, socket_reader, socket_writer, clock_offset, observer, version)) => {
                 debug!(
                     "handshake with connection_id={} succeeded => node_id={}",
                     new_connection_id, new_node_id
@@ -338,7 +435,12 @@ impl NetworkWorker {
                 });
 
                 // connection was banned in the meantime
-                if !self.running_handshakes.remove(&new_connection_id) {
+                if self
+                    .connections
+                    .get(&new_connection_id)
+                    .map(|connection| connection.state)
+                    != Some(ConnectionState::Handshaking)
+                {
                     debug!(
                         "connection_id={}, node_id={} peer was banned while handshaking",
                         new_connection_id, new_node_id
@@ -352,6 +454,21 @@ impl NetworkWorker {
                     return Ok(());
                 }
 
+                // the node id was banned, maybe under another ip
+                if self.peer_info_db.is_node_banned(&new_node_id) {
+                    debug!(
+                        "connection_id={}, node_id={} node is banned",
+                        new_connection_id, new_node_id
+                    );
+                    massa_trace!("handshake_banned_node", {
+                        "connection_id": new_connection_id,
+                        "node_id": new_node_id
+                    });
+                    self.connection_closed(new_connection_id, ConnectionClosureReason::Banned)
+                        .await?;
+                    return Ok(());
+                }
+
                 match self.active_nodes.entry(new_node_id) {
                     // we already have this node ID
                     hash_map::Entry::Occupied(_) => {
@@ -374,20 +491,27 @@ impl NetworkWorker {
                         });
 
                         // Note connection alive.
-                        let (ip, _) =
-                            self.active_connections
-                                .get(&new_connection_id)
-                                .ok_or_else(|| {
-                                    NetworkError::ActiveConnectionMissing(new_connection_id)
-                                })?;
-                        self.peer_info_db.peer_alive(ip)?;
+                        self.connections.handshake_succeeded(
+                            &mut self.peer_info_db,
+                            new_connection_id,
+                            new_node_id,
+                            version,
+                        )?;
 
                         // spawn node_controller_fn
-                        let (node_command_tx, node_command_rx) =
-                            mpsc::channel::<NodeCommand>(self.cfg.node_command_channel_size);
+                        let (node_command_tx, node_command_rx) = bounded_backpressure_channel(
+                            "node_commands",
+                            self.cfg.node_command_channel_size,
+                            self.cfg.node_command_channel_bytes,
+                        );
                         let node_event_tx_clone = self.event.clone_node_sender();
                         let cfg_copy = self.cfg.clone();
                         let node_worker_command_tx = node_command_tx.clone();
+                        let faults = self.faults.clone();
+                        let traffic = TrafficCounters {
+                            bytes_sent: socket_writer.bytes_written(),
+                            bytes_received: socket_reader.bytes_read(),
+                        };
                         let node_fn_handle = tokio::spawn(async move {
                             let res = NodeWorker::new(
                                 cfg_copy,
@@ -397,6 +521,7 @@ impl NetworkWorker {
                                 node_worker_command_tx,
                                 node_command_rx,
                                 node_event_tx_clone,
+                                faults,
                             )
                             .run_loop()
                             .await;
@@ -404,10 +529,21 @@ impl NetworkWorker {
                         });
                         entry.insert((new_connection_id, node_command_tx.clone()));
                         self.node_worker_handles.push(node_fn_handle);
+                        self.clock_offsets.insert(new_node_id, clock_offset);
+                        self.traffic.insert(new_node_id, traffic);
+                        self.check_clock_offset();
 
+                        let outgoing = self
+                            .connections
+                            .get(&new_connection_id)
+                            .map_or(false, |connection| connection.is_outgoing);
                         let res = self
                             .event
-                            .send(NetworkEvent::NewConnection(new_node_id))
+                            .send(NetworkEvent::NewConnection {
+                                node: new_node_id,
+                                observer,
+                                outgoing,
+                            })
                             .await;
 
                         // If we failed to send the event to protocol, close the connection.
@@ -432,10 +568,26 @@ impl NetworkWorker {
                 // instead of accepting a connection. Notify to the DB that `to_remove`
                 // has failed and merge new `to_add` candidates.
                 self.peer_info_db.merge_candidate_peers(&peers)?;
-                self.running_handshakes.remove(&new_connection_id);
                 self.connection_closed(new_connection_id, ConnectionClosureReason::Failed)
                     .await?;
             }
+            // the peer is on another network: ban it so that it is not retried
+            Err(NetworkError::HandshakeError(HandshakeErrorType::IncompatibleNetwork)) => {
+                if let Some(ip) = self
+                    .connections
+                    .get(&new_connection_id)
+                    .map(|connection| connection.ip)
+                {
+                    debug!(
+                        "peer {} is on another network, banning it (connection_id={})",
+                        ip, new_connection_id
+                    );
+                    self.peer_info_db
+                        .peer_banned(&ip, BanReason::OtherNetwork)?;
+                }
+                self.connection_closed(new_connection_id, ConnectionClosureReason::Banned)
+                    .await?;
+            }
             // a handshake finished and failed
             Err(err) => {
                 debug!(
@@ -446,7 +598,6 @@ impl NetworkWorker {
                     "connection_id": new_connection_id,
                     "err": err.to_string()
                 });
-                self.running_handshakes.remove(&new_connection_id);
                 self.connection_closed(new_connection_id, ConnectionClosureReason::Failed)
                     .await?;
             }
@@ -459,33 +610,18 @@ impl NetworkWorker {
         id: ConnectionId,
         reason: ConnectionClosureReason,
     ) -> Result<(), NetworkError> {
-        let (ip, is_outgoing) = self
-            .active_connections
-            .remove(&id)
-            .ok_or_else(|| NetworkError::ActiveConnectionMissing(id))?;
+        let connection = self
+            .connections
+            .closed(&mut self.peer_info_db, id, reason)?;
         debug!(
             "connection closed connection_id={}, ip={}, reason={:?}",
-            id, ip, reason
+            id, connection.ip, reason
         );
         massa_trace!("network_worker.connection_closed", {
             "connection_id": id,
-            "ip": ip,
+            "ip": connection.ip,
             "reason": reason
         });
-        match reason {
-            ConnectionClosureReason::Normal => {}
-            ConnectionClosureReason::Failed => {
-                self.peer_info_db.peer_failed(&ip)?;
-            }
-            ConnectionClosureReason::Banned => {
-                // nothing here, because peer_info_db.peer_banned called in NetworkCommand::Ban
-            }
-        }
-        if is_outgoing {
-            self.peer_info_db.out_connection_closed(&ip)?;
-        } else {
-            self.peer_info_db.in_connection_closed(&ip)?;
-        }
         Ok(())
     }
 
@@ -495,8 +631,7 @@ impl NetworkWorker {
     /// # Arguments
     /// * `cmd` : command to process.
     /// * `peer_info_db`: Database with peer information.
-    /// * `active_connections`: hashmap linking connection id to `ÌpAddr` to
-    ///   whether connection is outgoing (true)
+    /// * `connections`: connections by id, with their ip, direction and state
     /// * `event_tx`: channel to send network events out.
     ///
     /// # Command implementation
@@ -511,8 +646,12 @@ impl NetworkWorker {
     async fn manage_network_command(&mut self, cmd: NetworkCommand) -> Result<(), NetworkError> {
         use crate::network_cmd_impl::*;
         match cmd {
-            NetworkCommand::NodeBanByIps(ips) => on_node_ban_by_ips_cmd(self, ips).await?,
-            NetworkCommand::NodeBanByIds(ids) => on_node_ban_by_ids_cmd(self, ids).await?,
+            NetworkCommand::NodeBanByIps(ips, reason) => {
+                on_node_ban_by_ips_cmd(self, ips, reason).await?
+            }
+            NetworkCommand::NodeBanByIds(ids, reason) => {
+                on_node_ban_by_ids_cmd(self, ids, reason).await?
+            }
             NetworkCommand::SendBlockHeader { node, header } => {
                 on_send_block_header_cmd(self, node, header).await?
             }
@@ -536,16 +675,49 @@ impl NetworkWorker {
             NetworkCommand::SendEndorsements { node, endorsements } => {
                 on_send_endorsements_cmd(self, node, endorsements).await
             }
+            NetworkCommand::AskForBlockRange { node, start, end } => {
+                on_ask_for_block_range_cmd(self, node, start, end).await
+            }
+            NetworkCommand::SendBlockRange { node, blocks } => {
+                on_send_block_range_cmd(self, node, blocks).await
+            }
+            NetworkCommand::AskForHeaderRange { node, start, end } => {
+                on_ask_for_header_range_cmd(self, node, start, end).await
+            }
+            NetworkCommand::SendHeaderRange { node, headers } => {
+                on_send_header_range_cmd(self, node, headers).await
+            }
             NetworkCommand::NodeSignMessage { msg, response_tx } => {
                 on_node_sign_message_cmd(self, msg, response_tx).await?
             }
+            NetworkCommand::PeerFeedback(feedback) => on_peer_feedback_cmd(self, feedback),
             NetworkCommand::NodeUnbanByIds(ids) => on_node_unban_by_ids_cmd(self, ids).await?,
             NetworkCommand::NodeUnbanByIps(ips) => on_node_unban_by_ips_cmd(self, ips).await?,
+            NetworkCommand::NodeUnbanBySubnets(subnets) => {
+                on_node_unban_by_subnets_cmd(self, subnets).await?
+            }
+            NetworkCommand::GetBanList(response_tx) => on_get_ban_list_cmd(self, response_tx),
+            NetworkCommand::ImportBanList(ban_list) => {
+                on_import_ban_list_cmd(self, ban_list).await?
+            }
+            NetworkCommand::GetPeerHistory { ip, response_tx } => {
+                on_get_peer_history_cmd(self, ip, response_tx)
+            }
+            NetworkCommand::FlushPeers(response_tx) => on_flush_peers_cmd(self, response_tx),
+            NetworkCommand::ReloadPeers(response_tx) => {
+                on_reload_peers_cmd(self, response_tx).await
+            }
+            NetworkCommand::SetFaults {
+                node_id,
+                faults,
+                response_tx,
+            } => on_set_faults_cmd(self, node_id, faults, response_tx),
             NetworkCommand::GetStats { response_tx } => on_get_stats_cmd(self, response_tx).await,
             NetworkCommand::Whitelist(ips) => on_whitelist_cmd(self, ips).await?,
             NetworkCommand::RemoveFromWhitelist(ips) => {
                 on_remove_from_whitelist_cmd(self, ips).await?
             }
+            NetworkCommand::UpdateConfig(config) => on_update_config_cmd(self, config),
         };
         Ok(())
     }
@@ -556,21 +728,20 @@ impl NetworkWorker {
     /// # Arguments
     /// * `res`: `(reader, writer)` in a result coming out of `out_connecting_futures`
     /// * `ip_addr`: distant address we are trying to reach.
-    /// * `cur_connection_id`: connection id of the node we are trying to reach
+    /// * `connection_id`: id of the connection we are trying to establish
     async fn manage_out_connections(
         &mut self,
         res: tokio::io::Result<(ReadHalf, WriteHalf)>,
         ip_addr: IpAddr,
-        cur_connection_id: &mut ConnectionId,
+        connection_id: ConnectionId,
     ) -> Result<(), NetworkError> {
         match res {
             Ok((reader, writer)) => {
                 if self
-                    .peer_info_db
-                    .try_out_connection_attempt_success(&ip_addr)?
+                    .connections
+                    .dial_succeeded(&mut self.peer_info_db, connection_id)?
                 {
                     // outgoing connection established
-                    let connection_id = *cur_connection_id;
                     debug!(
                         "out connection towards ip={} established => connection_id={}",
                         ip_addr, connection_id
@@ -579,10 +750,7 @@ impl NetworkWorker {
                         "ip": ip_addr,
                         "connection_id": connection_id
                     });
-                    cur_connection_id.0 += 1;
-                    self.active_connections
-                        .insert(connection_id, (ip_addr, true));
-                    self.manage_successful_connection(connection_id, reader, writer)?;
+                    self.manage_successful_connection(connection_id, reader, writer);
                 } else {
                     debug!("out connection towards ip={} refused", ip_addr);
                     massa_trace!("out_connection_refused", { "ip": ip_addr });
@@ -597,7 +765,8 @@ impl NetworkWorker {
                     "ip": ip_addr,
                     "err": err.to_string()
                 });
-                self.peer_info_db.out_connection_attempt_failed(&ip_addr)?;
+                self.connections
+                    .dial_failed(&mut self.peer_info_db, connection_id)?;
             }
         }
         Ok(())
@@ -606,8 +775,7 @@ impl NetworkWorker {
     /// Manages in connection
     /// Only used inside worker's `run_loop`
     ///
-    /// Try a connection with an incoming node, if success insert the remote
-    /// address at the index `connection_id` inside `self.active_connections`
+    /// Try a connection with an incoming node, if success add it to `self.connections`
     /// and call `self.manage_successful_connection`
     ///
     /// If the connection failed with `MaxPeersConnectionReached`, mock the
@@ -615,17 +783,17 @@ impl NetworkWorker {
     ///
     /// # Arguments
     /// * `re` : `(reader, writer, socketAddr)` in a result coming out of the listener
-    /// * `cur_connection_id`: connection id of the node we are trying to reach
     async fn manage_in_connections(
         &mut self,
         res: std::io::Result<(ReadHalf, WriteHalf, SocketAddr)>,
-        cur_connection_id: &mut ConnectionId,
     ) -> Result<(), NetworkError> {
         match res {
             Ok((reader, writer, remote_addr)) => {
-                match self.peer_info_db.try_new_in_connection(&remote_addr.ip()) {
-                    Ok(_) => {
-                        let connection_id = *cur_connection_id;
+                match self
+                    .connections
+                    .accept(&mut self.peer_info_db, remote_addr.ip())
+                {
+                    Ok(connection_id) => {
                         debug!(
                             "inbound connection from addr={} succeeded => connection_id={}",
                             remote_addr, connection_id
@@ -634,10 +802,7 @@ impl NetworkWorker {
                             "ip": remote_addr.ip(),
                             "connection_id": connection_id
                         });
-                        cur_connection_id.0 += 1;
-                        self.active_connections
-                            .insert(connection_id, (remote_addr.ip(), false));
-                        self.manage_successful_connection(connection_id, reader, writer)?;
+                        self.manage_successful_connection(connection_id, reader, writer);
                     }
                     Err(NetworkError::PeerConnectionError(
                         NetworkConnectionErrorType::MaxPeersConnectionReached(_),
@@ -693,11 +858,12 @@ impl NetworkWorker {
             {"address": remote_addr}
         );
         if self.cfg.max_in_connection_overflow > self.handshake_peer_list_futures.len() {
-            let msg = Message::PeerList(self.peer_info_db.get_advertisable_peer_ips());
+            let msg = Message::PeerList(self.peer_info_db.get_advertisable_peers());
             let timeout = self.cfg.peer_list_send_timeout.to_duration();
             let max_bytes_read = self.cfg.max_bytes_read;
             let max_bytes_write = self.cfg.max_bytes_write;
             let max_ask_blocks = self.cfg.max_ask_blocks;
+            let max_block_range_length = self.cfg.max_block_range_length;
             let max_operations_per_block = self.cfg.max_operations_per_block;
             let thread_count = self.cfg.thread_count;
             let endorsement_count = self.cfg.endorsement_count;
@@ -705,6 +871,7 @@ impl NetworkWorker {
             let max_endorsements_per_message = self.cfg.max_endorsements_per_message;
             let max_operations_per_message = self.cfg.max_operations_per_message;
             let max_message_size = self.cfg.max_message_size;
+            let message_checksum = self.cfg.message_checksum;
             let max_datastore_value_length = self.cfg.max_datastore_value_length;
             let max_function_name_length = self.cfg.max_function_name_length;
             let max_parameters_size = self.cfg.max_parameters_size;
@@ -713,7 +880,12 @@ impl NetworkWorker {
             let max_op_datastore_value_length = self.cfg.max_op_datastore_value_length;
             self.handshake_peer_list_futures
                 .push(tokio::spawn(async move {
-                    let mut writer = WriteBinder::new(writer, max_bytes_read, max_message_size);
+                    let mut writer = WriteBinder::new(
+                        writer,
+                        max_bytes_read,
+                        max_message_size,
+                        message_checksum,
+                    );
                     let mut reader = ReadBinder::new(
                         reader,
                         max_bytes_write,
@@ -723,6 +895,7 @@ impl NetworkWorker {
                             endorsement_count,
                             max_advertise_length,
                             max_ask_blocks,
+                            max_block_range_length,
                             max_operations_per_block,
                             max_operations_per_message,
                             max_endorsements_per_message,
@@ -752,23 +925,14 @@ impl NetworkWorker {
         }
     }
 
-    /// Manage a successful incoming and outgoing connection,
-    /// Check if we're not already running an handshake for `connection_id` by inserting the connection id in
-    /// `self.running_handshakes`
+    /// Manage a successful incoming and outgoing connection, now `Handshaking` in `self.connections`.
     /// Add a new handshake to perform in `self.handshake_futures` to be handle in the main loop.
-    ///
-    /// Return an handshake error if connection already running/waiting
     fn manage_successful_connection(
         &mut self,
         connection_id: ConnectionId,
         reader: ReadHalf,
         writer: WriteHalf,
-    ) -> Result<(), NetworkError> {
-        if !self.running_handshakes.insert(connection_id) {
-            return Err(NetworkError::HandshakeError(
-                HandshakeErrorType::HandshakeIdAlreadyExist(format!("{}", connection_id)),
-            ));
-        }
+    ) {
         self.handshake_futures.push(HandshakeWorker::spawn(
             reader,
             writer,
@@ -776,11 +940,13 @@ impl NetworkWorker {
             self.keypair.clone(),
             self.cfg.connect_timeout,
             self.version,
+            self.cfg.network_id,
+            self.cfg.observer,
             connection_id,
             self.cfg.max_bytes_read,
             self.cfg.max_bytes_write,
+            self.cfg.message_checksum,
         ));
-        Ok(())
     }
 
     /// Manages node events.
@@ -819,7 +985,134 @@ impl NetworkWorker {
             NodeEvent(node, NodeEventType::ReceivedAskForOperations(operation_ids)) => {
                 event_impl::on_received_ask_for_operations(self, node, operation_ids).await
             }
+            NodeEvent(node, NodeEventType::ReceivedAskForBlockRange { start, end }) => {
+                event_impl::on_received_ask_for_block_range(self, node, start, end).await
+            }
+            NodeEvent(node, NodeEventType::ReceivedBlockRange(blocks)) => {
+                event_impl::on_received_block_range(self, node, blocks).await
+            }
+            NodeEvent(node, NodeEventType::ReceivedAskForHeaderRange { start, end }) => {
+                event_impl::on_received_ask_for_header_range(self, node, start, end).await
+            }
+            NodeEvent(node, NodeEventType::ReceivedHeaderRange(headers)) => {
+                event_impl::on_received_header_range(self, node, headers).await
+            }
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::tools::generate_peers_file;
+    use massa_network_exports::test_exports::mock_establisher;
+    use massa_time::{MassaTime, SystemClock};
+    use std::str::FromStr;
+
+    /// Network worker tolerating a clock offset of 1 second, without running its loop
+    async fn clock_offset_test_worker() -> NetworkWorker {
+        let peers_file = generate_peers_file(&[]);
+        let cfg = NetworkConfig {
+            max_clock_offset: MassaTime::from_millis(1000),
+            ..NetworkConfig::scenarios_default(50_000, peers_file.path())
+        };
+        let (mut establisher, _mock_interface) = mock_establisher::new();
+        let listener = establisher.get_listener(cfg.bind).await.unwrap();
+        let peer_info_db =
+            PeerInfoDatabase::from_peers(&cfg, HashMap::new(), Box::new(SystemClock));
+        let (_controller_command_tx, controller_command_rx) = mpsc::channel(1);
+        let (controller_event_tx, _controller_event_rx) = backpressure_channel("network_events", 1);
+        let (_controller_manager_tx, controller_manager_rx) = mpsc::channel(1);
+        NetworkWorker::new(
+            cfg,
+            KeyPair::generate(),
+            listener,
+            establisher,
+            peer_info_db,
+            NetworkWorkerChannels {
+                controller_command_rx,
+                controller_event_tx,
+                controller_manager_rx,
+            },
+            Version::from_str("TEST.1.10").unwrap(),
+        )
+    }
+
+    /// Register an active node whose clock was measured `clock_offset` ms ahead of ours, as a finished handshake does
+    fn add_active_node(
+        worker: &mut NetworkWorker,
+        connection_id: u64,
+        clock_offset: i64,
+    ) -> NodeId {
+        let node_id = NodeId::new(KeyPair::generate().get_public_key());
+        let (node_command_tx, _) = backpressure_channel::<NodeCommand>("node_commands", 1);
+        worker
+            .active_nodes
+            .insert(node_id, (ConnectionId(connection_id), node_command_tx));
+        worker.clock_offsets.insert(node_id, clock_offset);
+        worker.check_clock_offset();
+        node_id
+    }
+
+    #[tokio::test]
+    async fn test_median_clock_offset() {
+        let mut worker = clock_offset_test_worker().await;
+        assert_eq!(worker.median_clock_offset(), None);
+
+        // odd number of nodes: the middle offset
+        add_active_node(&mut worker, 0, -30);
+        add_active_node(&mut worker, 1, 500);
+        add_active_node(&mut worker, 2, 10);
+        assert_eq!(worker.median_clock_offset(), Some(10));
+
+        // even number of nodes: the mean of the two middle offsets
+        add_active_node(&mut worker, 3, 40);
+        assert_eq!(worker.median_clock_offset(), Some(25));
+    }
+
+    #[tokio::test]
+    async fn test_clock_offset_removed_on_disconnection() {
+        let mut worker = clock_offset_test_worker().await;
+        let ahead_1 = add_active_node(&mut worker, 0, 5000);
+        let ahead_2 = add_active_node(&mut worker, 1, 5000);
+        add_active_node(&mut worker, 2, 0);
+        assert_eq!(worker.median_clock_offset(), Some(5000));
+        assert!(worker.clock_offset_warned);
+
+        assert_eq!(worker.remove_active_node(&ahead_1), Some(ConnectionId(0)));
+        assert!(!worker.clock_offsets.contains_key(&ahead_1));
+        assert_eq!(worker.median_clock_offset(), Some(2500));
+        assert!(worker.clock_offset_warned);
+
+        // once the nodes ahead are gone, our clock matches the network one again
+        assert_eq!(worker.remove_active_node(&ahead_2), Some(ConnectionId(1)));
+        assert_eq!(worker.median_clock_offset(), Some(0));
+        assert!(!worker.clock_offset_warned);
+
+        // an unknown node changes nothing
+        assert_eq!(worker.remove_active_node(&ahead_2), None);
+        assert_eq!(worker.clock_offsets.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_clock_offset_warning_reset() {
+        let mut worker = clock_offset_test_worker().await;
+        let node_id = add_active_node(&mut worker, 0, -1001);
+        assert!(worker.clock_offset_warned);
+
+        // warned once while the offset stays too large
+        worker.check_clock_offset();
+        assert!(worker.clock_offset_warned);
+
+        // the warning is cleared at the tolerated offset
+        worker.clock_offsets.insert(node_id, -1000);
+        worker.check_clock_offset();
+        assert!(!worker.clock_offset_warned);
+
+        // and given again at the next deviation
+        worker.clock_offsets.insert(node_id, 2000);
+        worker.check_clock_offset();
+        assert!(worker.clock_offset_warned);
+    }
+}
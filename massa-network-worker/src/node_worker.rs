@@ -2,19 +2,20 @@
 
 use super::{
     binders::{ReadBinder, WriteBinder},
+    fault_injection::FaultInjector,
     messages::Message,
 };
 use itertools::Itertools;
 use massa_logging::massa_trace;
 use massa_models::{node::NodeId, wrapped::Id};
 use massa_network_exports::{
-    ConnectionClosureReason, NetworkConfig, NetworkError, NodeCommand, NodeEvent, NodeEventType,
+    BackpressureReceiver, BackpressureSender, ConnectionClosureReason, NetworkConfig, NetworkError,
+    NodeCommand, NodeEvent, NodeEventType,
 };
 use massa_time::MassaTime;
 use tokio::{
-    sync::mpsc,
-    sync::mpsc::{error::SendTimeoutError, Sender},
-    time::timeout,
+    sync::mpsc::error::SendTimeoutError,
+    time::{sleep, timeout, Instant},
 };
 use tracing::{debug, trace, warn};
 
@@ -30,11 +31,13 @@ pub struct NodeWorker {
     /// Optional writer to send data.
     socket_writer_opt: Option<WriteBinder>,
     /// Channel to send node commands.
-    node_command_tx: mpsc::Sender<NodeCommand>,
+    node_command_tx: BackpressureSender<NodeCommand>,
     /// Channel to receive node commands.
-    node_command_rx: mpsc::Receiver<NodeCommand>,
+    node_command_rx: BackpressureReceiver<NodeCommand>,
     /// Channel to send node events.
-    node_event_tx: mpsc::Sender<NodeEvent>,
+    node_event_tx: BackpressureSender<NodeEvent>,
+    /// Faults injected in the messages sent to the node.
+    faults: FaultInjector,
 }
 
 impl NodeWorker {
@@ -48,14 +51,17 @@ impl NodeWorker {
     /// * `node_command_rx`: Channel to receive node commands.
     /// * `node_event_tx`: Channel to send node events.
     /// * `storage`: Shared storage.
+    /// * `faults`: Faults injected in the messages sent to the node.
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         cfg: NetworkConfig,
         node_id: NodeId,
         socket_reader: ReadBinder,
         socket_writer: WriteBinder,
-        node_command_tx: mpsc::Sender<NodeCommand>,
-        node_command_rx: mpsc::Receiver<NodeCommand>,
-        node_event_tx: mpsc::Sender<NodeEvent>,
+        node_command_tx: BackpressureSender<NodeCommand>,
+        node_command_rx: BackpressureReceiver<NodeCommand>,
+        node_event_tx: BackpressureSender<NodeEvent>,
+        faults: FaultInjector,
     ) -> NodeWorker {
         NodeWorker {
             cfg,
@@ -65,6 +71,7 @@ impl NodeWorker {
             node_command_tx,
             node_command_rx,
             node_event_tx,
+            faults,
         }
     }
 
@@ -81,10 +88,13 @@ impl NodeWorker {
                 &mut socket_writer,
                 &mut self.node_command_rx,
                 self.cfg.message_timeout,
+                self.cfg.write_coalescing_delay,
                 self.node_id,
                 self.cfg.max_ask_blocks,
+                self.cfg.max_block_range_length,
                 self.cfg.max_operations_per_message,
                 self.cfg.max_endorsements_per_message,
+                self.faults,
             )
             .await
         });
@@ -184,22 +194,99 @@ impl NodeWorker {
     }
 }
 
-/// Handle incoming node command, convert to message(s) and write that to socket
+/// Whether a message is sent immediately, along with the buffered ones,
+/// instead of waiting for other messages to be grouped with
+fn is_urgent(message: &Message) -> bool {
+    matches!(
+        message,
+        Message::BlockHeader(_)
+            | Message::AskForBlocks(_)
+            | Message::ReplyForBlocks(_)
+            | Message::AskForBlockRange { .. }
+            | Message::BlockRange(_)
+            | Message::AskForHeaderRange { .. }
+            | Message::HeaderRange(_)
+            | Message::Disconnect
+    )
+}
+
+/// Send the buffered messages to the socket, returns false if the writing failed
+async fn flush_messages(
+    socket_writer: &mut WriteBinder,
+    write_timeout: MassaTime,
+    node_id: NodeId,
+) -> bool {
+    match timeout(write_timeout.to_duration(), socket_writer.flush()).await {
+        Err(err) => {
+            massa_trace!("node_worker.run_loop.loop.writer_command_rx.flush.timeout", {
+                "node": node_id,
+            });
+            debug!("Node data flushing timed out: {}", err);
+            false
+        }
+        Ok(Err(err)) => {
+            massa_trace!("node_worker.run_loop.loop.writer_command_rx.flush.error", {
+                "node": node_id, "err":  format!("{}", err),
+            });
+            debug!("Node data flushing error: {:?}", err);
+            false
+        }
+        Ok(Ok(())) => true,
+    }
+}
+
+/// Handle incoming node command, convert to message(s) and write that to socket.
+///
+/// The small messages are buffered for at most `coalescing_delay`, so that the messages queued meanwhile
+/// are written to the socket at once. Blocks and headers are sent immediately along with the buffered messages.
+/// With the `fault_injection` feature, the faults set for the node are applied to the messages before they are written.
+#[cfg_attr(not(feature = "fault_injection"), allow(unused_variables))]
 async fn node_writer_handle(
     socket_writer: &mut WriteBinder,
-    node_command_rx: &mut mpsc::Receiver<NodeCommand>,
+    node_command_rx: &mut BackpressureReceiver<NodeCommand>,
     write_timeout: MassaTime,
+    coalescing_delay: MassaTime,
     node_id: NodeId,
     max_ask_blocks: u32,
+    max_block_range_length: u32,
     max_operations_per_message: u32,
     max_endorsements_per_message: u32,
+    faults: FaultInjector,
 ) -> ConnectionClosureReason {
     let mut exit_reason = ConnectionClosureReason::Normal;
+    #[cfg(feature = "fault_injection")]
+    let mut faulty_writer = crate::fault_injection::FaultyWriter::new();
+    let coalescing_delay = coalescing_delay.to_duration();
+    let flush_deadline = sleep(coalescing_delay);
+    tokio::pin!(flush_deadline);
+    let mut flush_pending = false;
 
     'writer_loop: loop {
-        let messages_: Option<Vec<Message>> = match node_command_rx.recv().await {
+        let command = tokio::select! {
+            command = node_command_rx.recv() => command,
+            _ = &mut flush_deadline, if flush_pending => {
+                flush_pending = false;
+                if !flush_messages(socket_writer, write_timeout, node_id).await {
+                    exit_reason = ConnectionClosureReason::Failed;
+                    break 'writer_loop;
+                }
+                continue;
+            }
+        };
+        let messages_: Option<Vec<Message>> = match command {
             Some(NodeCommand::Close(r)) => {
                 exit_reason = r;
+                if r == ConnectionClosureReason::Normal {
+                    // let the peer know that the connection is closed on purpose, ignoring failures
+                    massa_trace!("node_worker.run_loop. send Message::Disconnect", {
+                        "node": node_id
+                    });
+                    let _ = timeout(
+                        write_timeout.to_duration(),
+                        socket_writer.send(&Message::Disconnect),
+                    )
+                    .await;
+                }
                 None
             }
             Some(NodeCommand::SendPeerList(ip_vec)) => {
@@ -271,6 +358,32 @@ async fn node_writer_handle(
                 Some(messages)
             }
             Some(NodeCommand::AskPeerList) => Some(vec![Message::AskPeerList]),
+            Some(NodeCommand::AskForBlockRange { start, end }) => {
+                massa_trace!("node_worker.run_loop. send Message::AskForBlockRange", {"node": node_id, "start": start, "end": end});
+                Some(vec![Message::AskForBlockRange { start, end }])
+            }
+            Some(NodeCommand::SendBlockRange(blocks)) => {
+                massa_trace!("node_worker.run_loop. send Message::BlockRange", {"node": node_id, "block_count": blocks.len()});
+                // cut block list if it exceeds max_block_range_length
+                let messages = blocks
+                    .chunks(max_block_range_length as usize)
+                    .map(|to_send| Message::BlockRange(to_send.to_vec()))
+                    .collect();
+                Some(messages)
+            }
+            Some(NodeCommand::AskForHeaderRange { start, end }) => {
+                massa_trace!("node_worker.run_loop. send Message::AskForHeaderRange", {"node": node_id, "start": start, "end": end});
+                Some(vec![Message::AskForHeaderRange { start, end }])
+            }
+            Some(NodeCommand::SendHeaderRange(headers)) => {
+                massa_trace!("node_worker.run_loop. send Message::HeaderRange", {"node": node_id, "header_count": headers.len()});
+                // cut header list if it exceeds max_block_range_length
+                let messages = headers
+                    .chunks(max_block_range_length as usize)
+                    .map(|to_send| Message::HeaderRange(to_send.to_vec()))
+                    .collect();
+                Some(messages)
+            }
             None => {
                 // Note: this should never happen,
                 // since it implies the network worker dropped its node command sender
@@ -286,8 +399,18 @@ async fn node_writer_handle(
         // safe to unwrap here
         let messages = messages_.unwrap();
 
+        #[cfg(feature = "fault_injection")]
+        let messages = match faults.get(&node_id) {
+            Some(message_faults) => {
+                let (messages, delay) = faulty_writer.apply(&message_faults, messages);
+                sleep(delay).await;
+                messages
+            }
+            None => messages,
+        };
+
         for msg in messages.iter() {
-            match timeout(write_timeout.to_duration(), socket_writer.send(msg)).await {
+            match timeout(write_timeout.to_duration(), socket_writer.feed(msg)).await {
                 Err(err) => {
                     massa_trace!("node_worker.run_loop.loop.writer_command_rx.recv.send.timeout", {
                         "node": node_id,
@@ -310,6 +433,19 @@ async fn node_writer_handle(
                 }
             }
         }
+
+        if coalescing_delay.is_zero() || messages.iter().any(is_urgent) {
+            flush_pending = false;
+            if !flush_messages(socket_writer, write_timeout, node_id).await {
+                exit_reason = ConnectionClosureReason::Failed;
+                break 'writer_loop;
+            }
+        } else if !flush_pending {
+            flush_pending = true;
+            flush_deadline
+                .as_mut()
+                .reset(Instant::now() + coalescing_delay);
+        }
     }
 
     exit_reason
@@ -319,7 +455,7 @@ async fn node_writer_handle(
 // via 'node_event_tx' queue
 async fn node_reader_handle(
     socket_reader: &mut ReadBinder,
-    node_event_tx: &mut Sender<NodeEvent>,
+    node_event_tx: &mut BackpressureSender<NodeEvent>,
     node_id: NodeId,
     max_send_wait: MassaTime,
 ) -> ConnectionClosureReason {
@@ -337,6 +473,7 @@ async fn node_reader_handle(
                             "node_worker.run_loop. receive Message::BlockHeader",
                             {"block_id": header.id.get_hash(), "header": header, "node": node_id}
                         );
+                        debug!(block_id = %header.id, "block header received");
                         let event = NodeEvent(node_id, NodeEventType::ReceivedBlockHeader(header));
                         send_node_event(node_event_tx, event, max_send_wait).await
                     }
@@ -395,6 +532,36 @@ async fn node_reader_handle(
                             NodeEvent(node_id, NodeEventType::ReceivedEndorsements(endorsements));
                         send_node_event(node_event_tx, event, max_send_wait).await
                     }
+                    Message::AskForBlockRange { start, end } => {
+                        massa_trace!("node_worker.run_loop. receive Message::AskForBlockRange", {"node": node_id, "start": start, "end": end});
+                        let event = NodeEvent(
+                            node_id,
+                            NodeEventType::ReceivedAskForBlockRange { start, end },
+                        );
+                        send_node_event(node_event_tx, event, max_send_wait).await
+                    }
+                    Message::BlockRange(blocks) => {
+                        massa_trace!("node_worker.run_loop. receive Message::BlockRange", {"node": node_id, "block_count": blocks.len()});
+                        let event = NodeEvent(node_id, NodeEventType::ReceivedBlockRange(blocks));
+                        send_node_event(node_event_tx, event, max_send_wait).await
+                    }
+                    Message::AskForHeaderRange { start, end } => {
+                        massa_trace!("node_worker.run_loop. receive Message::AskForHeaderRange", {"node": node_id, "start": start, "end": end});
+                        let event = NodeEvent(
+                            node_id,
+                            NodeEventType::ReceivedAskForHeaderRange { start, end },
+                        );
+                        send_node_event(node_event_tx, event, max_send_wait).await
+                    }
+                    Message::HeaderRange(headers) => {
+                        massa_trace!("node_worker.run_loop. receive Message::HeaderRange", {"node": node_id, "header_count": headers.len()});
+                        let event = NodeEvent(node_id, NodeEventType::ReceivedHeaderRange(headers));
+                        send_node_event(node_event_tx, event, max_send_wait).await
+                    }
+                    Message::Disconnect => {
+                        debug!("node_id={} closed the connection", node_id);
+                        break;
+                    }
                     _ => {
                         // TODO: Write a more user-friendly warning/logout after several consecutive fails? see #1082
                         massa_trace!("node_worker.run_loop.self.socket_reader.next(). Unexpected message Warning", {});
@@ -423,10 +590,10 @@ async fn node_reader_handle(
     exit_reason
 }
 
-/// Send a node event
+/// Send a node event: gossip never waits, consensus-critical events wait for room at most `max_send_wait`
 // via node_event_tx queue - used by 'node_reader_handle'
 async fn send_node_event(
-    node_event_tx: &mut Sender<NodeEvent>,
+    node_event_tx: &mut BackpressureSender<NodeEvent>,
     event: NodeEvent,
     max_send_wait: MassaTime,
 ) {
@@ -1,98 +1,328 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
-use enum_map::EnumMap;
+use crate::ban_audit::{BanAuditAction, BanAuditLog, BanAuditRecord, BanAuditTarget};
+use crate::peers_file::PeersFileContent;
+use enum_map::{enum_map, EnumMap};
+use im::HashMap;
 use itertools::Itertools;
 use massa_logging::massa_trace;
-use massa_network_exports::settings::PeerTypeConnectionConfig;
+use massa_metrics::NETWORK_METRICS;
+use massa_models::api::PeerConnectionEvent;
+use massa_models::ban_list::{BanList, BanListEntry, IpSubnet};
+use massa_models::config::{MAX_ADVERTISED_PEER_ADDRESSES, MAX_PEER_REPUTATION};
+use massa_models::node::NodeId;
+use massa_models::version::Version;
+use massa_network_exports::settings::PeersFileFormat;
+use massa_network_exports::AdvertisedPeer;
+use massa_network_exports::BanReason;
 use massa_network_exports::ConnectionCount;
 use massa_network_exports::NetworkConfig;
 use massa_network_exports::NetworkConnectionErrorType;
 use massa_network_exports::NetworkError;
+use massa_network_exports::PeerFeedback;
 use massa_network_exports::PeerInfo;
 use massa_network_exports::PeerType;
-use massa_time::MassaTime;
-use serde_json::json;
+use massa_time::{Clock, MassaTime};
 use std::cmp::Reverse;
-use std::collections::HashMap;
-use std::net::IpAddr;
+use std::collections::{HashSet, VecDeque};
+use std::net::{IpAddr, SocketAddr};
 use std::path::Path;
-use tokio::sync::watch;
+use tokio::sync::{mpsc, oneshot, watch};
 use tokio::task::JoinHandle;
 use tokio::time::{sleep, Duration};
 use tracing::{trace, warn};
+
+/// Peers and bans sent to the saver.
+/// Both are persistent maps: sending them shares their unchanged parts instead of copying them.
+pub(crate) type PeersSnapshot = (HashMap<IpAddr, PeerInfo>, HashMap<IpSubnet, BanListEntry>);
+
+/// Channel on which the saver answers a flush request, once the peers file is written
+pub(crate) type FlushResponseSender = oneshot::Sender<Result<(), NetworkError>>;
+
+/// Number of samples kept by the rolling aggregates of `ConnectionTimings`
+const CONNECTION_TIMINGS_WINDOW: usize = 64;
+
+/// Minimum number of dial latencies observed in a network group before its connection timeout is adapted
+const MIN_ADAPTIVE_CONNECT_TIMEOUT_SAMPLES: usize = 8;
+
+/// Credits of the dial budget worth one out connection attempt:
+/// the budget earns `max_out_attempts_per_minute` credits per millisecond
+const DIAL_CREDITS_PER_ATTEMPT: u64 = 60_000;
+
+/// Token bucket limiting the new out connection attempts towards all the peers.
+/// It earns `max_out_attempts_per_minute` attempts per minute, up to `max_out_attempts_burst`,
+/// and does not limit anything if `max_out_attempts_per_minute` is 0.
+/// The node settings check rejects an empty burst with a non-zero budget, which would block every attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct DialBudget {
+    /// credits at `updated_at`, `DIAL_CREDITS_PER_ATTEMPT` for each attempt
+    credits: u64,
+    /// time of the last update of the credits
+    updated_at: MassaTime,
+}
+
+impl DialBudget {
+    /// Full budget at `now`
+    fn new(cfg: &NetworkConfig, now: MassaTime) -> Self {
+        DialBudget {
+            credits: DialBudget::capacity(cfg),
+            updated_at: now,
+        }
+    }
+
+    fn capacity(cfg: &NetworkConfig) -> u64 {
+        u64::from(cfg.max_out_attempts_burst).saturating_mul(DIAL_CREDITS_PER_ATTEMPT)
+    }
+
+    /// Credits at `now`, with those earned since the last update
+    fn credits_at(&self, cfg: &NetworkConfig, now: MassaTime) -> u64 {
+        let earned = now
+            .saturating_sub(self.updated_at)
+            .to_millis()
+            .saturating_mul(u64::from(cfg.max_out_attempts_per_minute));
+        self.credits
+            .saturating_add(earned)
+            .min(DialBudget::capacity(cfg))
+    }
+
+    /// Number of out connection attempts that can be started at `now`
+    fn available(&self, cfg: &NetworkConfig, now: MassaTime) -> usize {
+        if cfg.max_out_attempts_per_minute == 0 {
+            return usize::MAX;
+        }
+        (self.credits_at(cfg, now) / DIAL_CREDITS_PER_ATTEMPT)
+            .try_into()
+            .unwrap_or(usize::MAX)
+    }
+
+    /// Spends the credits of an out connection attempt started at `now`
+    fn spend(&mut self, cfg: &NetworkConfig, now: MassaTime) {
+        self.credits = self
+            .credits_at(cfg, now)
+            .saturating_sub(DIAL_CREDITS_PER_ATTEMPT);
+        self.updated_at = now;
+    }
+}
+
+/// Rolling aggregates of the timings of the last connections with the peers of a network group
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConnectionTimings {
+    /// last TCP connect latencies of the outgoing connections, oldest first
+    dial_latencies: VecDeque<MassaTime>,
+    /// last durations of the successful handshakes, oldest first
+    handshake_durations: VecDeque<MassaTime>,
+}
+
+impl ConnectionTimings {
+    fn push(samples: &mut VecDeque<MassaTime>, sample: MassaTime) {
+        if samples.len() == CONNECTION_TIMINGS_WINDOW {
+            samples.pop_front();
+        }
+        samples.push_back(sample);
+    }
+
+    fn average(samples: &VecDeque<MassaTime>) -> Option<MassaTime> {
+        let total: u64 = samples.iter().map(|sample| sample.to_millis()).sum();
+        total
+            .checked_div(samples.len() as u64)
+            .map(MassaTime::from_millis)
+    }
+
+    /// Average of the last TCP connect latencies, none if no outgoing connection was established
+    pub fn average_dial_latency(&self) -> Option<MassaTime> {
+        ConnectionTimings::average(&self.dial_latencies)
+    }
+
+    /// Average of the last handshake durations, none if no handshake succeeded
+    pub fn average_handshake_duration(&self) -> Option<MassaTime> {
+        ConnectionTimings::average(&self.handshake_durations)
+    }
+
+    /// Nearest-rank `percentile` of the last TCP connect latencies,
+    /// none if no outgoing connection was established
+    pub fn dial_latency_percentile(&self, percentile: u8) -> Option<MassaTime> {
+        let mut latencies: Vec<MassaTime> = self.dial_latencies.iter().copied().collect();
+        latencies.sort_unstable();
+        let rank = (latencies.len() * percentile.min(100) as usize + 99) / 100;
+        latencies.get(rank.saturating_sub(1)).copied()
+    }
+
+    /// Number of dial latencies and handshake durations in the aggregates
+    pub fn sample_counts(&self) -> (usize, usize) {
+        (self.dial_latencies.len(), self.handshake_durations.len())
+    }
+}
+
 /// Contains all information about every peers we know about.
 pub struct PeerInfoDatabase {
     /// Network configuration.
     pub(crate) network_settings: NetworkConfig,
-    /// Maps an ip address to peer's info
+    /// Maps an ip address to peer's info.
+    /// Persistent map: the snapshots sent to the saver share its unchanged parts instead of copying them.
     pub peers: HashMap<IpAddr, PeerInfo>,
-    /// Handle on the task managing the dump
-    pub(crate) saver_join_handle: JoinHandle<()>,
-    /// Monitor changed peers.
-    pub(crate) saver_watch_tx: watch::Sender<HashMap<IpAddr, PeerInfo>>,
+    /// Handle on the task managing the dump, none if the peers are not dumped
+    pub(crate) saver_join_handle: Option<JoinHandle<()>>,
+    /// Monitor changed peers and bans.
+    pub(crate) saver_watch_tx: watch::Sender<PeersSnapshot>,
+    /// Requests to the saver to dump the last snapshot right away, whatever the dump interval
+    pub(crate) saver_flush_tx: mpsc::UnboundedSender<FlushResponseSender>,
+    /// Interval between two peer dumps, read by the saver before each dump
+    pub(crate) dump_interval_tx: watch::Sender<MassaTime>,
     /// Connections count for each `PeerType`
     pub(crate) peer_types_connection_count: EnumMap<PeerType, ConnectionCount>,
     /// Every `wakeup_interval` we try to establish a connection with known inactive peers
     pub(crate) wakeup_interval: MassaTime,
+    /// Bans of ip addresses and subnets, imported or asked through the API, kept until they expire or are lifted.
+    /// Unlike the bans of the peers, they are saved in the peers file.
+    pub(crate) ban_list: HashMap<IpSubnet, BanListEntry>,
+    /// Last ended connections of each known peer, oldest first. Isn't dump into peer file.
+    pub(crate) peer_histories: HashMap<IpAddr, VecDeque<PeerConnectionEvent>>,
+    /// Banned node ids with the time of their ban, refused whatever the ip they connect from.
+    /// Forgotten after `ban_timeout` like the banned peers. Isn't dump into peer file.
+    pub(crate) banned_node_ids: HashMap<NodeId, MassaTime>,
+    /// Connection timings by network group of the peers. Forgotten with the last known peer of the group,
+    /// isn't dump into peer file.
+    pub(crate) connection_timings: HashMap<IpSubnet, ConnectionTimings>,
+    /// Audit log of the bans and unbans, none if they are not logged
+    pub(crate) ban_audit_log: Option<BanAuditLog>,
+    /// Budget of the new out connection attempts towards all the peers
+    pub(crate) dial_budget: DialBudget,
+    /// Source of the current time
+    pub(crate) clock: Box<dyn Clock>,
 }
 
-/// Saves advertised and non standard peers to a file.
+/// Saves advertised and non standard peers to a file, along with the bans of ip addresses and subnets.
 ///
 /// # Arguments
 /// * `peers`: peers to save
+/// * `ban_list`: bans to save
 /// * `file_path`: path to the file
+/// * `format`: format of the file
 async fn dump_peers(
     peers: &HashMap<IpAddr, PeerInfo>,
+    ban_list: &HashMap<IpSubnet, BanListEntry>,
     file_path: &Path,
+    format: PeersFileFormat,
+    password: Option<&str>,
 ) -> Result<(), NetworkError> {
-    let peer_vec: Vec<_> = peers
+    let peers = peers
         .values()
         .filter(|v| v.advertised || v.peer_type != PeerType::Standard || v.banned)
-        .map(|peer| {
-            json!({
-                "ip": peer.ip,
-                "banned": peer.banned,
-                "peer_type": peer.peer_type,
-                "last_alive": peer.last_alive,
-                "last_failure": peer.last_failure,
-                "advertised": peer.advertised,
-            })
-        })
+        .cloned()
         .collect();
+    let mut bans: Vec<BanListEntry> = ban_list.values().cloned().collect();
+    bans.sort_unstable_by_key(|ban| ban.target);
 
-    tokio::fs::write(file_path, serde_json::to_string_pretty(&peer_vec)?).await?;
+    PeersFileContent { peers, bans }
+        .dump(file_path, format, password)
+        .await
+}
 
-    Ok(())
+/// Keeps the `count` smallest items according to `key`, in no particular order.
+/// Linear in the number of items, where sorting them all before truncating would not be.
+fn retain_smallest_by_key<T, K: Ord>(items: &mut Vec<T>, count: usize, key: impl FnMut(&T) -> K) {
+    if count == 0 {
+        items.clear();
+    } else if items.len() > count {
+        items.select_nth_unstable_by_key(count - 1, key);
+        items.truncate(count);
+    }
+}
+
+/// Network group of `ip`: the /16 subnet of an IPv4 address, the /32 subnet of an IPv6 address.
+/// The peers of a group are likely to be run by the same operator.
+fn network_group(ip: &IpAddr) -> IpSubnet {
+    let prefix_length = match ip {
+        IpAddr::V4(_) => 16,
+        IpAddr::V6(_) => 32,
+    };
+    IpSubnet::new(*ip, prefix_length).expect("critical: prefix length valid for the ip version")
+}
+
+/// Keeps the `per_group` smallest items of each network group according to `key`,
+/// then the `count` smallest of them, in no particular order. Linear in the number of items.
+fn retain_smallest_by_group<T, K: Ord>(
+    items: &mut Vec<T>,
+    count: usize,
+    per_group: usize,
+    ip: impl Fn(&T) -> IpAddr,
+    mut key: impl FnMut(&T) -> K,
+) {
+    // no group can exceed its quota otherwise
+    if items.len() > per_group {
+        let mut groups: std::collections::HashMap<IpSubnet, Vec<T>> =
+            std::collections::HashMap::new();
+        for item in items.drain(..) {
+            groups
+                .entry(network_group(&ip(&item)))
+                .or_default()
+                .push(item);
+        }
+        for mut group in groups.into_values() {
+            retain_smallest_by_key(&mut group, per_group, &mut key);
+            items.append(&mut group);
+        }
+    }
+    retain_smallest_by_key(items, count, key);
+}
+
+/// Other addresses of an advertised peer that can be dialed: on a global ip and a non-zero port,
+/// without the main address nor duplicates, up to `MAX_ADVERTISED_PEER_ADDRESSES` addresses in all
+fn get_other_dial_addresses(peer: &AdvertisedPeer) -> Vec<SocketAddr> {
+    let main_address = SocketAddr::new(peer.address.ip().to_canonical(), peer.address.port());
+    peer.other_addresses
+        .iter()
+        .map(|addr| SocketAddr::new(addr.ip().to_canonical(), addr.port()))
+        .filter(|addr| addr.port() != 0 && addr.ip().is_global() && *addr != main_address)
+        .unique()
+        .take(MAX_ADVERTISED_PEER_ADDRESSES.saturating_sub(1) as usize)
+        .collect()
 }
 
 /// Cleans up the peer database using max values
 /// provided by `NetworkConfig.ProtocolConfig`.
-/// If `opt_new_peers` is provided, adds its contents as well.
+/// If `opt_new_peers` is provided, adds its contents as well, with the advertised addresses.
+/// The addresses of a known standard peer are replaced by the advertised ones while no connection is established with it.
 ///
 /// Note: only standard non-active peers are counted when clipping to size limits.
+/// The idle and banned peers are first clipped to `max_peers_per_network_group` in each network group,
+/// so that the peers of a single subnet can not take the place of all the others.
 ///
 /// Arguments :
 /// * `cfg`: `NetworkConfig`
 /// * `peers`: peers to clean up
-/// * `opt_new_peers`: optional peers to add to the database
-/// * `clock_compensation`: to be sync with server time
+/// * `opt_new_peers`: optional peers to add to the database, the main addresses with port 0 being ignored
 /// * `ban_timeout`: after that time we forget we banned a peer
-pub(crate) fn cleanup_peers(
+/// * `now`: current time
+pub fn cleanup_peers(
     cfg: &NetworkConfig,
     peers: &mut HashMap<IpAddr, PeerInfo>,
-    opt_new_peers: Option<&Vec<IpAddr>>,
+    opt_new_peers: Option<&[AdvertisedPeer]>,
     ban_timeout: MassaTime,
+    now: MassaTime,
 ) -> Result<(), NetworkError> {
     // filter and map new peers, remove duplicates
     let mut res_new_peers: Vec<PeerInfo> = if let Some(new_peers) = opt_new_peers {
         new_peers
             .iter()
-            .map(|ip| ip.to_canonical())
-            .unique()
-            .filter(|&ip| {
+            .filter(|peer| peer.address.port() != 0)
+            .map(|peer| {
+                (
+                    peer.address.ip().to_canonical(),
+                    peer.address.port(),
+                    get_other_dial_addresses(peer),
+                )
+            })
+            .unique_by(|(ip, _, _)| *ip)
+            .filter(|(ip, port, other_addresses)| {
+                let ip = *ip;
                 if let Some(mut p) = peers.get_mut(&ip) {
                     // avoid already-known IPs, but mark them as advertised
                     p.advertised = true;
+                    if p.peer_type == PeerType::Standard && !p.is_active() {
+                        p.port = Some(*port);
+                        p.other_addresses = other_addresses.clone();
+                    }
                     return false;
                 }
                 if !ip.is_global() {
@@ -108,7 +338,11 @@ pub(crate) fn cleanup_peers(
                 true
             })
             .take(cfg.max_peer_advertise_length as usize)
-            .map(|ip| PeerInfo::new(ip, true))
+            .map(|(ip, port, other_addresses)| PeerInfo {
+                port: Some(port),
+                other_addresses,
+                ..PeerInfo::new(ip, true)
+            })
             .collect()
     } else {
         Vec::new()
@@ -121,7 +355,7 @@ pub(crate) fn cleanup_peers(
     let mut keep_peers: Vec<PeerInfo> = Vec::new();
     let mut banned_peers: Vec<PeerInfo> = Vec::new();
     let mut idle_peers: Vec<PeerInfo> = Vec::new();
-    for (ip, p) in peers.drain() {
+    for (ip, p) in std::mem::take(peers) {
         if !ip.is_global() {
             // avoid non-global IPs
             continue;
@@ -142,24 +376,42 @@ pub(crate) fn cleanup_peers(
     }
 
     // append new peers to idle_peers
-    // stable sort to keep new_peers order,
-    // also prefer existing peers over new ones
-    // truncate to max length
+    // keep the best reputed then most recently alive ones up to max length in each network group and overall,
+    // the position breaks ties to keep new_peers order
+    // and to prefer existing peers over new ones
     idle_peers.append(&mut res_new_peers);
-    idle_peers.sort_by_key(|&p| (std::cmp::Reverse(p.last_alive), p.last_failure));
-    idle_peers.truncate(cfg.max_idle_peers);
-
-    // sort and truncate inactive banned peers
+    let mut idle_peers: Vec<(usize, PeerInfo)> = idle_peers.into_iter().enumerate().collect();
+    retain_smallest_by_group(
+        &mut idle_peers,
+        cfg.max_idle_peers,
+        cfg.max_peers_per_network_group,
+        |(_, p)| p.ip,
+        |(index, p)| {
+            (
+                Reverse(p.reputation),
+                Reverse(p.last_alive),
+                p.last_failure,
+                *index,
+            )
+        },
+    );
+
+    // keep the most recently failed inactive banned peers up to max length in each network group and overall
     // forget about old banned peers
-    let ban_limit = MassaTime::now()?.saturating_sub(ban_timeout);
+    let ban_limit = now.saturating_sub(ban_timeout);
     banned_peers.retain(|p| p.last_failure.map_or(false, |v| v >= ban_limit));
-    banned_peers.sort_unstable_by_key(|&p| (std::cmp::Reverse(p.last_failure), p.last_alive));
-    banned_peers.truncate(cfg.max_banned_peers);
+    retain_smallest_by_group(
+        &mut banned_peers,
+        cfg.max_banned_peers,
+        cfg.max_peers_per_network_group,
+        |p| p.ip,
+        |p| (Reverse(p.last_failure), p.last_alive),
+    );
 
     // gather everything back
     peers.extend(keep_peers.into_iter().map(|p| (p.ip, p)));
     peers.extend(banned_peers.into_iter().map(|p| (p.ip, p)));
-    peers.extend(idle_peers.into_iter().map(|p| (p.ip, p)));
+    peers.extend(idle_peers.into_iter().map(|(_, p)| (p.ip, p)));
     Ok(())
 }
 
@@ -169,41 +421,46 @@ impl PeerInfoDatabase {
     ///
     /// # Argument
     /// * `cfg`: network configuration
-    pub async fn new(cfg: &NetworkConfig) -> Result<Self, NetworkError> {
+    /// * `clock`: source of the current time
+    pub async fn new(cfg: &NetworkConfig, clock: Box<dyn Clock>) -> Result<Self, NetworkError> {
         // wakeup interval
         let wakeup_interval = cfg.wakeup_interval;
 
         // load from initial file
-        let mut peers = serde_json::from_str::<Vec<PeerInfo>>(
-            &tokio::fs::read_to_string(&cfg.initial_peers_file).await?,
-        )?
-        .into_iter()
-        .map(|mut p| {
-            p.cleanup();
-            (p.ip, p)
-        })
-        .collect::<HashMap<IpAddr, PeerInfo>>();
+        let PeersFileContent {
+            peers: initial_peers,
+            mut bans,
+        } = PeersFileContent::load(&cfg.initial_peers_file, cfg.peers_file_password.as_deref())
+            .await?;
+        let mut peers = initial_peers
+            .into_iter()
+            .map(|p| (p.ip, p))
+            .collect::<HashMap<IpAddr, PeerInfo>>();
         if cfg.peers_file.is_file() {
-            peers.extend(
-                // previously known peers
-                serde_json::from_str::<Vec<PeerInfo>>(
-                    &tokio::fs::read_to_string(&cfg.peers_file).await?,
-                )?
-                .into_iter()
-                .map(|mut p| {
-                    p.cleanup();
-                    (p.ip, p)
-                }),
-            );
+            // previously known peers and bans
+            let known =
+                PeersFileContent::load(&cfg.peers_file, cfg.peers_file_password.as_deref()).await?;
+            peers.extend(known.peers.into_iter().map(|p| (p.ip, p)));
+            bans.extend(known.bans);
         }
 
         // cleanup
-        cleanup_peers(cfg, &mut peers, None, cfg.ban_timeout)?;
+        let now = clock.now()?;
+        cleanup_peers(cfg, &mut peers, None, cfg.ban_timeout, now)?;
+        let ban_list = bans
+            .into_iter()
+            .filter(|ban| !ban.is_expired(now))
+            .map(|ban| (ban.target, ban))
+            .collect::<HashMap<IpSubnet, BanListEntry>>();
 
         // setup saver
         let peers_file = cfg.peers_file.clone();
-        let peers_file_dump_interval = cfg.peers_file_dump_interval;
-        let (saver_watch_tx, mut saver_watch_rx) = watch::channel(peers.clone());
+        let peers_file_format = cfg.peers_file_format;
+        let peers_file_password = cfg.peers_file_password.clone();
+        let (dump_interval_tx, dump_interval_rx) = watch::channel(cfg.peers_file_dump_interval);
+        let (saver_watch_tx, mut saver_watch_rx) =
+            watch::channel((peers.clone(), ban_list.clone()));
+        let (saver_flush_tx, mut saver_flush_rx) = mpsc::unbounded_channel();
         let mut need_dump = false;
         let saver_join_handle = tokio::spawn(async move {
             let delay = sleep(Duration::from_millis(0));
@@ -212,20 +469,32 @@ impl PeerInfoDatabase {
                 tokio::select! {
                     opt_p = saver_watch_rx.changed() => match opt_p {
                         Ok(_) => if !need_dump {
-                            delay.set(sleep(peers_file_dump_interval.to_duration()));
+                            delay.set(sleep(dump_interval_rx.borrow().to_duration()));
                             need_dump = true;
                         },
                         Err(_) => break
                     },
                     _ = &mut delay, if need_dump => {
-                        let to_dump = saver_watch_rx.borrow().clone();
-                        match dump_peers(&to_dump, &peers_file).await {
+                        // cheap clone of the persistent map, so that the channel is not borrowed while dumping
+                        let (peers, ban_list) = saver_watch_rx.borrow().clone();
+                        let password = peers_file_password.as_deref();
+                        match dump_peers(&peers, &ban_list, &peers_file, peers_file_format, password).await {
                             Ok(_) => { need_dump = false; },
                             Err(e) => {
                                 warn!("could not dump peers to file: {}", e);
-                                delay.set(sleep(peers_file_dump_interval.to_duration()));
+                                delay.set(sleep(dump_interval_rx.borrow().to_duration()));
                             }
                         }
+                    },
+                    Some(response_tx) = saver_flush_rx.recv() => {
+                        // the last snapshot is marked as seen: it is not dumped a second time
+                        let (peers, ban_list) = saver_watch_rx.borrow_and_update().clone();
+                        let password = peers_file_password.as_deref();
+                        let res = dump_peers(&peers, &ban_list, &peers_file, peers_file_format, password).await;
+                        if res.is_ok() {
+                            need_dump = false;
+                        }
+                        let _ = response_tx.send(res);
                     }
                 }
             }
@@ -235,19 +504,94 @@ impl PeerInfoDatabase {
         Ok(PeerInfoDatabase {
             network_settings: cfg.clone(),
             peers,
-            saver_join_handle,
+            saver_join_handle: Some(saver_join_handle),
             saver_watch_tx,
+            saver_flush_tx,
+            dump_interval_tx,
             wakeup_interval,
             peer_types_connection_count: EnumMap::default(),
+            ban_list,
+            peer_histories: HashMap::new(),
+            banned_node_ids: HashMap::new(),
+            connection_timings: HashMap::new(),
+            ban_audit_log: cfg.ban_audit_file.clone().map(BanAuditLog::start),
+            dial_budget: DialBudget::new(cfg, now),
+            clock,
         })
     }
 
-    /// Cleanly closes `peerInfoDatabase`, performing one last peer dump.
+    /// Creates a `PeerInfoDatabase` holding `peers`, reading the time from `clock`,
+    /// without reading nor dumping peer files, without logging the bans and without spawning any task.
+    /// The connection counts are those of the given peers.
+    #[cfg(any(test, feature = "testing"))]
+    pub fn from_peers(
+        cfg: &NetworkConfig,
+        peers: HashMap<IpAddr, PeerInfo>,
+        clock: Box<dyn Clock>,
+    ) -> Self {
+        let mut peer_types_connection_count: EnumMap<PeerType, ConnectionCount> =
+            EnumMap::default();
+        for p in peers.values() {
+            let count = &mut peer_types_connection_count[p.peer_type];
+            count.active_out_connection_attempts += p.active_out_connection_attempts;
+            count.active_out_connections += p.active_out_connections;
+            count.active_in_connections += p.active_in_connections;
+        }
+        let (dump_interval_tx, _) = watch::channel(cfg.peers_file_dump_interval);
+        let (saver_watch_tx, _) = watch::channel((peers.clone(), HashMap::new()));
+        let (saver_flush_tx, _) = mpsc::unbounded_channel();
+        PeerInfoDatabase {
+            network_settings: cfg.clone(),
+            peers,
+            saver_join_handle: None,
+            saver_watch_tx,
+            saver_flush_tx,
+            dump_interval_tx,
+            wakeup_interval: cfg.wakeup_interval,
+            peer_types_connection_count,
+            ban_list: HashMap::new(),
+            peer_histories: HashMap::new(),
+            banned_node_ids: HashMap::new(),
+            connection_timings: HashMap::new(),
+            ban_audit_log: None,
+            dial_budget: DialBudget::new(
+                cfg,
+                clock.now().unwrap_or_else(|_| MassaTime::from_millis(0)),
+            ),
+            clock,
+        }
+    }
+
+    /// Apply new connection limits and peers dump interval.
+    /// The new limits apply to the next connections, the current ones are kept.
+    pub fn update_config(&mut self, config: NetworkReloadableConfig) {
+        self.network_settings.peer_types_config = config.peer_types_config;
+        self.network_settings.max_in_connections_per_ip = config.max_in_connections_per_ip;
+        self.network_settings.peers_file_dump_interval = config.peers_file_dump_interval;
+        let _ = self.dump_interval_tx.send(config.peers_file_dump_interval);
+    }
+
+    /// Cleanly closes `peerInfoDatabase`, writing the pending records of the ban audit log
+    /// and performing one last peer dump if the peers are dumped.
     /// A warning is raised on dump failure.
     pub async fn stop(self) -> Result<(), NetworkError> {
+        if let Some(ban_audit_log) = self.ban_audit_log {
+            ban_audit_log.stop().await;
+        }
         drop(self.saver_watch_tx);
-        self.saver_join_handle.await?;
-        if let Err(e) = dump_peers(&self.peers, &self.network_settings.peers_file).await {
+        match self.saver_join_handle {
+            Some(saver_join_handle) => saver_join_handle.await?,
+            None => return Ok(()),
+        }
+        if let Err(e) = dump_peers(
+            &self.peers,
+            &self.ban_list,
+            &self.network_settings.peers_file,
+            self.network_settings.peers_file_format,
+            self.network_settings.peers_file_password.as_deref(),
+        )
+        .await
+        {
             warn!("could not dump peers to file: {}", e);
         }
         Ok(())
@@ -286,59 +630,315 @@ impl PeerInfoDatabase {
     ///////////////////////
 
     /// Refreshes the peer list. Should be called at regular intervals.
-    /// Performs multiple cleanup tasks e.g. remove old banned peers
+    /// Performs multiple cleanup tasks e.g. remove old banned peers and expired imported bans
     pub fn update(&mut self) -> Result<(), NetworkError> {
+        let now = self.now()?;
         cleanup_peers(
             &self.network_settings,
             &mut self.peers,
             None,
             self.network_settings.ban_timeout,
+            now,
         )?;
+        if !self.ban_list.is_empty() {
+            self.ban_list.retain(|_, ban| !ban.is_expired(now));
+        }
+        if !self.banned_node_ids.is_empty() {
+            let ban_limit = now.saturating_sub(self.network_settings.ban_timeout);
+            self.banned_node_ids
+                .retain(|_, banned_at| *banned_at >= ban_limit);
+        }
+        let peers = &self.peers;
+        self.peer_histories.retain(|ip, _| peers.contains_key(ip));
+        if !self.connection_timings.is_empty() {
+            let groups: HashSet<IpSubnet> = peers.keys().map(network_group).collect();
+            self.connection_timings
+                .retain(|group, _| groups.contains(group));
+        }
         Ok(())
     }
 
-    /// Request peers dump to file
+    /// Account the TCP connect latency of an outgoing connection to `ip`
+    pub fn record_dial_latency(&mut self, ip: &IpAddr, latency: MassaTime) {
+        NETWORK_METRICS
+            .dial_latency_seconds
+            .observe(latency.to_duration().as_secs_f64());
+        let timings = self
+            .connection_timings
+            .entry(network_group(&ip.to_canonical()))
+            .or_insert_with(ConnectionTimings::default);
+        ConnectionTimings::push(&mut timings.dial_latencies, latency);
+    }
+
+    /// Account the duration of a successful handshake with `ip`
+    pub fn record_handshake_duration(
+        &mut self,
+        ip: &IpAddr,
+        is_outgoing: bool,
+        duration: MassaTime,
+    ) {
+        NETWORK_METRICS
+            .handshake_duration_seconds
+            .with_label_values(&[if is_outgoing { "out" } else { "in" }])
+            .observe(duration.to_duration().as_secs_f64());
+        let timings = self
+            .connection_timings
+            .entry(network_group(&ip.to_canonical()))
+            .or_insert_with(ConnectionTimings::default);
+        ConnectionTimings::push(&mut timings.handshake_durations, duration);
+    }
+
+    /// Get the timeout of a connection attempt towards `ip`: the 99th percentile of the dial latencies
+    /// of its network group times `connect_timeout_latency_factor`, clamped between `min_connect_timeout`
+    /// and `connect_timeout`. `connect_timeout` until enough latencies were observed in the group.
+    pub fn get_connect_timeout(&self, ip: &IpAddr) -> MassaTime {
+        let cfg = &self.network_settings;
+        if cfg.connect_timeout_latency_factor <= 0.0 {
+            return cfg.connect_timeout;
+        }
+        let latency = match self.get_connection_timings(ip) {
+            Some(timings)
+                if timings.dial_latencies.len() >= MIN_ADAPTIVE_CONNECT_TIMEOUT_SAMPLES =>
+            {
+                timings.dial_latency_percentile(99)
+            }
+            _ => None,
+        };
+        match latency {
+            Some(latency) => MassaTime::from_millis(
+                (latency.to_millis() as f64 * cfg.connect_timeout_latency_factor) as u64,
+            )
+            .max(cfg.min_connect_timeout)
+            .min(cfg.connect_timeout),
+            None => cfg.connect_timeout,
+        }
+    }
+
+    /// Get the connection timings of the network group of `ip`
+    pub fn get_connection_timings(&self, ip: &IpAddr) -> Option<&ConnectionTimings> {
+        self.connection_timings
+            .get(&network_group(&ip.to_canonical()))
+    }
+
+    /// Add an ended connection to the history of the peer at `ip`,
+    /// forgetting its oldest ones beyond `max_peer_history_length`.
+    pub fn record_connection(&mut self, ip: &IpAddr, event: PeerConnectionEvent) {
+        let max_length = self.network_settings.max_peer_history_length;
+        if max_length == 0 {
+            return;
+        }
+        let history = self
+            .peer_histories
+            .entry(ip.to_canonical())
+            .or_insert_with(VecDeque::new);
+        history.push_back(event);
+        while history.len() > max_length {
+            history.pop_front();
+        }
+    }
+
+    /// Get the last ended connections of the peer at `ip`, oldest first
+    pub fn get_peer_history(&self, ip: &IpAddr) -> Vec<PeerConnectionEvent> {
+        self.peer_histories
+            .get(&ip.to_canonical())
+            .map(|history| history.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Request peers dump to file.
+    /// The snapshot sent to the saver shares the maps, so the cost does not depend on the number of peers.
     fn request_dump(&self) -> Result<(), NetworkError> {
+        if self.saver_join_handle.is_none() {
+            // no saver: the snapshot is only kept for the subscribers of the channel
+            self.saver_watch_tx
+                .send_replace((self.peers.clone(), self.ban_list.clone()));
+            return Ok(());
+        }
         trace!("before sending self.peers.clone() from saver_watch_tx in peer_info_database request_dump");
         let res = self
             .saver_watch_tx
-            .send(self.peers.clone())
+            .send((self.peers.clone(), self.ban_list.clone()))
             .map_err(|_| NetworkError::ChannelError("could not send on saver_watch_tx".into()));
         trace!("before sending self.peers.clone() from saver_watch_tx in peer_info_database request_dump");
         res
     }
 
-    /// Merges `new_peers` with our peers using the `cleanup_peers` function.
+    /// Dump the peers and bans to the peers file right away, whatever the dump interval.
+    /// The saver answers on `response_tx` once the file is written, by the error of the dump if it failed.
+    pub fn flush(&self, response_tx: FlushResponseSender) -> Result<(), NetworkError> {
+        self.request_dump()?;
+        self.saver_flush_tx.send(response_tx).map_err(|_| {
+            NetworkError::ChannelError(
+                "could not send on saver_flush_tx: peers are not dumped".into(),
+            )
+        })
+    }
+
+    /// Read the peers file again and merge it with the peers and bans in memory,
+    /// so that the changes made to the file while the node runs are taken into account:
+    /// * the unknown peers of the file are added, within the limits of the configuration
+    /// * the known peers take the peer type and the addresses of the file, and are advertised if the file says so,
+    ///   their connections and bans being kept
+    /// * the bans of the file are merged with the ban list, the longest ban of a target winning
+    ///
+    /// A dump is requested afterwards. The connections of the newly banned ips are not closed here.
+    pub async fn reload_peers_file(&mut self) -> Result<(), NetworkError> {
+        let PeersFileContent { peers, bans } = PeersFileContent::load(
+            &self.network_settings.peers_file,
+            self.network_settings.peers_file_password.as_deref(),
+        )
+        .await?;
+        for file_peer in peers.into_iter() {
+            let ip = file_peer.ip.to_canonical();
+            let old_pt = match self.peers.get_mut(&ip) {
+                Some(peer) => {
+                    peer.advertised |= file_peer.advertised;
+                    if file_peer.port.is_some() {
+                        peer.port = file_peer.port;
+                        peer.other_addresses = file_peer.other_addresses.clone();
+                    }
+                    let old_pt = peer.peer_type;
+                    peer.peer_type = file_peer.peer_type;
+                    old_pt
+                }
+                None => {
+                    self.peers.insert(ip, PeerInfo { ip, ..file_peer });
+                    continue;
+                }
+            };
+            if old_pt != file_peer.peer_type {
+                self.move_connection_counts(&ip, old_pt, file_peer.peer_type)?;
+            }
+        }
+        self.update()?;
+        self.import_ban_list(BanList { bans })
+    }
+
+    /// Move the active connections of the peer at `ip` from the counts of `old_pt`
+    /// to those of `new_pt`, after a change of its peer type
+    fn move_connection_counts(
+        &mut self,
+        ip: &IpAddr,
+        old_pt: PeerType,
+        new_pt: PeerType,
+    ) -> Result<(), NetworkError> {
+        let peer = self
+            .peers
+            .get(ip)
+            .cloned()
+            .ok_or(NetworkError::PeerConnectionError(
+                NetworkConnectionErrorType::PeerInfoNotFoundError(*ip),
+            ))?;
+        if peer.active_out_connection_attempts > 0 {
+            self.decrease_global_active_out_connection_attempt_count(old_pt, ip)?;
+            self.increase_global_active_out_connection_attempt_count(new_pt, ip)?
+        }
+        if peer.active_out_connections > 0 {
+            self.decrease_global_active_out_connection_count(old_pt, ip)?;
+            self.increase_global_active_out_connection_count(new_pt)?
+        }
+        if peer.active_in_connections > 0 {
+            self.decrease_global_active_in_connection_count(old_pt, ip)?;
+            self.increase_global_active_in_connection_count(new_pt)?
+        }
+        Ok(())
+    }
+
+    /// Merges `new_peers` with our peers using the `cleanup_peers` function,
+    /// once the peers and the other addresses denied by `candidate_peer_filter` are dropped.
     /// A dump is requested afterwards.
     ///
     /// # Argument
-    /// `new_peers`: peers we are trying to merge
-    pub fn merge_candidate_peers(&mut self, new_peers: &[IpAddr]) -> Result<(), NetworkError> {
+    /// `new_peers`: addresses of the peers we are trying to merge
+    pub fn merge_candidate_peers(
+        &mut self,
+        new_peers: &[AdvertisedPeer],
+    ) -> Result<(), NetworkError> {
+        let filter = &self.network_settings.candidate_peer_filter;
+        let filtered_peers: Vec<AdvertisedPeer>;
+        let new_peers = if filter.is_empty() {
+            new_peers
+        } else {
+            filtered_peers = new_peers
+                .iter()
+                .filter(|peer| !filter.denies(&peer.address.ip()))
+                .map(|peer| AdvertisedPeer {
+                    address: peer.address,
+                    other_addresses: peer
+                        .other_addresses
+                        .iter()
+                        .filter(|addr| !filter.denies(&addr.ip()))
+                        .copied()
+                        .collect(),
+                })
+                .collect();
+            &filtered_peers
+        };
         if new_peers.is_empty() {
             return Ok(());
         }
+        let now = self.now()?;
         cleanup_peers(
             &self.network_settings,
             &mut self.peers,
-            Some(&new_peers.to_vec()),
+            Some(new_peers),
             self.network_settings.ban_timeout,
+            now,
         )?;
         self.request_dump()
     }
 
+    /// Current time, read from the clock of the database
+    pub(crate) fn now(&self) -> Result<MassaTime, NetworkError> {
+        Ok(self.clock.now()?)
+    }
+
+    /// Append a ban or an unban of `target` to the ban audit log, if the bans are logged
+    fn audit_ban(
+        &self,
+        action: BanAuditAction,
+        target: BanAuditTarget,
+        reason: Option<String>,
+        expiry: Option<MassaTime>,
+    ) -> Result<(), NetworkError> {
+        if let Some(ban_audit_log) = &self.ban_audit_log {
+            ban_audit_log.append(BanAuditRecord {
+                time: self.now()?,
+                action,
+                target,
+                reason,
+                expiry,
+            });
+        }
+        Ok(())
+    }
+
     ////////////////////////////////
     // high level peer management //
     ////////////////////////////////
 
-    /// Unban a list of ip
+    /// Unban a list of ip, lifting the bans of the ban list targeting them as well.
+    /// The bans of the subnets they belong to are kept.
     pub fn unban(&mut self, ips: Vec<IpAddr>) -> Result<(), NetworkError> {
         let mut update_happened = false;
         for ip in ips.into_iter() {
             let ip = ip.to_canonical();
+            let mut unbanned = self.ban_list.remove(&IpSubnet::from(ip)).is_some();
             if let Some(peer) = self.peers.get_mut(&ip) {
-                update_happened = update_happened || peer.banned;
+                unbanned |= peer.banned;
                 peer.banned = false;
+                peer.ban_reason = None;
+                peer.banned_at = None;
             }
+            if unbanned {
+                self.audit_ban(
+                    BanAuditAction::Unban,
+                    BanAuditTarget::Subnet(IpSubnet::from(ip)),
+                    None,
+                    None,
+                )?;
+            }
+            update_happened |= unbanned;
         }
         self.update()?;
         if update_happened {
@@ -347,6 +947,146 @@ impl PeerInfoDatabase {
         Ok(())
     }
 
+    /// Ban node ids for `reason`: the peers proving them in their handshake are refused, whatever their ip
+    pub fn ban_node_ids(&mut self, ids: &[NodeId], reason: BanReason) -> Result<(), NetworkError> {
+        let now = self.now()?;
+        for id in ids {
+            self.banned_node_ids.insert(*id, now);
+            self.audit_ban(
+                BanAuditAction::Ban,
+                BanAuditTarget::Node(*id),
+                Some(reason.to_string()),
+                Some(now.saturating_add(self.network_settings.ban_timeout)),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Lift the bans of node ids
+    pub fn unban_node_ids(&mut self, ids: &[NodeId]) -> Result<(), NetworkError> {
+        for id in ids {
+            if self.banned_node_ids.remove(id).is_some() {
+                self.audit_ban(BanAuditAction::Unban, BanAuditTarget::Node(*id), None, None)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// True if the node id is banned
+    pub fn is_node_banned(&self, id: &NodeId) -> bool {
+        self.banned_node_ids.contains_key(id)
+    }
+
+    /// Get the ips of the known peers that proved `id` in their last handshake
+    pub fn get_node_ips(&self, id: &NodeId) -> Vec<IpAddr> {
+        self.peers
+            .values()
+            .filter(|peer| peer.node_id.as_ref() == Some(id))
+            .map(|peer| peer.ip)
+            .collect()
+    }
+
+    /// Unban a list of subnets: the bans of the ban list targeting them or smaller subnets
+    /// and the bans of the peers they contain are lifted.
+    pub fn unban_subnets(&mut self, subnets: Vec<IpSubnet>) -> Result<(), NetworkError> {
+        let unbanned_targets: Vec<IpSubnet> = self
+            .ban_list
+            .keys()
+            .filter(|target| {
+                subnets.iter().any(|subnet| {
+                    target.prefix_length() >= subnet.prefix_length()
+                        && subnet.contains(&target.ip())
+                })
+            })
+            .copied()
+            .collect();
+        for target in unbanned_targets.iter() {
+            self.ban_list.remove(target);
+            self.audit_ban(
+                BanAuditAction::Unban,
+                BanAuditTarget::Subnet(*target),
+                None,
+                None,
+            )?;
+        }
+        let banned_ips: Vec<IpAddr> = self
+            .peers
+            .values()
+            .filter(|p| p.banned && subnets.iter().any(|subnet| subnet.contains(&p.ip)))
+            .map(|p| p.ip)
+            .collect();
+        self.unban(banned_ips)?;
+        if !unbanned_targets.is_empty() {
+            self.request_dump()?;
+        }
+        Ok(())
+    }
+
+    /// Merge bans into the ban list: the bans of a target already banned are merged
+    /// (the longest ban wins), the expired ones are ignored and those without a time of ban are banned now.
+    /// A dump is requested. The connections of the newly banned ips are not closed here.
+    pub fn import_ban_list(&mut self, ban_list: BanList) -> Result<(), NetworkError> {
+        let now = self.now()?;
+        for mut ban in ban_list.bans.into_iter() {
+            if ban.is_expired(now) {
+                continue;
+            }
+            if ban.target.is_single_ip() {
+                ban.target = IpSubnet::from(ban.target.ip().to_canonical());
+            }
+            ban.banned_at.get_or_insert(now);
+            self.audit_ban(
+                BanAuditAction::Ban,
+                BanAuditTarget::Subnet(ban.target),
+                ban.reason.clone(),
+                ban.expiry,
+            )?;
+            match self.ban_list.get_mut(&ban.target) {
+                Some(existing) => existing.merge(ban),
+                None => {
+                    self.ban_list.insert(ban.target, ban);
+                }
+            }
+        }
+        self.request_dump()
+    }
+
+    /// Get the bans of the database, sorted by target: the banned peers, whose ban is lifted
+    /// `ban_timeout` after their last failure, merged with the imported bans.
+    pub fn get_ban_list(&self) -> BanList {
+        let mut bans = self.ban_list.clone();
+        for peer in self.peers.values().filter(|p| p.banned) {
+            let ban = BanListEntry {
+                target: IpSubnet::from(peer.ip),
+                reason: peer.ban_reason.clone(),
+                expiry: peer.last_failure.map(|last_failure| {
+                    last_failure.saturating_add(self.network_settings.ban_timeout)
+                }),
+                banned_at: peer.banned_at,
+            };
+            match bans.get_mut(&ban.target) {
+                Some(existing) => existing.merge(ban),
+                None => {
+                    bans.insert(ban.target, ban);
+                }
+            }
+        }
+        let mut bans: Vec<BanListEntry> = bans.into_iter().map(|(_, ban)| ban).collect();
+        bans.sort_unstable_by_key(|ban| ban.target);
+        BanList { bans }
+    }
+
+    /// True if `ip` is banned, as a peer or by an imported ban of its address or subnet
+    pub fn is_banned(&self, ip: &IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        self.peers.get(&ip).map_or(false, |p| p.banned) || self.is_in_ban_list(&ip)
+    }
+
+    /// True if `ip` is covered by an imported ban
+    fn is_in_ban_list(&self, ip: &IpAddr) -> bool {
+        self.ban_list.keys().any(|target| target.contains(ip))
+    }
+
     pub async fn whitelist(&mut self, ips: Vec<IpAddr>) -> Result<(), NetworkError> {
         for ip in ips.into_iter() {
             let ip = ip.to_canonical();
@@ -364,22 +1104,7 @@ impl PeerInfoDatabase {
                 continue;
             };
             // update global connection counts by peer type
-            let peer = *self.peers.get(&ip).unwrap(); // filled just before
-            if peer.active_out_connection_attempts > 0 {
-                self.decrease_global_active_out_connection_attempt_count(old_pt, &ip)?;
-                self.increase_global_active_out_connection_attempt_count(
-                    PeerType::WhiteListed,
-                    &ip,
-                )?
-            }
-            if peer.active_out_connections > 0 {
-                self.decrease_global_active_out_connection_count(old_pt, &ip)?;
-                self.increase_global_active_out_connection_count(PeerType::WhiteListed)?
-            }
-            if peer.active_in_connections > 0 {
-                self.decrease_global_active_in_connection_count(old_pt, &ip)?;
-                self.increase_global_active_in_connection_count(PeerType::WhiteListed)?
-            }
+            self.move_connection_counts(&ip, old_pt, PeerType::WhiteListed)?;
         }
         self.update()
     }
@@ -398,22 +1123,7 @@ impl PeerInfoDatabase {
             if old_pt != Default::default() {
                 // update global connection counts by peer type
                 // as the peer isn't whitelist anymore
-                let peer = *self.peers.get(&ip).unwrap(); // filled just before
-                if peer.active_out_connection_attempts > 0 {
-                    self.decrease_global_active_out_connection_attempt_count(old_pt, &ip)?;
-                    self.increase_global_active_out_connection_attempt_count(
-                        Default::default(),
-                        &ip,
-                    )?
-                }
-                if peer.active_out_connections > 0 {
-                    self.decrease_global_active_out_connection_count(old_pt, &ip)?;
-                    self.increase_global_active_out_connection_count(Default::default())?
-                }
-                if peer.active_in_connections > 0 {
-                    self.decrease_global_active_in_connection_count(old_pt, &ip)?;
-                    self.increase_global_active_in_connection_count(Default::default())?
-                }
+                self.move_connection_counts(&ip, old_pt, Default::default())?;
             }
         }
         self.update()
@@ -428,6 +1138,12 @@ impl PeerInfoDatabase {
         if !ip.is_global() {
             return Err(NetworkError::InvalidIpError(ip));
         }
+        let now = self.now()?;
+        if self.dial_budget.available(&self.network_settings, now) == 0 {
+            return Err(NetworkError::PeerConnectionError(
+                NetworkConnectionErrorType::TooManyConnectionAttempts(ip),
+            ));
+        }
         let peer_type = if let Some(peer) = self.peers.get(&ip) {
             if self.can_try_new_out_connection(peer.peer_type) {
                 // Can unwrap because we checked above that there is a peer.
@@ -442,32 +1158,41 @@ impl PeerInfoDatabase {
         } else if self.can_try_new_out_connection(Default::default()) {
             let mut peer = PeerInfo::new(ip, false);
             peer.active_out_connection_attempts += 1;
+            let peer_type = peer.peer_type;
             self.peers.insert(ip, peer);
-            Ok(peer.peer_type)
+            Ok(peer_type)
         } else {
             Err(NetworkError::PeerConnectionError(
                 NetworkConnectionErrorType::TooManyConnectionAttempts(ip),
             ))
         }?;
+        self.dial_budget.spend(&self.network_settings, now);
         self.increase_global_active_out_connection_attempt_count(peer_type, &ip)?;
         self.update()
     }
 
-    /// Sets the peer status as alive.
+    /// Sets the peer status as alive, and records the node id it proved
+    /// and the version it announced in its handshake.
     /// Requests a subsequent dump.
     ///
     /// # Argument
     /// * ip : ip address of the considered peer.
-    pub fn peer_alive(&mut self, ip: &IpAddr) -> Result<(), NetworkError> {
+    /// * `node_id`: node id of the peer
+    /// * `version`: software version of the peer
+    pub fn peer_alive(
+        &mut self,
+        ip: &IpAddr,
+        node_id: NodeId,
+        version: Version,
+    ) -> Result<(), NetworkError> {
         let ip = ip.to_canonical();
-        self.peers
-            .get_mut(&ip)
-            .ok_or_else(|| {
-                NetworkError::PeerConnectionError(
-                    NetworkConnectionErrorType::PeerInfoNotFoundError(ip),
-                )
-            })?
-            .last_alive = Some(MassaTime::now()?);
+        let now = self.now()?;
+        let peer = self.peers.get_mut(&ip).ok_or_else(|| {
+            NetworkError::PeerConnectionError(NetworkConnectionErrorType::PeerInfoNotFoundError(ip))
+        })?;
+        peer.last_alive = Some(now);
+        peer.node_id = Some(node_id);
+        peer.version = Some(version);
         self.request_dump()
     }
 
@@ -478,6 +1203,7 @@ impl PeerInfoDatabase {
     /// * ip : ip address of the considered peer.
     pub fn peer_failed(&mut self, ip: &IpAddr) -> Result<(), NetworkError> {
         let ip = ip.to_canonical();
+        let now = self.now()?;
         self.peers
             .get_mut(&ip)
             .ok_or_else(|| {
@@ -485,23 +1211,49 @@ impl PeerInfoDatabase {
                     NetworkConnectionErrorType::PeerInfoNotFoundError(ip),
                 )
             })?
-            .last_failure = Some(MassaTime::now()?);
+            .last_failure = Some(now);
         self.request_dump()
     }
 
-    /// Sets that the peer is banned now.
+    /// Applies a feedback of the protocol to the reputation of the peer,
+    /// bounded by `MAX_PEER_REPUTATION` in absolute value.
+    /// The feedback on unknown peers is ignored. The reputation isn't dumped, so no dump is requested.
+    ///
+    /// # Argument
+    /// * ip : ip address of the considered peer.
+    /// * feedback : observation of the protocol on the blocks of the peer.
+    pub fn peer_feedback(&mut self, ip: &IpAddr, feedback: PeerFeedback) {
+        if let Some(peer) = self.peers.get_mut(&ip.to_canonical()) {
+            peer.reputation = peer
+                .reputation
+                .saturating_add(feedback.reputation_delta())
+                .clamp(-MAX_PEER_REPUTATION, MAX_PEER_REPUTATION);
+        }
+    }
+
+    /// Sets that the peer is banned now, for `reason`.
     /// If the peer is not active, the database is cleaned up.
     /// A dump is requested.
     ///
     /// # Argument
     /// * ip : ip address of the considered peer.
-    pub fn peer_banned(&mut self, ip: &IpAddr) -> Result<(), NetworkError> {
+    /// * reason : why the peer is banned.
+    pub fn peer_banned(&mut self, ip: &IpAddr, reason: BanReason) -> Result<(), NetworkError> {
         let ip = ip.to_canonical();
+        let now = self.now()?;
+        self.audit_ban(
+            BanAuditAction::Ban,
+            BanAuditTarget::Subnet(IpSubnet::from(ip)),
+            Some(reason.to_string()),
+            Some(now.saturating_add(self.network_settings.ban_timeout)),
+        )?;
         let peer = self
             .peers
             .entry(ip)
             .or_insert_with(|| PeerInfo::new(ip, false));
-        peer.last_failure = Some(MassaTime::now()?);
+        peer.last_failure = Some(now);
+        peer.ban_reason = Some(reason.to_string());
+        peer.banned_at = Some(now);
         if !peer.banned {
             peer.banned = true;
             if !peer.is_active() {
@@ -614,19 +1366,18 @@ impl PeerInfoDatabase {
             return Ok(false);
         }
 
+        // check the attempt of the peer before the global count is decreased
+        if self.peers[&ip].active_out_connection_attempts == 0 {
+            return Err(NetworkError::PeerConnectionError(
+                NetworkConnectionErrorType::TooManyConnectionAttempts(ip),
+            ));
+        }
+
         self.decrease_global_active_out_connection_attempt_count(peer_type, &ip)?;
 
+        let in_ban_list = self.is_in_ban_list(&ip);
+        let now = self.now()?;
         let peer_type = {
-            let peer = self.peers.get(&ip).ok_or_else(|| {
-                NetworkError::PeerConnectionError(
-                    NetworkConnectionErrorType::PeerInfoNotFoundError(ip),
-                )
-            })?;
-            if peer.active_out_connection_attempts == 0 {
-                return Err(NetworkError::PeerConnectionError(
-                    NetworkConnectionErrorType::TooManyConnectionAttempts(ip),
-                ));
-            }
             let peer = self.peers.get_mut(&ip).ok_or_else(|| {
                 NetworkError::PeerConnectionError(
                     NetworkConnectionErrorType::PeerInfoNotFoundError(ip),
@@ -635,8 +1386,8 @@ impl PeerInfoDatabase {
             peer.active_out_connection_attempts -= 1;
             peer.advertised = true; // we just connected to it. Assume advertised.
 
-            if peer.banned {
-                peer.last_failure = Some(MassaTime::now()?);
+            if peer.banned || in_ban_list {
+                peer.last_failure = Some(now);
                 if !peer.is_active() && peer.peer_type == Default::default() {
                     self.update()?;
                 }
@@ -659,6 +1410,7 @@ impl PeerInfoDatabase {
     /// * ip : ip address of the considered peer.
     pub fn out_connection_attempt_failed(&mut self, ip: &IpAddr) -> Result<(), NetworkError> {
         let ip = ip.to_canonical();
+        let now = self.now()?;
         let peer_type = {
             let peer = self.peers.get(&ip).ok_or_else(|| {
                 NetworkError::PeerConnectionError(
@@ -678,7 +1430,7 @@ impl PeerInfoDatabase {
                 )
             })?;
             peer.active_out_connection_attempts -= 1;
-            peer.last_failure = Some(MassaTime::now()?);
+            peer.last_failure = Some(now);
             let pt = peer.peer_type;
             if !peer.is_active() && peer.peer_type == PeerType::Standard {
                 self.update()?;
@@ -728,6 +1480,8 @@ impl PeerInfoDatabase {
             ));
         }
 
+        let in_ban_list = self.is_in_ban_list(&ip);
+        let now = self.now()?;
         let peer_type = {
             let peer = self.peers.get_mut(&ip).ok_or_else(|| {
                 NetworkError::PeerConnectionError(
@@ -736,9 +1490,9 @@ impl PeerInfoDatabase {
             })?; // peer was inserted just before
 
             // is there a attempt slot available
-            if peer.banned {
+            if peer.banned || in_ban_list {
                 massa_trace!("in_connection_refused_peer_banned", {"ip": peer.ip});
-                peer.last_failure = Some(MassaTime::now()?);
+                peer.last_failure = Some(now);
                 self.request_dump()?;
                 return Err(NetworkError::PeerConnectionError(
                     NetworkConnectionErrorType::BannedPeerTryingToConnect(ip),
@@ -764,23 +1518,49 @@ impl PeerInfoDatabase {
     // public getters //
     ////////////////////
 
-    /// Sorts peers by `( last_failure, rev(last_success) )`
-    /// and returns as many peers as there are available slots to attempt outgoing connections to.
+    /// Sorts peers by `( rev(reputation), last_failure, rev(last_success) )`
+    /// and returns, for each peer type, as many peers as there are available slots to attempt outgoing connections to.
+    /// The peers of the highest peer types are returned first, within the dial budget of all the peer types.
+    ///
+    /// The peers are scanned once for all types, and only the selected ones are sorted.
     pub fn get_out_connection_candidate_ips(&self) -> Result<Vec<IpAddr>, NetworkError> {
-        let mut connections = vec![];
-        let mut peer_types: Vec<PeerType> = self
-            .peer_types_connection_count
-            .iter()
-            .map(|(peer_type, _)| peer_type)
-            .collect();
+        let available_slots: EnumMap<PeerType, usize> = enum_map! {
+            peer_type => self.get_available_out_connection_attempts_for_peer_type(peer_type)
+        };
+        if available_slots.values().all(|&slots| slots == 0) {
+            return Ok(Vec::new());
+        }
+        let now = self.now()?;
+        let budget = self.dial_budget.available(&self.network_settings, now);
+        if budget == 0 {
+            return Ok(Vec::new());
+        }
+        let mut candidates: EnumMap<PeerType, Vec<&PeerInfo>> = EnumMap::default();
+        for p in self.peers.values() {
+            if available_slots[p.peer_type] == 0
+                || !p.advertised
+                || p.is_active()
+                || p.banned
+                || self.is_in_ban_list(&p.ip)
+                || !p.is_peer_ready(self.wakeup_interval, now)
+            {
+                continue;
+            }
+            candidates[p.peer_type].push(p);
+        }
+        let mut peer_types: Vec<PeerType> =
+            candidates.iter().map(|(peer_type, _)| peer_type).collect();
         peer_types.sort_by_key(|&peer_type| Reverse(peer_type));
-        for &peer_type in peer_types.iter() {
-            connections.append(&mut self.get_out_connection_candidate_ips_for_type(
-                peer_type,
-                &self.peer_types_connection_count[peer_type],
-                &self.network_settings.peer_types_config[peer_type],
-            )?);
+        let mut connections = vec![];
+        for peer_type in peer_types {
+            let peers = &mut candidates[peer_type];
+            let key =
+                |p: &&PeerInfo| (Reverse(p.reputation), p.last_failure, Reverse(p.last_alive));
+            retain_smallest_by_key(peers, available_slots[peer_type], key);
+            peers.sort_unstable_by_key(key);
+            connections.extend(peers.iter().map(|p| p.ip));
         }
+        connections.truncate(budget);
         Ok(connections)
     }
 
@@ -789,25 +1569,63 @@ impl PeerInfoDatabase {
         &self.peers
     }
 
-    /// Returns a vector of advertisable `IpAddr` sorted by `( last_failure, rev(last_success) )`
-    pub fn get_advertisable_peer_ips(&self) -> Vec<IpAddr> {
-        let mut sorted_peers: Vec<PeerInfo> = self
+    /// Returns the addresses of the advertisable peers sorted by `( last_failure, rev(last_success) )`,
+    /// without the peers and the other addresses denied by `advertised_peer_filter`.
+    /// The peers whose port is unknown are advertised with our `protocol_port`.
+    /// If our node is routable, it comes first with `other_routable_addresses`.
+    pub fn get_advertisable_peers(&self) -> Vec<AdvertisedPeer> {
+        let filter = &self.network_settings.advertised_peer_filter;
+        let mut sorted_peers: Vec<&PeerInfo> = self
             .peers
             .values()
-            .filter(|&p| (p.advertised && !p.banned))
-            .copied()
+            .filter(|&p| (p.advertised && !p.banned && !self.is_in_ban_list(&p.ip)))
+            .filter(|p| !filter.denies(&p.ip))
             .collect();
-        sorted_peers.sort_unstable_by_key(|&p| (std::cmp::Reverse(p.last_alive), p.last_failure));
-        let mut sorted_ips: Vec<IpAddr> = sorted_peers
+        let key = |p: &&PeerInfo| (Reverse(p.last_alive), p.last_failure);
+        retain_smallest_by_key(
+            &mut sorted_peers,
+            self.network_settings.max_peer_advertise_length as usize,
+            key,
+        );
+        sorted_peers.sort_unstable_by_key(key);
+        let protocol_port = self.network_settings.protocol_port;
+        let mut sorted_peers: Vec<AdvertisedPeer> = sorted_peers
             .into_iter()
-            .take(self.network_settings.max_peer_advertise_length as usize)
-            .map(|p| p.ip)
+            .map(|p| {
+                let mut peer = p.get_advertised_peer(protocol_port);
+                peer.other_addresses
+                    .retain(|addr| !filter.denies(&addr.ip()));
+                peer
+            })
             .collect();
         if let Some(our_ip) = self.network_settings.routable_ip {
-            sorted_ips.insert(0, our_ip.to_canonical());
-            sorted_ips.truncate(self.network_settings.max_peer_advertise_length as usize);
+            let our_peer = AdvertisedPeer {
+                address: SocketAddr::new(our_ip.to_canonical(), protocol_port),
+                other_addresses: self
+                    .network_settings
+                    .other_routable_addresses
+                    .iter()
+                    .take(MAX_ADVERTISED_PEER_ADDRESSES.saturating_sub(1) as usize)
+                    .copied()
+                    .collect(),
+            };
+            sorted_peers.insert(0, our_peer);
+            sorted_peers.truncate(self.network_settings.max_peer_advertise_length as usize);
+        }
+        sorted_peers
+    }
+
+    /// Addresses to dial the peer at `ip`, by order of preference: its main address, on our `protocol_port`
+    /// if its port is unknown, then its other advertised addresses
+    pub fn get_dial_addresses(&self, ip: &IpAddr) -> Vec<SocketAddr> {
+        let ip = ip.to_canonical();
+        let protocol_port = self.network_settings.protocol_port;
+        match self.peers.get(&ip) {
+            Some(peer) => std::iter::once(peer.get_address(protocol_port))
+                .chain(peer.other_addresses.iter().copied())
+                .collect(),
+            None => vec![SocketAddr::new(ip, protocol_port)],
         }
-        sorted_ips
     }
 
     //////////////////////////////
@@ -830,38 +1648,6 @@ impl PeerInfoDatabase {
             >= self.network_settings.peer_types_config[peer_type].max_in_connections
     }
 
-    /// Get ips we want to connect to for a given peer type
-    ///
-    /// # Arguments
-    /// * `peer_type`: which type to consider
-    /// * `count`: what is the current connection count for that type
-    /// * `cfg`: settings for that peer type
-    ///
-    /// Returns an iterator
-    fn get_out_connection_candidate_ips_for_type(
-        &self,
-        peer_type: PeerType,
-        count: &ConnectionCount,
-        cfg: &PeerTypeConnectionConfig,
-    ) -> Result<Vec<IpAddr>, NetworkError> {
-        let available_slots = count.get_available_out_connection_attempts(cfg);
-        let now = MassaTime::now()?;
-        let f = move |p: &&PeerInfo| {
-            if p.peer_type != peer_type || !p.advertised || p.is_active() || p.banned {
-                return false;
-            }
-            p.is_peer_ready(self.wakeup_interval, now)
-        };
-        let mut res: Vec<_> = self
-            .peers
-            .values()
-            .filter(f)
-            .take(available_slots)
-            .collect();
-        res.sort_unstable_by_key(|&p| (p.last_failure, std::cmp::Reverse(p.last_alive)));
-        Ok(res.into_iter().map(|p| p.ip).collect())
-    }
-
     fn get_peer_type(&self, ip: &IpAddr) -> Option<PeerType> {
         Some(self.peers.get(ip)?.peer_type)
     }
@@ -975,3 +1761,61 @@ impl PeerInfoDatabase {
         self.peer_types_connection_count[peer_type].active_in_connections
     }
 }
+
+/// Consistency checks of the database, used by the tests after each change
+#[cfg(test)]
+impl PeerInfoDatabase {
+    /// Get the peers having connections or connection attempts
+    pub(crate) fn get_active_peers(&self) -> HashMap<IpAddr, PeerInfo> {
+        self.peers
+            .iter()
+            .filter(|(_, p)| p.is_active())
+            .map(|(ip, p)| (*ip, p.clone()))
+            .collect()
+    }
+
+    /// Check that the connection counts by peer type are the sums of the counts of the peers of that type
+    /// and that they are within the limits of the configuration.
+    /// Returns a description of the first broken invariant.
+    pub(crate) fn check_invariants(&self) -> Result<(), String> {
+        let mut sums: EnumMap<PeerType, ConnectionCount> = EnumMap::default();
+        for (ip, peer) in self.peers.iter() {
+            if *ip != peer.ip {
+                return Err(format!("peer {} is stored under ip {}", peer.ip, ip));
+            }
+            if peer.active_in_connections > self.network_settings.max_in_connections_per_ip {
+                return Err(format!(
+                    "peer {} has {} in connections, more than max_in_connections_per_ip",
+                    ip, peer.active_in_connections
+                ));
+            }
+            let sum = &mut sums[peer.peer_type];
+            sum.active_out_connection_attempts += peer.active_out_connection_attempts;
+            sum.active_out_connections += peer.active_out_connections;
+            sum.active_in_connections += peer.active_in_connections;
+        }
+        for (peer_type, count) in self.peer_types_connection_count.iter() {
+            let sum = &sums[peer_type];
+            if count.active_out_connection_attempts != sum.active_out_connection_attempts
+                || count.active_out_connections != sum.active_out_connections
+                || count.active_in_connections != sum.active_in_connections
+            {
+                return Err(format!(
+                    "{} connection count {:?} differs from the sum of the counts of its peers {:?}",
+                    peer_type, count, sum
+                ));
+            }
+            let cfg = &self.network_settings.peer_types_config[peer_type];
+            if count.active_out_connection_attempts > cfg.max_out_attempts
+                || count.active_out_connections > cfg.target_out_connections
+                || count.active_in_connections > cfg.max_in_connections
+            {
+                return Err(format!(
+                    "{} connection count {:?} exceeds the limits {:?}",
+                    peer_type, count, cfg
+                ));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,624 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Formats of the peers file.
+//!
+//! The peers file is written in the `peers_file_format` of the configuration, and the format of a file
+//! is detected when it is read, so that changing the format does not lose the known peers:
+//! * JSON: object `{"peers": [...], "bans": [...]}`, or array of peers as in the initial peers file
+//!   and the peers files of previous versions
+//! * CSV: one line per peer and per ban, the `record` column telling which one it is
+//! * binary: compact encoding starting with `BINARY_PEERS_FILE_MAGIC` and the version of the encoding
+//!
+//! The peers files written before the peers had a port are read as well, their peers having no known port,
+//! as are those written before the peers had other addresses and before the bans had a reason and a time.
+//!
+//! When a `peers_file_password` is set, the peers file is encrypted with `massa_cipher`: the encrypted file
+//! starts with `ENCRYPTED_PEERS_FILE_MAGIC`, followed by the encrypted content in `peers_file_format`.
+//! Clear peers files are still read, and encrypted when they are next written.
+
+use itertools::Itertools;
+use massa_models::ban_list::{BanListEntry, IpSubnet};
+use massa_models::config::MAX_ADVERTISED_PEER_ADDRESSES;
+use massa_models::serialization::{
+    IpAddrDeserializer, IpAddrSerializer, SocketAddrDeserializer, SocketAddrSerializer,
+    StringDeserializer, StringSerializer,
+};
+use massa_network_exports::settings::PeersFileFormat;
+use massa_network_exports::{NetworkError, PeerInfo, PeerType};
+use massa_serialization::{
+    DeserializeError, Deserializer, OptionDeserializer, OptionSerializer, SerializeError,
+    Serializer, U16VarIntDeserializer, U16VarIntSerializer, U32VarIntDeserializer,
+    U32VarIntSerializer,
+};
+use massa_time::{MassaTime, MassaTimeDeserializer, MassaTimeSerializer};
+use nom::{
+    bytes::complete::tag,
+    combinator::verify,
+    error::{context, ContextError, ParseError},
+    multi::length_count,
+    number::complete::u8 as parse_u8,
+    sequence::{preceded, tuple},
+    IResult, Parser,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::net::SocketAddr;
+use std::ops::Bound::Included;
+use std::path::Path;
+use std::str::FromStr;
+
+/// First bytes of a binary peers file, followed by the version of the encoding
+const BINARY_PEERS_FILE_MAGIC: &[u8] = b"MASSA_PEERS";
+/// First bytes of an encrypted peers file, followed by the peers file encrypted by `massa_cipher`
+pub(crate) const ENCRYPTED_PEERS_FILE_MAGIC: &[u8] = b"MASSA_ENCRYPTED_PEERS";
+/// Version of the encoding of the binary peers files written,
+/// the version 1 having no peer ports, the version 2 no other peer addresses
+/// and the version 3 neither ban reasons of the peers nor ban times
+const BINARY_PEERS_FILE_VERSION: u8 = 4;
+
+/// Peers and bans of ip addresses and subnets, as saved in a peers file
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PeersFileContent {
+    pub peers: Vec<PeerInfo>,
+    pub bans: Vec<BanListEntry>,
+}
+
+impl PeersFileContent {
+    /// Read a peers file whatever its format, cleaning its peers up.
+    /// An encrypted file is decrypted with `password`, a clear file is read whatever `password`.
+    pub async fn load(path: &Path, password: Option<&str>) -> Result<Self, NetworkError> {
+        let mut data = tokio::fs::read(path).await?;
+        if let Some(encrypted) = data.strip_prefix(ENCRYPTED_PEERS_FILE_MAGIC) {
+            let password = password
+                .ok_or_else(|| {
+                    NetworkError::PeersFileError(format!(
+                        "{} is encrypted but no peers file password is set",
+                        path.display()
+                    ))
+                })?
+                .to_string();
+            let encrypted = encrypted.to_vec();
+            // the derivation of the key is costly: it does not block the runtime
+            data =
+                tokio::task::spawn_blocking(move || massa_cipher::decrypt(&password, &encrypted))
+                    .await?
+                    .map_err(|err| {
+                        NetworkError::PeersFileError(format!(
+                            "could not decrypt {}: {}",
+                            path.display(),
+                            err
+                        ))
+                    })?
+                    .1;
+        }
+        let mut content = Self::from_bytes(&data)?;
+        content.peers.iter_mut().for_each(PeerInfo::cleanup);
+        Ok(content)
+    }
+
+    /// Write the peers file in `format`, encrypted with `password` if any
+    pub async fn dump(
+        &self,
+        path: &Path,
+        format: PeersFileFormat,
+        password: Option<&str>,
+    ) -> Result<(), NetworkError> {
+        let mut data = self.to_bytes(format)?;
+        if let Some(password) = password {
+            let password = password.to_string();
+            let encrypted =
+                tokio::task::spawn_blocking(move || massa_cipher::encrypt(&password, &data))
+                    .await?
+                    .map_err(|err| NetworkError::PeersFileError(err.to_string()))?;
+            data = ENCRYPTED_PEERS_FILE_MAGIC.to_vec();
+            data.extend(encrypted);
+        }
+        tokio::fs::write(path, data).await?;
+        Ok(())
+    }
+
+    /// Decode a peers file, detecting its format
+    pub fn from_bytes(data: &[u8]) -> Result<Self, NetworkError> {
+        if data.starts_with(BINARY_PEERS_FILE_MAGIC) {
+            let (rest, content) = PeersFileDeserializer::new()
+                .deserialize::<DeserializeError>(data)
+                .map_err(|err| NetworkError::PeersFileError(err.to_string()))?;
+            if !rest.is_empty() {
+                return Err(NetworkError::PeersFileError(
+                    "trailing bytes after the bans".into(),
+                ));
+            }
+            return Ok(content);
+        }
+        match data.iter().find(|byte| !byte.is_ascii_whitespace()) {
+            Some(b'{') | Some(b'[') => Ok(serde_json::from_slice::<JsonPeersFile>(data)?.into()),
+            _ => Self::from_csv(data),
+        }
+    }
+
+    /// Encode a peers file in `format`
+    pub fn to_bytes(&self, format: PeersFileFormat) -> Result<Vec<u8>, NetworkError> {
+        match format {
+            PeersFileFormat::Json => {
+                // the connection counts are not saved
+                let peers: Vec<_> = self
+                    .peers
+                    .iter()
+                    .map(|peer| {
+                        json!({
+                            "ip": peer.ip,
+                            "port": peer.port,
+                            "other_addresses": peer.other_addresses,
+                            "banned": peer.banned,
+                            "ban_reason": peer.ban_reason,
+                            "banned_at": peer.banned_at,
+                            "peer_type": peer.peer_type,
+                            "last_alive": peer.last_alive,
+                            "last_failure": peer.last_failure,
+                            "advertised": peer.advertised,
+                        })
+                    })
+                    .collect();
+                Ok(serde_json::to_vec_pretty(&json!({
+                    "peers": peers,
+                    "bans": self.bans,
+                }))?)
+            }
+            PeersFileFormat::Csv => self.to_csv(),
+            PeersFileFormat::Binary => {
+                let mut buffer = Vec::new();
+                PeersFileSerializer::new().serialize(self, &mut buffer)?;
+                Ok(buffer)
+            }
+        }
+    }
+
+    fn to_csv(&self) -> Result<Vec<u8>, NetworkError> {
+        let records = self
+            .peers
+            .iter()
+            .map(CsvRecord::from)
+            .chain(self.bans.iter().map(CsvRecord::from));
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        for record in records {
+            writer
+                .serialize(record)
+                .map_err(|err| NetworkError::PeersFileError(err.to_string()))?;
+        }
+        writer
+            .into_inner()
+            .map_err(|err| NetworkError::PeersFileError(err.to_string()))
+    }
+
+    fn from_csv(data: &[u8]) -> Result<Self, NetworkError> {
+        let mut content = PeersFileContent::default();
+        let mut reader = csv::Reader::from_reader(data);
+        for (index, record) in reader.deserialize::<CsvRecord>().enumerate() {
+            let record = record.map_err(|err| NetworkError::PeersFileError(err.to_string()))?;
+            record.add_to(&mut content).map_err(|err| {
+                NetworkError::PeersFileError(format!("record {}: {}", index + 1, err))
+            })?;
+        }
+        Ok(content)
+    }
+}
+
+/// Content of a JSON peers file
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonPeersFile {
+    /// peers and bans of ip addresses and subnets, as dumped by the node
+    PeersAndBans {
+        peers: Vec<PeerInfo>,
+        #[serde(default)]
+        bans: Vec<BanListEntry>,
+    },
+    /// peers only, as in the initial peers file and the peers files of previous versions
+    Peers(Vec<PeerInfo>),
+}
+
+impl From<JsonPeersFile> for PeersFileContent {
+    fn from(file: JsonPeersFile) -> Self {
+        match file {
+            JsonPeersFile::PeersAndBans { peers, bans } => PeersFileContent { peers, bans },
+            JsonPeersFile::Peers(peers) => PeersFileContent {
+                peers,
+                bans: Vec::new(),
+            },
+        }
+    }
+}
+
+/// Kind of a line of a CSV peers file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum CsvRecordKind {
+    Peer,
+    Ban,
+}
+
+/// Line of a CSV peers file, the columns that do not apply to its kind being empty
+#[derive(Debug, Serialize, Deserialize)]
+struct CsvRecord {
+    record: CsvRecordKind,
+    /// ip of the peer, or banned ip address or subnet
+    target: IpSubnet,
+    /// protocol port of the peer, missing from the files of previous versions
+    #[serde(default)]
+    port: Option<u16>,
+    /// other addresses of the peer separated by spaces, missing from the files of previous versions
+    #[serde(default)]
+    other_addresses: Option<String>,
+    peer_type: Option<PeerType>,
+    last_alive: Option<MassaTime>,
+    last_failure: Option<MassaTime>,
+    advertised: Option<bool>,
+    banned: Option<bool>,
+    /// why the peer or the target was banned
+    reason: Option<String>,
+    expiry: Option<MassaTime>,
+    /// time of the ban of the peer or the target, missing from the files of previous versions
+    #[serde(default)]
+    banned_at: Option<MassaTime>,
+}
+
+impl From<&PeerInfo> for CsvRecord {
+    fn from(peer: &PeerInfo) -> Self {
+        CsvRecord {
+            record: CsvRecordKind::Peer,
+            target: IpSubnet::from(peer.ip),
+            port: peer.port,
+            other_addresses: (!peer.other_addresses.is_empty())
+                .then(|| peer.other_addresses.iter().join(" ")),
+            peer_type: Some(peer.peer_type),
+            last_alive: peer.last_alive,
+            last_failure: peer.last_failure,
+            advertised: Some(peer.advertised),
+            banned: Some(peer.banned),
+            reason: peer.ban_reason.clone(),
+            expiry: None,
+            banned_at: peer.banned_at,
+        }
+    }
+}
+
+impl From<&BanListEntry> for CsvRecord {
+    fn from(ban: &BanListEntry) -> Self {
+        CsvRecord {
+            record: CsvRecordKind::Ban,
+            target: ban.target,
+            port: None,
+            other_addresses: None,
+            peer_type: None,
+            last_alive: None,
+            last_failure: None,
+            advertised: None,
+            banned: None,
+            reason: ban.reason.clone(),
+            expiry: ban.expiry,
+            banned_at: ban.banned_at,
+        }
+    }
+}
+
+impl CsvRecord {
+    /// Add the peer or ban of the line to `content`
+    fn add_to(self, content: &mut PeersFileContent) -> Result<(), String> {
+        match self.record {
+            CsvRecordKind::Peer => {
+                if !self.target.is_single_ip() {
+                    return Err(format!("peer ip {} is a subnet", self.target));
+                }
+                let other_addresses = self
+                    .other_addresses
+                    .as_deref()
+                    .unwrap_or_default()
+                    .split_whitespace()
+                    .map(|addr| {
+                        SocketAddr::from_str(addr)
+                            .map_err(|err| format!("invalid peer address {}: {}", addr, err))
+                    })
+                    .collect::<Result<_, _>>()?;
+                content.peers.push(PeerInfo {
+                    ip: self.target.ip(),
+                    port: self.port,
+                    other_addresses,
+                    peer_type: self.peer_type.ok_or("missing peer type")?,
+                    last_alive: self.last_alive,
+                    last_failure: self.last_failure,
+                    advertised: self.advertised.unwrap_or_default(),
+                    banned: self.banned.unwrap_or_default(),
+                    ban_reason: self.reason,
+                    banned_at: self.banned_at,
+                    node_id: None,
+                    version: None,
+                    reputation: 0,
+                    active_out_connection_attempts: 0,
+                    active_out_connections: 0,
+                    active_in_connections: 0,
+                });
+            }
+            CsvRecordKind::Ban => content.bans.push(BanListEntry {
+                target: self.target,
+                reason: self.reason,
+                expiry: self.expiry,
+                banned_at: self.banned_at,
+            }),
+        }
+        Ok(())
+    }
+}
+
+/// Identifiers of the peer types in a binary peers file
+fn peer_type_id(peer_type: PeerType) -> u32 {
+    match peer_type {
+        PeerType::Standard => 0,
+        PeerType::WhiteListed => 1,
+        PeerType::Bootstrap => 2,
+    }
+}
+
+/// Serializer for binary peers files
+struct PeersFileSerializer {
+    u32_serializer: U32VarIntSerializer,
+    ip_addr_serializer: IpAddrSerializer,
+    port_serializer: OptionSerializer<u16, U16VarIntSerializer>,
+    socket_addr_serializer: SocketAddrSerializer,
+    time_serializer: OptionSerializer<MassaTime, MassaTimeSerializer>,
+    reason_serializer: OptionSerializer<String, StringSerializer<U32VarIntSerializer, u32>>,
+}
+
+impl PeersFileSerializer {
+    /// Creates a new `PeersFileSerializer`
+    fn new() -> Self {
+        Self {
+            u32_serializer: U32VarIntSerializer::new(),
+            ip_addr_serializer: IpAddrSerializer::new(),
+            port_serializer: OptionSerializer::new(U16VarIntSerializer::new()),
+            socket_addr_serializer: SocketAddrSerializer::new(),
+            time_serializer: OptionSerializer::new(MassaTimeSerializer::new()),
+            reason_serializer: OptionSerializer::new(StringSerializer::new(
+                U32VarIntSerializer::new(),
+            )),
+        }
+    }
+
+    fn serialize_count(&self, count: usize, buffer: &mut Vec<u8>) -> Result<(), SerializeError> {
+        let count: u32 = count.try_into().map_err(|err| {
+            SerializeError::NumberTooBig(format!("too many entries in peers file: {}", err))
+        })?;
+        self.u32_serializer.serialize(&count, buffer)
+    }
+}
+
+impl Serializer<PeersFileContent> for PeersFileSerializer {
+    fn serialize(
+        &self,
+        value: &PeersFileContent,
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), SerializeError> {
+        buffer.extend(BINARY_PEERS_FILE_MAGIC);
+        buffer.push(BINARY_PEERS_FILE_VERSION);
+        self.serialize_count(value.peers.len(), buffer)?;
+        for peer in value.peers.iter() {
+            self.ip_addr_serializer.serialize(&peer.ip, buffer)?;
+            self.port_serializer.serialize(&peer.port, buffer)?;
+            self.serialize_count(peer.other_addresses.len(), buffer)?;
+            for address in peer.other_addresses.iter() {
+                self.socket_addr_serializer.serialize(address, buffer)?;
+            }
+            self.u32_serializer
+                .serialize(&peer_type_id(peer.peer_type), buffer)?;
+            self.time_serializer.serialize(&peer.last_alive, buffer)?;
+            self.time_serializer.serialize(&peer.last_failure, buffer)?;
+            buffer.push(u8::from(peer.advertised) | (u8::from(peer.banned) << 1));
+            self.reason_serializer.serialize(&peer.ban_reason, buffer)?;
+            self.time_serializer.serialize(&peer.banned_at, buffer)?;
+        }
+        self.serialize_count(value.bans.len(), buffer)?;
+        for ban in value.bans.iter() {
+            self.ip_addr_serializer
+                .serialize(&ban.target.ip(), buffer)?;
+            buffer.push(ban.target.prefix_length());
+            self.reason_serializer.serialize(&ban.reason, buffer)?;
+            self.time_serializer.serialize(&ban.expiry, buffer)?;
+            self.time_serializer.serialize(&ban.banned_at, buffer)?;
+        }
+        Ok(())
+    }
+}
+
+/// Deserializer for binary peers files
+struct PeersFileDeserializer {
+    count_deserializer: U32VarIntDeserializer,
+    peer_type_deserializer: U32VarIntDeserializer,
+    ip_addr_deserializer: IpAddrDeserializer,
+    port_deserializer: OptionDeserializer<u16, U16VarIntDeserializer>,
+    addresses_count_deserializer: U32VarIntDeserializer,
+    socket_addr_deserializer: SocketAddrDeserializer,
+    time_deserializer: OptionDeserializer<MassaTime, MassaTimeDeserializer>,
+    reason_deserializer: OptionDeserializer<String, StringDeserializer<U32VarIntDeserializer, u32>>,
+}
+
+impl PeersFileDeserializer {
+    /// Creates a new `PeersFileDeserializer`
+    fn new() -> Self {
+        Self {
+            count_deserializer: U32VarIntDeserializer::new(Included(0), Included(u32::MAX)),
+            peer_type_deserializer: U32VarIntDeserializer::new(
+                Included(0),
+                Included(peer_type_id(PeerType::Bootstrap)),
+            ),
+            ip_addr_deserializer: IpAddrDeserializer::new(),
+            port_deserializer: OptionDeserializer::new(U16VarIntDeserializer::new(
+                Included(0),
+                Included(u16::MAX),
+            )),
+            addresses_count_deserializer: U32VarIntDeserializer::new(
+                Included(0),
+                Included(MAX_ADVERTISED_PEER_ADDRESSES),
+            ),
+            socket_addr_deserializer: SocketAddrDeserializer::new(),
+            time_deserializer: OptionDeserializer::new(MassaTimeDeserializer::new((
+                Included(MassaTime::from_millis(0)),
+                Included(MassaTime::from_millis(u64::MAX)),
+            ))),
+            reason_deserializer: OptionDeserializer::new(StringDeserializer::new(
+                U32VarIntDeserializer::new(Included(0), Included(u32::MAX)),
+            )),
+        }
+    }
+
+    /// Deserialize a peer encoded in `version`, the port being missing before the version 2,
+    /// the other addresses before the version 3 and the ban reason and time before the version 4
+    fn deserialize_peer<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+        &self,
+        version: u8,
+        buffer: &'a [u8],
+    ) -> IResult<&'a [u8], PeerInfo, E> {
+        context(
+            "Failed peer deserialization",
+            tuple((
+                |input| self.ip_addr_deserializer.deserialize(input),
+                |input| {
+                    if version >= 2 {
+                        self.port_deserializer.deserialize(input)
+                    } else {
+                        Ok((input, None))
+                    }
+                },
+                |input| {
+                    if version >= 3 {
+                        length_count(
+                            |input| self.addresses_count_deserializer.deserialize(input),
+                            |input| self.socket_addr_deserializer.deserialize(input),
+                        )(input)
+                    } else {
+                        Ok((input, Vec::new()))
+                    }
+                },
+                |input| self.peer_type_deserializer.deserialize(input),
+                |input| self.time_deserializer.deserialize(input),
+                |input| self.time_deserializer.deserialize(input),
+                parse_u8,
+                |input| {
+                    if version >= 4 {
+                        tuple((
+                            |input| self.reason_deserializer.deserialize(input),
+                            |input| self.time_deserializer.deserialize(input),
+                        ))(input)
+                    } else {
+                        Ok((input, (None, None)))
+                    }
+                },
+            )),
+        )
+        .map(
+            |(
+                ip,
+                port,
+                other_addresses,
+                peer_type,
+                last_alive,
+                last_failure,
+                flags,
+                (ban_reason, banned_at),
+            )| PeerInfo {
+                ip,
+                port,
+                other_addresses,
+                peer_type: match peer_type {
+                    0 => PeerType::Standard,
+                    1 => PeerType::WhiteListed,
+                    _ => PeerType::Bootstrap,
+                },
+                last_alive,
+                last_failure,
+                advertised: flags & 1 != 0,
+                banned: flags & (1 << 1) != 0,
+                ban_reason,
+                banned_at,
+                node_id: None,
+                version: None,
+                reputation: 0,
+                active_out_connection_attempts: 0,
+                active_out_connections: 0,
+                active_in_connections: 0,
+            },
+        )
+        .parse(buffer)
+    }
+
+    /// Deserialize a ban encoded in `version`, the time of ban being missing before the version 4
+    fn deserialize_ban<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+        &self,
+        version: u8,
+        buffer: &'a [u8],
+    ) -> IResult<&'a [u8], BanListEntry, E> {
+        let (rest, (ip, prefix_length, reason, expiry, banned_at)) = context(
+            "Failed ban deserialization",
+            tuple((
+                |input| self.ip_addr_deserializer.deserialize(input),
+                parse_u8,
+                |input| self.reason_deserializer.deserialize(input),
+                |input| self.time_deserializer.deserialize(input),
+                |input| {
+                    if version >= 4 {
+                        self.time_deserializer.deserialize(input)
+                    } else {
+                        Ok((input, None))
+                    }
+                },
+            )),
+        )
+        .parse(buffer)?;
+        let target = IpSubnet::new(ip, prefix_length).map_err(|_| {
+            nom::Err::Error(ParseError::from_error_kind(
+                buffer,
+                nom::error::ErrorKind::Verify,
+            ))
+        })?;
+        Ok((
+            rest,
+            BanListEntry {
+                target,
+                reason,
+                expiry,
+                banned_at,
+            },
+        ))
+    }
+}
+
+impl Deserializer<PeersFileContent> for PeersFileDeserializer {
+    fn deserialize<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+        &self,
+        buffer: &'a [u8],
+    ) -> IResult<&'a [u8], PeersFileContent, E> {
+        let (rest, version) = context(
+            "Failed PeersFileContent version deserialization",
+            preceded(
+                tag(BINARY_PEERS_FILE_MAGIC),
+                verify(parse_u8, |version| {
+                    (1..=BINARY_PEERS_FILE_VERSION).contains(version)
+                }),
+            ),
+        )
+        .parse(buffer)?;
+        context(
+            "Failed PeersFileContent deserialization",
+            tuple((
+                length_count(
+                    |input| self.count_deserializer.deserialize(input),
+                    |input| self.deserialize_peer(version, input),
+                ),
+                length_count(
+                    |input| self.count_deserializer.deserialize(input),
+                    |input| self.deserialize_ban(version, input),
+                ),
+            )),
+        )
+        .map(|(peers, bans)| PeersFileContent { peers, bans })
+        .parse(rest)
+    }
+}
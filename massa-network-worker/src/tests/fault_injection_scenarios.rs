@@ -0,0 +1,121 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use crate::fault_injection::{FaultInjector, FaultyWriter};
+use crate::messages::Message;
+use massa_models::{error::ModelsError, network_faults::MessageFaults, node::NodeId};
+use massa_network_exports::NetworkError;
+use massa_signature::KeyPair;
+use massa_time::MassaTime;
+use std::time::Duration;
+
+fn messages(count: usize) -> Vec<Message> {
+    (0..count)
+        .map(|index| {
+            if index % 2 == 0 {
+                Message::AskPeerList
+            } else {
+                Message::Disconnect
+            }
+        })
+        .collect()
+}
+
+fn random_node_id() -> NodeId {
+    NodeId::new(KeyPair::generate().get_public_key())
+}
+
+#[test]
+fn test_faulty_writer_drops_and_duplicates() {
+    let mut writer = FaultyWriter::new();
+
+    let (sent, delay) = writer.apply(&MessageFaults::default(), messages(4));
+    assert_eq!(sent.len(), 4);
+    assert_eq!(delay, Duration::ZERO);
+
+    let drop_all = MessageFaults {
+        drop_ratio: 1.0,
+        ..Default::default()
+    };
+    assert!(writer.apply(&drop_all, messages(4)).0.is_empty());
+
+    let duplicate_all = MessageFaults {
+        duplicate_ratio: 1.0,
+        ..Default::default()
+    };
+    let (sent, _) = writer.apply(&duplicate_all, messages(2));
+    assert_eq!(sent.len(), 4);
+    assert!(matches!(sent[0], Message::AskPeerList));
+    assert!(matches!(sent[1], Message::AskPeerList));
+    assert!(matches!(sent[2], Message::Disconnect));
+    assert!(matches!(sent[3], Message::Disconnect));
+}
+
+#[test]
+fn test_faulty_writer_reorders_and_delays() {
+    let mut writer = FaultyWriter::new();
+    let reorder_all = MessageFaults {
+        reorder_ratio: 1.0,
+        min_delay: MassaTime::from_millis(10),
+        max_delay: MassaTime::from_millis(20),
+        ..Default::default()
+    };
+
+    // the first message is held back and sent after the second one
+    let (sent, delay) = writer.apply(&reorder_all, messages(2));
+    assert_eq!(sent.len(), 2);
+    assert!(matches!(sent[0], Message::Disconnect));
+    assert!(matches!(sent[1], Message::AskPeerList));
+    assert!(delay >= Duration::from_millis(10) && delay <= Duration::from_millis(20));
+
+    // a message held back at the end of a batch is sent with the next batch
+    let (sent, _) = writer.apply(&reorder_all, messages(1));
+    assert!(sent.is_empty());
+    let (sent, _) = writer.apply(&MessageFaults::default(), messages(1));
+    assert_eq!(sent.len(), 2);
+}
+
+#[test]
+fn test_fault_injector_targets() {
+    let injector = FaultInjector::default();
+    let node_a = random_node_id();
+    let node_b = random_node_id();
+    assert!(injector.get(&node_a).is_none());
+
+    let all_faults = MessageFaults {
+        drop_ratio: 0.5,
+        ..Default::default()
+    };
+    let node_a_faults = MessageFaults {
+        duplicate_ratio: 0.5,
+        ..Default::default()
+    };
+    injector.set(None, Some(all_faults.clone())).unwrap();
+    let network_faults = injector
+        .set(Some(node_a), Some(node_a_faults.clone()))
+        .unwrap();
+    assert_eq!(network_faults.per_peer.len(), 1);
+    assert_eq!(injector.get(&node_a), Some(node_a_faults));
+    assert_eq!(injector.get(&node_b), Some(all_faults.clone()));
+
+    // invalid faults are refused and the previous ones are kept
+    let invalid = MessageFaults {
+        reorder_ratio: 1.5,
+        ..Default::default()
+    };
+    assert!(matches!(
+        injector.set(None, Some(invalid)),
+        Err(NetworkError::ModelsError(
+            ModelsError::InvalidNetworkFaults(_)
+        ))
+    ));
+    assert_eq!(injector.get(&node_b), Some(all_faults.clone()));
+
+    // removing the faults of a peer makes it fall back to the faults of all the peers
+    injector.set(Some(node_a), None).unwrap();
+    assert_eq!(injector.get(&node_a), Some(all_faults));
+
+    // removing the faults of all the peers clears everything
+    let network_faults = injector.set(None, None).unwrap();
+    assert_eq!(network_faults, Default::default());
+    assert!(injector.get(&node_a).is_none());
+}
@@ -1,8 +1,18 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
+#[cfg(all(test, feature = "fault_injection"))]
+mod fault_injection_scenarios;
 #[cfg(test)]
 mod scenarios;
 #[cfg(test)]
+mod test_codec;
+#[cfg(test)]
+mod test_connections;
+#[cfg(test)]
 mod test_peer_info_database;
 #[cfg(test)]
+mod test_peer_info_database_invariants;
+#[cfg(test)]
+mod test_peers_file;
+#[cfg(test)]
 pub mod tools;
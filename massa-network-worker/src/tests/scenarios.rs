@@ -2,6 +2,7 @@
 
 // To start alone RUST_BACKTRACE=1 cargo test -- --nocapture --test-threads=1
 use super::tools;
+use crate::handshake_worker::{HandshakeReturnType, HandshakeWorker};
 use crate::messages::{Message, MessageDeserializer};
 use crate::node_worker::NodeWorker;
 use crate::tests::tools::{get_dummy_block_id, get_transaction};
@@ -15,7 +16,7 @@ use enum_map::enum_map;
 use enum_map::EnumMap;
 use massa_hash::Hash;
 use massa_models::config::{
-    ENDORSEMENT_COUNT, MAX_ADVERTISE_LENGTH, MAX_ASK_BLOCKS_PER_MESSAGE,
+    ENDORSEMENT_COUNT, MAX_ADVERTISE_LENGTH, MAX_ASK_BLOCKS_PER_MESSAGE, MAX_BLOCK_RANGE_LENGTH,
     MAX_DATASTORE_VALUE_LENGTH, MAX_ENDORSEMENTS_PER_MESSAGE, MAX_FUNCTION_NAME_LENGTH,
     MAX_MESSAGE_SIZE, MAX_OPERATIONS_PER_BLOCK, MAX_OPERATIONS_PER_MESSAGE,
     MAX_OPERATION_DATASTORE_ENTRY_COUNT, MAX_OPERATION_DATASTORE_KEY_LENGTH,
@@ -25,23 +26,30 @@ use massa_models::{
     block::BlockId,
     endorsement::{Endorsement, EndorsementSerializer},
     node::NodeId,
+    operation::{OperationId, OperationPrefixIds},
     slot::Slot,
+    version::Version,
     wrapped::WrappedContent,
 };
-use massa_network_exports::{settings::PeerTypeConnectionConfig, NodeCommand, NodeEvent};
 use massa_network_exports::{
-    AskForBlocksInfo, BlockInfoReply, ConnectionClosureReason, ConnectionId, HandshakeErrorType,
-    PeerInfo, PeerType,
+    backpressure_channel,
+    settings::PeerTypeConnectionConfig,
+    test_exports::{mock_establisher::MockEstablisherInterface, tools::get_test_network_id},
+    BackpressureReceiver, BackpressureSender, NodeCommand, NodeEvent,
+};
+use massa_network_exports::{
+    AskForBlocksInfo, BanReason, BlockInfoReply, ConnectionClosureReason, ConnectionId,
+    HandshakeErrorType, PeerInfo, PeerType,
 };
 use massa_signature::KeyPair;
-use massa_time::MassaTime;
+use massa_time::{Clock, MassaTime, MockClock};
 use serial_test::serial;
 use std::collections::HashMap;
+use std::str::FromStr;
 use std::{
     net::{IpAddr, Ipv4Addr, SocketAddr},
     time::{Duration, Instant},
 };
-use tokio::sync::mpsc;
 use tokio::time::sleep;
 use tracing::trace;
 
@@ -84,6 +92,7 @@ async fn test_node_worker_shutdown() {
             ENDORSEMENT_COUNT,
             MAX_ADVERTISE_LENGTH,
             MAX_ASK_BLOCKS_PER_MESSAGE,
+            MAX_BLOCK_RANGE_LENGTH,
             MAX_OPERATIONS_PER_BLOCK,
             MAX_OPERATIONS_PER_MESSAGE,
             MAX_ENDORSEMENTS_PER_MESSAGE,
@@ -95,11 +104,12 @@ async fn test_node_worker_shutdown() {
             MAX_OPERATION_DATASTORE_VALUE_LENGTH,
         ),
     );
-    let writer = WriteBinder::new(duplex_mock_write, f64::INFINITY, MAX_MESSAGE_SIZE);
+    let writer = WriteBinder::new(duplex_mock_write, f64::INFINITY, MAX_MESSAGE_SIZE, false);
 
     // Note: both channels have size 1.
-    let (node_command_tx, node_command_rx) = mpsc::channel::<NodeCommand>(1);
-    let (node_event_tx, _node_event_rx) = mpsc::channel::<NodeEvent>(1);
+    let (node_command_tx, node_command_rx) =
+        backpressure_channel::<NodeCommand>("node_commands", 1);
+    let (node_event_tx, _node_event_rx) = backpressure_channel::<NodeEvent>("node_events", 1);
 
     let keypair = KeyPair::generate();
     let mock_node_id = NodeId::new(keypair.get_public_key());
@@ -114,6 +124,7 @@ async fn test_node_worker_shutdown() {
             node_worker_command_tx,
             node_command_rx,
             node_event_tx,
+            Default::default(),
         )
         .run_loop()
         .await
@@ -158,6 +169,7 @@ async fn test_node_worker_operations_message() {
             ENDORSEMENT_COUNT,
             MAX_ADVERTISE_LENGTH,
             MAX_ASK_BLOCKS_PER_MESSAGE,
+            MAX_BLOCK_RANGE_LENGTH,
             MAX_OPERATIONS_PER_BLOCK,
             MAX_OPERATIONS_PER_MESSAGE,
             MAX_ENDORSEMENTS_PER_MESSAGE,
@@ -169,11 +181,12 @@ async fn test_node_worker_operations_message() {
             MAX_OPERATION_DATASTORE_VALUE_LENGTH,
         ),
     );
-    let writer = WriteBinder::new(duplex_mock_write, f64::INFINITY, MAX_MESSAGE_SIZE);
+    let writer = WriteBinder::new(duplex_mock_write, f64::INFINITY, MAX_MESSAGE_SIZE, false);
 
     // Note: both channels have size 1.
-    let (node_command_tx, node_command_rx) = mpsc::channel::<NodeCommand>(1);
-    let (node_event_tx, _node_event_rx) = mpsc::channel::<NodeEvent>(1);
+    let (node_command_tx, node_command_rx) =
+        backpressure_channel::<NodeCommand>("node_commands", 1);
+    let (node_event_tx, _node_event_rx) = backpressure_channel::<NodeEvent>("node_events", 1);
 
     let keypair = KeyPair::generate();
     let mock_node_id = NodeId::new(keypair.get_public_key());
@@ -191,6 +204,7 @@ async fn test_node_worker_operations_message() {
             node_worker_command_tx,
             node_command_rx,
             node_event_tx,
+            Default::default(),
         )
         .run_loop()
         .await
@@ -221,6 +235,219 @@ async fn test_node_worker_operations_message() {
     node_fn_handle.await.unwrap().unwrap();
 }
 
+/// Create a reader of the messages sent through `read_half`
+fn test_read_binder(read_half: tokio::io::ReadHalf<tokio::io::DuplexStream>) -> ReadBinder {
+    ReadBinder::new(
+        read_half,
+        f64::INFINITY,
+        MAX_MESSAGE_SIZE,
+        MessageDeserializer::new(
+            THREAD_COUNT,
+            ENDORSEMENT_COUNT,
+            MAX_ADVERTISE_LENGTH,
+            MAX_ASK_BLOCKS_PER_MESSAGE,
+            MAX_BLOCK_RANGE_LENGTH,
+            MAX_OPERATIONS_PER_BLOCK,
+            MAX_OPERATIONS_PER_MESSAGE,
+            MAX_ENDORSEMENTS_PER_MESSAGE,
+            MAX_DATASTORE_VALUE_LENGTH,
+            MAX_FUNCTION_NAME_LENGTH,
+            MAX_PARAMETERS_SIZE,
+            MAX_OPERATION_DATASTORE_ENTRY_COUNT,
+            MAX_OPERATION_DATASTORE_KEY_LENGTH,
+            MAX_OPERATION_DATASTORE_VALUE_LENGTH,
+        ),
+    )
+}
+
+/// Node worker connected to a mock node
+struct MockNodeConnection {
+    /// commands of the node worker
+    node_command_tx: BackpressureSender<NodeCommand>,
+    /// handle of the node worker
+    node_fn_handle: tokio::task::JoinHandle<Result<ConnectionClosureReason, NetworkError>>,
+    /// reader of the messages received by the mock node
+    mock_reader: ReadBinder,
+    /// writer of the mock node, kept open for the connection to last
+    _mock_writer: tokio::io::WriteHalf<tokio::io::DuplexStream>,
+    _node_event_rx: BackpressureReceiver<NodeEvent>,
+}
+
+impl MockNodeConnection {
+    /// Close the connection and wait for the node worker to stop
+    async fn close(self) {
+        self.node_command_tx
+            .send(NodeCommand::Close(ConnectionClosureReason::Normal))
+            .await
+            .unwrap();
+        self.node_fn_handle.await.unwrap().unwrap();
+    }
+}
+
+/// Start a node worker from `network_conf`, connected to a mock node
+fn start_node_worker_with_mock_node(network_conf: NetworkConfig) -> MockNodeConnection {
+    let (duplex_controller, duplex_mock) = tokio::io::duplex(1_000_000);
+    let (duplex_controller_read, duplex_controller_write) = tokio::io::split(duplex_controller);
+    let (duplex_mock_read, duplex_mock_write) = tokio::io::split(duplex_mock);
+    let reader = test_read_binder(duplex_controller_read);
+    let writer = WriteBinder::new(
+        duplex_controller_write,
+        f64::INFINITY,
+        MAX_MESSAGE_SIZE,
+        false,
+    );
+
+    let (node_command_tx, node_command_rx) =
+        backpressure_channel::<NodeCommand>("node_commands", 10);
+    let (node_event_tx, node_event_rx) = backpressure_channel::<NodeEvent>("node_events", 10);
+    let mock_node_id = NodeId::new(KeyPair::generate().get_public_key());
+
+    let node_worker_command_tx = node_command_tx.clone();
+    let node_fn_handle = tokio::spawn(async move {
+        NodeWorker::new(
+            network_conf,
+            mock_node_id,
+            reader,
+            writer,
+            node_worker_command_tx,
+            node_command_rx,
+            node_event_tx,
+            Default::default(),
+        )
+        .run_loop()
+        .await
+    });
+    MockNodeConnection {
+        node_command_tx,
+        node_fn_handle,
+        mock_reader: test_read_binder(duplex_mock_read),
+        _mock_writer: duplex_mock_write,
+        _node_event_rx: node_event_rx,
+    }
+}
+
+/// Next message received by the mock node, the peer list requests aside
+async fn next_mock_node_message(mock_reader: &mut ReadBinder) -> Message {
+    loop {
+        match mock_reader.next().await.unwrap() {
+            Some((_, Message::AskPeerList)) => continue,
+            Some((_, message)) => return message,
+            None => panic!("the connection to the mock node was closed"),
+        }
+    }
+}
+
+/// Operation prefix ids of `count` distinct operations
+fn get_operation_prefix_ids(count: usize) -> OperationPrefixIds {
+    (0..count)
+        .map(|index| {
+            OperationId::new(Hash::compute_from(
+                format!("operation {}", index).as_bytes(),
+            ))
+            .prefix()
+        })
+        .collect()
+}
+
+/// Test that the operation announcements are split in messages of at most `max_operations_per_message` ids,
+/// kept until `write_coalescing_delay` is over, then written at once.
+#[tokio::test]
+#[serial]
+async fn test_node_worker_coalesces_operation_announcements() {
+    let temp_peers_file = super::tools::generate_peers_file(&[]);
+    let network_conf = NetworkConfig {
+        write_coalescing_delay: MassaTime::from_millis(300),
+        max_operations_per_message: 2,
+        ..NetworkConfig::scenarios_default(50_000, temp_peers_file.path())
+    };
+    let mut connection = start_node_worker_with_mock_node(network_conf);
+
+    let announced = get_operation_prefix_ids(5);
+    let sent_at = Instant::now();
+    connection
+        .node_command_tx
+        .send(NodeCommand::SendOperationAnnouncements(announced.clone()))
+        .await
+        .unwrap();
+
+    // nothing is written before the coalescing delay
+    assert!(
+        tokio::time::timeout(
+            Duration::from_millis(100),
+            next_mock_node_message(&mut connection.mock_reader)
+        )
+        .await
+        .is_err(),
+        "the announcements were written before the coalescing delay"
+    );
+
+    // then every message is received
+    let mut received = OperationPrefixIds::default();
+    let mut message_sizes = Vec::new();
+    for _ in 0..3 {
+        match next_mock_node_message(&mut connection.mock_reader).await {
+            Message::OperationsAnnouncement(operation_prefix_ids) => {
+                message_sizes.push(operation_prefix_ids.len());
+                received.extend(operation_prefix_ids);
+            }
+            message => panic!("unexpected message {:?}", message),
+        }
+    }
+    assert!(sent_at.elapsed() >= Duration::from_millis(300));
+    assert_eq!(message_sizes, vec![2, 2, 1]);
+    assert_eq!(received, announced);
+
+    connection.close().await;
+}
+
+/// Test that the buffered operation announcements are written along with a block request,
+/// without waiting for the coalescing delay.
+#[tokio::test]
+#[serial]
+async fn test_node_worker_flushes_announcements_with_urgent_message() {
+    let temp_peers_file = super::tools::generate_peers_file(&[]);
+    let network_conf = NetworkConfig {
+        write_coalescing_delay: MassaTime::from_millis(10_000),
+        ..NetworkConfig::scenarios_default(50_000, temp_peers_file.path())
+    };
+    let mut connection = start_node_worker_with_mock_node(network_conf);
+
+    let announced = get_operation_prefix_ids(3);
+    let block_id = get_dummy_block_id("block");
+    connection
+        .node_command_tx
+        .send(NodeCommand::SendOperationAnnouncements(announced.clone()))
+        .await
+        .unwrap();
+    connection
+        .node_command_tx
+        .send(NodeCommand::AskForBlocks(vec![(
+            block_id,
+            AskForBlocksInfo::Info,
+        )]))
+        .await
+        .unwrap();
+
+    let messages = tokio::time::timeout(Duration::from_millis(1_000), async {
+        (
+            next_mock_node_message(&mut connection.mock_reader).await,
+            next_mock_node_message(&mut connection.mock_reader).await,
+        )
+    })
+    .await
+    .expect("the messages were kept until the coalescing delay");
+    match messages {
+        (Message::OperationsAnnouncement(operation_prefix_ids), Message::AskForBlocks(asked)) => {
+            assert_eq!(operation_prefix_ids, announced);
+            assert_eq!(asked.len(), 1);
+            assert_eq!(asked[0].0, block_id);
+        }
+        messages => panic!("unexpected messages {:?}", messages),
+    }
+
+    connection.close().await;
+}
+
 // test connecting two different peers simultaneously to the controller
 // then attempt to connect to controller from an already connected peer to test max_in_connections_per_ip
 // then try to connect a third peer to test max_in_connection
@@ -399,7 +626,7 @@ async fn test_peer_ban() {
 
             // ban connection1.
             network_command_sender
-                .node_ban_by_ids(vec![conn1_id])
+                .node_ban_by_ids(vec![conn1_id], BanReason::Manual)
                 .await
                 .expect("error during send ban command.");
 
@@ -534,7 +761,7 @@ async fn test_peer_ban_by_ip() {
 
             // ban connection1.
             network_command_sender
-                .node_ban_by_ips(vec![mock_addr.ip()])
+                .node_ban_by_ips(vec![mock_addr.ip()], BanReason::Manual)
                 .await
                 .expect("error during send ban command.");
 
@@ -624,6 +851,8 @@ async fn test_advertised_and_wakeup_interval() {
     let mock_ignore_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(169, 202, 0, 13)), bind_port);
     let temp_peers_file = super::tools::generate_peers_file(&[PeerInfo {
         ip: mock_ignore_addr.ip(),
+        port: None,
+        other_addresses: Vec::new(),
         peer_type: PeerType::Bootstrap,
         last_alive: None,
         last_failure: None,
@@ -632,6 +861,11 @@ async fn test_advertised_and_wakeup_interval() {
         active_out_connections: 0,
         active_in_connections: 0,
         banned: false,
+        ban_reason: None,
+        banned_at: None,
+        node_id: None,
+        version: None,
+        reputation: 0,
     }]);
     let network_conf = NetworkConfig {
         wakeup_interval: MassaTime::from_millis(500),
@@ -658,7 +892,7 @@ async fn test_advertised_and_wakeup_interval() {
                     ConnectionId(0),
                 )
                 .await;
-                tools::advertise_peers_in_connection(&mut conn2_w, vec![mock_addr.ip()]).await;
+                tools::advertise_peers_in_connection(&mut conn2_w, vec![mock_addr.into()]).await;
                 // drop the connection
                 drop(conn2_r);
                 drop(conn2_w);
@@ -722,7 +956,7 @@ async fn test_advertised_and_wakeup_interval() {
                 &mut network_event_receiver,
                 1000.into(),
                 |msg| match msg {
-                    NetworkEvent::NewConnection(_) => Some(()),
+                    NetworkEvent::NewConnection { .. } => Some(()),
                     _ => None,
                 },
             )
@@ -742,6 +976,96 @@ async fn test_advertised_and_wakeup_interval() {
     .await;
 }
 
+// test that the connection retries follow the clock of the network:
+//   refuse the connection attempt from controller towards an advertised peer
+//   then check that no new attempt is made while the clock does not move,
+//   nor before the clock reaches the next wakeup
+//   then check that the controller retries once the clock passed the wakeup interval
+#[tokio::test]
+#[serial]
+async fn test_wakeup_interval_follows_clock() {
+    let bind_port: u16 = 50_000;
+    let mock_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(169, 202, 0, 12)), bind_port);
+    let temp_peers_file = super::tools::generate_peers_file(&[PeerInfo {
+        ip: mock_addr.ip(),
+        port: None,
+        other_addresses: Vec::new(),
+        peer_type: PeerType::Standard,
+        last_alive: None,
+        last_failure: None,
+        advertised: true,
+        active_out_connection_attempts: 0,
+        active_out_connections: 0,
+        active_in_connections: 0,
+        banned: false,
+        ban_reason: None,
+        banned_at: None,
+        node_id: None,
+        version: None,
+        reputation: 0,
+    }]);
+    let network_conf = NetworkConfig {
+        wakeup_interval: MassaTime::from_millis(60_000),
+        ..NetworkConfig::scenarios_default(bind_port, temp_peers_file.path())
+    };
+    let clock = MockClock::new(MassaTime::now().unwrap());
+
+    tools::network_test_with_clock(
+        network_conf.clone(),
+        temp_peers_file,
+        clock.clone_box(),
+        async move |_network_command_sender,
+                    network_event_receiver,
+                    network_manager,
+                    mut mock_interface| {
+            // refuse the first connection attempt
+            let (_, _, addr, accept_tx) = tokio::time::timeout(
+                Duration::from_millis(1000),
+                mock_interface.wait_connection_attempt_from_controller(),
+            )
+            .await
+            .expect("wait_connection_attempt_from_controller timed out")
+            .expect("wait_connection_attempt_from_controller failed");
+            assert_eq!(addr, mock_addr, "unexpected connection attempt address");
+            accept_tx.send(false).expect("accept_tx failed");
+
+            // no retry while the clock does not reach the wakeup interval
+            for duration in [0, network_conf.wakeup_interval.to_millis() / 2] {
+                clock.advance(MassaTime::from_millis(duration));
+                assert!(
+                    tokio::time::timeout(
+                        Duration::from_millis(500),
+                        mock_interface.wait_connection_attempt_from_controller(),
+                    )
+                    .await
+                    .is_err(),
+                    "controller retried before the wakeup interval"
+                );
+            }
+
+            // retry once the clock passed the wakeup interval
+            clock.advance(network_conf.wakeup_interval);
+            let (_, _, addr, accept_tx) = tokio::time::timeout(
+                Duration::from_millis(1000),
+                mock_interface.wait_connection_attempt_from_controller(),
+            )
+            .await
+            .expect("controller did not retry after the wakeup interval")
+            .expect("wait_connection_attempt_from_controller failed");
+            assert_eq!(addr, mock_addr, "unexpected connection attempt address");
+            accept_tx.send(false).expect("accept_tx failed");
+
+            (
+                network_event_receiver,
+                network_manager,
+                mock_interface,
+                vec![],
+            )
+        },
+    )
+    .await;
+}
+
 #[tokio::test]
 #[serial]
 async fn test_block_not_found() {
@@ -759,6 +1083,8 @@ async fn test_block_not_found() {
     // add advertised peer to controller
     let temp_peers_file = super::tools::generate_peers_file(&[PeerInfo {
         ip: mock_addr.ip(),
+        port: None,
+        other_addresses: Vec::new(),
         peer_type: PeerType::Bootstrap,
         last_alive: None,
         last_failure: None,
@@ -767,6 +1093,11 @@ async fn test_block_not_found() {
         active_out_connections: 0,
         active_in_connections: 0,
         banned: false,
+        ban_reason: None,
+        banned_at: None,
+        node_id: None,
+        version: None,
+        reputation: 0,
     }]);
     let network_conf = NetworkConfig {
         peer_types_config: default_testing_peer_type_enum_map(),
@@ -941,6 +1272,8 @@ async fn test_retry_connection_closed() {
     // add advertised peer to controller
     let temp_peers_file = super::tools::generate_peers_file(&[PeerInfo {
         ip: mock_addr.ip(),
+        port: None,
+        other_addresses: Vec::new(),
         peer_type: PeerType::Bootstrap,
         last_alive: None,
         last_failure: None,
@@ -949,6 +1282,11 @@ async fn test_retry_connection_closed() {
         active_out_connections: 0,
         active_in_connections: 0,
         banned: false,
+        ban_reason: None,
+        banned_at: None,
+        node_id: None,
+        version: None,
+        reputation: 0,
     }]);
     let network_conf = NetworkConfig {
         peer_types_config: default_testing_peer_type_enum_map(),
@@ -975,7 +1313,7 @@ async fn test_retry_connection_closed() {
 
             // Ban the node.
             network_command_sender
-                .node_ban_by_ids(vec![node_id])
+                .node_ban_by_ids(vec![node_id], BanReason::Manual)
                 .await
                 .expect("error during send ban command.");
 
@@ -1040,6 +1378,8 @@ async fn test_operation_messages() {
     // add advertised peer to controller
     let temp_peers_file = super::tools::generate_peers_file(&[PeerInfo {
         ip: mock_addr.ip(),
+        port: None,
+        other_addresses: Vec::new(),
         peer_type: PeerType::Bootstrap,
         last_alive: None,
         last_failure: None,
@@ -1048,6 +1388,11 @@ async fn test_operation_messages() {
         active_out_connections: 0,
         active_in_connections: 0,
         banned: false,
+        ban_reason: None,
+        banned_at: None,
+        node_id: None,
+        version: None,
+        reputation: 0,
     }]);
     let network_conf = NetworkConfig {
         peer_types_config: default_testing_peer_type_enum_map(),
@@ -1155,6 +1500,8 @@ async fn test_endorsements_messages() {
     // add advertised peer to controller
     let temp_peers_file = super::tools::generate_peers_file(&[PeerInfo {
         ip: mock_addr.ip(),
+        port: None,
+        other_addresses: Vec::new(),
         peer_type: PeerType::Bootstrap,
         last_alive: None,
         last_failure: None,
@@ -1163,6 +1510,11 @@ async fn test_endorsements_messages() {
         active_out_connections: 0,
         active_in_connections: 0,
         banned: false,
+        ban_reason: None,
+        banned_at: None,
+        node_id: None,
+        version: None,
+        reputation: 0,
     }]);
     let network_conf = NetworkConfig {
         peer_types_config: default_testing_peer_type_enum_map(),
@@ -1273,3 +1625,447 @@ async fn test_endorsements_messages() {
     )
     .await;
 }
+
+// test that a peer belonging to another network is refused during handshake
+#[tokio::test]
+#[serial]
+async fn test_incompatible_network_id() {
+    // test config
+    let bind_port: u16 = 50_000;
+    let temp_peers_file = super::tools::generate_peers_file(&[]);
+    let network_conf = NetworkConfig {
+        peer_types_config: default_testing_peer_type_enum_map(),
+        network_id: Hash::compute_from(b"another-network"),
+        ..NetworkConfig::scenarios_default(bind_port, temp_peers_file.path())
+    };
+
+    let mock_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(169, 202, 0, 11)), bind_port);
+
+    tools::network_test(
+        network_conf.clone(),
+        temp_peers_file,
+        async move |network_command_sender,
+                    mut network_event_receiver,
+                    network_manager,
+                    mut mock_interface| {
+            // the mock peer uses the default test network ID: the connection must be refused
+            let err: NetworkError = tools::rejected_connection_to_controller(
+                &mut network_event_receiver,
+                &mut mock_interface,
+                mock_addr,
+                1_000u64,
+                1_000u64,
+                1_000u64,
+                ConnectionId(1),
+            )
+            .await;
+
+            if !matches!(
+                err,
+                NetworkError::HandshakeError(HandshakeErrorType::IncompatibleNetwork)
+            ) {
+                panic!(
+                    "We were supposed to detect an incompatible network here\nReceived {}",
+                    err
+                )
+            }
+            // the peer is banned so that it is not retried
+            let mut banned = false;
+            for _ in 0..10 {
+                let peers = network_command_sender
+                    .get_peers()
+                    .await
+                    .expect("could not get peers");
+                if let Some(peer) = peers.peers.get(&mock_addr.ip()) {
+                    if peer.peer_info.banned {
+                        assert_eq!(
+                            peer.peer_info.ban_reason,
+                            Some(BanReason::OtherNetwork.to_string())
+                        );
+                        banned = true;
+                        break;
+                    }
+                }
+                sleep(Duration::from_millis(100)).await;
+            }
+            assert!(banned, "the peer on another network was not banned");
+            (
+                network_event_receiver,
+                network_manager,
+                mock_interface,
+                vec![],
+            )
+        },
+    )
+    .await;
+}
+
+// test that a node of another major version is refused during handshake:
+// the peer lists carry socket addresses since TEST.19 and the handshake messages changed
+#[tokio::test]
+#[serial]
+async fn test_incompatible_version() {
+    // test config
+    let bind_port: u16 = 50_000;
+    let temp_peers_file = super::tools::generate_peers_file(&[]);
+    let network_conf = NetworkConfig {
+        peer_types_config: default_testing_peer_type_enum_map(),
+        ..NetworkConfig::scenarios_default(bind_port, temp_peers_file.path())
+    };
+
+    let mock_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(169, 202, 0, 11)), bind_port);
+
+    tools::network_test(
+        network_conf.clone(),
+        temp_peers_file,
+        async move |_network_command_sender,
+                    mut network_event_receiver,
+                    network_manager,
+                    mut mock_interface| {
+            let (mock_read_half, mock_write_half) = tokio::time::timeout(
+                Duration::from_millis(1_000),
+                mock_interface.connect_to_controller(&mock_addr),
+            )
+            .await
+            .expect("connection towards controller timed out")
+            .expect("connection towards controller failed");
+
+            // the controller runs TEST.1.10, the mock peer an older major version
+            let keypair = KeyPair::generate();
+            let mock_node_id = NodeId::new(keypair.get_public_key());
+            let res = HandshakeWorker::spawn(
+                mock_read_half,
+                mock_write_half,
+                mock_node_id,
+                keypair,
+                1_000.into(),
+                Version::from_str("TEST.0.10").unwrap(),
+                get_test_network_id(),
+                false,
+                ConnectionId(1),
+                f64::INFINITY,
+                f64::INFINITY,
+                false,
+            )
+            .await
+            .expect("handshake creation failed")
+            .1;
+            match res {
+                Err(NetworkError::HandshakeError(HandshakeErrorType::IncompatibleVersion)) => {}
+                Err(err) => panic!("unexpected handshake error: {}", err),
+                Ok(_) => panic!("the handshake with an incompatible version succeeded"),
+            }
+
+            // the controller does not report the connection
+            assert!(
+                tools::wait_network_event(&mut network_event_receiver, 500.into(), |event| {
+                    match event {
+                        NetworkEvent::NewConnection { node, .. } if node == mock_node_id => {
+                            Some(())
+                        }
+                        _ => None,
+                    }
+                },)
+                .await
+                .is_none()
+            );
+            (
+                network_event_receiver,
+                network_manager,
+                mock_interface,
+                vec![],
+            )
+        },
+    )
+    .await;
+}
+
+// test that an observer node announces itself as such during handshake
+#[tokio::test]
+#[serial]
+async fn test_observer_handshake() {
+    // test config
+    let bind_port: u16 = 50_000;
+    let temp_peers_file = super::tools::generate_peers_file(&[]);
+    let network_conf = NetworkConfig {
+        peer_types_config: default_testing_peer_type_enum_map(),
+        observer: true,
+        ..NetworkConfig::scenarios_default(bind_port, temp_peers_file.path())
+    };
+
+    let mock_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(169, 202, 0, 11)), bind_port);
+
+    tools::network_test(
+        network_conf.clone(),
+        temp_peers_file,
+        async move |_network_command_sender,
+                    network_event_receiver,
+                    network_manager,
+                    mut mock_interface| {
+            let (mock_read_half, mock_write_half) = mock_interface
+                .connect_to_controller(&mock_addr)
+                .await
+                .expect("connection towards controller failed");
+            let keypair = KeyPair::generate();
+            let mock_node_id = NodeId::new(keypair.get_public_key());
+            let (_, _, _, _, observer, _) = HandshakeWorker::spawn(
+                mock_read_half,
+                mock_write_half,
+                mock_node_id,
+                keypair,
+                1_000u64.into(),
+                Version::from_str("TEST.1.10").unwrap(),
+                get_test_network_id(),
+                false,
+                ConnectionId(1),
+                f64::INFINITY,
+                f64::INFINITY,
+                false,
+            )
+            .await
+            .expect("handshake creation failed")
+            .1
+            .expect("handshake failed");
+            assert!(
+                observer,
+                "the controller did not announce itself as an observer"
+            );
+            (
+                network_event_receiver,
+                network_manager,
+                mock_interface,
+                vec![],
+            )
+        },
+    )
+    .await;
+}
+
+// test that a peer announcing a node id it does not own is rejected,
+// and that the node id proven by a peer is recorded in its peer info
+#[tokio::test]
+#[serial]
+async fn test_handshake_rejects_impersonation() {
+    // test config
+    let bind_port: u16 = 50_000;
+    let temp_peers_file = super::tools::generate_peers_file(&[]);
+    let network_conf = NetworkConfig {
+        peer_types_config: default_testing_peer_type_enum_map(),
+        ..NetworkConfig::scenarios_default(bind_port, temp_peers_file.path())
+    };
+
+    let impostor_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(169, 202, 0, 11)), bind_port);
+    let honest_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(169, 202, 0, 12)), bind_port);
+
+    tools::network_test(
+        network_conf.clone(),
+        temp_peers_file,
+        async move |network_command_sender,
+                    mut network_event_receiver,
+                    network_manager,
+                    mut mock_interface| {
+            // the impostor announces the node id of another key than its own
+            let (mock_read_half, mock_write_half) = mock_interface
+                .connect_to_controller(&impostor_addr)
+                .await
+                .expect("connection towards controller failed");
+            let impersonated_node_id = NodeId::new(KeyPair::generate().get_public_key());
+            let _ = HandshakeWorker::spawn(
+                mock_read_half,
+                mock_write_half,
+                impersonated_node_id,
+                KeyPair::generate(),
+                1_000u64.into(),
+                Version::from_str("TEST.1.10").unwrap(),
+                get_test_network_id(),
+                false,
+                ConnectionId(1),
+                f64::INFINITY,
+                f64::INFINITY,
+                false,
+            )
+            .await
+            .expect("handshake creation failed");
+            if tools::wait_network_event(
+                &mut network_event_receiver,
+                1000.into(),
+                |msg| match msg {
+                    NetworkEvent::NewConnection { node, .. } if node == impersonated_node_id => {
+                        Some(())
+                    }
+                    _ => None,
+                },
+            )
+            .await
+            .is_some()
+            {
+                panic!("the controller accepted a peer impersonating another node");
+            }
+
+            let (honest_node_id, conn_r, _conn_w) = tools::full_connection_to_controller(
+                &mut network_event_receiver,
+                &mut mock_interface,
+                honest_addr,
+                1_000,
+                1_000,
+                1_000,
+                ConnectionId(2),
+            )
+            .await;
+            let conn_drain = tools::incoming_message_drain_start(conn_r).await;
+
+            let peers = network_command_sender
+                .get_peers()
+                .await
+                .expect("could not get peers");
+            assert_eq!(
+                peers
+                    .peers
+                    .get(&impostor_addr.ip())
+                    .and_then(|peer| peer.peer_info.node_id),
+                None,
+                "the node id of the impostor was recorded"
+            );
+            assert_eq!(
+                peers.peers[&honest_addr.ip()].peer_info.node_id,
+                Some(honest_node_id),
+                "the node id proven by the peer was not recorded"
+            );
+            (
+                network_event_receiver,
+                network_manager,
+                mock_interface,
+                vec![conn_drain],
+            )
+        },
+    )
+    .await;
+}
+
+/// Connect to the controller from `addr` and perform a handshake as the node of `keypair`
+async fn handshake_to_controller(
+    mock_interface: &mut MockEstablisherInterface,
+    addr: SocketAddr,
+    keypair: &KeyPair,
+    connection_id: ConnectionId,
+) -> HandshakeReturnType {
+    let (mock_read_half, mock_write_half) = mock_interface
+        .connect_to_controller(&addr)
+        .await
+        .expect("connection towards controller failed");
+    HandshakeWorker::spawn(
+        mock_read_half,
+        mock_write_half,
+        NodeId::new(keypair.get_public_key()),
+        keypair.clone(),
+        1_000u64.into(),
+        Version::from_str("TEST.1.10").unwrap(),
+        get_test_network_id(),
+        false,
+        connection_id,
+        f64::INFINITY,
+        f64::INFINITY,
+        false,
+    )
+    .await
+    .expect("handshake creation failed")
+    .1
+}
+
+// test that a node banned by id is refused when it comes back under another ip, until it is unbanned
+#[tokio::test]
+#[serial]
+async fn test_ban_node_id_under_another_ip() {
+    // test config
+    let bind_port: u16 = 50_000;
+    let temp_peers_file = super::tools::generate_peers_file(&[]);
+    let network_conf = NetworkConfig {
+        peer_types_config: default_testing_peer_type_enum_map(),
+        ..NetworkConfig::scenarios_default(bind_port, temp_peers_file.path())
+    };
+
+    let first_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(169, 202, 0, 11)), bind_port);
+    let second_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(169, 202, 0, 12)), bind_port);
+
+    tools::network_test(
+        network_conf.clone(),
+        temp_peers_file,
+        async move |network_command_sender,
+                    mut network_event_receiver,
+                    network_manager,
+                    mut mock_interface| {
+            let keypair = KeyPair::generate();
+            let node_id = NodeId::new(keypair.get_public_key());
+            let is_new_connection = |evt: NetworkEvent| match evt {
+                NetworkEvent::NewConnection { node, .. } if node == node_id => Some(()),
+                _ => None,
+            };
+
+            // connect, then get banned by node id
+            let (_, conn_r, conn_w, _, _) =
+                handshake_to_controller(&mut mock_interface, first_addr, &keypair, ConnectionId(0))
+                    .await
+                    .expect("handshake failed");
+            tools::wait_network_event(&mut network_event_receiver, 1000.into(), is_new_connection)
+                .await
+                .expect("the node did not connect");
+            let conn_drain = tools::incoming_message_drain_start(conn_r).await;
+            network_command_sender
+                .node_ban_by_ids(vec![node_id], BanReason::Manual)
+                .await
+                .expect("error during send ban command.");
+            tools::wait_network_event(&mut network_event_receiver, 1000.into(), |evt| match evt {
+                NetworkEvent::ConnectionClosed(node) if node == node_id => Some(()),
+                _ => None,
+            })
+            .await
+            .expect("the banned node was not disconnected");
+            tools::incoming_message_drain_stop(conn_drain).await;
+            drop(conn_w);
+
+            // come back under another ip: refused
+            let _ = handshake_to_controller(
+                &mut mock_interface,
+                second_addr,
+                &keypair,
+                ConnectionId(1),
+            )
+            .await;
+            if tools::wait_network_event(
+                &mut network_event_receiver,
+                1000.into(),
+                is_new_connection,
+            )
+            .await
+            .is_some()
+            {
+                panic!("the banned node connected under another ip");
+            }
+
+            // unbanned: accepted again
+            network_command_sender
+                .node_unban_by_ids(vec![node_id])
+                .await
+                .expect("error during send unban command.");
+            let (_, conn_r, _conn_w, _, _) = handshake_to_controller(
+                &mut mock_interface,
+                second_addr,
+                &keypair,
+                ConnectionId(2),
+            )
+            .await
+            .expect("handshake failed");
+            tools::wait_network_event(&mut network_event_receiver, 1000.into(), is_new_connection)
+                .await
+                .expect("the unbanned node did not connect");
+            let conn_drain = tools::incoming_message_drain_start(conn_r).await;
+            (
+                network_event_receiver,
+                network_manager,
+                mock_interface,
+                vec![conn_drain],
+            )
+        },
+    )
+    .await;
+}
@@ -0,0 +1,132 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Random inputs fed to the frame codec: arbitrary bytes must never make the decoder panic
+//! nor yield a frame larger than the max size, and encoded frames must be decoded back
+//! whatever the way the stream is split.
+
+use crate::binders::FrameCodec;
+use bytes::{Bytes, BytesMut};
+use proptest::prelude::*;
+use tokio_util::codec::{Decoder, Encoder};
+
+const MAX_FRAME_SIZE: u32 = 1024;
+
+/// Decode every frame of `stream` fed by chunks of `chunk_size` bytes
+fn decode_chunks(
+    codec: &mut FrameCodec,
+    stream: &[u8],
+    chunk_size: usize,
+) -> Result<Vec<BytesMut>, String> {
+    let mut buffer = BytesMut::new();
+    let mut frames = Vec::new();
+    for chunk in stream.chunks(chunk_size.max(1)) {
+        buffer.extend_from_slice(chunk);
+        while let Some(frame) = codec.decode(&mut buffer).map_err(|err| err.to_string())? {
+            frames.push(frame);
+        }
+    }
+    Ok(frames)
+}
+
+proptest! {
+    #[test]
+    fn test_codec_decode_arbitrary_bytes(
+        stream in prop::collection::vec(any::<u8>(), 0..4096),
+        chunk_size in 1usize..64,
+    ) {
+        let mut codec = FrameCodec::new(MAX_FRAME_SIZE, false);
+        if let Ok(frames) = decode_chunks(&mut codec, &stream, chunk_size) {
+            for frame in frames {
+                prop_assert!(frame.len() <= MAX_FRAME_SIZE as usize);
+            }
+        }
+    }
+
+    #[test]
+    fn test_codec_roundtrip(
+        payloads in prop::collection::vec(prop::collection::vec(any::<u8>(), 0..MAX_FRAME_SIZE as usize), 1..8),
+        checksum in any::<bool>(),
+        chunk_size in 1usize..512,
+    ) {
+        let mut encoder = FrameCodec::new(MAX_FRAME_SIZE, checksum);
+        let mut stream = BytesMut::new();
+        for payload in &payloads {
+            encoder.encode(Bytes::from(payload.clone()), &mut stream).unwrap();
+        }
+        // the receiving codec checks the checksums whatever its own setting
+        let mut decoder = FrameCodec::new(MAX_FRAME_SIZE, false);
+        let frames = decode_chunks(&mut decoder, &stream, chunk_size).unwrap();
+        prop_assert_eq!(frames.len(), payloads.len());
+        for (frame, payload) in frames.iter().zip(payloads.iter()) {
+            prop_assert_eq!(&frame[..], &payload[..]);
+        }
+    }
+
+    #[test]
+    fn test_codec_detects_corruption(
+        payload in prop::collection::vec(any::<u8>(), 1..MAX_FRAME_SIZE as usize),
+        corrupted_index in any::<prop::sample::Index>(),
+        flipped_bit in 0u8..8,
+    ) {
+        let mut codec = FrameCodec::new(MAX_FRAME_SIZE, true);
+        let mut stream = BytesMut::new();
+        codec.encode(Bytes::from(payload.clone()), &mut stream).unwrap();
+        // flip a bit of the payload or of the checksum
+        let index = 4 + corrupted_index.index(stream.len() - 4);
+        stream[index] ^= 1 << flipped_bit;
+        prop_assert!(codec.decode(&mut stream).is_err());
+    }
+}
+
+#[test]
+fn test_codec_max_frame_size() {
+    let mut codec = FrameCodec::new(MAX_FRAME_SIZE, false);
+    let mut stream = BytesMut::new();
+    assert!(codec
+        .encode(
+            Bytes::from(vec![0u8; MAX_FRAME_SIZE as usize + 1]),
+            &mut stream
+        )
+        .is_err());
+
+    // an oversized frame is rejected as soon as its prefix is received
+    let mut stream = BytesMut::from(&(MAX_FRAME_SIZE + 1).to_be_bytes()[..]);
+    assert!(codec.decode(&mut stream).is_err());
+
+    // a frame of the max size is accepted
+    let mut stream = BytesMut::new();
+    codec
+        .encode(Bytes::from(vec![1u8; MAX_FRAME_SIZE as usize]), &mut stream)
+        .unwrap();
+    assert_eq!(
+        codec.decode(&mut stream).unwrap().unwrap().len(),
+        MAX_FRAME_SIZE as usize
+    );
+    assert!(stream.is_empty());
+}
+
+#[test]
+fn test_codec_frame_format() {
+    // without checksum, the frames are the 4-byte prefix and the payload
+    let mut codec = FrameCodec::new(MAX_FRAME_SIZE, false);
+    let mut stream = BytesMut::new();
+    codec
+        .encode(Bytes::from_static(b"handshake"), &mut stream)
+        .unwrap();
+    assert_eq!(&stream[..], b"\x00\x00\x00\x09handshake");
+    assert_eq!(
+        &codec.decode(&mut stream).unwrap().unwrap()[..],
+        b"handshake"
+    );
+
+    // with checksum, the frames have the checksum after the payload, even the handshake ones
+    let mut codec = FrameCodec::new(MAX_FRAME_SIZE, true);
+    let mut stream = BytesMut::new();
+    codec
+        .encode(Bytes::from_static(b"block"), &mut stream)
+        .unwrap();
+    assert_eq!(stream.len(), 4 + 5 + 4);
+    // the highest bit of the prefix flags the checksum
+    assert_eq!(stream[0], 0x80);
+    assert_eq!(&codec.decode(&mut stream).unwrap().unwrap()[..], b"block");
+}
@@ -0,0 +1,231 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use super::test_peer_info_database_invariants::new_database;
+use crate::connections::{ConnectionState, Connections};
+use massa_models::api::PeerConnectionCloseReason;
+use massa_models::node::NodeId;
+use massa_models::version::Version;
+use massa_network_exports::{BanReason, ConnectionClosureReason, NetworkError};
+use massa_signature::KeyPair;
+use massa_time::{Clock, MassaTime, MockClock};
+use std::net::{IpAddr, Ipv4Addr};
+use std::str::FromStr;
+
+const PEER_IP: IpAddr = IpAddr::V4(Ipv4Addr::new(169, 202, 0, 11));
+
+fn version() -> Version {
+    Version::from_str("TEST.1.10").unwrap()
+}
+
+#[tokio::test]
+async fn test_outgoing_connection_lifecycle() {
+    let (mut db, _saver_watch_rx) = new_database();
+    let mut connections = Connections::default();
+
+    let id = connections.dial(&mut db, PEER_IP).unwrap();
+    assert_eq!(
+        connections.get(&id).unwrap().state,
+        ConnectionState::Dialing
+    );
+    assert_eq!(db.peers[&PEER_IP].active_out_connection_attempts, 1);
+
+    assert!(connections.dial_succeeded(&mut db, id).unwrap());
+    assert_eq!(
+        connections.get(&id).unwrap().state,
+        ConnectionState::Handshaking
+    );
+    assert_eq!(db.peers[&PEER_IP].active_out_connections, 1);
+
+    let node_id = NodeId::new(KeyPair::generate().get_public_key());
+    connections
+        .handshake_succeeded(&mut db, id, node_id, version())
+        .unwrap();
+    assert_eq!(
+        connections.get(&id).unwrap().state,
+        ConnectionState::Connected(node_id)
+    );
+    assert!(db.peers[&PEER_IP].last_alive.is_some());
+    assert_eq!(db.peers[&PEER_IP].version, Some(version()));
+
+    connections.start_closing(&id);
+    assert_eq!(
+        connections.get(&id).unwrap().state,
+        ConnectionState::Closing
+    );
+    db.check_invariants().unwrap();
+
+    let connection = connections
+        .closed(&mut db, id, ConnectionClosureReason::Normal)
+        .unwrap();
+    assert!(connection.is_outgoing);
+    assert!(connections.get(&id).is_none());
+    assert_eq!(db.get_out_connection_count(), 0);
+    db.check_invariants().unwrap();
+}
+
+#[tokio::test]
+async fn test_invalid_connection_transitions() {
+    let (mut db, _saver_watch_rx) = new_database();
+    let mut connections = Connections::default();
+
+    let id = connections.accept(&mut db, PEER_IP).unwrap();
+    assert_eq!(
+        connections.get(&id).unwrap().state,
+        ConnectionState::Handshaking
+    );
+
+    // an incoming connection was never dialed, the database is left untouched
+    match connections.dial_failed(&mut db, id) {
+        Err(NetworkError::InvalidConnectionTransition(err_id, _)) => assert_eq!(err_id, id),
+        res => panic!("unexpected result {:?}", res),
+    }
+    assert_eq!(db.get_in_connection_count(), 1);
+    db.check_invariants().unwrap();
+
+    connections
+        .closed(&mut db, id, ConnectionClosureReason::Failed)
+        .unwrap();
+    assert!(db.peers[&PEER_IP].last_failure.is_some());
+    assert_eq!(db.get_in_connection_count(), 0);
+
+    // closing twice is refused
+    match connections.closed(&mut db, id, ConnectionClosureReason::Normal) {
+        Err(NetworkError::ActiveConnectionMissing(err_id)) => assert_eq!(err_id, id),
+        res => panic!("unexpected result {:?}", res),
+    }
+    db.check_invariants().unwrap();
+}
+
+#[tokio::test]
+async fn test_connection_banned_while_dialing() {
+    let (mut db, _saver_watch_rx) = new_database();
+    let mut connections = Connections::default();
+
+    let id = connections.dial(&mut db, PEER_IP).unwrap();
+    db.peer_banned(&PEER_IP, BanReason::Misbehavior).unwrap();
+    connections.start_closing(&id);
+    assert_eq!(
+        connections.get(&id).unwrap().state,
+        ConnectionState::Dialing
+    );
+
+    assert!(!connections.dial_succeeded(&mut db, id).unwrap());
+    assert!(connections.get(&id).is_none());
+    assert!(!db.peers[&PEER_IP].is_active());
+    db.check_invariants().unwrap();
+}
+
+#[tokio::test]
+async fn test_connection_history() {
+    let (mut db, _saver_watch_rx) = new_database();
+    db.network_settings.max_peer_history_length = 3;
+    let mut connections = Connections::default();
+
+    let id = connections.dial(&mut db, PEER_IP).unwrap();
+    connections.dial_failed(&mut db, id).unwrap();
+    let id = connections.accept(&mut db, PEER_IP).unwrap();
+    connections
+        .closed(&mut db, id, ConnectionClosureReason::Failed)
+        .unwrap();
+    let id = connections.dial(&mut db, PEER_IP).unwrap();
+    assert!(connections.dial_succeeded(&mut db, id).unwrap());
+    // connections are recorded once ended
+    assert_eq!(db.get_peer_history(&PEER_IP).len(), 2);
+    connections
+        .closed(&mut db, id, ConnectionClosureReason::Normal)
+        .unwrap();
+    db.peer_banned(&PEER_IP, BanReason::Misbehavior).unwrap();
+    assert!(connections.accept(&mut db, PEER_IP).is_err());
+
+    // the oldest connection is forgotten
+    let history = db.get_peer_history(&PEER_IP);
+    assert_eq!(
+        history
+            .iter()
+            .map(|event| (event.is_outgoing, event.close_reason))
+            .collect::<Vec<_>>(),
+        vec![
+            (false, PeerConnectionCloseReason::Failed),
+            (true, PeerConnectionCloseReason::Normal),
+            (false, PeerConnectionCloseReason::Refused),
+        ]
+    );
+    assert!(history.windows(2).all(|w| w[0].start <= w[1].start));
+    assert!(db
+        .get_peer_history(&IpAddr::V4(Ipv4Addr::new(169, 202, 0, 12)))
+        .is_empty());
+    db.check_invariants().unwrap();
+}
+
+#[tokio::test]
+async fn test_send_queue_overflow_is_recorded() {
+    let (mut db, _saver_watch_rx) = new_database();
+    let mut connections = Connections::default();
+
+    let id = connections.accept(&mut db, PEER_IP).unwrap();
+    connections
+        .closed(&mut db, id, ConnectionClosureReason::SendQueueOverflow)
+        .unwrap();
+    // the peer is considered as failed
+    assert!(db.peers[&PEER_IP].last_failure.is_some());
+    assert_eq!(
+        db.get_peer_history(&PEER_IP)
+            .iter()
+            .map(|event| event.close_reason)
+            .collect::<Vec<_>>(),
+        vec![PeerConnectionCloseReason::SendQueueOverflow]
+    );
+    db.check_invariants().unwrap();
+}
+
+#[tokio::test]
+async fn test_connection_timings() {
+    let (mut db, _saver_watch_rx) = new_database();
+    let clock = MockClock::new(MassaTime::from_millis(1_000_000));
+    db.clock = clock.clone_box();
+    let mut connections = Connections::default();
+    assert!(db.get_connection_timings(&PEER_IP).is_none());
+
+    // outgoing connection: connected in 30 ms, handshake in 120 ms
+    let id = connections.dial(&mut db, PEER_IP).unwrap();
+    clock.advance(MassaTime::from_millis(30));
+    assert!(connections.dial_succeeded(&mut db, id).unwrap());
+    clock.advance(MassaTime::from_millis(120));
+    connections
+        .handshake_succeeded(
+            &mut db,
+            id,
+            NodeId::new(KeyPair::generate().get_public_key()),
+            version(),
+        )
+        .unwrap();
+
+    // incoming connection from the same network group: handshake in 60 ms
+    let other_ip = IpAddr::V4(Ipv4Addr::new(169, 202, 1, 11));
+    let id = connections.accept(&mut db, other_ip).unwrap();
+    clock.advance(MassaTime::from_millis(60));
+    connections
+        .handshake_succeeded(
+            &mut db,
+            id,
+            NodeId::new(KeyPair::generate().get_public_key()),
+            version(),
+        )
+        .unwrap();
+
+    let timings = db.get_connection_timings(&PEER_IP).unwrap();
+    assert_eq!(timings, db.get_connection_timings(&other_ip).unwrap());
+    assert_eq!(timings.sample_counts(), (1, 2));
+    assert_eq!(
+        timings.average_dial_latency(),
+        Some(MassaTime::from_millis(30))
+    );
+    assert_eq!(
+        timings.average_handshake_duration(),
+        Some(MassaTime::from_millis(90))
+    );
+    assert!(db
+        .get_connection_timings(&IpAddr::V4(Ipv4Addr::new(169, 203, 0, 11)))
+        .is_none());
+    db.check_invariants().unwrap();
+}
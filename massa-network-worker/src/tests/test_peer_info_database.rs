@@ -1,15 +1,23 @@
+use super::test_peer_info_database_invariants::new_database;
 use crate::{
+    ban_audit::{BanAuditAction, BanAuditRecord, BanAuditTarget},
     peer_info_database::{cleanup_peers, PeerInfoDatabase},
+    peers_file::PeersFileContent,
     NetworkConfig, NetworkError,
 };
 use enum_map::enum_map;
+use im::HashMap;
+use massa_models::ban_list::{BanList, BanListEntry, IpSubnet};
+use massa_models::node::NodeId;
 use massa_network_exports::{
-    settings::PeerTypeConnectionConfig, NetworkConnectionErrorType, PeerInfo, PeerType,
+    settings::PeerTypeConnectionConfig, AdvertisedPeer, BanReason, NetworkConnectionErrorType,
+    PeerExpression, PeerFeedback, PeerFilter, PeerInfo, PeerType,
 };
-use massa_time::MassaTime;
+use massa_signature::KeyPair;
+use massa_time::{Clock, MassaTime, MockClock, SystemClock};
 use serial_test::serial;
-use std::{collections::HashMap, net::IpAddr};
-use tokio::sync::watch;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
 
 #[tokio::test]
 #[serial]
@@ -42,20 +50,7 @@ async fn test_try_new_in_connection_in_connection_closed() {
     connected_peers1.banned = true;
     peers.insert(connected_peers1.ip, connected_peers1);
 
-    let wakeup_interval = network_settings.wakeup_interval;
-    let (saver_watch_tx, mut saver_watch_rx) = watch::channel(peers.clone());
-
-    let saver_join_handle =
-        tokio::spawn(async move { while let Ok(()) = saver_watch_rx.changed().await {} });
-
-    let mut db = PeerInfoDatabase {
-        network_settings,
-        peers,
-        saver_join_handle,
-        saver_watch_tx,
-        wakeup_interval,
-        peer_types_connection_count: Default::default(),
-    };
+    let mut db = PeerInfoDatabase::from_peers(&network_settings, peers, Box::new(SystemClock));
 
     // test with no connection attempt before
     let res = db.in_connection_closed(&IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 11)));
@@ -144,20 +139,7 @@ async fn test_out_connection_attempt_failed() {
     connected_peers1.banned = true;
     peers.insert(connected_peers1.ip, connected_peers1);
 
-    let wakeup_interval = network_settings.wakeup_interval;
-    let (saver_watch_tx, mut saver_watch_rx) = watch::channel(peers.clone());
-
-    let saver_join_handle =
-        tokio::spawn(async move { while let Ok(()) = saver_watch_rx.changed().await {} });
-
-    let mut db = PeerInfoDatabase {
-        network_settings,
-        peers,
-        saver_join_handle,
-        saver_watch_tx,
-        peer_types_connection_count: Default::default(),
-        wakeup_interval,
-    };
+    let mut db = PeerInfoDatabase::from_peers(&network_settings, peers, Box::new(SystemClock));
 
     // test with no connection attempt before
     let res =
@@ -254,20 +236,7 @@ async fn test_try_out_connection_attempt_success() {
     connected_peers1.banned = true;
     peers.insert(connected_peers1.ip, connected_peers1);
 
-    let wakeup_interval = network_settings.wakeup_interval;
-    let (saver_watch_tx, mut saver_watch_rx) = watch::channel(peers.clone());
-
-    let saver_join_handle =
-        tokio::spawn(async move { while let Ok(()) = saver_watch_rx.changed().await {} });
-
-    let mut db = PeerInfoDatabase {
-        network_settings,
-        peers,
-        saver_join_handle,
-        saver_watch_tx,
-        peer_types_connection_count: Default::default(),
-        wakeup_interval,
-    };
+    let mut db = PeerInfoDatabase::from_peers(&network_settings, peers, Box::new(SystemClock));
 
     // test with no connection attempt before
     let res = db
@@ -346,19 +315,7 @@ async fn test_new_out_connection_closed() {
     let connected_peers1 =
         default_peer_info_not_connected(IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 11)));
     peers.insert(connected_peers1.ip, connected_peers1);
-    let wakeup_interval = network_settings.wakeup_interval;
-    let (saver_watch_tx, mut saver_watch_rx) = watch::channel(peers.clone());
-    let saver_join_handle =
-        tokio::spawn(async move { while let Ok(()) = saver_watch_rx.changed().await {} });
-
-    let mut db = PeerInfoDatabase {
-        network_settings,
-        peers,
-        saver_join_handle,
-        saver_watch_tx,
-        peer_types_connection_count: Default::default(),
-        wakeup_interval,
-    };
+    let mut db = PeerInfoDatabase::from_peers(&network_settings, peers, Box::new(SystemClock));
 
     //
     let res = db.out_connection_closed(&IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 11)));
@@ -427,18 +384,7 @@ async fn test_new_out_connection_attempt() {
     let connected_peers1 =
         default_peer_info_not_connected(IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 11)));
     peers.insert(connected_peers1.ip, connected_peers1);
-    let wakeup_interval = network_settings.wakeup_interval;
-    let (saver_watch_tx, _) = watch::channel(peers.clone());
-    let saver_join_handle = tokio::spawn(async move {});
-
-    let mut db = PeerInfoDatabase {
-        network_settings,
-        peers,
-        saver_join_handle,
-        saver_watch_tx,
-        peer_types_connection_count: Default::default(),
-        wakeup_interval,
-    };
+    let mut db = PeerInfoDatabase::from_peers(&network_settings, peers, Box::new(SystemClock));
 
     // test with no peers.
     let res = db.new_out_connection_attempt(&IpAddr::V4(std::net::Ipv4Addr::new(192, 168, 0, 11)));
@@ -471,7 +417,7 @@ async fn test_new_out_connection_attempt() {
 
 #[tokio::test]
 #[serial]
-async fn test_get_advertisable_peer_ips() {
+async fn test_get_advertisable_peers() {
     let network_settings = NetworkConfig::default();
     let mut peers: HashMap<IpAddr, PeerInfo> = HashMap::new();
 
@@ -513,21 +459,14 @@ async fn test_get_advertisable_peer_ips() {
         Some(MassaTime::now().unwrap().checked_sub(2000.into()).unwrap());
     peers.insert(connected_peers2.ip, connected_peers2);
 
-    let wakeup_interval = network_settings.wakeup_interval;
-    let (saver_watch_tx, _) = watch::channel(peers.clone());
-    let saver_join_handle = tokio::spawn(async move {});
-
-    let db = PeerInfoDatabase {
-        network_settings,
-        peers,
-        saver_join_handle,
-        saver_watch_tx,
-        peer_types_connection_count: Default::default(),
-        wakeup_interval,
-    };
+    let db = PeerInfoDatabase::from_peers(&network_settings, peers, Box::new(SystemClock));
 
     // test with no peers.
-    let ip_list = db.get_advertisable_peer_ips();
+    let ip_list: Vec<IpAddr> = db
+        .get_advertisable_peers()
+        .iter()
+        .map(|peer| peer.address.ip())
+        .collect();
 
     assert_eq!(5, ip_list.len());
 
@@ -624,18 +563,7 @@ async fn test_get_out_connection_candidate_ips() {
     connected_peers1.advertised = false;
     peers.insert(connected_peers1.ip, connected_peers1);
 
-    let wakeup_interval = network_settings.wakeup_interval;
-    let (saver_watch_tx, _) = watch::channel(peers.clone());
-    let saver_join_handle = tokio::spawn(async move {});
-
-    let db = PeerInfoDatabase {
-        network_settings,
-        peers,
-        saver_join_handle,
-        saver_watch_tx,
-        peer_types_connection_count: Default::default(),
-        wakeup_interval,
-    };
+    let db = PeerInfoDatabase::from_peers(&network_settings, peers, Box::new(SystemClock));
 
     // test with no peers.
     let ip_list = db.get_out_connection_candidate_ips().unwrap();
@@ -663,6 +591,336 @@ async fn test_get_out_connection_candidate_ips() {
     );
 }
 
+#[tokio::test]
+#[serial]
+async fn test_get_out_connection_candidate_ips_keeps_best_peers() {
+    let network_settings = NetworkConfig::default();
+    let mut peers: HashMap<IpAddr, PeerInfo> = HashMap::new();
+
+    // more bootstrap candidates than bootstrap slots,
+    // the one that never failed is returned, then the one that failed the longest ago
+    for (index, failure_age) in [(11, Some(20000)), (12, None), (13, Some(40000))] {
+        let mut peer = default_peer_info_not_connected(IpAddr::V4(std::net::Ipv4Addr::new(
+            169, 202, 0, index,
+        )));
+        peer.peer_type = PeerType::Bootstrap;
+        peer.last_failure =
+            failure_age.map(|age: u64| MassaTime::now().unwrap().checked_sub(age.into()).unwrap());
+        peers.insert(peer.ip, peer);
+    }
+
+    let mut db = PeerInfoDatabase::from_peers(&network_settings, peers, Box::new(SystemClock));
+
+    let ip_list = db.get_out_connection_candidate_ips().unwrap();
+    assert_eq!(
+        vec![IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 12))],
+        ip_list
+    );
+
+    db.network_settings.peer_types_config[PeerType::Bootstrap].target_out_connections = 2;
+    db.network_settings.peer_types_config[PeerType::Bootstrap].max_out_attempts = 2;
+    let ip_list = db.get_out_connection_candidate_ips().unwrap();
+    assert_eq!(
+        vec![
+            IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 12)),
+            IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 13))
+        ],
+        ip_list
+    );
+}
+
+#[test]
+fn test_peer_filters() {
+    let ip = |ip: &str| IpAddr::from_str(ip).unwrap();
+    let addr = |addr: &str| SocketAddr::from_str(addr).unwrap();
+    let network_settings = NetworkConfig {
+        routable_ip: None,
+        candidate_peer_filter: PeerFilter {
+            deny_subnets: vec![IpSubnet::from_str("169.203.0.0/16").unwrap()],
+            deny_expressions: vec![PeerExpression::from_str("ipv6 && !in 2a01:db8::/32").unwrap()],
+        },
+        advertised_peer_filter: PeerFilter {
+            deny_subnets: vec![IpSubnet::from_str("169.202.0.12").unwrap()],
+            deny_expressions: vec![],
+        },
+        ..Default::default()
+    };
+    let peer = |address: &str| AdvertisedPeer::from(addr(address));
+    let mut db =
+        PeerInfoDatabase::from_peers(&network_settings, HashMap::new(), Box::new(SystemClock));
+
+    // the candidates and the other addresses denied by the filter are not merged
+    db.merge_candidate_peers(&[
+        AdvertisedPeer {
+            address: addr("169.202.0.11:31244"),
+            other_addresses: vec![
+                addr("[2a02:db8::1]:31244"),
+                addr("[2a01:db8::2]:31244"),
+                addr("169.202.0.12:31245"),
+            ],
+        },
+        peer("169.202.0.12:31244"),
+        peer("169.203.0.11:31244"),
+        peer("[2a01:db8::1]:31245"),
+        peer("[2a02:db8::1]:31244"),
+    ])
+    .unwrap();
+    let mut known_ips: Vec<IpAddr> = db.peers.keys().copied().collect();
+    known_ips.sort();
+    assert_eq!(
+        known_ips,
+        vec![ip("169.202.0.11"), ip("169.202.0.12"), ip("2a01:db8::1")]
+    );
+
+    assert_eq!(
+        db.peers[&ip("169.202.0.11")].other_addresses,
+        vec![addr("[2a01:db8::2]:31244"), addr("169.202.0.12:31245")]
+    );
+
+    // the known peers and the other addresses denied by the advertised peer filter are not advertised, but kept
+    let mut advertised_peers = db.get_advertisable_peers();
+    advertised_peers.sort_by_key(|peer| peer.address);
+    assert_eq!(
+        advertised_peers,
+        vec![
+            AdvertisedPeer {
+                address: addr("169.202.0.11:31244"),
+                other_addresses: vec![addr("[2a01:db8::2]:31244")],
+            },
+            peer("[2a01:db8::1]:31245"),
+        ]
+    );
+    assert!(db.peers.contains_key(&ip("169.202.0.12")));
+    db.check_invariants().unwrap();
+}
+
+#[test]
+fn test_dial_and_advertise_other_addresses() {
+    let ip = |ip: &str| IpAddr::from_str(ip).unwrap();
+    let addr = |addr: &str| SocketAddr::from_str(addr).unwrap();
+    let network_settings = NetworkConfig {
+        routable_ip: Some(ip("169.202.0.1")),
+        protocol_port: 31244,
+        other_routable_addresses: vec![addr("[2a01:db8::1]:31245")],
+        ..Default::default()
+    };
+    let mut db =
+        PeerInfoDatabase::from_peers(&network_settings, HashMap::new(), Box::new(SystemClock));
+    db.merge_candidate_peers(&[AdvertisedPeer {
+        address: addr("169.202.0.11:31246"),
+        other_addresses: vec![addr("[2a01:db8::11]:31244"), addr("169.202.0.12:31244")],
+    }])
+    .unwrap();
+
+    // the addresses of a peer are dialed by order of preference
+    assert_eq!(
+        db.get_dial_addresses(&ip("169.202.0.11")),
+        vec![
+            addr("169.202.0.11:31246"),
+            addr("[2a01:db8::11]:31244"),
+            addr("169.202.0.12:31244")
+        ]
+    );
+    // an unknown peer is dialed on our protocol port
+    assert_eq!(
+        db.get_dial_addresses(&ip("169.202.0.13")),
+        vec![addr("169.202.0.13:31244")]
+    );
+
+    // we advertise ourselves first, with all our addresses
+    assert_eq!(
+        db.get_advertisable_peers(),
+        vec![
+            AdvertisedPeer {
+                address: addr("169.202.0.1:31244"),
+                other_addresses: vec![addr("[2a01:db8::1]:31245")],
+            },
+            AdvertisedPeer {
+                address: addr("169.202.0.11:31246"),
+                other_addresses: vec![addr("[2a01:db8::11]:31244"), addr("169.202.0.12:31244")],
+            },
+        ]
+    );
+    db.check_invariants().unwrap();
+}
+
+#[test]
+fn test_failed_peer_waits_for_wakeup_interval() {
+    let network_settings = NetworkConfig::default();
+    let wakeup_interval = network_settings.wakeup_interval;
+    let ip = IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 11));
+    let mut peers = HashMap::new();
+    peers.insert(ip, default_peer_info_not_connected(ip));
+
+    // the database reads the time set by the test
+    let clock = MockClock::new(MassaTime::from_millis(1_000_000));
+    let mut db = PeerInfoDatabase::from_peers(&network_settings, peers, clock.clone_box());
+
+    db.new_out_connection_attempt(&ip).unwrap();
+    db.out_connection_attempt_failed(&ip).unwrap();
+    assert_eq!(
+        db.get_peers().get(&ip).unwrap().last_failure,
+        Some(MassaTime::from_millis(1_000_000))
+    );
+    assert!(db.get_out_connection_candidate_ips().unwrap().is_empty());
+
+    clock.advance(wakeup_interval);
+    assert!(db.get_out_connection_candidate_ips().unwrap().is_empty());
+
+    clock.advance(MassaTime::from_millis(1));
+    assert_eq!(db.get_out_connection_candidate_ips().unwrap(), vec![ip]);
+}
+
+#[test]
+fn test_dial_budget_limits_out_connection_attempts() {
+    // one attempt every 10 seconds, at most 2 at once
+    let mut network_settings = NetworkConfig {
+        max_out_attempts_per_minute: 6,
+        max_out_attempts_burst: 2,
+        ..Default::default()
+    };
+    network_settings.peer_types_config[PeerType::Standard].target_out_connections = 5;
+    network_settings.peer_types_config[PeerType::Standard].max_out_attempts = 5;
+    let ips: Vec<IpAddr> = (11..16)
+        .map(|i| IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, i)))
+        .collect();
+    let peers = ips
+        .iter()
+        .map(|&ip| (ip, default_peer_info_not_connected(ip)))
+        .collect();
+    let clock = MockClock::new(MassaTime::from_millis(1_000_000));
+    let mut db = PeerInfoDatabase::from_peers(&network_settings, peers, clock.clone_box());
+
+    // the full budget allows the burst only, whatever the slots of the peer type
+    let candidates = db.get_out_connection_candidate_ips().unwrap();
+    assert_eq!(candidates.len(), 2);
+    for ip in candidates.iter() {
+        db.new_out_connection_attempt(ip).unwrap();
+    }
+    assert!(db.get_out_connection_candidate_ips().unwrap().is_empty());
+    let ip = *ips.iter().find(|ip| !candidates.contains(ip)).unwrap();
+    assert!(matches!(
+        db.new_out_connection_attempt(&ip),
+        Err(NetworkError::PeerConnectionError(
+            NetworkConnectionErrorType::TooManyConnectionAttempts(_)
+        ))
+    ));
+
+    // the budget earns an attempt every 10 seconds, up to the burst
+    clock.advance(MassaTime::from_millis(9_999));
+    assert!(db.get_out_connection_candidate_ips().unwrap().is_empty());
+    clock.advance(MassaTime::from_millis(1));
+    assert_eq!(db.get_out_connection_candidate_ips().unwrap().len(), 1);
+    clock.advance(MassaTime::from_millis(60_000));
+    assert_eq!(db.get_out_connection_candidate_ips().unwrap().len(), 2);
+    db.new_out_connection_attempt(&ip).unwrap();
+    assert_eq!(db.get_out_connection_candidate_ips().unwrap().len(), 1);
+}
+
+#[test]
+fn test_peer_feedback_orders_candidates() {
+    let mut network_settings = NetworkConfig::default();
+    network_settings.peer_types_config[PeerType::Standard].target_out_connections = 3;
+    network_settings.peer_types_config[PeerType::Standard].max_out_attempts = 3;
+    let helpful_ip = IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 11));
+    let neutral_ip = IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 12));
+    let harmful_ip = IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 13));
+    let mut peers = HashMap::new();
+    for ip in [helpful_ip, neutral_ip, harmful_ip] {
+        peers.insert(ip, default_peer_info_not_connected(ip));
+    }
+    // without feedback, the most recently alive peer comes first
+    peers.get_mut(&harmful_ip).unwrap().last_alive = Some(MassaTime::from_millis(1_000));
+    let mut db = PeerInfoDatabase::from_peers(&network_settings, peers, Box::new(SystemClock));
+    assert_eq!(
+        db.get_out_connection_candidate_ips().unwrap()[0],
+        harmful_ip
+    );
+
+    for _ in 0..3 {
+        db.peer_feedback(&helpful_ip, PeerFeedback::FinalBlock);
+    }
+    db.peer_feedback(&harmful_ip, PeerFeedback::InvalidBlock);
+    db.peer_feedback(&harmful_ip, PeerFeedback::LateAnnouncement);
+    // feedback on an unknown peer is ignored
+    db.peer_feedback(
+        &IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 14)),
+        PeerFeedback::InvalidBlock,
+    );
+    assert_eq!(db.get_peers().get(&helpful_ip).unwrap().reputation, 3);
+    assert_eq!(db.get_peers().get(&harmful_ip).unwrap().reputation, -22);
+    assert_eq!(
+        db.get_out_connection_candidate_ips().unwrap(),
+        vec![helpful_ip, neutral_ip, harmful_ip]
+    );
+
+    // the reputation is bounded
+    for _ in 0..10 {
+        db.peer_feedback(&harmful_ip, PeerFeedback::InvalidBlock);
+    }
+    assert_eq!(
+        db.get_peers().get(&harmful_ip).unwrap().reputation,
+        -massa_models::config::MAX_PEER_REPUTATION
+    );
+}
+
+#[test]
+fn test_connect_timeout_adapts_to_dial_latencies() {
+    let network_settings = NetworkConfig {
+        connect_timeout: MassaTime::from_millis(10_000),
+        min_connect_timeout: MassaTime::from_millis(1000),
+        connect_timeout_latency_factor: 4.0,
+        ..Default::default()
+    };
+    let mut db =
+        PeerInfoDatabase::from_peers(&network_settings, HashMap::new(), Box::new(SystemClock));
+    let ip = IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 11));
+    let same_group_ip = IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 3, 12));
+    let other_group_ip = IpAddr::V4(std::net::Ipv4Addr::new(169, 203, 0, 11));
+
+    // not enough latencies observed yet
+    for _ in 0..7 {
+        db.record_dial_latency(&ip, MassaTime::from_millis(500));
+    }
+    assert_eq!(
+        db.get_connect_timeout(&ip),
+        network_settings.connect_timeout
+    );
+
+    // the timeout follows the latencies of the network group
+    db.record_dial_latency(&ip, MassaTime::from_millis(600));
+    assert_eq!(db.get_connect_timeout(&ip), MassaTime::from_millis(2400));
+    assert_eq!(
+        db.get_connect_timeout(&same_group_ip),
+        MassaTime::from_millis(2400)
+    );
+    assert_eq!(
+        db.get_connect_timeout(&other_group_ip),
+        network_settings.connect_timeout
+    );
+
+    // clamped by the configured timeouts
+    db.record_dial_latency(&ip, MassaTime::from_millis(5000));
+    assert_eq!(
+        db.get_connect_timeout(&ip),
+        network_settings.connect_timeout
+    );
+    for _ in 0..8 {
+        db.record_dial_latency(&other_group_ip, MassaTime::from_millis(10));
+    }
+    assert_eq!(
+        db.get_connect_timeout(&other_group_ip),
+        network_settings.min_connect_timeout
+    );
+
+    // disabled with a zero factor
+    db.network_settings.connect_timeout_latency_factor = 0.0;
+    assert_eq!(
+        db.get_connect_timeout(&other_group_ip),
+        network_settings.connect_timeout
+    );
+}
+
 #[tokio::test]
 #[serial]
 async fn test_cleanup_peers() {
@@ -679,6 +937,7 @@ async fn test_cleanup_peers() {
         &mut peers,
         None,
         network_settings.ban_timeout,
+        MassaTime::now().unwrap(),
     )
     .unwrap();
     assert!(peers.is_empty());
@@ -688,12 +947,12 @@ async fn test_cleanup_peers() {
     let mut connected_peers1 =
         default_peer_info_connected(IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 11)));
     connected_peers1.last_alive = Some(MassaTime::now().unwrap().checked_sub(1000.into()).unwrap());
-    peers.insert(connected_peers1.ip, connected_peers1);
+    peers.insert(connected_peers1.ip, connected_peers1.clone());
 
     let mut connected_peers2 =
         default_peer_info_connected(IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 12)));
     connected_peers2.last_alive = Some(MassaTime::now().unwrap().checked_sub(900.into()).unwrap());
-    let same_connected_peer = connected_peers2;
+    let same_connected_peer = connected_peers2.clone();
 
     let non_global =
         default_peer_info_connected(IpAddr::V4(std::net::Ipv4Addr::new(192, 168, 0, 10)));
@@ -749,6 +1008,7 @@ async fn test_cleanup_peers() {
         &mut peers,
         None,
         network_settings.ban_timeout,
+        MassaTime::now().unwrap(),
     )
     .unwrap();
 
@@ -763,12 +1023,25 @@ async fn test_cleanup_peers() {
     assert!(peers.contains_key(&IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 36))));
 
     // test with advertised peers
-    let advertised = vec![
-        IpAddr::V4(std::net::Ipv4Addr::new(192, 168, 0, 10)),
-        IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 43)),
-        IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 11)),
-        IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 44)),
-        IpAddr::V4(std::net::Ipv4Addr::new(127, 0, 0, 1)),
+    let addr = |addr: &str| SocketAddr::from_str(addr).unwrap();
+    let mut advertised: Vec<AdvertisedPeer> = [
+        "192.168.0.10:31244",
+        "169.202.0.43:31245",
+        "169.202.0.11:31246",
+        "169.202.0.44:31244",
+        "127.0.0.1:31244",
+        "169.202.0.45:0",
+    ]
+    .iter()
+    .map(|address| AdvertisedPeer::from(addr(address)))
+    .collect();
+    // only the other addresses that can be dialed are kept, without duplicates
+    advertised[1].other_addresses = vec![
+        addr("[2a01:db8::1]:31244"),
+        addr("10.0.0.1:31244"),
+        addr("169.202.0.43:31245"),
+        addr("[2a01:db8::1]:31244"),
+        addr("169.202.0.50:0"),
     ];
 
     network_settings.max_idle_peers = 5;
@@ -778,10 +1051,80 @@ async fn test_cleanup_peers() {
         &mut peers,
         Some(&advertised),
         network_settings.ban_timeout,
+        MassaTime::now().unwrap(),
     )
     .unwrap();
 
     assert!(peers.contains_key(&IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 43))));
+    // the advertised ports are kept, but not those of the connected peers
+    assert_eq!(
+        peers[&IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 43))].port,
+        Some(31245)
+    );
+    assert_eq!(
+        peers[&IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 43))].other_addresses,
+        vec![addr("[2a01:db8::1]:31244")]
+    );
+    assert_eq!(
+        peers[&IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 11))].port,
+        None
+    );
+    // port 0 can not be dialed
+    assert!(!peers.contains_key(&IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 45))));
+}
+
+#[tokio::test]
+#[serial]
+async fn test_cleanup_peers_network_group_quota() {
+    let network_settings = NetworkConfig {
+        max_idle_peers: 10,
+        max_peers_per_network_group: 3,
+        ..Default::default()
+    };
+    let now = MassaTime::now().unwrap();
+    let mut peers = HashMap::new();
+    // many idle peers in 169.202.0.0/16, the most recently alive having the highest last byte
+    for last_byte in 1..=8u8 {
+        let mut peer = default_peer_info_not_connected(IpAddr::V4(std::net::Ipv4Addr::new(
+            169, 202, 0, last_byte,
+        )));
+        peer.last_alive = Some(now.saturating_sub(MassaTime::from_millis(1000 - last_byte as u64)));
+        peers.insert(peer.ip, peer);
+    }
+    // a few older ones in 169.203.0.0/16
+    for last_byte in 1..=2u8 {
+        let mut peer = default_peer_info_not_connected(IpAddr::V4(std::net::Ipv4Addr::new(
+            169, 203, 0, last_byte,
+        )));
+        peer.last_alive = Some(now.saturating_sub(MassaTime::from_millis(5000)));
+        peers.insert(peer.ip, peer);
+    }
+
+    cleanup_peers(
+        &network_settings,
+        &mut peers,
+        None,
+        network_settings.ban_timeout,
+        MassaTime::now().unwrap(),
+    )
+    .unwrap();
+
+    let mut kept: Vec<IpAddr> = peers.keys().copied().collect();
+    kept.sort_unstable();
+    assert_eq!(
+        kept,
+        [
+            "169.202.0.6",
+            "169.202.0.7",
+            "169.202.0.8",
+            "169.203.0.1",
+            "169.203.0.2"
+        ]
+        .iter()
+        .map(|ip| IpAddr::from_str(ip).unwrap())
+        .collect::<Vec<_>>(),
+        "each network group should keep its most recently alive peers up to its quota"
+    );
 }
 
 #[tokio::test]
@@ -792,9 +1135,325 @@ async fn test() {
     assert!(!p.is_active());
 }
 
+#[tokio::test]
+#[serial]
+async fn test_import_export_ban_list() {
+    let (mut db, _saver_watch_rx) = new_database();
+    let now = MassaTime::now().unwrap();
+    let ban = |target: &str, reason: Option<&str>, expiry: Option<MassaTime>| BanListEntry {
+        target: IpSubnet::from_str(target).unwrap(),
+        reason: reason.map(String::from),
+        expiry,
+        banned_at: Some(now),
+    };
+    db.import_ban_list(BanList {
+        bans: vec![
+            ban(
+                "169.202.0.0/24",
+                Some("spam"),
+                Some(now.saturating_add(60000.into())),
+            ),
+            ban("169.203.0.1", None, None),
+            ban(
+                "169.204.0.1",
+                Some("expired"),
+                Some(now.checked_sub(1000.into()).unwrap()),
+            ),
+        ],
+    })
+    .unwrap();
+
+    // the ips of the subnet are refused, the others accepted
+    let banned_ip = IpAddr::from_str("169.202.0.5").unwrap();
+    assert!(matches!(
+        db.try_new_in_connection(&banned_ip),
+        Err(NetworkError::PeerConnectionError(
+            NetworkConnectionErrorType::BannedPeerTryingToConnect(ip)
+        )) if ip == banned_ip
+    ));
+    assert!(db.is_banned(&IpAddr::from_str("169.203.0.1").unwrap()));
+    assert!(!db.is_banned(&IpAddr::from_str("169.204.0.1").unwrap()));
+    db.try_new_in_connection(&IpAddr::from_str("169.205.0.1").unwrap())
+        .unwrap();
+
+    // merged with the longest expiry, the reason being kept
+    db.import_ban_list(BanList {
+        bans: vec![ban(
+            "169.202.0.1/24",
+            None,
+            Some(now.saturating_add(120000.into())),
+        )],
+    })
+    .unwrap();
+    db.peer_banned(
+        &IpAddr::from_str("169.206.0.1").unwrap(),
+        BanReason::Misbehavior,
+    )
+    .unwrap();
+    db.unban(vec![IpAddr::from_str("169.203.0.1").unwrap()])
+        .unwrap();
+
+    let ban_list = db.get_ban_list();
+    assert_eq!(ban_list.bans.len(), 2);
+    assert_eq!(
+        ban_list.bans[0],
+        ban(
+            "169.202.0.0/24",
+            Some("spam"),
+            Some(now.saturating_add(120000.into()))
+        )
+    );
+    assert_eq!(ban_list.bans[1].target.to_string(), "169.206.0.1");
+    assert_eq!(
+        ban_list.bans[1].reason,
+        Some(BanReason::Misbehavior.to_string())
+    );
+    assert!(ban_list.bans[1].expiry.is_some());
+    assert!(ban_list.bans[1].banned_at.is_some());
+
+    // the exported list can be imported by another node
+    let (mut other_db, _other_saver_watch_rx) = new_database();
+    other_db
+        .import_ban_list(serde_json::from_str(&serde_json::to_string(&ban_list).unwrap()).unwrap())
+        .unwrap();
+    assert_eq!(other_db.get_ban_list(), ban_list);
+    db.check_invariants().unwrap();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_subnet_bans_persistence() {
+    let initial_peers_file = super::tools::generate_peers_file(&[]);
+    let peers_dir = tempfile::tempdir().unwrap();
+    let network_settings = NetworkConfig {
+        initial_peers_file: initial_peers_file.path().to_path_buf(),
+        peers_file: peers_dir.path().join("peers.json"),
+        ..Default::default()
+    };
+    let subnet = |target: &str| IpSubnet::from_str(target).unwrap();
+    let ip = |ip: &str| IpAddr::from_str(ip).unwrap();
+    let mut db = PeerInfoDatabase::new(&network_settings, Box::new(SystemClock))
+        .await
+        .unwrap();
+    db.import_ban_list(BanList {
+        bans: ["169.202.0.0/16", "169.202.1.1", "169.203.0.1"]
+            .into_iter()
+            .map(|target| BanListEntry {
+                target: subnet(target),
+                reason: None,
+                expiry: None,
+                banned_at: None,
+            })
+            .collect(),
+    })
+    .unwrap();
+    db.peer_banned(&ip("169.202.2.2"), BanReason::Manual)
+        .unwrap();
+    assert!(db.is_banned(&ip("169.202.3.3")));
+
+    // the bans of the subnet and of what it contains are lifted
+    db.unban_subnets(vec![subnet("169.202.0.0/16")]).unwrap();
+    assert!(!db.is_banned(&ip("169.202.1.1")));
+    assert!(!db.is_banned(&ip("169.202.2.2")));
+    assert!(!db.is_banned(&ip("169.202.3.3")));
+    assert!(db.is_banned(&ip("169.203.0.1")));
+    db.check_invariants().unwrap();
+
+    // the remaining bans are dumped and loaded again
+    db.stop().await.unwrap();
+    let db = PeerInfoDatabase::new(&network_settings, Box::new(SystemClock))
+        .await
+        .unwrap();
+    let ban_list = db.get_ban_list();
+    assert_eq!(ban_list.bans.len(), 1);
+    assert_eq!(ban_list.bans[0].target, subnet("169.203.0.1"));
+    assert!(db.is_banned(&ip("169.203.0.1")));
+    db.stop().await.unwrap();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_ban_audit_log() {
+    let initial_peers_file = super::tools::generate_peers_file(&[]);
+    let peers_dir = tempfile::tempdir().unwrap();
+    let network_settings = NetworkConfig {
+        initial_peers_file: initial_peers_file.path().to_path_buf(),
+        peers_file: peers_dir.path().join("peers.json"),
+        ban_audit_file: Some(peers_dir.path().join("ban_audit.log")),
+        ..Default::default()
+    };
+    let subnet = |target: &str| IpSubnet::from_str(target).unwrap();
+    let ip = |ip: &str| IpAddr::from_str(ip).unwrap();
+    let node_id = NodeId::new(KeyPair::generate().get_public_key());
+    let clock = MockClock::new(MassaTime::from_millis(1_670_000_000_000));
+    let mut db = PeerInfoDatabase::new(&network_settings, Box::new(clock.clone()))
+        .await
+        .unwrap();
+
+    // the reason and the time of the bans are kept with them
+    db.peer_banned(&ip("169.202.0.1"), BanReason::Misbehavior)
+        .unwrap();
+    let peer = &db.peers[&ip("169.202.0.1")];
+    assert_eq!(peer.ban_reason, Some(BanReason::Misbehavior.to_string()));
+    assert_eq!(peer.banned_at, Some(clock.now().unwrap()));
+    clock.advance(MassaTime::from_millis(1000));
+    db.ban_node_ids(&[node_id], BanReason::Manual).unwrap();
+    db.import_ban_list(BanList {
+        bans: vec![BanListEntry {
+            target: subnet("169.203.0.0/16"),
+            reason: Some("spam".into()),
+            expiry: None,
+            banned_at: None,
+        }],
+    })
+    .unwrap();
+    assert_eq!(
+        db.get_ban_list().bans[1].banned_at,
+        Some(clock.now().unwrap())
+    );
+    db.unban(vec![ip("169.202.0.1")]).unwrap();
+    assert_eq!(db.peers[&ip("169.202.0.1")].ban_reason, None);
+    db.unban_node_ids(&[node_id]).unwrap();
+    db.unban_subnets(vec![subnet("169.203.0.0/16")]).unwrap();
+    // lifting a ban that does not exist is not logged
+    db.unban(vec![ip("169.204.0.1")]).unwrap();
+
+    // every ban and unban is appended to the log, in order
+    db.stop().await.unwrap();
+    let records: Vec<BanAuditRecord> =
+        tokio::fs::read_to_string(peers_dir.path().join("ban_audit.log"))
+            .await
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+    let ban_timeout = network_settings.ban_timeout;
+    let first_ban_time = MassaTime::from_millis(1_670_000_000_000);
+    let next_time = MassaTime::from_millis(1_670_000_001_000);
+    assert_eq!(
+        records,
+        vec![
+            BanAuditRecord {
+                time: first_ban_time,
+                action: BanAuditAction::Ban,
+                target: BanAuditTarget::Subnet(subnet("169.202.0.1")),
+                reason: Some(BanReason::Misbehavior.to_string()),
+                expiry: Some(first_ban_time.saturating_add(ban_timeout)),
+            },
+            BanAuditRecord {
+                time: next_time,
+                action: BanAuditAction::Ban,
+                target: BanAuditTarget::Node(node_id),
+                reason: Some(BanReason::Manual.to_string()),
+                expiry: Some(next_time.saturating_add(ban_timeout)),
+            },
+            BanAuditRecord {
+                time: next_time,
+                action: BanAuditAction::Ban,
+                target: BanAuditTarget::Subnet(subnet("169.203.0.0/16")),
+                reason: Some("spam".into()),
+                expiry: None,
+            },
+            BanAuditRecord {
+                time: next_time,
+                action: BanAuditAction::Unban,
+                target: BanAuditTarget::Subnet(subnet("169.202.0.1")),
+                reason: None,
+                expiry: None,
+            },
+            BanAuditRecord {
+                time: next_time,
+                action: BanAuditAction::Unban,
+                target: BanAuditTarget::Node(node_id),
+                reason: None,
+                expiry: None,
+            },
+            BanAuditRecord {
+                time: next_time,
+                action: BanAuditAction::Unban,
+                target: BanAuditTarget::Subnet(subnet("169.203.0.0/16")),
+                reason: None,
+                expiry: None,
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn test_flush_and_reload_peers_file() {
+    let initial_peers_file = super::tools::generate_peers_file(&[]);
+    let peers_dir = tempfile::tempdir().unwrap();
+    let network_settings = NetworkConfig {
+        initial_peers_file: initial_peers_file.path().to_path_buf(),
+        peers_file: peers_dir.path().join("peers.json"),
+        peers_file_dump_interval: MassaTime::from_millis(3_600_000),
+        ..Default::default()
+    };
+    let ip = |ip: &str| IpAddr::from_str(ip).unwrap();
+    let mut db = PeerInfoDatabase::new(&network_settings, Box::new(SystemClock))
+        .await
+        .unwrap();
+
+    // the flush writes the file without waiting for the dump interval
+    db.merge_candidate_peers(&[AdvertisedPeer::from(SocketAddr::new(
+        ip("169.202.0.10"),
+        31244,
+    ))])
+    .unwrap();
+    let (response_tx, response_rx) = tokio::sync::oneshot::channel();
+    db.flush(response_tx).unwrap();
+    response_rx.await.unwrap().unwrap();
+    let content = PeersFileContent::load(&network_settings.peers_file, None)
+        .await
+        .unwrap();
+    assert_eq!(content.peers.len(), 1);
+    assert_eq!(content.peers[0].ip, ip("169.202.0.10"));
+
+    // the edits of the file are merged with the known peers and bans
+    let known_peer = db.peers[&ip("169.202.0.10")].clone();
+    db.new_out_connection_attempt(&known_peer.ip).unwrap();
+    let mut whitelisted_peer = known_peer;
+    whitelisted_peer.peer_type = PeerType::WhiteListed;
+    PeersFileContent {
+        peers: vec![whitelisted_peer, PeerInfo::new(ip("169.203.0.10"), true)],
+        bans: vec![BanListEntry {
+            target: IpSubnet::from_str("169.204.0.0/16").unwrap(),
+            reason: Some("edited".into()),
+            expiry: None,
+            banned_at: None,
+        }],
+    }
+    .dump(&network_settings.peers_file, Default::default(), None)
+    .await
+    .unwrap();
+    db.reload_peers_file().await.unwrap();
+    assert_eq!(
+        db.peers[&ip("169.202.0.10")].peer_type,
+        PeerType::WhiteListed
+    );
+    assert_eq!(
+        db.peers[&ip("169.202.0.10")].active_out_connection_attempts,
+        1
+    );
+    assert!(db.peers[&ip("169.203.0.10")].advertised);
+    assert!(db.is_banned(&ip("169.204.1.1")));
+    db.check_invariants().unwrap();
+
+    // the file is missing: nothing changes
+    tokio::fs::remove_file(&network_settings.peers_file)
+        .await
+        .unwrap();
+    assert!(db.reload_peers_file().await.is_err());
+    assert_eq!(db.peers.len(), 2);
+    db.stop().await.unwrap();
+}
+
 fn default_peer_info_connected(ip: IpAddr) -> PeerInfo {
     PeerInfo {
         ip,
+        port: None,
+        other_addresses: Vec::new(),
         peer_type: PeerType::Standard,
         last_alive: None,
         last_failure: None,
@@ -803,12 +1462,19 @@ fn default_peer_info_connected(ip: IpAddr) -> PeerInfo {
         active_out_connections: 1,
         active_in_connections: 0,
         banned: false,
+        ban_reason: None,
+        banned_at: None,
+        node_id: None,
+        version: None,
+        reputation: 0,
     }
 }
 
 fn default_peer_info_not_connected(ip: IpAddr) -> PeerInfo {
     PeerInfo {
         ip,
+        port: None,
+        other_addresses: Vec::new(),
         peer_type: PeerType::Standard,
         last_alive: None,
         last_failure: None,
@@ -817,6 +1483,11 @@ fn default_peer_info_not_connected(ip: IpAddr) -> PeerInfo {
         active_out_connections: 0,
         active_in_connections: 0,
         banned: false,
+        ban_reason: None,
+        banned_at: None,
+        node_id: None,
+        version: None,
+        reputation: 0,
     }
 }
 
@@ -829,6 +1500,8 @@ impl From<u32> for PeerInfoDatabase {
             let ip: [u8; 4] = [rng.gen(), rng.gen(), rng.gen(), rng.gen()];
             let peer = PeerInfo {
                 ip: IpAddr::from(ip),
+                port: None,
+                other_addresses: Vec::new(),
                 peer_type: match ip[0] % 5 {
                     0 | 1 => PeerType::Bootstrap,
                     2 | 3 | 4 => PeerType::Standard,
@@ -847,20 +1520,15 @@ impl From<u32> for PeerInfoDatabase {
                 active_out_connections: 0,
                 active_in_connections: 0,
                 banned: ip[1] % 5 == 0,
+                ban_reason: None,
+                banned_at: None,
+                node_id: None,
+                version: None,
+                reputation: 0,
             };
             peers.insert(peer.ip, peer);
         }
         let network_settings = NetworkConfig::default();
-        let wakeup_interval = network_settings.wakeup_interval;
-        let (saver_watch_tx, _) = watch::channel(peers.clone());
-        let saver_join_handle = tokio::spawn(async move {});
-        PeerInfoDatabase {
-            network_settings,
-            peers,
-            saver_join_handle,
-            saver_watch_tx,
-            peer_types_connection_count: Default::default(),
-            wakeup_interval,
-        }
+        PeerInfoDatabase::from_peers(&network_settings, peers, Box::new(SystemClock))
     }
 }
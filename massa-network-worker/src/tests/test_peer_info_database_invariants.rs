@@ -0,0 +1,161 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Random sequences of connection events applied to a `PeerInfoDatabase`,
+//! checking its invariants after each one.
+
+use crate::{
+    peer_info_database::{PeerInfoDatabase, PeersSnapshot},
+    NetworkConfig,
+};
+use enum_map::enum_map;
+use im::HashMap;
+use massa_network_exports::{
+    settings::PeerTypeConnectionConfig, AdvertisedPeer, BanReason, PeerInfo, PeerType,
+};
+use massa_time::SystemClock;
+use proptest::prelude::*;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use tokio::sync::watch;
+
+/// Number of global ips the events pick from, a non-global one is added after them
+const IP_COUNT: u8 = 6;
+
+/// Connection event, the `u8` being the index of the ip it concerns
+#[derive(Debug, Clone)]
+enum Event {
+    OutAttempt(u8),
+    OutSuccess(u8),
+    OutFailure(u8),
+    OutClosed(u8),
+    InConnection(u8),
+    InClosed(u8),
+    Banned(u8),
+    Unbanned(u8),
+    Merge(Vec<u8>),
+    Update,
+}
+
+impl Event {
+    /// ip whose counts the event changes
+    fn target(&self) -> Option<IpAddr> {
+        match self {
+            Event::OutAttempt(i)
+            | Event::OutSuccess(i)
+            | Event::OutFailure(i)
+            | Event::OutClosed(i)
+            | Event::InConnection(i)
+            | Event::InClosed(i)
+            | Event::Banned(i)
+            | Event::Unbanned(i) => Some(ip(*i)),
+            Event::Merge(_) | Event::Update => None,
+        }
+    }
+}
+
+fn ip(index: u8) -> IpAddr {
+    if index < IP_COUNT {
+        IpAddr::V4(Ipv4Addr::new(169, 202, 0, index))
+    } else {
+        IpAddr::V4(Ipv4Addr::new(192, 168, 0, 1))
+    }
+}
+
+fn event_strategy() -> impl Strategy<Value = Event> {
+    let index = 0..=IP_COUNT;
+    prop_oneof![
+        3 => index.clone().prop_map(Event::OutAttempt),
+        3 => index.clone().prop_map(Event::OutSuccess),
+        2 => index.clone().prop_map(Event::OutFailure),
+        2 => index.clone().prop_map(Event::OutClosed),
+        3 => index.clone().prop_map(Event::InConnection),
+        2 => index.clone().prop_map(Event::InClosed),
+        1 => index.clone().prop_map(Event::Banned),
+        1 => index.clone().prop_map(Event::Unbanned),
+        1 => prop::collection::vec(index, 0..4).prop_map(Event::Merge),
+        1 => Just(Event::Update),
+    ]
+}
+
+/// Database without peers and with small limits, so that they are reached by the events
+pub(super) fn new_database() -> (PeerInfoDatabase, watch::Receiver<PeersSnapshot>) {
+    let network_settings = NetworkConfig {
+        peer_types_config: enum_map! {
+            PeerType::Standard => PeerTypeConnectionConfig {
+                target_out_connections: 2,
+                max_out_attempts: 3,
+                max_in_connections: 3,
+            },
+            PeerType::Bootstrap => Default::default(),
+            PeerType::WhiteListed => Default::default(),
+        },
+        routable_ip: None,
+        observer: false,
+        max_in_connections_per_ip: 2,
+        max_idle_peers: 2,
+        max_banned_peers: 1,
+        ..Default::default()
+    };
+    let db = PeerInfoDatabase::from_peers(&network_settings, HashMap::new(), Box::new(SystemClock));
+    let saver_watch_rx = db.saver_watch_tx.subscribe();
+    (db, saver_watch_rx)
+}
+
+/// Apply the event, the refused ones returning an error
+fn apply(db: &mut PeerInfoDatabase, event: &Event) {
+    let _ = match event {
+        Event::OutAttempt(i) => db.new_out_connection_attempt(&ip(*i)),
+        Event::OutSuccess(i) => db.try_out_connection_attempt_success(&ip(*i)).map(|_| ()),
+        Event::OutFailure(i) => db.out_connection_attempt_failed(&ip(*i)),
+        Event::OutClosed(i) => db.out_connection_closed(&ip(*i)),
+        Event::InConnection(i) => db.try_new_in_connection(&ip(*i)),
+        Event::InClosed(i) => db.in_connection_closed(&ip(*i)),
+        Event::Banned(i) => db.peer_banned(&ip(*i), BanReason::Misbehavior),
+        Event::Unbanned(i) => db.unban(vec![ip(*i)]),
+        Event::Merge(indexes) => {
+            let new_peers: Vec<AdvertisedPeer> = indexes
+                .iter()
+                .map(|i| AdvertisedPeer::from(SocketAddr::new(ip(*i), 31244)))
+                .collect();
+            db.merge_candidate_peers(&new_peers)
+        }
+        Event::Update => db.update(),
+    };
+}
+
+fn connection_counts(peer: &PeerInfo) -> (usize, usize, usize) {
+    (
+        peer.active_out_connection_attempts,
+        peer.active_out_connections,
+        peer.active_in_connections,
+    )
+}
+
+proptest! {
+    #[test]
+    fn test_peer_info_database_invariants(events in prop::collection::vec(event_strategy(), 1..60)) {
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let _guard = runtime.enter();
+        let (mut db, _saver_watch_rx) = new_database();
+        for event in events.iter() {
+            let active_peers = db.get_active_peers();
+            apply(&mut db, event);
+            if let Err(err) = db.check_invariants() {
+                return Err(TestCaseError::fail(format!("after {:?}: {}", event, err)));
+            }
+            // only the target of the event can have its connections changed,
+            // and no cleanup can drop the others
+            for (peer_ip, peer) in active_peers {
+                if Some(peer_ip) == event.target() {
+                    continue;
+                }
+                prop_assert_eq!(
+                    db.peers.get(&peer_ip).map(connection_counts),
+                    Some(connection_counts(&peer)),
+                    "active peer {} changed after {:?}",
+                    peer_ip,
+                    event
+                );
+            }
+        }
+    }
+}
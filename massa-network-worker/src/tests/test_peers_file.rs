@@ -0,0 +1,255 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use crate::peers_file::{PeersFileContent, ENCRYPTED_PEERS_FILE_MAGIC};
+use massa_models::ban_list::{BanListEntry, IpSubnet};
+use massa_network_exports::settings::PeersFileFormat;
+use massa_network_exports::{NetworkError, PeerInfo, PeerType};
+use massa_time::MassaTime;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+
+fn peer(
+    ip: &str,
+    port: Option<u16>,
+    other_addresses: &[&str],
+    peer_type: PeerType,
+    banned: bool,
+) -> PeerInfo {
+    PeerInfo {
+        ip: IpAddr::from_str(ip).unwrap(),
+        port,
+        other_addresses: other_addresses
+            .iter()
+            .map(|addr| SocketAddr::from_str(addr).unwrap())
+            .collect(),
+        peer_type,
+        last_alive: Some(MassaTime::from_millis(1_660_000_000_000)),
+        last_failure: None,
+        advertised: true,
+        banned,
+        ban_reason: banned.then(|| "banned for misbehaving".into()),
+        banned_at: banned.then(|| MassaTime::from_millis(1_660_000_100_000)),
+        node_id: None,
+        version: None,
+        reputation: 0,
+        active_out_connection_attempts: 0,
+        active_out_connections: 0,
+        active_in_connections: 0,
+    }
+}
+
+fn content() -> PeersFileContent {
+    PeersFileContent {
+        peers: vec![
+            peer(
+                "169.202.0.11",
+                Some(31244),
+                &["[2001:db8::2]:31244", "169.202.0.11:31245"],
+                PeerType::Standard,
+                false,
+            ),
+            peer("169.202.0.12", None, &[], PeerType::Bootstrap, true),
+            peer(
+                "2001:db8::1",
+                Some(41244),
+                &[],
+                PeerType::WhiteListed,
+                false,
+            ),
+        ],
+        bans: vec![
+            BanListEntry {
+                target: IpSubnet::from_str("169.203.0.0/16").unwrap(),
+                reason: Some("spam, \"flood\"".into()),
+                expiry: Some(MassaTime::from_millis(1_670_000_000_000)),
+                banned_at: Some(MassaTime::from_millis(1_660_000_000_000)),
+            },
+            BanListEntry {
+                target: IpSubnet::from_str("169.204.0.1").unwrap(),
+                reason: None,
+                expiry: None,
+                banned_at: None,
+            },
+        ],
+    }
+}
+
+/// Fields of the peers that are saved
+fn saved_fields(
+    content: &PeersFileContent,
+) -> Vec<(
+    IpAddr,
+    Option<u16>,
+    Vec<SocketAddr>,
+    PeerType,
+    Option<MassaTime>,
+    Option<MassaTime>,
+    bool,
+    bool,
+    Option<String>,
+    Option<MassaTime>,
+)> {
+    content
+        .peers
+        .iter()
+        .map(|p| {
+            (
+                p.ip,
+                p.port,
+                p.other_addresses.clone(),
+                p.peer_type,
+                p.last_alive,
+                p.last_failure,
+                p.advertised,
+                p.banned,
+                p.ban_reason.clone(),
+                p.banned_at,
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_peers_file_formats_round_trip() {
+    let content = content();
+    for format in [
+        PeersFileFormat::Json,
+        PeersFileFormat::Csv,
+        PeersFileFormat::Binary,
+    ] {
+        let data = content.to_bytes(format).unwrap();
+        let read = PeersFileContent::from_bytes(&data).unwrap();
+        assert_eq!(saved_fields(&read), saved_fields(&content), "{:?}", format);
+        assert_eq!(read.bans, content.bans, "{:?}", format);
+    }
+}
+
+#[test]
+fn test_peers_file_format_detection() {
+    // array of peers, as in the initial peers file
+    let data = serde_json::to_vec(&content().peers).unwrap();
+    let read = PeersFileContent::from_bytes(&data).unwrap();
+    assert_eq!(saved_fields(&read), saved_fields(&content()));
+    assert!(read.bans.is_empty());
+
+    // empty file written as CSV without any peer
+    let read = PeersFileContent::from_bytes(
+        &PeersFileContent::default()
+            .to_bytes(PeersFileFormat::Csv)
+            .unwrap(),
+    )
+    .unwrap();
+    assert!(read.peers.is_empty() && read.bans.is_empty());
+
+    // truncated binary file
+    let data = content().to_bytes(PeersFileFormat::Binary).unwrap();
+    assert!(matches!(
+        PeersFileContent::from_bytes(&data[..data.len() - 1]),
+        Err(NetworkError::PeersFileError(_))
+    ));
+
+    // a peer ip cannot be a subnet, the port column of the previous versions being optional
+    let data = b"record,target,peer_type,last_alive,last_failure,advertised,banned,reason,expiry\n\
+        peer,169.202.0.0/24,Standard,,,true,false,,\n";
+    assert!(matches!(
+        PeersFileContent::from_bytes(data),
+        Err(NetworkError::PeersFileError(_))
+    ));
+}
+
+#[test]
+fn test_peers_file_of_previous_versions() {
+    // binary file of the version 1 encoding, whose peers have no port
+    let mut data = b"MASSA_PEERS\x01".to_vec();
+    data.extend([1, 4, 169, 202, 0, 11, 1, b'0', b'0', 1, 0]);
+    let read = PeersFileContent::from_bytes(&data).unwrap();
+    assert_eq!(read.peers.len(), 1);
+    assert_eq!(read.peers[0].ip, IpAddr::from_str("169.202.0.11").unwrap());
+    assert_eq!(read.peers[0].port, None);
+    assert_eq!(read.peers[0].peer_type, PeerType::WhiteListed);
+    assert!(read.peers[0].advertised);
+
+    // binary file of the version 2 encoding, whose peers have no other addresses
+    let mut data = b"MASSA_PEERS\x02".to_vec();
+    data.extend([1, 4, 169, 202, 0, 11, b'0', 1, b'0', b'0', 1, 0]);
+    let read = PeersFileContent::from_bytes(&data).unwrap();
+    assert_eq!(read.peers.len(), 1);
+    assert_eq!(read.peers[0].port, None);
+    assert!(read.peers[0].other_addresses.is_empty());
+    assert_eq!(read.peers[0].peer_type, PeerType::WhiteListed);
+
+    // binary file of the version 3 encoding, whose peers have no ban reason and whose bans have no time
+    let mut data = b"MASSA_PEERS\x03".to_vec();
+    data.extend([1, 4, 169, 202, 0, 11, b'0', 0, 1, b'0', b'0', 3, 1]);
+    data.extend([4, 169, 203, 0, 0, 16, b'0', b'0']);
+    let read = PeersFileContent::from_bytes(&data).unwrap();
+    assert_eq!(read.peers.len(), 1);
+    assert!(read.peers[0].banned);
+    assert_eq!(read.peers[0].ban_reason, None);
+    assert_eq!(read.peers[0].banned_at, None);
+    assert_eq!(read.bans.len(), 1);
+    assert_eq!(
+        read.bans[0].target,
+        IpSubnet::from_str("169.203.0.0/16").unwrap()
+    );
+    assert_eq!(read.bans[0].banned_at, None);
+
+    // JSON and CSV files of the previous versions, without the port, the other addresses nor the ban time of the peers
+    let data = br#"[{"ip": "169.202.0.11", "peer_type": "Standard", "last_alive": null,
+        "last_failure": null, "advertised": true, "banned": false}]"#;
+    let read = PeersFileContent::from_bytes(data).unwrap();
+    assert_eq!(read.peers[0].port, None);
+    assert!(read.peers[0].other_addresses.is_empty());
+    let data = b"record,target,peer_type,last_alive,last_failure,advertised,banned,reason,expiry\n\
+        peer,169.202.0.11,Standard,,,true,false,,\n";
+    let read = PeersFileContent::from_bytes(data).unwrap();
+    assert_eq!(read.peers[0].port, None);
+    assert!(read.peers[0].other_addresses.is_empty());
+    assert_eq!(read.peers[0].banned_at, None);
+
+    // unknown version of the binary encoding
+    let mut data = b"MASSA_PEERS\x05".to_vec();
+    data.extend([0, 0]);
+    assert!(PeersFileContent::from_bytes(&data).is_err());
+}
+
+#[tokio::test]
+async fn test_encrypted_peers_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("peers.json");
+    content()
+        .dump(&path, PeersFileFormat::Json, Some("peers password"))
+        .await
+        .unwrap();
+    let data = tokio::fs::read(&path).await.unwrap();
+    assert!(data.starts_with(ENCRYPTED_PEERS_FILE_MAGIC));
+    assert!(
+        !String::from_utf8_lossy(&data).contains("169.202.0.11"),
+        "the peers are written in clear"
+    );
+
+    // the file is only read with the right password
+    let read = PeersFileContent::load(&path, Some("peers password"))
+        .await
+        .unwrap();
+    assert_eq!(saved_fields(&read), saved_fields(&content()));
+    assert_eq!(read.bans, content().bans);
+    assert!(matches!(
+        PeersFileContent::load(&path, Some("wrong password")).await,
+        Err(NetworkError::PeersFileError(_))
+    ));
+    assert!(matches!(
+        PeersFileContent::load(&path, None).await,
+        Err(NetworkError::PeersFileError(_))
+    ));
+
+    // a clear file is still read when a password is set
+    content()
+        .dump(&path, PeersFileFormat::Csv, None)
+        .await
+        .unwrap();
+    let read = PeersFileContent::load(&path, Some("peers password"))
+        .await
+        .unwrap();
+    assert_eq!(saved_fields(&read), saved_fields(&content()));
+}
@@ -20,11 +20,13 @@ use massa_models::{
     version::Version,
 };
 use massa_network_exports::test_exports::mock_establisher::{self, MockEstablisherInterface};
+use massa_network_exports::test_exports::tools::get_test_network_id;
 use massa_network_exports::{
-    ConnectionId, NetworkCommandSender, NetworkEventReceiver, NetworkManager, PeerInfo,
+    AdvertisedPeer, ConnectionId, NetworkCommandSender, NetworkEventReceiver, NetworkManager,
+    PeerInfo,
 };
 use massa_signature::KeyPair;
-use massa_time::MassaTime;
+use massa_time::{Clock, MassaTime, SystemClock};
 use std::str::FromStr;
 use std::{
     future::Future,
@@ -91,9 +93,12 @@ pub async fn full_connection_to_controller(
         keypair,
         rw_timeout_ms.into(),
         Version::from_str("TEST.1.10").unwrap(),
+        get_test_network_id(),
+        false,
         connection_id,
         f64::INFINITY,
         f64::INFINITY,
+        false,
     )
     .await
     .expect("handshake creation failed")
@@ -105,7 +110,9 @@ pub async fn full_connection_to_controller(
         network_event_receiver,
         event_timeout_ms.into(),
         |msg| match msg {
-            NetworkEvent::NewConnection(conn_node_id) => {
+            NetworkEvent::NewConnection {
+                node: conn_node_id, ..
+            } => {
                 if conn_node_id == mock_node_id {
                     Some(())
                 } else {
@@ -150,9 +157,12 @@ pub async fn rejected_connection_to_controller(
         keypair,
         rw_timeout_ms.into(),
         Version::from_str("TEST.1.10").unwrap(),
+        get_test_network_id(),
+        false,
         connection_id,
         f64::INFINITY,
         f64::INFINITY,
+        false,
     )
     .await
     .expect("handshake creation failed")
@@ -169,7 +179,9 @@ pub async fn rejected_connection_to_controller(
         network_event_receiver,
         event_timeout_ms.into(),
         |msg| match msg {
-            NetworkEvent::NewConnection(conn_node_id) => {
+            NetworkEvent::NewConnection {
+                node: conn_node_id, ..
+            } => {
                 if conn_node_id == mock_node_id {
                     Some(())
                 } else {
@@ -235,9 +247,12 @@ pub async fn full_connection_from_controller(
         keypair,
         rw_timeout_ms.into(),
         Version::from_str("TEST.1.10").unwrap(),
+        get_test_network_id(),
+        false,
         connection_id,
         f64::INFINITY,
         f64::INFINITY,
+        false,
     )
     .await
     .expect("handshake creation failed")
@@ -249,7 +264,7 @@ pub async fn full_connection_from_controller(
         network_event_receiver,
         event_timeout_ms.into(),
         |evt| match evt {
-            NetworkEvent::NewConnection(node_id) => {
+            NetworkEvent::NewConnection { node: node_id, .. } => {
                 if node_id == mock_node_id {
                     Some(())
                 } else {
@@ -309,7 +324,10 @@ pub async fn incoming_message_drain_start(
     (join_handle, stop_tx)
 }
 
-pub async fn advertise_peers_in_connection(write_binder: &mut WriteBinder, peer_list: Vec<IpAddr>) {
+pub async fn advertise_peers_in_connection(
+    write_binder: &mut WriteBinder,
+    peer_list: Vec<AdvertisedPeer>,
+) {
     write_binder
         .send(&Message::PeerList(peer_list))
         .await
@@ -362,6 +380,37 @@ pub async fn network_test<F, V>(
             Vec<(JoinHandle<ReadBinder>, oneshot::Sender<()>)>,
         ),
     >,
+{
+    network_test_with_clock(
+        network_settings,
+        temp_peers_file,
+        Box::new(SystemClock),
+        test,
+    )
+    .await
+}
+
+/// Runs a network test like `network_test`, the network reading the time from `clock`.
+pub async fn network_test_with_clock<F, V>(
+    network_settings: NetworkConfig,
+    temp_peers_file: NamedTempFile,
+    clock: Box<dyn Clock>,
+    test: F,
+) where
+    F: FnOnce(
+        NetworkCommandSender,
+        NetworkEventReceiver,
+        NetworkManager,
+        MockEstablisherInterface,
+    ) -> V,
+    V: Future<
+        Output = (
+            NetworkEventReceiver,
+            NetworkManager,
+            MockEstablisherInterface,
+            Vec<(JoinHandle<ReadBinder>, oneshot::Sender<()>)>,
+        ),
+    >,
 {
     // create establisher
     let (establisher, mock_interface) = mock_establisher::new();
@@ -369,9 +418,11 @@ pub async fn network_test<F, V>(
     let (network_event_sender, network_event_receiver, network_manager, _keypair, _node_id) =
         start_network_controller(
             &network_settings,
+            KeyPair::generate(),
             establisher,
             None,
             Version::from_str("TEST.1.10").unwrap(),
+            clock,
         )
         .await
         .expect("could not start network controller");
@@ -0,0 +1,133 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Historical ledger states of an archival node.
+//!
+//! Every `check_interval` the archiver looks at the cycle of the final state. The first time it sees a cycle
+//! multiple of `snapshot_interval_cycles` without a snapshot, the final state is exported to the snapshots
+//! directory of the archive, in the format of the bootstrap snapshots so that a node can be started from it.
+//! When `retention_cycles` is not 0, the snapshots older than `retention_cycles` cycles are pruned,
+//! the archived blocks being pruned by the archive maintenance.
+//! The hash of the final state is also recorded at every check, so that a replay of the archived blocks
+//! (`--replay`) can be verified against it.
+
+use crate::settings::ArchiveSettings;
+use massa_bootstrap::export_snapshot;
+use massa_consensus_exports::ConsensusController;
+use massa_final_state::FinalState;
+use massa_models::{config::PERIODS_PER_CYCLE, version::Version};
+use massa_storage::archive::Archive;
+use parking_lot::RwLock;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+/// Takes the snapshots of the final state and prunes the archive
+pub struct Archiver {
+    settings: ArchiveSettings,
+    archive: Archive,
+    final_state: Arc<RwLock<FinalState>>,
+    consensus_controller: Box<dyn ConsensusController>,
+    version: Version,
+}
+
+impl Archiver {
+    /// Create the archiver of `archive`
+    pub fn new(
+        settings: ArchiveSettings,
+        archive: Archive,
+        final_state: Arc<RwLock<FinalState>>,
+        consensus_controller: Box<dyn ConsensusController>,
+        version: Version,
+    ) -> Self {
+        Archiver {
+            settings,
+            archive,
+            final_state,
+            consensus_controller,
+            version,
+        }
+    }
+
+    /// Spawn the task checking the archive every `check_interval`
+    pub fn start(self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.settings.check_interval.to_duration());
+            loop {
+                interval.tick().await;
+                let cycle = self.final_state.read().slot.get_cycle(PERIODS_PER_CYCLE);
+                self.record_state_hash();
+                self.snapshot(cycle).await;
+                self.prune(cycle);
+            }
+        })
+    }
+
+    /// Record the hash of the final state with its slot
+    fn record_state_hash(&self) {
+        let (slot, hash) = {
+            let final_state = self.final_state.read();
+            (final_state.slot, final_state.final_state_hash)
+        };
+        // the genesis state is rebuilt by the replay, not checked
+        if slot.period == 0 {
+            return;
+        }
+        if let Err(err) = self.archive.store_state_hash(slot, &hash) {
+            warn!(
+                "could not record the final state hash of slot {}: {}",
+                slot, err
+            );
+        }
+    }
+
+    /// Export the final state if `cycle` needs a snapshot that was not taken yet
+    async fn snapshot(&self, cycle: u64) {
+        if cycle % self.settings.snapshot_interval_cycles.max(1) != 0 {
+            return;
+        }
+        let path = self.archive.snapshot_path(cycle);
+        if path.exists() {
+            return;
+        }
+        let final_state = self.final_state.clone();
+        let consensus_controller = self.consensus_controller.clone();
+        let version = self.version;
+        let result = tokio::task::spawn_blocking(move || {
+            export_snapshot(&path, &final_state, consensus_controller.as_ref(), version)
+        })
+        .await;
+        match result {
+            Ok(Ok(slot)) => info!(
+                "archived the final state of slot {} as the snapshot of cycle {}",
+                slot, cycle
+            ),
+            Ok(Err(err)) => warn!("could not archive the snapshot of cycle {}: {}", cycle, err),
+            Err(err) => warn!("archive snapshot export panicked: {}", err),
+        }
+    }
+
+    /// Remove the snapshots older than `retention_cycles` cycles before `cycle`
+    fn prune(&self, cycle: u64) {
+        if self.settings.retention_cycles == 0 {
+            return;
+        }
+        let first_kept_cycle = match cycle.checked_sub(self.settings.retention_cycles) {
+            Some(first_kept_cycle) if first_kept_cycle > 0 => first_kept_cycle,
+            _ => return,
+        };
+        match self.archive.get_snapshot_cycles() {
+            Ok(cycles) => {
+                for old_cycle in cycles.into_iter().filter(|c| *c < first_kept_cycle) {
+                    match self.archive.remove_snapshot(old_cycle) {
+                        Ok(()) => debug!("removed the archived snapshot of cycle {}", old_cycle),
+                        Err(err) => warn!(
+                            "could not remove the archived snapshot of cycle {}: {}",
+                            old_cycle, err
+                        ),
+                    }
+                }
+            }
+            Err(err) => warn!("could not list the archived snapshots: {}", err),
+        }
+    }
+}
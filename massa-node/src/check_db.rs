@@ -0,0 +1,48 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Integrity check of the archive and of the address index, run instead of the node with `--check-db`
+//! or `--repair-db`. The same check can be run on a live node through the private API.
+
+use crate::open_archive;
+use crate::settings::SETTINGS;
+use massa_storage::{address_index::AddressIndex, check::check_storage};
+use tracing::info;
+
+/// Check the storage enabled in the configuration, removing the corrupted entries if `repair` is set.
+/// Fails if corrupted entries were found and not removed.
+pub fn check_db(repair: bool) -> anyhow::Result<()> {
+    if !SETTINGS.archive.enabled && !SETTINGS.address_index.enabled {
+        anyhow::bail!(
+            "neither the archive nor the address index is enabled, there is nothing to check"
+        );
+    }
+    let archive = if SETTINGS.archive.enabled {
+        info!(
+            "checking the archive in {}",
+            SETTINGS.archive.path.display()
+        );
+        Some(open_archive()?)
+    } else {
+        None
+    };
+    let address_index = if SETTINGS.address_index.enabled {
+        info!(
+            "checking the address index in {}",
+            SETTINGS.address_index.path.display()
+        );
+        Some(
+            AddressIndex::open(&SETTINGS.address_index.path)
+                .map_err(|err| anyhow::anyhow!("could not open the address index: {}", err))?,
+        )
+    } else {
+        None
+    };
+
+    let report = check_storage(archive.as_ref(), address_index.as_ref(), repair)
+        .map_err(|err| anyhow::anyhow!("storage check failed: {}", err))?;
+    info!("storage check finished:\n{}", report);
+    if !report.is_healthy() && !repair {
+        anyhow::bail!("corrupted entries found, run the node with --repair-db to remove them");
+    }
+    Ok(())
+}
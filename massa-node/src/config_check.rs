@@ -0,0 +1,264 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Cross-checks of the node settings, run before any worker starts.
+//!
+//! Every problem is collected so that they can all be reported at once,
+//! instead of failing on the first one somewhere inside a worker.
+
+use crate::settings::{ArchiveSettings, NetworkSettings, Settings};
+use massa_models::config::MAX_BLOCK_RANGE_LENGTH;
+use massa_network_exports::PeerType;
+use std::collections::HashMap;
+use std::fs::{remove_file, OpenOptions};
+use std::net::{SocketAddr, TcpListener};
+use std::path::Path;
+
+/// Check the settings, returning a description of each problem found
+pub fn check_settings(settings: &Settings) -> Vec<String> {
+    let mut problems = check_connection_limits(&settings.network);
+    problems.extend(check_files(settings));
+    problems.extend(check_bind_addresses(settings));
+    problems.extend(check_archive(&settings.archive));
+    problems.extend(check_trustless_sync(settings));
+    problems
+}
+
+/// Check that the connection limits of the network settings are consistent
+pub fn check_connection_limits(network: &NetworkSettings) -> Vec<String> {
+    let mut problems = Vec::new();
+    for (peer_type, config) in network.peer_types_config.iter() {
+        if config.target_out_connections > config.max_out_attempts {
+            problems.push(format!(
+                "network.peer_types_config.{}: target_out_connections ({}) is greater than max_out_attempts ({}), the target can never be reached: lower target_out_connections or raise max_out_attempts",
+                peer_type, config.target_out_connections, config.max_out_attempts
+            ));
+        }
+    }
+    let max_in_connections: usize = network
+        .peer_types_config
+        .values()
+        .map(|config| config.max_in_connections)
+        .sum();
+    if network.max_in_connections_per_ip > max_in_connections {
+        problems.push(format!(
+            "network: max_in_connections_per_ip ({}) is greater than the sum of the max_in_connections of all peer types ({}): lower max_in_connections_per_ip",
+            network.max_in_connections_per_ip, max_in_connections
+        ));
+    }
+    if network.max_out_attempts_per_minute > 0 && network.max_out_attempts_burst == 0 {
+        problems.push(format!(
+            "network: max_out_attempts_burst is 0 with a budget of {} max_out_attempts_per_minute, no out connection could ever be attempted: raise max_out_attempts_burst or set max_out_attempts_per_minute to 0 for no budget",
+            network.max_out_attempts_per_minute
+        ));
+    }
+    problems
+}
+
+/// Check that the archive settings can be applied
+fn check_archive(archive: &ArchiveSettings) -> Vec<String> {
+    let mut problems = Vec::new();
+    if !archive.enabled {
+        return problems;
+    }
+    if archive.snapshot_interval_cycles == 0 {
+        problems.push("archive.snapshot_interval_cycles: must be at least 1".to_string());
+    }
+    if archive.max_blocks_per_range == 0
+        || archive.max_blocks_per_range > MAX_BLOCK_RANGE_LENGTH as usize
+    {
+        problems.push(format!(
+            "archive.max_blocks_per_range: must be between 1 and {}, the maximum length of a block range message",
+            MAX_BLOCK_RANGE_LENGTH
+        ));
+    }
+    if let Err(err) = check_writable(&archive.path) {
+        problems.push(format!("archive.path: {}", err));
+    }
+    problems
+}
+
+/// Check that the trustless sync can keep the full history it downloads
+fn check_trustless_sync(settings: &Settings) -> Vec<String> {
+    let mut problems = Vec::new();
+    if !settings.bootstrap.trustless_sync {
+        return problems;
+    }
+    if !settings.archive.enabled {
+        problems.push(
+            "bootstrap.trustless_sync: the downloaded blocks are kept in the archive, set archive.enabled"
+                .to_string(),
+        );
+    }
+    if settings.archive.retention_cycles != 0 || settings.archive.body_retention_cycles != 0 {
+        problems.push("bootstrap.trustless_sync: the final state is rebuilt from the genesis, set archive.retention_cycles and archive.body_retention_cycles to 0".to_string());
+    }
+    if settings
+        .bootstrap
+        .trustless_sync_request_timeout
+        .to_millis()
+        == 0
+    {
+        problems.push("bootstrap.trustless_sync_request_timeout: must be at least 1".to_string());
+    }
+    problems
+}
+
+/// Check that the files read at startup exist and that the files written by the node can be created
+fn check_files(settings: &Settings) -> Vec<String> {
+    let mut problems = Vec::new();
+    let read_files = [
+        (
+            "network.initial_peers_file",
+            &settings.network.initial_peers_file,
+        ),
+        ("api.openrpc_spec_path", &settings.api.openrpc_spec_path),
+        (
+            "bootstrap.bootstrap_whitelist_path",
+            &settings.bootstrap.bootstrap_whitelist_path,
+        ),
+        (
+            "bootstrap.bootstrap_blacklist_path",
+            &settings.bootstrap.bootstrap_blacklist_path,
+        ),
+    ];
+    for (name, path) in read_files {
+        if !path.is_file() {
+            problems.push(format!(
+                "{}: file {} not found, check the path (relative paths start from the working directory of the node)",
+                name,
+                path.display()
+            ));
+        }
+    }
+    if let Some(path) = &settings.bootstrap.snapshot_import_path {
+        if !path.is_file() {
+            problems.push(format!(
+                "bootstrap.snapshot_import_path: file {} not found, fix the path or comment the setting to bootstrap from the network",
+                path.display()
+            ));
+        }
+    }
+    let written_files = [
+        ("network.peers_file", &settings.network.peers_file),
+        ("network.keypair_file", &settings.network.keypair_file),
+        ("ledger.disk_ledger_path", &settings.ledger.disk_ledger_path),
+        (
+            "bootstrap.server_stats_path",
+            &settings.bootstrap.server_stats_path,
+        ),
+    ];
+    for (name, path) in written_files {
+        if let Err(err) = check_writable(path) {
+            problems.push(format!("{}: {}", name, err));
+        }
+    }
+    if let Some(path) = &settings.bootstrap.shutdown_snapshot_path {
+        if let Err(err) = check_writable(path) {
+            problems.push(format!("bootstrap.shutdown_snapshot_path: {}", err));
+        }
+    }
+    if settings.address_index.enabled {
+        if let Err(err) = check_writable(&settings.address_index.path) {
+            problems.push(format!("address_index.path: {}", err));
+        }
+    }
+    problems
+}
+
+/// Check that `path` can be written, or created in its closest existing parent directory.
+/// If `path` is a directory, check that files can be created in it.
+fn check_writable(path: &Path) -> Result<(), String> {
+    if path.is_file() {
+        return OpenOptions::new()
+            .append(true)
+            .open(path)
+            .map(|_| ())
+            .map_err(|err| format!("file {} is not writable: {}", path.display(), err));
+    }
+    let dir = if path.is_dir() {
+        path
+    } else {
+        path.ancestors()
+            .skip(1)
+            .find(|ancestor| ancestor.is_dir())
+            .unwrap_or_else(|| Path::new("."))
+    };
+    let probe = dir.join(".massa_write_check");
+    match OpenOptions::new().write(true).create_new(true).open(&probe) {
+        Ok(_) => {
+            let _ = remove_file(&probe);
+            Ok(())
+        }
+        Err(err) => Err(format!(
+            "cannot create {} because directory {} is not writable: {}",
+            path.display(),
+            dir.display(),
+            err
+        )),
+    }
+}
+
+/// Check that the listening addresses are distinct and free
+fn check_bind_addresses(settings: &Settings) -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut binds: Vec<(&str, SocketAddr)> = vec![
+        ("network.bind", settings.network.bind),
+        ("api.bind_private", settings.api.bind_private),
+        ("api.bind_public", settings.api.bind_public),
+        ("api.bind_api", settings.api.bind_api),
+    ];
+    if let Some(bind) = settings.bootstrap.bind {
+        binds.push(("bootstrap.bind", bind));
+    }
+    if settings.metrics.enabled {
+        binds.push(("metrics.bind", settings.metrics.bind));
+    }
+    let mut by_port: HashMap<u16, &str> = HashMap::new();
+    for (name, addr) in binds {
+        if let Some(other) = by_port.insert(addr.port(), name) {
+            problems.push(format!(
+                "{}: port {} is also used by {}, give each listener its own port",
+                name,
+                addr.port(),
+                other
+            ));
+            continue;
+        }
+        if let Err(err) = TcpListener::bind(addr) {
+            problems.push(format!(
+                "{}: cannot listen on {}: {} (is another node already running?)",
+                name, addr, err
+            ));
+        }
+    }
+    problems
+}
+
+#[test]
+fn test_check_connection_limits() {
+    let mut network = crate::settings::SETTINGS.network.clone();
+    assert!(check_connection_limits(&network).is_empty());
+
+    network.peer_types_config[PeerType::Standard].target_out_connections =
+        network.peer_types_config[PeerType::Standard].max_out_attempts + 1;
+    network.max_in_connections_per_ip = network
+        .peer_types_config
+        .values()
+        .map(|config| config.max_in_connections)
+        .sum::<usize>()
+        + 1;
+    let problems = check_connection_limits(&network);
+    assert_eq!(problems.len(), 2);
+    assert!(problems[0].contains("target_out_connections"));
+    assert!(problems[1].contains("max_in_connections_per_ip"));
+
+    // an empty burst is only allowed without a dial budget
+    let mut network = crate::settings::SETTINGS.network.clone();
+    network.max_out_attempts_burst = 0;
+    network.max_out_attempts_per_minute = 0;
+    assert!(check_connection_limits(&network).is_empty());
+    network.max_out_attempts_per_minute = 1;
+    let problems = check_connection_limits(&network);
+    assert_eq!(problems.len(), 1);
+    assert!(problems[0].contains("max_out_attempts_burst"));
+}
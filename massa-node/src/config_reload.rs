@@ -0,0 +1,283 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Reload of the settings that can be changed without restarting the node.
+//!
+//! A reload is requested by sending `SIGHUP` to the node or through the `node_reload_config`
+//! private API method. The configuration files are read again and the changes to the connection limits,
+//! the peers dump interval, the pool minimal fee and the log levels are pushed to the workers.
+//! The other settings are only read at startup.
+
+use crate::config_check::check_connection_limits;
+use crate::settings::{NetworkSettings, Settings, SETTINGS};
+use massa_logging::{build_log_filter, level_filter_from_config, LogFilterHandle};
+use massa_models::{amount::Amount, config::try_build_massa_settings};
+use massa_network_exports::{NetworkCommandSender, NetworkReloadableConfig};
+use massa_pool_exports::PoolController;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+/// Settings that can be changed while the node is running
+#[derive(Debug, Clone, PartialEq)]
+struct ReloadableSettings {
+    /// connection limits and peers dump interval
+    network: NetworkReloadableConfig,
+    /// minimal fee of the operations accepted in the pool
+    minimal_fee: Amount,
+    /// log level
+    log_level: usize,
+    /// log filter directives
+    log_filters: Vec<String>,
+}
+
+impl From<&Settings> for ReloadableSettings {
+    fn from(settings: &Settings) -> Self {
+        ReloadableSettings {
+            network: get_network_reloadable_config(&settings.network),
+            minimal_fee: settings.pool.minimal_fee,
+            log_level: settings.logging.level,
+            log_filters: settings.logging.filters.clone(),
+        }
+    }
+}
+
+/// Applies the reloadable settings to the running workers
+pub struct ConfigReloader {
+    /// settings currently applied
+    applied: ReloadableSettings,
+    /// link to the network component
+    network_command_sender: NetworkCommandSender,
+    /// link to the pool component
+    pool_controller: Box<dyn PoolController>,
+    /// handle to change the log filter
+    log_filter_handle: LogFilterHandle,
+}
+
+impl ConfigReloader {
+    /// Create a reloader starting from the settings loaded at startup
+    pub fn new(
+        network_command_sender: NetworkCommandSender,
+        pool_controller: Box<dyn PoolController>,
+        log_filter_handle: LogFilterHandle,
+    ) -> Self {
+        ConfigReloader {
+            applied: ReloadableSettings::from(&*SETTINGS),
+            network_command_sender,
+            pool_controller,
+            log_filter_handle,
+        }
+    }
+
+    /// Spawn the task reloading the configuration on `SIGHUP` and on each request received on `reload_rx`
+    pub fn start(mut self, mut reload_rx: mpsc::Receiver<()>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut hangup = HangupListener::new();
+            loop {
+                tokio::select! {
+                    request = reload_rx.recv() => match request {
+                        Some(()) => info!("configuration reload requested from private API"),
+                        None => break,
+                    },
+                    _ = hangup.recv() => info!("SIGHUP received, reloading the configuration"),
+                }
+                self.reload().await;
+            }
+        })
+    }
+
+    /// Read the configuration files again and apply the changes of the reloadable settings
+    async fn reload(&mut self) {
+        let settings: Settings = match try_build_massa_settings("massa-node", "MASSA_NODE") {
+            Ok(settings) => settings,
+            Err(err) => {
+                warn!("configuration not reloaded, it could not be read: {}", err);
+                return;
+            }
+        };
+        let problems = check_connection_limits(&settings.network);
+        if !problems.is_empty() {
+            for problem in &problems {
+                warn!("invalid configuration: {}", problem);
+            }
+            warn!("configuration not reloaded, fix the problems above and try again");
+            return;
+        }
+
+        self.apply(ReloadableSettings::from(&settings)).await;
+    }
+
+    /// Push the changed settings to the workers.
+    /// A setting that could not be applied is kept as changed, to be retried on the next reload.
+    async fn apply(&mut self, settings: ReloadableSettings) {
+        if settings.network != self.applied.network {
+            match self
+                .network_command_sender
+                .update_config(settings.network.clone())
+                .await
+            {
+                Ok(()) => {
+                    info!("network connection limits and peers dump interval reloaded");
+                    self.applied.network = settings.network;
+                }
+                Err(err) => warn!("could not reload the network settings: {}", err),
+            }
+        }
+
+        if settings.minimal_fee != self.applied.minimal_fee {
+            self.pool_controller.set_minimal_fee(settings.minimal_fee);
+            info!("pool minimal fee reloaded: {}", settings.minimal_fee);
+            self.applied.minimal_fee = settings.minimal_fee;
+        }
+
+        if settings.log_level != self.applied.log_level
+            || settings.log_filters != self.applied.log_filters
+        {
+            match build_log_filter(
+                level_filter_from_config(settings.log_level),
+                &settings.log_filters,
+            )
+            .and_then(|filter| self.log_filter_handle.replace(filter))
+            {
+                Ok(()) => {
+                    info!("log level and filters reloaded");
+                    self.applied.log_level = settings.log_level;
+                    self.applied.log_filters = settings.log_filters;
+                }
+                Err(err) => warn!("could not reload the log filters: {}", err),
+            }
+        }
+    }
+}
+
+/// Get the part of the network settings that can be changed while the node is running
+fn get_network_reloadable_config(network: &NetworkSettings) -> NetworkReloadableConfig {
+    NetworkReloadableConfig {
+        peer_types_config: network.peer_types_config.clone(),
+        max_in_connections_per_ip: network.max_in_connections_per_ip,
+        peers_file_dump_interval: network.peers_file_dump_interval,
+    }
+}
+
+/// Listener of the `SIGHUP` signals, that never fires on platforms without them
+struct HangupListener(#[cfg(unix)] tokio::signal::unix::Signal);
+
+impl HangupListener {
+    #[cfg(unix)]
+    fn new() -> Self {
+        use tokio::signal::unix::{signal, SignalKind};
+        HangupListener(signal(SignalKind::hangup()).expect("could not listen to SIGHUP"))
+    }
+
+    #[cfg(not(unix))]
+    fn new() -> Self {
+        HangupListener()
+    }
+
+    /// Wait for the next signal
+    async fn recv(&mut self) {
+        #[cfg(unix)]
+        if self.0.recv().await.is_some() {
+            return;
+        }
+        std::future::pending::<()>().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_logging::{build_log_layer, LogFormat, RecentLogs};
+    use massa_network_exports::NetworkCommand;
+    use massa_pool_exports::test_exports::{MockPoolController, MockPoolControllerMessage};
+    use std::str::FromStr;
+
+    /// Settings differing from `SETTINGS` in each reloadable field
+    fn changed_settings() -> ReloadableSettings {
+        let mut settings = ReloadableSettings::from(&*SETTINGS);
+        settings.network.max_in_connections_per_ip += 1;
+        settings.minimal_fee = settings
+            .minimal_fee
+            .saturating_add(Amount::from_str("1").unwrap());
+        settings
+            .log_filters
+            .push("massa_pool_worker=debug".to_string());
+        settings
+    }
+
+    #[tokio::test]
+    async fn test_apply_changed_settings() {
+        let (network_tx, mut network_rx) = mpsc::channel(8);
+        let (pool_controller, pool_receiver) = MockPoolController::new_with_receiver();
+        let (_log_layer, log_filter_handle) = build_log_layer(
+            LogFormat::Text,
+            build_log_filter(level_filter_from_config(SETTINGS.logging.level), &[]).unwrap(),
+            RecentLogs::new(8),
+        );
+        let mut reloader = ConfigReloader::new(
+            NetworkCommandSender(network_tx),
+            pool_controller,
+            log_filter_handle,
+        );
+
+        // nothing is pushed if nothing changed
+        reloader.apply(ReloadableSettings::from(&*SETTINGS)).await;
+        assert!(network_rx.try_recv().is_err());
+        assert!(pool_receiver.0.try_recv().is_err());
+
+        let settings = changed_settings();
+        reloader.apply(settings.clone()).await;
+        match network_rx.try_recv() {
+            Ok(NetworkCommand::UpdateConfig(config)) => assert_eq!(config, settings.network),
+            _ => panic!("expected the network settings to be pushed"),
+        }
+        assert!(matches!(
+            pool_receiver.0.try_recv(),
+            Ok(MockPoolControllerMessage::SetMinimalFee { minimal_fee }) if minimal_fee == settings.minimal_fee
+        ));
+        assert!(reloader
+            .log_filter_handle
+            .get_filter()
+            .unwrap()
+            .contains("massa_pool_worker=debug"));
+        assert_eq!(reloader.applied, settings);
+    }
+
+    #[tokio::test]
+    async fn test_failed_network_reload_is_retried() {
+        let (network_tx, network_rx) = mpsc::channel(8);
+        let (pool_controller, pool_receiver) = MockPoolController::new_with_receiver();
+        let (_log_layer, log_filter_handle) = build_log_layer(
+            LogFormat::Text,
+            build_log_filter(level_filter_from_config(SETTINGS.logging.level), &[]).unwrap(),
+            RecentLogs::new(8),
+        );
+        let mut reloader = ConfigReloader::new(
+            NetworkCommandSender(network_tx),
+            pool_controller,
+            log_filter_handle,
+        );
+
+        // the network worker is unreachable: only the other settings are applied
+        drop(network_rx);
+        let settings = changed_settings();
+        reloader.apply(settings.clone()).await;
+        assert_eq!(
+            reloader.applied.network,
+            ReloadableSettings::from(&*SETTINGS).network
+        );
+        assert_eq!(reloader.applied.minimal_fee, settings.minimal_fee);
+        assert_eq!(reloader.applied.log_filters, settings.log_filters);
+        assert!(pool_receiver.0.try_recv().is_ok());
+
+        // the network settings are pushed again on the next reload, and only them
+        let (network_tx, mut network_rx) = mpsc::channel(8);
+        reloader.network_command_sender = NetworkCommandSender(network_tx);
+        reloader.apply(settings.clone()).await;
+        assert!(matches!(
+            network_rx.try_recv(),
+            Ok(NetworkCommand::UpdateConfig(_))
+        ));
+        assert!(pool_receiver.0.try_recv().is_err());
+        assert_eq!(reloader.applied, settings);
+    }
+}
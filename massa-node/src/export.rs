@@ -0,0 +1,62 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Export of the archived final blocks, run instead of the node with the `export` command.
+//! The same export can be run on a live node through the private API.
+
+use crate::open_archive;
+use crate::settings::SETTINGS;
+use massa_models::{
+    api::{ExportContent, ExportFormat},
+    slot::Slot,
+};
+use massa_storage::export::export_blocks;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+use structopt::StructOpt;
+use tracing::info;
+
+/// Arguments of the `export` command
+#[derive(StructOpt)]
+pub struct ExportArgs {
+    /// First slot of the range, as period.thread
+    #[structopt(long)]
+    start: Slot,
+    /// Slot after the range, as period.thread
+    #[structopt(long)]
+    end: Slot,
+    /// One record per block or per operation: blocks or operations
+    #[structopt(long, default_value = "blocks")]
+    content: ExportContent,
+    /// Format of the output file: jsonl or csv
+    #[structopt(long, default_value = "jsonl")]
+    format: ExportFormat,
+    /// Path of the output file
+    #[structopt(long, parse(from_os_str))]
+    output: PathBuf,
+}
+
+/// Export the archived final blocks as described by `args`
+pub fn export(args: ExportArgs) -> anyhow::Result<()> {
+    if !SETTINGS.archive.enabled {
+        anyhow::bail!("the archive is not enabled, there is nothing to export");
+    }
+    let archive = open_archive()?;
+    let file = File::create(&args.output)?;
+    let stats = export_blocks(
+        &archive,
+        args.start,
+        args.end,
+        args.content,
+        args.format,
+        BufWriter::new(file),
+    )
+    .map_err(|err| anyhow::anyhow!("export failed: {}", err))?;
+    info!(
+        "exported {} records from {} archived blocks to {}",
+        stats.records,
+        stats.blocks,
+        args.output.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,137 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Genesis parameters of the network, loaded from a versioned genesis file at startup.
+//!
+//! The genesis parameters are hashed into a network ID that is exchanged during the
+//! network handshake: nodes that do not share the same genesis refuse to peer.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context};
+use massa_hash::Hash;
+use massa_models::{
+    config::constants::{GENESIS_TIMESTAMP, THREAD_COUNT},
+    upgrade_schedule::UpgradeSchedule,
+};
+use massa_time::MassaTime;
+use serde::Deserialize;
+
+/// Version of the genesis file format supported by this node
+pub const GENESIS_FILE_VERSION: u32 = 0;
+
+/// Content of the genesis file
+#[derive(Debug, Deserialize, Clone)]
+pub struct GenesisConfig {
+    /// version of the genesis file format
+    pub version: u32,
+    /// time in milliseconds when the blockclique started.
+    /// Can also be written as an RFC 3339 date (ex: `"2023-01-04T00:00:01Z"`).
+    /// In sandbox mode it is overridden by the `GENESIS_TIMESTAMP` environment variable or the node start time.
+    #[serde(deserialize_with = "massa_time::deserialize_millis_or_utc")]
+    pub genesis_timestamp: MassaTime,
+    /// number of threads, must match the one the node was built with
+    pub thread_count: u8,
+    /// path to the initial ledger
+    pub initial_ledger_path: PathBuf,
+    /// path to the initial roll distribution (initial stakers)
+    pub initial_rolls_path: PathBuf,
+    /// slot from which each protocol feature is active, by feature name (ex: `state_commitment`).
+    /// The features not listed are not active, an unknown feature is an error
+    pub upgrade_schedule: UpgradeSchedule,
+}
+
+impl GenesisConfig {
+    /// Load and check the genesis file at `path`
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("could not read genesis file {}", path.display()))?;
+        let mut genesis: GenesisConfig = serde_json::from_str(&content)
+            .with_context(|| format!("could not parse genesis file {}", path.display()))?;
+        if genesis.version != GENESIS_FILE_VERSION {
+            bail!(
+                "unsupported genesis file version {} (supported version: {})",
+                genesis.version,
+                GENESIS_FILE_VERSION
+            );
+        }
+        if genesis.thread_count != THREAD_COUNT {
+            bail!(
+                "genesis thread count {} does not match the thread count of this node build ({})",
+                genesis.thread_count,
+                THREAD_COUNT
+            );
+        }
+        if cfg!(feature = "sandbox") {
+            genesis.genesis_timestamp = *GENESIS_TIMESTAMP;
+        }
+        Ok(genesis)
+    }
+
+    /// Compute the network ID: the hash of all the genesis parameters, upgrade schedule included.
+    ///
+    /// The initial ledger and rolls are hashed in their canonical JSON form
+    /// (keys sorted, no whitespace) so that formatting changes do not alter the network ID.
+    pub fn compute_network_id(&self) -> anyhow::Result<Hash> {
+        let mut bytes = Vec::new();
+        bytes.extend(self.version.to_be_bytes());
+        bytes.extend(self.genesis_timestamp.to_millis().to_be_bytes());
+        bytes.push(self.thread_count);
+        for path in [&self.initial_ledger_path, &self.initial_rolls_path] {
+            bytes.extend(canonical_json_hash(path)?.to_bytes());
+        }
+        for (feature, slot) in self.upgrade_schedule.iter() {
+            let feature = feature.to_string();
+            bytes.extend((feature.len() as u32).to_be_bytes());
+            bytes.extend(feature.as_bytes());
+            bytes.extend(slot.to_bytes_key());
+        }
+        Ok(Hash::compute_from(&bytes))
+    }
+}
+
+/// Hash of the canonical JSON serialization of the file at `path`
+fn canonical_json_hash(path: &Path) -> anyhow::Result<Hash> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("could not read {}", path.display()))?;
+    let value: serde_json::Value = serde_json::from_str(&content)
+        .with_context(|| format!("could not parse {}", path.display()))?;
+    let canonical = serde_json::to_vec(&value)?;
+    Ok(Hash::compute_from(&canonical))
+}
+
+#[cfg(test)]
+#[test]
+fn test_genesis_upgrade_schedule() {
+    use massa_models::{slot::Slot, upgrade_schedule::ProtocolFeature};
+
+    let genesis: GenesisConfig = serde_json::from_str(
+        r#"{
+            "version": 0,
+            "genesis_timestamp": 0,
+            "thread_count": 32,
+            "initial_ledger_path": "initial_ledger.json",
+            "initial_rolls_path": "initial_rolls.json",
+            "upgrade_schedule": { "state_commitment": { "period": 10, "thread": 1 } }
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(
+        genesis
+            .upgrade_schedule
+            .activation_slot(ProtocolFeature::StateCommitment),
+        Some(Slot::new(10, 1))
+    );
+
+    // an unknown feature is a genesis error
+    assert!(serde_json::from_str::<GenesisConfig>(
+        r#"{
+            "version": 0,
+            "genesis_timestamp": 0,
+            "thread_count": 32,
+            "initial_ledger_path": "initial_ledger.json",
+            "initial_rolls_path": "initial_rolls.json",
+            "upgrade_schedule": { "unknown_feature": { "period": 10, "thread": 1 } }
+        }"#,
+    )
+    .is_err());
+}
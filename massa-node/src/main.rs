@@ -4,15 +4,27 @@
 #![warn(missing_docs)]
 #![warn(unused_crate_dependencies)]
 extern crate massa_logging;
+use crate::archive::Archiver;
+use crate::config_reload::ConfigReloader;
+use crate::genesis::GenesisConfig;
+use crate::notifier::{Notifier, NotifierSources};
+use crate::resource_monitor::ResourceMonitor;
 use crate::settings::SETTINGS;
+use crate::shutdown::ShutdownWatchdog;
 
 use crossbeam_channel::{Receiver, TryRecvError};
 use dialoguer::Password;
-use massa_api::{APIConfig, ApiServer, ApiV2, Private, Public, RpcServer, StopHandle, API};
+use massa_api::{
+    APIConfig, ApiServer, ApiV2, Grpc, GrpcStopHandle, Private, Public, RpcServer, StopHandle, API,
+};
 use massa_async_pool::AsyncPoolConfig;
-use massa_bootstrap::{get_state, start_bootstrap_server, BootstrapConfig, BootstrapManager};
+use massa_bootstrap::{
+    export_snapshot, get_state, start_bootstrap_server, BootstrapConfig, BootstrapManager,
+};
 use massa_consensus_exports::events::ConsensusEvent;
-use massa_consensus_exports::{ConsensusChannels, ConsensusConfig, ConsensusManager};
+use massa_consensus_exports::{
+    ConsensusChannels, ConsensusConfig, ConsensusController, ConsensusManager,
+};
 use massa_consensus_worker::start_consensus_worker;
 use massa_executed_ops::ExecutedOpsConfig;
 use massa_execution_exports::{ExecutionConfig, ExecutionManager, GasCosts, StorageCostsConstants};
@@ -22,15 +34,20 @@ use massa_factory_worker::start_factory;
 use massa_final_state::{FinalState, FinalStateConfig};
 use massa_ledger_exports::LedgerConfig;
 use massa_ledger_worker::FinalLedger;
-use massa_logging::massa_trace;
+use massa_logging::{
+    build_log_filter, build_log_layer, level_filter_from_config, massa_trace, LogFilterHandle,
+    RecentLogs,
+};
+use massa_metrics::start_metrics_server;
 use massa_models::address::Address;
+use massa_models::block::BlockId;
 use massa_models::config::constants::{
     ASYNC_POOL_BOOTSTRAP_PART_SIZE, BLOCK_REWARD, BOOTSTRAP_RANDOMNESS_SIZE_BYTES, CHANNEL_SIZE,
     DEFERRED_CREDITS_BOOTSTRAP_PART_SIZE, DELTA_F0, ENDORSEMENT_COUNT, END_TIMESTAMP,
-    EXECUTED_OPS_BOOTSTRAP_PART_SIZE, GENESIS_KEY, GENESIS_TIMESTAMP, INITIAL_DRAW_SEED,
-    LEDGER_COST_PER_BYTE, LEDGER_ENTRY_BASE_SIZE, LEDGER_ENTRY_DATASTORE_BASE_SIZE,
-    LEDGER_PART_SIZE_MESSAGE_BYTES, MAX_ADVERTISE_LENGTH, MAX_ASK_BLOCKS_PER_MESSAGE,
-    MAX_ASYNC_GAS, MAX_ASYNC_MESSAGE_DATA, MAX_ASYNC_POOL_LENGTH, MAX_BLOCK_SIZE,
+    EXECUTED_OPS_BOOTSTRAP_PART_SIZE, GENESIS_KEY, INITIAL_DRAW_SEED, LEDGER_COST_PER_BYTE,
+    LEDGER_ENTRY_BASE_SIZE, LEDGER_ENTRY_DATASTORE_BASE_SIZE, LEDGER_PART_SIZE_MESSAGE_BYTES,
+    MAX_ADVERTISE_LENGTH, MAX_ASK_BLOCKS_PER_MESSAGE, MAX_ASYNC_GAS, MAX_ASYNC_MESSAGE_DATA,
+    MAX_ASYNC_POOL_LENGTH, MAX_BLOCK_RANGE_LENGTH, MAX_BLOCK_SIZE,
     MAX_BOOTSTRAP_ASYNC_POOL_CHANGES, MAX_BOOTSTRAP_BLOCKS, MAX_BOOTSTRAP_ERROR_LENGTH,
     MAX_BOOTSTRAP_FINAL_STATE_PARTS_SIZE, MAX_BOOTSTRAP_MESSAGE_SIZE, MAX_BYTECODE_LENGTH,
     MAX_DATASTORE_ENTRY_COUNT, MAX_DATASTORE_KEY_LENGTH, MAX_DATASTORE_VALUE_LENGTH,
@@ -39,15 +56,20 @@ use massa_models::config::constants::{
     MAX_MESSAGE_SIZE, MAX_OPERATIONS_PER_BLOCK, MAX_OPERATION_DATASTORE_ENTRY_COUNT,
     MAX_OPERATION_DATASTORE_KEY_LENGTH, MAX_OPERATION_DATASTORE_VALUE_LENGTH, MAX_PARAMETERS_SIZE,
     MAX_PRODUCTION_STATS_LENGTH, MAX_ROLLS_COUNT_LENGTH, NETWORK_CONTROLLER_CHANNEL_SIZE,
-    NETWORK_EVENT_CHANNEL_SIZE, NETWORK_NODE_COMMAND_CHANNEL_SIZE, NETWORK_NODE_EVENT_CHANNEL_SIZE,
-    OPERATION_VALIDITY_PERIODS, PERIODS_PER_CYCLE, POOL_CONTROLLER_CHANNEL_SIZE,
-    POS_MISS_RATE_DEACTIVATION_THRESHOLD, POS_SAVED_CYCLES, PROTOCOL_CONTROLLER_CHANNEL_SIZE,
-    PROTOCOL_EVENT_CHANNEL_SIZE, ROLL_PRICE, T0, THREAD_COUNT, VERSION,
+    NETWORK_EVENT_CHANNEL_SIZE, NETWORK_NODE_COMMAND_CHANNEL_BYTES,
+    NETWORK_NODE_COMMAND_CHANNEL_SIZE, NETWORK_NODE_EVENT_CHANNEL_SIZE, OPERATION_VALIDITY_PERIODS,
+    PERIODS_PER_CYCLE, POOL_CONTROLLER_CHANNEL_SIZE, POS_MISS_RATE_DEACTIVATION_THRESHOLD,
+    POS_SAVED_CYCLES, PROTOCOL_CONTROLLER_CHANNEL_SIZE, PROTOCOL_EVENT_CHANNEL_SIZE, ROLL_PRICE,
+    T0, VERSION,
 };
 use massa_models::config::CONSENSUS_BOOTSTRAP_PART_SIZE;
+use massa_models::resource_status::ResourceStatus;
+use massa_models::slot::Slot;
+use massa_models::slot_scheduler::{RealTimeSlotScheduler, SlotScheduler, SlotTiming};
+use massa_models::sync_progress::SyncProgressStatus;
 use massa_network_exports::{Establisher, NetworkConfig, NetworkManager};
 use massa_network_worker::start_network_controller;
-use massa_pool_exports::{PoolConfig, PoolManager};
+use massa_pool_exports::{PoolChannels, PoolConfig, PoolManager};
 use massa_pool_worker::start_pool_controller;
 use massa_pos_exports::{PoSConfig, SelectorConfig, SelectorManager};
 use massa_pos_worker::start_selector_worker;
@@ -56,24 +78,47 @@ use massa_protocol_exports::{
     ProtocolSenders,
 };
 use massa_protocol_worker::start_protocol_controller;
-use massa_storage::Storage;
-use massa_time::MassaTime;
-use massa_wallet::Wallet;
+use massa_signature::KeyPair;
+use massa_storage::{
+    address_index::AddressIndex,
+    archive::{Archive, ArchiveConfig},
+    maintenance::{start_maintenance, MaintenanceConfig, MaintenanceHandle},
+    Storage,
+};
+use massa_time::{MassaTime, SystemClock};
+use massa_wallet::{load_or_create_keypair_file, Wallet};
+use opentelemetry_otlp::WithExportConfig;
 use parking_lot::RwLock;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::thread::sleep;
 use std::time::Duration;
 use std::{path::Path, process, sync::Arc};
 use structopt::StructOpt;
-use tokio::signal;
 use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
 use tracing::{error, info, warn};
 use tracing_subscriber::filter::{filter_fn, LevelFilter};
+mod archive;
+mod check_db;
+mod config_check;
+mod config_reload;
+mod export;
+mod genesis;
+mod notifier;
+mod replay;
+mod resource_monitor;
 mod settings;
+mod shutdown;
+mod trustless_sync;
 
 async fn launch(
+    genesis: &GenesisConfig,
     node_wallet: Arc<RwLock<Wallet>>,
+    node_keypair: KeyPair,
+    log_filter_handle: LogFilterHandle,
+    recent_logs: RecentLogs,
 ) -> (
     Receiver<ConsensusEvent>,
     Option<BootstrapManager>,
@@ -83,11 +128,18 @@ async fn launch(
     Box<dyn PoolManager>,
     ProtocolManager,
     NetworkManager,
-    Box<dyn FactoryManager>,
+    Option<Box<dyn FactoryManager>>,
     mpsc::Receiver<()>,
     StopHandle,
     StopHandle,
     StopHandle,
+    Option<GrpcStopHandle>,
+    JoinHandle<()>,
+    Option<JoinHandle<()>>,
+    JoinHandle<()>,
+    Option<JoinHandle<()>>,
+    Option<MaintenanceHandle>,
+    ShutdownSnapshotSource,
 ) {
     info!("Node version : {}", *VERSION);
     if let Some(end) = *END_TIMESTAMP {
@@ -96,44 +148,17 @@ async fn launch(
         }
     }
 
+    // derive the network ID from the genesis parameters
+    let network_id = genesis
+        .compute_network_id()
+        .expect("could not compute network ID from genesis");
+    info!("Network ID : {}", network_id);
+
     // Storage shared by multiple components.
     let shared_storage: Storage = Storage::create_root();
 
     // init final state
-    let ledger_config = LedgerConfig {
-        thread_count: THREAD_COUNT,
-        initial_ledger_path: SETTINGS.ledger.initial_ledger_path.clone(),
-        disk_ledger_path: SETTINGS.ledger.disk_ledger_path.clone(),
-        max_key_length: MAX_DATASTORE_KEY_LENGTH,
-        max_ledger_part_size: LEDGER_PART_SIZE_MESSAGE_BYTES,
-    };
-    let async_pool_config = AsyncPoolConfig {
-        max_length: MAX_ASYNC_POOL_LENGTH,
-        thread_count: THREAD_COUNT,
-        bootstrap_part_size: ASYNC_POOL_BOOTSTRAP_PART_SIZE,
-        max_async_message_data: MAX_ASYNC_MESSAGE_DATA,
-    };
-    let pos_config = PoSConfig {
-        periods_per_cycle: PERIODS_PER_CYCLE,
-        thread_count: THREAD_COUNT,
-        cycle_history_length: POS_SAVED_CYCLES,
-        credits_bootstrap_part_size: DEFERRED_CREDITS_BOOTSTRAP_PART_SIZE,
-    };
-    let executed_ops_config = ExecutedOpsConfig {
-        thread_count: THREAD_COUNT,
-        bootstrap_part_size: EXECUTED_OPS_BOOTSTRAP_PART_SIZE,
-    };
-    let final_state_config = FinalStateConfig {
-        ledger_config: ledger_config.clone(),
-        async_pool_config,
-        pos_config,
-        executed_ops_config,
-        final_history_length: SETTINGS.ledger.final_history_length,
-        thread_count: THREAD_COUNT,
-        periods_per_cycle: PERIODS_PER_CYCLE,
-        initial_seed_string: INITIAL_DRAW_SEED.into(),
-        initial_rolls_path: SETTINGS.selector.initial_rolls_path.clone(),
-    };
+    let final_state_config = final_state_config(genesis, SETTINGS.ledger.disk_ledger_path.clone());
 
     // Remove current disk ledger if there is one
     // NOTE: this is temporary, since we cannot currently handle bootstrap from remaining ledger
@@ -143,18 +168,11 @@ async fn launch(
     }
 
     // Create final ledger
-    let ledger = FinalLedger::new(ledger_config.clone());
+    let ledger = FinalLedger::new(final_state_config.ledger_config.clone());
 
     // launch selector worker
-    let (selector_manager, selector_controller) = start_selector_worker(SelectorConfig {
-        max_draw_cache: SETTINGS.selector.max_draw_cache,
-        channel_size: CHANNEL_SIZE,
-        thread_count: THREAD_COUNT,
-        endorsement_count: ENDORSEMENT_COUNT,
-        periods_per_cycle: PERIODS_PER_CYCLE,
-        genesis_address: Address::from_public_key(&GENESIS_KEY.get_public_key()),
-    })
-    .expect("could not start selector worker");
+    let (selector_manager, selector_controller) =
+        start_selector_worker(selector_config(genesis)).expect("could not start selector worker");
 
     // Create final state
     let final_state = Arc::new(parking_lot::RwLock::new(
@@ -167,13 +185,19 @@ async fn launch(
     ));
 
     // interrupt signal listener
-    let stop_signal = signal::ctrl_c();
+    let stop_signal = shutdown::wait_for_stop_signal();
     tokio::pin!(stop_signal);
 
     let bootstrap_config: BootstrapConfig = BootstrapConfig {
         bootstrap_list: SETTINGS.bootstrap.bootstrap_list.clone(),
         bootstrap_whitelist_path: SETTINGS.bootstrap.bootstrap_whitelist_path.clone(),
         bootstrap_blacklist_path: SETTINGS.bootstrap.bootstrap_blacklist_path.clone(),
+        bootstrap_token: SETTINGS.bootstrap.bootstrap_token.clone(),
+        snapshot_import_path: SETTINGS.bootstrap.snapshot_import_path.clone(),
+        shutdown_snapshot_path: SETTINGS.bootstrap.shutdown_snapshot_path.clone(),
+        shutdown_snapshot_max_age: SETTINGS.bootstrap.shutdown_snapshot_max_age,
+        server_stats_path: SETTINGS.bootstrap.server_stats_path.clone(),
+        bootstrap_tokens: SETTINGS.bootstrap.bootstrap_tokens.clone(),
         bind: SETTINGS.bootstrap.bind,
         connect_timeout: SETTINGS.bootstrap.connect_timeout,
         read_timeout: SETTINGS.bootstrap.read_timeout,
@@ -184,14 +208,17 @@ async fn launch(
         max_ping: SETTINGS.bootstrap.max_ping,
         max_clock_delta: SETTINGS.bootstrap.max_clock_delta,
         cache_duration: SETTINGS.bootstrap.cache_duration,
+        part_cache_max_age: SETTINGS.bootstrap.part_cache_max_age,
+        part_cache_max_entries: SETTINGS.bootstrap.part_cache_max_entries,
         max_simultaneous_bootstraps: SETTINGS.bootstrap.max_simultaneous_bootstraps,
         per_ip_min_interval: SETTINGS.bootstrap.per_ip_min_interval,
         ip_list_max_size: SETTINGS.bootstrap.ip_list_max_size,
         max_bytes_read_write: SETTINGS.bootstrap.max_bytes_read_write,
+        max_bytes_read_write_total: SETTINGS.bootstrap.max_bytes_read_write_total,
         max_bootstrap_message_size: MAX_BOOTSTRAP_MESSAGE_SIZE,
         max_datastore_key_length: MAX_DATASTORE_KEY_LENGTH,
         randomness_size_bytes: BOOTSTRAP_RANDOMNESS_SIZE_BYTES,
-        thread_count: THREAD_COUNT,
+        thread_count: genesis.thread_count,
         periods_per_cycle: PERIODS_PER_CYCLE,
         endorsement_count: ENDORSEMENT_COUNT,
         max_advertise_length: MAX_ADVERTISE_LENGTH,
@@ -219,38 +246,57 @@ async fn launch(
         consensus_bootstrap_part_size: CONSENSUS_BOOTSTRAP_PART_SIZE,
     };
 
-    // bootstrap
-    let bootstrap_state = tokio::select! {
-        _ = &mut stop_signal => {
-            info!("interrupt signal received in bootstrap loop");
-            process::exit(0);
-        },
-        res = get_state(
-            &bootstrap_config,
-            final_state.clone(),
-            massa_bootstrap::types::Establisher::default(),
-            *VERSION,
-            *GENESIS_TIMESTAMP,
-            *END_TIMESTAMP,
-        ) => match res {
-            Ok(vals) => vals,
-            Err(err) => panic!("critical error detected in the bootstrap process: {}", err)
-        }
+    // open the archive of the final blocks on archival nodes
+    let archive = if SETTINGS.archive.enabled {
+        info!(
+            "archival mode: final blocks and snapshots are kept in {}",
+            SETTINGS.archive.path.display()
+        );
+        Some(
+            Archive::open(archive_config(genesis.thread_count))
+                .expect("could not open the archive"),
+        )
+    } else {
+        None
     };
 
     let network_config: NetworkConfig = NetworkConfig {
         bind: SETTINGS.network.bind,
-        routable_ip: SETTINGS.network.routable_ip,
+        // observer nodes do not advertise their IP
+        routable_ip: if SETTINGS.network.observer {
+            None
+        } else {
+            SETTINGS.network.routable_ip
+        },
+        observer: SETTINGS.network.observer,
         protocol_port: SETTINGS.network.protocol_port,
+        other_routable_addresses: if SETTINGS.network.observer {
+            Vec::new()
+        } else {
+            SETTINGS.network.other_routable_addresses.clone()
+        },
         connect_timeout: SETTINGS.network.connect_timeout,
+        min_connect_timeout: SETTINGS.network.min_connect_timeout,
+        connect_timeout_latency_factor: SETTINGS.network.connect_timeout_latency_factor,
         wakeup_interval: SETTINGS.network.wakeup_interval,
         initial_peers_file: SETTINGS.network.initial_peers_file.clone(),
         peers_file: SETTINGS.network.peers_file.clone(),
-        keypair_file: SETTINGS.network.keypair_file.clone(),
+        peers_file_format: SETTINGS.network.peers_file_format,
+        // the environment variable keeps the password out of the configuration files
+        peers_file_password: std::env::var("MASSA_PEERS_FILE_PASSWORD")
+            .ok()
+            .or_else(|| SETTINGS.network.peers_file_password.clone()),
+        ban_audit_file: SETTINGS.network.ban_audit_file.clone(),
         peer_types_config: SETTINGS.network.peer_types_config.clone(),
         max_in_connections_per_ip: SETTINGS.network.max_in_connections_per_ip,
+        max_out_attempts_per_minute: SETTINGS.network.max_out_attempts_per_minute,
+        max_out_attempts_burst: SETTINGS.network.max_out_attempts_burst,
         max_idle_peers: SETTINGS.network.max_idle_peers,
         max_banned_peers: SETTINGS.network.max_banned_peers,
+        max_peers_per_network_group: SETTINGS.network.max_peers_per_network_group,
+        max_peer_history_length: SETTINGS.network.max_peer_history_length,
+        candidate_peer_filter: SETTINGS.network.candidate_peer_filter.clone(),
+        advertised_peer_filter: SETTINGS.network.advertised_peer_filter.clone(),
         peers_file_dump_interval: SETTINGS.network.peers_file_dump_interval,
         message_timeout: SETTINGS.network.message_timeout,
         ask_peer_list_interval: SETTINGS.network.ask_peer_list_interval,
@@ -262,13 +308,18 @@ async fn launch(
         max_operations_per_message: SETTINGS.network.max_operations_per_message,
         max_bytes_read: SETTINGS.network.max_bytes_read,
         max_bytes_write: SETTINGS.network.max_bytes_write,
+        max_clock_offset: SETTINGS.network.max_clock_offset,
         max_ask_blocks: MAX_ASK_BLOCKS_PER_MESSAGE,
+        max_block_range_length: MAX_BLOCK_RANGE_LENGTH,
         max_operations_per_block: MAX_OPERATIONS_PER_BLOCK,
-        thread_count: THREAD_COUNT,
+        network_id,
+        thread_count: genesis.thread_count,
         endorsement_count: ENDORSEMENT_COUNT,
         max_peer_advertise_length: MAX_ADVERTISE_LENGTH,
         max_endorsements_per_message: MAX_ENDORSEMENTS_PER_MESSAGE,
         max_message_size: MAX_MESSAGE_SIZE,
+        message_checksum: SETTINGS.network.message_checksum,
+        write_coalescing_delay: SETTINGS.network.write_coalescing_delay,
         max_datastore_value_length: MAX_DATASTORE_VALUE_LENGTH,
         max_op_datastore_entry_count: MAX_OPERATION_DATASTORE_ENTRY_COUNT,
         max_op_datastore_key_length: MAX_OPERATION_DATASTORE_KEY_LENGTH,
@@ -278,16 +329,67 @@ async fn launch(
         controller_channel_size: NETWORK_CONTROLLER_CHANNEL_SIZE,
         event_channel_size: NETWORK_EVENT_CHANNEL_SIZE,
         node_command_channel_size: NETWORK_NODE_COMMAND_CHANNEL_SIZE,
+        node_command_channel_bytes: NETWORK_NODE_COMMAND_CHANNEL_BYTES,
         node_event_channel_size: NETWORK_NODE_EVENT_CHANNEL_SIZE,
     };
 
+    let checkpoints: BTreeMap<Slot, BlockId> =
+        SETTINGS.consensus.checkpoints.iter().copied().collect();
+
+    // bootstrap, or sync from the archival peers without trusting a bootstrap server
+    let sync_progress = SyncProgressStatus::default();
+    let bootstrap_state = if SETTINGS.bootstrap.trustless_sync {
+        tokio::select! {
+            signal_name = &mut stop_signal => {
+                info!("{} received in trustless sync", signal_name);
+                process::exit(0);
+            },
+            res = trustless_sync::trustless_sync(
+                genesis,
+                checkpoints.clone(),
+                &network_config,
+                node_keypair.clone(),
+                *VERSION,
+                archive.clone().expect("the trustless sync needs the archive"),
+                final_state.clone(),
+                selector_controller.clone(),
+                SETTINGS.bootstrap.trustless_sync_max_lag_periods,
+                SETTINGS.bootstrap.trustless_sync_request_timeout,
+            ) => match res {
+                Ok(vals) => vals,
+                Err(err) => panic!("critical error detected in the trustless sync: {}", err)
+            }
+        }
+    } else {
+        tokio::select! {
+            signal_name = &mut stop_signal => {
+                info!("{} received in bootstrap loop", signal_name);
+                process::exit(0);
+            },
+            res = get_state(
+                &bootstrap_config,
+                final_state.clone(),
+                massa_bootstrap::types::Establisher::default(),
+                *VERSION,
+                genesis.genesis_timestamp,
+                *END_TIMESTAMP,
+                sync_progress.clone(),
+            ) => match res {
+                Ok(vals) => vals,
+                Err(err) => panic!("critical error detected in the bootstrap process: {}", err)
+            }
+        }
+    };
+
     // launch network controller
     let (network_command_sender, network_event_receiver, network_manager, private_key, node_id) =
         start_network_controller(
             &network_config,
+            node_keypair,
             Establisher::new(),
             bootstrap_state.peers,
             *VERSION,
+            Box::new(SystemClock),
         )
         .await
         .expect("could not start network controller");
@@ -298,72 +400,72 @@ async fn launch(
         .compute_initial_draws()
         .expect("could not compute initial draws"); // TODO: this might just mean a bad bootstrap, no need to panic, just reboot
 
-    // Storage costs constants
-    let storage_costs_constants = StorageCostsConstants {
-        ledger_cost_per_byte: LEDGER_COST_PER_BYTE,
-        ledger_entry_base_cost: LEDGER_COST_PER_BYTE
-            .checked_mul_u64(LEDGER_ENTRY_BASE_SIZE as u64)
-            .expect("Overflow when creating constant ledger_entry_base_cost"),
-        ledger_entry_datastore_base_cost: LEDGER_COST_PER_BYTE
-            .checked_mul_u64(LEDGER_ENTRY_DATASTORE_BASE_SIZE as u64)
-            .expect("Overflow when creating constant ledger_entry_datastore_base_size"),
-    };
-    // launch execution module
-    let execution_config = ExecutionConfig {
-        max_final_events: SETTINGS.execution.max_final_events,
-        readonly_queue_length: SETTINGS.execution.readonly_queue_length,
-        cursor_delay: SETTINGS.execution.cursor_delay,
-        max_async_gas: MAX_ASYNC_GAS,
-        max_gas_per_block: MAX_GAS_PER_BLOCK,
-        roll_price: ROLL_PRICE,
-        thread_count: THREAD_COUNT,
+    // source of time of the slot-based workers
+    let slot_scheduler: Box<dyn SlotScheduler> = Box::new(RealTimeSlotScheduler::new(SlotTiming {
+        thread_count: genesis.thread_count,
         t0: T0,
-        genesis_timestamp: *GENESIS_TIMESTAMP,
-        block_reward: BLOCK_REWARD,
-        endorsement_count: ENDORSEMENT_COUNT as u64,
-        operation_validity_period: OPERATION_VALIDITY_PERIODS,
-        periods_per_cycle: PERIODS_PER_CYCLE,
-        stats_time_window_duration: SETTINGS.execution.stats_time_window_duration,
-        max_miss_ratio: *POS_MISS_RATE_DEACTIVATION_THRESHOLD,
-        max_datastore_key_length: MAX_DATASTORE_KEY_LENGTH,
-        max_bytecode_size: MAX_BYTECODE_LENGTH,
-        max_datastore_value_size: MAX_DATASTORE_VALUE_LENGTH,
-        storage_costs_constants,
-        max_read_only_gas: SETTINGS.execution.max_read_only_gas,
-        gas_costs: GasCosts::new(
-            SETTINGS.execution.abi_gas_costs_file.clone(),
-            SETTINGS.execution.wasm_gas_costs_file.clone(),
-        )
-        .expect("Failed to load gas costs"),
-    };
+        genesis_timestamp: genesis.genesis_timestamp,
+    }));
+
+    // launch execution module
     let (execution_manager, execution_controller) = start_execution_worker(
-        execution_config,
+        execution_config(genesis),
         final_state.clone(),
         selector_controller.clone(),
+        slot_scheduler.clone(),
     );
 
     // launch pool controller
     let pool_config = PoolConfig {
-        thread_count: THREAD_COUNT,
+        thread_count: genesis.thread_count,
         max_block_size: MAX_BLOCK_SIZE,
         max_block_gas: MAX_GAS_PER_BLOCK,
         roll_price: ROLL_PRICE,
         max_block_endorsement_count: ENDORSEMENT_COUNT,
         operation_validity_periods: OPERATION_VALIDITY_PERIODS,
         max_operation_pool_size_per_thread: SETTINGS.pool.max_pool_size_per_thread,
+        max_operations_per_sender: SETTINGS.pool.max_operations_per_sender,
+        minimal_fee: SETTINGS.pool.minimal_fee,
+        replace_by_fee_min_increment: SETTINGS.pool.replace_by_fee_min_increment,
+        max_operation_status_count: SETTINGS.pool.max_operation_status_count,
         max_endorsements_pool_size_per_thread: SETTINGS.pool.max_pool_size_per_thread,
         channels_size: POOL_CONTROLLER_CHANNEL_SIZE,
+        operation_selection_strategy: SETTINGS.pool.operation_selection_strategy,
+        local_priority_addresses: SETTINGS
+            .pool
+            .local_priority_addresses
+            .iter()
+            .copied()
+            .collect(),
+        broadcast_events_capacity: SETTINGS.pool.broadcast_events_capacity,
     };
-    let (pool_manager, pool_controller) =
-        start_pool_controller(pool_config, &shared_storage, execution_controller.clone());
+    // watch the resources, the node is in degraded read-only mode while they are short
+    let resource_status = ResourceStatus::default();
+    let resource_monitor_handle =
+        ResourceMonitor::new(SETTINGS.resource_monitor.clone(), resource_status.clone()).start();
+
+    let pool_channels = PoolChannels {
+        pool_event_sender: broadcast::channel(pool_config.broadcast_events_capacity).0,
+        resource_status: resource_status.clone(),
+    };
+    let (pool_manager, pool_controller) = start_pool_controller(
+        pool_config,
+        &shared_storage,
+        execution_controller.clone(),
+        pool_channels.clone(),
+    );
 
     let (protocol_command_sender, protocol_command_receiver) =
         mpsc::channel::<ProtocolCommand>(PROTOCOL_CONTROLLER_CHANNEL_SIZE);
 
+    let upgrade_schedule = genesis.upgrade_schedule.clone();
+    for (feature, slot) in upgrade_schedule.iter() {
+        info!("protocol feature {} active from slot {}", feature, slot);
+    }
     let consensus_config = ConsensusConfig {
-        genesis_timestamp: *GENESIS_TIMESTAMP,
+        genesis_timestamp: genesis.genesis_timestamp,
         end_timestamp: *END_TIMESTAMP,
-        thread_count: THREAD_COUNT,
+        thread_count: genesis.thread_count,
         t0: T0,
         genesis_key: GENESIS_KEY.clone(),
         max_discarded_blocks: SETTINGS.consensus.max_discarded_blocks,
@@ -374,6 +476,11 @@ async fn launch(
         operation_validity_periods: OPERATION_VALIDITY_PERIODS,
         periods_per_cycle: PERIODS_PER_CYCLE,
         stats_timespan: SETTINGS.consensus.stats_timespan,
+        stats_cycle_count: SETTINGS.consensus.stats_cycle_count,
+        max_operations_per_block: MAX_OPERATIONS_PER_BLOCK,
+        checkpoints: checkpoints.clone(),
+        upgrade_schedule: upgrade_schedule.clone(),
+        max_reorg_depth_periods: SETTINGS.consensus.max_reorg_depth_periods,
         max_send_wait: SETTINGS.consensus.max_send_wait,
         force_keep_final_periods: SETTINGS.consensus.force_keep_final_periods,
         endorsement_count: ENDORSEMENT_COUNT,
@@ -386,8 +493,14 @@ async fn launch(
         broadcast_blocks_headers_capacity: SETTINGS.consensus.broadcast_blocks_headers_capacity,
         broadcast_blocks_capacity: SETTINGS.consensus.broadcast_blocks_capacity,
         broadcast_filled_blocks_capacity: SETTINGS.consensus.broadcast_filled_blocks_capacity,
+        broadcast_final_blocks_capacity: SETTINGS.consensus.broadcast_final_blocks_capacity,
     };
 
+    // open the index of the final operations by address
+    let address_index = SETTINGS.address_index.enabled.then(|| {
+        AddressIndex::open(&SETTINGS.address_index.path).expect("could not open the address index")
+    });
+
     let (consensus_event_sender, consensus_event_receiver) =
         crossbeam_channel::bounded(CHANNEL_SIZE);
     let consensus_channels = ConsensusChannels {
@@ -401,6 +514,9 @@ async fn launch(
         block_sender: broadcast::channel(consensus_config.broadcast_blocks_capacity).0,
         filled_block_sender: broadcast::channel(consensus_config.broadcast_filled_blocks_capacity)
             .0,
+        final_block_sender: broadcast::channel(consensus_config.broadcast_final_blocks_capacity).0,
+        archive: archive.clone(),
+        address_index: address_index.clone(),
     };
 
     let (consensus_controller, consensus_manager) = start_consensus_worker(
@@ -408,11 +524,12 @@ async fn launch(
         consensus_channels.clone(),
         bootstrap_state.graph,
         shared_storage.clone(),
+        slot_scheduler.clone(),
     );
 
     // launch protocol controller
     let protocol_config = ProtocolConfig {
-        thread_count: THREAD_COUNT,
+        thread_count: genesis.thread_count,
         ask_block_timeout: SETTINGS.protocol.ask_block_timeout,
         max_known_blocks_size: SETTINGS.protocol.max_known_blocks_size,
         max_node_known_blocks_size: SETTINGS.protocol.max_node_known_blocks_size,
@@ -436,22 +553,58 @@ async fn launch(
         max_serialized_operations_size_per_block: MAX_BLOCK_SIZE as usize,
         controller_channel_size: PROTOCOL_CONTROLLER_CHANNEL_SIZE,
         event_channel_size: PROTOCOL_EVENT_CHANNEL_SIZE,
-        genesis_timestamp: *GENESIS_TIMESTAMP,
+        genesis_timestamp: genesis.genesis_timestamp,
         t0: T0,
         max_operations_propagation_time: SETTINGS.protocol.max_operations_propagation_time,
         max_endorsements_propagation_time: SETTINGS.protocol.max_endorsements_propagation_time,
         broadcast_enabled: SETTINGS.api.enable_ws,
         broadcast_operations_capacity: SETTINGS.protocol.broadcast_operations_capacity,
+        checkpoints,
+        upgrade_schedule: upgrade_schedule.clone(),
+        partition_check_interval: SETTINGS.protocol.partition_check_interval,
+        partition_max_slots_without_final_block: SETTINGS
+            .protocol
+            .partition_max_slots_without_final_block,
+        partition_min_connected_nodes: SETTINGS.protocol.partition_min_connected_nodes,
+        partition_min_out_connections: SETTINGS.protocol.partition_min_out_connections,
+        partition_low_out_connections_timeout: SETTINGS
+            .protocol
+            .partition_low_out_connections_timeout,
+        peer_feedback_interval: SETTINGS.protocol.peer_feedback_interval,
+        late_block_announcement_delay: SETTINGS.protocol.late_block_announcement_delay,
+        operation_stem_phase_duration: SETTINGS.protocol.operation_stem_phase_duration,
+        relevance_past_periods: SETTINGS.protocol.relevance_past_periods,
+        relevance_future_periods: SETTINGS.protocol.relevance_future_periods,
+        max_irrelevant_items_per_node: SETTINGS.protocol.max_irrelevant_items_per_node,
+        max_pending_blocks_per_node: SETTINGS.protocol.max_pending_blocks_per_node,
+        max_known_headers_size: SETTINGS.protocol.max_known_headers_size,
+        sync_progress_interval: SETTINGS.protocol.sync_progress_interval,
+        max_synced_final_lag_periods: SETTINGS.protocol.max_synced_final_lag_periods,
+        archive_max_blocks_per_range: SETTINGS.archive.max_blocks_per_range,
+        archive_max_served_blocks_per_node: SETTINGS.archive.max_served_blocks_per_node,
+        archive_serving_quota_period: SETTINGS.archive.serving_quota_period,
+        // the pruned or trimmed blocks are not asked again
+        archive_backfill_interval: if SETTINGS.archive.retention_cycles == 0
+            && SETTINGS.archive.body_retention_cycles == 0
+        {
+            SETTINGS.archive.backfill_interval
+        } else {
+            MassaTime::from_millis(0)
+        },
     };
 
     let protocol_senders = ProtocolSenders {
         network_command_sender: network_command_sender.clone(),
         operation_sender: broadcast::channel(protocol_config.broadcast_operations_capacity).0,
+        partition_status: Default::default(),
+        sync_progress: sync_progress.clone(),
+        production_gate: Default::default(),
     };
 
     let protocol_receivers = ProtocolReceivers {
         network_event_receiver,
         protocol_command_receiver,
+        pool_event_receiver: pool_channels.pool_event_sender.subscribe(),
     };
 
     let protocol_manager = start_protocol_controller(
@@ -461,27 +614,97 @@ async fn launch(
         consensus_controller.clone(),
         pool_controller.clone(),
         shared_storage.clone(),
+        archive.clone(),
+        slot_scheduler.clone(),
     )
     .await
     .expect("could not start protocol controller");
 
     // launch factory
     let factory_config = FactoryConfig {
-        thread_count: THREAD_COUNT,
-        genesis_timestamp: *GENESIS_TIMESTAMP,
+        thread_count: genesis.thread_count,
+        genesis_timestamp: genesis.genesis_timestamp,
         t0: T0,
         initial_delay: SETTINGS.factory.initial_delay,
         max_block_size: MAX_BLOCK_SIZE as u64,
         max_block_gas: MAX_GAS_PER_BLOCK,
+        catch_up_max_lag_periods: SETTINGS.factory.catch_up_max_lag_periods,
+        catch_up_timeout: SETTINGS.factory.catch_up_timeout,
+        min_production_peers: SETTINGS.factory.min_production_peers,
+        // the sync progress is not followed if its interval is 0
+        produce_only_when_synced: SETTINGS.factory.produce_only_when_synced
+            && SETTINGS.protocol.sync_progress_interval.to_millis() > 0,
+        upgrade_schedule,
     };
     let factory_channels = FactoryChannels {
         selector: selector_controller.clone(),
         consensus: consensus_controller.clone(),
+        execution: execution_controller.clone(),
         pool: pool_controller.clone(),
         protocol: ProtocolCommandSender(protocol_command_sender.clone()),
         storage: shared_storage.clone(),
+        production_gate: protocol_senders.production_gate.clone(),
+        sync_progress: protocol_senders.sync_progress.clone(),
+        slot_scheduler: slot_scheduler.clone(),
+    };
+    // observer nodes do not produce blocks nor endorsements
+    let factory_manager = if SETTINGS.network.observer {
+        info!("observer mode: block and endorsement production is disabled");
+        None
+    } else {
+        Some(start_factory(
+            factory_config,
+            node_wallet.clone(),
+            factory_channels,
+        ))
     };
-    let factory_manager = start_factory(factory_config, node_wallet.clone(), factory_channels);
+
+    // notify the events of the node to the webhook, if any
+    let notifier_handle = Notifier::new(
+        SETTINGS.notifier.clone(),
+        node_id,
+        NotifierSources {
+            consensus_controller: consensus_controller.clone(),
+            execution_controller: execution_controller.clone(),
+            network_command_sender: network_command_sender.clone(),
+            partition_status: protocol_senders.partition_status.clone(),
+            node_wallet: node_wallet.clone(),
+            slot_scheduler,
+        },
+    )
+    .map(Notifier::start);
+
+    // prune and compact the archived blocks in the background
+    let archive_maintenance_handle = archive.clone().map(|archive| {
+        start_maintenance(
+            archive,
+            MaintenanceConfig {
+                interval: SETTINGS.archive.maintenance_interval.to_duration(),
+                block_retention_periods: SETTINGS
+                    .archive
+                    .retention_cycles
+                    .saturating_mul(PERIODS_PER_CYCLE),
+                body_retention_periods: SETTINGS
+                    .archive
+                    .body_retention_cycles
+                    .saturating_mul(PERIODS_PER_CYCLE),
+                batch_size: SETTINGS.archive.maintenance_batch_size,
+                batch_pause: SETTINGS.archive.maintenance_batch_pause.to_duration(),
+            },
+        )
+    });
+
+    // take the snapshots of the final state on archival nodes
+    let archiver_handle = archive.clone().map(|archive| {
+        Archiver::new(
+            SETTINGS.archive.clone(),
+            archive,
+            final_state.clone(),
+            consensus_controller.clone(),
+            *VERSION,
+        )
+        .start()
+    });
 
     // launch bootstrap server
     let bootstrap_manager = start_bootstrap_server(
@@ -492,6 +715,7 @@ async fn launch(
         massa_bootstrap::Establisher::new(),
         private_key,
         *VERSION,
+        resource_status.clone(),
     )
     .await
     .unwrap();
@@ -500,6 +724,10 @@ async fn launch(
         bind_private: SETTINGS.api.bind_private,
         bind_public: SETTINGS.api.bind_public,
         bind_api: SETTINGS.api.bind_api,
+        bind_grpc: SETTINGS.api.bind_grpc,
+        observer: SETTINGS.network.observer,
+        private_api_token: SETTINGS.api.private_api_token.clone(),
+        max_sync_lag_periods: SETTINGS.api.max_sync_lag_periods,
         draw_lookahead_period_count: SETTINGS.api.draw_lookahead_period_count,
         max_arguments: SETTINGS.api.max_arguments,
         openrpc_spec_path: SETTINGS.api.openrpc_spec_path.clone(),
@@ -511,6 +739,8 @@ async fn launch(
         max_subscriptions_per_connection: SETTINGS.api.max_subscriptions_per_connection,
         max_log_length: SETTINGS.api.max_log_length,
         allow_hosts: SETTINGS.api.allow_hosts.clone(),
+        cors_allowed_origins: SETTINGS.api.cors_allowed_origins.clone(),
+        cors_max_age: SETTINGS.api.cors_max_age,
         batch_requests_supported: SETTINGS.api.batch_requests_supported,
         ping_interval: SETTINGS.api.ping_interval,
         enable_http: SETTINGS.api.enable_http,
@@ -521,16 +751,43 @@ async fn launch(
         max_op_datastore_value_length: MAX_OPERATION_DATASTORE_VALUE_LENGTH,
         max_function_name_length: MAX_FUNCTION_NAME_LENGTH,
         max_parameter_size: MAX_PARAMETERS_SIZE,
-        thread_count: THREAD_COUNT,
-        genesis_timestamp: *GENESIS_TIMESTAMP,
+        thread_count: genesis.thread_count,
+        genesis_timestamp: genesis.genesis_timestamp,
         t0: T0,
         periods_per_cycle: PERIODS_PER_CYCLE,
+        endorsement_count: ENDORSEMENT_COUNT,
+        operation_validity_periods: OPERATION_VALIDITY_PERIODS,
+        max_operation_future_validity_start_periods: SETTINGS
+            .pool
+            .max_operation_future_validity_start_periods,
+        roll_price: ROLL_PRICE,
+    };
+
+    // spawn gRPC API, if enabled
+    let grpc_handle = match SETTINGS.api.bind_grpc {
+        Some(bind_grpc) => Some(
+            API::<Grpc>::new(Grpc {
+                consensus_channels: consensus_channels.clone(),
+                execution_controller: execution_controller.clone(),
+                pool_command_sender: pool_controller.clone(),
+                protocol_command_sender: ProtocolCommandSender(protocol_command_sender.clone()),
+                storage: shared_storage.clone(),
+                api_settings: api_config.clone(),
+                version: *VERSION,
+                resource_status: resource_status.clone(),
+            })
+            .serve(&bind_grpc)
+            .await
+            .expect("failed to start gRPC API"),
+        ),
+        None => None,
     };
 
     // spawn Massa API
     let api = API::<ApiV2>::new(
         consensus_channels,
-        protocol_senders,
+        protocol_senders.clone(),
+        pool_channels,
         api_config.clone(),
         *VERSION,
     );
@@ -544,11 +801,26 @@ async fn launch(
     api_config.enable_ws = false;
 
     // spawn private API
+    if api_config.private_api_token.is_none() && !SETTINGS.api.bind_private.ip().is_loopback() {
+        warn!(
+            "private API bound to non-loopback address {} without private_api_token, anyone reaching it can manage the node",
+            SETTINGS.api.bind_private
+        );
+    }
+    let (reload_config_tx, reload_config_rx) = mpsc::channel(1);
     let (api_private, api_private_stop_rx) = API::<Private>::new(
         network_command_sender.clone(),
         execution_controller.clone(),
+        consensus_controller.clone(),
+        final_state.clone(),
         api_config.clone(),
         node_wallet,
+        log_filter_handle.clone(),
+        recent_logs,
+        reload_config_tx,
+        archive,
+        address_index.clone(),
+        protocol_senders.production_gate.clone(),
     );
     let api_private_handle = api_private
         .serve(&SETTINGS.api.bind_private, &api_config)
@@ -568,12 +840,24 @@ async fn launch(
         network_command_sender.clone(),
         node_id,
         shared_storage.clone(),
+        address_index,
+        protocol_senders.partition_status.clone(),
+        sync_progress,
+        resource_status,
     );
     let api_public_handle = api_public
         .serve(&SETTINGS.api.bind_public, &api_config)
         .await
         .expect("failed to start PUBLIC API");
 
+    // reload the runtime-tunable settings on SIGHUP or private API request
+    let config_reloader_handle = ConfigReloader::new(
+        network_command_sender.clone(),
+        pool_controller.clone(),
+        log_filter_handle,
+    )
+    .start(reload_config_rx);
+
     #[cfg(feature = "deadlock_detection")]
     {
         // only for #[cfg]
@@ -617,9 +901,25 @@ async fn launch(
         api_private_handle,
         api_public_handle,
         api_handle,
+        grpc_handle,
+        config_reloader_handle,
+        notifier_handle,
+        resource_monitor_handle,
+        archiver_handle,
+        archive_maintenance_handle,
+        ShutdownSnapshotSource {
+            final_state,
+            consensus_controller,
+        },
     )
 }
 
+/// Components the snapshot saved at clean shutdown is read from
+struct ShutdownSnapshotSource {
+    final_state: Arc<RwLock<FinalState>>,
+    consensus_controller: Box<dyn ConsensusController>,
+}
+
 struct Managers {
     bootstrap_manager: Option<BootstrapManager>,
     consensus_manager: Box<dyn ConsensusManager>,
@@ -628,9 +928,10 @@ struct Managers {
     pool_manager: Box<dyn PoolManager>,
     protocol_manager: ProtocolManager,
     network_manager: NetworkManager,
-    factory_manager: Box<dyn FactoryManager>,
+    factory_manager: Option<Box<dyn FactoryManager>>,
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn stop(
     _consensus_event_receiver: Receiver<ConsensusEvent>,
     Managers {
@@ -641,13 +942,24 @@ async fn stop(
         mut pool_manager,
         protocol_manager,
         network_manager,
-        mut factory_manager,
+        factory_manager,
     }: Managers,
     api_private_handle: StopHandle,
     api_public_handle: StopHandle,
     api_handle: StopHandle,
+    grpc_handle: Option<GrpcStopHandle>,
+    config_reloader_handle: JoinHandle<()>,
+    notifier_handle: Option<JoinHandle<()>>,
+    resource_monitor_handle: JoinHandle<()>,
+    archiver_handle: Option<JoinHandle<()>>,
+    archive_maintenance_handle: Option<MaintenanceHandle>,
+    shutdown_snapshot: Option<ShutdownSnapshotSource>,
 ) {
-    // stop bootstrap
+    // the process is ended if the workers take too long to stop
+    let mut watchdog = ShutdownWatchdog::start(SETTINGS.shutdown.timeout);
+
+    // stop serving bootstraps
+    watchdog.step("stopping bootstrap server");
     if let Some(bootstrap_manager) = bootstrap_manager {
         bootstrap_manager
             .stop()
@@ -655,80 +967,307 @@ async fn stop(
             .expect("bootstrap server shutdown failed")
     }
 
+    // stop accepting new connections, the established ones are closed when the network is stopped
+    watchdog.step("stopping network listener");
+    if let Err(err) = network_manager.stop_listening().await {
+        warn!("could not stop accepting new connections: {}", err);
+    }
+
+    watchdog.step("stopping APIs");
     // stop public API
     api_public_handle.stop();
 
     // stop private API
     api_private_handle.stop();
 
+    // stop the configuration reloader
+    config_reloader_handle.abort();
+
+    // stop the notifier
+    if let Some(notifier_handle) = notifier_handle {
+        notifier_handle.abort();
+    }
+
+    // stop the resource monitor
+    resource_monitor_handle.abort();
+
+    // stop the archiver
+    if let Some(archiver_handle) = archiver_handle {
+        archiver_handle.abort();
+    }
+
     // stop Massa API
     api_handle.stop();
 
-    // stop factory
-    factory_manager.stop();
+    // stop gRPC API
+    if let Some(grpc_handle) = grpc_handle {
+        grpc_handle.stop().await;
+    }
+
+    // stop the archive maintenance, after its current batch
+    if let Some(archive_maintenance_handle) = archive_maintenance_handle {
+        watchdog.step("stopping archive maintenance");
+        archive_maintenance_handle.stop();
+    }
+
+    // stop producing blocks and endorsements
+    watchdog.step("stopping factory");
+    if let Some(mut factory_manager) = factory_manager {
+        factory_manager.stop();
+    }
 
     // stop protocol controller
+    watchdog.step("stopping protocol");
     let network_event_receiver = protocol_manager
         .stop()
         .await
         .expect("protocol shutdown failed");
 
-    // stop consensus
+    // save the final state and the final blocks for a fast restart, no new block is received anymore
+    if let (Some(source), Some(path)) = (
+        shutdown_snapshot,
+        SETTINGS.bootstrap.shutdown_snapshot_path.clone(),
+    ) {
+        watchdog.step("saving shutdown snapshot");
+        let result = tokio::task::spawn_blocking(move || {
+            export_snapshot(
+                &path,
+                &source.final_state,
+                source.consensus_controller.as_ref(),
+                *VERSION,
+            )
+            .map(|slot| (slot, path))
+        })
+        .await;
+        match result {
+            Ok(Ok((slot, path))) => info!(
+                "state of final slot {} saved to {} for the next startup",
+                slot,
+                path.display()
+            ),
+            Ok(Err(err)) => warn!("could not save the shutdown snapshot: {}", err),
+            Err(err) => warn!("shutdown snapshot export panicked: {}", err),
+        }
+    }
+
+    // stop consensus, once the blocks already received are processed
+    watchdog.step("stopping consensus");
     consensus_manager.stop();
 
     // stop pool
+    // note that the pool is only kept in memory: there is nothing to save
+    watchdog.step("stopping pool");
     pool_manager.stop();
 
-    // stop execution controller
+    // stop execution controller, once the final slots already notified are written to the final state
+    watchdog.step("stopping execution");
     execution_manager.stop();
 
     // stop selector controller
+    watchdog.step("stopping selector");
     selector_manager.stop();
 
-    // stop pool controller
-    // TODO
-    //let protocol_pool_event_receiver = pool_manager.stop().await.expect("pool shutdown failed");
-
-    // stop network controller
+    // stop network controller: peers are sent a disconnect message and the peer database is saved
+    watchdog.step("stopping network");
     network_manager
         .stop(network_event_receiver)
         .await
         .expect("network shutdown failed");
 
+    watchdog.finish();
+
     // note that FinalLedger gets destroyed as soon as its Arc count goes to zero
 }
 
+/// Configuration of the final state, with its disk ledger at `disk_ledger_path`
+fn final_state_config(genesis: &GenesisConfig, disk_ledger_path: PathBuf) -> FinalStateConfig {
+    let ledger_config = LedgerConfig {
+        thread_count: genesis.thread_count,
+        initial_ledger_path: genesis.initial_ledger_path.clone(),
+        disk_ledger_path,
+        max_key_length: MAX_DATASTORE_KEY_LENGTH,
+        max_ledger_part_size: LEDGER_PART_SIZE_MESSAGE_BYTES,
+    };
+    let async_pool_config = AsyncPoolConfig {
+        max_length: MAX_ASYNC_POOL_LENGTH,
+        thread_count: genesis.thread_count,
+        bootstrap_part_size: ASYNC_POOL_BOOTSTRAP_PART_SIZE,
+        max_async_message_data: MAX_ASYNC_MESSAGE_DATA,
+    };
+    let pos_config = PoSConfig {
+        periods_per_cycle: PERIODS_PER_CYCLE,
+        thread_count: genesis.thread_count,
+        cycle_history_length: POS_SAVED_CYCLES,
+        credits_bootstrap_part_size: DEFERRED_CREDITS_BOOTSTRAP_PART_SIZE,
+    };
+    let executed_ops_config = ExecutedOpsConfig {
+        thread_count: genesis.thread_count,
+        bootstrap_part_size: EXECUTED_OPS_BOOTSTRAP_PART_SIZE,
+    };
+    FinalStateConfig {
+        ledger_config,
+        async_pool_config,
+        pos_config,
+        executed_ops_config,
+        final_history_length: SETTINGS.ledger.final_history_length,
+        thread_count: genesis.thread_count,
+        periods_per_cycle: PERIODS_PER_CYCLE,
+        initial_seed_string: INITIAL_DRAW_SEED.into(),
+        initial_rolls_path: genesis.initial_rolls_path.clone(),
+    }
+}
+
+/// Configuration of the selector
+fn selector_config(genesis: &GenesisConfig) -> SelectorConfig {
+    SelectorConfig {
+        max_draw_cache: SETTINGS.selector.max_draw_cache,
+        channel_size: CHANNEL_SIZE,
+        thread_count: genesis.thread_count,
+        endorsement_count: ENDORSEMENT_COUNT,
+        periods_per_cycle: PERIODS_PER_CYCLE,
+        genesis_address: Address::from_public_key(&GENESIS_KEY.get_public_key()),
+    }
+}
+
+/// Configuration of the execution
+fn execution_config(genesis: &GenesisConfig) -> ExecutionConfig {
+    // Storage costs constants
+    let storage_costs_constants = StorageCostsConstants {
+        ledger_cost_per_byte: LEDGER_COST_PER_BYTE,
+        ledger_entry_base_cost: LEDGER_COST_PER_BYTE
+            .checked_mul_u64(LEDGER_ENTRY_BASE_SIZE as u64)
+            .expect("Overflow when creating constant ledger_entry_base_cost"),
+        ledger_entry_datastore_base_cost: LEDGER_COST_PER_BYTE
+            .checked_mul_u64(LEDGER_ENTRY_DATASTORE_BASE_SIZE as u64)
+            .expect("Overflow when creating constant ledger_entry_datastore_base_size"),
+    };
+    ExecutionConfig {
+        max_final_events: SETTINGS.execution.max_final_events,
+        readonly_queue_length: SETTINGS.execution.readonly_queue_length,
+        cursor_delay: SETTINGS.execution.cursor_delay,
+        max_async_gas: MAX_ASYNC_GAS,
+        max_gas_per_block: MAX_GAS_PER_BLOCK,
+        roll_price: ROLL_PRICE,
+        thread_count: genesis.thread_count,
+        t0: T0,
+        genesis_timestamp: genesis.genesis_timestamp,
+        block_reward: BLOCK_REWARD,
+        endorsement_count: ENDORSEMENT_COUNT as u64,
+        operation_validity_period: OPERATION_VALIDITY_PERIODS,
+        periods_per_cycle: PERIODS_PER_CYCLE,
+        stats_time_window_duration: SETTINGS.execution.stats_time_window_duration,
+        max_miss_ratio: *POS_MISS_RATE_DEACTIVATION_THRESHOLD,
+        max_datastore_key_length: MAX_DATASTORE_KEY_LENGTH,
+        max_bytecode_size: MAX_BYTECODE_LENGTH,
+        max_datastore_value_size: MAX_DATASTORE_VALUE_LENGTH,
+        storage_costs_constants,
+        max_read_only_gas: SETTINGS.execution.max_read_only_gas,
+        gas_costs: GasCosts::new(
+            SETTINGS.execution.abi_gas_costs_file.clone(),
+            SETTINGS.execution.wasm_gas_costs_file.clone(),
+        )
+        .expect("Failed to load gas costs"),
+    }
+}
+
+/// Configuration of the archive of the final blocks
+fn archive_config(thread_count: u8) -> ArchiveConfig {
+    ArchiveConfig {
+        path: SETTINGS.archive.path.clone(),
+        thread_count,
+        endorsement_count: ENDORSEMENT_COUNT,
+        max_operations_per_block: MAX_OPERATIONS_PER_BLOCK,
+        max_datastore_value_length: MAX_DATASTORE_VALUE_LENGTH,
+        max_function_name_length: MAX_FUNCTION_NAME_LENGTH,
+        max_parameters_size: MAX_PARAMETERS_SIZE,
+        max_op_datastore_entry_count: MAX_OPERATION_DATASTORE_ENTRY_COUNT,
+        max_op_datastore_key_length: MAX_OPERATION_DATASTORE_KEY_LENGTH,
+        max_op_datastore_value_length: MAX_OPERATION_DATASTORE_VALUE_LENGTH,
+    }
+}
+
+/// Open the archive outside of the node, for the commands that exit without starting it
+fn open_archive() -> anyhow::Result<Archive> {
+    let genesis = GenesisConfig::load(&SETTINGS.genesis.genesis_file_path)?;
+    Archive::open(archive_config(genesis.thread_count))
+        .map_err(|err| anyhow::anyhow!("could not open the archive: {}", err))
+}
+
 #[derive(StructOpt)]
 struct Args {
     /// Wallet password
     #[structopt(short = "p", long = "pwd")]
     password: Option<String>,
+    /// Check the integrity of the archive and of the address index, then exit without starting the node
+    #[structopt(long = "check-db")]
+    check_db: bool,
+    /// Like --check-db, also removing the corrupted entries and indexing the archived blocks again
+    #[structopt(long = "repair-db")]
+    repair_db: bool,
+    /// Rebuild the final state from the blocks archived in the given directory, without networking,
+    /// checking it against the recorded final state hashes, then exit without starting the node
+    #[structopt(long = "replay", parse(from_os_str))]
+    replay: Option<PathBuf>,
+    #[structopt(subcommand)]
+    command: Option<NodeCommand>,
 }
 
-/// Load wallet, asking for passwords if necessary
-fn load_wallet(password: Option<String>, path: &Path) -> anyhow::Result<Arc<RwLock<Wallet>>> {
-    let password = if path.is_file() {
+#[derive(StructOpt)]
+enum NodeCommand {
+    /// Export the archived final blocks of a slot range, then exit without starting the node
+    Export(export::ExportArgs),
+}
+
+/// Ask for the password of the encrypted file at `path`, unless it is given
+fn ask_password(password: Option<String>, path: &Path, description: &str) -> String {
+    if path.is_file() {
         password.unwrap_or_else(|| {
             Password::new()
-                .with_prompt("Enter staking keys file password")
+                .with_prompt(format!("Enter {} password", description))
                 .interact()
-                .expect("IO error: Password reading failed, staking keys file couldn't be unlocked")
+                .unwrap_or_else(|_| {
+                    panic!(
+                        "IO error: Password reading failed, {} couldn't be unlocked",
+                        description
+                    )
+                })
         })
     } else {
         password.unwrap_or_else(|| {
             Password::new()
-                .with_prompt("Enter new password for staking keys file")
+                .with_prompt(format!("Enter new password for {}", description))
                 .with_confirmation("Confirm password", "Passwords mismatching")
                 .interact()
-                .expect("IO error: Password reading failed, staking keys file couldn't be created")
+                .unwrap_or_else(|_| {
+                    panic!(
+                        "IO error: Password reading failed, {} couldn't be created",
+                        description
+                    )
+                })
         })
-    };
+    }
+}
+
+/// Load wallet, asking for passwords if necessary
+fn load_wallet(password: Option<String>, path: &Path) -> anyhow::Result<Arc<RwLock<Wallet>>> {
+    let password = ask_password(password, path, "staking keys file");
     Ok(Arc::new(RwLock::new(Wallet::new(
         PathBuf::from(path),
         password,
     )?)))
 }
 
+/// Create the empty wallet of an observer node, without reading the staking keys file.
+/// The password is only used for the node key file.
+fn observer_wallet(password: Option<String>) -> Arc<RwLock<Wallet>> {
+    let password = ask_password(password, &SETTINGS.network.keypair_file, "node key file");
+    Arc::new(RwLock::new(Wallet {
+        keys: Default::default(),
+        wallet_path: SETTINGS.factory.staking_wallet_path.clone(),
+        password,
+    }))
+}
+
 #[paw::main]
 fn main(args: Args) -> anyhow::Result<()> {
     let tokio_rt = tokio::runtime::Builder::new_multi_thread()
@@ -746,22 +1285,50 @@ fn main(args: Args) -> anyhow::Result<()> {
 
 async fn run(args: Args) -> anyhow::Result<()> {
     use tracing_subscriber::prelude::*;
-    // spawn the console server in the background, returning a `Layer`:
-    let tracing_layer = tracing_subscriber::fmt::layer()
-        .with_filter(match SETTINGS.logging.level {
-            4 => LevelFilter::TRACE,
-            3 => LevelFilter::DEBUG,
-            2 => LevelFilter::INFO,
-            1 => LevelFilter::WARN,
-            _ => LevelFilter::ERROR,
-        })
-        .with_filter(filter_fn(|metadata| {
-            metadata.target().starts_with("massa") // ignore non-massa logs
-        }));
+    // log to the standard output with a filter that can be changed through the private API
+    let log_filter = build_log_filter(
+        level_filter_from_config(SETTINGS.logging.level),
+        &SETTINGS.logging.filters,
+    )
+    .expect("invalid logging filters in the configuration");
+    // the latest log lines are served by the private API
+    let recent_logs = RecentLogs::new(SETTINGS.logging.recent_lines);
+    let (tracing_layer, log_filter_handle) =
+        build_log_layer(SETTINGS.logging.format, log_filter, recent_logs.clone());
+    // export the spans of the workers to an OpenTelemetry collector if an endpoint is configured
+    let otlp_layer = match &SETTINGS.logging.otlp_endpoint {
+        Some(endpoint) => {
+            let tracer = opentelemetry_otlp::new_pipeline()
+                .tracing()
+                .with_exporter(
+                    opentelemetry_otlp::new_exporter()
+                        .tonic()
+                        .with_endpoint(endpoint.clone()),
+                )
+                .with_trace_config(opentelemetry::sdk::trace::config().with_resource(
+                    opentelemetry::sdk::Resource::new(vec![opentelemetry::KeyValue::new(
+                        "service.name",
+                        SETTINGS.logging.otlp_service_name.clone(),
+                    )]),
+                ))
+                .install_batch(opentelemetry::runtime::Tokio)
+                .map_err(|err| {
+                    anyhow::anyhow!("could not start the OpenTelemetry exporter: {}", err)
+                })?;
+            Some(
+                tracing_opentelemetry::layer()
+                    .with_tracer(tracer)
+                    .with_filter(LevelFilter::DEBUG)
+                    .with_filter(filter_fn(|metadata| metadata.target().starts_with("massa"))),
+            )
+        }
+        None => None,
+    };
     // build a `Subscriber` by combining layers with a `tracing_subscriber::Registry`:
     tracing_subscriber::registry()
         // add the console layer to the subscriber or default layers...
         .with(tracing_layer)
+        .with(otlp_layer)
         .init();
 
     // Setup panic handlers,
@@ -774,8 +1341,57 @@ async fn run(args: Args) -> anyhow::Result<()> {
         std::process::exit(1);
     }));
 
-    // load or create wallet, asking for password if necessary
-    let node_wallet = load_wallet(args.password, &SETTINGS.factory.staking_wallet_path)?;
+    // report all the configuration problems before starting any worker
+    let config_problems = config_check::check_settings(&SETTINGS);
+    if !config_problems.is_empty() {
+        for problem in &config_problems {
+            error!("invalid configuration: {}", problem);
+        }
+        anyhow::bail!(
+            "{} configuration problem(s) found, fix them and restart the node",
+            config_problems.len()
+        );
+    }
+
+    // the worker pool must be sized before its first use
+    if let Err(err) = massa_hash::init_worker_pool(SETTINGS.crypto.worker_pool_size) {
+        warn!("could not size the worker pool: {}", err);
+    }
+
+    if args.check_db || args.repair_db {
+        return check_db::check_db(args.repair_db);
+    }
+    if let Some(path) = args.replay {
+        return replay::replay(path);
+    }
+    if let Some(NodeCommand::Export(export_args)) = args.command {
+        return export::export(export_args);
+    }
+
+    // load the genesis parameters: an invalid genesis, ex: with an unknown protocol feature, stops the node
+    let genesis = GenesisConfig::load(&SETTINGS.genesis.genesis_file_path)?;
+
+    // load or create wallet, asking for password if necessary, observer nodes do not load the staking keys
+    let node_wallet = if SETTINGS.network.observer {
+        observer_wallet(args.password)
+    } else {
+        load_wallet(args.password, &SETTINGS.factory.staking_wallet_path)?
+    };
+
+    // load or create the key identifying the node on the network, encrypted with the same password
+    let node_keypair =
+        load_or_create_keypair_file(&SETTINGS.network.keypair_file, &node_wallet.read().password)
+            .map_err(|err| anyhow::anyhow!("could not load the node key file: {}", err))?;
+
+    // the metrics are kept when the workers are restarted to bootstrap again
+    let metrics_server = if SETTINGS.metrics.enabled {
+        Some(
+            start_metrics_server(SETTINGS.metrics.bind)
+                .map_err(|err| anyhow::anyhow!("could not start the metrics server: {}", err))?,
+        )
+    } else {
+        None
+    };
 
     loop {
         let (
@@ -792,13 +1408,27 @@ async fn run(args: Args) -> anyhow::Result<()> {
             api_private_handle,
             api_public_handle,
             api_handle,
-        ) = launch(node_wallet.clone()).await;
+            grpc_handle,
+            config_reloader_handle,
+            notifier_handle,
+            resource_monitor_handle,
+            archiver_handle,
+            archive_maintenance_handle,
+            shutdown_snapshot_source,
+        ) = launch(
+            &genesis,
+            node_wallet.clone(),
+            node_keypair.clone(),
+            log_filter_handle.clone(),
+            recent_logs.clone(),
+        )
+        .await;
 
         // interrupt signal listener
         let (tx, rx) = crossbeam_channel::bounded(1);
         let interrupt_signal_listener = tokio::spawn(async move {
-            signal::ctrl_c().await.unwrap();
-            tx.send(()).unwrap();
+            let signal_name = shutdown::wait_for_stop_signal().await;
+            tx.send(signal_name).unwrap();
         });
 
         // loop over messages
@@ -833,8 +1463,8 @@ async fn run(args: Args) -> anyhow::Result<()> {
                 _ => {}
             }
             match rx.try_recv() {
-                Ok(_) => {
-                    info!("interrupt signal received");
+                Ok(signal_name) => {
+                    info!("{} received, stopping the node", signal_name);
                     break false;
                 }
                 Err(crossbeam_channel::TryRecvError::Disconnected) => {
@@ -860,6 +1490,14 @@ async fn run(args: Args) -> anyhow::Result<()> {
             api_private_handle,
             api_public_handle,
             api_handle,
+            grpc_handle,
+            config_reloader_handle,
+            notifier_handle,
+            resource_monitor_handle,
+            archiver_handle,
+            archive_maintenance_handle,
+            // the state is not saved when bootstrapping again: it is out of sync
+            (!restart).then_some(shutdown_snapshot_source),
         )
         .await;
 
@@ -868,5 +1506,14 @@ async fn run(args: Args) -> anyhow::Result<()> {
         }
         interrupt_signal_listener.abort();
     }
+
+    if let Some(metrics_server) = metrics_server {
+        metrics_server.stop().await;
+    }
+
+    // flush the spans that were not exported yet
+    if SETTINGS.logging.otlp_endpoint.is_some() {
+        let _ = tokio::task::spawn_blocking(opentelemetry::global::shutdown_tracer_provider).await;
+    }
     Ok(())
 }
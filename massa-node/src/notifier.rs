@@ -0,0 +1,346 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Notification of operational events to an HTTP webhook.
+//!
+//! Every `check_interval` the notifier looks at the state of the node and posts the enabled events to the webhook:
+//! * `node_desynced`: the blockclique lags more than `max_sync_lag_periods` periods behind the current slot,
+//! * `missed_blocks`: a staking address of the node missed blocks,
+//! * `low_peer_count`: fewer than `min_peer_count` nodes are connected,
+//! * `low_disk_space`: less than `min_free_disk_space` bytes are available on the disk of the ledger or of the peers file,
+//! * `network_partition`: the network partition watchdog of the protocol worker raised an alert.
+//!
+//! Conditions are notified when they start and when they end. The payload is understood by Slack or Discord
+//! depending on `webhook_format`. Failed posts are retried with an exponential backoff, and at most
+//! `rate_limit_count` notifications are posted per `rate_limit_period`: the next ones are dropped and counted
+//! in the following notification.
+
+use crate::resource_monitor::get_available_space;
+use crate::settings::{NotifierSettings, SETTINGS};
+use anyhow::{bail, Context, Result};
+use hyper::client::HttpConnector;
+use hyper::{header::CONTENT_TYPE, Body, Client, Request};
+use hyper_rustls::HttpsConnector;
+use massa_consensus_exports::ConsensusController;
+use massa_execution_exports::ExecutionController;
+use massa_models::{
+    address::Address, api::NetworkPartitionAlert, node::NodeId, slot_scheduler::SlotScheduler,
+};
+use massa_network_exports::NetworkCommandSender;
+use massa_protocol_exports::NetworkPartitionStatus;
+use massa_wallet::Wallet;
+use parking_lot::RwLock;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+/// Maximum duration of a post to the webhook
+const WEBHOOK_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Events that can be notified
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NotifiedEvent {
+    /// the blockclique lags behind the current slot
+    NodeDesynced,
+    /// a staking address missed blocks
+    MissedBlocks,
+    /// few nodes are connected
+    LowPeerCount,
+    /// the disk of the node data is almost full
+    LowDiskSpace,
+    /// the network partition watchdog raised an alert
+    NetworkPartition,
+}
+
+/// Payload format of the webhook
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookFormat {
+    /// `{"text": "..."}`
+    Slack,
+    /// `{"content": "..."}`
+    Discord,
+}
+
+/// Components the state of the node is read from
+pub struct NotifierSources {
+    /// link to the consensus component
+    pub consensus_controller: Box<dyn ConsensusController>,
+    /// link to the execution component
+    pub execution_controller: Box<dyn ExecutionController>,
+    /// link to the network component
+    pub network_command_sender: NetworkCommandSender,
+    /// alerts of the network partition watchdog
+    pub partition_status: NetworkPartitionStatus,
+    /// staking wallet
+    pub node_wallet: Arc<RwLock<Wallet>>,
+    /// source of time of the slots
+    pub slot_scheduler: Box<dyn SlotScheduler>,
+}
+
+/// Posts the events of the node to the webhook
+pub struct Notifier {
+    settings: NotifierSettings,
+    webhook_url: String,
+    node_id: NodeId,
+    sources: NotifierSources,
+    client: Client<HttpsConnector<HttpConnector>, Body>,
+    /// conditions currently notified, by key, with their message
+    active_conditions: BTreeMap<String, String>,
+    /// missed block count of each staking address by cycle, as seen at the previous check
+    missed_blocks: HashMap<(Address, u64), u64>,
+    /// false until the missed blocks of the first check are known, they are not notified
+    missed_blocks_initialized: bool,
+    /// instants of the notifications posted during the current rate limit period
+    sent_instants: VecDeque<Instant>,
+    /// number of notifications dropped by the rate limit since the last one posted
+    dropped_count: usize,
+}
+
+impl Notifier {
+    /// Create the notifier, none if no webhook is configured
+    pub fn new(
+        settings: NotifierSettings,
+        node_id: NodeId,
+        sources: NotifierSources,
+    ) -> Option<Self> {
+        let webhook_url = settings.webhook_url.clone()?;
+        let connector = hyper_rustls::HttpsConnectorBuilder::new()
+            .with_native_roots()
+            .https_or_http()
+            .enable_http1()
+            .build();
+        Some(Notifier {
+            settings,
+            webhook_url,
+            node_id,
+            sources,
+            client: Client::builder().build(connector),
+            active_conditions: BTreeMap::new(),
+            missed_blocks: HashMap::new(),
+            missed_blocks_initialized: false,
+            sent_instants: VecDeque::new(),
+            dropped_count: 0,
+        })
+    }
+
+    /// Spawn the task checking the state of the node every `check_interval`
+    pub fn start(mut self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.settings.check_interval.to_duration());
+            loop {
+                interval.tick().await;
+                self.check().await;
+            }
+        })
+    }
+
+    fn is_enabled(&self, event: NotifiedEvent) -> bool {
+        self.settings.events.contains(&event)
+    }
+
+    /// Check the state of the node and notify the conditions that started or ended, and the missed blocks
+    async fn check(&mut self) {
+        let mut conditions = BTreeMap::new();
+        if self.is_enabled(NotifiedEvent::NodeDesynced) {
+            if let Some(message) = self.check_sync() {
+                conditions.insert("node_desynced".to_string(), message);
+            }
+        }
+        if self.is_enabled(NotifiedEvent::LowPeerCount) {
+            match self
+                .sources
+                .network_command_sender
+                .get_network_stats()
+                .await
+            {
+                Ok(stats) if (stats.active_node_count as usize) < self.settings.min_peer_count => {
+                    conditions.insert(
+                        "low_peer_count".to_string(),
+                        format!("only {} connected nodes", stats.active_node_count),
+                    );
+                }
+                Ok(_) => {}
+                Err(err) => debug!("notifier could not get the network stats: {}", err),
+            }
+        }
+        if self.is_enabled(NotifiedEvent::LowDiskSpace) {
+            for path in [
+                &SETTINGS.ledger.disk_ledger_path,
+                &SETTINGS.network.peers_file,
+            ] {
+                match get_available_space(path) {
+                    Ok(available) if available < self.settings.min_free_disk_space => {
+                        conditions.insert(
+                            format!("low_disk_space:{}", path.display()),
+                            format!(
+                                "only {} MB available on the disk of {}",
+                                available / 1_000_000,
+                                path.display()
+                            ),
+                        );
+                    }
+                    Ok(_) => {}
+                    Err(err) => debug!("notifier could not get the available disk space: {}", err),
+                }
+            }
+        }
+        if self.is_enabled(NotifiedEvent::NetworkPartition) {
+            for alert in self.sources.partition_status.get_alerts() {
+                let kind = match alert {
+                    NetworkPartitionAlert::StalledFinality { .. } => "stalled_finality",
+                    NetworkPartitionAlert::LowOutConnections { .. } => "low_out_connections",
+                };
+                conditions.insert(
+                    format!("network_partition:{}", kind),
+                    format!("network partition alert: {}", alert),
+                );
+            }
+        }
+
+        let mut messages: Vec<String> = conditions
+            .iter()
+            .filter(|(key, _)| !self.active_conditions.contains_key(*key))
+            .map(|(_, message)| message.clone())
+            .collect();
+        messages.extend(
+            self.active_conditions
+                .iter()
+                .filter(|(key, _)| !conditions.contains_key(*key))
+                .map(|(_, message)| format!("resolved: {}", message)),
+        );
+        self.active_conditions = conditions;
+        if self.is_enabled(NotifiedEvent::MissedBlocks) {
+            messages.extend(self.check_missed_blocks());
+        }
+
+        for message in messages {
+            self.notify(message).await;
+        }
+    }
+
+    /// Get the lag of the blockclique if it is more than `max_sync_lag_periods` periods behind the current slot
+    fn check_sync(&self) -> Option<String> {
+        let current_slot = self.sources.slot_scheduler.get_current_slot().ok()??;
+        let latest_period = self
+            .sources
+            .consensus_controller
+            .get_best_parents()
+            .iter()
+            .map(|(_, period)| *period)
+            .max()
+            .unwrap_or(0);
+        let lag = current_slot.period.saturating_sub(latest_period);
+        (lag > SETTINGS.api.max_sync_lag_periods).then(|| {
+            format!(
+                "node desynced: the blockclique lags {} periods behind the current slot",
+                lag
+            )
+        })
+    }
+
+    /// Get the messages of the blocks missed by the staking addresses since the previous check
+    fn check_missed_blocks(&mut self) -> Vec<String> {
+        let addresses: Vec<Address> = self
+            .sources
+            .node_wallet
+            .read()
+            .get_wallet_address_list()
+            .into_iter()
+            .collect();
+        let mut missed_blocks = HashMap::new();
+        let mut messages = Vec::new();
+        for (address, info) in addresses.iter().zip(
+            self.sources
+                .execution_controller
+                .get_addresses_infos(&addresses),
+        ) {
+            for cycle_info in info.cycle_infos {
+                let key = (*address, cycle_info.cycle);
+                let previous = match self.missed_blocks.get(&key) {
+                    Some(previous) => *previous,
+                    None if self.missed_blocks_initialized => 0,
+                    None => cycle_info.nok_count,
+                };
+                if cycle_info.nok_count > previous {
+                    messages.push(format!(
+                        "staking address {} missed {} blocks in cycle {} ({} produced, {} missed)",
+                        address,
+                        cycle_info.nok_count - previous,
+                        cycle_info.cycle,
+                        cycle_info.ok_count,
+                        cycle_info.nok_count
+                    ));
+                }
+                missed_blocks.insert(key, cycle_info.nok_count);
+            }
+        }
+        self.missed_blocks = missed_blocks;
+        self.missed_blocks_initialized = true;
+        messages
+    }
+
+    /// Post a notification, unless the rate limit is reached
+    async fn notify(&mut self, message: String) {
+        let now = Instant::now();
+        let period = self.settings.rate_limit_period.to_duration();
+        while let Some(sent) = self.sent_instants.front() {
+            if now.saturating_duration_since(*sent) < period {
+                break;
+            }
+            self.sent_instants.pop_front();
+        }
+        if self.sent_instants.len() >= self.settings.rate_limit_count {
+            self.dropped_count += 1;
+            debug!("notification dropped by the rate limit: {}", message);
+            return;
+        }
+        self.sent_instants.push_back(now);
+
+        let mut text = format!("[massa-node {}] {}", self.node_id, message);
+        if self.dropped_count > 0 {
+            text.push_str(&format!(
+                " ({} earlier notifications dropped by the rate limit)",
+                self.dropped_count
+            ));
+            self.dropped_count = 0;
+        }
+        info!("notifying: {}", message);
+        let body = match self.settings.webhook_format {
+            WebhookFormat::Slack => serde_json::json!({ "text": text }),
+            WebhookFormat::Discord => serde_json::json!({ "content": text }),
+        }
+        .to_string();
+
+        let mut delay = self.settings.retry_delay.to_duration();
+        for attempt in 1..=self.settings.max_attempts {
+            match self.post(body.clone()).await {
+                Ok(()) => return,
+                Err(err) if attempt < self.settings.max_attempts => {
+                    debug!("notification post attempt {} failed: {}", attempt, err);
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                }
+                Err(err) => warn!("notification dropped after {} attempts: {}", attempt, err),
+            }
+        }
+    }
+
+    /// Post a payload to the webhook once
+    async fn post(&self, body: String) -> Result<()> {
+        let request = Request::post(self.webhook_url.as_str())
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .context("invalid webhook request")?;
+        let response = tokio::time::timeout(WEBHOOK_REQUEST_TIMEOUT, self.client.request(request))
+            .await
+            .context("webhook request timed out")??;
+        if !response.status().is_success() {
+            bail!("webhook answered with status {}", response.status());
+        }
+        Ok(())
+    }
+}
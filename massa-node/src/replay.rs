@@ -0,0 +1,282 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Deterministic replay of the archived final blocks, run instead of the node with `--replay <archive directory>`,
+//! and by the trustless sync to rebuild the final state of the node.
+//!
+//! The final state is rebuilt from the genesis without networking, by executing the archived blocks slot by slot,
+//! the slots without an archived block being executed as misses. The blocks are checked again on the way:
+//! signatures of the header, of its endorsements and of the operations, operations root, producer drawn by
+//! the selector, and parent in the thread of the block being the previous block of that thread.
+//! At every slot whose final state hash was recorded by the archiver, the hash of the rebuilt state must match.
+
+use crate::genesis::GenesisConfig;
+use crate::settings::SETTINGS;
+use crate::{archive_config, execution_config, final_state_config, selector_config};
+use anyhow::{anyhow, bail};
+use massa_consensus_worker::create_genesis_block;
+use massa_execution_worker::FinalSlotReplayer;
+use massa_final_state::FinalState;
+use massa_hash::Hash;
+use massa_ledger_worker::FinalLedger;
+use massa_models::{
+    block::BlockId, config::constants::GENESIS_KEY, config::PERIODS_PER_CYCLE, slot::Slot,
+    wrapped::Wrapped,
+};
+use massa_pos_exports::SelectorController;
+use massa_pos_worker::start_selector_worker;
+use massa_storage::{
+    archive::{Archive, ArchiveConfig},
+    Storage,
+};
+use parking_lot::RwLock;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::{debug, error, info, warn};
+
+/// Replay the blocks archived in `path`, failing at the first block that does not check
+/// or at the first slot whose recorded final state hash does not match
+pub fn replay(path: PathBuf) -> anyhow::Result<()> {
+    let genesis = GenesisConfig::load(&SETTINGS.genesis.genesis_file_path)?;
+    let archive = Archive::open(ArchiveConfig {
+        path: path.clone(),
+        ..archive_config(genesis.thread_count)
+    })
+    .map_err(|err| anyhow!("could not open the archive: {}", err))?;
+    let recorded_hashes = archive
+        .get_state_hashes()
+        .map_err(|err| anyhow!("could not read the recorded state hashes: {}", err))?;
+    info!(
+        "replaying the blocks archived in {} against {} recorded state hashes",
+        path.display(),
+        recorded_hashes.len()
+    );
+
+    // the rebuilt ledger is kept apart from the one of the node
+    let ledger_path =
+        std::env::temp_dir().join(format!("massa_replay_ledger_{}", std::process::id()));
+    if ledger_path.exists() {
+        std::fs::remove_dir_all(&ledger_path)?;
+    }
+    let (mut selector_manager, selector_controller) =
+        start_selector_worker(selector_config(&genesis))
+            .map_err(|err| anyhow!("could not start the selector: {}", err))?;
+    let result = new_final_state(&genesis, ledger_path.clone(), selector_controller.clone())
+        .and_then(|final_state| {
+            replay_blocks(
+                &genesis,
+                &archive,
+                &recorded_hashes,
+                final_state,
+                selector_controller,
+                None,
+            )
+        })
+        .map(|_latest_blocks| ());
+    selector_manager.stop();
+    if let Err(err) = std::fs::remove_dir_all(&ledger_path) {
+        warn!(
+            "could not remove the replay ledger {}: {}",
+            ledger_path.display(),
+            err
+        );
+    }
+    result
+}
+
+/// Replay state: the rebuilt final state and the recorded hashes it is checked against
+struct Replay<'a> {
+    replayer: FinalSlotReplayer,
+    recorded_hashes: &'a BTreeMap<Slot, Hash>,
+    /// slot to execute next
+    next_slot: Slot,
+    /// last executed slot, with the hash of the final state at its output
+    last_executed: Option<(Slot, Hash)>,
+    /// number of recorded hashes that matched
+    checked_hashes: usize,
+}
+
+impl<'a> Replay<'a> {
+    /// Execute the next slot, with `block` unless it is a miss, and check the recorded hash of the slot
+    fn execute_next_slot(
+        &mut self,
+        block: Option<&(BlockId, Storage)>,
+        thread_count: u8,
+    ) -> anyhow::Result<()> {
+        let slot = self.next_slot;
+        let hash = self.replayer.execute_final_slot(slot, block);
+        if let Some(recorded_hash) = self.recorded_hashes.get(&slot) {
+            if *recorded_hash != hash {
+                error!(
+                    "replay diverged at slot {}: final state hash {} instead of the recorded {}",
+                    slot, hash, recorded_hash
+                );
+                bail!("the replayed final state diverged at slot {}", slot);
+            }
+            debug!("final state hash of slot {} matched: {}", slot, hash);
+            self.checked_hashes += 1;
+        }
+        self.last_executed = Some((slot, hash));
+        self.next_slot = slot.get_next_slot(thread_count)?;
+        Ok(())
+    }
+}
+
+/// Create the final state of the genesis, with its ledger in `ledger_path`
+fn new_final_state(
+    genesis: &GenesisConfig,
+    ledger_path: PathBuf,
+    selector: Box<dyn SelectorController>,
+) -> anyhow::Result<Arc<RwLock<FinalState>>> {
+    let final_state_config = final_state_config(genesis, ledger_path);
+    let ledger = FinalLedger::new(final_state_config.ledger_config.clone());
+    Ok(Arc::new(RwLock::new(
+        FinalState::new(final_state_config, Box::new(ledger), selector)
+            .map_err(|err| anyhow!("could not init the final state: {}", err))?,
+    )))
+}
+
+/// Rebuild `final_state` from the genesis with the archived blocks, up to `last_slot` (included) if any,
+/// otherwise up to the last archived block or recorded hash
+///
+/// # Returns
+/// The latest replayed block of each thread, in a storage holding a reference to it
+pub(crate) fn replay_blocks(
+    genesis: &GenesisConfig,
+    archive: &Archive,
+    recorded_hashes: &BTreeMap<Slot, Hash>,
+    final_state: Arc<RwLock<FinalState>>,
+    selector: Box<dyn SelectorController>,
+    last_slot: Option<Slot>,
+) -> anyhow::Result<Vec<(BlockId, Storage)>> {
+    let thread_count = genesis.thread_count;
+    final_state
+        .write()
+        .compute_initial_draws()
+        .map_err(|err| anyhow!("could not compute the initial draws: {}", err))?;
+    let next_slot = final_state.read().slot.get_next_slot(thread_count)?;
+    let mut replay = Replay {
+        replayer: FinalSlotReplayer::new(execution_config(genesis), final_state, selector.clone()),
+        recorded_hashes,
+        next_slot,
+        last_executed: None,
+        checked_hashes: 0,
+    };
+
+    // latest block of each thread, in a storage holding a reference to it, starting with the genesis blocks
+    let root_storage = Storage::create_root();
+    let mut latest_blocks = Vec::with_capacity(thread_count as usize);
+    for thread in 0..thread_count {
+        let block = create_genesis_block(&GENESIS_KEY, thread)?;
+        let mut storage = root_storage.clone_without_refs();
+        let block_id = block.id;
+        storage.store_block(block);
+        latest_blocks.push((block_id, storage));
+    }
+
+    for archived_block in archive.iter_blocks(next_slot) {
+        let archived_block =
+            archived_block.map_err(|err| anyhow!("could not read an archived block: {}", err))?;
+        let slot = archived_block.header.content.slot;
+        if last_slot.map_or(false, |last_slot| slot > last_slot) {
+            break;
+        }
+        skip_to_slot(&mut replay, archive, slot, thread_count)?;
+
+        // check the block again
+        let header = &archived_block.header;
+        header.verify_signature()?;
+        Wrapped::verify_signatures_batch(&header.content.endorsements)?;
+        Wrapped::verify_signatures_batch(&archived_block.operations)?;
+        if !archived_block.check_operations() {
+            bail!(
+                "the operations of block {} at slot {} do not match its header",
+                header.id,
+                slot
+            );
+        }
+        selector.wait_for_draws(slot.get_cycle(PERIODS_PER_CYCLE))?;
+        let producer = selector.get_producer(slot)?;
+        if producer != header.creator_address {
+            bail!(
+                "block {} at slot {} was created by {} instead of the drawn producer {}",
+                header.id,
+                slot,
+                header.creator_address,
+                producer
+            );
+        }
+        let (previous_id, previous_storage) = &latest_blocks[slot.thread as usize];
+        if header.content.parents.get(slot.thread as usize) != Some(previous_id) {
+            bail!(
+                "block {} at slot {} does not follow the previous archived block {} of its thread, the archive is incomplete",
+                header.id,
+                slot,
+                previous_id
+            );
+        }
+
+        // the block is executed with the block it endorses, its parent in the same thread
+        let (block, operations) = archived_block.into_block()?;
+        let block_id = block.id;
+        let mut storage = root_storage.clone_without_refs();
+        storage.store_operations(operations);
+        storage.store_block(block);
+        let mut exec_storage = storage.clone();
+        exec_storage.extend(previous_storage.clone());
+        replay.execute_next_slot(Some(&(block_id, exec_storage)), thread_count)?;
+        latest_blocks[slot.thread as usize] = (block_id, storage);
+    }
+
+    // the slots after the last archived block are misses, and the last hashes can be recorded after it
+    if let Some(end_slot) = last_slot.or_else(|| recorded_hashes.keys().next_back().copied()) {
+        if end_slot >= replay.next_slot {
+            skip_to_slot(
+                &mut replay,
+                archive,
+                end_slot.get_next_slot(thread_count)?,
+                thread_count,
+            )?;
+        }
+    }
+
+    match replay.last_executed {
+        Some((slot, hash)) => info!(
+            "replay finished at slot {} with the final state hash {}",
+            slot, hash
+        ),
+        None => info!("replay finished, no slot was archived"),
+    }
+    if replay.checked_hashes == 0 {
+        warn!("no recorded final state hash was replayed, the replay is not verified");
+    } else {
+        info!(
+            "{} recorded final state hashes matched",
+            replay.checked_hashes
+        );
+    }
+    Ok(latest_blocks)
+}
+
+/// Execute the slots before `slot` as misses, failing if one of them has a trimmed block
+fn skip_to_slot(
+    replay: &mut Replay,
+    archive: &Archive,
+    slot: Slot,
+    thread_count: u8,
+) -> anyhow::Result<()> {
+    while replay.next_slot < slot {
+        if let Some(header) = archive
+            .get_header(replay.next_slot)
+            .map_err(|err| anyhow!("could not read an archived header: {}", err))?
+        {
+            bail!(
+                "the operations of block {} at slot {} were trimmed from the archive, it cannot be replayed",
+                header.id,
+                replay.next_slot
+            );
+        }
+        replay.execute_next_slot(None, thread_count)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,125 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Guardrails on the resources of the node.
+//!
+//! Every `check_interval` the monitor looks at the space available on the disks of the ledger,
+//! the peers file and the bootstrap server stats, and at the resident memory of the node.
+//! While less than `min_free_disk_space` bytes are available on one of them, or more than `max_memory_usage`
+//! bytes of memory are used, the node is in degraded read-only mode: the bootstrap server refuses sessions,
+//! the pool and the API do not accept new operations and endorsements, and the alerts are shown in the node status.
+//! The node leaves the degraded mode at the first check where the resources are back within the thresholds.
+
+use crate::settings::{ResourceMonitorSettings, SETTINGS};
+use anyhow::{Context, Result};
+use massa_models::{api::ResourceAlert, resource_status::ResourceStatus};
+use std::path::{Path, PathBuf};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+/// Switches the node in and out of degraded read-only mode
+pub struct ResourceMonitor {
+    settings: ResourceMonitorSettings,
+    /// alerts currently raised, shared with the bootstrap server, the pool and the API
+    status: ResourceStatus,
+}
+
+impl ResourceMonitor {
+    /// Create a monitor publishing its alerts in `status`
+    pub fn new(settings: ResourceMonitorSettings, status: ResourceStatus) -> Self {
+        ResourceMonitor { settings, status }
+    }
+
+    /// Spawn the task checking the resources every `check_interval`
+    pub fn start(self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.settings.check_interval.to_duration());
+            loop {
+                interval.tick().await;
+                self.check();
+            }
+        })
+    }
+
+    /// Check the resources and publish the alerts, logging when the degraded mode starts or ends
+    fn check(&self) {
+        let mut alerts = Vec::new();
+        if self.settings.min_free_disk_space > 0 {
+            for path in [
+                &SETTINGS.ledger.disk_ledger_path,
+                &SETTINGS.network.peers_file,
+                &SETTINGS.bootstrap.server_stats_path,
+            ] {
+                match get_available_space(path) {
+                    Ok(available) if available < self.settings.min_free_disk_space => {
+                        alerts.push(ResourceAlert::LowDiskSpace {
+                            path: path.display().to_string(),
+                            available,
+                            min_available: self.settings.min_free_disk_space,
+                        });
+                    }
+                    Ok(_) => {}
+                    Err(err) => debug!(
+                        "resource monitor could not get the available disk space: {}",
+                        err
+                    ),
+                }
+            }
+        }
+        if self.settings.max_memory_usage > 0 {
+            match get_memory_usage() {
+                Ok(used) if used > self.settings.max_memory_usage => {
+                    alerts.push(ResourceAlert::HighMemoryUsage {
+                        used,
+                        max_used: self.settings.max_memory_usage,
+                    });
+                }
+                Ok(_) => {}
+                Err(err) => debug!("resource monitor could not get the memory usage: {}", err),
+            }
+        }
+
+        match (self.status.is_degraded(), alerts.is_empty()) {
+            (false, false) => warn!(
+                "entering degraded read-only mode, bootstraps are not served and no operation nor endorsement is accepted: {}",
+                alerts
+                    .iter()
+                    .map(|alert| alert.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            (true, true) => info!("leaving degraded read-only mode, the resources are back within the thresholds"),
+            _ => {}
+        }
+        self.status.set_alerts(alerts);
+    }
+}
+
+/// Get the space available on the disk of `path`, or of its nearest existing ancestor
+pub fn get_available_space(path: &Path) -> Result<u64> {
+    let existing: PathBuf = path
+        .ancestors()
+        .find(|ancestor| ancestor.exists())
+        .unwrap_or_else(|| Path::new("."))
+        .to_path_buf();
+    fs2::available_space(&existing).with_context(|| {
+        format!(
+            "could not get the available space of {}",
+            existing.display()
+        )
+    })
+}
+
+/// Get the resident memory of the node in bytes, only available on Linux
+fn get_memory_usage() -> Result<u64> {
+    let status = std::fs::read_to_string("/proc/self/status")
+        .context("could not read the status of the process")?;
+    let kilobytes = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))
+        .and_then(|value| value.trim().strip_suffix("kB"))
+        .context("no resident memory in the status of the process")?
+        .trim()
+        .parse::<u64>()
+        .context("invalid resident memory in the status of the process")?;
+    Ok(kilobytes.saturating_mul(1024))
+}
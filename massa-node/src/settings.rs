@@ -4,13 +4,22 @@
 use std::path::PathBuf;
 
 use enum_map::EnumMap;
-use massa_models::config::build_massa_settings;
+use massa_logging::LogFormat;
+use massa_models::{
+    address::Address, amount::Amount, block::BlockId, config::build_massa_settings, slot::Slot,
+};
 use massa_signature::PublicKey;
 use massa_time::MassaTime;
 use serde::Deserialize;
 use std::net::{IpAddr, SocketAddr};
 
-use massa_network_exports::{settings::PeerTypeConnectionConfig, PeerType};
+use massa_network_exports::{
+    settings::{PeerTypeConnectionConfig, PeersFileFormat},
+    PeerFilter, PeerType,
+};
+use massa_pool_exports::OperationSelectionStrategy;
+
+use crate::notifier::{NotifiedEvent, WebhookFormat};
 
 lazy_static::lazy_static! {
     pub static ref SETTINGS: Settings = build_massa_settings("massa-node", "MASSA_NODE");
@@ -19,6 +28,11 @@ lazy_static::lazy_static! {
 #[derive(Debug, Deserialize, Clone)]
 pub struct LoggingSettings {
     pub level: usize,
+    pub format: LogFormat,
+    pub filters: Vec<String>,
+    pub otlp_endpoint: Option<String>,
+    pub otlp_service_name: String,
+    pub recent_lines: usize,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -32,15 +46,18 @@ pub struct ExecutionSettings {
     pub wasm_gas_costs_file: PathBuf,
 }
 
+#[derive(Clone, Debug, Deserialize)]
+pub struct GenesisSettings {
+    pub genesis_file_path: PathBuf,
+}
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct SelectionSettings {
     pub max_draw_cache: usize,
-    pub initial_rolls_path: PathBuf,
 }
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct LedgerSettings {
-    pub initial_ledger_path: PathBuf,
     pub disk_ledger_path: PathBuf,
     pub final_history_length: usize,
 }
@@ -49,16 +66,29 @@ pub struct LedgerSettings {
 pub struct NetworkSettings {
     pub bind: SocketAddr,
     pub routable_ip: Option<IpAddr>,
+    pub observer: bool,
     pub protocol_port: u16,
+    pub other_routable_addresses: Vec<SocketAddr>,
     pub connect_timeout: MassaTime,
+    pub min_connect_timeout: MassaTime,
+    pub connect_timeout_latency_factor: f64,
     pub wakeup_interval: MassaTime,
     pub initial_peers_file: PathBuf,
     pub peers_file: PathBuf,
+    pub peers_file_format: PeersFileFormat,
+    pub peers_file_password: Option<String>,
+    pub ban_audit_file: Option<PathBuf>,
     pub keypair_file: PathBuf,
     pub peer_types_config: EnumMap<PeerType, PeerTypeConnectionConfig>,
     pub max_in_connections_per_ip: usize,
+    pub max_out_attempts_per_minute: u32,
+    pub max_out_attempts_burst: u32,
     pub max_idle_peers: usize,
     pub max_banned_peers: usize,
+    pub max_peers_per_network_group: usize,
+    pub max_peer_history_length: usize,
+    pub candidate_peer_filter: PeerFilter,
+    pub advertised_peer_filter: PeerFilter,
     pub peers_file_dump_interval: MassaTime,
     pub message_timeout: MassaTime,
     pub ask_peer_list_interval: MassaTime,
@@ -70,6 +100,9 @@ pub struct NetworkSettings {
     pub max_operations_per_message: u32,
     pub max_bytes_read: f64,
     pub max_bytes_write: f64,
+    pub max_clock_offset: MassaTime,
+    pub message_checksum: bool,
+    pub write_coalescing_delay: MassaTime,
 }
 
 /// Bootstrap configuration.
@@ -78,6 +111,12 @@ pub struct BootstrapSettings {
     pub bootstrap_list: Vec<(SocketAddr, PublicKey)>,
     pub bootstrap_whitelist_path: PathBuf,
     pub bootstrap_blacklist_path: PathBuf,
+    pub bootstrap_token: Option<String>,
+    pub bootstrap_tokens: Vec<String>,
+    pub snapshot_import_path: Option<PathBuf>,
+    pub shutdown_snapshot_path: Option<PathBuf>,
+    pub shutdown_snapshot_max_age: MassaTime,
+    pub server_stats_path: PathBuf,
     pub bind: Option<SocketAddr>,
     pub connect_timeout: MassaTime,
     pub read_timeout: MassaTime,
@@ -88,10 +127,16 @@ pub struct BootstrapSettings {
     pub max_ping: MassaTime,
     pub max_clock_delta: MassaTime,
     pub cache_duration: MassaTime,
+    pub part_cache_max_age: MassaTime,
+    pub part_cache_max_entries: usize,
     pub max_simultaneous_bootstraps: u32,
     pub per_ip_min_interval: MassaTime,
     pub ip_list_max_size: usize,
     pub max_bytes_read_write: f64,
+    pub max_bytes_read_write_total: f64,
+    pub trustless_sync: bool,
+    pub trustless_sync_max_lag_periods: u64,
+    pub trustless_sync_request_timeout: MassaTime,
 }
 
 /// Factory settings
@@ -101,15 +146,129 @@ pub struct FactorySettings {
     pub initial_delay: MassaTime,
     /// Staking wallet file
     pub staking_wallet_path: PathBuf,
+    /// Maximal number of periods between the latest blockclique block and the current slot to start producing blocks
+    pub catch_up_max_lag_periods: u64,
+    /// Maximal duration of the catch-up with the network after startup
+    pub catch_up_timeout: MassaTime,
+    /// Minimal number of connected nodes to produce blocks, 0 to disable
+    pub min_production_peers: usize,
+    /// Only produce blocks while the node is synced with the network
+    pub produce_only_when_synced: bool,
+}
+
+/// Shutdown settings
+#[derive(Debug, Deserialize, Clone)]
+pub struct ShutdownSettings {
+    /// Maximum duration of the shutdown of the workers, the process is ended once it is elapsed
+    pub timeout: MassaTime,
+}
+
+/// Metrics settings
+#[derive(Debug, Deserialize, Clone)]
+pub struct MetricsSettings {
+    /// Whether the metrics are served to Prometheus
+    pub enabled: bool,
+    /// Bind of the metrics HTTP endpoint
+    pub bind: SocketAddr,
+}
+
+/// Notifier settings
+#[derive(Debug, Deserialize, Clone)]
+pub struct NotifierSettings {
+    /// URL of the webhook the events are posted to, nothing is notified if not set
+    pub webhook_url: Option<String>,
+    /// Payload format of the webhook
+    pub webhook_format: WebhookFormat,
+    /// Events that are notified
+    pub events: Vec<NotifiedEvent>,
+    /// Interval between two checks of the state of the node
+    pub check_interval: MassaTime,
+    /// A low peer count is notified when fewer nodes than this are connected
+    pub min_peer_count: usize,
+    /// A low disk space is notified when fewer bytes than this are available on the disk of the node data
+    pub min_free_disk_space: u64,
+    /// Number of attempts to post a notification before dropping it
+    pub max_attempts: u32,
+    /// Delay before retrying a failed post, doubled after each attempt
+    pub retry_delay: MassaTime,
+    /// Maximum number of notifications posted per `rate_limit_period`, the next ones are dropped
+    pub rate_limit_count: usize,
+    /// Period of the rate limit
+    pub rate_limit_period: MassaTime,
+}
+
+/// Resource monitor settings
+#[derive(Debug, Deserialize, Clone)]
+pub struct ResourceMonitorSettings {
+    /// Interval between two checks of the resources
+    pub check_interval: MassaTime,
+    /// The node is degraded when fewer bytes than this are available on the disk of the node data, 0 to disable
+    pub min_free_disk_space: u64,
+    /// The node is degraded when it uses more bytes of memory than this, 0 to disable
+    pub max_memory_usage: u64,
+}
+
+/// Archive settings
+#[derive(Debug, Deserialize, Clone)]
+pub struct ArchiveSettings {
+    /// Whether the node is archival: it keeps the final blocks and snapshots of the final state, and serves the blocks to its peers
+    pub enabled: bool,
+    /// Directory of the archive
+    pub path: PathBuf,
+    /// Interval between two checks for a new snapshot or for pruning the snapshots
+    pub check_interval: MassaTime,
+    /// A snapshot of the final state is taken during every cycle multiple of this
+    pub snapshot_interval_cycles: u64,
+    /// Number of cycles of blocks and snapshots kept, 0 to keep the full history
+    pub retention_cycles: u64,
+    /// Number of cycles of blocks kept with their operations, only the headers of the older blocks are kept. 0 to keep all the operations
+    pub body_retention_cycles: u64,
+    /// Interval between two pruning and compaction passes
+    pub maintenance_interval: MassaTime,
+    /// Maximum number of blocks pruned or trimmed in a single write
+    pub maintenance_batch_size: usize,
+    /// Pause between two writes of the pruning, to limit the disk load
+    pub maintenance_batch_pause: MassaTime,
+    /// Maximum number of blocks served or asked in a single block range
+    pub max_blocks_per_range: usize,
+    /// Maximum number of blocks served to a node during `serving_quota_period`
+    pub max_served_blocks_per_node: usize,
+    /// Period of the serving quota
+    pub serving_quota_period: MassaTime,
+    /// Interval between two requests for the blocks older than the archived ones, only when the full history is kept
+    pub backfill_interval: MassaTime,
+}
+
+/// Address index settings
+#[derive(Debug, Deserialize, Clone)]
+pub struct AddressIndexSettings {
+    /// Whether the final operations are indexed by involved address, for the `get_final_operations_by_address` API
+    pub enabled: bool,
+    /// Directory of the index database
+    pub path: PathBuf,
+}
+
+/// Cryptography settings
+#[derive(Debug, Deserialize, Clone)]
+pub struct CryptoSettings {
+    /// Number of threads of the worker pool hashing and verifying signatures in parallel, 0 for one per CPU core
+    pub worker_pool_size: usize,
 }
 
 /// Pool configuration, read from a file configuration
 #[derive(Debug, Deserialize, Clone)]
 pub struct PoolSettings {
     pub max_pool_size_per_thread: usize,
+    pub max_operations_per_sender: usize,
+    pub minimal_fee: Amount,
+    pub replace_by_fee_min_increment: Amount,
     pub max_operation_future_validity_start_periods: u64,
+    pub max_operation_status_count: usize,
     pub max_endorsement_count: u64,
     pub max_item_return_count: usize,
+    pub operation_selection_strategy: OperationSelectionStrategy,
+    pub local_priority_addresses: Vec<Address>,
+    pub broadcast_events_capacity: usize,
 }
 
 /// API and server configuration, read from a file configuration.
@@ -119,6 +278,9 @@ pub struct APISettings {
     pub bind_private: SocketAddr,
     pub bind_public: SocketAddr,
     pub bind_api: SocketAddr,
+    pub bind_grpc: Option<SocketAddr>,
+    pub private_api_token: Option<String>,
+    pub max_sync_lag_periods: u64,
     pub max_arguments: u64,
     pub openrpc_spec_path: PathBuf,
     pub max_request_body_size: u32,
@@ -127,6 +289,8 @@ pub struct APISettings {
     pub max_subscriptions_per_connection: u32,
     pub max_log_length: u32,
     pub allow_hosts: Vec<String>,
+    pub cors_allowed_origins: Vec<String>,
+    pub cors_max_age: MassaTime,
     pub batch_requests_supported: bool,
     pub ping_interval: MassaTime,
     pub enable_http: bool,
@@ -136,6 +300,7 @@ pub struct APISettings {
 #[derive(Debug, Deserialize, Clone)]
 pub struct Settings {
     pub logging: LoggingSettings,
+    pub genesis: GenesisSettings,
     pub protocol: ProtocolSettings,
     pub network: NetworkSettings,
     pub consensus: ConsensusSettings,
@@ -146,6 +311,13 @@ pub struct Settings {
     pub ledger: LedgerSettings,
     pub selector: SelectionSettings,
     pub factory: FactorySettings,
+    pub shutdown: ShutdownSettings,
+    pub metrics: MetricsSettings,
+    pub notifier: NotifierSettings,
+    pub resource_monitor: ResourceMonitorSettings,
+    pub archive: ArchiveSettings,
+    pub address_index: AddressIndexSettings,
+    pub crypto: CryptoSettings,
 }
 
 /// Consensus configuration
@@ -162,6 +334,12 @@ pub struct ConsensusSettings {
     pub max_dependency_blocks: usize,
     /// stats time span
     pub stats_timespan: MassaTime,
+    /// number of cycles for which block production stats are kept
+    pub stats_cycle_count: usize,
+    /// checkpoints: the block expected at each listed slot
+    pub checkpoints: Vec<(Slot, BlockId)>,
+    /// max number of periods of the blockclique a switch to another clique can roll back without the administrator accepting it (0 to disable)
+    pub max_reorg_depth_periods: u64,
     /// max event send wait
     pub max_send_wait: MassaTime,
     /// force keep at least this number of final periods in RAM for each thread
@@ -176,6 +354,8 @@ pub struct ConsensusSettings {
     pub broadcast_blocks_capacity: usize,
     /// filled blocks sender(channel) capacity
     pub broadcast_filled_blocks_capacity: usize,
+    /// final blocks sender(channel) capacity
+    pub broadcast_final_blocks_capacity: usize,
 }
 
 /// Protocol Configuration, read from toml user configuration file
@@ -221,6 +401,42 @@ pub struct ProtocolSettings {
     pub max_endorsements_propagation_time: MassaTime,
     /// operations sender sender(channel) capacity
     pub broadcast_operations_capacity: usize,
+    /// interval between two checks of the network partition watchdog
+    pub partition_check_interval: MassaTime,
+    /// number of slots without new final block, while connected to `partition_min_connected_nodes` nodes,
+    /// after which a partition alert is raised, 0 to disable
+    pub partition_max_slots_without_final_block: u64,
+    /// minimal number of connected nodes for a stalled finality to be a sign of partition
+    pub partition_min_connected_nodes: usize,
+    /// number of outgoing connections below which a partition alert is raised
+    /// after `partition_low_out_connections_timeout`, 0 to disable
+    pub partition_min_out_connections: usize,
+    /// time during which the outgoing connections can be below `partition_min_out_connections`
+    pub partition_low_out_connections_timeout: MassaTime,
+    /// interval at which the feedback on the blocks of the nodes is fed into the peer reputations, 0 to disable
+    pub peer_feedback_interval: MassaTime,
+    /// delay after the first sighting of a header after which the nodes re-announcing it are late
+    pub late_block_announcement_delay: MassaTime,
+    /// duration of the stem phase of the operations submitted to this node, during which they are
+    /// only relayed by a random node we dialed, 0 to disable
+    pub operation_stem_phase_duration: MassaTime,
+    /// number of periods before the current one beyond which the received headers, endorsements
+    /// and operations (by expire period) are dropped, 0 to disable
+    pub relevance_past_periods: u64,
+    /// number of periods after the current one beyond which the received headers, endorsements
+    /// and operations (by expire period) are dropped, 0 to disable
+    pub relevance_future_periods: u64,
+    /// number of dropped items after which the sending node gets a reputation penalty
+    pub max_irrelevant_items_per_node: usize,
+    /// max number of blocks waiting for their slot or their parents that a node made us buffer,
+    /// beyond which the oldest ones are dropped and the node gets a reputation penalty, 0 to disable
+    pub max_pending_blocks_per_node: usize,
+    /// max number of verified headers kept to skip the verification of the ones received again
+    pub max_known_headers_size: usize,
+    /// interval at which the progress of the synchronization is updated, 0 to disable
+    pub sync_progress_interval: MassaTime,
+    /// max number of periods the final blocks lag behind the other nodes while the node is synced
+    pub max_synced_final_lag_periods: u64,
 }
 
 #[cfg(test)]
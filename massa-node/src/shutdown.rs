@@ -0,0 +1,95 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Coordinated shutdown of the node.
+//!
+//! The workers are stopped one after the other (see `stop` in `main.rs`) so that each one
+//! can flush what it received from the previous ones. A watchdog ends the process
+//! if the whole sequence takes longer than the configured timeout.
+
+use crossbeam_channel::RecvTimeoutError;
+use massa_time::MassaTime;
+use parking_lot::Mutex;
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::{error, info};
+
+/// Wait for a signal asking the node to stop: SIGINT (ctrl-c) or, on unix, SIGTERM.
+/// Returns the name of the received signal.
+pub async fn wait_for_stop_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        let mut terminate = signal(SignalKind::terminate()).expect("could not listen to SIGTERM");
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => "SIGINT",
+            _ = terminate.recv() => "SIGTERM",
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("could not listen to ctrl-c");
+        "ctrl-c"
+    }
+}
+
+/// Tracks the steps of the shutdown and ends the process if they take too long
+pub struct ShutdownWatchdog {
+    /// name of the step in progress
+    current_step: Arc<Mutex<&'static str>>,
+    /// start of the step in progress
+    step_start: Instant,
+    /// signals the watchdog thread that the shutdown is over
+    done_tx: crossbeam_channel::Sender<()>,
+}
+
+impl ShutdownWatchdog {
+    /// Start the watchdog thread, ending the process if `finish` is not called within `timeout`
+    pub fn start(timeout: MassaTime) -> Self {
+        let current_step = Arc::new(Mutex::new("starting shutdown"));
+        let (done_tx, done_rx) = crossbeam_channel::bounded(1);
+        let watched_step = current_step.clone();
+        std::thread::Builder::new()
+            .name("shutdown-watchdog".into())
+            .spawn(move || {
+                if let Err(RecvTimeoutError::Timeout) = done_rx.recv_timeout(timeout.to_duration())
+                {
+                    error!(
+                        "shutdown did not complete within {} ms, stuck at step: {}. Ending the process",
+                        timeout.to_millis(),
+                        *watched_step.lock()
+                    );
+                    std::process::exit(1);
+                }
+            })
+            .expect("failed to spawn thread : shutdown-watchdog");
+        ShutdownWatchdog {
+            current_step,
+            step_start: Instant::now(),
+            done_tx,
+        }
+    }
+
+    /// Note the start of a new step, logging the duration of the previous one
+    pub fn step(&mut self, name: &'static str) {
+        let previous_step = std::mem::replace(&mut *self.current_step.lock(), name);
+        info!(
+            "shutdown: {} done in {} ms, {}",
+            previous_step,
+            self.step_start.elapsed().as_millis(),
+            name
+        );
+        self.step_start = Instant::now();
+    }
+
+    /// Note the end of the shutdown, stopping the watchdog
+    pub fn finish(self) {
+        info!(
+            "shutdown: {} done in {} ms, all workers stopped",
+            *self.current_step.lock(),
+            self.step_start.elapsed().as_millis()
+        );
+        let _ = self.done_tx.send(());
+    }
+}
@@ -0,0 +1,847 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Trustless sync, run instead of the bootstrap when `bootstrap.trustless_sync` is set: nothing is taken
+//! from a bootstrap server, the history is downloaded from the archival nodes among the peers and checked locally.
+//!
+//! 1. headers: the headers of the final blocks are downloaded from the genesis on, and stored in the archive once
+//!    they extend the validated header chain (see `HeaderChain`), which must go through the consensus checkpoints,
+//! 2. bodies: the operations of the stored headers are downloaded next, and must match the operation merkle root
+//!    of their header,
+//! 3. state: the final state is rebuilt from the genesis by replaying the archived blocks (see `replay`),
+//!    which also checks that every block was created by its drawn producer.
+//!
+//! The headers and the bodies are kept in the archive, so that an interrupted sync resumes where it stopped.
+//! The consensus then starts from the latest replayed block of each thread, the blocks since being synced as usual.
+
+use crate::genesis::GenesisConfig;
+use crate::replay::replay_blocks;
+use anyhow::{anyhow, bail};
+use massa_bootstrap::GlobalBootstrapState;
+use massa_consensus_exports::{
+    bootstrapable_graph::BootstrapableGraph, export_active_block::ExportActiveBlock,
+};
+use massa_consensus_worker::create_genesis_block;
+use massa_final_state::FinalState;
+use massa_models::{
+    block::{ArchivedBlock, BlockId, WrappedHeader},
+    config::constants::{GENESIS_KEY, MAX_BLOCK_RANGE_LENGTH, T0},
+    node::NodeId,
+    prehash::PreHashMap,
+    slot::Slot,
+    timeslots::get_latest_block_slot_at_timestamp,
+    upgrade_schedule::UpgradeSchedule,
+    version::Version,
+    wrapped::Wrapped,
+};
+use massa_network_exports::{
+    BanReason, Establisher, NetworkCommandSender, NetworkConfig, NetworkEvent, NetworkEventReceiver,
+};
+use massa_network_worker::start_network_controller;
+use massa_pos_exports::SelectorController;
+use massa_signature::KeyPair;
+use massa_storage::archive::Archive;
+use massa_time::{MassaTime, SystemClock};
+use parking_lot::RwLock;
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::ops::Bound::Excluded;
+use std::sync::Arc;
+use tokio::time::sleep;
+use tracing::{debug, info, warn};
+
+/// Number of the latest validated blocks of each thread that the next headers can have as parents
+const RECENT_BLOCKS_PER_THREAD: usize = 1024;
+
+/// Chain of the validated headers of the final blocks, from the genesis blocks.
+///
+/// A header extends the chain if:
+/// * it is signed by its creator, and only uses the protocol features active at its slot,
+/// * it has one parent per thread, its parent in its thread being the latest validated block of that thread,
+///   and its parents in the other threads being validated blocks of those threads older than it,
+/// * its endorsements are signed, at its slot, with distinct indexes, and endorse its parent in its thread,
+/// * it is the checkpoint of its slot, if any, and it does not skip a checkpoint of its thread.
+///
+/// The producer draws depend on the final state, they are checked when the blocks are replayed.
+pub struct HeaderChain {
+    thread_count: u8,
+    /// consensus checkpoints the chain must go through
+    checkpoints: BTreeMap<Slot, BlockId>,
+    /// activation slots of the protocol features
+    upgrade_schedule: UpgradeSchedule,
+    /// latest validated block of each thread, with its slot
+    tips: Vec<(BlockId, Slot)>,
+    /// validated blocks that can still be parents of the next headers, with their slot
+    recent_blocks: PreHashMap<BlockId, Slot>,
+    /// ids of the recent blocks of each thread, oldest first
+    recent_blocks_by_thread: Vec<VecDeque<BlockId>>,
+    /// slot of the latest validated header
+    last_slot: Slot,
+}
+
+impl HeaderChain {
+    /// Create a chain holding the genesis blocks `genesis_ids`, one per thread
+    pub fn new(
+        genesis_ids: Vec<BlockId>,
+        checkpoints: BTreeMap<Slot, BlockId>,
+        upgrade_schedule: UpgradeSchedule,
+    ) -> Self {
+        let thread_count = genesis_ids.len() as u8;
+        let mut chain = HeaderChain {
+            thread_count,
+            checkpoints,
+            upgrade_schedule,
+            tips: Vec::with_capacity(thread_count as usize),
+            recent_blocks: Default::default(),
+            recent_blocks_by_thread: vec![VecDeque::new(); thread_count as usize],
+            last_slot: Slot::new(0, 0),
+        };
+        for (thread, id) in genesis_ids.into_iter().enumerate() {
+            let slot = Slot::new(0, thread as u8);
+            chain.tips.push((id, slot));
+            chain.push_recent_block(id, slot);
+            chain.last_slot = slot;
+        }
+        chain
+    }
+
+    /// Slot of the latest validated header
+    pub fn last_slot(&self) -> Slot {
+        self.last_slot
+    }
+
+    /// Latest slot up to which the final blocks of every thread are validated
+    pub fn last_complete_slot(&self) -> Slot {
+        self.tips
+            .iter()
+            .map(|(_, slot)| *slot)
+            .min()
+            .unwrap_or_else(|| Slot::new(0, 0))
+    }
+
+    /// Period of a recent validated block
+    pub fn period_of(&self, id: &BlockId) -> Option<u64> {
+        self.recent_blocks.get(id).map(|slot| slot.period)
+    }
+
+    /// Returns true if the parent of `header` in its thread was not validated, which happens
+    /// when a header of that thread was skipped
+    pub fn follows_unknown_block(&self, header: &WrappedHeader) -> bool {
+        header
+            .content
+            .parents
+            .get(header.content.slot.thread as usize)
+            .map_or(false, |parent| !self.recent_blocks.contains_key(parent))
+    }
+
+    /// Extend the chain with `header`
+    ///
+    /// # Returns
+    /// false if the header was already validated, an error if it does not extend the chain
+    pub fn extend(&mut self, header: &WrappedHeader) -> anyhow::Result<bool> {
+        let id = header.id;
+        let slot = header.content.slot;
+        if slot.thread >= self.thread_count {
+            bail!("header {} is in the invalid thread {}", id, slot.thread);
+        }
+        let (tip_id, tip_slot) = self.tips[slot.thread as usize];
+        if slot <= tip_slot {
+            if self.recent_blocks.get(&id) == Some(&slot) {
+                return Ok(false);
+            }
+            bail!(
+                "header {} at slot {} conflicts with the chain, whose latest block of thread {} is at slot {}",
+                id,
+                slot,
+                slot.thread,
+                tip_slot
+            );
+        }
+
+        header.verify_signature()?;
+        self.upgrade_schedule.check_header(&header.content)?;
+
+        // parents
+        let parents = &header.content.parents;
+        if parents.len() != self.thread_count as usize {
+            bail!(
+                "header {} at slot {} has {} parents instead of one per thread",
+                id,
+                slot,
+                parents.len()
+            );
+        }
+        if parents[slot.thread as usize] != tip_id {
+            bail!(
+                "header {} at slot {} does not follow the latest block {} of thread {}",
+                id,
+                slot,
+                tip_id,
+                slot.thread
+            );
+        }
+        for (thread, parent) in parents.iter().enumerate() {
+            match self.recent_blocks.get(parent) {
+                Some(parent_slot) if parent_slot.thread as usize == thread && *parent_slot < slot => {}
+                _ => bail!(
+                    "parent {} of header {} at slot {} is not a known block of thread {} older than it",
+                    parent,
+                    id,
+                    slot,
+                    thread
+                ),
+            }
+        }
+
+        // endorsements
+        Wrapped::verify_signatures_batch(&header.content.endorsements)?;
+        let mut indexes = HashSet::with_capacity(header.content.endorsements.len());
+        for endorsement in header.content.endorsements.iter() {
+            if endorsement.content.slot != slot
+                || endorsement.content.endorsed_block != tip_id
+                || !indexes.insert(endorsement.content.index)
+            {
+                bail!(
+                    "endorsement {} of header {} at slot {} is invalid",
+                    endorsement.id,
+                    id,
+                    slot
+                );
+            }
+        }
+
+        // checkpoints
+        if let Some(checkpoint_id) = self.checkpoints.get(&slot) {
+            if *checkpoint_id != id {
+                bail!(
+                    "header {} conflicts with the checkpoint {} at slot {}",
+                    id,
+                    checkpoint_id,
+                    slot
+                );
+            }
+        }
+        if let Some((checkpoint_slot, checkpoint_id)) = self
+            .checkpoints
+            .range((Excluded(tip_slot), Excluded(slot)))
+            .find(|(checkpoint_slot, _)| checkpoint_slot.thread == slot.thread)
+        {
+            bail!(
+                "header {} at slot {} skips the checkpoint {} at slot {}",
+                id,
+                slot,
+                checkpoint_id,
+                checkpoint_slot
+            );
+        }
+
+        self.tips[slot.thread as usize] = (id, slot);
+        self.push_recent_block(id, slot);
+        self.last_slot = std::cmp::max(self.last_slot, slot);
+        Ok(true)
+    }
+
+    /// Keep `id` as a possible parent, forgetting the oldest block of its thread if there are too many
+    fn push_recent_block(&mut self, id: BlockId, slot: Slot) {
+        let thread_blocks = &mut self.recent_blocks_by_thread[slot.thread as usize];
+        thread_blocks.push_back(id);
+        if thread_blocks.len() > RECENT_BLOCKS_PER_THREAD {
+            if let Some(oldest) = thread_blocks.pop_front() {
+                self.recent_blocks.remove(&oldest);
+            }
+        }
+        self.recent_blocks.insert(id, slot);
+    }
+}
+
+/// Check the body of `block` against the validated headers `expected`, by slot
+///
+/// # Returns
+/// false if there is no validated header at the slot of the block, an error if the block does not match it
+pub fn check_block_body(
+    block: &ArchivedBlock,
+    expected: &BTreeMap<Slot, BlockId>,
+) -> anyhow::Result<bool> {
+    let slot = block.header.content.slot;
+    match expected.get(&slot) {
+        Some(id) if *id == block.header.id => {}
+        Some(id) => bail!(
+            "block {} at slot {} is not the validated block {}",
+            block.header.id,
+            slot,
+            id
+        ),
+        None => return Ok(false),
+    }
+    if !block.check_operations() {
+        bail!(
+            "the operations of block {} at slot {} do not match its header",
+            block.header.id,
+            slot
+        );
+    }
+    Wrapped::verify_signatures_batch(&block.operations)?;
+    Ok(true)
+}
+
+/// Range asked to a connected node
+#[derive(Clone, Copy)]
+enum RangeRequest {
+    /// headers of the final blocks of the slots from `start` (included) to `end` (excluded)
+    Headers { start: Slot, end: Slot },
+    /// final blocks of the slots from `start` (included) to `end` (excluded)
+    Blocks { start: Slot, end: Slot },
+}
+
+/// Answer of a connected node to a `RangeRequest`
+enum RangeResponse {
+    Headers(Vec<WrappedHeader>),
+    Blocks(Vec<ArchivedBlock>),
+}
+
+/// Network session of the sync, in which one range is asked at a time
+struct SyncSession<'a> {
+    genesis: &'a GenesisConfig,
+    archive: &'a Archive,
+    command_sender: NetworkCommandSender,
+    event_receiver: NetworkEventReceiver,
+    /// time given to a node to answer, and between two rounds over the connected nodes
+    request_timeout: MassaTime,
+    /// maximal number of periods between the latest final header and the current slot to end the sync
+    max_lag_periods: u64,
+    /// connected nodes
+    nodes: HashSet<NodeId>,
+    /// nodes that did not help with the current range, skipped until every node was tried
+    skipped_nodes: HashSet<NodeId>,
+}
+
+impl<'a> SyncSession<'a> {
+    /// Current slot of the network
+    fn current_slot(&self) -> anyhow::Result<Slot> {
+        Ok(get_latest_block_slot_at_timestamp(
+            self.genesis.thread_count,
+            T0,
+            self.genesis.genesis_timestamp,
+            MassaTime::now()?,
+        )?
+        .unwrap_or_else(|| Slot::new(0, 0)))
+    }
+
+    /// Keep track of the connected nodes, and answer the ranges asked by them with nothing
+    async fn on_event(&mut self, event: NetworkEvent) -> anyhow::Result<()> {
+        match event {
+            NetworkEvent::NewConnection { node, .. } => {
+                self.nodes.insert(node);
+            }
+            NetworkEvent::ConnectionClosed(node) => {
+                self.nodes.remove(&node);
+                self.skipped_nodes.remove(&node);
+            }
+            NetworkEvent::AskedForBlockRange { node, .. } => {
+                self.command_sender
+                    .send_block_range(node, Vec::new())
+                    .await?;
+            }
+            NetworkEvent::AskedForHeaderRange { node, .. } => {
+                self.command_sender
+                    .send_header_range(node, Vec::new())
+                    .await?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Ban a node that sent an invalid range
+    async fn ban(&mut self, node: NodeId, err: anyhow::Error) -> anyhow::Result<()> {
+        warn!("node {} sent an invalid range: {}", node, err);
+        self.nodes.remove(&node);
+        self.skipped_nodes.remove(&node);
+        self.command_sender
+            .node_ban_by_ids(vec![node], BanReason::Misbehavior)
+            .await?;
+        Ok(())
+    }
+
+    /// Ask `request` to the connected nodes until one of them answers in time
+    async fn ask(&mut self, request: RangeRequest) -> anyhow::Result<(NodeId, RangeResponse)> {
+        loop {
+            let node = match self
+                .nodes
+                .iter()
+                .find(|node| !self.skipped_nodes.contains(*node))
+            {
+                Some(node) => *node,
+                None => {
+                    // every connected node was tried: wait for new ones, then try them all again
+                    let retry = sleep(self.request_timeout.to_duration());
+                    tokio::pin!(retry);
+                    loop {
+                        tokio::select! {
+                            _ = &mut retry => break,
+                            event = self.event_receiver.wait_event() => self.on_event(event?).await?,
+                        }
+                    }
+                    self.skipped_nodes.clear();
+                    continue;
+                }
+            };
+            match request {
+                RangeRequest::Headers { start, end } => {
+                    self.command_sender
+                        .ask_for_header_range(node, start, end)
+                        .await?
+                }
+                RangeRequest::Blocks { start, end } => {
+                    self.command_sender
+                        .ask_for_block_range(node, start, end)
+                        .await?
+                }
+            }
+            let timeout = sleep(self.request_timeout.to_duration());
+            tokio::pin!(timeout);
+            loop {
+                tokio::select! {
+                    _ = &mut timeout => {
+                        debug!("node {} did not answer the range in time", node);
+                        self.skipped_nodes.insert(node);
+                        break;
+                    }
+                    event = self.event_receiver.wait_event() => match (event?, request) {
+                        (
+                            NetworkEvent::ReceivedHeaderRange { node: from, headers },
+                            RangeRequest::Headers { .. },
+                        ) if from == node => {
+                            return Ok((node, RangeResponse::Headers(headers)));
+                        }
+                        (
+                            NetworkEvent::ReceivedBlockRange { node: from, blocks },
+                            RangeRequest::Blocks { .. },
+                        ) if from == node => {
+                            return Ok((node, RangeResponse::Blocks(blocks)));
+                        }
+                        (NetworkEvent::ConnectionClosed(closed), _) if closed == node => {
+                            self.on_event(NetworkEvent::ConnectionClosed(closed)).await?;
+                            break;
+                        }
+                        (event, _) => self.on_event(event).await?,
+                    }
+                }
+            }
+        }
+    }
+
+    /// Download and validate the headers following the chain, until the latest one is recent enough
+    async fn sync_headers(&mut self, chain: &mut HeaderChain) -> anyhow::Result<()> {
+        // the blocks of the threads become final at different times: when a header follows a block that the node
+        // serving it did not have as final yet, the headers missed are asked again from the last complete slot
+        let mut rewind = false;
+        loop {
+            let current_slot = self.current_slot()?;
+            if current_slot.period.saturating_sub(chain.last_slot().period) <= self.max_lag_periods
+            {
+                return Ok(());
+            }
+            let start = if rewind {
+                chain.last_complete_slot()
+            } else {
+                chain.last_slot()
+            };
+            let request = RangeRequest::Headers {
+                start: start.get_next_slot(self.genesis.thread_count)?,
+                end: current_slot.get_next_slot(self.genesis.thread_count)?,
+            };
+            rewind = false;
+            let (node, headers) = match self.ask(request).await? {
+                (node, RangeResponse::Headers(headers)) => (node, headers),
+                (_, RangeResponse::Blocks(_)) => continue,
+            };
+            let mut extended = false;
+            for header in headers.iter() {
+                match chain.extend(header) {
+                    Ok(true) => {
+                        self.archive.store_header(header)?;
+                        extended = true;
+                    }
+                    Ok(false) => {}
+                    Err(err) if chain.follows_unknown_block(header) => {
+                        debug!("header range of node {} has a gap: {}", node, err);
+                        rewind = true;
+                        break;
+                    }
+                    Err(err) => {
+                        self.ban(node, err).await?;
+                        break;
+                    }
+                }
+            }
+            if extended {
+                info!(
+                    "trustless sync: headers validated up to slot {}",
+                    chain.last_slot()
+                );
+                self.skipped_nodes.clear();
+            } else {
+                self.skipped_nodes.insert(node);
+            }
+        }
+    }
+
+    /// Download the bodies of the archived headers stored without their operations
+    async fn sync_bodies(&mut self) -> anyhow::Result<()> {
+        loop {
+            let headers = self.archive.get_headers_without_operations(
+                Slot::new(0, 0),
+                Slot::max(),
+                MAX_BLOCK_RANGE_LENGTH as usize,
+            )?;
+            let (first_slot, last_slot) = match (headers.first(), headers.last()) {
+                (Some(first), Some(last)) => (first.content.slot, last.content.slot),
+                _ => return Ok(()),
+            };
+            let expected: BTreeMap<Slot, BlockId> = headers
+                .iter()
+                .map(|header| (header.content.slot, header.id))
+                .collect();
+            let request = RangeRequest::Blocks {
+                start: first_slot,
+                end: last_slot.get_next_slot(self.genesis.thread_count)?,
+            };
+            let (node, blocks) = match self.ask(request).await? {
+                (node, RangeResponse::Blocks(blocks)) => (node, blocks),
+                (_, RangeResponse::Headers(_)) => continue,
+            };
+            let mut stored = 0usize;
+            for block in blocks.iter() {
+                match check_block_body(block, &expected) {
+                    Ok(true) => {
+                        self.archive.store_block(block)?;
+                        stored += 1;
+                    }
+                    Ok(false) => {}
+                    Err(err) => {
+                        self.ban(node, err).await?;
+                        break;
+                    }
+                }
+            }
+            if stored > 0 {
+                info!(
+                    "trustless sync: {} block bodies downloaded from slot {}",
+                    stored, first_slot
+                );
+                self.skipped_nodes.clear();
+            } else {
+                self.skipped_nodes.insert(node);
+            }
+        }
+    }
+}
+
+/// Validate the headers already in the archive, stored by an interrupted sync or by the node
+fn resume_header_chain(
+    chain: &mut HeaderChain,
+    archive: &Archive,
+    thread_count: u8,
+) -> anyhow::Result<()> {
+    let mut start = Slot::new(0, 0);
+    loop {
+        let headers = archive.get_headers(start, Slot::max(), MAX_BLOCK_RANGE_LENGTH as usize)?;
+        let last_slot = match headers.last() {
+            Some(last) => last.content.slot,
+            None => return Ok(()),
+        };
+        for header in headers.iter() {
+            chain.extend(header).map_err(|err| {
+                anyhow!(
+                    "the archived headers are not a valid chain, remove the archive to sync again: {}",
+                    err
+                )
+            })?;
+        }
+        start = last_slot.get_next_slot(thread_count)?;
+    }
+}
+
+/// Sync the node from the archival peers and rebuild `final_state`, in a network session of its own
+///
+/// # Returns
+/// The state to start the node from, holding the latest replayed block of every thread
+#[allow(clippy::too_many_arguments)]
+pub async fn trustless_sync(
+    genesis: &GenesisConfig,
+    checkpoints: BTreeMap<Slot, BlockId>,
+    network_config: &NetworkConfig,
+    node_keypair: KeyPair,
+    version: Version,
+    archive: Archive,
+    final_state: Arc<RwLock<FinalState>>,
+    selector: Box<dyn SelectorController>,
+    max_lag_periods: u64,
+    request_timeout: MassaTime,
+) -> anyhow::Result<GlobalBootstrapState> {
+    let thread_count = genesis.thread_count;
+    let genesis_ids = (0..thread_count)
+        .map(|thread| create_genesis_block(&GENESIS_KEY, thread).map(|block| block.id))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut chain = HeaderChain::new(genesis_ids, checkpoints, genesis.upgrade_schedule.clone());
+    resume_header_chain(&mut chain, &archive, thread_count)?;
+    info!(
+        "trustless sync: resuming from the archived headers, validated up to slot {}",
+        chain.last_slot()
+    );
+
+    let (command_sender, event_receiver, network_manager, _, _) = start_network_controller(
+        network_config,
+        node_keypair,
+        Establisher::new(),
+        None,
+        version,
+        Box::new(SystemClock),
+    )
+    .await?;
+    let mut session = SyncSession {
+        genesis,
+        archive: &archive,
+        command_sender,
+        event_receiver,
+        request_timeout,
+        max_lag_periods,
+        nodes: Default::default(),
+        skipped_nodes: Default::default(),
+    };
+    let result = async {
+        session.sync_headers(&mut chain).await?;
+        session.sync_bodies().await
+    }
+    .await;
+    network_manager.stop(session.event_receiver).await?;
+    result?;
+
+    let last_slot = chain.last_complete_slot();
+    info!(
+        "trustless sync: rebuilding the final state up to slot {}",
+        last_slot
+    );
+    let recorded_hashes = archive.get_state_hashes()?;
+    let replay_genesis = genesis.clone();
+    let replay_final_state = final_state.clone();
+    let latest_blocks = tokio::task::spawn_blocking(move || {
+        replay_blocks(
+            &replay_genesis,
+            &archive,
+            &recorded_hashes,
+            replay_final_state,
+            selector,
+            Some(last_slot),
+        )
+    })
+    .await??;
+
+    let mut final_blocks = Vec::with_capacity(latest_blocks.len());
+    for (block_id, storage) in latest_blocks {
+        let block = storage
+            .read_blocks()
+            .get(&block_id)
+            .cloned()
+            .ok_or_else(|| anyhow!("replayed block {} is not in its storage", block_id))?;
+        let parents = block
+            .content
+            .header
+            .content
+            .parents
+            .iter()
+            .map(|parent| {
+                chain
+                    .period_of(parent)
+                    .map(|period| (*parent, period))
+                    .ok_or_else(|| anyhow!("parent {} of block {} is too old", parent, block_id))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        final_blocks.push(ExportActiveBlock {
+            block,
+            parents,
+            is_final: true,
+        });
+    }
+    Ok(GlobalBootstrapState {
+        final_state,
+        graph: Some(BootstrapableGraph { final_blocks }),
+        peers: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_hash::Hash;
+    use massa_models::{
+        block::{BlockHeader, BlockHeaderSerializer},
+        operation::compute_operations_hash,
+    };
+
+    const THREAD_COUNT: u8 = 2;
+
+    fn header(keypair: &KeyPair, slot: Slot, parents: Vec<BlockId>) -> WrappedHeader {
+        BlockHeader::new_wrapped(
+            BlockHeader {
+                slot,
+                parents,
+                operation_merkle_root: compute_operations_hash(std::iter::empty()),
+                endorsements: Vec::new(),
+                state_commitment: None,
+            },
+            BlockHeaderSerializer::new(),
+            keypair,
+        )
+        .unwrap()
+    }
+
+    fn genesis_ids() -> Vec<BlockId> {
+        (0..THREAD_COUNT)
+            .map(|thread| create_genesis_block(&GENESIS_KEY, thread).unwrap().id)
+            .collect()
+    }
+
+    fn new_chain(checkpoints: BTreeMap<Slot, BlockId>) -> HeaderChain {
+        HeaderChain::new(genesis_ids(), checkpoints, UpgradeSchedule::default())
+    }
+
+    #[test]
+    fn test_header_chain_extension() {
+        let keypair = KeyPair::generate();
+        let genesis = genesis_ids();
+        let mut chain = new_chain(BTreeMap::new());
+        assert_eq!(chain.last_complete_slot(), Slot::new(0, 0));
+
+        let a = header(&keypair, Slot::new(1, 0), genesis.clone());
+        assert!(chain.extend(&a).unwrap());
+        // a header is only validated once
+        assert!(!chain.extend(&a).unwrap());
+        assert_eq!(chain.last_slot(), Slot::new(1, 0));
+        assert_eq!(chain.last_complete_slot(), Slot::new(0, 1));
+
+        let b = header(&keypair, Slot::new(1, 1), vec![a.id, genesis[1]]);
+        assert!(chain.extend(&b).unwrap());
+        assert_eq!(chain.last_complete_slot(), Slot::new(1, 1));
+        assert_eq!(chain.period_of(&a.id), Some(1));
+
+        // a miss in thread 1 then a block following both threads
+        let c = header(&keypair, Slot::new(3, 0), vec![a.id, b.id]);
+        assert!(chain.extend(&c).unwrap());
+        assert_eq!(chain.last_slot(), Slot::new(3, 0));
+        assert_eq!(chain.last_complete_slot(), Slot::new(1, 1));
+    }
+
+    #[test]
+    fn test_header_chain_rejections() {
+        let keypair = KeyPair::generate();
+        let genesis = genesis_ids();
+        let mut chain = new_chain(BTreeMap::new());
+        let a = header(&keypair, Slot::new(1, 0), genesis.clone());
+        chain.extend(&a).unwrap();
+
+        // another block at a validated slot
+        let fork = header(&KeyPair::generate(), Slot::new(1, 0), genesis.clone());
+        assert!(chain.extend(&fork).is_err());
+
+        // thread parent that is not the latest block of the thread
+        let skip = header(&keypair, Slot::new(2, 0), genesis.clone());
+        assert!(chain.extend(&skip).is_err());
+        assert!(!chain.follows_unknown_block(&skip));
+        let unknown_parent = header(&keypair, Slot::new(2, 0), vec![skip.id, genesis[1]]);
+        assert!(chain.extend(&unknown_parent).is_err());
+        assert!(chain.follows_unknown_block(&unknown_parent));
+
+        // unknown parent in the other thread
+        let unknown = header(&keypair, Slot::new(2, 1), genesis.clone()).id;
+        let orphan = header(&keypair, Slot::new(2, 0), vec![a.id, unknown]);
+        assert!(chain.extend(&orphan).is_err());
+
+        // parent in the other thread that is not older
+        let b = header(&keypair, Slot::new(3, 1), vec![a.id, genesis[1]]);
+        chain.extend(&b).unwrap();
+        let future_parent = header(&keypair, Slot::new(2, 0), vec![a.id, b.id]);
+        assert!(chain.extend(&future_parent).is_err());
+
+        // one parent missing
+        let missing = header(&keypair, Slot::new(4, 0), vec![a.id]);
+        assert!(chain.extend(&missing).is_err());
+
+        // the rejected headers left the chain unchanged
+        assert_eq!(chain.last_slot(), Slot::new(3, 1));
+        let next = header(&keypair, Slot::new(4, 0), vec![a.id, b.id]);
+        assert!(chain.extend(&next).unwrap());
+    }
+
+    #[test]
+    fn test_header_chain_checkpoints() {
+        let keypair = KeyPair::generate();
+        let genesis = genesis_ids();
+        let a = header(&keypair, Slot::new(1, 0), genesis.clone());
+        let other = header(&KeyPair::generate(), Slot::new(1, 0), genesis.clone());
+
+        // a block conflicting with the checkpoint of its slot
+        let checkpoints: BTreeMap<Slot, BlockId> = [(Slot::new(1, 0), a.id)].into_iter().collect();
+        let mut chain = new_chain(checkpoints.clone());
+        assert!(chain.extend(&other).is_err());
+        assert!(chain.extend(&a).unwrap());
+
+        // a block whose thread parent is older than a checkpoint of its thread
+        let mut chain = new_chain(checkpoints);
+        let skipping = header(&keypair, Slot::new(2, 0), genesis.clone());
+        assert!(chain.extend(&skipping).is_err());
+        // the checkpoints of other threads are not skipped
+        let b = header(&keypair, Slot::new(2, 1), genesis);
+        assert!(chain.extend(&b).unwrap());
+    }
+
+    #[test]
+    fn test_check_block_body() {
+        let keypair = KeyPair::generate();
+        let genesis = genesis_ids();
+        let a = header(&keypair, Slot::new(1, 0), genesis.clone());
+        let expected: BTreeMap<Slot, BlockId> = [(a.content.slot, a.id)].into_iter().collect();
+        let block = ArchivedBlock {
+            header: a.clone(),
+            operations: Vec::new(),
+        };
+        assert!(check_block_body(&block, &expected).unwrap());
+
+        // a block at a slot without a validated header is ignored
+        let b = header(&keypair, Slot::new(1, 1), genesis.clone());
+        let block = ArchivedBlock {
+            header: b,
+            operations: Vec::new(),
+        };
+        assert!(!check_block_body(&block, &expected).unwrap());
+
+        // another block at a validated slot
+        let other = header(&KeyPair::generate(), Slot::new(1, 0), genesis.clone());
+        let block = ArchivedBlock {
+            header: other,
+            operations: Vec::new(),
+        };
+        assert!(check_block_body(&block, &expected).is_err());
+
+        // operations not committed to by the header
+        let wrong_root = BlockHeader::new_wrapped(
+            BlockHeader {
+                slot: Slot::new(1, 0),
+                parents: genesis,
+                operation_merkle_root: Hash::compute_from(b"operations"),
+                endorsements: Vec::new(),
+                state_commitment: None,
+            },
+            BlockHeaderSerializer::new(),
+            &keypair,
+        )
+        .unwrap();
+        let expected: BTreeMap<Slot, BlockId> = [(wrong_root.content.slot, wrong_root.id)]
+            .into_iter()
+            .collect();
+        let block = ArchivedBlock {
+            header: wrong_root,
+            operations: Vec::new(),
+        };
+        assert!(check_block_body(&block, &expected).is_err());
+    }
+}
@@ -0,0 +1,13 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use crate::PoolEvent;
+use massa_models::resource_status::ResourceStatus;
+
+/// Contains the channels used by the pool to send info to other modules
+#[derive(Clone)]
+pub struct PoolChannels {
+    /// Broadcast sender(channel) for pool events
+    pub pool_event_sender: tokio::sync::broadcast::Sender<PoolEvent>,
+    /// Resource alerts of the node, no operation nor endorsement is added while it is degraded
+    pub resource_status: ResourceStatus,
+}
@@ -1,10 +1,23 @@
 //! Copyright (c) 2022 MASSA LABS <info@massa.net>
 
-use massa_models::amount::Amount;
+use massa_models::{address::Address, amount::Amount, prehash::PreHashSet};
 use serde::{Deserialize, Serialize};
 
+/// Strategy used to order the pool operations when assembling a block.
+/// Whatever the strategy, operations are only included while they fit in the block size and gas limits.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationSelectionStrategy {
+    /// operations with the highest fee density (fee per byte) first
+    GreedyByFee,
+    /// operations closest to their expiration first, then by fee density
+    OldestFirst,
+    /// operations created by the local priority addresses first, then by fee density
+    LocalPriority,
+}
+
 /// Pool configuration
-#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct PoolConfig {
     /// thread count
     pub thread_count: u8,
@@ -18,10 +31,24 @@ pub struct PoolConfig {
     pub operation_validity_periods: u64,
     /// max operation pool size per thread (in number of operations)
     pub max_operation_pool_size_per_thread: usize,
+    /// max number of operations of a single sender in the pool
+    pub max_operations_per_sender: usize,
+    /// minimal fee for an operation to be accepted in the pool
+    pub minimal_fee: Amount,
+    /// minimal fee increment for an operation to replace a conflicting pooled one of the same sender
+    pub replace_by_fee_min_increment: Amount,
+    /// max number of operations whose lifecycle status is tracked, the least recently updated ones are forgotten first
+    pub max_operation_status_count: usize,
     /// max endorsement pool size per thread (in number of endorsements)
     pub max_endorsements_pool_size_per_thread: usize,
     /// max number of endorsements per block
     pub max_block_endorsement_count: u32,
     /// operations and endorsements communication channels size
     pub channels_size: usize,
+    /// strategy used to select the operations of the produced blocks
+    pub operation_selection_strategy: OperationSelectionStrategy,
+    /// addresses whose operations are included first with the `LocalPriority` strategy
+    pub local_priority_addresses: PreHashSet<Address>,
+    /// pool events sender(channel) capacity
+    pub broadcast_events_capacity: usize,
 }
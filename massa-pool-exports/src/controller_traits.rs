@@ -1,21 +1,38 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
 use massa_models::{
-    block::BlockId, endorsement::EndorsementId, operation::OperationId, slot::Slot,
+    amount::Amount, api::OperationStatus, block::BlockId, endorsement::EndorsementId,
+    operation::OperationId, prehash::PreHashMap, slot::Slot, stats::OperationPoolStats,
 };
 use massa_storage::Storage;
 
+use crate::OperationInsertionOutcome;
+
 /// Trait defining a pool controller
 pub trait PoolController: Send + Sync {
     /// Asynchronously add operations to pool. Simply print a warning on failure.
     fn add_operations(&mut self, ops: Storage);
 
+    /// Synchronously add operations to pool and return the outcome of the insertion of each of them.
+    fn add_operations_with_outcome(
+        &mut self,
+        ops: Storage,
+    ) -> PreHashMap<OperationId, OperationInsertionOutcome>;
+
     /// Asynchronously add endorsements to pool. Simply print a warning on failure.
     fn add_endorsements(&mut self, endorsements: Storage);
 
     /// Asynchronously notify of new consensus final periods. Simply print a warning on failure.
     fn notify_final_cs_periods(&mut self, final_cs_periods: &[u64]);
 
+    /// Asynchronously notify of the operations included in newly active blocks,
+    /// given as (block id, block slot, operation ids). Simply print a warning on failure.
+    fn notify_included_operations(&mut self, included: Vec<(BlockId, Slot, Vec<OperationId>)>);
+
+    /// Asynchronously notify of the operations included in newly final blocks,
+    /// given as (block id, block slot, operation ids). Simply print a warning on failure.
+    fn notify_final_operations(&mut self, finals: Vec<(BlockId, Slot, Vec<OperationId>)>);
+
     /// Get operations for block creation.
     fn get_block_operations(&self, slot: &Slot) -> (Vec<OperationId>, Storage);
 
@@ -32,12 +49,22 @@ pub trait PoolController: Send + Sync {
     /// Get the number of operations in the pool
     fn get_operation_count(&self) -> usize;
 
+    /// Get the size and the fee density histogram of the operation pool
+    fn get_operation_pool_stats(&self) -> OperationPoolStats;
+
     /// Check if the pool contains a list of endorsements. Returns one boolean per item.
     fn contains_endorsements(&self, endorsements: &[EndorsementId]) -> Vec<bool>;
 
     /// Check if the pool contains a list of operations. Returns one boolean per item.
     fn contains_operations(&self, operations: &[OperationId]) -> Vec<bool>;
 
+    /// Get the lifecycle status of a list of operations. Returns one status per item.
+    fn get_operation_statuses(&self, operations: &[OperationId]) -> Vec<OperationStatus>;
+
+    /// Asynchronously set the minimal fee of the operations accepted in the pool.
+    /// Operations already in the pool are kept.
+    fn set_minimal_fee(&mut self, minimal_fee: Amount);
+
     /// Returns a boxed clone of self.
     /// Useful to allow cloning `Box<dyn PoolController>`.
     fn clone_box(&self) -> Box<dyn PoolController>;
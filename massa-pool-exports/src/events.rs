@@ -0,0 +1,38 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use massa_models::{api::OperationDropReason, block::BlockId, operation::OperationId, slot::Slot};
+use serde::{Deserialize, Serialize};
+
+/// Events emitted by the pool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum PoolEvent {
+    /// An operation was added to the pool
+    OperationAdded {
+        /// ID of the operation
+        operation_id: OperationId,
+    },
+    /// An operation was removed from the pool before being included in a final block
+    OperationDropped {
+        /// ID of the operation
+        operation_id: OperationId,
+        /// reason of the removal
+        reason: OperationDropReason,
+    },
+    /// An operation was included in a newly active block
+    OperationIncluded {
+        /// ID of the operation
+        operation_id: OperationId,
+        /// ID of the block
+        block_id: BlockId,
+        /// slot of the block
+        slot: Slot,
+    },
+    /// A pooled operation was replaced by a conflicting operation of the same sender paying a higher fee.
+    /// The replaced operation should not be propagated anymore.
+    OperationReplaced {
+        /// ID of the replaced operation
+        replaced: OperationId,
+        /// ID of the operation that replaced it
+        replacement: OperationId,
+    },
+}
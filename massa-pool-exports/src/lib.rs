@@ -5,11 +5,17 @@
 #![warn(missing_docs)]
 #![warn(unused_crate_dependencies)]
 
+mod channels;
 mod config;
 mod controller_traits;
+mod events;
+mod outcome;
 
-pub use config::PoolConfig;
+pub use channels::PoolChannels;
+pub use config::{OperationSelectionStrategy, PoolConfig};
 pub use controller_traits::{PoolController, PoolManager};
+pub use events::PoolEvent;
+pub use outcome::OperationInsertionOutcome;
 
 /// Test utils
 #[cfg(feature = "testing")]
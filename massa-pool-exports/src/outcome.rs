@@ -0,0 +1,38 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use massa_models::{amount::Amount, operation::OperationId};
+use serde::{Deserialize, Serialize};
+
+/// Outcome of the insertion of an operation in the pool
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OperationInsertionOutcome {
+    /// The operation was added to the pool
+    Added,
+    /// The operation was already in the pool
+    AlreadyPooled,
+    /// The operation can not be included in a future block anymore
+    Expired,
+    /// The fee of the operation is below the minimal fee of the pool
+    FeeTooLow {
+        /// minimal fee of the pool
+        minimal_fee: Amount,
+    },
+    /// The operation conflicts with a pooled operation of the same sender
+    /// and does not pay the fee increment required to replace it
+    ReplacementFeeTooLow {
+        /// ID of the conflicting pooled operation
+        conflicting: OperationId,
+        /// minimal fee to replace the conflicting operation
+        minimal_fee: Amount,
+    },
+    /// The sender already has the maximal number of operations in the pool,
+    /// all of them paying a better fee
+    SenderLimit {
+        /// maximal number of pooled operations per sender
+        max_operations_per_sender: usize,
+    },
+    /// The pool is full of operations paying a better fee
+    PoolFull,
+    /// The node is in degraded read-only mode and the pool does not grow
+    NodeDegraded,
+}
@@ -1,11 +1,14 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
-use massa_models::config::{
-    ENDORSEMENT_COUNT, MAX_BLOCK_SIZE, MAX_GAS_PER_BLOCK, OPERATION_VALIDITY_PERIODS, ROLL_PRICE,
-    THREAD_COUNT,
+use massa_models::{
+    amount::Amount,
+    config::{
+        ENDORSEMENT_COUNT, MAX_BLOCK_SIZE, MAX_GAS_PER_BLOCK, OPERATION_VALIDITY_PERIODS,
+        ROLL_PRICE, THREAD_COUNT,
+    },
 };
 
-use crate::PoolConfig;
+use crate::{OperationSelectionStrategy, PoolConfig};
 
 impl Default for PoolConfig {
     fn default() -> Self {
@@ -16,9 +19,16 @@ impl Default for PoolConfig {
             roll_price: ROLL_PRICE,
             max_block_size: MAX_BLOCK_SIZE,
             max_operation_pool_size_per_thread: 1000,
+            max_operations_per_sender: 1000,
+            minimal_fee: Amount::zero(),
+            replace_by_fee_min_increment: Amount::from_raw(1_000_000),
+            max_operation_status_count: 1000,
             max_endorsements_pool_size_per_thread: 1000,
             max_block_endorsement_count: ENDORSEMENT_COUNT,
             channels_size: 1024,
+            operation_selection_strategy: OperationSelectionStrategy::GreedyByFee,
+            local_priority_addresses: Default::default(),
+            broadcast_events_capacity: 128,
         }
     }
 }
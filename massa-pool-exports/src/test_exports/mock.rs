@@ -6,12 +6,13 @@ use std::sync::{
 };
 
 use massa_models::{
-    block::BlockId, endorsement::EndorsementId, operation::OperationId, slot::Slot,
+    amount::Amount, api::OperationStatus, block::BlockId, endorsement::EndorsementId,
+    operation::OperationId, prehash::PreHashMap, slot::Slot, stats::OperationPoolStats,
 };
 use massa_storage::Storage;
 use massa_time::MassaTime;
 
-use crate::PoolController;
+use crate::{OperationInsertionOutcome, PoolController};
 
 /// Test tool to mock pool controller responses
 pub struct PoolEventReceiver(pub Receiver<MockPoolControllerMessage>);
@@ -31,6 +32,13 @@ pub enum MockPoolControllerMessage {
         /// Storage that contains all operations
         operations: Storage,
     },
+    /// Add operations to the pool and get the outcome of their insertion
+    AddOperationsWithOutcome {
+        /// Storage that contains all operations
+        operations: Storage,
+        /// Response channel
+        response_tx: mpsc::Sender<PreHashMap<OperationId, OperationInsertionOutcome>>,
+    },
     /// Get block endorsements
     GetBlockEndorsements {
         /// Block id of the block endorsed
@@ -57,6 +65,11 @@ pub enum MockPoolControllerMessage {
         /// Response channel
         response_tx: mpsc::Sender<usize>,
     },
+    /// Get operation pool stats
+    GetOperationPoolStats {
+        /// Response channel
+        response_tx: mpsc::Sender<OperationPoolStats>,
+    },
     /// Contains endorsements
     ContainsEndorsements {
         /// ids to search
@@ -71,6 +84,13 @@ pub enum MockPoolControllerMessage {
         /// Response channel
         response_tx: mpsc::Sender<Vec<bool>>,
     },
+    /// Get operation statuses
+    GetOperationStatuses {
+        /// ids to search
+        ids: Vec<OperationId>,
+        /// Response channel
+        response_tx: mpsc::Sender<Vec<OperationStatus>>,
+    },
     /// Get stats of the pool
     GetStats {
         /// Response channel
@@ -81,6 +101,21 @@ pub enum MockPoolControllerMessage {
         /// Periods that are final
         periods: Vec<u64>,
     },
+    /// Notify of the operations included in new active blocks
+    NotifyIncludedOperations {
+        /// (block id, block slot, operation ids) of each block
+        included: Vec<(BlockId, Slot, Vec<OperationId>)>,
+    },
+    /// Notify of the operations included in new final blocks
+    NotifyFinalOperations {
+        /// (block id, block slot, operation ids) of each block
+        finals: Vec<(BlockId, Slot, Vec<OperationId>)>,
+    },
+    /// Set the minimal fee of the pool
+    SetMinimalFee {
+        /// new minimal fee
+        minimal_fee: Amount,
+    },
     /// No need to specify the response
     Any,
 }
@@ -139,6 +174,22 @@ impl PoolController for MockPoolController {
             .unwrap();
     }
 
+    fn add_operations_with_outcome(
+        &mut self,
+        operations: Storage,
+    ) -> PreHashMap<OperationId, OperationInsertionOutcome> {
+        let (response_tx, response_rx) = mpsc::channel();
+        self.0
+            .lock()
+            .unwrap()
+            .send(MockPoolControllerMessage::AddOperationsWithOutcome {
+                operations,
+                response_tx,
+            })
+            .unwrap();
+        response_rx.recv().unwrap()
+    }
+
     fn get_block_endorsements(
         &self,
         target_block: &BlockId,
@@ -190,6 +241,16 @@ impl PoolController for MockPoolController {
         response_rx.recv().unwrap()
     }
 
+    fn get_operation_pool_stats(&self) -> OperationPoolStats {
+        let (response_tx, response_rx) = mpsc::channel();
+        self.0
+            .lock()
+            .unwrap()
+            .send(MockPoolControllerMessage::GetOperationPoolStats { response_tx })
+            .unwrap();
+        response_rx.recv().unwrap()
+    }
+
     fn contains_endorsements(&self, endorsements: &[EndorsementId]) -> Vec<bool> {
         let (response_tx, response_rx) = mpsc::channel();
         self.0
@@ -226,6 +287,43 @@ impl PoolController for MockPoolController {
             .unwrap();
     }
 
+    fn notify_included_operations(&mut self, included: Vec<(BlockId, Slot, Vec<OperationId>)>) {
+        self.0
+            .lock()
+            .unwrap()
+            .send(MockPoolControllerMessage::NotifyIncludedOperations { included })
+            .unwrap();
+    }
+
+    fn notify_final_operations(&mut self, finals: Vec<(BlockId, Slot, Vec<OperationId>)>) {
+        self.0
+            .lock()
+            .unwrap()
+            .send(MockPoolControllerMessage::NotifyFinalOperations { finals })
+            .unwrap();
+    }
+
+    fn get_operation_statuses(&self, operations: &[OperationId]) -> Vec<OperationStatus> {
+        let (response_tx, response_rx) = mpsc::channel();
+        self.0
+            .lock()
+            .unwrap()
+            .send(MockPoolControllerMessage::GetOperationStatuses {
+                ids: operations.to_vec(),
+                response_tx,
+            })
+            .unwrap();
+        response_rx.recv().unwrap()
+    }
+
+    fn set_minimal_fee(&mut self, minimal_fee: Amount) {
+        self.0
+            .lock()
+            .unwrap()
+            .send(MockPoolControllerMessage::SetMinimalFee { minimal_fee })
+            .unwrap();
+    }
+
     fn clone_box(&self) -> Box<dyn PoolController> {
         Box::new(self.clone())
     }
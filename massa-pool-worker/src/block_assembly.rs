@@ -0,0 +1,31 @@
+//! Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Ordering of the pool operations considered for inclusion in a produced block
+
+use massa_models::{address::Address, prehash::PreHashSet};
+use massa_pool_exports::OperationSelectionStrategy;
+
+use crate::types::OperationInfo;
+
+/// Order the candidate operations of a block according to the selection strategy.
+///
+/// `candidates` must be sorted by decreasing fee density, which is the order of the pool:
+/// the other strategies rely on a stable sort so that fee density is the tie-breaker.
+pub(crate) fn order_candidates<'a>(
+    strategy: OperationSelectionStrategy,
+    local_priority_addresses: &PreHashSet<Address>,
+    mut candidates: Vec<&'a OperationInfo>,
+) -> Vec<&'a OperationInfo> {
+    match strategy {
+        OperationSelectionStrategy::GreedyByFee => {}
+        OperationSelectionStrategy::OldestFirst => {
+            candidates.sort_by_key(|op_info| *op_info.validity_period_range.end());
+        }
+        OperationSelectionStrategy::LocalPriority => {
+            candidates.sort_by_key(|op_info| {
+                !local_priority_addresses.contains(&op_info.creator_address)
+            });
+        }
+    }
+    candidates
+}
@@ -3,14 +3,16 @@
 //! Pool controller implementation
 
 use massa_models::{
-    block::BlockId, endorsement::EndorsementId, operation::OperationId, slot::Slot,
+    amount::Amount, api::OperationStatus, block::BlockId, endorsement::EndorsementId,
+    operation::OperationId, prehash::PreHashMap, resource_status::ResourceStatus, slot::Slot,
+    stats::OperationPoolStats,
 };
-use massa_pool_exports::{PoolConfig, PoolController, PoolManager};
+use massa_pool_exports::{OperationInsertionOutcome, PoolConfig, PoolController, PoolManager};
 use massa_storage::Storage;
 use parking_lot::RwLock;
 use std::sync::mpsc::TrySendError;
 use std::sync::{mpsc::SyncSender, Arc};
-use tracing::{info, warn};
+use tracing::{debug, info, warn};
 
 use crate::{endorsement_pool::EndorsementPool, operation_pool::OperationPool};
 
@@ -20,6 +22,12 @@ pub enum Command {
     AddItems(Storage),
     /// Notify of new final consensus periods
     NotifyFinalCsPeriods(Vec<u64>),
+    /// Notify of the operations included in new active blocks
+    NotifyIncludedOperations(Vec<(BlockId, Slot, Vec<OperationId>)>),
+    /// Notify of the operations included in new final blocks
+    NotifyFinalOperations(Vec<(BlockId, Slot, Vec<OperationId>)>),
+    /// Set the minimal fee of the operations accepted in the pool
+    SetMinimalFee(Amount),
     /// Stop the worker
     Stop,
 }
@@ -37,11 +45,17 @@ pub struct PoolControllerImpl {
     pub(crate) operations_input_sender: SyncSender<Command>,
     /// Endorsement write worker command sender
     pub(crate) endorsements_input_sender: SyncSender<Command>,
+    /// Resource alerts, the pool does not grow while the node is degraded
+    pub(crate) resource_status: ResourceStatus,
 }
 
 impl PoolController for PoolControllerImpl {
     /// Asynchronously add operations to pool. Simply print a warning on failure.
     fn add_operations(&mut self, ops: Storage) {
+        if self.resource_status.is_degraded() {
+            debug!("operations not added to pool: the node is in degraded read-only mode");
+            return;
+        }
         match self
             .operations_input_sender
             .try_send(Command::AddItems(ops))
@@ -56,8 +70,28 @@ impl PoolController for PoolControllerImpl {
         }
     }
 
+    /// Synchronously add operations to pool and return the outcome of the insertion of each of them.
+    fn add_operations_with_outcome(
+        &mut self,
+        ops: Storage,
+    ) -> PreHashMap<OperationId, OperationInsertionOutcome> {
+        if self.resource_status.is_degraded() {
+            debug!("operations not added to pool: the node is in degraded read-only mode");
+            return ops
+                .get_op_refs()
+                .iter()
+                .map(|op_id| (*op_id, OperationInsertionOutcome::NodeDegraded))
+                .collect();
+        }
+        self.operation_pool.write().add_operations(ops)
+    }
+
     /// Asynchronously add endorsements to pool. Simply print a warning on failure.
     fn add_endorsements(&mut self, endorsements: Storage) {
+        if self.resource_status.is_degraded() {
+            debug!("endorsements not added to pool: the node is in degraded read-only mode");
+            return;
+        }
         match self
             .endorsements_input_sender
             .try_send(Command::AddItems(endorsements))
@@ -107,6 +141,62 @@ impl PoolController for PoolControllerImpl {
         }
     }
 
+    /// Asynchronously notify of the operations included in newly active blocks. Simply print a warning on failure.
+    fn notify_included_operations(&mut self, included: Vec<(BlockId, Slot, Vec<OperationId>)>) {
+        match self
+            .operations_input_sender
+            .try_send(Command::NotifyIncludedOperations(included))
+        {
+            Err(TrySendError::Disconnected(_)) => {
+                warn!("Could not notify operation pool of included operations: worker is unreachable.");
+            }
+            Err(TrySendError::Full(_)) => {
+                warn!("Could not notify operation pool of included operations: worker channel is full.");
+            }
+            Ok(_) => {}
+        }
+    }
+
+    /// Asynchronously notify of the operations included in newly final blocks. Simply print a warning on failure.
+    fn notify_final_operations(&mut self, finals: Vec<(BlockId, Slot, Vec<OperationId>)>) {
+        match self
+            .operations_input_sender
+            .try_send(Command::NotifyFinalOperations(finals))
+        {
+            Err(TrySendError::Disconnected(_)) => {
+                warn!(
+                    "Could not notify operation pool of final operations: worker is unreachable."
+                );
+            }
+            Err(TrySendError::Full(_)) => {
+                warn!(
+                    "Could not notify operation pool of final operations: worker channel is full."
+                );
+            }
+            Ok(_) => {}
+        }
+    }
+
+    /// Asynchronously set the minimal fee of the operation pool. Simply print a warning on failure.
+    fn set_minimal_fee(&mut self, minimal_fee: Amount) {
+        match self
+            .operations_input_sender
+            .try_send(Command::SetMinimalFee(minimal_fee))
+        {
+            Err(TrySendError::Disconnected(_)) => {
+                warn!(
+                    "Could not set the minimal fee of the operation pool: worker is unreachable."
+                );
+            }
+            Err(TrySendError::Full(_)) => {
+                warn!(
+                    "Could not set the minimal fee of the operation pool: worker channel is full."
+                );
+            }
+            Ok(_) => {}
+        }
+    }
+
     /// get operations for block creation
     fn get_block_operations(&self, slot: &Slot) -> (Vec<OperationId>, Storage) {
         self.operation_pool.read().get_block_operations(slot)
@@ -139,6 +229,11 @@ impl PoolController for PoolControllerImpl {
         self.operation_pool.read().len()
     }
 
+    /// Get the size and the fee density histogram of the operation pool
+    fn get_operation_pool_stats(&self) -> OperationPoolStats {
+        self.operation_pool.read().get_stats()
+    }
+
     /// Check if the pool contains a list of endorsements. Returns one boolean per item.
     fn contains_endorsements(&self, endorsements: &[EndorsementId]) -> Vec<bool> {
         let lck = self.endorsement_pool.read();
@@ -150,6 +245,13 @@ impl PoolController for PoolControllerImpl {
         let lck = self.operation_pool.read();
         operations.iter().map(|id| lck.contains(id)).collect()
     }
+
+    /// Get the lifecycle status of a list of operations. Returns one status per item.
+    fn get_operation_statuses(&self, operations: &[OperationId]) -> Vec<OperationStatus> {
+        self.operation_pool
+            .read()
+            .get_operation_statuses(operations)
+    }
 }
 
 /// Implementation of the pool manager.
@@ -1,5 +1,6 @@
 //! Copyright (c) 2022 MASSA LABS <info@massa.net>
 
+use massa_metrics::POOL_METRICS;
 use massa_models::{
     block::BlockId,
     endorsement::EndorsementId,
@@ -72,6 +73,7 @@ impl EndorsementPool {
             }
         }
         self.storage.drop_endorsement_refs(&removed);
+        POOL_METRICS.endorsement_count.set(self.len() as i64);
     }
 
     /// Add a list of endorsements to the pool
@@ -143,6 +145,7 @@ impl EndorsementPool {
 
         // drop removed endorsements from storage
         self.storage.drop_endorsement_refs(&removed);
+        POOL_METRICS.endorsement_count.set(self.len() as i64);
     }
 
     /// get endorsements for block creation
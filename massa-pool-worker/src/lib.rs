@@ -8,9 +8,11 @@
 #![feature(map_try_insert)]
 #![feature(let_chains)]
 
+mod block_assembly;
 mod controller_impl;
 mod endorsement_pool;
 mod operation_pool;
+mod operation_status;
 mod types;
 mod worker;
 
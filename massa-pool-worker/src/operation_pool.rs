@@ -1,18 +1,26 @@
 //! Copyright (c) 2022 MASSA LABS <info@massa.net>
 
 use massa_execution_exports::ExecutionController;
+use massa_metrics::POOL_METRICS;
 use massa_models::{
     address::Address,
     amount::Amount,
+    api::{OperationDropReason, OperationStatus},
+    block::BlockId,
     operation::OperationId,
     prehash::{CapacityAllocator, PreHashMap, PreHashSet},
     slot::Slot,
+    stats::{FeeHistogramBucket, OperationPoolStats},
 };
-use massa_pool_exports::PoolConfig;
+use massa_pool_exports::{OperationInsertionOutcome, PoolChannels, PoolConfig, PoolEvent};
 use massa_storage::Storage;
-use std::collections::BTreeSet;
+use std::collections::{hash_map::Entry, BTreeMap, BTreeSet};
 
-use crate::types::{OperationInfo, PoolOperationCursor};
+use crate::{
+    block_assembly::order_candidates,
+    operation_status::OperationStatusIndex,
+    types::{OperationInfo, PoolOperationCursor},
+};
 
 pub struct OperationPool {
     /// configuration
@@ -27,12 +35,21 @@ pub struct OperationPool {
     /// operations sorted by increasing expiration slot
     ops_per_expiration: BTreeSet<(Slot, OperationId)>,
 
+    /// operations sorted by decreasing quality, per sender
+    ops_per_sender: PreHashMap<Address, BTreeSet<PoolOperationCursor>>,
+
+    /// lifecycle status of the operations known by the pool
+    statuses: OperationStatusIndex,
+
     /// storage instance
     pub(crate) storage: Storage,
 
     /// execution controller
     execution_controller: Box<dyn ExecutionController>,
 
+    /// channels used to send info to other modules
+    channels: PoolChannels,
+
     /// last consensus final periods, per thread
     last_cs_final_periods: Vec<u64>,
 }
@@ -42,15 +59,19 @@ impl OperationPool {
         config: PoolConfig,
         storage: &Storage,
         execution_controller: Box<dyn ExecutionController>,
+        channels: PoolChannels,
     ) -> Self {
         OperationPool {
             operations: Default::default(),
             sorted_ops_per_thread: vec![Default::default(); config.thread_count as usize],
             ops_per_expiration: Default::default(),
+            ops_per_sender: Default::default(),
             last_cs_final_periods: vec![0u64; config.thread_count as usize],
+            statuses: OperationStatusIndex::new(config.max_operation_status_count),
             config,
             storage: storage.clone_without_refs(),
             execution_controller,
+            channels,
         }
     }
 
@@ -64,6 +85,11 @@ impl OperationPool {
         self.operations.contains_key(id)
     }
 
+    /// Set the minimal fee of the operations added from now on
+    pub(crate) fn set_minimal_fee(&mut self, minimal_fee: Amount) {
+        self.config.minimal_fee = minimal_fee;
+    }
+
     /// notify of new final slot
     pub(crate) fn notify_final_cs_periods(&mut self, final_cs_periods: &[u64]) {
         // update internal final slot counter
@@ -75,19 +101,83 @@ impl OperationPool {
             if expire_slot.period > self.last_cs_final_periods[expire_slot.thread as usize] {
                 break;
             }
-            self.ops_per_expiration.pop_first();
-            let op_info = self
-                .operations
-                .remove(&op_id)
+            self.remove_operation(&op_id)
                 .expect("expected op presence in operations list");
-            if !self.sorted_ops_per_thread[expire_slot.thread as usize].remove(&op_info.cursor) {
-                panic!("expected op presence in sorted list")
-            }
+            self.mark_dropped(op_id, OperationDropReason::Expired);
             removed_ops.insert(op_id);
         }
 
         // notify storage that pool has lost references to removed_ops
         self.storage.drop_operation_refs(&removed_ops);
+        POOL_METRICS.operation_count.set(self.len() as i64);
+    }
+
+    /// Note the operations included in newly active blocks
+    pub(crate) fn notify_included_operations(
+        &mut self,
+        included: Vec<(BlockId, Slot, Vec<OperationId>)>,
+    ) {
+        for (block_id, slot, op_ids) in included {
+            for op_id in op_ids {
+                if !matches!(self.statuses.get(&op_id), OperationStatus::Final { .. }) {
+                    self.statuses
+                        .set(op_id, OperationStatus::Included { block_id, slot });
+                    self.emit_event(PoolEvent::OperationIncluded {
+                        operation_id: op_id,
+                        block_id,
+                        slot,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Note the operations included in newly final blocks
+    pub(crate) fn notify_final_operations(
+        &mut self,
+        finals: Vec<(BlockId, Slot, Vec<OperationId>)>,
+    ) {
+        for (block_id, slot, op_ids) in finals {
+            for op_id in op_ids {
+                self.statuses
+                    .set(op_id, OperationStatus::Final { block_id, slot });
+            }
+        }
+    }
+
+    /// Get the lifecycle status of a list of operations
+    pub fn get_operation_statuses(&self, ids: &[OperationId]) -> Vec<OperationStatus> {
+        ids.iter().map(|id| self.statuses.get(id)).collect()
+    }
+
+    /// Note that an operation was removed from the pool.
+    /// Operations that are final keep their status, and so do included ones unless they expired:
+    /// their block may still become final.
+    fn mark_dropped(&mut self, op_id: OperationId, reason: OperationDropReason) {
+        POOL_METRICS.note_dropped_operation(match reason {
+            OperationDropReason::Expired => "expired",
+            OperationDropReason::Evicted => "evicted",
+            OperationDropReason::SenderLimit => "sender_limit",
+            OperationDropReason::Replaced { .. } => "replaced",
+        });
+        match self.statuses.get(&op_id) {
+            OperationStatus::Final { .. } => {}
+            OperationStatus::Included { .. } if reason != OperationDropReason::Expired => {}
+            _ => {
+                self.statuses
+                    .set(op_id, OperationStatus::Dropped { reason });
+                self.emit_event(PoolEvent::OperationDropped {
+                    operation_id: op_id,
+                    reason,
+                });
+            }
+        }
+    }
+
+    /// Broadcast a pool event
+    fn emit_event(&self, event: PoolEvent) {
+        // an error only means that nobody is listening
+        let _ = self.channels.pool_event_sender.send(event);
     }
 
     /// Checks if an operation is relevant according to its thread and period validity range
@@ -97,32 +187,123 @@ impl OperationPool {
         // todo check if validity not started yet
     }
 
-    /// Add a list of operations to the pool
-    pub(crate) fn add_operations(&mut self, mut ops_storage: Storage) {
+    /// Remove an operation from the pool and from all its indexes
+    fn remove_operation(&mut self, op_id: &OperationId) -> Option<OperationInfo> {
+        let op_info = self.operations.remove(op_id)?;
+        if !self.sorted_ops_per_thread[op_info.thread as usize].remove(&op_info.cursor) {
+            panic!("expected op presence in sorted list");
+        }
+        let end_slot = Slot::new(*op_info.validity_period_range.end(), op_info.thread);
+        if !self.ops_per_expiration.remove(&(end_slot, op_info.id)) {
+            panic!("expected op presence in expiration indexed list");
+        }
+        match self.ops_per_sender.entry(op_info.creator_address) {
+            Entry::Occupied(mut sender_ops) => {
+                if !sender_ops.get_mut().remove(&op_info.cursor) {
+                    panic!("expected op presence in sender indexed list");
+                }
+                if sender_ops.get().is_empty() {
+                    sender_ops.remove();
+                }
+            }
+            Entry::Vacant(_) => panic!("expected op presence in sender indexed list"),
+        }
+        Some(op_info)
+    }
+
+    /// Add a list of operations to the pool and return the outcome of the insertion of each of them
+    pub(crate) fn add_operations(
+        &mut self,
+        mut ops_storage: Storage,
+    ) -> PreHashMap<OperationId, OperationInsertionOutcome> {
         let items = ops_storage
             .get_op_refs()
             .iter()
             .copied()
             .collect::<Vec<_>>();
 
+        let mut outcomes = PreHashMap::with_capacity(items.len());
         let mut added = PreHashSet::with_capacity(items.len());
         let mut removed = PreHashSet::with_capacity(items.len());
+        let mut senders = PreHashSet::with_capacity(items.len());
+        let mut replaced = Vec::new();
 
         // add items to pool
         {
             let ops = ops_storage.read_operations();
             for op_id in items {
+                let op = ops
+                    .get(&op_id)
+                    .expect("attempting to add operation to pool, but it is absent from storage");
                 let op_info = OperationInfo::from_op(
-                    ops.get(&op_id).expect(
-                        "attempting to add operation to pool, but it is absent from storage",
-                    ),
+                    op,
                     self.config.operation_validity_periods,
                     self.config.roll_price,
                     self.config.thread_count,
                 );
                 if !self.is_operation_relevant(&op_info) {
+                    outcomes.insert(op_id, OperationInsertionOutcome::Expired);
+                    continue;
+                }
+                if op_info.fee < self.config.minimal_fee {
+                    outcomes.insert(
+                        op_id,
+                        OperationInsertionOutcome::FeeTooLow {
+                            minimal_fee: self.config.minimal_fee,
+                        },
+                    );
                     continue;
                 }
+                // operations that are already in the pool are ignored
+                if self.operations.contains_key(&op_info.id) {
+                    outcomes.insert(op_id, OperationInsertionOutcome::AlreadyPooled);
+                    continue;
+                }
+                // a pooled operation of the same sender with the same expire period and content (except for the fee)
+                // is only replaced if the new operation pays at least the configured fee increment on top of its fee
+                let conflicting_op =
+                    self.ops_per_sender
+                        .get(&op_info.creator_address)
+                        .and_then(|sender_ops| {
+                            sender_ops
+                                .iter()
+                                .map(|cursor| cursor.get_id())
+                                .find(|pooled_id| {
+                                    ops.get(pooled_id).map_or(false, |pooled_op| {
+                                        pooled_op.content.expire_period == op.content.expire_period
+                                            && pooled_op.content.op == op.content.op
+                                    })
+                                })
+                        });
+                if let Some(conflicting_id) = conflicting_op {
+                    let conflicting_fee = self
+                        .operations
+                        .get(&conflicting_id)
+                        .expect("the operation should be in self.operations at this point")
+                        .fee;
+                    let replacement_fee =
+                        conflicting_fee.saturating_add(self.config.replace_by_fee_min_increment);
+                    if op_info.fee < replacement_fee {
+                        outcomes.insert(
+                            op_id,
+                            OperationInsertionOutcome::ReplacementFeeTooLow {
+                                conflicting: conflicting_id,
+                                minimal_fee: replacement_fee,
+                            },
+                        );
+                        continue;
+                    }
+                    self.remove_operation(&conflicting_id)
+                        .expect("the operation should be in self.operations at this point");
+                    self.mark_dropped(
+                        conflicting_id,
+                        OperationDropReason::Replaced {
+                            replacement: op_info.id,
+                        },
+                    );
+                    removed.insert(conflicting_id);
+                    replaced.push((conflicting_id, op_info.id));
+                }
                 if let Ok(op_info) = self.operations.try_insert(op_info.id, op_info) {
                     if !self.sorted_ops_per_thread[op_info.thread as usize].insert(op_info.cursor) {
                         panic!("sorted ops should not contain the op at this point");
@@ -133,27 +314,80 @@ impl OperationPool {
                     )) {
                         panic!("expiration indexed ops should not contain the op at this point");
                     }
+                    if !self
+                        .ops_per_sender
+                        .entry(op_info.creator_address)
+                        .or_default()
+                        .insert(op_info.cursor)
+                    {
+                        panic!("sender indexed ops should not contain the op at this point");
+                    }
+                    if !matches!(
+                        self.statuses.get(&op_info.id),
+                        OperationStatus::Included { .. } | OperationStatus::Final { .. }
+                    ) {
+                        self.statuses.set(op_info.id, OperationStatus::Pending);
+                    }
+                    self.emit_event(PoolEvent::OperationAdded {
+                        operation_id: op_info.id,
+                    });
+                    POOL_METRICS
+                        .operation_fee_per_byte
+                        .observe(op_info.fee.to_raw() as f64 / (op_info.size as f64).max(1.0));
+                    senders.insert(op_info.creator_address);
                     added.insert(op_info.id);
+                    outcomes.insert(op_info.id, OperationInsertionOutcome::Added);
+                }
+            }
+        }
+
+        // prune the worst operations of the senders having too many operations
+        for sender in senders {
+            let excess_ops: Vec<OperationId> = self
+                .ops_per_sender
+                .get(&sender)
+                .map(|sender_ops| {
+                    sender_ops
+                        .iter()
+                        .skip(self.config.max_operations_per_sender)
+                        .map(|cursor| cursor.get_id())
+                        .collect()
+                })
+                .unwrap_or_default();
+            for op_id in excess_ops {
+                self.remove_operation(&op_id)
+                    .expect("the operation should be in self.operations at this point");
+                self.mark_dropped(op_id, OperationDropReason::SenderLimit);
+                removed.insert(op_id);
+                // an operation pruned right after being added was rejected
+                if added.contains(&op_id) {
+                    outcomes.insert(
+                        op_id,
+                        OperationInsertionOutcome::SenderLimit {
+                            max_operations_per_sender: self.config.max_operations_per_sender,
+                        },
+                    );
                 }
             }
         }
 
         // prune excess operations
-        self.sorted_ops_per_thread.iter_mut().for_each(|ops| {
-            while ops.len() > self.config.max_operation_pool_size_per_thread {
-                // the unwrap below won't panic because the loop condition tests for non-emptines of self.operations
-                let cursor = ops.pop_last().unwrap();
-                let op_info = self
-                    .operations
-                    .remove(&cursor.get_id())
+        for thread in 0..self.config.thread_count as usize {
+            let excess_ops: Vec<OperationId> = self.sorted_ops_per_thread[thread]
+                .iter()
+                .skip(self.config.max_operation_pool_size_per_thread)
+                .map(|cursor| cursor.get_id())
+                .collect();
+            for op_id in excess_ops {
+                self.remove_operation(&op_id)
                     .expect("the operation should be in self.operations at this point");
-                let end_slot = Slot::new(*op_info.validity_period_range.end(), op_info.thread);
-                if !self.ops_per_expiration.remove(&(end_slot, op_info.id)) {
-                    panic!("the operation should be in self.ops_per_expiration at this point");
+                self.mark_dropped(op_id, OperationDropReason::Evicted);
+                removed.insert(op_id);
+                if added.contains(&op_id) {
+                    outcomes.insert(op_id, OperationInsertionOutcome::PoolFull);
                 }
-                removed.insert(op_info.id);
             }
-        });
+        }
 
         // This will add the new ops to the storage without taking locks.
         // It just take the local references from `ops_storage` if they are not in `self.storage` yet.
@@ -169,6 +403,55 @@ impl OperationPool {
 
         // Clean the removed operations from storage.
         self.storage.drop_operation_refs(&removed);
+
+        POOL_METRICS.operation_count.set(self.len() as i64);
+
+        // notify of the replaced operations so that their propagation is halted
+        for (replaced, replacement) in replaced {
+            self.emit_event(PoolEvent::OperationReplaced {
+                replaced,
+                replacement,
+            });
+        }
+
+        outcomes
+    }
+
+    /// Get the size and the fee density histogram of the pool.
+    ///
+    /// Bucket 0 of the histogram holds the operations with a zero fee per byte,
+    /// bucket `b > 0` holds those with a fee per byte in `[2^(b-1), 2^b)` raw units.
+    pub fn get_stats(&self) -> OperationPoolStats {
+        let mut histogram: BTreeMap<u32, u64> = BTreeMap::new();
+        let mut min_fee_per_byte: Option<u64> = None;
+        let mut max_fee_per_byte: Option<u64> = None;
+        for op_info in self.operations.values() {
+            let fee_per_byte = op_info.fee.to_raw() / (op_info.size as u64).max(1);
+            min_fee_per_byte = Some(min_fee_per_byte.map_or(fee_per_byte, |v| v.min(fee_per_byte)));
+            max_fee_per_byte = Some(max_fee_per_byte.map_or(fee_per_byte, |v| v.max(fee_per_byte)));
+            *histogram
+                .entry(u64::BITS - fee_per_byte.leading_zeros())
+                .or_default() += 1;
+        }
+        OperationPoolStats {
+            operation_count: self.operations.len() as u64,
+            max_operation_count: (self.config.max_operation_pool_size_per_thread as u64)
+                .saturating_mul(self.config.thread_count as u64),
+            minimal_fee: self.config.minimal_fee,
+            min_fee_per_byte: min_fee_per_byte.map(Amount::from_raw),
+            max_fee_per_byte: max_fee_per_byte.map(Amount::from_raw),
+            fee_histogram: histogram
+                .into_iter()
+                .map(|(bucket, operation_count)| FeeHistogramBucket {
+                    min_fee_per_byte: Amount::from_raw(match bucket {
+                        0 => 0,
+                        b => 1 << (b - 1),
+                    }),
+                    max_fee_per_byte: 1u64.checked_shl(bucket).map(Amount::from_raw),
+                    operation_count,
+                })
+                .collect(),
+        }
     }
 
     /// get operations for block creation
@@ -183,18 +466,23 @@ impl OperationPool {
         // cache of balances
         let mut balance_cache: PreHashMap<Address, Amount> = Default::default();
 
-        // iterate over pool operations in the right thread, from best to worst
-        for cursor in self.sorted_ops_per_thread[slot.thread as usize].iter() {
-            let op_info = self
-                .operations
-                .get(&cursor.get_id())
-                .expect("the operation should be in self.operations at this point");
-
-            // exclude ops for which the block slot is outside of their validity range
-            if !op_info.validity_period_range.contains(&slot.period) {
-                continue;
-            }
-
+        // list pool operations in the right thread that are valid at the block slot, from best to worst fee density
+        let candidates: Vec<&OperationInfo> = self.sorted_ops_per_thread[slot.thread as usize]
+            .iter()
+            .map(|cursor| {
+                self.operations
+                    .get(&cursor.get_id())
+                    .expect("the operation should be in self.operations at this point")
+            })
+            .filter(|op_info| op_info.validity_period_range.contains(&slot.period))
+            .collect();
+
+        // iterate over candidates in the order given by the selection strategy
+        for op_info in order_candidates(
+            self.config.operation_selection_strategy,
+            &self.config.local_priority_addresses,
+            candidates,
+        ) {
             // exclude ops that are too large
             if op_info.size > remaining_space {
                 continue;
@@ -0,0 +1,55 @@
+//! Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Index of the lifecycle status of the operations known by the pool
+
+use massa_models::{api::OperationStatus, operation::OperationId, prehash::PreHashMap};
+use std::collections::BTreeMap;
+
+/// Bounded index of operation statuses.
+/// When full, the statuses that were updated the longest time ago are forgotten first.
+pub(crate) struct OperationStatusIndex {
+    /// max number of tracked operations
+    max_count: usize,
+    /// status of each tracked operation along with the sequence number of its last update
+    statuses: PreHashMap<OperationId, (u64, OperationStatus)>,
+    /// tracked operations sorted by sequence number of their last update
+    by_update: BTreeMap<u64, OperationId>,
+    /// sequence number of the next update
+    next_sequence_number: u64,
+}
+
+impl OperationStatusIndex {
+    /// Create an empty index tracking at most `max_count` operations
+    pub fn new(max_count: usize) -> Self {
+        OperationStatusIndex {
+            max_count,
+            statuses: Default::default(),
+            by_update: Default::default(),
+            next_sequence_number: 0,
+        }
+    }
+
+    /// Get the status of an operation
+    pub fn get(&self, id: &OperationId) -> OperationStatus {
+        self.statuses
+            .get(id)
+            .map_or(OperationStatus::Unknown, |(_, status)| *status)
+    }
+
+    /// Set the status of an operation, forgetting the least recently updated ones if the index is full
+    pub fn set(&mut self, id: OperationId, status: OperationStatus) {
+        let sequence_number = self.next_sequence_number;
+        self.next_sequence_number += 1;
+        if let Some((prev_sequence_number, _)) = self.statuses.insert(id, (sequence_number, status))
+        {
+            self.by_update.remove(&prev_sequence_number);
+        }
+        self.by_update.insert(sequence_number, id);
+        while self.statuses.len() > self.max_count {
+            match self.by_update.pop_first() {
+                Some((_, oldest_id)) => self.statuses.remove(&oldest_id),
+                None => break,
+            };
+        }
+    }
+}
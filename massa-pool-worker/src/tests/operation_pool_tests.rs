@@ -12,26 +12,66 @@
 //! Same as classic but we try to add irrelevant operation. (See the definition
 //! chapter below)
 //!
+//! # Minimal fee and eviction
+//! Function: [`test_minimal_fee_and_eviction`]
+//! Operations below the minimal fee are rejected and the lowest fee density
+//! operations are evicted when a thread of the pool is full.
+//!
+//! # Operations per sender
+//! Function: [`test_max_operations_per_sender`]
+//! Only the best operations of a sender are kept, duplicates are ignored and
+//! expired operations are pruned when the final periods advance.
+//!
+//! # Replace by fee
+//! Function: [`test_replace_by_fee`]
+//! A conflicting operation of the same sender only replaces the pooled one if
+//! it pays the configured fee increment, and the additions, drops and
+//! replacement are notified.
+//!
+//! # Insertion outcomes
+//! Function: [`test_insertion_outcomes`]
+//! Each added or rejected operation is reported with the reason of its
+//! rejection, including the operations pruned right after being added.
+//!
+//! # Operation statuses
+//! Function: [`test_operation_statuses`]
+//! Lifecycle of the operations through replacement, inclusion, finality and
+//! expiry, and retention of a bounded number of statuses.
+//!
+//! # Order block candidates
+//! Function: [`test_order_candidates`]
+//! Ordering of the operations considered for a block by each selection strategy.
+//!
 //! # Definition
 //! Relevant operation: Operation with a validity range corresponding to the
 //! latest period given his own thread. All operation which doesn't fit these
 //! requirements are "irrelevant"
 //!
-use super::tools::{create_some_operations, operation_pool_test};
-use crate::operation_pool::OperationPool;
+use super::tools::{
+    _get_transaction_with_addresses, create_some_operations, operation_pool_test, pool_channels,
+};
+use crate::{
+    block_assembly::order_candidates, operation_pool::OperationPool, types::OperationInfo,
+};
 use massa_execution_exports::test_exports::MockExecutionController;
+use massa_hash::Hash;
 use massa_models::{
     address::Address,
     amount::Amount,
+    api::{OperationDropReason, OperationStatus},
+    block::BlockId,
     operation::{Operation, OperationSerializer, OperationType, WrappedOperation},
-    prehash::PreHashMap,
+    prehash::{PreHashMap, PreHashSet},
     slot::Slot,
     wrapped::WrappedContent,
 };
-use massa_pool_exports::PoolConfig;
+use massa_pool_exports::{
+    OperationInsertionOutcome, OperationSelectionStrategy, PoolChannels, PoolConfig, PoolEvent,
+};
 use massa_signature::KeyPair;
 use massa_storage::Storage;
 use std::str::FromStr;
+use tokio::sync::broadcast;
 
 #[test]
 fn test_add_operation() {
@@ -56,6 +96,359 @@ fn test_add_irrelevant_operation() {
     });
 }
 
+/// Check that the pool rejects operations below the minimal fee,
+/// evicts the lowest fee density ones when full and reports them in its stats.
+#[test]
+fn test_minimal_fee_and_eviction() {
+    let pool_config = PoolConfig {
+        minimal_fee: Amount::from_str("5").unwrap(),
+        max_operation_pool_size_per_thread: 2,
+        ..Default::default()
+    };
+    operation_pool_test(pool_config, |mut operation_pool, mut storage| {
+        // all operations are in the same thread
+        let sender_keypair = KeyPair::generate();
+        let recv_pub = KeyPair::generate().get_public_key();
+        let ops: Vec<WrappedOperation> = [1, 10, 20, 30]
+            .into_iter()
+            .map(|fee| _get_transaction_with_addresses(10, fee, &sender_keypair, recv_pub))
+            .collect();
+        storage.store_operations(ops.clone());
+        operation_pool.add_operations(storage);
+
+        // below the minimal fee
+        assert!(!operation_pool.contains(&ops[0].id));
+        // evicted by better operations
+        assert!(!operation_pool.contains(&ops[1].id));
+        assert!(operation_pool.contains(&ops[2].id));
+        assert!(operation_pool.contains(&ops[3].id));
+        assert_eq!(operation_pool.storage.get_op_refs().len(), 2);
+
+        let stats = operation_pool.get_stats();
+        assert_eq!(stats.operation_count, 2);
+        assert_eq!(stats.minimal_fee, Amount::from_str("5").unwrap());
+        assert_eq!(
+            stats
+                .fee_histogram
+                .iter()
+                .map(|bucket| bucket.operation_count)
+                .sum::<u64>(),
+            2
+        );
+        let (min, max) = (
+            stats.min_fee_per_byte.unwrap(),
+            stats.max_fee_per_byte.unwrap(),
+        );
+        let first = stats.fee_histogram.first().unwrap();
+        assert!(first.min_fee_per_byte <= min && Some(min) < first.max_fee_per_byte);
+        let last = stats.fee_histogram.last().unwrap();
+        assert!(last.min_fee_per_byte <= max && Some(max) < last.max_fee_per_byte);
+    });
+}
+
+/// Check the per-sender cap, the duplicate rejection and the pruning of expired operations.
+#[test]
+fn test_max_operations_per_sender() {
+    let pool_config = PoolConfig {
+        max_operations_per_sender: 2,
+        ..Default::default()
+    };
+    let thread_count = pool_config.thread_count;
+    operation_pool_test(pool_config, |mut operation_pool, storage| {
+        let sender_keypair = KeyPair::generate();
+        let recv_pub = KeyPair::generate().get_public_key();
+        let ops: Vec<WrappedOperation> = [(10, 10), (10, 20), (20, 30)]
+            .into_iter()
+            .map(|(expire_period, fee)| {
+                _get_transaction_with_addresses(expire_period, fee, &sender_keypair, recv_pub)
+            })
+            .collect();
+        let other_sender_op = _get_transaction_with_addresses(
+            10,
+            1,
+            &KeyPair::generate(),
+            KeyPair::generate().get_public_key(),
+        );
+
+        let mut ops_storage = storage.clone_without_refs();
+        ops_storage.store_operations(ops.clone());
+        ops_storage.store_operations(vec![other_sender_op.clone()]);
+        operation_pool.add_operations(ops_storage);
+
+        // the lowest fee operation of the sender was dropped, the other sender is not affected
+        assert!(!operation_pool.contains(&ops[0].id));
+        assert!(operation_pool.contains(&ops[1].id));
+        assert!(operation_pool.contains(&ops[2].id));
+        assert!(operation_pool.contains(&other_sender_op.id));
+        assert_eq!(operation_pool.len(), 3);
+
+        // duplicates are ignored
+        let mut ops_storage = storage.clone_without_refs();
+        ops_storage.store_operations(vec![ops[1].clone()]);
+        operation_pool.add_operations(ops_storage);
+        assert_eq!(operation_pool.len(), 3);
+        assert_eq!(operation_pool.storage.get_op_refs().len(), 3);
+
+        // operations expiring at period 10 are pruned once it is final
+        operation_pool.notify_final_cs_periods(&vec![10; thread_count.into()]);
+        assert_eq!(operation_pool.len(), 1);
+        assert!(operation_pool.contains(&ops[2].id));
+        assert_eq!(operation_pool.storage.get_op_refs().len(), 1);
+    });
+}
+
+/// Check that a conflicting operation replaces the pooled one only if it pays the fee increment.
+#[test]
+fn test_replace_by_fee() {
+    let pool_config = PoolConfig {
+        replace_by_fee_min_increment: Amount::from_str("5").unwrap(),
+        ..Default::default()
+    };
+    let (execution_controller, _execution_receiver) = MockExecutionController::new_with_receiver();
+    let (pool_event_sender, mut pool_event_receiver) =
+        broadcast::channel(pool_config.broadcast_events_capacity);
+    let storage = Storage::create_root();
+    let mut pool = OperationPool::init(
+        pool_config,
+        &storage,
+        execution_controller,
+        PoolChannels {
+            pool_event_sender,
+            resource_status: Default::default(),
+        },
+    );
+    let add_operation = |pool: &mut OperationPool, op: &WrappedOperation| {
+        let mut ops_storage = storage.clone_without_refs();
+        ops_storage.store_operations(vec![op.clone()]);
+        pool.add_operations(ops_storage);
+    };
+
+    let sender_keypair = KeyPair::generate();
+    let recv_pub = KeyPair::generate().get_public_key();
+    let original = _get_transaction_with_addresses(10, 10, &sender_keypair, recv_pub);
+    // a different expire period does not conflict
+    let other = _get_transaction_with_addresses(11, 1, &sender_keypair, recv_pub);
+    let low_bump = _get_transaction_with_addresses(10, 12, &sender_keypair, recv_pub);
+    let high_bump = _get_transaction_with_addresses(10, 15, &sender_keypair, recv_pub);
+
+    add_operation(&mut pool, &original);
+    add_operation(&mut pool, &other);
+    add_operation(&mut pool, &low_bump);
+    assert!(pool.contains(&original.id));
+    assert!(pool.contains(&other.id));
+    assert!(!pool.contains(&low_bump.id));
+    let mut added = Vec::new();
+    while let Ok(event) = pool_event_receiver.try_recv() {
+        match event {
+            PoolEvent::OperationAdded { operation_id } => added.push(operation_id),
+            event => panic!("unexpected pool event {:?}", event),
+        }
+    }
+    assert_eq!(added, vec![original.id, other.id]);
+
+    add_operation(&mut pool, &high_bump);
+    assert!(!pool.contains(&original.id));
+    assert!(pool.contains(&other.id));
+    assert!(pool.contains(&high_bump.id));
+    assert_eq!(pool.storage.get_op_refs().len(), 2);
+    let mut replaced_ops = Vec::new();
+    let mut dropped_ops = Vec::new();
+    while let Ok(event) = pool_event_receiver.try_recv() {
+        match event {
+            PoolEvent::OperationReplaced {
+                replaced,
+                replacement,
+            } => replaced_ops.push((replaced, replacement)),
+            PoolEvent::OperationDropped {
+                operation_id,
+                reason,
+            } => dropped_ops.push((operation_id, reason)),
+            PoolEvent::OperationAdded { operation_id } => assert_eq!(operation_id, high_bump.id),
+            event => panic!("unexpected pool event {:?}", event),
+        }
+    }
+    assert_eq!(replaced_ops, vec![(original.id, high_bump.id)]);
+    assert_eq!(
+        dropped_ops,
+        vec![(
+            original.id,
+            OperationDropReason::Replaced {
+                replacement: high_bump.id
+            }
+        )]
+    );
+}
+
+/// Check the outcome reported for each operation added to the pool.
+#[test]
+fn test_insertion_outcomes() {
+    let pool_config = PoolConfig {
+        minimal_fee: Amount::from_str("5").unwrap(),
+        replace_by_fee_min_increment: Amount::from_str("5").unwrap(),
+        max_operations_per_sender: 2,
+        ..Default::default()
+    };
+    let thread_count = pool_config.thread_count;
+    operation_pool_test(pool_config, |mut operation_pool, storage| {
+        let add_operation = |pool: &mut OperationPool, op: &WrappedOperation| {
+            let mut ops_storage = storage.clone_without_refs();
+            ops_storage.store_operations(vec![op.clone()]);
+            let mut outcomes = pool.add_operations(ops_storage);
+            assert_eq!(outcomes.len(), 1);
+            outcomes.remove(&op.id).unwrap()
+        };
+        let sender_keypair = KeyPair::generate();
+        let recv_pub = KeyPair::generate().get_public_key();
+        let op = |expire_period, fee| {
+            _get_transaction_with_addresses(expire_period, fee, &sender_keypair, recv_pub)
+        };
+
+        assert_eq!(
+            add_operation(&mut operation_pool, &op(10, 1)),
+            OperationInsertionOutcome::FeeTooLow {
+                minimal_fee: Amount::from_str("5").unwrap()
+            }
+        );
+        let pooled = op(10, 10);
+        assert_eq!(
+            add_operation(&mut operation_pool, &pooled),
+            OperationInsertionOutcome::Added
+        );
+        assert_eq!(
+            add_operation(&mut operation_pool, &pooled),
+            OperationInsertionOutcome::AlreadyPooled
+        );
+        assert_eq!(
+            add_operation(&mut operation_pool, &op(10, 12)),
+            OperationInsertionOutcome::ReplacementFeeTooLow {
+                conflicting: pooled.id,
+                minimal_fee: Amount::from_str("15").unwrap()
+            }
+        );
+        assert_eq!(
+            add_operation(&mut operation_pool, &op(11, 20)),
+            OperationInsertionOutcome::Added
+        );
+        // the sender already has 2 operations paying a better fee
+        let worst = op(12, 8);
+        assert_eq!(
+            add_operation(&mut operation_pool, &worst),
+            OperationInsertionOutcome::SenderLimit {
+                max_operations_per_sender: 2
+            }
+        );
+        assert!(!operation_pool.contains(&worst.id));
+        assert_eq!(operation_pool.len(), 2);
+
+        operation_pool.notify_final_cs_periods(&vec![20; thread_count.into()]);
+        assert_eq!(
+            add_operation(&mut operation_pool, &op(15, 100)),
+            OperationInsertionOutcome::Expired
+        );
+    });
+
+    let pool_config = PoolConfig {
+        max_operation_pool_size_per_thread: 1,
+        ..Default::default()
+    };
+    operation_pool_test(pool_config, |mut operation_pool, mut storage| {
+        // both operations are in the thread of the sender
+        let sender_keypair = KeyPair::generate();
+        let recv_pub = KeyPair::generate().get_public_key();
+        let best = _get_transaction_with_addresses(10, 20, &sender_keypair, recv_pub);
+        let evicted = _get_transaction_with_addresses(11, 10, &sender_keypair, recv_pub);
+        storage.store_operations(vec![best.clone(), evicted.clone()]);
+        let outcomes = operation_pool.add_operations(storage);
+        assert_eq!(outcomes.len(), 2);
+        assert_eq!(outcomes[&best.id], OperationInsertionOutcome::Added);
+        assert_eq!(outcomes[&evicted.id], OperationInsertionOutcome::PoolFull);
+    });
+}
+
+#[test]
+fn test_operation_statuses() {
+    let pool_config = PoolConfig {
+        replace_by_fee_min_increment: Amount::from_str("5").unwrap(),
+        max_operation_status_count: 3,
+        ..Default::default()
+    };
+    let thread_count = pool_config.thread_count as usize;
+    let (execution_controller, _execution_receiver) = MockExecutionController::new_with_receiver();
+    let storage = Storage::create_root();
+    let mut pool = OperationPool::init(
+        pool_config.clone(),
+        &storage,
+        execution_controller,
+        pool_channels(&pool_config),
+    );
+    let add_operation = |pool: &mut OperationPool, op: &WrappedOperation| {
+        let mut ops_storage = storage.clone_without_refs();
+        ops_storage.store_operations(vec![op.clone()]);
+        pool.add_operations(ops_storage);
+    };
+    let status =
+        |pool: &OperationPool, op: &WrappedOperation| pool.get_operation_statuses(&[op.id])[0];
+
+    let sender_keypair = KeyPair::generate();
+    let recv_pub = KeyPair::generate().get_public_key();
+    let original = _get_transaction_with_addresses(10, 10, &sender_keypair, recv_pub);
+    let replacement = _get_transaction_with_addresses(10, 15, &sender_keypair, recv_pub);
+    let other = _get_transaction_with_addresses(11, 1, &sender_keypair, recv_pub);
+    let late = _get_transaction_with_addresses(20, 1, &sender_keypair, recv_pub);
+    assert_eq!(status(&pool, &original), OperationStatus::Unknown);
+
+    add_operation(&mut pool, &original);
+    add_operation(&mut pool, &other);
+    assert_eq!(status(&pool, &original), OperationStatus::Pending);
+    add_operation(&mut pool, &replacement);
+    assert_eq!(
+        status(&pool, &original),
+        OperationStatus::Dropped {
+            reason: OperationDropReason::Replaced {
+                replacement: replacement.id
+            }
+        }
+    );
+    assert_eq!(status(&pool, &replacement), OperationStatus::Pending);
+
+    // inclusion in an active block, then finality
+    let block_id = BlockId(Hash::compute_from("block".as_bytes()));
+    let slot = Slot::new(
+        5,
+        replacement
+            .creator_address
+            .get_thread(pool_config.thread_count),
+    );
+    pool.notify_included_operations(vec![(block_id, slot, vec![replacement.id])]);
+    assert_eq!(
+        status(&pool, &replacement),
+        OperationStatus::Included { block_id, slot }
+    );
+    pool.notify_final_operations(vec![(block_id, slot, vec![replacement.id])]);
+
+    // final operations are not considered expired, pending ones are
+    pool.notify_final_cs_periods(&vec![11; thread_count]);
+    assert_eq!(
+        status(&pool, &replacement),
+        OperationStatus::Final { block_id, slot }
+    );
+    assert_eq!(
+        status(&pool, &other),
+        OperationStatus::Dropped {
+            reason: OperationDropReason::Expired
+        }
+    );
+
+    // the least recently updated status is forgotten once the index is full
+    add_operation(&mut pool, &late);
+    assert_eq!(status(&pool, &late), OperationStatus::Pending);
+    assert_eq!(status(&pool, &original), OperationStatus::Unknown);
+    assert_eq!(
+        status(&pool, &replacement),
+        OperationStatus::Final { block_id, slot }
+    );
+}
+
 fn get_transaction(expire_period: u64, fee: u64) -> WrappedOperation {
     let sender_keypair = KeyPair::generate();
 
@@ -73,6 +466,51 @@ fn get_transaction(expire_period: u64, fee: u64) -> WrappedOperation {
     Operation::new_wrapped(content, OperationSerializer::new(), &sender_keypair).unwrap()
 }
 
+/// Check the order in which each selection strategy considers the block candidates.
+/// The candidates are given by decreasing fee density, as sorted in the pool.
+#[test]
+fn test_order_candidates() {
+    let pool_config = PoolConfig::default();
+    let high_fee_op = get_transaction(20, 100);
+    let low_fee_op = get_transaction(10, 1);
+    let infos: Vec<OperationInfo> = [&high_fee_op, &low_fee_op]
+        .into_iter()
+        .map(|op| {
+            OperationInfo::from_op(
+                op,
+                pool_config.operation_validity_periods,
+                pool_config.roll_price,
+                pool_config.thread_count,
+            )
+        })
+        .collect();
+    let ordered_ids = |strategy: OperationSelectionStrategy, local: &PreHashSet<Address>| {
+        order_candidates(strategy, local, infos.iter().collect())
+            .into_iter()
+            .map(|op_info| op_info.id)
+            .collect::<Vec<_>>()
+    };
+    let no_local = PreHashSet::default();
+    let low_fee_local: PreHashSet<Address> = [low_fee_op.creator_address].into_iter().collect();
+
+    assert_eq!(
+        ordered_ids(OperationSelectionStrategy::GreedyByFee, &low_fee_local),
+        vec![high_fee_op.id, low_fee_op.id]
+    );
+    assert_eq!(
+        ordered_ids(OperationSelectionStrategy::OldestFirst, &no_local),
+        vec![low_fee_op.id, high_fee_op.id]
+    );
+    assert_eq!(
+        ordered_ids(OperationSelectionStrategy::LocalPriority, &no_local),
+        vec![high_fee_op.id, low_fee_op.id]
+    );
+    assert_eq!(
+        ordered_ids(OperationSelectionStrategy::LocalPriority, &low_fee_local),
+        vec![low_fee_op.id, high_fee_op.id]
+    );
+}
+
 /// TODO refactor old tests
 #[test]
 #[ignore]
@@ -80,7 +518,12 @@ fn test_pool() {
     let (execution_controller, _execution_receiver) = MockExecutionController::new_with_receiver();
     let pool_config = PoolConfig::default();
     let storage_base = Storage::create_root();
-    let mut pool = OperationPool::init(pool_config, &storage_base, execution_controller);
+    let mut pool = OperationPool::init(
+        pool_config.clone(),
+        &storage_base,
+        execution_controller,
+        pool_channels(&pool_config),
+    );
     // generate (id, transactions, range of validity) by threads
     let mut thread_tx_lists = vec![Vec::new(); pool_config.thread_count as usize];
     for i in 0..18 {
@@ -11,19 +11,28 @@
 //! Function: [`test_get_operations_overflow`]
 //! Same as the previous test with a low limit of size to check if
 //! configurations are taken into account.
+//!
+//! # Degraded read-only mode
+//! Function: [`test_degraded_pool_refuses_operations`]
+//! Operations are dropped while a resource alert is raised.
 
 use std::sync::mpsc::Receiver;
 use std::time::Duration;
 
+use crate::start_pool_controller;
 use crate::tests::tools::create_some_operations;
+use crate::tests::tools::pool_channels;
 use crate::tests::tools::pool_test;
+use massa_execution_exports::test_exports::MockExecutionController;
 use massa_execution_exports::test_exports::MockExecutionControllerMessage as ControllerMsg;
 use massa_models::address::Address;
+use massa_models::api::ResourceAlert;
 use massa_models::operation::OperationId;
 use massa_models::prehash::PreHashSet;
 use massa_models::slot::Slot;
 use massa_pool_exports::PoolConfig;
 use massa_signature::KeyPair;
+use massa_storage::Storage;
 
 /// # Test simple get operation
 /// Just try to get some operations stored in pool
@@ -48,7 +57,7 @@ use massa_signature::KeyPair;
 fn test_simple_get_operations() {
     let config = PoolConfig::default();
     pool_test(
-        config,
+        config.clone(),
         |mut pool_manager, mut pool_controller, execution_receiver, mut storage| {
             let keypair = KeyPair::generate();
             storage.store_operations(create_some_operations(10, &keypair, 1));
@@ -185,3 +194,44 @@ fn test_get_operations_overflow() {
         },
     );
 }
+
+/// # Test degraded read-only mode
+/// The pool does not grow while the resource monitor reports the node as degraded.
+///
+/// ## Expected result
+/// Operations added while a resource alert is raised are dropped,
+/// the same operations are added once the alert is cleared.
+#[test]
+fn test_degraded_pool_refuses_operations() {
+    let config = PoolConfig::default();
+    let storage = Storage::create_root();
+    let (execution_controller, _) = MockExecutionController::new_with_receiver();
+    let channels = pool_channels(&config);
+    let resource_status = channels.resource_status.clone();
+    let (mut pool_manager, mut pool_controller) =
+        start_pool_controller(config, &storage, execution_controller, channels);
+
+    let mut ops_storage = storage.clone_without_refs();
+    ops_storage.store_operations(create_some_operations(10, &KeyPair::generate(), 2));
+
+    resource_status.set_alerts(vec![ResourceAlert::HighMemoryUsage {
+        used: 2_000_000_000,
+        max_used: 1_000_000_000,
+    }]);
+    pool_controller.add_operations(ops_storage.clone());
+    std::thread::sleep(Duration::from_millis(100));
+    assert_eq!(pool_controller.get_operation_count(), 0);
+
+    resource_status.set_alerts(Vec::new());
+    pool_controller.add_operations(ops_storage);
+    let mut count = 0;
+    for _ in 0..100 {
+        count = pool_controller.get_operation_count();
+        if count == 10 {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    pool_manager.stop();
+    assert_eq!(count, 10);
+}
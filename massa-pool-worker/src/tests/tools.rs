@@ -14,11 +14,12 @@ use massa_models::{
     slot::Slot,
     wrapped::WrappedContent,
 };
-use massa_pool_exports::{PoolConfig, PoolController, PoolManager};
+use massa_pool_exports::{PoolChannels, PoolConfig, PoolController, PoolManager};
 use massa_signature::{KeyPair, PublicKey};
 use massa_storage::Storage;
 use std::str::FromStr;
 use std::sync::mpsc::Receiver;
+use tokio::sync::broadcast;
 
 /// Tooling to create a transaction with an expire periods
 /// TODO move tooling in a dedicated module
@@ -63,8 +64,9 @@ where
     let storage: Storage = Storage::create_root();
 
     let (execution_controller, execution_receiver) = MockExecutionController::new_with_receiver();
+    let channels = pool_channels(&cfg);
     let (pool_manager, pool_controller) =
-        start_pool_controller(cfg, &storage, execution_controller);
+        start_pool_controller(cfg, &storage, execution_controller, channels);
 
     test(pool_manager, pool_controller, execution_receiver, storage)
 }
@@ -75,12 +77,26 @@ where
 {
     let (execution_controller, _) = MockExecutionController::new_with_receiver();
     let storage = Storage::create_root();
+    let channels = pool_channels(&cfg);
     test(
-        OperationPool::init(cfg, &storage.clone_without_refs(), execution_controller),
+        OperationPool::init(
+            cfg,
+            &storage.clone_without_refs(),
+            execution_controller,
+            channels,
+        ),
         storage,
     )
 }
 
+/// Creates the pool channels, nobody listens to the events
+pub fn pool_channels(cfg: &PoolConfig) -> PoolChannels {
+    PoolChannels {
+        pool_event_sender: broadcast::channel(cfg.broadcast_events_capacity).0,
+        resource_status: Default::default(),
+    }
+}
+
 pub fn _get_transaction(expire_period: u64, fee: u64) -> WrappedOperation {
     let sender_keypair = KeyPair::generate();
 
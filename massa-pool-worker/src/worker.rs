@@ -6,7 +6,7 @@ use crate::controller_impl::{Command, PoolManagerImpl};
 use crate::operation_pool::OperationPool;
 use crate::{controller_impl::PoolControllerImpl, endorsement_pool::EndorsementPool};
 use massa_execution_exports::ExecutionController;
-use massa_pool_exports::PoolConfig;
+use massa_pool_exports::{PoolChannels, PoolConfig};
 use massa_pool_exports::{PoolController, PoolManager};
 use massa_storage::Storage;
 use parking_lot::RwLock;
@@ -57,6 +57,12 @@ impl EndorsementPoolThread {
                     .endorsement_pool
                     .write()
                     .notify_final_cs_periods(&final_cs_periods),
+                // operation statuses and fees are not tracked by the endorsement pool
+                Ok(
+                    Command::NotifyIncludedOperations(_)
+                    | Command::NotifyFinalOperations(_)
+                    | Command::SetMinimalFee(_),
+                ) => {}
             }
         }
     }
@@ -95,12 +101,22 @@ impl OperationPoolThread {
                 Err(RecvError) => break,
                 Ok(Command::Stop) => break,
                 Ok(Command::AddItems(operations)) => {
-                    self.operation_pool.write().add_operations(operations)
+                    self.operation_pool.write().add_operations(operations);
                 }
                 Ok(Command::NotifyFinalCsPeriods(final_cs_periods)) => self
                     .operation_pool
                     .write()
                     .notify_final_cs_periods(&final_cs_periods),
+                Ok(Command::NotifyIncludedOperations(included)) => self
+                    .operation_pool
+                    .write()
+                    .notify_included_operations(included),
+                Ok(Command::NotifyFinalOperations(finals)) => {
+                    self.operation_pool.write().notify_final_operations(finals)
+                }
+                Ok(Command::SetMinimalFee(minimal_fee)) => {
+                    self.operation_pool.write().set_minimal_fee(minimal_fee)
+                }
             };
         }
     }
@@ -112,22 +128,26 @@ pub fn start_pool_controller(
     config: PoolConfig,
     storage: &Storage,
     execution_controller: Box<dyn ExecutionController>,
+    channels: PoolChannels,
 ) -> (Box<dyn PoolManager>, Box<dyn PoolController>) {
     let (operations_input_sender, operations_input_receiver) = sync_channel(config.channels_size);
     let (endorsements_input_sender, endorsements_input_receiver) =
         sync_channel(config.channels_size);
+    let resource_status = channels.resource_status.clone();
     let operation_pool = Arc::new(RwLock::new(OperationPool::init(
-        config,
+        config.clone(),
         storage,
         execution_controller,
+        channels,
     )));
-    let endorsement_pool = Arc::new(RwLock::new(EndorsementPool::init(config, storage)));
+    let endorsement_pool = Arc::new(RwLock::new(EndorsementPool::init(config.clone(), storage)));
     let controller = PoolControllerImpl {
         _config: config,
         operation_pool: operation_pool.clone(),
         endorsement_pool: endorsement_pool.clone(),
         operations_input_sender: operations_input_sender.clone(),
         endorsements_input_sender: endorsements_input_sender.clone(),
+        resource_status,
     };
 
     let operations_thread_handle =
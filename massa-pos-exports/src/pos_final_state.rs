@@ -64,6 +64,12 @@ impl PoSFinalState {
         })
     }
 
+    /// Remove the cycle history and the deferred credits, keeping the initial rolls and seeds
+    pub fn reset(&mut self) {
+        self.cycle_history.clear();
+        self.deferred_credits = DeferredCredits::default();
+    }
+
     /// Create the initial cycle based off the initial rolls.
     ///
     /// This should be called only if bootstrap did not happen.
@@ -118,7 +118,7 @@ impl SelectorController for SelectorControllerImpl {
     fn get_address_selections(
         &self,
         address: &Address,
-        mut slot: Slot, /* starting slot */
+        start: Slot,
         end: Slot,
     ) -> PosResult<(Vec<Slot>, Vec<IndexedSlot>)> {
         let (_cache_cv, cache_lock) = &*self.cache;
@@ -126,7 +126,7 @@ impl SelectorController for SelectorControllerImpl {
         let cache = cache_guard.as_ref().map_err(|err| err.clone())?;
         let mut slot_producers = vec![];
         let mut slot_endorsers = vec![];
-        while slot < end {
+        for slot in Slot::iter_range(start..end, self.thread_count) {
             if let Some(selection) = cache
                 .get(slot.get_cycle(self.periods_per_cycle))
                 .and_then(|selections| selections.draws.get(&slot))
@@ -138,10 +138,6 @@ impl SelectorController for SelectorControllerImpl {
                     slot_endorsers.push(IndexedSlot { slot, index });
                 }
             }
-            slot = match slot.get_next_slot(self.thread_count) {
-                Ok(next_slot) => next_slot,
-                _ => break,
-            };
         }
         Ok((slot_producers, slot_endorsers))
     }
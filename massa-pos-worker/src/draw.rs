@@ -43,7 +43,7 @@ pub(crate) fn perform_draws(
     })?;
 
     // perform cycle draws
-    let mut cur_slot = Slot::new_first_of_cycle(cycle, cfg.periods_per_cycle).map_err(|err| {
+    let first_slot = Slot::new_first_of_cycle(cycle, cfg.periods_per_cycle).map_err(|err| {
         PosError::OverflowError(format!("start slot overflow in perform_draws: {}", err))
     })?;
     let last_slot = Slot::new_last_of_cycle(cycle, cfg.periods_per_cycle, cfg.thread_count)
@@ -59,7 +59,7 @@ pub(crate) fn perform_draws(
 
     let mut five_first_slots: Vec<(Slot, Selection)> = Vec::new();
     let mut count = 0;
-    loop {
+    for cur_slot in Slot::iter_range(first_slot..=last_slot, cfg.thread_count) {
         // draw block creator
         let producer = if cur_slot.period > 0 {
             addresses[dist.sample(&mut rng)]
@@ -83,13 +83,6 @@ pub(crate) fn perform_draws(
         }
         // add to draws
         cycle_draws.draws.insert(cur_slot, selection);
-
-        if cur_slot == last_slot {
-            break;
-        }
-        cur_slot = cur_slot.get_next_slot(cfg.thread_count).map_err(|err| {
-            PosError::OverflowError(format!("iteration slot overflow in perform_draws: {}", err))
-        })?;
     }
 
     debug!(
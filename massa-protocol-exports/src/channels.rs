@@ -1,8 +1,11 @@
-use massa_models::operation::Operation;
+use massa_models::{
+    operation::Operation, production_gate::ProductionGate, sync_progress::SyncProgressStatus,
+};
 use massa_network_exports::{NetworkCommandSender, NetworkEventReceiver};
+use massa_pool_exports::PoolEvent;
 use tokio::sync::mpsc;
 
-use crate::ProtocolCommand;
+use crate::{NetworkPartitionStatus, ProtocolCommand};
 
 /// Contains channels (senders) used by the protocol worker
 /// Contains (a) channel(s) to send info to api
@@ -12,6 +15,12 @@ pub struct ProtocolSenders {
     pub network_command_sender: NetworkCommandSender,
     /// Broadcast sender(channel) for new operations
     pub operation_sender: tokio::sync::broadcast::Sender<Operation>,
+    /// alerts of the network partition watchdog, read by the api
+    pub partition_status: NetworkPartitionStatus,
+    /// progress of the synchronization, read by the api
+    pub sync_progress: SyncProgressStatus,
+    /// number of connected nodes, read by the block factory
+    pub production_gate: ProductionGate,
 }
 
 /// Contains channels(receivers) used by the protocol worker
@@ -20,4 +29,6 @@ pub struct ProtocolReceivers {
     pub network_event_receiver: NetworkEventReceiver,
     /// protocol command receiver
     pub protocol_command_receiver: mpsc::Receiver<ProtocolCommand>,
+    /// pool events receiver
+    pub pool_event_receiver: tokio::sync::broadcast::Receiver<PoolEvent>,
 }
@@ -49,6 +49,8 @@ pub enum ProtocolError {
     ContainerInconsistencyError(String),
     /// Invalid operation error: {0}
     InvalidOperationError(String),
+    /// Invalid block error: {0}
+    InvalidBlockError(String),
 }
 
 #[derive(Debug)]
@@ -7,11 +7,13 @@
 #![warn(unused_crate_dependencies)]
 mod channels;
 mod error;
+mod partition_status;
 mod protocol_controller;
 mod settings;
 
 pub use channels::{ProtocolReceivers, ProtocolSenders};
 pub use error::ProtocolError;
+pub use partition_status::NetworkPartitionStatus;
 pub use protocol_controller::{
     BlocksResults, ProtocolCommand, ProtocolCommandSender, ProtocolManagementCommand,
     ProtocolManager,
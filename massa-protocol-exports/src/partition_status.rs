@@ -0,0 +1,24 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use massa_models::api::NetworkPartitionAlert;
+use std::sync::{Arc, PoisonError, RwLock};
+
+/// Alerts currently raised by the network partition watchdog of the protocol worker,
+/// shared with the API to be surfaced in the node status
+#[derive(Clone, Default)]
+pub struct NetworkPartitionStatus(Arc<RwLock<Vec<NetworkPartitionAlert>>>);
+
+impl NetworkPartitionStatus {
+    /// Get the alerts currently raised, empty if the node does not seem partitioned
+    pub fn get_alerts(&self) -> Vec<NetworkPartitionAlert> {
+        self.0
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Replace the alerts currently raised
+    pub fn set_alerts(&self, alerts: Vec<NetworkPartitionAlert>) {
+        *self.0.write().unwrap_or_else(PoisonError::into_inner) = alerts;
+    }
+}
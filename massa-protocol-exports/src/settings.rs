@@ -1,9 +1,12 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
+use std::collections::BTreeMap;
+
+use massa_models::{block::BlockId, slot::Slot, upgrade_schedule::UpgradeSchedule};
 use massa_time::MassaTime;
 use serde::Deserialize;
 /// Dynamic protocol configuration mix in static settings and constants configurations.
-#[derive(Debug, Deserialize, Clone, Copy)]
+#[derive(Debug, Deserialize, Clone)]
 pub struct ProtocolConfig {
     /// running threads count
     pub thread_count: u8,
@@ -59,4 +62,53 @@ pub struct ProtocolConfig {
     pub broadcast_enabled: bool,
     /// operation sender sender(channel) capacity
     pub broadcast_operations_capacity: usize,
+    /// checkpoints: nodes sending headers conflicting with them are banned
+    pub checkpoints: BTreeMap<Slot, BlockId>,
+    /// slots from which the protocol features are active: nodes sending headers using them before are banned
+    pub upgrade_schedule: UpgradeSchedule,
+    /// interval between two checks of the network partition watchdog
+    pub partition_check_interval: MassaTime,
+    /// a partition alert is raised when no block became final for this number of slots
+    /// while connected to at least `partition_min_connected_nodes` nodes, 0 to disable
+    pub partition_max_slots_without_final_block: u64,
+    /// minimal number of connected nodes for a stalled finality to be a sign of partition
+    pub partition_min_connected_nodes: usize,
+    /// a partition alert is raised when there are fewer outgoing connections than this
+    /// for `partition_low_out_connections_timeout`, 0 to disable
+    pub partition_min_out_connections: usize,
+    /// time during which the outgoing connections can be below `partition_min_out_connections`
+    pub partition_low_out_connections_timeout: MassaTime,
+    /// max number of archived blocks served or asked in a single block range request
+    pub archive_max_blocks_per_range: usize,
+    /// max number of archived blocks served to a node during `archive_serving_quota_period`
+    pub archive_max_served_blocks_per_node: usize,
+    /// period after which the archived blocks served to each node are counted again from zero
+    pub archive_serving_quota_period: MassaTime,
+    /// interval between two requests for the blocks preceding the oldest archived ones, 0 to disable
+    pub archive_backfill_interval: MassaTime,
+    /// interval at which the feedback on the blocks of the nodes is sent to the network, 0 to disable
+    pub peer_feedback_interval: MassaTime,
+    /// a node re-announcing a known header more than this after it was first seen is late
+    pub late_block_announcement_delay: MassaTime,
+    /// the operations submitted to this node are only announced to a random node we dialed
+    /// for this duration before being announced to all the nodes, 0 to disable
+    pub operation_stem_phase_duration: MassaTime,
+    /// the headers and endorsements of slots, and the operations of expire periods, more than this number
+    /// of periods before the current one are dropped, 0 to disable
+    pub relevance_past_periods: u64,
+    /// the headers and endorsements of slots, and the operations of expire periods, more than this number
+    /// of periods after the current one are dropped, 0 to disable
+    pub relevance_future_periods: u64,
+    /// a node gets a penalty every time it sent this number of items out of the relevance window
+    pub max_irrelevant_items_per_node: usize,
+    /// max number of blocks a node made us buffer while their slot is in the future or their parents are unknown,
+    /// the oldest ones are dropped beyond it and the node gets a penalty, 0 to disable
+    pub max_pending_blocks_per_node: usize,
+    /// max cache size for the headers whose signature and endorsements were already verified
+    pub max_known_headers_size: usize,
+    /// interval at which the progress of the synchronization is updated, 0 to disable
+    pub sync_progress_interval: MassaTime,
+    /// the node is synced while its latest final blocks lag at most this number of periods
+    /// behind the highest slot announced by the other nodes
+    pub max_synced_final_lag_periods: u64,
 }
@@ -10,8 +10,8 @@ use massa_models::{
     operation::{OperationId, WrappedOperation},
 };
 use massa_network_exports::{
-    AskForBlocksInfo, BlockInfoReply, NetworkCommand, NetworkCommandSender, NetworkEvent,
-    NetworkEventReceiver,
+    backpressure_channel, AskForBlocksInfo, BackpressureSender, BlockInfoReply, NetworkCommand,
+    NetworkCommandSender, NetworkEvent, NetworkEventReceiver,
 };
 use massa_time::MassaTime;
 use tokio::{sync::mpsc, time::sleep};
@@ -19,7 +19,7 @@ use tokio::{sync::mpsc, time::sleep};
 /// mock network controller
 pub struct MockNetworkController {
     network_command_rx: mpsc::Receiver<NetworkCommand>,
-    network_event_tx: mpsc::Sender<NetworkEvent>,
+    network_event_tx: BackpressureSender<NetworkEvent>,
 }
 
 impl MockNetworkController {
@@ -27,7 +27,8 @@ impl MockNetworkController {
     pub fn new() -> (Self, NetworkCommandSender, NetworkEventReceiver) {
         let (network_command_tx, network_command_rx) =
             mpsc::channel::<NetworkCommand>(CHANNEL_SIZE);
-        let (network_event_tx, network_event_rx) = mpsc::channel::<NetworkEvent>(CHANNEL_SIZE);
+        let (network_event_tx, network_event_rx) =
+            backpressure_channel::<NetworkEvent>("network_events", CHANNEL_SIZE);
         (
             MockNetworkController {
                 network_event_tx,
@@ -56,10 +57,26 @@ impl MockNetworkController {
         }
     }
 
-    /// new connection
+    /// new outgoing connection
     pub async fn new_connection(&mut self, new_node_id: NodeId) {
         self.network_event_tx
-            .send(NetworkEvent::NewConnection(new_node_id))
+            .send(NetworkEvent::NewConnection {
+                node: new_node_id,
+                observer: false,
+                outgoing: true,
+            })
+            .await
+            .expect("Couldn't connect node to protocol.");
+    }
+
+    /// new connection from an observer node
+    pub async fn new_observer_connection(&mut self, new_node_id: NodeId) {
+        self.network_event_tx
+            .send(NetworkEvent::NewConnection {
+                node: new_node_id,
+                observer: true,
+                outgoing: true,
+            })
             .await
             .expect("Couldn't connect node to protocol.");
     }
@@ -79,6 +96,7 @@ impl MockNetworkController {
             .send(NetworkEvent::ReceivedBlockHeader {
                 source_node_id,
                 header,
+                span: tracing::Span::none(),
             })
             .await
             .expect("Couldn't send header to protocol.");
@@ -4,15 +4,16 @@ use super::mock_network_controller::MockNetworkController;
 use crate::ProtocolConfig;
 use massa_hash::Hash;
 use massa_models::node::NodeId;
-use massa_models::operation::OperationSerializer;
 use massa_models::wrapped::WrappedContent;
 use massa_models::{
     address::Address,
     amount::Amount,
     block::{Block, BlockHeader, BlockHeaderSerializer, BlockId, BlockSerializer, WrappedBlock},
+    builder::OperationBuilder,
     endorsement::{Endorsement, EndorsementSerializerLW, WrappedEndorsement},
-    operation::{Operation, OperationType, WrappedOperation},
+    operation::{OperationType, WrappedOperation},
     slot::Slot,
+    upgrade_schedule::UpgradeSchedule,
 };
 use massa_network_exports::{AskForBlocksInfo, NetworkCommand};
 use massa_signature::KeyPair;
@@ -65,6 +66,7 @@ pub fn create_block(keypair: &KeyPair) -> WrappedBlock {
             ],
             operation_merkle_root: Hash::compute_from(&Vec::new()),
             endorsements: Vec::new(),
+            state_commitment: None,
         },
         BlockHeaderSerializer::new(),
         keypair,
@@ -106,6 +108,7 @@ pub fn create_block_with_operations(
             ],
             operation_merkle_root,
             endorsements: Vec::new(),
+            state_commitment: None,
         },
         BlockHeaderSerializer::new(),
         keypair,
@@ -143,6 +146,7 @@ pub fn create_block_with_endorsements(
             ],
             operation_merkle_root: Hash::compute_from(&Vec::new()),
             endorsements,
+            state_commitment: None,
         },
         BlockHeaderSerializer::new(),
         keypair,
@@ -184,12 +188,10 @@ pub fn create_operation_with_expire_period(
         recipient_address: Address::from_public_key(&recv_keypair.get_public_key()),
         amount: Amount::default(),
     };
-    let content = Operation {
-        fee: Amount::default(),
-        op,
-        expire_period,
-    };
-    Operation::new_wrapped(content, OperationSerializer::new(), keypair).unwrap()
+    OperationBuilder::new(op)
+        .expire_period(expire_period)
+        .build(keypair)
+        .unwrap()
 }
 
 lazy_static::lazy_static! {
@@ -226,6 +228,27 @@ pub fn create_protocol_config() -> ProtocolConfig {
         max_endorsements_propagation_time: MassaTime::from_millis(60000),
         broadcast_enabled: false,
         broadcast_operations_capacity: 128,
+        checkpoints: Default::default(),
+        upgrade_schedule: UpgradeSchedule::all_active(),
+        partition_check_interval: MassaTime::from_millis(1000),
+        partition_max_slots_without_final_block: 0,
+        partition_min_connected_nodes: 1,
+        partition_min_out_connections: 0,
+        partition_low_out_connections_timeout: MassaTime::from_millis(10000),
+        archive_max_blocks_per_range: 64,
+        archive_max_served_blocks_per_node: 256,
+        archive_serving_quota_period: MassaTime::from_millis(60000),
+        archive_backfill_interval: MassaTime::from_millis(1000),
+        peer_feedback_interval: MassaTime::from_millis(0),
+        late_block_announcement_delay: MassaTime::from_millis(2000),
+        operation_stem_phase_duration: MassaTime::from_millis(0),
+        relevance_past_periods: 0,
+        relevance_future_periods: 0,
+        max_irrelevant_items_per_node: 10,
+        max_pending_blocks_per_node: 0,
+        max_known_headers_size: 100,
+        sync_progress_interval: MassaTime::from_millis(0),
+        max_synced_final_lag_periods: 8,
     }
 }
 
@@ -272,7 +295,7 @@ pub async fn assert_banned_nodes(
         tokio::select! {
             msg = network_controller
                    .wait_command(2000.into(), |cmd| match cmd {
-                       NetworkCommand::NodeBanByIds(node) => Some(node),
+                       NetworkCommand::NodeBanByIds(node, _) => Some(node),
                        _ => None,
                    })
              =>  {
@@ -0,0 +1,302 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! History exchange between archival nodes:
+//! * serving: the final blocks, or only their headers, of a slot range asked by a peer are read from the archive,
+//!   at most `archive_max_blocks_per_range` per request and `archive_max_served_blocks_per_node` per node
+//!   during each `archive_serving_quota_period`,
+//! * backfill: every `archive_backfill_interval` a peer is asked for the blocks preceding the oldest archived ones.
+//!
+//! Blocks of the same thread that are both final are linked through their parent in that thread,
+//! so the block preceding the oldest archived block of each thread is known by its id.
+//! A received block is archived only if it is one of these expected blocks, with a valid signature
+//! and the operations its header commits to, so that a peer can not make us archive anything else.
+
+use massa_models::{
+    block::{ArchivedBlock, BlockId, WrappedHeader},
+    node::NodeId,
+    operation::WrappedOperation,
+    prehash::PreHashMap,
+    slot::Slot,
+};
+use massa_protocol_exports::{ProtocolConfig, ProtocolError};
+use massa_storage::archive::Archive;
+use std::collections::{HashMap, HashSet};
+use tokio::time::Instant;
+use tracing::warn;
+
+/// Range asked to a peer for backfill
+struct BackfillRequest {
+    node: NodeId,
+    start: Slot,
+    end: Slot,
+    instant: Instant,
+}
+
+/// State of the history exchange of an archival node
+pub(crate) struct ArchiveSync {
+    archive: Archive,
+    /// blocks and headers served to each node since `quota_period_start`
+    served_blocks: HashMap<NodeId, usize>,
+    /// start of the current serving quota period
+    quota_period_start: Instant,
+    /// blocks to backfill, mapped to the slot before which they are searched.
+    /// None until initialized from the archive
+    expected_blocks: Option<PreHashMap<BlockId, Slot>>,
+    /// range currently asked for backfill
+    pending_request: Option<BackfillRequest>,
+    /// nodes that did not send any expected block, not asked again until all nodes are
+    unhelpful_nodes: HashSet<NodeId>,
+}
+
+impl ArchiveSync {
+    /// Create the history exchange state of the given archive
+    pub fn new(archive: Archive) -> Self {
+        ArchiveSync {
+            archive,
+            served_blocks: Default::default(),
+            quota_period_start: Instant::now(),
+            expected_blocks: None,
+            pending_request: None,
+            unhelpful_nodes: Default::default(),
+        }
+    }
+
+    /// Get the blocks to send to `node` that asked for the slot range from `start` (included) to `end` (excluded),
+    /// within the serving quotas
+    pub fn get_blocks_to_serve(
+        &mut self,
+        config: &ProtocolConfig,
+        node: NodeId,
+        start: Slot,
+        end: Slot,
+    ) -> Vec<ArchivedBlock> {
+        let count = self.serving_quota(config, node);
+        if count == 0 {
+            return Vec::new();
+        }
+        match self.archive.get_blocks(start, end, count) {
+            Ok(blocks) => {
+                *self.served_blocks.entry(node).or_default() += blocks.len();
+                blocks
+            }
+            Err(err) => {
+                warn!(
+                    "could not read the archived blocks asked by {}: {}",
+                    node, err
+                );
+                Vec::new()
+            }
+        }
+    }
+
+    /// Get the headers to send to `node` that asked for the slot range from `start` (included) to `end` (excluded),
+    /// within the serving quotas. The headers of the trimmed blocks are served too.
+    pub fn get_headers_to_serve(
+        &mut self,
+        config: &ProtocolConfig,
+        node: NodeId,
+        start: Slot,
+        end: Slot,
+    ) -> Vec<WrappedHeader> {
+        let count = self.serving_quota(config, node);
+        if count == 0 {
+            return Vec::new();
+        }
+        match self.archive.get_headers(start, end, count) {
+            Ok(headers) => {
+                *self.served_blocks.entry(node).or_default() += headers.len();
+                headers
+            }
+            Err(err) => {
+                warn!(
+                    "could not read the archived headers asked by {}: {}",
+                    node, err
+                );
+                Vec::new()
+            }
+        }
+    }
+
+    /// Number of blocks or headers that can still be served to `node` in one range
+    fn serving_quota(&mut self, config: &ProtocolConfig, node: NodeId) -> usize {
+        if self.quota_period_start.elapsed() >= config.archive_serving_quota_period.to_duration() {
+            self.served_blocks.clear();
+            self.quota_period_start = Instant::now();
+        }
+        let served = self.served_blocks.get(&node).copied().unwrap_or_default();
+        config.archive_max_blocks_per_range.min(
+            config
+                .archive_max_served_blocks_per_node
+                .saturating_sub(served),
+        )
+    }
+
+    /// Forget the state of a node that disconnected
+    pub fn on_node_disconnected(&mut self, node: &NodeId) {
+        self.unhelpful_nodes.remove(node);
+        if matches!(&self.pending_request, Some(request) if &request.node == node) {
+            self.pending_request = None;
+        }
+    }
+
+    /// Find the blocks preceding the oldest archived block of each thread
+    fn init_expected_blocks(&self, config: &ProtocolConfig) -> PreHashMap<BlockId, Slot> {
+        let mut expected_blocks = PreHashMap::default();
+        let mut seen_threads = HashSet::new();
+        let mut start = match self.archive.get_first_slot() {
+            Ok(Some(slot)) => slot,
+            Ok(None) => return expected_blocks,
+            Err(err) => {
+                warn!("could not read the oldest archived block: {}", err);
+                return expected_blocks;
+            }
+        };
+        let page_size = config.archive_max_blocks_per_range.max(1);
+        while seen_threads.len() < config.thread_count as usize {
+            let blocks = match self
+                .archive
+                .get_blocks(start, Slot::new(u64::MAX, 0), page_size)
+            {
+                Ok(blocks) => blocks,
+                Err(err) => {
+                    warn!("could not read the oldest archived blocks: {}", err);
+                    break;
+                }
+            };
+            for block in &blocks {
+                let slot = block.header.content.slot;
+                if seen_threads.insert(slot.thread) {
+                    // genesis blocks have no parents, the history of their thread is complete
+                    if let Some(parent) = block.header.content.parents.get(slot.thread as usize) {
+                        expected_blocks.insert(*parent, slot);
+                    }
+                }
+            }
+            match blocks
+                .last()
+                .map(|block| block.header.content.slot.get_next_slot(config.thread_count))
+            {
+                Some(Ok(next_slot)) if blocks.len() == page_size => start = next_slot,
+                _ => break,
+            }
+        }
+        expected_blocks
+    }
+
+    /// Get the next range to ask for backfill and the node to ask it to,
+    /// none if a request is pending, the history is complete or no node can be asked
+    pub fn next_backfill_request(
+        &mut self,
+        config: &ProtocolConfig,
+        active_nodes: impl Iterator<Item = NodeId>,
+    ) -> Option<(NodeId, Slot, Slot)> {
+        if let Some(request) = &self.pending_request {
+            if request.instant.elapsed() < config.archive_backfill_interval.to_duration() {
+                return None;
+            }
+            // the node did not answer in time
+            self.unhelpful_nodes.insert(request.node);
+            self.pending_request = None;
+        }
+        // the archive may have been empty, or its history complete, at the previous initialization
+        if self
+            .expected_blocks
+            .as_ref()
+            .map_or(true, |expected_blocks| expected_blocks.is_empty())
+        {
+            self.expected_blocks = Some(self.init_expected_blocks(config));
+        }
+        // ask for the range just below the most recent expected block
+        let end = *self.expected_blocks.as_ref()?.values().max()?;
+        let periods = (config.archive_max_blocks_per_range / config.thread_count as usize).max(1);
+        let start = Slot::new(end.period.saturating_sub(periods as u64), 0);
+
+        let mut active_nodes = active_nodes.peekable();
+        active_nodes.peek()?;
+        let node = match active_nodes.find(|node| !self.unhelpful_nodes.contains(node)) {
+            Some(node) => node,
+            None => {
+                // every node was asked, try them all again at the next interval
+                self.unhelpful_nodes.clear();
+                return None;
+            }
+        };
+        self.pending_request = Some(BackfillRequest {
+            node,
+            start,
+            end,
+            instant: Instant::now(),
+        });
+        Some((node, start, end))
+    }
+
+    /// Archive the expected blocks among the ones sent by `node` for backfill.
+    ///
+    /// Returns the number of archived blocks, or an error if the node sent invalid blocks.
+    pub fn on_block_range(
+        &mut self,
+        config: &ProtocolConfig,
+        node: NodeId,
+        mut blocks: Vec<ArchivedBlock>,
+    ) -> Result<usize, ProtocolError> {
+        let request = match self.pending_request.take() {
+            Some(request) if request.node == node => request,
+            other => {
+                // not asked, or too late
+                self.pending_request = other;
+                return Ok(0);
+            }
+        };
+        for block in &blocks {
+            let slot = block.header.content.slot;
+            if slot < request.start || slot >= request.end {
+                return Err(ProtocolError::InvalidBlockError(format!(
+                    "block {} at slot {} is out of the asked range",
+                    block.header.id, slot
+                )));
+            }
+            if !block.check_operations() {
+                return Err(ProtocolError::InvalidBlockError(format!(
+                    "the operations of block {} do not match its header",
+                    block.header.id
+                )));
+            }
+            block.header.verify_signature()?;
+            WrappedOperation::verify_signatures_batch(&block.operations)?;
+        }
+
+        // follow the chain of each thread from the newest blocks
+        let truncated = blocks.len() >= config.archive_max_blocks_per_range;
+        blocks.sort_unstable_by_key(|block| std::cmp::Reverse(block.header.content.slot));
+        let expected_blocks = self.expected_blocks.get_or_insert_with(Default::default);
+        let mut archived = 0;
+        for block in blocks {
+            if expected_blocks.remove(&block.header.id).is_none() {
+                continue;
+            }
+            let slot = block.header.content.slot;
+            if let Some(parent) = block.header.content.parents.get(slot.thread as usize) {
+                expected_blocks.insert(*parent, slot);
+            }
+            if let Err(err) = self.archive.store_block(&block) {
+                warn!("could not archive block {}: {}", block.header.id, err);
+                // it will be asked again
+                expected_blocks.insert(block.header.id, request.end);
+                break;
+            }
+            archived += 1;
+        }
+        if archived > 0 && !truncated {
+            // the whole range was sent by a node keeping it: the remaining expected blocks are older than its start
+            for bound in expected_blocks.values_mut() {
+                if *bound > request.start && *bound <= request.end {
+                    *bound = request.start;
+                }
+            }
+        }
+        if archived == 0 {
+            self.unhelpful_nodes.insert(node);
+        }
+        Ok(archived)
+    }
+}
@@ -293,3 +293,103 @@ impl<K: PreHashed + std::hash::Hash + Clone + Copy + PartialEq + Eq, V> LinearHa
         });
     }
 }
+
+/// Structure holding a finite capacity cache map that deletes the least recently used item when full.
+/// Reading an item with `get` makes it the most recently used.
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub struct LruHashCacheMap<K: PreHashed + std::hash::Hash + Clone + Copy + PartialEq + Eq, V> {
+    /// Cache capacity
+    capacity: usize,
+    /// Container, with the generation of the last use of each item
+    container: PreHashMap<K, (V, u64)>,
+    /// Queue of the uses, the ones older than the last use of their item are skipped
+    queue: VecDeque<(K, u64)>,
+    /// Generation of the next use
+    generation: u64,
+}
+
+#[allow(dead_code)]
+impl<K: PreHashed + std::hash::Hash + Clone + Copy + PartialEq + Eq, V> LruHashCacheMap<K, V> {
+    /// Create a new cache instance
+    pub fn new(capacity: usize) -> Self {
+        LruHashCacheMap {
+            capacity,
+            container: PreHashMap::with_capacity(capacity.saturating_add(1)),
+            queue: VecDeque::with_capacity(capacity.saturating_add(1)),
+            generation: 0,
+        }
+    }
+
+    /// Number of items in cache
+    pub fn len(&self) -> usize {
+        self.container.len()
+    }
+
+    /// Check if the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.container.is_empty()
+    }
+
+    /// Check if a key is present in the cache, without refreshing it
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.container.contains_key(key)
+    }
+
+    /// Get an immutable reference to an element and mark it as the most recently used, `None` if not found
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        let generation = self.generation;
+        let (_, last_use) = self.container.get_mut(key)?;
+        *last_use = generation;
+        self.generation += 1;
+        self.queue.push_back((*key, generation));
+        if self.queue.len() > self.capacity.saturating_mul(2) {
+            // too many outdated uses: only keep the last use of each item
+            let container = &self.container;
+            self.queue
+                .retain(|(k, g)| container.get(k).map_or(false, |(_, last)| last == g));
+        }
+        self.container.get(key).map(|(value, _)| value)
+    }
+
+    /// Remove an item, returning it if it was present. Its uses left in the queue are skipped when pruning.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.container.remove(key).map(|(value, _)| value)
+    }
+
+    /// Clear the cache
+    pub fn clear(&mut self) {
+        self.container.clear();
+        self.queue.clear();
+    }
+
+    /// Tries to insert a new item. Does nothing and returns `false` if the item was already present.
+    pub fn insert(&mut self, key: K, value: V) -> bool {
+        if self.capacity == 0 {
+            return true;
+        }
+
+        if self
+            .container
+            .try_insert(key, (value, self.generation))
+            .is_err()
+        {
+            // item was already present
+            return false;
+        }
+
+        // add to queue
+        self.queue.push_back((key, self.generation));
+        self.generation += 1;
+
+        // prune container
+        while self.container.len() > self.capacity {
+            let (k, g) = self.queue.pop_front().unwrap();
+            if self.container.get(&k).map_or(false, |(_, last)| *last == g) {
+                self.container.remove(&k);
+            }
+        }
+
+        true
+    }
+}
@@ -14,11 +14,17 @@
 pub mod protocol_worker;
 pub mod worker_operations_impl;
 pub use protocol_worker::start_protocol_controller;
+mod archive_sync;
 mod cache;
 mod checked_operations;
 mod node_info;
+mod operation_stem;
+mod partition_watchdog;
+mod peer_feedback;
 mod protocol_network;
+mod relevance;
 mod sig_verifier;
+mod sync_progress;
 
 #[cfg(test)]
 pub mod tests;
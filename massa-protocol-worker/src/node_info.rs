@@ -8,8 +8,9 @@
 
 use massa_models::operation::OperationPrefixId;
 use massa_models::prehash::{CapacityAllocator, PreHashMap};
-use massa_models::{block::BlockId, endorsement::EndorsementId};
+use massa_models::{block::BlockId, endorsement::EndorsementId, slot::Slot};
 use massa_protocol_exports::ProtocolConfig;
+use std::collections::VecDeque;
 use tokio::time::Instant;
 
 use crate::cache::LinearHashCacheSet;
@@ -25,6 +26,15 @@ pub(crate) struct NodeInfo {
     pub asked_blocks: PreHashMap<BlockId, Instant>,
     /// Instant when the node was added
     pub connection_instant: Instant,
+    /// The node announced itself as an observer: it does not produce blocks,
+    /// other nodes are preferred when asking for blocks
+    pub observer: bool,
+    /// We dialed the node: only such nodes relay the stem phase of our operations
+    pub outgoing: bool,
+    /// Number of items out of the relevance window the node sent since its last penalty
+    pub irrelevant_items: usize,
+    /// Blocks the node first sent us that could not be processed yet, with their slot, oldest first
+    pub pending_blocks: VecDeque<(BlockId, Slot)>,
     /// all known operations (prefix-based)
     known_operations: LinearHashCacheSet<OperationPrefixId>,
     /// all known endorsements
@@ -33,11 +43,15 @@ pub(crate) struct NodeInfo {
 
 impl NodeInfo {
     /// Creates empty node info
-    pub fn new(pool_settings: &ProtocolConfig) -> NodeInfo {
+    pub fn new(pool_settings: &ProtocolConfig, observer: bool, outgoing: bool) -> NodeInfo {
         NodeInfo {
             known_blocks: PreHashMap::with_capacity(pool_settings.max_node_known_blocks_size),
             asked_blocks: Default::default(),
             connection_instant: Instant::now(),
+            observer,
+            outgoing,
+            irrelevant_items: 0,
+            pending_blocks: VecDeque::new(),
             known_operations: LinearHashCacheSet::new(pool_settings.max_node_known_ops_size),
             known_endorsements: LinearHashCacheSet::new(
                 pool_settings.max_node_known_endorsements_size,
@@ -45,6 +59,38 @@ impl NodeInfo {
         }
     }
 
+    /// Note that the node first sent us a block that cannot be processed yet.
+    ///
+    /// Returns the oldest pending blocks of the node beyond `max_pending_blocks`, to be dropped.
+    /// The pending blocks are only checked again beyond the quota, with `still_pending` telling for each one
+    /// whether it still cannot be processed: the other ones are forgotten without counting in the quota.
+    pub fn note_pending_block<F>(
+        &mut self,
+        block_id: BlockId,
+        slot: Slot,
+        max_pending_blocks: usize,
+        still_pending: F,
+    ) -> Vec<BlockId>
+    where
+        F: FnOnce(&[(BlockId, Slot)]) -> Vec<bool>,
+    {
+        self.pending_blocks.push_back((block_id, slot));
+        if self.pending_blocks.len() <= max_pending_blocks {
+            return Vec::new();
+        }
+        let pending = still_pending(self.pending_blocks.make_contiguous());
+        let mut index = 0;
+        self.pending_blocks.retain(|_| {
+            index += 1;
+            pending.get(index - 1).copied().unwrap_or(true)
+        });
+        let excess = self.pending_blocks.len().saturating_sub(max_pending_blocks);
+        self.pending_blocks
+            .drain(..excess)
+            .map(|(id, _)| id)
+            .collect()
+    }
+
     /// Get boolean if block knows about the block and when this information was got
     /// in a option if we don't know if that node knows that block or not
     pub fn get_known_block(&self, block_id: &BlockId) -> Option<&(bool, Instant)> {
@@ -0,0 +1,71 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Stem phase of the propagation of the operations submitted to this node, a light version of Dandelion.
+//!
+//! Announcing a locally submitted operation to all the nodes at once lets an observer connected to many nodes
+//! guess that we created it, as we are the first to announce it. Instead, the operation is first only announced
+//! to a single relay, picked at random among the nodes we dialed, which propagates it as any other operation.
+//! The operation is announced to all the nodes (fluffed) once `operation_stem_phase_duration` elapsed,
+//! or right away if its relay disconnects or if no node can relay it.
+
+use crate::node_info::NodeInfo;
+use massa_models::{node::NodeId, operation::OperationId, prehash::PreHashMap};
+use massa_protocol_exports::ProtocolConfig;
+use rand::seq::IteratorRandom;
+use std::collections::HashMap;
+use tokio::time::Instant;
+
+/// Operations in their stem phase
+#[derive(Default)]
+pub(crate) struct OperationStem {
+    /// relay and end of the stem phase of each operation
+    stems: PreHashMap<OperationId, (NodeId, Instant)>,
+}
+
+impl OperationStem {
+    /// Returns true if the stem phase is enabled in the configuration
+    pub fn is_enabled(config: &ProtocolConfig) -> bool {
+        config.operation_stem_phase_duration.to_millis() > 0
+    }
+
+    /// Pick a relay at random among the nodes we dialed, the observers excluded
+    pub fn choose_relay(active_nodes: &HashMap<NodeId, NodeInfo>) -> Option<NodeId> {
+        active_nodes
+            .iter()
+            .filter(|(_, node_info)| node_info.outgoing && !node_info.observer)
+            .map(|(node_id, _)| *node_id)
+            .choose(&mut rand::thread_rng())
+    }
+
+    /// Start the stem phase of `operations`, relayed by `relay` until `fluff_at`
+    pub fn start(&mut self, operations: &[OperationId], relay: NodeId, fluff_at: Instant) {
+        self.stems
+            .extend(operations.iter().map(|id| (*id, (relay, fluff_at))));
+    }
+
+    /// Take the operations whose stem phase ended at `now`
+    pub fn take_ended(&mut self, now: Instant) -> Vec<OperationId> {
+        self.take_where(|_, fluff_at| *fluff_at <= now)
+    }
+
+    /// Take the operations relayed by a node that disconnected, to fluff them without waiting
+    pub fn on_node_disconnected(&mut self, node_id: &NodeId) -> Vec<OperationId> {
+        self.take_where(|relay, _| relay == node_id)
+    }
+
+    fn take_where<F>(&mut self, predicate: F) -> Vec<OperationId>
+    where
+        F: Fn(&NodeId, &Instant) -> bool,
+    {
+        let mut taken = Vec::new();
+        self.stems.retain(|id, (relay, fluff_at)| {
+            if predicate(relay, fluff_at) {
+                taken.push(*id);
+                false
+            } else {
+                true
+            }
+        });
+        taken
+    }
+}
@@ -0,0 +1,138 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Watchdog detecting that the node may be cut from the rest of the network, instead of quietly stalling:
+//! * no block became final for `partition_max_slots_without_final_block` slots while the node is connected
+//!   to at least `partition_min_connected_nodes` nodes: the nodes we are connected to are likely on
+//!   the same minority side of a partition,
+//! * the outgoing connections stayed below `partition_min_out_connections`
+//!   for `partition_low_out_connections_timeout`.
+//!
+//! The alerts currently raised are shared with the API and the notifier through `NetworkPartitionStatus`,
+//! the raised and cleared alerts are logged.
+
+use massa_models::{api::NetworkPartitionAlert, slot::Slot};
+use massa_protocol_exports::{NetworkPartitionStatus, ProtocolConfig};
+use massa_time::MassaTime;
+use std::mem::discriminant;
+
+/// Alerts that changed during a check of the watchdog
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct PartitionAlertChanges {
+    /// alerts that were not raised at the previous check
+    pub raised: Vec<NetworkPartitionAlert>,
+    /// alerts raised at the previous check that are not anymore
+    pub cleared: Vec<NetworkPartitionAlert>,
+}
+
+/// State of the network partition watchdog
+pub(crate) struct PartitionWatchdog {
+    /// latest final period of each thread, as seen at the previous check
+    latest_final_periods: Vec<u64>,
+    /// slot at which a block was last seen becoming final
+    last_finality_slot: Option<Slot>,
+    /// time since which the outgoing connections are below the threshold
+    low_out_connections_since: Option<MassaTime>,
+    /// alerts currently raised, shared with the API
+    status: NetworkPartitionStatus,
+}
+
+impl PartitionWatchdog {
+    /// Create a watchdog publishing its alerts in `status`
+    pub fn new(status: NetworkPartitionStatus) -> Self {
+        PartitionWatchdog {
+            latest_final_periods: Vec::new(),
+            last_finality_slot: None,
+            low_out_connections_since: None,
+            status,
+        }
+    }
+
+    /// Returns true if at least one of the checks is enabled in the configuration
+    pub fn is_enabled(config: &ProtocolConfig) -> bool {
+        config.partition_max_slots_without_final_block > 0
+            || config.partition_min_out_connections > 0
+    }
+
+    /// Update the watchdog with the latest observations and publish the alerts they raise.
+    ///
+    /// # Arguments
+    /// * `current_slot`: latest slot at `now`, none before genesis
+    /// * `now`: current time
+    /// * `latest_final_periods`: period of the latest final block of each thread
+    /// * `connected_nodes`: number of nodes we are connected to
+    /// * `out_connections`: number of outgoing connections
+    pub fn check(
+        &mut self,
+        config: &ProtocolConfig,
+        current_slot: Option<Slot>,
+        now: MassaTime,
+        latest_final_periods: Vec<u64>,
+        connected_nodes: usize,
+        out_connections: usize,
+    ) -> PartitionAlertChanges {
+        let mut alerts = Vec::new();
+
+        // finality: nothing is expected to become final before genesis
+        match current_slot {
+            Some(current_slot) => {
+                if latest_final_periods != self.latest_final_periods
+                    || self.last_finality_slot.is_none()
+                {
+                    self.latest_final_periods = latest_final_periods;
+                    self.last_finality_slot = Some(current_slot);
+                }
+                let slots = self.last_finality_slot.map_or(0, |last_finality_slot| {
+                    current_slot
+                        .slots_since(&last_finality_slot, config.thread_count)
+                        .unwrap_or(0)
+                });
+                if config.partition_max_slots_without_final_block > 0
+                    && slots >= config.partition_max_slots_without_final_block
+                    && connected_nodes >= config.partition_min_connected_nodes
+                {
+                    alerts.push(NetworkPartitionAlert::StalledFinality {
+                        slots,
+                        connected_nodes,
+                    });
+                }
+            }
+            None => {
+                self.latest_final_periods = latest_final_periods;
+                self.last_finality_slot = None;
+            }
+        }
+
+        // outgoing connections
+        if out_connections < config.partition_min_out_connections {
+            let since = *self.low_out_connections_since.get_or_insert(now);
+            if now.saturating_sub(since) >= config.partition_low_out_connections_timeout {
+                alerts.push(NetworkPartitionAlert::LowOutConnections {
+                    out_connections,
+                    since,
+                });
+            }
+        } else {
+            self.low_out_connections_since = None;
+        }
+
+        let previous_alerts = self.status.get_alerts();
+        let is_raised_in = |alert: &NetworkPartitionAlert, list: &[NetworkPartitionAlert]| {
+            list.iter()
+                .any(|other| discriminant(other) == discriminant(alert))
+        };
+        let changes = PartitionAlertChanges {
+            raised: alerts
+                .iter()
+                .filter(|alert| !is_raised_in(alert, &previous_alerts[..]))
+                .copied()
+                .collect(),
+            cleared: previous_alerts
+                .iter()
+                .filter(|alert| !is_raised_in(alert, &alerts[..]))
+                .copied()
+                .collect(),
+        };
+        self.status.set_alerts(alerts);
+        changes
+    }
+}
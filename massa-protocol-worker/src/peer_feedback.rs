@@ -0,0 +1,168 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Observations on the blocks of the nodes, fed back into the reputation of their peers by the network:
+//! * a node sending an invalid block gets `PeerFeedback::InvalidBlock`,
+//! * the first node announcing a header gets `PeerFeedback::FinalBlock` once the block became final,
+//! * a node re-announcing a header more than `late_block_announcement_delay` after it was first seen,
+//!   while it was already connected at that time, gets `PeerFeedback::LateAnnouncement`,
+//! * a node repeatedly sending items out of the relevance window gets `PeerFeedback::IrrelevantItems`,
+//! * a node making us buffer more blocks that cannot be processed yet than `max_pending_blocks_per_node`
+//!   gets `PeerFeedback::ExcessPendingBlocks`.
+//!
+//! The feedback is gathered here and sent to the network in batches every `peer_feedback_interval`.
+
+use massa_models::{
+    api::BlockGraphStatus, block::BlockId, node::NodeId, prehash::PreHashMap, slot::Slot,
+};
+use massa_network_exports::PeerFeedback;
+use massa_protocol_exports::ProtocolConfig;
+use std::collections::HashSet;
+use tokio::time::Instant;
+
+/// Header whose fate is not observed yet
+struct AnnouncedBlock {
+    /// slot of the block
+    slot: Slot,
+    /// when the header was first received
+    first_seen: Instant,
+    /// node that sent the header first
+    first_announcer: NodeId,
+    /// nodes already found late for this header
+    late_announcers: HashSet<NodeId>,
+}
+
+/// Gathers the feedback on the blocks of the nodes until it is sent to the network
+pub(crate) struct PeerFeedbackTracker {
+    /// no feedback is gathered if false
+    enabled: bool,
+    /// max number of headers tracked at the same time, the new headers are not tracked beyond
+    max_tracked_blocks: usize,
+    /// headers waiting for their block to become final or to be dropped
+    announced_blocks: PreHashMap<BlockId, AnnouncedBlock>,
+    /// feedback to send at the next flush
+    pending: Vec<(NodeId, PeerFeedback)>,
+}
+
+impl PeerFeedbackTracker {
+    /// Create a tracker, that gathers nothing if `peer_feedback_interval` is 0
+    pub fn new(config: &ProtocolConfig) -> Self {
+        PeerFeedbackTracker {
+            enabled: Self::is_enabled(config),
+            max_tracked_blocks: config.max_known_blocks_size,
+            announced_blocks: Default::default(),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Returns true if the feedback is enabled in the configuration
+    pub fn is_enabled(config: &ProtocolConfig) -> bool {
+        config.peer_feedback_interval.to_millis() > 0
+    }
+
+    /// Note that `node_id` sent an invalid block
+    pub fn note_invalid_block(&mut self, node_id: NodeId) {
+        if self.enabled {
+            self.pending.push((node_id, PeerFeedback::InvalidBlock));
+        }
+    }
+
+    /// Note that `node_id` repeatedly sent items out of the relevance window
+    pub fn note_irrelevant_items(&mut self, node_id: NodeId) {
+        if self.enabled {
+            self.pending.push((node_id, PeerFeedback::IrrelevantItems));
+        }
+    }
+
+    /// Note that `node_id` made us buffer too many blocks that cannot be processed yet
+    pub fn note_excess_pending_blocks(&mut self, node_id: NodeId) {
+        if self.enabled {
+            self.pending
+                .push((node_id, PeerFeedback::ExcessPendingBlocks));
+        }
+    }
+
+    /// Note that `node_id` is the first node that sent us the header of `block_id`
+    pub fn note_new_header(
+        &mut self,
+        block_id: BlockId,
+        slot: Slot,
+        node_id: NodeId,
+        now: Instant,
+    ) {
+        if !self.enabled || self.announced_blocks.len() >= self.max_tracked_blocks {
+            return;
+        }
+        self.announced_blocks
+            .entry(block_id)
+            .or_insert_with(|| AnnouncedBlock {
+                slot,
+                first_seen: now,
+                first_announcer: node_id,
+                late_announcers: HashSet::new(),
+            });
+    }
+
+    /// Note that `node_id`, connected since `connected_since`, sent us the already known header of `block_id`.
+    /// The node is late if it was connected when the header was first seen, and sends it more than
+    /// `late_delay` after.
+    pub fn note_known_header(
+        &mut self,
+        block_id: &BlockId,
+        node_id: NodeId,
+        connected_since: Instant,
+        now: Instant,
+        late_delay: std::time::Duration,
+    ) {
+        if !self.enabled {
+            return;
+        }
+        if let Some(block) = self.announced_blocks.get_mut(block_id) {
+            if node_id != block.first_announcer
+                && connected_since <= block.first_seen
+                && now.saturating_duration_since(block.first_seen) > late_delay
+                && block.late_announcers.insert(node_id)
+            {
+                self.pending.push((node_id, PeerFeedback::LateAnnouncement));
+            }
+        }
+    }
+
+    /// Stop tracking the headers at or before the latest final period of their thread.
+    /// The first announcers of the ones whose block became final get `PeerFeedback::FinalBlock`.
+    ///
+    /// # Arguments
+    /// * `latest_final_periods`: period of the latest final block of each thread
+    /// * `get_block_statuses`: statuses of the blocks in the graph
+    pub fn resolve_blocks<F>(&mut self, latest_final_periods: &[u64], get_block_statuses: F)
+    where
+        F: FnOnce(&[BlockId]) -> Vec<BlockGraphStatus>,
+    {
+        let ids: Vec<BlockId> = self
+            .announced_blocks
+            .iter()
+            .filter(|(_, block)| {
+                latest_final_periods
+                    .get(block.slot.thread as usize)
+                    .map_or(false, |period| block.slot.period <= *period)
+            })
+            .map(|(id, _)| *id)
+            .collect();
+        if ids.is_empty() {
+            return;
+        }
+        let statuses = get_block_statuses(&ids);
+        for (index, id) in ids.iter().enumerate() {
+            if let Some(block) = self.announced_blocks.remove(id) {
+                if statuses.get(index) == Some(&BlockGraphStatus::Final) {
+                    self.pending
+                        .push((block.first_announcer, PeerFeedback::FinalBlock));
+                }
+            }
+        }
+    }
+
+    /// Take the feedback gathered since the previous call
+    pub fn take_pending(&mut self) -> Vec<(NodeId, PeerFeedback)> {
+        std::mem::take(&mut self.pending)
+    }
+}
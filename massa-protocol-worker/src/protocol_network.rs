@@ -6,13 +6,12 @@ use std::collections::hash_map::Entry;
 
 use crate::node_info::NodeInfo;
 use crate::protocol_worker::ProtocolWorker;
-use massa_hash::{Hash, HASH_SIZE_BYTES};
 use massa_logging::massa_trace;
 use massa_models::{
     block::Block,
     block::{BlockId, BlockSerializer, WrappedHeader},
     node::NodeId,
-    operation::{OperationId, WrappedOperation},
+    operation::{compute_operations_hash, OperationId, WrappedOperation},
     prehash::{CapacityAllocator, PreHashSet},
     wrapped::{Id, Wrapped},
 };
@@ -35,6 +34,10 @@ static ENDORSEMENTS: &str = "protocol.protocol_worker.on_network_event.received_
 static OPS_BATCH: &str =
     "protocol.protocol_worker.on_network_event.received_operation_announcements";
 static ASKED_OPS: &str = "protocol.protocol_worker.on_network_event.receive_ask_for_operations";
+static ASKED_BLOCK_RANGE: &str = "protocol.protocol_worker.on_network_event.asked_for_block_range";
+static BLOCK_RANGE: &str = "protocol.protocol_worker.on_network_event.received_block_range";
+static ASKED_HEADER_RANGE: &str =
+    "protocol.protocol_worker.on_network_event.asked_for_header_range";
 
 impl ProtocolWorker {
     /// Manages network event
@@ -50,18 +53,38 @@ impl ProtocolWorker {
         op_timer: &mut Pin<&mut Sleep>,
     ) -> Result<(), ProtocolError> {
         match evt {
-            NetworkEvent::NewConnection(node_id) => {
-                info!("Connected to node {}", node_id);
-                massa_trace!(NEW_CONN, { "node": node_id });
+            NetworkEvent::NewConnection {
+                node: node_id,
+                observer,
+                outgoing,
+            } => {
+                if observer {
+                    info!("Connected to observer node {}", node_id);
+                } else {
+                    info!("Connected to node {}", node_id);
+                }
+                massa_trace!(NEW_CONN, { "node": node_id, "observer": observer, "outgoing": outgoing });
                 self.active_nodes
-                    .insert(node_id, NodeInfo::new(&self.config));
+                    .insert(node_id, NodeInfo::new(&self.config, observer, outgoing));
+                self.note_connected_node_count();
+                self.send_latest_headers(node_id).await?;
                 self.update_ask_block(block_ask_timer).await?;
             }
             NetworkEvent::ConnectionClosed(node_id) => {
                 massa_trace!(CONN_CLOSED, { "node": node_id });
+                if let Some(archive_sync) = self.archive_sync.as_mut() {
+                    archive_sync.on_node_disconnected(&node_id);
+                }
+                // the operations it was relaying in their stem phase are announced to everyone
+                let orphan_stems = self.operation_stem.on_node_disconnected(&node_id);
+                if !orphan_stems.is_empty() {
+                    self.note_operations_to_announce(&orphan_stems, op_timer)
+                        .await;
+                }
                 if self.active_nodes.remove(&node_id).is_some() {
                     // deletes all node info
                     info!("Connection closed with {}", node_id);
+                    self.note_connected_node_count();
                     if self.active_nodes.is_empty() {
                         // if no more active nodes, print
                         info!("Not connected to any peers.");
@@ -92,24 +115,57 @@ impl ProtocolWorker {
             NetworkEvent::ReceivedBlockHeader {
                 source_node_id,
                 header,
+                span,
             } => {
                 massa_trace!(BLOCK_HEADER, { "node": source_node_id, "header": header});
-                if let Some((block_id, is_new)) =
-                    self.note_header_from_node(&header, &source_node_id).await?
-                {
-                    if is_new {
-                        self.consensus_controller
-                            .register_block_header(block_id, header);
+                if let Some(window) = self.relevance_window()? && !window.contains(header.content.slot.period) {
+                    self.note_irrelevant_items(&source_node_id, 1);
+                    return Ok(());
+                }
+                match self.note_header_from_node(&header, &source_node_id).await {
+                    Ok(Some((block_id, is_new))) => {
+                        let now = Instant::now();
+                        if is_new {
+                            self.sync_progress.note_remote_slot(header.content.slot);
+                            self.peer_feedback.note_new_header(
+                                block_id,
+                                header.content.slot,
+                                source_node_id,
+                                now,
+                            );
+                            self.note_pending_block(&source_node_id, &header)?;
+                            span.in_scope(|| {
+                                self.consensus_controller
+                                    .register_block_header(block_id, header)
+                            });
+                            self.block_spans.insert(block_id, span);
+                        } else if let Some(node_info) = self.active_nodes.get(&source_node_id) {
+                            self.peer_feedback.note_known_header(
+                                &block_id,
+                                source_node_id,
+                                node_info.connection_instant,
+                                now,
+                                self.config.late_block_announcement_delay.to_duration(),
+                            );
+                        }
+                        self.update_ask_block(block_ask_timer).await?;
                     }
-                    self.update_ask_block(block_ask_timer).await?;
-                } else {
-                    warn!(
-                        "node {} sent us critically incorrect header, \
-                        which may be an attack attempt by the remote node \
-                        or a loss of sync between us and the remote node",
-                        source_node_id,
-                    );
-                    let _ = self.ban_node(&source_node_id).await;
+                    Ok(None) => {
+                        warn!(
+                            "node {} sent us critically incorrect header, \
+                            which may be an attack attempt by the remote node \
+                            or a loss of sync between us and the remote node",
+                            source_node_id,
+                        );
+                        self.peer_feedback.note_invalid_block(source_node_id);
+                        let _ = self.ban_node(&source_node_id).await;
+                    }
+                    Err(ProtocolError::InvalidBlockError(err)) => {
+                        warn!("node {} sent us an invalid header: {}", source_node_id, err);
+                        self.peer_feedback.note_invalid_block(source_node_id);
+                        let _ = self.ban_node(&source_node_id).await;
+                    }
+                    Err(err) => return Err(err),
                 }
             }
             NetworkEvent::ReceivedOperations { node, operations } => {
@@ -117,8 +173,19 @@ impl ProtocolWorker {
                 self.on_operations_received(node, operations, op_timer)
                     .await;
             }
-            NetworkEvent::ReceivedEndorsements { node, endorsements } => {
+            NetworkEvent::ReceivedEndorsements {
+                node,
+                mut endorsements,
+            } => {
                 massa_trace!(ENDORSEMENTS, { "node": node, "endorsements": endorsements});
+                if let Some(window) = self.relevance_window()? {
+                    let received = endorsements.len();
+                    endorsements
+                        .retain(|endorsement| window.contains(endorsement.content.slot.period));
+                    if endorsements.len() < received {
+                        self.note_irrelevant_items(&node, received - endorsements.len());
+                    }
+                }
                 if let Err(err) = self
                     .note_endorsements_from_node(endorsements, &node, true)
                     .await
@@ -148,6 +215,50 @@ impl ProtocolWorker {
                 self.on_asked_operations_received(node, operation_prefix_ids)
                     .await?;
             }
+            NetworkEvent::AskedForBlockRange { node, start, end } => {
+                massa_trace!(ASKED_BLOCK_RANGE, { "node": node, "start": start, "end": end });
+                // a node that is not archival answers with an empty range
+                let blocks = match self.archive_sync.as_mut() {
+                    Some(archive_sync) => {
+                        archive_sync.get_blocks_to_serve(&self.config, node, start, end)
+                    }
+                    None => Vec::new(),
+                };
+                self.network_command_sender
+                    .send_block_range(node, blocks)
+                    .await?;
+            }
+            NetworkEvent::ReceivedBlockRange { node, blocks } => {
+                massa_trace!(BLOCK_RANGE, { "node": node, "count": blocks.len() });
+                if let Some(archive_sync) = self.archive_sync.as_mut() {
+                    match archive_sync.on_block_range(&self.config, node, blocks) {
+                        Ok(archived) => {
+                            if archived > 0 {
+                                info!("archived {} older blocks sent by {}", archived, node);
+                            }
+                        }
+                        Err(err) => {
+                            warn!("node {} sent us invalid archived blocks: {}", node, err);
+                            let _ = self.ban_node(&node).await;
+                        }
+                    }
+                }
+            }
+            NetworkEvent::AskedForHeaderRange { node, start, end } => {
+                massa_trace!(ASKED_HEADER_RANGE, { "node": node, "start": start, "end": end });
+                // a node that is not archival answers with an empty range
+                let headers = match self.archive_sync.as_mut() {
+                    Some(archive_sync) => {
+                        archive_sync.get_headers_to_serve(&self.config, node, start, end)
+                    }
+                    None => Vec::new(),
+                };
+                self.network_command_sender
+                    .send_header_range(node, headers)
+                    .await?;
+            }
+            // the header ranges are only asked for by the trustless sync, before the protocol starts
+            NetworkEvent::ReceivedHeaderRange { .. } => {}
         }
         Ok(())
     }
@@ -159,6 +270,26 @@ impl ProtocolWorker {
     /// the missing operations in his storage with `AskForBlocksInfo::Operations`
     ///
     /// Forward the reply to the network.
+    /// Send the headers of our latest integrated blocks to a newly connected node.
+    ///
+    /// A node catching up after bootstrap learns the tip of the network this way,
+    /// and then asks for all the blocks missing between that tip and its own graph.
+    async fn send_latest_headers(&mut self, node_id: NodeId) -> Result<(), ProtocolError> {
+        let headers: Vec<WrappedHeader> =
+            self.latest_integrated_headers.values().cloned().collect();
+        for header in headers {
+            self.network_command_sender
+                .send_block_header(node_id, header)
+                .await
+                .map_err(|_| {
+                    ProtocolError::ChannelError(
+                        "send block header network command send failed".into(),
+                    )
+                })?;
+        }
+        Ok(())
+    }
+
     async fn on_asked_for_blocks_received(
         &mut self,
         from_node_id: NodeId,
@@ -231,9 +362,15 @@ impl ProtocolWorker {
         total
     }
 
+    /// Id of the span of the reception of the header of a block being retrieved, if any
+    fn block_span_id(&mut self, block_id: &BlockId) -> Option<tracing::Id> {
+        self.block_spans.get(block_id).and_then(|span| span.id())
+    }
+
     /// On block header received from a node.
     /// If the header is new, we propagate it to the consensus.
     /// We pass the state of `block_wishlist` to ask for information about the block.
+    #[tracing::instrument(level = "debug", skip_all, parent = self.block_span_id(&block_id), fields(node_id = %from_node_id, block_id = %block_id))]
     async fn on_block_header_received(
         &mut self,
         from_node_id: NodeId,
@@ -261,6 +398,7 @@ impl ProtocolWorker {
                 or a loss of sync between us and the remote node. Err = {}",
                 from_node_id, err
             );
+            self.peer_feedback.note_invalid_block(from_node_id);
             let _ = self.ban_node(&from_node_id).await;
             return Ok(());
         };
@@ -291,6 +429,7 @@ impl ProtocolWorker {
     /// # Result
     /// return an error if stopping asking block failed. The error should be forwarded at the
     /// root. todo: check if if make panic.
+    #[tracing::instrument(level = "debug", skip_all, parent = self.block_span_id(&block_id), fields(node_id = %from_node_id, block_id = %block_id))]
     async fn on_block_operation_list_received(
         &mut self,
         from_node_id: NodeId,
@@ -343,15 +482,8 @@ impl ProtocolWorker {
             return Ok(());
         }
 
-        let mut total_hash: Vec<u8> =
-            Vec::with_capacity(operation_ids.len().saturating_mul(HASH_SIZE_BYTES));
-        operation_ids.iter().for_each(|op_id| {
-            let op_hash = op_id.get_hash().into_bytes();
-            total_hash.extend(op_hash);
-        });
-
         // Check operation_list against expected operations hash from header.
-        if header.content.operation_merkle_root == Hash::compute_from(&total_hash) {
+        if header.content.operation_merkle_root == compute_operations_hash(&operation_ids) {
             // Add the ops of info.
             info.operation_ids = Some(operation_ids.clone());
             let known_operations = info.storage.claim_operation_refs(&operation_ids_set);
@@ -406,6 +538,7 @@ impl ProtocolWorker {
     /// - full operations serialized size overflow
     ///
     /// We received these operation because we asked for the missing operation
+    #[tracing::instrument(level = "debug", skip_all, parent = self.block_span_id(&block_id), fields(node_id = %from_node_id, block_id = %block_id))]
     async fn on_block_full_operations_received(
         &mut self,
         from_node_id: NodeId,
@@ -466,6 +599,7 @@ impl ProtocolWorker {
                 };
                 if full_op_size > self.config.max_serialized_operations_size_per_block {
                     warn!("Node id {} sent us full operations for block id {} but they exceed max size.", from_node_id, block_id);
+                    self.peer_feedback.note_invalid_block(from_node_id);
                     let _ = self.ban_node(&from_node_id).await;
                     self.block_wishlist.remove(&block_id);
                     self.consensus_controller
@@ -501,7 +635,7 @@ impl ProtocolWorker {
                         creator_address: header.creator_address,
                         id: block_id,
                         content: block,
-                        serialized_data: content_serialized,
+                        serialized_data: content_serialized.into(),
                     };
 
                     // create block storage (without parents)
@@ -518,6 +652,7 @@ impl ProtocolWorker {
                     // Send to consensus
                     self.consensus_controller
                         .register_block(block_id, slot, block_storage, false);
+                    self.block_spans.remove(&block_id);
                 }
             }
             Entry::Vacant(_) => {
@@ -1,18 +1,28 @@
 //! Copyright (c) 2022 MASSA LABS <info@massa.net>
 
-use crate::cache::{LinearHashCacheMap, LinearHashCacheSet};
+use crate::archive_sync::ArchiveSync;
+use crate::cache::{LinearHashCacheSet, LruHashCacheMap};
 use crate::checked_operations::CheckedOperations;
+use crate::operation_stem::OperationStem;
+use crate::partition_watchdog::PartitionWatchdog;
+use crate::peer_feedback::PeerFeedbackTracker;
+use crate::relevance::RelevanceWindow;
 use crate::sig_verifier::verify_sigs_batch;
+use crate::sync_progress::SyncProgressTracker;
 use crate::{node_info::NodeInfo, worker_operations_impl::OperationBatchBuffer};
 
 use massa_consensus_exports::ConsensusController;
 use massa_logging::massa_trace;
+use massa_metrics::PROTOCOL_METRICS;
 
 use massa_models::operation::Operation;
+use massa_models::production_gate::ProductionGate;
 use massa_models::slot::Slot;
-use massa_models::timeslots::get_block_slot_timestamp;
+use massa_models::slot_scheduler::SlotScheduler;
+use massa_models::sync_progress::SyncProgressStatus;
 use massa_models::wrapped::Id;
 use massa_models::{
+    api::BlockGraphStatus,
     block::{BlockId, WrappedHeader},
     endorsement::{EndorsementId, WrappedEndorsement},
     node::NodeId,
@@ -20,14 +30,16 @@ use massa_models::{
     operation::{OperationId, WrappedOperation},
     prehash::{CapacityAllocator, PreHashMap, PreHashSet},
 };
-use massa_network_exports::{AskForBlocksInfo, NetworkCommandSender, NetworkEventReceiver};
-use massa_pool_exports::PoolController;
+use massa_network_exports::{
+    AskForBlocksInfo, BanReason, NetworkCommandSender, NetworkEventReceiver,
+};
+use massa_pool_exports::{PoolController, PoolEvent};
 use massa_protocol_exports::{
-    ProtocolCommand, ProtocolConfig, ProtocolError, ProtocolManagementCommand, ProtocolManager,
-    ProtocolReceivers, ProtocolSenders,
+    NetworkPartitionStatus, ProtocolCommand, ProtocolConfig, ProtocolError,
+    ProtocolManagementCommand, ProtocolManager, ProtocolReceivers, ProtocolSenders,
 };
-use massa_storage::Storage;
-use massa_time::{MassaTime, TimeError};
+use massa_storage::{archive::Archive, Storage};
+use massa_time::TimeError;
 use std::collections::{HashMap, HashSet};
 use std::mem;
 use std::pin::Pin;
@@ -37,6 +49,18 @@ use tokio::{
 };
 use tracing::{debug, error, info, warn};
 
+/// True if a block of this status is buffered by consensus without being processed yet,
+/// the blocks not found being possibly still on their way to consensus
+fn is_pending_status(status: BlockGraphStatus) -> bool {
+    matches!(
+        status,
+        BlockGraphStatus::NotFound
+            | BlockGraphStatus::Incoming
+            | BlockGraphStatus::WaitingForSlot
+            | BlockGraphStatus::WaitingForDependencies
+    )
+}
+
 /// start a new `ProtocolController` from a `ProtocolConfig`
 /// - generate keypair
 /// - create `protocol_command/protocol_event` channels
@@ -48,6 +72,9 @@ use tracing::{debug, error, info, warn};
 /// * `receivers`: receiver(s) channel(s) to communicate with other modules
 /// * `consensus_controller`: interact with consensus module
 /// * `storage`: Shared storage to fetch data that are fetch across all modules
+/// * `archive`: archive of the final blocks, on archival nodes
+/// * `slot_scheduler`: source of time of the slots
+#[allow(clippy::too_many_arguments)]
 pub async fn start_protocol_controller(
     config: ProtocolConfig,
     receivers: ProtocolReceivers,
@@ -55,6 +82,8 @@ pub async fn start_protocol_controller(
     consensus_controller: Box<dyn ConsensusController>,
     pool_controller: Box<dyn PoolController>,
     storage: Storage,
+    archive: Option<Archive>,
+    slot_scheduler: Box<dyn SlotScheduler>,
 ) -> Result<ProtocolManager, ProtocolError> {
     debug!("starting protocol controller");
 
@@ -70,10 +99,16 @@ pub async fn start_protocol_controller(
                 controller_command_rx: receivers.protocol_command_receiver,
                 controller_manager_rx,
                 operation_sender: senders.operation_sender,
+                pool_event_receiver: receivers.pool_event_receiver,
+                partition_status: senders.partition_status,
+                sync_progress: senders.sync_progress,
+                production_gate: senders.production_gate,
             },
             consensus_controller,
             pool_controller,
             storage,
+            archive,
+            slot_scheduler,
         )
         .run_loop()
         .await;
@@ -135,6 +170,8 @@ pub struct ProtocolWorker {
     controller_manager_rx: mpsc::Receiver<ProtocolManagementCommand>,
     /// Broadcast sender(channel) for new operations
     operation_sender: tokio::sync::broadcast::Sender<Operation>,
+    /// Channel receiving the pool events.
+    pool_event_receiver: tokio::sync::broadcast::Receiver<PoolEvent>,
     /// Ids of active nodes mapped to node info.
     pub(crate) active_nodes: HashMap<NodeId, NodeInfo>,
     /// List of wanted blocks,
@@ -144,8 +181,14 @@ pub struct ProtocolWorker {
     checked_endorsements: LinearHashCacheSet<EndorsementId>,
     /// Cache of processed operations
     pub(crate) checked_operations: CheckedOperations,
-    /// List of processed headers
-    pub(crate) checked_headers: LinearHashCacheMap<BlockId, WrappedHeader>,
+    /// Most recently received verified headers
+    pub(crate) checked_headers: LruHashCacheMap<BlockId, WrappedHeader>,
+    /// Spans of the reception of the headers of the blocks being retrieved,
+    /// parents of the spans of the retrieval of the rest of these blocks
+    pub(crate) block_spans: LruHashCacheMap<BlockId, tracing::Span>,
+    /// Header of the latest integrated block of each thread,
+    /// sent to the newly connected nodes so that they can catch up with our graph
+    pub(crate) latest_integrated_headers: HashMap<u8, WrappedHeader>,
     /// List of ids of operations that we asked to the nodes
     pub(crate) asked_operations: PreHashMap<OperationPrefixId, (Instant, Vec<NodeId>)>,
     /// Buffer for operations that we want later
@@ -154,6 +197,20 @@ pub struct ProtocolWorker {
     pub(crate) storage: Storage,
     /// Operations to announce at the next interval.
     operations_to_announce: Vec<OperationId>,
+    /// Operations submitted to this node, in their stem phase
+    pub(crate) operation_stem: OperationStem,
+    /// Source of time of the slots
+    slot_scheduler: Box<dyn SlotScheduler>,
+    /// Network partition watchdog
+    partition_watchdog: PartitionWatchdog,
+    /// History exchange with the other archival nodes, none if the node is not archival
+    pub(crate) archive_sync: Option<ArchiveSync>,
+    /// Feedback on the blocks of the nodes, to be fed into the reputation of their peers
+    pub(crate) peer_feedback: PeerFeedbackTracker,
+    /// Progress of the synchronization
+    pub(crate) sync_progress: SyncProgressTracker,
+    /// Number of connected nodes shared with the block factory
+    production_gate: ProductionGate,
 }
 
 /// channels used by the protocol worker
@@ -168,6 +225,14 @@ pub struct ProtocolWorkerChannels {
     pub controller_manager_rx: mpsc::Receiver<ProtocolManagementCommand>,
     /// Broadcast sender(channel) for new operations
     pub operation_sender: tokio::sync::broadcast::Sender<Operation>,
+    /// pool events receiver
+    pub pool_event_receiver: tokio::sync::broadcast::Receiver<PoolEvent>,
+    /// alerts of the network partition watchdog, read by the api
+    pub partition_status: NetworkPartitionStatus,
+    /// progress of the synchronization, read by the api
+    pub sync_progress: SyncProgressStatus,
+    /// number of connected nodes, read by the block factory
+    pub production_gate: ProductionGate,
 }
 
 impl ProtocolWorker {
@@ -179,6 +244,8 @@ impl ProtocolWorker {
     /// * `controller_event_tx`: Channel to send protocol events.
     /// * `controller_command_rx`: Channel receiving commands.
     /// * `controller_manager_rx`: Channel receiving management commands.
+    /// * `archive`: archive of the final blocks, on archival nodes.
+    /// * `slot_scheduler`: source of time of the slots.
     pub fn new(
         config: ProtocolConfig,
         ProtocolWorkerChannels {
@@ -187,13 +254,18 @@ impl ProtocolWorker {
             controller_command_rx,
             controller_manager_rx,
             operation_sender,
+            pool_event_receiver,
+            partition_status,
+            sync_progress,
+            production_gate,
         }: ProtocolWorkerChannels,
         consensus_controller: Box<dyn ConsensusController>,
         pool_controller: Box<dyn PoolController>,
         storage: Storage,
+        archive: Option<Archive>,
+        slot_scheduler: Box<dyn SlotScheduler>,
     ) -> ProtocolWorker {
         ProtocolWorker {
-            config,
             network_command_sender,
             network_event_receiver,
             consensus_controller,
@@ -201,11 +273,14 @@ impl ProtocolWorker {
             controller_command_rx,
             controller_manager_rx,
             operation_sender,
+            pool_event_receiver,
             active_nodes: Default::default(),
             block_wishlist: Default::default(),
             checked_endorsements: LinearHashCacheSet::new(config.max_known_endorsements_size),
             checked_operations: CheckedOperations::new(config.max_known_ops_size),
-            checked_headers: LinearHashCacheMap::new(config.max_node_known_blocks_size),
+            checked_headers: LruHashCacheMap::new(config.max_known_headers_size),
+            block_spans: LruHashCacheMap::new(config.max_known_headers_size),
+            latest_integrated_headers: Default::default(),
             asked_operations: Default::default(),
             op_batch_buffer: OperationBatchBuffer::with_capacity(
                 config.operation_batch_buffer_capacity,
@@ -214,6 +289,14 @@ impl ProtocolWorker {
             operations_to_announce: Vec::with_capacity(
                 config.operation_announcement_buffer_capacity,
             ),
+            operation_stem: Default::default(),
+            slot_scheduler,
+            partition_watchdog: PartitionWatchdog::new(partition_status),
+            archive_sync: archive.map(ArchiveSync::new),
+            peer_feedback: PeerFeedbackTracker::new(&config),
+            sync_progress: SyncProgressTracker::new(sync_progress),
+            production_gate,
+            config,
         }
     }
 
@@ -239,6 +322,19 @@ impl ProtocolWorker {
         let operation_announcement_interval =
             sleep(self.config.operation_announcement_interval.into());
         tokio::pin!(operation_announcement_interval);
+        let partition_watchdog_enabled = PartitionWatchdog::is_enabled(&self.config);
+        let partition_check_timer = sleep(self.config.partition_check_interval.into());
+        tokio::pin!(partition_check_timer);
+        let archive_backfill_enabled =
+            self.archive_sync.is_some() && self.config.archive_backfill_interval.to_millis() > 0;
+        let archive_backfill_timer = sleep(self.config.archive_backfill_interval.into());
+        tokio::pin!(archive_backfill_timer);
+        let peer_feedback_enabled = PeerFeedbackTracker::is_enabled(&self.config);
+        let peer_feedback_timer = sleep(self.config.peer_feedback_interval.into());
+        tokio::pin!(peer_feedback_timer);
+        let sync_progress_enabled = SyncProgressTracker::is_enabled(&self.config);
+        let sync_progress_timer = sleep(self.config.sync_progress_interval.into());
+        tokio::pin!(sync_progress_timer);
         loop {
             massa_trace!("protocol.protocol_worker.run_loop.begin", {});
             /*
@@ -248,7 +344,11 @@ impl ProtocolWorker {
                     * manager commands: low freq, avoid having to wait to stop
                     * incoming commands (high frequency): process commands in priority (this is a high-level crate so we prioritize this side to avoid slowing down consensus)
                     * network events (high frequency): process incoming events
+                    * pool events: stop propagating replaced operations
                     * ask for blocks (timing not important)
+                    * network partition checks (timing not important)
+                    * archive backfill (timing not important)
+                    * peer feedback (timing not important)
             */
             tokio::select! {
                 // listen to management commands
@@ -273,6 +373,11 @@ impl ProtocolWorker {
                     self.on_network_event(evt?, &mut block_ask_timer, &mut operation_announcement_interval).await?;
                 }
 
+                // listen to pool events
+                Ok(evt) = self.pool_event_receiver.recv() => {
+                    self.on_pool_event(evt);
+                }
+
                 // block ask timer
                 _ = &mut block_ask_timer => {
                     massa_trace!("protocol.protocol_worker.run_loop.block_ask_timer", { });
@@ -297,6 +402,30 @@ impl ProtocolWorker {
                     massa_trace!("protocol.protocol_worker.run_loop.operation_prune_timer", { });
                     self.prune_asked_operations(&mut operation_prune_timer)?;
                 }
+
+                // network partition watchdog timer
+                _ = &mut partition_check_timer, if partition_watchdog_enabled => {
+                    massa_trace!("protocol.protocol_worker.run_loop.partition_check_timer", { });
+                    self.check_partition(&mut partition_check_timer).await?;
+                }
+
+                // archive backfill timer
+                _ = &mut archive_backfill_timer, if archive_backfill_enabled => {
+                    massa_trace!("protocol.protocol_worker.run_loop.archive_backfill_timer", { });
+                    self.ask_archive_backfill(&mut archive_backfill_timer).await?;
+                }
+
+                // peer feedback timer
+                _ = &mut peer_feedback_timer, if peer_feedback_enabled => {
+                    massa_trace!("protocol.protocol_worker.run_loop.peer_feedback_timer", { });
+                    self.send_peer_feedback(&mut peer_feedback_timer).await?;
+                }
+
+                // sync progress timer
+                _ = &mut sync_progress_timer, if sync_progress_enabled => {
+                    massa_trace!("protocol.protocol_worker.run_loop.sync_progress_timer", { });
+                    self.update_sync_progress(&mut sync_progress_timer)?;
+                }
             }
             massa_trace!("protocol.protocol_worker.run_loop.end", {});
         }
@@ -308,7 +437,10 @@ impl ProtocolWorker {
     /// Side effects:
     /// - notes nodes as knowing about those operations from now on.
     /// - empties the buffer of operations to announce.
+    /// - the operations whose stem phase ended are announced along.
     async fn announce_ops(&mut self, timer: &mut Pin<&mut Sleep>) {
+        let ended_stems = self.operation_stem.take_ended(Instant::now());
+        self.operations_to_announce.extend(ended_stems);
         // Quit if empty  to avoid iterating on nodes
         if self.operations_to_announce.is_empty() {
             // Reset timer.
@@ -350,9 +482,147 @@ impl ProtocolWorker {
         timer.set(sleep_until(next_tick));
     }
 
+    /// Run the network partition watchdog on the current finality and connections,
+    /// logging the alerts that changed.
+    async fn check_partition(&mut self, timer: &mut Pin<&mut Sleep>) -> Result<(), ProtocolError> {
+        let next_tick = Instant::now()
+            .checked_add(self.config.partition_check_interval.into())
+            .expect("time overflow");
+        timer.set(sleep_until(next_tick));
+
+        let out_connections = match self.network_command_sender.get_network_stats().await {
+            Ok(stats) => stats.out_connection_count as usize,
+            Err(err) => {
+                warn!(
+                    "network partition watchdog could not get the network stats: {}",
+                    err
+                );
+                return Ok(());
+            }
+        };
+        let latest_final_periods = self
+            .consensus_controller
+            .get_latest_final_blocks_periods()
+            .into_iter()
+            .map(|(_, period)| period)
+            .collect();
+        let changes = self.partition_watchdog.check(
+            &self.config,
+            self.slot_scheduler.get_current_slot()?,
+            self.slot_scheduler.now()?,
+            latest_final_periods,
+            self.active_nodes.len(),
+            out_connections,
+        );
+        for alert in changes.raised {
+            warn!("network partition alert raised: {}", alert);
+        }
+        for alert in changes.cleared {
+            info!("network partition alert cleared: {}", alert);
+        }
+        Ok(())
+    }
+
+    /// Ask a node for the blocks preceding the oldest archived ones
+    async fn ask_archive_backfill(
+        &mut self,
+        timer: &mut Pin<&mut Sleep>,
+    ) -> Result<(), ProtocolError> {
+        let next_tick = Instant::now()
+            .checked_add(self.config.archive_backfill_interval.into())
+            .expect("time overflow");
+        timer.set(sleep_until(next_tick));
+
+        let request = match self.archive_sync.as_mut() {
+            Some(archive_sync) => {
+                archive_sync.next_backfill_request(&self.config, self.active_nodes.keys().copied())
+            }
+            None => None,
+        };
+        if let Some((node, start, end)) = request {
+            debug!(
+                "asking node {} for the archived blocks from slot {} to slot {}",
+                node, start, end
+            );
+            self.network_command_sender
+                .ask_for_block_range(node, start, end)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Observe which of the tracked blocks became final,
+    /// and send the feedback gathered since the previous tick to the network
+    async fn send_peer_feedback(
+        &mut self,
+        timer: &mut Pin<&mut Sleep>,
+    ) -> Result<(), ProtocolError> {
+        let next_tick = Instant::now()
+            .checked_add(self.config.peer_feedback_interval.into())
+            .expect("time overflow");
+        timer.set(sleep_until(next_tick));
+
+        let latest_final_periods: Vec<u64> = self
+            .consensus_controller
+            .get_latest_final_blocks_periods()
+            .into_iter()
+            .map(|(_, period)| period)
+            .collect();
+        let consensus_controller = &self.consensus_controller;
+        self.peer_feedback
+            .resolve_blocks(&latest_final_periods, |ids| {
+                consensus_controller.get_block_statuses(ids)
+            });
+        let feedback = self.peer_feedback.take_pending();
+        if !feedback.is_empty() {
+            self.network_command_sender
+                .send_peer_feedback(feedback)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Publish the progress of the synchronization
+    fn update_sync_progress(&mut self, timer: &mut Pin<&mut Sleep>) -> Result<(), ProtocolError> {
+        let now = Instant::now();
+        let next_tick = now
+            .checked_add(self.config.sync_progress_interval.into())
+            .expect("time overflow");
+        timer.set(sleep_until(next_tick));
+
+        let latest_final_slots: Vec<Slot> = self
+            .consensus_controller
+            .get_latest_final_blocks_periods()
+            .into_iter()
+            .enumerate()
+            .map(|(thread, (_, period))| Slot::new(period, thread as u8))
+            .collect();
+        let current_slot = self.slot_scheduler.get_current_slot()?;
+        self.sync_progress
+            .update(&self.config, &latest_final_slots, current_slot, now);
+        Ok(())
+    }
+
+    /// Process an event from the pool.
+    /// Replaced operations are removed from the announcement buffer so that their propagation stops.
+    fn on_pool_event(&mut self, event: PoolEvent) {
+        match event {
+            PoolEvent::OperationReplaced { replaced, .. } => {
+                massa_trace!(
+                    "protocol.protocol_worker.on_pool_event.operation_replaced",
+                    { "operation_id": replaced }
+                );
+                self.operations_to_announce.retain(|id| id != &replaced);
+            }
+            PoolEvent::OperationAdded { .. }
+            | PoolEvent::OperationDropped { .. }
+            | PoolEvent::OperationIncluded { .. } => {}
+        }
+    }
+
     /// Add an list of operations to a buffer for announcement at the next interval,
     /// or immediately if the buffer is full.
-    async fn note_operations_to_announce(
+    pub(crate) async fn note_operations_to_announce(
         &mut self,
         operations: &[OperationId],
         timer: &mut Pin<&mut Sleep>,
@@ -372,6 +642,41 @@ impl ProtocolWorker {
         }
     }
 
+    /// Propagate operations submitted to this node.
+    /// If the stem phase is enabled, they are first only announced to a random relay,
+    /// and announced to the other nodes when the phase ends.
+    /// They are announced to all the nodes right away if no relay is available.
+    async fn propagate_local_operations(
+        &mut self,
+        operations: &[OperationId],
+        timer: &mut Pin<&mut Sleep>,
+    ) {
+        if OperationStem::is_enabled(&self.config)
+            && let Some(relay) = OperationStem::choose_relay(&self.active_nodes)
+        {
+            if let Some(node_info) = self.active_nodes.get_mut(&relay) {
+                node_info.insert_known_ops(operations.iter().map(|id| id.prefix()));
+            }
+            let res = self
+                .network_command_sender
+                .announce_operations(relay, operations.iter().map(|id| id.into_prefix()).collect())
+                .await;
+            match res {
+                Ok(()) => {
+                    let fluff_at = Instant::now()
+                        .checked_add(self.config.operation_stem_phase_duration.into())
+                        .expect("time overflow");
+                    self.operation_stem.start(operations, relay, fluff_at);
+                    return;
+                }
+                Err(err) => {
+                    debug!("could not relay operations to node {}: {}", relay, err);
+                }
+            }
+        }
+        self.note_operations_to_announce(operations, timer).await;
+    }
+
     async fn propagate_endorsements(&mut self, storage: &Storage) {
         massa_trace!(
             "protocol.protocol_worker.process_command.propagate_endorsements.begin",
@@ -432,6 +737,15 @@ impl ProtocolWorker {
                             ))
                         })?
                 };
+                let thread = header.content.slot.thread;
+                if self
+                    .latest_integrated_headers
+                    .get(&thread)
+                    .map_or(true, |latest| latest.content.slot < header.content.slot)
+                {
+                    self.latest_integrated_headers
+                        .insert(thread, header.clone());
+                }
                 for (node_id, node_info) in self.active_nodes.iter_mut() {
                     // node that isn't asking for that block
                     let cond = node_info.get_known_block(&block_id);
@@ -471,6 +785,7 @@ impl ProtocolWorker {
                     .collect();
                 for id in to_ban.iter() {
                     massa_trace!("protocol.protocol_worker.process_command.attack_block_detected.ban_node", { "node": id, "block_id": block_id });
+                    self.peer_feedback.note_invalid_block(*id);
                     self.ban_node(id).await?;
                 }
                 massa_trace!(
@@ -492,6 +807,7 @@ impl ProtocolWorker {
                 // Remove from the wishlist.
                 for block_id in remove.iter() {
                     self.block_wishlist.remove(block_id);
+                    self.block_spans.remove(block_id);
                 }
                 self.update_ask_block(block_timer).await?;
                 massa_trace!(
@@ -513,7 +829,7 @@ impl ProtocolWorker {
 
                 // Announce operations to active nodes not knowing about it.
                 let to_announce: Vec<OperationId> = operation_ids.iter().copied().collect();
-                self.note_operations_to_announce(&to_announce, op_timer)
+                self.propagate_local_operations(&to_announce, op_timer)
                     .await;
             }
             ProtocolCommand::PropagateEndorsements(endorsements) => {
@@ -701,11 +1017,13 @@ impl ProtocolWorker {
                         <= self.config.max_simultaneous_ask_blocks_per_node
                 })
                 .min_by_key(|(knowledge, node_id, _)| {
+                    let node_info = self.active_nodes.get(node_id).unwrap(); // will not panic, already checked
                     (
-                        *knowledge,                                                 // block knowledge
+                        *knowledge,                                         // block knowledge
+                        node_info.observer,                                 // observer nodes last
                         *active_block_req_count.get(node_id).unwrap_or(&0), // active requests
-                        self.active_nodes.get(node_id).unwrap().connection_instant, // node age (will not panic, already checked)
-                        *node_id,                                                   // node ID
+                        node_info.connection_instant,                       // node age
+                        *node_id,                                           // node ID
                     )
                 })
             {
@@ -746,31 +1064,130 @@ impl ProtocolWorker {
         Ok(())
     }
 
-    /// Ban a node.
+    /// Share the number of connected nodes with the block factory
+    pub(crate) fn note_connected_node_count(&self) {
+        self.production_gate
+            .set_connected_node_count(self.active_nodes.len());
+    }
+
+    /// Ban a node for misbehaving.
     pub(crate) async fn ban_node(&mut self, node_id: &NodeId) -> Result<(), ProtocolError> {
         massa_trace!("protocol.protocol_worker.ban_node", { "node": node_id });
         self.active_nodes.remove(node_id);
+        self.note_connected_node_count();
         if self.active_nodes.is_empty() {
             info!("Not connected to any peers.");
         }
         self.network_command_sender
-            .node_ban_by_ids(vec![*node_id])
+            .node_ban_by_ids(vec![*node_id], BanReason::Misbehavior)
             .await
             .map_err(|_| ProtocolError::ChannelError("Ban node command send failed".into()))?;
         Ok(())
     }
 
+    /// Window of the periods the items received from the nodes must be in, none if unbounded
+    pub(crate) fn relevance_window(&self) -> Result<Option<RelevanceWindow>, ProtocolError> {
+        if !RelevanceWindow::is_enabled(&self.config) {
+            return Ok(None);
+        }
+        Ok(RelevanceWindow::new(
+            &self.config,
+            self.slot_scheduler.get_current_slot()?,
+        ))
+    }
+
+    /// Note that a node sent `count` items out of the relevance window.
+    /// The node gets a penalty every `max_irrelevant_items_per_node` items.
+    pub(crate) fn note_irrelevant_items(&mut self, node_id: &NodeId, count: usize) {
+        debug!(
+            "node {} sent us {} items out of the relevance window",
+            node_id, count
+        );
+        if let Some(node_info) = self.active_nodes.get_mut(node_id) {
+            node_info.irrelevant_items += count;
+            if node_info.irrelevant_items >= self.config.max_irrelevant_items_per_node {
+                node_info.irrelevant_items = 0;
+                self.peer_feedback.note_irrelevant_items(*node_id);
+            }
+        }
+    }
+
+    /// Note that a node first sent us `header`, about to be registered in consensus.
+    ///
+    /// If the block cannot be processed yet, because its slot is in the future or one of its parents is unknown,
+    /// it counts in the `max_pending_blocks_per_node` quota of the node. Beyond the quota, the oldest pending blocks
+    /// of the node are dropped from consensus, to be asked again if they are needed, and the node gets a penalty.
+    pub(crate) fn note_pending_block(
+        &mut self,
+        node_id: &NodeId,
+        header: &WrappedHeader,
+    ) -> Result<(), ProtocolError> {
+        let max_pending_blocks = self.config.max_pending_blocks_per_node;
+        if max_pending_blocks == 0 {
+            return Ok(());
+        }
+        let slot = header.content.slot;
+        let is_future = match self.slot_scheduler.get_current_slot()? {
+            Some(current_slot) => slot > current_slot,
+            None => true,
+        };
+        let is_pending = is_future
+            || self
+                .consensus_controller
+                .get_block_statuses(&header.content.parents)
+                .into_iter()
+                .any(is_pending_status);
+        if !is_pending {
+            return Ok(());
+        }
+        let node_info = match self.active_nodes.get_mut(node_id) {
+            Some(node_info) => node_info,
+            None => return Ok(()),
+        };
+        let consensus_controller = &self.consensus_controller;
+        // the blocks processed meanwhile, or that will be discarded as stale, are not pending anymore
+        let dropped =
+            node_info.note_pending_block(header.id, slot, max_pending_blocks, |pending_blocks| {
+                let ids: Vec<BlockId> = pending_blocks.iter().map(|(id, _)| *id).collect();
+                let latest_final_periods = consensus_controller.get_latest_final_blocks_periods();
+                consensus_controller
+                    .get_block_statuses(&ids)
+                    .into_iter()
+                    .zip(pending_blocks)
+                    .map(|(status, (_, slot))| {
+                        is_pending_status(status)
+                            && latest_final_periods
+                                .get(slot.thread as usize)
+                                .map_or(true, |(_, period)| slot.period > *period)
+                    })
+                    .collect()
+            });
+        if dropped.is_empty() {
+            return Ok(());
+        }
+        debug!(
+            "node {} exceeded its quota of {} pending blocks, dropping {:?}",
+            node_id, max_pending_blocks, dropped
+        );
+        self.consensus_controller.drop_pending_blocks(dropped);
+        self.peer_feedback.note_excess_pending_blocks(*node_id);
+        Ok(())
+    }
+
     /// Perform checks on a header,
     /// and if valid update the node's view of the world.
     ///
     /// Returns a boolean representing whether the header is new.
     ///
-    /// Does not ban the source node if the header is invalid.
+    /// Does not ban the source node if the header is invalid,
+    /// but returns a `ProtocolError::InvalidBlockError` if it conflicts with a checkpoint
+    /// so that the caller bans it.
     ///
     /// Checks performed on Header:
     /// - Not genesis.
     /// - Can compute a `BlockId`.
     /// - Valid signature.
+    /// - No conflict with a checkpoint.
     /// - Absence of duplicate endorsements.
     ///
     /// Checks performed on endorsements:
@@ -804,6 +1221,7 @@ impl ProtocolWorker {
         // check if this header was already verified
         let now = Instant::now();
         if let Some(block_header) = self.checked_headers.get(&block_id) {
+            PROTOCOL_METRICS.header_cache_hits.inc();
             if let Some(node_info) = self.active_nodes.get_mut(source_node_id) {
                 node_info.insert_known_blocks(
                     &header.content.parents,
@@ -823,6 +1241,7 @@ impl ProtocolWorker {
             }
             return Ok(Some((block_id, false)));
         }
+        PROTOCOL_METRICS.header_cache_misses.inc();
 
         if let Err(err) = self
             .note_endorsements_from_node(header.content.endorsements.clone(), source_node_id, false)
@@ -841,6 +1260,24 @@ impl ProtocolWorker {
             return Ok(None);
         };
 
+        // check that the header does not conflict with a checkpoint
+        if let Some(checkpoint_id) = self.config.checkpoints.get(&header.content.slot) {
+            if *checkpoint_id != block_id {
+                return Err(ProtocolError::InvalidBlockError(format!(
+                    "block {} conflicts with checkpoint {} at slot {}",
+                    block_id, checkpoint_id, header.content.slot
+                )));
+            }
+        }
+
+        // check that the header only uses the protocol features active at its slot
+        if let Err(err) = self.config.upgrade_schedule.check_header(&header.content) {
+            return Err(ProtocolError::InvalidBlockError(format!(
+                "block {}: {}",
+                block_id, err
+            )));
+        }
+
         // check endorsement in header integrity
         let mut used_endorsement_indices: HashSet<u32> =
             HashSet::with_capacity(header.content.endorsements.len());
@@ -865,6 +1302,9 @@ impl ProtocolWorker {
         }
 
         self.checked_headers.insert(block_id, header.clone());
+        PROTOCOL_METRICS
+            .header_cache_size
+            .set(self.checked_headers.len() as i64);
 
         if let Some(node_info) = self.active_nodes.get_mut(source_node_id) {
             node_info.insert_known_blocks(
@@ -951,7 +1391,7 @@ impl ProtocolWorker {
             // Propagate operations when their expire period isn't `max_operations_propagation_time` old.
             let mut ops_to_propagate = ops.clone();
             let operations_to_not_propagate = {
-                let now = MassaTime::now()?;
+                let now = self.slot_scheduler.now()?;
                 let read_operations = ops_to_propagate.read_operations();
                 ops_to_propagate
                     .get_op_refs()
@@ -959,12 +1399,9 @@ impl ProtocolWorker {
                     .filter(|op_id| {
                         let expire_period =
                             read_operations.get(op_id).unwrap().content.expire_period;
-                        let expire_period_timestamp = get_block_slot_timestamp(
-                            self.config.thread_count,
-                            self.config.t0,
-                            self.config.genesis_timestamp,
-                            Slot::new(expire_period, 0),
-                        );
+                        let expire_period_timestamp = self
+                            .slot_scheduler
+                            .get_slot_timestamp(Slot::new(expire_period, 0));
                         match expire_period_timestamp {
                             Ok(slot_timestamp) => {
                                 slot_timestamp
@@ -1052,7 +1489,7 @@ impl ProtocolWorker {
                 // Propagate endorsements when the slot of the block they endorse isn't `max_endorsements_propagation_time` old.
                 let mut endorsements_to_propagate = endorsements.clone();
                 let endorsements_to_not_propagate = {
-                    let now = MassaTime::now()?;
+                    let now = self.slot_scheduler.now()?;
                     let read_endorsements = endorsements_to_propagate.read_endorsements();
                     endorsements_to_propagate
                         .get_endorsement_refs()
@@ -1060,12 +1497,8 @@ impl ProtocolWorker {
                         .filter_map(|endorsement_id| {
                             let slot_endorsed_block =
                                 read_endorsements.get(endorsement_id).unwrap().content.slot;
-                            let slot_timestamp = get_block_slot_timestamp(
-                                self.config.thread_count,
-                                self.config.t0,
-                                self.config.genesis_timestamp,
-                                slot_endorsed_block,
-                            );
+                            let slot_timestamp =
+                                self.slot_scheduler.get_slot_timestamp(slot_endorsed_block);
                             match slot_timestamp {
                                 Ok(slot_timestamp) => {
                                     if slot_timestamp.saturating_add(
@@ -1116,7 +1549,7 @@ mod tests {
     fn test_node_info_know_block() {
         let max_node_known_blocks_size = 10;
         let config = &PROTOCOL_CONFIG;
-        let mut nodeinfo = NodeInfo::new(config);
+        let mut nodeinfo = NodeInfo::new(config, false, true);
         let instant = Instant::now();
 
         let hash_test = get_dummy_block_id("test");
@@ -0,0 +1,50 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Window of relevance of the items received from the nodes.
+//!
+//! The headers and endorsements whose slot, and the operations whose expire period, are more than
+//! `relevance_past_periods` periods before or `relevance_future_periods` periods after the current period
+//! are dropped without being processed nor relayed. The nodes sending such items get a
+//! `PeerFeedback::IrrelevantItems` every `max_irrelevant_items_per_node` items.
+
+use massa_models::slot::Slot;
+use massa_protocol_exports::ProtocolConfig;
+
+/// Periods in which the items received from the nodes are relevant
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RelevanceWindow {
+    /// first relevant period
+    min_period: u64,
+    /// last relevant period
+    max_period: u64,
+}
+
+impl RelevanceWindow {
+    /// Returns true if at least one of the bounds is enabled in the configuration
+    pub fn is_enabled(config: &ProtocolConfig) -> bool {
+        config.relevance_past_periods > 0 || config.relevance_future_periods > 0
+    }
+
+    /// Window around `current_slot`, none if no bound is enabled or before genesis
+    pub fn new(config: &ProtocolConfig, current_slot: Option<Slot>) -> Option<Self> {
+        if !Self::is_enabled(config) {
+            return None;
+        }
+        let current_period = current_slot?.period;
+        Some(RelevanceWindow {
+            min_period: match config.relevance_past_periods {
+                0 => 0,
+                past => current_period.saturating_sub(past),
+            },
+            max_period: match config.relevance_future_periods {
+                0 => u64::MAX,
+                future => current_period.saturating_add(future),
+            },
+        })
+    }
+
+    /// True if an item of `period` is relevant
+    pub fn contains(&self, period: u64) -> bool {
+        self.min_period <= period && period <= self.max_period
+    }
+}
@@ -0,0 +1,117 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Progress of the synchronization of the node once bootstrapped.
+//!
+//! The highest slot of the valid headers received from the other nodes is compared, every
+//! `sync_progress_interval`, to the highest slot of our latest final blocks. The node is synced while
+//! its final blocks lag at most `max_synced_final_lag_periods` periods behind. The time remaining is
+//! estimated from how fast the lag shrank over the last updates. The progress is shared with the API
+//! through `SyncProgressStatus`.
+
+use massa_models::{
+    api::{SyncPhase, SyncProgress},
+    slot::Slot,
+    sync_progress::SyncProgressStatus,
+};
+use massa_protocol_exports::ProtocolConfig;
+use massa_time::MassaTime;
+use std::collections::VecDeque;
+use tokio::time::Instant;
+
+/// Number of updates over which the catch-up speed is measured
+const LAG_SAMPLE_COUNT: usize = 10;
+
+/// Follows the progress of the synchronization and publishes it
+pub(crate) struct SyncProgressTracker {
+    /// progress shared with the API
+    status: SyncProgressStatus,
+    /// highest slot of the valid headers received from the other nodes
+    highest_remote_slot: Option<Slot>,
+    /// lag in slots of the final blocks at the previous updates, oldest first
+    lag_samples: VecDeque<(Instant, u64)>,
+}
+
+impl SyncProgressTracker {
+    /// Create a tracker publishing to `status`
+    pub fn new(status: SyncProgressStatus) -> Self {
+        SyncProgressTracker {
+            status,
+            highest_remote_slot: None,
+            lag_samples: VecDeque::with_capacity(LAG_SAMPLE_COUNT + 1),
+        }
+    }
+
+    /// Returns true if the progress is followed in the configuration
+    pub fn is_enabled(config: &ProtocolConfig) -> bool {
+        config.sync_progress_interval.to_millis() > 0
+    }
+
+    /// Note that a node sent us a valid header of `slot`
+    pub fn note_remote_slot(&mut self, slot: Slot) {
+        if self
+            .highest_remote_slot
+            .map_or(true, |highest| slot > highest)
+        {
+            self.highest_remote_slot = Some(slot);
+        }
+    }
+
+    /// Compute and publish the progress.
+    ///
+    /// # Arguments
+    /// * `latest_final_slots`: slot of the latest final block of each thread
+    /// * `current_slot`: latest slot according to the clock, the remote slots are not trusted beyond
+    pub fn update(
+        &mut self,
+        config: &ProtocolConfig,
+        latest_final_slots: &[Slot],
+        current_slot: Option<Slot>,
+        now: Instant,
+    ) {
+        let local_final_slot = latest_final_slots.iter().max().copied();
+        let target_slot = match (self.highest_remote_slot, current_slot) {
+            (Some(remote), Some(current)) => Some(std::cmp::min(remote, current)),
+            (remote, current) => remote.or(current),
+        };
+        let (phase, estimated_time_remaining) = match (local_final_slot, target_slot) {
+            (Some(local), Some(target)) => {
+                let lag = target.slots_since(&local, config.thread_count).unwrap_or(0);
+                let synced_lag = config
+                    .max_synced_final_lag_periods
+                    .saturating_mul(config.thread_count as u64);
+                if lag <= synced_lag {
+                    self.lag_samples.clear();
+                    (SyncPhase::Synced, None)
+                } else {
+                    self.lag_samples.push_back((now, lag));
+                    if self.lag_samples.len() > LAG_SAMPLE_COUNT {
+                        self.lag_samples.pop_front();
+                    }
+                    (
+                        SyncPhase::CatchingUp,
+                        self.estimate_time_remaining(lag - synced_lag, now),
+                    )
+                }
+            }
+            _ => (SyncPhase::CatchingUp, None),
+        };
+        self.status.set(SyncProgress {
+            phase,
+            highest_remote_slot: self.highest_remote_slot,
+            local_final_slot,
+            estimated_time_remaining,
+        });
+    }
+
+    /// Time to catch up `remaining` slots at the speed the lag shrank since the oldest sample,
+    /// none if it did not shrink
+    fn estimate_time_remaining(&self, remaining: u64, now: Instant) -> Option<MassaTime> {
+        let (oldest_instant, oldest_lag) = self.lag_samples.front()?;
+        let (_, lag) = self.lag_samples.back()?;
+        let caught_up = oldest_lag.checked_sub(*lag).filter(|slots| *slots > 0)?;
+        let elapsed = now.saturating_duration_since(*oldest_instant).as_millis() as u64;
+        Some(MassaTime::from_millis(
+            remaining.saturating_mul(elapsed) / caught_up,
+        ))
+    }
+}
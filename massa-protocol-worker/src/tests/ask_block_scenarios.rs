@@ -568,6 +568,69 @@ async fn test_no_one_has_it() {
     )
     .await;
 }
+
+#[tokio::test]
+#[serial]
+async fn test_observer_asked_last() {
+    // start
+    let protocol_config = &tools::PROTOCOL_CONFIG;
+
+    protocol_test(
+        protocol_config,
+        async move |mut network_controller,
+                    mut protocol_command_sender,
+                    protocol_manager,
+                    protocol_consensus_event_receiver,
+                    protocol_pool_event_receiver| {
+            // the observer is connected first: it would be preferred as the oldest connection
+            let observer = tools::create_node();
+            network_controller
+                .new_observer_connection(observer.id)
+                .await;
+            let node_a = tools::create_and_connect_nodes(1, &mut network_controller)
+                .await
+                .pop()
+                .unwrap();
+
+            let block = tools::create_block(&node_a.keypair);
+            let hash_1 = block.id;
+
+            // send wishlist
+            let protocol_command_sender = tokio::task::spawn_blocking(move || {
+                protocol_command_sender
+                    .send_wishlist_delta(
+                        vec![(hash_1, Some(block.content.header.clone()))]
+                            .into_iter()
+                            .collect(),
+                        PreHashSet::<BlockId>::default(),
+                    )
+                    .unwrap();
+                protocol_command_sender
+            })
+            .await
+            .unwrap();
+
+            // asked to node A first
+            assert_hash_asked_to_node(hash_1, node_a.id, &mut network_controller).await;
+
+            // node A does not have it: the observer is asked
+            network_controller
+                .send_block_info(node_a.id, vec![(hash_1, BlockInfoReply::NotFound)])
+                .await;
+            assert_hash_asked_to_node(hash_1, observer.id, &mut network_controller).await;
+
+            (
+                network_controller,
+                protocol_command_sender,
+                protocol_manager,
+                protocol_consensus_event_receiver,
+                protocol_pool_event_receiver,
+            )
+        },
+    )
+    .await;
+}
+
 #[tokio::test]
 #[serial]
 async fn test_multiple_blocks_without_a_priori() {
@@ -148,7 +148,7 @@ async fn test_protocol_bans_node_sending_operation_with_size_bigger_than_max_blo
                 tools::create_operation_with_expire_period(&creator_node.keypair, 1);
 
             // 2. Change the serialized data
-            operation.serialized_data = vec![1; 500_001];
+            operation.serialized_data = vec![1; 500_001].into();
 
             // 3. Send block to protocol.
             network_controller
@@ -652,3 +652,61 @@ async fn test_protocol_removes_banned_node_on_disconnection() {
     )
     .await;
 }
+
+#[tokio::test]
+#[serial]
+async fn test_protocol_bans_node_sending_block_header_conflicting_with_checkpoint() {
+    let mut protocol_config = tools::PROTOCOL_CONFIG.clone();
+    protocol_config.checkpoints.insert(
+        Slot::new(1, 0),
+        BlockId::new(Hash::compute_from("checkpoint".as_bytes())),
+    );
+    protocol_test(
+        &protocol_config,
+        async move |mut network_controller,
+                    protocol_command_sender,
+                    protocol_manager,
+                    mut protocol_consensus_event_receiver,
+                    protocol_pool_event_receiver| {
+            // Create 1 node.
+            let mut nodes = tools::create_and_connect_nodes(1, &mut network_controller).await;
+
+            let creator_node = nodes.pop().expect("Failed to get node info.");
+
+            // 1. Create a valid block at the checkpoint slot, with a different id.
+            let block = tools::create_block(&creator_node.keypair);
+
+            // 2. Send header to protocol.
+            network_controller
+                .send_header(creator_node.id, block.content.header.clone())
+                .await;
+
+            // The node is banned.
+            tools::assert_banned_nodes(vec![creator_node.id], &mut network_controller).await;
+
+            // Check protocol does not send the header to consensus.
+            let protocol_consensus_event_receiver = tokio::task::spawn_blocking(move || {
+                protocol_consensus_event_receiver.wait_command(
+                    MassaTime::from_millis(1000),
+                    |command| match command {
+                        MockConsensusControllerMessage::RegisterBlockHeader { .. } => {
+                            panic!("Protocol unexpectedly sent header.")
+                        }
+                        _ => Some(()),
+                    },
+                );
+                protocol_consensus_event_receiver
+            })
+            .await
+            .unwrap();
+            (
+                network_controller,
+                protocol_command_sender,
+                protocol_manager,
+                protocol_consensus_event_receiver,
+                protocol_pool_event_receiver,
+            )
+        },
+    )
+    .await;
+}
@@ -3,7 +3,9 @@
 // RUST_BACKTRACE=1 cargo test test_one_handshake -- --nocapture --test-threads=1
 
 use super::tools::protocol_test_with_storage;
-use massa_models::{self, address::Address, slot::Slot};
+use crate::cache::LruHashCacheMap;
+use massa_hash::Hash;
+use massa_models::{self, address::Address, block::BlockId, slot::Slot};
 use massa_network_exports::{AskForBlocksInfo, BlockInfoReply, NetworkCommand};
 use massa_protocol_exports::tests::tools;
 use massa_protocol_exports::ProtocolConfig;
@@ -11,7 +13,7 @@ use serial_test::serial;
 
 lazy_static::lazy_static! {
     pub static ref CUSTOM_PROTOCOL_CONFIG: ProtocolConfig = {
-        let mut protocol_config = *tools::PROTOCOL_CONFIG;
+        let mut protocol_config = tools::PROTOCOL_CONFIG.clone();
 
         // Set max_node_known_blocks_size to zero.
         protocol_config.max_node_known_blocks_size = 0;
@@ -97,3 +99,31 @@ async fn test_noting_block_does_not_panic_with_zero_max_node_known_blocks_size()
     )
     .await;
 }
+
+#[test]
+fn test_lru_cache_evicts_least_recently_used() {
+    let ids: Vec<BlockId> = (0..4u8)
+        .map(|i| BlockId(Hash::compute_from(&[i])))
+        .collect();
+    let mut cache = LruHashCacheMap::new(3);
+    for (index, id) in ids.iter().take(3).enumerate() {
+        assert!(cache.insert(*id, index));
+    }
+    assert!(!cache.insert(ids[0], 10));
+
+    // reading the oldest item makes it the most recently used
+    for _ in 0..10 {
+        assert_eq!(cache.get(&ids[0]), Some(&0));
+    }
+    assert!(cache.insert(ids[3], 3));
+    assert_eq!(cache.len(), 3);
+    assert!(cache.contains_key(&ids[0]));
+    assert!(!cache.contains_key(&ids[1]));
+    assert_eq!(cache.get(&ids[2]), Some(&2));
+    assert_eq!(cache.get(&ids[1]), None);
+
+    // a zero capacity cache keeps nothing
+    let mut cache = LruHashCacheMap::new(0);
+    assert!(cache.insert(ids[0], 0));
+    assert!(cache.is_empty());
+}
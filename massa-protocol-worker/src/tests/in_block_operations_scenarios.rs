@@ -218,6 +218,7 @@ async fn test_protocol_sends_blocks_with_operations_to_consensus() {
                             parents: Vec::new(),
                             operation_merkle_root,
                             endorsements: Vec::new(),
+                            state_commitment: None,
                         },
                         BlockHeaderSerializer::new(),
                         &creator_node.keypair,
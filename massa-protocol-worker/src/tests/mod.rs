@@ -6,5 +6,10 @@ mod cache_scenarios;
 mod endorsements_scenarios;
 mod in_block_operations_scenarios;
 mod operations_scenarios;
+mod partition_watchdog_scenarios;
+mod peer_feedback_scenarios;
+mod pending_blocks_scenarios;
+mod relevance_scenarios;
 mod scenarios;
+mod sync_progress_scenarios;
 mod tools;
@@ -202,6 +202,78 @@ async fn test_protocol_propagates_operations_to_active_nodes() {
     .await;
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+#[serial]
+async fn test_protocol_relays_local_operations_to_a_single_node_during_their_stem_phase() {
+    let mut protocol_config = tools::PROTOCOL_CONFIG.clone();
+    protocol_config.operation_stem_phase_duration = MassaTime::from_millis(1000);
+    protocol_test_with_storage(
+        &protocol_config,
+        async move |mut network_controller,
+                    mut protocol_command_sender,
+                    protocol_manager,
+                    protocol_consensus_event_receiver,
+                    pool_event_receiver,
+                    mut storage| {
+            // Create 3 nodes.
+            let nodes = tools::create_and_connect_nodes(3, &mut network_controller).await;
+
+            let operation = tools::create_operation_with_expire_period(&nodes[0].keypair, 1);
+            let expected_operation_id = operation.id;
+            storage.store_operations(vec![operation]);
+            protocol_command_sender = tokio::task::spawn_blocking(move || {
+                protocol_command_sender
+                    .propagate_operations(storage)
+                    .unwrap();
+                protocol_command_sender
+            })
+            .await
+            .unwrap();
+
+            let announcement_filter = |cmd| match cmd {
+                NetworkCommand::SendOperationAnnouncements { to_node, batch } => {
+                    Some((to_node, batch))
+                }
+                _ => None,
+            };
+
+            // stem: only one node is told
+            let (relay, batch) = network_controller
+                .wait_command(500.into(), announcement_filter)
+                .await
+                .expect("the operation was not relayed");
+            assert_eq!(batch.len(), 1);
+            assert!(batch.contains(&expected_operation_id.prefix()));
+            assert!(network_controller
+                .wait_command(300.into(), announcement_filter)
+                .await
+                .is_none());
+
+            // fluff: the other nodes are told once the stem phase ended
+            let mut fluffed = Vec::new();
+            for _ in 0..2 {
+                let (to_node, batch) = network_controller
+                    .wait_command(2000.into(), announcement_filter)
+                    .await
+                    .expect("the operation was not fluffed");
+                assert!(batch.contains(&expected_operation_id.prefix()));
+                fluffed.push(to_node);
+            }
+            assert!(!fluffed.contains(&relay));
+            fluffed.dedup();
+            assert_eq!(fluffed.len(), 2);
+            (
+                network_controller,
+                protocol_command_sender,
+                protocol_manager,
+                protocol_consensus_event_receiver,
+                pool_event_receiver,
+            )
+        },
+    )
+    .await;
+}
+
 #[tokio::test]
 #[serial]
 async fn test_protocol_propagates_operations_only_to_nodes_that_dont_know_about_it() {
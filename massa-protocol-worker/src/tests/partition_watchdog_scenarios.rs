@@ -0,0 +1,101 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use crate::partition_watchdog::{PartitionAlertChanges, PartitionWatchdog};
+use massa_models::{api::NetworkPartitionAlert, slot::Slot};
+use massa_protocol_exports::tests::tools;
+use massa_protocol_exports::{NetworkPartitionStatus, ProtocolConfig};
+use massa_time::MassaTime;
+
+fn watchdog_config() -> ProtocolConfig {
+    let mut protocol_config = tools::PROTOCOL_CONFIG.clone();
+    protocol_config.thread_count = 2;
+    protocol_config.partition_max_slots_without_final_block = 4;
+    protocol_config.partition_min_connected_nodes = 2;
+    protocol_config.partition_min_out_connections = 2;
+    protocol_config.partition_low_out_connections_timeout = MassaTime::from_millis(1000);
+    protocol_config
+}
+
+#[test]
+fn test_stalled_finality_alert() {
+    let config = watchdog_config();
+    let status = NetworkPartitionStatus::default();
+    let mut watchdog = PartitionWatchdog::new(status.clone());
+    let now = MassaTime::from_millis(0);
+    let check = |watchdog: &mut PartitionWatchdog, period, final_periods, connected_nodes| {
+        watchdog.check(
+            &config,
+            Some(Slot::new(period, 0)),
+            now,
+            final_periods,
+            connected_nodes,
+            2,
+        )
+    };
+
+    assert_eq!(
+        check(&mut watchdog, 1, vec![0, 0], 3),
+        PartitionAlertChanges::default()
+    );
+    // 2 periods (4 slots) without new final block, but only one connected node
+    assert_eq!(
+        check(&mut watchdog, 3, vec![0, 0], 1),
+        PartitionAlertChanges::default()
+    );
+    let alert = NetworkPartitionAlert::StalledFinality {
+        slots: 4,
+        connected_nodes: 3,
+    };
+    assert_eq!(
+        check(&mut watchdog, 3, vec![0, 0], 3),
+        PartitionAlertChanges {
+            raised: vec![alert],
+            cleared: vec![],
+        }
+    );
+    assert_eq!(status.get_alerts(), vec![alert]);
+    // already raised
+    assert!(check(&mut watchdog, 4, vec![0, 0], 3).raised.is_empty());
+    // a block became final
+    assert_eq!(
+        check(&mut watchdog, 4, vec![0, 1], 3),
+        PartitionAlertChanges {
+            raised: vec![],
+            cleared: vec![NetworkPartitionAlert::StalledFinality {
+                slots: 6,
+                connected_nodes: 3,
+            }],
+        }
+    );
+    assert!(status.get_alerts().is_empty());
+}
+
+#[test]
+fn test_low_out_connections_alert() {
+    let config = watchdog_config();
+    let status = NetworkPartitionStatus::default();
+    let mut watchdog = PartitionWatchdog::new(status.clone());
+    let check = |watchdog: &mut PartitionWatchdog, now, out_connections| {
+        watchdog.check(
+            &config,
+            None,
+            MassaTime::from_millis(now),
+            Vec::new(),
+            3,
+            out_connections,
+        )
+    };
+
+    assert!(check(&mut watchdog, 0, 1).raised.is_empty());
+    assert!(check(&mut watchdog, 500, 0).raised.is_empty());
+    let alert = NetworkPartitionAlert::LowOutConnections {
+        out_connections: 1,
+        since: MassaTime::from_millis(0),
+    };
+    assert_eq!(check(&mut watchdog, 1000, 1).raised, vec![alert]);
+    assert_eq!(status.get_alerts(), vec![alert]);
+    // enough outgoing connections: the timeout starts again
+    assert!(!check(&mut watchdog, 1500, 2).cleared.is_empty());
+    assert!(check(&mut watchdog, 2000, 1).raised.is_empty());
+    assert!(status.get_alerts().is_empty());
+}
@@ -0,0 +1,123 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use crate::peer_feedback::PeerFeedbackTracker;
+use massa_hash::Hash;
+use massa_models::{api::BlockGraphStatus, block::BlockId, node::NodeId, slot::Slot};
+use massa_network_exports::PeerFeedback;
+use massa_protocol_exports::tests::tools;
+use massa_protocol_exports::ProtocolConfig;
+use massa_signature::KeyPair;
+use massa_time::MassaTime;
+use std::time::Duration;
+use tokio::time::Instant;
+
+fn feedback_config() -> ProtocolConfig {
+    let mut protocol_config = tools::PROTOCOL_CONFIG.clone();
+    protocol_config.thread_count = 2;
+    protocol_config.peer_feedback_interval = MassaTime::from_millis(1000);
+    protocol_config
+}
+
+fn node_id() -> NodeId {
+    NodeId::new(KeyPair::generate().get_public_key())
+}
+
+fn block_id(s: &str) -> BlockId {
+    BlockId(Hash::compute_from(s.as_bytes()))
+}
+
+#[test]
+fn test_final_block_feedback() {
+    let mut tracker = PeerFeedbackTracker::new(&feedback_config());
+    let (node_a, node_b) = (node_id(), node_id());
+    let (final_block, discarded_block, pending_block) = (
+        block_id("final"),
+        block_id("discarded"),
+        block_id("pending"),
+    );
+    let now = Instant::now();
+    tracker.note_new_header(final_block, Slot::new(1, 0), node_a, now);
+    tracker.note_new_header(discarded_block, Slot::new(1, 1), node_b, now);
+    tracker.note_new_header(pending_block, Slot::new(2, 0), node_b, now);
+    // only the first announcer is credited
+    tracker.note_new_header(final_block, Slot::new(1, 0), node_b, now);
+
+    tracker.resolve_blocks(&[1, 1], |ids| {
+        assert_eq!(ids.len(), 2);
+        ids.iter()
+            .map(|id| {
+                if *id == final_block {
+                    BlockGraphStatus::Final
+                } else {
+                    BlockGraphStatus::Discarded
+                }
+            })
+            .collect()
+    });
+    assert_eq!(
+        tracker.take_pending(),
+        vec![(node_a, PeerFeedback::FinalBlock)]
+    );
+    assert!(tracker.take_pending().is_empty());
+
+    // the resolved blocks are not tracked anymore
+    tracker.resolve_blocks(&[2, 1], |ids| {
+        assert_eq!(ids, [pending_block]);
+        vec![BlockGraphStatus::Final]
+    });
+    assert_eq!(
+        tracker.take_pending(),
+        vec![(node_b, PeerFeedback::FinalBlock)]
+    );
+}
+
+#[test]
+fn test_late_announcement_feedback() {
+    let config = feedback_config();
+    let late_delay = Duration::from_millis(2000);
+    let mut tracker = PeerFeedbackTracker::new(&config);
+    let (first, late, newcomer) = (node_id(), node_id(), node_id());
+    let block = block_id("block");
+    let connected_at = Instant::now();
+    let first_seen = connected_at + Duration::from_millis(1000);
+    tracker.note_new_header(block, Slot::new(1, 0), first, first_seen);
+
+    // in time
+    tracker.note_known_header(
+        &block,
+        late,
+        connected_at,
+        first_seen + late_delay,
+        late_delay,
+    );
+    assert!(tracker.take_pending().is_empty());
+
+    let after_delay = first_seen + late_delay + Duration::from_millis(1);
+    tracker.note_known_header(&block, late, connected_at, after_delay, late_delay);
+    // counted once per header
+    tracker.note_known_header(&block, late, connected_at, after_delay, late_delay);
+    // connected after the header was first seen
+    tracker.note_known_header(&block, newcomer, after_delay, after_delay, late_delay);
+    // the first announcer is never late
+    tracker.note_known_header(&block, first, connected_at, after_delay, late_delay);
+    tracker.note_invalid_block(newcomer);
+    assert_eq!(
+        tracker.take_pending(),
+        vec![
+            (late, PeerFeedback::LateAnnouncement),
+            (newcomer, PeerFeedback::InvalidBlock)
+        ]
+    );
+}
+
+#[test]
+fn test_disabled_feedback() {
+    let mut config = feedback_config();
+    config.peer_feedback_interval = MassaTime::from_millis(0);
+    let mut tracker = PeerFeedbackTracker::new(&config);
+    let node = node_id();
+    tracker.note_new_header(block_id("block"), Slot::new(1, 0), node, Instant::now());
+    tracker.note_invalid_block(node);
+    tracker.resolve_blocks(&[1, 1], |_| panic!("no block is tracked"));
+    assert!(tracker.take_pending().is_empty());
+}
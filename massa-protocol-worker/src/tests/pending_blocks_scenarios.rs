@@ -0,0 +1,50 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use crate::node_info::NodeInfo;
+use massa_hash::Hash;
+use massa_models::{block::BlockId, slot::Slot};
+use massa_protocol_exports::tests::tools;
+
+fn block_id(index: u64) -> BlockId {
+    BlockId(Hash::compute_from(&index.to_be_bytes()))
+}
+
+#[test]
+fn test_pending_block_quota() {
+    let mut node_info = NodeInfo::new(&tools::PROTOCOL_CONFIG, false, true);
+
+    // within the quota, the pending blocks are not checked again
+    for index in 0..3 {
+        let dropped =
+            node_info.note_pending_block(block_id(index), Slot::new(index + 10, 0), 3, |_| {
+                panic!("the quota is not exceeded")
+            });
+        assert!(dropped.is_empty());
+    }
+
+    // the blocks that can be processed now do not count in the quota
+    let dropped = node_info.note_pending_block(block_id(3), Slot::new(13, 0), 3, |pending| {
+        assert_eq!(pending.len(), 4);
+        vec![true, false, true, true]
+    });
+    assert!(dropped.is_empty());
+    assert_eq!(node_info.pending_blocks.len(), 3);
+
+    // beyond the quota, the oldest pending blocks are dropped
+    let dropped = node_info.note_pending_block(block_id(4), Slot::new(14, 0), 3, |pending| {
+        assert_eq!(
+            pending.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            vec![block_id(0), block_id(2), block_id(3), block_id(4)]
+        );
+        vec![true; pending.len()]
+    });
+    assert_eq!(dropped, vec![block_id(0)]);
+    assert_eq!(
+        node_info
+            .pending_blocks
+            .iter()
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>(),
+        vec![block_id(2), block_id(3), block_id(4)]
+    );
+}
@@ -0,0 +1,119 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use super::tools::protocol_test;
+use crate::relevance::RelevanceWindow;
+use massa_consensus_exports::test_exports::MockConsensusControllerMessage;
+use massa_models::slot::Slot;
+use massa_pool_exports::test_exports::MockPoolControllerMessage;
+use massa_protocol_exports::tests::tools::{self, create_and_connect_nodes};
+use massa_protocol_exports::ProtocolConfig;
+use massa_time::MassaTime;
+use serial_test::serial;
+
+fn relevance_config(past: u64, future: u64) -> ProtocolConfig {
+    let mut protocol_config = tools::PROTOCOL_CONFIG.clone();
+    protocol_config.relevance_past_periods = past;
+    protocol_config.relevance_future_periods = future;
+    // the current period is 0 for the whole test
+    protocol_config.genesis_timestamp = MassaTime::now().unwrap();
+    protocol_config
+}
+
+#[test]
+fn test_relevance_window_bounds() {
+    let current_slot = Some(Slot::new(100, 3));
+
+    let window = RelevanceWindow::new(&relevance_config(10, 5), current_slot).unwrap();
+    assert!(!window.contains(89));
+    assert!(window.contains(90));
+    assert!(window.contains(105));
+    assert!(!window.contains(106));
+
+    // a disabled bound lets everything through on its side
+    let window = RelevanceWindow::new(&relevance_config(0, 5), current_slot).unwrap();
+    assert!(window.contains(0));
+    assert!(!window.contains(106));
+    let window = RelevanceWindow::new(&relevance_config(10, 0), current_slot).unwrap();
+    assert!(!window.contains(89));
+    assert!(window.contains(u64::MAX));
+
+    // no window when both bounds are disabled or before genesis
+    assert!(RelevanceWindow::new(&relevance_config(0, 0), current_slot).is_none());
+    assert!(RelevanceWindow::new(&relevance_config(10, 5), None).is_none());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+#[serial]
+async fn test_protocol_drops_far_future_headers_and_operations() {
+    let protocol_config = relevance_config(10, 5);
+
+    protocol_test(
+        &protocol_config,
+        async move |mut network_controller,
+                    protocol_command_sender,
+                    protocol_manager,
+                    mut protocol_consensus_event_receiver,
+                    mut protocol_pool_event_receiver| {
+            let nodes = create_and_connect_nodes(1, &mut network_controller).await;
+
+            // a header 50 periods ahead is neither processed nor relayed
+            let far_block = tools::create_block_with_operations(
+                &nodes[0].keypair,
+                Slot::new(50, 0),
+                Vec::new(),
+            );
+            network_controller
+                .send_header(nodes[0].id, far_block.content.header.clone())
+                .await;
+            let near_block = tools::create_block(&nodes[0].keypair);
+            network_controller
+                .send_header(nodes[0].id, near_block.content.header.clone())
+                .await;
+            let near_block_id = near_block.id;
+            let protocol_consensus_event_receiver = tokio::task::spawn_blocking(move || {
+                let id = protocol_consensus_event_receiver
+                    .wait_command(MassaTime::from_millis(1000), |command| match command {
+                        MockConsensusControllerMessage::RegisterBlockHeader {
+                            block_id, ..
+                        } => Some(block_id),
+                        _ => None,
+                    })
+                    .expect("the relevant header was not registered");
+                assert_eq!(id, near_block_id);
+                protocol_consensus_event_receiver
+            })
+            .await
+            .unwrap();
+
+            // an operation expiring 50 periods ahead is dropped
+            let far_operation = tools::create_operation_with_expire_period(&nodes[0].keypair, 50);
+            network_controller
+                .send_operations(nodes[0].id, vec![far_operation])
+                .await;
+            let near_operation = tools::create_operation_with_expire_period(&nodes[0].keypair, 1);
+            let near_operation_id = near_operation.id;
+            network_controller
+                .send_operations(nodes[0].id, vec![near_operation])
+                .await;
+            let added = protocol_pool_event_receiver
+                .wait_command(1000.into(), |evt| match evt {
+                    MockPoolControllerMessage::AddOperations { operations } => {
+                        Some(operations.get_op_refs().clone())
+                    }
+                    _ => None,
+                })
+                .expect("the relevant operation was not added to the pool");
+            assert_eq!(added.len(), 1);
+            assert!(added.contains(&near_operation_id));
+
+            (
+                network_controller,
+                protocol_command_sender,
+                protocol_manager,
+                protocol_consensus_event_receiver,
+                protocol_pool_event_receiver,
+            )
+        },
+    )
+    .await;
+}
@@ -566,3 +566,68 @@ async fn test_protocol_block_not_found() {
     )
     .await;
 }
+
+#[tokio::test]
+#[serial]
+async fn test_protocol_sends_latest_headers_to_new_nodes() {
+    let protocol_config = &tools::PROTOCOL_CONFIG;
+    protocol_test_with_storage(
+        protocol_config,
+        async move |mut network_controller,
+                    mut protocol_command_sender,
+                    protocol_manager,
+                    protocol_consensus_event_receiver,
+                    protocol_pool_event_receiver,
+                    mut storage| {
+            let send_header_cmd_filter = |cmd| match cmd {
+                cmd @ NetworkCommand::SendBlockHeader { .. } => Some(cmd),
+                _ => None,
+            };
+
+            // 1. Integrate a block while no node is connected.
+            let creator_node = create_and_connect_nodes(1, &mut network_controller)
+                .await
+                .pop()
+                .expect("Failed to get node info.");
+            network_controller.close_connection(creator_node.id).await;
+            let block = create_block(&creator_node.keypair);
+            let expected_hash = block.id;
+            storage.store_block(block.clone());
+            protocol_command_sender = tokio::task::spawn_blocking(move || {
+                protocol_command_sender
+                    .integrated_block(expected_hash, storage.clone())
+                    .unwrap();
+                protocol_command_sender
+            })
+            .await
+            .unwrap();
+
+            // 2. Connect a new node.
+            let new_node = create_and_connect_nodes(1, &mut network_controller)
+                .await
+                .pop()
+                .expect("Failed to get node info.");
+
+            // 3. Check that protocol sends it the header of the integrated block.
+            match network_controller
+                .wait_command(1000.into(), send_header_cmd_filter)
+                .await
+            {
+                Some(NetworkCommand::SendBlockHeader { node, header }) => {
+                    assert_eq!(node, new_node.id);
+                    assert_eq!(header.id, expected_hash);
+                }
+                _ => panic!("Unexpected or no network command."),
+            };
+
+            (
+                network_controller,
+                protocol_command_sender,
+                protocol_manager,
+                protocol_consensus_event_receiver,
+                protocol_pool_event_receiver,
+            )
+        },
+    )
+    .await;
+}
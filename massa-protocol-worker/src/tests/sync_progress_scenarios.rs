@@ -0,0 +1,105 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use crate::sync_progress::SyncProgressTracker;
+use massa_models::{api::SyncPhase, slot::Slot, sync_progress::SyncProgressStatus};
+use massa_protocol_exports::tests::tools;
+use massa_protocol_exports::ProtocolConfig;
+use massa_time::MassaTime;
+use std::time::Duration;
+use tokio::time::Instant;
+
+fn sync_config() -> ProtocolConfig {
+    let mut protocol_config = tools::PROTOCOL_CONFIG.clone();
+    protocol_config.thread_count = 2;
+    protocol_config.max_synced_final_lag_periods = 8;
+    protocol_config
+}
+
+#[test]
+fn test_sync_progress_phases() {
+    let config = sync_config();
+    let status = SyncProgressStatus::default();
+    assert_eq!(status.get().phase, SyncPhase::Bootstrapping);
+    let mut tracker = SyncProgressTracker::new(status.clone());
+    let now = Instant::now();
+
+    // far behind the remote slots
+    tracker.note_remote_slot(Slot::new(100, 1));
+    tracker.note_remote_slot(Slot::new(90, 0));
+    tracker.update(
+        &config,
+        &[Slot::new(10, 0), Slot::new(11, 1)],
+        Some(Slot::new(100, 1)),
+        now,
+    );
+    let progress = status.get();
+    assert_eq!(progress.phase, SyncPhase::CatchingUp);
+    assert_eq!(progress.highest_remote_slot, Some(Slot::new(100, 1)));
+    assert_eq!(progress.local_final_slot, Some(Slot::new(11, 1)));
+    // the speed is not known yet
+    assert_eq!(progress.estimated_time_remaining, None);
+
+    // within the allowed lag
+    tracker.update(
+        &config,
+        &[Slot::new(92, 0), Slot::new(92, 1)],
+        Some(Slot::new(100, 1)),
+        now + Duration::from_secs(1),
+    );
+    let progress = status.get();
+    assert_eq!(progress.phase, SyncPhase::Synced);
+    assert_eq!(progress.estimated_time_remaining, None);
+
+    // the remote slots are not trusted beyond the clock
+    tracker.note_remote_slot(Slot::new(1000, 0));
+    tracker.update(
+        &config,
+        &[Slot::new(92, 0), Slot::new(92, 1)],
+        Some(Slot::new(100, 1)),
+        now + Duration::from_secs(2),
+    );
+    let progress = status.get();
+    assert_eq!(progress.phase, SyncPhase::Synced);
+    assert_eq!(progress.highest_remote_slot, Some(Slot::new(1000, 0)));
+}
+
+#[test]
+fn test_sync_progress_estimated_time_remaining() {
+    let config = sync_config();
+    let status = SyncProgressStatus::default();
+    let mut tracker = SyncProgressTracker::new(status.clone());
+    let now = Instant::now();
+    tracker.note_remote_slot(Slot::new(100, 0));
+
+    // lag of 180 slots then 160 slots 10 seconds later: 2 slots caught up per second,
+    // 144 slots remaining before the allowed lag of 16 slots
+    tracker.update(&config, &[Slot::new(10, 0)], None, now);
+    tracker.update(
+        &config,
+        &[Slot::new(20, 0)],
+        None,
+        now + Duration::from_secs(10),
+    );
+    let progress = status.get();
+    assert_eq!(progress.phase, SyncPhase::CatchingUp);
+    assert_eq!(
+        progress.estimated_time_remaining,
+        Some(MassaTime::from_millis(72_000))
+    );
+
+    // no estimation while the lag grows
+    tracker.update(
+        &config,
+        &[Slot::new(20, 0)],
+        None,
+        now + Duration::from_secs(20),
+    );
+    tracker.note_remote_slot(Slot::new(200, 0));
+    tracker.update(
+        &config,
+        &[Slot::new(20, 0)],
+        None,
+        now + Duration::from_secs(30),
+    );
+    assert_eq!(status.get().estimated_time_remaining, None);
+}
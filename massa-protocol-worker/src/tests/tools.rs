@@ -6,6 +6,8 @@ use massa_models::{
     node::NodeId,
     operation::WrappedOperation,
     prehash::PreHashSet,
+    slot_scheduler::{SlotScheduler, SlotTiming},
+    test_exports::MockSlotScheduler,
 };
 use massa_network_exports::BlockInfoReply;
 use massa_pool_exports::test_exports::{MockPoolController, PoolEventReceiver};
@@ -14,8 +16,21 @@ use massa_protocol_exports::{
     ProtocolManager, ProtocolReceivers, ProtocolSenders,
 };
 use massa_storage::Storage;
+use massa_time::MassaTime;
 use tokio::sync::{broadcast, mpsc};
 
+/// Slot scheduler frozen at the time the test starts
+fn slot_scheduler(protocol_config: &ProtocolConfig) -> Box<dyn SlotScheduler> {
+    Box::new(MockSlotScheduler::new(
+        SlotTiming {
+            thread_count: protocol_config.thread_count,
+            t0: protocol_config.t0,
+            genesis_timestamp: protocol_config.genesis_timestamp,
+        },
+        MassaTime::now().unwrap(),
+    ))
+}
+
 pub async fn protocol_test<F, V>(protocol_config: &ProtocolConfig, test: F)
 where
     F: FnOnce(
@@ -48,19 +63,25 @@ where
     let protocol_receivers = ProtocolReceivers {
         network_event_receiver,
         protocol_command_receiver,
+        // the pool is mocked and emits no events
+        pool_event_receiver: broadcast::channel(1).1,
     };
     let protocol_senders = ProtocolSenders {
         network_command_sender,
         operation_sender,
+        partition_status: Default::default(),
+        sync_progress: Default::default(),
+        production_gate: Default::default(),
     };
     // start protocol controller
     let protocol_manager: ProtocolManager = start_protocol_controller(
-        *protocol_config,
+        protocol_config.clone(),
         protocol_receivers,
         protocol_senders,
         consensus_controller,
         pool_controller,
         Storage::create_root(),
+        slot_scheduler(protocol_config),
     )
     .await
     .expect("could not start protocol controller");
@@ -120,20 +141,26 @@ where
     let protocol_senders = ProtocolSenders {
         network_command_sender: network_command_sender.clone(),
         operation_sender: broadcast::channel(protocol_config.broadcast_operations_capacity).0,
+        partition_status: Default::default(),
+        sync_progress: Default::default(),
+        production_gate: Default::default(),
     };
 
     let protocol_receivers = ProtocolReceivers {
         network_event_receiver,
         protocol_command_receiver,
+        // the pool is mocked and emits no events
+        pool_event_receiver: broadcast::channel(1).1,
     };
 
     let protocol_manager = start_protocol_controller(
-        *protocol_config,
+        protocol_config.clone(),
         protocol_receivers,
         protocol_senders,
         consensus_controller,
         pool_controller,
         storage.clone(),
+        slot_scheduler(protocol_config),
     )
     .await
     .expect("could not start protocol controller");
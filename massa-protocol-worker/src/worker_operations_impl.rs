@@ -141,9 +141,24 @@ impl ProtocolWorker {
     pub(crate) async fn on_operations_received(
         &mut self,
         node_id: NodeId,
-        operations: Vec<WrappedOperation>,
+        mut operations: Vec<WrappedOperation>,
         op_timer: &mut Pin<&mut Sleep>,
     ) {
+        // drop the operations whose expire period is out of the relevance window
+        match self.relevance_window() {
+            Ok(Some(window)) => {
+                let received = operations.len();
+                operations.retain(|operation| window.contains(operation.content.expire_period));
+                if operations.len() < received {
+                    self.note_irrelevant_items(&node_id, received - operations.len());
+                }
+            }
+            Ok(None) => {}
+            Err(err) => warn!(
+                "could not get the relevance window of the operations: {}",
+                err
+            ),
+        }
         if let Err(err) = self
             .note_operations_from_node(operations, &node_id, op_timer)
             .await
@@ -5,31 +5,44 @@
 #![warn(unused_crate_dependencies)]
 
 use http::header::HeaderName;
-use jsonrpsee::core::client::{CertificateStore, ClientT, IdKind};
+use jsonrpsee::core::client::{CertificateStore, ClientT, IdKind, SubscriptionClientT};
 use jsonrpsee::http_client::HttpClient;
 use jsonrpsee::rpc_params;
-use jsonrpsee::ws_client::{HeaderMap, HeaderValue};
+use jsonrpsee::ws_client::{HeaderMap, HeaderValue, WsClient, WsClientBuilder};
 use massa_models::api::{
-    AddressInfo, BlockInfo, BlockSummary, DatastoreEntryInput, DatastoreEntryOutput,
-    EndorsementInfo, EventFilter, NodeStatus, OperationInfo, OperationInput,
-    ReadOnlyBytecodeExecution, ReadOnlyCall, TimeInterval,
+    AddressInfo, BlockCandidateInput, BlockInfo, BlockSummary, BlockValidityReport,
+    ConnectedNodeInfo, DatastoreEntryInput, DatastoreEntryOutput, EndorsementInfo, EventFilter,
+    ExportContent, ExportFormat, ExportStats, FinalOperationEntry, ForkChoiceExplanation,
+    NetworkVersions, NodeHealth, NodeStatus, OperationInfo, OperationInput, OperationStatusInfo,
+    OperationSubmissionResult, PagedResult, Pagination, PeerConnectionEvent, ProductionOverride,
+    ReadOnlyBytecodeExecution, ReadOnlyCall, ReorgAlert, StorageCheckReport, TimeInterval,
 };
+use massa_models::balance_proof::FinalBalanceProof;
+use massa_models::ban_list::{BanList, IpSubnet};
 use massa_models::clique::Clique;
 use massa_models::composite::PubkeySig;
 use massa_models::execution::ExecuteReadOnlyResponse;
+use massa_models::network_faults::{MessageFaults, NetworkFaults};
 use massa_models::node::NodeId;
 use massa_models::output_event::SCOutputEvent;
 use massa_models::prehash::{PreHashMap, PreHashSet};
+use massa_models::stats::{CycleProductionStats, OperationPoolStats};
 use massa_models::{
-    address::Address, block::BlockId, endorsement::EndorsementId, operation::OperationId,
+    address::Address,
+    block::{BlockHeader, BlockId},
+    endorsement::EndorsementId,
+    operation::OperationId,
+    slot::Slot,
 };
 
 use jsonrpsee::{core::Error as JsonRpseeError, core::RpcResult, http_client::HttpClientBuilder};
 use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
 use std::str::FromStr;
 
 mod config;
 pub use config::HttpConfig;
+pub use jsonrpsee::core::client::Subscription;
 
 /// Client
 pub struct Client {
@@ -41,24 +54,32 @@ pub struct Client {
 
 impl Client {
     /// creates a new client
+    /// `private_api_token` is sent as a bearer token with every private API request
     pub async fn new(
         ip: IpAddr,
         public_port: u16,
         private_port: u16,
+        private_api_token: Option<&str>,
         http_config: &HttpConfig,
     ) -> Client {
         let public_socket_addr = SocketAddr::new(ip, public_port);
         let private_socket_addr = SocketAddr::new(ip, private_port);
         let public_url = format!("http://{}", public_socket_addr);
         let private_url = format!("http://{}", private_socket_addr);
+        let mut private_http_config = http_config.clone();
+        if let Some(token) = private_api_token {
+            private_http_config
+                .headers
+                .push(("Authorization".to_string(), format!("Bearer {}", token)));
+        }
         Client {
             public: RpcClient::from_url(&public_url, http_config).await,
-            private: RpcClient::from_url(&private_url, http_config).await,
+            private: RpcClient::from_url(&private_url, &private_http_config).await,
         }
     }
 }
 
-/// TODO add ws client
+/// Client of the JSON-RPC API of a node over HTTP
 pub struct RpcClient {
     http_client: HttpClient,
 }
@@ -140,6 +161,11 @@ impl RpcClient {
             .await
     }
 
+    /// Return the block production stats of the staking addresses for the last cycles.
+    pub async fn get_stats(&self) -> RpcResult<Vec<CycleProductionStats>> {
+        self.http_client.request("get_stats", rpc_params![]).await
+    }
+
     /// Bans given ip address(es)
     /// No confirmation to expect.
     pub async fn node_ban_by_ip(&self, ips: Vec<IpAddr>) -> RpcResult<()> {
@@ -172,6 +198,69 @@ impl RpcClient {
             .await
     }
 
+    /// Bans given subnet(s)
+    /// No confirmation to expect.
+    pub async fn node_ban_by_subnet(&self, subnets: Vec<IpSubnet>) -> RpcResult<()> {
+        self.http_client
+            .request("node_ban_by_subnet", rpc_params![subnets])
+            .await
+    }
+
+    /// Unban given subnet(s)
+    /// No confirmation to expect.
+    pub async fn node_unban_by_subnet(&self, subnets: Vec<IpSubnet>) -> RpcResult<()> {
+        self.http_client
+            .request("node_unban_by_subnet", rpc_params![subnets])
+            .await
+    }
+
+    /// Get the banned IP addresses and subnets, in a format that other nodes can import
+    pub async fn node_export_ban_list(&self) -> RpcResult<BanList> {
+        self.http_client
+            .request("node_export_ban_list", rpc_params![])
+            .await
+    }
+
+    /// Merge the given bans with the bans of the node
+    pub async fn node_import_ban_list(&self, ban_list: BanList) -> RpcResult<()> {
+        self.http_client
+            .request("node_import_ban_list", rpc_params![ban_list])
+            .await
+    }
+
+    /// Write the known peers and bans to the peers file right away
+    pub async fn node_flush_peers(&self) -> RpcResult<()> {
+        self.http_client
+            .request("node_flush_peers", rpc_params![])
+            .await
+    }
+
+    /// Read the peers file again and merge it with the known peers and bans
+    pub async fn node_reload_peers(&self) -> RpcResult<()> {
+        self.http_client
+            .request("node_reload_peers", rpc_params![])
+            .await
+    }
+
+    /// Get the last ended connections of the peer at the given ip address, oldest first
+    pub async fn get_peer_history(&self, ip: IpAddr) -> RpcResult<Vec<PeerConnectionEvent>> {
+        self.http_client
+            .request("get_peer_history", rpc_params![ip])
+            .await
+    }
+
+    /// Set the faults injected in the messages sent to the given peer, or to all the peers,
+    /// only on a node built with the `fault_injection` feature
+    pub async fn node_set_network_faults(
+        &self,
+        node_id: Option<NodeId>,
+        faults: Option<MessageFaults>,
+    ) -> RpcResult<NetworkFaults> {
+        self.http_client
+            .request("node_set_network_faults", rpc_params![node_id, faults])
+            .await
+    }
+
     /// Returns node peers whitelist IP address(es).
     pub async fn node_peers_whitelist(&self) -> RpcResult<Vec<IpAddr>> {
         self.http_client
@@ -243,6 +332,93 @@ impl RpcClient {
             .await
     }
 
+    /// Export the final state and the final blocks to a snapshot file on the node machine.
+    /// Returns the final slot of the snapshot.
+    pub async fn node_export_snapshot(&self, path: PathBuf) -> RpcResult<Slot> {
+        self.http_client
+            .request("node_export_snapshot", rpc_params![path])
+            .await
+    }
+
+    /// Export the archived final blocks of a slot range to a file on the node machine
+    pub async fn node_export_chain_data(
+        &self,
+        start: Slot,
+        end: Slot,
+        content: ExportContent,
+        format: ExportFormat,
+        path: PathBuf,
+    ) -> RpcResult<ExportStats> {
+        self.http_client
+            .request(
+                "node_export_chain_data",
+                rpc_params![start, end, content, format, path],
+            )
+            .await
+    }
+
+    /// Replace the log filter of the node by comma-separated directives
+    pub async fn node_set_log_filter(&self, directives: String) -> RpcResult<()> {
+        self.http_client
+            .request("node_set_log_filter", rpc_params![directives])
+            .await
+    }
+
+    /// Get the current log filter directives of the node
+    pub async fn node_get_log_filter(&self) -> RpcResult<String> {
+        self.http_client
+            .request("node_get_log_filter", rpc_params![])
+            .await
+    }
+
+    /// Override the safety interlock of the block production
+    pub async fn node_set_production_override(
+        &self,
+        production_override: ProductionOverride,
+    ) -> RpcResult<()> {
+        self.http_client
+            .request(
+                "node_set_production_override",
+                rpc_params![production_override],
+            )
+            .await
+    }
+
+    /// Get the override of the safety interlock of the block production
+    pub async fn node_get_production_override(&self) -> RpcResult<ProductionOverride> {
+        self.http_client
+            .request("node_get_production_override", rpc_params![])
+            .await
+    }
+
+    /// Accept the blockclique switch rejected for its depth
+    pub async fn node_accept_reorg(&self) -> RpcResult<ReorgAlert> {
+        self.http_client
+            .request("node_accept_reorg", rpc_params![])
+            .await
+    }
+
+    /// Get the latest `count` lines logged by the node, oldest first
+    pub async fn node_get_recent_logs(&self, count: usize) -> RpcResult<Vec<String>> {
+        self.http_client
+            .request("node_get_recent_logs", rpc_params![count])
+            .await
+    }
+
+    /// Read the configuration files again and apply the settings that can be changed without restart
+    pub async fn node_reload_config(&self) -> RpcResult<()> {
+        self.http_client
+            .request("node_reload_config", rpc_params![])
+            .await
+    }
+
+    /// Check the integrity of the archive and of the address index, removing the corrupted entries if `repair` is true
+    pub async fn node_check_storage(&self, repair: bool) -> RpcResult<StorageCheckReport> {
+        self.http_client
+            .request("node_check_storage", rpc_params![repair])
+            .await
+    }
+
     ////////////////
     // public-api //
     ////////////////
@@ -254,6 +430,32 @@ impl RpcClient {
         self.http_client.request("get_status", rpc_params![]).await
     }
 
+    /// Get the nodes connected to our node, with the bytes exchanged with each of them
+    pub async fn get_connected_nodes(&self) -> RpcResult<Vec<ConnectedNodeInfo>> {
+        self.http_client
+            .request("get_connected_nodes", rpc_params![])
+            .await
+    }
+
+    /// Get the software versions of the connected nodes, with the number of nodes running each of them
+    pub async fn get_network_versions(&self) -> RpcResult<NetworkVersions> {
+        self.http_client
+            .request("get_network_versions", rpc_params![])
+            .await
+    }
+
+    /// Check that the node is synced and connected to other nodes
+    pub async fn get_health(&self) -> RpcResult<NodeHealth> {
+        self.http_client.request("get_health", rpc_params![]).await
+    }
+
+    /// Get the size of the operation pool and the histogram of the fee per byte of its operations.
+    pub async fn get_operation_pool_stats(&self) -> RpcResult<OperationPoolStats> {
+        self.http_client
+            .request("get_operation_pool_stats", rpc_params![])
+            .await
+    }
+
     pub(crate) async fn _get_cliques(&self) -> RpcResult<Vec<Clique>> {
         self.http_client.request("get_cliques", rpc_params![]).await
     }
@@ -275,6 +477,16 @@ impl RpcClient {
             .await
     }
 
+    /// Returns the lifecycle status of a given list of operation(s) ID(s).
+    pub async fn get_operation_statuses(
+        &self,
+        operation_ids: Vec<OperationId>,
+    ) -> RpcResult<Vec<OperationStatusInfo>> {
+        self.http_client
+            .request("get_operation_statuses", rpc_params![operation_ids])
+            .await
+    }
+
     /// Returns endorsement(s) information associated to a given list of endorsement(s) ID(s)
     pub async fn get_endorsements(
         &self,
@@ -313,6 +525,60 @@ impl RpcClient {
             .await
     }
 
+    /// Get the stored blocks from `start` (included) to `end` (excluded) slot, sorted by slot
+    pub async fn get_blocks_by_slot_range(
+        &self,
+        start: Slot,
+        end: Slot,
+        pagination: Pagination,
+    ) -> RpcResult<PagedResult<BlockSummary>> {
+        self.http_client
+            .request(
+                "get_blocks_by_slot_range",
+                rpc_params![start, end, pagination],
+            )
+            .await
+    }
+
+    /// Get the stored blocks created by an address, sorted by slot
+    pub async fn get_blocks_by_creator(
+        &self,
+        address: Address,
+        pagination: Pagination,
+    ) -> RpcResult<PagedResult<BlockSummary>> {
+        self.http_client
+            .request("get_blocks_by_creator", rpc_params![address, pagination])
+            .await
+    }
+
+    /// Get the stored operations involving an address, sorted by expiration period
+    pub async fn get_operations_by_address(
+        &self,
+        address: Address,
+        pagination: Pagination,
+    ) -> RpcResult<PagedResult<OperationInfo>> {
+        self.http_client
+            .request(
+                "get_operations_by_address",
+                rpc_params![address, pagination],
+            )
+            .await
+    }
+
+    /// Get the final operations involving an address, sorted by slot
+    pub async fn get_final_operations_by_address(
+        &self,
+        address: Address,
+        pagination: Pagination,
+    ) -> RpcResult<PagedResult<FinalOperationEntry>> {
+        self.http_client
+            .request(
+                "get_final_operations_by_address",
+                rpc_params![address, pagination],
+            )
+            .await
+    }
+
     /// Get info by addresses
     pub async fn get_addresses(&self, addresses: Vec<Address>) -> RpcResult<Vec<AddressInfo>> {
         self.http_client
@@ -342,6 +608,41 @@ impl RpcClient {
             .await
     }
 
+    /// Validate an operation, add it to pool and propagate it if valid.
+    /// Returns whether the operation was accepted, with a reason code if it was rejected.
+    pub async fn submit_operation(
+        &self,
+        operation: OperationInput,
+    ) -> RpcResult<OperationSubmissionResult> {
+        self.http_client
+            .request("submit_operation", rpc_params![operation])
+            .await
+    }
+
+    /// Get the final balance of an address with a Merkle proof
+    pub async fn get_balance_proof(&self, address: Address) -> RpcResult<FinalBalanceProof> {
+        self.http_client
+            .request("get_balance_proof", rpc_params![address])
+            .await
+    }
+
+    /// Explain the fork choice status of a block
+    pub async fn explain_fork_choice(&self, block_id: BlockId) -> RpcResult<ForkChoiceExplanation> {
+        self.http_client
+            .request("explain_fork_choice", rpc_params![block_id])
+            .await
+    }
+
+    /// Checks a candidate block against the node's graph without inserting it.
+    pub async fn check_block_validity(
+        &self,
+        candidate: BlockCandidateInput,
+    ) -> RpcResult<BlockValidityReport> {
+        self.http_client
+            .request("check_block_validity", rpc_params![candidate])
+            .await
+    }
+
     /// execute read only bytecode
     pub async fn execute_read_only_bytecode(
         &self,
@@ -376,3 +677,38 @@ impl RpcClient {
             })
     }
 }
+
+/// Client of the WebSocket subscription API of a node (enabled by `api.enable_ws` in the node configuration)
+pub struct RpcWsClient {
+    ws_client: WsClient,
+}
+
+impl RpcWsClient {
+    /// Connect to the subscription API at `url` (ex: `ws://127.0.0.1:33036`)
+    pub async fn from_url(url: &str, http_config: &HttpConfig) -> RpcResult<RpcWsClient> {
+        let id_kind = match http_config.id_kind.as_str() {
+            "Number" => IdKind::Number,
+            "String" => IdKind::String,
+            _ => IdKind::Number,
+        };
+        let ws_client = WsClientBuilder::default()
+            .max_request_body_size(http_config.max_request_body_size)
+            .request_timeout(http_config.request_timeout.to_duration())
+            .max_concurrent_requests(http_config.max_concurrent_requests)
+            .id_format(id_kind)
+            .build(url)
+            .await?;
+        Ok(RpcWsClient { ws_client })
+    }
+
+    /// Subscribe to the headers of the new blocks produced or received by the node
+    pub async fn subscribe_new_blocks_headers(&self) -> RpcResult<Subscription<BlockHeader>> {
+        self.ws_client
+            .subscribe(
+                "subscribe_new_blocks_headers",
+                rpc_params![],
+                "unsubscribe_new_blocks_headers",
+            )
+            .await
+    }
+}
@@ -14,6 +14,10 @@ use nom::{
 };
 use thiserror::Error;
 
+mod shared;
+
+pub use shared::{share_bytes, with_shared_buffer};
+
 #[non_exhaustive]
 #[derive(Display, Error, Debug, Clone)]
 pub enum SerializeError {
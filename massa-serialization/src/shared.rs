@@ -0,0 +1,89 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Zero-copy deserialization from a shared receive buffer.
+//!
+//! The deserializers work on byte slices. While `with_shared_buffer` runs, the deserializers that keep
+//! a part of their input get it with `share_bytes`, which returns a view of the shared buffer instead of
+//! copying the part. Outside of `with_shared_buffer`, or for slices that are not part of the shared buffer,
+//! `share_bytes` copies the slice.
+//!
+//! A view keeps the whole buffer alive, as long as any object deserialized from it is kept,
+//! for instance in a pool or in the storage. So only the slices making up a large part of the buffer
+//! are shared: a small slice, like one operation out of the many of a message, is copied.
+
+use bytes::Bytes;
+use std::cell::RefCell;
+
+thread_local! {
+    /// buffer being deserialized on this thread by `with_shared_buffer`
+    static SHARED_BUFFER: RefCell<Option<Bytes>> = RefCell::new(None);
+}
+
+/// Restores the previous shared buffer, even if the deserialization panics
+struct SharedBufferGuard(Option<Bytes>);
+
+impl Drop for SharedBufferGuard {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        SHARED_BUFFER.with(|shared| *shared.borrow_mut() = previous);
+    }
+}
+
+/// Run `deserialize` on `buffer`, the parts of `buffer` kept by the deserializers being shared instead of copied
+pub fn with_shared_buffer<R>(buffer: &Bytes, deserialize: impl FnOnce(&[u8]) -> R) -> R {
+    let _guard =
+        SharedBufferGuard(SHARED_BUFFER.with(|shared| shared.borrow_mut().replace(buffer.clone())));
+    deserialize(buffer)
+}
+
+/// A slice is shared only if the shared buffer is at most this many times larger,
+/// which bounds the memory kept alive by a view to this many times its size
+const MAX_SHARED_BUFFER_RATIO: usize = 8;
+
+/// Get `slice` as `Bytes`: a view of the shared buffer if `slice` is a large enough part of it, a copy otherwise
+pub fn share_bytes(slice: &[u8]) -> Bytes {
+    SHARED_BUFFER.with(|shared| match shared.borrow().as_ref() {
+        Some(buffer)
+            if contains(buffer, slice)
+                && slice.len().saturating_mul(MAX_SHARED_BUFFER_RATIO) >= buffer.len() =>
+        {
+            buffer.slice_ref(slice)
+        }
+        _ => Bytes::copy_from_slice(slice),
+    })
+}
+
+/// Whether `slice` lies in the memory of `buffer`
+fn contains(buffer: &[u8], slice: &[u8]) -> bool {
+    let start = buffer.as_ptr() as usize;
+    let slice_start = slice.as_ptr() as usize;
+    slice_start >= start && slice_start + slice.len() <= start + buffer.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_share_bytes() {
+        let buffer = Bytes::from((0..32).collect::<Vec<u8>>());
+        let other = vec![1, 2, 3];
+        let (shared, copied, small) = with_shared_buffer(&buffer, |input| {
+            (
+                share_bytes(&input[1..5]),
+                share_bytes(&other),
+                share_bytes(&input[1..4]),
+            )
+        });
+        assert_eq!(shared, Bytes::from_static(&[1, 2, 3, 4]));
+        assert_eq!(shared.as_ptr(), buffer[1..].as_ptr());
+        assert_eq!(copied, Bytes::from_static(&[1, 2, 3]));
+        assert_ne!(copied.as_ptr(), other.as_ptr());
+        // too small a part of the buffer to keep it alive
+        assert_eq!(small, Bytes::from_static(&[1, 2, 3]));
+        assert_ne!(small.as_ptr(), buffer[1..].as_ptr());
+        // the buffer is not shared anymore
+        let copied = share_bytes(&buffer[1..5]);
+        assert_ne!(copied.as_ptr(), buffer[1..].as_ptr());
+    }
+}
@@ -0,0 +1,41 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Compares the verification of signatures one by one with their batch verification.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use massa_hash::Hash;
+use massa_signature::{verify_signature_batch, KeyPair, PublicKey, Signature};
+
+/// Build `count` triplets `(hash, signature, public_key)` signed by distinct keypairs
+fn prepare_batch(count: usize) -> Vec<(Hash, Signature, PublicKey)> {
+    (0..count)
+        .map(|index| {
+            let keypair = KeyPair::generate();
+            let hash = Hash::compute_from(&index.to_be_bytes());
+            let signature = keypair.sign(&hash).unwrap();
+            (hash, signature, keypair.get_public_key())
+        })
+        .collect()
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("signature verification");
+    for count in [16, 64, 256, 1024] {
+        let batch = prepare_batch(count);
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(BenchmarkId::new("one by one", count), &batch, |b, batch| {
+            b.iter(|| {
+                for (hash, signature, public_key) in black_box(batch) {
+                    public_key.verify_signature(hash, signature).unwrap();
+                }
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("batch", count), &batch, |b, batch| {
+            b.iter(|| verify_signature_batch(black_box(batch)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -5,9 +5,11 @@
 #![warn(unused_crate_dependencies)]
 mod error;
 mod signature_impl;
+mod signer;
 
 pub use error::MassaSignatureError;
 pub use signature_impl::{
     verify_signature_batch, KeyPair, PublicKey, PublicKeyDeserializer, Signature,
     SignatureDeserializer, PUBLIC_KEY_SIZE_BYTES, SECRET_KEY_BYTES_SIZE, SIGNATURE_SIZE_BYTES,
 };
+pub use signer::Signer;
@@ -18,6 +18,7 @@ use serde::{
 };
 use std::{borrow::Cow, cmp::Ordering, hash::Hasher, ops::Bound::Included};
 use std::{convert::TryInto, str::FromStr};
+use zeroize::Zeroizing;
 
 /// Size of a public key
 pub const PUBLIC_KEY_SIZE_BYTES: usize = ed25519_dalek::PUBLIC_KEY_LENGTH;
@@ -25,7 +26,11 @@ pub const PUBLIC_KEY_SIZE_BYTES: usize = ed25519_dalek::PUBLIC_KEY_LENGTH;
 pub const SECRET_KEY_BYTES_SIZE: usize = ed25519_dalek::SECRET_KEY_LENGTH;
 /// Size of a signature
 pub const SIGNATURE_SIZE_BYTES: usize = ed25519_dalek::SIGNATURE_LENGTH;
-/// `KeyPair` is used for signature and decryption
+/// `KeyPair` is used for signature and decryption.
+///
+/// Its secret key is zeroed when it is dropped, and the copies of it made for encoding
+/// (see `into_bytes` and `Display`) are zeroed as well once used.
+/// Code that only needs to sign should take a `Signer` instead.
 pub struct KeyPair(ed25519_dalek::Keypair);
 
 impl Clone for KeyPair {
@@ -44,7 +49,7 @@ const KEYPAIR_VERSION: u64 = 0;
 impl std::fmt::Display for KeyPair {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         let u64_serializer = U64VarIntSerializer::new();
-        let mut bytes = Vec::new();
+        let mut bytes = Zeroizing::new(Vec::new());
         u64_serializer
             .serialize(&KEYPAIR_VERSION, &mut bytes)
             .map_err(|_| std::fmt::Error)?;
@@ -53,7 +58,7 @@ impl std::fmt::Display for KeyPair {
             f,
             "{}{}",
             SECRET_PREFIX,
-            bs58::encode(bytes).with_check().into_string()
+            bs58::encode(&*bytes).with_check().into_string()
         )
     }
 }
@@ -72,12 +77,11 @@ impl FromStr for KeyPair {
             Some(prefix) if prefix == SECRET_PREFIX => {
                 let data = chars.collect::<String>();
                 let decoded_bs58_check =
-                    bs58::decode(data)
-                        .with_check(None)
-                        .into_vec()
-                        .map_err(|_| {
+                    Zeroizing::new(bs58::decode(data).with_check(None).into_vec().map_err(
+                        |_| {
                             MassaSignatureError::ParsingError(format!("bad secret key bs58: {}", s))
-                        })?;
+                        },
+                    )?);
                 let u64_deserializer = U64VarIntDeserializer::new(Included(0), Included(u64::MAX));
                 let (rest, _version) = u64_deserializer
                     .deserialize::<DeserializeError>(&decoded_bs58_check[..])
@@ -142,7 +146,7 @@ impl KeyPair {
         self.0.secret.as_bytes()
     }
 
-    /// Return the bytes representing the keypair
+    /// Return a copy of the bytes representing the keypair, zeroed when dropped
     ///
     /// # Example
     /// ```
@@ -150,8 +154,8 @@ impl KeyPair {
     /// let keypair = KeyPair::generate();
     /// let bytes = keypair.into_bytes();
     /// ```
-    pub fn into_bytes(&self) -> [u8; SECRET_KEY_BYTES_SIZE] {
-        self.0.secret.to_bytes()
+    pub fn into_bytes(&self) -> Zeroizing<[u8; SECRET_KEY_BYTES_SIZE]> {
+        Zeroizing::new(self.0.secret.to_bytes())
     }
 
     /// Convert a byte array of size `SECRET_KEY_BYTES_SIZE` to a `KeyPair`
@@ -0,0 +1,42 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Abstraction over the signing backends.
+//!
+//! Everything that signs on behalf of the node (block, endorsement and operation production)
+//! only needs the public key and a way to sign a hash, so that the secret key can live elsewhere
+//! than in memory (file keystore, remote signer, HSM).
+
+use crate::error::MassaSignatureError;
+use crate::signature_impl::{KeyPair, PublicKey, Signature};
+use massa_hash::Hash;
+
+/// Signs hashes with a secret key it does not need to expose
+pub trait Signer: Send + Sync {
+    /// Get the public key matching the signatures produced by this signer
+    fn get_public_key(&self) -> PublicKey;
+
+    /// Sign a hash
+    ///
+    /// # Example
+    ///  ```
+    /// # use massa_signature::{KeyPair, Signer};
+    /// # use massa_hash::Hash;
+    /// let keypair = KeyPair::generate();
+    /// let signer: &dyn Signer = &keypair;
+    /// let data = Hash::compute_from("Hello World!".as_bytes());
+    /// let signature = signer.sign(&data).unwrap();
+    /// signer.get_public_key().verify_signature(&data, &signature).unwrap();
+    /// ```
+    fn sign(&self, hash: &Hash) -> Result<Signature, MassaSignatureError>;
+}
+
+/// Signer holding the secret key in memory
+impl Signer for KeyPair {
+    fn get_public_key(&self) -> PublicKey {
+        KeyPair::get_public_key(self)
+    }
+
+    fn sign(&self, hash: &Hash) -> Result<Signature, MassaSignatureError> {
+        KeyPair::sign(self, hash)
+    }
+}
@@ -0,0 +1,31 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use massa_models::{amount::Amount, config::T0};
+use massa_time::MassaTime;
+
+/// Parameters of a simulated network
+#[derive(Debug, Clone)]
+pub struct SimulationConfig {
+    /// number of nodes, all of them producing blocks
+    pub node_count: usize,
+    /// time between two periods in the same thread, in simulated time
+    pub t0: MassaTime,
+    /// real time given to the nodes to process a slot before the clock moves to the next one
+    pub slot_processing_delay: MassaTime,
+    /// rolls of each node at genesis
+    pub initial_rolls: u64,
+    /// balance of each node at genesis
+    pub initial_balance: Amount,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        SimulationConfig {
+            node_count: 4,
+            t0: T0,
+            slot_processing_delay: MassaTime::from_millis(50),
+            initial_rolls: 100,
+            initial_balance: Amount::from_mantissa_scale(1_000_000, 0),
+        }
+    }
+}
@@ -0,0 +1,31 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use displaydoc::Display;
+use massa_models::error::ModelsError;
+use massa_time::TimeError;
+use thiserror::Error;
+
+/// simulation result
+pub type SimulationResult<T, E = SimulationError> = core::result::Result<T, E>;
+
+/// simulation error
+#[non_exhaustive]
+#[derive(Display, Error, Debug)]
+pub enum SimulationError {
+    /// there is no node {0} in the simulation
+    UnknownNode(usize),
+    /// node {0} is not running
+    NodeNotRunning(usize),
+    /// node {0} is already running
+    NodeAlreadyRunning(usize),
+    /// could not start node {0}: {1}
+    NodeStartError(usize, String),
+    /// the running nodes did not converge within {0} slots
+    ConvergenceTimeout(u64),
+    /// IO error: {0}
+    IOError(#[from] std::io::Error),
+    /// models error: {0}
+    ModelsError(#[from] ModelsError),
+    /// time error: {0}
+    TimeError(#[from] TimeError),
+}
@@ -0,0 +1,41 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! End-to-end testing of the consensus with full nodes running in the same process.
+//!
+//! A `Simulation` launches `node_count` nodes with all their workers (network, protocol, consensus, pool,
+//! execution, selector and factory), sharing the same genesis and staking from the start.
+//! * The nodes are wired over an `InMemoryTransport` routing their connections to each other,
+//!   which can be cut into partitions and healed.
+//! * All the workers read the time from the same `MockClock`: the simulation moves it slot by slot,
+//!   so that many periods run in a few seconds.
+//! * Nodes can be killed and restarted from the snapshot saved when they were stopped.
+//!
+//! The framework is only available with the `testing` feature, as the nodes rely on the testing
+//! establisher of the network and on the testing configurations of the workers.
+
+#![warn(missing_docs)]
+
+#[cfg(any(test, feature = "testing"))]
+mod config;
+#[cfg(any(test, feature = "testing"))]
+mod error;
+#[cfg(any(test, feature = "testing"))]
+mod node;
+#[cfg(any(test, feature = "testing"))]
+mod simulation;
+#[cfg(any(test, feature = "testing"))]
+mod transport;
+
+#[cfg(any(test, feature = "testing"))]
+pub use config::SimulationConfig;
+#[cfg(any(test, feature = "testing"))]
+pub use error::{SimulationError, SimulationResult};
+#[cfg(any(test, feature = "testing"))]
+pub use node::SimulatedNode;
+#[cfg(any(test, feature = "testing"))]
+pub use simulation::Simulation;
+#[cfg(any(test, feature = "testing"))]
+pub use transport::InMemoryTransport;
+
+#[cfg(test)]
+mod tests;
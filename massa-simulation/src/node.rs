@@ -0,0 +1,570 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Full node running in the simulation, wired as in the node binary except for the transport and the clock.
+
+use crate::{
+    config::SimulationConfig,
+    error::{SimulationError, SimulationResult},
+    transport::{InMemoryTransport, NODE_PORT},
+};
+use crossbeam_channel::Receiver;
+use massa_bootstrap::{export_snapshot, import_snapshot, BootstrapConfig};
+use massa_consensus_exports::{
+    events::ConsensusEvent, ConsensusChannels, ConsensusConfig, ConsensusController,
+    ConsensusManager,
+};
+use massa_consensus_worker::start_consensus_worker;
+use massa_execution_exports::{ExecutionConfig, ExecutionController, ExecutionManager};
+use massa_execution_worker::start_execution_worker;
+use massa_factory_exports::{FactoryChannels, FactoryConfig, FactoryManager};
+use massa_factory_worker::start_factory;
+use massa_final_state::{FinalState, FinalStateConfig};
+use massa_ledger_exports::LedgerConfig;
+use massa_ledger_worker::FinalLedger;
+use massa_models::{
+    address::Address,
+    block::BlockId,
+    config::constants::*,
+    node::NodeId,
+    prehash::PreHashMap,
+    slot_scheduler::{SlotScheduler, SlotTiming},
+    test_exports::MockSlotScheduler,
+    version::Version,
+};
+use massa_network_exports::{
+    test_exports::mock_establisher, BootstrapPeers, NetworkConfig, NetworkManager,
+};
+use massa_network_worker::start_network_controller;
+use massa_pool_exports::{PoolChannels, PoolConfig, PoolController, PoolManager};
+use massa_pool_worker::start_pool_controller;
+use massa_pos_exports::{SelectorConfig, SelectorManager};
+use massa_pos_worker::start_selector_worker;
+use massa_protocol_exports::{
+    tests::tools::create_protocol_config, ProtocolCommand, ProtocolCommandSender, ProtocolManager,
+    ProtocolReceivers, ProtocolSenders,
+};
+use massa_protocol_worker::start_protocol_controller;
+use massa_signature::KeyPair;
+use massa_storage::Storage;
+use massa_time::{MassaTime, MockClock};
+use massa_wallet::test_exports::create_test_wallet;
+use parking_lot::RwLock;
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
+use tokio::sync::{broadcast, mpsc};
+use tracing::{debug, warn};
+
+/// Parameters and resources shared by all the nodes of a simulation
+pub(crate) struct NodeContext {
+    /// parameters of the simulation
+    pub config: SimulationConfig,
+    /// time at which the simulated network started
+    pub genesis_timestamp: MassaTime,
+    /// initial ledger of all the nodes
+    pub initial_ledger_path: PathBuf,
+    /// initial rolls of all the nodes
+    pub initial_rolls_path: PathBuf,
+    /// simulated time, shared by all the nodes
+    pub clock: MockClock,
+    /// connections between the nodes
+    pub transport: InMemoryTransport,
+}
+
+impl NodeContext {
+    fn slot_timing(&self) -> SlotTiming {
+        SlotTiming {
+            thread_count: THREAD_COUNT,
+            t0: self.config.t0,
+            genesis_timestamp: self.genesis_timestamp,
+        }
+    }
+}
+
+/// Version announced by the simulated nodes and written in their snapshots
+pub(crate) fn get_version() -> Version {
+    Version::from_str("SIMU.1.0").expect("invalid simulation version")
+}
+
+/// Workers of a running node
+struct RunningNode {
+    final_state: Arc<RwLock<FinalState>>,
+    consensus_controller: Box<dyn ConsensusController>,
+    execution_controller: Box<dyn ExecutionController>,
+    pool_controller: Box<dyn PoolController>,
+    /// events of the consensus, kept open so that the consensus can notify them
+    _consensus_event_receiver: Receiver<ConsensusEvent>,
+    factory_manager: Box<dyn FactoryManager>,
+    protocol_manager: ProtocolManager,
+    consensus_manager: Box<dyn ConsensusManager>,
+    pool_manager: Box<dyn PoolManager>,
+    execution_manager: Box<dyn ExecutionManager>,
+    selector_manager: Box<dyn SelectorManager>,
+    network_manager: NetworkManager,
+}
+
+/// Node of a simulation, that can be stopped and restarted
+pub struct SimulatedNode {
+    /// index of the node in the simulation
+    index: usize,
+    /// key of the node, used both as its network identity and as its staking key
+    keypair: KeyPair,
+    /// directory of the disk ledgers and of the snapshot of the node
+    dir: PathBuf,
+    /// number of times the node was started
+    start_count: usize,
+    /// workers, if the node is running
+    running: Option<RunningNode>,
+}
+
+impl SimulatedNode {
+    /// Create a stopped node
+    pub(crate) fn new(index: usize, keypair: KeyPair, dir: PathBuf) -> Self {
+        SimulatedNode {
+            index,
+            keypair,
+            dir,
+            start_count: 0,
+            running: None,
+        }
+    }
+
+    /// Index of the node in the simulation
+    pub fn get_index(&self) -> usize {
+        self.index
+    }
+
+    /// Network identity of the node
+    pub fn get_node_id(&self) -> NodeId {
+        NodeId::new(self.keypair.get_public_key())
+    }
+
+    /// Staking address of the node
+    pub fn get_address(&self) -> Address {
+        Address::from_public_key(&self.keypair.get_public_key())
+    }
+
+    /// Returns true if the node is running
+    pub fn is_running(&self) -> bool {
+        self.running.is_some()
+    }
+
+    /// Controller of the consensus of the running node
+    pub fn get_consensus_controller(&self) -> SimulationResult<Box<dyn ConsensusController>> {
+        Ok(self.get_running()?.consensus_controller.clone())
+    }
+
+    /// Controller of the execution of the running node
+    pub fn get_execution_controller(&self) -> SimulationResult<Box<dyn ExecutionController>> {
+        Ok(self.get_running()?.execution_controller.clone())
+    }
+
+    /// Controller of the pool of the running node
+    pub fn get_pool_controller(&self) -> SimulationResult<Box<dyn PoolController>> {
+        Ok(self.get_running()?.pool_controller.clone())
+    }
+
+    /// Latest final block of each thread of the running node, with its period
+    pub fn get_latest_final_blocks(&self) -> SimulationResult<Vec<(BlockId, u64)>> {
+        Ok(self
+            .get_running()?
+            .consensus_controller
+            .get_latest_final_blocks_periods())
+    }
+
+    fn get_running(&self) -> SimulationResult<&RunningNode> {
+        self.running
+            .as_ref()
+            .ok_or(SimulationError::NodeNotRunning(self.index))
+    }
+
+    /// Snapshot saved when the node was stopped, the next start resumes from it
+    fn snapshot_path(&self) -> PathBuf {
+        self.dir.join("snapshot.bin")
+    }
+
+    /// Start the workers of the node.
+    /// The node resumes from its last snapshot if it was already started, and from the genesis otherwise.
+    pub(crate) async fn start(&mut self, context: &NodeContext) -> SimulationResult<()> {
+        if self.running.is_some() {
+            return Err(SimulationError::NodeAlreadyRunning(self.index));
+        }
+        let index = self.index;
+        let start_error = |err: String| SimulationError::NodeStartError(index, err);
+        self.start_count += 1;
+        let run_dir = self.dir.join(format!("run_{}", self.start_count));
+        std::fs::create_dir_all(&run_dir)?;
+        let storage = Storage::create_root();
+
+        // final state
+        let (selector_manager, selector_controller) = start_selector_worker(SelectorConfig {
+            genesis_address: Address::from_public_key(&GENESIS_KEY.get_public_key()),
+            ..Default::default()
+        })
+        .map_err(|err| start_error(err.to_string()))?;
+        let final_state_config = final_state_config(context, run_dir.join("ledger"));
+        let ledger = FinalLedger::new(final_state_config.ledger_config.clone());
+        let final_state = Arc::new(RwLock::new(
+            FinalState::new(
+                final_state_config,
+                Box::new(ledger),
+                selector_controller.clone(),
+            )
+            .map_err(|err| start_error(err.to_string()))?,
+        ));
+        let graph = if self.snapshot_path().is_file() {
+            debug!("simulated node {} resumes from its snapshot", index);
+            import_snapshot(
+                &snapshot_config(),
+                &self.snapshot_path(),
+                final_state.clone(),
+                get_version(),
+            )
+            .map_err(|err| start_error(err.to_string()))?
+            .graph
+        } else {
+            let mut final_state_guard = final_state.write();
+            final_state_guard
+                .ledger
+                .load_initial_ledger()
+                .map_err(|err| start_error(err.to_string()))?;
+            final_state_guard.pos_state.create_initial_cycle();
+            None
+        };
+
+        // network over the in-memory transport, knowing all the other nodes
+        let peers_file = run_dir.join("peers.json");
+        std::fs::write(&peers_file, "[]")?;
+        let network_config = network_config(context, index, &peers_file);
+        let (establisher, establisher_interface) = mock_establisher::new();
+        context.transport.attach(index, establisher_interface);
+        let initial_peers = BootstrapPeers(
+            (0..context.config.node_count)
+                .filter(|other| *other != index)
+                .map(|other| InMemoryTransport::get_node_address(other).into())
+                .collect(),
+        );
+        let (network_command_sender, network_event_receiver, network_manager, _, _) =
+            start_network_controller(
+                &network_config,
+                self.keypair.clone(),
+                establisher,
+                Some(initial_peers),
+                get_version(),
+                Box::new(context.clock.clone()),
+            )
+            .await
+            .map_err(|err| start_error(err.to_string()))?;
+
+        final_state
+            .write()
+            .compute_initial_draws()
+            .map_err(|err| start_error(err.to_string()))?;
+
+        let slot_scheduler: Box<dyn SlotScheduler> = Box::new(MockSlotScheduler::with_clock(
+            context.slot_timing(),
+            context.clock.clone(),
+        ));
+
+        // execution
+        let (execution_manager, execution_controller) = start_execution_worker(
+            ExecutionConfig {
+                t0: context.config.t0,
+                genesis_timestamp: context.genesis_timestamp,
+                ..Default::default()
+            },
+            final_state.clone(),
+            selector_controller.clone(),
+            slot_scheduler.clone(),
+        );
+
+        // pool
+        let pool_config = PoolConfig::default();
+        let pool_channels = PoolChannels {
+            pool_event_sender: broadcast::channel(pool_config.broadcast_events_capacity).0,
+            resource_status: Default::default(),
+        };
+        let (pool_manager, pool_controller) = start_pool_controller(
+            pool_config,
+            &storage,
+            execution_controller.clone(),
+            pool_channels.clone(),
+        );
+
+        // consensus
+        let (protocol_command_sender, protocol_command_receiver) =
+            mpsc::channel::<ProtocolCommand>(PROTOCOL_CONTROLLER_CHANNEL_SIZE);
+        let consensus_config = ConsensusConfig {
+            genesis_timestamp: context.genesis_timestamp,
+            t0: context.config.t0,
+            broadcast_enabled: false,
+            ..Default::default()
+        };
+        let (consensus_event_sender, consensus_event_receiver) =
+            crossbeam_channel::bounded(CHANNEL_SIZE);
+        let consensus_channels = ConsensusChannels {
+            execution_controller: execution_controller.clone(),
+            selector_controller: selector_controller.clone(),
+            pool_command_sender: pool_controller.clone(),
+            controller_event_tx: consensus_event_sender,
+            protocol_command_sender: ProtocolCommandSender(protocol_command_sender.clone()),
+            block_header_sender: broadcast::channel(
+                consensus_config.broadcast_blocks_headers_capacity,
+            )
+            .0,
+            block_sender: broadcast::channel(consensus_config.broadcast_blocks_capacity).0,
+            filled_block_sender: broadcast::channel(
+                consensus_config.broadcast_filled_blocks_capacity,
+            )
+            .0,
+            final_block_sender: broadcast::channel(
+                consensus_config.broadcast_final_blocks_capacity,
+            )
+            .0,
+            archive: None,
+            address_index: None,
+        };
+        let (consensus_controller, consensus_manager) = start_consensus_worker(
+            consensus_config,
+            consensus_channels,
+            graph,
+            storage.clone(),
+            slot_scheduler.clone(),
+        );
+
+        // protocol
+        let mut protocol_config = create_protocol_config();
+        protocol_config.genesis_timestamp = context.genesis_timestamp;
+        protocol_config.t0 = context.config.t0;
+        protocol_config.max_serialized_operations_size_per_block = MAX_BLOCK_SIZE as usize;
+        let protocol_senders = ProtocolSenders {
+            network_command_sender,
+            operation_sender: broadcast::channel(protocol_config.broadcast_operations_capacity).0,
+            partition_status: Default::default(),
+            sync_progress: Default::default(),
+            production_gate: Default::default(),
+        };
+        let protocol_receivers = ProtocolReceivers {
+            network_event_receiver,
+            protocol_command_receiver,
+            pool_event_receiver: pool_channels.pool_event_sender.subscribe(),
+        };
+        let protocol_manager = start_protocol_controller(
+            protocol_config,
+            protocol_receivers,
+            protocol_senders.clone(),
+            consensus_controller.clone(),
+            pool_controller.clone(),
+            storage.clone(),
+            None,
+            slot_scheduler.clone(),
+        )
+        .await
+        .map_err(|err| start_error(err.to_string()))?;
+
+        // factory, producing with the key of the node
+        let factory_config = FactoryConfig {
+            genesis_timestamp: context.genesis_timestamp,
+            t0: context.config.t0,
+            // the node produces as soon as it is started, even after a long stop
+            catch_up_max_lag_periods: u64::MAX,
+            ..Default::default()
+        };
+        let wallet = create_test_wallet(Some(PreHashMap::from_iter([(
+            self.get_address(),
+            self.keypair.clone(),
+        )])));
+        let factory_manager = start_factory(
+            factory_config,
+            Arc::new(RwLock::new(wallet)),
+            FactoryChannels {
+                selector: selector_controller,
+                consensus: consensus_controller.clone(),
+                execution: execution_controller.clone(),
+                pool: pool_controller.clone(),
+                protocol: ProtocolCommandSender(protocol_command_sender),
+                storage,
+                production_gate: protocol_senders.production_gate,
+                sync_progress: protocol_senders.sync_progress,
+                slot_scheduler,
+            },
+        );
+
+        self.running = Some(RunningNode {
+            final_state,
+            consensus_controller,
+            execution_controller,
+            pool_controller,
+            _consensus_event_receiver: consensus_event_receiver,
+            factory_manager,
+            protocol_manager,
+            consensus_manager,
+            pool_manager,
+            execution_manager,
+            selector_manager,
+            network_manager,
+        });
+        Ok(())
+    }
+
+    /// Stop the workers of the node in the order of the node binary,
+    /// saving a snapshot of its final state and final blocks for the next start
+    pub(crate) async fn stop(&mut self, transport: &InMemoryTransport) -> SimulationResult<()> {
+        let RunningNode {
+            final_state,
+            consensus_controller,
+            mut factory_manager,
+            protocol_manager,
+            mut consensus_manager,
+            mut pool_manager,
+            mut execution_manager,
+            mut selector_manager,
+            network_manager,
+            ..
+        } = self
+            .running
+            .take()
+            .ok_or(SimulationError::NodeNotRunning(self.index))?;
+
+        factory_manager.stop();
+        let network_event_receiver = match protocol_manager.stop().await {
+            Ok(receiver) => Some(receiver),
+            Err(err) => {
+                warn!(
+                    "simulated node {} protocol shutdown failed: {}",
+                    self.index, err
+                );
+                None
+            }
+        };
+
+        // no block is received anymore: the snapshot is consistent
+        let snapshot_path = self.snapshot_path();
+        let exported = tokio::task::spawn_blocking(move || {
+            export_snapshot(
+                &snapshot_path,
+                &final_state,
+                consensus_controller.as_ref(),
+                get_version(),
+            )
+        })
+        .await;
+        match exported {
+            Ok(Ok(slot)) => debug!("simulated node {} saved at slot {}", self.index, slot),
+            Ok(Err(err)) => warn!("simulated node {} could not be saved: {}", self.index, err),
+            Err(err) => warn!("simulated node {} snapshot panicked: {}", self.index, err),
+        }
+
+        consensus_manager.stop();
+        pool_manager.stop();
+        execution_manager.stop();
+        selector_manager.stop();
+        if let Some(network_event_receiver) = network_event_receiver {
+            if let Err(err) = network_manager.stop(network_event_receiver).await {
+                warn!(
+                    "simulated node {} network shutdown failed: {}",
+                    self.index, err
+                );
+            }
+        }
+        transport.detach(self.index);
+        Ok(())
+    }
+}
+
+/// Configuration of the final state, with its disk ledger at `disk_ledger_path`
+fn final_state_config(context: &NodeContext, disk_ledger_path: PathBuf) -> FinalStateConfig {
+    FinalStateConfig {
+        ledger_config: LedgerConfig {
+            initial_ledger_path: context.initial_ledger_path.clone(),
+            disk_ledger_path,
+            ..Default::default()
+        },
+        thread_count: THREAD_COUNT,
+        periods_per_cycle: PERIODS_PER_CYCLE,
+        initial_seed_string: INITIAL_DRAW_SEED.into(),
+        initial_rolls_path: context.initial_rolls_path.clone(),
+        ..Default::default()
+    }
+}
+
+/// Configuration of the network of the node of index `index`
+fn network_config(context: &NodeContext, index: usize, peers_file: &Path) -> NetworkConfig {
+    let mut network_config = NetworkConfig::scenarios_default(NODE_PORT, peers_file);
+    network_config.routable_ip = Some(InMemoryTransport::get_node_ip(index));
+    network_config.endorsement_count = ENDORSEMENT_COUNT;
+    network_config.max_ask_blocks = MAX_ASK_BLOCKS_PER_MESSAGE;
+    network_config.max_block_range_length = MAX_BLOCK_RANGE_LENGTH;
+    network_config.max_peer_advertise_length = MAX_ADVERTISE_LENGTH;
+    // the nodes reconnect within a few slots once a partition is healed
+    network_config.wakeup_interval = context.config.t0;
+    for peer_type_config in network_config.peer_types_config.values_mut() {
+        peer_type_config.max_in_connections = peer_type_config
+            .max_in_connections
+            .max(context.config.node_count);
+    }
+    network_config
+}
+
+/// Limits of the snapshots of the nodes, the ones of the node binary
+fn snapshot_config() -> BootstrapConfig {
+    BootstrapConfig {
+        bootstrap_list: Vec::new(),
+        bootstrap_whitelist_path: PathBuf::new(),
+        bootstrap_blacklist_path: PathBuf::new(),
+        bootstrap_token: None,
+        snapshot_import_path: None,
+        shutdown_snapshot_path: None,
+        shutdown_snapshot_max_age: MassaTime::from_millis(0),
+        server_stats_path: PathBuf::new(),
+        bootstrap_tokens: Vec::new(),
+        bind: None,
+        connect_timeout: MassaTime::from_millis(0),
+        read_timeout: MassaTime::from_millis(0),
+        write_timeout: MassaTime::from_millis(0),
+        read_error_timeout: MassaTime::from_millis(0),
+        write_error_timeout: MassaTime::from_millis(0),
+        retry_delay: MassaTime::from_millis(0),
+        max_ping: MassaTime::from_millis(0),
+        max_clock_delta: MassaTime::from_millis(0),
+        cache_duration: MassaTime::from_millis(0),
+        part_cache_max_age: MassaTime::from_millis(0),
+        part_cache_max_entries: 0,
+        max_simultaneous_bootstraps: 0,
+        per_ip_min_interval: MassaTime::from_millis(0),
+        ip_list_max_size: 0,
+        max_bytes_read_write: std::f64::INFINITY,
+        max_bytes_read_write_total: std::f64::INFINITY,
+        max_bootstrap_message_size: MAX_BOOTSTRAP_MESSAGE_SIZE,
+        max_datastore_key_length: MAX_DATASTORE_KEY_LENGTH,
+        randomness_size_bytes: BOOTSTRAP_RANDOMNESS_SIZE_BYTES,
+        thread_count: THREAD_COUNT,
+        periods_per_cycle: PERIODS_PER_CYCLE,
+        endorsement_count: ENDORSEMENT_COUNT,
+        max_advertise_length: MAX_ADVERTISE_LENGTH,
+        max_bootstrap_blocks_length: MAX_BOOTSTRAP_BLOCKS,
+        max_bootstrap_error_length: MAX_BOOTSTRAP_ERROR_LENGTH,
+        max_bootstrap_final_state_parts_size: MAX_BOOTSTRAP_FINAL_STATE_PARTS_SIZE,
+        max_async_pool_changes: MAX_BOOTSTRAP_ASYNC_POOL_CHANGES,
+        max_async_pool_length: MAX_ASYNC_POOL_LENGTH,
+        max_async_message_data: MAX_ASYNC_MESSAGE_DATA,
+        max_operations_per_block: MAX_OPERATIONS_PER_BLOCK,
+        max_datastore_entry_count: MAX_DATASTORE_ENTRY_COUNT,
+        max_datastore_value_length: MAX_DATASTORE_VALUE_LENGTH,
+        max_function_name_length: MAX_FUNCTION_NAME_LENGTH,
+        max_ledger_changes_count: MAX_LEDGER_CHANGES_COUNT,
+        max_parameters_size: MAX_PARAMETERS_SIZE,
+        max_op_datastore_entry_count: MAX_OPERATION_DATASTORE_ENTRY_COUNT,
+        max_op_datastore_key_length: MAX_OPERATION_DATASTORE_KEY_LENGTH,
+        max_op_datastore_value_length: MAX_OPERATION_DATASTORE_VALUE_LENGTH,
+        max_changes_slot_count: FinalStateConfig::default().final_history_length as u64,
+        max_rolls_length: MAX_ROLLS_COUNT_LENGTH,
+        max_production_stats_length: MAX_PRODUCTION_STATS_LENGTH,
+        max_credits_length: MAX_DEFERRED_CREDITS_LENGTH,
+        max_executed_ops_length: MAX_EXECUTED_OPS_LENGTH,
+        max_ops_changes_length: MAX_EXECUTED_OPS_CHANGES_LENGTH,
+        consensus_bootstrap_part_size: CONSENSUS_BOOTSTRAP_PART_SIZE,
+    }
+}
@@ -0,0 +1,230 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use crate::{
+    config::SimulationConfig,
+    error::{SimulationError, SimulationResult},
+    node::{NodeContext, SimulatedNode},
+    transport::InMemoryTransport,
+};
+use massa_ledger_exports::LedgerEntry;
+use massa_models::{
+    address::Address,
+    block::BlockId,
+    config::THREAD_COUNT,
+    slot::Slot,
+    timeslots::{get_block_slot_timestamp, get_latest_block_slot_at_timestamp},
+};
+use massa_signature::KeyPair;
+use massa_time::{Clock, MassaTime, MockClock};
+use std::collections::{BTreeMap, HashMap};
+use tempfile::TempDir;
+use tracing::debug;
+
+/// Network of full nodes running in the same process.
+///
+/// The nodes share a simulated clock that only moves when the test advances it,
+/// and reach each other through an in-memory transport that can be partitioned.
+/// All the nodes have the same stake at genesis and produce blocks.
+pub struct Simulation {
+    /// resources shared by the nodes
+    context: NodeContext,
+    /// nodes, by index
+    nodes: Vec<SimulatedNode>,
+    /// directory of the genesis files and of the node directories, removed when the simulation is dropped
+    _dir: TempDir,
+}
+
+impl Simulation {
+    /// Create the genesis of the network and start all its nodes
+    pub async fn start(config: SimulationConfig) -> SimulationResult<Self> {
+        let dir = TempDir::new()?;
+        let keypairs: Vec<KeyPair> = (0..config.node_count)
+            .map(|_| KeyPair::generate())
+            .collect();
+
+        // every node starts with the same balance and rolls
+        let mut initial_ledger = HashMap::new();
+        let mut initial_rolls = BTreeMap::new();
+        for keypair in &keypairs {
+            let address = Address::from_public_key(&keypair.get_public_key());
+            initial_ledger.insert(
+                address,
+                LedgerEntry {
+                    balance: config.initial_balance,
+                    ..Default::default()
+                },
+            );
+            initial_rolls.insert(address, config.initial_rolls);
+        }
+        let initial_ledger_path = dir.path().join("initial_ledger.json");
+        std::fs::write(
+            &initial_ledger_path,
+            serde_json::to_string(&initial_ledger).expect("could not serialize initial ledger"),
+        )?;
+        let initial_rolls_path = dir.path().join("initial_rolls.json");
+        std::fs::write(
+            &initial_rolls_path,
+            serde_json::to_string(&initial_rolls).expect("could not serialize initial rolls"),
+        )?;
+
+        // the simulated time starts at genesis
+        let genesis_timestamp = MassaTime::now()?;
+        let context = NodeContext {
+            config,
+            genesis_timestamp,
+            initial_ledger_path,
+            initial_rolls_path,
+            clock: MockClock::new(genesis_timestamp),
+            transport: InMemoryTransport::new(),
+        };
+
+        let mut nodes = Vec::with_capacity(keypairs.len());
+        for (index, keypair) in keypairs.into_iter().enumerate() {
+            let node_dir = dir.path().join(format!("node_{}", index));
+            std::fs::create_dir_all(&node_dir)?;
+            let mut node = SimulatedNode::new(index, keypair, node_dir);
+            node.start(&context).await?;
+            nodes.push(node);
+        }
+        Ok(Simulation {
+            context,
+            nodes,
+            _dir: dir,
+        })
+    }
+
+    /// Simulated clock shared by the nodes
+    pub fn get_clock(&self) -> &MockClock {
+        &self.context.clock
+    }
+
+    /// In-memory transport between the nodes
+    pub fn get_transport(&self) -> &InMemoryTransport {
+        &self.context.transport
+    }
+
+    /// Latest slot reached by the simulated clock, `None` before the first slot
+    pub fn get_current_slot(&self) -> SimulationResult<Option<Slot>> {
+        Ok(get_latest_block_slot_at_timestamp(
+            THREAD_COUNT,
+            self.context.config.t0,
+            self.context.genesis_timestamp,
+            self.context.clock.now()?,
+        )?)
+    }
+
+    /// Node of index `index`
+    pub fn node(&self, index: usize) -> SimulationResult<&SimulatedNode> {
+        self.nodes
+            .get(index)
+            .ok_or(SimulationError::UnknownNode(index))
+    }
+
+    /// All the nodes, running or not
+    pub fn nodes(&self) -> &[SimulatedNode] {
+        &self.nodes
+    }
+
+    /// Move the simulated clock forward slot by slot,
+    /// giving the nodes `slot_processing_delay` of real time to process each slot
+    pub async fn advance_slots(&self, slot_count: u64) -> SimulationResult<()> {
+        for _ in 0..slot_count {
+            let next_slot = match self.get_current_slot()? {
+                Some(slot) => slot.get_next_slot(THREAD_COUNT)?,
+                None => Slot::new(0, 0),
+            };
+            let next_timestamp = get_block_slot_timestamp(
+                THREAD_COUNT,
+                self.context.config.t0,
+                self.context.genesis_timestamp,
+                next_slot,
+            )?;
+            self.context.clock.set_now(next_timestamp);
+            tokio::time::sleep(self.context.config.slot_processing_delay.to_duration()).await;
+        }
+        Ok(())
+    }
+
+    /// Advance the clock until all the running nodes have the same latest final blocks,
+    /// at or after `min_final_period` in every thread.
+    ///
+    /// Returns the common latest final blocks,
+    /// or `SimulationError::ConvergenceTimeout` if they differ after `max_slots` slots.
+    pub async fn wait_for_convergence(
+        &self,
+        min_final_period: u64,
+        max_slots: u64,
+    ) -> SimulationResult<Vec<(BlockId, u64)>> {
+        for _ in 0..=max_slots {
+            if let Some(final_blocks) = self.get_common_final_blocks()? {
+                if final_blocks
+                    .iter()
+                    .all(|(_, period)| *period >= min_final_period)
+                {
+                    return Ok(final_blocks);
+                }
+            }
+            self.advance_slots(1).await?;
+        }
+        Err(SimulationError::ConvergenceTimeout(max_slots))
+    }
+
+    /// Latest final blocks of the running nodes if they are all the same
+    pub fn get_common_final_blocks(&self) -> SimulationResult<Option<Vec<(BlockId, u64)>>> {
+        let mut common: Option<Vec<(BlockId, u64)>> = None;
+        for node in self.nodes.iter().filter(|node| node.is_running()) {
+            let final_blocks = node.get_latest_final_blocks()?;
+            if let Some(common) = &common {
+                if *common != final_blocks {
+                    debug!(
+                        "simulated node {} final blocks differ: {:?}",
+                        node.get_index(),
+                        final_blocks
+                    );
+                    return Ok(None);
+                }
+            } else {
+                common = Some(final_blocks);
+            }
+        }
+        Ok(common)
+    }
+
+    /// Split the nodes into partitions that cannot reach each other,
+    /// the nodes that are not listed being put together in another partition
+    pub fn partition(&self, partitions: &[&[usize]]) {
+        self.context.transport.partition(partitions);
+    }
+
+    /// Remove the partitions
+    pub fn heal(&self) {
+        self.context.transport.heal();
+    }
+
+    /// Stop the node of index `index`, saving its state for a later restart
+    pub async fn kill(&mut self, index: usize) -> SimulationResult<()> {
+        let node = self
+            .nodes
+            .get_mut(index)
+            .ok_or(SimulationError::UnknownNode(index))?;
+        node.stop(&self.context.transport).await
+    }
+
+    /// Start again the node of index `index` from the state it had when it was stopped.
+    /// It then gets the blocks it missed from the other nodes.
+    pub async fn restart(&mut self, index: usize) -> SimulationResult<()> {
+        let node = self
+            .nodes
+            .get_mut(index)
+            .ok_or(SimulationError::UnknownNode(index))?;
+        node.start(&self.context).await
+    }
+
+    /// Stop all the running nodes
+    pub async fn stop(mut self) -> SimulationResult<()> {
+        for node in self.nodes.iter_mut().filter(|node| node.is_running()) {
+            node.stop(&self.context.transport).await?;
+        }
+        Ok(())
+    }
+}
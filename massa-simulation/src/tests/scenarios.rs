@@ -0,0 +1,96 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use crate::{Simulation, SimulationConfig};
+use serial_test::serial;
+
+/// slots given to the nodes to agree on their final blocks, about 10 periods
+const MAX_CONVERGENCE_SLOTS: u64 = 320;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+#[serial]
+async fn test_nodes_converge() {
+    let simulation = Simulation::start(SimulationConfig::default())
+        .await
+        .unwrap();
+
+    let final_blocks = simulation
+        .wait_for_convergence(2, MAX_CONVERGENCE_SLOTS)
+        .await
+        .expect("the nodes did not converge");
+    assert!(final_blocks.iter().all(|(_, period)| *period >= 2));
+    assert!(simulation.get_transport().get_link_count() > 0);
+
+    simulation.stop().await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+#[serial]
+async fn test_nodes_converge_after_partition() {
+    let simulation = Simulation::start(SimulationConfig::default())
+        .await
+        .unwrap();
+    simulation
+        .wait_for_convergence(1, MAX_CONVERGENCE_SLOTS)
+        .await
+        .expect("the nodes did not converge before the partition");
+
+    // the isolated node is cut for too short a time to finalize its own blocks
+    simulation.partition(&[&[3]]);
+    simulation.advance_slots(16).await.unwrap();
+    let highest_period = simulation
+        .node(0)
+        .unwrap()
+        .get_latest_final_blocks()
+        .unwrap()
+        .iter()
+        .map(|(_, period)| *period)
+        .max()
+        .unwrap();
+
+    simulation.heal();
+    simulation
+        .wait_for_convergence(highest_period + 1, MAX_CONVERGENCE_SLOTS)
+        .await
+        .expect("the nodes did not converge after the partition was healed");
+
+    simulation.stop().await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+#[serial]
+async fn test_node_catches_up_after_restart() {
+    let mut simulation = Simulation::start(SimulationConfig::default())
+        .await
+        .unwrap();
+    simulation
+        .wait_for_convergence(1, MAX_CONVERGENCE_SLOTS)
+        .await
+        .expect("the nodes did not converge before the restart");
+
+    // the other nodes keep finalizing blocks while the node is stopped
+    simulation.kill(3).await.unwrap();
+    assert!(!simulation.node(3).unwrap().is_running());
+    assert!(simulation
+        .node(3)
+        .unwrap()
+        .get_latest_final_blocks()
+        .is_err());
+    simulation.advance_slots(32).await.unwrap();
+    let final_blocks = simulation
+        .get_common_final_blocks()
+        .unwrap()
+        .expect("the running nodes diverged");
+    let highest_period = final_blocks
+        .iter()
+        .map(|(_, period)| *period)
+        .max()
+        .unwrap();
+
+    simulation.restart(3).await.unwrap();
+    simulation
+        .wait_for_convergence(highest_period + 1, MAX_CONVERGENCE_SLOTS)
+        .await
+        .expect("the restarted node did not catch up");
+
+    simulation.stop().await.unwrap();
+}
@@ -0,0 +1,216 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! In-memory transport between the simulated nodes.
+//!
+//! Each node uses the testing establisher of the network. The connection attempts of a node are routed
+//! to the listener of the node owning the dialed IP, and the bytes are then piped between the two duplex streams.
+//! A connection is refused if the dialed node is not running or is in another partition,
+//! and the links crossing partitions are cut when the partitions are set.
+
+use massa_network_exports::test_exports::mock_establisher::{
+    MockEstablisherInterface, MockListenerHandle, ReadHalf, WriteHalf,
+};
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::Arc,
+};
+use tokio::{sync::oneshot, task::JoinHandle};
+use tracing::debug;
+
+/// Port the simulated nodes listen on
+pub(crate) const NODE_PORT: u16 = 31244;
+
+/// Established connection between two nodes
+struct Link {
+    /// nodes at both ends, the dialing one first
+    nodes: (usize, usize),
+    /// task piping the bytes between the two nodes, aborted to cut the link
+    pipe_handle: JoinHandle<()>,
+}
+
+#[derive(Default)]
+struct TransportState {
+    /// listener of each running node, by IP
+    listeners: HashMap<IpAddr, (usize, MockListenerHandle)>,
+    /// partition of each node, the nodes without partition are in the same one
+    partitions: HashMap<usize, usize>,
+    /// established links
+    links: Vec<Link>,
+    /// task routing the connection attempts of each running node
+    routers: HashMap<usize, JoinHandle<()>>,
+}
+
+impl TransportState {
+    fn can_reach(&self, from: usize, to: usize) -> bool {
+        self.partitions.get(&from) == self.partitions.get(&to)
+    }
+
+    /// Cut the links for which `predicate` holds
+    fn cut_links<F>(&mut self, predicate: F)
+    where
+        F: Fn(&Link) -> bool,
+    {
+        self.links.retain(|link| {
+            if predicate(link) {
+                link.pipe_handle.abort();
+                false
+            } else {
+                true
+            }
+        });
+    }
+}
+
+/// Routes the connections between the simulated nodes.
+/// Clones share the same routes.
+#[derive(Clone, Default)]
+pub struct InMemoryTransport {
+    state: Arc<Mutex<TransportState>>,
+}
+
+impl InMemoryTransport {
+    /// Create a transport without any node
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// IP of the node of index `index`
+    pub fn get_node_ip(index: usize) -> IpAddr {
+        IpAddr::V4(Ipv4Addr::new(
+            200,
+            200,
+            (index / 250) as u8,
+            (index % 250 + 1) as u8,
+        ))
+    }
+
+    /// Address the node of index `index` listens on
+    pub fn get_node_address(index: usize) -> SocketAddr {
+        SocketAddr::new(Self::get_node_ip(index), NODE_PORT)
+    }
+
+    /// Make a running node reachable and route its connection attempts.
+    ///
+    /// # Arguments
+    /// * `index`: index of the node
+    /// * `interface`: interface of the establisher given to the network worker of the node
+    pub fn attach(&self, index: usize, mut interface: MockEstablisherInterface) {
+        let listener = interface
+            .get_listener_handle()
+            .expect("the listener of the establisher was already taken");
+        let transport = self.clone();
+        let router_handle = tokio::spawn(async move {
+            // ends when the network worker of the node is stopped
+            while let Ok((read, write, addr, accept_tx)) =
+                interface.wait_connection_attempt_from_controller().await
+            {
+                transport.route(index, read, write, addr, accept_tx);
+            }
+        });
+        let mut state = self.state.lock();
+        state
+            .listeners
+            .insert(Self::get_node_ip(index), (index, listener));
+        if let Some(previous) = state.routers.insert(index, router_handle) {
+            previous.abort();
+        }
+    }
+
+    /// Make a node unreachable and cut all its links, once it is stopped
+    pub fn detach(&self, index: usize) {
+        let mut state = self.state.lock();
+        state.listeners.remove(&Self::get_node_ip(index));
+        if let Some(router_handle) = state.routers.remove(&index) {
+            router_handle.abort();
+        }
+        state.cut_links(|link| link.nodes.0 == index || link.nodes.1 == index);
+    }
+
+    /// Split the nodes into partitions that cannot reach each other, cutting the links between them.
+    /// The nodes that are not listed are put together in another partition.
+    pub fn partition(&self, partitions: &[&[usize]]) {
+        let mut state = self.state.lock();
+        state.partitions = partitions
+            .iter()
+            .enumerate()
+            .flat_map(|(partition, nodes)| nodes.iter().map(move |node| (*node, partition)))
+            .collect();
+        let partitions = state.partitions.clone();
+        state.cut_links(|link| partitions.get(&link.nodes.0) != partitions.get(&link.nodes.1));
+    }
+
+    /// Remove the partitions, the nodes reconnect by themselves
+    pub fn heal(&self) {
+        self.state.lock().partitions.clear();
+    }
+
+    /// Number of links currently established
+    pub fn get_link_count(&self) -> usize {
+        let mut state = self.state.lock();
+        state.links.retain(|link| !link.pipe_handle.is_finished());
+        state.links.len()
+    }
+
+    /// Route a connection attempt of the node `from` to the node listening on `addr`
+    fn route(
+        &self,
+        from: usize,
+        mut from_read: ReadHalf,
+        mut from_write: WriteHalf,
+        addr: SocketAddr,
+        accept_tx: oneshot::Sender<bool>,
+    ) {
+        let target = {
+            let state = self.state.lock();
+            state
+                .listeners
+                .get(&addr.ip())
+                .filter(|(to, _)| state.can_reach(from, *to))
+                .cloned()
+        };
+        let (to, listener) = match target {
+            Some(target) => target,
+            None => {
+                debug!("simulated node {} could not reach {}", from, addr);
+                let _ = accept_tx.send(false);
+                return;
+            }
+        };
+        let transport = self.clone();
+        tokio::spawn(async move {
+            let (mut to_read, mut to_write) = match listener
+                .connect_to_controller(&SocketAddr::new(
+                    InMemoryTransport::get_node_ip(from),
+                    NODE_PORT,
+                ))
+                .await
+            {
+                Ok(halves) => halves,
+                Err(err) => {
+                    debug!("simulated node {} refused a connection: {}", to, err);
+                    let _ = accept_tx.send(false);
+                    return;
+                }
+            };
+            // a partition may have been set while connecting
+            let mut state = transport.state.lock();
+            if !state.can_reach(from, to) || accept_tx.send(true).is_err() {
+                return;
+            }
+            let pipe_handle = tokio::spawn(async move {
+                // the link is closed on both sides as soon as one direction is
+                tokio::select! {
+                    _ = tokio::io::copy(&mut from_read, &mut to_write) => {},
+                    _ = tokio::io::copy(&mut to_read, &mut from_write) => {},
+                }
+            });
+            state.links.retain(|link| !link.pipe_handle.is_finished());
+            state.links.push(Link {
+                nodes: (from, to),
+                pipe_handle,
+            });
+        });
+    }
+}
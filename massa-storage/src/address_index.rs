@@ -0,0 +1,165 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Persistent index of the final operations by address.
+//!
+//! When a block becomes final, each of its operations is indexed under every address it involves
+//! as creator, recipient or call target. The entries are stored in a `RocksDB` database with the key
+//! `address | slot | operation id` and the block id as value, so that the final operations of an address
+//! are read in slot order by a prefix scan instead of going through the blocks.
+
+use displaydoc::Display;
+use massa_models::{
+    address::{Address, ADDRESS_SIZE_BYTES},
+    api::FinalOperationEntry,
+    block::BlockId,
+    operation::{OperationId, WrappedOperation, OPERATION_ID_SIZE_BYTES},
+    slot::{Slot, SLOT_KEY_SIZE},
+};
+use rocksdb::{Direction, IteratorMode, Options, WriteBatch, DB};
+use std::convert::TryInto;
+use std::path::Path;
+use std::sync::Arc;
+use thiserror::Error;
+
+const KEY_SIZE: usize = ADDRESS_SIZE_BYTES + SLOT_KEY_SIZE + OPERATION_ID_SIZE_BYTES;
+
+/// Address index errors
+#[non_exhaustive]
+#[derive(Display, Error, Debug)]
+pub enum AddressIndexError {
+    /// database error: {0}
+    DatabaseError(#[from] rocksdb::Error),
+    /// corrupted address index entry: {0}
+    CorruptedEntry(String),
+}
+
+/// Index of the final operations by involved address.
+/// Clones share the same database.
+#[derive(Clone)]
+pub struct AddressIndex {
+    db: Arc<DB>,
+}
+
+impl std::fmt::Debug for AddressIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "AddressIndex {{ path: {} }}", self.db.path().display())
+    }
+}
+
+impl AddressIndex {
+    /// Open the index at `path`, creating it if needed
+    pub fn open(path: &Path) -> Result<Self, AddressIndexError> {
+        let mut db_opts = Options::default();
+        db_opts.create_if_missing(true);
+        Ok(AddressIndex {
+            db: Arc::new(DB::open(&db_opts, path)?),
+        })
+    }
+
+    fn entry_key(address: &Address, slot: &Slot, operation_id: &OperationId) -> Vec<u8> {
+        let mut key = Vec::with_capacity(KEY_SIZE);
+        key.extend(address.to_bytes());
+        key.extend(slot.to_bytes_key());
+        key.extend(operation_id.to_bytes());
+        key
+    }
+
+    fn parse_entry(key: &[u8], value: &[u8]) -> Result<FinalOperationEntry, AddressIndexError> {
+        if key.len() != KEY_SIZE {
+            return Err(AddressIndexError::CorruptedEntry(
+                "invalid key length".to_string(),
+            ));
+        }
+        let slot_key: &[u8; SLOT_KEY_SIZE] = key
+            [ADDRESS_SIZE_BYTES..ADDRESS_SIZE_BYTES + SLOT_KEY_SIZE]
+            .try_into()
+            .expect("critical: slice length checked above");
+        let operation_id: &[u8; OPERATION_ID_SIZE_BYTES] = key
+            [ADDRESS_SIZE_BYTES + SLOT_KEY_SIZE..]
+            .try_into()
+            .expect("critical: slice length checked above");
+        let block_id = value
+            .try_into()
+            .map_err(|_| AddressIndexError::CorruptedEntry("invalid block id".to_string()))?;
+        Ok(FinalOperationEntry {
+            operation_id: OperationId::from_bytes(operation_id),
+            block_id: BlockId::from_bytes(block_id),
+            slot: Slot::from_bytes_key(slot_key),
+        })
+    }
+
+    /// Index the operations of a final block under the addresses they involve
+    pub fn index_block(
+        &self,
+        block_id: &BlockId,
+        slot: &Slot,
+        operations: &[WrappedOperation],
+    ) -> Result<(), AddressIndexError> {
+        let mut batch = WriteBatch::default();
+        for operation in operations {
+            for address in operation.get_ledger_involved_addresses() {
+                batch.put(
+                    Self::entry_key(&address, slot, &operation.id),
+                    block_id.to_bytes(),
+                );
+            }
+        }
+        self.db.write(batch)?;
+        Ok(())
+    }
+
+    /// Check every entry of the index with `is_valid`, which is given the indexed address and the entry.
+    /// The entries that can not be decoded or are not valid are removed if `repair` is set.
+    ///
+    /// Returns the number of checked entries and the number of corrupted ones.
+    pub fn check_entries<E: From<AddressIndexError>>(
+        &self,
+        repair: bool,
+        mut is_valid: impl FnMut(&Address, &FinalOperationEntry) -> Result<bool, E>,
+    ) -> Result<(usize, usize), E> {
+        let mut checked = 0;
+        let mut corrupted = 0;
+        let mut batch = WriteBatch::default();
+        for item in self.db.iterator(IteratorMode::Start) {
+            let (key, value) = item.map_err(AddressIndexError::from)?;
+            checked += 1;
+            let valid = match Self::parse_entry(&key, &value) {
+                Ok(entry) => {
+                    let address: &[u8; ADDRESS_SIZE_BYTES] = key[..ADDRESS_SIZE_BYTES]
+                        .try_into()
+                        .expect("critical: key length checked when parsing");
+                    is_valid(&Address::from_bytes(address), &entry)?
+                }
+                Err(_) => false,
+            };
+            if !valid {
+                corrupted += 1;
+                batch.delete(key);
+            }
+        }
+        if repair {
+            self.db.write(batch).map_err(AddressIndexError::from)?;
+        }
+        Ok((checked, corrupted))
+    }
+
+    /// Get the final operations involving `address`, by ascending slot
+    pub fn get_operations(
+        &self,
+        address: &Address,
+    ) -> Result<Vec<FinalOperationEntry>, AddressIndexError> {
+        let prefix = address.to_bytes();
+        let mut entries = Vec::new();
+        for item in self
+            .db
+            .iterator(IteratorMode::From(prefix, Direction::Forward))
+        {
+            let (key, value) = item?;
+            if !key.starts_with(prefix) {
+                break;
+            }
+            entries.push(Self::parse_entry(&key, &value)?);
+        }
+        Ok(entries)
+    }
+}
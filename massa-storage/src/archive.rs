@@ -0,0 +1,588 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Persistent archive of the final blocks, kept by archival nodes.
+//!
+//! Every block becoming final is stored with its operations in a `RocksDB` database, keyed by slot,
+//! so that the full history can be served to the peers syncing from scratch.
+//! The operations of the oldest blocks can be trimmed to save space, their headers are then kept apart
+//! and the blocks are not served anymore. A node syncing without trusting a bootstrap server
+//! also stores the headers it validated apart, until it gets their operations.
+//! The archive directory also holds the snapshots of the final state taken during some cycles,
+//! the historical ledger states, in `snapshots/cycle_<cycle>.snapshot`.
+//! The hashes of the final state recorded at some slots are kept as well, to check the replays of the blocks against.
+
+use displaydoc::Display;
+use massa_hash::{Hash, HASH_SIZE_BYTES};
+use massa_models::{
+    block::{
+        ArchivedBlock, ArchivedBlockDeserializer, ArchivedBlockSerializer, BlockHeader,
+        BlockHeaderDeserializer, WrappedHeader,
+    },
+    operation::WrappedOperation,
+    slot::{Slot, SLOT_KEY_SIZE},
+    wrapped::{WrappedDeserializer, WrappedSerializer},
+};
+use massa_serialization::{DeserializeError, Deserializer, SerializeError, Serializer};
+use rocksdb::{
+    ColumnFamily, ColumnFamilyDescriptor, Direction, IteratorMode, Options, WriteBatch, DB,
+};
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+use std::path::PathBuf;
+use std::sync::Arc;
+use thiserror::Error;
+use tracing::warn;
+
+const BLOCKS_CF: &str = "blocks";
+const HEADERS_CF: &str = "headers";
+const STATE_HASHES_CF: &str = "state_hashes";
+const CF_ERROR: &str = "critical: rocksdb column family operation failed";
+const SNAPSHOTS_DIR: &str = "snapshots";
+const SNAPSHOT_PREFIX: &str = "cycle_";
+const SNAPSHOT_EXTENSION: &str = "snapshot";
+
+/// Archive errors
+#[non_exhaustive]
+#[derive(Display, Error, Debug)]
+pub enum ArchiveError {
+    /// database error: {0}
+    DatabaseError(#[from] rocksdb::Error),
+    /// io error: {0}
+    IoError(#[from] std::io::Error),
+    /// serialization error: {0}
+    SerializeError(#[from] SerializeError),
+    /// corrupted archive entry: {0}
+    CorruptedEntry(String),
+}
+
+/// Archive configuration
+#[derive(Debug, Clone)]
+pub struct ArchiveConfig {
+    /// directory of the archive
+    pub path: PathBuf,
+    /// thread count
+    pub thread_count: u8,
+    /// endorsement count
+    pub endorsement_count: u32,
+    /// max number of operations per block
+    pub max_operations_per_block: u32,
+    /// max length of a datastore value
+    pub max_datastore_value_length: u64,
+    /// max length of a function name
+    pub max_function_name_length: u16,
+    /// max size of the parameters of a call
+    pub max_parameters_size: u32,
+    /// max number of datastore entries in an operation
+    pub max_op_datastore_entry_count: u64,
+    /// max length of a datastore key in an operation
+    pub max_op_datastore_key_length: u8,
+    /// max length of a datastore value in an operation
+    pub max_op_datastore_value_length: u64,
+}
+
+/// Archive of the final blocks and of the cycle snapshots of the final state.
+/// Clones share the same database.
+#[derive(Clone)]
+pub struct Archive {
+    db: Arc<DB>,
+    path: PathBuf,
+    block_deserializer: Arc<ArchivedBlockDeserializer>,
+    header_deserializer: Arc<WrappedDeserializer<BlockHeader, BlockHeaderDeserializer>>,
+}
+
+/// Outcome of an integrity check of the archive
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ArchiveCheckStats {
+    /// number of checked blocks and trimmed headers
+    pub checked: usize,
+    /// number of corrupted ones
+    pub corrupted: usize,
+}
+
+/// Outcome of a pruning or trimming pass over the archive
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PruneStats {
+    /// number of blocks removed or trimmed
+    pub count: usize,
+    /// size of the removed entries, before compaction
+    pub removed_bytes: u64,
+}
+
+impl std::fmt::Debug for Archive {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Archive {{ path: {} }}", self.path.display())
+    }
+}
+
+impl Archive {
+    /// Open the archive at `config.path`, creating it if needed
+    pub fn open(config: ArchiveConfig) -> Result<Self, ArchiveError> {
+        std::fs::create_dir_all(config.path.join(SNAPSHOTS_DIR))?;
+        let mut db_opts = Options::default();
+        db_opts.create_if_missing(true);
+        db_opts.create_missing_column_families(true);
+        let db = DB::open_cf_descriptors(
+            &db_opts,
+            config.path.join("db"),
+            vec![
+                ColumnFamilyDescriptor::new(BLOCKS_CF, Options::default()),
+                ColumnFamilyDescriptor::new(HEADERS_CF, Options::default()),
+                ColumnFamilyDescriptor::new(STATE_HASHES_CF, Options::default()),
+            ],
+        )?;
+        Ok(Archive {
+            db: Arc::new(db),
+            block_deserializer: Arc::new(ArchivedBlockDeserializer::new(
+                config.thread_count,
+                config.endorsement_count,
+                config.max_operations_per_block,
+                config.max_datastore_value_length,
+                config.max_function_name_length,
+                config.max_parameters_size,
+                config.max_op_datastore_entry_count,
+                config.max_op_datastore_key_length,
+                config.max_op_datastore_value_length,
+            )),
+            header_deserializer: Arc::new(WrappedDeserializer::new(BlockHeaderDeserializer::new(
+                config.thread_count,
+                config.endorsement_count,
+            ))),
+            path: config.path,
+        })
+    }
+
+    fn blocks_cf(&self) -> &ColumnFamily {
+        self.db.cf_handle(BLOCKS_CF).expect(CF_ERROR)
+    }
+
+    fn headers_cf(&self) -> &ColumnFamily {
+        self.db.cf_handle(HEADERS_CF).expect(CF_ERROR)
+    }
+
+    fn state_hashes_cf(&self) -> &ColumnFamily {
+        self.db.cf_handle(STATE_HASHES_CF).expect(CF_ERROR)
+    }
+
+    /// Deserialize a header, returning it with its serialized length
+    fn deserialize_header(&self, value: &[u8]) -> Result<(WrappedHeader, usize), ArchiveError> {
+        let (rest, header) = self
+            .header_deserializer
+            .deserialize::<DeserializeError>(value)
+            .map_err(|err| ArchiveError::CorruptedEntry(format!("header: {}", err)))?;
+        Ok((header, value.len() - rest.len()))
+    }
+
+    fn deserialize_block(&self, key: &[u8], value: &[u8]) -> Result<ArchivedBlock, ArchiveError> {
+        let (rest, block) = self
+            .block_deserializer
+            .deserialize::<DeserializeError>(value)
+            .map_err(|err| ArchiveError::CorruptedEntry(format!("block: {}", err)))?;
+        if !rest.is_empty() || block.header.content.slot.to_bytes_key()[..] != *key {
+            return Err(ArchiveError::CorruptedEntry(format!(
+                "block {} does not match its key",
+                block.header.id
+            )));
+        }
+        Ok(block)
+    }
+
+    fn slot_from_key(key: &[u8]) -> Result<Slot, ArchiveError> {
+        let key: &[u8; SLOT_KEY_SIZE] = key
+            .try_into()
+            .map_err(|_| ArchiveError::CorruptedEntry("invalid block key".to_string()))?;
+        Ok(Slot::from_bytes_key(key))
+    }
+
+    /// Describe a block key in the logs, by its slot if it is valid
+    fn describe_key(key: &[u8]) -> String {
+        match Self::slot_from_key(key) {
+            Ok(slot) => format!("at slot {}", slot),
+            Err(_) => format!("with invalid key {:?}", key),
+        }
+    }
+
+    /// Store a final block, replacing the block or the header previously stored at the same slot if any
+    pub fn store_block(&self, block: &ArchivedBlock) -> Result<(), ArchiveError> {
+        let mut value = Vec::new();
+        ArchivedBlockSerializer::new().serialize(block, &mut value)?;
+        let key = block.header.content.slot.to_bytes_key();
+        let mut batch = WriteBatch::default();
+        batch.delete_cf(self.headers_cf(), key);
+        batch.put_cf(self.blocks_cf(), key, value);
+        self.db.write(batch)?;
+        Ok(())
+    }
+
+    /// Store the header of a final block whose operations are not known yet,
+    /// replacing the header previously stored at the same slot if any.
+    /// The block is not returned by `get_block` and `get_blocks` until it is stored with `store_block`.
+    pub fn store_header(&self, header: &WrappedHeader) -> Result<(), ArchiveError> {
+        let mut value = Vec::new();
+        WrappedSerializer::new().serialize(header, &mut value)?;
+        self.db
+            .put_cf(self.headers_cf(), header.content.slot.to_bytes_key(), value)?;
+        Ok(())
+    }
+
+    /// Get the block stored at `slot`
+    pub fn get_block(&self, slot: Slot) -> Result<Option<ArchivedBlock>, ArchiveError> {
+        let key = slot.to_bytes_key();
+        self.db
+            .get_cf(self.blocks_cf(), key)?
+            .map(|value| self.deserialize_block(&key, &value))
+            .transpose()
+    }
+
+    /// Get the header of the block at `slot`, whether its operations were trimmed or not
+    pub fn get_header(&self, slot: Slot) -> Result<Option<WrappedHeader>, ArchiveError> {
+        let key = slot.to_bytes_key();
+        let value = match self.db.get_cf(self.blocks_cf(), key)? {
+            Some(value) => value,
+            None => match self.db.get_cf(self.headers_cf(), key)? {
+                Some(value) => value,
+                None => return Ok(None),
+            },
+        };
+        let (header, _) = self.deserialize_header(&value)?;
+        if header.content.slot != slot {
+            return Err(ArchiveError::CorruptedEntry(format!(
+                "header {} does not match its key",
+                header.id
+            )));
+        }
+        Ok(Some(header))
+    }
+
+    /// Get at most `max_count` blocks stored in the slot range from `start` (included) to `end` (excluded), by ascending slot
+    pub fn get_blocks(
+        &self,
+        start: Slot,
+        end: Slot,
+        max_count: usize,
+    ) -> Result<Vec<ArchivedBlock>, ArchiveError> {
+        let start_key = start.to_bytes_key();
+        let end_key = end.to_bytes_key();
+        let mut blocks = Vec::new();
+        for item in self.db.iterator_cf(
+            self.blocks_cf(),
+            IteratorMode::From(&start_key, Direction::Forward),
+        ) {
+            let (key, value) = item?;
+            if blocks.len() >= max_count || key[..] >= end_key[..] {
+                break;
+            }
+            blocks.push(self.deserialize_block(&key, &value)?);
+        }
+        Ok(blocks)
+    }
+
+    /// Get at most `max_count` headers stored in the slot range from `start` (included) to `end` (excluded),
+    /// whether the operations of their blocks are stored or not, by ascending slot
+    pub fn get_headers(
+        &self,
+        start: Slot,
+        end: Slot,
+        max_count: usize,
+    ) -> Result<Vec<WrappedHeader>, ArchiveError> {
+        let mut headers = BTreeMap::new();
+        for cf in [self.blocks_cf(), self.headers_cf()] {
+            for (slot, header) in self.read_headers(cf, start, end, max_count)? {
+                headers.insert(slot, header);
+            }
+        }
+        Ok(headers.into_values().take(max_count).collect())
+    }
+
+    /// Get at most `max_count` headers stored without the operations of their blocks
+    /// in the slot range from `start` (included) to `end` (excluded), by ascending slot
+    pub fn get_headers_without_operations(
+        &self,
+        start: Slot,
+        end: Slot,
+        max_count: usize,
+    ) -> Result<Vec<WrappedHeader>, ArchiveError> {
+        Ok(self
+            .read_headers(self.headers_cf(), start, end, max_count)?
+            .into_iter()
+            .map(|(_, header)| header)
+            .collect())
+    }
+
+    /// Read at most `max_count` headers of a column family in a slot range, the serialized header being
+    /// at the start of the serialized blocks
+    fn read_headers(
+        &self,
+        cf: &ColumnFamily,
+        start: Slot,
+        end: Slot,
+        max_count: usize,
+    ) -> Result<Vec<(Slot, WrappedHeader)>, ArchiveError> {
+        let start_key = start.to_bytes_key();
+        let end_key = end.to_bytes_key();
+        let mut headers = Vec::new();
+        for item in self
+            .db
+            .iterator_cf(cf, IteratorMode::From(&start_key, Direction::Forward))
+        {
+            let (key, value) = item?;
+            if headers.len() >= max_count || key[..] >= end_key[..] {
+                break;
+            }
+            let slot = Self::slot_from_key(&key)?;
+            let (header, _) = self.deserialize_header(&value)?;
+            if header.content.slot != slot {
+                return Err(ArchiveError::CorruptedEntry(format!(
+                    "header {} does not match its key",
+                    header.id
+                )));
+            }
+            headers.push((slot, header));
+        }
+        Ok(headers)
+    }
+
+    /// Iterate over the stored blocks from `start` by ascending slot, trimmed blocks excluded.
+    /// The blocks are read from the database one at a time.
+    pub fn iter_blocks(
+        &self,
+        start: Slot,
+    ) -> impl Iterator<Item = Result<ArchivedBlock, ArchiveError>> + '_ {
+        let start_key = start.to_bytes_key();
+        self.db
+            .iterator_cf(
+                self.blocks_cf(),
+                IteratorMode::From(&start_key, Direction::Forward),
+            )
+            .map(move |item| {
+                let (key, value) = item?;
+                self.deserialize_block(&key, &value)
+            })
+    }
+
+    /// Get the slot of the oldest stored block
+    pub fn get_first_slot(&self) -> Result<Option<Slot>, ArchiveError> {
+        match self
+            .db
+            .iterator_cf(self.blocks_cf(), IteratorMode::Start)
+            .next()
+        {
+            Some(item) => Ok(Some(Self::slot_from_key(&item?.0)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get the slot of the latest stored block
+    pub fn get_last_slot(&self) -> Result<Option<Slot>, ArchiveError> {
+        match self
+            .db
+            .iterator_cf(self.blocks_cf(), IteratorMode::End)
+            .next()
+        {
+            Some(item) => Ok(Some(Self::slot_from_key(&item?.0)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Record the hash of the final state at the output of `slot`
+    pub fn store_state_hash(&self, slot: Slot, hash: &Hash) -> Result<(), ArchiveError> {
+        self.db
+            .put_cf(self.state_hashes_cf(), slot.to_bytes_key(), hash.to_bytes())?;
+        Ok(())
+    }
+
+    /// Get the recorded hashes of the final state, by slot
+    pub fn get_state_hashes(&self) -> Result<BTreeMap<Slot, Hash>, ArchiveError> {
+        let mut hashes = BTreeMap::new();
+        for item in self
+            .db
+            .iterator_cf(self.state_hashes_cf(), IteratorMode::Start)
+        {
+            let (key, value) = item?;
+            let hash_bytes: &[u8; HASH_SIZE_BYTES] = value[..].try_into().map_err(|_| {
+                ArchiveError::CorruptedEntry(format!(
+                    "invalid state hash {}",
+                    Self::describe_key(&key)
+                ))
+            })?;
+            hashes.insert(Self::slot_from_key(&key)?, Hash::from_bytes(hash_bytes));
+        }
+        Ok(hashes)
+    }
+
+    /// Remove at most `max_count` blocks, trimmed headers and recorded state hashes of the periods before `period`, oldest first
+    pub fn prune_blocks_before(
+        &self,
+        period: u64,
+        max_count: usize,
+    ) -> Result<PruneStats, ArchiveError> {
+        let end_key = Slot::new(period, 0).to_bytes_key();
+        let mut batch = WriteBatch::default();
+        let mut stats = PruneStats::default();
+        for cf in [self.headers_cf(), self.blocks_cf(), self.state_hashes_cf()] {
+            for item in self.db.iterator_cf(cf, IteratorMode::Start) {
+                let (key, value) = item?;
+                if stats.count >= max_count || key[..] >= end_key[..] {
+                    break;
+                }
+                batch.delete_cf(cf, key);
+                stats.count += 1;
+                stats.removed_bytes += value.len() as u64;
+            }
+        }
+        self.db.write(batch)?;
+        Ok(stats)
+    }
+
+    /// Trim the operations of at most `max_count` blocks of the periods before `period`, oldest first.
+    /// Their headers are kept, but the blocks are not returned by `get_block` and `get_blocks` anymore.
+    pub fn trim_blocks_before(
+        &self,
+        period: u64,
+        max_count: usize,
+    ) -> Result<PruneStats, ArchiveError> {
+        let end_key = Slot::new(period, 0).to_bytes_key();
+        let mut batch = WriteBatch::default();
+        let mut stats = PruneStats::default();
+        for item in self.db.iterator_cf(self.blocks_cf(), IteratorMode::Start) {
+            let (key, value) = item?;
+            if stats.count >= max_count || key[..] >= end_key[..] {
+                break;
+            }
+            // the serialized header is at the start of the serialized block
+            let (_, header_len) = self.deserialize_header(&value)?;
+            batch.put_cf(self.headers_cf(), &key, &value[..header_len]);
+            batch.delete_cf(self.blocks_cf(), &key);
+            stats.count += 1;
+            stats.removed_bytes += (value.len() - header_len) as u64;
+        }
+        self.db.write(batch)?;
+        Ok(stats)
+    }
+
+    /// Size of the files of the archive database, as estimated by `RocksDB`
+    pub fn get_disk_size(&self) -> Result<u64, ArchiveError> {
+        let mut size = 0;
+        for cf in [self.blocks_cf(), self.headers_cf(), self.state_hashes_cf()] {
+            size += self
+                .db
+                .property_int_value_cf(cf, "rocksdb.total-sst-files-size")?
+                .unwrap_or(0);
+        }
+        Ok(size)
+    }
+
+    /// Compact the archive database so that the space of the removed entries is reclaimed
+    pub fn compact(&self) {
+        for cf in [self.blocks_cf(), self.headers_cf(), self.state_hashes_cf()] {
+            self.db.compact_range_cf(cf, None::<&[u8]>, None::<&[u8]>);
+        }
+    }
+
+    /// Check that every stored block and trimmed header decodes, matches its slot key and is validly signed,
+    /// and that the operations of the blocks are the ones committed to by their headers.
+    /// The corrupted entries are removed if `repair` is set.
+    pub fn check(&self, repair: bool) -> Result<ArchiveCheckStats, ArchiveError> {
+        let mut stats = ArchiveCheckStats::default();
+        let mut batch = WriteBatch::default();
+        for item in self.db.iterator_cf(self.blocks_cf(), IteratorMode::Start) {
+            let (key, value) = item?;
+            stats.checked += 1;
+            if let Some(problem) = self.check_block(&key, &value) {
+                warn!(
+                    "corrupted archived block {}: {}",
+                    Self::describe_key(&key),
+                    problem
+                );
+                stats.corrupted += 1;
+                batch.delete_cf(self.blocks_cf(), key);
+            }
+        }
+        for item in self.db.iterator_cf(self.headers_cf(), IteratorMode::Start) {
+            let (key, value) = item?;
+            stats.checked += 1;
+            if let Some(problem) = self.check_header(&key, &value) {
+                warn!(
+                    "corrupted archived header {}: {}",
+                    Self::describe_key(&key),
+                    problem
+                );
+                stats.corrupted += 1;
+                batch.delete_cf(self.headers_cf(), key);
+            }
+        }
+        if repair {
+            self.db.write(batch)?;
+        }
+        Ok(stats)
+    }
+
+    /// Describe the corruption of a block entry, if any
+    fn check_block(&self, key: &[u8], value: &[u8]) -> Option<String> {
+        let block = match self.deserialize_block(key, value) {
+            Ok(block) => block,
+            Err(err) => return Some(err.to_string()),
+        };
+        if !block.check_operations() {
+            return Some(format!(
+                "the operations of block {} do not match its header",
+                block.header.id
+            ));
+        }
+        block
+            .header
+            .verify_signature()
+            .and_then(|_| WrappedOperation::verify_signatures_batch(&block.operations))
+            .err()
+            .map(|err| format!("block {}: {}", block.header.id, err))
+    }
+
+    /// Describe the corruption of a trimmed header entry, if any
+    fn check_header(&self, key: &[u8], value: &[u8]) -> Option<String> {
+        let (header, header_len) = match self.deserialize_header(value) {
+            Ok(header) => header,
+            Err(err) => return Some(err.to_string()),
+        };
+        if header_len != value.len() || header.content.slot.to_bytes_key()[..] != *key {
+            return Some(format!("header {} does not match its key", header.id));
+        }
+        header
+            .verify_signature()
+            .err()
+            .map(|err| format!("header {}: {}", header.id, err))
+    }
+
+    /// Path of the snapshot of the final state taken during `cycle`
+    pub fn snapshot_path(&self, cycle: u64) -> PathBuf {
+        self.path.join(SNAPSHOTS_DIR).join(format!(
+            "{}{}.{}",
+            SNAPSHOT_PREFIX, cycle, SNAPSHOT_EXTENSION
+        ))
+    }
+
+    /// Get the cycles of the stored snapshots, by ascending cycle
+    pub fn get_snapshot_cycles(&self) -> Result<Vec<u64>, ArchiveError> {
+        let mut cycles = Vec::new();
+        for entry in std::fs::read_dir(self.path.join(SNAPSHOTS_DIR))? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some(SNAPSHOT_EXTENSION) {
+                continue;
+            }
+            if let Some(cycle) = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.strip_prefix(SNAPSHOT_PREFIX))
+                .and_then(|cycle| cycle.parse::<u64>().ok())
+            {
+                cycles.push(cycle);
+            }
+        }
+        cycles.sort_unstable();
+        Ok(cycles)
+    }
+
+    /// Remove the snapshot taken during `cycle`
+    pub fn remove_snapshot(&self, cycle: u64) -> Result<(), ArchiveError> {
+        std::fs::remove_file(self.snapshot_path(cycle))?;
+        Ok(())
+    }
+}
@@ -142,6 +142,21 @@ impl BlockIndexes {
         self.index_by_slot.get(slot)
     }
 
+    /// Get the block ids of the blocks in a slot range.
+    /// Arguments:
+    /// - start: the first slot of the range (included)
+    /// - end: the last slot of the range (excluded)
+    ///
+    /// Returns:
+    /// - the block ids of the blocks in the range along with their slot, unordered
+    pub fn get_blocks_by_slot_range(&self, start: &Slot, end: &Slot) -> Vec<(Slot, BlockId)> {
+        self.index_by_slot
+            .iter()
+            .filter(|(slot, _)| *slot >= start && *slot < end)
+            .flat_map(|(slot, ids)| ids.iter().map(move |id| (*slot, *id)))
+            .collect()
+    }
+
     /// Get the block ids of the blocks containing a given operation.
     /// Arguments:
     /// - id: the ID of the operation
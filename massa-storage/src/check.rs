@@ -0,0 +1,97 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Integrity check of the persistent storage of the node.
+//!
+//! Every archived block must decode, be stored under the key of its slot, be validly signed and carry
+//! the operations its header commits to. Every address index entry must decode and point to the block
+//! archived at its slot, and to an operation of that block involving the indexed address, when the block
+//! is still archived. With `repair`, the corrupted entries are removed and the archived blocks are indexed
+//! again, restoring the index entries that were lost.
+
+use crate::address_index::{AddressIndex, AddressIndexError};
+use crate::archive::{Archive, ArchiveError};
+use displaydoc::Display;
+use massa_models::{
+    address::Address, api::FinalOperationEntry, api::StorageCheckReport, slot::Slot,
+};
+use thiserror::Error;
+use tracing::warn;
+
+/// Storage check errors
+#[non_exhaustive]
+#[derive(Display, Error, Debug)]
+pub enum StorageCheckError {
+    /// archive error: {0}
+    ArchiveError(#[from] ArchiveError),
+    /// address index error: {0}
+    AddressIndexError(#[from] AddressIndexError),
+}
+
+/// Check the archive and the address index of the node, the ones that are enabled,
+/// removing the corrupted entries and indexing the archived blocks again if `repair` is set
+pub fn check_storage(
+    archive: Option<&Archive>,
+    address_index: Option<&AddressIndex>,
+    repair: bool,
+) -> Result<StorageCheckReport, StorageCheckError> {
+    let mut report = StorageCheckReport {
+        repaired: repair,
+        ..Default::default()
+    };
+    if let Some(archive) = archive {
+        let stats = archive.check(repair)?;
+        report.checked_archived_blocks = stats.checked;
+        report.corrupted_archived_blocks = stats.corrupted;
+    }
+    if let Some(address_index) = address_index {
+        let (checked, corrupted) = address_index.check_entries(repair, |address, entry| {
+            let valid = match archive {
+                Some(archive) => check_index_entry(archive, address, entry)?,
+                None => true,
+            };
+            if !valid {
+                warn!("corrupted address index entry for {}: {}", address, entry);
+            }
+            Ok::<_, StorageCheckError>(valid)
+        })?;
+        report.checked_index_entries = checked;
+        report.corrupted_index_entries = corrupted;
+        if let (Some(archive), true) = (archive, repair) {
+            for block in archive.iter_blocks(Slot::new(0, 0)) {
+                let block = block?;
+                address_index.index_block(
+                    &block.header.id,
+                    &block.header.content.slot,
+                    &block.operations,
+                )?;
+                report.reindexed_blocks += 1;
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// Check an address index entry against the archive.
+/// The entries of the blocks that are not archived, or whose archived entry is corrupted, can not be checked.
+fn check_index_entry(
+    archive: &Archive,
+    address: &Address,
+    entry: &FinalOperationEntry,
+) -> Result<bool, ArchiveError> {
+    match archive.get_header(entry.slot) {
+        Ok(Some(header)) if header.id != entry.block_id => return Ok(false),
+        Ok(_) | Err(ArchiveError::CorruptedEntry(_)) => {}
+        Err(err) => return Err(err),
+    }
+    match archive.get_block(entry.slot) {
+        Ok(Some(block)) => Ok(block
+            .operations
+            .iter()
+            .find(|operation| operation.id == entry.operation_id)
+            .map_or(false, |operation| {
+                operation.get_ledger_involved_addresses().contains(address)
+            })),
+        Ok(None) | Err(ArchiveError::CorruptedEntry(_)) => Ok(true),
+        Err(err) => Err(err),
+    }
+}
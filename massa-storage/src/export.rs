@@ -0,0 +1,215 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Export of the archived final blocks for analytics pipelines.
+//!
+//! The blocks of a slot range are read from the archive one at a time and written as one record per block
+//! or per operation, in JSON lines or CSV with a header line, so that the memory used does not depend on
+//! the size of the range. The records are flat so that both formats have the same columns.
+//! The trimmed blocks, whose operations were removed, are not exported.
+
+use crate::archive::{Archive, ArchiveError};
+use displaydoc::Display;
+use massa_models::{
+    address::Address,
+    amount::Amount,
+    api::{ExportContent, ExportFormat, ExportStats},
+    block::{ArchivedBlock, BlockId},
+    operation::{OperationId, OperationType, WrappedOperation},
+    slot::Slot,
+};
+use serde::Serialize;
+use std::io::Write;
+use thiserror::Error;
+
+/// Export errors
+#[non_exhaustive]
+#[derive(Display, Error, Debug)]
+pub enum ExportError {
+    /// archive error: {0}
+    ArchiveError(#[from] ArchiveError),
+    /// io error: {0}
+    IoError(#[from] std::io::Error),
+    /// json error: {0}
+    JsonError(#[from] serde_json::Error),
+    /// csv error: {0}
+    CsvError(#[from] csv::Error),
+}
+
+/// Exported block
+#[derive(Serialize)]
+struct BlockRecord {
+    block_id: BlockId,
+    period: u64,
+    thread: u8,
+    creator_address: Address,
+    /// parent ids separated by spaces, in thread order
+    parents: String,
+    endorsement_count: usize,
+    operation_count: usize,
+}
+
+impl From<&ArchivedBlock> for BlockRecord {
+    fn from(block: &ArchivedBlock) -> Self {
+        let header = &block.header;
+        BlockRecord {
+            block_id: header.id,
+            period: header.content.slot.period,
+            thread: header.content.slot.thread,
+            creator_address: header.creator_address,
+            parents: header
+                .content
+                .parents
+                .iter()
+                .map(|parent| parent.to_string())
+                .collect::<Vec<_>>()
+                .join(" "),
+            endorsement_count: header.content.endorsements.len(),
+            operation_count: block.operations.len(),
+        }
+    }
+}
+
+/// Exported operation, the fields that do not apply to its type are empty
+#[derive(Serialize)]
+struct OperationRecord {
+    operation_id: OperationId,
+    block_id: BlockId,
+    period: u64,
+    thread: u8,
+    creator_address: Address,
+    fee: Amount,
+    expire_period: u64,
+    operation_type: &'static str,
+    /// recipient of a transaction or target of a call
+    target_address: Option<Address>,
+    /// transferred amount or coins of a call
+    amount: Option<Amount>,
+    roll_count: Option<u64>,
+    max_gas: Option<u64>,
+    target_function: Option<String>,
+}
+
+impl OperationRecord {
+    fn new(block: &ArchivedBlock, operation: &WrappedOperation) -> Self {
+        let mut record = OperationRecord {
+            operation_id: operation.id,
+            block_id: block.header.id,
+            period: block.header.content.slot.period,
+            thread: block.header.content.slot.thread,
+            creator_address: operation.creator_address,
+            fee: operation.content.fee,
+            expire_period: operation.content.expire_period,
+            operation_type: "",
+            target_address: None,
+            amount: None,
+            roll_count: None,
+            max_gas: None,
+            target_function: None,
+        };
+        match &operation.content.op {
+            OperationType::Transaction {
+                recipient_address,
+                amount,
+            } => {
+                record.operation_type = "transaction";
+                record.target_address = Some(*recipient_address);
+                record.amount = Some(*amount);
+            }
+            OperationType::RollBuy { roll_count } => {
+                record.operation_type = "roll_buy";
+                record.roll_count = Some(*roll_count);
+            }
+            OperationType::RollSell { roll_count } => {
+                record.operation_type = "roll_sell";
+                record.roll_count = Some(*roll_count);
+            }
+            OperationType::ExecuteSC { max_gas, .. } => {
+                record.operation_type = "execute_sc";
+                record.max_gas = Some(*max_gas);
+            }
+            OperationType::CallSC {
+                target_addr,
+                target_func,
+                max_gas,
+                coins,
+                ..
+            } => {
+                record.operation_type = "call_sc";
+                record.target_address = Some(*target_addr);
+                record.amount = Some(*coins);
+                record.max_gas = Some(*max_gas);
+                record.target_function = Some(target_func.clone());
+            }
+        }
+        record
+    }
+}
+
+/// Writer of the records in an export format
+enum RecordWriter<W: Write> {
+    Jsonl(W),
+    Csv(csv::Writer<W>),
+}
+
+impl<W: Write> RecordWriter<W> {
+    fn new(format: ExportFormat, writer: W) -> Self {
+        match format {
+            ExportFormat::Jsonl => RecordWriter::Jsonl(writer),
+            ExportFormat::Csv => RecordWriter::Csv(csv::Writer::from_writer(writer)),
+        }
+    }
+
+    fn write<T: Serialize>(&mut self, record: &T) -> Result<(), ExportError> {
+        match self {
+            RecordWriter::Jsonl(writer) => {
+                serde_json::to_writer(&mut *writer, record)?;
+                writer.write_all(b"\n")?;
+            }
+            RecordWriter::Csv(writer) => writer.serialize(record)?,
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), ExportError> {
+        match self {
+            RecordWriter::Jsonl(writer) => writer.flush()?,
+            RecordWriter::Csv(writer) => writer.flush()?,
+        }
+        Ok(())
+    }
+}
+
+/// Write the `content` of the blocks archived in the slot range from `start` (included) to `end` (excluded)
+/// to `writer` in `format`, by ascending slot
+pub fn export_blocks<W: Write>(
+    archive: &Archive,
+    start: Slot,
+    end: Slot,
+    content: ExportContent,
+    format: ExportFormat,
+    writer: W,
+) -> Result<ExportStats, ExportError> {
+    let mut writer = RecordWriter::new(format, writer);
+    let mut stats = ExportStats::default();
+    for block in archive.iter_blocks(start) {
+        let block = block?;
+        if block.header.content.slot >= end {
+            break;
+        }
+        match content {
+            ExportContent::Blocks => {
+                writer.write(&BlockRecord::from(&block))?;
+                stats.records += 1;
+            }
+            ExportContent::Operations => {
+                for operation in &block.operations {
+                    writer.write(&OperationRecord::new(&block, operation))?;
+                    stats.records += 1;
+                }
+            }
+        }
+        stats.blocks += 1;
+    }
+    writer.flush()?;
+    Ok(stats)
+}
@@ -10,8 +10,13 @@
 #![feature(hash_drain_filter)]
 #![feature(map_try_insert)]
 
+pub mod address_index;
+pub mod archive;
 mod block_indexes;
+pub mod check;
 mod endorsement_indexes;
+pub mod export;
+pub mod maintenance;
 mod operation_indexes;
 
 #[cfg(test)]
@@ -0,0 +1,159 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Background pruning and compaction of the archive.
+//!
+//! Every `interval` a thread removes the blocks more than `block_retention_periods` periods older than
+//! the latest archived block, and trims the operations of the blocks more than `body_retention_periods`
+//! periods older, keeping their headers. To limit the disk load, at most `batch_size` blocks are written
+//! at once, with a pause of `batch_pause` between two batches. The database is then compacted
+//! to reclaim the space of the removed entries, and the outcome is reported in `STORAGE_METRICS`.
+
+use crate::archive::{Archive, ArchiveError, PruneStats};
+use massa_metrics::STORAGE_METRICS;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Archive maintenance configuration
+#[derive(Debug, Clone)]
+pub struct MaintenanceConfig {
+    /// interval between two maintenance passes
+    pub interval: Duration,
+    /// blocks more than this number of periods older than the latest archived block are removed, 0 to keep them
+    pub block_retention_periods: u64,
+    /// operations of the blocks more than this number of periods older than the latest archived block are trimmed, 0 to keep them
+    pub body_retention_periods: u64,
+    /// max number of blocks removed or trimmed in a single write
+    pub batch_size: usize,
+    /// pause between two writes
+    pub batch_pause: Duration,
+}
+
+/// Handle of the maintenance thread
+pub struct MaintenanceHandle {
+    stop_tx: mpsc::Sender<()>,
+    join_handle: JoinHandle<()>,
+}
+
+impl MaintenanceHandle {
+    /// Stop the maintenance thread, after the current batch
+    pub fn stop(self) {
+        let _ = self.stop_tx.send(());
+        if self.join_handle.join().is_err() {
+            warn!("archive maintenance thread panicked");
+        }
+    }
+}
+
+/// Start the maintenance of `archive` in a dedicated thread
+pub fn start_maintenance(archive: Archive, config: MaintenanceConfig) -> MaintenanceHandle {
+    let (stop_tx, stop_rx) = mpsc::channel();
+    let join_handle = std::thread::Builder::new()
+        .name("archive-maintenance".into())
+        .spawn(move || {
+            let mut maintenance = Maintenance {
+                archive,
+                config,
+                stop_rx,
+                stopped: false,
+            };
+            while maintenance.wait(maintenance.config.interval) {
+                if let Err(err) = maintenance.run() {
+                    warn!("archive maintenance failed: {}", err);
+                }
+            }
+        })
+        .expect("could not spawn the archive maintenance thread");
+    MaintenanceHandle {
+        stop_tx,
+        join_handle,
+    }
+}
+
+struct Maintenance {
+    archive: Archive,
+    config: MaintenanceConfig,
+    stop_rx: mpsc::Receiver<()>,
+    stopped: bool,
+}
+
+impl Maintenance {
+    /// Wait for `duration`, returns false if the maintenance was stopped meanwhile
+    fn wait(&mut self, duration: Duration) -> bool {
+        if !self.stopped && self.stop_rx.recv_timeout(duration) != Err(RecvTimeoutError::Timeout) {
+            self.stopped = true;
+        }
+        !self.stopped
+    }
+
+    /// Run a maintenance pass
+    fn run(&mut self) -> Result<(), ArchiveError> {
+        let last_period = match self.archive.get_last_slot()? {
+            Some(slot) => slot.period,
+            None => return Ok(()),
+        };
+        let mut removed = PruneStats::default();
+        if self.config.block_retention_periods > 0 {
+            let stats = self.run_batches(
+                last_period.saturating_sub(self.config.block_retention_periods),
+                Archive::prune_blocks_before,
+            )?;
+            STORAGE_METRICS.pruned_blocks.inc_by(stats.count as u64);
+            removed.count += stats.count;
+            removed.removed_bytes += stats.removed_bytes;
+        }
+        if self.config.body_retention_periods > 0 {
+            let stats = self.run_batches(
+                last_period.saturating_sub(self.config.body_retention_periods),
+                Archive::trim_blocks_before,
+            )?;
+            STORAGE_METRICS.trimmed_blocks.inc_by(stats.count as u64);
+            removed.count += stats.count;
+            removed.removed_bytes += stats.removed_bytes;
+        }
+
+        if self.stopped {
+            return Ok(());
+        }
+        let size_before = self.archive.get_disk_size()?;
+        if removed.count > 0 {
+            self.archive.compact();
+        }
+        let size_after = self.archive.get_disk_size()?;
+        let reclaimed = size_before.saturating_sub(size_after);
+        STORAGE_METRICS.reclaimed_bytes.inc_by(reclaimed);
+        STORAGE_METRICS.archive_size_bytes.set(size_after as i64);
+        if removed.count > 0 {
+            info!(
+                "archive maintenance: {} blocks pruned or trimmed, {} bytes removed, {} bytes reclaimed on disk",
+                removed.count, removed.removed_bytes, reclaimed
+            );
+        }
+        Ok(())
+    }
+
+    /// Apply `job` to the blocks before `period` by batches, until none is left or the maintenance is stopped
+    fn run_batches(
+        &mut self,
+        period: u64,
+        job: fn(&Archive, u64, usize) -> Result<PruneStats, ArchiveError>,
+    ) -> Result<PruneStats, ArchiveError> {
+        let batch_size = self.config.batch_size.max(1);
+        let mut total = PruneStats::default();
+        while !self.stopped {
+            let stats = job(&self.archive, period, batch_size)?;
+            total.count += stats.count;
+            total.removed_bytes += stats.removed_bytes;
+            if stats.count < batch_size {
+                return Ok(total);
+            }
+            debug!(
+                "archive maintenance: batch of {} blocks written",
+                stats.count
+            );
+            self.wait(self.config.batch_pause);
+        }
+        Ok(total)
+    }
+}
@@ -16,6 +16,8 @@ pub struct OperationIndexes {
     index_by_creator: PreHashMap<Address, PreHashSet<OperationId>>,
     /// Structure indexing operations by ID prefix
     index_by_prefix: PreHashMap<OperationPrefixId, PreHashSet<OperationId>>,
+    /// Structure mapping addresses with the operations involving them (as creator, recipient or target)
+    index_by_involved_address: PreHashMap<Address, PreHashSet<OperationId>>,
 }
 
 impl OperationIndexes {
@@ -34,6 +36,13 @@ impl OperationIndexes {
                 .entry(o.id.prefix())
                 .or_default()
                 .insert(o.id);
+            // update involved address index
+            for address in o.get_ledger_involved_addresses() {
+                self.index_by_involved_address
+                    .entry(address)
+                    .or_default()
+                    .insert(o.id);
+            }
         }
     }
 
@@ -58,6 +67,17 @@ impl OperationIndexes {
                     occ.remove();
                 }
             }
+            // update involved address index
+            for address in o.get_ledger_involved_addresses() {
+                if let hash_map::Entry::Occupied(mut occ) =
+                    self.index_by_involved_address.entry(address)
+                {
+                    occ.get_mut().remove(&o.id);
+                    if occ.get().is_empty() {
+                        occ.remove();
+                    }
+                }
+            }
             return Some(o);
         }
         None
@@ -83,6 +103,16 @@ impl OperationIndexes {
         self.index_by_creator.get(address)
     }
 
+    /// Get operations involving an address
+    /// Arguments:
+    /// * `address`: the address to get the operations of, as creator, recipient or call target
+    ///
+    /// Returns:
+    /// - optional reference to a set of operations involving that address
+    pub fn get_operations_involving(&self, address: &Address) -> Option<&PreHashSet<OperationId>> {
+        self.index_by_involved_address.get(address)
+    }
+
     /// Get operations by prefix
     /// Arguments:
     /// * `prefix`: the prefix to look up
@@ -0,0 +1,69 @@
+use crate::address_index::AddressIndex;
+use massa_factory_exports::test_exports::create_empty_block;
+use massa_models::{
+    address::Address,
+    amount::Amount,
+    operation::{Operation, OperationSerializer, OperationType, WrappedOperation},
+    slot::Slot,
+    wrapped::WrappedContent,
+};
+use massa_signature::KeyPair;
+use tempfile::TempDir;
+
+pub(super) fn create_transaction(sender_keypair: &KeyPair, recipient: Address) -> WrappedOperation {
+    let content = Operation {
+        fee: Amount::default(),
+        expire_period: 10,
+        op: OperationType::Transaction {
+            recipient_address: recipient,
+            amount: Amount::default(),
+        },
+    };
+    Operation::new_wrapped(content, OperationSerializer::new(), sender_keypair).unwrap()
+}
+
+#[test]
+/// Index the operations of final blocks and find them by involved address, by slot.
+fn test_address_index() {
+    let dir = TempDir::new().unwrap();
+    let keypair = KeyPair::generate();
+    let sender = Address::from_public_key(&keypair.get_public_key());
+    let recipient_1 = Address::from_public_key(&KeyPair::generate().get_public_key());
+    let recipient_2 = Address::from_public_key(&KeyPair::generate().get_public_key());
+    let operation_1 = create_transaction(&keypair, recipient_1);
+    let operation_2 = create_transaction(&keypair, recipient_2);
+    let slot_1 = Slot::new(1, 0);
+    let slot_2 = Slot::new(2, 3);
+    let block_1 = create_empty_block(&keypair, &slot_1);
+    let block_2 = create_empty_block(&keypair, &slot_2);
+    {
+        let index = AddressIndex::open(dir.path()).unwrap();
+        // indexed in reverse order, read by slot
+        index
+            .index_block(&block_2.id, &slot_2, &[operation_2.clone()])
+            .unwrap();
+        index
+            .index_block(&block_1.id, &slot_1, &[operation_1.clone()])
+            .unwrap();
+    }
+
+    // the index is persisted
+    let index = AddressIndex::open(dir.path()).unwrap();
+    let sender_entries = index.get_operations(&sender).unwrap();
+    assert_eq!(sender_entries.len(), 2);
+    assert_eq!(sender_entries[0].operation_id, operation_1.id);
+    assert_eq!(sender_entries[0].block_id, block_1.id);
+    assert_eq!(sender_entries[0].slot, slot_1);
+    assert_eq!(sender_entries[1].operation_id, operation_2.id);
+    assert_eq!(sender_entries[1].block_id, block_2.id);
+    assert_eq!(sender_entries[1].slot, slot_2);
+
+    let recipient_entries = index.get_operations(&recipient_2).unwrap();
+    assert_eq!(recipient_entries, vec![sender_entries[1].clone()]);
+    assert!(index
+        .get_operations(&Address::from_public_key(
+            &KeyPair::generate().get_public_key()
+        ))
+        .unwrap()
+        .is_empty());
+}
@@ -0,0 +1,225 @@
+use crate::archive::{Archive, ArchiveConfig};
+use massa_factory_exports::test_exports::create_empty_block;
+use massa_hash::Hash;
+use massa_models::{
+    block::{ArchivedBlock, BlockId, WrappedHeader},
+    config::{
+        ENDORSEMENT_COUNT, MAX_DATASTORE_VALUE_LENGTH, MAX_FUNCTION_NAME_LENGTH,
+        MAX_OPERATIONS_PER_BLOCK, MAX_OPERATION_DATASTORE_ENTRY_COUNT,
+        MAX_OPERATION_DATASTORE_KEY_LENGTH, MAX_OPERATION_DATASTORE_VALUE_LENGTH,
+        MAX_PARAMETERS_SIZE, THREAD_COUNT,
+    },
+    slot::Slot,
+};
+use massa_signature::KeyPair;
+use tempfile::TempDir;
+
+pub(super) fn open_archive(dir: &TempDir) -> Archive {
+    Archive::open(ArchiveConfig {
+        path: dir.path().to_path_buf(),
+        thread_count: THREAD_COUNT,
+        endorsement_count: ENDORSEMENT_COUNT,
+        max_operations_per_block: MAX_OPERATIONS_PER_BLOCK,
+        max_datastore_value_length: MAX_DATASTORE_VALUE_LENGTH,
+        max_function_name_length: MAX_FUNCTION_NAME_LENGTH,
+        max_parameters_size: MAX_PARAMETERS_SIZE,
+        max_op_datastore_entry_count: MAX_OPERATION_DATASTORE_ENTRY_COUNT,
+        max_op_datastore_key_length: MAX_OPERATION_DATASTORE_KEY_LENGTH,
+        max_op_datastore_value_length: MAX_OPERATION_DATASTORE_VALUE_LENGTH,
+    })
+    .unwrap()
+}
+
+fn archived_block(slot: Slot) -> ArchivedBlock {
+    ArchivedBlock {
+        header: create_empty_block(&KeyPair::generate(), &slot)
+            .content
+            .header,
+        operations: Vec::new(),
+    }
+}
+
+#[test]
+/// Store blocks and retrieve them by slot and by range.
+fn test_archive_blocks() {
+    let dir = TempDir::new().unwrap();
+    let archive = open_archive(&dir);
+    assert_eq!(archive.get_first_slot().unwrap(), None);
+
+    let slots = [
+        Slot::new(1, 0),
+        Slot::new(1, 3),
+        Slot::new(2, 1),
+        Slot::new(4, 0),
+    ];
+    for slot in slots {
+        archive.store_block(&archived_block(slot)).unwrap();
+    }
+    assert_eq!(archive.get_first_slot().unwrap(), Some(slots[0]));
+    assert_eq!(archive.get_last_slot().unwrap(), Some(slots[3]));
+    assert_eq!(
+        archive
+            .get_block(slots[2])
+            .unwrap()
+            .unwrap()
+            .header
+            .content
+            .slot,
+        slots[2]
+    );
+    assert!(archive.get_block(Slot::new(3, 0)).unwrap().is_none());
+
+    let range: Vec<Slot> = archive
+        .get_blocks(Slot::new(1, 1), Slot::new(4, 0), 10)
+        .unwrap()
+        .iter()
+        .map(|block| block.header.content.slot)
+        .collect();
+    assert_eq!(range, vec![slots[1], slots[2]]);
+    assert_eq!(
+        archive
+            .get_blocks(Slot::new(0, 0), Slot::new(10, 0), 3)
+            .unwrap()
+            .len(),
+        3
+    );
+
+    assert_eq!(archive.prune_blocks_before(2, 10).unwrap().count, 2);
+    assert_eq!(archive.get_first_slot().unwrap(), Some(slots[2]));
+
+    // the blocks are persisted
+    drop(archive);
+    let archive = open_archive(&dir);
+    assert_eq!(archive.get_last_slot().unwrap(), Some(slots[3]));
+}
+
+#[test]
+/// Trim the operations of the oldest blocks, keeping their headers, then prune them by batches.
+fn test_archive_trim_and_prune() {
+    let dir = TempDir::new().unwrap();
+    let archive = open_archive(&dir);
+    let slots = [Slot::new(1, 0), Slot::new(2, 0), Slot::new(3, 0)];
+    for slot in slots {
+        archive.store_block(&archived_block(slot)).unwrap();
+    }
+
+    let stats = archive.trim_blocks_before(3, 1).unwrap();
+    assert_eq!(stats.count, 1);
+    assert_eq!(archive.trim_blocks_before(3, 10).unwrap().count, 1);
+    assert_eq!(archive.get_first_slot().unwrap(), Some(slots[2]));
+    assert!(archive.get_block(slots[0]).unwrap().is_none());
+    for slot in slots {
+        assert_eq!(
+            archive.get_header(slot).unwrap().unwrap().content.slot,
+            slot
+        );
+    }
+
+    // trimmed headers are pruned along with the blocks
+    assert_eq!(archive.prune_blocks_before(4, 2).unwrap().count, 2);
+    assert!(archive.get_header(slots[0]).unwrap().is_none());
+    assert!(archive.get_header(slots[1]).unwrap().is_none());
+    assert_eq!(archive.prune_blocks_before(4, 2).unwrap().count, 1);
+    assert_eq!(archive.get_last_slot().unwrap(), None);
+    archive.compact();
+}
+
+#[test]
+/// Store headers before the operations of their blocks, and list them with the headers of the stored blocks.
+fn test_archive_headers() {
+    let dir = TempDir::new().unwrap();
+    let archive = open_archive(&dir);
+    let block = archived_block(Slot::new(1, 0));
+    archive.store_block(&block).unwrap();
+    let headers: Vec<ArchivedBlock> = [Slot::new(2, 0), Slot::new(2, 1)]
+        .into_iter()
+        .map(archived_block)
+        .collect();
+    for header in &headers {
+        archive.store_header(&header.header).unwrap();
+    }
+
+    // the blocks without their operations are not served
+    assert!(archive.get_block(Slot::new(2, 0)).unwrap().is_none());
+    assert_eq!(archive.get_last_slot().unwrap(), Some(Slot::new(1, 0)));
+    let ids = |headers: Vec<WrappedHeader>| -> Vec<BlockId> {
+        headers.into_iter().map(|header| header.id).collect()
+    };
+    assert_eq!(
+        ids(archive
+            .get_headers(Slot::new(0, 0), Slot::new(10, 0), 10)
+            .unwrap()),
+        vec![block.header.id, headers[0].header.id, headers[1].header.id]
+    );
+    assert_eq!(
+        ids(archive
+            .get_headers(Slot::new(0, 0), Slot::new(10, 0), 2)
+            .unwrap()),
+        vec![block.header.id, headers[0].header.id]
+    );
+    assert_eq!(
+        ids(archive
+            .get_headers_without_operations(Slot::new(0, 0), Slot::new(2, 1), 10)
+            .unwrap()),
+        vec![headers[0].header.id]
+    );
+
+    // storing the block replaces its header
+    archive.store_block(&headers[0]).unwrap();
+    assert!(archive.get_block(Slot::new(2, 0)).unwrap().is_some());
+    assert_eq!(
+        ids(archive
+            .get_headers_without_operations(Slot::new(0, 0), Slot::new(10, 0), 10)
+            .unwrap()),
+        vec![headers[1].header.id]
+    );
+    assert_eq!(
+        archive
+            .get_headers(Slot::new(0, 0), Slot::new(10, 0), 10)
+            .unwrap()
+            .len(),
+        3
+    );
+}
+
+#[test]
+/// List and remove the cycle snapshots.
+fn test_archive_snapshots() {
+    let dir = TempDir::new().unwrap();
+    let archive = open_archive(&dir);
+    for cycle in [12, 3] {
+        std::fs::write(archive.snapshot_path(cycle), b"snapshot").unwrap();
+    }
+    assert_eq!(archive.get_snapshot_cycles().unwrap(), vec![3, 12]);
+    archive.remove_snapshot(3).unwrap();
+    assert_eq!(archive.get_snapshot_cycles().unwrap(), vec![12]);
+}
+
+#[test]
+/// Record final state hashes, read them back by slot and prune them with the blocks.
+fn test_archive_state_hashes() {
+    let dir = TempDir::new().unwrap();
+    let archive = open_archive(&dir);
+    let hashes = [
+        (Slot::new(5, 2), Hash::compute_from(b"state at 5.2")),
+        (Slot::new(2, 0), Hash::compute_from(b"state at 2.0")),
+    ];
+    for (slot, hash) in &hashes {
+        archive.store_state_hash(*slot, hash).unwrap();
+    }
+    let recorded = archive.get_state_hashes().unwrap();
+    assert_eq!(
+        recorded.into_iter().collect::<Vec<_>>(),
+        vec![hashes[1], hashes[0]]
+    );
+
+    assert_eq!(archive.prune_blocks_before(3, 10).unwrap().count, 1);
+    assert_eq!(
+        archive
+            .get_state_hashes()
+            .unwrap()
+            .into_keys()
+            .collect::<Vec<_>>(),
+        vec![hashes[0].0]
+    );
+}
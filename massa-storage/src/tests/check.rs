@@ -0,0 +1,95 @@
+use super::address_index::create_transaction;
+use super::archive::open_archive;
+use crate::address_index::AddressIndex;
+use crate::check::check_storage;
+use massa_factory_exports::test_exports::create_empty_block;
+use massa_hash::Hash;
+use massa_models::{
+    address::Address,
+    block::{ArchivedBlock, BlockHeader, BlockHeaderSerializer},
+    operation::WrappedOperation,
+    slot::Slot,
+    wrapped::WrappedContent,
+};
+use massa_signature::KeyPair;
+use tempfile::TempDir;
+
+pub(super) fn archived_block(
+    keypair: &KeyPair,
+    slot: Slot,
+    operations: Vec<WrappedOperation>,
+) -> ArchivedBlock {
+    let mut total_hash = Vec::new();
+    for operation in &operations {
+        total_hash.extend(operation.id.get_hash().to_bytes());
+    }
+    let header = BlockHeader::new_wrapped(
+        BlockHeader {
+            slot,
+            parents: Vec::new(),
+            operation_merkle_root: Hash::compute_from(&total_hash),
+            endorsements: Vec::new(),
+            state_commitment: None,
+        },
+        BlockHeaderSerializer::new(),
+        keypair,
+    )
+    .unwrap();
+    ArchivedBlock { header, operations }
+}
+
+#[test]
+/// Find the corrupted archived blocks and address index entries, then remove them and index the archived blocks again.
+fn test_check_storage() {
+    let archive_dir = TempDir::new().unwrap();
+    let index_dir = TempDir::new().unwrap();
+    let archive = open_archive(&archive_dir);
+    let index = AddressIndex::open(index_dir.path()).unwrap();
+    let keypair = KeyPair::generate();
+    let recipient_1 = Address::from_public_key(&KeyPair::generate().get_public_key());
+    let recipient_2 = Address::from_public_key(&KeyPair::generate().get_public_key());
+    let operation_1 = create_transaction(&keypair, recipient_1);
+    let operation_2 = create_transaction(&keypair, recipient_2);
+    let slot_1 = Slot::new(1, 0);
+    let slot_2 = Slot::new(2, 0);
+
+    let block_1 = archived_block(&keypair, slot_1, vec![operation_1.clone()]);
+    archive.store_block(&block_1).unwrap();
+    // the operations do not match the header
+    let mut block_2 = archived_block(&keypair, slot_2, Vec::new());
+    block_2.operations.push(operation_2.clone());
+    archive.store_block(&block_2).unwrap();
+    index
+        .index_block(&block_1.header.id, &slot_1, &[operation_1])
+        .unwrap();
+    // not the block archived at this slot
+    let other_block = create_empty_block(&keypair, &slot_1);
+    index
+        .index_block(&other_block.id, &slot_1, &[operation_2])
+        .unwrap();
+
+    // nothing is removed without repair
+    let report = check_storage(Some(&archive), Some(&index), false).unwrap();
+    assert!(!report.is_healthy());
+    assert_eq!(report.checked_archived_blocks, 2);
+    assert_eq!(report.corrupted_archived_blocks, 1);
+    assert_eq!(report.checked_index_entries, 4);
+    assert_eq!(report.corrupted_index_entries, 2);
+    assert_eq!(report.reindexed_blocks, 0);
+    assert!(archive.get_block(slot_2).unwrap().is_some());
+    assert_eq!(index.get_operations(&recipient_2).unwrap().len(), 1);
+
+    let report = check_storage(Some(&archive), Some(&index), true).unwrap();
+    assert!(report.repaired);
+    assert_eq!(report.corrupted_archived_blocks, 1);
+    assert_eq!(report.corrupted_index_entries, 2);
+    assert_eq!(report.reindexed_blocks, 1);
+    assert!(archive.get_block(slot_2).unwrap().is_none());
+    assert!(index.get_operations(&recipient_2).unwrap().is_empty());
+    assert_eq!(index.get_operations(&recipient_1).unwrap().len(), 1);
+
+    let report = check_storage(Some(&archive), Some(&index), false).unwrap();
+    assert!(report.is_healthy());
+    assert_eq!(report.checked_archived_blocks, 1);
+    assert_eq!(report.checked_index_entries, 2);
+}
@@ -0,0 +1,78 @@
+use super::address_index::create_transaction;
+use super::archive::open_archive;
+use super::check::archived_block;
+use crate::export::export_blocks;
+use massa_models::{
+    address::Address,
+    api::{ExportContent, ExportFormat},
+    slot::Slot,
+};
+use massa_signature::KeyPair;
+use tempfile::TempDir;
+
+#[test]
+/// Export the blocks and the operations of a slot range in JSON lines and in CSV.
+fn test_export_blocks() {
+    let dir = TempDir::new().unwrap();
+    let archive = open_archive(&dir);
+    let keypair = KeyPair::generate();
+    let operations = vec![
+        create_transaction(
+            &keypair,
+            Address::from_public_key(&KeyPair::generate().get_public_key()),
+        ),
+        create_transaction(
+            &keypair,
+            Address::from_public_key(&KeyPair::generate().get_public_key()),
+        ),
+    ];
+    let block_1 = archived_block(&keypair, Slot::new(1, 0), operations.clone());
+    let block_2 = archived_block(&keypair, Slot::new(2, 0), Vec::new());
+    let block_3 = archived_block(&keypair, Slot::new(3, 0), Vec::new());
+    for block in [&block_1, &block_2, &block_3] {
+        archive.store_block(block).unwrap();
+    }
+
+    let mut output = Vec::new();
+    let stats = export_blocks(
+        &archive,
+        Slot::new(1, 0),
+        Slot::new(3, 0),
+        ExportContent::Blocks,
+        ExportFormat::Jsonl,
+        &mut output,
+    )
+    .unwrap();
+    assert_eq!(stats.blocks, 2);
+    assert_eq!(stats.records, 2);
+    let lines: Vec<serde_json::Value> = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["block_id"], block_1.header.id.to_string());
+    assert_eq!(lines[0]["operation_count"], 2);
+    assert_eq!(lines[1]["block_id"], block_2.header.id.to_string());
+
+    let mut output = Vec::new();
+    let stats = export_blocks(
+        &archive,
+        Slot::new(0, 0),
+        Slot::new(u64::MAX, 0),
+        ExportContent::Operations,
+        ExportFormat::Csv,
+        &mut output,
+    )
+    .unwrap();
+    assert_eq!(stats.blocks, 3);
+    assert_eq!(stats.records, 2);
+    let output = String::from_utf8(output).unwrap();
+    let lines: Vec<&str> = output.lines().collect();
+    // header line and one line per operation
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("operation_id,block_id,period,thread"));
+    assert!(lines[1].starts_with(&format!("{},{},1,0", operations[0].id, block_1.header.id)));
+    assert!(lines[1].contains(",transaction,"));
+    assert!(lines[2].starts_with(&operations[1].id.to_string()));
+}
@@ -1,6 +1,12 @@
 use crate::Storage;
 use massa_factory_exports::test_exports::create_empty_block;
-use massa_models::{address::Address, slot::Slot};
+use massa_models::{
+    address::Address,
+    amount::Amount,
+    operation::{Operation, OperationSerializer, OperationType},
+    slot::Slot,
+    wrapped::WrappedContent,
+};
 use massa_signature::KeyPair;
 
 #[test]
@@ -46,3 +52,56 @@ fn test_block_fail_find() {
         .get_blocks_created_by(&Address::from_public_key(&keypair2.get_public_key()))
         .is_none());
 }
+
+#[test]
+fn test_block_index_slot_range() {
+    let mut storage = Storage::create_root();
+    let keypair = KeyPair::generate();
+    let block_1 = create_empty_block(&keypair, &Slot::new(1, 0));
+    let block_2 = create_empty_block(&keypair, &Slot::new(1, 1));
+    let block_3 = create_empty_block(&keypair, &Slot::new(2, 0));
+
+    storage.store_block(block_1.clone());
+    storage.store_block(block_2.clone());
+    storage.store_block(block_3);
+    let blocks = storage.read_blocks();
+    let mut stored_blocks = blocks.get_blocks_by_slot_range(&Slot::new(1, 0), &Slot::new(2, 0));
+    stored_blocks.sort();
+    assert_eq!(
+        stored_blocks,
+        vec![(Slot::new(1, 0), block_1.id), (Slot::new(1, 1), block_2.id)]
+    );
+}
+
+#[test]
+fn test_operation_index_involved_address() {
+    let mut storage = Storage::create_root();
+    let sender_keypair = KeyPair::generate();
+    let sender = Address::from_public_key(&sender_keypair.get_public_key());
+    let recipient = Address::from_public_key(&KeyPair::generate().get_public_key());
+    let content = Operation {
+        fee: Amount::default(),
+        expire_period: 10,
+        op: OperationType::Transaction {
+            recipient_address: recipient,
+            amount: Amount::default(),
+        },
+    };
+    let operation =
+        Operation::new_wrapped(content, OperationSerializer::new(), &sender_keypair).unwrap();
+
+    storage.store_operations(vec![operation.clone()]);
+    {
+        let operations = storage.read_operations();
+        for address in [sender, recipient] {
+            let stored_operations = operations.get_operations_involving(&address).unwrap();
+            assert_eq!(stored_operations.len(), 1);
+            assert!(stored_operations.contains(&operation.id));
+        }
+    }
+    storage.drop_operation_refs(&[operation.id].into_iter().collect());
+    assert!(storage
+        .read_operations()
+        .get_operations_involving(&recipient)
+        .is_none());
+}
@@ -1,3 +1,7 @@
+mod address_index;
+mod archive;
 mod basic;
+mod check;
+mod export;
 mod indexes;
 mod references;
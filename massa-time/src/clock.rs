@@ -0,0 +1,124 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Source of the current time.
+//!
+//! The workers read the time and schedule their wakeups through a `Clock` instead of the system clock,
+//! so that tests can move the time by hand with a `MockClock` (feature `testing`).
+
+use crate::{MassaTime, TimeError};
+use std::time::Instant;
+
+/// Source of the current time
+pub trait Clock: Send + Sync {
+    /// Get the current time
+    fn now(&self) -> Result<MassaTime, TimeError>;
+
+    /// Get the instant at which a worker waiting for `timestamp` must wake up.
+    /// The worker must check with `now` that the timestamp was actually reached once woken up.
+    fn get_wakeup_instant(&self, timestamp: MassaTime) -> Instant;
+
+    /// Returns a boxed clone of self.
+    /// Useful to allow cloning `Box<dyn Clock>`.
+    fn clone_box(&self) -> Box<dyn Clock>;
+
+    /// Block the current thread until `timestamp` is reached
+    fn sleep_until(&self, timestamp: MassaTime) -> Result<(), TimeError> {
+        while self.now()? < timestamp {
+            std::thread::sleep(
+                self.get_wakeup_instant(timestamp)
+                    .saturating_duration_since(Instant::now()),
+            );
+        }
+        Ok(())
+    }
+}
+
+impl Clone for Box<dyn Clock> {
+    fn clone(&self) -> Box<dyn Clock> {
+        self.clone_box()
+    }
+}
+
+/// Clock following the system time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Result<MassaTime, TimeError> {
+        MassaTime::now()
+    }
+
+    fn get_wakeup_instant(&self, timestamp: MassaTime) -> Instant {
+        timestamp
+            .estimate_instant()
+            .unwrap_or_else(|_| Instant::now())
+    }
+
+    fn clone_box(&self) -> Box<dyn Clock> {
+        Box::new(*self)
+    }
+}
+
+#[cfg(feature = "testing")]
+pub use mock::MockClock;
+
+#[cfg(feature = "testing")]
+mod mock {
+    use super::Clock;
+    use crate::{MassaTime, TimeError};
+    use std::{
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc,
+        },
+        time::{Duration, Instant},
+    };
+
+    /// Interval at which the workers waiting on a `MockClock` check whether the time was moved
+    const MOCK_CLOCK_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+    /// Clock whose time only moves when the test says so.
+    /// Clones share the same time.
+    #[derive(Debug, Clone)]
+    pub struct MockClock {
+        /// current simulated time in milliseconds
+        now: Arc<AtomicU64>,
+    }
+
+    impl MockClock {
+        /// Create a mock clock starting at `now`
+        pub fn new(now: MassaTime) -> Self {
+            MockClock {
+                now: Arc::new(AtomicU64::new(now.to_millis())),
+            }
+        }
+
+        /// Set the simulated time
+        pub fn set_now(&self, now: MassaTime) {
+            self.now.store(now.to_millis(), Ordering::SeqCst);
+        }
+
+        /// Move the simulated time forward
+        pub fn advance(&self, duration: MassaTime) {
+            self.now.fetch_add(duration.to_millis(), Ordering::SeqCst);
+        }
+    }
+
+    impl Clock for MockClock {
+        fn now(&self) -> Result<MassaTime, TimeError> {
+            Ok(MassaTime::from_millis(self.now.load(Ordering::SeqCst)))
+        }
+
+        fn get_wakeup_instant(&self, timestamp: MassaTime) -> Instant {
+            if timestamp.to_millis() <= self.now.load(Ordering::SeqCst) {
+                Instant::now()
+            } else {
+                Instant::now() + MOCK_CLOCK_POLL_INTERVAL
+            }
+        }
+
+        fn clone_box(&self) -> Box<dyn Clock> {
+            Box::new(self.clone())
+        }
+    }
+}
@@ -4,7 +4,11 @@
 #![warn(unused_crate_dependencies)]
 #![feature(bound_map)]
 
+mod clock;
 mod error;
+#[cfg(feature = "testing")]
+pub use clock::MockClock;
+pub use clock::{Clock, SystemClock};
 pub use error::TimeError;
 use massa_serialization::{Deserializer, Serializer, U64VarIntDeserializer, U64VarIntSerializer};
 use nom::error::{context, ContextError, ParseError};
@@ -167,10 +171,78 @@ impl From<MassaTime> for Duration {
     }
 }
 
+impl TryFrom<OffsetDateTime> for MassaTime {
+    type Error = TimeError;
+
+    /// Conversion from `time::OffsetDateTime`, failing for dates before 01/01/1970.
+    /// ```
+    /// # use massa_time::*;
+    /// # use std::convert::TryFrom;
+    /// let date = time::OffsetDateTime::from_unix_timestamp(1_640_995_200).unwrap();
+    /// assert_eq!(MassaTime::try_from(date).unwrap(), MassaTime::from(1_640_995_200_000));
+    /// ```
+    fn try_from(value: OffsetDateTime) -> Result<Self, Self::Error> {
+        Ok(MassaTime(
+            (value.unix_timestamp_nanos() / 1_000_000)
+                .try_into()
+                .map_err(|_| TimeError::ConversionError)?,
+        ))
+    }
+}
+
+impl TryFrom<MassaTime> for OffsetDateTime {
+    type Error = TimeError;
+
+    /// Conversion to `time::OffsetDateTime`, failing for dates after the year 9999.
+    /// ```
+    /// # use massa_time::*;
+    /// # use std::convert::TryFrom;
+    /// let date = time::OffsetDateTime::try_from(MassaTime::from(1_640_995_200_000)).unwrap();
+    /// assert_eq!(date.unix_timestamp(), 1_640_995_200);
+    /// ```
+    fn try_from(value: MassaTime) -> Result<Self, Self::Error> {
+        OffsetDateTime::from_unix_timestamp_nanos(value.0 as i128 * 1_000_000)
+            .map_err(|_| TimeError::TimeOverflowError)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<chrono::DateTime<chrono::Utc>> for MassaTime {
+    type Error = TimeError;
+
+    /// Conversion from `chrono::DateTime<Utc>`, failing for dates before 01/01/1970.
+    fn try_from(value: chrono::DateTime<chrono::Utc>) -> Result<Self, Self::Error> {
+        Ok(MassaTime(
+            value
+                .timestamp_millis()
+                .try_into()
+                .map_err(|_| TimeError::ConversionError)?,
+        ))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<MassaTime> for chrono::DateTime<chrono::Utc> {
+    type Error = TimeError;
+
+    /// Conversion to `chrono::DateTime<Utc>`, failing for dates out of the range of `chrono`.
+    fn try_from(value: MassaTime) -> Result<Self, Self::Error> {
+        use chrono::TimeZone;
+        let secs: i64 = (value.0 / 1000)
+            .try_into()
+            .map_err(|_| TimeError::TimeOverflowError)?;
+        let nanos = (value.0 % 1000) as u32 * 1_000_000;
+        chrono::Utc
+            .timestamp_opt(secs, nanos)
+            .single()
+            .ok_or(TimeError::TimeOverflowError)
+    }
+}
+
 impl FromStr for MassaTime {
     type Err = crate::TimeError;
 
-    /// Conversion from `&str`.
+    /// Conversion from `&str`: milliseconds since 01/01/1970 or an RFC 3339 (ISO 8601) date.
     ///
     /// ```
     /// # use massa_time::*;
@@ -179,14 +251,55 @@ impl FromStr for MassaTime {
     /// let time : MassaTime = MassaTime::from(42);
     ///
     /// assert_eq!(time, MassaTime::from_str(duration).unwrap());
+    /// assert_eq!(
+    ///     MassaTime::from_str("2022-01-01T00:00:00Z").unwrap(),
+    ///     MassaTime::from(1_640_995_200_000)
+    /// );
     /// ```
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(MassaTime(
-            u64::from_str(s).map_err(|_| Self::Err::ConversionError)?,
-        ))
+        match u64::from_str(s) {
+            Ok(millis) => Ok(MassaTime(millis)),
+            Err(_) => MassaTime::from_utc_str(s),
+        }
     }
 }
 
+/// Deserialize a `MassaTime` written either as milliseconds since 01/01/1970 or as an RFC 3339 (ISO 8601) date,
+/// for the timestamps of the configuration files.
+/// Use with `#[serde(deserialize_with = "massa_time::deserialize_millis_or_utc")]`.
+pub fn deserialize_millis_or_utc<'de, D>(deserializer: D) -> Result<MassaTime, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    struct MillisOrUtcVisitor;
+
+    impl<'de> serde::de::Visitor<'de> for MillisOrUtcVisitor {
+        type Value = MassaTime;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str(
+                "milliseconds since 01/01/1970 or an RFC 3339 date (ex: \"2022-01-01T00:00:00Z\")",
+            )
+        }
+
+        fn visit_u64<E: serde::de::Error>(self, value: u64) -> Result<MassaTime, E> {
+            Ok(MassaTime(value))
+        }
+
+        fn visit_i64<E: serde::de::Error>(self, value: i64) -> Result<MassaTime, E> {
+            u64::try_from(value)
+                .map(MassaTime)
+                .map_err(|_| E::invalid_value(serde::de::Unexpected::Signed(value), &self))
+        }
+
+        fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<MassaTime, E> {
+            MassaTime::from_str(value).map_err(E::custom)
+        }
+    }
+
+    deserializer.deserialize_any(MillisOrUtcVisitor)
+}
+
 impl MassaTime {
     /// Conversion from `u64`, representing timestamp in milliseconds.
     /// ```
@@ -246,6 +359,28 @@ impl MassaTime {
         self.0
     }
 
+    /// Time elapsed since `self`, zero if `self` is in the future.
+    ///
+    /// ```
+    /// # use massa_time::*;
+    /// assert!(MassaTime::from(0).elapsed().unwrap() > MassaTime::from(0));
+    /// assert_eq!(MassaTime::from(u64::MAX).elapsed().unwrap(), MassaTime::from(0));
+    /// ```
+    pub fn elapsed(self) -> Result<MassaTime, TimeError> {
+        Ok(MassaTime::now()?.saturating_sub(self))
+    }
+
+    /// Time remaining until `self`, zero if `self` is in the past.
+    ///
+    /// ```
+    /// # use massa_time::*;
+    /// assert_eq!(MassaTime::from(0).time_until().unwrap(), MassaTime::from(0));
+    /// assert!(MassaTime::from(u64::MAX).time_until().unwrap() > MassaTime::from(0));
+    /// ```
+    pub fn time_until(self) -> Result<MassaTime, TimeError> {
+        Ok(self.saturating_sub(MassaTime::now()?))
+    }
+
     /// ```
     /// # use std::time::{Duration, SystemTime, UNIX_EPOCH};
     /// # use massa_time::*;
@@ -411,14 +546,67 @@ impl MassaTime {
         MassaTime(self.0.abs_diff(t.0))
     }
 
+    /// Signed difference `self - t` in milliseconds, saturating at the bounds of `i64`.
+    ///
+    /// ```
+    /// # use massa_time::*;
+    /// let time1 = MassaTime::from(42);
+    /// let time2 = MassaTime::from(84);
+    ///
+    /// assert_eq!(time1.signed_diff(time2), -42);
+    /// assert_eq!(time2.signed_diff(time1), 42);
+    /// assert_eq!(MassaTime::from(u64::MAX).signed_diff(time1), i64::MAX);
+    /// ```
+    pub fn signed_diff(&self, t: MassaTime) -> i64 {
+        (self.0 as i128 - t.0 as i128).clamp(i64::MIN as i128, i64::MAX as i128) as i64
+    }
+
+    /// Middle of the interval between `self` and `t`, rounded down.
+    ///
+    /// ```
+    /// # use massa_time::*;
+    /// let time1 = MassaTime::from(42);
+    /// let time2 = MassaTime::from(85);
+    ///
+    /// assert_eq!(time1.midpoint(time2), MassaTime::from(63));
+    /// assert_eq!(time2.midpoint(time1), MassaTime::from(63));
+    /// assert_eq!(MassaTime::from(u64::MAX).midpoint(MassaTime::from(u64::MAX)), MassaTime::from(u64::MAX));
+    /// ```
+    pub fn midpoint(&self, t: MassaTime) -> MassaTime {
+        MassaTime(self.0.min(t.0) + self.0.abs_diff(t.0) / 2)
+    }
+
+    /// Format as an RFC 3339 (ISO 8601) UTC date, at the second.
+    /// The times that cannot be represented as a date are formatted as milliseconds.
+    ///
     /// ```
     /// # use massa_time::*;
     /// let massa_time : MassaTime = MassaTime::from(1_640_995_200_000);
-    /// assert_eq!(massa_time.to_utc_string(), "2022-01-01T00:00:00Z")
+    /// assert_eq!(massa_time.to_utc_string(), "2022-01-01T00:00:00Z");
+    /// assert_eq!(MassaTime::from(u64::MAX).to_utc_string(), "18446744073709551615 ms");
     /// ```
     pub fn to_utc_string(self) -> String {
-        let naive = OffsetDateTime::from_unix_timestamp((self.to_millis() / 1000) as i64).unwrap();
-        naive.format(&Rfc3339).unwrap()
+        OffsetDateTime::from_unix_timestamp((self.to_millis() / 1000) as i64)
+            .ok()
+            .and_then(|date| date.format(&Rfc3339).ok())
+            .unwrap_or_else(|| format!("{} ms", self.to_millis()))
+    }
+
+    /// Parse an RFC 3339 (ISO 8601) date, ex: `2022-01-01T00:00:00Z` or `2022-01-01T01:00:00.5+01:00`.
+    ///
+    /// ```
+    /// # use massa_time::*;
+    /// assert_eq!(
+    ///     MassaTime::from_utc_str("2022-01-01T01:00:00.5+01:00").unwrap(),
+    ///     MassaTime::from(1_640_995_200_500)
+    /// );
+    /// assert!(MassaTime::from_utc_str("1969-12-31T23:59:59Z").is_err());
+    /// assert!(MassaTime::from_utc_str("2022-01-01").is_err());
+    /// ```
+    pub fn from_utc_str(s: &str) -> Result<Self, TimeError> {
+        let date = OffsetDateTime::parse(s, &Rfc3339)
+            .map_err(|err| TimeError::ParsingError(format!("invalid date {}: {}", s, err)))?;
+        MassaTime::try_from(date)
     }
 
     /// ```
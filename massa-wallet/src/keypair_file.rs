@@ -0,0 +1,123 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Single keypair stored in its own encrypted file, like the key identifying the node on the network.
+
+use crate::WalletError;
+use massa_cipher::{decrypt, encrypt, VERSION};
+use massa_signature::KeyPair;
+use std::path::Path;
+use tracing::warn;
+
+/// Load the keypair encrypted with `password` in the file at `path`,
+/// or generate one and save it there if the file does not exist.
+///
+/// Files holding the keypair in plain text, as written by the previous versions, are encrypted in place,
+/// and files encrypted by a previous version of the cipher are encrypted again with the current one.
+/// Failing to write the file is only logged: the keypair is still returned.
+pub fn load_or_create_keypair_file(path: &Path, password: &str) -> Result<KeyPair, WalletError> {
+    if !path.is_file() {
+        let keypair = KeyPair::generate();
+        save_keypair_file(path, password, &keypair);
+        return Ok(keypair);
+    }
+    let content = std::fs::read(path)?;
+    if let Ok(keypair) = serde_json::from_slice::<KeyPair>(&content) {
+        save_keypair_file(path, password, &keypair);
+        return Ok(keypair);
+    }
+    let (version, decrypted_content) = decrypt(password, &content)?;
+    let keypair = serde_json::from_slice::<KeyPair>(&decrypted_content)?;
+    if version != VERSION {
+        save_keypair_file(path, password, &keypair);
+    }
+    Ok(keypair)
+}
+
+/// Save the keypair encrypted with `password` in the file at `path`, log a warning on failure
+fn save_keypair_file(path: &Path, password: &str, keypair: &KeyPair) {
+    if let Err(err) = write_keypair_file(path, password, keypair) {
+        warn!("could not write the key file {}: {}", path.display(), err);
+    }
+}
+
+/// Write the keypair encrypted with `password` in the file at `path`
+fn write_keypair_file(path: &Path, password: &str, keypair: &KeyPair) -> Result<(), WalletError> {
+    let encrypted_content = encrypt(password, serde_json::to_string(keypair)?.as_bytes())?;
+    std::fs::write(path, encrypted_content)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::load_or_create_keypair_file;
+    use massa_cipher::{decrypt, encrypt, test_exports::encrypt_legacy, VERSION};
+    use massa_signature::KeyPair;
+    use tempfile::TempDir;
+
+    const PASSWORD: &str = "password";
+
+    fn read_version(path: &std::path::Path) -> u32 {
+        decrypt(PASSWORD, &std::fs::read(path).unwrap()).unwrap().0
+    }
+
+    #[test]
+    fn test_create_and_reload_keypair_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("node_privkey.key");
+        let keypair = load_or_create_keypair_file(&path, PASSWORD).unwrap();
+        assert_eq!(read_version(&path), VERSION);
+        let reloaded = load_or_create_keypair_file(&path, PASSWORD).unwrap();
+        assert_eq!(reloaded.get_public_key(), keypair.get_public_key());
+    }
+
+    #[test]
+    fn test_migrate_legacy_keypair_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("node_privkey.key");
+        let keypair = KeyPair::generate();
+        let json = serde_json::to_string(&keypair).unwrap();
+        std::fs::write(&path, encrypt_legacy(PASSWORD, json.as_bytes()).unwrap()).unwrap();
+
+        let loaded = load_or_create_keypair_file(&path, PASSWORD).unwrap();
+        assert_eq!(loaded.get_public_key(), keypair.get_public_key());
+        assert_eq!(read_version(&path), VERSION);
+        let reloaded = load_or_create_keypair_file(&path, PASSWORD).unwrap();
+        assert_eq!(reloaded.get_public_key(), keypair.get_public_key());
+    }
+
+    #[test]
+    fn test_encrypt_plain_keypair_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("node_privkey.key");
+        let keypair = KeyPair::generate();
+        std::fs::write(&path, serde_json::to_string(&keypair).unwrap()).unwrap();
+
+        let loaded = load_or_create_keypair_file(&path, PASSWORD).unwrap();
+        assert_eq!(loaded.get_public_key(), keypair.get_public_key());
+        assert_eq!(read_version(&path), VERSION);
+    }
+
+    #[test]
+    fn test_keypair_file_wrong_password() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("node_privkey.key");
+        let json = serde_json::to_string(&KeyPair::generate()).unwrap();
+        for content in [
+            encrypt(PASSWORD, json.as_bytes()).unwrap(),
+            encrypt_legacy(PASSWORD, json.as_bytes()).unwrap(),
+        ] {
+            std::fs::write(&path, &content).unwrap();
+            assert!(load_or_create_keypair_file(&path, "wrong password").is_err());
+            // the file is left untouched
+            assert_eq!(std::fs::read(&path).unwrap(), content);
+        }
+    }
+
+    #[test]
+    fn test_unwritable_keypair_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("missing_dir").join("node_privkey.key");
+        assert!(load_or_create_keypair_file(&path, PASSWORD).is_ok());
+        assert!(!path.exists());
+    }
+}
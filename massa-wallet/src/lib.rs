@@ -1,24 +1,29 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 //! Standalone massa wallet
 //! Keypair management
+//!
+//! The keys are stored encrypted with a password (see `massa-cipher`).
+//! The files written by older versions are encrypted again with the current cipher version when loaded.
 #![warn(missing_docs)]
 #![warn(unused_crate_dependencies)]
 #![feature(map_try_insert)]
 
 pub use error::WalletError;
+pub use keypair_file::load_or_create_keypair_file;
 
-use massa_cipher::{decrypt, encrypt};
+use massa_cipher::{decrypt, encrypt, VERSION};
 use massa_hash::Hash;
 use massa_models::address::Address;
+use massa_models::builder::OperationBuilder;
 use massa_models::composite::PubkeySig;
-use massa_models::operation::{Operation, OperationSerializer, WrappedOperation};
+use massa_models::operation::WrappedOperation;
 use massa_models::prehash::{PreHashMap, PreHashSet};
-use massa_models::wrapped::WrappedContent;
-use massa_signature::{KeyPair, PublicKey};
+use massa_signature::{KeyPair, PublicKey, Signer};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
 mod error;
+mod keypair_file;
 
 /// Contains the keypairs created in the wallet.
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -36,14 +41,18 @@ impl Wallet {
     pub fn new(path: PathBuf, password: String) -> Result<Wallet, WalletError> {
         if path.is_file() {
             let content = &std::fs::read(&path)?[..];
-            let (_version, decrypted_content) = decrypt(&password, content)?;
+            let (version, decrypted_content) = decrypt(&password, content)?;
             let keys =
                 serde_json::from_slice::<PreHashMap<Address, KeyPair>>(&decrypted_content[..])?;
-            Ok(Wallet {
+            let wallet = Wallet {
                 keys,
                 wallet_path: path,
                 password,
-            })
+            };
+            if version != VERSION {
+                wallet.save()?;
+            }
+            Ok(wallet)
         } else {
             let wallet = Wallet {
                 keys: PreHashMap::default(),
@@ -59,10 +68,10 @@ impl Wallet {
     /// returns none if the address isn't in the wallet or if an error occurred during the signature
     /// else returns the public key that signed the message and the signature
     pub fn sign_message(&self, address: &Address, msg: Vec<u8>) -> Option<PubkeySig> {
-        if let Some(key) = self.keys.get(address) {
-            if let Ok(signature) = key.sign(&Hash::compute_from(&msg)) {
+        if let Some(signer) = self.find_associated_signer(address) {
+            if let Ok(signature) = signer.sign(&Hash::compute_from(&msg)) {
                 Some(PubkeySig {
-                    public_key: key.get_public_key(),
+                    public_key: signer.get_public_key(),
                     signature,
                 })
             } else {
@@ -91,6 +100,16 @@ impl Wallet {
         Ok(addrs)
     }
 
+    /// Generates a new keypair and adds it to the wallet, returns its address.
+    /// The wallet file is updated.
+    pub fn generate_keypair(&mut self) -> Result<Address, WalletError> {
+        let keypair = KeyPair::generate();
+        let address = Address::from_public_key(&keypair.get_public_key());
+        self.keys.insert(address, keypair);
+        self.save()?;
+        Ok(address)
+    }
+
     /// Removes wallet entries given a list of addresses. Missing entries are ignored.
     /// The wallet file is updated.
     pub fn remove_addresses(&mut self, addresses: &Vec<Address>) -> Result<(), WalletError> {
@@ -111,6 +130,11 @@ impl Wallet {
         self.keys.get(address)
     }
 
+    /// Finds the signer of the keypair associated with given address
+    pub fn find_associated_signer(&self, address: &Address) -> Option<&dyn Signer> {
+        self.keys.get(address).map(|keypair| keypair as &dyn Signer)
+    }
+
     /// Finds the public key associated with given address
     pub fn find_associated_public_key(&self, address: &Address) -> Option<PublicKey> {
         self.keys
@@ -137,16 +161,16 @@ impl Wallet {
         &self.keys
     }
 
-    /// Signs an operation with the keypair corresponding to the given address
+    /// Builds an operation signed with the keypair corresponding to the given address
     pub fn create_operation(
         &self,
-        content: Operation,
+        builder: OperationBuilder,
         address: Address,
     ) -> Result<WrappedOperation, WalletError> {
-        let sender_keypair = self
-            .find_associated_keypair(&address)
+        let sender_signer = self
+            .find_associated_signer(&address)
             .ok_or_else(|| WalletError::MissingKeyError(address))?;
-        Ok(Operation::new_wrapped(content, OperationSerializer::new(), sender_keypair).unwrap())
+        Ok(builder.build(sender_signer)?)
     }
 }
 